@@ -0,0 +1,56 @@
+#!/bin/bash
+# ═══════════════════════════════════════════════════════════════════════════
+# iMAGE - ffmpeg Sidecar Fetch Script
+# Downloads a static ffmpeg for the target triple into src-tauri/binaries,
+# named the way Tauri's bundle.externalBin expects (ffmpeg-<triple>[.exe])
+# Usage: scripts/fetch-ffmpeg.sh [target-triple]
+# ═══════════════════════════════════════════════════════════════════════════
+
+set -e
+
+TRIPLE="${1:-$(rustc -vV | sed -n 's/^host: //p')}"
+ROOT="$(cd "$(dirname "$0")/.." && pwd)"
+OUT_DIR="$ROOT/src-tauri/binaries"
+BTBN="https://github.com/BtbN/FFmpeg-Builds/releases/download/latest"
+RIEDL="https://ffmpeg.martin-riedl.de/redirect/latest/macos"
+
+case "$TRIPLE" in
+    x86_64-unknown-linux-gnu)  URL="$BTBN/ffmpeg-master-latest-linux64-lgpl.tar.xz" ;;
+    aarch64-unknown-linux-gnu) URL="$BTBN/ffmpeg-master-latest-linuxarm64-lgpl.tar.xz" ;;
+    x86_64-pc-windows-msvc)    URL="$BTBN/ffmpeg-master-latest-win64-lgpl.zip" ;;
+    x86_64-apple-darwin)       URL="$RIEDL/amd64/release/ffmpeg.zip" ;;
+    aarch64-apple-darwin)      URL="$RIEDL/arm64/release/ffmpeg.zip" ;;
+    *)
+        echo "No ffmpeg build known for $TRIPLE" >&2
+        exit 1
+        ;;
+esac
+
+EXT=""
+[[ "$TRIPLE" == *windows* ]] && EXT=".exe"
+TARGET="$OUT_DIR/ffmpeg-$TRIPLE$EXT"
+if [ -x "$TARGET" ]; then
+    echo "ffmpeg for $TRIPLE already at $TARGET"
+    exit 0
+fi
+
+WORK="$(mktemp -d)"
+trap 'rm -rf "$WORK"' EXIT
+
+echo "Downloading ffmpeg for $TRIPLE..."
+curl -fsSL "$URL" -o "$WORK/archive"
+case "$URL" in
+    *.tar.xz) tar -xJf "$WORK/archive" -C "$WORK" ;;
+    *)        unzip -q "$WORK/archive" -d "$WORK" ;;
+esac
+
+BIN="$(find "$WORK" -type f -name "ffmpeg$EXT" | head -1)"
+if [ -z "$BIN" ]; then
+    echo "ffmpeg$EXT not found in $URL" >&2
+    exit 1
+fi
+
+mkdir -p "$OUT_DIR"
+cp "$BIN" "$TARGET"
+chmod +x "$TARGET"
+echo "ffmpeg for $TRIPLE at $TARGET"
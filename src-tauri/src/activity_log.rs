@@ -0,0 +1,254 @@
+//! Activity Log
+//!
+//! Every operation that changes a repository or a key is appended to the
+//! local store's `activity_log` table: uploads, deletes, renames and moves,
+//! visibility changes, album sharing and key operations. `get_activity_log`
+//! pages through it, newest first, so users can see what the app did.
+//!
+//! The log is append-only: triggers refuse to update or delete rows. Each
+//! row holds its entry, sealed like the rest of the store, and a MAC that
+//! chains it to the row before:
+//!
+//! ```text
+//! mac(n) = BLAKE3-keyed(log key, mac(n-1) || n || sealed entry(n))
+//! ```
+//!
+//! The log key is derived from the store key, so only this device can
+//! extend the chain. The sequence number and MAC of the newest row are kept
+//! as the log's head, which catches rows dropped from the end.
+//! `verify_activity_log` (see `security_verify`) walks the chain.
+//!
+//! Recording never fails the operation it records; if the store cannot be
+//! written, a warning is logged instead.
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::github::AppError;
+use crate::local_store::{db_error, with_store, LocalStore};
+use crate::util::now_secs;
+
+/// Migration 7 (see `migrations`)
+pub const ACTIVITY_LOG_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS activity_log (
+    seq INTEGER PRIMARY KEY,
+    record BLOB NOT NULL,
+    mac BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS activity_log_head (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    head BLOB NOT NULL
+);
+CREATE TRIGGER IF NOT EXISTS activity_log_no_update BEFORE UPDATE ON activity_log
+BEGIN
+    SELECT RAISE(ABORT, 'activity log is append-only');
+END;
+CREATE TRIGGER IF NOT EXISTS activity_log_no_delete BEFORE DELETE ON activity_log
+BEGIN
+    SELECT RAISE(ABORT, 'activity log is append-only');
+END;
+"#;
+
+const LOG_KEY_CONTEXT: &str = "vortex-image activity log key v1";
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Upload,
+    Delete,
+    Rename,
+    Move,
+    Visibility,
+    AlbumAccess,
+    Key,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    pub seq: u64,
+    pub at: u64,
+    pub kind: ActivityKind,
+    /// Operation, named after its command, such as `delete_photo`
+    pub action: String,
+    pub repo: Option<String>,
+    /// Path, album or key the operation was on
+    pub target: String,
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActivityPage {
+    /// Newest first
+    pub entries: Vec<ActivityEntry>,
+    /// Pass as `before_seq` for the next page; `None` at the first entry
+    pub next_before: Option<u64>,
+}
+
+/// Sequence number and MAC of the newest row
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityHead {
+    pub seq: u64,
+    pub mac: [u8; 32],
+}
+
+/// A row as stored
+pub struct ActivityRow {
+    pub seq: u64,
+    pub record: Vec<u8>,
+    pub mac: Vec<u8>,
+}
+
+pub(crate) fn log_key(store: &LocalStore) -> Zeroizing<[u8; 32]> {
+    store.derive_key(LOG_KEY_CONTEXT)
+}
+
+fn row_id(store: &LocalStore, seq: u64) -> [u8; 32] {
+    store.index_id(&["activity", &seq.to_string()])
+}
+
+fn head_id(store: &LocalStore) -> [u8; 32] {
+    store.index_id(&["activity", "head"])
+}
+
+/// MAC of row `seq`, chained to the MAC of the row before (zeros for the first)
+pub fn chain_mac(key: &[u8; 32], prev: &[u8; 32], seq: u64, record: &[u8]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_keyed(key);
+    hasher.update(prev);
+    hasher.update(&seq.to_be_bytes());
+    hasher.update(record);
+    *hasher.finalize().as_bytes()
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+pub fn activity_head_in(store: &LocalStore) -> Result<Option<ActivityHead>, AppError> {
+    let sealed: Option<Vec<u8>> = store
+        .connection()
+        .query_row("SELECT head FROM activity_log_head WHERE id = 1", [], |row| row.get(0))
+        .optional()
+        .map_err(db_error)?;
+    sealed
+        .map(|sealed| {
+            let json = store.unseal(&head_id(store), &sealed)?;
+            serde_json::from_slice(&json).map_err(|e| AppError::Validation(format!("Corrupt activity log head: {}", e)))
+        })
+        .transpose()
+}
+
+/// Every row, oldest first
+pub fn activity_rows_in(store: &LocalStore) -> Result<Vec<ActivityRow>, AppError> {
+    let mut statement = store
+        .connection()
+        .prepare("SELECT seq, record, mac FROM activity_log ORDER BY seq")
+        .map_err(db_error)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok(ActivityRow {
+                seq: row.get::<_, i64>(0)? as u64,
+                record: row.get(1)?,
+                mac: row.get(2)?,
+            })
+        })
+        .map_err(db_error)?;
+    rows.collect::<Result<_, _>>().map_err(db_error)
+}
+
+/// Decrypt the entry of a row
+pub fn open_activity(store: &LocalStore, seq: u64, record: &[u8]) -> Result<ActivityEntry, AppError> {
+    let json = store.unseal(&row_id(store, seq), record)?;
+    serde_json::from_slice(&json).map_err(|e| AppError::Validation(format!("Corrupt activity entry {}: {}", seq, e)))
+}
+
+pub fn append_activity_in(
+    store: &LocalStore,
+    kind: ActivityKind,
+    action: &str,
+    repo: Option<&str>,
+    target: &str,
+    detail: Option<String>,
+) -> Result<ActivityEntry, AppError> {
+    let transaction = store.connection().unchecked_transaction().map_err(db_error)?;
+    let (seq, prev) = match activity_head_in(store)? {
+        Some(head) => (head.seq + 1, head.mac),
+        None => (1, [0u8; 32]),
+    };
+    let entry = ActivityEntry {
+        seq,
+        at: now_secs(),
+        kind,
+        action: action.to_string(),
+        repo: repo.map(str::to_string),
+        target: target.to_string(),
+        detail,
+    };
+
+    let json = Zeroizing::new(serde_json::to_vec(&entry).map_err(|e| AppError::Validation(e.to_string()))?);
+    let record = store.seal(&row_id(store, seq), &json)?;
+    let mac = chain_mac(&log_key(store), &prev, seq, &record);
+    store
+        .connection()
+        .execute(
+            "INSERT INTO activity_log (seq, record, mac) VALUES (?1, ?2, ?3)",
+            params![seq as i64, record, &mac[..]],
+        )
+        .map_err(db_error)?;
+
+    let head = serde_json::to_vec(&ActivityHead { seq, mac }).map_err(|e| AppError::Validation(e.to_string()))?;
+    store
+        .connection()
+        .execute(
+            "INSERT OR REPLACE INTO activity_log_head (id, head) VALUES (1, ?1)",
+            params![store.seal(&head_id(store), &head)?],
+        )
+        .map_err(db_error)?;
+    transaction.commit().map_err(db_error)?;
+    Ok(entry)
+}
+
+/// Up to `limit` entries before `before_seq`, or the newest ones
+pub fn activity_page_in(store: &LocalStore, before_seq: Option<u64>, limit: usize) -> Result<ActivityPage, AppError> {
+    let before = before_seq.map_or(i64::MAX, |s| s as i64);
+    let mut statement = store
+        .connection()
+        .prepare("SELECT seq, record FROM activity_log WHERE seq < ?1 ORDER BY seq DESC LIMIT ?2")
+        .map_err(db_error)?;
+    let rows = statement
+        .query_map(params![before, limit as i64], |row| {
+            Ok((row.get::<_, i64>(0)? as u64, row.get::<_, Vec<u8>>(1)?))
+        })
+        .map_err(db_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(db_error)?;
+
+    let entries = rows
+        .iter()
+        .map(|(seq, record)| open_activity(store, *seq, record))
+        .collect::<Result<Vec<_>, _>>()?;
+    let next_before = entries.last().map(|e| e.seq).filter(|&seq| seq > 1);
+    Ok(ActivityPage { entries, next_before })
+}
+
+/// Log an operation that changed a repository or a key
+pub(crate) fn record_activity(kind: ActivityKind, action: &str, repo: Option<&str>, target: &str, detail: Option<String>) {
+    if let Err(e) = with_store(|store| append_activity_in(store, kind, action, repo, target, detail)) {
+        tracing::warn!("Could not record {} of {} in the activity log: {}", action, target, e);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Logged operations, newest first. Pass the returned `next_before` as
+/// `before_seq` to page back through older ones.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_activity_log(before_seq: Option<u64>, limit: Option<usize>) -> Result<ActivityPage, AppError> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    with_store(|store| activity_page_in(store, before_seq, limit))
+}
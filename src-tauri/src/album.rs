@@ -0,0 +1,927 @@
+//! Album Manifests and Encrypted Albums
+//!
+//! Every album created through `create_album` carries a small manifest file
+//! (`.vortex-album.json`) in its folder. Albums can be nested: a sub-album is
+//! a subdirectory with its own manifest, e.g. `photos/Trips/2024/Japan`.
+//!
+//! Albums flagged as encrypted store photos as `EncryptionMethod::AlbumKey`
+//! payloads under opaque blob names (keyed BLAKE3 of the content), so neither
+//! the image data nor the original filename is visible in the repository.
+//!
+//! The album key is derived from the owner's keypair (see
+//! `HybridKeypair::derive_album_key`), so nothing secret is stored remotely.
+//! Photos are sealed with that symmetric key rather than hybrid-encrypted
+//! each; the hybrid exchange is used to wrap the album key itself. Albums
+//! shared with contacts carry the key wrapped to each recipient's public
+//! bundle, and a keypair without such a grant reads none of the photos; revoking a recipient moves the album to a new key epoch
+//! (see `album_access`).
+//! Original filenames are kept twice, both encrypted under the album key:
+//! inside the photo payload (for downloads) and in the manifest (for listing).
+//! Captions and EXIF extracts live in the album's metadata vault (see
+//! `metadata_vault`), which also carries the filename.
+//!
+//! The manifest also holds display metadata: a cover photo, a description and
+//! free-form key-value pairs. These are stored in plain text, also for
+//! encrypted albums, so `list_albums` can show them without the keypair.
+//! Photo tags, favorites and ratings are kept too, sealed in encrypted
+//! albums (see `tagging`).
+//!
+//! Manifests are signed by whoever last wrote them with a keypair, and each
+//! uploaded photo gets a detached signature (see `security_verify`).
+//! They also carry a vector clock of the devices that wrote them, so edits
+//! made concurrently on two machines are merged (see `device_sync`).
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+use tokio::fs;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::batch::{plan_move, run_plan, BatchItemResult};
+use crate::compress::{compress_file_data, decompress_file_data, CompressedFileData, ItemCompressionSettings};
+use crate::crypto::{
+    decrypt_with_aad, decrypt_with_key, encrypt_with_aad, encrypt_with_key, with_keypair, EncryptedFileData,
+    EncryptedPayload, EncryptionMethod, KeypairHandle, PublicBundle,
+};
+use crate::device_sync::{device_id, merge_manifests, VectorClock};
+use crate::git_data::{branch_head, commit_changes, create_blob, TreeChange};
+use crate::github::{
+    get_album_recursive, put_file_contents, response_error, sanitize_filename, validate_repo, Album, AppError,
+    Bearer, GithubError, HttpClient, UploadResult,
+};
+use crate::metadata_vault::{fetch_vault, stage_vault, PhotoMetadata};
+use crate::retry::SendWithRetry;
+use crate::security_verify::{sign_manifest, sign_photo, signature_path, ManifestSignature};
+use crate::sharing::album_id;
+use crate::ipfs::IpfsEntry;
+use crate::mirror::replicate_tree_changes;
+use crate::tagging::PhotoOrganization;
+use crate::util::now_secs;
+use crate::video::MediaEntry;
+
+pub const ALBUM_MANIFEST_FILE: &str = ".vortex-album.json";
+/// Repo folder that holds all albums
+pub const ALBUM_ROOT: &str = "photos";
+/// Levels of nesting below `photos/`
+const MAX_ALBUM_DEPTH: usize = 8;
+const ALBUM_MANIFEST_VERSION: u8 = 1;
+/// Extension used for encrypted photo blobs
+pub const ENCRYPTED_BLOB_EXT: &str = "vxe";
+const BLOB_NAME_LEN: usize = 32;
+const MAX_DESCRIPTION_LEN: usize = 2000;
+const MAX_METADATA_ENTRIES: usize = 64;
+const MAX_METADATA_KEY_LEN: usize = 64;
+const MAX_METADATA_VALUE_LEN: usize = 1024;
+/// Times a save merges with concurrent writes before giving up
+const MAX_MANIFEST_MERGES: usize = 3;
+
+/// The album key wrapped to one recipient's public bundle. The bundle is kept
+/// so the key can be re-wrapped when it rotates.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AlbumGrant {
+    pub bundle: PublicBundle,
+    pub wrapped_key: EncryptedPayload,
+    pub granted_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlbumManifest {
+    pub version: u8,
+    pub encrypted: bool,
+    #[serde(default)]
+    pub owner_key_id: Option<String>,
+    pub created_at: u64,
+    /// Blob name -> base64 of the original filename encrypted with the album key
+    #[serde(default)]
+    pub entries: BTreeMap<String, String>,
+    /// Total size of photos before compression/encryption
+    #[serde(default)]
+    pub original_bytes: u64,
+    /// Total size of the uploaded payloads
+    #[serde(default)]
+    pub stored_bytes: u64,
+    /// File name of the cover photo within the album folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Bumped each time the album key is rotated; 0 for the original key
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub key_epoch: u32,
+    /// Recipient key id -> album key wrapped for that recipient
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub access: BTreeMap<String, AlbumGrant>,
+    /// The album key wrapped for the owner. Set when the owner's keypair has
+    /// rotated and can no longer derive the key of the current epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_key: Option<EncryptedPayload>,
+    /// File name -> container, codec and duration of the album's videos
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub media: BTreeMap<String, MediaEntry>,
+    /// File name -> tags, favorite and rating, for plain albums
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub organization: BTreeMap<String, PhotoOrganization>,
+    /// The same for encrypted albums, sealed with the album key (see `tagging`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sealed_organization: Option<String>,
+    /// File name -> where the file is pinned on IPFS (see `ipfs`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ipfs: BTreeMap<String, IpfsEntry>,
+    /// Hex BLAKE3 of a photo's content -> its file name, for plain albums
+    /// (see `upload_dedup`). Encrypted albums already name blobs by a keyed
+    /// hash of their content.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hashes: BTreeMap<String, String>,
+    /// File name -> hex BLAKE3 of the stored bytes, for photos uploaded with
+    /// a `.vxsig`. Covered by the manifest signature, so a listed photo whose
+    /// `.vxsig` goes missing is reported as tampered (see `security_verify`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub signed: BTreeMap<String, String>,
+    /// Writes per device id, to merge concurrent edits (see `device_sync`)
+    #[serde(default, skip_serializing_if = "VectorClock::is_empty")]
+    pub clock: VectorClock,
+    /// Device id of the last writer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// Signature of the last writer over everything above
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ManifestSignature>,
+    /// The manifest as last read or written, the common base when a save
+    /// has to merge with a concurrent write
+    #[serde(skip)]
+    pub loaded: Option<Box<AlbumManifest>>,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl AlbumManifest {
+    pub fn new(encrypted: bool, owner_key_id: Option<String>) -> Self {
+        Self {
+            version: ALBUM_MANIFEST_VERSION,
+            encrypted,
+            owner_key_id,
+            created_at: now_secs(),
+            entries: BTreeMap::new(),
+            original_bytes: 0,
+            stored_bytes: 0,
+            cover: None,
+            description: None,
+            metadata: BTreeMap::new(),
+            key_epoch: 0,
+            access: BTreeMap::new(),
+            owner_key: None,
+            media: BTreeMap::new(),
+            organization: BTreeMap::new(),
+            sealed_organization: None,
+            ipfs: BTreeMap::new(),
+            hashes: BTreeMap::new(),
+            signed: BTreeMap::new(),
+            clock: VectorClock::new(),
+            device: None,
+            signature: None,
+            loaded: None,
+        }
+    }
+
+    /// Remember the current state as the base of the next merge
+    fn mark_loaded(&mut self) {
+        self.loaded = None;
+        self.loaded = Some(Box::new(self.clone()));
+    }
+
+    /// Sign the manifest with the keypair behind `signer`. Without one, an
+    /// earlier signature is dropped, since the edit being saved breaks it.
+    pub fn resign(&mut self, signer: Option<KeypairHandle>) -> Result<(), AppError> {
+        self.signature = match signer {
+            Some(handle) => Some(
+                with_keypair(handle, |kp| sign_manifest(self, kp)).map_err(|e| AppError::Validation(e.to_string()))?,
+            ),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// List `name` as uploaded with a `.vxsig` over `stored`, the bytes in
+    /// the repository
+    pub fn record_signed(&mut self, name: &str, stored: &[u8]) {
+        self.signed.insert(name.to_string(), blake3::hash(stored).to_hex().to_string());
+    }
+
+    /// Drop what the manifest records about `name` once the file left the
+    /// album: its entry, per-file listings and the cover. Returns whether
+    /// anything changed.
+    pub fn forget_file(&mut self, name: &str) -> bool {
+        let mut changed = self.entries.remove(name).is_some();
+        changed |= self.media.remove(name).is_some();
+        changed |= self.organization.remove(name).is_some();
+        changed |= self.ipfs.remove(name).is_some();
+        changed |= self.hashes.remove(name).is_some();
+        changed |= self.signed.remove(name).is_some();
+        if self.cover.as_deref() == Some(name) {
+            self.cover = None;
+            changed = true;
+        }
+        changed
+    }
+
+    /// Set the cover photo by file name, or clear it with `None`
+    pub fn set_cover(&mut self, cover: Option<&str>) -> Result<(), AppError> {
+        self.cover = match cover.map(str::trim).filter(|c| !c.is_empty()) {
+            None => None,
+            Some(name) if name.contains(['/', '\\']) || name == ".." || name == ALBUM_MANIFEST_FILE => {
+                return Err(AppError::Validation("Cover must be a photo in the album folder".into()))
+            }
+            Some(name) if self.encrypted && !self.entries.contains_key(name) => {
+                return Err(AppError::Validation("Cover is not a photo in this album".into()))
+            }
+            Some(name) => Some(name.to_string()),
+        };
+        Ok(())
+    }
+
+    pub fn set_description(&mut self, description: Option<&str>) -> Result<(), AppError> {
+        let description = description.map(str::trim).filter(|d| !d.is_empty());
+        if description.is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_LEN) {
+            return Err(AppError::Validation(format!(
+                "Description exceeds {} characters",
+                MAX_DESCRIPTION_LEN
+            )));
+        }
+        self.description = description.map(str::to_string);
+        Ok(())
+    }
+
+    /// Set a metadata entry, or remove it when `value` is `None`
+    pub fn set_metadata(&mut self, key: &str, value: Option<&str>) -> Result<(), AppError> {
+        validate_metadata_key(key)?;
+        match value {
+            None => {
+                self.metadata.remove(key);
+            }
+            Some(value) => {
+                if value.chars().count() > MAX_METADATA_VALUE_LEN {
+                    return Err(AppError::Validation(format!(
+                        "Metadata value exceeds {} characters",
+                        MAX_METADATA_VALUE_LEN
+                    )));
+                }
+                if !self.metadata.contains_key(key) && self.metadata.len() >= MAX_METADATA_ENTRIES {
+                    return Err(AppError::Validation(format!(
+                        "Albums can hold at most {} metadata entries",
+                        MAX_METADATA_ENTRIES
+                    )));
+                }
+                self.metadata.insert(key.to_string(), value.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keys are short identifiers: letters, digits, `-`, `_` and `.`
+pub fn validate_metadata_key(key: &str) -> Result<(), AppError> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_METADATA_KEY_LEN
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!("Invalid metadata key: {}", key)))
+    }
+}
+
+// ============================================================================
+// Blob Naming and Payloads
+// ============================================================================
+
+/// Opaque, deterministic blob name for a photo in an encrypted album.
+/// Keyed with the album key so identical photos in different albums do not link.
+pub fn encrypted_blob_name(album_key: &[u8; 32], content: &[u8]) -> String {
+    let hash = blake3::keyed_hash(album_key, content).to_hex();
+    format!("{}.{}", &hash[..BLOB_NAME_LEN], ENCRYPTED_BLOB_EXT)
+}
+
+fn name_aad(album_id: &str) -> String {
+    format!("{}#name", album_id)
+}
+
+/// Encrypt an original filename for storage in a manifest or payload metadata
+pub fn seal_filename(album_key: &[u8; 32], album_id: &str, name: &str) -> Result<String, AppError> {
+    let sealed = encrypt_with_key(name.as_bytes(), album_key, name_aad(album_id).as_bytes())
+        .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))?;
+    Ok(STANDARD.encode(sealed))
+}
+
+pub fn open_filename(album_key: &[u8; 32], album_id: &str, sealed: &str) -> Result<String, AppError> {
+    let raw = STANDARD
+        .decode(sealed)
+        .map_err(|_| AppError::Validation("Invalid sealed filename".into()))?;
+    let name = decrypt_with_key(&raw, album_key, name_aad(album_id).as_bytes())
+        .map_err(|e| AppError::Validation(format!("Decryption failed: {}", e)))?;
+    String::from_utf8(name).map_err(|_| AppError::Validation("Invalid sealed filename".into()))
+}
+
+/// Compress and encrypt a photo for an encrypted album.
+/// Returns the serialized `EncryptedFileData` payload.
+pub fn seal_album_photo(
+    album_key: &[u8; 32],
+    album_id: &str,
+    filename: &str,
+    content: &[u8],
+) -> Result<Vec<u8>, AppError> {
+    let compressed = compress_file_data(content, filename, &ItemCompressionSettings::default())
+        .map_err(|e| AppError::Validation(format!("Compression failed: {}", e)))?;
+    let compressed_bytes = serde_json::to_vec(&compressed)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+
+    let data = encrypt_with_key(&compressed_bytes, album_key, album_id.as_bytes())
+        .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))?;
+
+    let payload = EncryptedFileData {
+        data,
+        encrypted: true,
+        method: EncryptionMethod::AlbumKey,
+        metadata: Some(serde_json::json!({ "name": seal_filename(album_key, album_id, filename)? })),
+    };
+
+    serde_json::to_vec(&payload).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))
+}
+
+/// Decrypt and decompress an album photo payload.
+/// Returns the photo bytes and, when present, the original filename.
+pub fn open_album_photo(
+    album_key: &[u8; 32],
+    album_id: &str,
+    payload: &[u8],
+) -> Result<(Vec<u8>, Option<String>), AppError> {
+    let encrypted: EncryptedFileData = serde_json::from_slice(payload)
+        .map_err(|e| AppError::Validation(format!("Invalid encrypted file format: {}", e)))?;
+
+    let compressed_bytes = match encrypted.method {
+        EncryptionMethod::AlbumKey => decrypt_with_key(&encrypted.data, album_key, album_id.as_bytes())
+            .map_err(|e| AppError::Validation(format!("Decryption failed: {}", e)))?,
+        EncryptionMethod::None if !encrypted.encrypted => encrypted.data,
+        _ => {
+            return Err(AppError::Validation(
+                "Photo is not encrypted with the album key".into(),
+            ))
+        }
+    };
+
+    let compressed: CompressedFileData = serde_json::from_slice(&compressed_bytes)
+        .map_err(|e| AppError::Validation(format!("Invalid compressed file format: {}", e)))?;
+
+    let data = decompress_file_data(&compressed)
+        .map_err(|e| AppError::Validation(format!("Decompression failed: {}", e)))?;
+
+    let name = encrypted
+        .metadata
+        .as_ref()
+        .and_then(|m| m["name"].as_str())
+        .and_then(|sealed| open_filename(album_key, album_id, sealed).ok());
+
+    Ok((data, name))
+}
+
+/// Album folder containing a repo path (everything before the last segment)
+pub fn parent_album_path(remote_path: &str) -> &str {
+    remote_path
+        .trim_matches('/')
+        .rsplit_once('/')
+        .map(|(parent, _)| parent)
+        .unwrap_or("")
+}
+
+/// KDF info for an album key. Epoch 0 keeps the original derivation.
+pub fn album_key_info(album_id: &str, epoch: u32) -> String {
+    match epoch {
+        0 => album_id.to_string(),
+        n => format!("{}#epoch{}", album_id, n),
+    }
+}
+
+fn grant_aad(album_id: &str, epoch: u32) -> String {
+    format!("{}#grant{}", album_id, epoch)
+}
+
+/// Wrap an album key for a recipient
+pub fn wrap_album_key(
+    album_key: &[u8; 32],
+    recipient: &PublicBundle,
+    album_id: &str,
+    epoch: u32,
+) -> Result<EncryptedPayload, AppError> {
+    encrypt_with_aad(album_key, recipient, Some(grant_aad(album_id, epoch).as_bytes()))
+        .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))
+}
+
+/// Derive the owner's album key for a given epoch
+pub(crate) fn owner_album_key(handle: KeypairHandle, album_id: &str, epoch: u32) -> Result<[u8; 32], AppError> {
+    with_keypair(handle, |kp| Ok(*kp.derive_album_key(&album_key_info(album_id, epoch))?.as_bytes()))
+        .map_err(|e| AppError::Validation(e.to_string()))
+}
+
+/// Unwrap an album key wrapped with `wrap_album_key`
+fn unwrap_album_key(
+    handle: KeypairHandle,
+    wrapped: &EncryptedPayload,
+    album_id: &str,
+    epoch: u32,
+) -> Result<[u8; 32], AppError> {
+    let aad = grant_aad(album_id, epoch);
+    let key = with_keypair(handle, |kp| decrypt_with_aad(wrapped, kp, Some(aad.as_bytes())))
+        .map_err(|e| AppError::Validation(format!("Album key could not be unwrapped: {}", e)))?;
+    key.try_into()
+        .map_err(|_| AppError::Validation("Wrapped album key has the wrong length".into()))
+}
+
+/// Content key of an album for the keypair behind `handle`: derived when it
+/// owns the album, unwrapped from the manifest when the album was shared with
+/// it or the owner's keypair has rotated since
+pub(crate) fn album_key_for(
+    handle: KeypairHandle,
+    repo: &str,
+    album_path: &str,
+    manifest: &AlbumManifest,
+) -> Result<[u8; 32], AppError> {
+    let id = album_id(repo, album_path);
+    let key_id = with_keypair(handle, |kp| Ok(kp.public_bundle().key_id))
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+
+    if manifest.owner_key_id.as_ref().is_none_or(|owner| *owner == key_id) {
+        return match &manifest.owner_key {
+            Some(wrapped) => unwrap_album_key(handle, wrapped, &id, manifest.key_epoch),
+            None => owner_album_key(handle, &id, manifest.key_epoch),
+        };
+    }
+
+    let grant = manifest
+        .access
+        .get(&key_id)
+        .ok_or_else(|| AppError::Validation("This album has not been shared with your key".into()))?;
+    unwrap_album_key(handle, &grant.wrapped_key, &id, manifest.key_epoch)
+}
+
+// ============================================================================
+// Manifest Storage
+// ============================================================================
+
+/// Load an album's manifest along with its blob SHA. Returns `None` for
+/// plain folders that were not created through `create_album`.
+pub(crate) async fn fetch_manifest(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+) -> Result<Option<(AlbumManifest, String)>, AppError> {
+    let url = format!(
+        "https://api.github.com/repos/{}/contents/{}/{}",
+        repo,
+        album_path.trim_matches('/'),
+        ALBUM_MANIFEST_FILE
+    );
+
+    let res = client
+        .get(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+
+    if res.status() == 404 {
+        return Ok(None);
+    }
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "Failed to load album manifest").await);
+    }
+
+    let json: serde_json::Value = res.json().await?;
+    let sha = json["sha"].as_str().unwrap_or("").to_string();
+    let content: String = json["content"]
+        .as_str()
+        .ok_or_else(|| AppError::Api("Album manifest has no content".into()))?
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    let raw = STANDARD
+        .decode(content)
+        .map_err(|_| AppError::Validation("Invalid album manifest encoding".into()))?;
+    let mut manifest: AlbumManifest = serde_json::from_slice(&raw)
+        .map_err(|e| AppError::Validation(format!("Invalid album manifest: {}", e)))?;
+    manifest.mark_loaded();
+
+    Ok(Some((manifest, sha)))
+}
+
+/// Count this device's write in the manifest's clock, sign the manifest and
+/// serialize it. `save_manifest` writes the result; writers that put the
+/// manifest into a larger git-data commit use this directly.
+pub(crate) fn manifest_body(manifest: &mut AlbumManifest, signer: Option<KeypairHandle>) -> Result<Vec<u8>, AppError> {
+    let device = device_id()?;
+    manifest.clock.tick(&device);
+    manifest.device = Some(device);
+    manifest.resign(signer)?;
+    serde_json::to_vec_pretty(manifest).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))
+}
+
+/// Sign (see `AlbumManifest::resign`) and write an album's manifest, counting
+/// the write in its vector clock. When another device wrote the manifest
+/// since it was loaded, the two versions are merged (see `device_sync`) and
+/// the merge is written instead; `manifest` then holds the merged version.
+pub(crate) async fn save_manifest(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+    manifest: &mut AlbumManifest,
+    sha: Option<&str>,
+    signer: Option<KeypairHandle>,
+) -> Result<UploadResult, AppError> {
+    let path = format!("{}/{}", album_path.trim_matches('/'), ALBUM_MANIFEST_FILE);
+    let message = format!("Update album manifest {}", album_path);
+    let mut sha = sha.map(str::to_string);
+    let mut merges = 0;
+    loop {
+        let body = manifest_body(manifest, signer)?;
+        let err = match put_file_contents(client, repo, token, &path, &body, &message, sha.as_deref()).await {
+            Ok(result) => {
+                manifest.mark_loaded();
+                return Ok(result);
+            }
+            Err(e) => e,
+        };
+        // Manifests created here rather than loaded have nothing to merge from
+        let conflict = matches!(err, AppError::Github(GithubError::Conflict { .. }));
+        let base = match manifest.loaded.take() {
+            Some(base) if conflict && merges < MAX_MANIFEST_MERGES => base,
+            loaded => {
+                manifest.loaded = loaded;
+                return Err(err);
+            }
+        };
+        let Some((theirs, their_sha)) = fetch_manifest(client, repo, token, album_path).await? else {
+            return Err(err);
+        };
+        tracing::info!(album = album_path, "Merging album manifest written by another device");
+        *manifest = merge_manifests(&base, manifest, &theirs)?;
+        manifest.loaded = theirs.loaded;
+        sha = Some(their_sha);
+        merges += 1;
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Sanitized album path below `photos/` for a user-supplied name.
+/// Names may contain `/` to create nested albums in one go (`Trips/2024/Japan`).
+pub fn album_path_for(parent: &str, name: &str) -> Result<String, AppError> {
+    let parent = parent.trim_matches('/');
+    if parent != ALBUM_ROOT && !parent.starts_with(&format!("{}/", ALBUM_ROOT)) {
+        return Err(AppError::Validation(format!("Albums must live under {}/", ALBUM_ROOT)));
+    }
+    if parent.split('/').any(|s| s.is_empty() || s == "." || s == "..") {
+        return Err(AppError::Validation("Invalid parent album path".into()));
+    }
+
+    let sanitized: Vec<String> = name
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(sanitize_filename)
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if sanitized.is_empty() {
+        return Err(AppError::Validation("Invalid album name".into()));
+    }
+
+    let path = format!("{}/{}", parent, sanitized.join("/"));
+    if path.split('/').count() - 1 > MAX_ALBUM_DEPTH {
+        return Err(AppError::Validation(format!(
+            "Albums can be nested at most {} levels deep",
+            MAX_ALBUM_DEPTH
+        )));
+    }
+    Ok(path)
+}
+
+async fn create_album_at(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+    encrypted: bool,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<(), AppError> {
+    if fetch_manifest(client, repo, token, album_path).await?.is_some() {
+        return Err(AppError::Validation("Album already exists".into()));
+    }
+
+    let owner_key_id = if encrypted {
+        let handle = keypair_handle
+            .ok_or_else(|| AppError::Validation("Encrypted albums require a keypair".into()))?;
+        Some(
+            with_keypair(handle, |kp| Ok(kp.public_bundle().key_id))
+                .map_err(|e| AppError::Validation(e.to_string()))?,
+        )
+    } else {
+        None
+    };
+
+    let mut manifest = AlbumManifest::new(encrypted, owner_key_id);
+    save_manifest(client, repo, token, album_path, &mut manifest, None, keypair_handle).await?;
+    Ok(())
+}
+
+/// Create an album under `photos/`. Encrypted albums require the owner's keypair.
+/// Returns the album path.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn create_album(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    name: String,
+    encrypted: bool,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<String, AppError> {
+    validate_repo(&repo)?;
+
+    let album_path = album_path_for(ALBUM_ROOT, &name)?;
+    create_album_at(&client.0, &repo, &token, &album_path, encrypted, keypair_handle).await?;
+
+    Ok(album_path)
+}
+
+/// Create an album nested inside an existing album or folder.
+/// Sub-albums are independent: each has its own manifest and, if encrypted,
+/// its own album key. Returns the sub-album path.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn create_subalbum(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    parent_path: String,
+    name: String,
+    encrypted: bool,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<String, AppError> {
+    validate_repo(&repo)?;
+
+    let album_path = album_path_for(&parent_path, &name)?;
+    let parent = parent_path.trim_matches('/');
+
+    let url = format!("https://api.github.com/repos/{}/contents/{}", repo, parent);
+    let res = client
+        .0
+        .get(&url)
+        .header("Authorization", Bearer(&token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+    if !res.status().is_success() {
+        return Err(response_error(res, "Parent album not found").await);
+    }
+
+    create_album_at(&client.0, &repo, &token, &album_path, encrypted, keypair_handle).await?;
+
+    Ok(album_path)
+}
+
+/// Move a photo into another album, typically one of its sub-albums.
+/// Returns the photo's new path.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn move_photo_to_subalbum(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    subalbum_path: String,
+) -> Result<String, AppError> {
+    validate_repo(&repo)?;
+
+    let destination = subalbum_path.trim_matches('/');
+    if !destination.starts_with(&format!("{}/", ALBUM_ROOT)) {
+        return Err(AppError::Validation(format!("Albums must live under {}/", ALBUM_ROOT)));
+    }
+
+    let paths = vec![path];
+    let result = run_plan(
+        &client,
+        &repo,
+        &token,
+        |index| plan_move(index, &paths, destination),
+        |_| format!("Move {} to {}", paths[0], destination),
+    )
+    .await?;
+
+    match result.results.into_iter().next() {
+        Some(BatchItemResult { success: true, new_path: Some(new_path), path, .. }) => {
+            record_activity(ActivityKind::Move, "move_photo_to_subalbum", Some(&repo), &path, Some(format!("to {}", new_path)));
+            Ok(new_path)
+        }
+        Some(BatchItemResult { error, .. }) => {
+            Err(AppError::Validation(error.unwrap_or_else(|| "Move failed".into())))
+        }
+        None => Err(AppError::Validation("Move failed".into())),
+    }
+}
+
+/// Album tree rooted at `album_path`, including photo counts and metadata
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn list_subalbums(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+) -> Result<Album, AppError> {
+    validate_repo(&repo)?;
+
+    let album_path = album_path.trim_matches('/');
+    if album_path.is_empty() || album_path.split('/').any(|s| s == "..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    let name = album_path.rsplit('/').next().unwrap_or(album_path);
+    get_album_recursive(&client.0, &repo, &token, album_path, name).await
+}
+
+/// Upload a local photo into an encrypted album.
+/// The photo is compressed, encrypted with the album key and stored under a
+/// hashed name. The photo, its signature, the manifest and the metadata vault
+/// are written in one commit, so a failed upload leaves no orphaned blob.
+///
+/// Photos are not sealed with `encrypt_hybrid` one by one: the hybrid
+/// exchange wraps the album key instead, once for the owner's rotated keys
+/// (`owner_key`) and once per recipient (`access`). Sharing, revoking and
+/// key rotation then rewrap one key rather than re-encrypting every photo
+/// for each reader. A keypair without a grant in the manifest cannot open
+/// any photo of the album, including ones uploaded before it was shared;
+/// `share_album_with_contact` grants it the current key.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn upload_encrypted_photo(
+    client: State<'_, HttpClient>,
+    path: String,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    keypair_handle: KeypairHandle,
+) -> Result<UploadResult, AppError> {
+    validate_repo(&repo)?;
+
+    let album_path = album_path.trim_matches('/').to_string();
+    if album_path.is_empty() || album_path.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    // Taken first: if the manifest or vault changes after this, the commit
+    // no longer fast-forwards and fails instead of overwriting them
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let (mut manifest, _) = fetch_manifest(&client.0, &repo, &token, &album_path)
+        .await?
+        .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+
+    if !manifest.encrypted {
+        return Err(AppError::Validation("Album is not encrypted".into()));
+    }
+
+    let content = fs::read(&path).await?;
+    let filename = std::path::Path::new(&path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("photo")
+        .to_string();
+
+    let id = album_id(&repo, &album_path);
+    let album_key = album_key_for(keypair_handle, &repo, &album_path, &manifest)?;
+    let blob_name = encrypted_blob_name(&album_key, &content);
+    let payload = seal_album_photo(&album_key, &id, &filename, &content)?;
+
+    if manifest.entries.contains_key(&blob_name) {
+        return Err(AppError::Validation("Photo already exists in album".into()));
+    }
+
+    let upload_path = format!("{}/{}", album_path, blob_name);
+    let signature = sign_photo(keypair_handle, &payload)?;
+    let photo_sha = create_blob(&client.0, &repo, &token, &payload).await?;
+    let signature_sha = create_blob(&client.0, &repo, &token, &signature).await?;
+
+    manifest
+        .entries
+        .insert(blob_name.clone(), seal_filename(&album_key, &id, &filename)?);
+    manifest.record_signed(&blob_name, &payload);
+    manifest.original_bytes += content.len() as u64;
+    manifest.stored_bytes += payload.len() as u64;
+
+    let (mut vault, _) = fetch_vault(&client.0, &repo, &token, &album_path, &album_key).await?;
+    vault
+        .entries
+        .insert(blob_name, PhotoMetadata::for_upload(&filename, &content));
+
+    let body = manifest_body(&mut manifest, Some(keypair_handle))?;
+    let manifest_sha = create_blob(&client.0, &repo, &token, &body).await?;
+    let changes = vec![
+        TreeChange::blob(&upload_path, &photo_sha),
+        TreeChange::blob(&signature_path(&upload_path), &signature_sha),
+        TreeChange::blob(&format!("{}/{}", album_path, ALBUM_MANIFEST_FILE), &manifest_sha),
+        stage_vault(&client.0, &repo, &token, &album_path, &mut vault, &manifest, &album_key).await?,
+    ];
+    commit_changes(&client.0, &repo, &token, &head, &changes, &format!("Upload {}", upload_path)).await?;
+    replicate_tree_changes(&client.0, &repo, &token, &changes);
+
+    record_activity(ActivityKind::Upload, "upload_encrypted_photo", Some(&repo), &upload_path, None);
+    Ok(UploadResult {
+        url: format!("https://github.com/{}/blob/{}/{}", repo, head.branch, upload_path),
+        sha: photo_sha,
+    })
+}
+
+/// Load an album's manifest for editing. Plain folders get a fresh
+/// unencrypted manifest so they can carry display metadata too.
+pub(crate) async fn manifest_for_update(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+) -> Result<(AlbumManifest, Option<String>), AppError> {
+    if album_path.is_empty() || album_path.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    Ok(match fetch_manifest(client, repo, token, album_path).await? {
+        Some((manifest, sha)) => (manifest, Some(sha)),
+        None => (AlbumManifest::new(false, None), None),
+    })
+}
+
+/// Set or clear an album's cover photo (a file name inside the album folder).
+/// With `keypair_handle`, the updated manifest is signed.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_album_cover(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    cover: Option<String>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<(), AppError> {
+    validate_repo(&repo)?;
+    let album_path = album_path.trim_matches('/');
+    let (mut manifest, sha) = manifest_for_update(&client.0, &repo, &token, album_path).await?;
+    manifest.set_cover(cover.as_deref())?;
+    save_manifest(&client.0, &repo, &token, album_path, &mut manifest, sha.as_deref(), keypair_handle).await?;
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_album_description(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    description: Option<String>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<(), AppError> {
+    validate_repo(&repo)?;
+    let album_path = album_path.trim_matches('/');
+    let (mut manifest, sha) = manifest_for_update(&client.0, &repo, &token, album_path).await?;
+    manifest.set_description(description.as_deref())?;
+    save_manifest(&client.0, &repo, &token, album_path, &mut manifest, sha.as_deref(), keypair_handle).await?;
+    Ok(())
+}
+
+/// Set a custom metadata entry on an album; a `None` value removes the key
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_album_metadata(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    key: String,
+    value: Option<String>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<(), AppError> {
+    validate_repo(&repo)?;
+    let album_path = album_path.trim_matches('/');
+    let (mut manifest, sha) = manifest_for_update(&client.0, &repo, &token, album_path).await?;
+    manifest.set_metadata(&key, value.as_deref())?;
+    save_manifest(&client.0, &repo, &token, album_path, &mut manifest, sha.as_deref(), keypair_handle).await?;
+    Ok(())
+}
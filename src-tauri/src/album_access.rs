@@ -0,0 +1,306 @@
+//! Shared Encrypted Albums
+//!
+//! An encrypted album is shared with a contact by wrapping its content key to
+//! the contact's public bundle and storing the result in the album manifest
+//! (`AlbumManifest::access`). The recipient unwraps it with their own keypair,
+//! so `album_key_for` works for owners and recipients alike.
+//!
+//! Revoking a recipient rotates the album key: the album moves to the next
+//! key epoch, every photo is re-sealed under the new key (which also changes
+//! its blob name) along with the album's metadata vault and comments, and the remaining
+//! recipients get the new key wrapped for them. Everything lands in a single commit. Old ciphertext stays reachable
+//! through Git history, so a revoked recipient keeps what they could already
+//! read; they cannot read anything added afterwards.
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::album::{
+    album_key_for, encrypted_blob_name, fetch_manifest, manifest_body, open_album_photo, open_filename,
+    owner_album_key, save_manifest, seal_album_photo, seal_filename, wrap_album_key, AlbumGrant, AlbumManifest,
+    ALBUM_MANIFEST_FILE,
+};
+use crate::comments::rekey_comments;
+use crate::contacts::{contact_bundle, load_contacts};
+use crate::crypto::{with_keypair, KeypairHandle, PublicBundle};
+use crate::git_data::{branch_head, commit_changes, create_blob, get_blob, get_tree_recursive, index_blobs, TreeChange};
+use crate::github::{validate_repo, AppError, GithubError, HttpClient};
+use crate::metadata_vault::{load_vault, stage_vault, MetadataVault};
+use crate::mirror::replicate_tree_changes;
+use crate::security_verify::{sign_photo, signature_path};
+use crate::sharing::album_id;
+use crate::util::now_secs;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlbumRecipient {
+    pub key_id: String,
+    pub fingerprint: String,
+    /// Matching entry of the local contact book, if any
+    pub contact_id: Option<String>,
+    pub contact_name: Option<String>,
+    pub granted_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevokeReport {
+    pub key_epoch: u32,
+    pub reencrypted: usize,
+    pub remaining_recipients: usize,
+    pub commit_sha: String,
+}
+
+/// A photo re-sealed under a new album key
+#[derive(Clone, Debug)]
+pub struct RekeyedPhoto {
+    pub blob_name: String,
+    pub payload: Vec<u8>,
+    pub sealed_name: String,
+}
+
+fn normalize_album(album_path: &str) -> Result<String, AppError> {
+    let album = album_path.trim().trim_matches('/');
+    if album.is_empty() || album.split('/').any(|s| s.is_empty() || s == "." || s == "..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    Ok(album.to_string())
+}
+
+/// Only the owner of an encrypted album may change who has access
+fn require_owner(manifest: &AlbumManifest, handle: KeypairHandle) -> Result<(), AppError> {
+    if !manifest.encrypted {
+        return Err(AppError::Validation("Only encrypted albums can be shared with contacts".into()));
+    }
+    let key_id = with_keypair(handle, |kp| Ok(kp.public_bundle().key_id))
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    if manifest.owner_key_id.as_deref() != Some(key_id.as_str()) {
+        return Err(AppError::Validation("Only the album owner can change who has access".into()));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Key Handling
+// ============================================================================
+
+/// Wrap `album_key` for `bundle` and record it in the manifest, replacing any
+/// earlier grant for the same key
+pub fn grant_access(
+    manifest: &mut AlbumManifest,
+    album_key: &[u8; 32],
+    album_id: &str,
+    bundle: PublicBundle,
+) -> Result<(), AppError> {
+    if manifest.owner_key_id.as_deref() == Some(bundle.key_id.as_str()) {
+        return Err(AppError::Validation("The owner already has access".into()));
+    }
+    let wrapped_key = wrap_album_key(album_key, &bundle, album_id, manifest.key_epoch)?;
+    manifest.access.insert(
+        bundle.key_id.clone(),
+        AlbumGrant {
+            bundle,
+            wrapped_key,
+            granted_at: now_secs(),
+        },
+    );
+    Ok(())
+}
+
+/// Re-wrap every remaining grant for the manifest's current epoch
+pub fn rewrap_grants(manifest: &mut AlbumManifest, album_key: &[u8; 32], album_id: &str) -> Result<(), AppError> {
+    let epoch = manifest.key_epoch;
+    for grant in manifest.access.values_mut() {
+        grant.wrapped_key = wrap_album_key(album_key, &grant.bundle, album_id, epoch)?;
+    }
+    Ok(())
+}
+
+/// Decrypt a photo payload with `old_key` and seal it again under `new_key`
+pub fn rekey_photo(
+    old_key: &[u8; 32],
+    new_key: &[u8; 32],
+    album_id: &str,
+    payload: &[u8],
+    sealed_name: Option<&str>,
+) -> Result<RekeyedPhoto, AppError> {
+    let (data, payload_name) = open_album_photo(old_key, album_id, payload)?;
+    let name = sealed_name
+        .and_then(|sealed| open_filename(old_key, album_id, sealed).ok())
+        .or(payload_name)
+        .unwrap_or_else(|| "photo".to_string());
+
+    Ok(RekeyedPhoto {
+        blob_name: encrypted_blob_name(new_key, &data),
+        payload: seal_album_photo(new_key, album_id, &name, &data)?,
+        sealed_name: seal_filename(new_key, album_id, &name)?,
+    })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Give a contact access to an encrypted album by wrapping its key for them
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn share_album_with_contact(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    contact_id: String,
+    keypair_handle: KeypairHandle,
+) -> Result<Vec<AlbumRecipient>, AppError> {
+    validate_repo(&repo)?;
+    let album = normalize_album(&album_path)?;
+    let bundle = contact_bundle(&contact_id)?;
+
+    let (mut manifest, sha) = fetch_manifest(&client.0, &repo, &token, &album)
+        .await?
+        .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+    require_owner(&manifest, keypair_handle)?;
+
+    let id = album_id(&repo, &album);
+    let key = album_key_for(keypair_handle, &repo, &album, &manifest)?;
+    let key_id = bundle.key_id.clone();
+    grant_access(&mut manifest, &key, &id, bundle)?;
+    save_manifest(&client.0, &repo, &token, &album, &mut manifest, Some(&sha), Some(keypair_handle)).await?;
+    record_activity(ActivityKind::AlbumAccess, "share_album_with_contact", Some(&repo), &album, Some(format!("with {}", key_id)));
+
+    recipients(&manifest)
+}
+
+/// Recipients an album is shared with
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn list_album_access(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+) -> Result<Vec<AlbumRecipient>, AppError> {
+    validate_repo(&repo)?;
+    let album = normalize_album(&album_path)?;
+    let (manifest, _) = fetch_manifest(&client.0, &repo, &token, &album)
+        .await?
+        .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+    recipients(&manifest)
+}
+
+fn recipients(manifest: &AlbumManifest) -> Result<Vec<AlbumRecipient>, AppError> {
+    let book = load_contacts()?;
+    Ok(manifest
+        .access
+        .iter()
+        .map(|(key_id, grant)| {
+            let contact = book.find_by_key(key_id);
+            AlbumRecipient {
+                key_id: key_id.clone(),
+                fingerprint: grant.bundle.fingerprint(),
+                contact_id: contact.map(|c| c.id.clone()),
+                contact_name: contact.map(|c| c.name.clone()),
+                granted_at: grant.granted_at,
+            }
+        })
+        .collect())
+}
+
+/// Remove a recipient and rotate the album key so they cannot read anything
+/// added from now on. All photos are re-sealed in one commit.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn revoke_album_access(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    key_id: String,
+    keypair_handle: KeypairHandle,
+) -> Result<RevokeReport, AppError> {
+    validate_repo(&repo)?;
+    let album = normalize_album(&album_path)?;
+
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let manifest_path = format!("{}/{}", album, ALBUM_MANIFEST_FILE);
+    let manifest_entry = index
+        .get(&manifest_path)
+        .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+    let mut manifest: AlbumManifest = serde_json::from_slice(&get_blob(&client.0, &repo, &token, &manifest_entry.sha).await?)
+        .map_err(|e| AppError::Validation(format!("Invalid album manifest: {}", e)))?;
+    require_owner(&manifest, keypair_handle)?;
+
+    if manifest.access.remove(&key_id).is_none() {
+        return Err(GithubError::NotFound {
+            message: format!("Album is not shared with key {}", key_id),
+        }
+        .into());
+    }
+
+    let id = album_id(&repo, &album);
+    let old_key = album_key_for(keypair_handle, &repo, &album, &manifest)?;
+    // The new epoch's key is derived from the owner's current keypair again
+    manifest.key_epoch += 1;
+    manifest.owner_key = None;
+    let new_key = owner_album_key(keypair_handle, &id, manifest.key_epoch)?;
+
+    let mut old_vault = load_vault(&client.0, &repo, &token, &index, &album, &old_key).await?;
+    let mut vault = MetadataVault::default();
+
+    let mut changes = Vec::new();
+    let mut entries = std::collections::BTreeMap::new();
+    let mut renamed = std::collections::BTreeMap::new();
+    for (blob, sealed) in std::mem::take(&mut manifest.entries) {
+        let path = format!("{}/{}", album, blob);
+        let Some(entry) = index.get(&path) else {
+            continue;
+        };
+        let payload = get_blob(&client.0, &repo, &token, &entry.sha).await?;
+        let rekeyed = rekey_photo(&old_key, &new_key, &id, &payload, Some(&sealed))?;
+
+        manifest.stored_bytes = manifest.stored_bytes.saturating_sub(payload.len() as u64) + rekeyed.payload.len() as u64;
+        if manifest.cover.as_deref() == Some(blob.as_str()) {
+            manifest.cover = Some(rekeyed.blob_name.clone());
+        }
+        let new_path = format!("{}/{}", album, rekeyed.blob_name);
+        let sha = create_blob(&client.0, &repo, &token, &rekeyed.payload).await?;
+        let signature = create_blob(&client.0, &repo, &token, &sign_photo(keypair_handle, &rekeyed.payload)?).await?;
+        changes.push(TreeChange::delete(&path));
+        if index.contains_key(&signature_path(&path)) {
+            changes.push(TreeChange::delete(&signature_path(&path)));
+        }
+        changes.push(TreeChange::blob(&new_path, &sha));
+        changes.push(TreeChange::blob(&signature_path(&new_path), &signature));
+        if let Some(metadata) = old_vault.entries.remove(&blob) {
+            vault.entries.insert(rekeyed.blob_name.clone(), metadata);
+        }
+        manifest.signed.remove(&blob);
+        manifest.record_signed(&rekeyed.blob_name, &rekeyed.payload);
+        renamed.insert(blob, rekeyed.blob_name.clone());
+        entries.insert(rekeyed.blob_name, rekeyed.sealed_name);
+    }
+    let reencrypted = entries.len();
+    manifest.entries = entries;
+    changes.push(stage_vault(&client.0, &repo, &token, &album, &mut vault, &manifest, &new_key).await?);
+    changes.extend(rekey_comments(&client.0, &repo, &token, &index, &album, &old_key, &new_key, &renamed).await?);
+    rewrap_grants(&mut manifest, &new_key, &id)?;
+
+    let body = manifest_body(&mut manifest, Some(keypair_handle))?;
+    let manifest_sha = create_blob(&client.0, &repo, &token, &body).await?;
+    changes.push(TreeChange::blob(&manifest_path, &manifest_sha));
+
+    // The commit message must not reveal who lost access
+    let message = format!("Rotate key of {}", album);
+    let commit_sha = commit_changes(&client.0, &repo, &token, &head, &changes, &message).await?;
+    replicate_tree_changes(&client.0, &repo, &token, &changes);
+    let detail = format!("from {}, key epoch {}", key_id, manifest.key_epoch);
+    record_activity(ActivityKind::AlbumAccess, "revoke_album_access", Some(&repo), &album, Some(detail));
+
+    Ok(RevokeReport {
+        key_epoch: manifest.key_epoch,
+        reencrypted,
+        remaining_recipients: manifest.access.len(),
+        commit_sha,
+    })
+}
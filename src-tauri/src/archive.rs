@@ -0,0 +1,419 @@
+//! Album Archives
+//!
+//! `create_archive` bundles files (typically a whole album) into one portable
+//! `.vortex` file and `extract_archive` unpacks it again. Each entry is
+//! compressed on its own (already-compressed media is stored as is, see
+//! `entropy`), optionally encrypted, and carries a BLAKE3 checksum of its
+//! original bytes that is checked on extraction.
+//!
+//! Format:
+//!
+//! ```text
+//! [magic "VXARCHIV"][version: 1][flags: 1]
+//! [kdf params: 12][salt: 16]                  (encrypted archives only)
+//! [entry 0][entry 1]...[entry n-1]
+//! [index][index length: u64 LE][magic "VXINDEX1"]
+//! ```
+//!
+//! The index is JSON listing each entry's name, offset, lengths, algorithm
+//! and checksum; it sits at the end so entries can be written as they are
+//! read. In encrypted archives the index and every entry are
+//! ChaCha20-Poly1305 under an Argon2id key from the password; the index AAD
+//! is the header, and each entry's AAD binds its position and name.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+use crate::compress::{compress_auto, decompress, Algorithm};
+use crate::crypto::{current_kdf_params, decrypt_with_key, encrypt_with_key, KdfParams};
+use crate::github::AppError;
+use crate::util::now_secs;
+
+const MAGIC: &[u8; 8] = b"VXARCHIV";
+const VERSION: u8 = 1;
+const INDEX_MAGIC: &[u8; 8] = b"VXINDEX1";
+const FLAG_ENCRYPTED: u8 = 1;
+const SALT_LEN: usize = 16;
+const FOOTER_LEN: u64 = 16;
+const ENTRY_AAD: &[u8] = b"vortex-archive-entry-v1\0";
+const MAX_INDEX_LEN: u64 = 64 * 1024 * 1024;
+/// Entries are processed in memory, one at a time
+pub const MAX_ENTRY_BYTES: u64 = 1024 * 1024 * 1024;
+const MAX_NAME_LEN: usize = 255;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub name: String,
+    /// Position of the stored bytes from the start of the archive
+    pub offset: u64,
+    pub stored_len: u64,
+    pub original_len: u64,
+    pub algorithm: Algorithm,
+    /// BLAKE3 of the original bytes, hex
+    pub checksum: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ArchiveIndex {
+    pub created_at: u64,
+    pub entries: Vec<ArchiveEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchiveSummary {
+    pub path: String,
+    pub entries: usize,
+    pub original_bytes: u64,
+    pub archive_bytes: u64,
+    pub encrypted: bool,
+}
+
+/// Entry names are bare file names: no separators, no `..`, no control
+/// characters, so extraction cannot escape its directory
+pub fn validate_entry_name(name: &str) -> Result<(), AppError> {
+    if name.is_empty()
+        || name.len() > MAX_NAME_LEN
+        || name == "."
+        || name == ".."
+        || name.contains(['/', '\\'])
+        || name.chars().any(char::is_control)
+    {
+        return Err(AppError::Validation(format!("Invalid archive entry name: {:?}", name)));
+    }
+    Ok(())
+}
+
+fn entry_aad(position: usize, name: &str) -> Vec<u8> {
+    let mut aad = ENTRY_AAD.to_vec();
+    aad.extend_from_slice(&(position as u64).to_le_bytes());
+    aad.extend_from_slice(name.as_bytes());
+    aad
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+pub struct ArchiveWriter<W: Write> {
+    writer: W,
+    header: Vec<u8>,
+    position: u64,
+    key: Option<Zeroizing<[u8; 32]>>,
+    names: HashSet<String>,
+    index: ArchiveIndex,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Start an archive, encrypted when `password` is given
+    pub fn new(mut writer: W, password: Option<&[u8]>) -> Result<Self, AppError> {
+        let mut header = MAGIC.to_vec();
+        header.push(VERSION);
+        let key = match password {
+            Some(password) => {
+                let params = current_kdf_params();
+                let salt = rand::random::<[u8; SALT_LEN]>();
+                header.push(FLAG_ENCRYPTED);
+                header.extend_from_slice(&params.to_bytes());
+                header.extend_from_slice(&salt);
+                Some(Zeroizing::new(params.derive(password, &salt)?))
+            }
+            None => {
+                header.push(0);
+                None
+            }
+        };
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            position: header.len() as u64,
+            header,
+            key,
+            names: HashSet::new(),
+            index: ArchiveIndex {
+                created_at: now_secs(),
+                entries: Vec::new(),
+            },
+        })
+    }
+
+    /// Compress, seal and append one entry
+    pub fn add(&mut self, name: &str, data: &[u8]) -> Result<&ArchiveEntry, AppError> {
+        validate_entry_name(name)?;
+        if data.len() as u64 > MAX_ENTRY_BYTES {
+            return Err(AppError::Validation(format!("{} is too large for an archive entry", name)));
+        }
+        if !self.names.insert(name.to_string()) {
+            return Err(AppError::Validation(format!("Duplicate archive entry: {}", name)));
+        }
+
+        let compressed = compress_auto(data, false).map_err(|e| AppError::Validation(e.to_string()))?;
+        let (algorithm, body) = if compressed.was_compressed {
+            (compressed.algorithm, compressed.data)
+        } else {
+            (Algorithm::None, data.to_vec())
+        };
+        let stored = match &self.key {
+            Some(key) => {
+                encrypt_with_key(&body, key, &entry_aad(self.index.entries.len(), name))?
+            }
+            None => body,
+        };
+        self.writer.write_all(&stored)?;
+
+        self.index.entries.push(ArchiveEntry {
+            name: name.to_string(),
+            offset: self.position,
+            stored_len: stored.len() as u64,
+            original_len: data.len() as u64,
+            algorithm,
+            checksum: blake3::hash(data).to_hex().to_string(),
+        });
+        self.position += stored.len() as u64;
+        Ok(self.index.entries.last().expect("just pushed"))
+    }
+
+    /// Write the index and footer; returns the writer and the index
+    pub fn finish(mut self) -> Result<(W, ArchiveIndex), AppError> {
+        let json = serde_json::to_vec(&self.index)
+            .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        let index = match &self.key {
+            Some(key) => encrypt_with_key(&json, key, &self.header)?,
+            None => json,
+        };
+        self.writer.write_all(&index)?;
+        self.writer.write_all(&(index.len() as u64).to_le_bytes())?;
+        self.writer.write_all(INDEX_MAGIC)?;
+        self.writer.flush()?;
+        Ok((self.writer, self.index))
+    }
+}
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+pub struct ArchiveReader<R: Read + Seek> {
+    reader: R,
+    key: Option<Zeroizing<[u8; 32]>>,
+    index: ArchiveIndex,
+}
+
+impl<R: Read + Seek> ArchiveReader<R> {
+    /// Read the header and index. Encrypted archives need `password`.
+    pub fn open(mut reader: R, password: Option<&[u8]>) -> Result<Self, AppError> {
+        let invalid = || AppError::Validation("Not a Vortex archive".into());
+
+        let mut header = vec![0u8; MAGIC.len() + 2];
+        reader.read_exact(&mut header).map_err(|_| invalid())?;
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(invalid());
+        }
+        if header[MAGIC.len()] != VERSION {
+            return Err(AppError::Validation(format!(
+                "Unsupported archive version {}",
+                header[MAGIC.len()]
+            )));
+        }
+        let encrypted = header[MAGIC.len() + 1] & FLAG_ENCRYPTED != 0;
+        let key = if encrypted {
+            let mut kdf = [0u8; KdfParams::ENCODED_LEN + SALT_LEN];
+            reader.read_exact(&mut kdf).map_err(|_| invalid())?;
+            header.extend_from_slice(&kdf);
+            let password =
+                password.ok_or_else(|| AppError::Validation("This archive is encrypted; a password is required".into()))?;
+            let params = KdfParams::from_bytes(&kdf[..KdfParams::ENCODED_LEN]);
+            Some(Zeroizing::new(
+                params.derive(password, &kdf[KdfParams::ENCODED_LEN..])?,
+            ))
+        } else {
+            None
+        };
+        let data_start = header.len() as u64;
+
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end < data_start + FOOTER_LEN {
+            return Err(invalid());
+        }
+        reader.seek(SeekFrom::Start(end - FOOTER_LEN))?;
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        reader.read_exact(&mut footer)?;
+        if &footer[8..] != INDEX_MAGIC {
+            return Err(AppError::Validation("Archive is truncated or damaged".into()));
+        }
+        let index_len = u64::from_le_bytes(footer[..8].try_into().expect("8 bytes"));
+        let index_start = (end - FOOTER_LEN)
+            .checked_sub(index_len)
+            .filter(|&start| start >= data_start && index_len <= MAX_INDEX_LEN)
+            .ok_or_else(|| AppError::Validation("Archive index is damaged".into()))?;
+
+        reader.seek(SeekFrom::Start(index_start))?;
+        let mut raw = vec![0u8; index_len as usize];
+        reader.read_exact(&mut raw)?;
+        let json = match &key {
+            Some(key) => decrypt_with_key(&raw, key, &header)
+                .map_err(|_| AppError::Validation("Wrong password or damaged archive".into()))?,
+            None => raw,
+        };
+        let index: ArchiveIndex = serde_json::from_slice(&json)
+            .map_err(|e| AppError::Validation(format!("Invalid archive index: {}", e)))?;
+
+        for entry in &index.entries {
+            validate_entry_name(&entry.name)?;
+            let within = entry.offset >= data_start
+                && entry
+                    .offset
+                    .checked_add(entry.stored_len)
+                    .is_some_and(|end| end <= index_start);
+            if !within || entry.original_len > MAX_ENTRY_BYTES || entry.stored_len > MAX_ENTRY_BYTES + 64 {
+                return Err(AppError::Validation(format!("Archive entry {} is out of bounds", entry.name)));
+            }
+        }
+
+        Ok(Self { reader, key, index })
+    }
+
+    pub fn index(&self) -> &ArchiveIndex {
+        &self.index
+    }
+
+    /// Original bytes of entry `position`, checked against its checksum
+    pub fn read_entry(&mut self, position: usize) -> Result<Vec<u8>, AppError> {
+        let entry = self
+            .index
+            .entries
+            .get(position)
+            .ok_or_else(|| AppError::Validation(format!("No archive entry {}", position)))?;
+
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        let mut stored = vec![0u8; entry.stored_len as usize];
+        self.reader.read_exact(&mut stored)?;
+        let body = match &self.key {
+            Some(key) => decrypt_with_key(&stored, key, &entry_aad(position, &entry.name))
+                .map_err(|_| AppError::Validation(format!("Archive entry {} failed authentication", entry.name)))?,
+            None => stored,
+        };
+        let data = match entry.algorithm {
+            Algorithm::None => body,
+            algorithm => decompress(&body, algorithm).map_err(|e| AppError::Validation(e.to_string()))?,
+        };
+
+        if data.len() as u64 != entry.original_len || blake3::hash(&data).to_hex().as_str() != entry.checksum {
+            return Err(AppError::Validation(format!("Archive entry {} is corrupted", entry.name)));
+        }
+        Ok(data)
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Validation(format!("Archive task failed: {}", e)))?
+}
+
+fn write_archive(path: &Path, files: &[String], password: Option<&Zeroizing<String>>) -> Result<ArchiveIndex, AppError> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut archive = ArchiveWriter::new(file, password.map(|p| p.as_bytes()))?;
+    for file in files {
+        let name = Path::new(file)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| AppError::Validation(format!("Invalid file path: {}", file)))?;
+        archive.add(name, &std::fs::read(file)?)?;
+    }
+    let (_, index) = archive.finish()?;
+    Ok(index)
+}
+
+fn open_archive(path: &str, password: Option<&Zeroizing<String>>) -> Result<ArchiveReader<BufReader<File>>, AppError> {
+    ArchiveReader::open(BufReader::new(File::open(path)?), password.map(|p| p.as_bytes()))
+}
+
+/// Bundle `files` into the archive `output`, encrypted when `password` is
+/// given. Entries are named after the files.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn create_archive(
+    files: Vec<String>,
+    output: String,
+    password: Option<String>,
+) -> Result<ArchiveSummary, AppError> {
+    let password = password.map(Zeroizing::new);
+    if let Some(password) = &password {
+        crate::password::ensure_strong(password)?;
+    }
+    if files.is_empty() {
+        return Err(AppError::Validation("No files to archive".into()));
+    }
+
+    run_blocking(move || {
+        let output_path = PathBuf::from(&output);
+        let partial = output_path.with_extension("part");
+        let index = match write_archive(&partial, &files, password.as_ref()) {
+            Ok(index) => index,
+            Err(e) => {
+                let _ = std::fs::remove_file(&partial);
+                return Err(e);
+            }
+        };
+        std::fs::rename(&partial, &output_path)?;
+
+        Ok(ArchiveSummary {
+            archive_bytes: std::fs::metadata(&output_path)?.len(),
+            path: output,
+            entries: index.entries.len(),
+            original_bytes: index.entries.iter().map(|e| e.original_len).sum(),
+            encrypted: password.is_some(),
+        })
+    })
+    .await
+}
+
+/// List the entries of an archive without extracting them
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn list_archive(archive: String, password: Option<String>) -> Result<ArchiveIndex, AppError> {
+    let password = password.map(Zeroizing::new);
+    run_blocking(move || Ok(open_archive(&archive, password.as_ref())?.index().clone())).await
+}
+
+/// Extract every entry of `archive` into `output_dir`. Existing files are
+/// never overwritten. Returns the written paths.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn extract_archive(
+    archive: String,
+    output_dir: String,
+    password: Option<String>,
+) -> Result<Vec<String>, AppError> {
+    let password = password.map(Zeroizing::new);
+    run_blocking(move || {
+        let mut reader = open_archive(&archive, password.as_ref())?;
+        let dir = PathBuf::from(&output_dir);
+        std::fs::create_dir_all(&dir)?;
+
+        let names: Vec<String> = reader.index().entries.iter().map(|e| e.name.clone()).collect();
+        if let Some(existing) = names.iter().find(|name| dir.join(name).exists()) {
+            return Err(AppError::Validation(format!("{} already exists in {}", existing, output_dir)));
+        }
+
+        let mut written = Vec::with_capacity(names.len());
+        for (position, name) in names.iter().enumerate() {
+            let data = reader.read_entry(position)?;
+            let path = dir.join(name);
+            std::fs::write(&path, data)?;
+            written.push(path.to_string_lossy().into_owned());
+        }
+        Ok(written)
+    })
+    .await
+}
@@ -0,0 +1,239 @@
+//! Batch Photo Operations
+//!
+//! Delete or move many photos in one Git commit using the Git data API.
+//! Each requested path is validated against the current tree first; paths
+//! that cannot be applied are reported individually and the rest are
+//! committed together. Detached photo signatures follow their photo.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::album::ENCRYPTED_BLOB_EXT;
+use crate::git_data::{branch_head, commit_changes, get_tree_recursive, index_blobs, TreeChange, TreeIndex};
+use crate::github::{validate_repo, AppError, HttpClient};
+use crate::mirror::replicate_tree_changes;
+use crate::security_verify::{move_signature, signature_path};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    pub path: String,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Destination path for moves
+    pub new_path: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchResult {
+    /// Commit containing all successful changes, `None` if nothing changed
+    pub commit_sha: Option<String>,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+/// Tree changes to commit together with the per-item outcome
+#[derive(Debug, Default)]
+pub struct BatchPlan {
+    pub changes: Vec<TreeChange>,
+    pub results: Vec<BatchItemResult>,
+}
+
+impl BatchPlan {
+    fn ok(&mut self, path: &str, new_path: Option<String>) {
+        self.results.push(BatchItemResult {
+            path: path.to_string(),
+            success: true,
+            error: None,
+            new_path,
+        });
+    }
+
+    fn fail(&mut self, path: &str, error: &str) {
+        self.results.push(BatchItemResult {
+            path: path.to_string(),
+            success: false,
+            error: Some(error.to_string()),
+            new_path: None,
+        });
+    }
+
+    fn into_result(self, commit_sha: Option<String>) -> BatchResult {
+        let succeeded = self.results.iter().filter(|r| r.success).count();
+        BatchResult {
+            commit_sha,
+            succeeded,
+            failed: self.results.len() - succeeded,
+            results: self.results,
+        }
+    }
+}
+
+fn normalize_path(path: &str) -> Option<String> {
+    let trimmed = path.trim().trim_matches('/');
+    if trimmed.is_empty() || trimmed.split('/').any(|s| s.is_empty() || s == "." || s == "..") {
+        return None;
+    }
+    Some(trimmed.to_string())
+}
+
+// ============================================================================
+// Planning
+// ============================================================================
+
+pub fn plan_delete(index: &TreeIndex, paths: &[String]) -> BatchPlan {
+    let mut plan = BatchPlan::default();
+    let mut staged = HashSet::new();
+
+    for raw in paths {
+        let Some(path) = normalize_path(raw) else {
+            plan.fail(raw, "Invalid path");
+            continue;
+        };
+
+        if !index.contains_key(&path) {
+            plan.fail(raw, "File not found");
+        } else if !staged.insert(path.clone()) {
+            plan.fail(raw, "Duplicate path");
+        } else {
+            plan.changes.push(TreeChange::delete(&path));
+            if index.contains_key(&signature_path(&path)) {
+                plan.changes.push(TreeChange::delete(&signature_path(&path)));
+            }
+            plan.ok(raw, None);
+        }
+    }
+
+    plan
+}
+
+pub fn plan_move(index: &TreeIndex, paths: &[String], destination: &str) -> BatchPlan {
+    let mut plan = BatchPlan::default();
+
+    let Some(destination) = normalize_path(destination) else {
+        for raw in paths {
+            plan.fail(raw, "Invalid destination");
+        }
+        return plan;
+    };
+
+    let mut sources = HashSet::new();
+    let mut targets = HashSet::new();
+
+    for raw in paths {
+        let Some(path) = normalize_path(raw) else {
+            plan.fail(raw, "Invalid path");
+            continue;
+        };
+
+        let Some(entry) = index.get(&path) else {
+            plan.fail(raw, "File not found");
+            continue;
+        };
+
+        let filename = path.rsplit('/').next().unwrap_or(&path);
+        let new_path = format!("{}/{}", destination, filename);
+
+        if new_path == path {
+            plan.fail(raw, "Source and destination are the same");
+        } else if path.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) {
+            // Album payloads are authenticated against their album path
+            plan.fail(raw, "Encrypted photos cannot leave their album");
+        } else if index.contains_key(&new_path) || targets.contains(&new_path) {
+            plan.fail(raw, "Destination already exists");
+        } else if !sources.insert(path.clone()) {
+            plan.fail(raw, "Duplicate path");
+        } else {
+            plan.changes.push(TreeChange::put_blob(&new_path, entry));
+            plan.changes.push(TreeChange::delete(&path));
+            plan.changes.extend(move_signature(index, &path, &new_path));
+            targets.insert(new_path.clone());
+            plan.ok(raw, Some(new_path));
+        }
+    }
+
+    plan
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+pub(crate) async fn run_plan(
+    client: &HttpClient,
+    repo: &str,
+    token: &str,
+    plan_fn: impl FnOnce(&TreeIndex) -> BatchPlan,
+    message: impl FnOnce(usize) -> String,
+) -> Result<BatchResult, AppError> {
+    let head = branch_head(&client.0, repo, token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, repo, token, &head.tree_sha).await?);
+
+    let plan = plan_fn(&index);
+    if plan.changes.is_empty() {
+        return Ok(plan.into_result(None));
+    }
+
+    let count = plan.results.iter().filter(|r| r.success).count();
+    let commit = commit_changes(&client.0, repo, token, &head, &plan.changes, &message(count)).await?;
+    replicate_tree_changes(&client.0, repo, token, &plan.changes);
+    Ok(plan.into_result(Some(commit)))
+}
+
+/// Log each photo a batch changed
+fn record_batch(kind: ActivityKind, action: &str, repo: &str, result: &BatchResult) {
+    for item in result.results.iter().filter(|r| r.success && result.commit_sha.is_some()) {
+        let detail = item.new_path.as_ref().map(|p| format!("to {}", p));
+        record_activity(kind, action, Some(repo), &item.path, detail);
+    }
+}
+
+/// Delete many photos in a single commit
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn delete_photos_batch(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    paths: Vec<String>,
+) -> Result<BatchResult, AppError> {
+    validate_repo(&repo)?;
+
+    let result = run_plan(
+        &client,
+        &repo,
+        &token,
+        |index| plan_delete(index, &paths),
+        |n| format!("Delete {} photo{}", n, if n == 1 { "" } else { "s" }),
+    )
+    .await?;
+    record_batch(ActivityKind::Delete, "delete_photos_batch", &repo, &result);
+    Ok(result)
+}
+
+/// Move many photos into `destination` (a folder path) in a single commit
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn move_photos(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    paths: Vec<String>,
+    destination: String,
+) -> Result<BatchResult, AppError> {
+    validate_repo(&repo)?;
+
+    let result = run_plan(
+        &client,
+        &repo,
+        &token,
+        |index| plan_move(index, &paths, &destination),
+        |n| format!("Move {} photo{} to {}", n, if n == 1 { "" } else { "s" }, destination),
+    )
+    .await?;
+    record_batch(ActivityKind::Move, "move_photos", &repo, &result);
+    Ok(result)
+}
@@ -0,0 +1,5 @@
+//! Headless CLI for backups and automation, see `vortex_image_lib::cli`
+
+fn main() -> std::process::ExitCode {
+    vortex_image_lib::cli::main()
+}
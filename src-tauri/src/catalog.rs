@@ -0,0 +1,595 @@
+//! Local Photo Catalog
+//!
+//! Every photo a listing has seen is indexed in the profile's local store,
+//! so listings come from disk at once and keep working offline.
+//! `list_photos` answers from the catalog when the album has been listed
+//! before and reconciles with GitHub in the background, emitting
+//! `catalog-updated` when anything changed; an album never listed is
+//! fetched first. `list_photos_page` writes its pages through, and its
+//! first page falls back to the catalog when GitHub cannot be reached.
+//! Tags, favorites and ratings are set through `tagging`, which records
+//! them in the album manifest as well; `query_photos` searches them here.
+//! Free-text search runs over an in-memory index of the catalog (see
+//! `search`).
+//!
+//! Rows follow the store's scheme (see `local_store`): IDs are keyed hashes
+//! of the repository and path, and the record (the listed `PhotoItem`, its
+//! tags, ...) is sealed under the store's value key. Only the capture time,
+//! size and sync state are kept in the clear, for sorting and filtering.
+//! Decrypted names of encrypted albums are only handed out again while a
+//! keypair is unlocked.
+
+use rusqlite::{params, OptionalExtension, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::crypto::KeypairHandle;
+use crate::github::{AppError, PhotoItem};
+use crate::image_metadata::capture_time;
+use crate::local_store::{db_error, with_store, LocalStore};
+use crate::search::{clear_index, index_photo, unindex_photo};
+use crate::tagging::{PhotoOrganization, MAX_RATING};
+use crate::util::now_secs;
+
+/// Emitted with a `CatalogUpdate` when a background reconcile changed an album
+pub const CATALOG_UPDATED_EVENT: &str = "catalog-updated";
+/// Longest tag accepted, in characters
+pub const MAX_TAG_LEN: usize = 64;
+
+/// Migration 2 (see `migrations`)
+pub const CATALOG_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS catalog (
+    id BLOB PRIMARY KEY,
+    album BLOB NOT NULL,
+    taken_at INTEGER,
+    size INTEGER,
+    sync_state TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    record BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_catalog_album ON catalog(album, taken_at);
+CREATE TABLE IF NOT EXISTS catalog_tags (
+    photo BLOB NOT NULL,
+    tag BLOB NOT NULL,
+    PRIMARY KEY (photo, tag)
+);
+CREATE INDEX IF NOT EXISTS idx_catalog_tags_tag ON catalog_tags(tag);
+CREATE TABLE IF NOT EXISTS catalog_albums (
+    id BLOB PRIMARY KEY,
+    reconciled_at INTEGER NOT NULL
+);
+"#;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// As GitHub listed it last
+    #[default]
+    Synced,
+    /// Queued for deletion; hidden from listings until the delete lands
+    PendingDelete,
+}
+
+impl SyncState {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Synced => "synced",
+            Self::PendingDelete => "pending_delete",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CatalogPhoto {
+    /// Library repository; shards are recorded in `item.repo`
+    pub repo: String,
+    pub album: String,
+    /// Path in the repository
+    pub path: String,
+    pub item: PhotoItem,
+    /// Unix seconds, from the EXIF the listing carried
+    #[serde(default)]
+    pub taken_at: Option<i64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    /// 1 to `MAX_RATING` stars
+    #[serde(default)]
+    pub rating: Option<u8>,
+    #[serde(default)]
+    pub sync_state: SyncState,
+    pub updated_at: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogChanges {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl CatalogChanges {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Payload of `CATALOG_UPDATED_EVENT`
+#[derive(Clone, Debug, Serialize)]
+pub struct CatalogUpdate {
+    pub repo: String,
+    pub album: String,
+    #[serde(flatten)]
+    pub changes: CatalogChanges,
+}
+
+impl CatalogPhoto {
+    pub fn organization(&self) -> PhotoOrganization {
+        PhotoOrganization {
+            tags: self.tags.clone(),
+            favorite: self.favorite,
+            rating: self.rating,
+        }
+    }
+
+    fn organize(&mut self, organization: PhotoOrganization) {
+        self.tags = organization.tags;
+        self.favorite = organization.favorite;
+        self.rating = organization.rating;
+        self.updated_at = now_secs();
+    }
+}
+
+/// Filters of `query_photos`; unset fields match everything
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PhotoQuery {
+    #[serde(default)]
+    pub album: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub favorite: Option<bool>,
+    #[serde(default)]
+    pub min_rating: Option<u8>,
+}
+
+pub fn photo_path(album: &str, item: &PhotoItem) -> String {
+    format!("{}/{}", album.trim_matches('/'), item.name)
+}
+
+pub(crate) fn photo_id(store: &LocalStore, repo: &str, path: &str) -> [u8; 32] {
+    store.index_id(&["catalog-photo", repo, path])
+}
+
+fn album_id(store: &LocalStore, repo: &str, album: &str) -> [u8; 32] {
+    store.index_id(&["catalog-album", repo, album.trim_matches('/')])
+}
+
+fn tag_id(store: &LocalStore, repo: &str, tag: &str) -> [u8; 32] {
+    store.index_id(&["catalog-tag", repo, &tag.to_lowercase()])
+}
+
+fn put_photo(store: &LocalStore, photo: &CatalogPhoto) -> Result<(), AppError> {
+    let id = photo_id(store, &photo.repo, &photo.path);
+    let record = zeroize::Zeroizing::new(
+        serde_json::to_vec(photo).map_err(|e| AppError::Validation(e.to_string()))?,
+    );
+    let conn = store.connection();
+    conn.execute(
+        "INSERT OR REPLACE INTO catalog (id, album, taken_at, size, sync_state, updated_at, record)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            &id[..],
+            &album_id(store, &photo.repo, &photo.album)[..],
+            photo.taken_at,
+            photo.item.size.map(|s| s as i64),
+            photo.sync_state.as_str(),
+            photo.updated_at as i64,
+            store.seal(&id, &record)?,
+        ],
+    )
+    .map_err(db_error)?;
+    conn.execute("DELETE FROM catalog_tags WHERE photo = ?1", params![&id[..]])
+        .map_err(db_error)?;
+    for tag in &photo.tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO catalog_tags (photo, tag) VALUES (?1, ?2)",
+            params![&id[..], &tag_id(store, &photo.repo, tag)[..]],
+        )
+        .map_err(db_error)?;
+    }
+    index_photo(store, &id, photo)
+}
+
+fn delete_photo(store: &LocalStore, id: &[u8; 32]) -> Result<bool, AppError> {
+    let conn = store.connection();
+    conn.execute("DELETE FROM catalog_tags WHERE photo = ?1", params![&id[..]])
+        .map_err(db_error)?;
+    let removed = conn
+        .execute("DELETE FROM catalog WHERE id = ?1", params![&id[..]])
+        .map_err(db_error)?;
+    unindex_photo(store, id)?;
+    Ok(removed > 0)
+}
+
+/// Decrypt the rows `sql` selects as `(id, record)`
+fn read_photos(store: &LocalStore, sql: &str, params: impl Params) -> Result<Vec<CatalogPhoto>, AppError> {
+    let mut statement = store.connection().prepare(sql).map_err(db_error)?;
+    let rows = statement
+        .query_map(params, |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?)))
+        .map_err(db_error)?;
+
+    let mut photos = Vec::new();
+    for row in rows {
+        let (id, sealed) = row.map_err(db_error)?;
+        let id: [u8; 32] = id
+            .try_into()
+            .map_err(|_| AppError::Validation("Corrupt catalog row".into()))?;
+        let record = store.unseal(&id, &sealed)?;
+        photos.push(
+            serde_json::from_slice(&record)
+                .map_err(|e| AppError::Validation(format!("Corrupt catalog row: {}", e)))?,
+        );
+    }
+    Ok(photos)
+}
+
+fn get_photo(store: &LocalStore, repo: &str, path: &str) -> Result<Option<CatalogPhoto>, AppError> {
+    photo_by_id(store, &photo_id(store, repo, path))
+}
+
+pub(crate) fn photo_by_id(store: &LocalStore, id: &[u8; 32]) -> Result<Option<CatalogPhoto>, AppError> {
+    Ok(read_photos(store, "SELECT id, record FROM catalog WHERE id = ?1", params![&id[..]])?.pop())
+}
+
+/// Every catalogued photo, of all repositories
+pub(crate) fn all_photos(store: &LocalStore) -> Result<Vec<CatalogPhoto>, AppError> {
+    read_photos(store, "SELECT id, record FROM catalog ORDER BY taken_at", [])
+}
+
+/// Every photo of `album`, by name; `None` for an album the catalog has
+/// not been reconciled with yet
+pub fn album_photos_in(store: &LocalStore, repo: &str, album: &str) -> Result<Option<Vec<CatalogPhoto>>, AppError> {
+    let album_key = album_id(store, repo, album);
+    let reconciled: Option<i64> = store
+        .connection()
+        .query_row(
+            "SELECT reconciled_at FROM catalog_albums WHERE id = ?1",
+            params![&album_key[..]],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_error)?;
+    if reconciled.is_none() {
+        return Ok(None);
+    }
+    let mut photos = read_photos(store, "SELECT id, record FROM catalog WHERE album = ?1", params![&album_key[..]])?;
+    photos.sort_by(|a, b| a.item.name.cmp(&b.item.name));
+    Ok(Some(photos))
+}
+
+/// `item` as listed now, keeping what a listing without the album key
+/// could not see: decrypted names, captions and EXIF of the same blob
+fn merge_listed(previous: Option<&CatalogPhoto>, mut item: PhotoItem) -> PhotoItem {
+    if let Some(previous) = previous.filter(|p| p.item.sha == item.sha && item.encrypted) {
+        if item.display_name.is_none() {
+            item.display_name = previous.item.display_name.clone();
+            item.caption = item.caption.or_else(|| previous.item.caption.clone());
+            item.exif = item.exif.or_else(|| previous.item.exif.clone());
+        }
+    }
+    item
+}
+
+fn upsert(
+    store: &LocalStore,
+    repo: &str,
+    album: &str,
+    items: &[PhotoItem],
+    existing: &HashMap<String, CatalogPhoto>,
+) -> Result<(CatalogChanges, HashSet<String>), AppError> {
+    let mut changes = CatalogChanges::default();
+    let mut seen = HashSet::new();
+    for item in items {
+        let path = photo_path(album, item);
+        let previous = existing.get(&path);
+        let item = merge_listed(previous, item.clone());
+        seen.insert(path.clone());
+        match previous {
+            Some(previous) if previous.item == item => continue,
+            Some(_) => changes.updated += 1,
+            None => changes.added += 1,
+        }
+        put_photo(store, &CatalogPhoto {
+            repo: repo.to_string(),
+            album: album.trim_matches('/').to_string(),
+            path,
+            taken_at: item.exif.as_ref().and_then(capture_time),
+            tags: previous.map(|p| p.tags.clone()).unwrap_or_default(),
+            favorite: previous.is_some_and(|p| p.favorite),
+            rating: previous.and_then(|p| p.rating),
+            sync_state: previous.map(|p| p.sync_state).unwrap_or_default(),
+            updated_at: now_secs(),
+            item,
+        })?;
+    }
+    Ok((changes, seen))
+}
+
+fn existing_photos(store: &LocalStore, repo: &str, album: &str) -> Result<HashMap<String, CatalogPhoto>, AppError> {
+    let album_key = album_id(store, repo, album);
+    Ok(read_photos(store, "SELECT id, record FROM catalog WHERE album = ?1", params![&album_key[..]])?
+        .into_iter()
+        .map(|p| (p.path.clone(), p))
+        .collect())
+}
+
+/// Make the catalog's copy of `album` match the complete listing `items`.
+/// Tags and sync states survive; photos no longer listed are dropped.
+pub fn reconcile_album_in(
+    store: &LocalStore,
+    repo: &str,
+    album: &str,
+    items: &[PhotoItem],
+) -> Result<CatalogChanges, AppError> {
+    let existing = existing_photos(store, repo, album)?;
+    let transaction = store.connection().unchecked_transaction().map_err(db_error)?;
+    let (mut changes, seen) = upsert(store, repo, album, items, &existing)?;
+    for path in existing.keys().filter(|p| !seen.contains(*p)) {
+        if delete_photo(store, &photo_id(store, repo, path))? {
+            changes.removed += 1;
+        }
+    }
+    transaction
+        .execute(
+            "INSERT OR REPLACE INTO catalog_albums (id, reconciled_at) VALUES (?1, ?2)",
+            params![&album_id(store, repo, album)[..], now_secs() as i64],
+        )
+        .map_err(db_error)?;
+    transaction.commit().map_err(db_error)?;
+    Ok(changes)
+}
+
+/// Add or update `items` of `album` without dropping anything, for partial
+/// listings such as one page
+pub fn record_items_in(
+    store: &LocalStore,
+    repo: &str,
+    album: &str,
+    items: &[PhotoItem],
+) -> Result<CatalogChanges, AppError> {
+    let existing = existing_photos(store, repo, album)?;
+    let transaction = store.connection().unchecked_transaction().map_err(db_error)?;
+    let (changes, _) = upsert(store, repo, album, items, &existing)?;
+    transaction.commit().map_err(db_error)?;
+    Ok(changes)
+}
+
+/// Replace the tags of the photo at `path`. Returns false if it is not in
+/// the catalog.
+pub fn set_tags_in(store: &LocalStore, repo: &str, path: &str, tags: &[String]) -> Result<bool, AppError> {
+    let tags = normalize_tags(tags)?;
+    let Some(mut photo) = get_photo(store, repo, path)? else {
+        return Ok(false);
+    };
+    photo.organize(PhotoOrganization { tags, ..photo.organization() });
+    put_photo(store, &photo)?;
+    Ok(true)
+}
+
+/// Trimmed, without blanks or duplicates (ignoring case), sorted
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, AppError> {
+    let mut tags: Vec<String> = tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+    if let Some(tag) = tags.iter().find(|t| t.chars().count() > MAX_TAG_LEN) {
+        return Err(AppError::Validation(format!("Tag {} is longer than {} characters", tag, MAX_TAG_LEN)));
+    }
+    tags.sort_by_key(|t| t.to_lowercase());
+    tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    Ok(tags)
+}
+
+/// Give the catalogued photos of `album` the organization an album
+/// manifest records for them, by file name. Returns how many changed.
+pub fn import_organization_in(
+    store: &LocalStore,
+    repo: &str,
+    album: &str,
+    organization: &BTreeMap<String, PhotoOrganization>,
+) -> Result<usize, AppError> {
+    let transaction = store.connection().unchecked_transaction().map_err(db_error)?;
+    let mut changed = 0;
+    for mut photo in existing_photos(store, repo, album)?.into_values() {
+        let recorded = organization.get(&photo.item.name).cloned().unwrap_or_default();
+        if photo.organization() != recorded {
+            photo.organize(recorded);
+            put_photo(store, &photo)?;
+            changed += 1;
+        }
+    }
+    transaction.commit().map_err(db_error)?;
+    Ok(changed)
+}
+
+/// Catalogued photos of `repo` matching `query`, oldest first
+pub fn query_photos_in(store: &LocalStore, repo: &str, query: &PhotoQuery) -> Result<Vec<CatalogPhoto>, AppError> {
+    if query.min_rating.is_some_and(|r| r > MAX_RATING) {
+        return Err(AppError::Validation(format!("Ratings go up to {}", MAX_RATING)));
+    }
+    let mut photos = match (&query.tag, &query.album) {
+        (Some(tag), _) => photos_with_tag_in(store, repo, tag)?,
+        (None, Some(album)) => album_photos_in(store, repo, album)?.unwrap_or_default(),
+        (None, None) => all_photos(store)?,
+    };
+    let album = query.album.as_deref().map(|a| a.trim_matches('/'));
+    photos.retain(|p| {
+        p.repo == repo
+            && p.sync_state != SyncState::PendingDelete
+            && album.is_none_or(|a| p.album == a)
+            && query.favorite.is_none_or(|f| p.favorite == f)
+            && query.min_rating.is_none_or(|min| p.rating.unwrap_or(0) >= min)
+    });
+    photos.sort_by(|a, b| (a.taken_at, &a.path).cmp(&(b.taken_at, &b.path)));
+    Ok(photos)
+}
+
+/// Photos of `repo` tagged `tag`, ignoring case
+pub fn photos_with_tag_in(store: &LocalStore, repo: &str, tag: &str) -> Result<Vec<CatalogPhoto>, AppError> {
+    let mut photos = read_photos(
+        store,
+        "SELECT catalog.id, catalog.record FROM catalog
+         JOIN catalog_tags ON catalog_tags.photo = catalog.id
+         WHERE catalog_tags.tag = ?1
+         ORDER BY catalog.taken_at",
+        params![&tag_id(store, repo, tag.trim())[..]],
+    )?;
+    photos.retain(|p| p.sync_state != SyncState::PendingDelete);
+    Ok(photos)
+}
+
+/// Set the sync state of the photo at `path`, if the catalog has it
+pub fn set_sync_state_in(store: &LocalStore, repo: &str, path: &str, state: SyncState) -> Result<bool, AppError> {
+    let Some(mut photo) = get_photo(store, repo, path)? else {
+        return Ok(false);
+    };
+    photo.sync_state = state;
+    photo.updated_at = now_secs();
+    put_photo(store, &photo)?;
+    Ok(true)
+}
+
+pub fn remove_photo_in(store: &LocalStore, repo: &str, path: &str) -> Result<bool, AppError> {
+    delete_photo(store, &photo_id(store, repo, path))
+}
+
+/// Empty the catalog, returning how many photos it held
+pub fn clear_catalog_in(store: &LocalStore) -> Result<usize, AppError> {
+    let conn = store.connection();
+    conn.execute("DELETE FROM catalog_tags", []).map_err(db_error)?;
+    conn.execute("DELETE FROM catalog_albums", []).map_err(db_error)?;
+    clear_index(store)?;
+    conn.execute("DELETE FROM catalog", []).map_err(db_error)
+}
+
+/// `item` without the decrypted details of an encrypted album
+fn locked(mut item: PhotoItem) -> PhotoItem {
+    if item.encrypted {
+        item.display_name = None;
+        item.caption = None;
+        item.exif = None;
+    }
+    item
+}
+
+/// What a listing shows of `photos`: those not pending deletion, without
+/// the decrypted details of encrypted albums unless `unlocked`
+pub fn listed_items(photos: Vec<CatalogPhoto>, unlocked: bool) -> Vec<PhotoItem> {
+    photos
+        .into_iter()
+        .filter(|p| p.sync_state != SyncState::PendingDelete)
+        .map(|p| if unlocked { p.item } else { locked(p.item) })
+        .collect()
+}
+
+fn for_caller(photos: Vec<CatalogPhoto>, keypair_handle: Option<KeypairHandle>) -> Vec<CatalogPhoto> {
+    if keypair_handle.is_some() {
+        return photos;
+    }
+    photos
+        .into_iter()
+        .map(|p| CatalogPhoto { item: locked(p.item), ..p })
+        .collect()
+}
+
+// ============================================================================
+// Listing Hooks
+// ============================================================================
+//
+// The catalog only speeds listings up, so these log failures instead of
+// failing the listing.
+
+/// `album` as the catalog has it, if it was listed before
+pub(crate) fn cached_listing(repo: &str, album: &str, unlocked: bool) -> Option<Vec<PhotoItem>> {
+    match with_store(|store| album_photos_in(store, repo, album)) {
+        Ok(photos) => photos.map(|p| listed_items(p, unlocked)),
+        Err(e) => {
+            tracing::warn!("Could not read the catalog of {}: {}", album, e);
+            None
+        }
+    }
+}
+
+/// Record the complete listing of `album`
+pub(crate) fn reconcile_listing(repo: &str, album: &str, items: &[PhotoItem]) -> CatalogChanges {
+    with_store(|store| reconcile_album_in(store, repo, album, items)).unwrap_or_else(|e| {
+        tracing::warn!("Could not update the catalog of {}: {}", album, e);
+        CatalogChanges::default()
+    })
+}
+
+/// Record part of the listing of `album`
+pub(crate) fn record_listed(repo: &str, album: &str, items: &[PhotoItem]) -> CatalogChanges {
+    with_store(|store| record_items_in(store, repo, album, items)).unwrap_or_else(|e| {
+        tracing::warn!("Could not update the catalog of {}: {}", album, e);
+        CatalogChanges::default()
+    })
+}
+
+pub(crate) fn note_sync_state(repo: &str, path: &str, state: SyncState) {
+    if let Err(e) = with_store(|store| set_sync_state_in(store, repo, path, state)) {
+        tracing::warn!("Could not update the catalog entry of {}: {}", path, e);
+    }
+}
+
+pub(crate) fn import_organization(repo: &str, album: &str, organization: &BTreeMap<String, PhotoOrganization>) {
+    if let Err(e) = with_store(|store| import_organization_in(store, repo, album, organization)) {
+        tracing::warn!("Could not update the catalog of {}: {}", album, e);
+    }
+}
+
+pub(crate) fn forget_photo(repo: &str, path: &str) {
+    if let Err(e) = with_store(|store| remove_photo_in(store, repo, path)) {
+        tracing::warn!("Could not update the catalog entry of {}: {}", path, e);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Catalogued photos tagged `tag`, oldest first
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn catalog_photos_with_tag(
+    repo: String,
+    tag: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<Vec<CatalogPhoto>, AppError> {
+    let photos = with_store(|store| photos_with_tag_in(store, &repo, &tag))?;
+    Ok(for_caller(photos, keypair_handle))
+}
+
+/// Catalogued photos matching `query`, oldest first
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn query_photos(
+    repo: String,
+    query: PhotoQuery,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<Vec<CatalogPhoto>, AppError> {
+    let photos = with_store(|store| query_photos_in(store, &repo, &query))?;
+    Ok(for_caller(photos, keypair_handle))
+}
+
+/// Catalogued photos of `album`, with tags and sync states; `None` if the
+/// album was never listed
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn catalog_album(
+    repo: String,
+    album: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<Option<Vec<CatalogPhoto>>, AppError> {
+    let photos = with_store(|store| album_photos_in(store, &repo, &album))?;
+    Ok(photos.map(|p| for_caller(p, keypair_handle)))
+}
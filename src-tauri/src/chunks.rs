@@ -0,0 +1,161 @@
+//! Chunked Storage for Large Files
+//!
+//! Videos routinely exceed what the contents API accepts, and not every
+//! repository has LFS enabled. Such files are split into `CHUNK_SIZE` parts
+//! stored under `.chunks/<sha256>/`, outside the album tree, and the file's
+//! own path holds a small JSON index listing the parts' blob SHAs. Parts
+//! are read from disk one at a time, so a video is never held in memory
+//! whole while uploading.
+//!
+//! `resolve_lfs_pointer` reassembles indexes the same way it fetches LFS
+//! objects, so downloads and every other reader see the original bytes.
+//! Parts are keyed by the file's hash, so uploading the same video again
+//! rewrites the same paths. Deleting the index leaves its parts in place.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+use crate::git_data::get_blob;
+use crate::github::{put_file_contents, AppError, UploadResult};
+use crate::offline_queue::remote_sha;
+
+/// Repo folder holding the parts of chunked files
+pub const CHUNK_DIR: &str = ".chunks";
+/// Part size; base64 keeps each request well under the contents API limit
+pub const CHUNK_SIZE: usize = 32 * 1024 * 1024;
+const INDEX_FORMAT: &str = "vortex-chunks";
+const INDEX_VERSION: u8 = 1;
+/// Indexes are small; anything larger is never parsed as one
+const MAX_INDEX_SIZE: usize = 256 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkPart {
+    /// Git blob SHA of the part
+    pub sha: String,
+    pub size: u64,
+}
+
+/// Contents of the file stored in place of a chunked one
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkIndex {
+    pub format: String,
+    pub version: u8,
+    pub size: u64,
+    /// SHA-256 hex of the whole file
+    pub sha256: String,
+    pub parts: Vec<ChunkPart>,
+}
+
+impl ChunkIndex {
+    pub fn new(sha256: String, parts: Vec<ChunkPart>) -> Self {
+        Self {
+            format: INDEX_FORMAT.to_string(),
+            version: INDEX_VERSION,
+            size: parts.iter().map(|p| p.size).sum(),
+            sha256,
+            parts,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec_pretty(self).unwrap_or_default()
+    }
+
+    /// Parse an index; returns `None` for regular content
+    pub fn parse(content: &[u8]) -> Option<Self> {
+        if content.len() > MAX_INDEX_SIZE || !content.starts_with(b"{") {
+            return None;
+        }
+        let index: Self = serde_json::from_slice(content).ok()?;
+        let consistent = index.format == INDEX_FORMAT
+            && index.version == INDEX_VERSION
+            && index.size == index.parts.iter().map(|p| p.size).sum::<u64>();
+        consistent.then_some(index)
+    }
+}
+
+/// Where part `index` of the file hashing to `sha256` is stored
+pub fn chunk_path(sha256: &str, index: usize) -> String {
+    format!("{}/{}/{:05}", CHUNK_DIR, sha256, index)
+}
+
+/// Fill `buf` from `file`, short only at the end of the file
+async fn read_chunk(file: &mut tokio::fs::File, buf: &mut [u8]) -> Result<usize, AppError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]).await? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+async fn file_sha256(path: &Path) -> Result<String, AppError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        match file.read(&mut buf).await? {
+            0 => break,
+            n => hasher.update(&buf[..n]),
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Upload the local file at `local_path` in parts and commit its index at
+/// `upload_path`. `on_progress` gets the bytes sent so far after each part.
+pub(crate) async fn put_chunked_file(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    upload_path: &str,
+    local_path: &Path,
+    message: &str,
+    on_progress: impl Fn(u64),
+) -> Result<UploadResult, AppError> {
+    let sha256 = file_sha256(local_path).await?;
+    let mut file = tokio::fs::File::open(local_path).await?;
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut parts = Vec::new();
+    let mut sent = 0u64;
+
+    loop {
+        let len = read_chunk(&mut file, &mut buf).await?;
+        if len == 0 {
+            break;
+        }
+        let path = chunk_path(&sha256, parts.len());
+        // A part left by an earlier, interrupted upload is overwritten
+        let existing = remote_sha(client, repo, token, &path).await?;
+        let part_message = format!("{} (part {})", message, parts.len() + 1);
+        let result = put_file_contents(client, repo, token, &path, &buf[..len], &part_message, existing.as_deref()).await?;
+        parts.push(ChunkPart { sha: result.sha, size: len as u64 });
+        sent += len as u64;
+        on_progress(sent);
+    }
+
+    let index = ChunkIndex::new(sha256, parts);
+    let sha = remote_sha(client, repo, token, upload_path).await?;
+    put_file_contents(client, repo, token, upload_path, &index.to_bytes(), message, sha.as_deref()).await
+}
+
+/// Reassemble the file behind `index`, checking its size and hash
+pub(crate) async fn fetch_chunked(client: &Client, repo: &str, token: &str, index: &ChunkIndex) -> Result<Vec<u8>, AppError> {
+    let mut content = Vec::with_capacity(index.size as usize);
+    for part in &index.parts {
+        let data = get_blob(client, repo, token, &part.sha).await?;
+        if data.len() as u64 != part.size {
+            return Err(AppError::Validation("Chunk has the wrong size".into()));
+        }
+        content.extend(data);
+    }
+    if format!("{:x}", Sha256::digest(&content)) != index.sha256 {
+        return Err(AppError::Validation("Chunked file failed its integrity check".into()));
+    }
+    Ok(content)
+}
@@ -0,0 +1,225 @@
+//! Photo Classification
+//!
+//! Labels photos found by `scan_folder` so uploads can be grouped and whole
+//! categories left out:
+//!
+//! - **screenshot**: named like one (`Screenshot_…`, `Screen Shot …`,
+//!   `Captura de tela …` and the other common localized names)
+//! - **burst**: named like a burst frame (`…_BURST…`), or one of at least
+//!   three photos from the same camera taken a second or less apart.
+//!   Frames of one burst share a `burst_id`.
+//! - **selfie**: taken with a front camera, per the EXIF lens model
+//! - **panorama**: named `PANO_…`, or at least 2.5 times as long as tall.
+//!   Screenshots are not panoramas, however long.
+//!
+//! Only the first `HEADER_BYTES` of each photo are read, which holds the
+//! EXIF and dimensions of the usual formats.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::image_metadata::describe_image;
+
+/// Bytes read from each photo
+pub const HEADER_BYTES: u64 = 256 * 1024;
+/// Frames of a burst are at most this far apart
+const BURST_GAP_SECS: i64 = 1;
+const BURST_MIN_FRAMES: usize = 3;
+/// Long side over short side from which a photo is a panorama
+const PANORAMA_RATIO: f64 = 2.5;
+
+/// Lowercase name fragments of screenshots
+const SCREENSHOT_NAMES: &[&str] = &[
+    "screenshot",
+    "screen shot",
+    "captura de tela",
+    "captura de pantalla",
+    "capture d’écran",
+    "capture d'écran",
+    "bildschirmfoto",
+    "schermata",
+    "スクリーンショット",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhotoCategory {
+    Screenshot,
+    Burst,
+    Selfie,
+    Panorama,
+}
+
+/// What classification needs to know about one photo
+#[derive(Clone, Debug, Default)]
+pub struct PhotoFacts {
+    pub name: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub lens_model: Option<String>,
+    /// Make and model
+    pub camera: Option<String>,
+    pub taken_at: Option<i64>,
+}
+
+impl PhotoFacts {
+    pub fn from_content(name: &str, content: &[u8]) -> Self {
+        let metadata = describe_image(name, content, None);
+        let exif = metadata.exif.unwrap_or_default();
+        let camera = match (exif.camera_make, exif.camera_model) {
+            (None, None) => None,
+            (make, model) => Some(format!("{} {}", make.unwrap_or_default(), model.unwrap_or_default())),
+        };
+        Self {
+            name: name.to_string(),
+            width: metadata.width,
+            height: metadata.height,
+            lens_model: exif.lens_model,
+            camera,
+            taken_at: metadata.taken_at_unix,
+        }
+    }
+
+    /// Facts from the start of the file at `path`; an unreadable file is
+    /// judged by its name alone
+    pub fn read(path: &Path) -> Self {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let mut header = Vec::new();
+        match std::fs::File::open(path).and_then(|f| f.take(HEADER_BYTES).read_to_end(&mut header)) {
+            Ok(_) => Self::from_content(&name, &header),
+            Err(_) => Self { name, ..Self::default() },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClassifiedFile {
+    pub name: String,
+    pub categories: Vec<PhotoCategory>,
+    /// Shared by the frames of one burst
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst_id: Option<String>,
+}
+
+fn is_screenshot(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SCREENSHOT_NAMES.iter().any(|pattern| name.contains(pattern))
+}
+
+/// Burst ID from a name like `00001IMG_00001_BURST20201231120000123.jpg`
+/// (the shared timestamp) or `20201231_120000_BURST001.jpg` (the shared
+/// prefix)
+fn burst_name_id(name: &str) -> Option<String> {
+    let stem = Path::new(name).file_stem()?.to_string_lossy().to_string();
+    let position = stem.to_ascii_uppercase().find("BURST")?;
+    let digits: String = stem[position + 5..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() >= 8 {
+        return Some(format!("BURST{}", digits));
+    }
+    let prefix = stem[..position].trim_end_matches(['_', '-', ' ']);
+    Some(if prefix.is_empty() { stem.clone() } else { prefix.to_string() })
+}
+
+fn is_selfie(facts: &PhotoFacts) -> bool {
+    facts.lens_model.as_deref().is_some_and(|lens| lens.to_lowercase().contains("front"))
+}
+
+fn is_panorama(facts: &PhotoFacts) -> bool {
+    if facts.name.to_uppercase().starts_with("PANO") {
+        return true;
+    }
+    match (facts.width, facts.height) {
+        (Some(w), Some(h)) if w > 0 && h > 0 => w.max(h) as f64 / w.min(h) as f64 >= PANORAMA_RATIO,
+        _ => false,
+    }
+}
+
+/// Bursts found by capture time: runs of `BURST_MIN_FRAMES` or more photos
+/// from one camera, each within `BURST_GAP_SECS` of the previous. Maps the
+/// index of each frame to the name of the run's first photo.
+fn timed_bursts(photos: &[PhotoFacts]) -> HashMap<usize, String> {
+    let mut timed: Vec<(&str, i64, usize)> = photos
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| Some((p.camera.as_deref().unwrap_or(""), p.taken_at?, i)))
+        .collect();
+    timed.sort_by(|a, b| (a.0, a.1, &photos[a.2].name).cmp(&(b.0, b.1, &photos[b.2].name)));
+
+    let mut bursts = HashMap::new();
+    let mut start = 0;
+    for end in 1..=timed.len() {
+        let continues = end < timed.len()
+            && timed[end].0 == timed[end - 1].0
+            && timed[end].1 - timed[end - 1].1 <= BURST_GAP_SECS;
+        if continues {
+            continue;
+        }
+        if end - start >= BURST_MIN_FRAMES {
+            let first = &photos[timed[start].2].name;
+            for &(_, _, index) in &timed[start..end] {
+                bursts.insert(index, first.clone());
+            }
+        }
+        start = end;
+    }
+    bursts
+}
+
+/// Classify the photos of one folder, in order. Bursts are only found
+/// among the photos given together.
+pub fn classify(photos: &[PhotoFacts]) -> Vec<ClassifiedFile> {
+    let timed = timed_bursts(photos);
+    photos
+        .iter()
+        .enumerate()
+        .map(|(index, facts)| {
+            let mut categories = Vec::new();
+            let screenshot = is_screenshot(&facts.name);
+            if screenshot {
+                categories.push(PhotoCategory::Screenshot);
+            }
+            let burst_id = burst_name_id(&facts.name).or_else(|| timed.get(&index).cloned());
+            if burst_id.is_some() {
+                categories.push(PhotoCategory::Burst);
+            }
+            if is_selfie(facts) {
+                categories.push(PhotoCategory::Selfie);
+            }
+            if !screenshot && is_panorama(facts) {
+                categories.push(PhotoCategory::Panorama);
+            }
+            ClassifiedFile { name: facts.name.clone(), categories, burst_id }
+        })
+        .collect()
+}
+
+/// Photos per category
+pub fn category_counts(files: &[ClassifiedFile]) -> BTreeMap<PhotoCategory, usize> {
+    let mut counts = BTreeMap::new();
+    for category in files.iter().flat_map(|f| &f.categories) {
+        *counts.entry(*category).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Of `paths`, the photos in any of the `excluded` categories. Paths are
+/// classified with the others in their folder.
+pub fn excluded_paths(paths: &[PathBuf], excluded: &[PhotoCategory]) -> HashSet<PathBuf> {
+    let mut folders: BTreeMap<&Path, Vec<&PathBuf>> = BTreeMap::new();
+    for path in paths {
+        folders.entry(path.parent().unwrap_or(Path::new(""))).or_default().push(path);
+    }
+
+    let mut matched = HashSet::new();
+    for files in folders.values() {
+        let facts: Vec<_> = files.iter().map(|p| PhotoFacts::read(p)).collect();
+        for (path, file) in files.iter().zip(classify(&facts)) {
+            if file.categories.iter().any(|c| excluded.contains(c)) {
+                matched.insert((*path).clone());
+            }
+        }
+    }
+    matched
+}
@@ -0,0 +1,271 @@
+//! Headless command line interface
+//!
+//! `vortex-cli` runs the same upload, sync and verify code as the app, minus
+//! the Tauri UI, so server users can script their backups:
+//!
+//! ```text
+//! vortex-cli upload --repo alice/photos --album Trips ~/Pictures/Trips
+//! vortex-cli sync --repo alice/photos --album Trips ~/Pictures/Trips
+//! vortex-cli verify --repo alice/photos --album Trips
+//! ```
+//!
+//! `--repo` falls back to `VORTEX_REPO`, and the token to `VORTEX_TOKEN`
+//! then `GITHUB_TOKEN`. Commands exit with 1 when anything failed to upload,
+//! conflicted or was skipped in a sync, or did not verify, and print their
+//! full report with `--json`.
+//!
+//! Built without the `gui` feature it links no webview, so it runs on
+//! servers without GTK or WebKit installed.
+
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use zeroize::Zeroizing;
+
+use crate::album::ALBUM_ROOT;
+use crate::crypto::KeypairHandle;
+use crate::github::{upload_album_folder, AppError, HttpClient, UploadBatchResult};
+use crate::keystore::load_keypair_from_keystore;
+use crate::security_verify::{verify_integrity, IntegrityReport, IntegrityStatus};
+use crate::sync::{run_sync, SyncReport};
+
+#[derive(Parser, Debug)]
+#[command(name = "vortex-cli", version, about = "Back up and check Vortex photo albums without the app")]
+pub(crate) struct Cli {
+    /// Print the full report as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Where a command works
+#[derive(Args, Debug)]
+pub(crate) struct Target {
+    /// Repository as owner/name
+    #[arg(long, env = "VORTEX_REPO")]
+    pub repo: String,
+    /// GitHub token, instead of VORTEX_TOKEN or GITHUB_TOKEN
+    #[arg(long, env = "VORTEX_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Upload the photos and videos in a folder as an album
+    Upload {
+        #[command(flatten)]
+        target: Target,
+        /// Album to upload into, e.g. Trips or photos/Trips
+        #[arg(long)]
+        album: String,
+        /// Keep subfolders as sub-albums
+        #[arg(long)]
+        recursive: bool,
+        /// Sign each photo with the keypair in the keystore
+        #[arg(long)]
+        sign: bool,
+        path: PathBuf,
+    },
+    /// Reconcile a folder with an album in both directions
+    Sync {
+        #[command(flatten)]
+        target: Target,
+        #[arg(long)]
+        album: String,
+        /// Include subfolders
+        #[arg(long)]
+        recursive: bool,
+        /// Glob of local files to leave alone; repeatable
+        #[arg(long = "ignore", value_name = "PATTERN")]
+        ignore_patterns: Vec<String>,
+        path: PathBuf,
+    },
+    /// Check photos and manifests against their signatures
+    Verify {
+        #[command(flatten)]
+        target: Target,
+        /// Album to check; the whole library by default
+        #[arg(long)]
+        album: Option<String>,
+    },
+}
+
+/// Repo path of `album`, which may be given with or without `photos/`
+pub(crate) fn album_path(album: &str) -> String {
+    let album = album.trim_matches('/');
+    if album == ALBUM_ROOT || album.starts_with(&format!("{}/", ALBUM_ROOT)) {
+        album.to_string()
+    } else {
+        format!("{}/{}", ALBUM_ROOT, album)
+    }
+}
+
+/// `flag` (or `VORTEX_TOKEN`, which clap reads into it), else `GITHUB_TOKEN`
+fn resolve_token(flag: Option<String>) -> Result<Zeroizing<String>, AppError> {
+    flag.or_else(|| std::env::var("GITHUB_TOKEN").ok())
+        .map(Zeroizing::new)
+        .filter(|t| !t.trim().is_empty())
+        .ok_or_else(|| AppError::Validation("No GitHub token: pass --token or set VORTEX_TOKEN".into()))
+}
+
+/// Handle of the keypair in the keystore, if there is one
+fn keystore_keypair() -> Result<Option<KeypairHandle>, AppError> {
+    load_keypair_from_keystore()
+        .map(|info| info.map(|i| i.handle))
+        .map_err(|e| AppError::Validation(e.to_string()))
+}
+
+/// Whether a report should fail the command
+pub(crate) trait Outcome: Serialize {
+    fn failed(&self) -> bool;
+    fn summary(&self) -> String;
+}
+
+impl Outcome for UploadBatchResult {
+    fn failed(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    fn summary(&self) -> String {
+        let mut lines: Vec<String> = self.failed.iter().map(|f| format!("failed  {}: {}", f.path, f.error)).collect();
+        lines.push(format!("{} uploaded, {} failed", self.succeeded.len(), self.failed.len()));
+        lines.join("\n")
+    }
+}
+
+impl Outcome for SyncReport {
+    /// Conflicts and files that could not be synced need a look
+    fn failed(&self) -> bool {
+        !self.conflicts.is_empty() || !self.skipped.is_empty()
+    }
+
+    fn summary(&self) -> String {
+        let mut lines: Vec<String> = self.skipped.iter().map(|p| format!("skipped {}", p)).collect();
+        lines.extend(self.conflicts.iter().map(|c| format!("conflict {}", c.conflict.path)));
+        lines.push(format!(
+            "{} uploaded, {} downloaded, {} deleted here, {} deleted remotely",
+            self.uploaded.len(),
+            self.downloaded.len(),
+            self.deleted_local.len(),
+            self.deleted_remote.len()
+        ));
+        if let Some(sha) = &self.commit_sha {
+            lines.push(format!("commit {}", sha));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Outcome for IntegrityReport {
+    fn failed(&self) -> bool {
+        self.tampered > 0 || self.untrusted > 0
+    }
+
+    fn summary(&self) -> String {
+        let mut lines: Vec<String> = self
+            .albums
+            .iter()
+            .flat_map(|a| a.manifest.iter().chain(&a.photos))
+            .filter(|c| matches!(c.status, IntegrityStatus::Tampered | IntegrityStatus::UntrustedSigner))
+            .map(|c| match &c.detail {
+                Some(detail) => format!("{:?}  {}: {}", c.status, c.path, detail),
+                None => format!("{:?}  {}", c.status, c.path),
+            })
+            .collect();
+        lines.push(format!(
+            "{} verified, {} untrusted, {} unsigned, {} tampered",
+            self.verified, self.untrusted, self.unsigned, self.tampered
+        ));
+        lines.join("\n")
+    }
+}
+
+fn report(outcome: &impl Outcome, json: bool) -> ExitCode {
+    if json {
+        match serde_json::to_string_pretty(outcome) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("error: {}", e),
+        }
+    } else {
+        println!("{}", outcome.summary());
+    }
+    if outcome.failed() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+async fn execute(command: Command, json: bool) -> Result<ExitCode, AppError> {
+    let client = HttpClient::new().0;
+    match command {
+        Command::Upload { target, album, recursive, sign, path } => {
+            let token = resolve_token(target.token)?;
+            let signer = if sign {
+                Some(keystore_keypair()?.ok_or_else(|| AppError::Validation("No keypair in the keystore to sign with".into()))?)
+            } else {
+                None
+            };
+            let album = album_path(&album);
+            let name = album.strip_prefix(&format!("{}/", ALBUM_ROOT)).unwrap_or_default();
+            let show_progress = !json && std::io::stderr().is_terminal();
+            let result = upload_album_folder(
+                &client,
+                &path.to_string_lossy(),
+                &target.repo,
+                &token,
+                name,
+                recursive,
+                signer,
+                None,
+                |p| {
+                    if show_progress && !p.current_file.is_empty() {
+                        eprintln!("[{}/{}] {}", p.completed_files + 1, p.total_files, p.current_file);
+                    }
+                },
+            )
+            .await?;
+            Ok(report(&result, json))
+        }
+        Command::Sync { target, album, recursive, ignore_patterns, path } => {
+            let token = resolve_token(target.token)?;
+            let result = run_sync(
+                &client,
+                &path.to_string_lossy(),
+                &target.repo,
+                &token,
+                &album_path(&album),
+                recursive,
+                &ignore_patterns,
+            )
+            .await?;
+            Ok(report(&result, json))
+        }
+        Command::Verify { target, album } => {
+            let token = resolve_token(target.token)?;
+            // Our own signatures only count as trusted once the keypair is loaded
+            let keypair = keystore_keypair().unwrap_or_else(|e| {
+                eprintln!("warning: could not load the keypair from the keystore: {}", e);
+                None
+            });
+            let album = album.as_deref().map(album_path);
+            let result = verify_integrity(&client, &token, &target.repo, album.as_deref(), keypair).await?;
+            Ok(report(&result, json))
+        }
+    }
+}
+
+/// Entry point of the `vortex-cli` binary
+pub fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match tauri::async_runtime::block_on(execute(cli.command, cli.json)) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,451 @@
+//! Album Comments
+//!
+//! Members of an encrypted album can discuss its photos. Comments are kept
+//! in the album folder, under `.vortex-comments/`:
+//!
+//! - `threads.bin` maps each commented photo's blob name to a random thread
+//!   id, sealed with the album key so it reveals nothing about the photos.
+//! - `<thread>/<seq>.cmt` is one comment: the text sealed with the album key,
+//!   the author's public bundle and the author's signature over the text.
+//!   The album, thread and sequence number are bound in as AAD and signed
+//!   along with the text, so a comment cannot be replayed elsewhere.
+//!
+//! Because the album key encrypts them, everyone the album is shared with
+//! can read every comment, and nobody else can. Revoking a recipient re-seals
+//! the comments under the new key with the photos (see `album_access`); the
+//! thread ids keep them attached to their photos across the rename, and the
+//! signatures stay valid.
+//!
+//! An append commits the comment (and, for a photo's first comment, the
+//! thread map) in a single commit. If another device commented at the same
+//! time, the branch update is rejected and the call can simply be retried.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::album::{album_key_for, AlbumManifest, ALBUM_MANIFEST_FILE};
+use crate::crypto::{decrypt_with_key, encrypt_with_key, with_keypair, KeypairHandle, PublicBundle};
+use crate::git_data::{
+    branch_head, commit_changes, create_blob, get_blob, get_tree_recursive, index_blobs, BranchHead, TreeChange,
+    TreeIndex,
+};
+use crate::github::{validate_repo, AppError, GithubError, HttpClient};
+use crate::metadata_vault::split_blob_path;
+use crate::mirror::replicate_tree_changes;
+use crate::sharing::album_id;
+use crate::util::now_secs;
+
+pub const COMMENTS_DIR: &str = ".vortex-comments";
+pub const COMMENT_THREADS_FILE: &str = "threads.bin";
+const COMMENT_THREADS_VERSION: u8 = 1;
+const MAX_COMMENT_LEN: usize = 4000;
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 200;
+
+/// Contents of `threads.bin`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommentThreads {
+    pub version: u8,
+    /// Blob name -> thread id
+    #[serde(default)]
+    pub threads: BTreeMap<String, String>,
+}
+
+impl Default for CommentThreads {
+    fn default() -> Self {
+        Self {
+            version: COMMENT_THREADS_VERSION,
+            threads: BTreeMap::new(),
+        }
+    }
+}
+
+/// A stored comment
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentEnvelope {
+    pub seq: u64,
+    pub author: PublicBundle,
+    pub posted_at: u64,
+    /// The text, encrypted with the album key
+    pub sealed: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlbumComment {
+    pub seq: u64,
+    /// Key id of the author
+    pub author: String,
+    pub posted_at: u64,
+    pub content: String,
+    /// Whether the author's signature checked out
+    pub verified: bool,
+    /// Whether the author owns the album or it is shared with them
+    pub author_is_member: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentPage {
+    pub path: String,
+    /// Oldest first
+    pub comments: Vec<AlbumComment>,
+    /// Pass as `before_seq` to load older comments; `None` at the start
+    pub next_before: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentResult {
+    pub seq: u64,
+    pub commit_sha: String,
+}
+
+fn new_thread_id() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+pub fn comment_threads_path(album_path: &str) -> String {
+    format!("{}/{}/{}", album_path.trim_matches('/'), COMMENTS_DIR, COMMENT_THREADS_FILE)
+}
+
+/// Zero-padded so the comments of a thread list in order
+pub fn comment_path(album_path: &str, thread_id: &str, seq: u64) -> String {
+    format!("{}/{}/{}/{:010}.cmt", album_path.trim_matches('/'), COMMENTS_DIR, thread_id, seq)
+}
+
+fn threads_aad(album_id: &str) -> String {
+    format!("{}#comments", album_id)
+}
+
+fn comment_aad(album_id: &str, thread_id: &str, seq: u64) -> Vec<u8> {
+    format!("{}#comment:{}:{}", album_id, thread_id, seq).into_bytes()
+}
+
+/// Bytes covered by the author's signature
+fn signed_bytes(album_id: &str, thread_id: &str, seq: u64, posted_at: u64, content: &[u8]) -> Vec<u8> {
+    let mut bytes = comment_aad(album_id, thread_id, seq);
+    bytes.extend_from_slice(&posted_at.to_be_bytes());
+    bytes.extend_from_slice(blake3::hash(content).as_bytes());
+    bytes
+}
+
+/// Key ids of the album's owner and recipients
+pub fn album_members(manifest: &AlbumManifest) -> Vec<String> {
+    manifest.owner_key_id.iter().cloned().chain(manifest.access.keys().cloned()).collect()
+}
+
+// ============================================================================
+// Sealing
+// ============================================================================
+
+impl CommentThreads {
+    pub fn seal(&self, album_key: &[u8; 32], album_id: &str) -> Result<Vec<u8>, AppError> {
+        let json = serde_json::to_vec(self).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        encrypt_with_key(&json, album_key, threads_aad(album_id).as_bytes())
+            .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))
+    }
+
+    pub fn open(album_key: &[u8; 32], album_id: &str, sealed: &[u8]) -> Result<Self, AppError> {
+        let json = decrypt_with_key(sealed, album_key, threads_aad(album_id).as_bytes())
+            .map_err(|e| AppError::Validation(format!("Comment threads could not be decrypted: {}", e)))?;
+        serde_json::from_slice(&json).map_err(|e| AppError::Validation(format!("Corrupt comment threads: {}", e)))
+    }
+}
+
+/// Encrypt `content` with the album key and sign it with `author_handle`
+pub fn seal_comment(
+    author_handle: KeypairHandle,
+    album_key: &[u8; 32],
+    album_id: &str,
+    thread_id: &str,
+    seq: u64,
+    content: &str,
+) -> Result<CommentEnvelope, AppError> {
+    let posted_at = now_secs();
+    let sealed = encrypt_with_key(content.as_bytes(), album_key, &comment_aad(album_id, thread_id, seq))
+        .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))?;
+    let (author, signature) = with_keypair(author_handle, |kp| {
+        let signature = kp.sign(&signed_bytes(album_id, thread_id, seq, posted_at, content.as_bytes()))?;
+        Ok((kp.public_bundle(), signature))
+    })
+    ?;
+
+    Ok(CommentEnvelope {
+        seq,
+        author,
+        posted_at,
+        sealed,
+        signature,
+    })
+}
+
+/// Decrypt a comment and check its author's signature. The author counts as
+/// a member when their key is one of `members`.
+pub fn open_comment(
+    album_key: &[u8; 32],
+    album_id: &str,
+    thread_id: &str,
+    envelope: &CommentEnvelope,
+    members: &[String],
+) -> Result<AlbumComment, AppError> {
+    let content = decrypt_with_key(&envelope.sealed, album_key, &comment_aad(album_id, thread_id, envelope.seq))
+        .map_err(|e| AppError::Validation(format!("Comment {} could not be decrypted: {}", envelope.seq, e)))?;
+
+    // The bundle's key id field is not trusted; the key material decides
+    let author = envelope.author.derived_key_id();
+    let signed = signed_bytes(album_id, thread_id, envelope.seq, envelope.posted_at, &content);
+    let verified = envelope.author.verify(&signed, &envelope.signature).is_ok();
+
+    Ok(AlbumComment {
+        seq: envelope.seq,
+        author_is_member: members.contains(&author),
+        author,
+        posted_at: envelope.posted_at,
+        content: String::from_utf8(content).map_err(|_| AppError::Validation("Comment is not valid UTF-8".into()))?,
+        verified,
+    })
+}
+
+/// Sequence numbers of a thread's comments, in order
+pub fn comment_seqs(index: &TreeIndex, album_path: &str, thread_id: &str) -> Vec<u64> {
+    let prefix = format!("{}/{}/{}/", album_path.trim_matches('/'), COMMENTS_DIR, thread_id);
+    let mut seqs: Vec<u64> = index
+        .keys()
+        .filter_map(|p| p.strip_prefix(&prefix)?.strip_suffix(".cmt")?.parse().ok())
+        .collect();
+    seqs.sort_unstable();
+    seqs
+}
+
+/// Up to `limit` of `seqs` before `before_seq` (or the newest ones), oldest
+/// first, plus the cursor for the next older page
+pub fn page_comments(seqs: &[u64], before_seq: Option<u64>, limit: usize) -> (&[u64], Option<u64>) {
+    let end = match before_seq {
+        Some(before) => seqs.partition_point(|&s| s < before),
+        None => seqs.len(),
+    };
+    let start = end.saturating_sub(limit);
+    let next = (start > 0).then(|| seqs[start]);
+    (&seqs[start..end], next)
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+async fn head_index(client: &Client, repo: &str, token: &str) -> Result<(BranchHead, TreeIndex), AppError> {
+    let head = branch_head(client, repo, token).await?;
+    let index = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+    Ok((head, index))
+}
+
+async fn load_manifest(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    index: &TreeIndex,
+    album_path: &str,
+) -> Result<AlbumManifest, AppError> {
+    let entry = index
+        .get(&format!("{}/{}", album_path, ALBUM_MANIFEST_FILE))
+        .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+    let manifest: AlbumManifest = serde_json::from_slice(&get_blob(client, repo, token, &entry.sha).await?)
+        .map_err(|e| AppError::Validation(format!("Invalid album manifest: {}", e)))?;
+    if !manifest.encrypted {
+        return Err(AppError::Validation("Comments need an encrypted album".into()));
+    }
+    Ok(manifest)
+}
+
+pub(crate) async fn load_comment_threads(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    index: &TreeIndex,
+    album_path: &str,
+    album_key: &[u8; 32],
+) -> Result<CommentThreads, AppError> {
+    match index.get(&comment_threads_path(album_path)) {
+        Some(entry) => {
+            let sealed = get_blob(client, repo, token, &entry.sha).await?;
+            CommentThreads::open(album_key, &album_id(repo, album_path), &sealed)
+        }
+        None => Ok(CommentThreads::default()),
+    }
+}
+
+async fn load_envelope(client: &Client, repo: &str, token: &str, index: &TreeIndex, path: &str) -> Result<CommentEnvelope, AppError> {
+    let entry = index.get(path).ok_or_else(|| {
+        AppError::from(GithubError::NotFound {
+            message: format!("Comment {} is missing", path),
+        })
+    })?;
+    serde_json::from_slice(&get_blob(client, repo, token, &entry.sha).await?)
+        .map_err(|e| AppError::Validation(format!("Corrupt comment {}: {}", path, e)))
+}
+
+/// Re-seal an album's comments under `new_key` after its key rotated.
+/// `renamed` maps the photos' old blob names to their new ones; comments on
+/// photos missing from it are deleted. Returns the changes to commit.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn rekey_comments(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    index: &TreeIndex,
+    album_path: &str,
+    old_key: &[u8; 32],
+    new_key: &[u8; 32],
+    renamed: &BTreeMap<String, String>,
+) -> Result<Vec<TreeChange>, AppError> {
+    if !index.contains_key(&comment_threads_path(album_path)) {
+        return Ok(Vec::new());
+    }
+    let id = album_id(repo, album_path);
+    let old_threads = load_comment_threads(client, repo, token, index, album_path, old_key).await?;
+
+    let mut threads = CommentThreads::default();
+    let mut changes = Vec::new();
+    for (blob, thread_id) in old_threads.threads {
+        let Some(new_blob) = renamed.get(&blob) else {
+            for seq in comment_seqs(index, album_path, &thread_id) {
+                changes.push(TreeChange::delete(&comment_path(album_path, &thread_id, seq)));
+            }
+            continue;
+        };
+        for seq in comment_seqs(index, album_path, &thread_id) {
+            let path = comment_path(album_path, &thread_id, seq);
+            let mut envelope = load_envelope(client, repo, token, index, &path).await?;
+            let aad = comment_aad(&id, &thread_id, seq);
+            let content = decrypt_with_key(&envelope.sealed, old_key, &aad)
+                .map_err(|e| AppError::Validation(format!("Comment {} could not be decrypted: {}", seq, e)))?;
+            envelope.sealed = encrypt_with_key(&content, new_key, &aad)
+                .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))?;
+            let json = serde_json::to_vec(&envelope)
+                .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+            changes.push(TreeChange::blob(&path, &create_blob(client, repo, token, &json).await?));
+        }
+        threads.threads.insert(new_blob.clone(), thread_id);
+    }
+
+    let sha = create_blob(client, repo, token, &threads.seal(new_key, &id)?).await?;
+    changes.push(TreeChange::blob(&comment_threads_path(album_path), &sha));
+    Ok(changes)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Comment on the photo at `path` in an encrypted album
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn add_comment(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    content: String,
+    keypair_handle: KeypairHandle,
+) -> Result<CommentResult, AppError> {
+    validate_repo(&repo)?;
+    let (album_path, blob_name) = split_blob_path(&path)?;
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(AppError::Validation("Comment is empty".into()));
+    }
+    if content.chars().count() > MAX_COMMENT_LEN {
+        return Err(AppError::Validation(format!("Comment exceeds {} characters", MAX_COMMENT_LEN)));
+    }
+
+    let (head, index) = head_index(&client.0, &repo, &token).await?;
+    let manifest = load_manifest(&client.0, &repo, &token, &index, album_path).await?;
+    if !manifest.entries.contains_key(blob_name) {
+        return Err(GithubError::NotFound {
+            message: format!("Photo not found: {}", path),
+        }
+        .into());
+    }
+    let key = album_key_for(keypair_handle, &repo, album_path, &manifest)?;
+    let id = album_id(&repo, album_path);
+
+    let mut changes = Vec::new();
+    let mut threads = load_comment_threads(&client.0, &repo, &token, &index, album_path, &key).await?;
+    let thread_id = match threads.threads.get(blob_name) {
+        Some(thread_id) => thread_id.clone(),
+        None => {
+            let thread_id = new_thread_id();
+            threads.threads.insert(blob_name.to_string(), thread_id.clone());
+            let sha = create_blob(&client.0, &repo, &token, &threads.seal(&key, &id)?).await?;
+            changes.push(TreeChange::blob(&comment_threads_path(album_path), &sha));
+            thread_id
+        }
+    };
+
+    let seq = comment_seqs(&index, album_path, &thread_id).last().map(|s| s + 1).unwrap_or(1);
+    let envelope = seal_comment(keypair_handle, &key, &id, &thread_id, seq, content)?;
+    let json = serde_json::to_vec(&envelope).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+    let sha = create_blob(&client.0, &repo, &token, &json).await?;
+    changes.push(TreeChange::blob(&comment_path(album_path, &thread_id, seq), &sha));
+
+    // The message names neither the photo nor the author
+    let message = format!("Add comment in {}", album_path);
+    let commit_sha = commit_changes(&client.0, &repo, &token, &head, &changes, &message).await?;
+    replicate_tree_changes(&client.0, &repo, &token, &changes);
+    record_activity(ActivityKind::Upload, "add_comment", Some(&repo), &comment_path(album_path, &thread_id, seq), None);
+
+    Ok(CommentResult { seq, commit_sha })
+}
+
+/// One page of decrypted comments on the photo at `path`, newest page first.
+/// Pass the returned `next_before` as `before_seq` to page back through
+/// older comments.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn list_comments(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    keypair_handle: KeypairHandle,
+    before_seq: Option<u64>,
+    limit: Option<usize>,
+) -> Result<CommentPage, AppError> {
+    validate_repo(&repo)?;
+    let (album_path, blob_name) = split_blob_path(&path)?;
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let (_, index) = head_index(&client.0, &repo, &token).await?;
+    let manifest = load_manifest(&client.0, &repo, &token, &index, album_path).await?;
+    let key = album_key_for(keypair_handle, &repo, album_path, &manifest)?;
+    let threads = load_comment_threads(&client.0, &repo, &token, &index, album_path, &key).await?;
+    let Some(thread_id) = threads.threads.get(blob_name) else {
+        return Ok(CommentPage {
+            path,
+            comments: Vec::new(),
+            next_before: None,
+        });
+    };
+
+    let id = album_id(&repo, album_path);
+    let members = album_members(&manifest);
+    let seqs = comment_seqs(&index, album_path, thread_id);
+    let (seqs, next_before) = page_comments(&seqs, before_seq, limit);
+    let mut comments = Vec::with_capacity(seqs.len());
+    for &seq in seqs {
+        let envelope = load_envelope(&client.0, &repo, &token, &index, &comment_path(album_path, thread_id, seq)).await?;
+        if envelope.seq != seq {
+            return Err(AppError::Validation(format!("Comment {} is out of place", seq)));
+        }
+        comments.push(open_comment(&key, &id, thread_id, &envelope, &members)?);
+    }
+
+    Ok(CommentPage {
+        path,
+        comments,
+        next_before,
+    })
+}
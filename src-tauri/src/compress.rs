@@ -4,8 +4,12 @@
 
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+use crate::entropy::{analyze, ContentAnalysis, ContentKind};
+
 #[derive(Error, Debug)]
 pub enum CompressError {
     #[error("compression failed: {0}")]
@@ -16,6 +20,110 @@ pub enum CompressError {
     InvalidData,
     #[error("unsupported algorithm: {0}")]
     UnsupportedAlgorithm(String),
+    #[error("decompression stopped: {limit} limit of {max} exceeded")]
+    LimitExceeded { limit: DecompressionLimit, max: u64 },
+}
+
+/// Which of the `DecompressionLimits` stopped a decompression
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecompressionLimit {
+    OutputSize,
+    Ratio,
+    Timeout,
+}
+
+impl std::fmt::Display for DecompressionLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::OutputSize => "output size",
+            Self::Ratio => "expansion ratio",
+            Self::Timeout => "time",
+        })
+    }
+}
+
+/// Bounds on a single in-memory decompression, so a small crafted input
+/// cannot claim or expand into gigabytes
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecompressionLimits {
+    pub max_output_bytes: u64,
+    /// Output may be at most this many times the input, once it is past
+    /// `RATIO_CHECK_FLOOR`
+    pub max_ratio: u64,
+    pub timeout_ms: u64,
+}
+
+impl Default for DecompressionLimits {
+    fn default() -> Self {
+        Self {
+            max_output_bytes: 512 * 1024 * 1024,
+            max_ratio: 1000,
+            timeout_ms: 30_000,
+        }
+    }
+}
+
+/// Outputs up to this size are never rejected for their ratio; small runs
+/// of zeros legitimately compress by far more than any sane limit
+pub const RATIO_CHECK_FLOOR: u64 = 8 * 1024 * 1024;
+const READ_CHUNK: usize = 64 * 1024;
+
+lazy_static::lazy_static! {
+    static ref LIMITS: RwLock<DecompressionLimits> = RwLock::new(DecompressionLimits::default());
+}
+
+/// Limits applied by `decompress` and the per-algorithm functions
+pub fn decompression_limits() -> DecompressionLimits {
+    LIMITS.read().map(|l| *l).unwrap_or_default()
+}
+
+impl DecompressionLimits {
+    /// Fail if `output` bytes from `input` bytes, `started` ago, break a limit
+    pub fn check(&self, output: u64, input: u64, started: Instant) -> Result<(), CompressError> {
+        if output > self.max_output_bytes {
+            return Err(CompressError::LimitExceeded {
+                limit: DecompressionLimit::OutputSize,
+                max: self.max_output_bytes,
+            });
+        }
+        if output > RATIO_CHECK_FLOOR && output / input.max(1) > self.max_ratio {
+            return Err(CompressError::LimitExceeded {
+                limit: DecompressionLimit::Ratio,
+                max: self.max_ratio,
+            });
+        }
+        if started.elapsed() > Duration::from_millis(self.timeout_ms) {
+            return Err(CompressError::LimitExceeded {
+                limit: DecompressionLimit::Timeout,
+                max: self.timeout_ms,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Drain a decoder in chunks, checking `limits` as the output grows
+fn read_limited(mut decoder: impl Read, input_len: usize, limits: &DecompressionLimits) -> Result<Vec<u8>, CompressError> {
+    let started = Instant::now();
+    let mut output = Vec::new();
+    let mut buf = vec![0u8; READ_CHUNK];
+    loop {
+        let n = match decoder.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(CompressError::Decompress(e.to_string())),
+        };
+        output.extend_from_slice(&buf[..n]);
+        limits.check(output.len() as u64, input_len as u64, started)?;
+    }
+    Ok(output)
+}
+
+/// Check a size claimed by a header before allocating for it
+fn check_claimed_size(claimed: usize, input_len: usize, limits: &DecompressionLimits) -> Result<(), CompressError> {
+    limits.check(claimed as u64, input_len as u64, Instant::now())
 }
 
 impl Serialize for CompressError {
@@ -36,6 +144,7 @@ pub enum Algorithm {
     Snap,
     Brotli,
     Gzip,
+    Xz,
     None,
 }
 
@@ -47,6 +156,7 @@ impl From<&str> for Algorithm {
             "snap" | "snappy" => Self::Snap,
             "brotli" | "br" => Self::Brotli,
             "gzip" | "gz" => Self::Gzip,
+            "xz" | "lzma" => Self::Xz,
             "none" => Self::None,
             _ => Self::Zstd,
         }
@@ -64,10 +174,37 @@ impl Algorithm {
             "snap" | "snappy" => Ok(Self::Snap),
             "brotli" | "br" => Ok(Self::Brotli),
             "gzip" | "gz" => Ok(Self::Gzip),
+            "xz" | "lzma" => Ok(Self::Xz),
             "none" => Ok(Self::None),
             _ => Err(CompressError::UnsupportedAlgorithm(s.to_string())),
         }
     }
+
+    /// Byte identifying the algorithm in container headers
+    pub fn tag(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zstd => 1,
+            Self::Lz4 => 2,
+            Self::Snap => 3,
+            Self::Brotli => 4,
+            Self::Gzip => 5,
+            Self::Xz => 6,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Result<Self, CompressError> {
+        Ok(match tag {
+            0 => Self::None,
+            1 => Self::Zstd,
+            2 => Self::Lz4,
+            3 => Self::Snap,
+            4 => Self::Brotli,
+            5 => Self::Gzip,
+            6 => Self::Xz,
+            _ => return Err(CompressError::UnsupportedAlgorithm(format!("tag {}", tag))),
+        })
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -97,6 +234,20 @@ pub struct CompressionResult {
     /// Indicates whether compression was actually applied.
     /// False when data was too small or compression would increase size.
     pub was_compressed: bool,
+    /// How automatic selection classified the input; only set by `compress_auto`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decision: Option<AutoDecision>,
+}
+
+/// Why `compress_auto` picked its algorithm
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutoDecision {
+    pub content: ContentKind,
+    /// Bits per byte of the sampled input
+    pub entropy: f64,
+    /// The input was stored as is because it would not shrink
+    pub store_only: bool,
+    pub reason: String,
 }
 
 pub fn zstd_compress(data: &[u8], level: i32) -> Result<(Vec<u8>, bool), CompressError> {
@@ -111,8 +262,13 @@ pub fn zstd_compress(data: &[u8], level: i32) -> Result<(Vec<u8>, bool), Compres
 }
 
 pub fn zstd_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
-    zstd::decode_all(data)
-        .map_err(|e| CompressError::Decompress(e.to_string()))
+    zstd_decompress_limited(data, &decompression_limits())
+}
+
+fn zstd_decompress_limited(data: &[u8], limits: &DecompressionLimits) -> Result<Vec<u8>, CompressError> {
+    let decoder = zstd::stream::read::Decoder::new(data)
+        .map_err(|e| CompressError::Decompress(e.to_string()))?;
+    read_limited(decoder, data.len(), limits)
 }
 
 pub fn lz4_compress(data: &[u8]) -> Vec<u8> {
@@ -120,6 +276,15 @@ pub fn lz4_compress(data: &[u8]) -> Vec<u8> {
 }
 
 pub fn lz4_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
+    lz4_decompress_limited(data, &decompression_limits())
+}
+
+fn lz4_decompress_limited(data: &[u8], limits: &DecompressionLimits) -> Result<Vec<u8>, CompressError> {
+    if data.len() < 4 {
+        return Err(CompressError::InvalidData);
+    }
+    let claimed = u32::from_le_bytes(data[..4].try_into().map_err(|_| CompressError::InvalidData)?) as usize;
+    check_claimed_size(claimed, data.len(), limits)?;
     lz4_flex::decompress_size_prepended(data)
         .map_err(|_| CompressError::InvalidData)
 }
@@ -136,12 +301,17 @@ pub fn snap_compress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
 }
 
 pub fn snap_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
+    snap_decompress_limited(data, &decompression_limits())
+}
+
+fn snap_decompress_limited(data: &[u8], limits: &DecompressionLimits) -> Result<Vec<u8>, CompressError> {
     if data.len() < 4 {
         return Err(CompressError::InvalidData);
     }
     let original_size = u32::from_le_bytes(
         data[..4].try_into().map_err(|_| CompressError::InvalidData)?
     ) as usize;
+    check_claimed_size(original_size, data.len(), limits)?;
     let mut decoder = snap::raw::Decoder::new();
     let mut output = vec![0u8; original_size];
     decoder.decompress(&data[4..], &mut output)
@@ -161,10 +331,11 @@ pub fn brotli_compress(data: &[u8], level: i32) -> Result<Vec<u8>, CompressError
 }
 
 pub fn brotli_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
-    let mut output = Vec::new();
-    brotli::BrotliDecompress(&mut &data[..], &mut output)
-        .map_err(|e| CompressError::Decompress(e.to_string()))?;
-    Ok(output)
+    brotli_decompress_limited(data, &decompression_limits())
+}
+
+fn brotli_decompress_limited(data: &[u8], limits: &DecompressionLimits) -> Result<Vec<u8>, CompressError> {
+    read_limited(brotli::Decompressor::new(data, READ_CHUNK), data.len(), limits)
 }
 
 pub fn gzip_compress(data: &[u8], level: i32) -> Result<Vec<u8>, CompressError> {
@@ -180,13 +351,30 @@ pub fn gzip_compress(data: &[u8], level: i32) -> Result<Vec<u8>, CompressError>
 }
 
 pub fn gzip_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
-    use flate2::read::GzDecoder;
-    
-    let mut decoder = GzDecoder::new(data);
-    let mut output = Vec::new();
-    decoder.read_to_end(&mut output)
-        .map_err(|e| CompressError::Decompress(e.to_string()))?;
-    Ok(output)
+    gzip_decompress_limited(data, &decompression_limits())
+}
+
+fn gzip_decompress_limited(data: &[u8], limits: &DecompressionLimits) -> Result<Vec<u8>, CompressError> {
+    read_limited(flate2::read::GzDecoder::new(data), data.len(), limits)
+}
+
+pub fn xz_compress(data: &[u8], level: i32) -> Result<Vec<u8>, CompressError> {
+    use xz2::write::XzEncoder;
+
+    let level = level.clamp(0, 9) as u32;
+    let mut encoder = XzEncoder::new(Vec::new(), level);
+    encoder.write_all(data)
+        .map_err(|e| CompressError::Compress(e.to_string()))?;
+    encoder.finish()
+        .map_err(|e| CompressError::Compress(e.to_string()))
+}
+
+pub fn xz_decompress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
+    xz_decompress_limited(data, &decompression_limits())
+}
+
+fn xz_decompress_limited(data: &[u8], limits: &DecompressionLimits) -> Result<Vec<u8>, CompressError> {
+    read_limited(xz2::read::XzDecoder::new(data), data.len(), limits)
 }
 
 pub fn compress(data: &[u8], settings: &CompressionSettings) -> Result<CompressionResult, CompressError> {
@@ -198,6 +386,7 @@ pub fn compress(data: &[u8], settings: &CompressionSettings) -> Result<Compressi
         Algorithm::Snap => (snap_compress(data)?, true),
         Algorithm::Brotli => (brotli_compress(data, settings.level)?, true),
         Algorithm::Gzip => (gzip_compress(data, settings.level)?, true),
+        Algorithm::Xz => (xz_compress(data, settings.level)?, true),
         Algorithm::None => (data.to_vec(), false),
     };
     
@@ -215,40 +404,262 @@ pub fn compress(data: &[u8], settings: &CompressionSettings) -> Result<Compressi
         compressed_size,
         ratio,
         was_compressed,
+        decision: None,
     })
 }
 
 pub fn decompress(data: &[u8], algorithm: Algorithm) -> Result<Vec<u8>, CompressError> {
+    decompress_with_limits(data, algorithm, &decompression_limits())
+}
+
+pub fn decompress_with_limits(
+    data: &[u8],
+    algorithm: Algorithm,
+    limits: &DecompressionLimits,
+) -> Result<Vec<u8>, CompressError> {
     match algorithm {
-        Algorithm::Zstd => zstd_decompress(data),
-        Algorithm::Lz4 => lz4_decompress(data),
-        Algorithm::Snap => snap_decompress(data),
-        Algorithm::Brotli => brotli_decompress(data),
-        Algorithm::Gzip => gzip_decompress(data),
+        Algorithm::Zstd => zstd_decompress_limited(data, limits),
+        Algorithm::Lz4 => lz4_decompress_limited(data, limits),
+        Algorithm::Snap => snap_decompress_limited(data, limits),
+        Algorithm::Brotli => brotli_decompress_limited(data, limits),
+        Algorithm::Gzip => gzip_decompress_limited(data, limits),
+        Algorithm::Xz => xz_decompress_limited(data, limits),
         Algorithm::None => Ok(data.to_vec()),
     }
 }
 
+/// JSON documents and manifests: UTF-8 text opening with `{` or `[`
+fn looks_like_json(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(4096)];
+    let text = match std::str::from_utf8(sample) {
+        Ok(text) => text,
+        // The sample may cut a multi-byte character in half
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&sample[..e.valid_up_to()]).unwrap_or(""),
+        Err(_) => return false,
+    };
+    matches!(text.trim_start().as_bytes().first(), Some(b'{') | Some(b'['))
+}
+
 pub fn select_algorithm(data: &[u8], prefer_speed: bool) -> Algorithm {
+    select_algorithm_for(data, prefer_speed, false)
+}
+
+/// Pick an algorithm for `data`. `archival` trades speed for the best ratio
+/// (XZ); otherwise JSON payloads get Brotli, whose built-in dictionary
+/// favours them, and everything else Zstd, or LZ4 when `prefer_speed`.
+/// Incompressible input (JPEG, video, encrypted data) is stored as is.
+pub fn select_algorithm_for(data: &[u8], prefer_speed: bool, archival: bool) -> Algorithm {
     if data.len() < 64 {
         return Algorithm::None;
     }
-    
-    if prefer_speed {
+    select_for_content(data, prefer_speed, archival, &analyze(data))
+}
+
+fn select_for_content(data: &[u8], prefer_speed: bool, archival: bool, analysis: &ContentAnalysis) -> Algorithm {
+    if !analysis.compressible {
+        Algorithm::None
+    } else if prefer_speed {
         Algorithm::Lz4
+    } else if archival {
+        Algorithm::Xz
+    } else if looks_like_json(data) {
+        Algorithm::Brotli
     } else {
         Algorithm::Zstd
     }
 }
 
+fn auto_level(algorithm: Algorithm, prefer_speed: bool) -> i32 {
+    match algorithm {
+        _ if prefer_speed => 1,
+        Algorithm::Brotli => 9,
+        Algorithm::Xz => 9,
+        _ => 3,
+    }
+}
+
 pub fn compress_auto(data: &[u8], prefer_speed: bool) -> Result<CompressionResult, CompressError> {
-    let algorithm = select_algorithm(data, prefer_speed);
+    compress_auto_for(data, prefer_speed, false)
+}
+
+pub fn compress_auto_for(data: &[u8], prefer_speed: bool, archival: bool) -> Result<CompressionResult, CompressError> {
+    let (settings, decision) = auto_settings(data, prefer_speed, archival);
+    let mut result = compress(data, &settings)?;
+    result.decision = Some(decision);
+    Ok(result)
+}
+
+/// Settings `compress_auto_for` would use for `data`, and why. Works on a
+/// leading sample too, for input that is streamed.
+pub fn auto_settings(data: &[u8], prefer_speed: bool, archival: bool) -> (CompressionSettings, AutoDecision) {
+    let analysis = analyze(data);
+    let algorithm = if data.len() < 64 {
+        Algorithm::None
+    } else {
+        select_for_content(data, prefer_speed, archival, &analysis)
+    };
     let settings = CompressionSettings {
         algorithm,
-        level: if prefer_speed { 1 } else { 3 },
+        level: auto_level(algorithm, prefer_speed),
         prefer_speed,
     };
-    compress(data, &settings)
+    let reason = if data.len() < 64 {
+        "Too small to benefit from compression".to_string()
+    } else if analysis.kind.is_precompressed() {
+        format!("{:?} data is already compressed; stored as is", analysis.kind)
+    } else if !analysis.compressible {
+        format!("High entropy ({:.2} bits/byte); stored as is", analysis.entropy)
+    } else {
+        format!("{:?} data at {:.2} bits/byte; using {:?}", analysis.kind, analysis.entropy, algorithm)
+    };
+
+    let decision = AutoDecision {
+        content: analysis.kind,
+        entropy: analysis.entropy,
+        store_only: algorithm == Algorithm::None,
+        reason,
+    };
+    (settings, decision)
+}
+
+const SEGMENT_MAGIC: &[u8; 5] = b"VXSEG";
+const SEGMENT_VERSION: u8 = 1;
+const SEGMENT_HEADER_LEN: usize = 5 + 2 + 4 + 4;
+const SEGMENT_ENTRY_LEN: usize = 4 + 4 + 1;
+/// Bytes per independently compressed segment
+pub const DEFAULT_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+const MIN_SEGMENT_SIZE: usize = 64 * 1024;
+const MAX_PARALLELISM: usize = 64;
+
+/// Worker threads to use for `threads`, where 0 means one per core
+pub fn effective_parallelism(threads: usize) -> usize {
+    match threads {
+        0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        n => n.min(MAX_PARALLELISM),
+    }
+}
+
+fn segment_pool(threads: usize) -> Result<rayon::ThreadPool, CompressError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(effective_parallelism(threads))
+        .build()
+        .map_err(|e| CompressError::Compress(e.to_string()))
+}
+
+/// Compress `data` as independent segments on `threads` workers.
+///
+/// Container:
+///
+/// ```text
+/// [magic "VXSEG"][version: 1][algorithm: 1][segment size: u32][count: u32]
+/// count x [original length: u32][stored length: u32][compressed: 1]
+/// [segment 0][segment 1]...[segment count-1]
+/// ```
+///
+/// All integers are little-endian. A segment that would not shrink is stored
+/// as is with its compressed flag clear.
+pub fn compress_segmented(
+    data: &[u8],
+    algorithm: Algorithm,
+    level: i32,
+    segment_size: usize,
+    threads: usize,
+) -> Result<Vec<u8>, CompressError> {
+    use rayon::prelude::*;
+
+    let segment_size = segment_size.clamp(MIN_SEGMENT_SIZE, u32::MAX as usize);
+    let settings = CompressionSettings {
+        algorithm,
+        level,
+        prefer_speed: false,
+    };
+    let segments: Vec<&[u8]> = data.chunks(segment_size).collect();
+    let compressed = segment_pool(threads)?.install(|| {
+        segments
+            .par_iter()
+            .map(|segment| {
+                let result = compress(segment, &settings)?;
+                Ok(if result.was_compressed && result.compressed_size < segment.len() {
+                    (result.data, true)
+                } else {
+                    (segment.to_vec(), false)
+                })
+            })
+            .collect::<Result<Vec<_>, CompressError>>()
+    })?;
+
+    let body_len: usize = compressed.iter().map(|(c, _)| c.len()).sum();
+    let mut output = Vec::with_capacity(SEGMENT_HEADER_LEN + SEGMENT_ENTRY_LEN * segments.len() + body_len);
+    output.extend_from_slice(SEGMENT_MAGIC);
+    output.push(SEGMENT_VERSION);
+    output.push(algorithm.tag());
+    output.extend_from_slice(&(segment_size as u32).to_le_bytes());
+    output.extend_from_slice(&(segments.len() as u32).to_le_bytes());
+    for (segment, (stored, was_compressed)) in segments.iter().zip(&compressed) {
+        output.extend_from_slice(&(segment.len() as u32).to_le_bytes());
+        output.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+        output.push(*was_compressed as u8);
+    }
+    for (stored, _) in &compressed {
+        output.extend_from_slice(stored);
+    }
+    Ok(output)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> usize {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().expect("4 bytes")) as usize
+}
+
+/// Decompress a `compress_segmented` container on `threads` workers
+pub fn decompress_segmented(data: &[u8], threads: usize) -> Result<Vec<u8>, CompressError> {
+    use rayon::prelude::*;
+
+    if data.len() < SEGMENT_HEADER_LEN || &data[..5] != SEGMENT_MAGIC || data[5] != SEGMENT_VERSION {
+        return Err(CompressError::InvalidData);
+    }
+    let algorithm = Algorithm::from_tag(data[6])?;
+    let segment_size = read_u32(data, 7);
+    let count = read_u32(data, 11);
+    let table_end = count
+        .checked_mul(SEGMENT_ENTRY_LEN)
+        .and_then(|n| n.checked_add(SEGMENT_HEADER_LEN))
+        .filter(|&end| end <= data.len())
+        .ok_or(CompressError::InvalidData)?;
+
+    let mut segments = Vec::with_capacity(count);
+    let mut offset = table_end;
+    for i in 0..count {
+        let entry = SEGMENT_HEADER_LEN + i * SEGMENT_ENTRY_LEN;
+        let (original, stored, was_compressed) = (read_u32(data, entry), read_u32(data, entry + 4), data[entry + 8] != 0);
+        if original > segment_size || offset + stored > data.len() {
+            return Err(CompressError::InvalidData);
+        }
+        segments.push((&data[offset..offset + stored], original, was_compressed));
+        offset += stored;
+    }
+    if offset != data.len() {
+        return Err(CompressError::InvalidData);
+    }
+    let total: usize = segments.iter().map(|&(_, original, _)| original).sum();
+    check_claimed_size(total, data.len(), &decompression_limits())?;
+
+    let parts = segment_pool(threads)?.install(|| {
+        segments
+            .par_iter()
+            .map(|&(stored, original, was_compressed)| {
+                let part = if was_compressed {
+                    decompress(stored, algorithm)?
+                } else {
+                    stored.to_vec()
+                };
+                if part.len() != original {
+                    return Err(CompressError::Decompress("segment length mismatch".into()));
+                }
+                Ok(part)
+            })
+            .collect::<Result<Vec<_>, CompressError>>()
+    })?;
+    Ok(parts.concat())
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -283,6 +694,9 @@ pub struct CompressedFileData {
     pub compressed_size: usize,
     pub ratio: f64,
     pub checksum: Vec<u8>, 
+    /// Data is a `compress_segmented` container
+    #[serde(default)]
+    pub segmented: bool,
 }
 
 fn is_compressed_format(filename: &str) -> bool {
@@ -291,7 +705,7 @@ fn is_compressed_format(filename: &str) -> bool {
         "jpg" | "jpeg" | "png" | "gif" | "webp" | "avif" | "heic" | "heif" |
         "mp4" | "mkv" | "avi" | "mov" | "webm" |
         "mp3" | "aac" | "ogg" | "flac" |
-        "zip" | "gz" | "bz2" | "xz" | "7z" | "rar" |
+        "zip" | "gz" | "bz2" | "xz" | "lzma" | "txz" | "7z" | "rar" |
         "zst" | "lz4" | "br"
     )
 }
@@ -301,6 +715,17 @@ pub fn compress_file_data(
     filename: &str,
     settings: &ItemCompressionSettings,
 ) -> Result<CompressedFileData, CompressError> {
+    compress_file_data_parallel(data, filename, settings, 1)
+}
+
+/// `compress_file_data`, splitting files of at least two segments across
+/// `threads` workers (0 = one per core)
+pub fn compress_file_data_parallel(
+    data: &[u8],
+    filename: &str,
+    settings: &ItemCompressionSettings,
+    threads: usize,
+) -> Result<CompressedFileData, CompressError> {
     
     let checksum = blake3::hash(data).as_bytes().to_vec();
 
@@ -313,6 +738,7 @@ pub fn compress_file_data(
             compressed_size: data.len(),
             ratio: 1.0,
             checksum,
+            segmented: false,
         });
     }
     
@@ -325,10 +751,11 @@ pub fn compress_file_data(
             compressed_size: data.len(),
             ratio: 1.0,
             checksum,
+            segmented: false,
         });
     }
     
-    if settings.skip_already_compressed && is_compressed_format(filename) {
+    if settings.skip_already_compressed && (is_compressed_format(filename) || !analyze(data).compressible) {
         return Ok(CompressedFileData {
             data: data.to_vec(),
             compressed: false,
@@ -337,6 +764,7 @@ pub fn compress_file_data(
             compressed_size: data.len(),
             ratio: 1.0,
             checksum,
+            segmented: false,
         });
     }
 
@@ -346,7 +774,23 @@ pub fn compress_file_data(
         prefer_speed: settings.prefer_speed,
     };
     
-    let result = compress(data, &comp_settings)?;
+    let segmented = effective_parallelism(threads) > 1
+        && data.len() >= 2 * DEFAULT_SEGMENT_SIZE
+        && settings.algorithm != Algorithm::None;
+    let result = if segmented {
+        let compressed = compress_segmented(data, settings.algorithm, settings.level, DEFAULT_SEGMENT_SIZE, threads)?;
+        CompressionResult {
+            algorithm: settings.algorithm,
+            original_size: data.len(),
+            compressed_size: compressed.len(),
+            ratio: compressed.len() as f64 / data.len() as f64,
+            was_compressed: true,
+            decision: None,
+            data: compressed,
+        }
+    } else {
+        compress(data, &comp_settings)?
+    };
 
     if result.compressed_size >= data.len() {
         return Ok(CompressedFileData {
@@ -357,6 +801,7 @@ pub fn compress_file_data(
             compressed_size: data.len(),
             ratio: 1.0,
             checksum,
+            segmented: false,
         });
     }
     
@@ -368,6 +813,7 @@ pub fn compress_file_data(
         compressed_size: result.compressed_size,
         ratio: result.ratio,
         checksum,
+        segmented,
     })
 }
 
@@ -378,7 +824,11 @@ pub fn decompress_file_data(
         return Ok(compressed.data.clone());
     }
     
-    let decompressed = decompress(&compressed.data, compressed.algorithm)?;
+    let decompressed = if compressed.segmented {
+        decompress_segmented(&compressed.data, 0)?
+    } else {
+        decompress(&compressed.data, compressed.algorithm)?
+    };
 
     let checksum = blake3::hash(&decompressed).as_bytes().to_vec();
     if checksum != compressed.checksum {
@@ -389,8 +839,11 @@ pub fn decompress_file_data(
 }
 
 use crate::github::AppError;
+use crate::jobs::{Job, JobCommand};
+use crate::AppHandle;
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn compress_data_strict(
     data: Vec<u8>,
     algorithm: String,
@@ -408,6 +861,7 @@ pub async fn compress_data_strict(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn compress_data(
     data: Vec<u8>,
     algorithm: String,
@@ -424,6 +878,7 @@ pub async fn compress_data(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn decompress_data(
     data: Vec<u8>,
     algorithm: String,
@@ -434,6 +889,7 @@ pub async fn decompress_data(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn estimate_compression(
     data: Vec<u8>,
     algorithm: String,
@@ -449,6 +905,7 @@ pub async fn estimate_compression(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all)]
 pub fn list_compression_algorithms() -> Vec<String> {
     vec![
         "zstd".to_string(),
@@ -456,30 +913,111 @@ pub fn list_compression_algorithms() -> Vec<String> {
         "snap".to_string(),
         "brotli".to_string(),
         "gzip".to_string(),
+        "xz".to_string(),
         "none".to_string(),
     ]
 }
 
+/// Compress with an automatically chosen algorithm; `archival` asks for the
+/// best ratio regardless of speed
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn compress_data_auto(
     data: Vec<u8>,
     prefer_speed: bool,
+    archival: Option<bool>,
 ) -> Result<CompressionResult, AppError> {
-    compress_auto(&data, prefer_speed)
+    compress_auto_for(&data, prefer_speed, archival.unwrap_or(false))
         .map_err(|e| AppError::Validation(e.to_string()))
 }
 
+/// Compress `data` as a job (see `jobs`); cancelling drops the result at once
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn compress_file(
+    app: AppHandle,
     data: Vec<u8>,
     filename: String,
     settings: ItemCompressionSettings,
+    job_id: Option<String>,
 ) -> Result<CompressedFileData, AppError> {
-    compress_file_data(&data, &filename, &settings)
-        .map_err(|e| AppError::Validation(e.to_string()))
+    let job = Job::announce(&app, job_id, JobCommand::CompressFile)?;
+    let threads = configured_parallelism();
+    job.run_blocking(move || {
+        compress_file_data_parallel(&data, &filename, &settings, threads).map_err(|e| AppError::Validation(e.to_string()))
+    })
+    .await
+}
+
+const PARALLELISM_SETTING: &str = "compression_parallelism";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompressionParallelism {
+    /// Configured worker threads; 0 means one per core
+    pub threads: usize,
+    /// Threads `compress_file` will actually use
+    pub effective: usize,
+    pub available_cores: usize,
+}
+
+/// The saved thread setting, or 0 (one per core) if none is saved or the
+/// store is unavailable
+fn configured_parallelism() -> usize {
+    crate::local_store::with_store(|store| store.get_json(crate::local_store::SETTINGS_NS, PARALLELISM_SETTING))
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+}
+
+fn parallelism_info(threads: usize) -> CompressionParallelism {
+    CompressionParallelism {
+        threads,
+        effective: effective_parallelism(threads),
+        available_cores: effective_parallelism(0),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn get_decompression_limits() -> DecompressionLimits {
+    decompression_limits()
+}
+
+/// Replace the decompression limits for this session, for example to open
+/// a trusted archive that expands further than the defaults allow. `None`
+/// restores the defaults.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn set_decompression_limits(limits: Option<DecompressionLimits>) -> Result<DecompressionLimits, AppError> {
+    let limits = limits.unwrap_or_default();
+    if limits.max_output_bytes == 0 || limits.max_ratio == 0 || limits.timeout_ms == 0 {
+        return Err(AppError::Validation("Decompression limits must be greater than zero".into()));
+    }
+    *LIMITS.write().unwrap() = limits;
+    Ok(limits)
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn get_compression_parallelism() -> CompressionParallelism {
+    parallelism_info(configured_parallelism())
+}
+
+/// Set the worker threads for `compress_file`; 0 uses one per core
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn set_compression_parallelism(threads: usize) -> Result<CompressionParallelism, AppError> {
+    if threads > MAX_PARALLELISM {
+        return Err(AppError::Validation(format!("At most {} threads", MAX_PARALLELISM)));
+    }
+    crate::local_store::with_store(|store| {
+        store.put_json(crate::local_store::SETTINGS_NS, PARALLELISM_SETTING, &threads, None)
+    })?;
+    Ok(parallelism_info(threads))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn decompress_file(
     compressed: CompressedFileData,
 ) -> Result<Vec<u8>, AppError> {
@@ -488,6 +1026,7 @@ pub async fn decompress_file(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all)]
 pub fn get_compression_recommendation(filename: String, file_size: usize) -> serde_json::Value {
     let ext = filename.rsplit('.').next().unwrap_or("").to_lowercase();
     
@@ -497,18 +1036,46 @@ pub fn get_compression_recommendation(filename: String, file_size: usize) -> ser
         ("none", 0, "File too small to benefit from compression")
     } else if file_size > 100 * 1024 * 1024 {
         ("lz4", 1, "Large file - using fast compression")
-    } else if matches!(ext.as_str(), "txt" | "json" | "xml" | "html" | "css" | "js" | "ts") {
+    } else if matches!(ext.as_str(), "json" | "manifest" | "webmanifest" | "geojson") {
+        ("brotli", 9, "JSON or manifest - Brotli's dictionary suits structured text")
+    } else if matches!(ext.as_str(), "txt" | "xml" | "html" | "css" | "js" | "ts") {
         ("zstd", 6, "Text file - high compression ratio recommended")
+    } else if matches!(ext.as_str(), "tar" | "log" | "sql" | "csv" | "dump" | "bak") {
+        ("xz", 9, "Archival data - maximum compression ratio")
     } else if matches!(ext.as_str(), "bmp" | "tiff" | "tif" | "raw") {
         ("zstd", 3, "Uncompressed image - good compression potential")
     } else {
         ("zstd", 3, "Default balanced compression")
     };
     
+    if algorithm != "none" {
+        let calibrated = crate::compress_bench::saved_report()
+            .and_then(|report| crate::compress_bench::recommend_from_benchmark(&report, file_size).cloned());
+        if let Some(entry) = calibrated {
+            return serde_json::json!({
+                "algorithm": entry.algorithm,
+                "level": entry.level,
+                "reason": format!(
+                    "Calibrated on this device: {:.0} MB/s at {:.0}% of original size",
+                    entry.compress_mbps,
+                    entry.ratio * 100.0
+                ),
+                "estimated_ratio": entry.ratio,
+                "calibrated": true
+            });
+        }
+    }
+
     serde_json::json!({
         "algorithm": algorithm,
         "level": level,
         "reason": reason,
-        "estimated_ratio": if algorithm == "none" { 1.0 } else { 0.6 }
+        "estimated_ratio": match algorithm {
+            "none" => 1.0,
+            "brotli" => 0.35,
+            "xz" => 0.3,
+            _ => 0.6,
+        },
+        "calibrated": false
     })
 }
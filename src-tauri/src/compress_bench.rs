@@ -0,0 +1,222 @@
+//! Compression Benchmark
+//!
+//! `benchmark_compression` runs every algorithm at a few levels against a
+//! sample file on this machine and records compression and decompression
+//! throughput, ratio and memory. The report is kept in the local store, and
+//! `get_compression_recommendation` uses it to pick the best ratio this
+//! device can deliver within `TARGET_SECONDS`, instead of static guesses.
+//!
+//! Memory is an estimate: the algorithm's documented working set at that
+//! level plus the input and output buffers. Measuring allocator peaks would
+//! need a global allocator hook for one diagnostic command.
+
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+use crate::compress::{compress, decompress, Algorithm, CompressionSettings};
+use crate::github::AppError;
+use crate::local_store::{with_store, BENCHMARKS_NS};
+use crate::util::now_secs;
+
+const REPORT_KEY: &str = "compression";
+/// Only the first part of a large sample is used
+pub const MAX_SAMPLE_BYTES: usize = 16 * 1024 * 1024;
+const MIN_SAMPLE_BYTES: usize = 4 * 1024;
+/// Each candidate repeats until it has run this long, for stable timings
+const MIN_RUN_TIME: Duration = Duration::from_millis(200);
+/// A recommendation must compress the whole file within this time
+pub const TARGET_SECONDS: f64 = 5.0;
+
+/// Algorithms and levels measured by `benchmark_compression`
+pub const CANDIDATES: &[(Algorithm, i32)] = &[
+    (Algorithm::Lz4, 1),
+    (Algorithm::Snap, 1),
+    (Algorithm::Zstd, 1),
+    (Algorithm::Zstd, 3),
+    (Algorithm::Zstd, 9),
+    (Algorithm::Zstd, 19),
+    (Algorithm::Brotli, 5),
+    (Algorithm::Brotli, 9),
+    (Algorithm::Gzip, 6),
+    (Algorithm::Xz, 6),
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BenchmarkEntry {
+    pub algorithm: Algorithm,
+    pub level: i32,
+    /// Megabytes of input per second
+    pub compress_mbps: f64,
+    /// Megabytes of output per second
+    pub decompress_mbps: f64,
+    /// Compressed size over original size
+    pub ratio: f64,
+    pub estimated_memory_bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub sample_bytes: usize,
+    pub cores: usize,
+    pub measured_at: u64,
+    pub entries: Vec<BenchmarkEntry>,
+}
+
+/// Working set of `algorithm` at `level` plus buffers for `sample_len` bytes
+pub fn estimated_memory(algorithm: Algorithm, level: i32, sample_len: usize) -> u64 {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    let working_set = match algorithm {
+        Algorithm::None => 0,
+        Algorithm::Lz4 => 80 * KIB,
+        Algorithm::Snap => 64 * KIB,
+        Algorithm::Gzip => 256 * KIB,
+        Algorithm::Zstd => {
+            let window_log = match level {
+                ..=2 => 19,
+                3..=5 => 21,
+                6..=12 => 22,
+                _ => 23,
+            };
+            (1u64 << window_log) * 2
+        }
+        // 4 MiB window; the large hash tables start at quality 10
+        Algorithm::Brotli if level >= 10 => 40 * MIB,
+        Algorithm::Brotli => 12 * MIB,
+        // xz presets: dictionary size times about 11.5 for the encoder
+        Algorithm::Xz => {
+            let dict = match level {
+                ..=0 => 256 * KIB,
+                1 => MIB,
+                2 => 2 * MIB,
+                3 | 4 => 4 * MIB,
+                5 | 6 => 8 * MIB,
+                7 => 16 * MIB,
+                8 => 32 * MIB,
+                _ => 64 * MIB,
+            };
+            dict * 23 / 2
+        }
+    };
+    working_set + 2 * sample_len as u64
+}
+
+fn mbps(bytes: usize, runs: u32, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64().max(1e-9);
+    (bytes as f64 * runs as f64) / (1024.0 * 1024.0) / secs
+}
+
+/// Time one candidate against `sample`
+pub fn benchmark_one(sample: &[u8], algorithm: Algorithm, level: i32) -> Result<BenchmarkEntry, AppError> {
+    let settings = CompressionSettings {
+        algorithm,
+        level,
+        prefer_speed: false,
+    };
+    let to_error = |e: crate::compress::CompressError| AppError::Validation(e.to_string());
+
+    let started = Instant::now();
+    let mut runs = 0u32;
+    let mut result = compress(sample, &settings).map_err(to_error)?;
+    runs += 1;
+    while started.elapsed() < MIN_RUN_TIME {
+        result = compress(sample, &settings).map_err(to_error)?;
+        runs += 1;
+    }
+    let compress_mbps = mbps(sample.len(), runs, started.elapsed());
+
+    let started = Instant::now();
+    let mut runs = 0u32;
+    loop {
+        let restored = if result.was_compressed {
+            decompress(&result.data, algorithm).map_err(to_error)?
+        } else {
+            result.data.clone()
+        };
+        runs += 1;
+        if restored.len() != sample.len() {
+            return Err(AppError::Validation(format!("{:?} did not roundtrip the sample", algorithm)));
+        }
+        if started.elapsed() >= MIN_RUN_TIME {
+            break;
+        }
+    }
+
+    Ok(BenchmarkEntry {
+        algorithm,
+        level,
+        compress_mbps,
+        decompress_mbps: mbps(sample.len(), runs, started.elapsed()),
+        ratio: result.ratio,
+        estimated_memory_bytes: estimated_memory(algorithm, level, sample.len()),
+    })
+}
+
+pub fn run_benchmark(sample: &[u8]) -> Result<BenchmarkReport, AppError> {
+    if sample.len() < MIN_SAMPLE_BYTES {
+        return Err(AppError::Validation(format!(
+            "Sample must be at least {} bytes",
+            MIN_SAMPLE_BYTES
+        )));
+    }
+    let entries = CANDIDATES
+        .iter()
+        .map(|&(algorithm, level)| benchmark_one(sample, algorithm, level))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(BenchmarkReport {
+        sample_bytes: sample.len(),
+        cores: crate::compress::effective_parallelism(0),
+        measured_at: now_secs(),
+        entries,
+    })
+}
+
+/// Best ratio among entries that compress `file_size` bytes within
+/// `TARGET_SECONDS`, the faster one on ties. `None` if nothing is fast enough.
+pub fn recommend_from_benchmark(report: &BenchmarkReport, file_size: usize) -> Option<&BenchmarkEntry> {
+    let size_mb = file_size as f64 / (1024.0 * 1024.0);
+    report
+        .entries
+        .iter()
+        .filter(|e| e.ratio < 1.0 && size_mb / e.compress_mbps.max(1e-9) <= TARGET_SECONDS)
+        .min_by(|a, b| {
+            a.ratio
+                .total_cmp(&b.ratio)
+                .then(b.compress_mbps.total_cmp(&a.compress_mbps))
+        })
+}
+
+/// The last benchmark of this device, if any
+pub(crate) fn saved_report() -> Option<BenchmarkReport> {
+    with_store(|store| store.get_json(BENCHMARKS_NS, REPORT_KEY)).ok().flatten()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Benchmark every algorithm against the file at `sample_path` and save the
+/// report for `get_compression_recommendation`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn benchmark_compression(sample_path: String) -> Result<BenchmarkReport, AppError> {
+    let report = tokio::task::spawn_blocking(move || {
+        let mut sample = Vec::new();
+        std::fs::File::open(&sample_path)?
+            .take(MAX_SAMPLE_BYTES as u64)
+            .read_to_end(&mut sample)?;
+        run_benchmark(&sample)
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Benchmark task failed: {}", e)))??;
+
+    with_store(|store| store.put_json(BENCHMARKS_NS, REPORT_KEY, &report, None))?;
+    Ok(report)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn get_compression_benchmark() -> Option<BenchmarkReport> {
+    saved_report()
+}
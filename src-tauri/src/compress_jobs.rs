@@ -0,0 +1,302 @@
+//! Folder Compression Jobs
+//!
+//! `compress_folder_start` walks a folder, returns a job ID straight away and
+//! compresses each file in the background with `compress_stream`, writing
+//! `<output_dir>/<relative path>.vxc`. Progress goes out as
+//! `compress-job-progress` events with file and byte counts; the last event
+//! of a job has a final `state`. `compress_job_cancel` stops a job between
+//! buffers, and the file being written is removed.
+//!
+//! The runner is not tied to compression: `start_job` takes the per-file work
+//! as a closure, and `pipeline_folder_start` uses it to run a pipeline over a
+//! folder with the same events and cancellation; `pipeline_process_folder`
+//! shares the registry but runs its own worker pool. Finished jobs leave the
+//! registry, so `compress_job_list` only shows running ones.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::Emitter;
+
+use crate::AppHandle;
+use crate::compress::Algorithm;
+use crate::compress_stream::{compress_stream, write_via_partial};
+use crate::github::AppError;
+use crate::scheduler::{JobClass, SCHEDULER};
+
+/// Extension appended to files written by `compress_folder_start`
+pub const COMPRESSED_FILE_EXT: &str = "vxc";
+
+lazy_static::lazy_static! {
+    static ref JOBS: Mutex<HashMap<String, JobEntry>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Compress,
+    Pipeline,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub job_id: String,
+    pub kind: JobKind,
+    pub state: JobState,
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Relative path of the file being processed
+    pub current_file: Option<String>,
+    pub error: Option<String>,
+}
+
+impl JobProgress {
+    pub fn new(job_id: &str, kind: JobKind, files: &[JobFile]) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            kind,
+            state: JobState::Running,
+            files_done: 0,
+            files_total: files.len(),
+            bytes_done: 0,
+            bytes_total: files.iter().map(|f| f.size).sum(),
+            current_file: None,
+            error: None,
+        }
+    }
+}
+
+/// A file found by `collect_files`
+#[derive(Clone, Debug)]
+pub struct JobFile {
+    pub source: PathBuf,
+    /// Path below the folder, `/`-separated
+    pub relative: String,
+    pub size: u64,
+}
+
+impl JobFile {
+    /// Where this file's output goes below `output_dir`, with `extension` appended
+    pub fn output_path(&self, output_dir: &Path, extension: &str) -> PathBuf {
+        output_dir.join(format!("{}.{}", self.relative, extension))
+    }
+}
+
+struct JobEntry {
+    cancel: Arc<AtomicBool>,
+    progress: JobProgress,
+}
+
+/// Every regular file below `root`, skipping hidden files and folders,
+/// sorted by relative path
+pub fn collect_files(root: &Path) -> Result<Vec<JobFile>, AppError> {
+    let mut files = Vec::new();
+    walk(root, "", &mut files)?;
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(files)
+}
+
+fn walk(dir: &Path, prefix: &str, files: &mut Vec<JobFile>) -> Result<(), AppError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        let relative = format!("{}{}", prefix, name);
+        let metadata = std::fs::metadata(&path)?;
+        if metadata.is_dir() {
+            walk(&path, &format!("{}/", relative), files)?;
+        } else if metadata.is_file() {
+            files.push(JobFile {
+                source: path,
+                relative,
+                size: metadata.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Run `work` over `files` in order, updating `job` and passing it to
+/// `report` as bytes go by. Each file is background work (see `scheduler`).
+/// Stops at the first failure or once `cancel` is set; `job.state` holds
+/// the outcome.
+pub fn run_job<W>(
+    job: &mut JobProgress,
+    files: &[JobFile],
+    cancel: &AtomicBool,
+    mut work: W,
+    mut report: impl FnMut(&JobProgress),
+) where
+    W: FnMut(&JobFile, &AtomicBool, &mut dyn FnMut(u64)) -> Result<(), AppError>,
+{
+    for file in files {
+        if cancel.load(Ordering::Relaxed) {
+            job.state = JobState::Cancelled;
+            break;
+        }
+        job.current_file = Some(file.relative.clone());
+        report(job);
+
+        let _permit = SCHEDULER.acquire_blocking(JobClass::Background);
+        let base = job.bytes_done;
+        let result = work(file, cancel, &mut |done| {
+            job.bytes_done = base + done.min(file.size);
+            report(job);
+        });
+        match result {
+            Ok(()) => {
+                job.files_done += 1;
+                job.bytes_done = base + file.size;
+            }
+            Err(_) if cancel.load(Ordering::Relaxed) => {
+                job.state = JobState::Cancelled;
+                break;
+            }
+            Err(e) => {
+                job.state = JobState::Failed;
+                job.error = Some(format!("{}: {}", file.relative, e));
+                break;
+            }
+        }
+    }
+    if job.state == JobState::Running {
+        job.state = JobState::Completed;
+    }
+    job.current_file = None;
+    report(job);
+}
+
+pub(crate) fn new_job_id() -> String {
+    format!("job-{}", hex::encode(rand::random::<[u8; 8]>()))
+}
+
+/// Add a job over `files` to the registry so `compress_job_cancel` and
+/// `compress_job_list` see it. Returns its starting progress and cancel flag.
+pub(crate) fn register_job(job_id: &str, kind: JobKind, files: &[JobFile]) -> (JobProgress, Arc<AtomicBool>) {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let job = JobProgress::new(job_id, kind, files);
+    JOBS.lock().unwrap().insert(
+        job.job_id.clone(),
+        JobEntry {
+            cancel: cancel.clone(),
+            progress: job.clone(),
+        },
+    );
+    (job, cancel)
+}
+
+/// Record `progress` in the registry and emit it as `compress-job-progress`
+pub(crate) fn publish_progress(app: &AppHandle, progress: &JobProgress) {
+    if let Some(entry) = JOBS.lock().unwrap().get_mut(&progress.job_id) {
+        entry.progress = progress.clone();
+    }
+    let _ = app.emit("compress-job-progress", progress);
+}
+
+pub(crate) fn is_running(job_id: &str) -> bool {
+    JOBS.lock().unwrap().contains_key(job_id)
+}
+
+pub(crate) fn finish_job(job_id: &str) {
+    JOBS.lock().unwrap().remove(job_id);
+}
+
+/// Register a job over `files` and run it on a blocking thread, emitting
+/// `compress-job-progress`. Returns the job ID.
+pub(crate) fn start_job<W>(app: AppHandle, kind: JobKind, files: Vec<JobFile>, work: W) -> String
+where
+    W: FnMut(&JobFile, &AtomicBool, &mut dyn FnMut(u64)) -> Result<(), AppError> + Send + 'static,
+{
+    let (mut job, cancel) = register_job(&new_job_id(), kind, &files);
+    let id = job.job_id.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        run_job(&mut job, &files, &cancel, work, |progress| publish_progress(&app, progress));
+        finish_job(&job.job_id);
+    });
+    id
+}
+
+/// Files of `folder`, or an error if it is not a folder or holds none
+pub(crate) fn folder_files(folder: &str) -> Result<Vec<JobFile>, AppError> {
+    let folder = Path::new(folder);
+    if !folder.is_dir() {
+        return Err(AppError::Validation(format!("{} is not a folder", folder.display())));
+    }
+    let files = collect_files(folder)?;
+    if files.is_empty() {
+        return Err(AppError::Validation("Folder has no files to process".into()));
+    }
+    Ok(files)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Compress every file of `folder` into `output_dir` in the background.
+/// Returns the job ID used by the progress events and `compress_job_cancel`.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn compress_folder_start(
+    app: AppHandle,
+    folder: String,
+    output_dir: String,
+    algorithm: String,
+    level: Option<i32>,
+) -> Result<String, AppError> {
+    let algorithm = Algorithm::try_from_str(&algorithm).map_err(|e| AppError::Validation(e.to_string()))?;
+    let level = level.unwrap_or(3);
+    let files = folder_files(&folder)?;
+    let output_dir = PathBuf::from(output_dir);
+
+    Ok(start_job(app, JobKind::Compress, files, move |file, cancel, progress| {
+        let output = file.output_path(&output_dir, COMPRESSED_FILE_EXT);
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let input = File::open(&file.source)?;
+        write_via_partial(&output, |writer| {
+            compress_stream(input, writer, algorithm, level, file.size, cancel, progress)
+        })?;
+        Ok(())
+    }))
+}
+
+/// Ask a running job to stop. Returns false if no such job is running.
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn compress_job_cancel(job_id: String) -> bool {
+    match JOBS.lock().unwrap().get(&job_id) {
+        Some(entry) => {
+            entry.cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Latest progress of every running job
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn compress_job_list() -> Vec<JobProgress> {
+    JOBS.lock().unwrap().values().map(|entry| entry.progress.clone()).collect()
+}
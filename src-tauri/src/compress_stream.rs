@@ -0,0 +1,483 @@
+//! Streaming File Compression
+//!
+//! `compress_file_stream` and `decompress_file_stream` work on paths so a
+//! multi-gigabyte video never has to fit in memory. Compression runs as a
+//! pipeline: a reader thread fills fixed-size buffers and hands them to the
+//! encoder through a bounded channel, so a slow encoder or disk stalls the
+//! reader instead of letting buffers pile up.
+//!
+//! Format:
+//!
+//! ```text
+//! [magic "VXCOMP"][version: 1][algorithm: 1][original length: u64 LE]
+//! [the algorithm's own streaming format]
+//! ```
+//!
+//! The streaming formats are the standard framed ones (zstd frames, LZ4
+//! frames, Snappy framing, Brotli, gzip, XZ), not the size-prefixed blocks of
+//! `compress_data`. Output goes to `<output>.part` and is renamed when done;
+//! a failed or cancelled run removes it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use tauri::Emitter;
+
+use crate::AppHandle;
+use crate::compress::Algorithm;
+use crate::github::AppError;
+
+const MAGIC: &[u8; 6] = b"VXCOMP";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 2 + 8;
+/// Bytes read per buffer
+pub const STREAM_BUFFER_SIZE: usize = 1024 * 1024;
+/// Buffers in flight between reader and encoder; bounds memory to about
+/// `(STREAM_QUEUE_DEPTH + 2) * STREAM_BUFFER_SIZE`
+pub const STREAM_QUEUE_DEPTH: usize = 4;
+
+lazy_static::lazy_static! {
+    static ref OPERATIONS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompressionProgress {
+    pub id: String,
+    pub bytes_done: u64,
+    pub total_bytes: u64,
+    pub percent: u8,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StreamCompressionResult {
+    pub output_path: String,
+    pub algorithm: Algorithm,
+    pub original_size: u64,
+    pub compressed_size: u64,
+    pub ratio: f64,
+}
+
+fn cancelled() -> AppError {
+    AppError::Validation("Compression was cancelled".into())
+}
+
+/// Counts bytes passing through to the inner writer
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+enum StreamEncoder<W: Write> {
+    None(W),
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    Lz4(lz4_flex::frame::FrameEncoder<W>),
+    Snap(Box<snap::write::FrameEncoder<W>>),
+    Brotli(Box<brotli::CompressorWriter<W>>),
+    Gzip(flate2::write::GzEncoder<W>),
+    Xz(xz2::write::XzEncoder<W>),
+}
+
+impl<W: Write> StreamEncoder<W> {
+    fn new(writer: W, algorithm: Algorithm, level: i32) -> Result<Self, AppError> {
+        Ok(match algorithm {
+            Algorithm::None => Self::None(writer),
+            Algorithm::Zstd => Self::Zstd(zstd::stream::write::Encoder::new(writer, level.clamp(1, 22))?),
+            Algorithm::Lz4 => Self::Lz4(lz4_flex::frame::FrameEncoder::new(writer)),
+            Algorithm::Snap => Self::Snap(Box::new(snap::write::FrameEncoder::new(writer))),
+            Algorithm::Brotli => Self::Brotli(Box::new(brotli::CompressorWriter::new(
+                writer,
+                STREAM_BUFFER_SIZE.min(64 * 1024),
+                level.clamp(0, 11) as u32,
+                22,
+            ))),
+            Algorithm::Gzip => Self::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::new(level.clamp(0, 9) as u32),
+            )),
+            Algorithm::Xz => Self::Xz(xz2::write::XzEncoder::new(writer, level.clamp(0, 9) as u32)),
+        })
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::None(w) => w.write_all(buf),
+            Self::Zstd(w) => w.write_all(buf),
+            Self::Lz4(w) => w.write_all(buf),
+            Self::Snap(w) => w.write_all(buf),
+            Self::Brotli(w) => w.write_all(buf),
+            Self::Gzip(w) => w.write_all(buf),
+            Self::Xz(w) => w.write_all(buf),
+        }
+    }
+
+    /// Write the end of the stream and hand back the writer
+    fn finish(self) -> Result<W, AppError> {
+        Ok(match self {
+            Self::None(w) => w,
+            Self::Zstd(w) => w.finish()?,
+            Self::Lz4(w) => w
+                .finish()
+                .map_err(|e| AppError::Validation(format!("LZ4 stream failed: {}", e)))?,
+            Self::Snap(w) => w
+                .into_inner()
+                .map_err(|e| AppError::Validation(format!("Snappy stream failed: {}", e.error())))?,
+            Self::Brotli(mut w) => {
+                w.flush()?;
+                w.into_inner()
+            }
+            Self::Gzip(w) => w.finish()?,
+            Self::Xz(w) => w.finish()?,
+        })
+    }
+}
+
+fn stream_decoder<'a, R: Read + 'a>(reader: R, algorithm: Algorithm) -> Result<Box<dyn Read + 'a>, AppError> {
+    Ok(match algorithm {
+        Algorithm::None => Box::new(reader),
+        Algorithm::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        Algorithm::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(reader)),
+        Algorithm::Snap => Box::new(snap::read::FrameDecoder::new(reader)),
+        Algorithm::Brotli => Box::new(brotli::Decompressor::new(reader, 64 * 1024)),
+        Algorithm::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+        Algorithm::Xz => Box::new(xz2::read::XzDecoder::new(reader)),
+    })
+}
+
+/// Compress everything `reader` yields into `writer`. `reader` runs on its
+/// own thread and blocks once `STREAM_QUEUE_DEPTH` buffers are waiting.
+/// `progress` gets the input bytes consumed so far. Returns the input and
+/// output sizes.
+pub fn compress_stream<R, W>(
+    reader: R,
+    writer: W,
+    algorithm: Algorithm,
+    level: i32,
+    total: u64,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(u64),
+) -> Result<(u64, u64), AppError>
+where
+    R: Read + Send + 'static,
+    W: Write,
+{
+    let (tx, rx) = sync_channel::<std::io::Result<Vec<u8>>>(STREAM_QUEUE_DEPTH);
+    let producer = std::thread::spawn(move || {
+        let mut reader = reader;
+        loop {
+            let mut buf = vec![0u8; STREAM_BUFFER_SIZE];
+            let result = read_full(&mut reader, &mut buf).map(|n| {
+                buf.truncate(n);
+                buf
+            });
+            let done = matches!(&result, Ok(buf) if buf.is_empty()) || result.is_err();
+            // The encoder hung up (error or cancel): stop reading
+            if tx.send(result).is_err() || done {
+                return;
+            }
+        }
+    });
+
+    let mut out = CountingWriter { inner: writer, count: 0 };
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION, algorithm.tag()])?;
+    out.write_all(&total.to_le_bytes())?;
+
+    let mut encoder = StreamEncoder::new(out, algorithm, level)?;
+    let result = pump(&rx, &mut encoder, cancel, &mut progress);
+    // Unblocks the reader if it is waiting on a full queue
+    drop(rx);
+    let _ = producer.join();
+    let done = result?;
+
+    let mut out = encoder.finish()?;
+    out.flush()?;
+    Ok((done, out.count))
+}
+
+fn pump<W: Write>(
+    rx: &Receiver<std::io::Result<Vec<u8>>>,
+    encoder: &mut StreamEncoder<W>,
+    cancel: &AtomicBool,
+    progress: &mut impl FnMut(u64),
+) -> Result<u64, AppError> {
+    let mut done = 0u64;
+    for chunk in rx.iter() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(cancelled());
+        }
+        let chunk = chunk?;
+        if chunk.is_empty() {
+            return Ok(done);
+        }
+        encoder.write_all(&chunk)?;
+        done += chunk.len() as u64;
+        progress(done);
+    }
+    Err(AppError::Validation("Reader stopped unexpectedly".into()))
+}
+
+/// Fill `buf` unless the reader ends first; returns the bytes read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Read the stream header: algorithm and original length
+pub fn read_stream_header(reader: &mut impl Read) -> Result<(Algorithm, u64), AppError> {
+    let mut header = [0u8; HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .map_err(|_| AppError::Validation("Not a Vortex compressed file".into()))?;
+    if &header[..MAGIC.len()] != MAGIC {
+        return Err(AppError::Validation("Not a Vortex compressed file".into()));
+    }
+    if header[MAGIC.len()] != VERSION {
+        return Err(AppError::Validation(format!(
+            "Unsupported compressed file version {}",
+            header[MAGIC.len()]
+        )));
+    }
+    let algorithm = Algorithm::from_tag(header[MAGIC.len() + 1]).map_err(|e| AppError::Validation(e.to_string()))?;
+    let total = u64::from_le_bytes(header[MAGIC.len() + 2..].try_into().expect("8 bytes"));
+    Ok((algorithm, total))
+}
+
+/// Decompress a stream written by `compress_stream` into `writer`.
+/// `progress` gets the output bytes written so far. Returns the algorithm
+/// and the output size.
+pub fn decompress_stream<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<(Algorithm, u64), AppError> {
+    let (algorithm, total) = read_stream_header(&mut reader)?;
+    let mut decoder = stream_decoder(&mut reader, algorithm)?;
+    let mut buf = vec![0u8; STREAM_BUFFER_SIZE];
+    let mut done = 0u64;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(cancelled());
+        }
+        let n = read_full(&mut decoder, &mut buf)
+            .map_err(|e| AppError::Validation(format!("Decompression failed: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        done += n as u64;
+        // A stream that outgrows its header is corrupt or hostile; stop
+        // before writing past the size the caller was shown
+        if done > total {
+            return Err(AppError::Validation(format!(
+                "Decompressed output exceeds the {} bytes the header promised",
+                total
+            )));
+        }
+        writer.write_all(&buf[..n])?;
+        progress(done, total);
+    }
+    if done != total {
+        return Err(AppError::Validation(format!(
+            "Decompressed {} bytes but the header promised {}",
+            done, total
+        )));
+    }
+    writer.flush()?;
+    Ok((algorithm, done))
+}
+
+// ============================================================================
+// Operations
+// ============================================================================
+
+/// Cancellation flag of a running operation, removed from the registry on drop
+struct Operation {
+    id: String,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Operation {
+    fn start(id: &str) -> Result<Self, AppError> {
+        let mut operations = OPERATIONS.lock().unwrap();
+        if operations.contains_key(id) {
+            return Err(AppError::Validation(format!("Operation {} is already running", id)));
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        operations.insert(id.to_string(), cancel.clone());
+        Ok(Self { id: id.to_string(), cancel })
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        OPERATIONS.lock().unwrap().remove(&self.id);
+    }
+}
+
+fn progress_emitter(app: AppHandle, id: String) -> impl FnMut(u64, u64) {
+    move |done, total| {
+        let _ = app.emit(
+            "compression-progress",
+            CompressionProgress {
+                id: id.clone(),
+                bytes_done: done,
+                total_bytes: total,
+                percent: (done * 100).checked_div(total).unwrap_or(100).min(100) as u8,
+            },
+        );
+    }
+}
+
+/// Run `f` writing to `<output>.part`, renaming it on success and removing
+/// it on failure
+pub(crate) fn write_via_partial<T>(
+    output: &Path,
+    f: impl FnOnce(BufWriter<File>) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    let partial = output.with_file_name(name);
+    let result = f(BufWriter::new(File::create(&partial)?));
+    match result {
+        Ok(value) => {
+            std::fs::rename(&partial, output)?;
+            Ok(value)
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            Err(e)
+        }
+    }
+}
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Validation(format!("Compression task failed: {}", e)))?
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Compress a file on disk into `output_path` without loading it into
+/// memory, emitting `compression-progress` under `operation_id`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn compress_file_stream(
+    app: AppHandle,
+    input_path: String,
+    output_path: String,
+    algorithm: String,
+    level: Option<i32>,
+    operation_id: String,
+) -> Result<StreamCompressionResult, AppError> {
+    let algorithm = Algorithm::try_from_str(&algorithm).map_err(|e| AppError::Validation(e.to_string()))?;
+    let operation = Operation::start(&operation_id)?;
+
+    run_blocking(move || {
+        let input = File::open(&input_path)?;
+        let total = input.metadata()?.len();
+        let mut emit = progress_emitter(app, operation_id);
+        let (original_size, compressed_size) = write_via_partial(Path::new(&output_path), |writer| {
+            compress_stream(
+                input,
+                writer,
+                algorithm,
+                level.unwrap_or(3),
+                total,
+                &operation.cancel,
+                |done| emit(done, total),
+            )
+        })?;
+
+        Ok(StreamCompressionResult {
+            output_path,
+            algorithm,
+            original_size,
+            compressed_size,
+            ratio: if original_size > 0 {
+                compressed_size as f64 / original_size as f64
+            } else {
+                1.0
+            },
+        })
+    })
+    .await
+}
+
+/// Decompress a file written by `compress_file_stream` into `output_path`,
+/// emitting `compression-progress` under `operation_id`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn decompress_file_stream(
+    app: AppHandle,
+    input_path: String,
+    output_path: String,
+    operation_id: String,
+) -> Result<StreamCompressionResult, AppError> {
+    let operation = Operation::start(&operation_id)?;
+
+    run_blocking(move || {
+        let input = File::open(&input_path)?;
+        let compressed_size = input.metadata()?.len();
+        let emit = progress_emitter(app, operation_id);
+        let (algorithm, original_size) = write_via_partial(Path::new(&output_path), |writer| {
+            decompress_stream(BufReader::new(input), writer, &operation.cancel, emit)
+        })?;
+
+        Ok(StreamCompressionResult {
+            output_path,
+            algorithm,
+            original_size,
+            compressed_size,
+            ratio: if original_size > 0 {
+                compressed_size as f64 / original_size as f64
+            } else {
+                1.0
+            },
+        })
+    })
+    .await
+}
+
+/// Ask a running stream operation to stop. Returns false if none is running
+/// under `operation_id`.
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn cancel_compression(operation_id: String) -> bool {
+    match OPERATIONS.lock().unwrap().get(&operation_id) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
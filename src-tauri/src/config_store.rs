@@ -0,0 +1,124 @@
+//! Per-Profile Config Lists
+//!
+//! Mirrors, retention policies and album schedules are each kept as a JSON
+//! list in the active profile's data folder, loaded on first use and cached
+//! until the profile changes. Changes are made to a copy that replaces the
+//! cache only once it is on disk, so a failed write leaves both as they
+//! were. The GitHub tokens they run with live in the
+//! keychain, one entry per config id.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use zeroize::Zeroizing;
+
+use crate::crypto::{keychain_delete, keychain_retrieve, keychain_store};
+use crate::github::AppError;
+
+/// A list of configs stored in `file` below the profile's data folder
+pub(crate) struct ConfigList<T> {
+    file: &'static str,
+    /// What the list holds, for error messages
+    what: &'static str,
+    /// `None` until first use
+    cache: Mutex<Option<Vec<T>>>,
+}
+
+impl<T: Clone + Serialize + DeserializeOwned> ConfigList<T> {
+    pub(crate) const fn new(file: &'static str, what: &'static str) -> Self {
+        Self {
+            file,
+            what,
+            cache: Mutex::new(None),
+        }
+    }
+
+    fn path(&self) -> Result<PathBuf, AppError> {
+        Ok(crate::profiles::data_dir()?.join(self.file))
+    }
+
+    /// The cache, loaded from disk first if needed
+    fn loaded(&self) -> Result<MutexGuard<'_, Option<Vec<T>>>, AppError> {
+        let mut guard = self.cache.lock().unwrap();
+        if guard.is_none() {
+            let path = self.path()?;
+            let loaded = if path.exists() {
+                serde_json::from_slice(&std::fs::read(&path)?)
+                    .map_err(|e| AppError::Validation(format!("Corrupt {}: {}", self.what, e)))?
+            } else {
+                Vec::new()
+            };
+            *guard = Some(loaded);
+        }
+        Ok(guard)
+    }
+
+    /// Run `f` on the list
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&[T]) -> R) -> Result<R, AppError> {
+        Ok(f(self.loaded()?.as_deref().unwrap()))
+    }
+
+    /// Run `f` on a copy of the list and save it; the cache only takes the
+    /// copy once it is written
+    pub(crate) fn update<R>(&self, f: impl FnOnce(&mut Vec<T>) -> R) -> Result<R, AppError> {
+        let mut guard = self.loaded()?;
+        let mut items = guard.as_ref().unwrap().clone();
+        let result = f(&mut items);
+        self.save(&items)?;
+        *guard = Some(items);
+        Ok(result)
+    }
+
+    /// Write atomically, so a crash mid-write leaves the previous list
+    fn save(&self, items: &[T]) -> Result<(), AppError> {
+        let json = serde_json::to_vec_pretty(items)
+            .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        let path = self.path()?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Reload from disk on next use, after a profile switch
+    pub(crate) fn forget(&self) {
+        *self.cache.lock().unwrap() = None;
+    }
+}
+
+/// Keychain entries `<prefix>:<id>` holding the token a config runs with
+pub(crate) struct TokenSlot {
+    prefix: &'static str,
+    /// What the token is for, for error messages
+    what: &'static str,
+}
+
+impl TokenSlot {
+    pub(crate) const fn new(prefix: &'static str, what: &'static str) -> Self {
+        Self { prefix, what }
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("{}:{}", self.prefix, id)
+    }
+
+    pub(crate) fn store(&self, id: &str, token: &str) -> Result<(), AppError> {
+        keychain_store(&self.key(id), token.as_bytes())
+            .map_err(|e| AppError::Validation(format!("Failed to store {} token: {}", self.what, e)))
+    }
+
+    pub(crate) fn load(&self, id: &str) -> Result<Zeroizing<String>, AppError> {
+        let raw = Zeroizing::new(
+            keychain_retrieve(&self.key(id))
+                .map_err(|e| AppError::Validation(format!("The {} token is unavailable: {}", self.what, e)))?,
+        );
+        std::str::from_utf8(&raw)
+            .map(|token| Zeroizing::new(token.to_string()))
+            .map_err(|_| AppError::Validation(format!("The {} token is unavailable", self.what)))
+    }
+
+    /// Best effort; a missing entry is not an error
+    pub(crate) fn delete(&self, id: &str) {
+        let _ = keychain_delete(&self.key(id));
+    }
+}
@@ -0,0 +1,224 @@
+//! Contact Book
+//!
+//! Peers and their public bundles, so encryption and messaging can name a
+//! contact instead of passing raw key material around. A contact starts out
+//! unverified; `verify_contact_fingerprint` marks it verified once the user
+//! has compared the bundle fingerprint with the peer out of band.
+//!
+//! The book is stored in `<profile data>/contacts.bin`, encrypted
+//! with a key derived from the machine key and a random per-file salt:
+//! `[salt: 32][nonce: 12][ciphertext]`.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::crypto::{open_with_machine_key, seal_with_machine_key, PublicBundle};
+use crate::github::{AppError, GithubError};
+use crate::util::now_secs;
+
+const CONTACTS_FILE: &str = "contacts.bin";
+const CONTACTS_AAD: &[u8] = b"vortex-contacts-v1";
+const MAX_NAME_LEN: usize = 100;
+
+lazy_static::lazy_static! {
+    static ref CONTACTS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Contact {
+    pub id: String,
+    pub name: String,
+    pub bundle: PublicBundle,
+    /// Fingerprint of `bundle`, cached for display
+    pub fingerprint: String,
+    pub verified: bool,
+    pub added_at: u64,
+    pub verified_at: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ContactBook {
+    pub contacts: Vec<Contact>,
+}
+
+fn not_found(id: &str) -> AppError {
+    GithubError::NotFound {
+        message: format!("Contact {} not found", id),
+    }
+    .into()
+}
+
+/// Compare fingerprints ignoring case, spaces and dashes. `given` may also be
+/// the fingerprint words (see `fingerprint::to_words`).
+pub fn fingerprints_match(expected: &str, given: &str) -> bool {
+    let clean = |s: &str| -> String {
+        s.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != ':')
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    };
+    let given = match crate::fingerprint::parse_fingerprint(given) {
+        Ok(bytes) => hex::encode_upper(bytes),
+        Err(_) => clean(given),
+    };
+    !given.is_empty() && clean(expected) == given
+}
+
+// ============================================================================
+// Book
+// ============================================================================
+
+impl ContactBook {
+    /// Add a contact. The bundle's key id is recomputed from its key material;
+    /// a bundle already in the book is rejected.
+    pub fn add(&mut self, name: &str, mut bundle: PublicBundle) -> Result<&Contact, AppError> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN || name.chars().any(char::is_control) {
+            return Err(AppError::Validation("Invalid contact name".into()));
+        }
+        if bundle.pq_encap.is_empty() || bundle.pq_verify.is_empty() {
+            return Err(AppError::Validation("Public bundle is incomplete".into()));
+        }
+        bundle.key_id = bundle.derived_key_id();
+        if let Some(existing) = self.contacts.iter().find(|c| c.bundle.key_id == bundle.key_id) {
+            return Err(AppError::Validation(format!(
+                "This key already belongs to contact {}",
+                existing.name
+            )));
+        }
+
+        self.contacts.push(Contact {
+            id: hex::encode(rand::random::<[u8; 8]>()),
+            name: name.to_string(),
+            fingerprint: bundle.fingerprint(),
+            bundle,
+            verified: false,
+            added_at: now_secs(),
+            verified_at: None,
+        });
+        Ok(self.contacts.last().expect("just pushed"))
+    }
+
+    pub fn find_by_key(&self, key_id: &str) -> Option<&Contact> {
+        self.contacts.iter().find(|c| c.bundle.key_id == key_id)
+    }
+
+    pub fn get(&self, id: &str) -> Result<&Contact, AppError> {
+        self.contacts.iter().find(|c| c.id == id).ok_or_else(|| not_found(id))
+    }
+
+    /// Mark a contact verified if `fingerprint` matches its bundle
+    pub fn verify(&mut self, id: &str, fingerprint: &str) -> Result<&Contact, AppError> {
+        let contact = self.contacts.iter_mut().find(|c| c.id == id).ok_or_else(|| not_found(id))?;
+        if !fingerprints_match(&contact.bundle.fingerprint(), fingerprint) {
+            return Err(AppError::Validation(format!(
+                "Fingerprint does not match the key stored for {}",
+                contact.name
+            )));
+        }
+        if !contact.verified {
+            contact.verified = true;
+            contact.verified_at = Some(now_secs());
+        }
+        Ok(contact)
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<(), AppError> {
+        let before = self.contacts.len();
+        self.contacts.retain(|c| c.id != id);
+        if self.contacts.len() == before {
+            return Err(not_found(id));
+        }
+        Ok(())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, AppError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = open_with_machine_key(&std::fs::read(path)?, CONTACTS_AAD)
+            .map_err(|_| AppError::Validation("Contact book could not be decrypted".into()))?;
+        serde_json::from_slice(&json).map_err(|e| AppError::Validation(format!("Corrupt contact book: {}", e)))
+    }
+
+    /// Encrypt with a fresh salt and write atomically
+    pub fn save_to(&self, path: &Path) -> Result<(), AppError> {
+        let json = serde_json::to_vec(self)
+            .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        let sealed = seal_with_machine_key(&json, CONTACTS_AAD).map_err(|e| AppError::Validation(e.to_string()))?;
+        let tmp = path.with_extension("bin.tmp");
+        std::fs::write(&tmp, sealed)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+fn contacts_file() -> Result<PathBuf, AppError> {
+    Ok(crate::profiles::data_dir()?.join(CONTACTS_FILE))
+}
+
+fn with_contacts<T>(save: bool, f: impl FnOnce(&mut ContactBook) -> Result<T, AppError>) -> Result<T, AppError> {
+    let _guard = CONTACTS_LOCK.lock().unwrap();
+    let path = contacts_file()?;
+    let mut book = ContactBook::load_from(&path)?;
+    let result = f(&mut book)?;
+    if save {
+        book.save_to(&path)?;
+    }
+    Ok(result)
+}
+
+pub(crate) fn load_contacts() -> Result<ContactBook, AppError> {
+    with_contacts(false, |book| Ok(std::mem::take(book)))
+}
+
+/// Public bundle of a stored contact
+pub(crate) fn contact_bundle(id: &str) -> Result<PublicBundle, AppError> {
+    with_contacts(false, |book| Ok(book.get(id)?.bundle.clone()))
+}
+
+/// The bundle to encrypt for: a raw bundle or a contact, exactly one of them
+pub(crate) fn resolve_recipient(bundle: Option<PublicBundle>, contact_id: Option<&str>) -> Result<PublicBundle, AppError> {
+    match (bundle, contact_id) {
+        (Some(bundle), None) => Ok(bundle),
+        (None, Some(id)) => contact_bundle(id),
+        _ => Err(AppError::Validation(
+            "Pass either a public bundle or a contact id".into(),
+        )),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn add_contact(name: String, public_bundle: PublicBundle) -> Result<Contact, AppError> {
+    with_contacts(true, |book| book.add(&name, public_bundle).cloned())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn list_contacts() -> Result<Vec<Contact>, AppError> {
+    with_contacts(false, |book| {
+        let mut contacts = book.contacts.clone();
+        contacts.sort_by_key(|c| c.name.to_lowercase());
+        Ok(contacts)
+    })
+}
+
+/// Mark a contact verified after comparing fingerprints out of band.
+/// Fails, leaving the contact unverified, if the fingerprint differs.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn verify_contact_fingerprint(contact_id: String, fingerprint: String) -> Result<Contact, AppError> {
+    with_contacts(true, |book| book.verify(&contact_id, &fingerprint).cloned())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn remove_contact(contact_id: String) -> Result<(), AppError> {
+    with_contacts(true, |book| book.remove(&contact_id))
+}
@@ -39,6 +39,9 @@ use hkdf::Hkdf;
 use sha2::Sha512;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
+use crate::keystore::Keystore;
+use crate::util::now_secs;
+
 #[cfg(not(feature = "pqcrypto-backend"))]
 use pqc_kyber::{
     decapsulate, encapsulate, keypair as kyber_keypair, KYBER_CIPHERTEXTBYTES,
@@ -84,6 +87,17 @@ const HYBRID_KDF_DOMAIN: &[u8] = b"vortex-hybrid-pq-v2";
 const SESSION_KDF_DOMAIN: &[u8] = b"vortex-session-v3";
 /// Domain separator for token encryption
 const TOKEN_KDF_DOMAIN: &[u8] = b"vortex-token-v4";
+/// Domain separator for per-album content keys
+const ALBUM_KDF_DOMAIN: &[u8] = b"vortex-album-key-v1";
+/// Domain separator for public bundle fingerprints
+const FINGERPRINT_DOMAIN: &[u8] = b"vortex-fingerprint-v1";
+/// Secret kinds as they appear in `crypto_hygiene_report`
+const KEM_SECRET_KIND: &str = "ML-KEM decapsulation key";
+const PQ_SIGNING_KIND: &str = "ML-DSA signing key";
+const X25519_SECRET_KIND: &str = "X25519 secret key";
+const ED25519_SIGNING_KIND: &str = "Ed25519 signing key";
+const ALBUM_KEY_KIND: &str = "album content key";
+const SERIALIZED_KEYPAIR_KIND: &str = "serialized keypair";
 
 // ============================================================================
 // Error Types
@@ -150,24 +164,87 @@ impl Serialize for CryptoError {
 // Argon2 Configuration - Secure Parameters
 // ============================================================================
 
-/// Secure Argon2id parameters following OWASP recommendations
-/// Memory: 64 MiB, Iterations: 3, Parallelism: 4
-fn get_argon2_params() -> argon2::Params {
-    argon2::Params::new(
-        64 * 1024, // 64 MiB memory cost
-        3,         // 3 iterations
-        4,         // 4 parallel lanes
-        Some(32),  // 32-byte output
-    )
-    .expect("valid argon2 params")
+/// Argon2id costs. Password-encrypted data records the costs it was made
+/// with, so they can be raised per machine (see `password::calibrate_kdf`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// OWASP-derived minimum, and the costs of data without a KDF header
+    /// Memory: 64 MiB, Iterations: 3, Parallelism: 4
+    pub const FLOOR: Self = Self {
+        memory_kib: 64 * 1024,
+        iterations: 3,
+        parallelism: 4,
+    };
+    /// Upper bounds accepted from a header, so a crafted file cannot make
+    /// decryption allocate without limit
+    const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+    const MAX_ITERATIONS: u32 = 64;
+    const MAX_PARALLELISM: u32 = 16;
+    pub(crate) const ENCODED_LEN: usize = 12;
+
+    fn argon2(&self) -> Result<argon2::Argon2<'static>, CryptoError> {
+        if self.memory_kib > Self::MAX_MEMORY_KIB
+            || self.iterations > Self::MAX_ITERATIONS
+            || self.parallelism > Self::MAX_PARALLELISM
+        {
+            return Err(CryptoError::InvalidInput("KDF parameters out of range".into()));
+        }
+        let params = argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|_| CryptoError::InvalidInput("invalid KDF parameters".into()))?;
+        Ok(argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params))
+    }
+
+    /// Derive a 32-byte key from a password and salt with these costs
+    pub fn derive(&self, password: &[u8], salt: &[u8]) -> Result<[u8; 32], CryptoError> {
+        let mut key = [0u8; 32];
+        self.argon2()?
+            .hash_password_into(password, salt, &mut key)
+            .map_err(|_| CryptoError::KeyDerivation("argon2 failed".into()))?;
+        Ok(key)
+    }
+
+    pub(crate) fn to_bytes(self) -> [u8; Self::ENCODED_LEN] {
+        let mut out = [0u8; Self::ENCODED_LEN];
+        out[..4].copy_from_slice(&self.memory_kib.to_le_bytes());
+        out[4..8].copy_from_slice(&self.iterations.to_le_bytes());
+        out[8..].copy_from_slice(&self.parallelism.to_le_bytes());
+        out
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Self {
+        let word = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().expect("4 bytes"));
+        Self {
+            memory_kib: word(0),
+            iterations: word(4),
+            parallelism: word(8),
+        }
+    }
+}
+
+static KDF_PARAMS: RwLock<Option<KdfParams>> = RwLock::new(None);
+
+/// Costs for new password-encrypted data: the calibrated ones if this host
+/// has been calibrated, the floor otherwise
+pub fn current_kdf_params() -> KdfParams {
+    KDF_PARAMS
+        .read()
+        .ok()
+        .and_then(|p| *p)
+        .unwrap_or(KdfParams::FLOOR)
 }
 
-fn get_argon2() -> argon2::Argon2<'static> {
-    argon2::Argon2::new(
-        argon2::Algorithm::Argon2id,
-        argon2::Version::V0x13,
-        get_argon2_params(),
-    )
+pub fn set_kdf_params(params: KdfParams) -> Result<(), CryptoError> {
+    params.argon2()?;
+    *KDF_PARAMS
+        .write()
+        .map_err(|_| CryptoError::KeyDerivation("KDF parameter lock poisoned".into()))? = Some(params);
+    Ok(())
 }
 
 // ============================================================================
@@ -175,54 +252,135 @@ fn get_argon2() -> argon2::Argon2<'static> {
 // ============================================================================
 
 /// Wrapper for secret key material that zeroizes on drop
+/// The buffer is memory-locked where the OS allows (see `hygiene`)
 /// NOTE: Clone intentionally NOT derived to prevent accidental copies
-#[derive(Zeroize, ZeroizeOnDrop)]
-pub struct SecretBytes(Vec<u8>);
+pub struct SecretBytes {
+    data: Vec<u8>,
+    kind: &'static str,
+    locked: bool,
+}
 
 impl SecretBytes {
     pub fn new(data: Vec<u8>) -> Self {
-        Self(data)
+        Self::labeled("unlabeled", data)
+    }
+
+    /// Wrap `data`, reporting it under `kind` in the hygiene report
+    pub fn labeled(kind: &'static str, data: Vec<u8>) -> Self {
+        let locked = crate::hygiene::lock(data.as_ptr(), data.len());
+        crate::hygiene::record_created(kind, data.len(), locked);
+        Self { data, kind, locked }
     }
 
     pub fn as_slice(&self) -> &[u8] {
-        &self.0
+        &self.data
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.data.len()
     }
 
     #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.data.is_empty()
+    }
+
+    /// Whether the buffer is pinned in RAM
+    #[allow(dead_code)]
+    pub fn is_locked(&self) -> bool {
+        self.locked
     }
 
     /// Explicit clone for when absolutely necessary (auditable)
     /// This method name makes cloning visible in code review
     #[allow(dead_code)]
     pub fn clone_secret(&self) -> Self {
-        Self(self.0.clone())
+        Self::labeled(self.kind, self.data.clone())
+    }
+}
+
+impl std::ops::Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Zeroize for SecretBytes {
+    fn zeroize(&mut self) {
+        self.data.as_mut_slice().zeroize();
     }
 }
 
-/// Fixed-size secret key with zeroization
+impl ZeroizeOnDrop for SecretBytes {}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        let len = self.data.len();
+        self.data.as_mut_slice().zeroize();
+        if self.locked {
+            crate::hygiene::unlock(self.data.as_ptr(), len);
+        }
+        crate::hygiene::record_wiped(self.kind, len, self.locked);
+    }
+}
+
+/// Key bytes arrive from the frontend as a plain byte array
+impl<'de> Deserialize<'de> for SecretBytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<u8>::deserialize(deserializer).map(|data| Self::labeled("command argument", data))
+    }
+}
+
+/// Fixed-size secret key with zeroization, boxed so it can be memory-locked
 /// NOTE: Clone intentionally NOT derived
-#[derive(Zeroize, ZeroizeOnDrop)]
-pub struct SecretKey32([u8; 32]);
+pub struct SecretKey32 {
+    data: Box<[u8; 32]>,
+    kind: &'static str,
+    locked: bool,
+}
 
 impl SecretKey32 {
     pub fn new(data: [u8; 32]) -> Self {
-        Self(data)
+        Self::labeled("unlabeled", data)
+    }
+
+    /// Wrap `data`, reporting it under `kind` in the hygiene report
+    pub fn labeled(kind: &'static str, mut data: [u8; 32]) -> Self {
+        let boxed = Box::new(data);
+        data.zeroize();
+        let locked = crate::hygiene::lock(boxed.as_ptr(), boxed.len());
+        crate::hygiene::record_created(kind, boxed.len(), locked);
+        Self { data: boxed, kind, locked }
     }
 
     pub fn as_bytes(&self) -> &[u8; 32] {
-        &self.0
+        &self.data
     }
 
     /// Explicit clone for when absolutely necessary (auditable)
     #[allow(dead_code)]
     pub fn clone_secret(&self) -> Self {
-        Self(self.0)
+        Self::labeled(self.kind, *self.data)
+    }
+}
+
+impl Zeroize for SecretKey32 {
+    fn zeroize(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretKey32 {}
+
+impl Drop for SecretKey32 {
+    fn drop(&mut self) {
+        self.data.zeroize();
+        if self.locked {
+            crate::hygiene::unlock(self.data.as_ptr(), self.data.len());
+        }
+        crate::hygiene::record_wiped(self.kind, self.data.len(), self.locked);
     }
 }
 
@@ -274,8 +432,28 @@ impl KeypairStore {
         self.keypairs.remove(&handle)
     }
 
+    /// Drop every keypair. Handles keep counting up, so an old handle never
+    /// names a keypair loaded later.
+    pub(crate) fn clear(&mut self) {
+        self.keypairs.clear();
+        self.rotated_keypairs.clear();
+    }
+
     /// Rotate a keypair: generate new one, keep old for decryption
+    #[allow(dead_code)]
     pub(crate) fn rotate(&mut self, handle: KeypairHandle) -> Result<PublicBundle, CryptoError> {
+        if !self.keypairs.contains_key(&handle) {
+            return Err(CryptoError::KeypairNotFound);
+        }
+        self.rotate_to(handle, HybridKeypair::generate()?)
+    }
+
+    /// Rotate a keypair to `new_keypair`, keeping the old one for decryption
+    pub(crate) fn rotate_to(
+        &mut self,
+        handle: KeypairHandle,
+        mut new_keypair: HybridKeypair,
+    ) -> Result<PublicBundle, CryptoError> {
         let old_keypair = self.keypairs.remove(&handle)
             .ok_or(CryptoError::KeypairNotFound)?;
 
@@ -291,8 +469,7 @@ impl KeypairStore {
             .or_insert_with(Vec::new)
             .push(old_keypair);
 
-        // Generate new keypair with same handle
-        let mut new_keypair = HybridKeypair::generate()?;
+        // The new keypair takes over the same handle
         new_keypair.rotation_count = old_rotation_count + 1;
         let public_bundle = new_keypair.public_bundle();
         
@@ -373,13 +550,13 @@ pub struct PublicBundle {
     pub key_id: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct EncapsulatedKey {
     pub pq_ciphertext: Vec<u8>,
     pub x25519_ephemeral: [u8; 32],
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct EncryptedPayload {
     pub nonce: [u8; 12],
     pub ciphertext: Vec<u8>,
@@ -419,19 +596,16 @@ impl HybridKeypair {
         let ed_sign_key = SigningKey::generate(&mut rng);
         let ed_verify_key = ed_sign_key.verifying_key();
 
-        let created_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
+        let created_at = now_secs();
 
         Ok(Self {
             pq_encap_key: kyber_keys.public.to_vec(),
-            pq_decap_key: SecretBytes::new(kyber_keys.secret.to_vec()),
-            x25519_secret: SecretKey32::new(x_secret.to_bytes()),
+            pq_decap_key: SecretBytes::labeled(KEM_SECRET_KIND, kyber_keys.secret.to_vec()),
+            x25519_secret: SecretKey32::labeled(X25519_SECRET_KIND, x_secret.to_bytes()),
             x25519_public: x_public.to_bytes(),
-            pq_signing_key: SecretBytes::new(dil_keys.expose_secret().to_vec()),
+            pq_signing_key: SecretBytes::labeled(PQ_SIGNING_KIND, dil_keys.expose_secret().to_vec()),
             pq_verifying_key: dil_keys.public.to_vec(),
-            ed_signing_key: SecretKey32::new(ed_sign_key.to_bytes()),
+            ed_signing_key: SecretKey32::labeled(ED25519_SIGNING_KIND, ed_sign_key.to_bytes()),
             ed_verifying_key: ed_verify_key.to_bytes(),
             created_at,
             rotation_count: 0,
@@ -457,19 +631,16 @@ impl HybridKeypair {
         let ed_sign_key = SigningKey::generate(&mut rng);
         let ed_verify_key = ed_sign_key.verifying_key();
 
-        let created_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
+        let created_at = now_secs();
 
         Ok(Self {
             pq_encap_key: pq_encap.as_bytes().to_vec(),
-            pq_decap_key: SecretBytes::new(pq_decap.as_bytes().to_vec()),
-            x25519_secret: SecretKey32::new(x_secret.to_bytes()),
+            pq_decap_key: SecretBytes::labeled(KEM_SECRET_KIND, pq_decap.as_bytes().to_vec()),
+            x25519_secret: SecretKey32::labeled(X25519_SECRET_KIND, x_secret.to_bytes()),
             x25519_public: x_public.to_bytes(),
-            pq_signing_key: SecretBytes::new(pq_sign.as_bytes().to_vec()),
+            pq_signing_key: SecretBytes::labeled(PQ_SIGNING_KIND, pq_sign.as_bytes().to_vec()),
             pq_verifying_key: pq_verify.as_bytes().to_vec(),
-            ed_signing_key: SecretKey32::new(ed_sign_key.to_bytes()),
+            ed_signing_key: SecretKey32::labeled(ED25519_SIGNING_KIND, ed_sign_key.to_bytes()),
             ed_verifying_key: ed_verify_key.to_bytes(),
             created_at,
             rotation_count: 0,
@@ -698,6 +869,44 @@ impl PublicBundle {
     }
 }
 
+// ============================================================================
+// PublicBundle Implementation - Identity
+// ============================================================================
+
+impl PublicBundle {
+    /// Key ID computed from the key material, as `HybridKeypair` does.
+    /// Use this instead of trusting the `key_id` field of a received bundle.
+    pub fn derived_key_id(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.pq_encap);
+        hasher.update(&self.x25519);
+        hasher.update(&self.ed_verify);
+        hex::encode(&hasher.finalize().as_bytes()[..8])
+    }
+
+    /// 128-bit BLAKE3 hash over all public key material
+    pub fn fingerprint_bytes(&self) -> [u8; 16] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(FINGERPRINT_DOMAIN);
+        for part in [&self.pq_encap[..], &self.x25519, &self.pq_verify, &self.ed_verify] {
+            hasher.update(&(part.len() as u32).to_le_bytes());
+            hasher.update(part);
+        }
+        hasher.finalize().as_bytes()[..16].try_into().expect("hash is 32 bytes")
+    }
+
+    /// Fingerprint over all public key material, for out-of-band comparison.
+    /// 32 uppercase hex digits in groups of four; see `fingerprint` for words.
+    pub fn fingerprint(&self) -> String {
+        let hex = hex::encode_upper(self.fingerprint_bytes());
+        hex.as_bytes()
+            .chunks(4)
+            .map(|c| std::str::from_utf8(c).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
 // ============================================================================
 // Hybrid Key Derivation
 // ============================================================================
@@ -991,7 +1200,7 @@ pub fn decrypt(payload: &EncryptedPayload, keypair: &HybridKeypair) -> Result<Ve
 pub fn keychain_store(key: &str, value: &[u8]) -> Result<(), CryptoError> {
     let entry = keyring::Entry::new(KEYCHAIN_SERVICE, key)
         .map_err(|e| {
-            log::debug!("Failed to create keychain entry '{}': {}", key, e);
+            tracing::debug!("Failed to create keychain entry '{}': {}", key, e);
             CryptoError::Keychain(format!("failed to create entry: {}", e))
         })?;
 
@@ -1001,11 +1210,11 @@ pub fn keychain_store(key: &str, value: &[u8]) -> Result<(), CryptoError> {
     entry
         .set_password(&encoded)
         .map_err(|e| {
-            log::warn!("Keychain storage failed for '{}': {}", key, e);
+            tracing::warn!("Keychain storage failed for '{}': {}", key, e);
             CryptoError::Keychain(format!("failed to store: {}", e))
         })?;
     
-    log::debug!("Successfully stored '{}' in OS keychain", key);
+    tracing::debug!("Successfully stored '{}' in OS keychain", key);
     Ok(())
 }
 
@@ -1013,20 +1222,20 @@ pub fn keychain_store(key: &str, value: &[u8]) -> Result<(), CryptoError> {
 pub fn keychain_retrieve(key: &str) -> Result<Vec<u8>, CryptoError> {
     let entry = keyring::Entry::new(KEYCHAIN_SERVICE, key)
         .map_err(|e| {
-            log::debug!("Failed to create keychain entry for retrieval '{}': {}", key, e);
+            tracing::debug!("Failed to create keychain entry for retrieval '{}': {}", key, e);
             CryptoError::Keychain(format!("failed to create entry: {}", e))
         })?;
 
     let encoded = entry
         .get_password()
         .map_err(|e| {
-            log::debug!("Failed to retrieve '{}' from keychain: {}", key, e);
+            tracing::debug!("Failed to retrieve '{}' from keychain: {}", key, e);
             CryptoError::Keychain(format!("failed to retrieve: {}", e))
         })?;
 
     base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &encoded)
         .map_err(|e| {
-            log::warn!("Failed to decode keychain value for '{}': {}", key, e);
+            tracing::warn!("Failed to decode keychain value for '{}': {}", key, e);
             CryptoError::Keychain(format!("failed to decode: {}", e))
         })
 }
@@ -1039,11 +1248,11 @@ pub fn keychain_delete(key: &str) -> Result<(), CryptoError> {
     entry
         .delete_password()
         .map_err(|e| {
-            log::debug!("Failed to delete '{}' from keychain: {}", key, e);
+            tracing::debug!("Failed to delete '{}' from keychain: {}", key, e);
             CryptoError::Keychain(format!("failed to delete: {}", e))
         })?;
     
-    log::debug!("Successfully deleted '{}' from OS keychain", key);
+    tracing::debug!("Successfully deleted '{}' from OS keychain", key);
     Ok(())
 }
 
@@ -1063,17 +1272,17 @@ pub fn keychain_available() -> bool {
             
             match test_result {
                 Ok(_) => {
-                    log::debug!("OS keychain is available and functional");
+                    tracing::debug!("OS keychain is available and functional");
                     true
                 }
                 Err(e) => {
-                    log::debug!("OS keychain test failed: {}", e);
+                    tracing::debug!("OS keychain test failed: {}", e);
                     false
                 }
             }
         }
         Err(e) => {
-            log::debug!("OS keychain not available: {}", e);
+            tracing::debug!("OS keychain not available: {}", e);
             false
         }
     }
@@ -1094,10 +1303,7 @@ impl TokenContext {
     pub fn new() -> Self {
         Self {
             service_id: KEYCHAIN_SERVICE.to_string(),
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs())
-                .unwrap_or(0),
+            timestamp: now_secs(),
         }
     }
 
@@ -1142,7 +1348,7 @@ pub(crate) fn get_machine_key_with_salt(salt: &[u8]) -> [u8; 32] {
         if !trimmed.is_empty() && trimmed.len() >= 32 {
             hasher.update(trimmed.as_bytes());
             has_strong_id = true;
-            log::debug!("Using /etc/machine-id for token encryption key");
+            tracing::debug!("Using /etc/machine-id for token encryption key");
         }
     }
 
@@ -1159,7 +1365,7 @@ pub(crate) fn get_machine_key_with_salt(salt: &[u8]) -> [u8; 32] {
                     if let Some(uuid) = uuid_line.split('"').nth(3) {
                         hasher.update(uuid.as_bytes());
                         has_strong_id = true;
-                        log::debug!("Using IOPlatformUUID for token encryption key");
+                        tracing::debug!("Using IOPlatformUUID for token encryption key");
                     }
                 }
             }
@@ -1181,7 +1387,7 @@ pub(crate) fn get_machine_key_with_salt(salt: &[u8]) -> [u8; 32] {
                         if let Some(guid) = line.split_whitespace().last() {
                             hasher.update(guid.as_bytes());
                             has_strong_id = true;
-                            log::debug!("Using Windows MachineGuid for token encryption key");
+                            tracing::debug!("Using Windows MachineGuid for token encryption key");
                         }
                     }
                 }
@@ -1191,7 +1397,7 @@ pub(crate) fn get_machine_key_with_salt(salt: &[u8]) -> [u8; 32] {
 
     // Fallback: use weaker identifiers with warning
     if !has_strong_id {
-        log::warn!(
+        tracing::warn!(
             "SECURITY WARNING: Using weak machine identifiers for token encryption. \
              This may occur in containers, VMs, or systems without /etc/machine-id. \
              Consider using OS keychain for better security. \
@@ -1369,7 +1575,7 @@ pub fn decrypt_token(data: &[u8]) -> Result<(String, Option<Vec<u8>>), CryptoErr
             // Auto-migrate to v4
             let context = TokenContext::new();
             let upgraded = encrypt_token_v4(&plaintext, &context)?;
-            log::info!("Migrated v3 token to v4 format");
+            tracing::info!("Migrated v3 token to v4 format");
             Ok((plaintext, Some(upgraded)))
         }
         TOKEN_VERSION_V2 => {
@@ -1377,7 +1583,7 @@ pub fn decrypt_token(data: &[u8]) -> Result<(String, Option<Vec<u8>>), CryptoErr
             // Auto-migrate to v4
             let context = TokenContext::new();
             let upgraded = encrypt_token_v4(&plaintext, &context)?;
-            log::info!("Migrated v2 token to v4 format");
+            tracing::info!("Migrated v2 token to v4 format");
             Ok((plaintext, Some(upgraded)))
         }
         _ => Err(CryptoError::UnsupportedTokenVersion(version)),
@@ -1394,19 +1600,34 @@ pub fn encrypt_token(plaintext: &str) -> Result<Vec<u8>, CryptoError> {
 // Password-Based Encryption
 // ============================================================================
 
-/// Encrypt data with a password using Argon2id + ChaCha20-Poly1305
+const PASSWORD_MAGIC: &[u8; 4] = b"VXPW";
+const PASSWORD_FORMAT_VERSION: u8 = 2;
+const PASSWORD_HEADER_LEN: usize = 5 + KdfParams::ENCODED_LEN;
+
+/// Encrypt data with a password using Argon2id + ChaCha20-Poly1305, with the
+/// costs from `current_kdf_params`
 pub fn encrypt_with_password(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    encrypt_with_password_params(data, password, current_kdf_params())
+}
+
+/// Encrypt data with a password and explicit Argon2id costs
+///
+/// Output: [magic: 4]["2"][kdf params: 12][salt: 16][nonce: 12][ciphertext]
+/// The header is authenticated as AAD.
+pub fn encrypt_with_password_params(data: &[u8], password: &[u8], params: KdfParams) -> Result<Vec<u8>, CryptoError> {
     let mut rng = OsRng;
 
+    let mut header = Vec::with_capacity(PASSWORD_HEADER_LEN);
+    header.extend_from_slice(PASSWORD_MAGIC);
+    header.push(PASSWORD_FORMAT_VERSION);
+    header.extend_from_slice(&params.to_bytes());
+
     // Generate random salt
     let mut salt = [0u8; 16];
     rng.fill_bytes(&mut salt);
 
     // Derive key using Argon2id
-    let mut key = [0u8; 32];
-    get_argon2()
-        .hash_password_into(password, &salt, &mut key)
-        .map_err(|_| CryptoError::KeyDerivation("argon2 failed".into()))?;
+    let mut key = params.derive(password, &salt)?;
 
     let cipher = ChaCha20Poly1305::new(&key.into());
 
@@ -1416,46 +1637,54 @@ pub fn encrypt_with_password(data: &[u8], password: &[u8]) -> Result<Vec<u8>, Cr
 
     // Encrypt
     let ciphertext = cipher
-        .encrypt(Nonce::from_slice(&nonce), data)
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: data, aad: &header })
         .map_err(|_| CryptoError::Encrypt("encryption failed".into()))?;
 
     // Zeroize key
     key.zeroize();
 
-    // Output: [salt: 16][nonce: 12][ciphertext: var]
-    let mut out = Vec::with_capacity(16 + 12 + ciphertext.len());
+    let mut out = header;
+    out.reserve(16 + 12 + ciphertext.len());
     out.extend_from_slice(&salt);
     out.extend_from_slice(&nonce);
     out.extend_from_slice(&ciphertext);
     Ok(out)
 }
 
-/// Decrypt data with a password
+/// Decrypt data with a password. Data without a KDF header (format 1:
+/// [salt: 16][nonce: 12][ciphertext]) was made with the floor costs.
 pub fn decrypt_with_password(data: &[u8], password: &[u8]) -> Result<Vec<u8>, CryptoError> {
-    if data.len() < 28 {
+    let has_header = data.len() >= PASSWORD_HEADER_LEN
+        && data.starts_with(PASSWORD_MAGIC)
+        && data[4] == PASSWORD_FORMAT_VERSION;
+    let (params, header, body) = if has_header {
+        let (header, body) = data.split_at(PASSWORD_HEADER_LEN);
+        (KdfParams::from_bytes(&header[5..]), header, body)
+    } else {
+        (KdfParams::FLOOR, &[][..], data)
+    };
+
+    if body.len() < 28 {
         return Err(CryptoError::InvalidInput("data too short".into()));
     }
 
-    let salt = &data[..16];
-    let nonce = &data[16..28];
-    let ciphertext = &data[28..];
+    let salt = &body[..16];
+    let nonce = &body[16..28];
+    let ciphertext = &body[28..];
 
     // Derive key
-    let mut key = [0u8; 32];
-    get_argon2()
-        .hash_password_into(password, salt, &mut key)
-        .map_err(|_| CryptoError::KeyDerivation("argon2 failed".into()))?;
+    let mut key = params.derive(password, salt)?;
 
     let cipher = ChaCha20Poly1305::new(&key.into());
 
     let plaintext = cipher
-        .decrypt(Nonce::from_slice(nonce), ciphertext)
-        .map_err(|_| CryptoError::Decrypt("wrong password or corrupted data".into()))?;
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: header })
+        .map_err(|_| CryptoError::Decrypt("wrong password or corrupted data".into()));
 
     // Zeroize key
     key.zeroize();
 
-    Ok(plaintext)
+    plaintext
 }
 
 // ============================================================================
@@ -1497,6 +1726,123 @@ impl SessionKeys {
     }
 }
 
+impl HybridKeypair {
+    /// X25519 agreement between this keypair's static key and `peer`
+    pub(crate) fn x25519_agree(&self, peer: &[u8; 32]) -> zeroize::Zeroizing<[u8; 32]> {
+        let secret = StaticSecret::from(*self.x25519_secret.as_bytes());
+        zeroize::Zeroizing::new(secret.diffie_hellman(&X25519Public::from(*peer)).to_bytes())
+    }
+}
+
+// ============================================================================
+// Album Keys (symmetric, derived from the owner's keypair)
+// ============================================================================
+
+impl HybridKeypair {
+    /// Derive the symmetric content key for an album.
+    ///
+    /// The key is deterministic for a given keypair and album ID, so the owner
+    /// never has to store it. It only grants decryption of album content: it
+    /// cannot sign and reveals nothing about the keypair it came from, which
+    /// makes it safe to hand out through share links.
+    pub fn derive_album_key(&self, album_id: &str) -> Result<SecretKey32, CryptoError> {
+        let mut ikm = Vec::with_capacity(64);
+        ikm.extend_from_slice(self.x25519_secret.as_bytes());
+        ikm.extend_from_slice(self.ed_signing_key.as_bytes());
+
+        let hk = Hkdf::<Sha512>::new(Some(ALBUM_KDF_DOMAIN), &ikm);
+        ikm.zeroize();
+
+        let mut key = [0u8; 32];
+        hk.expand(album_id.as_bytes(), &mut key)
+            .map_err(|_| CryptoError::KeyDerivation("hkdf expand failed".into()))?;
+        Ok(SecretKey32::labeled(ALBUM_KEY_KIND, key))
+    }
+}
+
+/// Encrypt data with a raw 32-byte key using ChaCha20-Poly1305
+/// Output: [nonce: 12][ciphertext: var]
+pub fn encrypt_with_key(data: &[u8], key: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let cipher = ChaCha20Poly1305::new(key.into());
+
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: data, aad })
+        .map_err(|_| CryptoError::Encrypt("AEAD encryption failed".into()))?;
+
+    let mut out = Vec::with_capacity(12 + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt data produced by `encrypt_with_key`
+pub fn decrypt_with_key(data: &[u8], key: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    // nonce(12) + tag(16)
+    if data.len() < 28 {
+        return Err(CryptoError::InvalidInput("data too short".into()));
+    }
+
+    let cipher = ChaCha20Poly1305::new(key.into());
+    cipher
+        .decrypt(
+            Nonce::from_slice(&data[..12]),
+            Payload {
+                msg: &data[12..],
+                aad,
+            },
+        )
+        .map_err(|_| CryptoError::Decrypt("authentication failed".into()))
+}
+
+/// Encrypt data for local storage with a key derived from the machine key
+/// and a fresh salt. Output: [salt: 32][nonce: 12][ciphertext: var]
+pub(crate) fn seal_with_machine_key(data: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+    let mut key = get_machine_key_with_salt(&salt);
+    let sealed = encrypt_with_key(data, &key, aad);
+    key.zeroize();
+
+    let sealed = sealed?;
+    let mut out = Vec::with_capacity(salt.len() + sealed.len());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Decrypt data produced by `seal_with_machine_key`
+pub(crate) fn open_with_machine_key(data: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if data.len() < 32 {
+        return Err(CryptoError::InvalidInput("data too short".into()));
+    }
+    let (salt, sealed) = data.split_at(32);
+    let mut key = get_machine_key_with_salt(salt);
+    let opened = decrypt_with_key(sealed, &key, aad);
+    key.zeroize();
+    opened
+}
+
+/// Run a closure against the keypair behind a handle
+///
+/// Lets other modules use a stored keypair without the key material ever
+/// leaving the store.
+pub(crate) fn with_keypair<T>(
+    handle: KeypairHandle,
+    f: impl FnOnce(&HybridKeypair) -> Result<T, CryptoError>,
+) -> Result<T, CryptoError> {
+    let store = KEYPAIR_STORE
+        .read()
+        .map_err(|_| CryptoError::KeyGeneration("keypair store lock poisoned".into()))?;
+    let keypair_arc = store.get(handle).ok_or(CryptoError::KeypairNotFound)?;
+    let keypair = keypair_arc
+        .lock()
+        .map_err(|_| CryptoError::KeyGeneration("keypair mutex poisoned".into()))?;
+    f(&keypair)
+}
+
 // ============================================================================
 // Utility Functions
 // ============================================================================
@@ -1518,14 +1864,78 @@ pub fn is_pqcrypto_backend() -> bool {
     }
 }
 
+/// Post-quantum backend in use and whether it runs at full speed here
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PqBackendStatus {
+    pub backend: String,
+    /// The pqcrypto backend on a CPU with the SIMD extensions its assembly uses
+    pub optimized: bool,
+    pub cpu_features: Vec<String>,
+    /// A fresh keypair encrypted, decrypted, signed and verified
+    pub self_test_passed: bool,
+    pub self_test_error: Option<String>,
+}
+
+fn simd_features() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut features: Vec<String> = Vec::new();
+    #[cfg(target_arch = "x86_64")]
+    for (name, found) in [
+        ("sse4.1", std::arch::is_x86_feature_detected!("sse4.1")),
+        ("avx2", std::arch::is_x86_feature_detected!("avx2")),
+        ("bmi2", std::arch::is_x86_feature_detected!("bmi2")),
+    ] {
+        if found {
+            features.push(name.to_string());
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        features.push("neon".to_string());
+    }
+    features
+}
+
+fn pq_self_test() -> Result<(), CryptoError> {
+    let keypair = HybridKeypair::generate()?;
+    let probe = b"vortex-image backend self-test";
+    let bundle = keypair.public_bundle();
+    let payload = encrypt(probe, &bundle)?;
+    if decrypt(&payload, &keypair)? != probe {
+        return Err(CryptoError::Decrypt("self-test plaintext mismatch".into()));
+    }
+    let signature = keypair.sign(probe)?;
+    bundle.verify(probe, &signature)
+}
+
+/// Probe the post-quantum backend: which one is compiled in, whether the
+/// CPU lets it use its optimized code, and whether it works at all.
+pub fn check_pqcrypto_backend() -> PqBackendStatus {
+    let cpu_features = simd_features();
+    let simd = cpu_features.iter().any(|f| f == "avx2" || f == "neon");
+    let self_test = pq_self_test();
+    PqBackendStatus {
+        backend: if is_pqcrypto_backend() { "pqcrypto" } else { "pure-rust" }.to_string(),
+        optimized: is_pqcrypto_backend() && simd,
+        cpu_features,
+        self_test_passed: self_test.is_ok(),
+        self_test_error: self_test.err().map(|e| format!("{:?}", e)),
+    }
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
 
 /// Generate a new keypair and return opaque handle + public bundle
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn generate_keypair() -> Result<KeypairInfo, CryptoError> {
-    let keypair = HybridKeypair::generate()?;
+    register_keypair(HybridKeypair::generate()?)
+}
+
+/// Put a keypair in the store and return its handle + public bundle
+pub(crate) fn register_keypair(keypair: HybridKeypair) -> Result<KeypairInfo, CryptoError> {
     let public_bundle = keypair.public_bundle();
     let created_at = keypair.created_at;
     let key_id = public_bundle.key_id.clone();
@@ -1546,6 +1956,7 @@ pub fn generate_keypair() -> Result<KeypairInfo, CryptoError> {
 
 /// Release a keypair handle (removes from memory)
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn release_keypair(handle: KeypairHandle) -> Result<(), CryptoError> {
     KEYPAIR_STORE
         .write()
@@ -1555,13 +1966,25 @@ pub fn release_keypair(handle: KeypairHandle) -> Result<(), CryptoError> {
     Ok(())
 }
 
-/// Rotate a keypair (generate new, keep old for decryption)
-#[tauri::command]
-pub fn rotate_keypair(handle: KeypairHandle) -> Result<PublicBundle, CryptoError> {
+/// Release every keypair handle, e.g. when another profile becomes active
+pub(crate) fn release_all_keypairs() -> Result<(), CryptoError> {
     KEYPAIR_STORE
         .write()
         .map_err(|_| CryptoError::KeyGeneration("keypair store lock poisoned".into()))?
-        .rotate(handle)
+        .clear();
+    Ok(())
+}
+
+/// Swap in a new keypair under an existing handle, keeping the old one for
+/// decryption. The `rotate_keypair` command (see `key_rotation`) wraps this.
+pub(crate) fn install_rotated_keypair(
+    handle: KeypairHandle,
+    new_keypair: HybridKeypair,
+) -> Result<PublicBundle, CryptoError> {
+    KEYPAIR_STORE
+        .write()
+        .map_err(|_| CryptoError::KeyGeneration("keypair store lock poisoned".into()))?
+        .rotate_to(handle, new_keypair)
 }
 
 /// Validate that a keypair handle is still valid in the store
@@ -1569,6 +1992,7 @@ pub fn rotate_keypair(handle: KeypairHandle) -> Result<PublicBundle, CryptoError
 /// Used by frontend to verify stored handles before attempting crypto operations.
 /// Returns true if the handle exists, false otherwise.
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn validate_keypair_handle(handle: KeypairHandle) -> Result<bool, CryptoError> {
     let store = KEYPAIR_STORE
         .read()
@@ -1578,6 +2002,7 @@ pub fn validate_keypair_handle(handle: KeypairHandle) -> Result<bool, CryptoErro
 
 /// Sign data using a keypair handle
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn sign_data(data: Vec<u8>, handle: KeypairHandle) -> Result<Vec<u8>, CryptoError> {
     let store = KEYPAIR_STORE
         .read()
@@ -1591,6 +2016,7 @@ pub fn sign_data(data: Vec<u8>, handle: KeypairHandle) -> Result<Vec<u8>, Crypto
 
 /// Verify a signature using a public bundle
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn verify_signature(
     data: Vec<u8>,
     signature: Vec<u8>,
@@ -1603,18 +2029,134 @@ pub fn verify_signature(
     }
 }
 
-/// Encrypt data for a recipient
+/// Most items `sign_batch` and `verify_batch` accept in one call
+pub const MAX_BATCH_ITEMS: usize = 10_000;
+
+/// Outcome of signing one item of a batch
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchSignResult {
+    pub index: usize,
+    pub signature: Option<Vec<u8>>,
+    pub error: Option<String>,
+}
+
+/// One signature to check; `public_bundle` overrides the batch's bundle
+#[derive(Clone, Debug, Deserialize)]
+pub struct BatchVerifyItem {
+    pub data: Vec<u8>,
+    pub signature: Vec<u8>,
+    #[serde(default)]
+    pub public_bundle: Option<PublicBundle>,
+}
+
+/// Outcome of checking one item of a batch. `error` is set when the item
+/// could not be checked at all, e.g. for lack of a public bundle.
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchVerifyResult {
+    pub index: usize,
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+fn check_batch_len(len: usize) -> Result<(), CryptoError> {
+    if len > MAX_BATCH_ITEMS {
+        return Err(CryptoError::InvalidInput(format!(
+            "batches are limited to {} items",
+            MAX_BATCH_ITEMS
+        )));
+    }
+    Ok(())
+}
+
+/// Sign every item with one keypair, in parallel
+pub fn sign_all(keypair: &HybridKeypair, items: &[Vec<u8>]) -> Vec<BatchSignResult> {
+    use rayon::prelude::*;
+    items
+        .par_iter()
+        .enumerate()
+        .map(|(index, data)| match keypair.sign(data) {
+            Ok(signature) => BatchSignResult {
+                index,
+                signature: Some(signature),
+                error: None,
+            },
+            Err(e) => BatchSignResult {
+                index,
+                signature: None,
+                error: Some(e.to_string()),
+            },
+        })
+        .collect()
+}
+
+/// Check every item against its own bundle or `default_bundle`, in parallel
+pub fn verify_all(items: &[BatchVerifyItem], default_bundle: Option<&PublicBundle>) -> Vec<BatchVerifyResult> {
+    use rayon::prelude::*;
+    items
+        .par_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let Some(bundle) = item.public_bundle.as_ref().or(default_bundle) else {
+                return BatchVerifyResult {
+                    index,
+                    valid: false,
+                    error: Some("no public bundle for this item".into()),
+                };
+            };
+            match bundle.verify(&item.data, &item.signature) {
+                Ok(()) => BatchVerifyResult { index, valid: true, error: None },
+                Err(CryptoError::SignatureInvalid) => BatchVerifyResult { index, valid: false, error: None },
+                Err(e) => BatchVerifyResult {
+                    index,
+                    valid: false,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Sign many items in one call. Results are in input order.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn sign_batch(items: Vec<Vec<u8>>, handle: KeypairHandle) -> Result<Vec<BatchSignResult>, CryptoError> {
+    check_batch_len(items.len())?;
+    tokio::task::spawn_blocking(move || with_keypair(handle, |kp| Ok(sign_all(kp, &items))))
+        .await
+        .map_err(|e| CryptoError::InvalidInput(e.to_string()))?
+}
+
+/// Verify many signatures in one call, against `public_bundle` unless an
+/// item carries its own. Results are in input order.
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn verify_batch(
+    items: Vec<BatchVerifyItem>,
+    public_bundle: Option<PublicBundle>,
+) -> Result<Vec<BatchVerifyResult>, CryptoError> {
+    check_batch_len(items.len())?;
+    tokio::task::spawn_blocking(move || verify_all(&items, public_bundle.as_ref()))
+        .await
+        .map_err(|e| CryptoError::InvalidInput(e.to_string()))
+}
+
+/// Encrypt data for a recipient, given as a public bundle or a contact ID
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn encrypt_hybrid(
     data: Vec<u8>,
-    recipient_bundle: PublicBundle,
+    recipient_bundle: Option<PublicBundle>,
+    contact_id: Option<String>,
     aad: Option<Vec<u8>>,
 ) -> Result<EncryptedPayload, CryptoError> {
-    encrypt_with_aad(&data, &recipient_bundle, aad.as_deref())
+    let recipient = crate::contacts::resolve_recipient(recipient_bundle, contact_id.as_deref())
+        .map_err(|e| CryptoError::InvalidInput(e.to_string()))?;
+    encrypt_with_aad(&data, &recipient, aad.as_deref())
 }
 
 /// Decrypt data using a keypair handle (tries current + rotated keys)
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn decrypt_hybrid(
     encrypted_data: EncryptedPayload,
     handle: KeypairHandle,
@@ -1643,54 +2185,31 @@ pub fn decrypt_hybrid(
     ))
 }
 
-/// Encrypt data with password
+/// Encrypt data with password, refusing passwords that are too weak
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn encrypt_data_password(data: Vec<u8>, password: String) -> Result<Vec<u8>, CryptoError> {
+    crate::password::ensure_strong(&password)?;
     encrypt_with_password(&data, password.as_bytes())
 }
 
 /// Decrypt data with password
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn decrypt_data_password(data: Vec<u8>, password: String) -> Result<Vec<u8>, CryptoError> {
     decrypt_with_password(&data, password.as_bytes())
 }
 
 /// Store a token securely (tries keychain first, falls back to machine-key)
 /// 
-/// Storage priority:
+/// Storage priority (see `keystore::Keystore::detect`):
 /// 1. OS Keychain (most secure - uses platform-specific secure storage)
 /// 2. Encrypted file with machine-key (fallback - less secure on weak systems)
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn secure_store_token(key: String, value: String) -> Result<(), CryptoError> {
-    // Try keychain first (preferred)
-    if keychain_available() {
-        match keychain_store(&key, value.as_bytes()) {
-            Ok(()) => {
-                log::info!("Token '{}' stored in OS keychain", key);
-                return Ok(());
-            }
-            Err(e) => {
-                log::warn!("Keychain storage failed for '{}', falling back to file: {}", key, e);
-            }
-        }
-    } else {
-        log::info!("OS keychain not available, using encrypted file storage for '{}'", key);
-    }
-
-    // Fall back to encrypted file storage
-    let encrypted = encrypt_token(&value)?;
-    let path = dirs::data_local_dir()
-        .ok_or_else(|| CryptoError::Keychain("no local data dir".into()))?
-        .join("vortex-image")
-        .join("tokens");
-
-    std::fs::create_dir_all(&path)
-        .map_err(|e| CryptoError::Keychain(format!("failed to create dir: {}", e)))?;
-
-    std::fs::write(path.join(&key), &encrypted)
-        .map_err(|e| CryptoError::Keychain(format!("failed to write: {}", e)))?;
-
-    log::info!("Token '{}' stored in encrypted file (keychain unavailable)", key);
+    let value = zeroize::Zeroizing::new(value);
+    Keystore::detect()?.store(&key, &value)?;
     Ok(())
 }
 
@@ -1702,74 +2221,21 @@ pub fn secure_store_token(key: String, value: String) -> Result<(), CryptoError>
 /// 
 /// Automatically migrates legacy token formats (v2, v3) to v4
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn secure_retrieve_token(key: String) -> Result<String, CryptoError> {
-    // Try keychain first
-    if keychain_available() {
-        match keychain_retrieve(&key) {
-            Ok(data) => {
-                return String::from_utf8(data)
-                    .map_err(|_| CryptoError::Keychain("invalid utf8".into()));
-            }
-            Err(e) => {
-                log::debug!("Keychain retrieval failed for '{}', trying file: {}", key, e);
-            }
-        }
-    }
-
-    // Fall back to encrypted file storage
-    let path = dirs::data_local_dir()
-        .ok_or_else(|| CryptoError::Keychain("no local data dir".into()))?
-        .join("vortex-image")
-        .join("tokens")
-        .join(&key);
-
-    let encrypted = std::fs::read(&path)
-        .map_err(|e| CryptoError::Keychain(format!("failed to read: {}", e)))?;
-
-    let (plaintext, upgraded) = decrypt_token(&encrypted)?;
-
-    // If token was upgraded from legacy format, save the new version
-    if let Some(new_token) = upgraded {
-        if let Err(e) = std::fs::write(&path, &new_token) {
-            log::warn!("Failed to save upgraded token: {}", e);
-        } else {
-            log::info!("Token '{}' migrated to v4 format", key);
-        }
-    }
-
-    Ok(plaintext)
+    Keystore::detect()?
+        .retrieve(&key)?
+        .map(|value| value.to_string())
+        .ok_or_else(|| CryptoError::Keychain(format!("token '{}' not found", key)))
 }
 
 /// Delete a token from secure storage
 /// 
 /// Removes from both keychain and file storage to ensure complete cleanup
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn secure_delete_token(key: String) -> Result<(), CryptoError> {
-    let mut deleted = false;
-    
-    // Try to delete from keychain
-    if keychain_available() {
-        if keychain_delete(&key).is_ok() {
-            deleted = true;
-            log::debug!("Deleted '{}' from keychain", key);
-        }
-    }
-    
-    // Also try to delete from file storage
-    let path = dirs::data_local_dir()
-        .ok_or_else(|| CryptoError::Keychain("no local data dir".into()))?
-        .join("vortex-image")
-        .join("tokens")
-        .join(&key);
-    
-    if path.exists() {
-        std::fs::remove_file(&path)
-            .map_err(|e| CryptoError::Keychain(format!("failed to delete file: {}", e)))?;
-        deleted = true;
-        log::debug!("Deleted '{}' from file storage", key);
-    }
-    
-    if deleted {
+    if Keystore::detect()?.delete(&key)? {
         Ok(())
     } else {
         Err(CryptoError::Keychain(format!("token '{}' not found", key)))
@@ -1778,12 +2244,14 @@ pub fn secure_delete_token(key: String) -> Result<(), CryptoError> {
 
 /// Hash data using BLAKE3
 #[tauri::command]
+#[tracing::instrument(skip_all)]
 pub fn hash_data_blake3(data: Vec<u8>) -> Vec<u8> {
     hash_data(&data).to_vec()
 }
 
 /// Get crypto module info
 #[tauri::command]
+#[tracing::instrument(skip_all)]
 pub fn get_crypto_info() -> serde_json::Value {
     let keychain_status = if keychain_available() {
         "available"
@@ -1808,6 +2276,7 @@ pub fn get_crypto_info() -> serde_json::Value {
             "Automatic token migration (v2→v3→v4)",
             "AAD in AEAD encryption",
             "Zeroizing secret types",
+            "Memory-locked secret keys",
             "Safe Dilithium signing (no unsafe transmute in pqcrypto backend)"
         ],
         "backend": if is_pqcrypto_backend() { "pqcrypto (optimized assembly)" } else { "pure-rust (iOS compatible)" },
@@ -1827,6 +2296,8 @@ pub enum EncryptionMethod {
     None,
     Password,
     HybridPQ,
+    /// Symmetric album key (see `HybridKeypair::derive_album_key`)
+    AlbumKey,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -1845,102 +2316,6 @@ pub struct EncryptedFileData {
     pub metadata: Option<serde_json::Value>,
 }
 
-#[tauri::command]
-pub fn encrypt_file(
-    data: Vec<u8>,
-    settings: EncryptionSettings,
-    password: Option<String>,
-    _handle: Option<KeypairHandle>, // Reserved for future use (e.g., signing)
-) -> Result<EncryptedFileData, CryptoError> {
-    if !settings.enabled {
-        return Ok(EncryptedFileData {
-            data,
-            encrypted: false,
-            method: EncryptionMethod::None,
-            metadata: None,
-        });
-    }
-
-    if settings.use_password {
-        let pwd = password.ok_or_else(|| CryptoError::InvalidInput("password required".into()))?;
-        let encrypted = encrypt_with_password(&data, pwd.as_bytes())?;
-        return Ok(EncryptedFileData {
-            data: encrypted,
-            encrypted: true,
-            method: EncryptionMethod::Password,
-            metadata: None,
-        });
-    }
-
-    if settings.use_keypair {
-        let recipient = settings
-            .recipient_bundle
-            .ok_or_else(|| CryptoError::InvalidInput("recipient bundle required".into()))?;
-        let payload = encrypt(&data, &recipient)?;
-        let serialized = serde_json::to_vec(&payload)
-            .map_err(|e| CryptoError::Encrypt(format!("serialization failed: {}", e)))?;
-        return Ok(EncryptedFileData {
-            data: serialized,
-            encrypted: true,
-            method: EncryptionMethod::HybridPQ,
-            metadata: None,
-        });
-    }
-
-    Ok(EncryptedFileData {
-        data,
-        encrypted: false,
-        method: EncryptionMethod::None,
-        metadata: None,
-    })
-}
-
-#[tauri::command]
-pub fn decrypt_file(
-    encrypted: EncryptedFileData,
-    password: Option<String>,
-    handle: Option<KeypairHandle>,
-) -> Result<Vec<u8>, CryptoError> {
-    if !encrypted.encrypted {
-        return Ok(encrypted.data);
-    }
-
-    match encrypted.method {
-        EncryptionMethod::None => Ok(encrypted.data),
-        EncryptionMethod::Password => {
-            let pwd =
-                password.ok_or_else(|| CryptoError::InvalidInput("password required".into()))?;
-            decrypt_with_password(&encrypted.data, pwd.as_bytes())
-        }
-        EncryptionMethod::HybridPQ => {
-            let h = handle.ok_or_else(|| CryptoError::InvalidInput("keypair handle required".into()))?;
-            let payload: EncryptedPayload = serde_json::from_slice(&encrypted.data)
-                .map_err(|e| CryptoError::Decrypt(format!("deserialization failed: {}", e)))?;
-
-            let store = KEYPAIR_STORE
-                .read()
-                .map_err(|_| CryptoError::Decrypt("keypair store lock poisoned".into()))?;
-            let keypairs = store.get_all_for_decryption(h);
-
-            if keypairs.is_empty() {
-                return Err(CryptoError::KeypairNotFound);
-            }
-
-            for keypair_arc in keypairs {
-                if let Ok(keypair) = keypair_arc.lock() {
-                    if let Ok(plaintext) = decrypt(&payload, &keypair) {
-                        return Ok(plaintext);
-                    }
-                }
-            }
-
-            Err(CryptoError::Decrypt(
-                "decryption failed with all available keys".into(),
-            ))
-        }
-    }
-}
-
 
 // ============================================================================
 // Legacy Compatibility Functions (for github.rs)
@@ -1956,12 +2331,11 @@ pub fn decrypt_with_keypair_bytes(
     decrypt(payload, &keypair)
 }
 
-/// Serialize keypair to bytes (for storage)
+/// Serialize keypair to bytes (for storage), zeroized and memory-locked
 /// Used by decrypt_with_keypair_bytes for legacy compatibility
 impl HybridKeypair {
-    #[allow(dead_code)]
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut out = Vec::new();
+    pub fn to_bytes(&self) -> SecretBytes {
+        let mut out = Vec::with_capacity(self.serialized_len());
         // PQ encapsulation key
         out.extend_from_slice(&(self.pq_encap_key.len() as u32).to_le_bytes());
         out.extend_from_slice(&self.pq_encap_key);
@@ -1983,7 +2357,19 @@ impl HybridKeypair {
         // Metadata
         out.extend_from_slice(&self.created_at.to_le_bytes());
         out.extend_from_slice(&self.rotation_count.to_le_bytes());
-        out
+        SecretBytes::labeled(SERIALIZED_KEYPAIR_KIND, out)
+    }
+
+    /// Exact length of `to_bytes`, so its buffer never reallocates and leaves
+    /// an unwiped copy of the secret keys behind
+    fn serialized_len(&self) -> usize {
+        4 + self.pq_encap_key.len()
+            + 4 + self.pq_decap_key.len()
+            + 64
+            + 4 + self.pq_signing_key.len()
+            + 4 + self.pq_verifying_key.len()
+            + 64
+            + 8 + 4
     }
 
     pub fn from_bytes(data: &[u8]) -> Result<Self, CryptoError> {
@@ -2010,7 +2396,7 @@ impl HybridKeypair {
         if data.len() < offset + pq_decap_len {
             return Err(CryptoError::InvalidInput("data too short".into()));
         }
-        let pq_decap_key = SecretBytes::new(data[offset..offset + pq_decap_len].to_vec());
+        let pq_decap_key = SecretBytes::labeled(KEM_SECRET_KIND, data[offset..offset + pq_decap_len].to_vec());
         offset += pq_decap_len;
 
         // X25519 keys
@@ -2035,7 +2421,7 @@ impl HybridKeypair {
         if data.len() < offset + pq_sign_len {
             return Err(CryptoError::InvalidInput("data too short".into()));
         }
-        let pq_signing_key = SecretBytes::new(data[offset..offset + pq_sign_len].to_vec());
+        let pq_signing_key = SecretBytes::labeled(PQ_SIGNING_KIND, data[offset..offset + pq_sign_len].to_vec());
         offset += pq_sign_len;
 
         // PQ verifying key
@@ -2076,11 +2462,11 @@ impl HybridKeypair {
         Ok(Self {
             pq_encap_key,
             pq_decap_key,
-            x25519_secret: SecretKey32::new(x25519_secret),
+            x25519_secret: SecretKey32::labeled(X25519_SECRET_KIND, x25519_secret),
             x25519_public,
             pq_signing_key,
             pq_verifying_key,
-            ed_signing_key: SecretKey32::new(ed_signing_key),
+            ed_signing_key: SecretKey32::labeled(ED25519_SIGNING_KIND, ed_signing_key),
             ed_verifying_key,
             created_at,
             rotation_count,
@@ -2088,3 +2474,80 @@ impl HybridKeypair {
     }
 }
 
+
+/// Keypairs whose classical half is derived from a mnemonic seed (see `recovery`)
+impl HybridKeypair {
+    /// Generate a keypair with the given X25519 and Ed25519 secrets and fresh
+    /// post-quantum keys
+    pub(crate) fn generate_with_classical(x25519_secret: [u8; 32], ed_secret: [u8; 32]) -> Result<Self, CryptoError> {
+        let mut keypair = Self::generate()?;
+        keypair.set_classical(x25519_secret, ed_secret);
+        Ok(keypair)
+    }
+
+    /// Rebuild a keypair from classical secrets and the output of `pq_key_bytes`
+    pub(crate) fn from_classical_and_pq(
+        x25519_secret: [u8; 32],
+        ed_secret: [u8; 32],
+        pq_keys: &[u8],
+        created_at: u64,
+    ) -> Result<Self, CryptoError> {
+        let mut offset = 0;
+        let mut field = || -> Result<Vec<u8>, CryptoError> {
+            let len_bytes = pq_keys
+                .get(offset..offset + 4)
+                .ok_or_else(|| CryptoError::InvalidInput("data too short".into()))?;
+            let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+            let value = pq_keys
+                .get(offset + 4..offset + 4 + len)
+                .ok_or_else(|| CryptoError::InvalidInput("data too short".into()))?;
+            offset += 4 + len;
+            Ok(value.to_vec())
+        };
+        let pq_encap_key = field()?;
+        let pq_decap_key = SecretBytes::labeled(KEM_SECRET_KIND, field()?);
+        let pq_verifying_key = field()?;
+        let pq_signing_key = SecretBytes::labeled(PQ_SIGNING_KIND, field()?);
+
+        let mut keypair = Self {
+            pq_encap_key,
+            pq_decap_key,
+            x25519_secret: SecretKey32::labeled(X25519_SECRET_KIND, [0u8; 32]),
+            x25519_public: [0u8; 32],
+            pq_signing_key,
+            pq_verifying_key,
+            ed_signing_key: SecretKey32::labeled(ED25519_SIGNING_KIND, [0u8; 32]),
+            ed_verifying_key: [0u8; 32],
+            created_at,
+            rotation_count: 0,
+        };
+        keypair.set_classical(x25519_secret, ed_secret);
+        Ok(keypair)
+    }
+
+    /// Post-quantum keys only: length-prefixed encap, decap, verify and sign keys
+    pub(crate) fn pq_key_bytes(&self) -> zeroize::Zeroizing<Vec<u8>> {
+        let mut out = zeroize::Zeroizing::new(Vec::new());
+        for field in [
+            self.pq_encap_key.as_slice(),
+            self.pq_decap_key.as_slice(),
+            self.pq_verifying_key.as_slice(),
+            self.pq_signing_key.as_slice(),
+        ] {
+            out.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            out.extend_from_slice(field);
+        }
+        out
+    }
+
+    fn set_classical(&mut self, mut x25519_secret: [u8; 32], mut ed_secret: [u8; 32]) {
+        let x_secret = StaticSecret::from(x25519_secret);
+        let ed_sign_key = SigningKey::from_bytes(&ed_secret);
+        self.x25519_public = X25519Public::from(&x_secret).to_bytes();
+        self.x25519_secret = SecretKey32::labeled(X25519_SECRET_KIND, x25519_secret);
+        self.ed_verifying_key = ed_sign_key.verifying_key().to_bytes();
+        self.ed_signing_key = SecretKey32::labeled(ED25519_SIGNING_KIND, ed_secret);
+        x25519_secret.zeroize();
+        ed_secret.zeroize();
+    }
+}
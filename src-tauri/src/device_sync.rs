@@ -0,0 +1,229 @@
+//! Multi-Device Manifest Sync
+//!
+//! The same account can run Vortex on several machines. Each installation
+//! has a random device id, and every album manifest carries a vector clock
+//! with one counter per device that wrote it. `save_manifest` ticks this
+//! device's counter on each write.
+//!
+//! When a write is rejected because the manifest changed remotely (stale
+//! SHA), the remote version is merged instead of overwritten. The merge is
+//! three-way against the manifest as it was loaded:
+//!
+//! - map entries (photos, hashes, tags, metadata, grants, ...) are merged per
+//!   key, so photos added on two devices both survive, and a removal on one
+//!   device sticks unless the other device changed the same entry,
+//! - a field or entry changed on both devices goes to the later writer by
+//!   vector clock; for concurrent writes, to the device with more writes and
+//!   then the greater device id, so every device settles on the same winner,
+//! - byte totals add up both devices' changes.
+//!
+//! Sealed organization data of encrypted albums is opaque here and follows
+//! the same winner rule. Manifests whose album key was rotated on one side
+//! are not merged; the save fails with the conflict so it can be redone on
+//! the current key.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::album::AlbumManifest;
+use crate::github::{AppError, GithubError};
+
+const DEVICE_ID_FILE: &str = "device-id";
+const DEVICE_ID_LEN: usize = 16;
+
+lazy_static::lazy_static! {
+    static ref DEVICE_ID: Mutex<Option<String>> = Mutex::new(None);
+}
+
+// ============================================================================
+// Vector Clock
+// ============================================================================
+
+/// Write counters per device id. Causality between two manifest versions
+/// follows from comparing them (Lamport 1978, Fidge/Mattern 1988).
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct VectorClock(pub BTreeMap<String, u64>);
+
+impl VectorClock {
+    pub fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    /// Count a write by `device`, returning its new counter
+    pub fn tick(&mut self, device: &str) -> u64 {
+        let counter = self.0.entry(device.to_string()).or_insert(0);
+        *counter += 1;
+        *counter
+    }
+
+    pub fn get(&self, device: &str) -> u64 {
+        self.0.get(device).copied().unwrap_or(0)
+    }
+
+    /// Take the larger counter of each device
+    pub fn merge(&mut self, other: &Self) {
+        for (device, &counter) in &other.0 {
+            let entry = self.0.entry(device.clone()).or_insert(0);
+            *entry = (*entry).max(counter);
+        }
+    }
+
+    /// True when every counter is at most the other's and one is smaller
+    pub fn happens_before(&self, other: &Self) -> bool {
+        self.0.iter().all(|(device, &counter)| counter <= other.get(device))
+            && other.0.iter().any(|(device, &counter)| counter > self.get(device))
+    }
+
+    /// Neither happened before the other, e.g. edits on two devices since
+    /// the same version
+    pub fn is_concurrent(&self, other: &Self) -> bool {
+        self != other && !self.happens_before(other) && !other.happens_before(self)
+    }
+
+    /// Writes counted over all devices
+    pub fn total(&self) -> u64 {
+        self.0.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// ============================================================================
+// Device Id
+// ============================================================================
+
+fn valid_device_id(id: &str) -> bool {
+    id.len() == DEVICE_ID_LEN && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Device id stored in `dir`, created on first use
+pub fn device_id_in(dir: &Path) -> Result<String, AppError> {
+    let path = dir.join(DEVICE_ID_FILE);
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        let existing = existing.trim();
+        if valid_device_id(existing) {
+            return Ok(existing.to_string());
+        }
+    }
+    let id = hex::encode(rand::random::<[u8; DEVICE_ID_LEN / 2]>());
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&path, &id)?;
+    Ok(id)
+}
+
+/// Id of this installation, shared by all of its profiles
+pub(crate) fn device_id() -> Result<String, AppError> {
+    let mut guard = DEVICE_ID.lock().unwrap();
+    if let Some(id) = guard.as_ref() {
+        return Ok(id.clone());
+    }
+    let id = device_id_in(&crate::profiles::root_dir()?)?;
+    *guard = Some(id.clone());
+    Ok(id)
+}
+
+// ============================================================================
+// Manifest Merge
+// ============================================================================
+
+/// Whether `ours` wins fields both sides changed: the later write by vector
+/// clock, and for concurrent writes the one with more writes, then the
+/// greater device id. Both devices reach the same answer.
+pub fn ours_wins(ours: &AlbumManifest, theirs: &AlbumManifest) -> bool {
+    if theirs.clock.happens_before(&ours.clock) {
+        return true;
+    }
+    if ours.clock.happens_before(&theirs.clock) {
+        return false;
+    }
+    (ours.clock.total(), &ours.device) >= (theirs.clock.total(), &theirs.device)
+}
+
+fn merge_value<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T, ours_wins: bool) -> T {
+    if ours == theirs || theirs == base {
+        ours.clone()
+    } else if ours == base || !ours_wins {
+        theirs.clone()
+    } else {
+        ours.clone()
+    }
+}
+
+fn merge_map<V: PartialEq + Clone>(
+    base: &BTreeMap<String, V>,
+    ours: &BTreeMap<String, V>,
+    theirs: &BTreeMap<String, V>,
+    ours_wins: bool,
+) -> BTreeMap<String, V> {
+    let keys: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            merge_value(&base.get(key), &ours.get(key), &theirs.get(key), ours_wins).map(|v| (key.clone(), v.clone()))
+        })
+        .collect()
+}
+
+/// `theirs` plus the change from `base` to `ours`
+fn merge_total(base: u64, ours: u64, theirs: u64) -> u64 {
+    (theirs as i128 + ours as i128 - base as i128).clamp(0, u64::MAX as i128) as u64
+}
+
+/// Merge our edit of `base` with the version another device wrote since.
+/// The result carries both clocks merged and no signature; `save_manifest`
+/// ticks and signs it before writing.
+pub fn merge_manifests(
+    base: &AlbumManifest,
+    ours: &AlbumManifest,
+    theirs: &AlbumManifest,
+) -> Result<AlbumManifest, AppError> {
+    if ours.key_epoch != theirs.key_epoch || ours.encrypted != theirs.encrypted {
+        return Err(GithubError::Conflict {
+            message: "The album key changed on another device; reload the album and try again".into(),
+        }
+        .into());
+    }
+    let wins = ours_wins(ours, theirs);
+
+    let mut merged = ours.clone();
+    merged.version = ours.version.max(theirs.version);
+    merged.owner_key_id = merge_value(&base.owner_key_id, &ours.owner_key_id, &theirs.owner_key_id, wins);
+    merged.created_at = merge_value(&base.created_at, &ours.created_at, &theirs.created_at, wins);
+    merged.entries = merge_map(&base.entries, &ours.entries, &theirs.entries, wins);
+    merged.original_bytes = merge_total(base.original_bytes, ours.original_bytes, theirs.original_bytes);
+    merged.stored_bytes = merge_total(base.stored_bytes, ours.stored_bytes, theirs.stored_bytes);
+    merged.cover = merge_value(&base.cover, &ours.cover, &theirs.cover, wins);
+    merged.description = merge_value(&base.description, &ours.description, &theirs.description, wins);
+    merged.metadata = merge_map(&base.metadata, &ours.metadata, &theirs.metadata, wins);
+    merged.access = merge_map(&base.access, &ours.access, &theirs.access, wins);
+    merged.owner_key = merge_value(&base.owner_key, &ours.owner_key, &theirs.owner_key, wins);
+    merged.media = merge_map(&base.media, &ours.media, &theirs.media, wins);
+    merged.organization = merge_map(&base.organization, &ours.organization, &theirs.organization, wins);
+    merged.sealed_organization = merge_value(
+        &base.sealed_organization,
+        &ours.sealed_organization,
+        &theirs.sealed_organization,
+        wins,
+    );
+    merged.ipfs = merge_map(&base.ipfs, &ours.ipfs, &theirs.ipfs, wins);
+    merged.hashes = merge_map(&base.hashes, &ours.hashes, &theirs.hashes, wins);
+    merged.signed = merge_map(&base.signed, &ours.signed, &theirs.signed, wins);
+    merged.clock.merge(&theirs.clock);
+    merged.signature = None;
+    Ok(merged)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Id this installation records in the vector clocks of manifests it writes
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_device_id() -> Result<String, AppError> {
+    device_id()
+}
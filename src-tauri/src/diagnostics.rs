@@ -0,0 +1,311 @@
+//! Tracing and Diagnostics
+//!
+//! Logging goes through `tracing`. `init_tracing` installs a subscriber
+//! that writes to stderr and to daily log files under
+//! `<local data>/vortex-image/logs`, keeping the last `MAX_LOG_FILES` days.
+//! Records of crates still on `log` (Tauri and its plugins) are forwarded.
+//! `VORTEX_LOG` takes an `EnvFilter` directive, `info` by default.
+//!
+//! Every command runs in a span named after it, with no fields so tokens and
+//! passwords never reach a log; failed commands log their error on the span.
+//!
+//! `export_diagnostics_bundle` zips what a bug report needs:
+//!
+//! - `logs/` - the tail of the most recent log files,
+//! - `system.json` - app version, platform, keystore backend and schema version,
+//! - `health.json` - circuit breaker states and the activity log check.
+//!
+//! Everything goes through `redact_secrets` first, which masks GitHub tokens,
+//! `Authorization` values, `password=`-style fields and the home directory.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use zip::write::SimpleFileOptions;
+
+use crate::github::AppError;
+use crate::keystore::{Keystore, KeystoreInfo};
+use crate::local_store::with_store;
+use crate::migrations::get_db_schema_version;
+use crate::retry::{get_backend_status, BackendStatus};
+use crate::security_verify::{check_activity_log_in, ActivityLogCheck};
+use crate::util::now_secs;
+
+pub const LOG_FILE_PREFIX: &str = "vortex";
+const LOG_FILTER_ENV: &str = "VORTEX_LOG";
+const DEFAULT_FILTER: &str = "info";
+const MAX_LOG_FILES: usize = 7;
+/// Log files included in a bundle
+const MAX_BUNDLED_LOGS: usize = 3;
+/// Bytes kept from the end of each bundled log file
+const MAX_LOG_BYTES: usize = 2 * 1024 * 1024;
+pub const REDACTED: &str = "[REDACTED]";
+
+/// GitHub token formats
+const TOKEN_PREFIXES: &[&str] = &["ghp_", "gho_", "ghu_", "ghs_", "ghr_", "github_pat_"];
+/// Schemes whose credentials follow them in an `Authorization` value
+const AUTH_SCHEMES: &[&str] = &["bearer ", "basic "];
+/// Field names whose values are masked, also as suffixes (`access_token`)
+const SECRET_FIELDS: &[&str] = &["token", "password", "passphrase", "secret", "authorization", "mnemonic"];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub cpus: usize,
+    pub keystore: Option<KeystoreInfo>,
+    pub schema_version: Option<u32>,
+    pub generated_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackendHealth {
+    pub backends: Vec<BackendStatus>,
+    pub activity_log: Option<ActivityLogCheck>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DiagnosticsBundle {
+    pub path: String,
+    pub log_files: usize,
+    pub bytes: u64,
+}
+
+fn log_dir() -> Result<PathBuf, AppError> {
+    let dir = dirs::data_local_dir()
+        .ok_or_else(|| AppError::Validation("No local data directory".into()))?
+        .join("vortex-image")
+        .join("logs");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Install the global subscriber. Logging to files is skipped, with a note
+/// on stderr, when the log directory cannot be used.
+pub fn init_tracing() {
+    let filter = EnvFilter::try_from_env(LOG_FILTER_ENV).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let appender = log_dir().map_err(|e| e.to_string()).and_then(|dir| {
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .map_err(|e| e.to_string())
+    });
+    let file_layer = match appender {
+        Ok(appender) => Some(fmt::layer().with_ansi(false).with_writer(appender)),
+        Err(e) => {
+            eprintln!("Not writing log files: {}", e);
+            None
+        }
+    };
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Tracing was already initialized: {}", e);
+    }
+}
+
+// ============================================================================
+// Redaction
+// ============================================================================
+
+fn is_word(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn is_value(b: u8) -> bool {
+    !b.is_ascii_whitespace() && !matches!(b, b'"' | b'\'' | b',' | b'&' | b';' | b'}' | b')')
+}
+
+fn scan(bytes: &[u8], mut i: usize, keep: impl Fn(u8) -> bool) -> usize {
+    while i < bytes.len() && keep(bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// Byte ranges of `line` holding secrets. Ranges start and end next to
+/// ASCII bytes, so they fall on character boundaries.
+fn secret_ranges(line: &str) -> Vec<(usize, usize)> {
+    let lower = line.to_ascii_lowercase();
+    let bytes = line.as_bytes();
+    let mut ranges = Vec::new();
+
+    for prefix in TOKEN_PREFIXES {
+        for (start, _) in lower.match_indices(prefix) {
+            if start > 0 && is_word(bytes[start - 1]) {
+                continue;
+            }
+            let end = scan(bytes, start + prefix.len(), is_word);
+            if end > start + prefix.len() {
+                ranges.push((start, end));
+            }
+        }
+    }
+
+    for scheme in AUTH_SCHEMES {
+        for (at, _) in lower.match_indices(scheme) {
+            if at > 0 && is_word(bytes[at - 1]) {
+                continue;
+            }
+            let start = scan(bytes, at + scheme.len(), |b| b == b' ');
+            let end = scan(bytes, start, is_value);
+            if end > start {
+                ranges.push((start, end));
+            }
+        }
+    }
+
+    // `field=value`, `field: value` and `"field": "value"`
+    for field in SECRET_FIELDS {
+        for (at, _) in lower.match_indices(field) {
+            let mut i = scan(bytes, at + field.len(), |b| b == b'"' || b == b'\'');
+            i = scan(bytes, i, |b| b == b' ');
+            if i >= bytes.len() || !matches!(bytes[i], b'=' | b':') {
+                continue;
+            }
+            i = scan(bytes, i + 1, |b| b == b' ');
+            let start = scan(bytes, i, |b| b == b'"' || b == b'\'');
+            let end = scan(bytes, start, is_value);
+            if end > start {
+                ranges.push((start, end));
+            }
+        }
+    }
+
+    ranges.sort_unstable();
+    ranges
+}
+
+/// Mask secrets in log text, and the home directory `home` if given
+pub fn redact_secrets(text: &str, home: Option<&str>) -> String {
+    let text = match home.filter(|h| h.len() > 1) {
+        Some(home) => text.replace(home, "~"),
+        None => text.to_string(),
+    };
+
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let mut last = 0;
+        for (start, end) in secret_ranges(line) {
+            if end <= last {
+                continue;
+            }
+            if start >= last {
+                out.push_str(&line[last..start]);
+                out.push_str(REDACTED);
+            }
+            last = end;
+        }
+        out.push_str(&line[last..]);
+    }
+    out
+}
+
+// ============================================================================
+// Bundle
+// ============================================================================
+
+/// The last `max_bytes` of `content`, starting at a line
+pub fn log_tail(content: &[u8], max_bytes: usize) -> String {
+    if content.len() <= max_bytes {
+        return String::from_utf8_lossy(content).into_owned();
+    }
+    let tail = &content[content.len() - max_bytes..];
+    let start = tail.iter().position(|&b| b == b'\n').map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&tail[start..]).into_owned()
+}
+
+/// The newest `MAX_BUNDLED_LOGS` log files in `dir`, oldest first.
+/// Rotated files are named by date, so names sort by age.
+pub fn recent_log_files(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(LOG_FILE_PREFIX)))
+        .collect();
+    files.sort();
+    let skip = files.len().saturating_sub(MAX_BUNDLED_LOGS);
+    Ok(files.split_off(skip))
+}
+
+fn system_info() -> SystemInfo {
+    SystemInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        cpus: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        keystore: Keystore::detect().ok().map(|k| k.info()),
+        schema_version: get_db_schema_version().ok().map(|v| v.version),
+        generated_at: now_secs(),
+    }
+}
+
+fn backend_health() -> BackendHealth {
+    BackendHealth {
+        backends: get_backend_status(),
+        activity_log: with_store(check_activity_log_in).ok(),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string_pretty(value).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))
+}
+
+fn write_bundle(dest: &Path) -> Result<DiagnosticsBundle, AppError> {
+    let home = dirs::home_dir().map(|h| h.to_string_lossy().into_owned());
+    let redact = |text: &str| redact_secrets(text, home.as_deref());
+    let zip_error = |e: zip::result::ZipError| AppError::Validation(format!("Could not write bundle: {}", e));
+
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(dest)?);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let logs = match log_dir() {
+        Ok(dir) => recent_log_files(&dir)?,
+        Err(_) => Vec::new(),
+    };
+    for path in &logs {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or(LOG_FILE_PREFIX);
+        zip.start_file(format!("logs/{}", name), options).map_err(zip_error)?;
+        zip.write_all(redact(&log_tail(&std::fs::read(path)?, MAX_LOG_BYTES)).as_bytes())?;
+    }
+
+    zip.start_file("system.json", options).map_err(zip_error)?;
+    zip.write_all(redact(&to_json(&system_info())?).as_bytes())?;
+    zip.start_file("health.json", options).map_err(zip_error)?;
+    zip.write_all(redact(&to_json(&backend_health())?).as_bytes())?;
+    zip.finish().map_err(zip_error)?;
+
+    Ok(DiagnosticsBundle {
+        path: dest.to_string_lossy().into_owned(),
+        log_files: logs.len(),
+        bytes: std::fs::metadata(dest)?.len(),
+    })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Write a zip of recent logs, system info and backend health to `dest_path`
+/// for attaching to a bug report. Secrets are redacted.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn export_diagnostics_bundle(dest_path: String) -> Result<DiagnosticsBundle, AppError> {
+    let dest = PathBuf::from(dest_path);
+    if dest.extension().and_then(|e| e.to_str()) != Some("zip") {
+        return Err(AppError::Validation("Diagnostics bundles are .zip files".into()));
+    }
+    tauri::async_runtime::spawn_blocking(move || write_bundle(&dest))
+        .await
+        .map_err(|e| AppError::Validation(format!("Export task failed: {}", e)))?
+}
@@ -0,0 +1,488 @@
+//! Offline Download Cache
+//!
+//! Photos opened with `download_photo` are kept under
+//! `<profile data>/offline/` so they open again without asking GitHub,
+//! offline included. Blobs are kept as they are in the repo, so photos of
+//! encrypted albums stay encrypted to their album key, and are sealed with
+//! the local store's key (see `local_store`) on top. Files are named by a
+//! keyed hash of their content: paths with the same content share a file,
+//! and the names give nothing away.
+//!
+//! The index lives in the store's `download_cache` table: per path, the
+//! blob SHA, the integrity result of the download and, for encrypted
+//! albums, the manifest needed to open the photo. An entry is only served
+//! while the catalog (see `catalog`) lists the same blob SHA for its path,
+//! and an entry downloaded without verification is not served to a
+//! verifying caller.
+//!
+//! Unpinned photos take up at most the configured limit
+//! (`DEFAULT_LIMIT_BYTES` unless set); the least recently opened go first.
+//! Photos pinned with `pin_photo_offline` are never evicted.
+
+use reqwest::Client;
+use rusqlite::{params, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::album::AlbumManifest;
+use crate::catalog::{photo_by_id, photo_id};
+use crate::crypto::{hash_data, KeypairHandle};
+use crate::github::{fetch_photo, validate_repo, AppError, DownloadIntegrity, HttpClient};
+use crate::local_store::{db_error, with_store, LocalStore, SETTINGS_NS};
+
+const CACHE_DIR: &str = "offline";
+const BLOB_EXT: &str = "blob";
+/// Setting holding the size limit in bytes
+pub const LIMIT_SETTING: &str = "download_cache_limit";
+pub const DEFAULT_LIMIT_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Migration 4 (see `migrations`)
+pub const DOWNLOAD_CACHE_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS download_cache (
+    id BLOB PRIMARY KEY,
+    record BLOB NOT NULL
+);
+"#;
+
+/// Served and missed lookups since start
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// A photo's blob as stored in the repo, with what opening it needs
+#[derive(Clone, Debug)]
+pub struct CachedPhoto {
+    /// Blob SHA in the repo
+    pub sha: String,
+    pub content: Vec<u8>,
+    /// `None` when it was downloaded without verification
+    pub integrity: Option<DownloadIntegrity>,
+    /// Manifest of its album, for photos of encrypted albums
+    pub manifest: Option<AlbumManifest>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CacheRecord {
+    repo: String,
+    path: String,
+    sha: String,
+    /// Keyed hash of the content, naming its file
+    blob: String,
+    bytes: u64,
+    #[serde(default)]
+    pinned: bool,
+    /// Milliseconds since the epoch
+    used: u64,
+    integrity: Option<DownloadIntegrity>,
+    manifest: Option<AlbumManifest>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadCacheStats {
+    pub photos: usize,
+    /// On disk, counting shared content once
+    pub bytes: u64,
+    pub pinned_photos: usize,
+    pub pinned_bytes: u64,
+    pub limit_bytes: u64,
+    /// Downloads served from the cache since start
+    pub hits: u64,
+    pub misses: u64,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn record_id(store: &LocalStore, repo: &str, path: &str) -> [u8; 32] {
+    store.index_id(&["download", repo, path])
+}
+
+fn blob_id(store: &LocalStore, content: &[u8]) -> [u8; 32] {
+    store.index_id(&["download-blob", &hex::encode(hash_data(content))])
+}
+
+fn blob_path(dir: &Path, blob: &str) -> PathBuf {
+    dir.join(format!("{}.{}", blob, BLOB_EXT))
+}
+
+// ============================================================================
+// Index
+// ============================================================================
+
+fn put_record(store: &LocalStore, record: &CacheRecord) -> Result<(), AppError> {
+    let id = record_id(store, &record.repo, &record.path);
+    let json = zeroize::Zeroizing::new(
+        serde_json::to_vec(record).map_err(|e| AppError::Validation(e.to_string()))?,
+    );
+    store
+        .connection()
+        .execute(
+            "INSERT OR REPLACE INTO download_cache (id, record) VALUES (?1, ?2)",
+            params![&id[..], store.seal(&id, &json)?],
+        )
+        .map_err(db_error)?;
+    Ok(())
+}
+
+/// Decrypt the rows `sql` selects as `(id, record)`
+fn read_records(store: &LocalStore, sql: &str, params: impl Params) -> Result<Vec<CacheRecord>, AppError> {
+    let mut statement = store.connection().prepare(sql).map_err(db_error)?;
+    let rows = statement
+        .query_map(params, |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?)))
+        .map_err(db_error)?;
+
+    let mut records = Vec::new();
+    for row in rows {
+        let (id, sealed) = row.map_err(db_error)?;
+        let id: [u8; 32] = id
+            .try_into()
+            .map_err(|_| AppError::Validation("Corrupt download cache row".into()))?;
+        let json = store.unseal(&id, &sealed)?;
+        records.push(
+            serde_json::from_slice(&json)
+                .map_err(|e| AppError::Validation(format!("Corrupt download cache row: {}", e)))?,
+        );
+    }
+    Ok(records)
+}
+
+fn all_records(store: &LocalStore) -> Result<Vec<CacheRecord>, AppError> {
+    read_records(store, "SELECT id, record FROM download_cache", [])
+}
+
+fn get_record(store: &LocalStore, repo: &str, path: &str) -> Result<Option<CacheRecord>, AppError> {
+    let id = record_id(store, repo, path);
+    Ok(read_records(store, "SELECT id, record FROM download_cache WHERE id = ?1", params![&id[..]])?.pop())
+}
+
+/// Drop the entry of `repo`/`path`, and its file unless another path
+/// shares it
+fn forget(store: &LocalStore, dir: &Path, repo: &str, path: &str) -> Result<bool, AppError> {
+    let Some(record) = get_record(store, repo, path)? else {
+        return Ok(false);
+    };
+    store
+        .connection()
+        .execute("DELETE FROM download_cache WHERE id = ?1", params![&record_id(store, repo, path)[..]])
+        .map_err(db_error)?;
+    drop_unused_blob(store, dir, &record.blob)?;
+    Ok(true)
+}
+
+fn drop_unused_blob(store: &LocalStore, dir: &Path, blob: &str) -> Result<(), AppError> {
+    if !all_records(store)?.iter().any(|r| r.blob == blob) {
+        let path = blob_path(dir, blob);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Evict the least recently used unpinned files until they fit `limit`.
+/// Returns how many files were removed.
+fn evict(store: &LocalStore, dir: &Path, limit: u64) -> Result<usize, AppError> {
+    // Per file: size, whether any path sharing it is pinned, last use
+    let mut blobs: HashMap<String, (u64, bool, u64)> = HashMap::new();
+    let records = all_records(store)?;
+    for record in &records {
+        let blob = blobs.entry(record.blob.clone()).or_insert((record.bytes, false, 0));
+        blob.1 |= record.pinned;
+        blob.2 = blob.2.max(record.used);
+    }
+    let mut unpinned: Vec<(u64, String, u64)> = blobs
+        .into_iter()
+        .filter(|(_, (_, pinned, _))| !pinned)
+        .map(|(blob, (bytes, _, used))| (used, blob, bytes))
+        .collect();
+    let mut total: u64 = unpinned.iter().map(|(_, _, bytes)| bytes).sum();
+    unpinned.sort();
+
+    let mut evicted = 0;
+    for (_, blob, bytes) in unpinned {
+        if total <= limit {
+            break;
+        }
+        for record in records.iter().filter(|r| r.blob == blob) {
+            store
+                .connection()
+                .execute(
+                    "DELETE FROM download_cache WHERE id = ?1",
+                    params![&record_id(store, &record.repo, &record.path)[..]],
+                )
+                .map_err(db_error)?;
+        }
+        let path = blob_path(dir, &blob);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        total -= bytes;
+        evicted += 1;
+    }
+    Ok(evicted)
+}
+
+// ============================================================================
+// Cache
+// ============================================================================
+
+/// Size limit of unpinned photos, from the settings
+pub fn cache_limit(store: &LocalStore) -> Result<u64, AppError> {
+    Ok(store.get_json(SETTINGS_NS, LIMIT_SETTING)?.unwrap_or(DEFAULT_LIMIT_BYTES))
+}
+
+/// The cached blob of `repo`/`path`, marking it as used. `verified` callers
+/// only get photos whose download was verified.
+pub fn cached_photo_in(
+    store: &LocalStore,
+    dir: &Path,
+    repo: &str,
+    path: &str,
+    verified: bool,
+) -> Result<Option<CachedPhoto>, AppError> {
+    let Some(mut record) = get_record(store, repo, path)? else {
+        return Ok(None);
+    };
+    let listed = photo_by_id(store, &photo_id(store, repo, path))?;
+    if listed.is_some_and(|photo| photo.item.sha != record.sha) {
+        forget(store, dir, repo, path)?;
+        return Ok(None);
+    }
+    if verified && record.integrity.is_none() {
+        return Ok(None);
+    }
+
+    let blob = hex::decode(&record.blob)
+        .ok()
+        .and_then(|id| <[u8; 32]>::try_from(id).ok())
+        .ok_or_else(|| AppError::Validation("Corrupt download cache row".into()))?;
+    let content = match std::fs::read(blob_path(dir, &record.blob)).map(|sealed| store.unseal(&blob, &sealed)) {
+        Ok(Ok(content)) => content.to_vec(),
+        // Deleted or damaged on disk: download it again
+        _ => {
+            forget(store, dir, repo, path)?;
+            return Ok(None);
+        }
+    };
+
+    record.used = now_millis();
+    put_record(store, &record)?;
+    Ok(Some(CachedPhoto {
+        sha: record.sha,
+        content,
+        integrity: if verified { record.integrity } else { None },
+        manifest: record.manifest,
+    }))
+}
+
+/// Keep `photo` for `repo`/`path`, then evict down to `limit`. A pinned
+/// path stays pinned.
+pub fn store_photo_in(
+    store: &LocalStore,
+    dir: &Path,
+    repo: &str,
+    path: &str,
+    photo: &CachedPhoto,
+    limit: u64,
+) -> Result<(), AppError> {
+    std::fs::create_dir_all(dir)?;
+    let blob = blob_id(store, &photo.content);
+    let blob_name = hex::encode(blob);
+    let file = blob_path(dir, &blob_name);
+    if !file.exists() {
+        let tmp = file.with_extension("tmp");
+        std::fs::write(&tmp, store.seal(&blob, &photo.content)?)?;
+        std::fs::rename(&tmp, &file)?;
+    }
+
+    let previous = get_record(store, repo, path)?;
+    put_record(
+        store,
+        &CacheRecord {
+            repo: repo.to_string(),
+            path: path.to_string(),
+            sha: photo.sha.clone(),
+            blob: blob_name.clone(),
+            bytes: photo.content.len() as u64,
+            pinned: previous.as_ref().is_some_and(|p| p.pinned),
+            used: now_millis(),
+            integrity: photo.integrity.clone(),
+            manifest: photo.manifest.clone(),
+        },
+    )?;
+    if let Some(previous) = previous.filter(|p| p.blob != blob_name) {
+        drop_unused_blob(store, dir, &previous.blob)?;
+    }
+    evict(store, dir, limit)?;
+    Ok(())
+}
+
+/// Pin or unpin a cached photo; false when it is not cached
+pub fn set_pinned_in(
+    store: &LocalStore,
+    dir: &Path,
+    repo: &str,
+    path: &str,
+    pinned: bool,
+    limit: u64,
+) -> Result<bool, AppError> {
+    let Some(mut record) = get_record(store, repo, path)? else {
+        return Ok(false);
+    };
+    record.pinned = pinned;
+    put_record(store, &record)?;
+    evict(store, dir, limit)?;
+    Ok(true)
+}
+
+pub fn cache_stats_in(store: &LocalStore) -> Result<DownloadCacheStats, AppError> {
+    let records = all_records(store)?;
+    let mut blobs: HashMap<&str, (u64, bool)> = HashMap::new();
+    for record in &records {
+        blobs.entry(record.blob.as_str()).or_insert((record.bytes, false)).1 |= record.pinned;
+    }
+    Ok(DownloadCacheStats {
+        photos: records.len(),
+        bytes: blobs.values().map(|(bytes, _)| bytes).sum(),
+        pinned_photos: records.iter().filter(|r| r.pinned).count(),
+        pinned_bytes: blobs.values().filter(|(_, pinned)| *pinned).map(|(bytes, _)| bytes).sum(),
+        limit_bytes: cache_limit(store)?,
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    })
+}
+
+/// Whether `cached_photo_in` would serve `repo`/`path`, without reading it
+pub fn has_photo_in(store: &LocalStore, dir: &Path, repo: &str, path: &str, verified: bool) -> Result<bool, AppError> {
+    let Some(record) = get_record(store, repo, path)? else {
+        return Ok(false);
+    };
+    let listed = photo_by_id(store, &photo_id(store, repo, path))?;
+    Ok(listed.is_none_or(|photo| photo.item.sha == record.sha)
+        && (!verified || record.integrity.is_some())
+        && blob_path(dir, &record.blob).exists())
+}
+
+/// Set the size limit and evict down to it
+pub fn set_cache_limit_in(store: &LocalStore, dir: &Path, limit: u64) -> Result<usize, AppError> {
+    store.put_json(SETTINGS_NS, LIMIT_SETTING, &limit, None)?;
+    evict(store, dir, limit)
+}
+
+/// Remove cached photos, pinned ones only if `include_pinned`. Returns how
+/// many photos were removed.
+pub fn clear_download_cache_in(store: &LocalStore, dir: &Path, include_pinned: bool) -> Result<usize, AppError> {
+    let mut removed = 0;
+    for record in all_records(store)? {
+        if include_pinned || !record.pinned {
+            forget(store, dir, &record.repo, &record.path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+pub(crate) fn cache_dir() -> Result<PathBuf, AppError> {
+    Ok(crate::profiles::data_dir()?.join(CACHE_DIR))
+}
+
+/// Keep a freshly fetched photo. Failures only cost the cache, so they are
+/// logged.
+pub(crate) fn remember_photo(dir: &Path, repo: &str, path: &str, photo: &CachedPhoto) {
+    let stored = with_store(|store| store_photo_in(store, dir, repo, path, photo, cache_limit(store)?));
+    if let Err(e) = stored {
+        tracing::warn!("Could not cache {}: {}", path, e);
+    }
+}
+
+/// A photo from the cache, or from the repo and then into the cache. The
+/// flag is true for cache hits.
+pub(crate) async fn load_photo(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    path: &str,
+    keypair_handle: Option<KeypairHandle>,
+    verify: bool,
+) -> Result<(CachedPhoto, bool), AppError> {
+    let dir = cache_dir()?;
+    match with_store(|store| cached_photo_in(store, &dir, repo, path, verify)) {
+        Ok(Some(photo)) => {
+            HITS.fetch_add(1, Ordering::Relaxed);
+            return Ok((photo, true));
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Could not read the download cache: {}", e),
+    }
+    MISSES.fetch_add(1, Ordering::Relaxed);
+
+    let photo = fetch_photo(client, repo, token, path, keypair_handle, verify).await?;
+    remember_photo(&dir, repo, path, &photo);
+    Ok((photo, false))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Keep a photo available offline and out of eviction, downloading and
+/// verifying it first if it is not cached
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn pin_photo_offline(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<DownloadCacheStats, AppError> {
+    validate_repo(&repo)?;
+    load_photo(&client.0, &repo, &token, &path, keypair_handle, true).await?;
+    let dir = cache_dir()?;
+    with_store(|store| {
+        if !set_pinned_in(store, &dir, &repo, &path, true, cache_limit(store)?)? {
+            return Err(AppError::Validation(format!("Could not keep {} offline", path)));
+        }
+        cache_stats_in(store)
+    })
+}
+
+/// Let a pinned photo be evicted again
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn unpin_photo_offline(repo: String, path: String) -> Result<bool, AppError> {
+    let dir = cache_dir()?;
+    with_store(|store| set_pinned_in(store, &dir, &repo, &path, false, cache_limit(store)?))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn download_cache_stats() -> Result<DownloadCacheStats, AppError> {
+    with_store(cache_stats_in)
+}
+
+/// Limit the space unpinned photos take, in bytes
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn set_download_cache_limit(limit_bytes: u64) -> Result<DownloadCacheStats, AppError> {
+    let dir = cache_dir()?;
+    with_store(|store| {
+        set_cache_limit_in(store, &dir, limit_bytes)?;
+        cache_stats_in(store)
+    })
+}
+
+/// Empty the cache, keeping pinned photos unless `include_pinned`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn clear_download_cache(include_pinned: Option<bool>) -> Result<usize, AppError> {
+    let dir = cache_dir()?;
+    with_store(|store| clear_download_cache_in(store, &dir, include_pinned.unwrap_or(false)))
+}
@@ -0,0 +1,162 @@
+//! Content Classification
+//!
+//! Decides whether data is worth compressing before any CPU is spent on it.
+//! Magic bytes identify formats that are already compressed (JPEG, PNG,
+//! HEIC, video, archives); for everything else the Shannon entropy of a
+//! sample decides. Encrypted or already-compressed blobs sit near 8 bits per
+//! byte and shrink by a few percent at best.
+//!
+//! The sample is up to three 16 KiB windows (start, middle, end), so large
+//! inputs cost the same as small ones.
+
+use serde::{Deserialize, Serialize};
+
+const WINDOW: usize = 16 * 1024;
+/// Above this many bits per byte, unknown binary data is treated as
+/// incompressible
+pub const INCOMPRESSIBLE_ENTROPY: f64 = 7.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Jpeg,
+    Png,
+    Gif,
+    Webp,
+    Heic,
+    Avif,
+    Video,
+    Audio,
+    Archive,
+    Text,
+    Binary,
+}
+
+impl ContentKind {
+    /// Formats that carry their own compression
+    pub fn is_precompressed(self) -> bool {
+        !matches!(self, Self::Text | Self::Binary)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContentAnalysis {
+    pub kind: ContentKind,
+    /// Shannon entropy of the sample in bits per byte (0 to 8)
+    pub entropy: f64,
+    pub compressible: bool,
+}
+
+/// Identify the format from its leading bytes
+pub fn detect_kind(data: &[u8]) -> ContentKind {
+    let starts = |magic: &[u8]| data.starts_with(magic);
+
+    if starts(&[0xFF, 0xD8, 0xFF]) {
+        return ContentKind::Jpeg;
+    }
+    if starts(b"\x89PNG\r\n\x1a\n") {
+        return ContentKind::Png;
+    }
+    if starts(b"GIF87a") || starts(b"GIF89a") {
+        return ContentKind::Gif;
+    }
+    if starts(b"RIFF") && data.len() >= 12 {
+        match &data[8..12] {
+            b"WEBP" => return ContentKind::Webp,
+            b"AVI " => return ContentKind::Video,
+            b"WAVE" => return ContentKind::Binary,
+            _ => {}
+        }
+    }
+    if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        return match &data[8..12] {
+            b"heic" | b"heix" | b"hevc" | b"heim" | b"heis" | b"mif1" | b"msf1" => ContentKind::Heic,
+            b"avif" | b"avis" => ContentKind::Avif,
+            b"M4A " | b"M4B " => ContentKind::Audio,
+            _ => ContentKind::Video,
+        };
+    }
+    if starts(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return ContentKind::Video;
+    }
+    if starts(b"ID3") || starts(b"OggS") || starts(b"fLaC") {
+        return ContentKind::Audio;
+    }
+    const ARCHIVES: &[&[u8]] = &[
+        b"PK\x03\x04",
+        &[0x1F, 0x8B],
+        &[0xFD, b'7', b'z', b'X', b'Z', 0x00],
+        &[0x28, 0xB5, 0x2F, 0xFD],
+        &[0x04, 0x22, 0x4D, 0x18],
+        b"7z\xBC\xAF\x27\x1C",
+        b"Rar!",
+        b"BZh",
+        b"VXSEG",
+        b"VXCOMP",
+    ];
+    if ARCHIVES.iter().any(|magic| starts(magic)) {
+        return ContentKind::Archive;
+    }
+    if looks_like_text(&data[..data.len().min(WINDOW)]) {
+        return ContentKind::Text;
+    }
+    ContentKind::Binary
+}
+
+fn looks_like_text(sample: &[u8]) -> bool {
+    if sample.is_empty() || sample.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        // The sample may cut a multi-byte character in half
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// Shannon entropy of `data` in bits per byte
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Start, middle and end windows of `data`
+fn sample(data: &[u8]) -> Vec<u8> {
+    if data.len() <= 3 * WINDOW {
+        return data.to_vec();
+    }
+    let middle = data.len() / 2 - WINDOW / 2;
+    [&data[..WINDOW], &data[middle..middle + WINDOW], &data[data.len() - WINDOW..]].concat()
+}
+
+pub fn analyze(data: &[u8]) -> ContentAnalysis {
+    let kind = detect_kind(data);
+    let entropy = shannon_entropy(&sample(data));
+    let compressible = !kind.is_precompressed() && (kind == ContentKind::Text || entropy < INCOMPRESSIBLE_ENTROPY);
+    ContentAnalysis {
+        kind,
+        entropy,
+        compressible,
+    }
+}
+
+/// Classify data without compressing it
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn analyze_content(data: Vec<u8>) -> ContentAnalysis {
+    analyze(&data)
+}
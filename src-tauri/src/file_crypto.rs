@@ -0,0 +1,566 @@
+//! Streaming File Encryption
+//!
+//! `encrypt_file` and `decrypt_file` work on paths and process the file in
+//! fixed-size chunks, so memory stays flat no matter how large the file is.
+//!
+//! Format:
+//!
+//! ```text
+//! [magic "VXSTREAM"][version: 2][method: 1][chunk size: u32][nonce prefix: 7]
+//! [plaintext length: u64][key block length: u32][key block]
+//! [chunk 0][chunk 1]...[chunk n-1]
+//! ```
+//!
+//! The key block is the Argon2id costs and salt for passwords
+//! (`[memory KiB: u32][iterations: u32][parallelism: u32][salt: 16]`), or the
+//! file key wrapped to the recipient's public bundle. Version 1 files carry
+//! only the salt and were derived with the floor costs. Every chunk is ChaCha20-Poly1305 with
+//! nonce `[prefix: 7][index: u32 BE][last: 1]` and AAD
+//! `[BLAKE3(header): 32][tag of the previous chunk: 16]`. The tag chain ties
+//! each chunk to everything before it, and the last-chunk flag plus the
+//! stored length catch truncation.
+//!
+//! Decryption writes to `<output>.<header id>.part` and renames it when done.
+//! If that file already exists, whole chunks in it are kept and decryption
+//! carries on from there.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use tauri::Emitter;
+use zeroize::Zeroizing;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+use crate::AppHandle;
+use crate::crypto::{
+    current_kdf_params, decrypt_hybrid, encrypt_with_aad, EncryptedPayload, EncryptionMethod,
+    EncryptionSettings, KdfParams, KeypairHandle, PublicBundle,
+};
+use crate::github::AppError;
+use crate::jobs::{CancelOnWrite, Job, JobCommand};
+
+const MAGIC: &[u8; 8] = b"VXSTREAM";
+const VERSION: u8 = 2;
+/// Salt-only password key blocks, derived with `KdfParams::FLOOR`
+const VERSION_FLOOR_KDF: u8 = 1;
+pub const DEFAULT_CHUNK_SIZE: u32 = 1024 * 1024;
+const MIN_CHUNK_SIZE: u32 = 1024;
+const MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+const TAG_LEN: usize = 16;
+const SALT_LEN: usize = 16;
+const MAX_KEY_BLOCK: u32 = 64 * 1024;
+const KEY_WRAP_AAD: &[u8] = b"vortex-stream-key-v1";
+
+/// How the file key is obtained
+pub enum StreamKey {
+    Password(Zeroizing<Vec<u8>>),
+    Recipient(PublicBundle),
+}
+
+/// Parsed stream header
+#[derive(Clone, Debug)]
+pub struct StreamHeader {
+    pub version: u8,
+    pub method: EncryptionMethod,
+    pub chunk_size: u32,
+    pub nonce_prefix: [u8; 7],
+    pub plaintext_len: u64,
+    pub key_block: Vec<u8>,
+    /// BLAKE3 of the serialized header, bound into every chunk
+    pub hash: [u8; 32],
+    /// Serialized header length, where the first chunk starts
+    pub len: u64,
+}
+
+impl StreamHeader {
+    pub fn chunk_count(&self) -> u64 {
+        self.plaintext_len.div_ceil(self.chunk_size as u64).max(1)
+    }
+
+    fn chunk_len(&self, index: u64) -> usize {
+        let start = index * self.chunk_size as u64;
+        (self.plaintext_len - start.min(self.plaintext_len)).min(self.chunk_size as u64) as usize
+    }
+
+    /// Where ciphertext chunk `index` starts in the file
+    fn chunk_offset(&self, index: u64) -> u64 {
+        self.len + index * (self.chunk_size as u64 + TAG_LEN as u64)
+    }
+
+    fn nonce(&self, index: u64) -> Result<[u8; 12], AppError> {
+        let counter = u32::try_from(index).map_err(|_| AppError::Validation("File has too many chunks".into()))?;
+        let mut nonce = [0u8; 12];
+        nonce[..7].copy_from_slice(&self.nonce_prefix);
+        nonce[7..11].copy_from_slice(&counter.to_be_bytes());
+        nonce[11] = (index + 1 == self.chunk_count()) as u8;
+        Ok(nonce)
+    }
+
+    fn aad(&self, prev_tag: &[u8; TAG_LEN]) -> [u8; 48] {
+        let mut aad = [0u8; 48];
+        aad[..32].copy_from_slice(&self.hash);
+        aad[32..].copy_from_slice(prev_tag);
+        aad
+    }
+
+    fn method_byte(&self) -> u8 {
+        match self.method {
+            EncryptionMethod::Password => 1,
+            _ => 2,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(33 + self.key_block.len());
+        out.extend_from_slice(MAGIC);
+        out.push(self.version);
+        out.push(self.method_byte());
+        out.extend_from_slice(&self.chunk_size.to_le_bytes());
+        out.extend_from_slice(&self.nonce_prefix);
+        out.extend_from_slice(&self.plaintext_len.to_le_bytes());
+        out.extend_from_slice(&(self.key_block.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.key_block);
+        out
+    }
+
+    fn sealed(mut self) -> Self {
+        let bytes = self.to_bytes();
+        self.hash = *blake3::hash(&bytes).as_bytes();
+        self.len = bytes.len() as u64;
+        self
+    }
+
+    /// Read and validate a header, leaving `reader` at the first chunk
+    pub fn read_from(reader: &mut impl Read) -> Result<Self, AppError> {
+        let invalid = || AppError::Validation("Not an encrypted Vortex file".into());
+        let mut fixed = [0u8; 33];
+        reader.read_exact(&mut fixed).map_err(|_| invalid())?;
+        if &fixed[..8] != MAGIC {
+            return Err(invalid());
+        }
+        let version = fixed[8];
+        if version != VERSION && version != VERSION_FLOOR_KDF {
+            return Err(AppError::Validation(format!("Unsupported encrypted file version {}", fixed[8])));
+        }
+        let method = match fixed[9] {
+            1 => EncryptionMethod::Password,
+            2 => EncryptionMethod::HybridPQ,
+            _ => return Err(invalid()),
+        };
+        let chunk_size = u32::from_le_bytes(fixed[10..14].try_into().unwrap());
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            return Err(invalid());
+        }
+        let key_len = u32::from_le_bytes(fixed[29..33].try_into().unwrap());
+        if key_len > MAX_KEY_BLOCK {
+            return Err(invalid());
+        }
+        let mut key_block = vec![0u8; key_len as usize];
+        reader.read_exact(&mut key_block).map_err(|_| invalid())?;
+
+        Ok(Self {
+            version,
+            method,
+            chunk_size,
+            nonce_prefix: fixed[14..21].try_into().unwrap(),
+            plaintext_len: u64::from_le_bytes(fixed[21..29].try_into().unwrap()),
+            key_block,
+            hash: [0u8; 32],
+            len: 0,
+        }
+        .sealed())
+    }
+
+    /// Short id of this file, used to name its partial output
+    pub fn id(&self) -> String {
+        hex::encode(&self.hash[..6])
+    }
+}
+
+// ============================================================================
+// Keys
+// ============================================================================
+
+/// Build a header and file key for a new stream
+fn new_stream(key: &StreamKey, chunk_size: u32, plaintext_len: u64) -> Result<(StreamHeader, Zeroizing<[u8; 32]>), AppError> {
+    if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+        return Err(AppError::Validation("Invalid chunk size".into()));
+    }
+    let nonce_prefix: [u8; 7] = rand::random();
+    let (method, key_block, file_key) = match key {
+        StreamKey::Password(password) => {
+            let params = current_kdf_params();
+            let salt: [u8; SALT_LEN] = rand::random();
+            let file_key = Zeroizing::new(params.derive(password, &salt)?);
+            let mut block = params.to_bytes().to_vec();
+            block.extend_from_slice(&salt);
+            (EncryptionMethod::Password, block, file_key)
+        }
+        StreamKey::Recipient(bundle) => {
+            let file_key = Zeroizing::new(rand::random::<[u8; 32]>());
+            let wrapped = encrypt_with_aad(file_key.as_slice(), bundle, Some(&wrap_aad(&nonce_prefix)))
+                ?;
+            let block = serde_json::to_vec(&wrapped)
+                .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+            (EncryptionMethod::HybridPQ, block, file_key)
+        }
+    };
+    let header = StreamHeader {
+        version: VERSION,
+        method,
+        chunk_size,
+        nonce_prefix,
+        plaintext_len,
+        key_block,
+        hash: [0u8; 32],
+        len: 0,
+    }
+    .sealed();
+    Ok((header, file_key))
+}
+
+fn wrap_aad(nonce_prefix: &[u8; 7]) -> Vec<u8> {
+    let mut aad = KEY_WRAP_AAD.to_vec();
+    aad.extend_from_slice(nonce_prefix);
+    aad
+}
+
+/// Argon2id costs and salt from a password key block
+fn password_kdf(header: &StreamHeader) -> Result<(KdfParams, &[u8]), AppError> {
+    let block = header.key_block.as_slice();
+    if header.version == VERSION_FLOOR_KDF {
+        return Ok((KdfParams::FLOOR, block));
+    }
+    if block.len() != KdfParams::ENCODED_LEN + SALT_LEN {
+        return Err(AppError::Validation("Corrupt file key".into()));
+    }
+    let (params, salt) = block.split_at(KdfParams::ENCODED_LEN);
+    Ok((KdfParams::from_bytes(params), salt))
+}
+
+/// Recover the file key of an existing stream
+pub fn stream_key(
+    header: &StreamHeader,
+    password: Option<&[u8]>,
+    handle: Option<KeypairHandle>,
+) -> Result<Zeroizing<[u8; 32]>, AppError> {
+    match header.method {
+        EncryptionMethod::Password => {
+            let password = password.ok_or_else(|| AppError::Validation("Password required".into()))?;
+            let (params, salt) = password_kdf(header)?;
+            Ok(Zeroizing::new(params.derive(password, salt)?))
+        }
+        _ => {
+            let handle = handle.ok_or_else(|| AppError::Validation("Keypair handle required".into()))?;
+            let wrapped: EncryptedPayload = serde_json::from_slice(&header.key_block)
+                .map_err(|_| AppError::Validation("Corrupt file key".into()))?;
+            let key = Zeroizing::new(
+                decrypt_hybrid(wrapped, handle, Some(wrap_aad(&header.nonce_prefix)))?,
+            );
+            key.as_slice()
+                .try_into()
+                .map(Zeroizing::new)
+                .map_err(|_| AppError::Validation("Corrupt file key".into()))
+        }
+    }
+}
+
+// ============================================================================
+// Streaming
+// ============================================================================
+
+/// Encrypt `plaintext_len` bytes from `reader` into `writer`. `progress` gets
+/// the number of plaintext bytes done after every chunk.
+pub fn encrypt_stream(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    key: &StreamKey,
+    chunk_size: u32,
+    plaintext_len: u64,
+    mut progress: impl FnMut(u64),
+) -> Result<StreamHeader, AppError> {
+    let (header, file_key) = new_stream(key, chunk_size, plaintext_len)?;
+    let cipher = ChaCha20Poly1305::new(file_key.as_slice().into());
+    writer.write_all(&header.to_bytes())?;
+
+    let mut buf = Zeroizing::new(vec![0u8; chunk_size as usize]);
+    let mut prev_tag = [0u8; TAG_LEN];
+    let mut done = 0u64;
+    for index in 0..header.chunk_count() {
+        let len = header.chunk_len(index);
+        reader
+            .read_exact(&mut buf[..len])
+            .map_err(|_| AppError::Validation("File shrank while it was being encrypted".into()))?;
+        let nonce = header.nonce(index)?;
+        let chunk = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &buf[..len],
+                    aad: &header.aad(&prev_tag),
+                },
+            )
+            .map_err(|_| AppError::Validation("Encryption failed".into()))?;
+        prev_tag.copy_from_slice(&chunk[chunk.len() - TAG_LEN..]);
+        writer.write_all(&chunk)?;
+        done += len as u64;
+        progress(done);
+    }
+    if reader.read(&mut buf[..1])? != 0 {
+        return Err(AppError::Validation("File grew while it was being encrypted".into()));
+    }
+    writer.flush()?;
+    Ok(header)
+}
+
+/// Decrypt chunks `start_chunk..` of a stream whose header has been read,
+/// appending plaintext to `writer`
+pub fn decrypt_stream<R: Read + Seek>(
+    reader: &mut R,
+    writer: &mut impl Write,
+    header: &StreamHeader,
+    file_key: &[u8; 32],
+    start_chunk: u64,
+    mut progress: impl FnMut(u64),
+) -> Result<(), AppError> {
+    let chunks = header.chunk_count();
+    if start_chunk >= chunks {
+        return Err(AppError::Validation("Nothing left to decrypt".into()));
+    }
+    let cipher = ChaCha20Poly1305::new(file_key.into());
+
+    // Resume: the chain continues from the tag of the previous chunk
+    let mut prev_tag = [0u8; TAG_LEN];
+    if start_chunk > 0 {
+        reader.seek(SeekFrom::Start(header.chunk_offset(start_chunk) - TAG_LEN as u64))?;
+        reader.read_exact(&mut prev_tag)?;
+    } else {
+        reader.seek(SeekFrom::Start(header.len))?;
+    }
+
+    let mut buf = vec![0u8; header.chunk_size as usize + TAG_LEN];
+    let mut done = start_chunk * header.chunk_size as u64;
+    for index in start_chunk..chunks {
+        let len = header.chunk_len(index) + TAG_LEN;
+        reader
+            .read_exact(&mut buf[..len])
+            .map_err(|_| AppError::Validation("Encrypted file is truncated".into()))?;
+        let nonce = header.nonce(index)?;
+        let plain = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: &buf[..len],
+                        aad: &header.aad(&prev_tag),
+                    },
+                )
+                .map_err(|_| AppError::Validation(format!("Chunk {} failed authentication", index)))?,
+        );
+        prev_tag.copy_from_slice(&buf[len - TAG_LEN..len]);
+        writer.write_all(&plain)?;
+        done += plain.len() as u64;
+        progress(done);
+    }
+    if reader.read(&mut buf[..1])? != 0 {
+        return Err(AppError::Validation("Encrypted file has trailing data".into()));
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileCryptoProgress {
+    pub id: String,
+    pub bytes_done: u64,
+    pub total_bytes: u64,
+    pub percent: u8,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileCryptoResult {
+    pub output_path: String,
+    pub method: EncryptionMethod,
+    pub bytes: u64,
+    pub chunks: u64,
+    /// Chunks kept from an earlier, interrupted decryption
+    pub resumed_chunks: u64,
+}
+
+fn progress_emitter(app: AppHandle, id: String, total: u64) -> impl FnMut(u64) {
+    move |done| {
+        let _ = app.emit(
+            "file-crypto-progress",
+            FileCryptoProgress {
+                id: id.clone(),
+                bytes_done: done,
+                total_bytes: total,
+                percent: (done * 100).checked_div(total).unwrap_or(100) as u8,
+            },
+        );
+    }
+}
+
+/// Partial output of a decryption, named after the input's header
+pub fn partial_path(output: &Path, header: &StreamHeader) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.part", header.id()));
+    output.with_file_name(name)
+}
+
+/// Partial output of an encryption: the whole output name plus a random
+/// tag, so runs writing `photo.jpg` and `photo.png`, or the same output
+/// twice, never share one
+pub fn encrypt_partial_path(output: &Path) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.part", hex::encode(rand::random::<[u8; 8]>())));
+    output.with_file_name(name)
+}
+
+/// Whole chunks already present in a partial output. The last chunk is always
+/// decrypted again so the end-of-stream checks run.
+pub fn resumable_chunks(header: &StreamHeader, partial_len: u64) -> u64 {
+    (partial_len / header.chunk_size as u64).min(header.chunk_count() - 1)
+}
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Validation(format!("File task failed: {}", e)))?
+}
+
+/// Encrypt a file on disk into `output_path`, emitting `file-crypto-progress`.
+/// Runs as a job (see `jobs`); cancelling removes the partial output.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn encrypt_file(
+    app: AppHandle,
+    input_path: String,
+    output_path: String,
+    settings: EncryptionSettings,
+    password: Option<String>,
+    job_id: Option<String>,
+) -> Result<FileCryptoResult, AppError> {
+    if !settings.enabled {
+        return Err(AppError::Validation("Encryption is disabled in these settings".into()));
+    }
+    let key = if settings.use_password {
+        let password = password.ok_or_else(|| AppError::Validation("Password required".into()))?;
+        StreamKey::Password(Zeroizing::new(password.into_bytes()))
+    } else if settings.use_keypair {
+        StreamKey::Recipient(
+            settings
+                .recipient_bundle
+                .ok_or_else(|| AppError::Validation("Recipient bundle required".into()))?,
+        )
+    } else {
+        return Err(AppError::Validation("Choose a password or a recipient".into()));
+    };
+
+    let job = Job::announce(&app, job_id, JobCommand::EncryptFile)?;
+    let cancel = job.flag();
+    let result = run_blocking(move || {
+        let mut input = File::open(&input_path)?;
+        let total = input.metadata()?.len();
+        let output = PathBuf::from(&output_path);
+        let partial = encrypt_partial_path(&output);
+        let mut writer = CancelOnWrite::new(std::io::BufWriter::new(File::create(&partial)?), cancel);
+        let result = encrypt_stream(
+            &mut input,
+            &mut writer,
+            &key,
+            DEFAULT_CHUNK_SIZE,
+            total,
+            progress_emitter(app, output_path.clone(), total),
+        );
+        drop(writer);
+        let header = match result {
+            Ok(header) => header,
+            Err(e) => {
+                let _ = std::fs::remove_file(&partial);
+                return Err(e);
+            }
+        };
+        std::fs::rename(&partial, &output)?;
+
+        Ok(FileCryptoResult {
+            output_path,
+            method: header.method.clone(),
+            bytes: total,
+            chunks: header.chunk_count(),
+            resumed_chunks: 0,
+        })
+    })
+    .await;
+    job.outcome(result)
+}
+
+/// Decrypt a file written by `encrypt_file` into `output_path`, emitting
+/// `file-crypto-progress`. An interrupted run is picked up where it stopped;
+/// a cancelled one (see `jobs`) removes its partial output instead.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn decrypt_file(
+    app: AppHandle,
+    input_path: String,
+    output_path: String,
+    password: Option<String>,
+    handle: Option<KeypairHandle>,
+    job_id: Option<String>,
+) -> Result<FileCryptoResult, AppError> {
+    let password = password.map(Zeroizing::new);
+    let job = Job::announce(&app, job_id, JobCommand::DecryptFile)?;
+    let cancel = job.flag();
+
+    let result = run_blocking(move || {
+        let mut input = std::io::BufReader::new(File::open(&input_path)?);
+        let header = StreamHeader::read_from(&mut input)?;
+        let file_key = stream_key(&header, password.as_ref().map(|p| p.as_bytes()), handle)?;
+
+        let output = PathBuf::from(&output_path);
+        let partial = partial_path(&output, &header);
+        let partial_len = std::fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+        let start = resumable_chunks(&header, partial_len);
+
+        let file = OpenOptions::new().create(true).write(true).truncate(false).open(&partial)?;
+        file.set_len(start * header.chunk_size as u64)?;
+        let mut writer = std::io::BufWriter::new(file);
+        writer.seek(SeekFrom::End(0))?;
+        let mut writer = CancelOnWrite::new(writer, cancel.clone());
+
+        let decrypted = decrypt_stream(
+            &mut input,
+            &mut writer,
+            &header,
+            &file_key,
+            start,
+            progress_emitter(app, output_path.clone(), header.plaintext_len),
+        );
+        drop(writer);
+        if decrypted.is_err() && cancel.load(Ordering::Relaxed) {
+            let _ = std::fs::remove_file(&partial);
+        }
+        decrypted?;
+        std::fs::rename(&partial, &output)?;
+
+        Ok(FileCryptoResult {
+            output_path,
+            method: header.method.clone(),
+            bytes: header.plaintext_len,
+            chunks: header.chunk_count(),
+            resumed_chunks: start,
+        })
+    })
+    .await;
+    job.outcome(result)
+}
@@ -0,0 +1,259 @@
+//! File Hashing
+//!
+//! `hash_data_blake3` needs the whole input in memory. `hash_file_blake3`
+//! hashes a file where it lies instead: memory-mapped when the platform
+//! allows it and read in blocks otherwise, each block hashed on all cores
+//! with BLAKE3's rayon mode. Progress goes out as `hash-progress` events,
+//! and the command runs as a job (see `jobs`), so `cancel_job` stops it.
+//!
+//! With `tree` set it also hashes every `chunk_size` bytes on their own and
+//! returns those leaf hashes with a root over them. Comparing the tree of
+//! what an upload sent with the tree of what arrived gives the offset to
+//! resume from (`verified_prefix`). The leaves cost a second pass over each
+//! block in memory, not a second read.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Emitter;
+
+use crate::AppHandle;
+use crate::github::AppError;
+use crate::jobs::{cancelled, Job, JobCommand};
+
+pub const HASH_PROGRESS_EVENT: &str = "hash-progress";
+pub const DEFAULT_TREE_CHUNK: u64 = 4 * 1024 * 1024;
+pub const MIN_TREE_CHUNK: u64 = 64 * 1024;
+pub const MAX_TREE_CHUNK: u64 = 1024 * 1024 * 1024;
+/// Bytes hashed between progress checks when no tree is asked for
+const BLOCK_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkHash {
+    pub offset: u64,
+    pub length: u64,
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashTree {
+    pub chunk_size: u64,
+    pub chunks: Vec<ChunkHash>,
+    /// BLAKE3 of the leaf hashes, in order
+    pub root: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileHash {
+    /// Hex BLAKE3 of the whole file, as `hash_data_blake3` gives it
+    pub hash: String,
+    pub size: u64,
+    pub memory_mapped: bool,
+    pub tree: Option<HashTree>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HashProgress {
+    pub job_id: String,
+    pub path: String,
+    pub bytes_done: u64,
+    pub total_bytes: u64,
+    pub percent: u8,
+}
+
+/// The whole-file hash and, if asked for, the leaves of the tree
+struct Digest {
+    hasher: blake3::Hasher,
+    tree: Option<(u64, Vec<ChunkHash>, blake3::Hasher)>,
+    offset: u64,
+}
+
+impl Digest {
+    fn new(tree_chunk: Option<u64>) -> Self {
+        Self {
+            hasher: blake3::Hasher::new(),
+            tree: tree_chunk.map(|size| (size, Vec::new(), blake3::Hasher::new())),
+            offset: 0,
+        }
+    }
+
+    /// Add the next block; with a tree each block is one chunk
+    fn add(&mut self, block: &[u8]) {
+        self.hasher.update_rayon(block);
+        if let Some((_, chunks, root)) = self.tree.as_mut() {
+            let leaf = blake3::Hasher::new().update_rayon(block).finalize();
+            root.update(leaf.as_bytes());
+            chunks.push(ChunkHash {
+                offset: self.offset,
+                length: block.len() as u64,
+                hash: leaf.to_hex().to_string(),
+            });
+        }
+        self.offset += block.len() as u64;
+    }
+
+    fn finish(self, memory_mapped: bool) -> FileHash {
+        FileHash {
+            hash: self.hasher.finalize().to_hex().to_string(),
+            size: self.offset,
+            memory_mapped,
+            tree: self.tree.map(|(chunk_size, chunks, root)| HashTree {
+                chunk_size,
+                chunks,
+                root: root.finalize().to_hex().to_string(),
+            }),
+        }
+    }
+}
+
+/// The tree chunk size for the options given, or `None` without a tree
+pub fn tree_chunk_size(tree: bool, chunk_size: Option<u64>) -> Result<Option<u64>, AppError> {
+    if !tree {
+        return Ok(None);
+    }
+    let size = chunk_size.unwrap_or(DEFAULT_TREE_CHUNK);
+    if !(MIN_TREE_CHUNK..=MAX_TREE_CHUNK).contains(&size) {
+        return Err(AppError::Validation(format!(
+            "Chunk size must be between {} and {} bytes",
+            MIN_TREE_CHUNK, MAX_TREE_CHUNK
+        )));
+    }
+    Ok(Some(size))
+}
+
+/// Feed `block` to `digest` unless cancelled, then report the bytes done
+fn feed(
+    digest: &mut Digest,
+    block: &[u8],
+    cancel: &AtomicBool,
+    progress: &mut impl FnMut(u64),
+) -> Result<(), AppError> {
+    if cancel.load(Ordering::Relaxed) {
+        return Err(cancelled("Hashing cancelled"));
+    }
+    digest.add(block);
+    progress(digest.offset);
+    Ok(())
+}
+
+/// Hash everything `reader` yields in blocks, with a tree of `tree_chunk`
+/// sized chunks if given
+pub fn hash_reader(
+    mut reader: impl Read,
+    tree_chunk: Option<u64>,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(u64),
+) -> Result<FileHash, AppError> {
+    let block_size = tree_chunk.unwrap_or(BLOCK_SIZE);
+    let mut digest = Digest::new(tree_chunk);
+    let mut block = Vec::new();
+    loop {
+        block.clear();
+        if reader.by_ref().take(block_size).read_to_end(&mut block)? == 0 {
+            return Ok(digest.finish(false));
+        }
+        feed(&mut digest, &block, cancel, &mut progress)?;
+    }
+}
+
+fn map_file(file: &File) -> Option<memmap2::Mmap> {
+    // SAFETY: the map is only read while `file` is open. Another process
+    // changing the file meanwhile changes what gets hashed, as it would for
+    // a streamed read; truncating it faults, which BLAKE3's own mmap helpers
+    // accept too.
+    unsafe { memmap2::Mmap::map(file) }.ok()
+}
+
+/// Hash the file at `path`, memory-mapped if possible and streamed
+/// otherwise. `progress` gets the bytes hashed so far.
+pub fn hash_file(
+    path: &Path,
+    tree_chunk: Option<u64>,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(u64),
+) -> Result<FileHash, AppError> {
+    let file = File::open(path)?;
+    // Empty files cannot be mapped everywhere, and gain nothing from it
+    let map = if file.metadata()?.len() > 0 { map_file(&file) } else { None };
+    let Some(map) = map else {
+        return hash_reader(file, tree_chunk, cancel, progress);
+    };
+
+    let block_size = tree_chunk.unwrap_or(BLOCK_SIZE) as usize;
+    let mut digest = Digest::new(tree_chunk);
+    for block in map.chunks(block_size) {
+        feed(&mut digest, block, cancel, &mut progress)?;
+    }
+    Ok(digest.finish(true))
+}
+
+/// Bytes from the start where `actual` matches `expected` chunk for chunk,
+/// where an interrupted upload can resume. Trees of different chunk sizes
+/// share nothing.
+pub fn verified_prefix(expected: &HashTree, actual: &HashTree) -> u64 {
+    if expected.chunk_size != actual.chunk_size {
+        return 0;
+    }
+    expected
+        .chunks
+        .iter()
+        .zip(&actual.chunks)
+        .take_while(|(a, b)| a == b)
+        .map(|(chunk, _)| chunk.length)
+        .sum()
+}
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Validation(format!("Hash task failed: {}", e)))?
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// BLAKE3 of a file without loading it, emitting `hash-progress`. With
+/// `tree` set, also the per-chunk hash tree. Runs as a job (see `jobs`).
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn hash_file_blake3(
+    app: AppHandle,
+    path: String,
+    tree: Option<bool>,
+    chunk_size: Option<u64>,
+    job_id: Option<String>,
+) -> Result<FileHash, AppError> {
+    let tree_chunk = tree_chunk_size(tree.unwrap_or(false), chunk_size)?;
+    let job = Job::announce(&app, job_id, JobCommand::HashFile)?;
+    let cancel = job.flag();
+    let job_id = job.id().to_string();
+
+    let result = run_blocking(move || {
+        let total = std::fs::metadata(&path)?.len();
+        let mut last_percent = None;
+        hash_file(Path::new(&path), tree_chunk, &cancel, |done| {
+            let percent = (done * 100).checked_div(total).unwrap_or(100).min(100) as u8;
+            if last_percent == Some(percent) {
+                return;
+            }
+            last_percent = Some(percent);
+            let _ = app.emit(
+                HASH_PROGRESS_EVENT,
+                HashProgress {
+                    job_id: job_id.clone(),
+                    path: path.clone(),
+                    bytes_done: done,
+                    total_bytes: total,
+                    percent,
+                },
+            );
+        })
+    })
+    .await;
+    job.outcome(result)
+}
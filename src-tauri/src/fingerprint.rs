@@ -0,0 +1,225 @@
+//! Key Fingerprints
+//!
+//! `get_key_fingerprint` shows the 128-bit BLAKE3 fingerprint of a public
+//! bundle (see `PublicBundle::fingerprint`) both as hex and as 16 words, one
+//! per byte, in the style of the PGP word list: bytes at even positions come
+//! from a list of two-syllable words, bytes at odd positions from a list of
+//! three-syllable words. Words are easy to read out over a phone call, and a
+//! swapped, repeated or dropped word lands on the wrong list and is caught.
+//!
+//! `compare_fingerprint` checks a fingerprint read back by the peer, in
+//! either form, and reports which positions differ.
+
+use serde::Serialize;
+
+use crate::contacts::resolve_recipient;
+use crate::crypto::{with_keypair, KeypairHandle, PublicBundle};
+use crate::github::AppError;
+
+/// Words for bytes at even positions
+const EVEN_WORDS: [&str; 256] = [
+    "aardvark", "absurd", "accrue", "acme", "adrift", "adult", "afflict", "ahead", "aimless",
+    "algol", "allow", "alone", "ammo", "ancient", "apple", "artist", "assume", "athens", "atlas",
+    "aztec", "baboon", "backfield", "backward", "banjo", "beaming", "bedlamp", "beehive", "beeswax",
+    "befriend", "belfast", "berserk", "billiard", "bison", "blackjack", "blockade", "blowtorch",
+    "bluebird", "bombast", "bookshelf", "brackish", "breadline", "breakup", "brickyard",
+    "briefcase", "burbank", "button", "buzzard", "cement", "chairlift", "chatter", "checkup",
+    "chisel", "choking", "chopper", "christmas", "clamshell", "classic", "classroom", "cleanup",
+    "clockwork", "cobra", "commence", "concert", "cowbell", "crackdown", "cranky", "crowfoot",
+    "crucial", "crumpled", "crusade", "cubic", "dashboard", "deadbolt", "deckhand", "dogsled",
+    "dragnet", "drainage", "dreadful", "drifter", "dropper", "drumbeat", "drunken", "dupont",
+    "dwelling", "eating", "edict", "egghead", "eightball", "endorse", "endow", "enlist", "erase",
+    "escape", "exceed", "eyeglass", "eyetooth", "facial", "fallout", "flagpole", "flatfoot",
+    "flytrap", "fracture", "framework", "freedom", "frighten", "gazelle", "geiger", "glitter",
+    "glucose", "goggles", "goldfish", "gremlin", "guidance", "hamlet", "highchair", "hockey",
+    "indoors", "indulge", "inverse", "involve", "island", "jawbone", "keyboard", "kickoff", "kiwi",
+    "klaxon", "locale", "lockup", "merit", "minnow", "miser", "mohawk", "mural", "music",
+    "necklace", "neptune", "newborn", "nightbird", "oakland", "obtuse", "offload", "optic", "orca",
+    "payday", "peachy", "pheasant", "physique", "playhouse", "pluto", "preclude", "prefer",
+    "preshrunk", "printer", "prowler", "pupil", "puppy", "python", "quadrant", "quiver", "quota",
+    "ragtime", "ratchet", "rebirth", "reform", "regain", "reindeer", "rematch", "repay", "retouch",
+    "revenge", "reward", "rhythm", "ribcage", "ringbolt", "robust", "rocker", "ruffled", "sailboat",
+    "sawdust", "scallion", "scenic", "scorecard", "scotland", "seabird", "select", "sentence",
+    "shadow", "shamrock", "showgirl", "skullcap", "skydive", "slingshot", "slowdown", "snapline",
+    "snapshot", "snowcap", "snowslide", "solo", "southward", "soybean", "spaniel", "spearhead",
+    "spellbind", "spheroid", "spigot", "spindle", "spyglass", "stagehand", "stagnate", "stairway",
+    "standard", "stapler", "steamship", "sterling", "stockman", "stopwatch", "stormy", "sugar",
+    "surmount", "suspense", "sweatband", "swelter", "tactics", "talon", "tapeworm", "tempest",
+    "tiger", "tissue", "tonic", "topmost", "tracker", "transit", "trauma", "treadmill", "trojan",
+    "trouble", "tumor", "tunnel", "tycoon", "uncut", "unearth", "unwind", "uproot", "upset",
+    "upshot", "vapor", "village", "virus", "vulcan", "waffle", "wallet", "watchword", "wayside",
+    "willow", "woodlark", "zulu",
+];
+
+/// Words for bytes at odd positions
+const ODD_WORDS: [&str; 256] = [
+    "adroitness", "adviser", "aftermath", "aggregate", "alkali", "almighty", "amulet", "amusement",
+    "antenna", "applicant", "apollo", "armistice", "article", "asteroid", "atlantic", "atmosphere",
+    "autopsy", "babylon", "backwater", "barbecue", "belowground", "bifocals", "bodyguard",
+    "bookseller", "borderline", "bottomless", "bradbury", "bravado", "brazilian", "breakaway",
+    "burlington", "businessman", "butterfat", "camelot", "candidate", "cannonball", "capricorn",
+    "caravan", "caretaker", "celebrate", "cellulose", "certify", "chambermaid", "cherokee",
+    "chicago", "clergyman", "coherence", "combustion", "commando", "company", "component",
+    "concurrent", "confidence", "conformist", "congregate", "consensus", "consulting", "corporate",
+    "corrosion", "councilman", "crossover", "crucifix", "cumbersome", "customer", "dakota",
+    "decadence", "december", "decimal", "designing", "detector", "detergent", "determine",
+    "dictator", "dinosaur", "direction", "disable", "disbelief", "disruptive", "distortion",
+    "document", "embezzle", "enchanting", "enrollment", "enterprise", "equation", "equipment",
+    "escapade", "eskimo", "everyday", "examine", "existence", "exodus", "fascinate", "filament",
+    "finicky", "forever", "fortitude", "frequency", "gadgetry", "galveston", "getaway", "glossary",
+    "gossamer", "graduate", "gravity", "guitarist", "hamburger", "hamilton", "handiwork",
+    "hazardous", "headwaters", "hemisphere", "hesitate", "hideaway", "holiness", "hurricane",
+    "hydraulic", "impartial", "impetus", "inception", "indigo", "inertia", "infancy", "inferno",
+    "informant", "insincere", "insurgent", "integrate", "intention", "inventive", "istanbul",
+    "jamaica", "jupiter", "leprosy", "letterhead", "liberty", "maritime", "matchmaker", "maverick",
+    "medusa", "megaton", "microscope", "microwave", "midsummer", "millionaire", "miracle",
+    "misnomer", "molasses", "molecule", "montana", "monument", "mosquito", "narrative", "nebula",
+    "newsletter", "norwegian", "october", "ohio", "onlooker", "opulent", "orlando", "outfielder",
+    "pacific", "pandemic", "pandora", "paperweight", "paragon", "paragraph", "paramount",
+    "passenger", "pedigree", "pegasus", "penetrate", "perceptive", "performance", "pharmacy",
+    "phonetic", "photograph", "pioneer", "pocketful", "politeness", "positive", "potato",
+    "processor", "provincial", "proximate", "puberty", "publisher", "pyramid", "quantity",
+    "racketeer", "rebellion", "recipe", "recover", "repellent", "replica", "reproduce", "resistor",
+    "responsive", "retraction", "retrieval", "retrospect", "revenue", "revival", "revolver",
+    "sandalwood", "sardonic", "saturday", "savagery", "scavenger", "sensation", "sociable",
+    "souvenir", "specialist", "speculate", "stethoscope", "stupendous", "supportive", "surrender",
+    "suspicious", "sympathy", "tambourine", "telephone", "therapist", "tobacco", "tolerance",
+    "tomorrow", "torpedo", "tradition", "travesty", "trombonist", "truncated", "typewriter",
+    "ultimate", "undaunted", "underfoot", "unicorn", "unify", "universe", "unravel", "upcoming",
+    "vacancy", "vagabond", "vertigo", "virginia", "visitor", "vocalist", "voyager", "warranty",
+    "waterloo", "whimsical", "wichita", "wilmington", "wyoming", "yesteryear", "yucatan",
+];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct KeyFingerprint {
+    pub key_id: String,
+    /// 32 uppercase hex digits in groups of four
+    pub hex: String,
+    /// One word per fingerprint byte
+    pub words: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FingerprintComparison {
+    pub matches: bool,
+    /// Byte (and word) positions that differ, counted from 0
+    pub mismatches: Vec<usize>,
+}
+
+/// Encode bytes as alternating even/odd list words
+pub fn to_words(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            let list = if i % 2 == 0 { &EVEN_WORDS } else { &ODD_WORDS };
+            list[b as usize].to_string()
+        })
+        .collect()
+}
+
+/// Decode words produced by `to_words`, ignoring case and separators
+pub fn from_words(words: &str) -> Result<Vec<u8>, AppError> {
+    words
+        .split(|c: char| c.is_whitespace() || matches!(c, '-' | ',' | '.'))
+        .filter(|w| !w.is_empty())
+        .enumerate()
+        .map(|(i, word)| {
+            let word = word.to_lowercase();
+            let (list, other) = if i % 2 == 0 {
+                (&EVEN_WORDS, &ODD_WORDS)
+            } else {
+                (&ODD_WORDS, &EVEN_WORDS)
+            };
+            match list.iter().position(|w| *w == word) {
+                Some(b) => Ok(b as u8),
+                None if other.contains(&word.as_str()) => Err(AppError::Validation(format!(
+                    "Word {} ('{}') is out of place; a word may be missing or repeated",
+                    i + 1,
+                    word
+                ))),
+                None => Err(AppError::Validation(format!("Word {} ('{}') is not a fingerprint word", i + 1, word))),
+            }
+        })
+        .collect()
+}
+
+/// Parse a fingerprint given as hex (any grouping) or as words
+pub fn parse_fingerprint(given: &str) -> Result<Vec<u8>, AppError> {
+    let compact: String = given
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '-' | ':'))
+        .collect();
+    if compact.is_empty() {
+        return Err(AppError::Validation("Fingerprint is empty".into()));
+    }
+    if compact.chars().all(|c| c.is_ascii_hexdigit()) {
+        return hex::decode(&compact).map_err(|_| AppError::Validation("Fingerprint has an odd number of digits".into()));
+    }
+    from_words(given)
+}
+
+pub fn key_fingerprint(bundle: &PublicBundle) -> KeyFingerprint {
+    KeyFingerprint {
+        key_id: bundle.derived_key_id(),
+        hex: bundle.fingerprint(),
+        words: to_words(&bundle.fingerprint_bytes()),
+    }
+}
+
+/// Compare `given` against the fingerprint of `bundle`, position by position
+pub fn compare(bundle: &PublicBundle, given: &str) -> Result<FingerprintComparison, AppError> {
+    let expected = bundle.fingerprint_bytes();
+    let given = parse_fingerprint(given)?;
+    let mismatches: Vec<usize> = (0..expected.len().max(given.len()))
+        .filter(|&i| expected.get(i) != given.get(i))
+        .collect();
+    Ok(FingerprintComparison {
+        matches: mismatches.is_empty(),
+        mismatches,
+    })
+}
+
+/// The bundle of our own keypair, a raw bundle or a contact, exactly one of them
+fn resolve_bundle(
+    keypair_handle: Option<KeypairHandle>,
+    bundle: Option<PublicBundle>,
+    contact_id: Option<String>,
+) -> Result<PublicBundle, AppError> {
+    match keypair_handle {
+        Some(handle) if bundle.is_none() && contact_id.is_none() => {
+            with_keypair(handle, |kp| Ok(kp.public_bundle())).map_err(|e| AppError::Validation(e.to_string()))
+        }
+        Some(_) => Err(AppError::Validation(
+            "Pass either a keypair handle, a public bundle or a contact id".into(),
+        )),
+        None => resolve_recipient(bundle, contact_id.as_deref()),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Fingerprint of our own key, a public bundle or a contact, as hex and words
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_key_fingerprint(
+    keypair_handle: Option<KeypairHandle>,
+    bundle: Option<PublicBundle>,
+    contact_id: Option<String>,
+) -> Result<KeyFingerprint, AppError> {
+    Ok(key_fingerprint(&resolve_bundle(keypair_handle, bundle, contact_id)?))
+}
+
+/// Check a fingerprint read back by the peer, as hex or words, against a
+/// public bundle or a contact
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn compare_fingerprint(
+    fingerprint: String,
+    bundle: Option<PublicBundle>,
+    contact_id: Option<String>,
+) -> Result<FingerprintComparison, AppError> {
+    compare(&resolve_recipient(bundle, contact_id.as_deref())?, &fingerprint)
+}
@@ -0,0 +1,520 @@
+//! LAN Gallery Server
+//!
+//! `serve_gallery` shares one album with other devices on the local network:
+//! a small read-only HTTPS server that shows the album in a browser and
+//! offers the same as JSON.
+//!
+//! - `GET /` - a page showing the album
+//! - `GET /api/album` - the album's photos, with captions and capture dates
+//! - `GET /api/photos/{id}` - a photo, decrypted for encrypted albums
+//!
+//! Every request needs HTTP basic auth as `GALLERY_USERNAME` with the
+//! password given when starting; failed attempts are answered slowly, and
+//! an address that fails `MAX_FAILED_ATTEMPTS` times in a row is locked out,
+//! for longer with each lockout. The
+//! server uses a self-signed certificate made for the session, so browsers
+//! warn about it: the certificate's SHA-256 fingerprint is returned to
+//! compare against the one the browser shows.
+//!
+//! The photo list is taken when the server starts. Photos are fetched and
+//! verified as for `download_photo`, through the download cache, and are
+//! decrypted in memory only. One gallery runs at a time; starting another
+//! stops it and waits for its listener to close, so the port can be reused.
+
+use axum::extract::{ConnectInfo, Path, Request, State};
+use axum::http::header::{
+    CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_SECURITY_POLICY, CONTENT_TYPE, REFERRER_POLICY, RETRY_AFTER,
+    WWW_AUTHENTICATE, X_CONTENT_TYPE_OPTIONS,
+};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::State as TauriState;
+use zeroize::Zeroizing;
+
+use crate::album::{album_key_for, open_album_photo};
+use crate::crypto::KeypairHandle;
+use crate::download_cache::load_photo;
+use crate::github::{list_shards, validate_repo, AppError, HttpClient, PhotoItem};
+use crate::sharing::album_id;
+use crate::video::MediaType;
+use crate::util::now_secs;
+
+pub const GALLERY_USERNAME: &str = "vortex";
+pub const DEFAULT_PORT: u16 = 8443;
+const MIN_PASSWORD_LEN: usize = 8;
+const FAILED_AUTH_DELAY: Duration = Duration::from_millis(750);
+/// Failed logins in a row before an address is locked out
+pub const MAX_FAILED_ATTEMPTS: u32 = 5;
+/// First lockout; each further one doubles, up to `MAX_LOCKOUT`
+pub const LOCKOUT: Duration = Duration::from_secs(60);
+pub const MAX_LOCKOUT: Duration = Duration::from_secs(3600);
+/// Addresses remembered at once; unlocked ones are forgotten past this
+const TRACKED_ADDRESSES: usize = 1024;
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+const GALLERY_PAGE: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Vortex gallery</title>
+<style>
+body { margin: 0; font-family: system-ui, sans-serif; background: #111; color: #eee; }
+h1 { font-size: 1.2rem; padding: 1rem; margin: 0; }
+main { display: grid; grid-template-columns: repeat(auto-fill, minmax(200px, 1fr)); gap: 4px; padding: 4px; }
+figure { margin: 0; }
+img, video { width: 100%; height: 200px; object-fit: cover; display: block; background: #222; }
+figcaption { font-size: 0.8rem; padding: 0.25rem; color: #aaa; }
+</style>
+</head>
+<body>
+<h1 id="title"></h1>
+<main id="photos"></main>
+<script>
+fetch("/api/album").then(r => r.json()).then(album => {
+  document.getElementById("title").textContent = album.name;
+  const main = document.getElementById("photos");
+  for (const photo of album.photos) {
+    const figure = document.createElement("figure");
+    const link = document.createElement("a");
+    link.href = "/api/photos/" + photo.id;
+    const media = document.createElement(photo.media_type === "video" ? "video" : "img");
+    media.src = link.href;
+    if (photo.media_type === "video") { media.controls = true; media.preload = "none"; } else { media.loading = "lazy"; }
+    media.title = photo.name;
+    link.appendChild(media);
+    figure.appendChild(link);
+    const caption = document.createElement("figcaption");
+    caption.textContent = photo.caption || photo.name;
+    figure.appendChild(caption);
+    main.appendChild(figure);
+  }
+});
+</script>
+</body>
+</html>
+"#;
+
+lazy_static::lazy_static! {
+    static ref RUNNING: Mutex<Option<RunningGallery>> = Mutex::new(None);
+}
+
+struct RunningGallery {
+    handle: Handle,
+    server: tauri::async_runtime::JoinHandle<()>,
+    info: GalleryInfo,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GalleryInfo {
+    pub album: String,
+    /// Addresses to open on another device
+    pub urls: Vec<String>,
+    pub port: u16,
+    pub username: String,
+    /// SHA-256 of the session's certificate, as browsers show it
+    pub fingerprint: String,
+    pub photos: usize,
+    /// Seconds since the epoch
+    pub started_at: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GalleryPhoto {
+    pub id: usize,
+    pub name: String,
+    pub media_type: MediaType,
+    pub size: Option<u64>,
+    pub caption: Option<String>,
+    /// `YYYY:MM:DD HH:MM:SS` in the camera's local time
+    pub taken_at: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GalleryListing {
+    pub name: String,
+    pub photos: Vec<GalleryPhoto>,
+}
+
+struct Gallery {
+    client: Client,
+    repo: String,
+    token: Zeroizing<String>,
+    album: String,
+    keypair_handle: Option<KeypairHandle>,
+    photos: Vec<PhotoItem>,
+    /// BLAKE3 of `user:password`; `blake3::Hash` compares in constant time
+    credentials: blake3::Hash,
+    throttle: Mutex<AuthThrottle>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct FailedLogins {
+    /// Failures since the last successful login
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+/// What became of a login attempt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthOutcome {
+    Accepted,
+    /// Wrong credentials; `Some` with the lockout this failure started
+    Rejected(Option<Duration>),
+    /// Not checked: the address is locked out for this long
+    LockedOut(Duration),
+}
+
+/// Failed logins per client address
+#[derive(Debug, Default)]
+pub struct AuthThrottle {
+    clients: HashMap<IpAddr, FailedLogins>,
+}
+
+impl AuthThrottle {
+    /// Time left on `ip`'s lockout, if it is locked out
+    pub fn locked_for(&self, ip: IpAddr, now: Instant) -> Option<Duration> {
+        let until = self.clients.get(&ip)?.locked_until?;
+        (until > now).then(|| until - now)
+    }
+
+    /// Check `ip`'s lockout, run `verify` and count its result in one step.
+    /// Callers hold the throttle's lock throughout, so parallel requests are
+    /// checked one after another and none gets past a lockout that an
+    /// earlier one started.
+    pub fn attempt(&mut self, ip: IpAddr, now: Instant, verify: impl FnOnce() -> bool) -> AuthOutcome {
+        if let Some(wait) = self.locked_for(ip, now) {
+            return AuthOutcome::LockedOut(wait);
+        }
+        if verify() {
+            self.record_success(ip);
+            AuthOutcome::Accepted
+        } else {
+            AuthOutcome::Rejected(self.record_failure(ip, now))
+        }
+    }
+
+    /// Count a failed login from `ip`. Every `MAX_FAILED_ATTEMPTS`th failure
+    /// locks it out; the lockout is returned.
+    fn record_failure(&mut self, ip: IpAddr, now: Instant) -> Option<Duration> {
+        if !self.clients.contains_key(&ip) && self.clients.len() >= TRACKED_ADDRESSES {
+            self.clients.retain(|_, client| client.locked_until.is_some_and(|until| until > now));
+        }
+        let client = self.clients.entry(ip).or_default();
+        client.failures += 1;
+        if client.failures % MAX_FAILED_ATTEMPTS != 0 {
+            return None;
+        }
+        let lockouts = client.failures / MAX_FAILED_ATTEMPTS;
+        let lockout = LOCKOUT.saturating_mul(1 << (lockouts - 1).min(16)).min(MAX_LOCKOUT);
+        client.locked_until = Some(now + lockout);
+        Some(lockout)
+    }
+
+    fn record_success(&mut self, ip: IpAddr) {
+        self.clients.remove(&ip);
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+pub fn credentials_hash(username: &str, password: &str) -> blake3::Hash {
+    blake3::hash(format!("{}:{}", username, password).as_bytes())
+}
+
+/// Whether an `Authorization` header carries the expected basic credentials
+pub fn check_basic_auth(header: Option<&str>, expected: &blake3::Hash) -> bool {
+    let Some(encoded) = header.and_then(|h| h.strip_prefix("Basic ")) else {
+        return false;
+    };
+    match STANDARD.decode(encoded.trim()) {
+        Ok(decoded) => blake3::hash(&decoded) == *expected,
+        Err(_) => false,
+    }
+}
+
+/// `Content-Type` of a file by its extension
+pub fn content_type(name: &str) -> &'static str {
+    let ext = name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+    match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "heic" | "heif" => "image/heif",
+        "jxl" => "image/jxl",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "webm" => "video/webm",
+        "mkv" => "video/x-matroska",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Colon-separated uppercase hex, as browsers show fingerprints
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der).iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+/// The JSON listing of `photos`, by their position
+pub fn gallery_listing(album: &str, photos: &[PhotoItem]) -> GalleryListing {
+    GalleryListing {
+        name: album.rsplit('/').next().unwrap_or(album).to_string(),
+        photos: photos
+            .iter()
+            .enumerate()
+            .map(|(id, photo)| GalleryPhoto {
+                id,
+                name: photo.display_name.clone().unwrap_or_else(|| photo.name.clone()),
+                media_type: photo.media_type,
+                size: photo.size,
+                caption: photo.caption.clone(),
+                taken_at: photo.exif.as_ref().and_then(|exif| exif.taken_at.clone()),
+            })
+            .collect(),
+    }
+}
+
+/// This machine's address on the network other devices reach it by. The
+/// socket is only connected, nothing is sent.
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+fn locked_out(wait: Duration) -> Response {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, "Too many failed logins").into_response();
+    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(wait.as_secs().max(1)));
+    response
+}
+
+async fn require_auth(State(gallery): State<Arc<Gallery>>, request: Request, next: Next) -> Response {
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    let header = request.headers().get(axum::http::header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+    let outcome = gallery
+        .throttle
+        .lock()
+        .unwrap()
+        .attempt(ip, Instant::now(), || check_basic_auth(header, &gallery.credentials));
+    let lockout = match outcome {
+        AuthOutcome::Accepted => return next.run(request).await,
+        AuthOutcome::LockedOut(wait) => return locked_out(wait),
+        AuthOutcome::Rejected(lockout) => lockout,
+    };
+    tokio::time::sleep(FAILED_AUTH_DELAY).await;
+    if let Some(wait) = lockout {
+        tracing::warn!("Gallery locked out {} for {}s after failed logins", ip, wait.as_secs());
+        return locked_out(wait);
+    }
+    let mut response = StatusCode::UNAUTHORIZED.into_response();
+    response
+        .headers_mut()
+        .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Basic realm=\"Vortex gallery\", charset=\"UTF-8\""));
+    response
+}
+
+async fn security_headers(mut response: Response) -> Response {
+    let headers: &mut HeaderMap = response.headers_mut();
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(REFERRER_POLICY, HeaderValue::from_static("no-referrer"));
+    headers.insert(
+        CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static(
+            "default-src 'none'; img-src 'self'; media-src 'self'; connect-src 'self'; \
+             script-src 'unsafe-inline'; style-src 'unsafe-inline'",
+        ),
+    );
+    response
+}
+
+async fn gallery_page() -> Html<&'static str> {
+    Html(GALLERY_PAGE)
+}
+
+async fn album_listing(State(gallery): State<Arc<Gallery>>) -> Json<GalleryListing> {
+    Json(gallery_listing(&gallery.album, &gallery.photos))
+}
+
+async fn open_photo(gallery: &Gallery, photo: &PhotoItem) -> Result<Vec<u8>, AppError> {
+    let repo = photo.repo.as_deref().unwrap_or(&gallery.repo);
+    let path = format!("{}/{}", gallery.album, photo.name);
+    let (cached, _) = load_photo(&gallery.client, repo, &gallery.token, &path, gallery.keypair_handle, true).await?;
+    let Some(manifest) = cached.manifest else {
+        return Ok(cached.content);
+    };
+    let handle = gallery
+        .keypair_handle
+        .ok_or_else(|| AppError::Validation("Photo is encrypted; a keypair is required".into()))?;
+    let album_key = Zeroizing::new(album_key_for(handle, repo, &gallery.album, &manifest)?);
+    let (content, _) = open_album_photo(&album_key, &album_id(repo, &gallery.album), &cached.content)?;
+    Ok(content)
+}
+
+async fn photo_content(State(gallery): State<Arc<Gallery>>, Path(id): Path<usize>) -> Response {
+    let Some(photo) = gallery.photos.get(id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match open_photo(&gallery, photo).await {
+        Ok(content) => {
+            let name = photo.display_name.as_deref().unwrap_or(&photo.name);
+            let disposition = format!("inline; filename=\"{}\"", name.replace(['"', '\\', '\r', '\n'], "_"));
+            let mut response = content.into_response();
+            let headers = response.headers_mut();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type(name)));
+            headers.insert(CACHE_CONTROL, HeaderValue::from_static("private, max-age=3600"));
+            if let Ok(value) = HeaderValue::from_str(&disposition) {
+                headers.insert(CONTENT_DISPOSITION, value);
+            }
+            response
+        }
+        Err(e) => {
+            tracing::warn!("Gallery could not serve {}: {}", photo.name, e);
+            (StatusCode::BAD_GATEWAY, "Photo unavailable").into_response()
+        }
+    }
+}
+
+/// Shut the running gallery down and wait until it has closed its listener
+async fn stop_running() -> Option<GalleryInfo> {
+    let running = RUNNING.lock().unwrap().take()?;
+    running.handle.graceful_shutdown(Some(SHUTDOWN_GRACE));
+    if let Err(e) = running.server.await {
+        tracing::warn!("Gallery server task failed: {}", e);
+    }
+    Some(running.info)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Serve `album` read-only over HTTPS on `port` (`DEFAULT_PORT` unless
+/// given; 0 picks a free one), behind basic auth with `password`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn serve_gallery(
+    client: TauriState<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album: String,
+    port: Option<u16>,
+    password: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<GalleryInfo, AppError> {
+    validate_repo(&repo)?;
+    let password = Zeroizing::new(password);
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(AppError::Validation(format!(
+            "Gallery password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        )));
+    }
+    let album = album.trim_matches('/').to_string();
+    if album.is_empty() || album.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    let photos = list_shards(&client.0, &repo, &token, &album, keypair_handle).await?;
+    if photos.is_empty() {
+        return Err(AppError::Validation(format!("{} has no photos", album)));
+    }
+
+    let lan = lan_address();
+    let mut names = vec!["localhost".to_string()];
+    names.extend(lan.map(|ip| ip.to_string()));
+    let certified = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| AppError::Validation(format!("Could not create the gallery certificate: {}", e)))?;
+    let tls = RustlsConfig::from_pem(
+        certified.cert.pem().into_bytes(),
+        certified.key_pair.serialize_pem().into_bytes(),
+    )
+    .await?;
+
+    stop_running().await;
+    let listener = TcpListener::bind(("0.0.0.0", port.unwrap_or(DEFAULT_PORT)))?;
+    listener.set_nonblocking(true)?;
+    let port = listener.local_addr()?.port();
+
+    let info = GalleryInfo {
+        urls: lan
+            .into_iter()
+            .map(|ip| format!("https://{}:{}/", ip, port))
+            .chain([format!("https://localhost:{}/", port)])
+            .collect(),
+        album: album.clone(),
+        port,
+        username: GALLERY_USERNAME.to_string(),
+        fingerprint: fingerprint(certified.cert.der()),
+        photos: photos.len(),
+        started_at: now_secs(),
+    };
+
+    let gallery = Arc::new(Gallery {
+        client: client.0.as_ref().clone(),
+        repo,
+        token,
+        album,
+        keypair_handle,
+        photos,
+        credentials: credentials_hash(GALLERY_USERNAME, &password),
+        throttle: Mutex::new(AuthThrottle::default()),
+    });
+    let app = Router::new()
+        .route("/", get(gallery_page))
+        .route("/api/album", get(album_listing))
+        .route("/api/photos/:id", get(photo_content))
+        .layer(middleware::from_fn_with_state(gallery.clone(), require_auth))
+        .layer(middleware::map_response(security_headers))
+        .with_state(gallery);
+
+    let handle = Handle::new();
+    let server = axum_server::from_tcp_rustls(listener, tls).handle(handle.clone());
+    let server = tauri::async_runtime::spawn(async move {
+        if let Err(e) = server.serve(app.into_make_service_with_connect_info::<SocketAddr>()).await {
+            tracing::warn!("Gallery server stopped: {}", e);
+        }
+    });
+    *RUNNING.lock().unwrap() = Some(RunningGallery { handle, server, info: info.clone() });
+    tracing::info!("Serving {} on port {}", info.album, port);
+    Ok(info)
+}
+
+/// Stop the gallery server; `None` when none was running
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub async fn stop_gallery() -> Option<GalleryInfo> {
+    stop_running().await
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn gallery_status() -> Option<GalleryInfo> {
+    RUNNING.lock().unwrap().as_ref().map(|running| running.info.clone())
+}
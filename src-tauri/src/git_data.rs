@@ -0,0 +1,284 @@
+//! Git Data API Helpers
+//!
+//! Thin wrappers over GitHub's low-level refs/trees/commits endpoints. The
+//! contents API creates one commit per file; these helpers let callers stage
+//! many path changes and publish them as a single commit on the default branch.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::github::{response_error, AppError, Bearer};
+use crate::retry::SendWithRetry;
+
+/// One entry of a recursive tree listing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TreeEntry {
+    pub path: String,
+    pub mode: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub sha: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// A staged change for `create_tree`. `sha: None` removes the path.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TreeChange {
+    pub path: String,
+    pub mode: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub sha: Option<String>,
+}
+
+impl TreeChange {
+    pub fn delete(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            mode: "100644".to_string(),
+            kind: "blob".to_string(),
+            sha: None,
+        }
+    }
+
+    /// Point `path` at a blob by SHA with the regular file mode
+    pub fn blob(path: &str, sha: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            mode: "100644".to_string(),
+            kind: "blob".to_string(),
+            sha: Some(sha.to_string()),
+        }
+    }
+
+    /// Point `path` at an existing blob (keeps the original file mode)
+    pub fn put_blob(path: &str, entry: &TreeEntry) -> Self {
+        Self {
+            path: path.to_string(),
+            mode: entry.mode.clone(),
+            kind: "blob".to_string(),
+            sha: Some(entry.sha.clone()),
+        }
+    }
+}
+
+/// Head of a branch: the commit SHA and its root tree SHA
+#[derive(Clone, Debug)]
+pub struct BranchHead {
+    pub branch: String,
+    pub commit_sha: String,
+    pub tree_sha: String,
+}
+
+/// Blob entries of a tree keyed by path
+pub type TreeIndex = HashMap<String, TreeEntry>;
+
+pub fn index_blobs(entries: Vec<TreeEntry>) -> TreeIndex {
+    entries
+        .into_iter()
+        .filter(|e| e.kind == "blob")
+        .map(|e| (e.path.clone(), e))
+        .collect()
+}
+
+// ============================================================================
+// API Calls
+// ============================================================================
+
+pub(crate) async fn get_json(client: &Client, token: &str, url: &str, what: &str) -> Result<serde_json::Value, AppError> {
+    let res = client
+        .get(url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, &format!("Failed to {}", what)).await);
+    }
+
+    Ok(res.json().await?)
+}
+
+async fn post_json(
+    client: &Client,
+    token: &str,
+    url: &str,
+    body: &serde_json::Value,
+    what: &str,
+) -> Result<serde_json::Value, AppError> {
+    let res = client
+        .post(url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .json(body)
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, &format!("Failed to {}", what)).await);
+    }
+
+    Ok(res.json().await?)
+}
+
+pub async fn default_branch(client: &Client, repo: &str, token: &str) -> Result<String, AppError> {
+    let url = format!("https://api.github.com/repos/{}", repo);
+    let json = get_json(client, token, &url, "get repository info").await?;
+    Ok(json["default_branch"].as_str().unwrap_or("main").to_string())
+}
+
+/// Resolve the current head of the repository's default branch
+pub async fn branch_head(client: &Client, repo: &str, token: &str) -> Result<BranchHead, AppError> {
+    let branch = default_branch(client, repo, token).await?;
+
+    let url = format!("https://api.github.com/repos/{}/git/ref/heads/{}", repo, branch);
+    let json = get_json(client, token, &url, "get branch ref").await?;
+    let commit_sha = json["object"]["sha"]
+        .as_str()
+        .ok_or_else(|| AppError::Api("Branch ref has no commit".into()))?
+        .to_string();
+
+    let tree_sha = commit_tree(client, repo, token, &commit_sha).await?;
+
+    Ok(BranchHead { branch, commit_sha, tree_sha })
+}
+
+/// Root tree SHA of a commit
+pub async fn commit_tree(client: &Client, repo: &str, token: &str, commit_sha: &str) -> Result<String, AppError> {
+    let url = format!("https://api.github.com/repos/{}/git/commits/{}", repo, commit_sha);
+    let json = get_json(client, token, &url, "get commit").await?;
+    json["tree"]["sha"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| AppError::Api("Commit has no tree".into()))
+}
+
+/// List every entry of a tree recursively
+pub async fn get_tree_recursive(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    tree_sha: &str,
+) -> Result<Vec<TreeEntry>, AppError> {
+    let url = format!("https://api.github.com/repos/{}/git/trees/{}?recursive=1", repo, tree_sha);
+    let json = get_json(client, token, &url, "get tree").await?;
+
+    if json["truncated"].as_bool().unwrap_or(false) {
+        return Err(AppError::Api("Repository tree is too large to list in one request".into()));
+    }
+
+    serde_json::from_value(json["tree"].clone())
+        .map_err(|e| AppError::Api(format!("Invalid tree response: {}", e)))
+}
+
+/// Read a blob's raw content
+pub async fn get_blob(client: &Client, repo: &str, token: &str, sha: &str) -> Result<Vec<u8>, AppError> {
+    let url = format!("https://api.github.com/repos/{}/git/blobs/{}", repo, sha);
+    let json = get_json(client, token, &url, "get blob").await?;
+
+    let content: String = json["content"]
+        .as_str()
+        .ok_or_else(|| AppError::Api("Blob response has no content".into()))?
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    STANDARD
+        .decode(content)
+        .map_err(|_| AppError::Api("Invalid blob encoding".into()))
+}
+
+/// Upload raw content as a blob and return its SHA
+pub async fn create_blob(client: &Client, repo: &str, token: &str, content: &[u8]) -> Result<String, AppError> {
+    let url = format!("https://api.github.com/repos/{}/git/blobs", repo);
+    let body = serde_json::json!({ "content": STANDARD.encode(content), "encoding": "base64" });
+    let json = post_json(client, token, &url, &body, "create blob").await?;
+
+    json["sha"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| AppError::Api("Blob response has no sha".into()))
+}
+
+/// Create a new tree on top of `base_tree` and return its SHA
+pub async fn create_tree(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    base_tree: &str,
+    changes: &[TreeChange],
+) -> Result<String, AppError> {
+    let url = format!("https://api.github.com/repos/{}/git/trees", repo);
+    let body = serde_json::json!({ "base_tree": base_tree, "tree": changes });
+    let json = post_json(client, token, &url, &body, "create tree").await?;
+
+    json["sha"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| AppError::Api("Tree response has no sha".into()))
+}
+
+pub async fn create_commit(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    message: &str,
+    tree_sha: &str,
+    parent_sha: &str,
+) -> Result<String, AppError> {
+    let url = format!("https://api.github.com/repos/{}/git/commits", repo);
+    let body = serde_json::json!({ "message": message, "tree": tree_sha, "parents": [parent_sha] });
+    let json = post_json(client, token, &url, &body, "create commit").await?;
+
+    json["sha"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| AppError::Api("Commit response has no sha".into()))
+}
+
+/// Fast-forward a branch to `commit_sha`. Fails if the branch moved meanwhile.
+pub async fn update_branch(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    branch: &str,
+    commit_sha: &str,
+) -> Result<(), AppError> {
+    let url = format!("https://api.github.com/repos/{}/git/refs/heads/{}", repo, branch);
+    let res = client
+        .patch(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .json(&serde_json::json!({ "sha": commit_sha, "force": false }))
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "Failed to update branch").await);
+    }
+
+    Ok(())
+}
+
+/// Apply `changes` on top of `head` as a single commit and advance the branch.
+/// Returns the new commit SHA.
+pub async fn commit_changes(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    head: &BranchHead,
+    changes: &[TreeChange],
+    message: &str,
+) -> Result<String, AppError> {
+    let tree = create_tree(client, repo, token, &head.tree_sha, changes).await?;
+    let commit = create_commit(client, repo, token, message, &tree, &head.commit_sha).await?;
+    update_branch(client, repo, token, &head.branch, &commit).await?;
+    Ok(commit)
+}
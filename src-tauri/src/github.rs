@@ -4,19 +4,51 @@
 
 use base64::{engine::general_purpose::STANDARD, Engine};
 use image::ImageFormat;
+use reqwest::header::{HeaderValue, InvalidHeaderValue};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::io::Cursor;
 use std::sync::Arc;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{Emitter, State};
 use thiserror::Error;
 use tokio::fs;
-use tokio::time::sleep;
+use zeroize::Zeroizing;
 
+use crate::AppHandle;
 use crate::compress::{compress_file_data, ItemCompressionSettings, Algorithm, CompressedFileData};
-use crate::crypto::{encrypt, decrypt_with_keypair_bytes, PublicBundle, EncryptedFileData, EncryptedPayload, encrypt_with_password};
+use crate::crypto::{encrypt, decrypt_with_keypair_bytes, PublicBundle, EncryptedFileData, EncryptedPayload, encrypt_with_password, HybridKeypair, KeypairHandle, SecretBytes, CryptoError};
+use crate::album::{album_key_for, fetch_manifest, open_album_photo, open_filename, parent_album_path, ALBUM_MANIFEST_FILE, ENCRYPTED_BLOB_EXT};
+use crate::sharing::album_id;
+use crate::sharding::{resolve_upload_repo, shard_repos};
+use crate::git_data::get_blob;
+use crate::lfs::{put_lfs_file, resolve_lfs_pointer};
+use crate::metadata_vault::{ExifExtract, MetadataVault, VAULT_FILE};
+use crate::mirror::{replicate_delete, replicate_put};
+use crate::retry::SendWithRetry;
+use crate::revocation::ensure_not_revoked;
+use crate::security_verify::{
+    check_album_photo, check_manifest, check_photo, ensure_intact, fetch_photo_signature, put_photo_signature,
+    sign_photo, IntegrityCheck, TrustedSigners, SIGNATURE_EXT,
+};
+use crate::pipeline::{pipeline_context, process_pipeline_for_file, PipelineConfig, PipelineContext};
+use crate::pipeline_history::{record_run, PipelineRun};
+use crate::pipeline_routing::{originals_album, upload_intent, Router};
+use crate::catalog::{cached_listing, forget_photo, reconcile_listing, CatalogUpdate, CATALOG_UPDATED_EVENT};
+use crate::smart_albums::notify_smart_albums;
+use crate::download_cache::{load_photo, CachedPhoto};
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::ipfs::{fetch_pinned_photo, github_unreachable};
+use crate::webdav::fetch_webdav_photo;
+use crate::classify::{category_counts, classify, excluded_paths, ClassifiedFile, PhotoCategory, PhotoFacts};
+use crate::heif::{convert_heif, converted_name, is_heif, HeifConversion};
+use crate::raw::{is_raw_file, pair_photos, raw_pairs};
+use crate::upload_policy::{check_upload, UploadIntent};
+use crate::video::{is_video_file, put_video, record_media, MediaType};
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::batch::{plan_delete, run_plan};
+use crate::jobs::{cancelled, Job, JobCommand};
 
 /// Upload processing settings - allows per-item customization
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -25,6 +57,9 @@ pub struct UploadProcessingSettings {
     pub compression: CompressionConfig,
     /// Encryption configuration  
     pub encryption: EncryptionConfig,
+    /// Convert HEIF/HEIC photos to JPEG or WebP before processing
+    #[serde(default)]
+    pub convert_heif: Option<HeifConversion>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -60,6 +95,7 @@ impl Default for UploadProcessingSettings {
                 use_password: false,
                 use_keypair: true,
             },
+            convert_heif: None,
         }
     }
 }
@@ -68,11 +104,8 @@ pub struct GithubConfig {
     pub client_id: String,
 }
 
-const MAX_RETRIES: u32 = 3;
-const INITIAL_RETRY_DELAY_MS: u64 = 1000;
 const UPLOAD_TIMEOUT_SECS: u64 = 120;
-const LFS_UPLOAD_TIMEOUT_SECS: u64 = 300;
-const LFS_THRESHOLD_BYTES: u64 = 50 * 1024 * 1024;
+pub(crate) const LFS_THRESHOLD_BYTES: u64 = 50 * 1024 * 1024;
 const HTTP_POOL_SIZE: usize = 5;
 const DEFAULT_TIMEOUT_SECS: u64 = 30;
 
@@ -86,6 +119,78 @@ pub enum AppError {
     Validation(String),
     #[error("API error: {0}")]
     Api(String),
+    #[error("Backend unavailable, retry in {0}s")]
+    Unavailable(u64),
+    #[error("{0}")]
+    Github(GithubError),
+}
+
+/// Error shape sent to the frontend. Tagged by `kind` so the UI can branch on
+/// the failure (re-authenticate, wait for the rate limit, refresh and retry)
+/// instead of matching on message text.
+#[derive(Error, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GithubError {
+    #[error("{message}")]
+    NotFound { message: String },
+    /// Missing, expired or revoked token
+    #[error("{message}")]
+    Unauthorized { message: String },
+    /// Token lacks the required scope or repository access
+    #[error("{message}")]
+    Forbidden { message: String },
+    #[error("{message} (retry in {retry_after}s)")]
+    RateLimited { retry_after: u64, message: String },
+    /// The file changed remotely since it was read (stale SHA)
+    #[error("{message}")]
+    Conflict { message: String },
+    #[error("{message}")]
+    NetworkTimeout { message: String },
+    #[error("{message}")]
+    Network { message: String },
+    #[error("{message}")]
+    TooLarge { message: String },
+    #[error("{message}")]
+    Validation { message: String },
+    /// The circuit breaker is open for the backend
+    #[error("{message}")]
+    Unavailable { retry_after: u64, message: String },
+    #[error("{message}")]
+    Server { status: u16, message: String },
+    #[error("{message}")]
+    Api { status: Option<u16>, message: String },
+    #[error("{message}")]
+    Io { message: String },
+    /// Stopped by `cancel_job` (see `jobs`)
+    #[error("{message}")]
+    Cancelled { message: String },
+}
+
+impl From<GithubError> for AppError {
+    fn from(e: GithubError) -> Self {
+        AppError::Github(e)
+    }
+}
+
+impl From<CryptoError> for AppError {
+    fn from(e: CryptoError) -> Self {
+        AppError::Validation(e.to_string())
+    }
+}
+
+impl From<&AppError> for GithubError {
+    fn from(e: &AppError) -> Self {
+        let message = e.to_string();
+        match e {
+            AppError::Network(err) if err.is_timeout() => GithubError::NetworkTimeout { message },
+            AppError::Network(_) => GithubError::Network { message },
+            AppError::Io(_) => GithubError::Io { message },
+            AppError::Validation(_) => GithubError::Validation { message },
+            AppError::Api(_) => GithubError::Api { status: None, message },
+            AppError::Unavailable(secs) => GithubError::Unavailable { retry_after: *secs, message },
+            AppError::Github(err) => err.clone(),
+        }
+    }
 }
 
 impl Serialize for AppError {
@@ -93,10 +198,55 @@ impl Serialize for AppError {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        GithubError::from(self).serialize(serializer)
+    }
+}
+
+/// Map an unsuccessful HTTP status to a typed error
+pub fn classify_status(status: u16, retry_after: Option<u64>, message: String) -> GithubError {
+    match status {
+        401 => GithubError::Unauthorized { message },
+        403 => match retry_after {
+            Some(retry_after) => GithubError::RateLimited { retry_after, message },
+            None => GithubError::Forbidden { message },
+        },
+        404 => GithubError::NotFound { message },
+        409 => GithubError::Conflict { message },
+        // GitHub reports a stale blob SHA on the contents API as 422
+        422 if message.to_lowercase().contains("sha") => GithubError::Conflict { message },
+        422 => GithubError::Validation { message },
+        413 => GithubError::TooLarge { message },
+        429 => GithubError::RateLimited {
+            retry_after: retry_after.unwrap_or(60),
+            message,
+        },
+        408 | 504 => GithubError::NetworkTimeout { message },
+        500..=599 => GithubError::Server { status, message },
+        _ => GithubError::Api {
+            status: Some(status),
+            message,
+        },
     }
 }
 
+/// Turn a non-success response into a typed error, preferring GitHub's
+/// JSON `message` over the raw body
+pub(crate) async fn response_error(res: reqwest::Response, context: &str) -> AppError {
+    let status = res.status().as_u16();
+    let retry_after = crate::retry::retry_after(&res);
+    let body = res.text().await.unwrap_or_default();
+    let detail = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(str::to_string))
+        .unwrap_or(body);
+    let message = if detail.trim().is_empty() {
+        format!("{} ({})", context, status)
+    } else {
+        format!("{} ({}): {}", context, status, detail.trim())
+    };
+    classify_status(status, retry_after, message).into()
+}
+
 pub struct HttpClient(pub Arc<Client>);
 
 impl HttpClient {
@@ -123,56 +273,6 @@ impl Default for HttpClient {
     }
 }
 
-async fn retry_with_backoff<F, Fut, T, E>(
-    mut operation: F,
-    max_retries: u32,
-    initial_delay_ms: u64,
-) -> Result<T, E>
-where
-    F: FnMut() -> Fut,
-    Fut: std::future::Future<Output = Result<T, E>>,
-    E: std::fmt::Display,
-{
-    let mut attempt = 0;
-    let mut delay = initial_delay_ms;
-    
-    loop {
-        match operation().await {
-            Ok(result) => return Ok(result),
-            Err(e) => {
-                attempt += 1;
-                if attempt >= max_retries {
-                    return Err(e);
-                }
-                
-                let jitter = rand::random::<u64>() % (delay / 2);
-                sleep(Duration::from_millis(delay + jitter)).await;
-                delay *= 2;
-            }
-        }
-    }
-}
-
-#[inline]
-fn is_retryable_status(status: reqwest::StatusCode) -> bool {
-    matches!(
-        status,
-        reqwest::StatusCode::TOO_MANY_REQUESTS
-            | reqwest::StatusCode::INTERNAL_SERVER_ERROR
-            | reqwest::StatusCode::BAD_GATEWAY
-            | reqwest::StatusCode::SERVICE_UNAVAILABLE
-            | reqwest::StatusCode::GATEWAY_TIMEOUT
-            | reqwest::StatusCode::REQUEST_TIMEOUT
-    )
-}
-
-fn get_retry_after(headers: &reqwest::header::HeaderMap) -> Option<u64> {
-    headers
-        .get(reqwest::header::RETRY_AFTER)
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse::<u64>().ok())
-}
-
 #[derive(Serialize, Deserialize)]
 pub struct DeviceCodeResponse {
     pub device_code: String,
@@ -208,7 +308,23 @@ pub struct UploadProgress {
     pub percent: u8,
 }
 
-fn validate_repo(repo: &str) -> Result<(), AppError> {
+/// `Authorization: Bearer <token>` header value. The formatted string is
+/// wiped after parsing and the header is marked sensitive, so it is left
+/// out of debug output.
+pub(crate) struct Bearer<'a>(pub &'a str);
+
+impl TryFrom<Bearer<'_>> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(bearer: Bearer<'_>) -> Result<Self, Self::Error> {
+        let formatted = Zeroizing::new(format!("Bearer {}", bearer.0));
+        let mut value = HeaderValue::from_str(&formatted)?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+pub(crate) fn validate_repo(repo: &str) -> Result<(), AppError> {
     let parts: Vec<&str> = repo.split('/').collect();
     if parts.len() != 2 || parts.iter().any(|p| p.is_empty() || p.contains("..")) {
         return Err(AppError::Validation("Invalid repo format. Use owner/repo".into()));
@@ -216,7 +332,7 @@ fn validate_repo(repo: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-fn sanitize_filename(name: &str) -> String {
+pub(crate) fn sanitize_filename(name: &str) -> String {
     name.replace("..", "")
         .replace('/', "_")
         .replace('\\', "_")
@@ -226,6 +342,7 @@ fn sanitize_filename(name: &str) -> String {
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn start_oauth(
     client: State<'_, HttpClient>,
     config: State<'_, GithubConfig>,
@@ -235,17 +352,18 @@ pub async fn start_oauth(
         .post("https://github.com/login/device/code")
         .header("Accept", "application/json")
         .form(&[("client_id", config.client_id.as_str()), ("scope", "repo")])
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        return Err(AppError::Api(format!("OAuth failed: {}", res.status())));
+        return Err(response_error(res, "OAuth failed").await);
     }
 
     Ok(res.json().await?)
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn poll_oauth(
     client: State<'_, HttpClient>,
     device_code: String,
@@ -260,7 +378,7 @@ pub async fn poll_oauth(
             ("device_code", device_code.as_str()),
             ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
         ])
-        .send()
+        .send_with_retry()
         .await?;
 
     let token_res: TokenResponse = res.json().await?;
@@ -276,20 +394,21 @@ pub async fn poll_oauth(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn get_user(
     client: State<'_, HttpClient>,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<GitHubUser, AppError> {
     let res = client
         .0
         .get("https://api.github.com/user")
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        return Err(AppError::Api("Invalid token".into()));
+        return Err(response_error(res, "Invalid token").await);
     }
 
     Ok(res.json().await?)
@@ -304,16 +423,17 @@ pub struct TokenValidation {
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn validate_token(
     client: State<'_, HttpClient>,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<TokenValidation, AppError> {
     let res = client
         .0
         .get("https://api.github.com/user")
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
@@ -404,6 +524,7 @@ async fn prepare_upload_payload(
             compressed_size: content.len(),
             ratio: 1.0,
             checksum: blake3::hash(content).as_bytes().to_vec(),
+            segmented: false,
         };
         
         let _ = app.emit("upload-progress", UploadProgress {
@@ -492,30 +613,55 @@ async fn prepare_upload_payload(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_photo(
     app: AppHandle,
     client: State<'_, HttpClient>,
     path: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
     filename: String,
     upload_id: String,
     public_bundle: Option<PublicBundle>,
     password: Option<String>,
     settings: Option<UploadProcessingSettings>,
+    keypair_handle: Option<KeypairHandle>,
 ) -> Result<UploadResult, AppError> {
     validate_repo(&repo)?;
+    let repo = resolve_upload_repo(&client.0, &repo, &token).await?;
     let safe_filename = sanitize_filename(&filename);
 
     if safe_filename.is_empty() {
         return Err(AppError::Validation("Invalid filename".into()));
     }
 
+    let _permit = SCHEDULER.acquire(JobClass::Sync).await;
     let content = fs::read(&path).await?;
 
     // Use provided settings or defaults
     let processing_settings = settings.unwrap_or_default();
 
+    let (content, safe_filename) = match processing_settings.convert_heif.clone() {
+        Some(conversion) if is_heif(&content) => {
+            let name = converted_name(&safe_filename, conversion.format);
+            let (converted, _) = tauri::async_runtime::spawn_blocking(move || convert_heif(&content, &conversion))
+                .await
+                .map_err(|e| AppError::Validation(format!("Conversion task failed: {}", e)))??;
+            (converted, name)
+        }
+        _ => (content, safe_filename),
+    };
+
+    check_upload(
+        &safe_filename,
+        &content,
+        UploadIntent {
+            encrypted: processing_settings.encryption.enabled,
+            strips_metadata: false,
+        },
+    )?;
+
     // Validate encryption requirements
     if processing_settings.encryption.enabled {
         if processing_settings.encryption.use_keypair && public_bundle.is_none() {
@@ -540,7 +686,10 @@ pub async fn upload_photo(
         &upload_id
     ).await?;
 
-    upload_to_github(
+    // Signed before the payload is handed off, so it is never held twice
+    let signature = keypair_handle.map(|h| sign_photo(h, &final_payload)).transpose()?;
+
+    let result = upload_to_github(
         &app,
         &client.0,
         final_payload,
@@ -549,7 +698,13 @@ pub async fn upload_photo(
         &safe_filename,
         &upload_id,
     )
-    .await
+    .await?;
+
+    if let Some(signature) = signature {
+        put_photo_signature(&client.0, &repo, &token, &format!("photos/{}", safe_filename), &signature).await?;
+    }
+    record_activity(ActivityKind::Upload, "upload_photo", Some(&repo), &format!("photos/{}", safe_filename), None);
+    Ok(result)
 }
 
 async fn upload_to_github(
@@ -568,7 +723,6 @@ async fn upload_to_github(
     }
 
     let encoded = STANDARD.encode(&payload);
-    drop(payload);
 
     let upload_path = format!("photos/{}", filename);
     let url = format!("https://api.github.com/repos/{}/contents/{}", repo, upload_path);
@@ -581,11 +735,11 @@ async fn upload_to_github(
     let res = client
         .put(&url)
         .timeout(Duration::from_secs(UPLOAD_TIMEOUT_SECS))
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
         .json(&body)
-        .send()
+        .send_with_retry()
         .await?;
 
     let _ = app.emit("upload-progress", UploadProgress {
@@ -596,11 +750,12 @@ async fn upload_to_github(
     });
 
     if !res.status().is_success() {
-        let status = res.status();
-        let err_text = res.text().await.map_err(|e| AppError::Api(format!("Failed to read error response body: {}", e)))?;
-        return Err(AppError::Api(format!("Upload failed ({}): {}", status, err_text)));
+        return Err(response_error(res, "Upload failed").await);
     }
 
+    replicate_put(client, repo, token, &upload_path, &payload);
+    drop(payload);
+
     let json: serde_json::Value = res.json().await?;
 
     Ok(UploadResult {
@@ -668,58 +823,9 @@ async fn upload_lfs_internal(
         percent: 10,
     });
 
-    let mut hasher = Sha256::new();
-    hasher.update(&content);
-    let oid = format!("{:x}", hasher.finalize());
-
-    let _ = app.emit("upload-progress", UploadProgress {
-        id: upload_id.to_string(),
-        bytes_sent: 0,
-        total_bytes,
-        percent: 20,
-    });
-
-    let batch_url = format!("https://github.com/{}.git/info/lfs/objects/batch", repo);
-    let batch_body = serde_json::json!({
-        "operation": "upload",
-        "transfers": ["basic"],
-        "objects": [{ "oid": oid, "size": total_bytes }]
-    });
-
-    let batch_res = client
-        .post(&batch_url)
-        .timeout(std::time::Duration::from_secs(60))
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Accept", "application/vnd.git-lfs+json")
-        .header("Content-Type", "application/vnd.git-lfs+json")
-        .json(&batch_body)
-        .send()
-        .await?;
-
-    if !batch_res.status().is_success() {
-        return Err(AppError::Api(format!("LFS batch failed: {}", batch_res.status())));
-    }
-
-    let batch_json: serde_json::Value = batch_res.json().await?;
-
-    let upload_href = batch_json["objects"][0]["actions"]["upload"]["href"]
-        .as_str()
-        .ok_or_else(|| AppError::Api("No LFS upload URL returned".into()))?;
-
-    let _ = app.emit("upload-progress", UploadProgress {
-        id: upload_id.to_string(),
-        bytes_sent: 0,
-        total_bytes,
-        percent: 30,
-    });
-
-    let upload_res = client
-        .put(upload_href)
-        .timeout(Duration::from_secs(LFS_UPLOAD_TIMEOUT_SECS))
-        .header("Content-Type", "application/octet-stream")
-        .body(content)
-        .send()
-        .await?;
+    let upload_path = format!("photos/{}", filename);
+    let message = format!("Upload {} (secure, LFS)", filename);
+    let result = put_lfs_file(client, repo, token, &upload_path, content, &message).await?;
 
     let _ = app.emit("upload-progress", UploadProgress {
         id: upload_id.to_string(),
@@ -728,21 +834,39 @@ async fn upload_lfs_internal(
         percent: 100,
     });
 
-    if !upload_res.status().is_success() {
-        return Err(AppError::Api(format!("LFS upload failed: {}", upload_res.status())));
-    }
-
-    Ok(UploadResult {
-        url: format!("https://github.com/{}/blob/main/photos/{}", repo, filename),
-        sha: oid,
-    })
+    Ok(result)
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PhotoItem {
     pub name: String,
     pub url: String,
     pub sha: String,
+    /// Blob size in bytes, when the listing reported it
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Set for blobs in an encrypted album
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Decrypted original filename, when the album key was available
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Shard repository holding the photo, when it is not the primary repo
+    #[serde(default)]
+    pub repo: Option<String>,
+    /// Caption from the album's metadata vault
+    #[serde(default)]
+    pub caption: Option<String>,
+    /// EXIF extract from the album's metadata vault
+    #[serde(default)]
+    pub exif: Option<ExifExtract>,
+    /// Blob name of the RAW file shot alongside this photo, which the
+    /// listing folds into this entry
+    #[serde(default)]
+    pub raw_companion: Option<String>,
+    /// Photo or video, by the file's extension
+    #[serde(default)]
+    pub media_type: MediaType,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -790,9 +914,10 @@ pub fn validate_repo_name(name: &str) -> Result<(), AppError> {
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn create_repo(
     client: State<'_, HttpClient>,
-    token: String,
+    token: Zeroizing<String>,
     name: String,
     description: String,
     private: bool,
@@ -809,20 +934,15 @@ pub async fn create_repo(
     let res = client
         .0
         .post("https://api.github.com/user/repos")
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
         .json(&body)
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let err_text = res.text().await.map_err(|e| AppError::Api(format!("Failed to read error response body: {}", e)))?;
-        return Err(AppError::Api(format!(
-            "Failed to create repository ({}): {}",
-            status, err_text
-        )));
+        return Err(response_error(res, "Failed to create repository").await);
     }
 
     let json: serde_json::Value = res.json().await?;
@@ -838,9 +958,10 @@ pub async fn create_repo(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn get_repo_info(
     client: State<'_, HttpClient>,
-    token: String,
+    token: Zeroizing<String>,
     repo: String,
 ) -> Result<RepoInfo, AppError> {
     validate_repo(&repo)?;
@@ -850,18 +971,14 @@ pub async fn get_repo_info(
     let res = client
         .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        return Err(AppError::Api(format!(
-            "Failed to get repository info: {}",
-            status
-        )));
+        return Err(response_error(res, "Failed to get repository info").await);
     }
 
     let json: serde_json::Value = res.json().await?;
@@ -877,14 +994,23 @@ pub async fn get_repo_info(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn update_repo_visibility(
     client: State<'_, HttpClient>,
-    token: String,
+    token: Zeroizing<String>,
     repo: String,
     private: bool,
 ) -> Result<RepoInfo, AppError> {
     validate_repo(&repo)?;
+    set_repo_visibility(&client.0, &token, &repo, private).await
+}
 
+pub(crate) async fn set_repo_visibility(
+    client: &Client,
+    token: &str,
+    repo: &str,
+    private: bool,
+) -> Result<RepoInfo, AppError> {
     let url = format!("https://api.github.com/repos/{}", repo);
 
     let body = serde_json::json!({
@@ -892,25 +1018,21 @@ pub async fn update_repo_visibility(
     });
 
     let res = client
-        .0
         .patch(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
         .json(&body)
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let err_text = res.text().await.map_err(|e| AppError::Api(format!("Failed to read error response body: {}", e)))?;
-        return Err(AppError::Api(format!(
-            "Failed to update repository visibility ({}): {}",
-            status, err_text
-        )));
+        return Err(response_error(res, "Failed to update repository visibility").await);
     }
 
     let json: serde_json::Value = res.json().await?;
+    let visibility = if private { "private" } else { "public" };
+    record_activity(ActivityKind::Visibility, "update_repo_visibility", Some(repo), repo, Some(visibility.into()));
 
     Ok(RepoInfo {
         name: json["name"].as_str().ok_or_else(|| AppError::Validation("GitHub API response did not contain name".to_string()))?.to_string(),
@@ -922,24 +1044,84 @@ pub async fn update_repo_visibility(
     })
 }
 
+/// List photos in every shard of the library, presenting one merged folder.
+///
+/// A folder listed before is answered from the local catalog and
+/// reconciled with GitHub in the background (see `catalog`); `refresh`
+/// waits for GitHub instead, as after an upload.
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn list_photos(
+    app: AppHandle,
     client: State<'_, HttpClient>,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
     folder: Option<String>,
+    keypair_handle: Option<KeypairHandle>,
+    refresh: Option<bool>,
 ) -> Result<Vec<PhotoItem>, AppError> {
     validate_repo(&repo)?;
     
     let folder_path = folder.unwrap_or_else(|| "photos".to_string());
+
+    if !refresh.unwrap_or(false) {
+        if let Some(photos) = cached_listing(&repo, &folder_path, keypair_handle.is_some()) {
+            let client = client.0.clone();
+            tauri::async_runtime::spawn(async move {
+                match list_shards(&client, &repo, &token, &folder_path, keypair_handle).await {
+                    Ok(listed) => {
+                        let changes = reconcile_listing(&repo, &folder_path, &listed);
+                        notify_smart_albums(&app, &repo);
+                        if !changes.is_empty() {
+                            let _ = app.emit(
+                                CATALOG_UPDATED_EVENT,
+                                CatalogUpdate { repo, album: folder_path, changes },
+                            );
+                        }
+                    }
+                    Err(e) => tracing::info!("Listed {} from the catalog only: {}", folder_path, e),
+                }
+            });
+            return Ok(photos);
+        }
+    }
+
+    let photos = list_shards(&client.0, &repo, &token, &folder_path, keypair_handle).await?;
+    reconcile_listing(&repo, &folder_path, &photos);
+    notify_smart_albums(&app, &repo);
+    Ok(photos)
+}
+
+pub(crate) async fn list_shards(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    folder_path: &str,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<Vec<PhotoItem>, AppError> {
+    let mut photos = Vec::new();
+    for shard in shard_repos(client, repo, token).await? {
+        let shard_label = (shard != repo).then(|| shard.clone());
+        photos.extend(list_folder(client, &shard, token, folder_path, keypair_handle, shard_label).await?);
+    }
+    Ok(photos)
+}
+
+async fn list_folder(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    folder_path: &str,
+    keypair_handle: Option<KeypairHandle>,
+    shard: Option<String>,
+) -> Result<Vec<PhotoItem>, AppError> {
     let url = format!("https://api.github.com/repos/{}/contents/{}", repo, folder_path);
 
     let res = client
-        .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(token))
         .header("User-Agent", "vortex-image")
-        .send()
+        .send_with_retry()
         .await?;
 
     if res.status() == 404 {
@@ -947,39 +1129,123 @@ pub async fn list_photos(
     }
 
     if !res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to list photos: {}", res.status())));
+        return Err(response_error(res, "Failed to list photos").await);
     }
 
     let json: Vec<serde_json::Value> = res.json().await?;
 
-    Ok(json
+    let has_manifest = json.iter().any(|f| f["name"].as_str() == Some(ALBUM_MANIFEST_FILE));
+    let manifest = if has_manifest {
+        fetch_manifest(client, repo, token, folder_path).await?.map(|(m, _)| m)
+    } else {
+        None
+    };
+    let encrypted = manifest.as_ref().map(|m| m.encrypted).unwrap_or(false);
+
+    // Names are decrypted from the manifest, so listing needs no per-photo requests
+    let album_key = match (keypair_handle, manifest.as_ref().filter(|m| m.encrypted)) {
+        (Some(handle), Some(manifest)) => Some(album_key_for(handle, repo, folder_path, manifest)?),
+        _ => None,
+    };
+    let id = album_id(repo, folder_path);
+
+    // One more request for the vault's captions and EXIF; a vault that cannot
+    // be read only costs those, the sealed names above still apply
+    let vault_sha = json
         .iter()
+        .find(|f| f["name"].as_str() == Some(VAULT_FILE))
+        .and_then(|f| f["sha"].as_str());
+    let mut vault = match (&album_key, vault_sha) {
+        (Some(key), Some(sha)) => match get_blob(client, repo, token, sha).await {
+            Ok(sealed) => MetadataVault::open(key, &id, &sealed).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring metadata vault of {}: {}", folder_path, e);
+                MetadataVault::default()
+            }),
+            Err(e) => {
+                tracing::warn!("Could not load metadata vault of {}: {}", folder_path, e);
+                MetadataVault::default()
+            }
+        },
+        _ => MetadataVault::default(),
+    };
+
+    let photos = json
+        .iter()
+        .filter(|f| f["name"].as_str() != Some(ALBUM_MANIFEST_FILE))
+        .filter(|f| f["name"].as_str() != Some(VAULT_FILE))
+        .filter(|f| !f["name"].as_str().unwrap_or("").ends_with(&format!(".{}", SIGNATURE_EXT)))
         .filter_map(|f| {
+            let name = f["name"].as_str()?.to_string();
+            let metadata = vault.entries.remove(&name);
+            let display_name = match (&album_key, &manifest) {
+                (Some(key), Some(m)) => metadata.as_ref().map(|v| v.filename.clone()).or_else(|| {
+                    m.entries
+                        .get(&name)
+                        .and_then(|sealed| open_filename(key, &id, sealed).ok())
+                }),
+                _ => None,
+            };
+            let (caption, exif) = metadata.map(|v| (v.caption, v.exif)).unwrap_or_default();
+            let media_type = MediaType::of(display_name.as_deref().unwrap_or(&name));
             Some(PhotoItem {
-                name: f["name"].as_str()?.to_string(),
+                name,
                 url: f["download_url"].as_str()?.to_string(),
                 sha: f["sha"].as_str()?.to_string(),
+                size: f["size"].as_u64(),
+                encrypted,
+                display_name,
+                repo: shard.clone(),
+                caption,
+                exif,
+                raw_companion: None,
+                media_type,
             })
         })
-        .collect())
+        .collect();
+    Ok(pair_photos(photos))
 }
 
-const IMAGE_EXTENSIONS: &[&str] = &[
+pub(crate) const IMAGE_EXTENSIONS: &[&str] = &[
     "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif", "svg", "ico", "heic", "heif", "avif",
 ];
 
-fn is_image_file(path: &std::path::Path) -> bool {
+pub(crate) fn is_image_file(path: &std::path::Path) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
         .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
         .unwrap_or(false)
 }
 
+/// Images and camera RAW files
+pub(crate) fn is_photo_file(path: &std::path::Path) -> bool {
+    is_image_file(path) || is_raw_file(path)
+}
+
+/// Files picked up by album folder uploads
+pub(crate) fn is_album_file(path: &std::path::Path) -> bool {
+    is_photo_file(path) || is_video_file(path)
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FolderScanResult {
     pub path: String,
     pub name: String,
     pub image_count: usize,
+    /// RAW files among `image_count`
+    #[serde(default)]
+    pub raw_count: usize,
+    /// RAW files with a JPEG of the same name next to them
+    #[serde(default)]
+    pub paired_count: usize,
+    /// Videos, counted apart from `image_count`
+    #[serde(default)]
+    pub video_count: usize,
+    /// Photos of this folder with at least one category; see `classify`
+    #[serde(default)]
+    pub classified: Vec<ClassifiedFile>,
+    /// Photos of this folder per category
+    #[serde(default)]
+    pub category_counts: BTreeMap<PhotoCategory, usize>,
     pub total_size: u64,
     pub subfolders: Vec<FolderScanResult>,
 }
@@ -1014,6 +1280,7 @@ pub struct UploadFailure {
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn scan_folder(path: String) -> Result<FolderScanResult, AppError> {
     let folder_path = std::path::Path::new(&path);
 
@@ -1036,8 +1303,12 @@ async fn scan_folder_recursive(folder_path: &std::path::Path) -> Result<FolderSc
         .to_string();
 
     let mut image_count = 0;
+    let mut raw_count = 0;
+    let mut video_count = 0;
     let mut total_size = 0u64;
     let mut subfolders = Vec::new();
+    let mut names = Vec::new();
+    let mut photo_paths = Vec::new();
 
     let mut entries = fs::read_dir(folder_path).await?;
 
@@ -1058,16 +1329,36 @@ async fn scan_folder_recursive(folder_path: &std::path::Path) -> Result<FolderSc
 
             let subfolder = Box::pin(scan_folder_recursive(&entry_path)).await?;
             subfolders.push(subfolder);
-        } else if metadata.is_file() && is_image_file(&entry_path) {
+        } else if metadata.is_file() && is_photo_file(&entry_path) {
             image_count += 1;
+            if is_raw_file(&entry_path) {
+                raw_count += 1;
+            }
+            total_size += metadata.len();
+            names.push(entry.file_name().to_string_lossy().to_string());
+            photo_paths.push(entry_path);
+        } else if metadata.is_file() && is_video_file(&entry_path) {
+            video_count += 1;
             total_size += metadata.len();
         }
     }
 
+    let classified = tokio::task::spawn_blocking(move || {
+        let facts: Vec<_> = photo_paths.iter().map(|p| PhotoFacts::read(p)).collect();
+        classify(&facts)
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Classification task failed: {}", e)))?;
+
     Ok(FolderScanResult {
         path: folder_path.to_string_lossy().to_string(),
         name,
         image_count,
+        raw_count,
+        paired_count: raw_pairs(names.iter().map(String::as_str)).len(),
+        video_count,
+        category_counts: category_counts(&classified),
+        classified: classified.into_iter().filter(|f| !f.categories.is_empty()).collect(),
         total_size,
         subfolders,
     })
@@ -1081,7 +1372,7 @@ async fn collect_images_in_folder(folder_path: &std::path::Path) -> Result<Vec<I
         let entry_path = entry.path();
         let metadata = entry.metadata().await?;
 
-        if metadata.is_file() && is_image_file(&entry_path) {
+        if metadata.is_file() && is_album_file(&entry_path) {
             let name = entry_path
                 .file_name()
                 .and_then(|n| n.to_str())
@@ -1100,9 +1391,11 @@ async fn collect_images_in_folder(folder_path: &std::path::Path) -> Result<Vec<I
     Ok(images)
 }
 
-async fn collect_images_recursive(
+/// Files under `folder_path` accepted by `include`, skipping hidden folders
+pub(crate) async fn collect_images_recursive(
     folder_path: &std::path::Path,
     base_path: &std::path::Path,
+    include: &(dyn Fn(&std::path::Path) -> bool + Sync),
 ) -> Result<Vec<ImageFile>, AppError> {
     let mut images = Vec::new();
     let mut entries = fs::read_dir(folder_path).await?;
@@ -1122,9 +1415,9 @@ async fn collect_images_recursive(
                 continue;
             }
 
-            let mut sub_images = Box::pin(collect_images_recursive(&entry_path, base_path)).await?;
+            let mut sub_images = Box::pin(collect_images_recursive(&entry_path, base_path, include)).await?;
             images.append(&mut sub_images);
-        } else if metadata.is_file() && is_image_file(&entry_path) {
+        } else if metadata.is_file() && include(&entry_path) {
             let name = entry_path
                 .file_name()
                 .and_then(|n| n.to_str())
@@ -1149,49 +1442,109 @@ async fn collect_images_recursive(
     Ok(images)
 }
 
+/// `images` without the photos in any of the `excluded` categories
+async fn without_categories(
+    images: Vec<ImageFile>,
+    excluded: Option<Vec<PhotoCategory>>,
+) -> Result<Vec<ImageFile>, AppError> {
+    let excluded = excluded.unwrap_or_default();
+    if excluded.is_empty() {
+        return Ok(images);
+    }
+    let photos: Vec<_> = images
+        .iter()
+        .map(|i| std::path::PathBuf::from(&i.path))
+        .filter(|p| is_photo_file(p))
+        .collect();
+    let dropped = tokio::task::spawn_blocking(move || excluded_paths(&photos, &excluded))
+        .await
+        .map_err(|e| AppError::Validation(format!("Classification task failed: {}", e)))?;
+    Ok(images
+        .into_iter()
+        .filter(|i| !dropped.contains(std::path::Path::new(&i.path)))
+        .collect())
+}
+
+/// Upload the photos in `path` as the album `album_name`. Camera RAW files
+/// go up as they are, next to the JPEGs they were shot with, and videos are
+/// recorded in the album manifest when there is one (see `video`). Photos
+/// in `exclude_categories` (see `classify`) are left out.
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_folder_as_album(
     app: AppHandle,
     client: State<'_, HttpClient>,
     path: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
     album_name: String,
     create_subalbums: bool,
+    keypair_handle: Option<KeypairHandle>,
+    exclude_categories: Option<Vec<PhotoCategory>>,
 ) -> Result<UploadBatchResult, AppError> {
-    validate_repo(&repo)?;
+    upload_album_folder(
+        &client.0,
+        &path,
+        &repo,
+        &token,
+        &album_name,
+        create_subalbums,
+        keypair_handle,
+        exclude_categories,
+        |progress| {
+            let _ = app.emit("batch-upload-progress", progress);
+        },
+    )
+    .await
+}
 
-    let folder_path = std::path::Path::new(&path);
+/// `upload_folder_as_album` without the Tauri state, reporting through
+/// `progress`; also used by the CLI
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn upload_album_folder(
+    client: &Client,
+    path: &str,
+    repo: &str,
+    token: &str,
+    album_name: &str,
+    create_subalbums: bool,
+    keypair_handle: Option<KeypairHandle>,
+    exclude_categories: Option<Vec<PhotoCategory>>,
+    progress: impl Fn(UploadBatchProgress),
+) -> Result<UploadBatchResult, AppError> {
+    validate_repo(repo)?;
+
+    let folder_path = std::path::Path::new(path);
     if !folder_path.exists() || !folder_path.is_dir() {
         return Err(AppError::Validation("Invalid folder path".into()));
     }
 
-    let safe_album_name = sanitize_filename(&album_name);
+    let safe_album_name = sanitize_filename(album_name);
     if safe_album_name.is_empty() {
         return Err(AppError::Validation("Invalid album name".into()));
     }
 
     let images = if create_subalbums {
-        collect_images_recursive(folder_path, folder_path).await?
+        collect_images_recursive(folder_path, folder_path, &is_album_file).await?
     } else {
         collect_images_in_folder(folder_path).await?
     };
+    let images = without_categories(images, exclude_categories).await?;
 
     let total_files = images.len();
     let mut succeeded = Vec::new();
     let mut failed = Vec::new();
+    let mut media = Vec::new();
 
     for (index, image) in images.iter().enumerate() {
         
-        let _ = app.emit(
-            "batch-upload-progress",
-            UploadBatchProgress {
-                total_files,
-                completed_files: index,
-                current_file: image.name.clone(),
-                percent: ((index * 100) / total_files.max(1)) as u8,
-            },
-        );
+        progress(UploadBatchProgress {
+            total_files,
+            completed_files: index,
+            current_file: image.name.clone(),
+            percent: ((index * 100) / total_files.max(1)) as u8,
+        });
 
         let upload_path = if create_subalbums {
             format!("photos/{}/{}", safe_album_name, image.relative_path.replace('\\', "/"))
@@ -1199,7 +1552,17 @@ pub async fn upload_folder_as_album(
             format!("photos/{}/{}", safe_album_name, image.name)
         };
 
-        match upload_single_file(&client.0, &image.path, &repo, &token, &upload_path).await {
+        let uploaded = if is_video_file(std::path::Path::new(&image.path)) {
+            put_video(client, repo, token, &upload_path, std::path::Path::new(&image.path), keypair_handle, |_| {})
+                .await
+                .map(|(result, entry)| {
+                    media.push((upload_path.clone(), entry));
+                    result
+                })
+        } else {
+            upload_single_file(client, &image.path, repo, token, &upload_path, keypair_handle).await
+        };
+        match uploaded {
             Ok(result) => succeeded.push(result),
             Err(e) => failed.push(UploadFailure {
                 path: image.path.clone(),
@@ -1209,42 +1572,73 @@ pub async fn upload_folder_as_album(
         }
     }
 
-    let _ = app.emit(
-        "batch-upload-progress",
-        UploadBatchProgress {
-            total_files,
-            completed_files: total_files,
-            current_file: String::new(),
-            percent: 100,
-        },
-    );
+    // The videos are uploaded either way; only their listing details are lost
+    if let Err(e) = record_media(client, repo, token, media, keypair_handle).await {
+        tracing::warn!("Could not record videos in the manifest of {}: {}", safe_album_name, e);
+    }
 
+    progress(UploadBatchProgress {
+        total_files,
+        completed_files: total_files,
+        current_file: String::new(),
+        percent: 100,
+    });
+
+    let detail = format!("{} uploaded, {} failed", succeeded.len(), failed.len());
+    record_activity(ActivityKind::Upload, "upload_folder_as_album", Some(repo), &format!("photos/{}", safe_album_name), Some(detail));
     Ok(UploadBatchResult { succeeded, failed })
 }
 
+/// Upload every photo under `path` to `photos/`
+///
+/// Files whose category has a pipeline route (see `pipeline_routing`) are
+/// also picked up and run through the routed preset first, using
+/// `passwords` and `pipeline_keypair` for its layers, then uploaded as
+/// `<name>.vxp`. A resize layer with `originals_album` also has the
+/// original uploaded to `photos/<album>/`. Photos in `exclude_categories`
+/// (see `classify`) are left out.
+///
+/// Runs as a job (see `jobs`). Cancelling stops before the next photo and
+/// deletes the photos this run already uploaded.
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_folder_recursive(
     app: AppHandle,
     client: State<'_, HttpClient>,
     path: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
+    keypair_handle: Option<KeypairHandle>,
+    passwords: Option<std::collections::HashMap<String, String>>,
+    pipeline_keypair: Option<Vec<u8>>,
+    exclude_categories: Option<Vec<PhotoCategory>>,
+    job_id: Option<String>,
 ) -> Result<UploadBatchResult, AppError> {
     validate_repo(&repo)?;
+    let job = Job::announce(&app, job_id, JobCommand::UploadFolder)?;
 
     let folder_path = std::path::Path::new(&path);
     if !folder_path.exists() || !folder_path.is_dir() {
         return Err(AppError::Validation("Invalid folder path".into()));
     }
 
-    let images = collect_images_recursive(folder_path, folder_path).await?;
+    let router = Router::load()?;
+    let context = Arc::new(pipeline_context(passwords.unwrap_or_default(), pipeline_keypair)?);
+    let images = collect_images_recursive(folder_path, folder_path, &|p| router.includes(p)).await?;
+    let images = without_categories(images, exclude_categories).await?;
 
     let total_files = images.len();
     let mut succeeded = Vec::new();
     let mut failed = Vec::new();
+    // Paths created by this run, removed again if it is cancelled
+    let mut uploaded = Vec::new();
 
     for (index, image) in images.iter().enumerate() {
-        
+        if job.is_cancelled() {
+            return Err(roll_back_upload(&client, &repo, &token, &uploaded).await);
+        }
+
         let _ = app.emit(
             "batch-upload-progress",
             UploadBatchProgress {
@@ -1255,17 +1649,48 @@ pub async fn upload_folder_recursive(
             },
         );
 
+        // Per file, so a click on a photo does not wait for the whole folder
+        let _permit = SCHEDULER.acquire(JobClass::Sync).await;
         let safe_name = sanitize_filename(&image.name);
-        let upload_path = format!("photos/{}", safe_name);
+        let route = router.pipeline_for(std::path::Path::new(&image.name));
+        let upload_path = match route {
+            Some(_) => format!("photos/{}.vxp", safe_name),
+            None => format!("photos/{}", safe_name),
+        };
+        let result = match route {
+            Some(config) => {
+                upload_routed_file(&client.0, image, config, &context, &repo, &token, &upload_path, keypair_handle).await
+            }
+            None => upload_single_file(&client.0, &image.path, &repo, &token, &upload_path, keypair_handle).await,
+        };
 
-        match upload_single_file(&client.0, &image.path, &repo, &token, &upload_path).await {
-            Ok(result) => succeeded.push(result),
+        match result {
+            Ok(result) => {
+                uploaded.push(upload_path);
+                succeeded.push(result);
+            }
             Err(e) => failed.push(UploadFailure {
                 path: image.path.clone(),
                 name: image.name.clone(),
                 error: e.to_string(),
             }),
         }
+
+        // Resized photos can keep their full-size original in another album
+        if let Some(album) = router.pipeline_for(std::path::Path::new(&image.name)).and_then(originals_album) {
+            let upload_path = format!("photos/{}/{}", album, safe_name);
+            match upload_single_file(&client.0, &image.path, &repo, &token, &upload_path, keypair_handle).await {
+                Ok(result) => {
+                    uploaded.push(upload_path);
+                    succeeded.push(result);
+                }
+                Err(e) => failed.push(UploadFailure {
+                    path: image.path.clone(),
+                    name: image.name.clone(),
+                    error: format!("Original not uploaded: {}", e),
+                }),
+            }
+        }
     }
 
     let _ = app.emit(
@@ -1278,67 +1703,143 @@ pub async fn upload_folder_recursive(
         },
     );
 
+    let detail = format!("{} uploaded, {} failed", succeeded.len(), failed.len());
+    record_activity(ActivityKind::Upload, "upload_folder_recursive", Some(&repo), "photos", Some(detail));
     Ok(UploadBatchResult { succeeded, failed })
 }
 
+/// Delete the photos of a cancelled folder upload in one commit. Returns
+/// the error the upload fails with.
+async fn roll_back_upload(client: &HttpClient, repo: &str, token: &str, uploaded: &[String]) -> AppError {
+    if uploaded.is_empty() {
+        return cancelled("Upload cancelled");
+    }
+    let rolled_back = run_plan(
+        client,
+        repo,
+        token,
+        |index| plan_delete(index, uploaded),
+        |n| format!("Remove {} photo{} of a cancelled upload", n, if n == 1 { "" } else { "s" }),
+    )
+    .await;
+    match rolled_back {
+        Ok(result) => {
+            let detail = format!("cancelled, {} uploads removed", result.succeeded);
+            record_activity(ActivityKind::Upload, "upload_folder_recursive", Some(repo), "photos", Some(detail));
+            cancelled(format!("Upload cancelled; {} uploaded photos removed", result.succeeded))
+        }
+        Err(e) => {
+            let detail = format!("cancelled, {} uploads kept", uploaded.len());
+            record_activity(ActivityKind::Upload, "upload_folder_recursive", Some(repo), "photos", Some(detail));
+            cancelled(format!("Upload cancelled, but {} uploaded photos could not be removed: {}", uploaded.len(), e))
+        }
+    }
+}
+
 async fn upload_single_file(
     client: &Client,
     local_path: &str,
     repo: &str,
     token: &str,
     upload_path: &str,
+    signer: Option<KeypairHandle>,
 ) -> Result<UploadResult, AppError> {
     let content = fs::read(local_path).await?;
-    let encoded = STANDARD.encode(&content);
+    check_upload(upload_path, &content, UploadIntent::default())?;
+    upload_content(client, &content, repo, token, upload_path, signer).await
+}
+
+/// Run `image` through the routed pipeline `config` and upload the output
+#[allow(clippy::too_many_arguments)]
+async fn upload_routed_file(
+    client: &Client,
+    image: &ImageFile,
+    config: &PipelineConfig,
+    context: &Arc<PipelineContext>,
+    repo: &str,
+    token: &str,
+    upload_path: &str,
+    signer: Option<KeypairHandle>,
+) -> Result<UploadResult, AppError> {
+    let content = fs::read(&image.path).await?;
+    check_upload(&image.name, &content, upload_intent(config))?;
+
+    let started = std::time::Instant::now();
+    let (name, job_config, job_context) = (image.name.clone(), config.clone(), Arc::clone(context));
+    let input_size = content.len();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        process_pipeline_for_file(&content, Some(&name), &job_config, &job_context)
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Pipeline task failed: {}", e)))?;
+    record_run(&PipelineRun::single(config, input_size, &result, started.elapsed()));
+    let processed = result.map_err(|e| AppError::Validation(e.to_string()))?;
+
+    upload_content(client, &processed.data, repo, token, upload_path, signer).await
+}
+
+pub(crate) async fn upload_content(
+    client: &Client,
+    content: &[u8],
+    repo: &str,
+    token: &str,
+    upload_path: &str,
+    signer: Option<KeypairHandle>,
+) -> Result<UploadResult, AppError> {
+    let message = format!("Upload {}", upload_path);
+    let result = put_file_contents(client, repo, token, upload_path, content, &message, None).await?;
+    if let Some(handle) = signer {
+        put_photo_signature(client, repo, token, upload_path, &sign_photo(handle, content)?).await?;
+    }
+    Ok(result)
+}
+
+/// Create or update a file through the contents API
+///
+/// `sha` must be the current blob SHA when overwriting an existing file.
+pub(crate) async fn put_file_contents(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    upload_path: &str,
+    content: &[u8],
+    message: &str,
+    sha: Option<&str>,
+) -> Result<UploadResult, AppError> {
+    let encoded = STANDARD.encode(content);
 
     let url = format!("https://api.github.com/repos/{}/contents/{}", repo, upload_path);
 
-    let body = serde_json::json!({
-        "message": format!("Upload {}", upload_path),
+    let mut body = serde_json::json!({
+        "message": message,
         "content": encoded
     });
 
-    let result = retry_with_backoff(
-        || async {
-            let res = client
-                .put(&url)
-                .timeout(Duration::from_secs(UPLOAD_TIMEOUT_SECS))
-                .header("Authorization", format!("Bearer {}", token))
-                .header("User-Agent", "vortex-image")
-                .header("Accept", "application/vnd.github+json")
-                .json(&body)
-                .send()
-                .await?;
-
-            if res.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-                
-                if let Some(retry_secs) = get_retry_after(res.headers()) {
-                    sleep(Duration::from_secs(retry_secs)).await;
-                }
-                return Err(AppError::Api("Rate limited".into()));
-            }
+    if let Some(sha) = sha {
+        body["sha"] = serde_json::Value::String(sha.to_string());
+    }
 
-            if is_retryable_status(res.status()) {
-                return Err(AppError::Api(format!("Retryable error: {}", res.status())));
-            }
+    let res = client
+        .put(&url)
+        .timeout(Duration::from_secs(UPLOAD_TIMEOUT_SECS))
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .json(&body)
+        .send_with_retry()
+        .await?;
 
-            if !res.status().is_success() {
-                let status = res.status();
-                let err = res.text().await.unwrap_or_default();
-                return Err(AppError::Api(format!("Upload failed ({}): {}", status, err)));
-            }
+    if !res.status().is_success() {
+        return Err(response_error(res, "Upload failed").await);
+    }
 
-            let json: serde_json::Value = res.json().await?;
-            Ok(UploadResult {
-                url: json["content"]["html_url"].as_str().unwrap_or("").to_string(),
-                sha: json["content"]["sha"].as_str().unwrap_or("").to_string(),
-            })
-        },
-        MAX_RETRIES,
-        INITIAL_RETRY_DELAY_MS,
-    )
-    .await?;
+    let json: serde_json::Value = res.json().await?;
+    let result = UploadResult {
+        url: json["content"]["html_url"].as_str().unwrap_or("").to_string(),
+        sha: json["content"]["sha"].as_str().unwrap_or("").to_string(),
+    };
 
+    replicate_put(client, repo, token, upload_path, content);
     Ok(result)
 }
 
@@ -1348,13 +1849,21 @@ pub struct Album {
     pub path: String,
     pub photo_count: usize,
     pub children: Vec<Album>,
+    /// Repo path of the cover photo, from the album manifest
+    #[serde(default)]
+    pub cover: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn list_albums(
     client: State<'_, HttpClient>,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<Vec<Album>, AppError> {
     validate_repo(&repo)?;
 
@@ -1363,10 +1872,10 @@ pub async fn list_albums(
     let res = client
         .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if res.status() == 404 {
@@ -1374,7 +1883,7 @@ pub async fn list_albums(
     }
 
     if !res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to list albums: {}", res.status())));
+        return Err(response_error(res, "Failed to list albums").await);
     }
 
     let items: Vec<serde_json::Value> = res.json().await?;
@@ -1391,10 +1900,11 @@ pub async fn list_albums(
         }
     }
 
+    crate::local_store::cache_album_listing(&repo, &albums);
     Ok(albums)
 }
 
-async fn get_album_recursive(
+pub(crate) async fn get_album_recursive(
     client: &Client,
     repo: &str,
     token: &str,
@@ -1405,10 +1915,10 @@ async fn get_album_recursive(
 
     let res = client
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
@@ -1417,6 +1927,9 @@ async fn get_album_recursive(
             path: path.to_string(),
             photo_count: 0,
             children: vec![],
+            cover: None,
+            description: None,
+            metadata: BTreeMap::new(),
         });
     }
 
@@ -1424,13 +1937,15 @@ async fn get_album_recursive(
 
     let mut photo_count = 0;
     let mut children = Vec::new();
+    let mut manifest = None;
 
     for item in items {
         let item_type = item["type"].as_str().unwrap_or("");
         let item_name = item["name"].as_str().unwrap_or("");
 
-        if item_type == "file" {
-            
+        if item_type == "file" && item_name == ALBUM_MANIFEST_FILE {
+            manifest = fetch_manifest(client, repo, token, path).await?.map(|(m, _)| m);
+        } else if item_type == "file" {
             let ext = std::path::Path::new(item_name)
                 .extension()
                 .and_then(|e| e.to_str())
@@ -1446,11 +1961,19 @@ async fn get_album_recursive(
         }
     }
 
+    let (cover, description, metadata) = match manifest {
+        Some(m) => (m.cover.map(|c| format!("{}/{}", path, c)), m.description, m.metadata),
+        None => (None, None, BTreeMap::new()),
+    };
+
     Ok(Album {
         name: name.to_string(),
         path: path.to_string(),
         photo_count,
         children,
+        cover,
+        description,
+        metadata,
     })
 }
 
@@ -1462,17 +1985,43 @@ pub struct DownloadProgress {
     pub percent: u8,
 }
 
+/// Integrity of a downloaded photo. `manifest` is checked for photos of
+/// encrypted albums, whose names and keys come from it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DownloadIntegrity {
+    pub photo: IntegrityCheck,
+    pub manifest: Option<IntegrityCheck>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DownloadedPhoto {
+    pub path: String,
+    /// `None` when verification was turned off
+    pub integrity: Option<DownloadIntegrity>,
+    /// True when it was opened from the offline cache
+    pub cached: bool,
+}
+
+/// Download a photo to `local_dir` (the downloads folder by default).
+/// Unless `verify` is false, its signature is checked first and tampered
+/// photos are not written. Photos opened before are served from the
+/// offline cache (see `download_cache`) when their blob is unchanged.
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
 pub async fn download_photo(
     app: AppHandle,
     client: State<'_, HttpClient>,
     remote_path: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
     download_id: String,
     local_dir: Option<String>,
-) -> Result<String, AppError> {
+    keypair_handle: Option<KeypairHandle>,
+    verify: Option<bool>,
+) -> Result<DownloadedPhoto, AppError> {
     validate_repo(&repo)?;
+    let _permit = SCHEDULER.acquire(JobClass::Interactive).await;
 
     let _ = app.emit("download-progress", DownloadProgress {
         id: download_id.clone(),
@@ -1481,68 +2030,217 @@ pub async fn download_photo(
         percent: 0,
     });
 
+    let verify = verify.unwrap_or(true);
+    let (photo, cached) = load_photo(&client.0, &repo, &token, &remote_path, keypair_handle, verify).await?;
+
+    let _ = app.emit("download-progress", DownloadProgress {
+        id: download_id.clone(),
+        bytes_received: photo.content.len() as u64,
+        total_bytes: photo.content.len() as u64,
+        percent: 100,
+    });
+
+    let integrity = photo.integrity.clone();
+    let (content, filename) = open_photo(&repo, &remote_path, photo, keypair_handle)?;
+
+    let local_path = if let Some(dir) = local_dir {
+        std::path::Path::new(&dir).join(&filename)
+    } else {
+        
+        let downloads = dirs::download_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."));
+        downloads.join(&filename)
+    };
+
+    fs::write(&local_path, &content).await?;
+
+    Ok(DownloadedPhoto {
+        path: local_path.to_string_lossy().to_string(),
+        integrity,
+        cached,
+    })
+}
+
+/// A photo's content and file name as its owner sees them. Blobs from
+/// encrypted albums are decrypted with the locally held keypair.
+pub(crate) fn open_photo(
+    repo: &str,
+    remote_path: &str,
+    photo: CachedPhoto,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<(Vec<u8>, String), AppError> {
+    let CachedPhoto { content, manifest, .. } = photo;
+    let filename = remote_path.split('/').last().unwrap_or("photo").to_string();
+    let Some(manifest) = manifest else {
+        return Ok((content, filename));
+    };
+
+    let handle = keypair_handle
+        .ok_or_else(|| AppError::Validation("Photo is encrypted; a keypair is required".into()))?;
+    let album_path = parent_album_path(remote_path);
+    let album_key = album_key_for(handle, repo, album_path, &manifest)?;
+    let id = album_id(repo, album_path);
+    let (data, payload_name) = open_album_photo(&album_key, &id, &content)?;
+    // Renames only update the manifest, so its name takes precedence
+    let manifest_name = manifest
+        .entries
+        .get(&filename)
+        .and_then(|sealed| open_filename(&album_key, &id, sealed).ok());
+    let filename = manifest_name
+        .or(payload_name)
+        .map(|n| sanitize_filename(&n))
+        .filter(|n| !n.is_empty())
+        .unwrap_or(filename);
+    Ok((data, filename))
+}
+
+/// Fetch a photo's blob as stored in the repo, checking its signature (and
+/// its album manifest's, for encrypted albums) unless `verify` is false.
+/// When GitHub cannot serve it, its IPFS copy is used if it was pinned (see
+/// `ipfs`), else its WebDAV backup (see `webdav`).
+pub(crate) async fn fetch_photo(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    remote_path: &str,
+    keypair_handle: Option<KeypairHandle>,
+    verify: bool,
+) -> Result<CachedPhoto, AppError> {
+    let error = match fetch_photo_from_github(client, repo, token, remote_path, keypair_handle, verify).await {
+        Err(e) if github_unreachable(&e) => e,
+        result => return result,
+    };
+    match fetch_pinned_photo(client, repo, remote_path, keypair_handle, verify).await {
+        Ok(Some(photo)) => return Ok(photo),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("No IPFS copy of {}: {}", remote_path, e),
+    }
+    match fetch_webdav_photo(client, repo, remote_path, keypair_handle, verify).await {
+        Ok(Some(photo)) => return Ok(photo),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("No WebDAV copy of {}: {}", remote_path, e),
+    }
+    Err(error)
+}
+
+async fn fetch_photo_from_github(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    remote_path: &str,
+    keypair_handle: Option<KeypairHandle>,
+    verify: bool,
+) -> Result<CachedPhoto, AppError> {
     let url = format!("https://api.github.com/repos/{}/contents/{}", repo, remote_path);
-    
+
     let res = client
-        .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to get file info: {}", res.status())));
+        return Err(response_error(res, "Failed to get file info").await);
     }
 
     let json: serde_json::Value = res.json().await?;
+    let sha = json["sha"].as_str().unwrap_or("").to_string();
     let download_url = json["download_url"]
         .as_str()
         .ok_or_else(|| AppError::Api("No download URL found".into()))?;
 
     let content_res = client
-        .0
         .get(download_url)
         .header("User-Agent", "vortex-image")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !content_res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to download file: {}", content_res.status())));
+        return Err(response_error(content_res, "Failed to download file").await);
     }
 
-    let total_bytes = content_res.content_length().unwrap_or(0);
-    let content = content_res.bytes().await?;
+    let content = resolve_lfs_pointer(client, repo, token, content_res.bytes().await?.to_vec()).await?;
 
-    let _ = app.emit("download-progress", DownloadProgress {
-        id: download_id.clone(),
-        bytes_received: content.len() as u64,
-        total_bytes,
-        percent: 100,
-    });
+    // Blobs from encrypted albums are opened with keys from the manifest
+    let album_path = parent_album_path(remote_path);
+    let manifest = if remote_path.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) {
+        let (manifest, _) = fetch_manifest(client, repo, token, album_path)
+            .await?
+            .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+        Some(manifest)
+    } else {
+        None
+    };
 
-    let filename = remote_path.split('/').last().unwrap_or("photo");
-    let local_path = if let Some(dir) = local_dir {
-        std::path::Path::new(&dir).join(filename)
+    let integrity = if verify {
+        let trusted = TrustedSigners::load(keypair_handle);
+        let manifest_path = format!("{}/{}", album_path, ALBUM_MANIFEST_FILE);
+        let manifest_check = manifest
+            .as_ref()
+            .map(|m| ensure_intact(check_manifest(&manifest_path, m, &trusted)))
+            .transpose()?;
+        let signature = fetch_photo_signature(client, repo, token, remote_path).await?;
+        let photo = check_album_photo(remote_path, &content, signature.as_deref(), manifest.as_ref(), &trusted);
+        Some(DownloadIntegrity {
+            photo: ensure_intact(photo)?,
+            manifest: manifest_check,
+        })
     } else {
-        
-        let downloads = dirs::download_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("."));
-        downloads.join(filename)
+        None
     };
 
-    fs::write(&local_path, &content).await?;
+    Ok(CachedPhoto { sha, content, integrity, manifest })
+}
+
+/// Fetch the raw bytes of a file in a repo (contents lookup + download_url)
+pub(crate) async fn fetch_file_bytes(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    remote_path: &str,
+) -> Result<Vec<u8>, AppError> {
+    let url = format!("https://api.github.com/repos/{}/contents/{}", repo, remote_path);
+
+    let res = client
+        .get(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "Failed to get file info").await);
+    }
+
+    let json: serde_json::Value = res.json().await?;
+    let download_url = json["download_url"]
+        .as_str()
+        .ok_or_else(|| AppError::Api("No download URL found".into()))?;
 
-    Ok(local_path.to_string_lossy().to_string())
+    let content_res = client
+        .get(download_url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .send_with_retry()
+        .await?;
+
+    if !content_res.status().is_success() {
+        return Err(response_error(content_res, "Failed to download file").await);
+    }
+
+    resolve_lfs_pointer(client, repo, token, content_res.bytes().await?.to_vec()).await
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn delete_photo(
     client: State<'_, HttpClient>,
     path: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<(), AppError> {
     validate_repo(&repo)?;
 
@@ -1551,14 +2249,14 @@ pub async fn delete_photo(
     let get_res = client
         .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !get_res.status().is_success() {
-        return Err(AppError::Api(format!("File not found: {}", get_res.status())));
+        return Err(response_error(get_res, "File not found").await);
     }
 
     let json: serde_json::Value = get_res.json().await?;
@@ -1574,23 +2272,25 @@ pub async fn delete_photo(
     let delete_res = client
         .0
         .delete(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
         .json(&delete_body)
-        .send()
+        .send_with_retry()
         .await?;
 
     if !delete_res.status().is_success() {
-        let status = delete_res.status();
-        let err_text = delete_res.text().await.map_err(|e| AppError::Api(format!("Failed to read error response body: {}", e)))?;
-        return Err(AppError::Api(format!("Failed to delete file ({}): {}", status, err_text)));
+        return Err(response_error(delete_res, "Failed to delete file").await);
     }
 
+    replicate_delete(&client.0, &repo, &token, &path);
+    forget_photo(&repo, &path);
+    record_activity(ActivityKind::Delete, "delete_photo", Some(&repo), &path, None);
     Ok(())
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn remove_local_file(path: String) -> Result<(), AppError> {
     let file_path = std::path::Path::new(&path);
     
@@ -1607,6 +2307,7 @@ pub async fn remove_local_file(path: String) -> Result<(), AppError> {
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn get_local_image_info(path: String) -> Result<ImageFile, AppError> {
     let file_path = std::path::Path::new(&path);
     
@@ -1630,11 +2331,12 @@ pub async fn get_local_image_info(path: String) -> Result<ImageFile, AppError> {
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn delete_album(
     client: State<'_, HttpClient>,
     album_path: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<u32, AppError> {
     validate_repo(&repo)?;
 
@@ -1652,10 +2354,10 @@ pub async fn delete_album(
         let get_res = client
             .0
             .get(&url)
-            .header("Authorization", format!("Bearer {}", token))
+            .header("Authorization", Bearer(&token))
             .header("User-Agent", "vortex-image")
             .header("Accept", "application/vnd.github+json")
-            .send()
+            .send_with_retry()
             .await?;
 
         if !get_res.status().is_success() {
@@ -1676,11 +2378,11 @@ pub async fn delete_album(
         let delete_res = client
             .0
             .delete(&url)
-            .header("Authorization", format!("Bearer {}", token))
+            .header("Authorization", Bearer(&token))
             .header("User-Agent", "vortex-image")
             .header("Accept", "application/vnd.github+json")
             .json(&delete_body)
-            .send()
+            .send_with_retry()
             .await?;
 
         if delete_res.status().is_success() {
@@ -1688,6 +2390,7 @@ pub async fn delete_album(
         }
     }
 
+    record_activity(ActivityKind::Delete, "delete_album", Some(&repo), &album_path, Some(format!("{} files", deleted_count)));
     Ok(deleted_count)
 }
 
@@ -1706,10 +2409,10 @@ async fn get_album_files_recursive(
 
     let res = client
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
@@ -1735,12 +2438,13 @@ async fn get_album_files_recursive(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn rename_album(
     client: State<'_, HttpClient>,
     old_path: String,
     new_name: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<u32, AppError> {
     validate_repo(&repo)?;
     
@@ -1777,10 +2481,10 @@ pub async fn rename_album(
         let get_res = client
             .0
             .get(&url)
-            .header("Authorization", format!("Bearer {}", token))
+            .header("Authorization", Bearer(&token))
             .header("User-Agent", "vortex-image")
             .header("Accept", "application/vnd.github+json")
-            .send()
+            .send_with_retry()
             .await?;
 
         if !get_res.status().is_success() {
@@ -1800,11 +2504,11 @@ pub async fn rename_album(
         let create_res = client
             .0
             .put(&create_url)
-            .header("Authorization", format!("Bearer {}", token))
+            .header("Authorization", Bearer(&token))
             .header("User-Agent", "vortex-image")
             .header("Accept", "application/vnd.github+json")
             .json(&create_body)
-            .send()
+            .send_with_retry()
             .await?;
 
         if !create_res.status().is_success() {
@@ -1819,26 +2523,29 @@ pub async fn rename_album(
         let _ = client
             .0
             .delete(&url)
-            .header("Authorization", format!("Bearer {}", token))
+            .header("Authorization", Bearer(&token))
             .header("User-Agent", "vortex-image")
             .header("Accept", "application/vnd.github+json")
             .json(&delete_body)
-            .send()
+            .send_with_retry()
             .await;
 
         moved_count += 1;
     }
 
+    let detail = format!("to {}, {} files", new_path, moved_count);
+    record_activity(ActivityKind::Rename, "rename_album", Some(&repo), &old_path, Some(detail));
     Ok(moved_count)
 }
 
 /// Create a folder (or subfolder) on GitHub by creating a .gitkeep placeholder file
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn create_folder(
     client: State<'_, HttpClient>,
     folder_path: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<String, AppError> {
     validate_repo(&repo)?;
 
@@ -1864,10 +2571,10 @@ pub async fn create_folder(
     let check_res = client
         .0
         .get(&check_url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if check_res.status().is_success() {
@@ -1882,45 +2589,64 @@ pub async fn create_folder(
     let res = client
         .0
         .put(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
         .json(&body)
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let err = res.text().await.unwrap_or_default();
-        return Err(AppError::Api(format!("Failed to create folder ({}): {}", status, err)));
+        return Err(response_error(res, "Failed to create folder").await);
     }
 
     Ok(full_path)
 }
 
+#[derive(Serialize, Clone)]
+pub struct SecurePhoto {
+    pub data: Vec<u8>,
+    /// `None` when verification was turned off
+    pub integrity: Option<IntegrityCheck>,
+}
+
+/// Key id of serialized keypair bytes passed in by the frontend
+fn keypair_key_id(keypair_bytes: &[u8]) -> Result<String, AppError> {
+    HybridKeypair::from_bytes(keypair_bytes)
+        .map(|keypair| keypair.public_bundle().key_id)
+        .map_err(|e| AppError::Validation(format!("Invalid keypair: {}", e)))
+}
+
+/// Download and decrypt a keypair-encrypted photo. Unless `verify` is false,
+/// its signature is checked before decryption. Keys revoked in `repo` are
+/// refused (see `revocation`).
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn download_secure_photo(
     client: State<'_, HttpClient>,
     remote_path: String,
     repo: String,
-    token: String,
-    keypair_bytes: Vec<u8>,
-) -> Result<Vec<u8>, AppError> {
+    token: Zeroizing<String>,
+    keypair_bytes: SecretBytes,
+    verify: Option<bool>,
+) -> Result<SecurePhoto, AppError> {
     validate_repo(&repo)?;
+    let key_id = keypair_key_id(&keypair_bytes)?;
+    ensure_not_revoked(&client.0, &repo, &token, &key_id).await?;
 
     let url = format!("https://api.github.com/repos/{}/contents/{}", repo, remote_path);
 
     let res = client
         .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to get file info: {}", res.status())));
+        return Err(response_error(res, "Failed to get file info").await);
     }
 
     let json: serde_json::Value = res.json().await?;
@@ -1932,14 +2658,23 @@ pub async fn download_secure_photo(
         .0
         .get(download_url)
         .header("User-Agent", "vortex-image")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !content_res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to download file: {}", content_res.status())));
+        return Err(response_error(content_res, "Failed to download file").await);
     }
 
-    let encrypted_bytes = content_res.bytes().await?;
+    let encrypted_bytes = resolve_lfs_pointer(&client.0, &repo, &token, content_res.bytes().await?.to_vec()).await?;
+
+    let integrity = if verify.unwrap_or(true) {
+        let mut trusted = TrustedSigners::load(None);
+        trusted.insert(key_id);
+        let signature = fetch_photo_signature(&client.0, &repo, &token, &remote_path).await?;
+        Some(ensure_intact(check_photo(&remote_path, &encrypted_bytes, signature.as_deref(), &trusted))?)
+    } else {
+        None
+    };
 
     let encrypted_data: EncryptedFileData = serde_json::from_slice(&encrypted_bytes)
         .map_err(|e| AppError::Validation(format!("Invalid encrypted file format: {}", e)))?;
@@ -1960,17 +2695,22 @@ pub async fn download_secure_photo(
     let final_image = crate::compress::decompress_file_data(&compressed_file)
         .map_err(|e| AppError::Validation(format!("Decompression failed: {}", e)))?;
 
-    Ok(final_image)
+    Ok(SecurePhoto {
+        data: final_image,
+        integrity,
+    })
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn upload_secure_message(
     client: State<'_, HttpClient>,
     content: String,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
     filename: String,
-    public_bundle: PublicBundle,
+    public_bundle: Option<PublicBundle>,
+    contact_id: Option<String>,
 ) -> Result<UploadResult, AppError> {
     validate_repo(&repo)?;
     let safe_filename = sanitize_filename(&filename);
@@ -1979,6 +2719,8 @@ pub async fn upload_secure_message(
         return Err(AppError::Validation("Invalid filename".into()));
     }
 
+    let public_bundle = crate::contacts::resolve_recipient(public_bundle, contact_id.as_deref())?;
+
     let encrypted_payload = encrypt(content.as_bytes(), &public_bundle)
         .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))?;
 
@@ -1998,17 +2740,15 @@ pub async fn upload_secure_message(
     let res = client
         .0
         .put(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
         .json(&body)
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let err = res.text().await.unwrap_or_default();
-        return Err(AppError::Api(format!("Upload failed ({}): {}", status, err)));
+        return Err(response_error(res, "Upload failed").await);
     }
 
     let json: serde_json::Value = res.json().await?;
@@ -2019,15 +2759,18 @@ pub async fn upload_secure_message(
     })
 }
 
+/// Download and decrypt a message; keys revoked in `repo` are refused
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn download_secure_message(
     client: State<'_, HttpClient>,
     filename: String,
     repo: String,
-    token: String,
-    keypair_bytes: Vec<u8>,
+    token: Zeroizing<String>,
+    keypair_bytes: SecretBytes,
 ) -> Result<String, AppError> {
     validate_repo(&repo)?;
+    ensure_not_revoked(&client.0, &repo, &token, &keypair_key_id(&keypair_bytes)?).await?;
     let safe_filename = sanitize_filename(&filename);
 
     let remote_path = if safe_filename.ends_with(".msg") {
@@ -2041,14 +2784,14 @@ pub async fn download_secure_message(
     let res = client
         .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to get message: {}", res.status())));
+        return Err(response_error(res, "Failed to get message").await);
     }
 
     let json: serde_json::Value = res.json().await?;
@@ -2089,10 +2832,11 @@ pub struct KeypairSyncInfo {
 
 /// Check if encrypted keypair exists in repo
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn check_keypair_sync(
     client: State<'_, HttpClient>,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<KeypairSyncInfo, AppError> {
     validate_repo(&repo)?;
 
@@ -2101,10 +2845,10 @@ pub async fn check_keypair_sync(
     let res = client
         .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if res.status() == 404 {
@@ -2112,7 +2856,7 @@ pub async fn check_keypair_sync(
     }
 
     if !res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to check keypair: {}", res.status())));
+        return Err(response_error(res, "Failed to check keypair").await);
     }
 
     let json: serde_json::Value = res.json().await?;
@@ -2126,11 +2870,12 @@ pub async fn check_keypair_sync(
 
 /// Upload encrypted keypair to repo for cross-device sync
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn upload_keypair_sync(
     client: State<'_, HttpClient>,
     encrypted_keypair: Vec<u8>,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<String, AppError> {
     validate_repo(&repo)?;
 
@@ -2139,10 +2884,10 @@ pub async fn upload_keypair_sync(
     let check_res = client
         .0
         .get(&check_url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     let existing_sha = if check_res.status().is_success() {
@@ -2167,29 +2912,29 @@ pub async fn upload_keypair_sync(
     let res = client
         .0
         .put(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
         .json(&body)
-        .send()
+        .send_with_retry()
         .await?;
 
     if !res.status().is_success() {
-        let status = res.status();
-        let err = res.text().await.unwrap_or_default();
-        return Err(AppError::Api(format!("Failed to upload keypair ({}): {}", status, err)));
+        return Err(response_error(res, "Failed to upload keypair").await);
     }
 
     let json: serde_json::Value = res.json().await?;
+    record_activity(ActivityKind::Key, "upload_keypair_sync", Some(&repo), KEYPAIR_PATH, None);
     Ok(json["content"]["sha"].as_str().unwrap_or("").to_string())
 }
 
 /// Download encrypted keypair from repo
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn download_keypair_sync(
     client: State<'_, HttpClient>,
     repo: String,
-    token: String,
+    token: Zeroizing<String>,
 ) -> Result<Vec<u8>, AppError> {
     validate_repo(&repo)?;
 
@@ -2198,18 +2943,21 @@ pub async fn download_keypair_sync(
     let res = client
         .0
         .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
+        .header("Authorization", Bearer(&token))
         .header("User-Agent", "vortex-image")
         .header("Accept", "application/vnd.github+json")
-        .send()
+        .send_with_retry()
         .await?;
 
     if res.status() == 404 {
-        return Err(AppError::Api("No synced keypair found in repository".into()));
+        return Err(GithubError::NotFound {
+            message: "No synced keypair found in repository".into(),
+        }
+        .into());
     }
 
     if !res.status().is_success() {
-        return Err(AppError::Api(format!("Failed to download keypair: {}", res.status())));
+        return Err(response_error(res, "Failed to download keypair").await);
     }
 
     let json: serde_json::Value = res.json().await?;
@@ -0,0 +1,298 @@
+//! Startup Health Check
+//!
+//! `run_health_check` answers, in one call, whether the app can do its job
+//! on this machine, so the UI can show problems at startup instead of on the
+//! first failed upload:
+//!
+//! - **token** - GitHub accepts the token and it carries the `repo` scope,
+//! - **repo** - the repository exists, is visible to the token and writable,
+//! - **crypto** - the post-quantum backend works and whether it is optimized
+//!   (see `crypto::check_pqcrypto_backend`),
+//! - **keystore** - secrets go to the OS keystore or only to the file fallback,
+//! - **disk** - the download cache has room to grow to its limit.
+//!
+//! Each check reports `ok`, `warning`, `error` or `skipped` with a message;
+//! `status` is the worst of them. A failing check does not fail the command.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::crypto::{check_pqcrypto_backend, PqBackendStatus};
+use crate::download_cache::{cache_dir, cache_stats_in};
+use crate::github::{response_error, validate_repo, AppError, Bearer, HttpClient};
+use crate::keystore::{Keystore, KeystoreInfo};
+use crate::local_store::with_store;
+use crate::retry::SendWithRetry;
+use crate::util::now_secs;
+
+/// Below this much free space the cache cannot work at all
+pub const MIN_FREE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Ordered from least to most severe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Skipped,
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub status: HealthStatus,
+    pub message: String,
+}
+
+impl HealthCheck {
+    fn new(status: HealthStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DiskSpace {
+    pub path: String,
+    pub available_bytes: u64,
+    /// Unpinned photos in the download cache
+    pub cache_bytes: u64,
+    pub cache_limit_bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Worst status of all checks
+    pub status: HealthStatus,
+    pub checked_at: u64,
+    pub token: HealthCheck,
+    pub login: Option<String>,
+    pub scopes: Vec<String>,
+    pub repo: HealthCheck,
+    pub crypto: HealthCheck,
+    pub pq_backend: PqBackendStatus,
+    pub keystore: HealthCheck,
+    pub keystore_info: Option<KeystoreInfo>,
+    pub disk: HealthCheck,
+    pub disk_space: Option<DiskSpace>,
+}
+
+// ============================================================================
+// Checks
+// ============================================================================
+
+/// Scopes of a classic token from its `x-oauth-scopes` header
+pub fn parse_scopes(header: &str) -> Vec<String> {
+    header.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()
+}
+
+/// Fine-grained tokens send no scopes header (`None`); their repository
+/// access shows up in the repo check instead.
+pub fn token_check(login: &str, scopes: Option<&[String]>) -> HealthCheck {
+    match scopes {
+        Some(scopes) if !scopes.iter().any(|s| s == "repo" || s == "public_repo") => HealthCheck::new(
+            HealthStatus::Warning,
+            format!("Signed in as {}, but the token lacks the 'repo' scope", login),
+        ),
+        _ => HealthCheck::new(HealthStatus::Ok, format!("Signed in as {}", login)),
+    }
+}
+
+/// Judge a `GET /repos/{repo}` response
+pub fn repo_check(repo: &str, json: &serde_json::Value) -> HealthCheck {
+    let visibility = if json["private"].as_bool().unwrap_or(false) { "private" } else { "public" };
+    if json["permissions"]["push"].as_bool() == Some(false) {
+        return HealthCheck::new(
+            HealthStatus::Warning,
+            format!("{} ({}) is read-only for this token", repo, visibility),
+        );
+    }
+    HealthCheck::new(HealthStatus::Ok, format!("{} ({}) is reachable", repo, visibility))
+}
+
+pub fn crypto_check(status: &PqBackendStatus) -> HealthCheck {
+    if !status.self_test_passed {
+        let reason = status.self_test_error.as_deref().unwrap_or("unknown error");
+        return HealthCheck::new(HealthStatus::Error, format!("{} backend self-test failed: {}", status.backend, reason));
+    }
+    let speed = match (status.backend.as_str(), status.optimized) {
+        (_, true) => "optimized",
+        ("pqcrypto", false) => "without SIMD acceleration on this CPU",
+        _ => "portable",
+    };
+    HealthCheck::new(HealthStatus::Ok, format!("{} backend, {}", status.backend, speed))
+}
+
+pub fn keystore_check(info: &KeystoreInfo) -> HealthCheck {
+    match &info.fallback_reason {
+        Some(reason) => HealthCheck::new(
+            HealthStatus::Warning,
+            format!("Using the {:?} backend: {}", info.backend, reason),
+        ),
+        None => HealthCheck::new(HealthStatus::Ok, format!("Using the {:?} backend", info.backend)),
+    }
+}
+
+/// Free space against the cache's remaining room to grow
+pub fn disk_check(space: &DiskSpace) -> HealthCheck {
+    let wanted = space.cache_limit_bytes.saturating_sub(space.cache_bytes);
+    let free_mb = space.available_bytes / (1024 * 1024);
+    if space.available_bytes < MIN_FREE_BYTES {
+        HealthCheck::new(HealthStatus::Error, format!("Only {} MB free for the download cache", free_mb))
+    } else if space.available_bytes < wanted {
+        HealthCheck::new(
+            HealthStatus::Warning,
+            format!("{} MB free, less than the cache may still use ({} MB)", free_mb, wanted / (1024 * 1024)),
+        )
+    } else {
+        HealthCheck::new(HealthStatus::Ok, format!("{} MB free", free_mb))
+    }
+}
+
+pub fn overall_status(checks: &[&HealthCheck]) -> HealthStatus {
+    checks.iter().map(|c| c.status).max().unwrap_or(HealthStatus::Skipped)
+}
+
+/// The directory itself, or its closest parent that exists yet
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|p| p.exists())
+}
+
+fn disk_space() -> Result<DiskSpace, AppError> {
+    let dir = cache_dir()?;
+    let probe = existing_ancestor(&dir).ok_or_else(|| AppError::Validation("No existing cache directory".into()))?;
+    let available_bytes = fs2::available_space(probe)?;
+    let stats = with_store(cache_stats_in)?;
+    Ok(DiskSpace {
+        path: dir.to_string_lossy().into_owned(),
+        available_bytes,
+        cache_bytes: stats.bytes.saturating_sub(stats.pinned_bytes),
+        cache_limit_bytes: stats.limit_bytes,
+    })
+}
+
+struct LocalChecks {
+    pq_backend: PqBackendStatus,
+    keystore: Result<KeystoreInfo, String>,
+    disk: Result<DiskSpace, AppError>,
+}
+
+fn local_checks() -> LocalChecks {
+    LocalChecks {
+        pq_backend: check_pqcrypto_backend(),
+        keystore: Keystore::detect().map(|k| k.info()).map_err(|e| e.to_string()),
+        disk: disk_space(),
+    }
+}
+
+async fn fetch_user(client: &Client, token: &str) -> Result<(String, Option<Vec<String>>), AppError> {
+    let res = client
+        .get("https://api.github.com/user")
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .send_with_retry()
+        .await?;
+    if !res.status().is_success() {
+        return Err(response_error(res, "Token check failed").await);
+    }
+    let scopes = res.headers().get("x-oauth-scopes").and_then(|v| v.to_str().ok()).map(parse_scopes);
+    let json: serde_json::Value = res.json().await?;
+    let login = json["login"].as_str().unwrap_or_default().to_string();
+    Ok((login, scopes))
+}
+
+async fn fetch_repo(client: &Client, token: &str, repo: &str) -> Result<HealthCheck, AppError> {
+    validate_repo(repo)?;
+    let res = client
+        .get(format!("https://api.github.com/repos/{}", repo))
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+    if !res.status().is_success() {
+        return Err(response_error(res, "Repository check failed").await);
+    }
+    let json: serde_json::Value = res.json().await?;
+    Ok(repo_check(repo, &json))
+}
+
+pub(crate) async fn health_report(client: &Client, token: Option<&str>, repo: Option<&str>) -> Result<HealthReport, AppError> {
+    let local = tauri::async_runtime::spawn_blocking(local_checks);
+    let skipped = |what: &str| HealthCheck::new(HealthStatus::Skipped, format!("No {} given", what));
+
+    let (user, repo_check) = futures::join!(
+        async {
+            match token {
+                Some(token) => Some(fetch_user(client, token).await),
+                None => None,
+            }
+        },
+        async {
+            match (token, repo) {
+                (Some(token), Some(repo)) => Some(fetch_repo(client, token, repo).await),
+                _ => None,
+            }
+        }
+    );
+
+    let (token_check, login, scopes) = match user {
+        Some(Ok((login, scopes))) => (token_check(&login, scopes.as_deref()), Some(login), scopes.unwrap_or_default()),
+        Some(Err(e)) => (HealthCheck::new(HealthStatus::Error, e.to_string()), None, Vec::new()),
+        None => (skipped("token"), None, Vec::new()),
+    };
+    let repo = match repo_check {
+        Some(Ok(check)) => check,
+        Some(Err(e)) => HealthCheck::new(HealthStatus::Error, e.to_string()),
+        None => skipped(if token.is_none() { "token" } else { "repository" }),
+    };
+
+    let local = local
+        .await
+        .map_err(|e| AppError::Validation(format!("Health check task failed: {}", e)))?;
+    let crypto = crypto_check(&local.pq_backend);
+    let keystore = match &local.keystore {
+        Ok(info) => keystore_check(info),
+        Err(e) => HealthCheck::new(HealthStatus::Error, format!("No keystore available: {}", e)),
+    };
+    let disk = match &local.disk {
+        Ok(space) => disk_check(space),
+        Err(e) => HealthCheck::new(HealthStatus::Warning, format!("Could not measure free space: {}", e)),
+    };
+
+    Ok(HealthReport {
+        status: overall_status(&[&token_check, &repo, &crypto, &keystore, &disk]),
+        checked_at: now_secs(),
+        token: token_check,
+        login,
+        scopes,
+        repo,
+        crypto,
+        pq_backend: local.pq_backend,
+        keystore,
+        keystore_info: local.keystore.ok(),
+        disk,
+        disk_space: local.disk.ok(),
+    })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Check the token, repository, crypto backend, keystore and disk space.
+/// Without a token the GitHub checks are skipped.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn run_health_check(
+    client: State<'_, HttpClient>,
+    token: Option<Zeroizing<String>>,
+    repo: Option<String>,
+) -> Result<HealthReport, AppError> {
+    health_report(&client.0, token.as_ref().map(|t| t.as_str()), repo.as_deref()).await
+}
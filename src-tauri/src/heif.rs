@@ -0,0 +1,241 @@
+//! HEIF/HEIC Decoding
+//!
+//! iPhones save photos as HEIC, which `image` cannot decode. With the
+//! optional `heif` feature they are decoded through libheif, which applies
+//! the container's rotation and mirroring, so everything built on
+//! `decode_upright` (thumbnails, transcoding, perceptual hashes) handles
+//! them like any other photo. Without it, HEIF photos still report their
+//! size, read from the container, but cannot be rendered.
+//!
+//! `convert_heif_image` writes a JPEG or WebP copy for apps that cannot
+//! show HEIC, and `upload_photo` can convert on the way up when its
+//! settings ask for it. Copies carry no EXIF, as with transcoding.
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::compress_stream::write_via_partial;
+use crate::entropy::{detect_kind, ContentKind};
+use crate::github::AppError;
+use crate::transcode::{encode_jpeg, encode_webp};
+
+const DEFAULT_QUALITY: u8 = 85;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayFormat {
+    Jpeg,
+    Webp,
+}
+
+impl DisplayFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+fn default_quality() -> u8 {
+    DEFAULT_QUALITY
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HeifConversion {
+    pub format: DisplayFormat,
+    /// 1 to 100, as in the JPEG quality scale
+    #[serde(default = "default_quality")]
+    pub quality: u8,
+}
+
+impl Default for HeifConversion {
+    fn default() -> Self {
+        Self {
+            format: DisplayFormat::Jpeg,
+            quality: DEFAULT_QUALITY,
+        }
+    }
+}
+
+impl HeifConversion {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !(1..=100).contains(&self.quality) {
+            return Err(AppError::Validation("Quality must be 1-100".into()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeifConversionResult {
+    pub output_path: String,
+    pub width: u32,
+    pub height: u32,
+    pub original_size: u64,
+    pub converted_size: u64,
+}
+
+pub fn is_heif(data: &[u8]) -> bool {
+    detect_kind(data) == ContentKind::Heic
+}
+
+// ============================================================================
+// Container
+// ============================================================================
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
+
+/// Contents of the top-level `meta` box
+fn meta_box(data: &[u8]) -> Option<&[u8]> {
+    let mut at = 0;
+    while at + 8 <= data.len() {
+        let size = be_u32(data, at)? as u64;
+        let (header, size) = match size {
+            0 => (8, (data.len() - at) as u64),
+            1 => (16, u64::from_be_bytes(data.get(at + 8..at + 16)?.try_into().ok()?)),
+            _ => (8, size),
+        };
+        if size < header as u64 {
+            return None;
+        }
+        let end = at.checked_add(usize::try_from(size).ok()?)?.min(data.len());
+        if data.get(at + 4..at + 8)? == b"meta" {
+            return data.get(at + header..end);
+        }
+        at = end;
+    }
+    None
+}
+
+/// Size of the primary image as displayed, read from the image spatial
+/// extents in the `meta` box. Grid images list their tiles' extents too,
+/// so the largest is the full image; a quarter or three-quarter turn in
+/// `irot` swaps the sides.
+pub fn heif_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if !is_heif(data) {
+        return None;
+    }
+    let meta = meta_box(data)?;
+    let (width, height) = meta
+        .windows(4)
+        .enumerate()
+        .filter(|(_, w)| *w == b"ispe")
+        .filter_map(|(at, _)| Some((be_u32(meta, at + 8)?, be_u32(meta, at + 12)?)))
+        .filter(|(w, h)| *w > 0 && *h > 0)
+        .max_by_key(|(w, h)| *w as u64 * *h as u64)?;
+    let turned = meta
+        .windows(4)
+        .position(|w| w == b"irot")
+        .and_then(|at| meta.get(at + 4))
+        .is_some_and(|angle| angle & 1 == 1);
+    Some(if turned { (height, width) } else { (width, height) })
+}
+
+// ============================================================================
+// Decoding
+// ============================================================================
+
+/// Decode the primary image of `data`, upright
+#[cfg(feature = "heif")]
+pub fn decode_heif(data: &[u8]) -> Result<DynamicImage, AppError> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let decode_error = |e: libheif_rs::HeifError| AppError::Validation(format!("Cannot decode HEIF image: {}", e));
+    let context = HeifContext::read_from_bytes(data).map_err(decode_error)?;
+    let handle = context.primary_image_handle().map_err(decode_error)?;
+    let decoded = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(decode_error)?;
+    let plane = decoded
+        .planes()
+        .interleaved
+        .ok_or_else(|| AppError::Validation("HEIF image has no RGBA plane".into()))?;
+
+    // Rows may be padded past width * 4 bytes
+    let row = plane.width as usize * 4;
+    let pixels = (0..plane.height as usize)
+        .flat_map(|y| &plane.data[y * plane.stride..y * plane.stride + row])
+        .copied()
+        .collect();
+    image::RgbaImage::from_raw(plane.width, plane.height, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| AppError::Validation("HEIF image has an invalid size".into()))
+}
+
+#[cfg(not(feature = "heif"))]
+pub fn decode_heif(_data: &[u8]) -> Result<DynamicImage, AppError> {
+    Err(AppError::Validation("HEIF support is not included in this build".into()))
+}
+
+// ============================================================================
+// Conversion
+// ============================================================================
+
+/// Convert the HEIF image `data`, returning the encoded copy and its size
+pub fn convert_heif(data: &[u8], conversion: &HeifConversion) -> Result<(Vec<u8>, (u32, u32)), AppError> {
+    conversion.validate()?;
+    let image = decode_heif(data)?;
+    let encoded = match conversion.format {
+        DisplayFormat::Jpeg => encode_jpeg(&image, conversion.quality)?,
+        DisplayFormat::Webp => encode_webp(&image, conversion.quality)?,
+    };
+    Ok((encoded, (image.width(), image.height())))
+}
+
+/// `name` with its extension replaced by the one of `format`
+pub fn converted_name(name: &str, format: DisplayFormat) -> String {
+    Path::new(name).with_extension(format.extension()).to_string_lossy().to_string()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Write a JPEG (default) or WebP copy of the HEIF image at `input_path`
+/// to `output_path`, or next to it with the new extension. The original
+/// is kept.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn convert_heif_image(
+    input_path: String,
+    output_path: Option<String>,
+    conversion: Option<HeifConversion>,
+) -> Result<HeifConversionResult, AppError> {
+    let conversion = conversion.unwrap_or_default();
+    conversion.validate()?;
+
+    tokio::task::spawn_blocking(move || {
+        let input = PathBuf::from(&input_path);
+        let data = std::fs::read(&input)?;
+        if !is_heif(&data) {
+            return Err(AppError::Validation("Not a HEIF image".into()));
+        }
+        let (output, (width, height)) = convert_heif(&data, &conversion)?;
+
+        let target = output_path
+            .map(PathBuf::from)
+            .unwrap_or_else(|| input.with_extension(conversion.format.extension()));
+        if target == input {
+            return Err(AppError::Validation("Output would overwrite the original".into()));
+        }
+        write_via_partial(&target, |mut writer| {
+            writer.write_all(&output)?;
+            writer.flush()?;
+            Ok(())
+        })?;
+        Ok(HeifConversionResult {
+            output_path: target.to_string_lossy().to_string(),
+            width,
+            height,
+            original_size: data.len() as u64,
+            converted_size: output.len() as u64,
+        })
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Conversion task failed: {}", e)))?
+}
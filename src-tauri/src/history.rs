@@ -0,0 +1,214 @@
+//! Album History and Point-in-Time Restore
+//!
+//! Every change to an album is a Git commit, so the repository already holds
+//! its full history. `get_album_history` lists the commits that touched an
+//! album with the files each one changed.
+//!
+//! `restore_album_to_commit` never rewrites history: it adds a new commit on
+//! top of the default branch whose album folder matches the chosen commit.
+//! Photos that were deleted since come back (blobs are reused, nothing is
+//! re-uploaded), photos added since are removed, and the restore itself can
+//! be undone by restoring to the commit before it.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::git_data::{
+    branch_head, commit_changes, commit_tree, get_json, get_tree_recursive, index_blobs, TreeChange, TreeIndex,
+};
+use crate::github::{validate_repo, AppError, HttpClient};
+use crate::mirror::replicate_tree_changes;
+
+const DEFAULT_HISTORY_LIMIT: u32 = 20;
+const MAX_HISTORY_LIMIT: u32 = 50;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangedFile {
+    pub path: String,
+    /// `added`, `removed`, `modified`, `renamed`, ...
+    pub status: String,
+    pub previous_path: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlbumCommit {
+    pub sha: String,
+    pub message: String,
+    pub author: String,
+    /// ISO 8601 commit date
+    pub date: String,
+    /// Files inside the album changed by this commit
+    pub files: Vec<ChangedFile>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RestoreReport {
+    /// New commit performing the restore, `None` if the album already matched
+    pub commit_sha: Option<String>,
+    pub restored: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+fn normalize_album(album_path: &str) -> Result<String, AppError> {
+    let album = album_path.trim().trim_matches('/');
+    if album.is_empty() || album.split('/').any(|s| s.is_empty() || s == "." || s == "..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    Ok(album.to_string())
+}
+
+fn validate_commit_sha(sha: &str) -> Result<(), AppError> {
+    if (7..=40).contains(&sha.len()) && sha.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(AppError::Validation("Invalid commit SHA".into()))
+    }
+}
+
+fn in_album(path: &str, album: &str) -> bool {
+    path.strip_prefix(album).is_some_and(|rest| rest.starts_with('/'))
+}
+
+// ============================================================================
+// Planning
+// ============================================================================
+
+/// Tree changes that make `album` in `current` match `target`.
+/// Changes are sorted by path so commits are reproducible.
+pub fn plan_restore(current: &TreeIndex, target: &TreeIndex, album: &str) -> Vec<TreeChange> {
+    let mut changes: Vec<TreeChange> = target
+        .iter()
+        .filter(|(path, _)| in_album(path, album))
+        .filter(|(path, entry)| {
+            current
+                .get(*path)
+                .map(|c| c.sha != entry.sha || c.mode != entry.mode)
+                .unwrap_or(true)
+        })
+        .map(|(path, entry)| TreeChange::put_blob(path, entry))
+        .chain(
+            current
+                .keys()
+                .filter(|path| in_album(path, album) && !target.contains_key(*path))
+                .map(|path| TreeChange::delete(path)),
+        )
+        .collect();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// Keep only the files of a commit that belong to `album`
+pub fn album_files(files: &[serde_json::Value], album: &str) -> Vec<ChangedFile> {
+    files
+        .iter()
+        .filter_map(|f| {
+            let path = f["filename"].as_str()?;
+            let previous = f["previous_filename"].as_str();
+            if !in_album(path, album) && !previous.is_some_and(|p| in_album(p, album)) {
+                return None;
+            }
+            Some(ChangedFile {
+                path: path.to_string(),
+                status: f["status"].as_str().unwrap_or("modified").to_string(),
+                previous_path: previous.map(|p| p.to_string()),
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+async fn commit_details(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    sha: &str,
+    album: &str,
+) -> Result<AlbumCommit, AppError> {
+    let url = format!("https://api.github.com/repos/{}/commits/{}", repo, sha);
+    let json = get_json(client, token, &url, "get commit").await?;
+    let commit = &json["commit"];
+
+    Ok(AlbumCommit {
+        sha: sha.to_string(),
+        message: commit["message"].as_str().unwrap_or("").to_string(),
+        author: commit["author"]["name"].as_str().unwrap_or("").to_string(),
+        date: commit["author"]["date"].as_str().unwrap_or("").to_string(),
+        files: album_files(json["files"].as_array().map(Vec::as_slice).unwrap_or(&[]), album),
+    })
+}
+
+/// Commits that touched an album, newest first. `page` starts at 1.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn get_album_history(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    limit: Option<u32>,
+    page: Option<u32>,
+) -> Result<Vec<AlbumCommit>, AppError> {
+    validate_repo(&repo)?;
+    let album = normalize_album(&album_path)?;
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+
+    let url = format!(
+        "https://api.github.com/repos/{}/commits?path={}&per_page={}&page={}",
+        repo,
+        album,
+        limit,
+        page.unwrap_or(1).max(1)
+    );
+    let json = get_json(&client.0, &token, &url, "list album history").await?;
+
+    let mut history = Vec::new();
+    for sha in json.as_array().into_iter().flatten().filter_map(|c| c["sha"].as_str()) {
+        history.push(commit_details(&client.0, &repo, &token, sha, &album).await?);
+    }
+    Ok(history)
+}
+
+/// Make the album match its state at `commit_sha` with a new commit
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn restore_album_to_commit(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    commit_sha: String,
+) -> Result<RestoreReport, AppError> {
+    validate_repo(&repo)?;
+    let album = normalize_album(&album_path)?;
+    validate_commit_sha(&commit_sha)?;
+
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let current = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let target_tree = commit_tree(&client.0, &repo, &token, &commit_sha).await?;
+    let target = index_blobs(get_tree_recursive(&client.0, &repo, &token, &target_tree).await?);
+
+    let changes = plan_restore(&current, &target, &album);
+    let (removed, restored): (Vec<_>, Vec<_>) = changes.iter().partition(|c| c.sha.is_none());
+    let restored: Vec<String> = restored.into_iter().map(|c| c.path.clone()).collect();
+    let removed: Vec<String> = removed.into_iter().map(|c| c.path.clone()).collect();
+
+    if changes.is_empty() {
+        return Ok(RestoreReport { commit_sha: None, restored, removed });
+    }
+
+    let short = &commit_sha[..7];
+    let message = format!("Restore {} to {}", album, short);
+    let commit = commit_changes(&client.0, &repo, &token, &head, &changes, &message).await?;
+    replicate_tree_changes(&client.0, &repo, &token, &changes);
+
+    Ok(RestoreReport {
+        commit_sha: Some(commit),
+        restored,
+        removed,
+    })
+}
@@ -0,0 +1,150 @@
+//! Secret Hygiene Accounting
+//!
+//! `SecretBytes` and `SecretKey32` report here when they are created and
+//! wiped. Each one tries to pin its buffer in RAM (mlock / VirtualLock) so it
+//! never reaches swap; when the OS refuses (RLIMIT_MEMLOCK, sandboxing) the
+//! secret is still zeroized on drop and the failure is counted.
+//!
+//! `crypto_hygiene_report` lists live secrets by kind with how many are
+//! locked, plus the secrets held in `Zeroizing` wrappers (tokens, passwords,
+//! derived keys), which are wiped but never locked. The `HeaderValue` built
+//! from a token is a copy owned by reqwest; it is marked sensitive, which
+//! keeps it out of logs, but it is not wiped.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+lazy_static::lazy_static! {
+    static ref LEDGER: Mutex<BTreeMap<&'static str, KindStats>> = Mutex::new(BTreeMap::new());
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct KindStats {
+    live: u64,
+    locked: u64,
+    live_bytes: u64,
+    wiped: u64,
+    lock_failures: u64,
+}
+
+/// Live secrets of one kind
+#[derive(Clone, Debug, Serialize)]
+pub struct SecretKindReport {
+    pub kind: String,
+    pub live: u64,
+    /// How many of the live secrets are pinned in RAM
+    pub memory_locked: u64,
+    pub live_bytes: u64,
+    /// Secrets of this kind zeroized so far
+    pub zeroized: u64,
+    pub lock_failures: u64,
+}
+
+/// A secret holder that zeroizes without locking memory
+#[derive(Clone, Debug, Serialize)]
+pub struct WipedOnlySecret {
+    pub secret: &'static str,
+    pub holder: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HygieneReport {
+    /// Whether this process may lock memory at all
+    pub memory_lock_available: bool,
+    pub kinds: Vec<SecretKindReport>,
+    pub zeroized_only: Vec<WipedOnlySecret>,
+}
+
+/// Secrets kept in `zeroize::Zeroizing` rather than the locked secret types
+const ZEROIZED_ONLY: &[WipedOnlySecret] = &[
+    WipedOnlySecret { secret: "GitHub tokens passed to commands, watchers and the offline queue", holder: "Zeroizing<String>" },
+    WipedOnlySecret { secret: "Formatted Authorization header strings", holder: "Zeroizing<String>" },
+    WipedOnlySecret { secret: "Tokens passed to secure_store_token", holder: "Zeroizing<String>" },
+    WipedOnlySecret { secret: "Vault, time-lock and data op passwords", holder: "Zeroizing<String>" },
+    WipedOnlySecret { secret: "Derived symmetric and session keys", holder: "Zeroizing<[u8; 32]>" },
+    WipedOnlySecret { secret: "Recovery shares being combined", holder: "Zeroizing<Vec<u8>>" },
+    WipedOnlySecret { secret: "Local store index and value keys", holder: "Zeroizing<[u8; 32]>" },
+];
+
+/// Try to pin `len` bytes at `ptr` in RAM. The guard is released by
+/// `unlock`, not on drop, so the buffer owner decides when.
+pub(crate) fn lock(ptr: *const u8, len: usize) -> bool {
+    if len == 0 {
+        return false;
+    }
+    match region::lock(ptr, len) {
+        Ok(guard) => {
+            std::mem::forget(guard);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Release a lock taken by `lock`. Locks are per page, so this may also
+/// unpin a neighbouring secret sharing the page; it stays zeroized on drop.
+pub(crate) fn unlock(ptr: *const u8, len: usize) {
+    let _ = region::unlock(ptr, len);
+}
+
+pub(crate) fn record_created(kind: &'static str, len: usize, locked: bool) {
+    let mut ledger = LEDGER.lock().unwrap();
+    let stats = ledger.entry(kind).or_default();
+    stats.live += 1;
+    stats.live_bytes += len as u64;
+    if locked {
+        stats.locked += 1;
+    } else if len > 0 {
+        stats.lock_failures += 1;
+    }
+}
+
+pub(crate) fn record_wiped(kind: &'static str, len: usize, locked: bool) {
+    let mut ledger = LEDGER.lock().unwrap();
+    let stats = ledger.entry(kind).or_default();
+    stats.live = stats.live.saturating_sub(1);
+    stats.live_bytes = stats.live_bytes.saturating_sub(len as u64);
+    if locked {
+        stats.locked = stats.locked.saturating_sub(1);
+    }
+    stats.wiped += 1;
+}
+
+fn probe_memory_lock() -> bool {
+    let probe = Box::new([0u8; 32]);
+    let locked = lock(probe.as_ptr(), probe.len());
+    if locked {
+        unlock(probe.as_ptr(), probe.len());
+    }
+    locked
+}
+
+pub fn hygiene_report() -> HygieneReport {
+    let kinds = LEDGER
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(kind, stats)| SecretKindReport {
+            kind: kind.to_string(),
+            live: stats.live,
+            memory_locked: stats.locked,
+            live_bytes: stats.live_bytes,
+            zeroized: stats.wiped,
+            lock_failures: stats.lock_failures,
+        })
+        .collect();
+    HygieneReport {
+        memory_lock_available: probe_memory_lock(),
+        kinds,
+        zeroized_only: ZEROIZED_ONLY.to_vec(),
+    }
+}
+
+/// Report which secrets are memory-locked and zeroized right now
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn crypto_hygiene_report() -> HygieneReport {
+    hygiene_report()
+}
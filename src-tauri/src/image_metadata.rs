@@ -0,0 +1,483 @@
+//! Image Metadata Extraction
+//!
+//! `get_image_metadata` reports what a local photo says about itself: EXIF
+//! (camera, lens, exposure, timestamps, GPS, orientation) and the XMP packet
+//! written by editors such as Lightroom or darktable (rating, title,
+//! keywords). XMP also fills fields the EXIF block lacks, and RAW files
+//! without an embedded packet are read together with their `.xmp` sidecar.
+//!
+//! The same merge feeds the EXIF extract stored in album vaults on upload
+//! (`PhotoMetadata::for_upload`). `taken_at_unix` gives every photo one
+//! comparable capture time for browsing by date.
+//!
+//! XMP is matched on the conventional namespace prefixes (`xmp:`, `exif:`,
+//! `tiff:`, `dc:` ...) rather than through a full RDF parser; that covers
+//! what cameras and common editors write.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::fs;
+
+use crate::entropy::detect_kind;
+use crate::github::AppError;
+use crate::metadata_vault::{extract_exif, ExifExtract};
+use crate::raw::{is_raw, raw_exif};
+use crate::upload_policy::image_dimensions;
+
+const XMP_START: &[u8] = b"<x:xmpmeta";
+const XMP_END: &[u8] = b"</x:xmpmeta>";
+const SIDECAR_EXT: &str = "xmp";
+
+/// Fields read from an XMP packet
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct XmpExtract {
+    /// `YYYY:MM:DD HH:MM:SS`, like the EXIF fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taken_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_offset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_make: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lens_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// -1 (rejected) to 5 stars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ImageMetadata {
+    pub name: String,
+    pub size: u64,
+    /// Detected content kind, such as `jpeg`
+    pub format: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// EXIF with gaps filled from XMP
+    pub exif: Option<ExifExtract>,
+    pub xmp: Option<XmpExtract>,
+    /// Capture time in Unix seconds. Without a recorded UTC offset the
+    /// camera's local time is taken as UTC.
+    pub taken_at_unix: Option<i64>,
+}
+
+// ============================================================================
+// Timestamps
+// ============================================================================
+
+struct DateTimeParts {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    /// Seconds east of UTC, when the string carried one
+    offset: Option<i64>,
+}
+
+fn number(s: &str, range: std::ops::RangeInclusive<u32>) -> Option<u32> {
+    s.parse().ok().filter(|n| range.contains(n))
+}
+
+/// `Z`, `+HH:MM`, `-HH:MM` or `+HHMM` as seconds east of UTC
+fn parse_offset(s: &str) -> Option<i64> {
+    if s == "Z" {
+        return Some(0);
+    }
+    let sign = match s.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits: String = s[1..].chars().filter(|c| *c != ':').collect();
+    if digits.len() != 4 || !digits.is_ascii() {
+        return None;
+    }
+    let hours = number(&digits[..2], 0..=14)? as i64;
+    let minutes = number(&digits[2..], 0..=59)? as i64;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+fn format_offset(offset: i64) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.abs() / 60;
+    format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+/// EXIF (`YYYY:MM:DD HH:MM:SS`) or ISO 8601 date and time. The time and
+/// the offset are optional, as in XMP.
+fn parse_date_time(s: &str) -> Option<DateTimeParts> {
+    let s = s.trim();
+    let date = s.get(..10).filter(|d| d.is_ascii())?;
+    let bytes = date.as_bytes();
+    if !matches!((bytes[4], bytes[7]), (b':', b':') | (b'-', b'-')) {
+        return None;
+    }
+    let mut parts = DateTimeParts {
+        year: number(&date[..4], 1..=9999)? as i64,
+        month: number(&date[5..7], 1..=12)?,
+        day: number(&date[8..10], 1..=31)?,
+        hour: 0,
+        minute: 0,
+        second: 0,
+        offset: None,
+    };
+
+    let rest = &s[10..];
+    if rest.is_empty() {
+        return Some(parts);
+    }
+    if !rest.starts_with([' ', 'T']) {
+        return None;
+    }
+    let time = rest.get(1..6).filter(|t| t.is_ascii())?;
+    if time.as_bytes()[2] != b':' {
+        return None;
+    }
+    parts.hour = number(&time[..2], 0..=23)?;
+    parts.minute = number(&time[3..5], 0..=59)?;
+    let mut rest = &rest[6..];
+    if let Some(seconds) = rest.strip_prefix(':') {
+        parts.second = number(seconds.get(..2)?, 0..=60)?;
+        rest = &seconds[2..];
+    }
+    if let Some(fraction) = rest.strip_prefix('.') {
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+    }
+    if !rest.is_empty() {
+        parts.offset = Some(parse_offset(rest.trim())?);
+    }
+    Some(parts)
+}
+
+/// Days from 1970-01-01 to a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+impl DateTimeParts {
+    fn exif_style(&self) -> String {
+        format!(
+            "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+
+    fn unix(&self, offset: i64) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * 86_400
+            + (self.hour * 3600 + self.minute * 60 + self.second) as i64
+            - offset
+    }
+}
+
+/// Capture time of `exif` in Unix seconds, using its recorded UTC offset
+pub fn capture_time(exif: &ExifExtract) -> Option<i64> {
+    let parts = parse_date_time(exif.taken_at.as_deref()?)?;
+    let offset = parts
+        .offset
+        .or_else(|| exif.time_offset.as_deref().and_then(parse_offset))
+        .unwrap_or(0);
+    Some(parts.unix(offset))
+}
+
+// ============================================================================
+// XMP
+// ============================================================================
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| i + from)
+}
+
+/// The `x:xmpmeta` element embedded anywhere in `content`
+pub fn xmp_packet(content: &[u8]) -> Option<String> {
+    let start = find(content, XMP_START, 0)?;
+    let end = find(content, XMP_END, start)? + XMP_END.len();
+    Some(String::from_utf8_lossy(&content[start..end]).into_owned())
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let decoded = match &rest[1..semi] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16).ok())
+                .unwrap_or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `name="value"` on any element of the packet
+fn attribute<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let mut from = 0;
+    while let Some(i) = xml[from..].find(name).map(|i| i + from) {
+        from = i + name.len();
+        let preceded = xml[..i].ends_with(|c: char| c.is_whitespace());
+        let rest = xml[from..].trim_start();
+        let Some(value) = rest.strip_prefix('=').map(str::trim_start) else { continue };
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else { continue };
+        if preceded {
+            let value = &value[1..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+    }
+    None
+}
+
+/// Contents of the first `<name>` element
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut from = 0;
+    while let Some(i) = xml[from..].find(&open).map(|i| i + from) {
+        from = i + open.len();
+        let rest = &xml[from..];
+        if !rest.starts_with(|c: char| c == '>' || c.is_whitespace()) {
+            continue;
+        }
+        let tag_end = rest.find('>')?;
+        if rest[..tag_end].ends_with('/') {
+            continue;
+        }
+        let inner = &rest[tag_end + 1..];
+        return inner.find(&close).map(|end| &inner[..end]);
+    }
+    None
+}
+
+/// Text of every `rdf:li` in `xml`
+fn list_items(xml: &str) -> Vec<String> {
+    const CLOSE: &str = "</rdf:li>";
+    let mut items = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find("<rdf:li") {
+        let item = &rest[start..];
+        let Some(open_end) = item.find('>') else { break };
+        if item[..open_end].ends_with('/') {
+            rest = &item[open_end + 1..];
+            continue;
+        }
+        let Some(close) = item.find(CLOSE) else { break };
+        let text = unescape(item[open_end + 1..close].trim());
+        if !text.is_empty() {
+            items.push(text);
+        }
+        rest = &item[close + CLOSE.len()..];
+    }
+    items
+}
+
+/// Simple property, written either as an attribute or as an element; for
+/// alternatives such as `dc:title` the first item
+fn property(xml: &str, name: &str) -> Option<String> {
+    let value = match attribute(xml, name) {
+        Some(value) => unescape(value),
+        None => {
+            let inner = element(xml, name)?;
+            if inner.contains("<rdf:li") {
+                list_items(inner).into_iter().next()?
+            } else {
+                unescape(inner)
+            }
+        }
+    };
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// XMP GPS coordinate: `DDD,MM.mmk` or `DDD,MM,SSk`, with `k` one of N, S, E, W
+fn xmp_coordinate(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let direction = value.chars().last()?;
+    let sign = match direction.to_ascii_uppercase() {
+        'N' | 'E' => 1.0,
+        'S' | 'W' => -1.0,
+        _ => return None,
+    };
+    let parts: Vec<f64> = value[..value.len() - 1]
+        .split(',')
+        .map(|p| p.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let degrees = match parts[..] {
+        [d, m] => d + m / 60.0,
+        [d, m, s] => d + m / 60.0 + s / 3600.0,
+        _ => return None,
+    };
+    degrees.is_finite().then_some(sign * degrees)
+}
+
+/// Read the fields of `XmpExtract` from an XMP packet
+pub fn parse_xmp(xml: &str) -> Option<XmpExtract> {
+    let date = ["exif:DateTimeOriginal", "photoshop:DateCreated", "xmp:CreateDate"]
+        .iter()
+        .find_map(|name| parse_date_time(&property(xml, name)?));
+    let keywords = element(xml, "dc:subject").map(list_items).unwrap_or_default();
+
+    let extract = XmpExtract {
+        taken_at: date.as_ref().map(DateTimeParts::exif_style),
+        time_offset: date.as_ref().and_then(|d| d.offset).map(format_offset),
+        camera_make: property(xml, "tiff:Make"),
+        camera_model: property(xml, "tiff:Model"),
+        lens_model: property(xml, "exifEX:LensModel").or_else(|| property(xml, "aux:Lens")),
+        orientation: property(xml, "tiff:Orientation").and_then(|v| v.parse().ok()),
+        latitude: property(xml, "exif:GPSLatitude").as_deref().and_then(xmp_coordinate),
+        longitude: property(xml, "exif:GPSLongitude").as_deref().and_then(xmp_coordinate),
+        rating: property(xml, "xmp:Rating")
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|r| r.round().clamp(-1.0, 5.0) as i32),
+        title: property(xml, "dc:title"),
+        description: property(xml, "dc:description"),
+        keywords,
+    };
+    (extract != XmpExtract::default()).then_some(extract)
+}
+
+pub fn extract_xmp(content: &[u8]) -> Option<XmpExtract> {
+    parse_xmp(&xmp_packet(content)?)
+}
+
+// ============================================================================
+// Merging
+// ============================================================================
+
+fn fill<T: Clone>(slot: &mut Option<T>, value: &Option<T>) {
+    if slot.is_none() {
+        slot.clone_from(value);
+    }
+}
+
+/// `exif` with the fields it lacks taken from `xmp`
+pub fn merge_xmp(exif: Option<ExifExtract>, xmp: Option<&XmpExtract>) -> Option<ExifExtract> {
+    let mut merged = exif.unwrap_or_default();
+    if let Some(xmp) = xmp {
+        if merged.taken_at.is_none() {
+            merged.taken_at.clone_from(&xmp.taken_at);
+            merged.time_offset.clone_from(&xmp.time_offset);
+        }
+        fill(&mut merged.camera_make, &xmp.camera_make);
+        fill(&mut merged.camera_model, &xmp.camera_model);
+        fill(&mut merged.lens_model, &xmp.lens_model);
+        fill(&mut merged.orientation, &xmp.orientation);
+        if merged.latitude.is_none() && merged.longitude.is_none() {
+            merged.latitude = xmp.latitude;
+            merged.longitude = xmp.longitude;
+        }
+    }
+    (merged != ExifExtract::default()).then_some(merged)
+}
+
+/// EXIF of a photo with gaps filled from its embedded XMP
+pub fn photo_exif(content: &[u8]) -> Option<ExifExtract> {
+    let exif = if is_raw(content) { raw_exif(content) } else { extract_exif(content) };
+    merge_xmp(exif, extract_xmp(content).as_ref())
+}
+
+/// Everything `get_image_metadata` reports about `content`. `sidecar` is
+/// an XMP file stored next to it, used when the image has no packet of its
+/// own.
+///
+/// RAW files report their sensor size and the EXIF of the file or its
+/// preview, and their extension as the format.
+pub fn describe_image(name: &str, content: &[u8], sidecar: Option<&[u8]>) -> ImageMetadata {
+    let xmp = extract_xmp(content).or_else(|| sidecar.and_then(extract_xmp));
+    let raw = is_raw(content);
+    let (exif, dimensions, format) = if raw {
+        let format = Path::new(name).extension().map(|e| e.to_string_lossy().to_lowercase());
+        (raw_exif(content), None, format)
+    } else {
+        let format = serde_json::to_value(detect_kind(content))
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string));
+        (extract_exif(content), image_dimensions(content), format)
+    };
+    let exif = merge_xmp(exif, xmp.as_ref());
+    ImageMetadata {
+        name: name.to_string(),
+        size: content.len() as u64,
+        format,
+        width: dimensions.map(|(w, _)| w).or_else(|| exif.as_ref()?.width),
+        height: dimensions.map(|(_, h)| h).or_else(|| exif.as_ref()?.height),
+        taken_at_unix: exif.as_ref().and_then(capture_time),
+        exif,
+        xmp,
+    }
+}
+
+/// `photo.xmp` or `photo.cr3.xmp`, whichever exists
+fn sidecar_path(path: &Path) -> Option<std::path::PathBuf> {
+    let mut appended = path.as_os_str().to_owned();
+    appended.push(format!(".{}", SIDECAR_EXT));
+    [path.with_extension(SIDECAR_EXT), appended.into()]
+        .into_iter()
+        .find(|candidate| candidate != path && candidate.is_file())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// EXIF and XMP metadata of the local image at `path`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn get_image_metadata(path: String) -> Result<ImageMetadata, AppError> {
+    let file_path = Path::new(&path);
+    if !file_path.is_file() {
+        return Err(AppError::Validation("File does not exist".into()));
+    }
+    let content = fs::read(file_path).await?;
+    let sidecar = match sidecar_path(file_path) {
+        Some(sidecar) => Some(fs::read(sidecar).await?),
+        None => None,
+    };
+    let name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown");
+    Ok(describe_image(name, &content, sidecar.as_deref()))
+}
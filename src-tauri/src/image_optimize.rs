@@ -0,0 +1,136 @@
+//! Lossless Image Optimization
+//!
+//! `optimize_image` shrinks photos before upload without changing a pixel.
+//! PNGs go through oxipng (filter and deflate search); JPEGs through
+//! `jpeg_optimize` (rebuilt Huffman tables). Either can also drop metadata
+//! that does not affect rendering. Other formats, files the optimizers
+//! cannot handle and results that are not smaller come back unchanged, so
+//! the pipeline step is always safe to run.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+use crate::compress_stream::write_via_partial;
+use crate::entropy::{detect_kind, ContentKind};
+use crate::github::AppError;
+use crate::jpeg_optimize::optimize_jpeg;
+
+/// Highest oxipng preset; higher levels try more filter and deflate options
+pub const MAX_OPTIMIZE_LEVEL: u8 = 6;
+const DEFAULT_OPTIMIZE_LEVEL: u8 = 2;
+
+fn default_level() -> u8 {
+    DEFAULT_OPTIMIZE_LEVEL
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OptimizeOptions {
+    /// oxipng preset, 0 to `MAX_OPTIMIZE_LEVEL`; JPEG has a single mode
+    #[serde(default = "default_level")]
+    pub level: u8,
+    /// Drop comments, EXIF, XMP and other chunks that do not affect
+    /// rendering. Keeps color profiles.
+    #[serde(default)]
+    pub strip_metadata: bool,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_OPTIMIZE_LEVEL,
+            strip_metadata: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OptimizeResult {
+    pub format: ContentKind,
+    pub original_size: u64,
+    pub optimized_size: u64,
+    pub saved_bytes: u64,
+    /// False when the input was left as it was
+    pub optimized: bool,
+    /// Why the input was left as it was
+    pub skipped_reason: Option<String>,
+}
+
+fn optimize_png(data: &[u8], options: &OptimizeOptions) -> Result<Vec<u8>, AppError> {
+    let mut png_options = oxipng::Options::from_preset(options.level.min(MAX_OPTIMIZE_LEVEL));
+    png_options.strip = if options.strip_metadata {
+        oxipng::StripChunks::Safe
+    } else {
+        oxipng::StripChunks::None
+    };
+    oxipng::optimize_from_memory(data, &png_options)
+        .map_err(|e| AppError::Validation(format!("Cannot optimize PNG: {}", e)))
+}
+
+/// Optimize `data`, returning the bytes to keep and what happened
+pub fn optimize_image_data(data: &[u8], options: &OptimizeOptions) -> (Vec<u8>, OptimizeResult) {
+    let format = detect_kind(data);
+    let attempt = match format {
+        ContentKind::Png => optimize_png(data, options),
+        ContentKind::Jpeg => optimize_jpeg(data, options.strip_metadata),
+        _ => Err(AppError::Validation("Only PNG and JPEG can be optimized".into())),
+    };
+    let (output, skipped_reason) = match attempt {
+        Ok(output) if output.len() < data.len() => (output, None),
+        Ok(_) => (data.to_vec(), Some("Already optimal".to_string())),
+        Err(e) => (data.to_vec(), Some(e.to_string())),
+    };
+
+    let result = OptimizeResult {
+        format,
+        original_size: data.len() as u64,
+        optimized_size: output.len() as u64,
+        saved_bytes: (data.len() - output.len()) as u64,
+        optimized: skipped_reason.is_none(),
+        skipped_reason,
+    };
+    (output, result)
+}
+
+pub(crate) fn validate_options(options: &OptimizeOptions) -> Result<(), AppError> {
+    if options.level > MAX_OPTIMIZE_LEVEL {
+        return Err(AppError::Validation(format!(
+            "Optimization level must be 0-{}",
+            MAX_OPTIMIZE_LEVEL
+        )));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Losslessly optimize the image at `input_path`, writing `output_path` or,
+/// without one, replacing the input once the smaller file is complete
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn optimize_image(
+    input_path: String,
+    output_path: Option<String>,
+    options: Option<OptimizeOptions>,
+) -> Result<OptimizeResult, AppError> {
+    let options = options.unwrap_or_default();
+    validate_options(&options)?;
+
+    tokio::task::spawn_blocking(move || {
+        let data = std::fs::read(&input_path)?;
+        let (output, result) = optimize_image_data(&data, &options);
+        let target = output_path.unwrap_or_else(|| input_path.clone());
+        if result.optimized || target != input_path {
+            write_via_partial(Path::new(&target), |mut writer| {
+                writer.write_all(&output)?;
+                writer.flush()?;
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Optimization task failed: {}", e)))?
+}
@@ -0,0 +1,483 @@
+//! Large Payloads
+//!
+//! Data commands such as `compress_data` and `encrypt_hybrid` take their
+//! input as a JSON byte array through invoke, which costs several times the
+//! payload in memory on both sides and stalls the webview past ~100 MB. This
+//! module keeps big payloads off the JSON channel:
+//!
+//! - **file variant** - `data_op_file` runs any data command (a `DataOp`) on
+//!   a file and writes the result to another, so the bytes never reach JS,
+//! - **shared temp files** - `buffer_create` makes a buffer, a temp file
+//!   below the profile's `buffers/` folder that both sides can use by ID or
+//!   by path (`buffer_path`); `data_op_buffer` runs a data command on one and
+//!   returns the result as a new buffer,
+//! - **streaming** - `buffer_write` appends a raw request body (an
+//!   `ArrayBuffer`, no JSON) to a buffer, and `buffer_read` streams one back
+//!   as raw chunks over a `Channel`.
+//!
+//! Compression, decompression of streamed files and hashing read their
+//! input in chunks; the crypto ops hold it in memory, as their formats are
+//! single-shot. A decrypt op on a buffer writes its plaintext through
+//! `secure_temp` rather than `buffers/`, so it is wiped on release and by
+//! `purge_decrypted_cache`.
+//!
+//! Commands that already work on files (`compress_file_stream`,
+//! `encrypt_file`) need no variant. Buffers live until `buffer_release` and
+//! are cleared at startup.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use tauri::ipc::{Channel, InvokeBody, InvokeResponseBody, Request};
+use zeroize::Zeroizing;
+
+use crate::compress::{auto_settings, decompress, Algorithm, AutoDecision, CompressionResult};
+use crate::compress_stream::{
+    compress_stream, decompress_stream, read_stream_header, write_via_partial, STREAM_BUFFER_SIZE,
+};
+use crate::crypto::{
+    decrypt_hybrid, decrypt_with_password, encrypt_hybrid, encrypt_with_password, sign_data, verify_signature,
+    EncryptedPayload, KeypairHandle, PublicBundle,
+};
+use crate::github::AppError;
+use crate::secure_temp::{release_plaintext, tracked_path, write_plaintext};
+
+const BUFFERS_DIR: &str = "buffers";
+const BUFFER_PREFIX: &str = "buf-";
+/// Request header naming the buffer `buffer_write` appends to
+pub const BUFFER_ID_HEADER: &str = "buffer-id";
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+pub const MIN_CHUNK_SIZE: usize = 4 * 1024;
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// A data command and its arguments, minus the data
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum DataOp {
+    /// `compress_data`, or `compress_data_strict` when `strict` is set
+    Compress {
+        algorithm: String,
+        level: Option<i32>,
+        #[serde(default)]
+        strict: bool,
+    },
+    CompressAuto {
+        prefer_speed: bool,
+        archival: Option<bool>,
+    },
+    /// Files written by a streamed compress are read by their header, which
+    /// names the algorithm
+    Decompress {
+        algorithm: String,
+    },
+    /// Writes the `EncryptedPayload` as JSON, as photos store it
+    EncryptHybrid {
+        recipient_bundle: Option<PublicBundle>,
+        contact_id: Option<String>,
+        aad: Option<Vec<u8>>,
+    },
+    /// Reads an `EncryptedPayload` as JSON
+    DecryptHybrid {
+        handle: KeypairHandle,
+        aad: Option<Vec<u8>>,
+    },
+    EncryptPassword {
+        password: Zeroizing<String>,
+    },
+    DecryptPassword {
+        password: Zeroizing<String>,
+    },
+    Sign {
+        handle: KeypairHandle,
+    },
+    /// Produces no output, only `valid`
+    Verify {
+        signature: Vec<u8>,
+        public_bundle: PublicBundle,
+    },
+    /// BLAKE3 digest, also returned as `digest`
+    Hash,
+}
+
+impl DataOp {
+    fn has_output(&self) -> bool {
+        !matches!(self, DataOp::Verify { .. })
+    }
+
+    /// Whether the output is plaintext of something encrypted
+    fn yields_plaintext(&self) -> bool {
+        matches!(self, DataOp::DecryptHybrid { .. } | DataOp::DecryptPassword { .. })
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DataOpOutput {
+    /// Bytes the op produced
+    pub size: u64,
+    /// Where they went: the output path, or the new buffer's ID
+    pub output: Option<String>,
+    /// Details of a compress op, with `data` left empty
+    pub compression: Option<CompressionResult>,
+    /// Outcome of a `verify` op
+    pub valid: Option<bool>,
+    /// Hex digest of a `hash` op
+    pub digest: Option<String>,
+}
+
+fn invalid(e: impl ToString) -> AppError {
+    AppError::Validation(e.to_string())
+}
+
+fn parse_algorithm(algorithm: &str, strict: bool) -> Result<Algorithm, AppError> {
+    if strict {
+        Algorithm::try_from_str(algorithm).map_err(invalid)
+    } else {
+        Ok(Algorithm::from(algorithm))
+    }
+}
+
+/// Run an in-memory `op` on `data`, returning the bytes it produced and its
+/// details
+fn apply(op: DataOp, data: Vec<u8>) -> Result<(Zeroizing<Vec<u8>>, DataOpOutput), AppError> {
+    let mut details = DataOpOutput::default();
+    let bytes = match op {
+        DataOp::Decompress { algorithm } => decompress(&data, Algorithm::from(algorithm.as_str())).map_err(invalid)?,
+        DataOp::EncryptHybrid {
+            recipient_bundle,
+            contact_id,
+            aad,
+        } => {
+            let payload = encrypt_hybrid(data, recipient_bundle, contact_id, aad).map_err(invalid)?;
+            serde_json::to_vec(&payload).map_err(invalid)?
+        }
+        DataOp::DecryptHybrid { handle, aad } => {
+            let payload: EncryptedPayload =
+                serde_json::from_slice(&data).map_err(|e| invalid(format!("Not an encrypted payload: {}", e)))?;
+            decrypt_hybrid(payload, handle, aad).map_err(invalid)?
+        }
+        DataOp::EncryptPassword { password } => {
+            crate::password::ensure_strong(&password).map_err(invalid)?;
+            encrypt_with_password(&data, password.as_bytes()).map_err(invalid)?
+        }
+        DataOp::DecryptPassword { password } => decrypt_with_password(&data, password.as_bytes()).map_err(invalid)?,
+        DataOp::Sign { handle } => sign_data(data, handle).map_err(invalid)?,
+        DataOp::Verify {
+            signature,
+            public_bundle,
+        } => {
+            details.valid = Some(verify_signature(data, signature, public_bundle).map_err(invalid)?);
+            Vec::new()
+        }
+        DataOp::Compress { .. } | DataOp::CompressAuto { .. } | DataOp::Hash => {
+            unreachable!("streamed by run_data_op")
+        }
+    };
+    details.size = bytes.len() as u64;
+    Ok((Zeroizing::new(bytes), details))
+}
+
+/// Run `f` on a writer to `output`, or on one that discards everything
+fn write_output<T>(
+    output: Option<&Path>,
+    f: impl FnOnce(&mut dyn Write) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    match output {
+        Some(output) => write_via_partial(output, |mut writer| {
+            let value = f(&mut writer)?;
+            writer.flush()?;
+            Ok(value)
+        }),
+        None => f(&mut std::io::sink()),
+    }
+}
+
+/// Stream `input` through `compress_stream`
+fn compress_file(
+    input: &Path,
+    output: Option<&Path>,
+    algorithm: Algorithm,
+    level: i32,
+    decision: Option<AutoDecision>,
+) -> Result<DataOpOutput, AppError> {
+    let file = File::open(input)?;
+    let total = file.metadata()?.len();
+    let cancel = AtomicBool::new(false);
+    let (original_size, compressed_size) =
+        write_output(output, |writer| compress_stream(file, writer, algorithm, level, total, &cancel, |_| {}))?;
+    Ok(DataOpOutput {
+        size: compressed_size,
+        compression: Some(CompressionResult {
+            data: Vec::new(),
+            algorithm,
+            original_size: original_size as usize,
+            compressed_size: compressed_size as usize,
+            ratio: if original_size > 0 {
+                compressed_size as f64 / original_size as f64
+            } else {
+                1.0
+            },
+            was_compressed: algorithm != Algorithm::None,
+            decision,
+        }),
+        ..Default::default()
+    })
+}
+
+/// Whether `path` starts with a `compress_stream` header
+fn is_stream_file(path: &Path) -> bool {
+    File::open(path).is_ok_and(|mut file| read_stream_header(&mut file).is_ok())
+}
+
+/// The first `STREAM_BUFFER_SIZE` bytes of `path`
+fn read_sample(path: &Path) -> Result<Vec<u8>, AppError> {
+    let mut sample = Vec::new();
+    File::open(path)?.take(STREAM_BUFFER_SIZE as u64).read_to_end(&mut sample)?;
+    Ok(sample)
+}
+
+/// Run `op` on the contents of `input`, writing the result to `output` if
+/// given. Without an output only the details are kept, as
+/// `estimate_compression` does.
+pub fn run_data_op(op: DataOp, input: &Path, output: Option<&Path>) -> Result<DataOpOutput, AppError> {
+    let output = output.filter(|_| op.has_output());
+    let mut details = match op {
+        DataOp::Compress {
+            algorithm,
+            level,
+            strict,
+        } => compress_file(input, output, parse_algorithm(&algorithm, strict)?, level.unwrap_or(3), None)?,
+        DataOp::CompressAuto { prefer_speed, archival } => {
+            let (settings, decision) = auto_settings(&read_sample(input)?, prefer_speed, archival.unwrap_or(false));
+            compress_file(input, output, settings.algorithm, settings.level, Some(decision))?
+        }
+        DataOp::Decompress { .. } if is_stream_file(input) => {
+            let cancel = AtomicBool::new(false);
+            let (_, size) =
+                write_output(output, |writer| decompress_stream(File::open(input)?, writer, &cancel, |_, _| {}))?;
+            DataOpOutput {
+                size,
+                ..Default::default()
+            }
+        }
+        DataOp::Hash => {
+            let mut hasher = blake3::Hasher::new();
+            std::io::copy(&mut File::open(input)?, &mut hasher)?;
+            let digest = *hasher.finalize().as_bytes();
+            write_output(output, |writer| Ok(writer.write_all(&digest)?))?;
+            DataOpOutput {
+                size: digest.len() as u64,
+                digest: Some(hex::encode(digest)),
+                ..Default::default()
+            }
+        }
+        op => {
+            let (bytes, details) = apply(op, std::fs::read(input)?)?;
+            write_output(output, |writer| Ok(writer.write_all(&bytes)?))?;
+            details
+        }
+    };
+    if let Some(output) = output {
+        details.output = Some(output.display().to_string());
+    }
+    Ok(details)
+}
+
+// ============================================================================
+// Buffers
+// ============================================================================
+
+fn buffers_dir() -> Result<PathBuf, AppError> {
+    let dir = crate::profiles::data_dir()?.join(BUFFERS_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Whether `id` is a buffer ID as `create_buffer_in` makes them
+pub fn is_buffer_id(id: &str) -> bool {
+    id.strip_prefix(BUFFER_PREFIX)
+        .is_some_and(|rest| rest.len() == 16 && rest.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')))
+}
+
+/// Path of the existing buffer `id` in `dir`
+pub fn buffer_path_in(dir: &Path, id: &str) -> Result<PathBuf, AppError> {
+    if !is_buffer_id(id) {
+        return Err(AppError::Validation(format!("Invalid buffer ID: {}", id)));
+    }
+    let path = dir.join(id);
+    if !path.is_file() {
+        return Err(AppError::Validation(format!("No buffer {}", id)));
+    }
+    Ok(path)
+}
+
+/// Create an empty buffer in `dir`. Returns its ID.
+pub fn create_buffer_in(dir: &Path) -> Result<String, AppError> {
+    let id = format!("{}{}", BUFFER_PREFIX, hex::encode(rand::random::<[u8; 8]>()));
+    OpenOptions::new().write(true).create_new(true).open(dir.join(&id))?;
+    Ok(id)
+}
+
+/// Append `bytes` to buffer `id`. Returns its new size.
+pub fn append_buffer_in(dir: &Path, id: &str, bytes: &[u8]) -> Result<u64, AppError> {
+    let mut file = OpenOptions::new().append(true).open(buffer_path_in(dir, id)?)?;
+    file.write_all(bytes)?;
+    Ok(file.metadata()?.len())
+}
+
+/// Path of buffer `id`: a temp file below `buffers/`, or the tracked
+/// plaintext file a decrypt op wrote
+fn resolve_buffer(id: &str) -> Result<PathBuf, AppError> {
+    match tracked_path(id) {
+        Some(path) => Ok(path),
+        None => buffer_path_in(&buffers_dir()?, id),
+    }
+}
+
+/// Remove buffer `id`. Returns false if there was none.
+pub fn release_buffer_in(dir: &Path, id: &str) -> bool {
+    buffer_path_in(dir, id).is_ok_and(|path| std::fs::remove_file(path).is_ok())
+}
+
+/// Pass the contents of `path` to `send` in chunks of `chunk_size`. Returns
+/// the bytes sent.
+pub fn read_chunks(
+    path: &Path,
+    chunk_size: usize,
+    mut send: impl FnMut(Vec<u8>) -> Result<(), AppError>,
+) -> Result<u64, AppError> {
+    let mut file = File::open(path)?;
+    let mut sent = 0;
+    loop {
+        let mut chunk = Vec::with_capacity(chunk_size);
+        let read = file.by_ref().take(chunk_size as u64).read_to_end(&mut chunk)?;
+        if read == 0 {
+            return Ok(sent);
+        }
+        sent += read as u64;
+        send(chunk)?;
+    }
+}
+
+/// Remove every buffer of the active profile
+pub fn clear_buffers() -> Result<(), AppError> {
+    let dir = buffers_dir()?;
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Validation(format!("Data task failed: {}", e)))?
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Run a data command on `input_path`, writing its result to `output_path`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn data_op_file(op: DataOp, input_path: String, output_path: Option<String>) -> Result<DataOpOutput, AppError> {
+    run_blocking(move || run_data_op(op, Path::new(&input_path), output_path.as_deref().map(Path::new))).await
+}
+
+/// Run a data command on a buffer. The result goes to a new buffer, named
+/// in `output`; plaintext from a decrypt op goes to a wiped-on-release
+/// plaintext file instead, whose ID the buffer commands also take.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn data_op_buffer(op: DataOp, buffer_id: String) -> Result<DataOpOutput, AppError> {
+    run_blocking(move || {
+        let dir = buffers_dir()?;
+        let input = resolve_buffer(&buffer_id)?;
+        if !op.has_output() {
+            return run_data_op(op, &input, None);
+        }
+        if op.yields_plaintext() {
+            let (bytes, details) = apply(op, std::fs::read(&input)?)?;
+            let file = write_plaintext(&format!("buffer:{}", buffer_id), "", &bytes)?;
+            return Ok(DataOpOutput {
+                output: Some(file.id),
+                ..details
+            });
+        }
+        let output_id = create_buffer_in(&dir)?;
+        let result = run_data_op(op, &input, Some(&dir.join(&output_id)));
+        match result {
+            Ok(details) => Ok(DataOpOutput {
+                output: Some(output_id),
+                ..details
+            }),
+            Err(e) => {
+                release_buffer_in(&dir, &output_id);
+                Err(e)
+            }
+        }
+    })
+    .await
+}
+
+/// Create an empty buffer. Returns its ID.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn buffer_create() -> Result<String, AppError> {
+    create_buffer_in(&buffers_dir()?)
+}
+
+/// Append the raw request body to the buffer named by the `buffer-id`
+/// header. Returns the buffer's new size.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn buffer_write(request: Request<'_>) -> Result<u64, AppError> {
+    let id = request
+        .headers()
+        .get(BUFFER_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| AppError::Validation(format!("Missing {} header", BUFFER_ID_HEADER)))?;
+    let InvokeBody::Raw(bytes) = request.body() else {
+        return Err(AppError::Validation("buffer_write takes a raw ArrayBuffer body".into()));
+    };
+    append_buffer_in(&buffers_dir()?, id, bytes)
+}
+
+/// Stream a buffer to `on_chunk` as raw chunks. Returns the bytes sent.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn buffer_read(
+    buffer_id: String,
+    on_chunk: Channel<InvokeResponseBody>,
+    chunk_size: Option<usize>,
+) -> Result<u64, AppError> {
+    let path = resolve_buffer(&buffer_id)?;
+    let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE);
+    run_blocking(move || {
+        read_chunks(&path, chunk_size, |chunk| {
+            on_chunk
+                .send(InvokeResponseBody::Raw(chunk))
+                .map_err(|e| AppError::Validation(format!("Could not send chunk: {}", e)))
+        })
+    })
+    .await
+}
+
+/// Path of a buffer's temp file, for tools that take a path
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn buffer_path(buffer_id: String) -> Result<String, AppError> {
+    Ok(resolve_buffer(&buffer_id)?.display().to_string())
+}
+
+/// Remove a buffer. Returns false if there was none.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn buffer_release(buffer_id: String) -> Result<bool, AppError> {
+    if tracked_path(&buffer_id).is_some() {
+        return release_plaintext(buffer_id);
+    }
+    Ok(release_buffer_in(&buffers_dir()?, &buffer_id))
+}
@@ -0,0 +1,562 @@
+//! IPFS Storage Backend
+//!
+//! `pin_album_to_ipfs` adds every file of an album (photos, signatures, the
+//! metadata vault and, last, the manifest) to an IPFS node through its HTTP
+//! RPC API (`/api/v0`, as served by Kubo) and pins them there. The node can
+//! be local or remote; remote nodes are reached over HTTPS with an optional
+//! bearer token kept in the keychain.
+//!
+//! The CID of each file is recorded in the album manifest's `ipfs` map, so
+//! other devices see what is pinned, and in the local store's `ipfs_pins`
+//! table, which is what lets photos be found when GitHub cannot serve them:
+//! `fetch_photo` falls back to `fetch_pinned_photo` when GitHub is down or
+//! the file is gone. Content comes from the node or, failing that, from the
+//! configured gateway, and is checked against the recorded size and BLAKE3
+//! hash before use, so an untrusted gateway cannot swap it. Signatures and
+//! manifests are checked as for downloads from GitHub.
+//!
+//! Files are pinned as stored in the repo: photos of encrypted albums stay
+//! encrypted to their album key, and Git LFS or chunked files are pinned
+//! with their full content.
+
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, RequestBuilder};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::album::{
+    fetch_manifest, manifest_for_update, parent_album_path, save_manifest, AlbumManifest, ALBUM_MANIFEST_FILE,
+    ENCRYPTED_BLOB_EXT,
+};
+use crate::crypto::{hash_data, keychain_delete, keychain_retrieve, keychain_store, KeypairHandle};
+use crate::download_cache::CachedPhoto;
+use crate::git_data::{branch_head, get_blob, get_tree_recursive, index_blobs, TreeEntry, TreeIndex};
+use crate::github::{validate_repo, AppError, Bearer, DownloadIntegrity, GithubError, HttpClient};
+use crate::lfs::resolve_lfs_pointer;
+use crate::local_store::{db_error, with_store, LocalStore, SETTINGS_NS};
+use crate::retry::SendWithRetry;
+use crate::security_verify::{check_album_photo, check_manifest, ensure_intact, signature_path, TrustedSigners};
+use crate::storage_backend::validate_server_url;
+
+/// Setting holding the `IpfsConfig`
+pub const CONFIG_SETTING: &str = "ipfs_node";
+pub const DEFAULT_API_URL: &str = "http://127.0.0.1:5001";
+const TOKEN_KEY: &str = "ipfs-node-token";
+
+/// Migration 5 (see `migrations`)
+pub const IPFS_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS ipfs_pins (
+    id BLOB PRIMARY KEY,
+    record BLOB NOT NULL
+);
+"#;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpfsConfig {
+    /// Base URL of the node's RPC API
+    pub api_url: String,
+    /// Gateway to read from when the node cannot serve a file
+    #[serde(default)]
+    pub gateway_url: Option<String>,
+    /// Whether a token for the node is in the keychain
+    #[serde(default)]
+    pub authenticated: bool,
+}
+
+/// Where a file of an album is pinned, as recorded in its manifest
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpfsEntry {
+    pub cid: String,
+    /// Blob SHA in the repo when it was pinned
+    pub sha: String,
+    pub size: u64,
+    /// BLAKE3 of the content, hex
+    pub blake3: String,
+}
+
+impl IpfsEntry {
+    pub fn for_content(cid: &str, sha: &str, content: &[u8]) -> Self {
+        Self {
+            cid: cid.to_string(),
+            sha: sha.to_string(),
+            size: content.len() as u64,
+            blake3: hex::encode(hash_data(content)),
+        }
+    }
+
+    /// Fail unless `content` is what was pinned
+    pub fn check(&self, content: &[u8]) -> Result<(), AppError> {
+        if content.len() as u64 != self.size || hex::encode(hash_data(content)) != self.blake3 {
+            return Err(AppError::Validation(format!("IPFS content of {} does not match its record", self.cid)));
+        }
+        Ok(())
+    }
+}
+
+/// A file pinned from a repo, as recorded locally
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpfsPin {
+    pub repo: String,
+    pub path: String,
+    #[serde(flatten)]
+    pub entry: IpfsEntry,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IpfsAlbumPin {
+    pub album: String,
+    /// Files added to the node
+    pub pinned: usize,
+    /// Files already pinned at their current SHA
+    pub unchanged: usize,
+    /// Bytes added to the node
+    pub bytes: u64,
+    pub manifest_cid: String,
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// Check a node or gateway URL (see `validate_server_url`)
+pub fn validate_node_url(url: &str) -> Result<String, AppError> {
+    validate_server_url(url)
+}
+
+/// CID of the file added by an `add` call. The response has one JSON
+/// object per line; the last one is the file itself.
+pub fn parse_add_response(body: &str) -> Result<String, AppError> {
+    body.lines()
+        .rev()
+        .filter(|line| !line.trim().is_empty())
+        .find_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .and_then(|json| json["Hash"].as_str().map(str::to_string))
+        .filter(|cid| !cid.is_empty())
+        .ok_or_else(|| AppError::Api("IPFS node returned no CID".into()))
+}
+
+/// Whether `error` means GitHub could not serve a file, so a pinned copy
+/// should be tried
+pub fn github_unreachable(error: &AppError) -> bool {
+    match error {
+        AppError::Network(_) | AppError::Unavailable(_) => true,
+        AppError::Github(e) => matches!(
+            e,
+            GithubError::NotFound { .. }
+                | GithubError::Unauthorized { .. }
+                | GithubError::Network { .. }
+                | GithubError::NetworkTimeout { .. }
+                | GithubError::Unavailable { .. }
+                | GithubError::Server { .. }
+        ),
+        _ => false,
+    }
+}
+
+fn load_config() -> Result<Option<IpfsConfig>, AppError> {
+    with_store(|store| store.get_json(SETTINGS_NS, CONFIG_SETTING))
+}
+
+fn not_configured() -> AppError {
+    AppError::Validation("Set up an IPFS node first".into())
+}
+
+// ============================================================================
+// Node
+// ============================================================================
+
+struct IpfsNode {
+    config: IpfsConfig,
+    token: Option<Zeroizing<String>>,
+}
+
+impl IpfsNode {
+    fn load() -> Result<Option<Self>, AppError> {
+        let Some(config) = load_config()? else {
+            return Ok(None);
+        };
+        let token = if config.authenticated {
+            let raw = keychain_retrieve(TOKEN_KEY)
+                .map_err(|e| AppError::Validation(format!("IPFS token unavailable: {}", e)))?;
+            Some(Zeroizing::new(
+                String::from_utf8(raw).map_err(|_| AppError::Validation("IPFS token unavailable".into()))?,
+            ))
+        } else {
+            None
+        };
+        Ok(Some(Self { config, token }))
+    }
+
+    fn rpc(&self, client: &Client, call: &str) -> RequestBuilder {
+        let request = client
+            .post(format!("{}/api/v0/{}", self.config.api_url, call))
+            .header("User-Agent", "vortex-image");
+        match &self.token {
+            Some(token) => request.header("Authorization", Bearer(token)),
+            None => request,
+        }
+    }
+
+    async fn call(&self, request: RequestBuilder, context: &str) -> Result<Vec<u8>, AppError> {
+        let res = request.send_with_retry().await?;
+        let status = res.status();
+        let body = res.bytes().await?.to_vec();
+        if !status.is_success() {
+            // Kubo reports errors as {"Message": ..., "Code": ..., "Type": "error"}
+            let detail = serde_json::from_slice::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|v| v["Message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).into_owned());
+            return Err(AppError::Api(format!("{} ({}): {}", context, status.as_u16(), detail.trim())));
+        }
+        Ok(body)
+    }
+
+    async fn version(&self, client: &Client) -> Result<String, AppError> {
+        let body = self.call(self.rpc(client, "version"), "IPFS node unreachable").await?;
+        let json: serde_json::Value =
+            serde_json::from_slice(&body).map_err(|e| AppError::Api(format!("Unexpected IPFS response: {}", e)))?;
+        Ok(json["Version"].as_str().unwrap_or("unknown").to_string())
+    }
+
+    /// Add and pin `content`, returning its CID
+    async fn add(&self, client: &Client, name: &str, content: Vec<u8>) -> Result<String, AppError> {
+        let form = Form::new().part("file", Part::bytes(content).file_name(name.to_string()));
+        let request = self.rpc(client, "add?pin=true&cid-version=1&quieter=true").multipart(form);
+        parse_add_response(&String::from_utf8_lossy(&self.call(request, "IPFS add failed").await?))
+    }
+
+    async fn unpin(&self, client: &Client, cid: &str) -> Result<(), AppError> {
+        match self.call(self.rpc(client, &format!("pin/rm?arg={}", cid)), "IPFS unpin failed").await {
+            Err(AppError::Api(message)) if message.contains("not pinned") => Ok(()),
+            other => other.map(|_| ()),
+        }
+    }
+
+    /// Content of `entry` from the node, else the gateway, checked against
+    /// the record
+    async fn fetch(&self, client: &Client, entry: &IpfsEntry) -> Result<Vec<u8>, AppError> {
+        let from_node = self
+            .call(self.rpc(client, &format!("cat?arg={}", entry.cid)), "IPFS cat failed")
+            .await;
+        let content = match (from_node, &self.config.gateway_url) {
+            (Ok(body), _) => body,
+            (Err(e), None) => return Err(e),
+            (Err(_), Some(gateway)) => {
+                let res = client
+                    .get(format!("{}/ipfs/{}", gateway, entry.cid))
+                    .header("User-Agent", "vortex-image")
+                    .send_with_retry()
+                    .await?;
+                if !res.status().is_success() {
+                    return Err(AppError::Api(format!("IPFS gateway returned {} for {}", res.status(), entry.cid)));
+                }
+                res.bytes().await?.to_vec()
+            }
+        };
+        entry.check(&content)?;
+        Ok(content)
+    }
+}
+
+// ============================================================================
+// Pin Records
+// ============================================================================
+
+fn record_id(store: &LocalStore, repo: &str, path: &str) -> [u8; 32] {
+    store.index_id(&["ipfs", repo, path])
+}
+
+pub fn record_pin_in(store: &LocalStore, repo: &str, path: &str, entry: &IpfsEntry) -> Result<(), AppError> {
+    let pin = IpfsPin { repo: repo.to_string(), path: path.to_string(), entry: entry.clone() };
+    let id = record_id(store, repo, path);
+    let json = Zeroizing::new(serde_json::to_vec(&pin).map_err(|e| AppError::Validation(e.to_string()))?);
+    store
+        .connection()
+        .execute(
+            "INSERT OR REPLACE INTO ipfs_pins (id, record) VALUES (?1, ?2)",
+            params![&id[..], store.seal(&id, &json)?],
+        )
+        .map_err(db_error)?;
+    Ok(())
+}
+
+fn read_pins(store: &LocalStore, sql: &str, params: impl rusqlite::Params) -> Result<Vec<IpfsPin>, AppError> {
+    let mut statement = store.connection().prepare(sql).map_err(db_error)?;
+    let rows = statement
+        .query_map(params, |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?)))
+        .map_err(db_error)?;
+
+    let mut pins = Vec::new();
+    for row in rows {
+        let (id, sealed) = row.map_err(db_error)?;
+        let id: [u8; 32] = id
+            .try_into()
+            .map_err(|_| AppError::Validation("Corrupt IPFS pin row".into()))?;
+        let json = store.unseal(&id, &sealed)?;
+        pins.push(
+            serde_json::from_slice(&json)
+                .map_err(|e| AppError::Validation(format!("Corrupt IPFS pin row: {}", e)))?,
+        );
+    }
+    Ok(pins)
+}
+
+pub fn pinned_in(store: &LocalStore, repo: &str, path: &str) -> Result<Option<IpfsEntry>, AppError> {
+    let id = record_id(store, repo, path);
+    Ok(read_pins(store, "SELECT id, record FROM ipfs_pins WHERE id = ?1", params![&id[..]])?
+        .pop()
+        .map(|pin| pin.entry))
+}
+
+pub fn forget_pin_in(store: &LocalStore, repo: &str, path: &str) -> Result<bool, AppError> {
+    let id = record_id(store, repo, path);
+    let removed = store
+        .connection()
+        .execute("DELETE FROM ipfs_pins WHERE id = ?1", params![&id[..]])
+        .map_err(db_error)?;
+    Ok(removed > 0)
+}
+
+/// Pins of `repo`, limited to the files directly in `album` when given
+pub fn pins_in(store: &LocalStore, repo: &str, album: Option<&str>) -> Result<Vec<IpfsPin>, AppError> {
+    let mut pins: Vec<IpfsPin> = read_pins(store, "SELECT id, record FROM ipfs_pins", [])?
+        .into_iter()
+        .filter(|pin| pin.repo == repo)
+        .filter(|pin| album.is_none_or(|album| in_album(&pin.path, album)))
+        .collect();
+    pins.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(pins)
+}
+
+// ============================================================================
+// Albums
+// ============================================================================
+
+fn in_album(path: &str, album: &str) -> bool {
+    path.rsplit_once('/').is_some_and(|(folder, _)| folder == album)
+}
+
+/// Files of `album` to pin: everything directly in its folder but the
+/// manifest, which is pinned once it records the others
+pub fn album_files(index: &TreeIndex, album: &str) -> Vec<TreeEntry> {
+    let mut files: Vec<TreeEntry> = index
+        .values()
+        .filter(|entry| in_album(&entry.path, album) && !entry.path.ends_with(&format!("/{}", ALBUM_MANIFEST_FILE)))
+        .cloned()
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// The photo at `remote_path` from its pinned copy, checked as `fetch_photo`
+/// checks downloads from GitHub. `None` when it is not pinned or no node is
+/// set up.
+pub(crate) async fn fetch_pinned_photo(
+    client: &Client,
+    repo: &str,
+    remote_path: &str,
+    keypair_handle: Option<KeypairHandle>,
+    verify: bool,
+) -> Result<Option<CachedPhoto>, AppError> {
+    let Some(node) = IpfsNode::load()? else {
+        return Ok(None);
+    };
+    let Some(entry) = with_store(|store| pinned_in(store, repo, remote_path))? else {
+        return Ok(None);
+    };
+    let content = node.fetch(client, &entry).await?;
+
+    let album_path = parent_album_path(remote_path);
+    let manifest_path = format!("{}/{}", album_path, ALBUM_MANIFEST_FILE);
+    let manifest = if remote_path.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) {
+        let entry = with_store(|store| pinned_in(store, repo, &manifest_path))?
+            .ok_or_else(|| AppError::Validation("Album manifest is not pinned".into()))?;
+        let manifest: AlbumManifest = serde_json::from_slice(&node.fetch(client, &entry).await?)
+            .map_err(|e| AppError::Validation(format!("Invalid album manifest: {}", e)))?;
+        Some(manifest)
+    } else {
+        None
+    };
+
+    let mut integrity = None;
+    if verify {
+        let trusted = TrustedSigners::load(keypair_handle);
+        let manifest_check = manifest
+            .as_ref()
+            .map(|m| ensure_intact(check_manifest(&manifest_path, m, &trusted)))
+            .transpose()?;
+        let signature = match with_store(|store| pinned_in(store, repo, &signature_path(remote_path)))? {
+            Some(entry) => Some(node.fetch(client, &entry).await?),
+            None => None,
+        };
+        let photo = check_album_photo(remote_path, &content, signature.as_deref(), manifest.as_ref(), &trusted);
+        integrity = Some(DownloadIntegrity {
+            photo: ensure_intact(photo)?,
+            manifest: manifest_check,
+        });
+    }
+
+    tracing::info!("Served {} from IPFS ({})", remote_path, entry.cid);
+    Ok(Some(CachedPhoto { sha: entry.sha, content, integrity, manifest }))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Use the node at `api_url` (the local default when omitted), checking
+/// that it answers. Returns the node's version.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn configure_ipfs(
+    client: State<'_, HttpClient>,
+    api_url: Option<String>,
+    gateway_url: Option<String>,
+    token: Option<Zeroizing<String>>,
+) -> Result<String, AppError> {
+    let token = token.filter(|t| !t.trim().is_empty());
+    let config = IpfsConfig {
+        api_url: validate_node_url(api_url.as_deref().unwrap_or(DEFAULT_API_URL))?,
+        gateway_url: gateway_url
+            .filter(|url| !url.trim().is_empty())
+            .map(|url| validate_node_url(&url))
+            .transpose()?,
+        authenticated: token.is_some(),
+    };
+
+    let node = IpfsNode { config: config.clone(), token: token.clone() };
+    let version = node.version(&client.0).await?;
+
+    match &token {
+        Some(token) => keychain_store(TOKEN_KEY, token.as_bytes())
+            .map_err(|e| AppError::Validation(format!("Failed to store IPFS token: {}", e)))?,
+        None => {
+            let _ = keychain_delete(TOKEN_KEY);
+        }
+    }
+    with_store(|store| store.put_json(SETTINGS_NS, CONFIG_SETTING, &config, None))?;
+    Ok(version)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_ipfs_config() -> Result<Option<IpfsConfig>, AppError> {
+    load_config()
+}
+
+/// Stop using the node. Pins stay on it and in the manifests.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn remove_ipfs_config() -> Result<bool, AppError> {
+    let _ = keychain_delete(TOKEN_KEY);
+    with_store(|store| store.remove(SETTINGS_NS, CONFIG_SETTING))
+}
+
+/// Pin every file of `album_path` and record the CIDs in its manifest.
+/// Files already pinned at their current SHA are not added again.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn pin_album_to_ipfs(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<IpfsAlbumPin, AppError> {
+    validate_repo(&repo)?;
+    let album_path = album_path.trim_matches('/').to_string();
+    let node = IpfsNode::load()?.ok_or_else(not_configured)?;
+    let (mut manifest, manifest_sha) = manifest_for_update(&client.0, &repo, &token, &album_path).await?;
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let files = album_files(&index, &album_path);
+
+    let mut report = IpfsAlbumPin { album: album_path.clone(), ..Default::default() };
+    for file in &files {
+        let name = file_name(&file.path);
+        if let Some(entry) = manifest.ipfs.get(name).filter(|entry| entry.sha == file.sha) {
+            with_store(|store| record_pin_in(store, &repo, &file.path, entry))?;
+            report.unchanged += 1;
+            continue;
+        }
+        let content = get_blob(&client.0, &repo, &token, &file.sha).await?;
+        let content = resolve_lfs_pointer(&client.0, &repo, &token, content).await?;
+        let mut entry = IpfsEntry::for_content("", &file.sha, &content);
+        entry.cid = node.add(&client.0, name, content).await?;
+        with_store(|store| record_pin_in(store, &repo, &file.path, &entry))?;
+        report.pinned += 1;
+        report.bytes += entry.size;
+        manifest.ipfs.insert(name.to_string(), entry);
+    }
+
+    let names: HashSet<&str> = files.iter().map(|file| file_name(&file.path)).collect();
+    manifest.ipfs.retain(|name, _| names.contains(name.as_str()));
+    let saved = save_manifest(&client.0, &repo, &token, &album_path, &mut manifest, manifest_sha.as_deref(), keypair_handle)
+        .await?;
+
+    // The same bytes `save_manifest` committed
+    let body = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+    let mut entry = IpfsEntry::for_content("", &saved.sha, &body);
+    entry.cid = node.add(&client.0, ALBUM_MANIFEST_FILE, body).await?;
+    let manifest_path = format!("{}/{}", album_path, ALBUM_MANIFEST_FILE);
+    with_store(|store| record_pin_in(store, &repo, &manifest_path, &entry))?;
+    report.bytes += entry.size;
+    report.manifest_cid = entry.cid;
+    Ok(report)
+}
+
+/// Unpin the files of `album_path` from the node and drop their CIDs from
+/// the manifest and the local records. Returns how many were unpinned.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn unpin_album_from_ipfs(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<usize, AppError> {
+    validate_repo(&repo)?;
+    let album_path = album_path.trim_matches('/').to_string();
+    let node = IpfsNode::load()?.ok_or_else(not_configured)?;
+    let pins = with_store(|store| pins_in(store, &repo, Some(&album_path)))?;
+    let manifest = fetch_manifest(&client.0, &repo, &token, &album_path).await?;
+
+    let mut cids: HashSet<String> = pins.iter().map(|pin| pin.entry.cid.clone()).collect();
+    if let Some((manifest, _)) = &manifest {
+        cids.extend(manifest.ipfs.values().map(|entry| entry.cid.clone()));
+    }
+    for cid in &cids {
+        node.unpin(&client.0, cid).await?;
+    }
+    with_store(|store| {
+        for pin in &pins {
+            forget_pin_in(store, &repo, &pin.path)?;
+        }
+        Ok(())
+    })?;
+
+    if let Some((mut manifest, sha)) = manifest.filter(|(manifest, _)| !manifest.ipfs.is_empty()) {
+        manifest.ipfs.clear();
+        save_manifest(&client.0, &repo, &token, &album_path, &mut manifest, Some(&sha), keypair_handle).await?;
+    }
+    Ok(cids.len())
+}
+
+/// Files of `repo` (of `album_path` when given) pinned from this device
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn list_ipfs_pins(repo: String, album_path: Option<String>) -> Result<Vec<IpfsPin>, AppError> {
+    validate_repo(&repo)?;
+    let album = album_path.map(|p| p.trim_matches('/').to_string());
+    with_store(|store| pins_in(store, &repo, album.as_deref()))
+}
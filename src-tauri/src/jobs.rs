@@ -0,0 +1,234 @@
+//! Cancellable Jobs
+//!
+//! Long-running commands (`upload_folder_recursive`, `compress_file`,
+//! `encrypt_file`, `decrypt_file`, `hash_file_blake3`, `decrypt_timelock`)
+//! run as jobs. Each takes an optional `job_id`, generates one if it is left
+//! out, and emits `job-started` with it before doing any work, so the UI can
+//! offer to cancel while the invoke is still pending. `list_jobs` shows the running ones.
+//!
+//! `cancel_job` stops a job cooperatively: the job notices at its next
+//! checkpoint (between files or chunks), undoes what it left half done and
+//! fails with a `cancelled` error:
+//!
+//! - a folder upload deletes the photos it already uploaded, in one commit,
+//! - file encryption and decryption remove their partial output,
+//! - `compress_file` works in memory, so its result is dropped at once,
+//! - `hash_file_blake3` writes nothing and simply stops,
+//! - `decrypt_timelock` stops squaring and keeps nothing.
+//!
+//! `cancel_job` also reaches folder jobs (`compress_jobs`) and stream
+//! operations (`compress_stream`), so one command stops anything by ID.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::AppHandle;
+use crate::compress_jobs::{compress_job_cancel, new_job_id};
+use crate::compress_stream::cancel_compression;
+use crate::github::{AppError, GithubError};
+use crate::util::now_secs;
+
+/// Emitted with a `JobInfo` as soon as a job is registered
+pub const JOB_STARTED_EVENT: &str = "job-started";
+const MAX_JOB_ID_LEN: usize = 128;
+const CANCEL_POLL_MS: u64 = 100;
+
+lazy_static::lazy_static! {
+    static ref JOBS: Mutex<HashMap<String, RunningJob>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobCommand {
+    UploadFolder,
+    CompressFile,
+    EncryptFile,
+    DecryptFile,
+    HashFile,
+    DecryptTimelock,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub job_id: String,
+    pub command: JobCommand,
+    pub started_at: u64,
+    /// `cancel_job` was called and the job has not stopped yet
+    pub cancelling: bool,
+}
+
+struct RunningJob {
+    info: JobInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Error a job fails with once cancelled
+pub fn cancelled(message: impl Into<String>) -> AppError {
+    GithubError::Cancelled { message: message.into() }.into()
+}
+
+pub fn is_cancelled_error(e: &AppError) -> bool {
+    matches!(e, AppError::Github(GithubError::Cancelled { .. }))
+}
+
+/// A registered job, removed from the registry on drop
+pub struct Job {
+    info: JobInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Job {
+    /// Register a job under `job_id`, or a fresh ID
+    pub fn start(job_id: Option<String>, command: JobCommand) -> Result<Self, AppError> {
+        let job_id = job_id.unwrap_or_else(new_job_id);
+        if job_id.is_empty() || job_id.len() > MAX_JOB_ID_LEN {
+            return Err(AppError::Validation(format!("Job IDs are 1 to {} characters", MAX_JOB_ID_LEN)));
+        }
+        let mut jobs = JOBS.lock().unwrap();
+        if jobs.contains_key(&job_id) {
+            return Err(AppError::Validation(format!("Job {} is already running", job_id)));
+        }
+        let info = JobInfo {
+            job_id: job_id.clone(),
+            command,
+            started_at: now_secs(),
+            cancelling: false,
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        jobs.insert(
+            job_id,
+            RunningJob {
+                info: info.clone(),
+                cancel: cancel.clone(),
+            },
+        );
+        Ok(Self { info, cancel })
+    }
+
+    /// `start`, then emit `job-started`
+    pub fn announce(app: &AppHandle, job_id: Option<String>, command: JobCommand) -> Result<Self, AppError> {
+        let job = Self::start(job_id, command)?;
+        let _ = app.emit(JOB_STARTED_EVENT, &job.info);
+        Ok(job)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.info.job_id
+    }
+
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// `Err(cancelled)` once the job is cancelled; call at checkpoints
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            return Err(cancelled("Cancelled"));
+        }
+        Ok(())
+    }
+
+    /// Report a failure caused by cancelling as `cancelled`
+    pub fn outcome<T>(&self, result: Result<T, AppError>) -> Result<T, AppError> {
+        match result {
+            Err(e) if self.is_cancelled() && !is_cancelled_error(&e) => Err(cancelled("Cancelled")),
+            other => other,
+        }
+    }
+
+    /// Run `f` on a blocking thread, giving up on its result as soon as the
+    /// job is cancelled
+    pub async fn run_blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+    ) -> Result<T, AppError> {
+        let mut task = tauri::async_runtime::spawn_blocking(f);
+        loop {
+            let poll = Box::pin(tokio::time::sleep(Duration::from_millis(CANCEL_POLL_MS)));
+            match futures::future::select(&mut task, poll).await {
+                futures::future::Either::Left((result, _)) => {
+                    return self.outcome(result.map_err(|e| AppError::Validation(format!("Job task failed: {}", e)))?);
+                }
+                futures::future::Either::Right(_) => self.check()?,
+            }
+        }
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        JOBS.lock().unwrap().remove(&self.info.job_id);
+    }
+}
+
+/// Writer that fails once `cancel` is set, so stream loops stop at their
+/// next chunk
+pub struct CancelOnWrite<W> {
+    inner: W,
+    cancel: Arc<AtomicBool>,
+}
+
+impl<W: Write> CancelOnWrite<W> {
+    pub fn new(inner: W, cancel: Arc<AtomicBool>) -> Self {
+        Self { inner, cancel }
+    }
+}
+
+impl<W: Write> Write for CancelOnWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(std::io::Error::other("cancelled"));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Flag the job `job_id` for cancelling. Returns false if none is running.
+pub fn cancel_job_in(job_id: &str) -> bool {
+    match JOBS.lock().unwrap().get_mut(job_id) {
+        Some(job) => {
+            job.cancel.store(true, Ordering::Relaxed);
+            job.info.cancelling = true;
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn running_jobs() -> Vec<JobInfo> {
+    let mut jobs: Vec<JobInfo> = JOBS.lock().unwrap().values().map(|job| job.info.clone()).collect();
+    jobs.sort_by(|a, b| a.started_at.cmp(&b.started_at).then_with(|| a.job_id.cmp(&b.job_id)));
+    jobs
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Ask a running job, folder job or stream operation to stop. Returns false
+/// if nothing is running under `job_id`.
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn cancel_job(job_id: String) -> bool {
+    cancel_job_in(&job_id) || compress_job_cancel(job_id.clone()) || cancel_compression(job_id)
+}
+
+/// Running jobs, oldest first
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn list_jobs() -> Vec<JobInfo> {
+    running_jobs()
+}
@@ -0,0 +1,698 @@
+//! Lossless JPEG Optimization
+//!
+//! What `jpegtran -optimize` does, without a C dependency: the entropy-coded
+//! data of each scan is decoded into Huffman symbols and re-encoded with
+//! tables built for that scan's actual symbol frequencies (ITU T.81 Annex
+//! K.2). The quantized coefficients are untouched, so the decoded pixels are
+//! bit-identical; camera JPEGs written with the standard example tables
+//! typically shrink by 5 to 15 percent.
+//!
+//! Only Huffman-coded sequential JPEGs (SOF0 and SOF1) are re-encoded.
+//! Progressive and arithmetic-coded files keep their entropy data and can
+//! only lose metadata. Data after the end-of-image marker (such as the video
+//! of a motion photo) is always kept.
+
+use crate::github::AppError;
+
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const DHT: u8 = 0xC4;
+const DRI: u8 = 0xDD;
+const COM: u8 = 0xFE;
+const APP0: u8 = 0xE0;
+const APP2: u8 = 0xE2;
+const APP14: u8 = 0xEE;
+
+fn invalid(reason: &str) -> AppError {
+    AppError::Validation(format!("Cannot optimize JPEG: {}", reason))
+}
+
+/// Markers without a length field
+fn is_standalone(marker: u8) -> bool {
+    matches!(marker, 0x01 | 0xD0..=0xD9)
+}
+
+fn is_sof(marker: u8) -> bool {
+    matches!(marker, 0xC0..=0xCF) && !matches!(marker, DHT | 0xC8 | 0xCC)
+}
+
+/// Whether a marker segment survives `strip_metadata`: JFIF, the ICC profile
+/// and the Adobe color transform affect how pixels decode, the rest do not
+fn keep_when_stripping(marker: u8, payload: &[u8]) -> bool {
+    match marker {
+        COM => false,
+        APP0 => payload.starts_with(b"JFIF\0"),
+        APP2 => payload.starts_with(b"ICC_PROFILE\0"),
+        APP14 => payload.starts_with(b"Adobe"),
+        0xE1..=0xEF => false,
+        _ => true,
+    }
+}
+
+// ============================================================================
+// Huffman Tables
+// ============================================================================
+
+#[derive(Clone)]
+struct HuffmanTable {
+    /// Number of codes of each length 1 to 16
+    bits: [u8; 16],
+    values: Vec<u8>,
+}
+
+impl HuffmanTable {
+    fn parse(bits: &[u8], values: &[u8]) -> Result<Self, AppError> {
+        let total: usize = bits.iter().map(|&b| b as usize).sum();
+        if total > 256 || values.len() != total {
+            return Err(invalid("bad Huffman table"));
+        }
+        Ok(Self {
+            bits: bits.try_into().expect("16 lengths"),
+            values: values.to_vec(),
+        })
+    }
+
+    fn decoder(&self) -> Result<HuffmanDecoder, AppError> {
+        let mut decoder = HuffmanDecoder {
+            max_code: [-1; 17],
+            val_offset: [0; 17],
+            values: self.values.clone(),
+        };
+        let mut code = 0i32;
+        let mut k = 0usize;
+        for len in 1..=16 {
+            let count = self.bits[len - 1] as usize;
+            if count > 0 {
+                decoder.val_offset[len] = k as i32 - code;
+                code += count as i32;
+                k += count;
+                decoder.max_code[len] = code - 1;
+            }
+            if code > 1 << len {
+                return Err(invalid("oversubscribed Huffman table"));
+            }
+            code <<= 1;
+        }
+        Ok(decoder)
+    }
+
+    /// Code and length for every symbol
+    fn encoder(&self) -> [(u16, u8); 256] {
+        let mut table = [(0u16, 0u8); 256];
+        let mut code = 0u16;
+        let mut k = 0usize;
+        for len in 1..=16u8 {
+            for _ in 0..self.bits[len as usize - 1] {
+                table[self.values[k] as usize] = (code, len);
+                code += 1;
+                k += 1;
+            }
+            code <<= 1;
+        }
+        table
+    }
+
+    /// Optimal length-limited table for `freq` (Annex K.2, as in libjpeg).
+    /// A reserved pseudo-symbol keeps any code from being all ones.
+    fn optimal(freq: &[u64; 256]) -> Self {
+        let mut freq = *freq;
+        loop {
+            if let Some(table) = Self::try_optimal(&freq) {
+                return table;
+            }
+            // Code lengths past 32 only happen with extremely skewed counts;
+            // flatten them and try again
+            for f in freq.iter_mut().filter(|f| **f > 0) {
+                *f = (*f / 2).max(1);
+            }
+        }
+    }
+
+    fn try_optimal(input: &[u64; 256]) -> Option<Self> {
+        let mut freq = [0u64; 257];
+        freq[..256].copy_from_slice(input);
+        freq[256] = 1;
+        let mut code_size = [0usize; 257];
+        let mut others = [usize::MAX; 257];
+
+        loop {
+            let mut c1 = None;
+            let mut v = u64::MAX;
+            for (i, &f) in freq.iter().enumerate() {
+                if f != 0 && f <= v {
+                    v = f;
+                    c1 = Some(i);
+                }
+            }
+            let mut c2 = None;
+            v = u64::MAX;
+            for (i, &f) in freq.iter().enumerate() {
+                if f != 0 && f <= v && Some(i) != c1 {
+                    v = f;
+                    c2 = Some(i);
+                }
+            }
+            let (Some(mut c1), Some(mut c2)) = (c1, c2) else {
+                break;
+            };
+            freq[c1] += freq[c2];
+            freq[c2] = 0;
+            code_size[c1] += 1;
+            while others[c1] != usize::MAX {
+                c1 = others[c1];
+                code_size[c1] += 1;
+            }
+            others[c1] = c2;
+            code_size[c2] += 1;
+            while others[c2] != usize::MAX {
+                c2 = others[c2];
+                code_size[c2] += 1;
+            }
+        }
+
+        let mut bits = [0u32; 33];
+        for &size in code_size.iter().filter(|&&s| s > 0) {
+            if size > 32 {
+                return None;
+            }
+            bits[size] += 1;
+        }
+        for i in (17..=32).rev() {
+            while bits[i] > 0 {
+                let mut j = i - 2;
+                while bits[j] == 0 {
+                    j -= 1;
+                }
+                bits[i] -= 2;
+                bits[i - 1] += 1;
+                bits[j + 1] += 2;
+                bits[j] -= 1;
+            }
+        }
+        let mut longest = 16;
+        while bits[longest] == 0 {
+            longest -= 1;
+        }
+        bits[longest] -= 1;
+
+        let mut values = Vec::new();
+        for len in 1..=32 {
+            for (symbol, &size) in code_size[..256].iter().enumerate() {
+                if size == len {
+                    values.push(symbol as u8);
+                }
+            }
+        }
+        let mut table_bits = [0u8; 16];
+        for (len, slot) in table_bits.iter_mut().enumerate() {
+            *slot = bits[len + 1] as u8;
+        }
+        Some(Self {
+            bits: table_bits,
+            values,
+        })
+    }
+}
+
+struct HuffmanDecoder {
+    max_code: [i32; 17],
+    val_offset: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl HuffmanDecoder {
+    fn decode(&self, reader: &mut BitReader) -> Result<u8, AppError> {
+        let mut code = 0i32;
+        for len in 1..=16 {
+            code = (code << 1) | reader.bit()? as i32;
+            if code <= self.max_code[len] {
+                return self
+                    .values
+                    .get((code + self.val_offset[len]) as usize)
+                    .copied()
+                    .ok_or_else(|| invalid("bad Huffman code"));
+            }
+        }
+        Err(invalid("bad Huffman code"))
+    }
+}
+
+// ============================================================================
+// Bit I/O
+// ============================================================================
+
+/// Reads one restart interval of entropy-coded data, already unstuffed
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0, bit: 0 }
+    }
+
+    fn bit(&mut self) -> Result<u8, AppError> {
+        let byte = *self.data.get(self.pos).ok_or_else(|| invalid("entropy data ends early"))?;
+        let value = (byte >> (7 - self.bit)) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn bits(&mut self, count: u8) -> Result<u16, AppError> {
+        let mut value = 0u16;
+        for _ in 0..count {
+            value = (value << 1) | self.bit()? as u16;
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u16, len: u8) {
+        for i in (0..len).rev() {
+            self.acc = (self.acc << 1) | ((value >> i) & 1) as u32;
+            self.count += 1;
+            if self.count == 8 {
+                self.emit(self.acc as u8);
+                self.acc = 0;
+                self.count = 0;
+            }
+        }
+    }
+
+    fn emit(&mut self, byte: u8) {
+        self.out.push(byte);
+        if byte == 0xFF {
+            self.out.push(0x00);
+        }
+    }
+
+    /// Pad the last byte with ones, as the standard requires
+    fn flush(&mut self) {
+        if self.count > 0 {
+            let pad = 8 - self.count;
+            self.put((1 << pad) - 1, pad);
+        }
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+#[derive(Clone, Copy)]
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+}
+
+impl Frame {
+    fn parse(payload: &[u8]) -> Result<Self, AppError> {
+        if payload.len() < 6 {
+            return Err(invalid("short frame header"));
+        }
+        let height = u16::from_be_bytes([payload[1], payload[2]]) as usize;
+        let width = u16::from_be_bytes([payload[3], payload[4]]) as usize;
+        let count = payload[5] as usize;
+        if payload.len() < 6 + 3 * count || count == 0 || width == 0 || height == 0 {
+            return Err(invalid("bad frame header"));
+        }
+        let components = (0..count)
+            .map(|i| {
+                let c = &payload[6 + 3 * i..9 + 3 * i];
+                Component {
+                    id: c[0],
+                    h: (c[1] >> 4).max(1) as usize,
+                    v: (c[1] & 15).max(1) as usize,
+                }
+            })
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            components,
+        })
+    }
+
+    fn max_h(&self) -> usize {
+        self.components.iter().map(|c| c.h).max().unwrap_or(1)
+    }
+
+    fn max_v(&self) -> usize {
+        self.components.iter().map(|c| c.v).max().unwrap_or(1)
+    }
+}
+
+/// One component of a scan and its table slots
+struct ScanComponent {
+    component: Component,
+    dc: usize,
+    ac: usize,
+}
+
+/// A coded symbol and the extra bits that follow it
+#[derive(Clone, Copy)]
+struct Symbol {
+    /// Slot in `SymbolCounts`: DC tables 0..4, AC tables 4..8
+    table: usize,
+    value: u8,
+    extra: u16,
+    extra_len: u8,
+}
+
+/// Split the entropy-coded data after an SOS header into restart intervals
+/// (unstuffed), returning them and the offset of the next marker
+fn split_intervals(data: &[u8], start: usize) -> Result<(Vec<Vec<u8>>, usize), AppError> {
+    let mut intervals = vec![Vec::new()];
+    let mut i = start;
+    loop {
+        let byte = *data.get(i).ok_or_else(|| invalid("missing end of image"))?;
+        if byte != 0xFF {
+            intervals.last_mut().expect("one interval").push(byte);
+            i += 1;
+            continue;
+        }
+        let next = *data.get(i + 1).ok_or_else(|| invalid("missing end of image"))?;
+        match next {
+            0x00 => {
+                intervals.last_mut().expect("one interval").push(0xFF);
+                i += 2;
+            }
+            // Fill bytes before a marker
+            0xFF => i += 1,
+            0xD0..=0xD7 => {
+                intervals.push(Vec::new());
+                i += 2;
+            }
+            _ => return Ok((intervals, i)),
+        }
+    }
+}
+
+struct Scan {
+    components: Vec<ScanComponent>,
+    /// Header bytes after the component list (Ss, Se, Ah/Al)
+    tail: [u8; 3],
+    intervals: Vec<Vec<Symbol>>,
+}
+
+fn decode_block(
+    reader: &mut BitReader,
+    decoders: &[Option<HuffmanDecoder>; 8],
+    sc: &ScanComponent,
+    out: &mut Vec<Symbol>,
+) -> Result<(), AppError> {
+    let dc = decoders[sc.dc].as_ref().ok_or_else(|| invalid("scan uses an undefined table"))?;
+    let size = dc.decode(reader)?;
+    if size > 16 {
+        return Err(invalid("bad DC difference"));
+    }
+    out.push(Symbol {
+        table: sc.dc,
+        value: size,
+        extra: reader.bits(size)?,
+        extra_len: size,
+    });
+
+    let ac = decoders[sc.ac].as_ref().ok_or_else(|| invalid("scan uses an undefined table"))?;
+    let mut k = 1;
+    while k < 64 {
+        let rs = ac.decode(reader)?;
+        let (run, size) = ((rs >> 4) as usize, rs & 15);
+        out.push(Symbol {
+            table: sc.ac,
+            value: rs,
+            extra: reader.bits(size)?,
+            extra_len: size,
+        });
+        if size == 0 {
+            if run == 15 {
+                k += 16;
+                continue;
+            }
+            break;
+        }
+        k += run + 1;
+    }
+    if k > 64 {
+        return Err(invalid("coefficient run past the block"));
+    }
+    Ok(())
+}
+
+fn decode_scan(
+    frame: &Frame,
+    scan_components: Vec<ScanComponent>,
+    tail: [u8; 3],
+    decoders: &[Option<HuffmanDecoder>; 8],
+    restart_interval: usize,
+    intervals: Vec<Vec<u8>>,
+) -> Result<Scan, AppError> {
+    if tail[0] != 0 || tail[1] != 63 || tail[2] != 0 {
+        return Err(invalid("not a sequential scan"));
+    }
+    let (max_h, max_v) = (frame.max_h(), frame.max_v());
+    // Blocks per MCU for each scan component, and the MCU count
+    let (blocks, mcus): (Vec<usize>, usize) = if scan_components.len() == 1 {
+        let c = scan_components[0].component;
+        let w = (frame.width * c.h).div_ceil(max_h).div_ceil(8);
+        let h = (frame.height * c.v).div_ceil(max_v).div_ceil(8);
+        (vec![1], w * h)
+    } else {
+        let w = frame.width.div_ceil(8 * max_h);
+        let h = frame.height.div_ceil(8 * max_v);
+        (scan_components.iter().map(|sc| sc.component.h * sc.component.v).collect(), w * h)
+    };
+    let per_interval = if restart_interval == 0 { mcus } else { restart_interval };
+    if intervals.len() != mcus.div_ceil(per_interval).max(1) {
+        return Err(invalid("restart markers do not match the image size"));
+    }
+
+    let mut decoded = Vec::with_capacity(intervals.len());
+    let mut remaining = mcus;
+    for data in &intervals {
+        let mut reader = BitReader::new(data);
+        let mut symbols = Vec::new();
+        for _ in 0..per_interval.min(remaining) {
+            for (sc, &count) in scan_components.iter().zip(&blocks) {
+                for _ in 0..count {
+                    decode_block(&mut reader, decoders, sc, &mut symbols)?;
+                }
+            }
+        }
+        remaining -= per_interval.min(remaining);
+        decoded.push(symbols);
+    }
+    Ok(Scan {
+        components: scan_components,
+        tail,
+        intervals: decoded,
+    })
+}
+
+/// The frame marker (SOFn), from the segments before the first scan
+fn frame_marker(data: &[u8]) -> Option<u8> {
+    let mut i = 2;
+    while i + 4 <= data.len() && data[i] == 0xFF {
+        let marker = data[i + 1];
+        if marker == 0xFF {
+            i += 1;
+            continue;
+        }
+        if is_sof(marker) {
+            return Some(marker);
+        }
+        if marker == SOS || is_standalone(marker) {
+            return None;
+        }
+        i += 2 + u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+    }
+    None
+}
+
+fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![0xFF, marker];
+    out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Optimal tables for `scan`, then its header and re-encoded data
+fn encode_scan(scan: &Scan) -> Vec<u8> {
+    let mut freq = [[0u64; 256]; 8];
+    for symbol in scan.intervals.iter().flatten() {
+        freq[symbol.table][symbol.value as usize] += 1;
+    }
+
+    let mut used: Vec<usize> = scan.components.iter().flat_map(|sc| [sc.dc, sc.ac]).collect();
+    used.sort_unstable();
+    used.dedup();
+    let mut dht = Vec::new();
+    let mut encoders = [[(0u16, 0u8); 256]; 8];
+    for &slot in &used {
+        let table = HuffmanTable::optimal(&freq[slot]);
+        let class = if slot < 4 { 0 } else { 1 };
+        dht.push((class << 4) | (slot % 4) as u8);
+        dht.extend_from_slice(&table.bits);
+        dht.extend_from_slice(&table.values);
+        encoders[slot] = table.encoder();
+    }
+
+    let mut out = segment(DHT, &dht);
+    let mut header = vec![scan.components.len() as u8];
+    for sc in &scan.components {
+        header.push(sc.component.id);
+        header.push(((sc.dc as u8) << 4) | (sc.ac - 4) as u8);
+    }
+    header.extend_from_slice(&scan.tail);
+    out.extend(segment(SOS, &header));
+
+    for (i, symbols) in scan.intervals.iter().enumerate() {
+        if i > 0 {
+            out.extend_from_slice(&[0xFF, 0xD0 + ((i - 1) % 8) as u8]);
+        }
+        let mut writer = BitWriter::default();
+        for symbol in symbols {
+            let (code, len) = encoders[symbol.table][symbol.value as usize];
+            writer.put(code, len);
+            writer.put(symbol.extra, symbol.extra_len);
+        }
+        writer.flush();
+        out.extend(writer.out);
+    }
+    out
+}
+
+/// Rebuild `data` with optimal Huffman tables, dropping metadata segments if
+/// `strip_metadata` is set. Fails on anything it cannot re-encode exactly.
+pub fn optimize_jpeg(data: &[u8], strip_metadata: bool) -> Result<Vec<u8>, AppError> {
+    if data.len() < 4 || data[0] != 0xFF || data[1] != SOI {
+        return Err(invalid("missing start of image"));
+    }
+    let mut out = vec![0xFF, SOI];
+    let mut tables: [Option<HuffmanTable>; 8] = Default::default();
+    let mut frame: Option<Frame> = None;
+    let reencode = matches!(frame_marker(data), Some(0xC0 | 0xC1));
+    let mut restart_interval = 0usize;
+    let mut i = 2;
+
+    loop {
+        if data.get(i) != Some(&0xFF) {
+            return Err(invalid("expected a marker"));
+        }
+        while data.get(i + 1) == Some(&0xFF) {
+            i += 1;
+        }
+        let marker = *data.get(i + 1).ok_or_else(|| invalid("missing end of image"))?;
+        i += 2;
+        if marker == EOI {
+            out.extend_from_slice(&[0xFF, EOI]);
+            out.extend_from_slice(&data[i..]);
+            return Ok(out);
+        }
+        if is_standalone(marker) {
+            return Err(invalid("unexpected marker"));
+        }
+        let len = data
+            .get(i..i + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+            .filter(|&len| len >= 2 && i + len <= data.len())
+            .ok_or_else(|| invalid("truncated segment"))?;
+        let payload = &data[i + 2..i + len];
+        i += len;
+
+        match marker {
+            _ if is_sof(marker) => {
+                if frame.is_some() {
+                    return Err(invalid("more than one frame"));
+                }
+                frame = Some(Frame::parse(payload)?);
+                out.extend(segment(marker, payload));
+            }
+            DHT => {
+                let mut p = payload;
+                while !p.is_empty() {
+                    if p.len() < 17 {
+                        return Err(invalid("truncated Huffman table"));
+                    }
+                    let (class, slot) = (p[0] >> 4, (p[0] & 15) as usize);
+                    if class > 1 || slot > 3 {
+                        return Err(invalid("bad Huffman table id"));
+                    }
+                    let count: usize = p[1..17].iter().map(|&b| b as usize).sum();
+                    let values = p.get(17..17 + count).ok_or_else(|| invalid("truncated Huffman table"))?;
+                    tables[class as usize * 4 + slot] = Some(HuffmanTable::parse(&p[1..17], values)?);
+                    p = &p[17 + count..];
+                }
+                // Re-encoded scans get fresh tables of their own
+                if !reencode {
+                    out.extend(segment(marker, payload));
+                }
+            }
+            DRI => {
+                if payload.len() != 2 {
+                    return Err(invalid("bad restart interval"));
+                }
+                restart_interval = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+                out.extend(segment(marker, payload));
+            }
+            SOS => {
+                let frame = frame.as_ref().ok_or_else(|| invalid("scan before frame header"))?;
+                let (intervals, next) = split_intervals(data, i)?;
+                if !reencode {
+                    out.extend(segment(marker, payload));
+                    out.extend_from_slice(&data[i..next]);
+                    i = next;
+                    continue;
+                }
+                let count = *payload.first().ok_or_else(|| invalid("empty scan header"))? as usize;
+                if count == 0 || payload.len() != 1 + 2 * count + 3 {
+                    return Err(invalid("bad scan header"));
+                }
+                let mut scan_components = Vec::with_capacity(count);
+                for c in payload[1..1 + 2 * count].chunks(2) {
+                    let component = *frame
+                        .components
+                        .iter()
+                        .find(|fc| fc.id == c[0])
+                        .ok_or_else(|| invalid("scan names an unknown component"))?;
+                    let (dc, ac) = ((c[1] >> 4) as usize, (c[1] & 15) as usize);
+                    if dc > 3 || ac > 3 {
+                        return Err(invalid("bad table selector"));
+                    }
+                    scan_components.push(ScanComponent { component, dc, ac: ac + 4 });
+                }
+                let decoders: [Option<HuffmanDecoder>; 8] = std::array::from_fn(|slot| {
+                    tables[slot].as_ref().and_then(|t| t.decoder().ok())
+                });
+                let tail = payload[1 + 2 * count..].try_into().expect("3 bytes");
+                let scan = decode_scan(frame, scan_components, tail, &decoders, restart_interval, intervals)?;
+                out.extend(encode_scan(&scan));
+                i = next;
+            }
+            _ if strip_metadata && !keep_when_stripping(marker, payload) => {}
+            _ => out.extend(segment(marker, payload)),
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! Keypair Rotation
+//!
+//! `rotate_keypair` replaces the keypair behind a handle with a freshly
+//! generated one. With a repository given, everything in it that was bound to
+//! the old key is moved to the new one in a single commit:
+//!
+//! - albums owned by the old key get their content key wrapped for the new
+//!   key (`AlbumManifest::owner_key`), so no photo has to be re-encrypted,
+//!   and their manifests are signed with the new key,
+//! - album grants and thread payloads addressed to the old key are re-wrapped,
+//! - single secure messages (`messages/*.msg`) sealed to the old key are
+//!   re-encrypted,
+//! - the new public bundle is published to `keys/public_bundle.json`.
+//!
+//! The old keypair stays usable for decryption under the same handle for the
+//! rest of the session, and is written to a local archive
+//! (`<profile data>/key_archive/<key_id>.bin`, sealed with the
+//! machine key) so it can be restored for emergency decryption later.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::State;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::album::{album_key_for, wrap_album_key, AlbumGrant, AlbumManifest, ALBUM_MANIFEST_FILE, ALBUM_ROOT};
+use crate::crypto::{
+    decrypt, encrypt, install_rotated_keypair, open_with_machine_key, register_keypair, seal_with_machine_key,
+    with_keypair, EncryptedPayload, HybridKeypair, KeypairHandle, KeypairInfo, PublicBundle,
+};
+use crate::git_data::{branch_head, commit_changes, create_blob, get_blob, get_tree_recursive, index_blobs, TreeChange};
+use crate::github::{validate_repo, AppError, GithubError, HttpClient};
+use crate::mirror::replicate_tree_changes;
+use crate::security_verify::sign_manifest;
+use crate::sharing::album_id;
+use crate::threads::{rewrap_envelope, MessageEnvelope, ThreadIndex, THREADS_ROOT, THREAD_INDEX_FILE};
+use crate::util::now_secs;
+
+pub const PUBLISHED_BUNDLE_PATH: &str = "keys/public_bundle.json";
+const ARCHIVE_DIR: &str = "key_archive";
+const ARCHIVE_AAD: &[u8] = b"vortex-key-archive-v1";
+
+/// Contents of `keys/public_bundle.json`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PublishedBundle {
+    pub bundle: PublicBundle,
+    pub previous_key_id: Option<String>,
+    pub published_at: u64,
+}
+
+/// A retired keypair as stored in the local archive. Holds key material, so
+/// it is zeroized on drop.
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+struct ArchivedKey {
+    key_id: String,
+    #[zeroize(skip)]
+    public_bundle: PublicBundle,
+    archived_at: u64,
+    /// Base64 of `HybridKeypair::to_bytes`
+    keypair: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedKeyInfo {
+    pub key_id: String,
+    pub created_at: u64,
+    pub archived_at: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RewrapCounts {
+    /// Albums owned by the old key
+    pub albums: usize,
+    /// Albums shared with the old key
+    pub grants: usize,
+    pub messages: usize,
+    pub thread_messages: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RotationReport {
+    pub old_key_id: String,
+    pub public_bundle: PublicBundle,
+    pub rewrapped: RewrapCounts,
+    /// Commit moving the repository to the new key, when one was given
+    pub commit_sha: Option<String>,
+}
+
+fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, AppError> {
+    serde_json::to_vec_pretty(value).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))
+}
+
+// ============================================================================
+// Re-wrapping
+// ============================================================================
+
+/// Move an album manifest from the keypair behind `handle` to `new_bundle`.
+/// Returns whether the album was owned by the old key and whether it held a
+/// grant for it.
+pub fn rewrap_manifest(
+    handle: KeypairHandle,
+    repo: &str,
+    album_path: &str,
+    manifest: &mut AlbumManifest,
+    new_bundle: &PublicBundle,
+) -> Result<(bool, bool), AppError> {
+    let old_key_id = with_keypair(handle, |kp| Ok(kp.public_bundle().key_id))?;
+    let owned = manifest.encrypted && manifest.owner_key_id.as_deref() == Some(old_key_id.as_str());
+    let granted = manifest.access.contains_key(&old_key_id);
+    if !owned && !granted {
+        return Ok((false, false));
+    }
+
+    let id = album_id(repo, album_path);
+    let key = Zeroizing::new(album_key_for(handle, repo, album_path, manifest)?);
+    let wrapped = wrap_album_key(&key, new_bundle, &id, manifest.key_epoch)?;
+
+    if owned {
+        manifest.owner_key = Some(wrapped);
+        manifest.owner_key_id = Some(new_bundle.key_id.clone());
+    } else if let Some(grant) = manifest.access.remove(&old_key_id) {
+        manifest.access.insert(
+            new_bundle.key_id.clone(),
+            AlbumGrant {
+                bundle: new_bundle.clone(),
+                wrapped_key: wrapped,
+                granted_at: grant.granted_at,
+            },
+        );
+    }
+    Ok((owned, granted))
+}
+
+/// Re-encrypt a single secure message for `new_bundle` if the keypair behind
+/// `handle` can open it. Returns `None` for messages sealed to other keys.
+pub fn rewrap_secure_message(
+    handle: KeypairHandle,
+    raw: &[u8],
+    new_bundle: &PublicBundle,
+) -> Result<Option<Vec<u8>>, AppError> {
+    let Ok(payload) = serde_json::from_slice::<EncryptedPayload>(raw) else {
+        return Ok(None);
+    };
+    let Ok(content) = with_keypair(handle, |kp| decrypt(&payload, kp)).map(Zeroizing::new) else {
+        return Ok(None);
+    };
+    let rewrapped = encrypt(&content, new_bundle)?;
+    serde_json::to_vec(&rewrapped)
+        .map(Some)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))
+}
+
+async fn rewrap_repository(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    handle: KeypairHandle,
+    old_key_id: &str,
+    new_keypair: &HybridKeypair,
+) -> Result<(RewrapCounts, Option<String>), AppError> {
+    let new_bundle = &new_keypair.public_bundle();
+    let head = branch_head(client, repo, token).await?;
+    let index = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+    let mut paths: Vec<&String> = index.keys().collect();
+    paths.sort();
+
+    let mut counts = RewrapCounts::default();
+    let mut changes = Vec::new();
+    let album_prefix = format!("{}/", ALBUM_ROOT);
+    let manifest_suffix = format!("/{}", ALBUM_MANIFEST_FILE);
+    let thread_prefix = format!("{}/", THREADS_ROOT);
+    let thread_index_suffix = format!("/{}", THREAD_INDEX_FILE);
+
+    for path in paths {
+        let sha = &index[path].sha;
+        let album = path
+            .strip_suffix(&manifest_suffix)
+            .filter(|album| album.starts_with(&album_prefix));
+        if let Some(album) = album {
+            let Ok(mut manifest) = serde_json::from_slice::<AlbumManifest>(&get_blob(client, repo, token, sha).await?)
+            else {
+                continue;
+            };
+            let (owned, granted) = rewrap_manifest(handle, repo, album, &mut manifest, new_bundle)?;
+            if owned || granted {
+                counts.albums += owned as usize;
+                counts.grants += granted as usize;
+                manifest.signature = Some(sign_manifest(&manifest, new_keypair)?);
+                changes.push(TreeChange::blob(path, &create_blob(client, repo, token, &serialize(&manifest)?).await?));
+            }
+        } else if let Some(thread) = path.strip_prefix(&thread_prefix) {
+            if let Some(thread_id) = thread.strip_suffix(&thread_index_suffix) {
+                let Ok(mut thread) = serde_json::from_slice::<ThreadIndex>(&get_blob(client, repo, token, sha).await?)
+                else {
+                    continue;
+                };
+                if thread.thread_id == thread_id && thread.rotate_participant(old_key_id, new_bundle.clone()) {
+                    changes.push(TreeChange::blob(path, &create_blob(client, repo, token, &serialize(&thread)?).await?));
+                }
+            } else if path.ends_with(".msg") {
+                let Ok(mut envelope) =
+                    serde_json::from_slice::<MessageEnvelope>(&get_blob(client, repo, token, sha).await?)
+                else {
+                    continue;
+                };
+                if rewrap_envelope(handle, &mut envelope, new_bundle)? {
+                    counts.thread_messages += 1;
+                    let body = serde_json::to_vec(&envelope)
+                        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+                    changes.push(TreeChange::blob(path, &create_blob(client, repo, token, &body).await?));
+                }
+            }
+        } else if path.starts_with("messages/") && path.ends_with(".msg") {
+            let raw = get_blob(client, repo, token, sha).await?;
+            if let Some(body) = rewrap_secure_message(handle, &raw, new_bundle)? {
+                counts.messages += 1;
+                changes.push(TreeChange::blob(path, &create_blob(client, repo, token, &body).await?));
+            }
+        }
+    }
+
+    let published = PublishedBundle {
+        bundle: new_bundle.clone(),
+        previous_key_id: Some(old_key_id.to_string()),
+        published_at: now_secs(),
+    };
+    changes.push(TreeChange::blob(
+        PUBLISHED_BUNDLE_PATH,
+        &create_blob(client, repo, token, &serialize(&published)?).await?,
+    ));
+
+    let message = format!("Rotate keypair to {}", new_bundle.key_id);
+    let commit = commit_changes(client, repo, token, &head, &changes, &message).await?;
+    replicate_tree_changes(client, repo, token, &changes);
+    Ok((counts, Some(commit)))
+}
+
+// ============================================================================
+// Archive
+// ============================================================================
+
+fn archive_dir() -> Result<PathBuf, AppError> {
+    let dir = crate::profiles::data_dir()?.join(ARCHIVE_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn archive_keypair(handle: KeypairHandle) -> Result<(), AppError> {
+    let archived = with_keypair(handle, |kp| {
+        let public_bundle = kp.public_bundle();
+        Ok(ArchivedKey {
+            key_id: public_bundle.key_id.clone(),
+            public_bundle,
+            archived_at: now_secs(),
+            keypair: STANDARD.encode(kp.to_bytes().as_slice()),
+        })
+    })
+    ?;
+
+    let json = Zeroizing::new(serde_json::to_vec(&archived).map_err(|e| AppError::Validation(e.to_string()))?);
+    let sealed = seal_with_machine_key(&json, ARCHIVE_AAD)?;
+    let path = archive_dir()?.join(format!("{}.bin", archived.key_id));
+    let tmp = path.with_extension("bin.tmp");
+    std::fs::write(&tmp, sealed)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn read_archive(path: &std::path::Path) -> Result<ArchivedKey, AppError> {
+    let json = Zeroizing::new(
+        open_with_machine_key(&std::fs::read(path)?, ARCHIVE_AAD)
+            .map_err(|_| AppError::Validation("Archived key could not be decrypted".into()))?,
+    );
+    serde_json::from_slice(&json).map_err(|e| AppError::Validation(format!("Corrupt key archive: {}", e)))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Rotate the keypair behind `handle`. With `repo` and `token`, albums,
+/// grants and messages in that repository are moved to the new key and the
+/// new public bundle is published there.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn rotate_keypair(
+    client: State<'_, HttpClient>,
+    handle: KeypairHandle,
+    repo: Option<String>,
+    token: Option<Zeroizing<String>>,
+) -> Result<RotationReport, AppError> {
+    if let Some(repo) = &repo {
+        validate_repo(repo)?;
+    }
+
+    let new_keypair = HybridKeypair::generate()?;
+    let old_key_id = with_keypair(handle, |kp| Ok(kp.public_bundle().key_id))?;
+
+    // Archive first: if anything below fails, the old key is still safe
+    archive_keypair(handle)?;
+
+    let (rewrapped, commit_sha) = match (repo.as_deref(), token.as_deref()) {
+        (Some(repo), Some(token)) => {
+            rewrap_repository(&client.0, repo, token, handle, &old_key_id, &new_keypair).await?
+        }
+        (None, None) => (RewrapCounts::default(), None),
+        _ => return Err(AppError::Validation("Pass both repo and token, or neither".into())),
+    };
+
+    let public_bundle = install_rotated_keypair(handle, new_keypair)?;
+    let detail = format!("to {}", public_bundle.key_id);
+    record_activity(ActivityKind::Key, "rotate_keypair", repo.as_deref(), &old_key_id, Some(detail));
+    Ok(RotationReport {
+        old_key_id,
+        public_bundle,
+        rewrapped,
+        commit_sha,
+    })
+}
+
+/// Keys retired by `rotate_keypair`, newest first
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn list_archived_keys() -> Result<Vec<ArchivedKeyInfo>, AppError> {
+    let mut keys = Vec::new();
+    for entry in std::fs::read_dir(archive_dir()?)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("bin") {
+            continue;
+        }
+        if let Ok(archived) = read_archive(&path) {
+            keys.push(ArchivedKeyInfo {
+                key_id: archived.key_id.clone(),
+                created_at: archived.public_bundle.created_at,
+                archived_at: archived.archived_at,
+            });
+        }
+    }
+    keys.sort_by_key(|k| std::cmp::Reverse(k.archived_at));
+    Ok(keys)
+}
+
+/// Load an archived keypair into the store for emergency decryption
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn restore_archived_key(key_id: String) -> Result<KeypairInfo, AppError> {
+    if key_id.is_empty() || !key_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::Validation("Invalid key id".into()));
+    }
+    let path = archive_dir()?.join(format!("{}.bin", key_id));
+    if !path.exists() {
+        return Err(GithubError::NotFound {
+            message: format!("No archived key {}", key_id),
+        }
+        .into());
+    }
+    let archived = read_archive(&path)?;
+    let bytes = Zeroizing::new(
+        STANDARD
+            .decode(archived.keypair.as_bytes())
+            .map_err(|_| AppError::Validation("Corrupt key archive".into()))?,
+    );
+    let keypair = HybridKeypair::from_bytes(&bytes)?;
+    let info = register_keypair(keypair)?;
+    record_activity(ActivityKind::Key, "restore_archived_key", None, &key_id, None);
+    Ok(info)
+}
@@ -0,0 +1,450 @@
+//! Keystore
+//!
+//! One interface over the places secrets can live on this machine:
+//!
+//! - the OS keystore through `keyring`: macOS Keychain, Windows Credential
+//!   Manager (DPAPI-protected, per user), Linux Secret Service,
+//! - an encrypted file under `<local data>/vortex-image/tokens`, sealed with
+//!   the machine key, when no OS keystore is reachable (headless Linux,
+//!   containers, locked keychains).
+//!
+//! `Keystore::detect` chains them in that order, so writes land in the best
+//! available store and reads find secrets written by either. Secret names
+//! are scoped to the active profile (see `profiles::secret_name`).
+//!
+//! Secure Enclave and TPM keys cannot perform the hybrid post-quantum
+//! operations, so they are not used to hold keys directly. Whether the OS
+//! keystore binds its own master keys to such a chip is up to the platform;
+//! `get_keystore_backend` reports which one is present.
+//!
+//! The keypair is too large for some OS keystores (Credential Manager caps a
+//! secret at 2.5 KB), so `store_keypair_in_keystore` keeps a random key
+//! encryption key in the keystore and the keypair, sealed with it, in
+//! `keypair.bin` in the active profile's data directory.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::crypto::{
+    decrypt_token, decrypt_with_key, encrypt_token, encrypt_with_key, keychain_available, keychain_delete,
+    keychain_retrieve, keychain_store, register_keypair, with_keypair, CryptoError, HybridKeypair, KeypairHandle,
+    KeypairInfo,
+};
+
+const KEYPAIR_KEK_ENTRY: &str = "keypair_kek";
+const KEYPAIR_FILE: &str = "keypair.bin";
+const KEYPAIR_AAD: &[u8] = b"vortex-keystore-keypair-v1";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeystoreBackend {
+    MacosKeychain,
+    /// Windows Credential Manager, encrypted with DPAPI
+    WindowsCredentialManager,
+    /// freedesktop Secret Service (GNOME Keyring, KWallet)
+    SecretService,
+    /// Machine-key encrypted files
+    EncryptedFile,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HardwareModule {
+    SecureEnclave,
+    Tpm,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeystoreInfo {
+    /// Where new secrets are written
+    pub backend: KeystoreBackend,
+    /// All backends in lookup order
+    pub chain: Vec<KeystoreBackend>,
+    /// Security chip found on this machine, if any
+    pub hardware: Option<HardwareModule>,
+    /// Why the OS keystore is not in use
+    pub fallback_reason: Option<String>,
+}
+
+/// A place to keep named secrets
+pub trait SecretStore: Send + Sync {
+    fn backend(&self) -> KeystoreBackend;
+    fn store(&self, name: &str, value: &str) -> Result<(), CryptoError>;
+    /// `Ok(None)` when the secret does not exist
+    fn retrieve(&self, name: &str) -> Result<Option<Zeroizing<String>>, CryptoError>;
+    /// Whether anything was deleted
+    fn delete(&self, name: &str) -> Result<bool, CryptoError>;
+}
+
+fn validate_name(name: &str) -> Result<(), CryptoError> {
+    let valid = !name.is_empty()
+        && name.len() <= 128
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !name.starts_with('.');
+    if valid {
+        Ok(())
+    } else {
+        Err(CryptoError::InvalidInput(format!("invalid secret name '{}'", name)))
+    }
+}
+
+// ============================================================================
+// Backends
+// ============================================================================
+
+/// The platform keystore behind `keyring`
+pub struct OsKeychain;
+
+impl OsKeychain {
+    pub fn platform_backend() -> KeystoreBackend {
+        if cfg!(target_os = "macos") || cfg!(target_os = "ios") {
+            KeystoreBackend::MacosKeychain
+        } else if cfg!(target_os = "windows") {
+            KeystoreBackend::WindowsCredentialManager
+        } else {
+            KeystoreBackend::SecretService
+        }
+    }
+}
+
+impl SecretStore for OsKeychain {
+    fn backend(&self) -> KeystoreBackend {
+        Self::platform_backend()
+    }
+
+    fn store(&self, name: &str, value: &str) -> Result<(), CryptoError> {
+        keychain_store(name, value.as_bytes())
+    }
+
+    fn retrieve(&self, name: &str) -> Result<Option<Zeroizing<String>>, CryptoError> {
+        // keyring reports a missing entry as an error, which is not one here
+        match keychain_retrieve(name) {
+            Ok(bytes) => String::from_utf8(bytes)
+                .map(|s| Some(Zeroizing::new(s)))
+                .map_err(|_| CryptoError::Keychain("invalid utf8".into())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn delete(&self, name: &str) -> Result<bool, CryptoError> {
+        Ok(keychain_delete(name).is_ok())
+    }
+}
+
+/// Machine-key encrypted files, one per secret (v4 token format)
+pub struct EncryptedFileStore {
+    dir: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// `<local data>/vortex-image/tokens`
+    pub fn default_location() -> Result<Self, CryptoError> {
+        let dir = dirs::data_local_dir()
+            .ok_or_else(|| CryptoError::Keychain("no local data dir".into()))?
+            .join("vortex-image")
+            .join("tokens");
+        Ok(Self::new(dir))
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn backend(&self) -> KeystoreBackend {
+        KeystoreBackend::EncryptedFile
+    }
+
+    fn store(&self, name: &str, value: &str) -> Result<(), CryptoError> {
+        let encrypted = encrypt_token(value)?;
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| CryptoError::Keychain(format!("failed to create dir: {}", e)))?;
+        let tmp = self.dir.join(format!("{}.tmp", name));
+        std::fs::write(&tmp, &encrypted)
+            .and_then(|_| std::fs::rename(&tmp, self.dir.join(name)))
+            .map_err(|e| CryptoError::Keychain(format!("failed to write: {}", e)))
+    }
+
+    /// Migrates legacy token formats (v2, v3) to v4 on read
+    fn retrieve(&self, name: &str) -> Result<Option<Zeroizing<String>>, CryptoError> {
+        let path = self.dir.join(name);
+        let encrypted = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(CryptoError::Keychain(format!("failed to read: {}", e))),
+        };
+        let (plaintext, upgraded) = decrypt_token(&encrypted)?;
+
+        if let Some(new_token) = upgraded {
+            if let Err(e) = std::fs::write(&path, &new_token) {
+                tracing::warn!("Failed to save upgraded token: {}", e);
+            } else {
+                tracing::info!("Token '{}' migrated to v4 format", name);
+            }
+        }
+        Ok(Some(Zeroizing::new(plaintext)))
+    }
+
+    fn delete(&self, name: &str) -> Result<bool, CryptoError> {
+        match std::fs::remove_file(self.dir.join(name)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(CryptoError::Keychain(format!("failed to delete file: {}", e))),
+        }
+    }
+}
+
+// ============================================================================
+// Chain
+// ============================================================================
+
+/// Secret stores in order of preference
+pub struct Keystore {
+    stores: Vec<Box<dyn SecretStore>>,
+    fallback_reason: Option<String>,
+    /// Profile whose secrets this keystore reads and writes
+    profile: Option<String>,
+}
+
+impl Keystore {
+    pub fn new(stores: Vec<Box<dyn SecretStore>>) -> Self {
+        Self {
+            stores,
+            fallback_reason: None,
+            profile: None,
+        }
+    }
+
+    /// Scope secret names to profile `id`
+    pub fn for_profile(self, id: &str) -> Self {
+        Self {
+            profile: Some(id.to_string()),
+            ..self
+        }
+    }
+
+    fn scoped(&self, name: &str) -> Result<String, CryptoError> {
+        validate_name(name)?;
+        let scoped = match &self.profile {
+            Some(id) => crate::profiles::secret_name(id, name),
+            None => name.to_string(),
+        };
+        validate_name(&scoped)?;
+        Ok(scoped)
+    }
+
+    /// The OS keystore if it works, then the encrypted file store, scoped to
+    /// the active profile
+    pub fn detect() -> Result<Self, CryptoError> {
+        let profile = crate::profiles::active_id().map_err(|e| CryptoError::Keychain(e.to_string()))?;
+        let mut stores: Vec<Box<dyn SecretStore>> = Vec::new();
+        let mut fallback_reason = None;
+        if keychain_available() {
+            stores.push(Box::new(OsKeychain));
+        } else {
+            fallback_reason = Some("OS keystore is unavailable or locked".to_string());
+        }
+        stores.push(Box::new(EncryptedFileStore::default_location()?));
+        Ok(Self {
+            fallback_reason,
+            ..Self::new(stores).for_profile(&profile)
+        })
+    }
+
+    pub fn backend(&self) -> KeystoreBackend {
+        self.stores
+            .first()
+            .map(|s| s.backend())
+            .unwrap_or(KeystoreBackend::EncryptedFile)
+    }
+
+    /// Write to the first store that accepts the secret
+    pub fn store(&self, name: &str, value: &str) -> Result<KeystoreBackend, CryptoError> {
+        let name = &self.scoped(name)?;
+        let mut last_error = CryptoError::Keychain("no secret store configured".into());
+        for store in &self.stores {
+            match store.store(name, value) {
+                Ok(()) => {
+                    tracing::info!("Secret '{}' stored in {:?}", name, store.backend());
+                    return Ok(store.backend());
+                }
+                Err(e) => {
+                    tracing::warn!("{:?} could not store '{}', trying next store: {}", store.backend(), name, e);
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Read from the first store that has the secret
+    pub fn retrieve(&self, name: &str) -> Result<Option<Zeroizing<String>>, CryptoError> {
+        let name = &self.scoped(name)?;
+        for store in &self.stores {
+            match store.retrieve(name) {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) => {}
+                Err(e) => tracing::debug!("{:?} could not read '{}': {}", store.backend(), name, e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Delete from every store, so no stale copy is left behind
+    pub fn delete(&self, name: &str) -> Result<bool, CryptoError> {
+        let name = &self.scoped(name)?;
+        let mut deleted = false;
+        for store in &self.stores {
+            deleted |= store.delete(name)?;
+        }
+        Ok(deleted)
+    }
+
+    pub fn info(&self) -> KeystoreInfo {
+        KeystoreInfo {
+            backend: self.backend(),
+            chain: self.stores.iter().map(|s| s.backend()).collect(),
+            hardware: detect_hardware(),
+            fallback_reason: self.fallback_reason.clone(),
+        }
+    }
+}
+
+/// Look for a Secure Enclave or TPM, once per process
+pub fn detect_hardware() -> Option<HardwareModule> {
+    static HARDWARE: OnceLock<Option<HardwareModule>> = OnceLock::new();
+    *HARDWARE.get_or_init(|| {
+        #[cfg(target_os = "macos")]
+        {
+            let sep = std::process::Command::new("ioreg")
+                .args(["-c", "AppleSEPManager"])
+                .output()
+                .map(|o| String::from_utf8_lossy(&o.stdout).contains("AppleSEPManager"))
+                .unwrap_or(false);
+            if sep {
+                return Some(HardwareModule::SecureEnclave);
+            }
+        }
+
+        #[cfg(target_os = "ios")]
+        {
+            return Some(HardwareModule::SecureEnclave);
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            // ACPI device id of TPM 2.0 modules
+            let tpm = std::process::Command::new("reg")
+                .args(["query", "HKLM\\SYSTEM\\CurrentControlSet\\Enum\\ACPI\\MSFT0101"])
+                .output()
+                .map(|o| o.status.success())
+                .unwrap_or(false);
+            if tpm {
+                return Some(HardwareModule::Tpm);
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if std::path::Path::new("/sys/class/tpm/tpm0").exists() {
+                return Some(HardwareModule::Tpm);
+            }
+        }
+
+        #[allow(unreachable_code)]
+        None
+    })
+}
+
+// ============================================================================
+// Keypair Persistence
+// ============================================================================
+
+fn keypair_file() -> Result<PathBuf, CryptoError> {
+    let dir = crate::profiles::data_dir().map_err(|e| CryptoError::Keychain(e.to_string()))?;
+    Ok(dir.join(KEYPAIR_FILE))
+}
+
+/// Seal a keypair with a fresh key encryption key, which goes into `keystore`
+pub fn seal_keypair(keystore: &Keystore, keypair: &HybridKeypair) -> Result<Vec<u8>, CryptoError> {
+    let kek = Zeroizing::new(rand::random::<[u8; 32]>());
+    let sealed = encrypt_with_key(&keypair.to_bytes(), &kek, KEYPAIR_AAD)?;
+    keystore.store(KEYPAIR_KEK_ENTRY, &Zeroizing::new(hex::encode(kek.as_slice())))?;
+    Ok(sealed)
+}
+
+/// Open a keypair sealed by `seal_keypair`
+pub fn open_keypair(keystore: &Keystore, sealed: &[u8]) -> Result<Option<HybridKeypair>, CryptoError> {
+    let Some(encoded) = keystore.retrieve(KEYPAIR_KEK_ENTRY)? else {
+        return Ok(None);
+    };
+    let kek: Zeroizing<[u8; 32]> = Zeroizing::new(
+        hex::decode(encoded.as_str())
+            .ok()
+            .and_then(|k| k.try_into().ok())
+            .ok_or_else(|| CryptoError::Keychain("corrupt key encryption key".into()))?,
+    );
+    let bytes = Zeroizing::new(decrypt_with_key(sealed, &kek, KEYPAIR_AAD)?);
+    HybridKeypair::from_bytes(&bytes).map(Some)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Which keystore secrets go to on this machine, and what protects it
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_keystore_backend() -> Result<KeystoreInfo, CryptoError> {
+    Ok(Keystore::detect()?.info())
+}
+
+/// Persist the keypair behind `handle` so it survives restarts
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn store_keypair_in_keystore(handle: KeypairHandle) -> Result<KeystoreBackend, CryptoError> {
+    let keystore = Keystore::detect()?;
+    let sealed = with_keypair(handle, |kp| seal_keypair(&keystore, kp))?;
+    let path = keypair_file()?;
+    let tmp = path.with_extension("bin.tmp");
+    std::fs::write(&tmp, sealed)
+        .and_then(|_| std::fs::rename(&tmp, &path))
+        .map_err(|e| CryptoError::Keychain(format!("failed to write: {}", e)))?;
+    let key_id = with_keypair(handle, |kp| Ok(kp.public_bundle().key_id))?;
+    record_activity(ActivityKind::Key, "store_keypair_in_keystore", None, &key_id, None);
+    Ok(keystore.backend())
+}
+
+/// Load the persisted keypair into the store. `None` if there is none.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn load_keypair_from_keystore() -> Result<Option<KeypairInfo>, CryptoError> {
+    let path = keypair_file()?;
+    let sealed = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(CryptoError::Keychain(format!("failed to read: {}", e))),
+    };
+    match open_keypair(&Keystore::detect()?, &sealed)? {
+        Some(keypair) => register_keypair(keypair).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Remove the persisted keypair and its key encryption key
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn delete_keypair_from_keystore() -> Result<(), CryptoError> {
+    Keystore::detect()?.delete(KEYPAIR_KEK_ENTRY)?;
+    match std::fs::remove_file(keypair_file()?) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(CryptoError::Keychain(format!("failed to delete file: {}", e))),
+    }
+    record_activity(ActivityKind::Key, "delete_keypair_from_keystore", None, "keystore", None);
+    Ok(())
+}
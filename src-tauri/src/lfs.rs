@@ -0,0 +1,412 @@
+//! Git LFS Storage for Large Originals
+//!
+//! RAW files and videos routinely exceed what the contents API accepts. Once a
+//! repository opts in with `enable_lfs`, its `.gitattributes` routes those
+//! extensions through LFS: the content goes to the LFS object store via the
+//! batch API and the repository only holds a small pointer file. Downloads
+//! recognise pointer files and fetch the real object transparently, so
+//! `download_photo` and every other reader see the original bytes.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::time::Duration;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::chunks::{fetch_chunked, ChunkIndex};
+use crate::github::{put_file_contents, response_error, sanitize_filename, validate_repo, AppError, Bearer, HttpClient, UploadResult, LFS_THRESHOLD_BYTES};
+use crate::offline_queue::remote_sha;
+use crate::retry::SendWithRetry;
+
+pub const LFS_SPEC_URL: &str = "https://git-lfs.github.com/spec/v1";
+pub const GITATTRIBUTES_FILE: &str = ".gitattributes";
+/// Pointer files are tiny; anything larger is never parsed as one
+const MAX_POINTER_SIZE: usize = 1024;
+const LFS_BATCH_TIMEOUT_SECS: u64 = 60;
+const LFS_TRANSFER_TIMEOUT_SECS: u64 = 600;
+
+/// Camera RAW formats and videos stored through LFS once enabled
+pub const LFS_EXTENSIONS: &[&str] = &[
+    // RAW
+    "cr2", "cr3", "nef", "nrw", "arw", "srf", "sr2", "dng", "raf", "orf", "rw2", "pef", "srw", "x3f", "3fr", "iiq",
+    // Video
+    "mp4", "mov", "m4v", "avi", "mkv", "webm", "3gp", "mts", "m2ts",
+];
+
+/// Contents of a Git LFS pointer file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LfsPointer {
+    /// SHA-256 hex of the object
+    pub oid: String,
+    pub size: u64,
+}
+
+impl LfsPointer {
+    pub fn for_content(content: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(content);
+        Self {
+            oid: format!("{:x}", hasher.finalize()),
+            size: content.len() as u64,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!("version {}\noid sha256:{}\nsize {}\n", LFS_SPEC_URL, self.oid, self.size).into_bytes()
+    }
+
+    /// Parse a pointer file; returns `None` for regular content
+    pub fn parse(content: &[u8]) -> Option<Self> {
+        if content.len() > MAX_POINTER_SIZE {
+            return None;
+        }
+        let text = std::str::from_utf8(content).ok()?;
+        let mut lines = text.lines();
+
+        if lines.next()?.strip_prefix("version ")? != LFS_SPEC_URL {
+            return None;
+        }
+
+        let (mut oid, mut size) = (None, None);
+        for line in lines {
+            if let Some(hex) = line.strip_prefix("oid sha256:") {
+                oid = Some(hex.to_string());
+            } else if let Some(n) = line.strip_prefix("size ") {
+                size = n.parse().ok();
+            }
+        }
+
+        let oid = oid.filter(|o| o.len() == 64 && o.chars().all(|c| c.is_ascii_hexdigit()))?;
+        Some(Self { oid, size: size? })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LfsStatus {
+    pub enabled: bool,
+    /// Extensions routed through LFS by the repository's `.gitattributes`
+    pub tracked_extensions: Vec<String>,
+}
+
+// ============================================================================
+// .gitattributes
+// ============================================================================
+
+fn lfs_rule(ext: &str) -> String {
+    format!("*.{} filter=lfs diff=lfs merge=lfs -text", ext)
+}
+
+/// Extensions tracked by `*.ext filter=lfs` rules
+pub fn tracked_extensions(gitattributes: &str) -> Vec<String> {
+    gitattributes
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next()?;
+            let ext = pattern.strip_prefix("*.")?;
+            parts
+                .any(|attr| attr == "filter=lfs")
+                .then(|| ext.to_lowercase())
+        })
+        .collect()
+}
+
+/// Whether `filename` is routed through LFS by `gitattributes`
+pub fn is_tracked(gitattributes: &str, filename: &str) -> bool {
+    let Some(ext) = Path::new(filename).extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    let ext = ext.to_lowercase();
+    tracked_extensions(gitattributes).contains(&ext)
+}
+
+/// Add LFS rules for every large-original extension, keeping existing lines
+pub fn with_lfs_rules(gitattributes: &str) -> String {
+    let tracked = tracked_extensions(gitattributes);
+    let mut out = gitattributes.trim_end().to_string();
+    for ext in LFS_EXTENSIONS.iter().filter(|e| !tracked.iter().any(|t| t == *e)) {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&lfs_rule(ext));
+    }
+    out.push('\n');
+    out
+}
+
+/// Current `.gitattributes` and its blob SHA, if the file exists
+async fn fetch_gitattributes(client: &Client, repo: &str, token: &str) -> Result<Option<(String, String)>, AppError> {
+    let url = format!("https://api.github.com/repos/{}/contents/{}", repo, GITATTRIBUTES_FILE);
+    let res = client
+        .get(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+
+    if res.status() == 404 {
+        return Ok(None);
+    }
+    if !res.status().is_success() {
+        return Err(response_error(res, &format!("Failed to get {}", GITATTRIBUTES_FILE)).await);
+    }
+
+    let json: serde_json::Value = res.json().await?;
+    let encoded: String = json["content"].as_str().unwrap_or("").chars().filter(|c| !c.is_whitespace()).collect();
+    let raw = STANDARD
+        .decode(encoded)
+        .map_err(|_| AppError::Api(format!("Invalid {} encoding", GITATTRIBUTES_FILE)))?;
+    let sha = json["sha"].as_str().unwrap_or("").to_string();
+
+    Ok(Some((String::from_utf8_lossy(&raw).to_string(), sha)))
+}
+
+/// Whether uploads of `filename` to `repo` should go through LFS
+pub(crate) async fn lfs_tracks(client: &Client, repo: &str, token: &str, filename: &str) -> Result<bool, AppError> {
+    Ok(fetch_gitattributes(client, repo, token)
+        .await?
+        .map(|(attrs, _)| is_tracked(&attrs, filename))
+        .unwrap_or(false))
+}
+
+// ============================================================================
+// Batch API
+// ============================================================================
+
+/// Ask the LFS server for transfer actions for one object
+async fn batch_object(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    operation: &str,
+    pointer: &LfsPointer,
+) -> Result<serde_json::Value, AppError> {
+    let url = format!("https://github.com/{}.git/info/lfs/objects/batch", repo);
+    let body = serde_json::json!({
+        "operation": operation,
+        "transfers": ["basic"],
+        "objects": [{ "oid": pointer.oid, "size": pointer.size }]
+    });
+
+    let res = client
+        .post(&url)
+        .timeout(Duration::from_secs(LFS_BATCH_TIMEOUT_SECS))
+        .header("Authorization", Bearer(token))
+        .header("Accept", "application/vnd.git-lfs+json")
+        .header("Content-Type", "application/vnd.git-lfs+json")
+        .json(&body)
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "LFS batch failed").await);
+    }
+
+    let json: serde_json::Value = res.json().await?;
+    let object = json["objects"][0].clone();
+    if let Some(message) = object["error"]["message"].as_str() {
+        return Err(AppError::Api(format!("LFS {} failed: {}", operation, message)));
+    }
+    Ok(object)
+}
+
+/// Apply the `header` map of a batch action to a request
+fn with_action_headers(mut req: reqwest::RequestBuilder, action: &serde_json::Value) -> reqwest::RequestBuilder {
+    if let Some(headers) = action["header"].as_object() {
+        for (name, value) in headers {
+            if let Some(value) = value.as_str() {
+                req = req.header(name.as_str(), value);
+            }
+        }
+    }
+    req
+}
+
+/// Store `content` in the LFS object store and return its pointer.
+/// Objects the server already has are not uploaded again.
+pub(crate) async fn upload_lfs_object(client: &Client, repo: &str, token: &str, content: Vec<u8>) -> Result<LfsPointer, AppError> {
+    let pointer = LfsPointer::for_content(&content);
+    let object = batch_object(client, repo, token, "upload", &pointer).await?;
+
+    let upload = &object["actions"]["upload"];
+    let Some(href) = upload["href"].as_str() else {
+        return Ok(pointer);
+    };
+
+    let res = with_action_headers(client.put(href), upload)
+        .timeout(Duration::from_secs(LFS_TRANSFER_TIMEOUT_SECS))
+        .header("Content-Type", "application/octet-stream")
+        .body(content)
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "LFS upload failed").await);
+    }
+
+    let verify = &object["actions"]["verify"];
+    if let Some(href) = verify["href"].as_str() {
+        let res = with_action_headers(client.post(href), verify)
+            .header("Accept", "application/vnd.git-lfs+json")
+            .header("Content-Type", "application/vnd.git-lfs+json")
+            .json(&serde_json::json!({ "oid": pointer.oid, "size": pointer.size }))
+            .send_with_retry()
+            .await?;
+
+        if !res.status().is_success() {
+            return Err(response_error(res, "LFS verify failed").await);
+        }
+    }
+
+    Ok(pointer)
+}
+
+/// Download an LFS object and check it against its pointer
+pub(crate) async fn download_lfs_object(client: &Client, repo: &str, token: &str, pointer: &LfsPointer) -> Result<Vec<u8>, AppError> {
+    let object = batch_object(client, repo, token, "download", pointer).await?;
+
+    let download = &object["actions"]["download"];
+    let href = download["href"]
+        .as_str()
+        .ok_or_else(|| AppError::Api("No LFS download URL returned".into()))?;
+
+    let res = with_action_headers(client.get(href), download)
+        .timeout(Duration::from_secs(LFS_TRANSFER_TIMEOUT_SECS))
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "LFS download failed").await);
+    }
+
+    let content = res.bytes().await?.to_vec();
+    if LfsPointer::for_content(&content) != *pointer {
+        return Err(AppError::Api("LFS object does not match its pointer".into()));
+    }
+
+    Ok(content)
+}
+
+/// Replace pointer file content with the object it points to. Chunk
+/// indexes (see `chunks`) are reassembled the same way.
+pub(crate) async fn resolve_lfs_pointer(client: &Client, repo: &str, token: &str, content: Vec<u8>) -> Result<Vec<u8>, AppError> {
+    if let Some(index) = ChunkIndex::parse(&content) {
+        return fetch_chunked(client, repo, token, &index).await;
+    }
+    match LfsPointer::parse(&content) {
+        Some(pointer) => download_lfs_object(client, repo, token, &pointer).await,
+        None => Ok(content),
+    }
+}
+
+/// Upload `content` to LFS and commit a pointer file at `upload_path`
+pub(crate) async fn put_lfs_file(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    upload_path: &str,
+    content: Vec<u8>,
+    message: &str,
+) -> Result<UploadResult, AppError> {
+    let pointer = upload_lfs_object(client, repo, token, content).await?;
+    let sha = remote_sha(client, repo, token, upload_path).await?;
+    put_file_contents(client, repo, token, upload_path, &pointer.to_bytes(), message, sha.as_deref()).await
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Opt a repository into LFS storage for RAW files and videos
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn enable_lfs(client: State<'_, HttpClient>, repo: String, token: Zeroizing<String>) -> Result<LfsStatus, AppError> {
+    validate_repo(&repo)?;
+
+    let (current, sha) = match fetch_gitattributes(&client.0, &repo, &token).await? {
+        Some((attrs, sha)) => (attrs, Some(sha)),
+        None => (String::new(), None),
+    };
+
+    let updated = with_lfs_rules(&current);
+    if updated != current {
+        put_file_contents(
+            &client.0,
+            &repo,
+            &token,
+            GITATTRIBUTES_FILE,
+            updated.as_bytes(),
+            "Track RAW files and videos with Git LFS",
+            sha.as_deref(),
+        )
+        .await?;
+    }
+
+    Ok(LfsStatus {
+        enabled: true,
+        tracked_extensions: tracked_extensions(&updated),
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn get_lfs_status(client: State<'_, HttpClient>, repo: String, token: Zeroizing<String>) -> Result<LfsStatus, AppError> {
+    validate_repo(&repo)?;
+
+    let tracked = fetch_gitattributes(&client.0, &repo, &token)
+        .await?
+        .map(|(attrs, _)| tracked_extensions(&attrs))
+        .unwrap_or_default();
+
+    Ok(LfsStatus {
+        enabled: !tracked.is_empty(),
+        tracked_extensions: tracked,
+    })
+}
+
+/// Upload an original file unmodified into `album_path`, through LFS when
+/// the repository tracks its extension
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn upload_original(
+    client: State<'_, HttpClient>,
+    path: String,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+) -> Result<UploadResult, AppError> {
+    validate_repo(&repo)?;
+
+    let album = album_path.trim_matches('/');
+    if album.is_empty() || album.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    let filename = Path::new(&path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(sanitize_filename)
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| AppError::Validation("Invalid filename".into()))?;
+    let upload_path = format!("{}/{}", album, filename);
+    let message = format!("Upload {}", upload_path);
+
+    let content = tokio::fs::read(&path).await?;
+
+    if lfs_tracks(&client.0, &repo, &token, &filename).await? {
+        return put_lfs_file(&client.0, &repo, &token, &upload_path, content, &message).await;
+    }
+
+    if content.len() as u64 > LFS_THRESHOLD_BYTES {
+        return Err(AppError::Validation(
+            "File is too large for the contents API; enable Git LFS for this repository first".into(),
+        ));
+    }
+
+    let sha = remote_sha(&client.0, &repo, &token, &upload_path).await?;
+    put_file_contents(&client.0, &repo, &token, &upload_path, &content, &message, sha.as_deref()).await
+}
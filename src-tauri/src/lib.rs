@@ -1,16 +1,131 @@
 //! Rust Module - 1 functions, 0 structs
 //! Core functionality: Backend operations and data processing
 //! External crates: 4 dependencies
+//!
+//! The `gui` feature (on by default) builds the app; without it only the
+//! headless `vortex-cli` builds, with no webview to link:
+//! `cargo build --no-default-features --features headless --bin vortex-cli`.
+
+// Commands stay compiled for the CLI but only the app registers them
+#![cfg_attr(not(feature = "gui"), allow(dead_code, unused_imports))]
+
+#[cfg(not(any(feature = "gui", feature = "headless")))]
+compile_error!("Enable the `gui` feature for the app or `headless` for vortex-cli");
 
 mod github;
+mod util;
+mod archive;
 mod compress;
+mod compress_bench;
+mod compress_jobs;
+mod compress_stream;
 mod crypto;
+mod entropy;
+mod image_optimize;
+mod jpeg_optimize;
+mod transcode;
+mod heif;
+mod resize;
+mod watermark;
+mod classify;
 mod pipeline;
+mod pipeline_batch;
+mod pipeline_checkpoint;
+mod pipeline_condition;
+mod pipeline_dry_run;
+mod pipeline_history;
+mod pipeline_presets;
+mod pipeline_routing;
+mod pipeline_script;
+mod pipeline_steps;
+mod sharing;
+mod album;
+mod git_data;
+mod batch;
+mod stats;
+mod similarity;
+mod sharding;
+mod listing;
+mod offline_queue;
+mod watcher;
+mod sync;
+mod lfs;
+mod mirror;
+mod retry;
+mod organize;
+mod history;
+mod remote_watch;
+mod upload_policy;
+mod security_verify;
+mod threads;
+mod contacts;
+mod album_access;
+mod key_rotation;
+mod recovery;
+mod shamir;
+mod file_crypto;
+mod keystore;
+mod password;
+mod profiles;
+mod fingerprint;
+mod metadata_vault;
+mod image_metadata;
+mod session;
+mod timelock;
+mod local_vault;
+mod hygiene;
+mod local_store;
+mod activity_log;
+mod migrations;
+mod catalog;
+mod tagging;
+mod search;
+mod smart_albums;
+mod timeline;
+mod download_cache;
+mod prefetch;
+mod repo_import;
+mod takeout;
+mod library_export;
+mod ipfs;
+mod storage_backend;
+mod webdav;
+mod gallery_server;
+pub mod cli;
+mod p2p_transfer;
+mod comments;
+mod diagnostics;
+mod health;
+mod jobs;
+mod scheduler;
+mod ipc_buffers;
+mod file_hash;
+mod upload_dedup;
+mod secure_temp;
+mod retention;
+mod sync_schedule;
+mod config_store;
+mod device_sync;
+mod revocation;
+mod qr_escrow;
+mod thumbnails;
+mod raw;
+mod chunks;
+mod video;
 
 // Test modules - organized by functionality
 #[cfg(test)]
 mod tests;
 
+/// Runtime the app's handles are typed with. The headless CLI never builds
+/// an app, so it names tauri's mock runtime rather than the webview.
+#[cfg(feature = "gui")]
+pub(crate) type Runtime = tauri::Wry;
+#[cfg(not(feature = "gui"))]
+pub(crate) type Runtime = tauri::test::MockRuntime;
+
+pub(crate) type AppHandle = tauri::AppHandle<Runtime>;
+
 use tauri::Manager;
 use github::{
     get_user, list_photos, poll_oauth, start_oauth, upload_photo, validate_token,
@@ -23,25 +138,142 @@ use github::{
 
 use compress::{
     compress_data, compress_data_strict, decompress_data, estimate_compression, list_compression_algorithms,
-    compress_data_auto, compress_file, decompress_file, get_compression_recommendation
+    compress_data_auto, compress_file, decompress_file, get_compression_recommendation, get_compression_parallelism,
+    set_compression_parallelism, get_decompression_limits, set_decompression_limits,
 };
+use entropy::analyze_content;
+use archive::{create_archive, list_archive, extract_archive};
+use compress_bench::{benchmark_compression, get_compression_benchmark};
+use compress_stream::{compress_file_stream, decompress_file_stream, cancel_compression};
+use compress_jobs::{compress_folder_start, compress_job_cancel, compress_job_list};
+use image_optimize::optimize_image;
+use transcode::transcode_image;
+use heif::convert_heif_image;
+use resize::resize_image;
 
 use crypto::{
-    generate_keypair, release_keypair, rotate_keypair, validate_keypair_handle,
+    generate_keypair, release_keypair, validate_keypair_handle,
     encrypt_data_password, decrypt_data_password,
     hash_data_blake3, get_crypto_info,
-    encrypt_hybrid, decrypt_hybrid, sign_data, verify_signature,
+    encrypt_hybrid, decrypt_hybrid, sign_data, verify_signature, sign_batch, verify_batch,
     secure_store_token, secure_retrieve_token, secure_delete_token,
-    encrypt_file, decrypt_file,
 };
 
 use pipeline::{
-    pipeline_process, pipeline_reverse, pipeline_get_presets,
-    pipeline_validate, pipeline_estimate
+    pipeline_process, pipeline_reverse, pipeline_inspect, pipeline_get_presets,
+    pipeline_validate, pipeline_estimate, pipeline_folder_start
+};
+use pipeline_batch::{pipeline_process_folder, pipeline_resume_job};
+use pipeline_checkpoint::{pipeline_resumable_jobs, pipeline_discard_job};
+use pipeline_dry_run::pipeline_dry_run;
+use pipeline_history::{pipeline_get_history, pipeline_get_stats, pipeline_clear_history};
+use pipeline_presets::{
+    pipeline_save_preset, pipeline_delete_preset, pipeline_export_preset, pipeline_import_preset,
+};
+use pipeline_routing::{pipeline_get_routes, pipeline_set_route};
+use pipeline_steps::pipeline_describe_steps;
+
+use sharing::{
+    create_share_link, open_share_link, download_shared_photo, create_photo_link, open_photo_link,
+    download_photo_link,
+};
+
+use album::{
+    create_album, upload_encrypted_photo, set_album_cover, set_album_description, set_album_metadata,
+    create_subalbum, move_photo_to_subalbum, list_subalbums,
 };
 
+use batch::{delete_photos_batch, move_photos};
+
+use stats::{get_album_stats, get_storage_usage};
+use similarity::find_similar_photos;
+
+use sharding::{get_shard_map, set_shard_threshold, rebalance_shards};
+
+use listing::list_photos_page;
+
+use offline_queue::{
+    queue_upload, queue_delete, get_pending_operations, replay_pending_operations,
+    discard_pending_operation
+};
+
+use watcher::{watch_folder, stop_watch, list_watches};
+
+use sync::{sync_album, get_sync_policy, set_sync_policy};
+
+use lfs::{enable_lfs, get_lfs_status, upload_original};
+
+use mirror::{configure_mirror, remove_mirror, list_mirrors, verify_mirror};
+
+use organize::{rename_photo, move_photo_between_albums};
+use history::{get_album_history, restore_album_to_commit};
+use remote_watch::{start_remote_watch, stop_remote_watch, list_remote_watches};
+use upload_policy::{get_upload_policy, set_upload_policy, validate_upload};
+use security_verify::{security_audit_albums, verify_album_integrity, verify_activity_log};
+use activity_log::get_activity_log;
+use threads::{list_secure_threads, append_secure_message, fetch_thread_messages};
+use contacts::{add_contact, list_contacts, verify_contact_fingerprint, remove_contact};
+use album_access::{share_album_with_contact, list_album_access, revoke_album_access};
+use key_rotation::{rotate_keypair, list_archived_keys, restore_archived_key};
+use recovery::{export_keypair_mnemonic, import_keypair_mnemonic};
+use shamir::{split_key_shares, recover_key_from_shares};
+use qr_escrow::{export_keypair_qr, import_keypair_qr};
+use file_crypto::{encrypt_file, decrypt_file};
+use keystore::{get_keystore_backend, store_keypair_in_keystore, load_keypair_from_keystore, delete_keypair_from_keystore};
+use password::{check_password_strength, calibrate_kdf, get_kdf_params};
+use profiles::{create_profile, switch_profile, list_profiles};
+use fingerprint::{get_key_fingerprint, compare_fingerprint};
+use metadata_vault::{get_photo_metadata, set_photo_caption};
+use image_metadata::get_image_metadata;
+use raw::extract_raw_preview;
+use video::{get_video_info, upload_video};
+use session::{start_session, accept_session, session_encrypt, session_decrypt, list_sessions, close_session};
+use timelock::{encrypt_timelock, decrypt_timelock, inspect_timelock};
+use local_vault::{
+    create_local_vault, add_hidden_volume, unlock_local_vault, lock_local_vault, list_local_vault,
+    local_vault_add_photo, local_vault_read_photo, local_vault_remove_photo,
+};
+use hygiene::crypto_hygiene_report;
+use local_store::{get_local_setting, set_local_setting, delete_local_setting, get_cached_albums, clear_local_cache};
+use migrations::get_db_schema_version;
+use catalog::{catalog_photos_with_tag, catalog_album, query_photos};
+use tagging::{tag_photo, set_favorite, set_rating, sync_photo_organization};
+use search::search_photos;
+use smart_albums::{create_smart_album, list_smart_albums, smart_album_photos, delete_smart_album};
+use timeline::get_timeline;
+use download_cache::{
+    pin_photo_offline, unpin_photo_offline, download_cache_stats, set_download_cache_limit, clear_download_cache,
+};
+use prefetch::{prefetch_photos, cancel_prefetch};
+use repo_import::import_repo_as_album;
+use takeout::import_google_takeout;
+use library_export::export_library;
+use ipfs::{
+    configure_ipfs, get_ipfs_config, remove_ipfs_config, pin_album_to_ipfs, unpin_album_from_ipfs, list_ipfs_pins,
+};
+use webdav::{configure_webdav, get_webdav_config, remove_webdav_config, backup_album_to_webdav};
+use gallery_server::{serve_gallery, stop_gallery, gallery_status};
+use p2p_transfer::{offer_p2p_transfer, list_p2p_offers, cancel_p2p_offer, receive_p2p_transfer};
+use comments::{add_comment, list_comments};
+use diagnostics::export_diagnostics_bundle;
+use health::run_health_check;
+use jobs::{cancel_job, list_jobs};
+use scheduler::get_job_queue_status;
+use file_hash::hash_file_blake3;
+use upload_dedup::upload_folder_to_album;
+use retention::{set_retention_policy, remove_retention_policy, list_retention_policies, preview_retention, run_retention};
+use sync_schedule::{set_sync_schedule, remove_sync_schedule, get_sync_schedules, run_sync_schedule_now};
+use device_sync::get_device_id;
+use secure_temp::{open_photo_for_viewing, list_plaintext_files, release_plaintext, purge_decrypted_cache};
+use ipc_buffers::{data_op_file, data_op_buffer, buffer_create, buffer_write, buffer_read, buffer_path, buffer_release};
+use revocation::{revoke_device_key, check_revocation};
+use thumbnails::{generate_thumbnail, pregenerate_thumbnails, clear_thumbnail_cache};
+use retry::{get_retry_policy, set_retry_policy, get_backend_status, reset_circuit_breakers};
+
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    diagnostics::init_tracing();
     tauri::Builder::default()
         .manage(HttpClient::new())
         .setup(|_app| {
@@ -49,6 +281,18 @@ pub fn run() {
             let client_id = std::env::var("GITHUB_CLIENT_ID")
                 .unwrap_or_else(|_| "Ov23lijNSMM1i93CQdfQ".to_string());
             _app.manage(GithubConfig { client_id });
+            offline_queue::start_replay_worker(_app.handle().clone());
+            retention::start_retention_worker(_app.handle().clone());
+            sync_schedule::start_schedule_worker(_app.handle().clone());
+            // Buffers do not outlive a session
+            let _ = ipc_buffers::clear_buffers();
+            // Neither does plaintext a crash left behind
+            let _ = secure_temp::purge_plaintext();
+            password::load_saved_kdf_params();
+            // Poster frames decode with the ffmpeg sidecar shipped in the bundle
+            if let Ok(ffmpeg) = tauri_plugin_shell::ShellExt::shell(_app.handle()).sidecar("ffmpeg") {
+                video::set_ffmpeg_sidecar(std::process::Command::from(ffmpeg).get_program().into());
+            }
             Ok(())
         })
         .plugin(tauri_plugin_shell::init())
@@ -66,6 +310,7 @@ pub fn run() {
             
             upload_photo,
             list_photos,
+            list_photos_page,
             
             create_repo,
             get_repo_info,
@@ -74,6 +319,7 @@ pub fn run() {
             scan_folder,
             upload_folder_as_album,
             upload_folder_recursive,
+            upload_folder_to_album,
             list_albums,
             delete_album,
             rename_album,
@@ -86,6 +332,10 @@ pub fn run() {
             delete_photo,
             remove_local_file,
             get_local_image_info,
+            get_image_metadata,
+            extract_raw_preview,
+            get_video_info,
+            upload_video,
             
             compress_data,
             compress_data_strict,
@@ -96,10 +346,38 @@ pub fn run() {
             compress_file,
             decompress_file,
             get_compression_recommendation,
+            analyze_content,
+            create_archive,
+            list_archive,
+            extract_archive,
+            get_compression_parallelism,
+            set_compression_parallelism,
+            get_decompression_limits,
+            set_decompression_limits,
+            benchmark_compression,
+            get_compression_benchmark,
+            compress_file_stream,
+            decompress_file_stream,
+            cancel_compression,
+            compress_folder_start,
+            compress_job_cancel,
+            compress_job_list,
+            optimize_image,
+            transcode_image,
+            convert_heif_image,
+            resize_image,
             
             generate_keypair,
             release_keypair,
             rotate_keypair,
+            list_archived_keys,
+            restore_archived_key,
+            export_keypair_mnemonic,
+            import_keypair_mnemonic,
+            split_key_shares,
+            recover_key_from_shares,
+            export_keypair_qr,
+            import_keypair_qr,
             validate_keypair_handle,
             encrypt_data_password,
             decrypt_data_password,
@@ -111,10 +389,44 @@ pub fn run() {
             
             sign_data,
             verify_signature,
+            sign_batch,
+            verify_batch,
             
             secure_store_token,
             secure_retrieve_token,
             secure_delete_token,
+            get_keystore_backend,
+            store_keypair_in_keystore,
+            load_keypair_from_keystore,
+            delete_keypair_from_keystore,
+            check_password_strength,
+            calibrate_kdf,
+            get_kdf_params,
+            create_profile,
+            switch_profile,
+            list_profiles,
+            get_key_fingerprint,
+            compare_fingerprint,
+            get_photo_metadata,
+            set_photo_caption,
+            start_session,
+            accept_session,
+            session_encrypt,
+            session_decrypt,
+            list_sessions,
+            close_session,
+            encrypt_timelock,
+            decrypt_timelock,
+            inspect_timelock,
+            create_local_vault,
+            add_hidden_volume,
+            unlock_local_vault,
+            lock_local_vault,
+            list_local_vault,
+            local_vault_add_photo,
+            local_vault_read_photo,
+            local_vault_remove_photo,
+            crypto_hygiene_report,
             
             encrypt_file,
             decrypt_file,
@@ -126,10 +438,266 @@ pub fn run() {
             
             pipeline_process,
             pipeline_reverse,
+            pipeline_inspect,
             pipeline_get_presets,
             pipeline_validate,
-            pipeline_estimate
+            pipeline_estimate,
+            pipeline_dry_run,
+            pipeline_get_history,
+            pipeline_get_stats,
+            pipeline_clear_history,
+            pipeline_get_routes,
+            pipeline_set_route,
+            pipeline_folder_start,
+            pipeline_process_folder,
+            pipeline_resume_job,
+            pipeline_resumable_jobs,
+            pipeline_discard_job,
+            pipeline_save_preset,
+            pipeline_delete_preset,
+            pipeline_export_preset,
+            pipeline_import_preset,
+            pipeline_describe_steps,
+            
+            // Album sharing
+            create_share_link,
+            open_share_link,
+            download_shared_photo,
+            create_photo_link,
+            open_photo_link,
+            download_photo_link,
+            
+            // LAN gallery
+            serve_gallery,
+            stop_gallery,
+            gallery_status,
+            
+            // Peer-to-peer transfers
+            offer_p2p_transfer,
+            list_p2p_offers,
+            cancel_p2p_offer,
+            receive_p2p_transfer,
+            
+            // Album comments
+            add_comment,
+            list_comments,
+            
+            // Diagnostics
+            export_diagnostics_bundle,
+            run_health_check,
+            
+            // Cancellable jobs
+            cancel_job,
+            list_jobs,
+            
+            // Job scheduler
+            get_job_queue_status,
+            
+            // Large payloads
+            data_op_file,
+            data_op_buffer,
+            buffer_create,
+            buffer_write,
+            buffer_read,
+            buffer_path,
+            buffer_release,
+            hash_file_blake3,
+            
+            // Encrypted albums
+            create_album,
+            upload_encrypted_photo,
+            
+            // Album metadata
+            set_album_cover,
+            set_album_description,
+            set_album_metadata,
+            
+            // Nested albums
+            create_subalbum,
+            move_photo_to_subalbum,
+            list_subalbums,
+            
+            // Rename and move
+            rename_photo,
+            move_photo_between_albums,
+            
+            // Library import and export
+            import_repo_as_album,
+            import_google_takeout,
+            export_library,
+            
+            // Album history
+            get_album_history,
+            restore_album_to_commit,
+            
+            // Remote change notifications
+            start_remote_watch,
+            stop_remote_watch,
+            list_remote_watches,
+            
+            // Upload policy
+            get_upload_policy,
+            set_upload_policy,
+            validate_upload,
+
+            // Retention policies
+            set_retention_policy,
+            remove_retention_policy,
+            list_retention_policies,
+            preview_retention,
+            run_retention,
+
+            // Scheduled album runs
+            set_sync_schedule,
+            remove_sync_schedule,
+            get_sync_schedules,
+            run_sync_schedule_now,
+
+            // Multi-device sync
+            get_device_id,
+            
+            // Security audit
+            security_audit_albums,
+            verify_album_integrity,
+            verify_activity_log,
+            get_activity_log,
+            
+            // Message threads
+            list_secure_threads,
+            append_secure_message,
+            fetch_thread_messages,
+            
+            // Contacts
+            add_contact,
+            list_contacts,
+            verify_contact_fingerprint,
+            remove_contact,
+            
+            // Shared albums
+            share_album_with_contact,
+            list_album_access,
+            revoke_album_access,
+            
+            // Batch operations
+            delete_photos_batch,
+            move_photos,
+            
+            // Storage statistics
+            get_album_stats,
+            get_storage_usage,
+
+            // Duplicate detection
+            find_similar_photos,
+            
+            // Repository sharding
+            get_shard_map,
+            set_shard_threshold,
+            rebalance_shards,
+            
+            // Offline queue
+            queue_upload,
+            queue_delete,
+            get_pending_operations,
+            replay_pending_operations,
+            discard_pending_operation,
+            
+            // Folder watcher
+            watch_folder,
+            stop_watch,
+            list_watches,
+
+            // Two-way sync
+            sync_album,
+            get_sync_policy,
+            set_sync_policy,
+
+            // Git LFS
+            enable_lfs,
+            get_lfs_status,
+            upload_original,
+
+            // Album mirrors
+            configure_mirror,
+            remove_mirror,
+            list_mirrors,
+            verify_mirror,
+
+            // IPFS storage
+            configure_ipfs,
+            get_ipfs_config,
+            remove_ipfs_config,
+            pin_album_to_ipfs,
+            unpin_album_from_ipfs,
+            list_ipfs_pins,
+
+            // WebDAV backups
+            configure_webdav,
+            get_webdav_config,
+            remove_webdav_config,
+            backup_album_to_webdav,
+
+            // Key revocation
+            revoke_device_key,
+            check_revocation,
+
+            // Encrypted local store
+            get_local_setting,
+            set_local_setting,
+            delete_local_setting,
+            get_cached_albums,
+            clear_local_cache,
+            get_db_schema_version,
+
+            // Photo catalog
+            catalog_photos_with_tag,
+            catalog_album,
+            query_photos,
+
+            // Tags, favorites and ratings
+            tag_photo,
+            set_favorite,
+            set_rating,
+            sync_photo_organization,
+
+            // Library search
+            search_photos,
+            create_smart_album,
+            list_smart_albums,
+            smart_album_photos,
+            delete_smart_album,
+            get_timeline,
+
+            // Offline download cache
+            pin_photo_offline,
+            unpin_photo_offline,
+            download_cache_stats,
+            set_download_cache_limit,
+            clear_download_cache,
+            prefetch_photos,
+            cancel_prefetch,
+
+            // Plaintext temp files
+            open_photo_for_viewing,
+            list_plaintext_files,
+            release_plaintext,
+            purge_decrypted_cache,
+
+            // Thumbnails
+            generate_thumbnail,
+            pregenerate_thumbnails,
+            clear_thumbnail_cache,
+
+            // Network resilience
+            get_retry_policy,
+            set_retry_policy,
+            get_backend_status,
+            reset_circuit_breakers
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                let _ = secure_temp::purge_plaintext();
+            }
+        });
 }
\ No newline at end of file
@@ -0,0 +1,399 @@
+//! Library Export
+//!
+//! `export_library` copies every album of a repository into a local folder
+//! tree that needs nothing from Vortex to browse: `photos/Trips/Rome` on
+//! GitHub becomes `<destination>/Trips/Rome`, photos keep their original
+//! file names, and `index.csv` at the top lists each exported file with
+//! its album, capture time, place, camera, caption, tags, favorite and
+//! rating.
+//!
+//! With `decrypt`, encrypted albums are opened with the keypair: photos are
+//! written under the names they were uploaded with, and their captions and
+//! EXIF come from the album's metadata vault. Without it they are copied as
+//! stored, along with their manifest and vault, so they can be opened again
+//! later; the index then only lists their blob names. LFS and chunked
+//! photos are resolved either way, and pipeline outputs (`.vxp`) are copied
+//! as they are.
+//!
+//! The destination must be empty, so an export never overwrites anything.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, State};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::album::{album_key_for, open_album_photo, open_filename, AlbumManifest, ALBUM_MANIFEST_FILE, ALBUM_ROOT};
+use crate::crypto::KeypairHandle;
+use crate::git_data::{branch_head, get_blob, get_tree_recursive, index_blobs, TreeEntry, TreeIndex};
+use crate::github::{sanitize_filename, validate_repo, AppError, HttpClient, UploadBatchProgress};
+use crate::image_metadata::photo_exif;
+use crate::lfs::resolve_lfs_pointer;
+use crate::metadata_vault::{load_vault, ExifExtract, MetadataVault, VAULT_FILE};
+use crate::organize::is_encrypted_blob;
+use crate::security_verify::SIGNATURE_EXT;
+use crate::sharing::album_id;
+use crate::tagging::{read_organization, PhotoOrganization};
+
+pub const INDEX_FILE: &str = "index.csv";
+/// Emitted with an `UploadBatchProgress` after each file
+pub const EXPORT_PROGRESS_EVENT: &str = "library-export-progress";
+
+const INDEX_COLUMNS: &[&str] = &[
+    "album",
+    "file",
+    "source_path",
+    "bytes",
+    "blake3",
+    "encrypted",
+    "taken_at",
+    "time_offset",
+    "latitude",
+    "longitude",
+    "altitude",
+    "camera_make",
+    "camera_model",
+    "caption",
+    "tags",
+    "favorite",
+    "rating",
+];
+
+/// One album folder of the repository and the files to export from it
+#[derive(Clone, Debug)]
+pub struct ExportAlbum {
+    pub path: String,
+    pub files: Vec<TreeEntry>,
+    pub manifest: Option<TreeEntry>,
+    pub vault: Option<TreeEntry>,
+}
+
+/// One line of `index.csv`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexRow {
+    /// Album path below `photos/`, empty for loose photos
+    pub album: String,
+    /// Path of the exported file within the destination
+    pub file: String,
+    pub source_path: String,
+    pub bytes: u64,
+    pub blake3: String,
+    pub encrypted: bool,
+    pub caption: Option<String>,
+    pub exif: Option<ExifExtract>,
+    pub organization: PhotoOrganization,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LibraryExport {
+    pub albums: usize,
+    pub files: usize,
+    pub bytes: u64,
+    pub index_path: String,
+    pub failed: Vec<ExportFailure>,
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn folder_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(folder, _)| folder)
+}
+
+/// Album folders under `photos/` with the photos, videos and pipeline
+/// outputs they hold, sorted by path. Signatures, chunks and other hidden
+/// files are left out; manifests and vaults are picked up separately.
+pub fn export_albums(index: &TreeIndex) -> Vec<ExportAlbum> {
+    let prefix = format!("{}/", ALBUM_ROOT);
+    let mut albums: BTreeMap<&str, ExportAlbum> = BTreeMap::new();
+    for entry in index.values() {
+        let Some(relative) = entry.path.strip_prefix(&prefix) else {
+            continue;
+        };
+        if relative.split('/').any(|part| part.starts_with('.'))
+            || entry.path.ends_with(&format!(".{}", SIGNATURE_EXT))
+        {
+            continue;
+        }
+        let folder = folder_of(&entry.path);
+        albums
+            .entry(folder)
+            .or_insert_with(|| ExportAlbum {
+                path: folder.to_string(),
+                files: Vec::new(),
+                manifest: index.get(&format!("{}/{}", folder, ALBUM_MANIFEST_FILE)).cloned(),
+                vault: index.get(&format!("{}/{}", folder, VAULT_FILE)).cloned(),
+            })
+            .files
+            .push(entry.clone());
+    }
+    albums
+        .into_values()
+        .map(|mut album| {
+            album.files.sort_by(|a, b| a.path.cmp(&b.path));
+            album
+        })
+        .collect()
+}
+
+/// Album path below `photos/`, as used in the destination
+pub fn relative_album(album_path: &str) -> &str {
+    album_path.strip_prefix(ALBUM_ROOT).unwrap_or(album_path).trim_start_matches('/')
+}
+
+/// A name for `name` that is not in `taken` yet: `IMG.jpg`, `IMG (2).jpg`, ...
+pub fn unique_name(taken: &mut HashSet<String>, name: &str) -> String {
+    let name = match sanitize_filename(name) {
+        n if n.is_empty() || n.starts_with('.') => "photo".to_string(),
+        n => n,
+    };
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem.to_string(), format!(".{}", ext)),
+        _ => (name.clone(), String::new()),
+    };
+    let mut candidate = name;
+    let mut counter = 2;
+    while !taken.insert(candidate.to_lowercase()) {
+        candidate = format!("{} ({}){}", stem, counter, ext);
+        counter += 1;
+    }
+    candidate
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) || value.starts_with(' ') || value.ends_with(' ') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// The header and rows of `index.csv`
+pub fn index_csv(rows: &[IndexRow]) -> String {
+    let mut csv = INDEX_COLUMNS.join(",");
+    csv.push('\n');
+    for row in rows {
+        let exif = row.exif.clone().unwrap_or_default();
+        let fields = [
+            row.album.clone(),
+            row.file.clone(),
+            row.source_path.clone(),
+            row.bytes.to_string(),
+            row.blake3.clone(),
+            row.encrypted.to_string(),
+            optional(exif.taken_at),
+            optional(exif.time_offset),
+            optional(exif.latitude),
+            optional(exif.longitude),
+            optional(exif.altitude),
+            optional(exif.camera_make),
+            optional(exif.camera_model),
+            optional(row.caption.as_deref()),
+            row.organization.tags.join(";"),
+            row.organization.favorite.to_string(),
+            optional(row.organization.rating),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn write_file(destination: &Path, relative: &str, content: &[u8]) -> Result<(), AppError> {
+    let path = destination.join(relative);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+fn join_relative(folder: &str, name: &str) -> String {
+    if folder.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", folder, name)
+    }
+}
+
+/// What an album needs for decrypting: its key and vault
+struct OpenAlbum {
+    key: [u8; 32],
+    id: String,
+    vault: MetadataVault,
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Export every album of `repo` into the empty folder `destination`,
+/// decrypting encrypted albums with `keypair_handle` when `decrypt` is set
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn export_library(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    destination: String,
+    decrypt: bool,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<LibraryExport, AppError> {
+    validate_repo(&repo)?;
+    if decrypt && keypair_handle.is_none() {
+        return Err(AppError::Validation("Unlock your keypair to decrypt encrypted albums".into()));
+    }
+    let destination = PathBuf::from(&destination);
+    std::fs::create_dir_all(&destination)?;
+    if std::fs::read_dir(&destination)?.next().is_some() {
+        return Err(AppError::Validation("Choose an empty folder to export to".into()));
+    }
+
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let albums = export_albums(&index);
+    let total_files: usize = albums.iter().map(|a| a.files.len()).sum();
+    let mut result = LibraryExport { albums: albums.len(), ..Default::default() };
+    let mut rows = Vec::new();
+    let mut completed_files = 0;
+
+    for album in albums {
+        let folder = relative_album(&album.path).to_string();
+        let manifest: Option<AlbumManifest> = match &album.manifest {
+            Some(entry) => {
+                let raw = get_blob(&client.0, &repo, &token, &entry.sha).await?;
+                Some(serde_json::from_slice(&raw).map_err(|e| {
+                    AppError::Validation(format!("Invalid album manifest in {}: {}", album.path, e))
+                })?)
+            }
+            None => None,
+        };
+        let encrypted = manifest.as_ref().is_some_and(|m| m.encrypted);
+        let opened = match (encrypted && decrypt, &manifest, keypair_handle) {
+            (true, Some(manifest), Some(handle)) => {
+                let opened = async {
+                    let key = album_key_for(handle, &repo, &album.path, manifest)?;
+                    let vault = load_vault(&client.0, &repo, &token, &index, &album.path, &key).await?;
+                    Ok::<_, AppError>(OpenAlbum { key, id: album_id(&repo, &album.path), vault })
+                };
+                match opened.await {
+                    Ok(opened) => Some(opened),
+                    Err(e) => {
+                        completed_files += album.files.len();
+                        for file in &album.files {
+                            result.failed.push(ExportFailure { path: file.path.clone(), error: e.to_string() });
+                        }
+                        continue;
+                    }
+                }
+            }
+            _ => None,
+        };
+        // Sealed tags of albums left encrypted cannot be read, and are left out
+        let key = opened.as_ref().map(|o| &o.key);
+        let organization = manifest
+            .as_ref()
+            .and_then(|m| read_organization(m, key, &album_id(&repo, &album.path)).ok())
+            .unwrap_or_default();
+
+        // Encrypted albums copied as stored stay openable with their manifest and vault
+        if encrypted && opened.is_none() {
+            for entry in [&album.manifest, &album.vault].into_iter().flatten() {
+                let content = get_blob(&client.0, &repo, &token, &entry.sha).await?;
+                write_file(&destination, &join_relative(&folder, file_name(&entry.path)), &content)?;
+            }
+        }
+
+        let mut taken = HashSet::new();
+        for entry in &album.files {
+            let _ = app.emit(
+                EXPORT_PROGRESS_EVENT,
+                UploadBatchProgress {
+                    total_files,
+                    completed_files,
+                    current_file: entry.path.clone(),
+                    percent: ((completed_files * 100) / total_files.max(1)) as u8,
+                },
+            );
+            completed_files += 1;
+
+            let blob_name = file_name(&entry.path);
+            let exported = async {
+                let stored = get_blob(&client.0, &repo, &token, &entry.sha).await?;
+                let stored = resolve_lfs_pointer(&client.0, &repo, &token, stored).await?;
+                let mut row = IndexRow {
+                    album: folder.clone(),
+                    source_path: entry.path.clone(),
+                    encrypted: encrypted && is_encrypted_blob(blob_name),
+                    organization: organization.get(blob_name).cloned().unwrap_or_default(),
+                    ..Default::default()
+                };
+                let (name, content) = match (&opened, &manifest) {
+                    (Some(opened), Some(manifest)) if row.encrypted => {
+                        let (content, name) = open_album_photo(&opened.key, &opened.id, &stored)?;
+                        let name = name
+                            .or_else(|| {
+                                let sealed = manifest.entries.get(blob_name)?;
+                                open_filename(&opened.key, &opened.id, sealed).ok()
+                            })
+                            .unwrap_or_else(|| blob_name.to_string());
+                        if let Some(metadata) = opened.vault.entries.get(blob_name) {
+                            row.caption = metadata.caption.clone();
+                            row.exif = metadata.exif.clone();
+                        }
+                        row.encrypted = false;
+                        (name, content)
+                    }
+                    _ => {
+                        if !row.encrypted {
+                            row.exif = photo_exif(&stored);
+                        }
+                        (blob_name.to_string(), stored)
+                    }
+                };
+                row.file = join_relative(&folder, &unique_name(&mut taken, &name));
+                row.bytes = content.len() as u64;
+                row.blake3 = blake3::hash(&content).to_hex().to_string();
+                write_file(&destination, &row.file, &content)?;
+                Ok::<_, AppError>(row)
+            };
+            match exported.await {
+                Ok(row) => {
+                    result.files += 1;
+                    result.bytes += row.bytes;
+                    rows.push(row);
+                }
+                Err(e) => result.failed.push(ExportFailure { path: entry.path.clone(), error: e.to_string() }),
+            }
+        }
+    }
+
+    let index_path = destination.join(INDEX_FILE);
+    std::fs::write(&index_path, index_csv(&rows))?;
+    result.index_path = index_path.to_string_lossy().into_owned();
+
+    let _ = app.emit(
+        EXPORT_PROGRESS_EVENT,
+        UploadBatchProgress {
+            total_files,
+            completed_files: total_files,
+            current_file: String::new(),
+            percent: 100,
+        },
+    );
+    Ok(result)
+}
@@ -0,0 +1,307 @@
+//! Paginated Photo Listing
+//!
+//! `list_photos_page` serves album contents in pages from a recursive Git tree
+//! listing. Cursors are opaque to the frontend and pin the commit they started
+//! from, so paging stays consistent while uploads happen in the background.
+//! Trees are content-addressed, which makes them safe to cache indefinitely;
+//! only a handful of recent trees are kept in memory.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::album::{album_key_for, open_filename, AlbumManifest, ALBUM_MANIFEST_FILE};
+use crate::catalog::{cached_listing, record_listed};
+use crate::crypto::KeypairHandle;
+use crate::git_data::{branch_head, get_blob, get_tree_recursive, TreeEntry};
+use crate::github::{validate_repo, AppError, HttpClient, PhotoItem};
+use crate::metadata_vault::{vault_path, MetadataVault};
+use crate::sharding::shard_repos;
+use crate::sharing::album_id;
+use crate::smart_albums::notify_smart_albums;
+use crate::stats::is_photo_path;
+use crate::video::{is_video_file, MediaType};
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 1000;
+const TREE_CACHE_CAPACITY: usize = 8;
+
+type CachedTree = Arc<Vec<TreeEntry>>;
+
+lazy_static::lazy_static! {
+    /// (repo, tree sha) -> photo entries sorted by path
+    static ref TREE_CACHE: Mutex<VecDeque<((String, String), CachedTree)>> = Mutex::new(VecDeque::new());
+}
+
+/// Position within a paginated listing
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    /// Index into the library's shard list
+    pub shard: usize,
+    pub commit_sha: String,
+    pub tree_sha: String,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PhotoPage {
+    pub items: Vec<PhotoItem>,
+    /// Pass back to fetch the next page; `None` when the listing is complete
+    pub next_cursor: Option<String>,
+    /// Photos in the album within the current shard
+    pub shard_total: usize,
+}
+
+pub fn encode_cursor(cursor: &PageCursor) -> String {
+    let json = serde_json::to_vec(cursor).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<PageCursor, AppError> {
+    let raw = URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .map_err(|_| AppError::Validation("Invalid page cursor".into()))?;
+    serde_json::from_slice(&raw).map_err(|_| AppError::Validation("Invalid page cursor".into()))
+}
+
+/// Photos and videos directly inside `album` (sub-albums are listed
+/// separately)
+pub fn album_entries<'a>(entries: &'a [TreeEntry], album: &str) -> Vec<&'a TreeEntry> {
+    let prefix = format!("{}/", album.trim_matches('/'));
+    entries
+        .iter()
+        .filter(|e| {
+            e.path
+                .strip_prefix(&prefix)
+                .map(|rest| !rest.contains('/') && (is_photo_path(rest) || is_video_file(Path::new(rest))))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Slice one page out of a listing; returns the next offset if more remain
+pub fn page_bounds(total: usize, offset: usize, limit: usize) -> (usize, usize, Option<usize>) {
+    let start = offset.min(total);
+    let end = (start + limit).min(total);
+    let next = (end < total).then_some(end);
+    (start, end, next)
+}
+
+// ============================================================================
+// Tree Cache
+// ============================================================================
+
+async fn cached_tree(client: &Client, repo: &str, token: &str, tree_sha: &str) -> Result<CachedTree, AppError> {
+    let key = (repo.to_string(), tree_sha.to_string());
+
+    if let Some((_, tree)) = TREE_CACHE.lock().unwrap().iter().find(|(k, _)| *k == key) {
+        return Ok(tree.clone());
+    }
+
+    let mut entries: Vec<TreeEntry> = get_tree_recursive(client, repo, token, tree_sha)
+        .await?
+        .into_iter()
+        .filter(|e| e.kind == "blob")
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    let tree = Arc::new(entries);
+
+    let mut cache = TREE_CACHE.lock().unwrap();
+    if cache.len() >= TREE_CACHE_CAPACITY {
+        cache.pop_front();
+    }
+    cache.push_back((key, tree.clone()));
+
+    Ok(tree)
+}
+
+async fn start_cursor(client: &Client, repo: &str, token: &str, shard: usize) -> Result<PageCursor, AppError> {
+    let head = branch_head(client, repo, token).await?;
+    Ok(PageCursor {
+        shard,
+        commit_sha: head.commit_sha,
+        tree_sha: head.tree_sha,
+        offset: 0,
+    })
+}
+
+/// Decrypt display names for an encrypted album from its manifest blob, and
+/// captions and EXIF from its metadata vault
+async fn display_names(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    album: &str,
+    tree: &[TreeEntry],
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<(bool, HashMap<String, String>, MetadataVault), AppError> {
+    let manifest_path = format!("{}/{}", album, ALBUM_MANIFEST_FILE);
+    let Some(entry) = tree.iter().find(|e| e.path == manifest_path) else {
+        return Ok((false, HashMap::new(), MetadataVault::default()));
+    };
+
+    let raw = get_blob(client, repo, token, &entry.sha).await?;
+    let manifest: AlbumManifest = serde_json::from_slice(&raw)
+        .map_err(|e| AppError::Validation(format!("Invalid album manifest: {}", e)))?;
+
+    let mut names = HashMap::new();
+    let mut vault = MetadataVault::default();
+    if let (true, Some(handle)) = (manifest.encrypted, keypair_handle) {
+        let key = album_key_for(handle, repo, album, &manifest)?;
+        let id = album_id(repo, album);
+        for (blob, sealed) in &manifest.entries {
+            if let Ok(name) = open_filename(&key, &id, sealed) {
+                names.insert(blob.clone(), name);
+            }
+        }
+
+        let vault_path = vault_path(album);
+        if let Some(entry) = tree.iter().find(|e| e.path == vault_path) {
+            match get_blob(client, repo, token, &entry.sha)
+                .await
+                .and_then(|sealed| MetadataVault::open(&key, &id, &sealed))
+            {
+                Ok(opened) => vault = opened,
+                Err(e) => tracing::warn!("Ignoring metadata vault of {}: {}", album, e),
+            }
+        }
+        for (blob, metadata) in &vault.entries {
+            names.insert(blob.clone(), metadata.filename.clone());
+        }
+    }
+
+    Ok((manifest.encrypted, names, vault))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// List one page of an album. Omit `cursor` for the first page.
+///
+/// Pages are recorded in the local catalog. When GitHub cannot be reached,
+/// the first page is the album as last catalogued, in a single page.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
+pub async fn list_photos_page(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<PhotoPage, AppError> {
+    validate_repo(&repo)?;
+
+    let album = album.unwrap_or_else(|| "photos".to_string()).trim_matches('/').to_string();
+    if album.is_empty() || album.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let first_page = cursor.is_none();
+
+    match fetch_page(&client.0, &repo, &token, &album, cursor, limit, keypair_handle).await {
+        Ok(page) => {
+            if !record_listed(&repo, &album, &page.items).is_empty() {
+                notify_smart_albums(&app, &repo);
+            }
+            Ok(page)
+        }
+        Err(e @ (AppError::Network(_) | AppError::Unavailable(_))) if first_page => {
+            match cached_listing(&repo, &album, keypair_handle.is_some()) {
+                Some(items) => {
+                    tracing::info!("Listing {} from the catalog: {}", album, e);
+                    Ok(PhotoPage { shard_total: items.len(), items, next_cursor: None })
+                }
+                None => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn fetch_page(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    album: &str,
+    cursor: Option<String>,
+    limit: usize,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<PhotoPage, AppError> {
+    let shards = shard_repos(client, repo, token).await?;
+
+    let mut cursor = match cursor {
+        Some(c) => decode_cursor(&c)?,
+        None => start_cursor(client, &shards[0], token, 0).await?,
+    };
+
+    let shard_repo = shards
+        .get(cursor.shard)
+        .ok_or_else(|| AppError::Validation("Invalid page cursor".into()))?
+        .clone();
+
+    let tree = cached_tree(client, &shard_repo, token, &cursor.tree_sha).await?;
+    let entries = album_entries(&tree, album);
+    let (start, end, next) = page_bounds(entries.len(), cursor.offset, limit);
+
+    let (encrypted, names, mut vault) = display_names(client, &shard_repo, token, album, &tree, keypair_handle).await?;
+    let shard_label = (shard_repo != repo).then(|| shard_repo.clone());
+
+    let items = entries[start..end]
+        .iter()
+        .map(|e| {
+            let name = e.path.rsplit('/').next().unwrap_or(&e.path).to_string();
+            let (caption, exif) = vault
+                .entries
+                .remove(&name)
+                .map(|m| (m.caption, m.exif))
+                .unwrap_or_default();
+            let display_name = names.get(&name).cloned();
+            PhotoItem {
+                media_type: MediaType::of(display_name.as_deref().unwrap_or(&name)),
+                display_name,
+                caption,
+                exif,
+                url: format!(
+                    "https://raw.githubusercontent.com/{}/{}/{}",
+                    shard_repo, cursor.commit_sha, e.path
+                ),
+                sha: e.sha.clone(),
+                size: e.size,
+                name,
+                encrypted,
+                repo: shard_label.clone(),
+                raw_companion: None,
+            }
+        })
+        .collect();
+
+    let next_cursor = match next {
+        Some(offset) => {
+            cursor.offset = offset;
+            Some(encode_cursor(&cursor))
+        }
+        None if cursor.shard + 1 < shards.len() => {
+            let next_shard = cursor.shard + 1;
+            Some(encode_cursor(&start_cursor(client, &shards[next_shard], token, next_shard).await?))
+        }
+        None => None,
+    };
+
+    Ok(PhotoPage {
+        items,
+        next_cursor,
+        shard_total: entries.len(),
+    })
+}
@@ -0,0 +1,323 @@
+//! Encrypted Local Settings and Cache Store
+//!
+//! One SQLite database per profile, `<profile data>/store.db`, holding
+//! preferences and caches (album listings, thumbnail metadata) so none of
+//! them sit on disk in plaintext. Every row is
+//!
+//! ```text
+//! id        = BLAKE3-keyed(index key, namespace || 0 || key)
+//! namespace = BLAKE3-keyed(index key, namespace)
+//! value     = ChaCha20-Poly1305(value key, value, aad = id)
+//! ```
+//!
+//! so neither names nor contents are readable, and a row copied under
+//! another id fails to decrypt. The index and value keys are derived from a
+//! random store key kept in the profile's keystore (see
+//! `keystore::Keystore::detect`); losing it only loses cached data.
+//!
+//! Tables are created and upgraded by versioned migrations (see
+//! `migrations`), checked every time the store opens.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use zeroize::Zeroizing;
+
+use crate::crypto::{decrypt_with_key, encrypt_with_key};
+use crate::github::{AppError, Album};
+use crate::keystore::Keystore;
+use crate::util::now_secs;
+
+const STORE_FILE: &str = "store.db";
+const STORE_KEY_ENTRY: &str = "local-store-key";
+const INDEX_KEY_CONTEXT: &str = "vortex-image local store index key v1";
+const VALUE_KEY_CONTEXT: &str = "vortex-image local store value key v1";
+
+/// Namespace of user preferences; never cleared with the caches
+pub const SETTINGS_NS: &str = "settings";
+/// Last album listing fetched per repository
+pub const ALBUMS_NS: &str = "album_listing";
+/// Dimensions and other metadata of generated thumbnails
+pub const THUMBNAILS_NS: &str = "thumbnail_meta";
+/// Measurements of this device, such as the compression benchmark
+pub const BENCHMARKS_NS: &str = "benchmarks";
+/// Log of pipeline runs and their all-time totals
+pub const PIPELINE_HISTORY_NS: &str = "pipeline_history";
+/// Perceptual hashes of photos by blob SHA, for duplicate detection
+pub const PHOTO_HASHES_NS: &str = "photo_hashes";
+/// The photo catalog, kept in its own tables (see `catalog`)
+pub const CATALOG_NS: &str = "catalog";
+
+/// Migration 1 (see `migrations`)
+pub(crate) const ENTRIES_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS entries (
+    id BLOB PRIMARY KEY,
+    namespace BLOB NOT NULL,
+    value BLOB NOT NULL,
+    expires_at INTEGER
+);
+CREATE INDEX IF NOT EXISTS idx_entries_namespace ON entries(namespace);
+"#;
+
+lazy_static::lazy_static! {
+    static ref STORE: Mutex<Option<LocalStore>> = Mutex::new(None);
+}
+
+pub(crate) fn db_error(e: rusqlite::Error) -> AppError {
+    AppError::Validation(format!("Local store error: {}", e))
+}
+
+pub struct LocalStore {
+    conn: Connection,
+    index_key: Zeroizing<[u8; 32]>,
+    value_key: Zeroizing<[u8; 32]>,
+}
+
+impl LocalStore {
+    pub fn open(path: &Path, store_key: &[u8; 32]) -> Result<Self, AppError> {
+        Self::with_connection(Connection::open(path).map_err(db_error)?, store_key)
+    }
+
+    pub fn in_memory(store_key: &[u8; 32]) -> Result<Self, AppError> {
+        Self::with_connection(Connection::open_in_memory().map_err(db_error)?, store_key)
+    }
+
+    fn with_connection(conn: Connection, store_key: &[u8; 32]) -> Result<Self, AppError> {
+        crate::migrations::migrate(&conn, crate::migrations::MIGRATIONS)?;
+        // In memory only, rebuilt for every connection
+        conn.execute_batch(crate::search::SEARCH_SCHEMA).map_err(db_error)?;
+        Ok(Self {
+            conn,
+            index_key: Zeroizing::new(blake3::derive_key(INDEX_KEY_CONTEXT, store_key)),
+            value_key: Zeroizing::new(blake3::derive_key(VALUE_KEY_CONTEXT, store_key)),
+        })
+    }
+
+    fn namespace_id(&self, namespace: &str) -> [u8; 32] {
+        *blake3::keyed_hash(&self.index_key, namespace.as_bytes()).as_bytes()
+    }
+
+    fn entry_id(&self, namespace: &str, key: &str) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new_keyed(&self.index_key);
+        hasher.update(namespace.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(key.as_bytes());
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Row ID for tables other than `entries`: a keyed hash of `parts`
+    pub(crate) fn index_id(&self, parts: &[&str]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new_keyed(&self.index_key);
+        for part in parts {
+            hasher.update(part.as_bytes());
+            hasher.update(&[0]);
+        }
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Encrypt `value` for the row `id` of another table
+    pub(crate) fn seal(&self, id: &[u8; 32], value: &[u8]) -> Result<Vec<u8>, AppError> {
+        encrypt_with_key(value, &self.value_key, id).map_err(AppError::from)
+    }
+
+    pub(crate) fn unseal(&self, id: &[u8; 32], sealed: &[u8]) -> Result<Zeroizing<Vec<u8>>, AppError> {
+        Ok(Zeroizing::new(decrypt_with_key(sealed, &self.value_key, id)?))
+    }
+
+    /// Key for another purpose, derived from the store key under `context`
+    pub(crate) fn derive_key(&self, context: &str) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(blake3::derive_key(context, self.value_key.as_slice()))
+    }
+
+    pub(crate) fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Store `value` under `namespace`/`key`, replacing any previous value.
+    /// Entries with a `ttl` read as missing once it has passed.
+    pub fn put(&self, namespace: &str, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), AppError> {
+        let id = self.entry_id(namespace, key);
+        let sealed = encrypt_with_key(value, &self.value_key, &id)?;
+        let expires_at = ttl.map(|ttl| (now_secs() + ttl.as_secs()) as i64);
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO entries (id, namespace, value, expires_at) VALUES (?1, ?2, ?3, ?4)",
+                params![&id[..], &self.namespace_id(namespace)[..], sealed, expires_at],
+            )
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    pub fn get(&self, namespace: &str, key: &str) -> Result<Option<Zeroizing<Vec<u8>>>, AppError> {
+        let id = self.entry_id(namespace, key);
+        let row: Option<(Vec<u8>, Option<i64>)> = self
+            .conn
+            .query_row(
+                "SELECT value, expires_at FROM entries WHERE id = ?1",
+                params![&id[..]],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(db_error)?;
+        match row {
+            Some((_, Some(expires_at))) if expires_at as u64 <= now_secs() => {
+                self.remove(namespace, key)?;
+                Ok(None)
+            }
+            Some((sealed, _)) => {
+                let value = decrypt_with_key(&sealed, &self.value_key, &id)?;
+                Ok(Some(Zeroizing::new(value)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn remove(&self, namespace: &str, key: &str) -> Result<bool, AppError> {
+        let id = self.entry_id(namespace, key);
+        let removed = self
+            .conn
+            .execute("DELETE FROM entries WHERE id = ?1", params![&id[..]])
+            .map_err(db_error)?;
+        Ok(removed > 0)
+    }
+
+    /// Drop every entry in `namespace`
+    pub fn clear(&self, namespace: &str) -> Result<usize, AppError> {
+        if namespace == CATALOG_NS {
+            return crate::catalog::clear_catalog_in(self);
+        }
+        self.conn
+            .execute("DELETE FROM entries WHERE namespace = ?1", params![&self.namespace_id(namespace)[..]])
+            .map_err(db_error)
+    }
+
+    /// Drop every entry except settings, and the catalog
+    pub fn clear_caches(&self) -> Result<usize, AppError> {
+        let entries = self
+            .conn
+            .execute("DELETE FROM entries WHERE namespace != ?1", params![&self.namespace_id(SETTINGS_NS)[..]])
+            .map_err(db_error)?;
+        Ok(entries + crate::catalog::clear_catalog_in(self)?)
+    }
+
+    /// Drop entries whose TTL has passed
+    pub fn purge_expired(&self) -> Result<usize, AppError> {
+        self.conn
+            .execute(
+                "DELETE FROM entries WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                params![now_secs() as i64],
+            )
+            .map_err(db_error)
+    }
+
+    pub fn put_json<T: Serialize>(&self, namespace: &str, key: &str, value: &T, ttl: Option<Duration>) -> Result<(), AppError> {
+        let json = Zeroizing::new(serde_json::to_vec(value).map_err(|e| AppError::Validation(e.to_string()))?);
+        self.put(namespace, key, &json, ttl)
+    }
+
+    pub fn get_json<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Result<Option<T>, AppError> {
+        self.get(namespace, key)?
+            .map(|json| {
+                serde_json::from_slice(&json)
+                    .map_err(|e| AppError::Validation(format!("Corrupt local store entry: {}", e)))
+            })
+            .transpose()
+    }
+}
+
+// ============================================================================
+// Profile Store
+// ============================================================================
+
+fn store_key() -> Result<Zeroizing<[u8; 32]>, AppError> {
+    let keystore = Keystore::detect()?;
+    if let Some(stored) = keystore.retrieve(STORE_KEY_ENTRY)? {
+        let bytes = Zeroizing::new(
+            hex::decode(stored.as_str()).map_err(|_| AppError::Validation("Corrupt local store key".into()))?,
+        );
+        let mut key = Zeroizing::new([0u8; 32]);
+        if bytes.len() != key.len() {
+            return Err(AppError::Validation("Corrupt local store key".into()));
+        }
+        key.copy_from_slice(&bytes);
+        return Ok(key);
+    }
+    let key = Zeroizing::new(rand::random::<[u8; 32]>());
+    keystore
+        .store(STORE_KEY_ENTRY, &Zeroizing::new(hex::encode(key.as_slice())))
+        ?;
+    Ok(key)
+}
+
+/// Run `f` against the active profile's store, opening it on first use
+pub(crate) fn with_store<T>(f: impl FnOnce(&LocalStore) -> Result<T, AppError>) -> Result<T, AppError> {
+    let mut guard = STORE.lock().unwrap();
+    if guard.is_none() {
+        let path = crate::profiles::data_dir()?.join(STORE_FILE);
+        let fresh = !path.exists();
+        let key = store_key()?;
+        let store = LocalStore::open(&path, &key)?;
+        if !fresh {
+            store.purge_expired()?;
+        }
+        *guard = Some(store);
+    }
+    f(guard.as_ref().expect("opened above"))
+}
+
+/// Close the store so the next use opens the new profile's, after a switch
+pub(crate) fn forget_local_store() {
+    *STORE.lock().unwrap() = None;
+}
+
+/// Remember the album listing of `repo` for offline display. Failures only
+/// cost the cache, so they are logged rather than returned.
+pub(crate) fn cache_album_listing(repo: &str, albums: &[Album]) {
+    if let Err(e) = with_store(|store| store.put_json(ALBUMS_NS, repo, &albums, None)) {
+        tracing::warn!("Could not cache album listing of {}: {}", repo, e);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_local_setting(key: String) -> Result<Option<serde_json::Value>, AppError> {
+    with_store(|store| store.get_json(SETTINGS_NS, &key))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn set_local_setting(key: String, value: serde_json::Value) -> Result<(), AppError> {
+    with_store(|store| store.put_json(SETTINGS_NS, &key, &value, None))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn delete_local_setting(key: String) -> Result<bool, AppError> {
+    with_store(|store| store.remove(SETTINGS_NS, &key))
+}
+
+/// Album listing of `repo` as of the last successful `list_albums`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_cached_albums(repo: String) -> Result<Option<Vec<Album>>, AppError> {
+    with_store(|store| store.get_json(ALBUMS_NS, &repo))
+}
+
+/// Clear one cache namespace, or every cache when `namespace` is omitted.
+/// Settings are kept either way.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn clear_local_cache(namespace: Option<String>) -> Result<usize, AppError> {
+    with_store(|store| match namespace.as_deref() {
+        Some(SETTINGS_NS) => Err(AppError::Validation("Settings are not a cache".into())),
+        Some(namespace) => store.clear(namespace),
+        None => store.clear_caches(),
+    })
+}
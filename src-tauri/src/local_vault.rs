@@ -0,0 +1,662 @@
+//! Local Vault and Hidden Volumes
+//!
+//! An on-disk store for albums that should not exist in plain form anywhere,
+//! not even in the sync folder. One container file holds up to two volumes,
+//! each opened by its own password: the outer volume for whatever the user
+//! is willing to reveal, and an optional hidden one. Nothing in the file
+//! tells whether a hidden volume exists:
+//!
+//! ```text
+//! [magic "VXLV"][version: 1][KDF params: 12]
+//! [slot 0: salt 16 | sealed header]    one per volume; an unused slot
+//! [slot 1: salt 16 | sealed header]    is random bytes
+//! [block 0][block 1]...                fixed-size, each [nonce 12][ciphertext]
+//! ```
+//!
+//! The whole container is filled with random bytes when created, and every
+//! block is re-encrypted with a fresh nonce when written, so free blocks,
+//! outer blocks and hidden blocks look alike. A password is tried against
+//! both slots; the header it opens holds the volume key and the blocks of
+//! the volume's index (albums, photos and the blocks holding them).
+//!
+//! Writes are copy-on-write: data and a new index go to free blocks, then the
+//! header is rewritten in place. The outer volume allocates from the front,
+//! the hidden one from the back. An outer volume cannot see the hidden one,
+//! so writing a lot to it can overwrite hidden data; unlock the outer volume
+//! with `protect_password` set to the hidden password to reserve the hidden
+//! blocks while writing.
+
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::crypto::{current_kdf_params, decrypt_with_key, encrypt_with_key, KdfParams};
+use crate::github::{sanitize_filename, AppError, GithubError};
+use crate::util::now_secs;
+
+pub const LOCAL_VAULT_MAGIC: &[u8; 4] = b"VXLV";
+const LOCAL_VAULT_VERSION: u8 = 1;
+const PREFIX_LEN: u64 = 5 + KdfParams::ENCODED_LEN as u64;
+const SALT_LEN: usize = 16;
+/// Plaintext header, length-prefixed and zero-padded
+const HEADER_LEN: usize = 4096;
+const SLOT_LEN: u64 = (SALT_LEN + 12 + HEADER_LEN + 16) as u64;
+pub const BLOCK_SIZE: usize = 64 * 1024;
+/// Data carried per block after nonce and tag
+pub const BLOCK_PAYLOAD: usize = BLOCK_SIZE - 12 - 16;
+const MAX_CAPACITY_MB: u64 = 64 * 1024;
+
+lazy_static::lazy_static! {
+    static ref VAULTS: Mutex<VaultStore> = Mutex::new(VaultStore::default());
+}
+
+pub type VaultHandle = u64;
+
+#[derive(Default)]
+struct VaultStore {
+    open: HashMap<VaultHandle, LocalVault>,
+    next_handle: VaultHandle,
+}
+
+/// One photo inside a volume
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultFile {
+    pub size: u64,
+    pub blocks: Vec<u32>,
+    pub added_at: u64,
+}
+
+/// Album name -> file name -> file
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultIndex {
+    pub albums: BTreeMap<String, BTreeMap<String, VaultFile>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VolumeHeader {
+    version: u8,
+    volume_key: [u8; 32],
+    /// Allocate from the end of the container (hidden volumes)
+    from_end: bool,
+    index_blocks: Vec<u32>,
+    index_len: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VaultAlbum {
+    pub name: String,
+    pub photos: Vec<VaultPhoto>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VaultPhoto {
+    pub name: String,
+    pub size: u64,
+    pub added_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LocalVaultInfo {
+    pub handle: VaultHandle,
+    pub albums: Vec<VaultAlbum>,
+    pub free_bytes: u64,
+    pub capacity_bytes: u64,
+}
+
+fn slot_offset(slot: usize) -> u64 {
+    PREFIX_LEN + slot as u64 * SLOT_LEN
+}
+
+fn block_offset(block: u32) -> u64 {
+    PREFIX_LEN + 2 * SLOT_LEN + block as u64 * BLOCK_SIZE as u64
+}
+
+fn header_aad(prefix: &[u8], slot: usize) -> Vec<u8> {
+    let mut aad = prefix.to_vec();
+    aad.push(slot as u8);
+    aad
+}
+
+// ============================================================================
+// Container
+// ============================================================================
+
+/// An unlocked volume
+pub struct LocalVault {
+    path: PathBuf,
+    prefix: Vec<u8>,
+    slot: usize,
+    /// Password-derived key and salt of this volume's slot
+    slot_key: Zeroizing<[u8; 32]>,
+    salt: [u8; SALT_LEN],
+    volume_key: Zeroizing<[u8; 32]>,
+    from_end: bool,
+    index: VaultIndex,
+    index_blocks: Vec<u32>,
+    block_count: u32,
+    /// Blocks of the other volume, when it was unlocked for protection
+    reserved: BTreeSet<u32>,
+}
+
+fn read_prefix(file: &mut File) -> Result<(Vec<u8>, KdfParams, u32), AppError> {
+    let mut prefix = vec![0u8; PREFIX_LEN as usize];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut prefix)
+        .map_err(|_| AppError::Validation("Not a local vault".into()))?;
+    if &prefix[..4] != LOCAL_VAULT_MAGIC {
+        return Err(AppError::Validation("Not a local vault".into()));
+    }
+    if prefix[4] != LOCAL_VAULT_VERSION {
+        return Err(AppError::Validation(format!("Unsupported local vault version {}", prefix[4])));
+    }
+    let params = KdfParams::from_bytes(&prefix[5..]);
+    let data_len = file.metadata()?.len().saturating_sub(block_offset(0));
+    Ok((prefix, params, (data_len / BLOCK_SIZE as u64) as u32))
+}
+
+fn read_slot(file: &mut File, slot: usize) -> Result<([u8; SALT_LEN], Vec<u8>), AppError> {
+    let mut raw = vec![0u8; SLOT_LEN as usize];
+    file.seek(SeekFrom::Start(slot_offset(slot)))?;
+    file.read_exact(&mut raw)?;
+    let salt = raw[..SALT_LEN].try_into().expect("salt length");
+    Ok((salt, raw.split_off(SALT_LEN)))
+}
+
+fn random_fill(file: &mut File, mut len: u64) -> Result<(), AppError> {
+    let mut chunk = vec![0u8; BLOCK_SIZE];
+    while len > 0 {
+        let n = len.min(chunk.len() as u64) as usize;
+        OsRng.fill_bytes(&mut chunk[..n]);
+        file.write_all(&chunk[..n])?;
+        len -= n as u64;
+    }
+    Ok(())
+}
+
+/// Create a container of `block_count` blocks with an outer volume and,
+/// optionally, a hidden one
+pub fn create(
+    path: &Path,
+    block_count: u32,
+    params: KdfParams,
+    password: &[u8],
+    hidden_password: Option<&[u8]>,
+) -> Result<(), AppError> {
+    if hidden_password == Some(password) {
+        return Err(AppError::Validation("The hidden volume needs a different password".into()));
+    }
+    if block_count < 2 {
+        return Err(AppError::Validation("Local vault is too small".into()));
+    }
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(LOCAL_VAULT_MAGIC)?;
+    file.write_all(&[LOCAL_VAULT_VERSION])?;
+    file.write_all(&params.to_bytes())?;
+    random_fill(&mut file, 2 * SLOT_LEN + block_count as u64 * BLOCK_SIZE as u64)?;
+    file.sync_all()?;
+    drop(file);
+
+    // Which slot holds the outer volume is random too
+    let outer_slot = (OsRng.next_u32() & 1) as usize;
+    let outer = LocalVault::init(path, outer_slot, password, false, BTreeSet::new())?;
+    if let Some(hidden_password) = hidden_password {
+        LocalVault::init(path, 1 - outer_slot, hidden_password, true, outer.used_blocks())?;
+    }
+    Ok(())
+}
+
+/// Add a hidden volume to an existing container, replacing any hidden
+/// volume already there. The outer password keeps the new volume clear of
+/// the outer volume's blocks.
+pub fn add_hidden(path: &Path, password: &[u8], hidden_password: &[u8]) -> Result<(), AppError> {
+    if hidden_password == password {
+        return Err(AppError::Validation("The hidden volume needs a different password".into()));
+    }
+    let outer = LocalVault::unlock(path, password, None)?;
+    LocalVault::init(path, 1 - outer.slot, hidden_password, true, outer.used_blocks())?;
+    Ok(())
+}
+
+impl LocalVault {
+    /// Write a fresh, empty volume into `slot`
+    fn init(path: &Path, slot: usize, password: &[u8], from_end: bool, reserved: BTreeSet<u32>) -> Result<Self, AppError> {
+        let mut file = OpenOptions::new().read(true).open(path)?;
+        let (prefix, params, block_count) = read_prefix(&mut file)?;
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut volume_key = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(volume_key.as_mut());
+
+        let mut vault = Self {
+            path: path.to_path_buf(),
+            prefix,
+            slot,
+            slot_key: Zeroizing::new(params.derive(password, &salt)?),
+            salt,
+            volume_key,
+            from_end,
+            index: VaultIndex::default(),
+            index_blocks: Vec::new(),
+            block_count,
+            reserved,
+        };
+        vault.commit()?;
+        Ok(vault)
+    }
+
+    /// Open the volume `password` belongs to. With `protect_password`, the
+    /// other volume's blocks are kept free of writes.
+    pub fn unlock(path: &Path, password: &[u8], protect_password: Option<&[u8]>) -> Result<Self, AppError> {
+        let mut vault = Self::open_slot(path, password)?;
+        if let Some(protect) = protect_password {
+            let other = Self::open_slot(path, protect)?;
+            if other.slot == vault.slot {
+                return Err(AppError::Validation("The protected password opens the same volume".into()));
+            }
+            vault.reserved = other.used_blocks();
+        }
+        Ok(vault)
+    }
+
+    fn open_slot(path: &Path, password: &[u8]) -> Result<Self, AppError> {
+        let mut file = File::open(path)?;
+        let (prefix, params, block_count) = read_prefix(&mut file)?;
+
+        for slot in 0..2 {
+            let (salt, sealed) = read_slot(&mut file, slot)?;
+            let slot_key = Zeroizing::new(params.derive(password, &salt)?);
+            let Ok(plain) = decrypt_with_key(&sealed, &slot_key, &header_aad(&prefix, slot)) else {
+                continue;
+            };
+            let plain = Zeroizing::new(plain);
+            let len = u32::from_le_bytes(plain[..4].try_into().expect("4 bytes")) as usize;
+            let header: VolumeHeader = serde_json::from_slice(plain.get(4..4 + len).unwrap_or_default())
+                .map_err(|e| AppError::Validation(format!("Corrupt local vault header: {}", e)))?;
+
+            let mut vault = Self {
+                path: path.to_path_buf(),
+                prefix,
+                slot,
+                slot_key,
+                salt,
+                volume_key: Zeroizing::new(header.volume_key),
+                from_end: header.from_end,
+                index: VaultIndex::default(),
+                index_blocks: header.index_blocks,
+                block_count,
+                reserved: BTreeSet::new(),
+            };
+            let raw = vault.read_blocks(&mut file, &vault.index_blocks, header.index_len)?;
+            vault.index = serde_json::from_slice(&raw)
+                .map_err(|e| AppError::Validation(format!("Corrupt local vault index: {}", e)))?;
+            return Ok(vault);
+        }
+        // Deliberately the same error whether or not a volume exists
+        Err(AppError::Validation("Wrong password for this local vault".into()))
+    }
+
+    /// Blocks holding this volume's index and photos, plus reserved ones
+    pub fn used_blocks(&self) -> BTreeSet<u32> {
+        let mut used: BTreeSet<u32> = self.index_blocks.iter().copied().collect();
+        for files in self.index.albums.values() {
+            for file in files.values() {
+                used.extend(file.blocks.iter().copied());
+            }
+        }
+        used.extend(self.reserved.iter().copied());
+        used
+    }
+
+    pub fn free_blocks(&self) -> u32 {
+        self.block_count.saturating_sub(self.used_blocks().len() as u32)
+    }
+
+    pub fn capacity_bytes(&self) -> u64 {
+        self.block_count as u64 * BLOCK_PAYLOAD as u64
+    }
+
+    fn allocate(&self, count: usize, also_used: &BTreeSet<u32>) -> Result<Vec<u32>, AppError> {
+        let used = self.used_blocks();
+        let free = |b: &u32| !used.contains(b) && !also_used.contains(b);
+        let picked: Vec<u32> = if self.from_end {
+            (0..self.block_count).rev().filter(free).take(count).collect()
+        } else {
+            (0..self.block_count).filter(free).take(count).collect()
+        };
+        if picked.len() < count {
+            return Err(AppError::Validation("Local vault is full".into()));
+        }
+        Ok(picked)
+    }
+
+    fn block_aad(&self, block: u32) -> [u8; 4] {
+        block.to_le_bytes()
+    }
+
+    fn write_blocks(&self, file: &mut File, blocks: &[u32], data: &[u8]) -> Result<(), AppError> {
+        for (block, chunk) in blocks.iter().zip(data.chunks(BLOCK_PAYLOAD).chain(std::iter::repeat(&[][..]))) {
+            let mut payload = Zeroizing::new(vec![0u8; BLOCK_PAYLOAD]);
+            payload[..chunk.len()].copy_from_slice(chunk);
+            let sealed = encrypt_with_key(&payload, &self.volume_key, &self.block_aad(*block))?;
+            file.seek(SeekFrom::Start(block_offset(*block)))?;
+            file.write_all(&sealed)?;
+        }
+        Ok(())
+    }
+
+    fn read_blocks(&self, file: &mut File, blocks: &[u32], len: u64) -> Result<Vec<u8>, AppError> {
+        let mut out = Vec::with_capacity(len as usize);
+        let mut sealed = vec![0u8; BLOCK_SIZE];
+        for block in blocks {
+            if *block >= self.block_count {
+                return Err(AppError::Validation("Local vault block out of range".into()));
+            }
+            file.seek(SeekFrom::Start(block_offset(*block)))?;
+            file.read_exact(&mut sealed)?;
+            let payload = decrypt_with_key(&sealed, &self.volume_key, &self.block_aad(*block))?;
+            out.extend_from_slice(&payload);
+        }
+        if (out.len() as u64) < len {
+            return Err(AppError::Validation("Local vault file is truncated".into()));
+        }
+        out.truncate(len as usize);
+        Ok(out)
+    }
+
+    fn blocks_for(len: usize) -> usize {
+        len.div_ceil(BLOCK_PAYLOAD).max(1)
+    }
+
+    /// Write the index to fresh blocks, then point the header at them
+    fn commit(&mut self) -> Result<(), AppError> {
+        let index = Zeroizing::new(
+            serde_json::to_vec(&self.index).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?,
+        );
+        let blocks = self.allocate(Self::blocks_for(index.len()), &BTreeSet::new())?;
+
+        let header = VolumeHeader {
+            version: LOCAL_VAULT_VERSION,
+            volume_key: *self.volume_key,
+            from_end: self.from_end,
+            index_blocks: blocks.clone(),
+            index_len: index.len() as u64,
+        };
+        let json = Zeroizing::new(
+            serde_json::to_vec(&header).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?,
+        );
+        if json.len() + 4 > HEADER_LEN {
+            return Err(AppError::Validation("Local vault index is too large".into()));
+        }
+        let mut plain = Zeroizing::new(vec![0u8; HEADER_LEN]);
+        plain[..4].copy_from_slice(&(json.len() as u32).to_le_bytes());
+        plain[4..4 + json.len()].copy_from_slice(&json);
+        let sealed = encrypt_with_key(&plain, &self.slot_key, &header_aad(&self.prefix, self.slot))?;
+
+        let mut file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        self.write_blocks(&mut file, &blocks, &index)?;
+        file.sync_data()?;
+        file.seek(SeekFrom::Start(slot_offset(self.slot)))?;
+        file.write_all(&self.salt)?;
+        file.write_all(&sealed)?;
+        file.sync_data()?;
+
+        self.index_blocks = blocks;
+        Ok(())
+    }
+
+    pub fn add_photo(&mut self, album: &str, name: &str, data: &[u8]) -> Result<VaultPhoto, AppError> {
+        let (album, name) = (vault_name(album)?, vault_name(name)?);
+        if self.index.albums.get(&album).is_some_and(|a| a.contains_key(&name)) {
+            return Err(AppError::Validation("A photo with that name is already in the album".into()));
+        }
+        let blocks = self.allocate(Self::blocks_for(data.len()), &BTreeSet::new())?;
+        let mut file = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        self.write_blocks(&mut file, &blocks, data)?;
+
+        let entry = VaultFile {
+            size: data.len() as u64,
+            blocks,
+            added_at: now_secs(),
+        };
+        let photo = VaultPhoto {
+            name: name.clone(),
+            size: entry.size,
+            added_at: entry.added_at,
+        };
+        self.index.albums.entry(album.clone()).or_default().insert(name.clone(), entry);
+        if let Err(e) = self.commit() {
+            if let Some(files) = self.index.albums.get_mut(&album) {
+                files.remove(&name);
+            }
+            return Err(e);
+        }
+        Ok(photo)
+    }
+
+    pub fn read_photo(&self, album: &str, name: &str) -> Result<Vec<u8>, AppError> {
+        let entry = self
+            .index
+            .albums
+            .get(album)
+            .and_then(|files| files.get(name))
+            .ok_or_else(|| AppError::from(GithubError::NotFound {
+                message: format!("Photo not found: {}/{}", album, name),
+            }))?;
+        let mut file = File::open(&self.path)?;
+        self.read_blocks(&mut file, &entry.blocks, entry.size)
+    }
+
+    /// Remove a photo; its blocks become free and are overwritten later
+    pub fn remove_photo(&mut self, album: &str, name: &str) -> Result<(), AppError> {
+        let files = self.index.albums.get_mut(album).ok_or_else(|| {
+            AppError::from(GithubError::NotFound {
+                message: format!("Album not found: {}", album),
+            })
+        })?;
+        files.remove(name).ok_or_else(|| {
+            AppError::from(GithubError::NotFound {
+                message: format!("Photo not found: {}/{}", album, name),
+            })
+        })?;
+        if files.is_empty() {
+            self.index.albums.remove(album);
+        }
+        self.commit()
+    }
+
+    pub fn albums(&self) -> Vec<VaultAlbum> {
+        self.index
+            .albums
+            .iter()
+            .map(|(name, files)| VaultAlbum {
+                name: name.clone(),
+                photos: files
+                    .iter()
+                    .map(|(name, f)| VaultPhoto {
+                        name: name.clone(),
+                        size: f.size,
+                        added_at: f.added_at,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Album and photo names are single path components
+fn vault_name(name: &str) -> Result<String, AppError> {
+    let sanitized = sanitize_filename(name.trim());
+    if sanitized.is_empty() || sanitized == "." || sanitized == ".." {
+        return Err(AppError::Validation(format!("Invalid name: {}", name)));
+    }
+    Ok(sanitized)
+}
+
+// ============================================================================
+// Unlocked Volumes
+// ============================================================================
+
+fn with_vault<T>(handle: VaultHandle, f: impl FnOnce(&mut LocalVault) -> Result<T, AppError>) -> Result<T, AppError> {
+    let mut store = VAULTS.lock().unwrap();
+    let vault = store.open.get_mut(&handle).ok_or_else(|| {
+        AppError::from(GithubError::NotFound {
+            message: "Local vault is not unlocked".into(),
+        })
+    })?;
+    f(vault)
+}
+
+fn vault_info(handle: VaultHandle, vault: &LocalVault) -> LocalVaultInfo {
+    LocalVaultInfo {
+        handle,
+        albums: vault.albums(),
+        free_bytes: vault.free_blocks() as u64 * BLOCK_PAYLOAD as u64,
+        capacity_bytes: vault.capacity_bytes(),
+    }
+}
+
+/// Lock every unlocked volume, e.g. when switching profiles
+pub(crate) fn lock_all_vaults() {
+    VAULTS.lock().unwrap().open.clear();
+}
+
+fn ensure_strong(password: &str) -> Result<(), AppError> {
+    crate::password::ensure_strong(password).map_err(AppError::from)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Create a local vault of `capacity_mb`, with a hidden volume when
+/// `hidden_password` is given
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn create_local_vault(
+    path: String,
+    capacity_mb: u64,
+    password: String,
+    hidden_password: Option<String>,
+) -> Result<(), AppError> {
+    if capacity_mb == 0 || capacity_mb > MAX_CAPACITY_MB {
+        return Err(AppError::Validation(format!(
+            "Capacity must be 1 to {} MB",
+            MAX_CAPACITY_MB
+        )));
+    }
+    let password = Zeroizing::new(password);
+    let hidden_password = hidden_password.map(Zeroizing::new);
+    ensure_strong(&password)?;
+    if let Some(hidden) = &hidden_password {
+        ensure_strong(hidden)?;
+    }
+    let blocks = (capacity_mb * 1024 * 1024 / BLOCK_SIZE as u64) as u32;
+
+    tokio::task::spawn_blocking(move || {
+        create(
+            Path::new(&path),
+            blocks,
+            current_kdf_params(),
+            password.as_bytes(),
+            hidden_password.as_ref().map(|p| p.as_bytes()),
+        )
+    })
+    .await
+    .map_err(|e| AppError::Validation(e.to_string()))?
+}
+
+/// Add a hidden volume to a local vault. Replaces any existing hidden volume.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn add_hidden_volume(path: String, password: String, hidden_password: String) -> Result<(), AppError> {
+    let password = Zeroizing::new(password);
+    let hidden_password = Zeroizing::new(hidden_password);
+    ensure_strong(&hidden_password)?;
+    tokio::task::spawn_blocking(move || add_hidden(Path::new(&path), password.as_bytes(), hidden_password.as_bytes()))
+        .await
+        .map_err(|e| AppError::Validation(e.to_string()))?
+}
+
+/// Unlock the volume `password` opens. Pass the hidden password as
+/// `protect_password` when writing to the outer volume of a vault that has one.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn unlock_local_vault(
+    path: String,
+    password: String,
+    protect_password: Option<String>,
+) -> Result<LocalVaultInfo, AppError> {
+    let password = Zeroizing::new(password);
+    let protect_password = protect_password.map(Zeroizing::new);
+    let vault = tokio::task::spawn_blocking(move || {
+        LocalVault::unlock(
+            Path::new(&path),
+            password.as_bytes(),
+            protect_password.as_ref().map(|p| p.as_bytes()),
+        )
+    })
+    .await
+    .map_err(|e| AppError::Validation(e.to_string()))??;
+
+    let mut store = VAULTS.lock().unwrap();
+    store.next_handle += 1;
+    let handle = store.next_handle;
+    let info = vault_info(handle, &vault);
+    store.open.insert(handle, vault);
+    Ok(info)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn lock_local_vault(handle: VaultHandle) -> Result<(), AppError> {
+    VAULTS.lock().unwrap().open.remove(&handle).map(|_| ()).ok_or_else(|| {
+        GithubError::NotFound {
+            message: "Local vault is not unlocked".into(),
+        }
+        .into()
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn list_local_vault(handle: VaultHandle) -> Result<LocalVaultInfo, AppError> {
+    with_vault(handle, |vault| Ok(vault_info(handle, vault)))
+}
+
+/// Copy a local file into an album of the unlocked volume
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn local_vault_add_photo(
+    handle: VaultHandle,
+    album: String,
+    path: String,
+    name: Option<String>,
+) -> Result<VaultPhoto, AppError> {
+    let data = Zeroizing::new(tokio::fs::read(&path).await?);
+    let name = name.unwrap_or_else(|| {
+        Path::new(&path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("photo")
+            .to_string()
+    });
+    with_vault(handle, |vault| vault.add_photo(&album, &name, &data))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn local_vault_read_photo(handle: VaultHandle, album: String, name: String) -> Result<Vec<u8>, AppError> {
+    with_vault(handle, |vault| vault.read_photo(&album, &name))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn local_vault_remove_photo(handle: VaultHandle, album: String, name: String) -> Result<(), AppError> {
+    with_vault(handle, |vault| vault.remove_photo(&album, &name))
+}
@@ -0,0 +1,382 @@
+//! Album Metadata Vault
+//!
+//! Encrypted albums keep everything that describes a photo out of the
+//! repository's plain text: next to the manifest, each album holds a vault
+//! (`.vortex-vault.bin`) mapping blob names to the original filename, a
+//! caption and an EXIF extract (capture time, camera, exposure, location).
+//! The vault is one ChaCha20-Poly1305 blob under the album key, so it follows
+//! the album's key epochs and recipients like the photos do, and
+//! `list_photos` / `list_photos_page` decrypt it locally in one request.
+//!
+//! The manifest still carries each photo's sealed filename, so albums whose
+//! vault is missing or was written by an older client keep their names.
+//! Vault entries for photos no longer in the manifest are pruned on write.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::album::{album_key_for, fetch_manifest, parent_album_path, AlbumManifest, ENCRYPTED_BLOB_EXT};
+use crate::crypto::{decrypt_with_key, encrypt_with_key, KeypairHandle};
+use crate::git_data::{create_blob, get_blob, get_json, TreeChange, TreeIndex};
+use crate::github::{put_file_contents, validate_repo, AppError, GithubError, HttpClient, UploadResult};
+use crate::image_metadata::photo_exif;
+use crate::sharing::album_id;
+use crate::util::now_secs;
+
+pub const VAULT_FILE: &str = ".vortex-vault.bin";
+const VAULT_VERSION: u8 = 1;
+const MAX_CAPTION_LEN: usize = 2000;
+
+/// The EXIF fields worth showing, read before the photo is encrypted
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExifExtract {
+    /// `YYYY:MM:DD HH:MM:SS` in the camera's local time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taken_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digitized_at: Option<String>,
+    /// Last edit, from the EXIF `DateTime` tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<String>,
+    /// UTC offset of `taken_at`, such as `+02:00`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_offset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_make: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lens_model: Option<String>,
+    /// Millimetres
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focal_length: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub f_number: Option<f64>,
+    /// Seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exposure_time: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iso: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<u32>,
+    /// Decimal degrees, south negative
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    /// Decimal degrees, west negative
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// Metres above sea level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhotoMetadata {
+    pub filename: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exif: Option<ExifExtract>,
+    pub added_at: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetadataVault {
+    pub version: u8,
+    /// Blob name -> metadata
+    #[serde(default)]
+    pub entries: BTreeMap<String, PhotoMetadata>,
+}
+
+impl Default for MetadataVault {
+    fn default() -> Self {
+        Self {
+            version: VAULT_VERSION,
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+fn vault_aad(album_id: &str) -> String {
+    format!("{}#vault", album_id)
+}
+
+pub fn vault_path(album_path: &str) -> String {
+    format!("{}/{}", album_path.trim_matches('/'), VAULT_FILE)
+}
+
+impl PhotoMetadata {
+    /// Metadata for a photo about to be uploaded, with EXIF read from
+    /// `content` and gaps filled from its XMP packet
+    pub fn for_upload(filename: &str, content: &[u8]) -> Self {
+        Self {
+            filename: filename.to_string(),
+            caption: None,
+            exif: photo_exif(content),
+            added_at: now_secs(),
+        }
+    }
+}
+
+impl MetadataVault {
+    pub fn seal(&self, album_key: &[u8; 32], album_id: &str) -> Result<Vec<u8>, AppError> {
+        let json = serde_json::to_vec(self).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        encrypt_with_key(&json, album_key, vault_aad(album_id).as_bytes())
+            .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))
+    }
+
+    pub fn open(album_key: &[u8; 32], album_id: &str, sealed: &[u8]) -> Result<Self, AppError> {
+        let json = decrypt_with_key(sealed, album_key, vault_aad(album_id).as_bytes())
+            .map_err(|e| AppError::Validation(format!("Metadata vault could not be decrypted: {}", e)))?;
+        serde_json::from_slice(&json).map_err(|e| AppError::Validation(format!("Corrupt metadata vault: {}", e)))
+    }
+
+    /// Set or clear the caption of a photo in the vault
+    pub fn set_caption(&mut self, blob_name: &str, caption: Option<&str>) -> Result<(), AppError> {
+        let caption = caption.map(str::trim).filter(|c| !c.is_empty());
+        if caption.is_some_and(|c| c.chars().count() > MAX_CAPTION_LEN) {
+            return Err(AppError::Validation(format!("Caption exceeds {} characters", MAX_CAPTION_LEN)));
+        }
+        let entry = self
+            .entries
+            .get_mut(blob_name)
+            .ok_or_else(|| AppError::Validation("Photo has no vault entry".into()))?;
+        entry.caption = caption.map(str::to_string);
+        Ok(())
+    }
+
+    /// Set the filename of a photo, creating its entry if needed
+    pub fn set_filename(&mut self, blob_name: &str, filename: &str) {
+        self.entries
+            .entry(blob_name.to_string())
+            .and_modify(|m| m.filename = filename.to_string())
+            .or_insert_with(|| PhotoMetadata {
+                filename: filename.to_string(),
+                caption: None,
+                exif: None,
+                added_at: now_secs(),
+            });
+    }
+
+    /// Drop entries of photos that are no longer in the album
+    pub fn retain_photos(&mut self, manifest: &AlbumManifest) {
+        self.entries.retain(|blob, _| manifest.entries.contains_key(blob));
+    }
+}
+
+// ============================================================================
+// EXIF
+// ============================================================================
+
+fn gps_degrees(exif: &exif::Exif, tag: exif::Tag, reference: exif::Tag, negative: &[u8]) -> Option<f64> {
+    let exif::Value::Rational(ref parts) = exif.get_field(tag, exif::In::PRIMARY)?.value else {
+        return None;
+    };
+    if parts.len() < 3 {
+        return None;
+    }
+    let degrees = parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0;
+    let sign = match exif.get_field(reference, exif::In::PRIMARY).map(|f| &f.value) {
+        Some(exif::Value::Ascii(values)) if values.first().is_some_and(|v| v.as_slice() == negative) => -1.0,
+        _ => 1.0,
+    };
+    degrees.is_finite().then_some(sign * degrees)
+}
+
+/// Read the EXIF fields of `ExifExtract` from a JPEG, TIFF, HEIF, PNG or WebP
+pub fn extract_exif(content: &[u8]) -> Option<ExifExtract> {
+    let exif = exif::Reader::new()
+        .read_from_container(&mut std::io::Cursor::new(content))
+        .ok()?;
+
+    let text = |tag| match exif.get_field(tag, exif::In::PRIMARY).map(|f| &f.value) {
+        Some(exif::Value::Ascii(values)) => values
+            .first()
+            .map(|v| String::from_utf8_lossy(v).trim().to_string())
+            .filter(|s| !s.is_empty()),
+        _ => None,
+    };
+    let rational = |tag| match exif.get_field(tag, exif::In::PRIMARY).map(|f| &f.value) {
+        Some(exif::Value::Rational(values)) => values.first().map(|r| r.to_f64()).filter(|v| v.is_finite()),
+        _ => None,
+    };
+    let uint = |tag| exif.get_field(tag, exif::In::PRIMARY).and_then(|f| f.value.get_uint(0));
+
+    let extract = ExifExtract {
+        taken_at: text(exif::Tag::DateTimeOriginal).or_else(|| text(exif::Tag::DateTime)),
+        digitized_at: text(exif::Tag::DateTimeDigitized),
+        modified_at: text(exif::Tag::DateTime),
+        time_offset: text(exif::Tag::OffsetTimeOriginal).or_else(|| text(exif::Tag::OffsetTime)),
+        camera_make: text(exif::Tag::Make),
+        camera_model: text(exif::Tag::Model),
+        lens_model: text(exif::Tag::LensModel),
+        focal_length: rational(exif::Tag::FocalLength),
+        f_number: rational(exif::Tag::FNumber),
+        exposure_time: rational(exif::Tag::ExposureTime),
+        iso: uint(exif::Tag::PhotographicSensitivity),
+        width: uint(exif::Tag::PixelXDimension).or_else(|| uint(exif::Tag::ImageWidth)),
+        height: uint(exif::Tag::PixelYDimension).or_else(|| uint(exif::Tag::ImageLength)),
+        orientation: uint(exif::Tag::Orientation),
+        latitude: gps_degrees(&exif, exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, b"S"),
+        longitude: gps_degrees(&exif, exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef, b"W"),
+        // Reference 1 means below sea level
+        altitude: rational(exif::Tag::GPSAltitude)
+            .map(|metres| if uint(exif::Tag::GPSAltitudeRef) == Some(1) { -metres } else { metres }),
+    };
+    (extract != ExifExtract::default()).then_some(extract)
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Load and decrypt an album's vault along with its blob SHA. Albums without
+/// a vault get an empty one.
+pub(crate) async fn fetch_vault(
+    client: &reqwest::Client,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+    album_key: &[u8; 32],
+) -> Result<(MetadataVault, Option<String>), AppError> {
+    let url = format!("https://api.github.com/repos/{}/contents/{}", repo, vault_path(album_path));
+    let json = match get_json(client, token, &url, "load metadata vault").await {
+        Ok(json) => json,
+        Err(AppError::Github(GithubError::NotFound { .. })) => return Ok((MetadataVault::default(), None)),
+        Err(e) => return Err(e),
+    };
+    let sha = json["sha"]
+        .as_str()
+        .ok_or_else(|| AppError::Api("Metadata vault has no SHA".into()))?
+        .to_string();
+    // The blob endpoint also serves files above the contents API's inline limit
+    let sealed = get_blob(client, repo, token, &sha).await?;
+    let vault = MetadataVault::open(album_key, &album_id(repo, album_path), &sealed)?;
+    Ok((vault, Some(sha)))
+}
+
+/// Prune, seal and write an album's vault
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn save_vault(
+    client: &reqwest::Client,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+    vault: &mut MetadataVault,
+    manifest: &AlbumManifest,
+    album_key: &[u8; 32],
+    sha: Option<&str>,
+) -> Result<UploadResult, AppError> {
+    vault.retain_photos(manifest);
+    let sealed = vault.seal(album_key, &album_id(repo, album_path))?;
+    let message = format!("Update metadata vault of {}", album_path.trim_matches('/'));
+    put_file_contents(client, repo, token, &vault_path(album_path), &sealed, &message, sha).await
+}
+
+/// Load and decrypt an album's vault from a tree index
+pub(crate) async fn load_vault(
+    client: &reqwest::Client,
+    repo: &str,
+    token: &str,
+    index: &TreeIndex,
+    album_path: &str,
+    album_key: &[u8; 32],
+) -> Result<MetadataVault, AppError> {
+    match index.get(&vault_path(album_path)) {
+        Some(entry) => {
+            let sealed = get_blob(client, repo, token, &entry.sha).await?;
+            MetadataVault::open(album_key, &album_id(repo, album_path), &sealed)
+        }
+        None => Ok(MetadataVault::default()),
+    }
+}
+
+/// Prune, seal and stage an album's vault as a new blob
+pub(crate) async fn stage_vault(
+    client: &reqwest::Client,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+    vault: &mut MetadataVault,
+    manifest: &AlbumManifest,
+    album_key: &[u8; 32],
+) -> Result<TreeChange, AppError> {
+    vault.retain_photos(manifest);
+    let sealed = vault.seal(album_key, &album_id(repo, album_path))?;
+    let sha = create_blob(client, repo, token, &sealed).await?;
+    Ok(TreeChange::blob(&vault_path(album_path), &sha))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Decrypted metadata of one photo in an encrypted album
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn get_photo_metadata(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    keypair_handle: KeypairHandle,
+) -> Result<Option<PhotoMetadata>, AppError> {
+    validate_repo(&repo)?;
+    let (album_path, blob_name) = split_blob_path(&path)?;
+    let (manifest, _) = fetch_manifest(&client.0, &repo, &token, album_path)
+        .await?
+        .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+    let key = album_key_for(keypair_handle, &repo, album_path, &manifest)?;
+    let (mut vault, _) = fetch_vault(&client.0, &repo, &token, album_path, &key).await?;
+    Ok(vault.entries.remove(blob_name))
+}
+
+/// Set or clear the caption of a photo in an encrypted album
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_photo_caption(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    caption: Option<String>,
+    keypair_handle: KeypairHandle,
+) -> Result<UploadResult, AppError> {
+    validate_repo(&repo)?;
+    let (album_path, blob_name) = split_blob_path(&path)?;
+    let (manifest, _) = fetch_manifest(&client.0, &repo, &token, album_path)
+        .await?
+        .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+    if !manifest.entries.contains_key(blob_name) {
+        return Err(GithubError::NotFound {
+            message: format!("Photo not found: {}", path),
+        }
+        .into());
+    }
+    let key = album_key_for(keypair_handle, &repo, album_path, &manifest)?;
+    let (mut vault, sha) = fetch_vault(&client.0, &repo, &token, album_path, &key).await?;
+    if !vault.entries.contains_key(blob_name) {
+        // Photos uploaded before the vault existed only have a sealed name
+        let name = crate::album::open_filename(&key, &album_id(&repo, album_path), &manifest.entries[blob_name])?;
+        vault.set_filename(blob_name, &name);
+    }
+    vault.set_caption(blob_name, caption.as_deref())?;
+    save_vault(&client.0, &repo, &token, album_path, &mut vault, &manifest, &key, sha.as_deref()).await
+}
+
+/// Album path and blob name of an encrypted photo path
+pub(crate) fn split_blob_path(path: &str) -> Result<(&str, &str), AppError> {
+    let path = path.trim_matches('/');
+    let album_path = parent_album_path(path);
+    let blob_name = path.rsplit('/').next().unwrap_or(path);
+    if album_path.is_empty() || path.contains("..") || !blob_name.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) {
+        return Err(AppError::Validation("Not a photo in an encrypted album".into()));
+    }
+    Ok((album_path, blob_name))
+}
@@ -0,0 +1,172 @@
+//! Local Store Schema Migrations
+//!
+//! The local store's schema (see `local_store`) is built by an ordered list
+//! of migrations, `MIGRATIONS`. Each one that has run is recorded in
+//! `schema_migrations` with a BLAKE3 checksum of its SQL, and the latest
+//! version is mirrored in `PRAGMA user_version` for outside tools.
+//!
+//! When the store opens:
+//!
+//! - SQLite's `quick_check` must pass
+//! - recorded migrations must match `MIGRATIONS` by version, name and
+//!   checksum, in order; a store written by a newer version of the app, or
+//!   by a migration that was since edited, is refused rather than guessed at
+//! - pending migrations run in order, each in its own transaction together
+//!   with its record, so an interrupted upgrade leaves the previous version
+//!
+//! The first migrations create what earlier versions created without
+//! tracking, with `IF NOT EXISTS`, so stores from before migrations adopt
+//! them in place. Shipped migrations are never edited: schema changes are
+//! new migrations at the end of the list.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::github::AppError;
+use crate::local_store::{db_error, with_store};
+use crate::util::now_secs;
+
+const MIGRATIONS_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    applied_at INTEGER NOT NULL
+);
+"#;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every migration, oldest first, numbered from 1 without gaps
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "entries", sql: crate::local_store::ENTRIES_SCHEMA },
+    Migration { version: 2, name: "catalog", sql: crate::catalog::CATALOG_SCHEMA },
+    Migration { version: 3, name: "smart_albums", sql: crate::smart_albums::SMART_ALBUM_SCHEMA },
+    Migration { version: 4, name: "download_cache", sql: crate::download_cache::DOWNLOAD_CACHE_SCHEMA },
+    Migration { version: 5, name: "ipfs_pins", sql: crate::ipfs::IPFS_SCHEMA },
+    Migration { version: 6, name: "storage_backups", sql: crate::storage_backend::STORAGE_BACKUP_SCHEMA },
+    Migration { version: 7, name: "activity_log", sql: crate::activity_log::ACTIVITY_LOG_SCHEMA },
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaVersion {
+    pub version: u32,
+    /// Version this build migrates to
+    pub latest: u32,
+    pub migrations: Vec<AppliedMigration>,
+}
+
+pub fn checksum(sql: &str) -> String {
+    blake3::hash(sql.as_bytes()).to_hex().to_string()
+}
+
+fn applied_migrations(conn: &Connection) -> Result<Vec<AppliedMigration>, AppError> {
+    let mut statement = conn
+        .prepare("SELECT version, name, checksum, applied_at FROM schema_migrations ORDER BY version")
+        .map_err(db_error)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok(AppliedMigration {
+                version: row.get(0)?,
+                name: row.get(1)?,
+                checksum: row.get(2)?,
+                applied_at: row.get::<_, i64>(3)? as u64,
+            })
+        })
+        .map_err(db_error)?;
+    rows.collect::<Result<_, _>>().map_err(db_error)
+}
+
+fn check_integrity(conn: &Connection) -> Result<(), AppError> {
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(db_error)?;
+    if result != "ok" {
+        return Err(AppError::Validation(format!("Local store is damaged: {}", result)));
+    }
+    Ok(())
+}
+
+/// Check that `applied` is a prefix of `migrations`
+fn check_history(applied: &[AppliedMigration], migrations: &[Migration]) -> Result<(), AppError> {
+    for (index, record) in applied.iter().enumerate() {
+        let Some(known) = migrations.get(index) else {
+            return Err(AppError::Validation(format!(
+                "Local store is at schema version {}, newer than this app's {}; update the app",
+                applied.last().map_or(0, |m| m.version),
+                migrations.last().map_or(0, |m| m.version),
+            )));
+        };
+        if record.version != known.version || record.name != known.name || record.checksum != checksum(known.sql) {
+            return Err(AppError::Validation(format!(
+                "Local store migration {} ({}) does not match this app's",
+                record.version, record.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Bring `conn` up to the last of `migrations`, returning how many ran
+pub fn migrate(conn: &Connection, migrations: &[Migration]) -> Result<usize, AppError> {
+    if let Some((index, m)) = migrations.iter().enumerate().find(|(i, m)| m.version as usize != i + 1) {
+        return Err(AppError::Validation(format!(
+            "Migration {} is out of order at position {}",
+            m.version,
+            index + 1
+        )));
+    }
+    check_integrity(conn)?;
+    conn.execute_batch(MIGRATIONS_SCHEMA).map_err(db_error)?;
+    let applied = applied_migrations(conn)?;
+    check_history(&applied, migrations)?;
+
+    let pending = &migrations[applied.len()..];
+    for migration in pending {
+        let tx = conn.unchecked_transaction().map_err(db_error)?;
+        tx.execute_batch(migration.sql).map_err(|e| {
+            AppError::Validation(format!("Migration {} ({}) failed: {}", migration.version, migration.name, e))
+        })?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?1, ?2, ?3, ?4)",
+            params![migration.version, migration.name, checksum(migration.sql), now_secs() as i64],
+        )
+        .map_err(db_error)?;
+        tx.pragma_update(None, "user_version", migration.version).map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+    }
+    Ok(pending.len())
+}
+
+pub fn schema_version(conn: &Connection) -> Result<SchemaVersion, AppError> {
+    let migrations = applied_migrations(conn)?;
+    Ok(SchemaVersion {
+        version: migrations.last().map_or(0, |m| m.version),
+        latest: MIGRATIONS.last().map_or(0, |m| m.version),
+        migrations,
+    })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Schema version of the active profile's local store and its migrations
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_db_schema_version() -> Result<SchemaVersion, AppError> {
+    with_store(|store| schema_version(store.connection()))
+}
@@ -0,0 +1,431 @@
+//! Album Mirrors
+//!
+//! An album can be mirrored to a second repository, possibly owned by another
+//! account or hosted on a GitHub-compatible server (`api_base`). Uploads and
+//! deletes in the album are replicated to the mirror in the background; a
+//! failed replication only shows up in the mirror's stats and never fails
+//! the original operation. `verify_mirror` compares both trees by blob SHA.
+//!
+//! Mirror configuration lives in `<profile data>/mirrors.json`.
+//! Mirror tokens are kept in the OS keychain so replication keeps working
+//! after a restart. LFS objects are not copied; the mirror receives their
+//! pointer files only.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::config_store::{ConfigList, TokenSlot};
+use crate::git_data::{branch_head, get_blob, get_tree_recursive, TreeChange};
+use crate::github::{response_error, validate_repo, AppError, Bearer, HttpClient};
+use crate::retry::SendWithRetry;
+use crate::sharing::album_id;
+
+pub const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+static MIRRORS: ConfigList<MirrorConfig> = ConfigList::new("mirrors.json", "mirror config");
+static MIRROR_TOKENS: TokenSlot = TokenSlot::new("mirror-token", "mirror");
+
+lazy_static::lazy_static! {
+    static ref MIRROR_STATS: Mutex<HashMap<String, MirrorStats>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorConfig {
+    pub repo: String,
+    pub album_path: String,
+    pub mirror_repo: String,
+    pub mirror_album_path: String,
+    /// REST API root of the mirror's host
+    #[serde(default = "default_api_base")]
+    pub api_base: String,
+}
+
+fn default_api_base() -> String {
+    DEFAULT_API_BASE.to_string()
+}
+
+impl MirrorConfig {
+    pub fn id(&self) -> String {
+        album_id(&self.repo, &self.album_path)
+    }
+
+    /// Mirror location of a primary path, if the path belongs to this album
+    pub fn mirror_path(&self, repo: &str, path: &str) -> Option<String> {
+        if repo != self.repo {
+            return None;
+        }
+        let rest = path
+            .trim_matches('/')
+            .strip_prefix(self.album_path.trim_matches('/'))?
+            .strip_prefix('/')?;
+        Some(format!("{}/{}", self.mirror_album_path.trim_matches('/'), rest))
+    }
+}
+
+/// Replication counters since the app started
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MirrorStats {
+    pub replicated: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirrorInfo {
+    pub config: MirrorConfig,
+    pub stats: MirrorStats,
+}
+
+/// Differences between an album and its mirror, as paths relative to the album
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorReport {
+    pub missing_on_mirror: Vec<String>,
+    pub extra_on_mirror: Vec<String>,
+    /// Present on both sides with different content
+    pub differing: Vec<String>,
+    pub in_sync: bool,
+}
+
+/// Compare two `relative path -> blob SHA` listings
+pub fn diff_listings(primary: &BTreeMap<String, String>, mirror: &BTreeMap<String, String>) -> MirrorReport {
+    let mut report = MirrorReport::default();
+
+    for (path, sha) in primary {
+        match mirror.get(path) {
+            None => report.missing_on_mirror.push(path.clone()),
+            Some(m) if m != sha => report.differing.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    report.extra_on_mirror = mirror.keys().filter(|p| !primary.contains_key(*p)).cloned().collect();
+    report.in_sync = report.missing_on_mirror.is_empty() && report.extra_on_mirror.is_empty() && report.differing.is_empty();
+    report
+}
+
+/// Blobs under `album`, keyed by path relative to it
+pub fn album_listing<'a>(entries: impl IntoIterator<Item = (&'a str, &'a str)>, album: &str) -> BTreeMap<String, String> {
+    let prefix = format!("{}/", album.trim_matches('/'));
+    entries
+        .into_iter()
+        .filter_map(|(path, sha)| Some((path.strip_prefix(&prefix)?.to_string(), sha.to_string())))
+        .collect()
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+/// Reload the mirror list from disk on next use, after a profile switch
+pub(crate) fn forget_cached_mirrors() {
+    MIRRORS.forget();
+    MIRROR_STATS.lock().unwrap().clear();
+}
+
+fn record(id: &str, result: &Result<(), AppError>) {
+    let mut stats = MIRROR_STATS.lock().unwrap();
+    let entry = stats.entry(id.to_string()).or_default();
+    match result {
+        Ok(()) => entry.replicated += 1,
+        Err(e) => {
+            entry.failed += 1;
+            entry.last_error = Some(e.to_string());
+        }
+    }
+}
+
+// ============================================================================
+// Mirror API Calls
+// ============================================================================
+
+fn contents_url(m: &MirrorConfig, path: &str) -> String {
+    format!("{}/repos/{}/contents/{}", m.api_base.trim_end_matches('/'), m.mirror_repo, path)
+}
+
+async fn mirror_get(client: &Client, m: &MirrorConfig, token: &str, url: &str) -> Result<Option<serde_json::Value>, AppError> {
+    let res = client
+        .get(url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+
+    if res.status() == 404 {
+        return Ok(None);
+    }
+    if !res.status().is_success() {
+        return Err(response_error(res, &format!("Mirror {} request failed", m.mirror_repo)).await);
+    }
+    Ok(Some(res.json().await?))
+}
+
+async fn mirror_sha(client: &Client, m: &MirrorConfig, token: &str, path: &str) -> Result<Option<String>, AppError> {
+    Ok(mirror_get(client, m, token, &contents_url(m, path))
+        .await?
+        .and_then(|json| json["sha"].as_str().map(|s| s.to_string())))
+}
+
+async fn mirror_put(client: &Client, m: &MirrorConfig, token: &str, path: &str, content: &[u8]) -> Result<(), AppError> {
+    let mut body = serde_json::json!({
+        "message": format!("Mirror {}", path),
+        "content": STANDARD.encode(content),
+    });
+    if let Some(sha) = mirror_sha(client, m, token, path).await? {
+        body["sha"] = serde_json::Value::String(sha);
+    }
+
+    let res = client
+        .put(contents_url(m, path))
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .json(&body)
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "Mirror upload failed").await);
+    }
+    Ok(())
+}
+
+async fn mirror_delete(client: &Client, m: &MirrorConfig, token: &str, path: &str) -> Result<(), AppError> {
+    let Some(sha) = mirror_sha(client, m, token, path).await? else {
+        return Ok(());
+    };
+
+    let res = client
+        .delete(contents_url(m, path))
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .json(&serde_json::json!({ "message": format!("Mirror delete {}", path), "sha": sha }))
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "Mirror delete failed").await);
+    }
+    Ok(())
+}
+
+/// Recursive blob listing of the mirror's default branch
+async fn mirror_tree(client: &Client, m: &MirrorConfig, token: &str) -> Result<Vec<(String, String)>, AppError> {
+    let base = m.api_base.trim_end_matches('/');
+    let repo_url = format!("{}/repos/{}", base, m.mirror_repo);
+    let repo = mirror_get(client, m, token, &repo_url)
+        .await?
+        .ok_or_else(|| AppError::Api(format!("Mirror repository {} not found", m.mirror_repo)))?;
+    let branch = repo["default_branch"].as_str().unwrap_or("main");
+
+    let tree_url = format!("{}/git/trees/{}?recursive=1", repo_url, branch);
+    let Some(tree) = mirror_get(client, m, token, &tree_url).await? else {
+        // Empty repositories have no tree yet
+        return Ok(Vec::new());
+    };
+    if tree["truncated"].as_bool().unwrap_or(false) {
+        return Err(AppError::Api("Mirror tree is too large to list in one request".into()));
+    }
+
+    Ok(tree["tree"]
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|e| e["type"] == "blob")
+                .filter_map(|e| Some((e["path"].as_str()?.to_string(), e["sha"].as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+// ============================================================================
+// Replication
+// ============================================================================
+
+enum Replication {
+    Put(Vec<u8>),
+    /// Copy a blob from the primary repository
+    Blob(String),
+    Delete,
+}
+
+fn spawn_replication(client: &Client, repo: &str, token: &str, path: &str, op: impl Fn() -> Replication) {
+    let targets: Vec<(MirrorConfig, String)> = MIRRORS.with(|mirrors| {
+        mirrors
+            .iter()
+            .filter_map(|m| m.mirror_path(repo, path).map(|p| (m.clone(), p)))
+            .collect()
+    })
+    .unwrap_or_default();
+
+    for (mirror, mirror_path) in targets {
+        let client = client.clone();
+        let repo = repo.to_string();
+        let token = Zeroizing::new(token.to_string());
+        let op = op();
+
+        tauri::async_runtime::spawn(async move {
+            let id = mirror.id();
+            let result = async {
+                let mirror_token = MIRROR_TOKENS.load(&id)?;
+                match op {
+                    Replication::Put(content) => mirror_put(&client, &mirror, &mirror_token, &mirror_path, &content).await,
+                    Replication::Blob(sha) => {
+                        let content = get_blob(&client, &repo, &token, &sha).await?;
+                        mirror_put(&client, &mirror, &mirror_token, &mirror_path, &content).await
+                    }
+                    Replication::Delete => mirror_delete(&client, &mirror, &mirror_token, &mirror_path).await,
+                }
+            }
+            .await;
+            if let Err(e) = &result {
+                tracing::warn!("Mirror replication to {} failed: {}", mirror.mirror_repo, e);
+            }
+            record(&id, &result);
+        });
+    }
+}
+
+/// Replicate a file written to `repo` to any mirror covering its album
+pub(crate) fn replicate_put(client: &Client, repo: &str, token: &str, path: &str, content: &[u8]) {
+    spawn_replication(client, repo, token, path, || Replication::Put(content.to_vec()));
+}
+
+pub(crate) fn replicate_delete(client: &Client, repo: &str, token: &str, path: &str) {
+    spawn_replication(client, repo, token, path, || Replication::Delete);
+}
+
+/// Replicate the effect of a Git data API commit
+pub(crate) fn replicate_tree_changes(client: &Client, repo: &str, token: &str, changes: &[TreeChange]) {
+    for change in changes {
+        match &change.sha {
+            Some(sha) => spawn_replication(client, repo, token, &change.path, || Replication::Blob(sha.clone())),
+            None => replicate_delete(client, repo, token, &change.path),
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Register (or replace) the mirror of an album. The mirror token is stored
+/// in the OS keychain.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn configure_mirror(
+    client: State<'_, HttpClient>,
+    repo: String,
+    album_path: String,
+    mirror_repo: String,
+    mirror_token: Zeroizing<String>,
+    mirror_album_path: Option<String>,
+    api_base: Option<String>,
+) -> Result<MirrorConfig, AppError> {
+    validate_repo(&repo)?;
+    validate_repo(&mirror_repo)?;
+
+    let album_path = album_path.trim_matches('/').to_string();
+    let mirror_album_path = mirror_album_path
+        .map(|p| p.trim_matches('/').to_string())
+        .unwrap_or_else(|| album_path.clone());
+    if [&album_path, &mirror_album_path].iter().any(|p| p.is_empty() || p.contains("..")) {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    let api_base = api_base.unwrap_or_else(default_api_base);
+    if !api_base.starts_with("https://") {
+        return Err(AppError::Validation("Mirror API base must use HTTPS".into()));
+    }
+
+    let config = MirrorConfig {
+        repo,
+        album_path,
+        mirror_repo,
+        mirror_album_path,
+        api_base,
+    };
+    if config.repo == config.mirror_repo && config.api_base == DEFAULT_API_BASE {
+        return Err(AppError::Validation("An album cannot be mirrored to its own repository".into()));
+    }
+
+    // Fail early on a wrong token or repository name
+    let repo_url = format!("{}/repos/{}", config.api_base.trim_end_matches('/'), config.mirror_repo);
+    mirror_get(&client.0, &config, &mirror_token, &repo_url)
+        .await?
+        .ok_or_else(|| AppError::Validation(format!("Mirror repository {} not found", config.mirror_repo)))?;
+
+    MIRROR_TOKENS.store(&config.id(), &mirror_token)?;
+
+    MIRRORS.update(|mirrors| {
+        mirrors.retain(|m| m.id() != config.id());
+        mirrors.push(config.clone());
+    })?;
+
+    Ok(config)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn remove_mirror(repo: String, album_path: String) -> Result<(), AppError> {
+    let id = album_id(&repo, &album_path);
+    MIRRORS.update(|mirrors| mirrors.retain(|m| m.id() != id))?;
+    MIRROR_TOKENS.delete(&id);
+    MIRROR_STATS.lock().unwrap().remove(&id);
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn list_mirrors() -> Result<Vec<MirrorInfo>, AppError> {
+    let configs = MIRRORS.with(|mirrors| mirrors.to_vec())?;
+    let stats = MIRROR_STATS.lock().unwrap();
+    Ok(configs
+        .into_iter()
+        .map(|config| MirrorInfo {
+            stats: stats.get(&config.id()).cloned().unwrap_or_default(),
+            config,
+        })
+        .collect())
+}
+
+/// Compare an album with its mirror
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn verify_mirror(
+    client: State<'_, HttpClient>,
+    repo: String,
+    album_path: String,
+    token: Zeroizing<String>,
+) -> Result<MirrorReport, AppError> {
+    validate_repo(&repo)?;
+
+    let id = album_id(&repo, &album_path);
+    let config = MIRRORS.with(|mirrors| mirrors.iter().find(|m| m.id() == id).cloned())?
+        .ok_or_else(|| AppError::Validation("Album has no mirror".into()))?;
+    let mirror_token = MIRROR_TOKENS.load(&id)?;
+
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let primary_tree = get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?;
+    let primary = album_listing(
+        primary_tree
+            .iter()
+            .filter(|e| e.kind == "blob")
+            .map(|e| (e.path.as_str(), e.sha.as_str())),
+        &config.album_path,
+    );
+
+    let mirror_tree = mirror_tree(&client.0, &config, &mirror_token).await?;
+    let mirror = album_listing(
+        mirror_tree.iter().map(|(p, s)| (p.as_str(), s.as_str())),
+        &config.mirror_album_path,
+    );
+
+    Ok(diff_listings(&primary, &mirror))
+}
@@ -0,0 +1,480 @@
+//! Offline Operation Queue
+//!
+//! Uploads and deletes issued while offline are persisted under
+//! `<profile data>/offline_queue/` and replayed once GitHub is
+//! reachable again. Upload content is snapshotted into the queue directory so
+//! later edits or moves of the local file do not change what gets uploaded.
+//!
+//! Every operation records the remote blob SHA the user last saw. Before
+//! replaying, the current SHA is compared; if the remote changed meanwhile
+//! the operation is marked as a conflict instead of being applied.
+//!
+//! Tokens are never written to disk. They are kept in memory for the session
+//! and the background worker only replays repositories it has a token for;
+//! after a restart the frontend calls `replay_pending_operations`.
+
+use rand::RngCore;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::catalog::{forget_photo, note_sync_state, SyncState};
+use crate::github::{put_file_contents, response_error, validate_repo, AppError, Bearer, HttpClient};
+use crate::mirror::replicate_delete;
+use crate::retry::SendWithRetry;
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::util::now_secs;
+
+const QUEUE_FILE: &str = "queue.json";
+/// How often the background worker checks connectivity
+const REPLAY_INTERVAL_SECS: u64 = 30;
+
+lazy_static::lazy_static! {
+    static ref QUEUE_LOCK: Mutex<()> = Mutex::new(());
+    static ref SESSION_TOKENS: Mutex<HashMap<String, Zeroizing<String>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueuedAction {
+    /// Upload a snapshot stored in the queue directory under the operation id
+    Upload { remote_path: String, source_name: String },
+    Delete { remote_path: String },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Pending,
+    /// The remote file changed since the operation was queued
+    Conflict,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QueuedOperation {
+    pub id: String,
+    pub repo: String,
+    pub action: QueuedAction,
+    /// Remote blob SHA when queued; `None` means the path was expected not to exist
+    pub expected_sha: Option<String>,
+    pub queued_at: u64,
+    pub attempts: u32,
+    pub status: OperationStatus,
+    pub last_error: Option<String>,
+}
+
+impl QueuedOperation {
+    pub fn remote_path(&self) -> &str {
+        match &self.action {
+            QueuedAction::Upload { remote_path, .. } | QueuedAction::Delete { remote_path } => remote_path,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OfflineQueue {
+    pub operations: Vec<QueuedOperation>,
+}
+
+/// What to do with a queued operation given the current remote state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayDecision {
+    Apply,
+    Conflict,
+    /// Nothing left to do (e.g. deleting a file that is already gone)
+    AlreadyApplied,
+}
+
+pub fn replay_decision(action: &QueuedAction, expected: Option<&str>, remote: Option<&str>) -> ReplayDecision {
+    match action {
+        QueuedAction::Delete { .. } => match (expected, remote) {
+            (_, None) => ReplayDecision::AlreadyApplied,
+            (Some(e), Some(r)) if e == r => ReplayDecision::Apply,
+            _ => ReplayDecision::Conflict,
+        },
+        QueuedAction::Upload { .. } => {
+            if expected == remote {
+                ReplayDecision::Apply
+            } else {
+                ReplayDecision::Conflict
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn queue_dir() -> Result<PathBuf, AppError> {
+    let dir = crate::profiles::data_dir()?.join("offline_queue");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+impl OfflineQueue {
+    pub fn load_from(dir: &Path) -> Result<Self, AppError> {
+        let path = dir.join(QUEUE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read(&path)?;
+        serde_json::from_slice(&raw).map_err(|e| AppError::Validation(format!("Corrupt offline queue: {}", e)))
+    }
+
+    /// Write atomically via a temp file so a crash never leaves a torn queue
+    pub fn save_to(&self, dir: &Path) -> Result<(), AppError> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        let tmp = dir.join(format!("{}.tmp", QUEUE_FILE));
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, dir.join(QUEUE_FILE))?;
+        Ok(())
+    }
+
+    /// Remove an operation and its snapshot, if any
+    pub fn remove(&mut self, dir: &Path, id: &str) {
+        if let Some(pos) = self.operations.iter().position(|op| op.id == id) {
+            let op = self.operations.remove(pos);
+            if matches!(op.action, QueuedAction::Upload { .. }) {
+                let _ = std::fs::remove_file(dir.join(&op.id));
+            }
+        }
+    }
+}
+
+fn with_queue<T>(f: impl FnOnce(&Path, &mut OfflineQueue) -> Result<T, AppError>) -> Result<T, AppError> {
+    let _guard = QUEUE_LOCK.lock().unwrap();
+    let dir = queue_dir()?;
+    let mut queue = OfflineQueue::load_from(&dir)?;
+    let result = f(&dir, &mut queue)?;
+    queue.save_to(&dir)?;
+    Ok(result)
+}
+
+fn new_operation_id() -> String {
+    let mut bytes = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn remember_token(repo: &str, token: &str) {
+    SESSION_TOKENS
+        .lock()
+        .unwrap()
+        .insert(repo.to_string(), Zeroizing::new(token.to_string()));
+}
+
+fn validate_remote_path(path: &str) -> Result<String, AppError> {
+    let path = path.trim().trim_matches('/');
+    if path.is_empty() || path.split('/').any(|s| s.is_empty() || s == "..") {
+        return Err(AppError::Validation("Invalid remote path".into()));
+    }
+    Ok(path.to_string())
+}
+
+// ============================================================================
+// Replay
+// ============================================================================
+
+/// Current blob SHA of a remote path, `None` if it does not exist
+pub(crate) async fn remote_sha(client: &Client, repo: &str, token: &str, path: &str) -> Result<Option<String>, AppError> {
+    let url = format!("https://api.github.com/repos/{}/contents/{}", repo, path);
+    let res = client
+        .get(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+
+    if res.status() == 404 {
+        return Ok(None);
+    }
+    if !res.status().is_success() {
+        return Err(response_error(res, "Failed to get file info").await);
+    }
+
+    let json: serde_json::Value = res.json().await?;
+    Ok(json["sha"].as_str().map(|s| s.to_string()))
+}
+
+async fn delete_remote(client: &Client, repo: &str, token: &str, path: &str, sha: &str) -> Result<(), AppError> {
+    let url = format!("https://api.github.com/repos/{}/contents/{}", repo, path);
+    let res = client
+        .delete(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .json(&serde_json::json!({ "message": format!("Delete {}", path), "sha": sha }))
+        .send_with_retry()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "Failed to delete file").await);
+    }
+    replicate_delete(client, repo, token, path);
+    Ok(())
+}
+
+async fn apply_operation(client: &Client, token: &str, dir: &Path, op: &QueuedOperation) -> Result<ReplayDecision, AppError> {
+    let current = remote_sha(client, &op.repo, token, op.remote_path()).await?;
+    let decision = replay_decision(&op.action, op.expected_sha.as_deref(), current.as_deref());
+
+    if decision == ReplayDecision::Apply {
+        match &op.action {
+            QueuedAction::Upload { remote_path, .. } => {
+                let content = tokio::fs::read(dir.join(&op.id)).await?;
+                let message = format!("Upload {}", remote_path);
+                put_file_contents(client, &op.repo, token, remote_path, &content, &message, current.as_deref()).await?;
+            }
+            QueuedAction::Delete { remote_path } => {
+                delete_remote(client, &op.repo, token, remote_path, current.as_deref().unwrap_or_default()).await?;
+            }
+        }
+    }
+
+    Ok(decision)
+}
+
+/// Replay pending operations for repos we have a token for.
+/// Stops at the first network error so the order of operations is preserved.
+async fn replay(client: &Client, tokens: &HashMap<String, Zeroizing<String>>) -> Result<usize, AppError> {
+    let pending: Vec<QueuedOperation> = with_queue(|_, q| {
+        Ok(q.operations
+            .iter()
+            .filter(|op| op.status == OperationStatus::Pending && tokens.contains_key(&op.repo))
+            .cloned()
+            .collect())
+    })?;
+
+    let dir = queue_dir()?;
+    let mut applied = 0;
+
+    for op in pending {
+        let token = &tokens[&op.repo];
+        let outcome = apply_operation(client, token, &dir, &op).await;
+
+        let offline = matches!(outcome, Err(AppError::Network(_) | AppError::Unavailable(_)));
+        with_queue(|dir, q| {
+            match &outcome {
+                Ok(ReplayDecision::Apply) | Ok(ReplayDecision::AlreadyApplied) => {
+                    q.remove(dir, &op.id);
+                    applied += 1;
+                }
+                Ok(ReplayDecision::Conflict) => {
+                    if let Some(entry) = q.operations.iter_mut().find(|o| o.id == op.id) {
+                        entry.status = OperationStatus::Conflict;
+                        entry.last_error = Some("Remote file changed since the operation was queued".into());
+                    }
+                }
+                Err(e) => {
+                    if let Some(entry) = q.operations.iter_mut().find(|o| o.id == op.id) {
+                        entry.attempts += 1;
+                        entry.last_error = Some(e.to_string());
+                        if !offline {
+                            entry.status = OperationStatus::Failed;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })?;
+
+        if let (Ok(ReplayDecision::Apply | ReplayDecision::AlreadyApplied), QueuedAction::Delete { remote_path }) =
+            (&outcome, &op.action)
+        {
+            forget_photo(&op.repo, remote_path);
+        }
+
+        if offline {
+            break;
+        }
+    }
+
+    Ok(applied)
+}
+
+async fn is_online(client: &Client) -> bool {
+    client
+        .get("https://api.github.com")
+        .header("User-Agent", "vortex-image")
+        .timeout(Duration::from_secs(10))
+        .send_with_retry()
+        .await
+        .is_ok()
+}
+
+/// Background task that replays the queue whenever GitHub is reachable
+pub fn start_replay_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(REPLAY_INTERVAL_SECS)).await;
+
+            let tokens: HashMap<String, Zeroizing<String>> = SESSION_TOKENS
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(repo, token)| (repo.clone(), token.clone()))
+                .collect();
+            if tokens.is_empty() {
+                continue;
+            }
+
+            let client = app.state::<HttpClient>().0.clone();
+            if !is_online(&client).await {
+                continue;
+            }
+
+            let _permit = SCHEDULER.acquire(JobClass::Background).await;
+            if let Ok(applied) = replay(&client, &tokens).await {
+                if applied > 0 {
+                    let _ = app.emit("offline-queue-updated", applied);
+                }
+            }
+        }
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Queue an upload of in-memory content (used by background uploaders)
+pub(crate) fn enqueue_upload_bytes(
+    repo: &str,
+    token: &str,
+    content: &[u8],
+    source_name: &str,
+    remote_path: &str,
+    expected_sha: Option<String>,
+) -> Result<QueuedOperation, AppError> {
+    validate_repo(repo)?;
+    let remote_path = validate_remote_path(remote_path)?;
+    remember_token(repo, token);
+
+    with_queue(|dir, q| {
+        let id = new_operation_id();
+        std::fs::write(dir.join(&id), content)?;
+
+        let op = QueuedOperation {
+            id,
+            repo: repo.to_string(),
+            action: QueuedAction::Upload {
+                remote_path,
+                source_name: source_name.to_string(),
+            },
+            expected_sha,
+            queued_at: now_secs(),
+            attempts: 0,
+            status: OperationStatus::Pending,
+            last_error: None,
+        };
+        q.operations.push(op.clone());
+        Ok(op)
+    })
+}
+
+/// Queue an upload of a local file. `expected_sha` is the remote SHA the user
+/// is replacing, or `None` for a new file.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn queue_upload(
+    repo: String,
+    token: Zeroizing<String>,
+    local_path: String,
+    remote_path: String,
+    expected_sha: Option<String>,
+) -> Result<QueuedOperation, AppError> {
+    let content = std::fs::read(&local_path)?;
+    let source_name = Path::new(&local_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("photo")
+        .to_string();
+
+    enqueue_upload_bytes(&repo, &token, &content, &source_name, &remote_path, expected_sha)
+}
+
+/// Queue deletion of a remote file the user last saw at `expected_sha`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn queue_delete(
+    repo: String,
+    token: Zeroizing<String>,
+    remote_path: String,
+    expected_sha: String,
+) -> Result<QueuedOperation, AppError> {
+    validate_repo(&repo)?;
+    let remote_path = validate_remote_path(&remote_path)?;
+    remember_token(&repo, &token);
+
+    with_queue(|_, q| {
+        let op = QueuedOperation {
+            id: new_operation_id(),
+            repo,
+            action: QueuedAction::Delete { remote_path: remote_path.clone() },
+            expected_sha: Some(expected_sha),
+            queued_at: now_secs(),
+            attempts: 0,
+            status: OperationStatus::Pending,
+            last_error: None,
+        };
+        q.operations.push(op.clone());
+        Ok(op)
+    })
+    .inspect(|op| note_sync_state(&op.repo, &remote_path, SyncState::PendingDelete))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_pending_operations() -> Result<Vec<QueuedOperation>, AppError> {
+    with_queue(|_, q| Ok(q.operations.clone()))
+}
+
+/// Replay queued operations for `repo` now. Returns how many were applied.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn replay_pending_operations(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+) -> Result<usize, AppError> {
+    validate_repo(&repo)?;
+    remember_token(&repo, &token);
+
+    // Failed operations get another chance on an explicit replay
+    with_queue(|_, q| {
+        for op in q.operations.iter_mut().filter(|op| op.repo == repo && op.status == OperationStatus::Failed) {
+            op.status = OperationStatus::Pending;
+        }
+        Ok(())
+    })?;
+
+    let tokens = HashMap::from([(repo, token)]);
+    replay(&client.0, &tokens).await
+}
+
+/// Drop a queued operation, e.g. after resolving a conflict manually
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn discard_pending_operation(id: String) -> Result<(), AppError> {
+    let discarded = with_queue(|dir, q| {
+        let op = q.operations.iter().find(|op| op.id == id).cloned();
+        q.remove(dir, &id);
+        Ok(op)
+    })?;
+
+    // A discarded delete leaves the photo where it was
+    if let Some(QueuedOperation { repo, action: QueuedAction::Delete { remote_path }, .. }) = discarded {
+        note_sync_state(&repo, &remote_path, SyncState::Synced);
+    }
+    Ok(())
+}
@@ -0,0 +1,370 @@
+//! Photo Rename and Move
+//!
+//! Renames and moves are single Git commits built with the Git data API, so
+//! plain photos keep their blob SHA and are never re-uploaded. Album
+//! manifests touched by the operation (cover photo, encrypted entries) are
+//! updated in the same commit.
+//!
+//! Encrypted photos are stored under opaque blob names, so renaming one only
+//! re-seals its name in the album manifest and metadata vault and leaves the
+//! payload untouched.
+//! Moving one between encrypted albums cannot preserve the payload: it is
+//! bound to its album key, so the photo is decrypted and re-sealed locally
+//! for the destination album and only the new ciphertext is uploaded. Its
+//! caption and EXIF move to the destination's metadata vault.
+//!
+//! Detached photo signatures move along with their photo; re-sealed photos
+//! are signed again. Manifests are signed when a keypair is given.
+
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::album::{
+    album_key_for, encrypted_blob_name, manifest_body, open_album_photo, open_filename, parent_album_path,
+    seal_album_photo, seal_filename, AlbumManifest, ALBUM_MANIFEST_FILE, ALBUM_ROOT, ENCRYPTED_BLOB_EXT,
+};
+use crate::crypto::KeypairHandle;
+use crate::git_data::{
+    branch_head, commit_changes, create_blob, get_blob, get_tree_recursive, index_blobs, BranchHead, TreeChange,
+    TreeIndex,
+};
+use crate::github::{sanitize_filename, validate_repo, AppError, GithubError, HttpClient};
+use crate::metadata_vault::{load_vault, stage_vault, PhotoMetadata};
+use crate::mirror::replicate_tree_changes;
+use crate::security_verify::{move_signature, sign_photo, signature_path};
+use crate::sharing::album_id;
+
+// ============================================================================
+// Planning
+// ============================================================================
+
+fn normalize(path: &str) -> Result<String, AppError> {
+    let trimmed = path.trim().trim_matches('/');
+    if trimmed.is_empty() || trimmed.split('/').any(|s| s.is_empty() || s == "." || s == "..") {
+        return Err(AppError::Validation(format!("Invalid path: {}", path)));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+pub fn is_encrypted_blob(path: &str) -> bool {
+    path.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT))
+}
+
+/// Sanitized display name for a photo. Plain names must not collide with
+/// the manifest or look like encrypted blobs.
+pub fn validate_photo_name(name: &str) -> Result<String, AppError> {
+    let name = sanitize_filename(name.trim());
+    if name.is_empty() || name.starts_with('.') || name == ALBUM_MANIFEST_FILE || is_encrypted_blob(&name) {
+        return Err(AppError::Validation("Invalid photo name".into()));
+    }
+    Ok(name)
+}
+
+/// New path of a plain photo renamed within its folder
+pub fn rename_target(path: &str, new_name: &str) -> Result<String, AppError> {
+    let path = normalize(path)?;
+    let name = validate_photo_name(new_name)?;
+    let target = match parent_album_path(&path) {
+        "" => name,
+        parent => format!("{}/{}", parent, name),
+    };
+    if target == path {
+        return Err(AppError::Validation("Photo already has that name".into()));
+    }
+    Ok(target)
+}
+
+/// New path of a photo moved into `destination_album`, keeping its file name
+pub fn move_target(path: &str, destination_album: &str) -> Result<String, AppError> {
+    let path = normalize(path)?;
+    let destination = normalize(destination_album)?;
+    if !destination.starts_with(&format!("{}/", ALBUM_ROOT)) {
+        return Err(AppError::Validation(format!("Albums must live under {}/", ALBUM_ROOT)));
+    }
+    if parent_album_path(&path) == destination {
+        return Err(AppError::Validation("Photo is already in that album".into()));
+    }
+    Ok(format!("{}/{}", destination, file_name(&path)))
+}
+
+/// Point the manifest cover at the photo's new name, or clear it when the
+/// photo left the album. Returns whether the manifest changed.
+pub fn retarget_cover(manifest: &mut AlbumManifest, old_name: &str, new_name: Option<&str>) -> bool {
+    if manifest.cover.as_deref() != Some(old_name) {
+        return false;
+    }
+    manifest.cover = new_name.map(str::to_string);
+    true
+}
+
+// ============================================================================
+// Tree Helpers
+// ============================================================================
+
+fn manifest_path(album_path: &str) -> String {
+    format!("{}/{}", album_path, ALBUM_MANIFEST_FILE)
+}
+
+/// Read an album manifest from the tree being edited
+async fn load_manifest(
+    client: &HttpClient,
+    repo: &str,
+    token: &str,
+    index: &TreeIndex,
+    album_path: &str,
+) -> Result<Option<AlbumManifest>, AppError> {
+    let Some(entry) = index.get(&manifest_path(album_path)) else {
+        return Ok(None);
+    };
+    let raw = get_blob(&client.0, repo, token, &entry.sha).await?;
+    serde_json::from_slice(&raw)
+        .map(Some)
+        .map_err(|e| AppError::Validation(format!("Invalid album manifest: {}", e)))
+}
+
+/// Sign and stage an updated manifest as a new blob
+async fn stage_manifest(
+    client: &HttpClient,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+    manifest: &mut AlbumManifest,
+    signer: Option<KeypairHandle>,
+) -> Result<TreeChange, AppError> {
+    let sha = create_blob(&client.0, repo, token, &manifest_body(manifest, signer)?).await?;
+    Ok(TreeChange::blob(&manifest_path(album_path), &sha))
+}
+
+fn not_found(path: &str) -> AppError {
+    GithubError::NotFound {
+        message: format!("Photo not found: {}", path),
+    }
+    .into()
+}
+
+async fn commit(
+    client: &HttpClient,
+    repo: &str,
+    token: &str,
+    head: &BranchHead,
+    changes: &[TreeChange],
+    message: &str,
+) -> Result<(), AppError> {
+    commit_changes(&client.0, repo, token, head, changes, message).await?;
+    replicate_tree_changes(&client.0, repo, token, changes);
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Rename a photo in place. Returns the photo's path afterwards, which is
+/// unchanged for encrypted photos since only their sealed name changes.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn rename_photo(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    new_name: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<String, AppError> {
+    validate_repo(&repo)?;
+
+    let path = normalize(&path)?;
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let entry = index.get(&path).ok_or_else(|| not_found(&path))?;
+    let album_path = parent_album_path(&path);
+
+    if is_encrypted_blob(&path) {
+        let handle = keypair_handle
+            .ok_or_else(|| AppError::Validation("Photo is encrypted; a keypair is required".into()))?;
+        let name = validate_photo_name(&new_name)?;
+        let mut manifest = load_manifest(&client, &repo, &token, &index, album_path)
+            .await?
+            .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+        let blob_name = file_name(&path).to_string();
+        if !manifest.entries.contains_key(&blob_name) {
+            return Err(not_found(&path));
+        }
+
+        let key = album_key_for(handle, &repo, album_path, &manifest)?;
+        let sealed = seal_filename(&key, &album_id(&repo, album_path), &name)?;
+        manifest.entries.insert(blob_name.clone(), sealed);
+
+        let mut vault = load_vault(&client.0, &repo, &token, &index, album_path, &key).await?;
+        vault.set_filename(&blob_name, &name);
+
+        let changes = vec![
+            stage_manifest(&client, &repo, &token, album_path, &mut manifest, Some(handle)).await?,
+            stage_vault(&client.0, &repo, &token, album_path, &mut vault, &manifest, &key).await?,
+        ];
+        // The commit message must not reveal the plaintext name
+        commit(&client, &repo, &token, &head, &changes, &format!("Rename photo in {}", album_path)).await?;
+        record_activity(ActivityKind::Rename, "rename_photo", Some(&repo), &path, None);
+        return Ok(path);
+    }
+
+    let target = rename_target(&path, &new_name)?;
+    if index.contains_key(&target) {
+        return Err(AppError::Validation("A photo with that name already exists".into()));
+    }
+
+    let mut changes = vec![TreeChange::put_blob(&target, entry), TreeChange::delete(&path)];
+    changes.extend(move_signature(&index, &path, &target));
+    if let Some(mut manifest) = load_manifest(&client, &repo, &token, &index, album_path).await? {
+        let listed = manifest.signed.remove(file_name(&path));
+        let relisted = listed.is_some();
+        if let Some(hash) = listed {
+            manifest.signed.insert(file_name(&target).to_string(), hash);
+        }
+        if retarget_cover(&mut manifest, file_name(&path), Some(file_name(&target))) || relisted {
+            changes.push(stage_manifest(&client, &repo, &token, album_path, &mut manifest, keypair_handle).await?);
+        }
+    }
+
+    commit(&client, &repo, &token, &head, &changes, &format!("Rename {} to {}", path, file_name(&target))).await?;
+    record_activity(ActivityKind::Rename, "rename_photo", Some(&repo), &path, Some(format!("to {}", target)));
+    Ok(target)
+}
+
+/// Move a photo into another album in a single commit. Plain photos keep
+/// their blob; encrypted photos can only move between encrypted albums and
+/// are re-sealed for the destination. Returns the photo's new path.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn move_photo_between_albums(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    destination_album: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<String, AppError> {
+    validate_repo(&repo)?;
+
+    let path = normalize(&path)?;
+    let target = move_target(&path, &destination_album)?;
+    let source_album = parent_album_path(&path).to_string();
+    let destination = parent_album_path(&target).to_string();
+
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let entry = index.get(&path).ok_or_else(|| not_found(&path))?;
+
+    let source_manifest = load_manifest(&client, &repo, &token, &index, &source_album).await?;
+    let dest_manifest = load_manifest(&client, &repo, &token, &index, &destination).await?;
+    let dest_encrypted = dest_manifest.as_ref().is_some_and(|m| m.encrypted);
+    let mut changes = Vec::new();
+
+    let new_path = if is_encrypted_blob(&path) {
+        let handle = keypair_handle
+            .ok_or_else(|| AppError::Validation("Photo is encrypted; a keypair is required".into()))?;
+        let (Some(mut source_manifest), Some(mut dest_manifest)) = (source_manifest, dest_manifest) else {
+            return Err(AppError::Validation("Encrypted photos can only move between encrypted albums".into()));
+        };
+        if !dest_manifest.encrypted {
+            return Err(AppError::Validation("Encrypted photos can only move between encrypted albums".into()));
+        }
+
+        let blob_name = file_name(&path).to_string();
+        let source_id = album_id(&repo, &source_album);
+        let dest_id = album_id(&repo, &destination);
+        let source_key = album_key_for(handle, &repo, &source_album, &source_manifest)?;
+        let dest_key = album_key_for(handle, &repo, &destination, &dest_manifest)?;
+
+        let payload = get_blob(&client.0, &repo, &token, &entry.sha).await?;
+        let (data, payload_name) = open_album_photo(&source_key, &source_id, &payload)?;
+        let name = source_manifest
+            .entries
+            .get(&blob_name)
+            .and_then(|sealed| open_filename(&source_key, &source_id, sealed).ok())
+            .or(payload_name)
+            .unwrap_or_else(|| "photo".to_string());
+
+        let new_blob = encrypted_blob_name(&dest_key, &data);
+        if dest_manifest.entries.contains_key(&new_blob) {
+            return Err(AppError::Validation("Photo already exists in the destination album".into()));
+        }
+        let sealed_payload = seal_album_photo(&dest_key, &dest_id, &name, &data)?;
+        let new_path = format!("{}/{}", destination, new_blob);
+        let sha = create_blob(&client.0, &repo, &token, &sealed_payload).await?;
+        let signature = create_blob(&client.0, &repo, &token, &sign_photo(handle, &sealed_payload)?).await?;
+        changes.push(TreeChange::blob(&new_path, &sha));
+        changes.push(TreeChange::blob(&signature_path(&new_path), &signature));
+        changes.push(TreeChange::delete(&path));
+        if index.contains_key(&signature_path(&path)) {
+            changes.push(TreeChange::delete(&signature_path(&path)));
+        }
+
+        source_manifest.entries.remove(&blob_name);
+        source_manifest.signed.remove(&blob_name);
+        source_manifest.original_bytes = source_manifest.original_bytes.saturating_sub(data.len() as u64);
+        source_manifest.stored_bytes = source_manifest.stored_bytes.saturating_sub(payload.len() as u64);
+        retarget_cover(&mut source_manifest, &blob_name, None);
+
+        dest_manifest.entries.insert(new_blob.clone(), seal_filename(&dest_key, &dest_id, &name)?);
+        dest_manifest.record_signed(&new_blob, &sealed_payload);
+        dest_manifest.original_bytes += data.len() as u64;
+        dest_manifest.stored_bytes += sealed_payload.len() as u64;
+
+        // Caption and EXIF follow the photo into the destination's vault
+        let mut source_vault = load_vault(&client.0, &repo, &token, &index, &source_album, &source_key).await?;
+        let mut dest_vault = load_vault(&client.0, &repo, &token, &index, &destination, &dest_key).await?;
+        let metadata = source_vault
+            .entries
+            .remove(&blob_name)
+            .map(|m| PhotoMetadata { filename: name.clone(), ..m })
+            .unwrap_or_else(|| PhotoMetadata::for_upload(&name, &data));
+        dest_vault.entries.insert(new_blob, metadata);
+
+        changes.push(stage_manifest(&client, &repo, &token, &source_album, &mut source_manifest, Some(handle)).await?);
+        changes.push(stage_manifest(&client, &repo, &token, &destination, &mut dest_manifest, Some(handle)).await?);
+        let source_change =
+            stage_vault(&client.0, &repo, &token, &source_album, &mut source_vault, &source_manifest, &source_key);
+        changes.push(source_change.await?);
+        let dest_change = stage_vault(&client.0, &repo, &token, &destination, &mut dest_vault, &dest_manifest, &dest_key);
+        changes.push(dest_change.await?);
+        new_path
+    } else {
+        if dest_encrypted {
+            return Err(AppError::Validation(
+                "Plain photos cannot be moved into an encrypted album; upload them with encryption instead".into(),
+            ));
+        }
+        if index.contains_key(&target) {
+            return Err(AppError::Validation("Destination already exists".into()));
+        }
+
+        changes.push(TreeChange::put_blob(&target, entry));
+        changes.push(TreeChange::delete(&path));
+        changes.extend(move_signature(&index, &path, &target));
+        // The signed listing follows the photo, as its signature does
+        let mut listed = None;
+        if let Some(mut manifest) = source_manifest {
+            listed = manifest.signed.remove(file_name(&path));
+            if retarget_cover(&mut manifest, file_name(&path), None) || listed.is_some() {
+                let change = stage_manifest(&client, &repo, &token, &source_album, &mut manifest, keypair_handle).await?;
+                changes.push(change);
+            }
+        }
+        if let (Some(hash), Some(mut manifest)) = (listed, dest_manifest) {
+            manifest.signed.insert(file_name(&target).to_string(), hash);
+            let change = stage_manifest(&client, &repo, &token, &destination, &mut manifest, keypair_handle).await?;
+            changes.push(change);
+        }
+        target
+    };
+
+    commit(&client, &repo, &token, &head, &changes, &format!("Move {} to {}", path, destination)).await?;
+    record_activity(ActivityKind::Move, "move_photo_between_albums", Some(&repo), &path, Some(format!("to {}", new_path)));
+    Ok(new_path)
+}
@@ -0,0 +1,604 @@
+//! Peer-to-Peer Transfer
+//!
+//! Sends photos and album folders straight to another Vortex instance over
+//! an iroh QUIC connection, without going through GitHub. Peers on the same
+//! network connect directly; elsewhere iroh's relays carry the traffic, still
+//! end-to-end encrypted between the two nodes.
+//!
+//! The sender makes an offer with `offer_p2p_transfer` and passes the ticket
+//! on (a chat message, a QR code). The ticket holds the sender's node
+//! address and a random secret; whoever presents the secret gets the files,
+//! so tickets should be shared like passwords. Offers last `OFFER_TTL` or
+//! until cancelled, and can be received more than once in that time.
+//!
+//! On the wire, the receiver opens a stream and writes the secret; the
+//! sender answers with a status byte, the length-prefixed JSON manifest and
+//! then every file's bytes in manifest order. The receiver checks each file
+//! against its size and BLAKE3 hash before moving it into place and
+//! acknowledges the whole transfer with a final byte.
+//!
+//! Both sides emit `p2p-transfer-progress` under the offer id.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use iroh::endpoint::{Connection, RecvStream, SendStream};
+use iroh::{Endpoint, NodeAddr, NodeId, RelayUrl};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::AppHandle;
+use crate::github::{is_album_file, sanitize_filename, AppError};
+use crate::util::now_secs;
+
+pub const P2P_ALPN: &[u8] = b"vortex/p2p-transfer/1";
+pub const TICKET_PREFIX: &str = "vortexp2p";
+pub const OFFER_TTL: Duration = Duration::from_secs(60 * 60);
+const SECRET_LEN: usize = 32;
+const CHUNK_SIZE: usize = 64 * 1024;
+const MAX_MANIFEST_BYTES: u32 = 8 * 1024 * 1024;
+/// Progress is emitted at most this often, besides at the end of each file
+const PROGRESS_STEP: u64 = 1024 * 1024;
+const ACCEPTED: u8 = 1;
+const REJECTED: u8 = 0;
+
+lazy_static::lazy_static! {
+    static ref ENDPOINT: Mutex<Option<Endpoint>> = Mutex::new(None);
+    /// Open offers by the BLAKE3 of their secret
+    static ref OFFERS: Mutex<HashMap<String, Offer>> = Mutex::new(HashMap::new());
+}
+
+/// What a receiver needs to fetch an offer
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferTicket {
+    pub node_id: String,
+    pub relay_url: Option<String>,
+    pub addrs: Vec<SocketAddr>,
+    /// Hex of the offer's secret
+    pub secret: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferFile {
+    /// `/`-separated, relative to the receiver's destination folder
+    pub path: String,
+    pub size: u64,
+    /// Hex BLAKE3 of the content
+    pub blake3: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferManifest {
+    pub files: Vec<TransferFile>,
+}
+
+impl TransferManifest {
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransferOffer {
+    pub id: String,
+    pub ticket: String,
+    pub files: usize,
+    pub total_bytes: u64,
+    /// Seconds since the epoch
+    pub expires_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Send,
+    Receive,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransferProgress {
+    pub id: String,
+    pub direction: TransferDirection,
+    pub file: String,
+    pub files_done: usize,
+    pub total_files: usize,
+    pub bytes_done: u64,
+    pub total_bytes: u64,
+    pub percent: u8,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReceivedTransfer {
+    pub id: String,
+    /// Where each file was written; renamed when the name was taken
+    pub files: Vec<String>,
+    pub total_bytes: u64,
+}
+
+struct Offer {
+    info: TransferOffer,
+    manifest: TransferManifest,
+    sources: Vec<PathBuf>,
+    app: AppHandle,
+}
+
+// ============================================================================
+// Tickets and manifests
+// ============================================================================
+
+impl TransferTicket {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        format!("{}{}", TICKET_PREFIX, URL_SAFE_NO_PAD.encode(json))
+    }
+
+    pub fn decode(ticket: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::Validation("Not a Vortex transfer ticket".into());
+        let encoded = ticket.trim().strip_prefix(TICKET_PREFIX).ok_or_else(invalid)?;
+        let json = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| invalid())?;
+        let ticket: Self = serde_json::from_slice(&json).map_err(|_| invalid())?;
+        ticket.secret_bytes()?;
+        Ok(ticket)
+    }
+
+    pub fn secret_bytes(&self) -> Result<[u8; SECRET_LEN], AppError> {
+        hex::decode(&self.secret)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| AppError::Validation("Transfer ticket has an invalid secret".into()))
+    }
+
+    fn node_addr(&self) -> Result<NodeAddr, AppError> {
+        let node_id: NodeId = self
+            .node_id
+            .parse()
+            .map_err(|_| AppError::Validation("Transfer ticket has an invalid node id".into()))?;
+        let relay_url = match &self.relay_url {
+            Some(url) => Some(
+                url.parse::<RelayUrl>()
+                    .map_err(|_| AppError::Validation("Transfer ticket has an invalid relay".into()))?,
+            ),
+            None => None,
+        };
+        Ok(NodeAddr::from_parts(node_id, relay_url, self.addrs.iter().copied()))
+    }
+}
+
+/// Key of the offer behind `secret`; lookups never compare secrets directly
+fn offer_key(secret: &[u8]) -> String {
+    blake3::hash(secret).to_hex().to_string()
+}
+
+/// Id shown on both sides for the offer behind `secret`
+pub fn offer_id(secret: &[u8]) -> String {
+    offer_key(secret)[..16].to_string()
+}
+
+/// Where a manifest path lands below `dest`, or `None` for paths that would
+/// leave it. Each component is sanitized as an uploaded file name would be.
+pub fn safe_destination(dest: &Path, path: &str) -> Option<PathBuf> {
+    let mut target = dest.to_path_buf();
+    for component in path.split('/') {
+        if component.is_empty() || component == "." || component == ".." {
+            return None;
+        }
+        let name = sanitize_filename(component);
+        if name.is_empty() || name.starts_with('.') {
+            return None;
+        }
+        target.push(name);
+    }
+    Some(target)
+}
+
+/// `path`, or `name (2).ext`, `name (3).ext`, ... when it already exists
+fn free_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("photo");
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| format!(".{}", e)).unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+fn hash_file(path: &Path) -> Result<(u64, String), AppError> {
+    let mut hasher = blake3::Hasher::new();
+    let size = std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok((size, hasher.finalize().to_hex().to_string()))
+}
+
+/// Album files under `dir`, skipping hidden folders, as manifest paths
+/// starting with `prefix`
+fn collect_folder(dir: &Path, prefix: &str, out: &mut Vec<(PathBuf, String)>) -> Result<(), AppError> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let relative = format!("{}/{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            collect_folder(&path, &relative, out)?;
+        } else if is_album_file(&path) {
+            out.push((path, relative));
+        }
+    }
+    Ok(())
+}
+
+/// The files behind `paths` with their manifest entries. Folders are sent
+/// whole under their own name, so the receiver gets them as albums.
+pub(crate) fn build_manifest(paths: &[String]) -> Result<(TransferManifest, Vec<PathBuf>), AppError> {
+    let mut found = Vec::new();
+    for path in paths {
+        let path = PathBuf::from(path);
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| AppError::Validation(format!("Cannot send {}", path.display())))?;
+        if path.is_dir() {
+            collect_folder(&path, &name, &mut found)?;
+        } else if path.is_file() {
+            found.push((path, name));
+        } else {
+            return Err(AppError::Validation(format!("{} does not exist", path.display())));
+        }
+    }
+    if found.is_empty() {
+        return Err(AppError::Validation("Nothing to send".into()));
+    }
+
+    let mut files = Vec::with_capacity(found.len());
+    let mut sources = Vec::with_capacity(found.len());
+    for (source, path) in found {
+        let (size, blake3) = hash_file(&source)?;
+        files.push(TransferFile { path, size, blake3 });
+        sources.push(source);
+    }
+    Ok((TransferManifest { files }, sources))
+}
+
+fn percent(done: u64, total: u64) -> u8 {
+    (done * 100).checked_div(total).unwrap_or(100).min(100) as u8
+}
+
+// ============================================================================
+// Connections
+// ============================================================================
+
+fn peer_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Api(format!("Peer transfer failed: {}", e))
+}
+
+/// The node's endpoint, bound and accepting offers on first use
+async fn endpoint() -> Result<Endpoint, AppError> {
+    if let Some(endpoint) = ENDPOINT.lock().unwrap().clone() {
+        return Ok(endpoint);
+    }
+    let bound = Endpoint::builder()
+        .discovery_n0()
+        .alpns(vec![P2P_ALPN.to_vec()])
+        .bind()
+        .await
+        .map_err(peer_error)?;
+
+    // Another call may have bound one in the meantime
+    let existing = {
+        let mut slot = ENDPOINT.lock().unwrap();
+        match slot.clone() {
+            Some(existing) => Some(existing),
+            None => {
+                *slot = Some(bound.clone());
+                None
+            }
+        }
+    };
+    if let Some(existing) = existing {
+        bound.close().await;
+        return Ok(existing);
+    }
+
+    let listener = bound.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(incoming) = listener.accept().await {
+            tauri::async_runtime::spawn(async move {
+                let connection = match incoming.accept() {
+                    Ok(connecting) => connecting.await,
+                    Err(e) => Err(e),
+                };
+                match connection {
+                    Ok(connection) => {
+                        if let Err(e) = serve_connection(connection).await {
+                            tracing::warn!("Peer transfer to a receiver failed: {}", e);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Peer connection failed: {}", e),
+                }
+            });
+        }
+    });
+    Ok(bound)
+}
+
+async fn write_frame(send: &mut SendStream, payload: &[u8]) -> Result<(), AppError> {
+    send.write_all(&(payload.len() as u32).to_be_bytes()).await.map_err(peer_error)?;
+    send.write_all(payload).await.map_err(peer_error)
+}
+
+async fn read_frame(recv: &mut RecvStream) -> Result<Vec<u8>, AppError> {
+    let mut len = [0u8; 4];
+    recv.read_exact(&mut len).await.map_err(peer_error)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_MANIFEST_BYTES {
+        return Err(AppError::Validation("Transfer manifest is too large".into()));
+    }
+    let mut payload = vec![0u8; len as usize];
+    recv.read_exact(&mut payload).await.map_err(peer_error)?;
+    Ok(payload)
+}
+
+/// Answer one receiver: check its secret, then stream the offer
+async fn serve_connection(connection: Connection) -> Result<(), AppError> {
+    let (mut send, mut recv) = connection.accept_bi().await.map_err(peer_error)?;
+    let mut secret = [0u8; SECRET_LEN];
+    recv.read_exact(&mut secret).await.map_err(peer_error)?;
+
+    let key = offer_key(&secret);
+    let offer = {
+        let mut offers = OFFERS.lock().unwrap();
+        offers.retain(|_, o| o.info.expires_at > now_secs());
+        offers.get(&key).map(|o| (o.info.id.clone(), o.manifest.clone(), o.sources.clone(), o.app.clone()))
+    };
+    let Some((id, manifest, sources, app)) = offer else {
+        send.write_all(&[REJECTED]).await.map_err(peer_error)?;
+        let _ = send.finish();
+        send.stopped().await.ok();
+        return Err(AppError::Validation("Receiver presented an unknown or expired ticket".into()));
+    };
+
+    send.write_all(&[ACCEPTED]).await.map_err(peer_error)?;
+    write_frame(&mut send, &serde_json::to_vec(&manifest).map_err(peer_error)?).await?;
+
+    let total_bytes = manifest.total_bytes();
+    let mut progress = Progress::new(&app, &id, TransferDirection::Send, &manifest);
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    for (file, source) in manifest.files.iter().zip(&sources) {
+        let mut reader = tokio::fs::File::open(source).await?;
+        let mut remaining = file.size;
+        while remaining > 0 {
+            let want = remaining.min(CHUNK_SIZE as u64) as usize;
+            let read = reader.read(&mut buffer[..want]).await?;
+            if read == 0 {
+                return Err(AppError::Validation(format!("{} changed while it was being sent", file.path)));
+            }
+            send.write_all(&buffer[..read]).await.map_err(peer_error)?;
+            remaining -= read as u64;
+            progress.advance(&file.path, read as u64);
+        }
+        progress.file_done(&file.path);
+    }
+    send.finish().map_err(peer_error)?;
+
+    let mut ack = [0u8; 1];
+    recv.read_exact(&mut ack).await.map_err(peer_error)?;
+    tracing::info!("Sent {} files ({} bytes) for transfer {}", manifest.files.len(), total_bytes, id);
+    connection.close(0u32.into(), b"done");
+    Ok(())
+}
+
+/// Progress of one transfer, emitted as `p2p-transfer-progress`
+struct Progress<'a> {
+    app: &'a AppHandle,
+    event: TransferProgress,
+    last_emitted: u64,
+}
+
+impl<'a> Progress<'a> {
+    fn new(app: &'a AppHandle, id: &str, direction: TransferDirection, manifest: &TransferManifest) -> Self {
+        Self {
+            app,
+            event: TransferProgress {
+                id: id.to_string(),
+                direction,
+                file: String::new(),
+                files_done: 0,
+                total_files: manifest.files.len(),
+                bytes_done: 0,
+                total_bytes: manifest.total_bytes(),
+                percent: 0,
+            },
+            last_emitted: 0,
+        }
+    }
+
+    fn emit(&mut self, file: &str) {
+        self.event.file = file.to_string();
+        self.event.percent = percent(self.event.bytes_done, self.event.total_bytes);
+        self.last_emitted = self.event.bytes_done;
+        let _ = self.app.emit("p2p-transfer-progress", &self.event);
+    }
+
+    fn advance(&mut self, file: &str, bytes: u64) {
+        self.event.bytes_done += bytes;
+        if self.event.bytes_done - self.last_emitted >= PROGRESS_STEP {
+            self.emit(file);
+        }
+    }
+
+    fn file_done(&mut self, file: &str) {
+        self.event.files_done += 1;
+        self.emit(file);
+    }
+}
+
+/// Read `file` from `recv` into `target` through a `.part` file, checking
+/// its size and hash; the partial file is removed on failure
+async fn receive_file(
+    recv: &mut RecvStream,
+    file: &TransferFile,
+    target: &Path,
+    progress: &mut Progress<'_>,
+) -> Result<(), AppError> {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    let partial = target.with_file_name(name);
+
+    let result = async {
+        let mut writer = tokio::fs::File::create(&partial).await?;
+        let mut hasher = blake3::Hasher::new();
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut remaining = file.size;
+        while remaining > 0 {
+            let want = remaining.min(CHUNK_SIZE as u64) as usize;
+            let read = recv
+                .read(&mut buffer[..want])
+                .await
+                .map_err(peer_error)?
+                .ok_or_else(|| AppError::Validation(format!("Sender stopped in the middle of {}", file.path)))?;
+            hasher.update(&buffer[..read]);
+            writer.write_all(&buffer[..read]).await?;
+            remaining -= read as u64;
+            progress.advance(&file.path, read as u64);
+        }
+        writer.flush().await?;
+        if hasher.finalize().to_hex().as_str() != file.blake3 {
+            return Err(AppError::Validation(format!("{} does not match the sender's hash", file.path)));
+        }
+        tokio::fs::rename(&partial, target).await?;
+        Ok(())
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&partial).await;
+    }
+    result
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Offer the files and folders at `paths` to whoever gets the ticket
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn offer_p2p_transfer(app: AppHandle, paths: Vec<String>) -> Result<TransferOffer, AppError> {
+    let (manifest, sources) = tauri::async_runtime::spawn_blocking(move || build_manifest(&paths))
+        .await
+        .map_err(|e| AppError::Validation(format!("Transfer task failed: {}", e)))??;
+
+    let endpoint = endpoint().await?;
+    let addr = endpoint.node_addr().await.map_err(peer_error)?;
+    let secret: [u8; SECRET_LEN] = rand::random();
+    let ticket = TransferTicket {
+        node_id: addr.node_id.to_string(),
+        relay_url: addr.relay_url.map(|url| url.to_string()),
+        addrs: addr.direct_addresses.into_iter().collect(),
+        secret: hex::encode(secret),
+    };
+
+    let info = TransferOffer {
+        id: offer_id(&secret),
+        ticket: ticket.encode(),
+        files: manifest.files.len(),
+        total_bytes: manifest.total_bytes(),
+        expires_at: now_secs() + OFFER_TTL.as_secs(),
+    };
+    OFFERS.lock().unwrap().insert(
+        offer_key(&secret),
+        Offer {
+            info: info.clone(),
+            manifest,
+            sources,
+            app,
+        },
+    );
+    Ok(info)
+}
+
+/// Offers that can still be received
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn list_p2p_offers() -> Vec<TransferOffer> {
+    let mut offers = OFFERS.lock().unwrap();
+    offers.retain(|_, o| o.info.expires_at > now_secs());
+    let mut open: Vec<TransferOffer> = offers.values().map(|o| o.info.clone()).collect();
+    open.sort_by_key(|o| o.expires_at);
+    open
+}
+
+/// Withdraw an offer; its ticket stops working. `false` if there was none.
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn cancel_p2p_offer(id: String) -> bool {
+    let mut offers = OFFERS.lock().unwrap();
+    let before = offers.len();
+    offers.retain(|_, o| o.info.id != id);
+    offers.len() != before
+}
+
+/// Fetch the offer behind `ticket` into `dest_dir`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn receive_p2p_transfer(app: AppHandle, ticket: String, dest_dir: String) -> Result<ReceivedTransfer, AppError> {
+    let ticket = TransferTicket::decode(&ticket)?;
+    let secret = ticket.secret_bytes()?;
+    let dest = PathBuf::from(&dest_dir);
+    if !dest.is_dir() {
+        return Err(AppError::Validation("Destination is not a directory".into()));
+    }
+
+    let endpoint = endpoint().await?;
+    let connection = endpoint.connect(ticket.node_addr()?, P2P_ALPN).await.map_err(peer_error)?;
+    let (mut send, mut recv) = connection.open_bi().await.map_err(peer_error)?;
+    send.write_all(&secret).await.map_err(peer_error)?;
+
+    let mut status = [0u8; 1];
+    recv.read_exact(&mut status).await.map_err(peer_error)?;
+    if status[0] != ACCEPTED {
+        return Err(AppError::Validation("The sender no longer offers this transfer".into()));
+    }
+    let manifest: TransferManifest = serde_json::from_slice(&read_frame(&mut recv).await?)
+        .map_err(|_| AppError::Validation("Sender sent an invalid manifest".into()))?;
+
+    let targets = manifest
+        .files
+        .iter()
+        .map(|f| {
+            safe_destination(&dest, &f.path)
+                .ok_or_else(|| AppError::Validation(format!("Sender offered an unsafe path: {}", f.path)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let id = offer_id(&secret);
+    let mut progress = Progress::new(&app, &id, TransferDirection::Receive, &manifest);
+    let mut written = Vec::with_capacity(targets.len());
+    for (file, target) in manifest.files.iter().zip(targets) {
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let target = free_path(&target);
+        receive_file(&mut recv, file, &target, &mut progress).await?;
+        progress.file_done(&file.path);
+        written.push(target.to_string_lossy().to_string());
+    }
+
+    send.write_all(&[ACCEPTED]).await.map_err(peer_error)?;
+    send.finish().map_err(peer_error)?;
+    connection.closed().await;
+
+    Ok(ReceivedTransfer {
+        id,
+        files: written,
+        total_bytes: manifest.total_bytes(),
+    })
+}
@@ -0,0 +1,192 @@
+//! Password Strength and KDF Calibration
+//!
+//! `check_password_strength` scores a password with zxcvbn (0 = trivially
+//! guessable .. 4 = very strong); `encrypt_data_password` refuses anything
+//! below `MIN_PASSWORD_SCORE`.
+//!
+//! `calibrate_kdf` benchmarks Argon2id on this host and raises memory, then
+//! iterations, until one derivation takes about the target time (500ms by
+//! default). Costs never drop below `KdfParams::FLOOR`, and because every
+//! password-encrypted blob records its own costs, data made on a fast
+//! machine still decrypts on a slow one. The calibrated costs describe the
+//! host rather than a profile, so they are saved next to the device id and
+//! loaded again at startup.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::crypto::{current_kdf_params, set_kdf_params, CryptoError, KdfParams};
+
+/// Minimum zxcvbn score for passwords that protect data
+pub const MIN_PASSWORD_SCORE: u8 = 3;
+/// Default time one key derivation should take
+pub const DEFAULT_TARGET: Duration = Duration::from_millis(500);
+/// Calibration never picks more memory than this, whatever the host
+const MAX_CALIBRATED_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_CALIBRATED_ITERATIONS: u32 = 16;
+const CALIBRATION_FILE: &str = "kdf-params.json";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PasswordStrength {
+    /// 0 (weakest) to 4 (strongest)
+    pub score: u8,
+    pub guesses_log10: f64,
+    /// Estimated time to crack offline against a slow hash, human readable
+    pub crack_time: String,
+    /// Whether the score meets `MIN_PASSWORD_SCORE`
+    pub acceptable: bool,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct KdfCalibration {
+    pub params: KdfParams,
+    /// Measured time of one derivation with `params`
+    pub elapsed_ms: u64,
+}
+
+pub fn estimate_strength(password: &str, user_inputs: &[&str]) -> PasswordStrength {
+    let entropy = zxcvbn::zxcvbn(password, user_inputs);
+    let score = u8::from(entropy.score());
+    let feedback = entropy.feedback();
+    PasswordStrength {
+        score,
+        guesses_log10: entropy.guesses_log10(),
+        crack_time: entropy.crack_times().offline_slow_hashing_1e4_per_second().to_string(),
+        acceptable: score >= MIN_PASSWORD_SCORE,
+        warning: feedback.and_then(|f| f.warning()).map(|w| w.to_string()),
+        suggestions: feedback
+            .map(|f| f.suggestions().iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// Reject passwords too weak to protect data
+pub fn ensure_strong(password: &str) -> Result<(), CryptoError> {
+    let strength = estimate_strength(password, &[]);
+    if strength.acceptable {
+        return Ok(());
+    }
+    let mut message = format!(
+        "password is too weak (strength {} of 4, at least {} required)",
+        strength.score, MIN_PASSWORD_SCORE
+    );
+    if let Some(warning) = strength.warning {
+        message.push_str(": ");
+        message.push_str(&warning);
+    }
+    Err(CryptoError::InvalidInput(message))
+}
+
+fn time_derivation(params: KdfParams) -> Result<Duration, CryptoError> {
+    let salt = [0u8; 16];
+    let start = Instant::now();
+    params.derive(b"vortex-image kdf calibration", &salt)?;
+    Ok(start.elapsed())
+}
+
+/// Scale `value` by `target / elapsed`, clamped to `[value, max]`
+fn scale(value: u32, elapsed: Duration, target: Duration, max: u32) -> u32 {
+    let ratio = target.as_secs_f64() / elapsed.as_secs_f64().max(1e-6);
+    ((value as f64 * ratio) as u32).clamp(value, max.max(value))
+}
+
+/// Pick Argon2id costs for this host so one derivation takes about `target`
+pub fn calibrate(target: Duration) -> Result<KdfCalibration, CryptoError> {
+    let mut params = KdfParams::FLOOR;
+    let mut elapsed = time_derivation(params)?;
+
+    if elapsed < target {
+        // Memory first: it is what makes GPU and ASIC attacks expensive
+        let memory = scale(params.memory_kib, elapsed, target, MAX_CALIBRATED_MEMORY_KIB);
+        let memory = memory / 1024 * 1024;
+        if memory > params.memory_kib {
+            params.memory_kib = memory;
+            elapsed = time_derivation(params)?;
+        }
+    }
+    if elapsed < target {
+        let iterations = scale(params.iterations, elapsed, target, MAX_CALIBRATED_ITERATIONS);
+        if iterations > params.iterations {
+            params.iterations = iterations;
+            elapsed = time_derivation(params)?;
+        }
+    }
+
+    Ok(KdfCalibration {
+        params,
+        elapsed_ms: elapsed.as_millis() as u64,
+    })
+}
+
+/// Save calibrated costs in `dir`, atomically
+pub fn save_calibration_in(dir: &Path, params: KdfParams) -> std::io::Result<()> {
+    let json = serde_json::to_vec_pretty(&params).map_err(std::io::Error::other)?;
+    let path = dir.join(CALIBRATION_FILE);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, &path)
+}
+
+/// Costs saved in `dir`, unless missing, unreadable or below the floor
+pub fn load_calibration_in(dir: &Path) -> Option<KdfParams> {
+    let raw = std::fs::read(dir.join(CALIBRATION_FILE)).ok()?;
+    let params: KdfParams = serde_json::from_slice(&raw).ok()?;
+    let floor = KdfParams::FLOOR;
+    (params.memory_kib >= floor.memory_kib
+        && params.iterations >= floor.iterations
+        && params.parallelism >= floor.parallelism)
+        .then_some(params)
+}
+
+/// Use the costs of the last calibration on this host, if there was one.
+/// Called at startup.
+pub fn load_saved_kdf_params() {
+    let Some(params) = crate::profiles::root_dir().ok().and_then(|dir| load_calibration_in(&dir)) else {
+        return;
+    };
+    if let Err(e) = set_kdf_params(params) {
+        tracing::warn!("Ignoring saved KDF calibration: {}", e);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Score a password; `user_inputs` (names, emails, repo names) count as
+/// guessable words
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn check_password_strength(password: String, user_inputs: Option<Vec<String>>) -> PasswordStrength {
+    let password = zeroize::Zeroizing::new(password);
+    let inputs = user_inputs.unwrap_or_default();
+    let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+    estimate_strength(&password, &inputs)
+}
+
+/// Benchmark this host and use the resulting Argon2id costs for new
+/// password-encrypted data, now and after restarts
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn calibrate_kdf(target_ms: Option<u64>) -> Result<KdfCalibration, CryptoError> {
+    let target = target_ms.map(Duration::from_millis).unwrap_or(DEFAULT_TARGET);
+    let calibration = tokio::task::spawn_blocking(move || calibrate(target))
+        .await
+        .map_err(|e| CryptoError::KeyDerivation(e.to_string()))??;
+    set_kdf_params(calibration.params)?;
+    let dir = crate::profiles::root_dir().map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+    save_calibration_in(&dir, calibration.params)
+        .map_err(|e| CryptoError::KeyDerivation(format!("could not save the calibration: {}", e)))?;
+    Ok(calibration)
+}
+
+/// Argon2id costs currently used for new password-encrypted data
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn get_kdf_params() -> KdfParams {
+    current_kdf_params()
+}
@@ -3,15 +3,32 @@
 //! External crates: 6 dependencies
 
 use serde::{Deserialize, Serialize};
-use crate::compress::{
-    Algorithm as CompressAlgorithm, CompressionSettings,
-    compress, decompress
-};
-use crate::crypto::{
-    encrypt_with_password, decrypt_with_password,
-    encrypt, decrypt, HybridKeypair, PublicBundle, hash_data
-};
+use crate::crypto::{HybridKeypair, PublicBundle, hash_data};
+use crate::compress_jobs::{folder_files, start_job, JobFile, JobKind};
+use crate::compress_stream::write_via_partial;
+use crate::entropy::detect_kind;
 use crate::github::AppError;
+use crate::image_optimize::OptimizeOptions;
+use crate::pipeline_condition::{Condition, ConditionFacts};
+use crate::pipeline_history::{record_run, PipelineRun};
+use crate::pipeline_steps::{find_step, StepContext};
+use crate::resize::ResizeOptions;
+use crate::transcode::TranscodeOptions;
+use crate::util::now_secs;
+use crate::watermark::WatermarkOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// Extension appended to files stored as pipeline output
+pub const PIPELINE_FILE_EXT: &str = "vxp";
+
+/// Pipeline output starts with `VXPL`, a format version byte, the u32 LE
+/// length of the JSON `PipelineMetadata` and the metadata itself, so the
+/// file alone says which steps, step versions and parameters to reverse.
+/// Output from before the header had only the length and the metadata.
+pub const PIPELINE_MAGIC: &[u8; 4] = b"VXPL";
+pub const PIPELINE_FORMAT_VERSION: u8 = 2;
+/// Largest metadata `read_metadata` accepts
+const MAX_METADATA_LEN: usize = 16 * 1024 * 1024;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -36,6 +53,83 @@ pub enum PipelineOperation {
     Hash,
     
     Base64Encode,
+
+    /// Lossless PNG/JPEG optimization. Not reversed: the optimized image
+    /// becomes the content the pipeline restores.
+    OptimizeImage {
+        #[serde(flatten)]
+        options: OptimizeOptions,
+    },
+
+    /// Convert to AVIF, WebP or JPEG XL. Like `OptimizeImage`, not reversed.
+    Transcode {
+        #[serde(flatten)]
+        options: TranscodeOptions,
+    },
+
+    /// Scale to a maximum edge, exact size or percentage. Like
+    /// `OptimizeImage`, not reversed.
+    Resize {
+        #[serde(flatten)]
+        options: ResizeOptions,
+    },
+
+    /// Composite a PNG or text onto the photo. Like `OptimizeImage`, not
+    /// reversed.
+    Watermark {
+        #[serde(flatten)]
+        options: WatermarkOptions,
+    },
+
+    /// Any step added with `pipeline_steps::register_step`
+    Step {
+        step: String,
+        #[serde(default)]
+        params: serde_json::Value,
+    },
+}
+
+impl PipelineOperation {
+    /// ID of the registered `PipelineStep` that runs this operation
+    pub fn step_id(&self) -> &str {
+        match self {
+            Self::Compress { .. } => "compress",
+            Self::EncryptPassword { .. } => "encrypt_password",
+            Self::EncryptHybridPQ { .. } => "encrypt_hybrid_pq",
+            Self::Hash => "hash",
+            Self::Base64Encode => "base64_encode",
+            Self::OptimizeImage { .. } => "optimize_image",
+            Self::Transcode { .. } => "transcode",
+            Self::Resize { .. } => "resize",
+            Self::Watermark { .. } => "watermark",
+            Self::Step { step, .. } => step,
+        }
+    }
+
+    /// Parameters handed to the step
+    pub fn step_params(&self) -> serde_json::Value {
+        match self {
+            Self::Compress { algorithm, level } => serde_json::json!({
+                "algorithm": algorithm,
+                "level": level
+            }),
+            Self::EncryptHybridPQ { recipient_bundle } => serde_json::json!({
+                "recipient_bundle": recipient_bundle
+            }),
+            Self::OptimizeImage { options } => serde_json::to_value(options).unwrap_or_default(),
+            Self::Transcode { options } => serde_json::to_value(options).unwrap_or_default(),
+            Self::Resize { options } => serde_json::to_value(options).unwrap_or_default(),
+            Self::Watermark { options } => serde_json::to_value(options).unwrap_or_default(),
+            Self::Step { params, .. } if !params.is_null() => params.clone(),
+            _ => serde_json::json!({}),
+        }
+    }
+
+    /// Operations that replace the input instead of wrapping it. They must
+    /// run before any other layer, and the checksum covers their output.
+    pub fn is_source_transform(&self) -> bool {
+        find_step(self.step_id()).is_some_and(|s| s.describe().source_transform)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -44,6 +138,9 @@ pub struct PipelineLayer {
     pub operation: PipelineOperation,
     pub enabled: bool,
     pub order: u32,
+    /// Run only when this expression holds; see `pipeline_condition`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -73,11 +170,20 @@ pub struct LayerResult {
     pub output_size: usize,
     pub success: bool,
     pub error: Option<String>,
+    /// The layer's condition did not hold, so it was not applied
+    #[serde(default)]
+    pub skipped: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PipelineMetadata {
     pub version: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_name: Option<String>,
+    #[serde(default)]
+    pub created_at: u64,
     pub layers: Vec<LayerMetadata>,
     pub original_checksum: Vec<u8>,
     pub original_size: usize,
@@ -85,10 +191,19 @@ pub struct PipelineMetadata {
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LayerMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_id: Option<String>,
     pub operation_type: String,
+    /// `PipelineStep::version` of the step that wrote the layer
+    #[serde(default = "default_step_version")]
+    pub step_version: u32,
     pub params: serde_json::Value,
 }
 
+fn default_step_version() -> u32 {
+    1
+}
+
 impl Default for PipelineConfig {
     fn default() -> Self {
         Self {
@@ -99,14 +214,8 @@ impl Default for PipelineConfig {
             name: "Default Pipeline".to_string(),
             description: "Standard compression + encryption".to_string(),
             layers: vec![],
-            created_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
-            updated_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            created_at: now_secs(),
+            updated_at: now_secs(),
         }
     }
 }
@@ -129,9 +238,30 @@ pub fn process_pipeline(
     data: &[u8],
     config: &PipelineConfig,
     context: &PipelineContext,
+) -> Result<PipelineResult, PipelineError> {
+    process_pipeline_for_file(data, None, config, context)
+}
+
+/// `process_pipeline` for the file `file_name`, which layer conditions on
+/// `name` and `extension` are checked against
+pub fn process_pipeline_for_file(
+    data: &[u8],
+    file_name: Option<&str>,
+    config: &PipelineConfig,
+    context: &PipelineContext,
 ) -> Result<PipelineResult, PipelineError> {
     let original_size = data.len();
-    let original_checksum = hash_data(data).to_vec();
+    let facts = ConditionFacts {
+        size: original_size as u64,
+        name: file_name.map(str::to_string),
+        format: serde_json::to_value(detect_kind(data))
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string)),
+    };
+    let mut baseline_size = original_size;
+    let mut baseline_checksum = hash_data(data).to_vec();
+    // Set once a layer has wrapped the content; source transforms must come first
+    let mut wrapped = false;
     
     let mut current_data = data.to_vec();
     let mut layers_applied = Vec::new();
@@ -144,8 +274,31 @@ pub fn process_pipeline(
     
     for layer in sorted_layers {
         let input_size = current_data.len();
+        if let Some(condition) = &layer.condition {
+            let condition = Condition::parse(condition)
+                .map_err(|e| PipelineError::InvalidData(e.to_string()))?;
+            if condition.evaluate(&facts) != Some(true) {
+                layers_applied.push(LayerResult {
+                    layer_id: layer.id.clone(),
+                    operation_type: get_operation_type(&layer.operation),
+                    input_size,
+                    output_size: input_size,
+                    success: true,
+                    error: None,
+                    skipped: true,
+                });
+                continue;
+            }
+        }
+        let source_transform = layer.operation.is_source_transform();
+        if source_transform && wrapped {
+            return Err(PipelineError::InvalidData(format!(
+                "{} must come before other layers",
+                get_operation_type(&layer.operation)
+            )));
+        }
         
-        let result = apply_layer(&current_data, layer, context);
+        let result = apply_layer(&current_data, layer, file_name, context);
         
         match result {
             Ok((output, metadata)) => {
@@ -156,9 +309,16 @@ pub fn process_pipeline(
                     output_size: output.len(),
                     success: true,
                     error: None,
+                    skipped: false,
                 });
                 layer_metadata.push(metadata);
                 current_data = output;
+                if source_transform {
+                    baseline_size = current_data.len();
+                    baseline_checksum = hash_data(&current_data).to_vec();
+                } else {
+                    wrapped = true;
+                }
             }
             Err(e) => {
                 layers_applied.push(LayerResult {
@@ -168,6 +328,7 @@ pub fn process_pipeline(
                     output_size: 0,
                     success: false,
                     error: Some(e.to_string()),
+                    skipped: false,
                 });
                 return Err(e);
             }
@@ -175,16 +336,21 @@ pub fn process_pipeline(
     }
 
     let metadata = PipelineMetadata {
-        version: 1,
+        version: PIPELINE_FORMAT_VERSION,
+        pipeline_id: Some(config.id.clone()),
+        pipeline_name: Some(config.name.clone()),
+        created_at: now_secs(),
         layers: layer_metadata,
-        original_checksum: original_checksum.clone(),
-        original_size,
+        original_checksum: baseline_checksum,
+        original_size: baseline_size,
     };
     
     let metadata_json = serde_json::to_vec(&metadata)
         .map_err(|e| PipelineError::Serialization(e.to_string()))?;
 
-    let mut final_data = Vec::with_capacity(4 + metadata_json.len() + current_data.len());
+    let mut final_data = Vec::with_capacity(PIPELINE_MAGIC.len() + 5 + metadata_json.len() + current_data.len());
+    final_data.extend_from_slice(PIPELINE_MAGIC);
+    final_data.push(PIPELINE_FORMAT_VERSION);
     final_data.extend_from_slice(&(metadata_json.len() as u32).to_le_bytes());
     final_data.extend_from_slice(&metadata_json);
     final_data.extend_from_slice(&current_data);
@@ -201,23 +367,48 @@ pub fn process_pipeline(
     })
 }
 
-pub fn reverse_pipeline(
-    data: &[u8],
-    context: &PipelineContext,
-) -> Result<PipelineResult, PipelineError> {
-    if data.len() < 4 {
-        return Err(PipelineError::InvalidData("Data too short".into()));
+/// Offset and length of the metadata in pipeline output starting with
+/// `prefix`, which must hold at least the first 9 bytes
+fn metadata_span(prefix: &[u8]) -> Result<(usize, usize), PipelineError> {
+    let offset = if prefix.starts_with(PIPELINE_MAGIC) {
+        let version = prefix[PIPELINE_MAGIC.len()..].first()
+            .ok_or_else(|| PipelineError::InvalidData("Data too short".into()))?;
+        if *version > PIPELINE_FORMAT_VERSION {
+            return Err(PipelineError::InvalidData(format!(
+                "Pipeline format {} is newer than this app supports", version
+            )));
+        }
+        PIPELINE_MAGIC.len() + 1
+    } else {
+        0
+    };
+    let len_bytes = prefix.get(offset..offset + 4)
+        .ok_or_else(|| PipelineError::InvalidData("Data too short".into()))?;
+    let metadata_len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+    if metadata_len > MAX_METADATA_LEN {
+        return Err(PipelineError::InvalidData("Invalid metadata length".into()));
     }
+    Ok((offset + 4, metadata_len))
+}
 
-    let metadata_len = u32::from_le_bytes(data[..4].try_into().unwrap()) as usize;
-    if data.len() < 4 + metadata_len {
+/// The metadata of pipeline output `data` and the offset of its payload
+pub fn read_metadata(data: &[u8]) -> Result<(PipelineMetadata, usize), PipelineError> {
+    let (offset, metadata_len) = metadata_span(data)?;
+    if data.len() < offset + metadata_len {
         return Err(PipelineError::InvalidData("Invalid metadata length".into()));
     }
-    
-    let metadata: PipelineMetadata = serde_json::from_slice(&data[4..4 + metadata_len])
+    let metadata = serde_json::from_slice(&data[offset..offset + metadata_len])
         .map_err(|e| PipelineError::Serialization(e.to_string()))?;
-    
-    let mut current_data = data[4 + metadata_len..].to_vec();
+    Ok((metadata, offset + metadata_len))
+}
+
+/// Undo the layers recorded in `data`'s own metadata; no preset is needed
+pub fn reverse_pipeline(
+    data: &[u8],
+    context: &PipelineContext,
+) -> Result<PipelineResult, PipelineError> {
+    let (metadata, payload_offset) = read_metadata(data)?;
+    let mut current_data = data[payload_offset..].to_vec();
     let mut layers_applied = Vec::new();
 
     for layer_meta in metadata.layers.iter().rev() {
@@ -234,6 +425,7 @@ pub fn reverse_pipeline(
                     output_size: output.len(),
                     success: true,
                     error: None,
+                    skipped: false,
                 });
                 current_data = output;
             }
@@ -245,6 +437,7 @@ pub fn reverse_pipeline(
                     output_size: 0,
                     success: false,
                     error: Some(e.to_string()),
+                    skipped: false,
                 });
                 return Err(e);
             }
@@ -268,76 +461,21 @@ pub fn reverse_pipeline(
 fn apply_layer(
     data: &[u8],
     layer: &PipelineLayer,
+    file_name: Option<&str>,
     context: &PipelineContext,
 ) -> Result<(Vec<u8>, LayerMetadata), PipelineError> {
-    match &layer.operation {
-        PipelineOperation::Compress { algorithm, level } => {
-            let settings = CompressionSettings {
-                algorithm: CompressAlgorithm::from(algorithm.as_str()),
-                level: *level,
-                prefer_speed: false,
-            };
-            let result = compress(data, &settings)
-                .map_err(|e| PipelineError::Compression(e.to_string()))?;
-            
-            Ok((result.data, LayerMetadata {
-                operation_type: "compress".to_string(),
-                params: serde_json::json!({
-                    "algorithm": algorithm,
-                    "level": level
-                }),
-            }))
-        }
-        
-        PipelineOperation::EncryptPassword { .. } => {
-            let password = context.passwords.get(&layer.id)
-                .ok_or_else(|| PipelineError::MissingPassword(layer.id.clone()))?;
-            
-            let encrypted = encrypt_with_password(data, password.as_bytes())
-                .map_err(|e| PipelineError::Encryption(e.to_string()))?;
-            
-            Ok((encrypted, LayerMetadata {
-                operation_type: "encrypt_password".to_string(),
-                params: serde_json::json!({}),
-            }))
-        }
-        
-        PipelineOperation::EncryptHybridPQ { recipient_bundle } => {
-            let bundle = recipient_bundle.as_ref()
-                .ok_or_else(|| PipelineError::MissingRecipient)?;
-            
-            let payload = encrypt(data, bundle)
-                .map_err(|e| PipelineError::Encryption(e.to_string()))?;
-            
-            let serialized = serde_json::to_vec(&payload)
-                .map_err(|e| PipelineError::Serialization(e.to_string()))?;
-            
-            Ok((serialized, LayerMetadata {
-                operation_type: "encrypt_hybrid_pq".to_string(),
-                params: serde_json::json!({}),
-            }))
-        }
-        
-        PipelineOperation::Hash => {
-            
-            let hash = hash_data(data);
-            Ok((data.to_vec(), LayerMetadata {
-                operation_type: "hash".to_string(),
-                params: serde_json::json!({
-                    "hash": hex::encode(hash)
-                }),
-            }))
-        }
-        
-        PipelineOperation::Base64Encode => {
-            use base64::{engine::general_purpose::STANDARD, Engine};
-            let encoded = STANDARD.encode(data);
-            Ok((encoded.into_bytes(), LayerMetadata {
-                operation_type: "base64_encode".to_string(),
-                params: serde_json::json!({}),
-            }))
-        }
-    }
+    let step_id = layer.operation.step_id();
+    let step = find_step(step_id)
+        .ok_or_else(|| PipelineError::UnknownOperation(step_id.to_string()))?;
+    let ctx = StepContext { layer_id: &layer.id, file_name, pipeline: context };
+    let (output, params) = step.process(data, &layer.operation.step_params(), &ctx)?;
+
+    Ok((output, LayerMetadata {
+        layer_id: Some(layer.id.clone()),
+        operation_type: step_id.to_string(),
+        step_version: step.version(),
+        params,
+    }))
 }
 
 fn reverse_layer(
@@ -345,58 +483,23 @@ fn reverse_layer(
     metadata: &LayerMetadata,
     context: &PipelineContext,
 ) -> Result<Vec<u8>, PipelineError> {
-    match metadata.operation_type.as_str() {
-        "compress" => {
-            let algorithm = metadata.params["algorithm"].as_str().unwrap_or("zstd");
-            decompress(data, CompressAlgorithm::from(algorithm))
-                .map_err(|e| PipelineError::Compression(e.to_string()))
-        }
-        
-        "encrypt_password" => {
-            
-            for password in context.passwords.values() {
-                if let Ok(decrypted) = decrypt_with_password(data, password.as_bytes()) {
-                    return Ok(decrypted);
-                }
-            }
-            Err(PipelineError::MissingPassword("No valid password found".into()))
-        }
-        
-        "encrypt_hybrid_pq" => {
-            let keypair = context.keypair.as_ref()
-                .ok_or_else(|| PipelineError::MissingKeypair)?;
-            
-            let payload = serde_json::from_slice(data)
-                .map_err(|e| PipelineError::Serialization(e.to_string()))?;
-            
-            decrypt(&payload, keypair)
-                .map_err(|e| PipelineError::Encryption(e.to_string()))
-        }
-        
-        "hash" => {
-            
-            Ok(data.to_vec())
-        }
-        
-        "base64_encode" => {
-            use base64::{engine::general_purpose::STANDARD, Engine};
-            let decoded = STANDARD.decode(data)
-                .map_err(|e| PipelineError::Encoding(e.to_string()))?;
-            Ok(decoded)
-        }
-        
-        _ => Err(PipelineError::UnknownOperation(metadata.operation_type.clone()))
+    let step = find_step(&metadata.operation_type)
+        .ok_or_else(|| PipelineError::UnknownOperation(metadata.operation_type.clone()))?;
+    if metadata.step_version > step.version() {
+        return Err(PipelineError::InvalidData(format!(
+            "{} layer was written by a newer version of the step", metadata.operation_type
+        )));
     }
+    let ctx = StepContext {
+        layer_id: metadata.layer_id.as_deref().unwrap_or_default(),
+        file_name: None,
+        pipeline: context,
+    };
+    step.reverse(data, &metadata.params, &ctx)
 }
 
 fn get_operation_type(op: &PipelineOperation) -> String {
-    match op {
-        PipelineOperation::Compress { .. } => "compress".to_string(),
-        PipelineOperation::EncryptPassword { .. } => "encrypt_password".to_string(),
-        PipelineOperation::EncryptHybridPQ { .. } => "encrypt_hybrid_pq".to_string(),
-        PipelineOperation::Hash => "hash".to_string(),
-        PipelineOperation::Base64Encode => "base64_encode".to_string(),
-    }
+    op.step_id().to_string()
 }
 
 #[derive(Debug)]
@@ -448,6 +551,7 @@ pub fn get_preset_pipelines() -> Vec<PipelineConfig> {
                     },
                     enabled: true,
                     order: 0,
+                    condition: None,
                 },
             ],
             created_at: 0,
@@ -467,6 +571,7 @@ pub fn get_preset_pipelines() -> Vec<PipelineConfig> {
                     },
                     enabled: true,
                     order: 0,
+                    condition: None,
                 },
             ],
             created_at: 0,
@@ -486,12 +591,14 @@ pub fn get_preset_pipelines() -> Vec<PipelineConfig> {
                     },
                     enabled: true,
                     order: 0,
+                    condition: None,
                 },
                 PipelineLayer {
                     id: "password-encrypt".to_string(),
                     operation: PipelineOperation::EncryptPassword { password: None },
                     enabled: true,
                     order: 1,
+                    condition: None,
                 },
             ],
             created_at: 0,
@@ -511,12 +618,14 @@ pub fn get_preset_pipelines() -> Vec<PipelineConfig> {
                     },
                     enabled: true,
                     order: 0,
+                    condition: None,
                 },
                 PipelineLayer {
                     id: "pq-encrypt".to_string(),
                     operation: PipelineOperation::EncryptHybridPQ { recipient_bundle: None },
                     enabled: true,
                     order: 1,
+                    condition: None,
                 },
             ],
             created_at: 0,
@@ -536,24 +645,28 @@ pub fn get_preset_pipelines() -> Vec<PipelineConfig> {
                     },
                     enabled: true,
                     order: 0,
+                    condition: None,
                 },
                 PipelineLayer {
                     id: "password-layer".to_string(),
                     operation: PipelineOperation::EncryptPassword { password: None },
                     enabled: true,
                     order: 1,
+                    condition: None,
                 },
                 PipelineLayer {
                     id: "pq-layer".to_string(),
                     operation: PipelineOperation::EncryptHybridPQ { recipient_bundle: None },
                     enabled: true,
                     order: 2,
+                    condition: None,
                 },
                 PipelineLayer {
                     id: "base64-layer".to_string(),
                     operation: PipelineOperation::Base64Encode,
                     enabled: true,
                     order: 3,
+                    condition: None,
                 },
             ],
             created_at: 0,
@@ -563,11 +676,13 @@ pub fn get_preset_pipelines() -> Vec<PipelineConfig> {
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn pipeline_process(
     data: Vec<u8>,
     config: PipelineConfig,
     passwords: std::collections::HashMap<String, String>,
     keypair_bytes: Option<Vec<u8>>,
+    file_name: Option<String>,
 ) -> Result<PipelineResult, AppError> {
     let keypair = if let Some(bytes) = keypair_bytes {
         Some(HybridKeypair::from_bytes(&bytes)
@@ -578,11 +693,14 @@ pub async fn pipeline_process(
     
     let context = PipelineContext { passwords, keypair };
     
-    process_pipeline(&data, &config, &context)
-        .map_err(|e| AppError::Validation(e.to_string()))
+    let started = std::time::Instant::now();
+    let result = process_pipeline_for_file(&data, file_name.as_deref(), &config, &context);
+    record_run(&PipelineRun::single(&config, data.len(), &result, started.elapsed()));
+    result.map_err(|e| AppError::Validation(e.to_string()))
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub async fn pipeline_reverse(
     data: Vec<u8>,
     passwords: std::collections::HashMap<String, String>,
@@ -601,12 +719,184 @@ pub async fn pipeline_reverse(
         .map_err(|e| AppError::Validation(e.to_string()))
 }
 
+/// A layer of pipeline output, as `pipeline_inspect` reports it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InspectedLayer {
+    pub layer_id: Option<String>,
+    pub step: String,
+    /// Label of the registered step; `None` if the step is not registered
+    pub label: Option<String>,
+    pub step_version: u32,
+    /// A registered step can reverse this layer
+    pub supported: bool,
+    /// What reversing needs: `"password"` or `"keypair"`
+    pub secret: Option<String>,
+    pub params: serde_json::Value,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PipelineInspection {
+    pub format_version: u8,
+    pub pipeline_id: Option<String>,
+    pub pipeline_name: Option<String>,
+    pub created_at: Option<u64>,
+    pub original_size: usize,
+    pub payload_size: u64,
+    /// In the order they were applied
+    pub layers: Vec<InspectedLayer>,
+    /// Every layer is supported, so `pipeline_reverse` can undo the file
+    /// given the secrets the layers list
+    pub reversible: bool,
+}
+
+pub fn inspect_metadata(metadata: &PipelineMetadata, payload_size: u64) -> PipelineInspection {
+    let layers: Vec<_> = metadata.layers.iter().map(|layer| {
+        let step = find_step(&layer.operation_type);
+        InspectedLayer {
+            layer_id: layer.layer_id.clone(),
+            step: layer.operation_type.clone(),
+            label: step.as_ref().map(|s| s.describe().label),
+            step_version: layer.step_version,
+            supported: step.as_ref().is_some_and(|s| layer.step_version <= s.version()),
+            secret: step.as_ref().and_then(|s| s.secret()).map(str::to_string),
+            params: layer.params.clone(),
+        }
+    }).collect();
+
+    PipelineInspection {
+        format_version: metadata.version,
+        pipeline_id: metadata.pipeline_id.clone(),
+        pipeline_name: metadata.pipeline_name.clone(),
+        created_at: Some(metadata.created_at).filter(|&t| t > 0),
+        original_size: metadata.original_size,
+        payload_size,
+        reversible: layers.iter().all(|l| l.supported),
+        layers,
+    }
+}
+
+/// Describe the pipeline output at `path` from its header alone, without
+/// reading the payload or needing any password or key
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_inspect(path: String) -> Result<PipelineInspection, AppError> {
+    let mut file = std::fs::File::open(&path)?;
+    let file_len = file.metadata()?.len();
+    let mut prefix = Vec::new();
+    (&mut file).take(PIPELINE_MAGIC.len() as u64 + 5).read_to_end(&mut prefix)?;
+    let (offset, metadata_len) = metadata_span(&prefix)
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let header_len = (offset + metadata_len) as u64;
+    let mut header = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.take(header_len).read_to_end(&mut header)?;
+    let (metadata, _) = read_metadata(&header)
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    Ok(inspect_metadata(&metadata, file_len - header_len))
+}
+
+/// Run `config` over every file of `folder` in the background, writing
+/// `<output_dir>/<relative path>.vxp`. Returns a job ID that shares the
+/// `compress-job-progress` events and `compress_job_cancel` with folder
+/// compression. A transcode or resize layer with `keep_original` also gets
+/// the source file copied to `<output_dir>/<relative path>`.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_folder_start(
+    app: crate::AppHandle,
+    folder: String,
+    output_dir: String,
+    config: PipelineConfig,
+    passwords: std::collections::HashMap<String, String>,
+    keypair_bytes: Option<Vec<u8>>,
+) -> Result<String, AppError> {
+    let context = pipeline_context(passwords, keypair_bytes)?;
+    let files = folder_files(&folder)?;
+    let output_dir = std::path::PathBuf::from(output_dir);
+
+    Ok(start_job(app, JobKind::Pipeline, files, move |file, _cancel, progress| {
+        let started = std::time::Instant::now();
+        let result = process_folder_file(file, &output_dir, &config, &context);
+        record_run(&PipelineRun::file(&config, file.size, &result, started.elapsed()));
+        result?;
+        progress(file.size);
+        Ok(())
+    }))
+}
+
+pub(crate) fn pipeline_context(
+    passwords: std::collections::HashMap<String, String>,
+    keypair_bytes: Option<Vec<u8>>,
+) -> Result<PipelineContext, AppError> {
+    let keypair = if let Some(bytes) = keypair_bytes {
+        Some(HybridKeypair::from_bytes(&bytes)
+            .map_err(|e| AppError::Validation(e.to_string()))?)
+    } else {
+        None
+    };
+    Ok(PipelineContext { passwords, keypair })
+}
+
+/// What `process_folder_file` read and wrote
+pub(crate) struct ProcessedFile {
+    pub output_size: u64,
+    pub source_hash: [u8; 32],
+    pub output_hash: [u8; 32],
+}
+
+/// Run `config` over one file of a folder job, writing
+/// `<output_dir>/<relative path>.vxp` (and the original, for a transcode or
+/// resize layer with `keep_original`)
+pub(crate) fn process_folder_file(
+    file: &JobFile,
+    output_dir: &std::path::Path,
+    config: &PipelineConfig,
+    context: &PipelineContext,
+) -> Result<ProcessedFile, AppError> {
+    let data = std::fs::read(&file.source)?;
+    let name = file.source.file_name().and_then(|n| n.to_str());
+    let result = process_pipeline_for_file(&data, name, config, context)
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let output = file.output_path(output_dir, PIPELINE_FILE_EXT);
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_via_partial(&output, |mut writer| {
+        writer.write_all(&result.data)?;
+        writer.flush()?;
+        Ok(())
+    })?;
+    let keep_originals = config.layers.iter().any(|l| {
+        l.enabled
+            && match &l.operation {
+                PipelineOperation::Transcode { options } => options.keep_original,
+                PipelineOperation::Resize { options } => options.keep_original,
+                _ => false,
+            }
+    });
+    if keep_originals {
+        std::fs::write(output_dir.join(&file.relative), &data)?;
+    }
+    Ok(ProcessedFile {
+        output_size: result.data.len() as u64,
+        source_hash: hash_data(&data),
+        output_hash: hash_data(&result.data),
+    })
+}
+
+/// Built-in presets followed by the user's saved ones
+#[tauri::command]
+#[tracing::instrument(skip_all)]
 pub fn pipeline_get_presets() -> Vec<PipelineConfig> {
-    get_preset_pipelines()
+    let mut presets = get_preset_pipelines();
+    presets.extend(crate::pipeline_presets::user_presets());
+    presets
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 pub fn pipeline_validate(config: PipelineConfig) -> Result<bool, AppError> {
     
     let mut ids = std::collections::HashSet::new();
@@ -617,61 +907,73 @@ pub fn pipeline_validate(config: PipelineConfig) -> Result<bool, AppError> {
     }
 
     for layer in &config.layers {
-        match &layer.operation {
-            PipelineOperation::Compress { algorithm, level } => {
-                let _ = CompressAlgorithm::from(algorithm.as_str());
-                if *level < 0 || *level > 22 {
-                    return Err(AppError::Validation(format!(
-                        "Invalid compression level: {} (must be 0-22)", level
-                    )));
-                }
-            }
-            _ => {}
+        let step_id = layer.operation.step_id();
+        let step = find_step(step_id)
+            .ok_or_else(|| AppError::Validation(format!("Unknown pipeline step: {}", step_id)))?;
+        step.validate(&layer.operation.step_params())?;
+        if let Some(condition) = &layer.condition {
+            Condition::parse(condition)?;
         }
     }
+
+    let mut enabled: Vec<_> = config.layers.iter().filter(|l| l.enabled).collect();
+    enabled.sort_by_key(|l| l.order);
+    let leading = enabled.iter().take_while(|l| l.operation.is_source_transform()).count();
+    if let Some(late) = enabled[leading..].iter().find(|l| l.operation.is_source_transform()) {
+        return Err(AppError::Validation(format!(
+            "Layer {} must come before compression and encryption layers", late.id
+        )));
+    }
     
     Ok(true)
 }
 
+/// Estimated output size of `config` for a file of `original_size` bytes.
+/// Layer conditions are checked against the size and, when given,
+/// `file_name`; each operation reports whether it `applies`, with `null`
+/// for a condition that depends on the content and is counted as applied.
 #[tauri::command]
+#[tracing::instrument(skip_all)]
 pub fn pipeline_estimate(
     original_size: usize,
     config: PipelineConfig,
+    file_name: Option<String>,
 ) -> serde_json::Value {
     let mut estimated_size = original_size as f64;
     let mut operations = Vec::new();
+    let facts = ConditionFacts {
+        size: original_size as u64,
+        name: file_name,
+        format: None,
+    };
     
-    for layer in config.layers.iter().filter(|l| l.enabled) {
-        let (ratio, op_name) = match &layer.operation {
-            PipelineOperation::Compress { algorithm, .. } => {
-                let ratio = match algorithm.as_str() {
-                    "zstd" => 0.4,
-                    "lz4" => 0.6,
-                    "snap" => 0.65,
-                    "brotli" => 0.35,
-                    "gzip" => 0.45,
-                    _ => 1.0,
-                };
-                (ratio, format!("Compress ({})", algorithm))
-            }
-            PipelineOperation::EncryptPassword { .. } => {
-                (1.05, "Password Encryption".to_string()) 
-            }
-            PipelineOperation::EncryptHybridPQ { .. } => {
-                (1.1, "PQ Encryption".to_string()) 
-            }
-            PipelineOperation::Hash => {
-                (1.0, "Hash".to_string())
-            }
-            PipelineOperation::Base64Encode => {
-                (1.33, "Base64 Encode".to_string()) 
+    let mut sorted_layers: Vec<_> = config.layers.iter()
+        .filter(|l| l.enabled)
+        .collect();
+    sorted_layers.sort_by_key(|l| l.order);
+
+    for layer in sorted_layers {
+        let applies = match &layer.condition {
+            Some(condition) => Condition::parse(condition)
+                .ok()
+                .and_then(|c| c.evaluate(&facts)),
+            None => Some(true),
+        };
+        let (ratio, op_name) = match find_step(layer.operation.step_id()) {
+            Some(step) => {
+                let estimate = step.estimate(&layer.operation.step_params());
+                (estimate.ratio, estimate.label)
             }
+            None => (1.0, layer.operation.step_id().to_string()),
         };
         
-        estimated_size *= ratio;
+        if applies != Some(false) {
+            estimated_size *= ratio;
+        }
         operations.push(serde_json::json!({
             "operation": op_name,
             "ratio": ratio,
+            "applies": applies,
             "estimated_size_after": estimated_size as usize
         }));
     }
@@ -682,4 +984,4 @@ pub fn pipeline_estimate(
         "overall_ratio": estimated_size / original_size as f64,
         "operations": operations
     })
-}
\ No newline at end of file
+}
@@ -0,0 +1,387 @@
+//! Batch Pipeline Processing
+//!
+//! `pipeline_process_folder` runs a pipeline over every matching file of a
+//! folder with a bounded pool of worker threads, writing
+//! `<output_dir>/<relative path>.vxp` like `pipeline_folder_start`. Each
+//! worker holds one whole file in memory, so `concurrency` also bounds
+//! memory use.
+//!
+//! The job is listed and cancelled through the folder job registry
+//! (`compress_job_list`, `compress_job_cancel`) and sends its totals as
+//! `compress-job-progress`. Each file also gets `pipeline-file-progress`
+//! events when it starts and when it finishes. A failed file does not stop
+//! the others unless `stop_on_error` is set; cancelling lets files already
+//! in progress finish. When the job ends, a summary of every file is written
+//! to `<output_dir>/pipeline-report.json`. Jobs are checkpointed as they go
+//! and can be continued with `pipeline_resume_job`; see `pipeline_checkpoint`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::Emitter;
+
+use crate::AppHandle;
+use crate::compress_jobs::{
+    finish_job, folder_files, is_running, new_job_id, publish_progress, register_job, JobFile, JobKind,
+    JobProgress, JobState,
+};
+use crate::github::AppError;
+use crate::pipeline::{pipeline_context, pipeline_validate, process_folder_file, PipelineConfig, PipelineContext};
+use crate::pipeline_history::{record_run, PipelineRun};
+use crate::pipeline_checkpoint::{
+    find_job, forget_job, is_unchanged, read_checkpoint, read_journal, remember_job, remove_checkpoint,
+    write_checkpoint, FileCheckpoint, JobCheckpoint, Journal, CHECKPOINT_VERSION,
+};
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::util::now_secs;
+
+/// Name of the summary written to the output folder
+pub const PIPELINE_REPORT_FILE: &str = "pipeline-report.json";
+/// Most worker threads a batch may use
+pub const MAX_CONCURRENCY: usize = 16;
+const DEFAULT_CONCURRENCY: usize = 4;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BatchOptions {
+    /// Extensions to include, without the dot and ignoring case. Empty
+    /// includes every file.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Worker threads, 1 to `MAX_CONCURRENCY`. Defaults to the number of
+    /// cores, at most 4.
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// Stop starting new files after the first failure
+    #[serde(default)]
+    pub stop_on_error: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileState {
+    Processing,
+    Done,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileProgress {
+    pub job_id: String,
+    pub relative: String,
+    pub state: FileState,
+    pub input_size: u64,
+    pub output_size: Option<u64>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub job_id: String,
+    pub state: JobState,
+    pub pipeline_name: String,
+    pub folder: String,
+    pub output_dir: String,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub files_total: usize,
+    pub files_succeeded: usize,
+    pub files_failed: usize,
+    /// Files never started because the job was cancelled or stopped
+    pub files_not_processed: usize,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Files a resumed job found already done and unchanged
+    #[serde(default)]
+    pub files_resumed: usize,
+    /// Finished files, sorted by relative path
+    pub files: Vec<FileProgress>,
+}
+
+/// `files` whose extension is in `extensions`, or all of them if it is empty
+pub fn matching_files(files: Vec<JobFile>, extensions: &[String]) -> Vec<JobFile> {
+    if extensions.is_empty() {
+        return files;
+    }
+    let wanted: Vec<String> = extensions
+        .iter()
+        .map(|e| e.trim_start_matches('.').to_lowercase())
+        .collect();
+    files
+        .into_iter()
+        .filter(|f| {
+            Path::new(&f.relative)
+                .extension()
+                .map(|e| wanted.contains(&e.to_string_lossy().to_lowercase()))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+pub fn resolve_concurrency(requested: Option<usize>) -> Result<usize, AppError> {
+    match requested {
+        Some(n) if (1..=MAX_CONCURRENCY).contains(&n) => Ok(n),
+        Some(_) => Err(AppError::Validation(format!("Concurrency must be 1-{}", MAX_CONCURRENCY))),
+        None => Ok(std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(DEFAULT_CONCURRENCY)),
+    }
+}
+
+/// Run `process` over `files` on `concurrency` threads, updating `job` and
+/// passing each file's progress with the job totals to `report`. Each file
+/// is background work (see `scheduler`), which caps how many run at once.
+/// `process` returns the size written. Returns the finished files sorted by path;
+/// `job.state` holds the outcome.
+pub fn run_batch<P, R>(
+    job: &mut JobProgress,
+    files: &[JobFile],
+    concurrency: usize,
+    stop_on_error: bool,
+    cancel: &AtomicBool,
+    process: P,
+    report: R,
+) -> Vec<FileProgress>
+where
+    P: Fn(&JobFile) -> Result<u64, AppError> + Sync,
+    R: Fn(&FileProgress, &JobProgress) + Sync,
+{
+    let job_id = job.job_id.clone();
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let shared = Mutex::new((job.clone(), Vec::<FileProgress>::with_capacity(files.len())));
+
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, files.len().max(1)) {
+            scope.spawn(|| loop {
+                if cancel.load(Ordering::Relaxed) || stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Some(file) = files.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    break;
+                };
+                let mut outcome = FileProgress {
+                    job_id: job_id.clone(),
+                    relative: file.relative.clone(),
+                    state: FileState::Processing,
+                    input_size: file.size,
+                    output_size: None,
+                    duration_ms: 0,
+                    error: None,
+                };
+                {
+                    let mut guard = shared.lock().unwrap();
+                    guard.0.current_file = Some(file.relative.clone());
+                    report(&outcome, &guard.0);
+                }
+
+                let started = Instant::now();
+                let permit = SCHEDULER.acquire_blocking(JobClass::Background);
+                let result = process(file);
+                drop(permit);
+                outcome.duration_ms = started.elapsed().as_millis() as u64;
+                match result {
+                    Ok(size) => {
+                        outcome.state = FileState::Done;
+                        outcome.output_size = Some(size);
+                    }
+                    Err(e) => {
+                        outcome.state = FileState::Failed;
+                        outcome.error = Some(e.to_string());
+                        if stop_on_error {
+                            stop.store(true, Ordering::Relaxed);
+                        }
+                    }
+                }
+
+                let mut guard = shared.lock().unwrap();
+                let (progress, finished) = &mut *guard;
+                progress.files_done += 1;
+                progress.bytes_done += file.size;
+                report(&outcome, progress);
+                finished.push(outcome);
+            });
+        }
+    });
+
+    let (progress, mut finished) = shared.into_inner().unwrap();
+    *job = progress;
+    finished.sort_by(|a, b| a.relative.cmp(&b.relative));
+
+    let failed = finished.iter().filter(|f| f.state == FileState::Failed).count();
+    job.state = if failed > 0 {
+        job.error = Some(format!("{} of {} files failed", failed, files.len()));
+        JobState::Failed
+    } else if finished.len() < files.len() {
+        JobState::Cancelled
+    } else {
+        JobState::Completed
+    };
+    job.current_file = None;
+    finished
+}
+
+pub fn build_report(
+    job: &JobProgress,
+    config: &PipelineConfig,
+    folder: &str,
+    output_dir: &str,
+    started_at: u64,
+    files: Vec<FileProgress>,
+) -> BatchReport {
+    let succeeded: Vec<_> = files.iter().filter(|f| f.state == FileState::Done).collect();
+    BatchReport {
+        job_id: job.job_id.clone(),
+        state: job.state,
+        pipeline_name: config.name.clone(),
+        folder: folder.to_string(),
+        output_dir: output_dir.to_string(),
+        started_at,
+        finished_at: now_secs(),
+        files_total: job.files_total,
+        files_succeeded: succeeded.len(),
+        files_failed: files.len() - succeeded.len(),
+        files_not_processed: job.files_total - files.len(),
+        bytes_in: succeeded.iter().map(|f| f.input_size).sum(),
+        bytes_out: succeeded.iter().filter_map(|f| f.output_size).sum(),
+        files_resumed: 0,
+        files,
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Run `checkpoint`'s job over `files` in the background, skipping files
+/// in `done` that are unchanged since. Returns the job ID.
+fn spawn_batch(
+    app: AppHandle,
+    checkpoint: JobCheckpoint,
+    files: Vec<JobFile>,
+    context: PipelineContext,
+    done: HashMap<String, FileCheckpoint>,
+) -> Result<String, AppError> {
+    let concurrency = resolve_concurrency(checkpoint.options.concurrency)?;
+    let output_root = PathBuf::from(&checkpoint.output_dir);
+    let journal = Journal::open(&output_root)?;
+    let (mut job, cancel) = register_job(&checkpoint.job_id, JobKind::Pipeline, &files);
+    let id = job.job_id.clone();
+    let started_at = now_secs();
+    let started = Instant::now();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let resumed = AtomicUsize::new(0);
+        let finished = run_batch(
+            &mut job,
+            &files,
+            concurrency,
+            checkpoint.options.stop_on_error,
+            &cancel,
+            |file| {
+                if let Some(entry) = done.get(&file.relative) {
+                    if is_unchanged(file, entry, &output_root) {
+                        resumed.fetch_add(1, Ordering::Relaxed);
+                        return Ok(entry.output_size);
+                    }
+                }
+                let processed = process_folder_file(file, &output_root, &checkpoint.config, &context)?;
+                journal.record(&FileCheckpoint::new(file, &processed))?;
+                Ok(processed.output_size)
+            },
+            |file, progress| {
+                publish_progress(&app, progress);
+                let _ = app.emit("pipeline-file-progress", file);
+            },
+        );
+
+        let mut report = build_report(
+            &job,
+            &checkpoint.config,
+            &checkpoint.folder,
+            &checkpoint.output_dir,
+            started_at,
+            finished,
+        );
+        report.files_resumed = resumed.into_inner();
+        record_run(&PipelineRun::folder(&checkpoint.config, &report, started.elapsed()));
+        let json = serde_json::to_vec_pretty(&report).unwrap_or_default();
+        if let Err(e) = std::fs::write(output_root.join(PIPELINE_REPORT_FILE), json) {
+            job.error.get_or_insert(format!("Could not write report: {}", e));
+        }
+        if job.state == JobState::Completed {
+            remove_checkpoint(&output_root);
+            let _ = forget_job(&job.job_id);
+        }
+        publish_progress(&app, &job);
+        finish_job(&job.job_id);
+    });
+    Ok(id)
+}
+
+/// Run `config` over the files of `folder` that match `options`, in the
+/// background. Returns the job ID used by the progress events,
+/// `compress_job_cancel` and `pipeline_resume_job`.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_process_folder(
+    app: AppHandle,
+    folder: String,
+    output_dir: String,
+    config: PipelineConfig,
+    passwords: HashMap<String, String>,
+    keypair_bytes: Option<Vec<u8>>,
+    options: Option<BatchOptions>,
+) -> Result<String, AppError> {
+    let options = options.unwrap_or_default();
+    resolve_concurrency(options.concurrency)?;
+    pipeline_validate(config.clone())?;
+    let context = pipeline_context(passwords, keypair_bytes)?;
+    let files = matching_files(folder_files(&folder)?, &options.extensions);
+    if files.is_empty() {
+        return Err(AppError::Validation("No files in the folder match".into()));
+    }
+
+    let checkpoint = JobCheckpoint {
+        version: CHECKPOINT_VERSION,
+        job_id: new_job_id(),
+        folder,
+        output_dir,
+        config,
+        options,
+        created_at: now_secs(),
+    };
+    write_checkpoint(&checkpoint)?;
+    remember_job(&checkpoint)?;
+    spawn_batch(app, checkpoint, files, context, HashMap::new())
+}
+
+/// Continue an unfinished `pipeline_process_folder` job from its checkpoint.
+/// Password and key layers need the same secrets as the first run.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_resume_job(
+    app: AppHandle,
+    job_id: String,
+    passwords: HashMap<String, String>,
+    keypair_bytes: Option<Vec<u8>>,
+) -> Result<String, AppError> {
+    if is_running(&job_id) {
+        return Err(AppError::Validation("Job is already running".into()));
+    }
+    let job = find_job(&job_id).ok_or_else(|| AppError::Validation(format!("No unfinished job {}", job_id)))?;
+    let output_dir = PathBuf::from(&job.output_dir);
+    let checkpoint = read_checkpoint(&output_dir)?;
+    if checkpoint.job_id != job_id {
+        return Err(AppError::Validation("Output folder holds a different job".into()));
+    }
+    pipeline_validate(checkpoint.config.clone())?;
+    let context = pipeline_context(passwords, keypair_bytes)?;
+    let files = matching_files(folder_files(&checkpoint.folder)?, &checkpoint.options.extensions);
+    let done = read_journal(&output_dir);
+    spawn_batch(app, checkpoint, files, context, done)
+}
@@ -0,0 +1,228 @@
+//! Resumable Pipeline Jobs
+//!
+//! `pipeline_process_folder` checkpoints its job in the output folder so a
+//! job cut short by a crash, a restart or a cancel can be picked up with
+//! `pipeline_resume_job`:
+//!
+//! - `.pipeline-job.json` holds the folder, pipeline and options, written
+//!   once when the job starts
+//! - `.pipeline-job.journal` gets one JSON line per finished file with the
+//!   BLAKE3 hashes of its source and output, flushed to disk before the
+//!   next file is reported done
+//!
+//! On resume a file is skipped only if its source still hashes the same and
+//! its output is on disk with the recorded hash; anything else runs again,
+//! so resuming is safe after edits to the folder or a half-written output.
+//! Unfinished jobs are listed in the settings store, which cache clearing
+//! leaves alone. Passwords and keys are never stored and must be given
+//! again to resume.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::compress_jobs::{is_running, JobFile};
+use crate::compress_stream::write_via_partial;
+use crate::crypto::hash_data;
+use crate::github::AppError;
+use crate::local_store::{with_store, SETTINGS_NS};
+use crate::pipeline::{PipelineConfig, ProcessedFile, PIPELINE_FILE_EXT};
+use crate::pipeline_batch::BatchOptions;
+
+pub const CHECKPOINT_FILE: &str = ".pipeline-job.json";
+pub const JOURNAL_FILE: &str = ".pipeline-job.journal";
+pub const CHECKPOINT_VERSION: u8 = 1;
+const JOBS_KEY: &str = "pipeline_jobs";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobCheckpoint {
+    pub version: u8,
+    pub job_id: String,
+    pub folder: String,
+    pub output_dir: String,
+    pub config: PipelineConfig,
+    pub options: BatchOptions,
+    pub created_at: u64,
+}
+
+/// A finished file, as recorded in the journal
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FileCheckpoint {
+    pub relative: String,
+    pub source_hash: String,
+    pub output_hash: String,
+    pub output_size: u64,
+}
+
+impl FileCheckpoint {
+    pub(crate) fn new(file: &JobFile, processed: &ProcessedFile) -> Self {
+        Self {
+            relative: file.relative.clone(),
+            source_hash: hex::encode(processed.source_hash),
+            output_hash: hex::encode(processed.output_hash),
+            output_size: processed.output_size,
+        }
+    }
+}
+
+/// Entry of the unfinished job list shown to the user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResumableJob {
+    pub job_id: String,
+    pub folder: String,
+    pub output_dir: String,
+    pub pipeline_name: String,
+    pub created_at: u64,
+}
+
+pub fn write_checkpoint(checkpoint: &JobCheckpoint) -> Result<(), AppError> {
+    let dir = Path::new(&checkpoint.output_dir);
+    std::fs::create_dir_all(dir)?;
+    let json = serde_json::to_vec_pretty(checkpoint).map_err(|e| AppError::Validation(e.to_string()))?;
+    write_via_partial(&dir.join(CHECKPOINT_FILE), |mut writer| {
+        writer.write_all(&json)?;
+        writer.flush()?;
+        Ok(())
+    })?;
+    // A new job starts with an empty journal
+    File::create(dir.join(JOURNAL_FILE))?;
+    Ok(())
+}
+
+pub fn read_checkpoint(output_dir: &Path) -> Result<JobCheckpoint, AppError> {
+    let bytes = std::fs::read(output_dir.join(CHECKPOINT_FILE))
+        .map_err(|_| AppError::Validation(format!("No pipeline job in {}", output_dir.display())))?;
+    let checkpoint: JobCheckpoint = serde_json::from_slice(&bytes)
+        .map_err(|e| AppError::Validation(format!("Damaged job checkpoint: {}", e)))?;
+    if checkpoint.version > CHECKPOINT_VERSION {
+        return Err(AppError::Validation("Job checkpoint is from a newer version of the app".into()));
+    }
+    Ok(checkpoint)
+}
+
+/// Finished files by relative path. Lines that do not parse, such as one
+/// cut off by a crash, are ignored; the last entry for a path wins.
+pub fn read_journal(output_dir: &Path) -> HashMap<String, FileCheckpoint> {
+    let Ok(file) = File::open(output_dir.join(JOURNAL_FILE)) else {
+        return HashMap::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<FileCheckpoint>(&line).ok())
+        .map(|entry| (entry.relative.clone(), entry))
+        .collect()
+}
+
+/// Append-only journal shared by a job's workers
+pub struct Journal {
+    file: Mutex<File>,
+}
+
+impl Journal {
+    pub fn open(output_dir: &Path) -> Result<Self, AppError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(output_dir.join(JOURNAL_FILE))?;
+        Ok(Self { file: Mutex::new(file) })
+    }
+
+    pub fn record(&self, entry: &FileCheckpoint) -> Result<(), AppError> {
+        let mut line = serde_json::to_vec(entry).map_err(|e| AppError::Validation(e.to_string()))?;
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&line)?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    std::fs::read(path).ok().map(|data| hex::encode(hash_data(&data)))
+}
+
+/// Whether `file` was already processed into `output_dir` as `entry`
+/// records, with neither the source nor the output changed since
+pub fn is_unchanged(file: &JobFile, entry: &FileCheckpoint, output_dir: &Path) -> bool {
+    hash_file(&file.source).as_ref() == Some(&entry.source_hash)
+        && hash_file(&file.output_path(output_dir, PIPELINE_FILE_EXT)).as_ref() == Some(&entry.output_hash)
+}
+
+/// Remove a job's checkpoint and journal
+pub fn remove_checkpoint(output_dir: &Path) {
+    let _ = std::fs::remove_file(output_dir.join(CHECKPOINT_FILE));
+    let _ = std::fs::remove_file(output_dir.join(JOURNAL_FILE));
+}
+
+fn stored_jobs() -> Vec<ResumableJob> {
+    with_store(|store| store.get_json(SETTINGS_NS, JOBS_KEY))
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+fn save_jobs(jobs: &[ResumableJob]) -> Result<(), AppError> {
+    with_store(|store| store.put_json(SETTINGS_NS, JOBS_KEY, &jobs, None))
+}
+
+pub(crate) fn remember_job(checkpoint: &JobCheckpoint) -> Result<(), AppError> {
+    let mut jobs = stored_jobs();
+    jobs.retain(|j| j.job_id != checkpoint.job_id && j.output_dir != checkpoint.output_dir);
+    jobs.push(ResumableJob {
+        job_id: checkpoint.job_id.clone(),
+        folder: checkpoint.folder.clone(),
+        output_dir: checkpoint.output_dir.clone(),
+        pipeline_name: checkpoint.config.name.clone(),
+        created_at: checkpoint.created_at,
+    });
+    save_jobs(&jobs)
+}
+
+pub(crate) fn forget_job(job_id: &str) -> Result<Option<ResumableJob>, AppError> {
+    let mut jobs = stored_jobs();
+    let Some(index) = jobs.iter().position(|j| j.job_id == job_id) else {
+        return Ok(None);
+    };
+    let job = jobs.remove(index);
+    save_jobs(&jobs)?;
+    Ok(Some(job))
+}
+
+pub(crate) fn find_job(job_id: &str) -> Option<ResumableJob> {
+    stored_jobs().into_iter().find(|j| j.job_id == job_id)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Unfinished pipeline jobs whose checkpoint is still on disk
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn pipeline_resumable_jobs() -> Vec<ResumableJob> {
+    stored_jobs()
+        .into_iter()
+        .filter(|j| Path::new(&j.output_dir).join(CHECKPOINT_FILE).is_file())
+        .collect()
+}
+
+/// Drop an unfinished job and its checkpoint, keeping the files it wrote.
+/// Returns false if there was no such job.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_discard_job(job_id: String) -> Result<bool, AppError> {
+    if is_running(&job_id) {
+        return Err(AppError::Validation("Cancel the job before discarding it".into()));
+    }
+    match forget_job(&job_id)? {
+        Some(job) => {
+            remove_checkpoint(Path::new(&job.output_dir));
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,424 @@
+//! Pipeline Layer Conditions
+//!
+//! A layer with a `condition` only runs when the expression holds for the
+//! file being processed, e.g.
+//!
+//! ```text
+//! size > 5MB and format == "jpeg"
+//! extension in [".raw", ".dng"] or not (size < 100KB)
+//! ```
+//!
+//! Facts: `size` (bytes of the input file), `name`, `extension` (lowercase,
+//! no dot) and `format` (detected content, e.g. `jpeg`, `png`). Sizes take
+//! `B`, `KB`, `MB` or `GB` suffixes (powers of 1024). Text comparisons
+//! ignore case. Branches are written as layers with opposite conditions.
+//!
+//! Evaluation is three-valued: a condition that needs a fact that is not
+//! known, such as `extension` for data without a file name, is undecided.
+//! `process_pipeline` skips layers whose condition is not true;
+//! `pipeline_estimate` reports undecided layers and counts them as applied.
+
+use crate::github::AppError;
+
+/// Longest condition accepted, in characters
+pub const MAX_CONDITION_LEN: usize = 500;
+
+/// What a condition can ask about the file being processed
+#[derive(Clone, Debug, Default)]
+pub struct ConditionFacts {
+    pub size: u64,
+    pub name: Option<String>,
+    pub format: Option<String>,
+}
+
+impl ConditionFacts {
+    pub fn extension(&self) -> Option<String> {
+        let name = self.name.as_deref()?;
+        std::path::Path::new(name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Fact {
+    Size,
+    Name,
+    Extension,
+    Format,
+}
+
+impl Fact {
+    fn parse(ident: &str) -> Option<Self> {
+        match ident {
+            "size" => Some(Self::Size),
+            "name" => Some(Self::Name),
+            "extension" | "ext" => Some(Self::Extension),
+            "format" => Some(Self::Format),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        self == Self::Size
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    Fact(Fact),
+    Literal(Value),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CmpOp, Operand),
+    In(Operand, Vec<Operand>),
+    Literal(bool),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Text(String),
+    Op(CmpOp),
+    And,
+    Or,
+    Not,
+    In,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+fn syntax_error(message: impl std::fmt::Display) -> AppError {
+    AppError::Validation(format!("Invalid condition: {}", message))
+}
+
+fn unit_multiplier(unit: &str) -> Option<f64> {
+    match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => Some(1.0),
+        "k" | "kb" | "kib" => Some(1024.0),
+        "m" | "mb" | "mib" => Some(1024.0 * 1024.0),
+        "g" | "gb" | "gib" => Some(1024.0 * 1024.0 * 1024.0),
+        _ => None,
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, AppError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => { tokens.push(Token::LParen); i += 1; }
+            ')' => { tokens.push(Token::RParen); i += 1; }
+            '[' => { tokens.push(Token::LBracket); i += 1; }
+            ']' => { tokens.push(Token::RBracket); i += 1; }
+            ',' => { tokens.push(Token::Comma); i += 1; }
+            '=' if next == Some('=') => { tokens.push(Token::Op(CmpOp::Eq)); i += 2; }
+            '!' if next == Some('=') => { tokens.push(Token::Op(CmpOp::Ne)); i += 2; }
+            '!' => { tokens.push(Token::Not); i += 1; }
+            '<' if next == Some('=') => { tokens.push(Token::Op(CmpOp::Le)); i += 2; }
+            '<' => { tokens.push(Token::Op(CmpOp::Lt)); i += 1; }
+            '>' if next == Some('=') => { tokens.push(Token::Op(CmpOp::Ge)); i += 2; }
+            '>' => { tokens.push(Token::Op(CmpOp::Gt)); i += 1; }
+            '&' if next == Some('&') => { tokens.push(Token::And); i += 2; }
+            '|' if next == Some('|') => { tokens.push(Token::Or); i += 2; }
+            '"' | '\'' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == c)
+                    .ok_or_else(|| syntax_error("unterminated string"))?;
+                tokens.push(Token::Text(chars[i + 1..i + 1 + end].iter().collect()));
+                i += end + 2;
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                let unit_start = i;
+                while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                let unit: String = chars[unit_start..i].iter().collect();
+                let value: f64 = number.parse().map_err(|_| syntax_error(format!("bad number {}", number)))?;
+                let multiplier = unit_multiplier(&unit).ok_or_else(|| syntax_error(format!("unknown size unit {}", unit)))?;
+                tokens.push(Token::Number(value * multiplier));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "in" => Token::In,
+                    _ => Token::Ident(word),
+                });
+            }
+            other => return Err(syntax_error(format!("unexpected '{}'", other))),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), AppError> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            _ => Err(syntax_error(format!("expected {}", what))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, AppError> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, AppError> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, AppError> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let inner = self.or()?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(inner)
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, AppError> {
+        let left = self.operand()?;
+        match self.peek() {
+            Some(Token::Op(op)) => {
+                let op = *op;
+                self.pos += 1;
+                let right = self.operand()?;
+                check_comparison(&left, op, &right)?;
+                let normalized_left = normalize(left.clone(), &right);
+                Ok(Expr::Compare(normalized_left, op, normalize(right, &left)))
+            }
+            Some(Token::In) => {
+                self.pos += 1;
+                self.expect(Token::LBracket, "'[' after in")?;
+                let mut items = Vec::new();
+                while self.peek() != Some(&Token::RBracket) {
+                    let item = self.operand()?;
+                    check_comparison(&left, CmpOp::Eq, &item)?;
+                    items.push(normalize(item, &left));
+                    if self.peek() == Some(&Token::Comma) {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                self.expect(Token::RBracket, "']'")?;
+                Ok(Expr::In(left, items))
+            }
+            _ => match left {
+                Operand::Literal(Value::Bool(b)) => Ok(Expr::Literal(b)),
+                _ => Err(syntax_error("expected a comparison")),
+            },
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, AppError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Operand::Literal(Value::Number(n))),
+            Some(Token::Text(s)) => Ok(Operand::Literal(Value::Text(s))),
+            Some(Token::Ident(word)) => match word.to_ascii_lowercase().as_str() {
+                "true" => Ok(Operand::Literal(Value::Bool(true))),
+                "false" => Ok(Operand::Literal(Value::Bool(false))),
+                ident => Fact::parse(ident)
+                    .map(Operand::Fact)
+                    .ok_or_else(|| syntax_error(format!("unknown fact {}", word))),
+            },
+            _ => Err(syntax_error("expected a value")),
+        }
+    }
+}
+
+fn is_numeric(operand: &Operand) -> Option<bool> {
+    match operand {
+        Operand::Fact(f) => Some(f.is_numeric()),
+        Operand::Literal(Value::Number(_)) => Some(true),
+        Operand::Literal(Value::Text(_)) => Some(false),
+        Operand::Literal(Value::Bool(_)) => None,
+    }
+}
+
+fn check_comparison(left: &Operand, op: CmpOp, right: &Operand) -> Result<(), AppError> {
+    let (l, r) = match (is_numeric(left), is_numeric(right)) {
+        (Some(l), Some(r)) => (l, r),
+        _ => return Err(syntax_error("true and false cannot be compared")),
+    };
+    if l != r {
+        return Err(syntax_error("cannot compare a size with text"));
+    }
+    if !l && !matches!(op, CmpOp::Eq | CmpOp::Ne) {
+        return Err(syntax_error("text can only be compared with == or !="));
+    }
+    Ok(())
+}
+
+/// Extension literals are written with or without the dot
+fn normalize(operand: Operand, other: &Operand) -> Operand {
+    match (operand, other) {
+        (Operand::Literal(Value::Text(s)), Operand::Fact(Fact::Extension)) => {
+            Operand::Literal(Value::Text(s.trim_start_matches('.').to_string()))
+        }
+        (operand, _) => operand,
+    }
+}
+
+fn resolve(operand: &Operand, facts: &ConditionFacts) -> Option<Value> {
+    match operand {
+        Operand::Literal(v) => Some(v.clone()),
+        Operand::Fact(Fact::Size) => Some(Value::Number(facts.size as f64)),
+        Operand::Fact(Fact::Name) => facts.name.clone().map(Value::Text),
+        Operand::Fact(Fact::Extension) => facts.extension().map(Value::Text),
+        Operand::Fact(Fact::Format) => facts.format.clone().map(Value::Text),
+    }
+}
+
+fn compare(left: &Value, op: CmpOp, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => match op {
+            CmpOp::Eq => l == r,
+            CmpOp::Ne => l != r,
+            CmpOp::Lt => l < r,
+            CmpOp::Le => l <= r,
+            CmpOp::Gt => l > r,
+            CmpOp::Ge => l >= r,
+        },
+        (Value::Text(l), Value::Text(r)) => {
+            let equal = l.eq_ignore_ascii_case(r);
+            if op == CmpOp::Ne { !equal } else { equal }
+        }
+        _ => false,
+    }
+}
+
+fn eval(expr: &Expr, facts: &ConditionFacts) -> Option<bool> {
+    match expr {
+        Expr::Literal(b) => Some(*b),
+        Expr::Not(inner) => eval(inner, facts).map(|b| !b),
+        Expr::And(l, r) => match (eval(l, facts), eval(r, facts)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        Expr::Or(l, r) => match (eval(l, facts), eval(r, facts)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+        Expr::Compare(l, op, r) => Some(compare(&resolve(l, facts)?, *op, &resolve(r, facts)?)),
+        Expr::In(l, items) => {
+            let value = resolve(l, facts)?;
+            let mut undecided = false;
+            for item in items {
+                match resolve(item, facts) {
+                    Some(v) if compare(&value, CmpOp::Eq, &v) => return Some(true),
+                    Some(_) => {}
+                    None => undecided = true,
+                }
+            }
+            if undecided { None } else { Some(false) }
+        }
+    }
+}
+
+/// A parsed layer condition
+#[derive(Clone, Debug)]
+pub struct Condition {
+    expr: Expr,
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Self, AppError> {
+        if source.chars().count() > MAX_CONDITION_LEN {
+            return Err(syntax_error(format!("longer than {} characters", MAX_CONDITION_LEN)));
+        }
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err(syntax_error("empty"));
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(syntax_error("unexpected text after the expression"));
+        }
+        Ok(Self { expr })
+    }
+
+    /// `Some(result)`, or `None` when a fact it needs is not known
+    pub fn evaluate(&self, facts: &ConditionFacts) -> Option<bool> {
+        eval(&self.expr, facts)
+    }
+}
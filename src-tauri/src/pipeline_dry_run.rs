@@ -0,0 +1,307 @@
+//! Pipeline Dry Run
+//!
+//! `pipeline_estimate` works from fixed per-step ratios. `pipeline_dry_run`
+//! measures instead: it picks up to `max_samples` files spread over the
+//! input, runs every enabled step on a window of each (the first
+//! `window_bytes`, or the whole file when the pipeline starts with an image
+//! step that needs to decode it), and projects each step's ratio and speed
+//! onto the full input. Nothing is written.
+//!
+//! Memory is the size of the input and output buffers a step holds for the
+//! largest input file, since pipelines work on whole files in memory. A
+//! step that fails on a sample, such as hybrid encryption without a
+//! recipient, falls back to its fixed ratio and reports the error; later
+//! steps then run on its unchanged input. Password layers without a
+//! password use a throwaway one, so key derivation time is still measured.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::compress_jobs::{collect_files, JobFile};
+use crate::entropy::detect_kind;
+use crate::github::AppError;
+use crate::pipeline::{pipeline_context, pipeline_validate, PipelineConfig, PipelineContext, PipelineOperation};
+use crate::pipeline_condition::{Condition, ConditionFacts};
+use crate::pipeline_steps::{find_step, StepContext};
+
+/// Most files a dry run samples
+pub const MAX_SAMPLES: usize = 64;
+/// Smallest and largest window read from each sample
+pub const MIN_WINDOW_BYTES: u64 = 64 * 1024;
+pub const MAX_WINDOW_BYTES: u64 = 64 * 1024 * 1024;
+const DEFAULT_SAMPLES: usize = 8;
+const DEFAULT_WINDOW_BYTES: u64 = 4 * 1024 * 1024;
+
+fn default_samples() -> usize {
+    DEFAULT_SAMPLES
+}
+
+fn default_window() -> u64 {
+    DEFAULT_WINDOW_BYTES
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DryRunOptions {
+    #[serde(default = "default_samples")]
+    pub max_samples: usize,
+    #[serde(default = "default_window")]
+    pub window_bytes: u64,
+}
+
+impl Default for DryRunOptions {
+    fn default() -> Self {
+        Self {
+            max_samples: DEFAULT_SAMPLES,
+            window_bytes: DEFAULT_WINDOW_BYTES,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepDryRun {
+    pub layer_id: String,
+    pub operation: String,
+    /// Samples whose condition held, so the step ran
+    pub applied_samples: usize,
+    pub sample_input_bytes: u64,
+    pub sample_output_bytes: u64,
+    /// Output size relative to input, over all samples
+    pub ratio: f64,
+    /// False when the step failed on every sample it applied to and the
+    /// ratio is the step's fixed estimate
+    pub measured: bool,
+    pub estimated_input_size: u64,
+    pub estimated_output_size: u64,
+    pub estimated_duration_ms: u64,
+    pub estimated_peak_memory_bytes: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DryRunReport {
+    pub files_total: usize,
+    pub bytes_total: u64,
+    pub files_sampled: usize,
+    pub bytes_sampled: u64,
+    pub steps: Vec<StepDryRun>,
+    pub estimated_final_size: u64,
+    pub estimated_duration_ms: u64,
+    pub overall_ratio: f64,
+}
+
+#[derive(Clone, Default)]
+struct StepTally {
+    input: u64,
+    output: u64,
+    applied: usize,
+    applied_input: u64,
+    measured_input: u64,
+    duration: Duration,
+    error: Option<String>,
+}
+
+fn validate_options(options: &DryRunOptions) -> Result<(), AppError> {
+    if !(1..=MAX_SAMPLES).contains(&options.max_samples) {
+        return Err(AppError::Validation(format!("Samples must be 1-{}", MAX_SAMPLES)));
+    }
+    if !(MIN_WINDOW_BYTES..=MAX_WINDOW_BYTES).contains(&options.window_bytes) {
+        return Err(AppError::Validation(format!(
+            "Window must be {}-{} bytes",
+            MIN_WINDOW_BYTES, MAX_WINDOW_BYTES
+        )));
+    }
+    Ok(())
+}
+
+/// Up to `max` files spread evenly over `files`
+pub fn sample_files(files: &[JobFile], max: usize) -> Vec<&JobFile> {
+    if files.len() <= max {
+        return files.iter().collect();
+    }
+    (0..max).map(|i| &files[i * files.len() / max]).collect()
+}
+
+fn read_window(file: &JobFile, limit: u64) -> Result<Vec<u8>, AppError> {
+    let mut data = Vec::new();
+    std::fs::File::open(&file.source)?.take(limit).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Measure `config` on samples of `files` and project it onto all of them
+pub fn dry_run(
+    files: &[JobFile],
+    config: &PipelineConfig,
+    context: &PipelineContext,
+    options: &DryRunOptions,
+) -> Result<DryRunReport, AppError> {
+    validate_options(options)?;
+    pipeline_validate(config.clone())?;
+
+    let mut layers: Vec<_> = config.layers.iter().filter(|l| l.enabled).collect();
+    layers.sort_by_key(|l| l.order);
+    let conditions = layers
+        .iter()
+        .map(|l| l.condition.as_deref().map(Condition::parse).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+    let limit = if layers.iter().any(|l| l.operation.is_source_transform()) {
+        MAX_WINDOW_BYTES
+    } else {
+        options.window_bytes
+    };
+
+    let samples = sample_files(files, options.max_samples);
+    let mut tallies = vec![StepTally::default(); layers.len()];
+    let mut bytes_sampled = 0;
+
+    for file in &samples {
+        let mut data = read_window(file, limit)?;
+        bytes_sampled += data.len() as u64;
+        let facts = ConditionFacts {
+            size: file.size,
+            name: file.source.file_name().map(|n| n.to_string_lossy().to_string()),
+            format: serde_json::to_value(detect_kind(&data))
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string)),
+        };
+
+        for ((layer, condition), tally) in layers.iter().zip(&conditions).zip(tallies.iter_mut()) {
+            let input_len = data.len() as u64;
+            tally.input += input_len;
+            if condition.as_ref().is_some_and(|c| c.evaluate(&facts) != Some(true)) {
+                tally.output += input_len;
+                continue;
+            }
+            tally.applied += 1;
+            tally.applied_input += input_len;
+
+            let step = find_step(layer.operation.step_id())
+                .ok_or_else(|| AppError::Validation(format!("Unknown pipeline step: {}", layer.operation.step_id())))?;
+            let params = layer.operation.step_params();
+            let ctx = StepContext {
+                layer_id: &layer.id,
+                file_name: facts.name.as_deref(),
+                pipeline: context,
+            };
+            let started = Instant::now();
+            match step.process(&data, &params, &ctx) {
+                Ok((output, _)) => {
+                    tally.duration += started.elapsed();
+                    tally.measured_input += input_len;
+                    tally.output += output.len() as u64;
+                    data = output;
+                }
+                Err(e) => {
+                    tally.output += (input_len as f64 * step.estimate(&params).ratio) as u64;
+                    tally.error.get_or_insert(e.to_string());
+                }
+            }
+        }
+    }
+
+    let bytes_total: u64 = files.iter().map(|f| f.size).sum();
+    let largest = files.iter().map(|f| f.size).max().unwrap_or(0) as f64;
+    let mut size = bytes_total as f64;
+    let mut largest_at_step = largest;
+    let mut total_ms = 0u64;
+    let mut steps = Vec::with_capacity(layers.len());
+
+    for (layer, tally) in layers.iter().zip(tallies) {
+        let ratio = if tally.input > 0 { tally.output as f64 / tally.input as f64 } else { 1.0 };
+        let applied_share = if tally.input > 0 { tally.applied_input as f64 / tally.input as f64 } else { 0.0 };
+        let secs_per_byte = if tally.measured_input > 0 {
+            tally.duration.as_secs_f64() / tally.measured_input as f64
+        } else {
+            0.0
+        };
+        let duration_ms = (size * applied_share * secs_per_byte * 1000.0) as u64;
+        total_ms += duration_ms;
+
+        let label = find_step(layer.operation.step_id())
+            .map(|s| s.estimate(&layer.operation.step_params()).label)
+            .unwrap_or_else(|| layer.operation.step_id().to_string());
+        steps.push(StepDryRun {
+            layer_id: layer.id.clone(),
+            operation: label,
+            applied_samples: tally.applied,
+            sample_input_bytes: tally.input,
+            sample_output_bytes: tally.output,
+            ratio,
+            measured: tally.applied == 0 || tally.measured_input > 0,
+            estimated_input_size: size as u64,
+            estimated_output_size: (size * ratio) as u64,
+            estimated_duration_ms: duration_ms,
+            estimated_peak_memory_bytes: (largest_at_step * (1.0 + ratio)) as u64,
+            error: tally.error,
+        });
+        size *= ratio;
+        largest_at_step *= ratio;
+    }
+
+    Ok(DryRunReport {
+        files_total: files.len(),
+        bytes_total,
+        files_sampled: samples.len(),
+        bytes_sampled,
+        steps,
+        estimated_final_size: size as u64,
+        estimated_duration_ms: total_ms,
+        overall_ratio: if bytes_total > 0 { size / bytes_total as f64 } else { 1.0 },
+    })
+}
+
+/// Files named by `paths`, with folders expanded
+fn input_files(paths: &[String]) -> Result<Vec<JobFile>, AppError> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = PathBuf::from(path);
+        let metadata = std::fs::metadata(&path)?;
+        if metadata.is_dir() {
+            files.extend(collect_files(&path)?);
+        } else {
+            files.push(JobFile {
+                relative: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                source: path,
+                size: metadata.len(),
+            });
+        }
+    }
+    if files.is_empty() {
+        return Err(AppError::Validation("No files to sample".into()));
+    }
+    Ok(files)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Dry-run `config` on the files and folders in `paths`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn pipeline_dry_run(
+    paths: Vec<String>,
+    config: PipelineConfig,
+    mut passwords: HashMap<String, String>,
+    keypair_bytes: Option<Vec<u8>>,
+    options: Option<DryRunOptions>,
+) -> Result<DryRunReport, AppError> {
+    for layer in &config.layers {
+        if matches!(layer.operation, PipelineOperation::EncryptPassword { .. }) {
+            passwords
+                .entry(layer.id.clone())
+                .or_insert_with(|| hex::encode(rand::random::<[u8; 16]>()));
+        }
+    }
+    let context = pipeline_context(passwords, keypair_bytes)?;
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let files = input_files(&paths)?;
+        dry_run(&files, &config, &context, &options)
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Dry run task failed: {}", e)))?
+}
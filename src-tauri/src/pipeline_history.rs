@@ -0,0 +1,315 @@
+//! Pipeline Run History
+//!
+//! Every pipeline run is logged to the local store: `pipeline_process`,
+//! watched-folder uploads and each file of `pipeline_folder_start` as single
+//! runs, and `pipeline_process_folder` jobs as one run per job. The log keeps
+//! the last `MAX_HISTORY` runs; all-time totals are kept alongside it so
+//! storage savings stay cumulative once old runs drop out.
+//!
+//! `pipeline_get_stats` also compares each pipeline's throughput over its
+//! last `RECENT_RUNS` runs with the runs before, so a slowdown after an
+//! update or a settings change shows up as a regression.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::compress_jobs::JobState;
+use crate::github::AppError;
+use crate::local_store::{with_store, LocalStore, PIPELINE_HISTORY_NS};
+use crate::pipeline::{PipelineConfig, PipelineError, PipelineResult, ProcessedFile};
+use crate::pipeline_batch::BatchReport;
+use crate::util::now_secs;
+
+/// Most runs kept in the log
+pub const MAX_HISTORY: usize = 1000;
+/// Runs per pipeline compared against the earlier ones
+pub const RECENT_RUNS: usize = 5;
+/// Throughput drop, as a fraction, reported as a regression
+pub const REGRESSION_THRESHOLD: f64 = 0.2;
+const RUNS_KEY: &str = "runs";
+const TOTALS_KEY: &str = "totals";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunKind {
+    Single,
+    Folder,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PipelineRun {
+    pub id: String,
+    pub pipeline_id: String,
+    pub pipeline_name: String,
+    pub kind: RunKind,
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub files: usize,
+    pub input_size: u64,
+    pub output_size: u64,
+    /// Step IDs in the order they ran
+    pub steps: Vec<String>,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// All-time totals, including runs no longer in the log
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HistoryTotals {
+    pub runs: u64,
+    pub failed_runs: u64,
+    pub files: u64,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    pub duration_ms: u64,
+    /// When the first run was recorded
+    pub since: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PipelineUsage {
+    pub pipeline_id: String,
+    pub pipeline_name: String,
+    pub runs: usize,
+    pub failed_runs: usize,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    pub bytes_saved: i64,
+    pub bytes_per_sec: f64,
+    /// Throughput of the last `RECENT_RUNS` successful runs relative to
+    /// the ones before, minus one; negative when it got slower
+    pub throughput_change: Option<f64>,
+    pub regressed: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PipelineStats {
+    pub totals: HistoryTotals,
+    pub bytes_saved: i64,
+    pub overall_ratio: f64,
+    pub bytes_per_sec: f64,
+    /// Per pipeline over the logged runs, most used first
+    pub pipelines: Vec<PipelineUsage>,
+}
+
+fn enabled_steps(config: &PipelineConfig) -> Vec<String> {
+    let mut layers: Vec<_> = config.layers.iter().filter(|l| l.enabled).collect();
+    layers.sort_by_key(|l| l.order);
+    layers.iter().map(|l| l.operation.step_id().to_string()).collect()
+}
+
+impl PipelineRun {
+    fn new(config: &PipelineConfig, kind: RunKind, duration: Duration) -> Self {
+        Self {
+            id: hex::encode(rand::random::<[u8; 8]>()),
+            pipeline_id: config.id.clone(),
+            pipeline_name: config.name.clone(),
+            kind,
+            started_at: now_secs().saturating_sub(duration.as_secs()),
+            duration_ms: duration.as_millis() as u64,
+            files: 1,
+            input_size: 0,
+            output_size: 0,
+            steps: enabled_steps(config),
+            success: true,
+            error: None,
+        }
+    }
+
+    /// Run of `config` over one input of `input_size` bytes
+    pub fn single(
+        config: &PipelineConfig,
+        input_size: usize,
+        result: &Result<PipelineResult, PipelineError>,
+        duration: Duration,
+    ) -> Self {
+        let mut run = Self::new(config, RunKind::Single, duration);
+        run.input_size = input_size as u64;
+        match result {
+            Ok(result) => {
+                run.output_size = result.final_size as u64;
+                run.steps = result.layers_applied.iter()
+                    .filter(|l| !l.skipped)
+                    .map(|l| l.operation_type.clone())
+                    .collect();
+            }
+            Err(e) => {
+                run.success = false;
+                run.error = Some(e.to_string());
+            }
+        }
+        run
+    }
+
+    /// Run of `config` over one file written to an output folder
+    pub(crate) fn file(
+        config: &PipelineConfig,
+        input_size: u64,
+        result: &Result<ProcessedFile, AppError>,
+        duration: Duration,
+    ) -> Self {
+        let mut run = Self::new(config, RunKind::Single, duration);
+        run.input_size = input_size;
+        match result {
+            Ok(processed) => run.output_size = processed.output_size,
+            Err(e) => {
+                run.success = false;
+                run.error = Some(e.to_string());
+            }
+        }
+        run
+    }
+
+    /// Run of a folder job, sized by the files that succeeded
+    pub fn folder(config: &PipelineConfig, report: &BatchReport, duration: Duration) -> Self {
+        let mut run = Self::new(config, RunKind::Folder, duration);
+        run.started_at = report.started_at;
+        run.files = report.files_succeeded;
+        run.input_size = report.bytes_in;
+        run.output_size = report.bytes_out;
+        run.success = report.state == JobState::Completed && report.files_failed == 0;
+        run.error = report.files.iter().find_map(|f| f.error.clone());
+        run
+    }
+}
+
+impl HistoryTotals {
+    fn add(&mut self, run: &PipelineRun) {
+        if self.runs == 0 {
+            self.since = run.started_at;
+        }
+        self.runs += 1;
+        if !run.success {
+            self.failed_runs += 1;
+        }
+        self.files += run.files as u64;
+        self.input_bytes += run.input_size;
+        self.output_bytes += run.output_size;
+        self.duration_ms += run.duration_ms;
+    }
+}
+
+fn bytes_per_sec(bytes: u64, duration_ms: u64) -> f64 {
+    if duration_ms == 0 {
+        0.0
+    } else {
+        bytes as f64 * 1000.0 / duration_ms as f64
+    }
+}
+
+/// Throughput of `runs` taken together
+fn throughput(runs: &[&PipelineRun]) -> f64 {
+    bytes_per_sec(
+        runs.iter().map(|r| r.input_size).sum(),
+        runs.iter().map(|r| r.duration_ms).sum(),
+    )
+}
+
+fn usage(runs: &[&PipelineRun]) -> PipelineUsage {
+    let last = runs[runs.len() - 1];
+    let ok: Vec<_> = runs.iter().copied().filter(|r| r.success).collect();
+    let input_bytes = runs.iter().map(|r| r.input_size).sum();
+    let output_bytes = runs.iter().map(|r| r.output_size).sum();
+
+    let throughput_change = (ok.len() > RECENT_RUNS)
+        .then(|| ok.split_at(ok.len() - RECENT_RUNS))
+        .map(|(earlier, recent)| (throughput(earlier), throughput(recent)))
+        .filter(|&(earlier, _)| earlier > 0.0)
+        .map(|(earlier, recent)| recent / earlier - 1.0);
+
+    PipelineUsage {
+        pipeline_id: last.pipeline_id.clone(),
+        pipeline_name: last.pipeline_name.clone(),
+        runs: runs.len(),
+        failed_runs: runs.len() - ok.len(),
+        input_bytes,
+        output_bytes,
+        bytes_saved: input_bytes as i64 - output_bytes as i64,
+        bytes_per_sec: throughput(&ok),
+        throughput_change,
+        regressed: throughput_change.is_some_and(|c| c < -REGRESSION_THRESHOLD),
+    }
+}
+
+/// Statistics from the all-time `totals` and the logged `history`, oldest
+/// run first
+pub fn compute_stats(totals: HistoryTotals, history: &[PipelineRun]) -> PipelineStats {
+    let mut by_pipeline: HashMap<&str, Vec<&PipelineRun>> = HashMap::new();
+    for run in history {
+        by_pipeline.entry(run.pipeline_id.as_str()).or_default().push(run);
+    }
+    let mut pipelines: Vec<_> = by_pipeline.values().map(|runs| usage(runs)).collect();
+    pipelines.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.pipeline_name.cmp(&b.pipeline_name)));
+
+    PipelineStats {
+        bytes_saved: totals.input_bytes as i64 - totals.output_bytes as i64,
+        overall_ratio: if totals.input_bytes > 0 {
+            totals.output_bytes as f64 / totals.input_bytes as f64
+        } else {
+            1.0
+        },
+        bytes_per_sec: bytes_per_sec(totals.input_bytes, totals.duration_ms),
+        totals,
+        pipelines,
+    }
+}
+
+/// Logged runs, oldest first
+pub fn history_in(store: &LocalStore) -> Result<Vec<PipelineRun>, AppError> {
+    Ok(store.get_json(PIPELINE_HISTORY_NS, RUNS_KEY)?.unwrap_or_default())
+}
+
+pub fn totals_in(store: &LocalStore) -> Result<HistoryTotals, AppError> {
+    Ok(store.get_json(PIPELINE_HISTORY_NS, TOTALS_KEY)?.unwrap_or_default())
+}
+
+pub fn record_run_in(store: &LocalStore, run: &PipelineRun) -> Result<(), AppError> {
+    let mut runs = history_in(store)?;
+    runs.push(run.clone());
+    if runs.len() > MAX_HISTORY {
+        runs.drain(..runs.len() - MAX_HISTORY);
+    }
+    let mut totals = totals_in(store)?;
+    totals.add(run);
+    store.put_json(PIPELINE_HISTORY_NS, RUNS_KEY, &runs, None)?;
+    store.put_json(PIPELINE_HISTORY_NS, TOTALS_KEY, &totals, None)
+}
+
+/// Log `run`. Failures only cost the history, so they are logged rather
+/// than returned.
+pub(crate) fn record_run(run: &PipelineRun) {
+    if let Err(e) = with_store(|store| record_run_in(store, run)) {
+        tracing::warn!("Could not record pipeline run of {}: {}", run.pipeline_name, e);
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Logged runs, newest first, optionally of one pipeline only
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_get_history(limit: Option<usize>, pipeline_id: Option<String>) -> Result<Vec<PipelineRun>, AppError> {
+    let runs = with_store(history_in)?;
+    Ok(runs
+        .into_iter()
+        .rev()
+        .filter(|r| pipeline_id.as_ref().is_none_or(|id| &r.pipeline_id == id))
+        .take(limit.unwrap_or(MAX_HISTORY))
+        .collect())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_get_stats() -> Result<PipelineStats, AppError> {
+    with_store(|store| Ok(compute_stats(totals_in(store)?, &history_in(store)?)))
+}
+
+/// Forget every logged run and the totals
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_clear_history() -> Result<usize, AppError> {
+    with_store(|store| store.clear(PIPELINE_HISTORY_NS))
+}
@@ -0,0 +1,190 @@
+//! User Pipeline Presets
+//!
+//! Presets saved with `pipeline_save_preset` live in the settings namespace
+//! of the encrypted local store, so clearing caches keeps them, and are
+//! listed after the built-ins by `pipeline_get_presets`. Built-in IDs start
+//! with `preset-` and cannot be overwritten or deleted.
+//!
+//! `pipeline_export_preset` writes a preset as a small JSON file that
+//! `pipeline_import_preset` reads on another device:
+//!
+//! ```json
+//! { "format": "vortex-pipeline-preset", "version": 1, "preset": { ... } }
+//! ```
+//!
+//! Passwords never leave the device: password layers serialize without one.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::github::AppError;
+use crate::local_store::{with_store, SETTINGS_NS};
+use crate::pipeline::{get_preset_pipelines, pipeline_validate, PipelineConfig};
+use crate::util::now_secs;
+
+const PRESETS_KEY: &str = "pipeline_presets";
+const FILE_FORMAT: &str = "vortex-pipeline-preset";
+const FILE_VERSION: u8 = 1;
+const BUILTIN_PREFIX: &str = "preset-";
+/// Largest preset file accepted by `pipeline_import_preset`
+pub const MAX_PRESET_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_NAME_LEN: usize = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresetFile {
+    pub format: String,
+    pub version: u8,
+    pub preset: PipelineConfig,
+}
+
+fn new_preset_id() -> String {
+    format!("user-{}", hex::encode(rand::random::<[u8; 8]>()))
+}
+
+pub fn is_builtin(id: &str) -> bool {
+    id.starts_with(BUILTIN_PREFIX)
+}
+
+fn check_preset(config: &PipelineConfig) -> Result<(), AppError> {
+    let name = config.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::Validation(format!(
+            "Preset name must be 1-{} characters",
+            MAX_NAME_LEN
+        )));
+    }
+    if config.layers.is_empty() {
+        return Err(AppError::Validation("Preset has no layers".into()));
+    }
+    pipeline_validate(config.clone()).map(|_| ())
+}
+
+/// Insert or replace `config` in `presets` by ID. An empty ID gets a fresh
+/// one; `created_at` is kept from the preset being replaced.
+pub fn upsert_preset(presets: &mut Vec<PipelineConfig>, mut config: PipelineConfig, now: u64) -> Result<PipelineConfig, AppError> {
+    if is_builtin(&config.id) {
+        return Err(AppError::Validation("Built-in presets cannot be changed; save a copy instead".into()));
+    }
+    check_preset(&config)?;
+    if config.id.is_empty() {
+        config.id = new_preset_id();
+    }
+    config.name = config.name.trim().to_string();
+    config.updated_at = now;
+
+    match presets.iter_mut().find(|p| p.id == config.id) {
+        Some(existing) => {
+            config.created_at = existing.created_at;
+            *existing = config.clone();
+        }
+        None => {
+            config.created_at = now;
+            presets.push(config.clone());
+        }
+    }
+    Ok(config)
+}
+
+/// Add an imported preset, under a fresh ID if its own is taken or built-in
+pub fn import_preset(presets: &mut Vec<PipelineConfig>, mut config: PipelineConfig, now: u64) -> Result<PipelineConfig, AppError> {
+    if config.id.is_empty() || is_builtin(&config.id) || presets.iter().any(|p| p.id == config.id) {
+        config.id = new_preset_id();
+    }
+    upsert_preset(presets, config, now)
+}
+
+pub fn encode_preset_file(config: &PipelineConfig) -> Result<Vec<u8>, AppError> {
+    let file = PresetFile {
+        format: FILE_FORMAT.to_string(),
+        version: FILE_VERSION,
+        preset: config.clone(),
+    };
+    serde_json::to_vec_pretty(&file).map_err(|e| AppError::Validation(e.to_string()))
+}
+
+pub fn decode_preset_file(bytes: &[u8]) -> Result<PipelineConfig, AppError> {
+    let file: PresetFile = serde_json::from_slice(bytes)
+        .map_err(|e| AppError::Validation(format!("Not a pipeline preset file: {}", e)))?;
+    if file.format != FILE_FORMAT {
+        return Err(AppError::Validation("Not a pipeline preset file".into()));
+    }
+    if file.version > FILE_VERSION {
+        return Err(AppError::Validation(format!(
+            "Preset file version {} is newer than this app supports",
+            file.version
+        )));
+    }
+    Ok(file.preset)
+}
+
+pub(crate) fn user_presets() -> Vec<PipelineConfig> {
+    with_store(|store| store.get_json(SETTINGS_NS, PRESETS_KEY))
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+fn save_user_presets(presets: &[PipelineConfig]) -> Result<(), AppError> {
+    with_store(|store| store.put_json(SETTINGS_NS, PRESETS_KEY, &presets, None))
+}
+
+fn find_preset(id: &str) -> Option<PipelineConfig> {
+    get_preset_pipelines()
+        .into_iter()
+        .chain(user_presets())
+        .find(|p| p.id == id)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Save a user preset, creating it if its ID is empty or unknown
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_save_preset(config: PipelineConfig) -> Result<PipelineConfig, AppError> {
+    let mut presets = user_presets();
+    let saved = upsert_preset(&mut presets, config, now_secs())?;
+    save_user_presets(&presets)?;
+    Ok(saved)
+}
+
+/// Delete a user preset. Returns false if there was none with that ID.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_delete_preset(id: String) -> Result<bool, AppError> {
+    if is_builtin(&id) {
+        return Err(AppError::Validation("Built-in presets cannot be deleted".into()));
+    }
+    let mut presets = user_presets();
+    let before = presets.len();
+    presets.retain(|p| p.id != id);
+    if presets.len() == before {
+        return Ok(false);
+    }
+    save_user_presets(&presets)?;
+    Ok(true)
+}
+
+/// Write the preset `id`, built-in or user, to `path` as a preset file
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_export_preset(id: String, path: String) -> Result<(), AppError> {
+    let preset = find_preset(&id).ok_or_else(|| AppError::Validation(format!("No preset {}", id)))?;
+    std::fs::write(Path::new(&path), encode_preset_file(&preset)?)?;
+    Ok(())
+}
+
+/// Read a preset file and save it as a user preset
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_import_preset(path: String) -> Result<PipelineConfig, AppError> {
+    if std::fs::metadata(&path)?.len() > MAX_PRESET_FILE_BYTES {
+        return Err(AppError::Validation("Preset file is too large".into()));
+    }
+    let preset = decode_preset_file(&std::fs::read(&path)?)?;
+    let mut presets = user_presets();
+    let imported = import_preset(&mut presets, preset, now_secs())?;
+    save_user_presets(&presets)?;
+    Ok(imported)
+}
@@ -0,0 +1,150 @@
+//! Per-Category Pipeline Routing
+//!
+//! A routing table maps file categories (photo, RAW, video, document) to the
+//! ID of a preset from `pipeline_get_presets`. `upload_folder_recursive`
+//! runs each file through the preset of its category and uploads the
+//! output as `<name>.vxp`; files of a category without a route are uploaded
+//! as they are, and only photos are picked up unless their category is
+//! routed. A resize layer with `originals_album` also has the original
+//! uploaded to that album. The table is kept in the settings store.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::github::{AppError, IMAGE_EXTENSIONS};
+use crate::local_store::{with_store, SETTINGS_NS};
+use crate::pipeline::{pipeline_get_presets, pipeline_validate, PipelineConfig, PipelineOperation};
+use crate::raw::RAW_EXTENSIONS;
+use crate::upload_policy::UploadIntent;
+use crate::video::VIDEO_EXTENSIONS;
+
+const ROUTES_KEY: &str = "pipeline_routes";
+
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "txt", "md", "rtf", "doc", "docx", "odt", "xls", "xlsx", "ods", "ppt", "pptx", "odp", "csv", "epub",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCategory {
+    Photo,
+    Raw,
+    Video,
+    Document,
+}
+
+impl FileCategory {
+    pub const ALL: [FileCategory; 4] = [Self::Photo, Self::Raw, Self::Video, Self::Document];
+
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Photo => IMAGE_EXTENSIONS,
+            Self::Raw => RAW_EXTENSIONS,
+            Self::Video => VIDEO_EXTENSIONS,
+            Self::Document => DOCUMENT_EXTENSIONS,
+        }
+    }
+
+    /// Category of `path` by its extension
+    pub fn of(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        Self::ALL.into_iter().find(|c| c.extensions().contains(&ext.as_str()))
+    }
+}
+
+/// Preset ID per category
+pub type RoutingTable = BTreeMap<FileCategory, String>;
+
+pub fn load_routes() -> Result<RoutingTable, AppError> {
+    Ok(with_store(|store| store.get_json(SETTINGS_NS, ROUTES_KEY))?.unwrap_or_default())
+}
+
+/// Resolves files to the preset their category is routed to
+pub struct Router {
+    routes: BTreeMap<FileCategory, PipelineConfig>,
+}
+
+impl Router {
+    /// Router over `presets`. Routes to presets that no longer exist are
+    /// dropped, so their files are uploaded unprocessed.
+    pub fn new(table: &RoutingTable, presets: &[PipelineConfig]) -> Self {
+        let routes = table
+            .iter()
+            .filter_map(|(category, id)| Some((*category, presets.iter().find(|p| &p.id == id)?.clone())))
+            .collect();
+        Self { routes }
+    }
+
+    /// Router for the saved table and the current presets
+    pub fn load() -> Result<Self, AppError> {
+        Ok(Self::new(&load_routes()?, &pipeline_get_presets()))
+    }
+
+    pub fn pipeline_for(&self, path: &Path) -> Option<&PipelineConfig> {
+        self.routes.get(&FileCategory::of(path)?)
+    }
+
+    /// Files to pick up from a folder: photos, and anything routed
+    pub fn includes(&self, path: &Path) -> bool {
+        match FileCategory::of(path) {
+            Some(FileCategory::Photo) => true,
+            Some(category) => self.routes.contains_key(&category),
+            None => false,
+        }
+    }
+}
+
+/// How uploading the output of `config` should be judged by the upload policy
+pub fn upload_intent(config: &PipelineConfig) -> UploadIntent {
+    let enabled = || config.layers.iter().filter(|l| l.enabled);
+    UploadIntent {
+        encrypted: enabled().any(|l| {
+            matches!(l.operation, PipelineOperation::EncryptPassword { .. } | PipelineOperation::EncryptHybridPQ { .. })
+        }),
+        strips_metadata: enabled()
+            .any(|l| matches!(&l.operation, PipelineOperation::OptimizeImage { options } if options.strip_metadata)),
+    }
+}
+
+/// Album under `photos/` that the originals of photos resized by `config`
+/// are uploaded to
+pub fn originals_album(config: &PipelineConfig) -> Option<&str> {
+    config.layers.iter().filter(|l| l.enabled).find_map(|l| match &l.operation {
+        PipelineOperation::Resize { options } => options.originals_album.as_deref(),
+        _ => None,
+    })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_get_routes() -> Result<RoutingTable, AppError> {
+    load_routes()
+}
+
+/// Route `category` to the preset `preset_id`, or remove its route when
+/// `preset_id` is `None`. Returns the updated table.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn pipeline_set_route(category: FileCategory, preset_id: Option<String>) -> Result<RoutingTable, AppError> {
+    let mut routes = load_routes()?;
+    match preset_id {
+        Some(id) => {
+            let preset = pipeline_get_presets()
+                .into_iter()
+                .find(|p| p.id == id)
+                .ok_or_else(|| AppError::Validation(format!("No preset with ID {}", id)))?;
+            pipeline_validate(preset)?;
+            routes.insert(category, id);
+        }
+        None => {
+            routes.remove(&category);
+        }
+    }
+    with_store(|store| store.put_json(SETTINGS_NS, ROUTES_KEY, &routes, None))?;
+    Ok(routes)
+}
@@ -0,0 +1,225 @@
+//! Scripted Pipeline Step
+//!
+//! The `script` step runs a small Rhai script per file to decide how the
+//! file is prepared, such as picking a transcode quality from the image
+//! resolution:
+//!
+//! ```rhai
+//! if width > 4000 {
+//!     #{ step: "transcode", params: #{ format: "webp", quality: 70 } }
+//! } else if format == "png" {
+//!     #{ step: "optimize_image", params: #{ level: 3 } }
+//! }
+//! ```
+//!
+//! The script sees `size`, `name`, `extension`, `format` (the detected
+//! content kind), `width` and `height` (0 when the input is not an image).
+//! It returns `()` to leave the file as it is, or a map naming a step that
+//! prepares the input (a source transform) with its `params`. A `tags` map
+//! of strings is recorded in the layer metadata, where `pipeline_inspect`
+//! shows it. Like the steps it picks, a script layer runs before
+//! compression and encryption and is not undone by `reverse_pipeline`.
+//!
+//! Scripts cannot reach files or the network. Each run is limited in
+//! operations, wall time, call depth and the size of the strings, arrays
+//! and maps it builds.
+
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::entropy::detect_kind;
+use crate::github::AppError;
+use crate::pipeline::PipelineError;
+use crate::pipeline_steps::{find_step, PipelineStep, StepContext, StepDescriptor, StepEstimate};
+use crate::upload_policy::image_dimensions;
+
+/// Longest script accepted, in bytes
+pub const MAX_SCRIPT_LEN: usize = 16 * 1024;
+pub const MAX_OPERATIONS: u64 = 10_000_000;
+pub const MAX_TIMEOUT_MS: u64 = 5_000;
+const DEFAULT_OPERATIONS: u64 = 100_000;
+const DEFAULT_TIMEOUT_MS: u64 = 200;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+/// Operations between wall time checks
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+fn default_operations() -> u64 {
+    DEFAULT_OPERATIONS
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+#[derive(Deserialize)]
+struct ScriptParams {
+    script: String,
+    #[serde(default = "default_operations")]
+    max_operations: u64,
+    #[serde(default = "default_timeout")]
+    timeout_ms: u64,
+}
+
+/// What a script asked for
+#[derive(Debug, Default, Deserialize)]
+struct ScriptChoice {
+    #[serde(default)]
+    step: Option<String>,
+    #[serde(default)]
+    params: Value,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+/// Engine with the sandbox limits, stopping after `timeout`
+fn sandboxed_engine(max_operations: u64, timeout: Duration) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(max_operations);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+    engine.disable_symbol("eval");
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+    let started = Instant::now();
+    engine.on_progress(move |ops| {
+        (ops.is_multiple_of(TIME_CHECK_INTERVAL) && started.elapsed() > timeout)
+            .then(|| Dynamic::from("Script timed out"))
+    });
+    engine
+}
+
+fn check_limits(params: &ScriptParams) -> Result<(), String> {
+    if params.script.trim().is_empty() {
+        return Err("Script cannot be empty".into());
+    }
+    if params.script.len() > MAX_SCRIPT_LEN {
+        return Err(format!("Script is longer than {} bytes", MAX_SCRIPT_LEN));
+    }
+    if !(1..=MAX_OPERATIONS).contains(&params.max_operations) {
+        return Err(format!("Operation limit must be 1-{}", MAX_OPERATIONS));
+    }
+    if !(1..=MAX_TIMEOUT_MS).contains(&params.timeout_ms) {
+        return Err(format!("Timeout must be 1-{} ms", MAX_TIMEOUT_MS));
+    }
+    Ok(())
+}
+
+fn compile(engine: &Engine, script: &str) -> Result<AST, String> {
+    engine.compile(script).map_err(|e| format!("Script does not compile: {}", e))
+}
+
+fn script_error(message: impl std::fmt::Display) -> PipelineError {
+    PipelineError::InvalidData(format!("Script failed: {}", message))
+}
+
+/// Variables a script sees for `data`
+fn file_scope(data: &[u8], file_name: Option<&str>) -> Scope<'static> {
+    let name = file_name.unwrap_or_default();
+    let extension = std::path::Path::new(name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let format = serde_json::to_value(detect_kind(data))
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let (width, height) = image_dimensions(data).unwrap_or((0, 0));
+
+    let mut scope = Scope::new();
+    scope.push_constant("size", data.len() as i64);
+    scope.push_constant("name", name.to_string());
+    scope.push_constant("extension", extension);
+    scope.push_constant("format", format);
+    scope.push_constant("width", width as i64);
+    scope.push_constant("height", height as i64);
+    scope
+}
+
+/// Run `params`' script against `data`
+fn run_script(params: &ScriptParams, data: &[u8], file_name: Option<&str>) -> Result<ScriptChoice, PipelineError> {
+    check_limits(params).map_err(PipelineError::InvalidData)?;
+    let engine = sandboxed_engine(params.max_operations, Duration::from_millis(params.timeout_ms));
+    let ast = compile(&engine, &params.script).map_err(PipelineError::InvalidData)?;
+    let result: Dynamic = engine
+        .eval_ast_with_scope(&mut file_scope(data, file_name), &ast)
+        .map_err(script_error)?;
+    if result.is_unit() {
+        return Ok(ScriptChoice::default());
+    }
+    rhai::serde::from_dynamic(&result).map_err(|e| script_error(format!("unexpected result: {}", e)))
+}
+
+pub struct ScriptStep;
+
+impl PipelineStep for ScriptStep {
+    fn id(&self) -> &str {
+        "script"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Script".to_string(),
+            description: "Rhai script that picks how each file is prepared".to_string(),
+            source_transform: true,
+            params_schema: json!({
+                "type": "object",
+                "required": ["script"],
+                "properties": {
+                    "script": { "type": "string", "maxLength": MAX_SCRIPT_LEN },
+                    "max_operations": {
+                        "type": "integer", "minimum": 1, "maximum": MAX_OPERATIONS, "default": DEFAULT_OPERATIONS
+                    },
+                    "timeout_ms": {
+                        "type": "integer", "minimum": 1, "maximum": MAX_TIMEOUT_MS, "default": DEFAULT_TIMEOUT_MS
+                    }
+                }
+            }),
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), AppError> {
+        let params: ScriptParams = serde_json::from_value(params.clone())
+            .map_err(|e| AppError::Validation(format!("Bad step parameters: {}", e)))?;
+        check_limits(&params).map_err(AppError::Validation)?;
+        let engine = sandboxed_engine(params.max_operations, Duration::from_millis(params.timeout_ms));
+        compile(&engine, &params.script).map_err(AppError::Validation)?;
+        Ok(())
+    }
+
+    fn process(&self, data: &[u8], params: &Value, ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let params: ScriptParams = serde_json::from_value(params.clone())
+            .map_err(|e| PipelineError::InvalidData(format!("Bad step parameters: {}", e)))?;
+        let choice = run_script(&params, data, ctx.file_name)?;
+        let Some(step_id) = choice.step else {
+            return Ok((data.to_vec(), json!({ "step": null, "tags": choice.tags })));
+        };
+
+        let step = find_step(&step_id)
+            .ok_or_else(|| script_error(format!("unknown step {}", step_id)))?;
+        if step_id == self.id() || !step.describe().source_transform {
+            return Err(script_error(format!("{} cannot be chosen by a script", step_id)));
+        }
+        let step_params = if choice.params.is_null() { json!({}) } else { choice.params };
+        step.validate(&step_params).map_err(script_error)?;
+        let (output, metadata) = step.process(data, &step_params, ctx)?;
+        Ok((output, json!({ "step": step_id, "params": metadata, "tags": choice.tags })))
+    }
+
+    fn reverse(&self, data: &[u8], _metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        Ok(data.to_vec())
+    }
+
+    fn estimate(&self, _params: &Value) -> StepEstimate {
+        StepEstimate { ratio: 1.0, label: "Script".to_string() }
+    }
+}
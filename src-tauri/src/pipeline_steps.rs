@@ -0,0 +1,618 @@
+//! Pipeline Step Registry
+//!
+//! Every pipeline operation runs through a `PipelineStep` looked up by ID in
+//! a process-wide registry. The built-in operations register themselves on
+//! first use; new steps (OCR, face blurring, ...) only need an implementation
+//! and a call to `register_step`, after which layers can use them as
+//!
+//! ```json
+//! { "type": "step", "step": "ocr", "params": { ... } }
+//! ```
+//!
+//! A step's ID is also its `operation_type` in the layer metadata, which is
+//! how `reverse_pipeline` finds the step again. The metadata also records
+//! the step's `version`; output from a newer version than the registered
+//! step is refused rather than reversed wrongly. `pipeline_describe_steps`
+//! lists the registered steps with a JSON Schema of their parameters so the
+//! editor can build their forms.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::compress::{compress, decompress, Algorithm as CompressAlgorithm, CompressionSettings};
+use crate::crypto::{decrypt, decrypt_with_password, encrypt, encrypt_with_password, hash_data, PublicBundle};
+use crate::github::AppError;
+use crate::image_optimize::{optimize_image_data, OptimizeOptions, MAX_OPTIMIZE_LEVEL};
+use crate::pipeline::{PipelineContext, PipelineError};
+use crate::pipeline_script::ScriptStep;
+use crate::resize::{resize_image_data, ResizeMode, ResizeOptions, MAX_DIMENSION};
+use crate::transcode::{transcode_image_data, TranscodeOptions};
+use crate::watermark::{watermark_image_data, WatermarkOptions, MAX_TEXT_LEN};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepDescriptor {
+    pub id: String,
+    pub label: String,
+    pub description: String,
+    /// Replaces the input instead of wrapping it, so it must run before any
+    /// wrapping layer and is not undone by `reverse_pipeline`
+    pub source_transform: bool,
+    /// JSON Schema of the `params` the step accepts
+    pub params_schema: Value,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepEstimate {
+    /// Expected output size relative to the input
+    pub ratio: f64,
+    pub label: String,
+}
+
+pub struct StepContext<'a> {
+    /// Layer being applied or reversed; empty when reversing output that
+    /// predates recorded layer IDs
+    pub layer_id: &'a str,
+    /// Name of the file being processed, when known
+    pub file_name: Option<&'a str>,
+    pub pipeline: &'a PipelineContext,
+}
+
+pub trait PipelineStep: Send + Sync {
+    /// Registry key and the `operation_type` recorded in layer metadata
+    fn id(&self) -> &str;
+
+    fn describe(&self) -> StepDescriptor;
+
+    /// Version of the step's output format, recorded with every layer it
+    /// writes. Bump it when `reverse` could misread the new output.
+    fn version(&self) -> u32 {
+        1
+    }
+
+    /// What `reverse` needs from the caller: `"password"` or `"keypair"`
+    fn secret(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Check `params` when a pipeline is validated or saved
+    fn validate(&self, _params: &Value) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    /// Apply the step, returning the output and the metadata that
+    /// `reverse` will get back
+    fn process(&self, data: &[u8], params: &Value, ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError>;
+
+    fn reverse(&self, data: &[u8], metadata: &Value, ctx: &StepContext) -> Result<Vec<u8>, PipelineError>;
+
+    fn estimate(&self, params: &Value) -> StepEstimate;
+}
+
+lazy_static::lazy_static! {
+    static ref STEPS: RwLock<HashMap<String, Arc<dyn PipelineStep>>> = RwLock::new(builtin_steps());
+}
+
+fn builtin_steps() -> HashMap<String, Arc<dyn PipelineStep>> {
+    let steps: Vec<Arc<dyn PipelineStep>> = vec![
+        Arc::new(CompressStep),
+        Arc::new(PasswordStep),
+        Arc::new(HybridPqStep),
+        Arc::new(HashStep),
+        Arc::new(Base64Step),
+        Arc::new(OptimizeImageStep),
+        Arc::new(TranscodeStep),
+        Arc::new(ResizeStep),
+        Arc::new(WatermarkStep),
+        Arc::new(ScriptStep),
+    ];
+    steps.into_iter().map(|s| (s.id().to_string(), s)).collect()
+}
+
+/// Add `step` to the registry. IDs are unique; a built-in cannot be replaced.
+pub fn register_step(step: Arc<dyn PipelineStep>) -> Result<(), AppError> {
+    let id = step.id().to_string();
+    if id.is_empty() {
+        return Err(AppError::Validation("Pipeline step ID cannot be empty".into()));
+    }
+    let mut steps = STEPS.write().unwrap_or_else(|e| e.into_inner());
+    if steps.contains_key(&id) {
+        return Err(AppError::Validation(format!("Pipeline step {} is already registered", id)));
+    }
+    steps.insert(id, step);
+    Ok(())
+}
+
+pub fn find_step(id: &str) -> Option<Arc<dyn PipelineStep>> {
+    STEPS.read().unwrap_or_else(|e| e.into_inner()).get(id).cloned()
+}
+
+/// Descriptors of every registered step, sorted by ID
+pub fn describe_steps() -> Vec<StepDescriptor> {
+    let mut descriptors: Vec<_> = STEPS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .map(|s| s.describe())
+        .collect();
+    descriptors.sort_by(|a, b| a.id.cmp(&b.id));
+    descriptors
+}
+
+fn parse_params<T: DeserializeOwned>(params: &Value) -> Result<T, PipelineError> {
+    serde_json::from_value(params.clone()).map_err(|e| PipelineError::InvalidData(format!("Bad step parameters: {}", e)))
+}
+
+fn validate_params<T: DeserializeOwned>(params: &Value) -> Result<T, AppError> {
+    parse_params(params).map_err(|e| AppError::Validation(e.to_string()))
+}
+
+// ============================================================================
+// Built-in Steps
+// ============================================================================
+
+const ALGORITHMS: [&str; 7] = ["zstd", "lz4", "snap", "brotli", "gzip", "xz", "none"];
+
+#[derive(Deserialize)]
+struct CompressParams {
+    algorithm: String,
+    level: i32,
+}
+
+struct CompressStep;
+
+impl PipelineStep for CompressStep {
+    fn id(&self) -> &str {
+        "compress"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Compress".to_string(),
+            description: "Lossless compression with a choice of algorithm".to_string(),
+            source_transform: false,
+            params_schema: json!({
+                "type": "object",
+                "required": ["algorithm", "level"],
+                "properties": {
+                    "algorithm": { "type": "string", "enum": ALGORITHMS, "default": "zstd" },
+                    "level": { "type": "integer", "minimum": 0, "maximum": 22, "default": 3 }
+                }
+            }),
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), AppError> {
+        let params: CompressParams = validate_params(params)?;
+        if params.level < 0 || params.level > 22 {
+            return Err(AppError::Validation(format!(
+                "Invalid compression level: {} (must be 0-22)", params.level
+            )));
+        }
+        Ok(())
+    }
+
+    fn process(&self, data: &[u8], params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let params: CompressParams = parse_params(params)?;
+        let settings = CompressionSettings {
+            algorithm: CompressAlgorithm::from(params.algorithm.as_str()),
+            level: params.level,
+            prefer_speed: false,
+        };
+        let result = compress(data, &settings).map_err(|e| PipelineError::Compression(e.to_string()))?;
+        Ok((result.data, json!({ "algorithm": params.algorithm, "level": params.level })))
+    }
+
+    fn reverse(&self, data: &[u8], metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        let algorithm = metadata["algorithm"].as_str().unwrap_or("zstd");
+        decompress(data, CompressAlgorithm::from(algorithm)).map_err(|e| PipelineError::Compression(e.to_string()))
+    }
+
+    fn estimate(&self, params: &Value) -> StepEstimate {
+        let algorithm = params["algorithm"].as_str().unwrap_or_default();
+        let ratio = match algorithm {
+            "zstd" => 0.4,
+            "lz4" => 0.6,
+            "snap" => 0.65,
+            "brotli" => 0.35,
+            "gzip" => 0.45,
+            "xz" => 0.3,
+            _ => 1.0,
+        };
+        StepEstimate { ratio, label: format!("Compress ({})", algorithm) }
+    }
+}
+
+struct PasswordStep;
+
+impl PipelineStep for PasswordStep {
+    fn id(&self) -> &str {
+        "encrypt_password"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Password Encryption".to_string(),
+            description: "Encrypt with a password supplied when the pipeline runs".to_string(),
+            source_transform: false,
+            params_schema: json!({ "type": "object", "properties": {} }),
+        }
+    }
+
+    fn process(&self, data: &[u8], _params: &Value, ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let password = ctx.pipeline.passwords.get(ctx.layer_id)
+            .ok_or_else(|| PipelineError::MissingPassword(ctx.layer_id.to_string()))?;
+        let encrypted = encrypt_with_password(data, password.as_bytes())
+            .map_err(|e| PipelineError::Encryption(e.to_string()))?;
+        Ok((encrypted, json!({})))
+    }
+
+    fn secret(&self) -> Option<&'static str> {
+        Some("password")
+    }
+
+    fn reverse(&self, data: &[u8], _metadata: &Value, ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        // The layer's own password first, then any other that was given
+        let own = ctx.pipeline.passwords.get(ctx.layer_id);
+        let others = ctx.pipeline.passwords.iter().filter(|(id, _)| id.as_str() != ctx.layer_id).map(|(_, p)| p);
+        for password in own.into_iter().chain(others) {
+            if let Ok(decrypted) = decrypt_with_password(data, password.as_bytes()) {
+                return Ok(decrypted);
+            }
+        }
+        Err(PipelineError::MissingPassword("No valid password found".into()))
+    }
+
+    fn estimate(&self, _params: &Value) -> StepEstimate {
+        StepEstimate { ratio: 1.05, label: "Password Encryption".to_string() }
+    }
+}
+
+#[derive(Deserialize)]
+struct HybridPqParams {
+    #[serde(default)]
+    recipient_bundle: Option<PublicBundle>,
+}
+
+struct HybridPqStep;
+
+impl PipelineStep for HybridPqStep {
+    fn id(&self) -> &str {
+        "encrypt_hybrid_pq"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "PQ Encryption".to_string(),
+            description: "ML-KEM-1024 + X25519 hybrid encryption to a recipient".to_string(),
+            source_transform: false,
+            params_schema: json!({
+                "type": "object",
+                "properties": {
+                    "recipient_bundle": { "type": ["object", "null"], "description": "Recipient public key bundle" }
+                }
+            }),
+        }
+    }
+
+    fn process(&self, data: &[u8], params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let params: HybridPqParams = parse_params(params)?;
+        let bundle = params.recipient_bundle.ok_or(PipelineError::MissingRecipient)?;
+        let payload = encrypt(data, &bundle).map_err(|e| PipelineError::Encryption(e.to_string()))?;
+        let serialized = serde_json::to_vec(&payload).map_err(|e| PipelineError::Serialization(e.to_string()))?;
+        Ok((serialized, json!({})))
+    }
+
+    fn secret(&self) -> Option<&'static str> {
+        Some("keypair")
+    }
+
+    fn reverse(&self, data: &[u8], _metadata: &Value, ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        let keypair = ctx.pipeline.keypair.as_ref().ok_or(PipelineError::MissingKeypair)?;
+        let payload = serde_json::from_slice(data).map_err(|e| PipelineError::Serialization(e.to_string()))?;
+        decrypt(&payload, keypair).map_err(|e| PipelineError::Encryption(e.to_string()))
+    }
+
+    fn estimate(&self, _params: &Value) -> StepEstimate {
+        StepEstimate { ratio: 1.1, label: "PQ Encryption".to_string() }
+    }
+}
+
+struct HashStep;
+
+impl PipelineStep for HashStep {
+    fn id(&self) -> &str {
+        "hash"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Hash".to_string(),
+            description: "Record a BLAKE3 hash of the data at this point".to_string(),
+            source_transform: false,
+            params_schema: json!({ "type": "object", "properties": {} }),
+        }
+    }
+
+    fn process(&self, data: &[u8], _params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        Ok((data.to_vec(), json!({ "hash": hex::encode(hash_data(data)) })))
+    }
+
+    fn reverse(&self, data: &[u8], _metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        Ok(data.to_vec())
+    }
+
+    fn estimate(&self, _params: &Value) -> StepEstimate {
+        StepEstimate { ratio: 1.0, label: "Hash".to_string() }
+    }
+}
+
+struct Base64Step;
+
+impl PipelineStep for Base64Step {
+    fn id(&self) -> &str {
+        "base64_encode"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Base64 Encode".to_string(),
+            description: "Encode as Base64 text".to_string(),
+            source_transform: false,
+            params_schema: json!({ "type": "object", "properties": {} }),
+        }
+    }
+
+    fn process(&self, data: &[u8], _params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        Ok((STANDARD.encode(data).into_bytes(), json!({})))
+    }
+
+    fn reverse(&self, data: &[u8], _metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        STANDARD.decode(data).map_err(|e| PipelineError::Encoding(e.to_string()))
+    }
+
+    fn estimate(&self, _params: &Value) -> StepEstimate {
+        StepEstimate { ratio: 1.33, label: "Base64 Encode".to_string() }
+    }
+}
+
+struct OptimizeImageStep;
+
+impl PipelineStep for OptimizeImageStep {
+    fn id(&self) -> &str {
+        "optimize_image"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Optimize Image".to_string(),
+            description: "Lossless PNG/JPEG optimization".to_string(),
+            source_transform: true,
+            params_schema: json!({
+                "type": "object",
+                "properties": {
+                    "level": { "type": "integer", "minimum": 0, "maximum": MAX_OPTIMIZE_LEVEL, "default": 2 },
+                    "strip_metadata": { "type": "boolean", "default": false }
+                }
+            }),
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), AppError> {
+        crate::image_optimize::validate_options(&validate_params(params)?)
+    }
+
+    fn process(&self, data: &[u8], params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let options: OptimizeOptions = parse_params(params)?;
+        let (output, result) = optimize_image_data(data, &options);
+        Ok((output, json!({ "optimized": result.optimized, "saved_bytes": result.saved_bytes })))
+    }
+
+    // The optimized image is what the pipeline restores
+    fn reverse(&self, data: &[u8], _metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        Ok(data.to_vec())
+    }
+
+    fn estimate(&self, _params: &Value) -> StepEstimate {
+        StepEstimate { ratio: 0.85, label: "Optimize Image".to_string() }
+    }
+}
+
+struct TranscodeStep;
+
+impl PipelineStep for TranscodeStep {
+    fn id(&self) -> &str {
+        "transcode"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Transcode".to_string(),
+            description: "Convert to AVIF, WebP or JPEG XL".to_string(),
+            source_transform: true,
+            params_schema: json!({
+                "type": "object",
+                "required": ["format"],
+                "properties": {
+                    "format": { "type": "string", "enum": ["avif", "webp", "jxl"] },
+                    "quality": { "type": "integer", "minimum": 1, "maximum": 100, "default": 75 },
+                    "keep_original": { "type": "boolean", "default": false }
+                }
+            }),
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), AppError> {
+        crate::transcode::validate_options(&validate_params(params)?)
+    }
+
+    fn process(&self, data: &[u8], params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let options: TranscodeOptions = parse_params(params)?;
+        let (output, result) = transcode_image_data(data, &options)
+            .map_err(|e| PipelineError::Encoding(e.to_string()))?;
+        Ok((output, json!({
+            "format": result.format,
+            "quality": options.quality,
+            "transcoded": result.transcoded
+        })))
+    }
+
+    fn reverse(&self, data: &[u8], _metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        Ok(data.to_vec())
+    }
+
+    fn estimate(&self, params: &Value) -> StepEstimate {
+        match serde_json::from_value::<TranscodeOptions>(params.clone()) {
+            Ok(options) => StepEstimate {
+                ratio: options.format.typical_ratio(),
+                label: format!("Transcode ({})", options.format.extension()),
+            },
+            Err(_) => StepEstimate { ratio: 1.0, label: "Transcode".to_string() },
+        }
+    }
+}
+
+struct ResizeStep;
+
+impl PipelineStep for ResizeStep {
+    fn id(&self) -> &str {
+        "resize"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Resize".to_string(),
+            description: "Scale to a maximum edge, exact size or percentage".to_string(),
+            source_transform: true,
+            params_schema: json!({
+                "type": "object",
+                "required": ["mode"],
+                "properties": {
+                    "mode": { "type": "string", "enum": ["max_edge", "exact", "percent"] },
+                    "max_edge": { "type": "integer", "minimum": 1, "maximum": MAX_DIMENSION },
+                    "width": { "type": "integer", "minimum": 1, "maximum": MAX_DIMENSION },
+                    "height": { "type": "integer", "minimum": 1, "maximum": MAX_DIMENSION },
+                    "percent": { "type": "integer", "minimum": 1, "maximum": 100 },
+                    "quality": { "type": "integer", "minimum": 1, "maximum": 100, "default": 85 },
+                    "keep_original": { "type": "boolean", "default": false },
+                    "originals_album": { "type": "string" }
+                }
+            }),
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), AppError> {
+        crate::resize::validate_options(&validate_params(params)?)
+    }
+
+    fn process(&self, data: &[u8], params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let options: ResizeOptions = parse_params(params)?;
+        let (output, result) = resize_image_data(data, &options)
+            .map_err(|e| PipelineError::Encoding(e.to_string()))?;
+        Ok((output, json!({
+            "width": result.width,
+            "height": result.height,
+            "resized": result.resized
+        })))
+    }
+
+    // The resized image is what the pipeline restores
+    fn reverse(&self, data: &[u8], _metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        Ok(data.to_vec())
+    }
+
+    fn estimate(&self, params: &Value) -> StepEstimate {
+        match serde_json::from_value::<ResizeOptions>(params.clone()) {
+            Ok(ResizeOptions { mode: ResizeMode::Percent, percent: Some(percent), .. }) => StepEstimate {
+                ratio: (percent as f64 / 100.0).powi(2),
+                label: format!("Resize ({}%)", percent),
+            },
+            Ok(ResizeOptions { mode: ResizeMode::MaxEdge, max_edge: Some(edge), .. }) => StepEstimate {
+                ratio: 0.5,
+                label: format!("Resize (max {}px)", edge),
+            },
+            _ => StepEstimate { ratio: 0.5, label: "Resize".to_string() },
+        }
+    }
+}
+
+struct WatermarkStep;
+
+impl PipelineStep for WatermarkStep {
+    fn id(&self) -> &str {
+        "watermark"
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.id().to_string(),
+            label: "Watermark".to_string(),
+            description: "Composite a PNG or text onto photos".to_string(),
+            source_transform: true,
+            params_schema: json!({
+                "type": "object",
+                "properties": {
+                    "image_path": { "type": "string" },
+                    "text": { "type": "string", "maxLength": MAX_TEXT_LEN },
+                    "font_path": { "type": "string" },
+                    "position": {
+                        "type": "string",
+                        "enum": ["top_left", "top_right", "bottom_left", "bottom_right", "center"],
+                        "default": "bottom_right"
+                    },
+                    "opacity": { "type": "number", "exclusiveMinimum": 0, "maximum": 1, "default": 0.5 },
+                    "scale": { "type": "number", "exclusiveMinimum": 0, "maximum": 1, "default": 0.2 },
+                    "quality": { "type": "integer", "minimum": 1, "maximum": 100, "default": 90 }
+                }
+            }),
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), AppError> {
+        crate::watermark::validate_options(&validate_params(params)?)
+    }
+
+    fn process(&self, data: &[u8], params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let options: WatermarkOptions = parse_params(params)?;
+        let (output, format) = watermark_image_data(data, &options)
+            .map_err(|e| PipelineError::Encoding(e.to_string()))?;
+        Ok((output, json!({ "format": format, "position": options.position })))
+    }
+
+    // The watermarked image is what the pipeline restores
+    fn reverse(&self, data: &[u8], _metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        Ok(data.to_vec())
+    }
+
+    // Compositing barely changes the size; JPEGs are re-encoded
+    fn estimate(&self, params: &Value) -> StepEstimate {
+        let label = match serde_json::from_value::<WatermarkOptions>(params.clone()) {
+            Ok(WatermarkOptions { text: Some(text), .. }) => format!("Watermark \"{}\"", text),
+            _ => "Watermark".to_string(),
+        };
+        StepEstimate { ratio: 1.0, label }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Registered pipeline steps with their parameter schemas
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn pipeline_describe_steps() -> Vec<StepDescriptor> {
+    describe_steps()
+}
@@ -0,0 +1,121 @@
+//! Prefetching Around the Viewed Photo
+//!
+//! `prefetch_photos` warms the download cache (see `download_cache`) with
+//! the neighbors of the photo being viewed, nearest first and the next one
+//! before the previous, so stepping through an album opens each photo from
+//! disk. Neighbors come from the album's catalog listing, in listing order.
+//!
+//! Prefetching only uses idle bandwidth: photos are fetched one at a time
+//! as background work (see `scheduler`), so not while the user opens one.
+//! Each call supersedes the previous one, so jumping elsewhere in the album
+//! cancels the old neighbors; `cancel_prefetch` stops prefetching outright.
+//! A photo already being fetched when cancelled is still cached.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{Emitter, State};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::catalog::{cached_listing, photo_path};
+use crate::crypto::KeypairHandle;
+use crate::download_cache::{cache_dir, has_photo_in, remember_photo};
+use crate::github::{fetch_photo, validate_repo, AppError, HttpClient};
+use crate::local_store::with_store;
+use crate::scheduler::{JobClass, SCHEDULER};
+
+pub const DEFAULT_PREFETCH_COUNT: usize = 4;
+pub const MAX_PREFETCH_COUNT: usize = 20;
+/// Emitted with the path of each prefetched photo
+pub const PHOTO_PREFETCHED_EVENT: &str = "photo-prefetched";
+
+/// Bumped by every request, so older prefetches see they are superseded
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Indexes of the `count` nearest neighbors of `around` among `len`
+/// photos, alternating after and before it
+pub fn neighbor_indexes(len: usize, around: usize, count: usize) -> Vec<usize> {
+    let mut indexes = Vec::new();
+    if around >= len {
+        return indexes;
+    }
+    for distance in 1..len {
+        if indexes.len() >= count {
+            break;
+        }
+        if let Some(next) = around.checked_add(distance).filter(|&i| i < len) {
+            indexes.push(next);
+        }
+        if indexes.len() < count {
+            if let Some(previous) = around.checked_sub(distance) {
+                indexes.push(previous);
+            }
+        }
+    }
+    indexes
+}
+
+fn superseded(generation: u64) -> bool {
+    GENERATION.load(Ordering::Relaxed) != generation
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Cache up to `count` neighbors of the photo at `around_index` of
+/// `album` in the background. Returns the paths that will be fetched;
+/// those already cached are left out.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
+pub fn prefetch_photos(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album: String,
+    around_index: usize,
+    count: Option<usize>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<Vec<String>, AppError> {
+    validate_repo(&repo)?;
+    let generation = GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+    let Some(items) = cached_listing(&repo, &album, true) else {
+        return Ok(Vec::new());
+    };
+    let count = count.unwrap_or(DEFAULT_PREFETCH_COUNT).min(MAX_PREFETCH_COUNT);
+    let dir = cache_dir()?;
+    let paths: Vec<String> = neighbor_indexes(items.len(), around_index, count)
+        .into_iter()
+        .map(|i| photo_path(&album, &items[i]))
+        .filter(|path| !with_store(|store| has_photo_in(store, &dir, &repo, path, true)).unwrap_or(false))
+        .collect();
+
+    let client = client.0.clone();
+    let queued = paths.clone();
+    tauri::async_runtime::spawn(async move {
+        for path in queued {
+            // Background work waits while the user opens photos
+            let _permit = SCHEDULER.acquire(JobClass::Background).await;
+            if superseded(generation) {
+                return;
+            }
+            match fetch_photo(&client, &repo, &token, &path, keypair_handle, true).await {
+                Ok(photo) => {
+                    remember_photo(&dir, &repo, &path, &photo);
+                    let _ = app.emit(PHOTO_PREFETCHED_EVENT, &path);
+                }
+                // Only a warm-up: the photo is fetched again when opened
+                Err(e) => tracing::debug!("Could not prefetch {}: {}", path, e),
+            }
+        }
+    });
+    Ok(paths)
+}
+
+/// Stop prefetching after the photo being fetched
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn cancel_prefetch() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
@@ -0,0 +1,289 @@
+//! Identity Profiles
+//!
+//! Several local identities ("personal", "work") side by side, one active at
+//! a time. Each profile has its own
+//!
+//! - data directory, `<profile data>` in other modules' docs. The default
+//!   profile keeps `<local data>/vortex-image`, so existing installs need no
+//!   migration; others live in `<local data>/vortex-image/profiles/<id>`.
+//!   Contacts, the persisted keypair, archived keys, mirrors, sync state,
+//!   the offline queue, the upload policy and the encrypted local store all
+//!   resolve through `data_dir`;
+//! - keystore namespace: secrets of non-default profiles are stored as
+//!   `<id>.<name>` (see `Keystore::detect`);
+//! - frontend settings file, holding the GitHub token and the bound repo.
+//!
+//! The registry of profiles and the active one is
+//! `<local data>/vortex-image/profiles.json`. `switch_profile` drops every
+//! in-memory keypair, session, unlocked local vault and cache and stops
+//! background watches of the old profile, then emits `profile-switched` so
+//! the frontend reloads its state.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Emitter;
+
+use crate::AppHandle;
+use crate::github::{AppError, GithubError};
+use crate::util::now_secs;
+
+pub const DEFAULT_PROFILE: &str = "default";
+const REGISTRY_FILE: &str = "profiles.json";
+const PROFILES_DIR: &str = "profiles";
+const MAX_NAME_LEN: usize = 64;
+const MAX_ID_LEN: usize = 32;
+
+lazy_static::lazy_static! {
+    /// Registry as last loaded or saved; `None` until first use
+    static ref REGISTRY: Mutex<Option<ProfileRegistry>> = Mutex::new(None);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    /// Repository this identity works with, if bound
+    #[serde(default)]
+    pub repo: Option<String>,
+    /// Frontend settings store of this profile
+    pub store_file: String,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileRegistry {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+fn not_found(id: &str) -> AppError {
+    GithubError::NotFound {
+        message: format!("Profile {} not found", id),
+    }
+    .into()
+}
+
+fn store_file(id: &str) -> String {
+    if id == DEFAULT_PROFILE {
+        "settings.json".into()
+    } else {
+        format!("settings-{}.json", id)
+    }
+}
+
+/// Lowercase ASCII letters, digits and dashes from `name`
+fn slug(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.truncate(MAX_ID_LEN);
+    out.trim_end_matches('-').to_string()
+}
+
+impl Default for ProfileRegistry {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE.into(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE.into(),
+                name: "Default".into(),
+                repo: None,
+                store_file: store_file(DEFAULT_PROFILE),
+                created_at: 0,
+            }],
+        }
+    }
+}
+
+impl ProfileRegistry {
+    pub fn get(&self, id: &str) -> Result<&Profile, AppError> {
+        self.profiles.iter().find(|p| p.id == id).ok_or_else(|| not_found(id))
+    }
+
+    pub fn active(&self) -> &Profile {
+        self.get(&self.active).unwrap_or(&self.profiles[0])
+    }
+
+    /// Add a profile with an id derived from `name`
+    pub fn create(&mut self, name: &str, repo: Option<String>) -> Result<Profile, AppError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(AppError::Validation(format!(
+                "Profile name must be 1 to {} characters",
+                MAX_NAME_LEN
+            )));
+        }
+        if self.profiles.iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+            return Err(AppError::Validation(format!("A profile named '{}' already exists", name)));
+        }
+
+        let base = match slug(name) {
+            s if s.is_empty() => "profile".to_string(),
+            s => s,
+        };
+        let mut id = base.clone();
+        let mut n = 2;
+        while self.profiles.iter().any(|p| p.id == id) {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+
+        let profile = Profile {
+            store_file: store_file(&id),
+            id,
+            name: name.to_string(),
+            repo: repo.filter(|r| !r.trim().is_empty()),
+            created_at: now_secs(),
+        };
+        self.profiles.push(profile.clone());
+        Ok(profile)
+    }
+
+    pub fn switch(&mut self, id: &str) -> Result<Profile, AppError> {
+        let profile = self.get(id)?.clone();
+        self.active = profile.id.clone();
+        Ok(profile)
+    }
+
+    pub fn list(&self) -> ProfileList {
+        ProfileList {
+            active: self.active.clone(),
+            profiles: self.profiles.clone(),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, AppError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let registry: Self = serde_json::from_slice(&std::fs::read(path)?)
+            .map_err(|e| AppError::Validation(format!("Corrupt profile registry: {}", e)))?;
+        if registry.profiles.is_empty() {
+            return Ok(Self::default());
+        }
+        Ok(registry)
+    }
+
+    /// Write atomically
+    pub fn save_to(&self, path: &Path) -> Result<(), AppError> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Data directory of profile `id` under `root`
+pub fn profile_dir(root: &Path, id: &str) -> PathBuf {
+    if id == DEFAULT_PROFILE {
+        root.to_path_buf()
+    } else {
+        root.join(PROFILES_DIR).join(id)
+    }
+}
+
+/// Keystore name of secret `name` for profile `id`
+pub fn secret_name(id: &str, name: &str) -> String {
+    if id == DEFAULT_PROFILE {
+        name.to_string()
+    } else {
+        format!("{}.{}", id, name)
+    }
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+/// Folder holding the profile registry and everything shared by all profiles
+pub(crate) fn root_dir() -> Result<PathBuf, AppError> {
+    let dir = dirs::data_local_dir()
+        .ok_or_else(|| AppError::Validation("No local data directory".into()))?
+        .join("vortex-image");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn with_registry<T>(save: bool, f: impl FnOnce(&mut ProfileRegistry) -> Result<T, AppError>) -> Result<T, AppError> {
+    let mut guard = REGISTRY.lock().unwrap();
+    let path = root_dir()?.join(REGISTRY_FILE);
+    if guard.is_none() {
+        *guard = Some(ProfileRegistry::load_from(&path)?);
+    }
+    let registry = guard.as_mut().unwrap();
+    let result = f(registry)?;
+    if save {
+        registry.save_to(&path)?;
+    }
+    Ok(result)
+}
+
+/// Id of the active profile
+pub(crate) fn active_id() -> Result<String, AppError> {
+    with_registry(false, |registry| Ok(registry.active().id.clone()))
+}
+
+/// Data directory of the active profile, created if missing
+pub(crate) fn data_dir() -> Result<PathBuf, AppError> {
+    let dir = profile_dir(&root_dir()?, &active_id()?);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn list_profiles() -> Result<ProfileList, AppError> {
+    with_registry(false, |registry| Ok(registry.list()))
+}
+
+/// Add a profile; it starts empty and becomes active on `switch_profile`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn create_profile(name: String, repo: Option<String>) -> Result<Profile, AppError> {
+    with_registry(true, |registry| registry.create(&name, repo))
+}
+
+/// Make `id` the active profile. Keypair handles and background watches of
+/// the previous profile stop working.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn switch_profile(app: AppHandle, id: String) -> Result<Profile, AppError> {
+    let (previous, profile) = with_registry(true, |registry| {
+        let previous = registry.active.clone();
+        Ok((previous, registry.switch(&id)?))
+    })?;
+    if previous != profile.id {
+        crate::crypto::release_all_keypairs().map_err(|e| AppError::Validation(e.to_string()))?;
+        crate::upload_policy::forget_cached_policy();
+        crate::mirror::forget_cached_mirrors();
+        crate::retention::forget_cached_policies();
+        crate::sync_schedule::forget_cached_schedules();
+        crate::watcher::stop_all_watches();
+        crate::remote_watch::stop_all_remote_watches();
+        crate::session::close_all_sessions();
+        crate::local_vault::lock_all_vaults();
+        crate::local_store::forget_local_store();
+        crate::thumbnails::forget_thumbnail_cache();
+        tracing::info!("Switched profile from {} to {}", previous, profile.id);
+    }
+    let _ = app.emit("profile-switched", &profile);
+    Ok(profile)
+}
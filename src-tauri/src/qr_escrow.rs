@@ -0,0 +1,212 @@
+//! QR Keypair Transfer
+//!
+//! `export_keypair_qr` encrypts the keypair behind a handle with a password
+//! (the same Argon2id envelope as `encrypt_data_password`) and cuts the
+//! result into parts small enough for one QR code each, so a phone camera
+//! can carry the key to another device. `import_keypair_qr` takes the
+//! scanned parts in any order and rebuilds the keypair.
+//!
+//! Each part is a self-describing string,
+//! `vortex-qr-1:<base64 of [transfer id: 8][index][total][checksum: 4][data]>`,
+//! where the checksum is a BLAKE3 prefix over everything before it. A misread
+//! part is rejected on its own; the envelope's AEAD tag covers the whole.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use zeroize::Zeroizing;
+
+use crate::crypto::{
+    decrypt_with_password, encrypt_with_password, register_keypair, with_keypair, HybridKeypair,
+    KeypairHandle, KeypairInfo,
+};
+use crate::github::AppError;
+
+const PART_PREFIX: &str = "vortex-qr-1:";
+const HEADER_LEN: usize = 10;
+const CHECKSUM_LEN: usize = 4;
+/// Default payload bytes per part: about 1.7 KB of text, which still scans
+/// reliably from a screen at medium error correction
+pub const DEFAULT_PART_BYTES: usize = 1200;
+const MIN_PART_BYTES: usize = 100;
+/// Largest payload that fits a version 40 code at medium error correction
+/// once base64 encoded
+const MAX_PART_BYTES: usize = 1700;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QrExport {
+    pub key_id: String,
+    /// Shared by all parts of one export, to tell transfers apart
+    pub transfer_id: String,
+    /// One string per QR code, in order
+    pub parts: Vec<String>,
+}
+
+/// A decoded part
+#[derive(Clone, Debug, PartialEq)]
+pub struct QrPart {
+    pub transfer_id: [u8; 8],
+    pub index: u8,
+    pub total: u8,
+    pub data: Vec<u8>,
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut out = [0u8; CHECKSUM_LEN];
+    out.copy_from_slice(&blake3::hash(bytes).as_bytes()[..CHECKSUM_LEN]);
+    out
+}
+
+/// Cut `payload` into encoded parts of at most `part_bytes` payload bytes
+pub fn split_into_parts(payload: &[u8], part_bytes: usize) -> Result<(String, Vec<String>), AppError> {
+    if !(MIN_PART_BYTES..=MAX_PART_BYTES).contains(&part_bytes) {
+        return Err(AppError::Validation(format!(
+            "Part size must be between {} and {} bytes",
+            MIN_PART_BYTES, MAX_PART_BYTES
+        )));
+    }
+    let total = payload.len().div_ceil(part_bytes).max(1);
+    let total = u8::try_from(total)
+        .map_err(|_| AppError::Validation("Payload needs more than 255 QR codes; use larger parts".into()))?;
+    let transfer_id = rand::random::<[u8; 8]>();
+
+    let parts = payload
+        .chunks(part_bytes)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut raw = Vec::with_capacity(HEADER_LEN + CHECKSUM_LEN + chunk.len());
+            raw.extend_from_slice(&transfer_id);
+            raw.push(i as u8 + 1);
+            raw.push(total);
+            let sum = checksum(&[&raw[..], chunk].concat());
+            raw.extend_from_slice(&sum);
+            raw.extend_from_slice(chunk);
+            format!("{}{}", PART_PREFIX, STANDARD.encode(raw))
+        })
+        .collect();
+    Ok((hex::encode(transfer_id), parts))
+}
+
+/// Decode and checksum one scanned part
+pub fn decode_part(part: &str) -> Result<QrPart, AppError> {
+    let encoded = part
+        .trim()
+        .strip_prefix(PART_PREFIX)
+        .ok_or_else(|| AppError::Validation("Not a Vortex key transfer code".into()))?;
+    let raw = STANDARD
+        .decode(encoded)
+        .map_err(|_| AppError::Validation("Unreadable key transfer code".into()))?;
+    if raw.len() <= HEADER_LEN + CHECKSUM_LEN {
+        return Err(AppError::Validation("Key transfer code is too short".into()));
+    }
+    let (header, rest) = raw.split_at(HEADER_LEN);
+    let (sum, data) = rest.split_at(CHECKSUM_LEN);
+    if checksum(&[header, data].concat()) != sum {
+        return Err(AppError::Validation("Key transfer code failed its checksum; scan it again".into()));
+    }
+    let (index, total) = (header[8], header[9]);
+    if total == 0 || index == 0 || index > total {
+        return Err(AppError::Validation("Key transfer code has an invalid part number".into()));
+    }
+    Ok(QrPart {
+        transfer_id: header[..8].try_into().expect("8 bytes"),
+        index,
+        total,
+        data: data.to_vec(),
+    })
+}
+
+/// Collects scanned parts until the payload is complete
+#[derive(Debug, Default)]
+pub struct QrAssembler {
+    transfer_id: Option<[u8; 8]>,
+    total: u8,
+    parts: BTreeMap<u8, Vec<u8>>,
+}
+
+impl QrAssembler {
+    /// Add a scanned part. Rescanning a part is harmless; a part of another
+    /// transfer is an error.
+    pub fn add(&mut self, part: &str) -> Result<(), AppError> {
+        let part = decode_part(part)?;
+        match self.transfer_id {
+            None => {
+                self.transfer_id = Some(part.transfer_id);
+                self.total = part.total;
+            }
+            Some(id) if id != part.transfer_id || self.total != part.total => {
+                return Err(AppError::Validation("Code belongs to a different key transfer".into()));
+            }
+            Some(_) => {}
+        }
+        self.parts.insert(part.index, part.data);
+        Ok(())
+    }
+
+    /// Part numbers (1-based) not scanned yet
+    pub fn missing(&self) -> Vec<u8> {
+        (1..=self.total).filter(|i| !self.parts.contains_key(i)).collect()
+    }
+
+    /// The reassembled payload once every part is in
+    pub fn finish(self) -> Result<Vec<u8>, AppError> {
+        if self.transfer_id.is_none() {
+            return Err(AppError::Validation("No key transfer codes given".into()));
+        }
+        let missing = self.missing();
+        if !missing.is_empty() {
+            let list: Vec<String> = missing.iter().map(|i| i.to_string()).collect();
+            return Err(AppError::Validation(format!(
+                "Missing parts {} of {}",
+                list.join(", "),
+                self.total
+            )));
+        }
+        Ok(self.parts.into_values().flatten().collect())
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Export the keypair behind `keypair_handle` as password-protected QR code
+/// payloads. `part_bytes` bounds the payload per code.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn export_keypair_qr(
+    keypair_handle: KeypairHandle,
+    password: String,
+    part_bytes: Option<usize>,
+) -> Result<QrExport, AppError> {
+    let password = Zeroizing::new(password);
+    crate::password::ensure_strong(&password)?;
+    let (key_id, bytes) = with_keypair(keypair_handle, |kp| Ok((kp.public_bundle().key_id, kp.to_bytes())))
+        ?;
+    let sealed = encrypt_with_password(&bytes, password.as_bytes())?;
+
+    let (transfer_id, parts) = split_into_parts(&sealed, part_bytes.unwrap_or(DEFAULT_PART_BYTES))?;
+    Ok(QrExport {
+        key_id,
+        transfer_id,
+        parts,
+    })
+}
+
+/// Rebuild a keypair from every part of a QR export, in any order
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn import_keypair_qr(parts: Vec<String>, password: String) -> Result<KeypairInfo, AppError> {
+    let password = Zeroizing::new(password);
+    let mut assembler = QrAssembler::default();
+    for part in &parts {
+        assembler.add(part)?;
+    }
+    let sealed = assembler.finish()?;
+    let bytes = Zeroizing::new(
+        decrypt_with_password(&sealed, password.as_bytes())
+            .map_err(|_| AppError::Validation("Wrong password or corrupted key transfer".into()))?,
+    );
+    let keypair = HybridKeypair::from_bytes(&bytes)?;
+    register_keypair(keypair).map_err(AppError::from)
+}
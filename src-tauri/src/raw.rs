@@ -0,0 +1,432 @@
+//! Camera RAW Files
+//!
+//! RAW files are not decoded themselves; every common format carries a
+//! camera-rendered JPEG that is good enough to preview, and that is what the
+//! gallery shows. TIFF-based formats (CR2, NEF, ARW, DNG, ORF, RW2, PEF, ...)
+//! point at their previews from their IFDs, RAF stores an offset in its
+//! header, and anything else (CR3, X3F) is scanned for JPEG streams. The
+//! largest baseline or progressive JPEG found wins; lossless JPEG streams
+//! hold sensor data and are skipped.
+//!
+//! Cameras shooting RAW+JPEG write two files with the same stem. Album
+//! uploads keep both, and listings fold the RAW into its JPEG's entry.
+
+use image::DynamicImage;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::fs;
+
+use crate::github::{AppError, PhotoItem};
+use crate::metadata_vault::{extract_exif, ExifExtract};
+use crate::transcode::decode_upright;
+
+pub const RAW_EXTENSIONS: &[&str] = &[
+    "cr2", "cr3", "nef", "nrw", "arw", "srf", "sr2", "dng", "raf", "orf", "rw2", "pef", "srw", "x3f", "3fr", "iiq",
+];
+
+/// Extensions of the rendered half of a RAW+JPEG pair
+const PAIRED_EXTENSIONS: &[&str] = &["jpg", "jpeg", "heic", "heif"];
+
+const RAF_MAGIC: &[u8] = b"FUJIFILMCCD-RAW ";
+const RAF_JPEG_OFFSET: usize = 84;
+
+/// Bounds on walking untrusted IFD chains and scanning for JPEG streams
+const MAX_IFDS: usize = 32;
+const MAX_SCAN_CANDIDATES: usize = 64;
+
+// TIFF tags
+const NEW_SUBFILE_TYPE: u16 = 0x00FE;
+const IMAGE_WIDTH: u16 = 0x0100;
+const IMAGE_LENGTH: u16 = 0x0101;
+const STRIP_OFFSETS: u16 = 0x0111;
+const SUB_IFDS: u16 = 0x014A;
+const JPEG_INTERCHANGE_FORMAT: u16 = 0x0201;
+const DNG_VERSION: u16 = 0xC612;
+
+pub fn is_raw_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| RAW_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// The embedded preview of a RAW file
+#[derive(Clone, Debug)]
+pub struct RawPreview {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RawPreviewFile {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: u64,
+}
+
+// ============================================================================
+// Container Detection
+// ============================================================================
+
+#[derive(Clone, Copy)]
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    /// TIFF header, including the variant magics of ORF and RW2
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        let tiff = Self { data, little_endian };
+        matches!(tiff.u16(2)?, 42 | 0x4F52 | 0x5352 | 0x55).then_some(tiff)
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(at..at.checked_add(2)?)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(at..at.checked_add(4)?)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    /// Values of the entry at `at`, for SHORT, LONG and IFD entries
+    fn values(&self, at: usize) -> Vec<u32> {
+        let (Some(kind), Some(count)) = (self.u16(at + 2), self.u32(at + 4)) else {
+            return Vec::new();
+        };
+        let width = match kind {
+            3 => 2,
+            4 | 13 => 4,
+            _ => return Vec::new(),
+        };
+        let count = (count as usize).min(64);
+        let start = if count * width <= 4 {
+            at + 8
+        } else {
+            match self.u32(at + 8) {
+                Some(offset) => offset as usize,
+                None => return Vec::new(),
+            }
+        };
+        (0..count)
+            .map_while(|i| match width {
+                2 => self.u16(start + i * 2).map(u32::from),
+                _ => self.u32(start + i * 4),
+            })
+            .collect()
+    }
+
+    /// Every IFD reachable from the header through next-IFD links and
+    /// SubIFDs, as lists of `(tag, values)`
+    fn ifds(&self) -> Vec<Vec<(u16, Vec<u32>)>> {
+        let mut pending = vec![self.u32(4).unwrap_or(0) as usize];
+        let mut seen = Vec::new();
+        let mut ifds = Vec::new();
+        while let Some(offset) = pending.pop() {
+            if offset == 0 || seen.contains(&offset) || seen.len() >= MAX_IFDS {
+                continue;
+            }
+            seen.push(offset);
+            let Some(count) = self.u16(offset) else { continue };
+            let entries: Vec<(u16, Vec<u32>)> = (0..count as usize)
+                .map(|i| offset + 2 + i * 12)
+                .map_while(|at| Some((self.u16(at)?, self.values(at))))
+                .collect();
+            if let Some(next) = self.u32(offset + 2 + count as usize * 12) {
+                pending.push(next as usize);
+            }
+            for (tag, values) in &entries {
+                if *tag == SUB_IFDS {
+                    pending.extend(values.iter().map(|v| *v as usize));
+                }
+            }
+            ifds.push(entries);
+        }
+        ifds
+    }
+}
+
+fn tag(ifd: &[(u16, Vec<u32>)], tag: u16) -> Option<u32> {
+    ifd.iter().find(|(t, _)| *t == tag).and_then(|(_, values)| values.first().copied())
+}
+
+fn is_cr3(data: &[u8]) -> bool {
+    data.get(4..12) == Some(&b"ftypcrx "[..])
+}
+
+/// Whether `data` is a camera RAW file rather than a plain image. Plain
+/// TIFFs are told apart by their first IFD holding the full image, where
+/// RAW formats put a reduced preview or thumbnail there.
+pub fn is_raw(data: &[u8]) -> bool {
+    if data.starts_with(RAF_MAGIC) || is_cr3(data) || data.starts_with(b"FOVb") {
+        return true;
+    }
+    let Some(tiff) = Tiff::parse(data) else {
+        return false;
+    };
+    // CR2 marks itself after the header; ORF and RW2 have their own magics
+    if data.get(8..10) == Some(&b"CR"[..]) || tiff.u16(2) != Some(42) {
+        return true;
+    }
+    tiff.ifds().first().is_some_and(|ifd0| {
+        tag(ifd0, DNG_VERSION).is_some() || tag(ifd0, NEW_SUBFILE_TYPE).is_some_and(|t| t & 1 == 1)
+    })
+}
+
+// ============================================================================
+// JPEG Streams
+// ============================================================================
+
+/// A JPEG stream inside a RAW file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct JpegStream {
+    start: usize,
+    len: usize,
+    width: u32,
+    height: u32,
+}
+
+/// The JPEG stream starting at `start`, if it is a baseline or progressive
+/// one that can be decoded. Its end is the first EOI after its first scan.
+fn jpeg_at(data: &[u8], start: usize) -> Option<JpegStream> {
+    if data.get(start..start + 2)? != [0xFF, 0xD8] {
+        return None;
+    }
+    let mut at = start + 2;
+    let mut frame = None;
+    loop {
+        if *data.get(at)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(at + 1)?;
+        match marker {
+            // Fill bytes and markers without a payload
+            0xFF => at += 1,
+            0x01 | 0xD0..=0xD7 => at += 2,
+            0xD9 => return None,
+            _ => {
+                let len = u16::from_be_bytes([*data.get(at + 2)?, *data.get(at + 3)?]) as usize;
+                if len < 2 {
+                    return None;
+                }
+                match marker {
+                    0xC0..=0xC2 => {
+                        let height = u16::from_be_bytes([*data.get(at + 5)?, *data.get(at + 6)?]);
+                        let width = u16::from_be_bytes([*data.get(at + 7)?, *data.get(at + 8)?]);
+                        frame = Some((width as u32, height as u32));
+                    }
+                    // Lossless, hierarchical and arithmetic-coded frames
+                    0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return None,
+                    0xDA => {
+                        let (width, height) = frame.filter(|(w, h)| *w > 0 && *h > 0)?;
+                        let scan = at + 2 + len;
+                        let end = data.get(scan..)?.windows(2).position(|w| w == [0xFF, 0xD9])? + scan + 2;
+                        return Some(JpegStream { start, len: end - start, width, height });
+                    }
+                    _ => {}
+                }
+                at += 2 + len;
+            }
+        }
+    }
+}
+
+/// JPEG offsets named by the IFDs of a TIFF-based RAW file
+fn tiff_candidates(data: &[u8]) -> Vec<usize> {
+    let Some(tiff) = Tiff::parse(data) else {
+        return Vec::new();
+    };
+    tiff.ifds()
+        .iter()
+        .flat_map(|ifd| [tag(ifd, JPEG_INTERCHANGE_FORMAT), tag(ifd, STRIP_OFFSETS)])
+        .flatten()
+        .map(|offset| offset as usize)
+        .collect()
+}
+
+fn raf_candidates(data: &[u8]) -> Vec<usize> {
+    if !data.starts_with(RAF_MAGIC) {
+        return Vec::new();
+    }
+    data.get(RAF_JPEG_OFFSET..RAF_JPEG_OFFSET + 4)
+        .map(|b| vec![u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize])
+        .unwrap_or_default()
+}
+
+/// Every decodable JPEG stream in `data`, skipping past each one found
+fn scan_streams(data: &[u8]) -> Vec<JpegStream> {
+    let mut streams = Vec::new();
+    let mut at = 0;
+    let mut tried = 0;
+    while tried < MAX_SCAN_CANDIDATES {
+        let Some(found) = data.get(at..).and_then(|rest| rest.windows(3).position(|w| w == [0xFF, 0xD8, 0xFF])) else {
+            break;
+        };
+        let start = at + found;
+        tried += 1;
+        match jpeg_at(data, start) {
+            Some(stream) => {
+                at = stream.start + stream.len;
+                streams.push(stream);
+            }
+            None => at = start + 3,
+        }
+    }
+    streams
+}
+
+/// The largest preview JPEG embedded in the RAW file `data`
+pub fn extract_preview(data: &[u8]) -> Option<RawPreview> {
+    if !is_raw(data) {
+        return None;
+    }
+    let pointed: Vec<JpegStream> = tiff_candidates(data)
+        .into_iter()
+        .chain(raf_candidates(data))
+        .filter_map(|start| jpeg_at(data, start))
+        .collect();
+    let streams = if pointed.is_empty() { scan_streams(data) } else { pointed };
+    let best = streams.into_iter().max_by_key(|s| s.width as u64 * s.height as u64)?;
+    Some(RawPreview {
+        jpeg: data[best.start..best.start + best.len].to_vec(),
+        width: best.width,
+        height: best.height,
+    })
+}
+
+// ============================================================================
+// Metadata
+// ============================================================================
+
+/// Sensor size: the largest image described by the file's IFDs
+fn sensor_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    Tiff::parse(data)?
+        .ifds()
+        .iter()
+        .filter_map(|ifd| Some((tag(ifd, IMAGE_WIDTH)?, tag(ifd, IMAGE_LENGTH)?)))
+        .max_by_key(|(w, h)| *w as u64 * *h as u64)
+}
+
+/// EXIF of a RAW file, read from the file itself when it is TIFF-based and
+/// from its preview otherwise. Width and height are the sensor's, or the
+/// preview's when the file does not describe its sensor.
+pub fn raw_exif(data: &[u8]) -> Option<ExifExtract> {
+    let preview = extract_preview(data);
+    let mut exif = extract_exif(data)
+        .or_else(|| preview.as_ref().and_then(|p| extract_exif(&p.jpeg)))
+        .unwrap_or_default();
+    let preview_size = preview.as_ref().map(|p| (p.width, p.height));
+    if let Some((width, height)) = sensor_dimensions(data).or(preview_size) {
+        exif.width = Some(width);
+        exif.height = Some(height);
+    }
+    (exif != ExifExtract::default()).then_some(exif)
+}
+
+/// The preview of the RAW file `data`, upright. Previews usually leave
+/// orientation to the RAW file, so its own is applied when they have none.
+pub fn decode_preview(data: &[u8]) -> Result<DynamicImage, AppError> {
+    let preview = extract_preview(data).ok_or_else(|| AppError::Validation("RAW file has no preview".into()))?;
+    let mut image = decode_upright(&preview.jpeg)?;
+    if extract_exif(&preview.jpeg).and_then(|e| e.orientation).is_some() {
+        return Ok(image);
+    }
+    let orientation = extract_exif(data)
+        .and_then(|e| e.orientation)
+        .and_then(|o| image::metadata::Orientation::from_exif(o as u8));
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+    Ok(image)
+}
+
+// ============================================================================
+// RAW+JPEG Pairs
+// ============================================================================
+
+fn split_name(name: &str) -> Option<(String, String)> {
+    let path = Path::new(name);
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    let stem = path.with_extension("").to_string_lossy().to_lowercase();
+    Some((stem, ext))
+}
+
+/// RAW+JPEG pairs among `names`, as the rendered file's name mapped to the
+/// RAW's. Stems match case-insensitively, within the same folder.
+pub fn raw_pairs<'a>(names: impl IntoIterator<Item = &'a str>) -> HashMap<String, String> {
+    let mut raws = HashMap::new();
+    let mut rendered = Vec::new();
+    for name in names {
+        let Some((stem, ext)) = split_name(name) else { continue };
+        if RAW_EXTENSIONS.contains(&ext.as_str()) {
+            raws.entry(stem).or_insert(name);
+        } else if PAIRED_EXTENSIONS.contains(&ext.as_str()) {
+            rendered.push((stem, name));
+        }
+    }
+    rendered
+        .into_iter()
+        .filter_map(|(stem, name)| Some((name.to_string(), raws.get(&stem)?.to_string())))
+        .collect()
+}
+
+/// Fold the RAW half of each RAW+JPEG pair into its JPEG's entry. Photos
+/// are matched on their original names, so encrypted albums pair once
+/// their names are known.
+pub fn pair_photos(photos: Vec<PhotoItem>) -> Vec<PhotoItem> {
+    let label = |p: &PhotoItem| p.display_name.clone().unwrap_or_else(|| p.name.clone());
+    let labels: Vec<String> = photos.iter().map(label).collect();
+    let pairs = raw_pairs(labels.iter().map(String::as_str));
+    if pairs.is_empty() {
+        return photos;
+    }
+    let blob_names: HashMap<String, String> = photos.iter().map(|p| (label(p), p.name.clone())).collect();
+    let paired: Vec<&String> = pairs.values().collect();
+    photos
+        .into_iter()
+        .filter(|p| !paired.contains(&&label(p)))
+        .map(|mut p| {
+            p.raw_companion = pairs.get(&label(&p)).and_then(|raw| blob_names.get(raw)).cloned();
+            p
+        })
+        .collect()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Save the embedded preview of the RAW file at `path` as a JPEG at
+/// `destination`, which must not exist yet
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn extract_raw_preview(path: String, destination: String) -> Result<RawPreviewFile, AppError> {
+    if !Path::new(&path).is_file() {
+        return Err(AppError::Validation("File does not exist".into()));
+    }
+    if Path::new(&destination).exists() {
+        return Err(AppError::Validation("Destination already exists".into()));
+    }
+    let data = fs::read(&path).await?;
+    let preview = tauri::async_runtime::spawn_blocking(move || extract_preview(&data))
+        .await
+        .map_err(|e| AppError::Validation(format!("Preview task failed: {}", e)))?
+        .ok_or_else(|| AppError::Validation("RAW file has no preview".into()))?;
+    fs::write(&destination, &preview.jpeg).await?;
+    Ok(RawPreviewFile {
+        path: destination,
+        width: preview.width,
+        height: preview.height,
+        bytes: preview.jpeg.len() as u64,
+    })
+}
@@ -0,0 +1,232 @@
+//! Mnemonic Backup
+//!
+//! `export_keypair_mnemonic` creates an identity that can be rebuilt from a
+//! 24-word BIP39 phrase. The classical keys (X25519, Ed25519) are derived from
+//! the phrase's seed with HKDF. Neither post-quantum backend can generate keys
+//! from a seed, so the ML-KEM and ML-DSA keys are random and wrapped with a
+//! third seed-derived key instead. The wrapped keys (`MnemonicBackup`) are
+//! worthless without the phrase, so they can be published next to the photos:
+//! `keys/recovery/<lookup id>.json`, where the lookup id is derived from the
+//! phrase as well, so `import_keypair_mnemonic` only needs the phrase and the
+//! repository.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bip39::Mnemonic;
+use ed25519_dalek::SigningKey;
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::crypto::{decrypt_with_key, encrypt_with_key, register_keypair, HybridKeypair, KeypairInfo};
+use crate::github::{fetch_file_bytes, put_file_contents, validate_repo, AppError, HttpClient};
+
+pub const MNEMONIC_WORDS: usize = 24;
+pub const RECOVERY_ROOT: &str = "keys/recovery";
+const BACKUP_VERSION: u32 = 1;
+const SEED_SALT: &[u8] = b"vortex-mnemonic-v1";
+
+/// Post-quantum keys of a mnemonic identity, wrapped with a seed-derived key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MnemonicBackup {
+    pub version: u32,
+    pub key_id: String,
+    pub created_at: u64,
+    /// Base64 of `[nonce: 12][ciphertext]` over `HybridKeypair::pq_key_bytes`
+    pub pq_keys: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MnemonicExport {
+    /// The 24 words, space separated. Shown once, never stored.
+    pub mnemonic: String,
+    pub keypair: KeypairInfo,
+    pub backup: MnemonicBackup,
+    /// Where the backup was published, when a repository was given
+    pub backup_path: Option<String>,
+}
+
+/// Keys derived from a mnemonic seed
+struct SeedKeys {
+    x25519: Zeroizing<[u8; 32]>,
+    ed25519: Zeroizing<[u8; 32]>,
+    wrap: Zeroizing<[u8; 32]>,
+}
+
+impl SeedKeys {
+    fn derive(mnemonic: &Mnemonic) -> Self {
+        let seed = Zeroizing::new(mnemonic.to_seed(""));
+        let hk = Hkdf::<Sha512>::new(Some(SEED_SALT), seed.as_slice());
+        let expand = |info: &[u8]| {
+            let mut key = Zeroizing::new([0u8; 32]);
+            hk.expand(info, key.as_mut_slice()).expect("32 bytes is a valid HKDF-SHA512 length");
+            key
+        };
+        Self {
+            x25519: expand(b"x25519"),
+            ed25519: expand(b"ed25519"),
+            wrap: expand(b"pq-wrap"),
+        }
+    }
+
+    /// Repository lookup id: a hash of the Ed25519 public key, so the phrase
+    /// alone finds its backup without revealing the key id
+    fn lookup_id(&self) -> String {
+        let public = SigningKey::from_bytes(&self.ed25519).verifying_key().to_bytes();
+        let mut hasher = blake3::Hasher::new_derive_key("vortex-image mnemonic lookup v1");
+        hasher.update(&public);
+        hex::encode(&hasher.finalize().as_bytes()[..16])
+    }
+}
+
+fn backup_aad(key_id: &str, created_at: u64) -> Vec<u8> {
+    format!("vortex-mnemonic-backup:{}:{}", key_id, created_at).into_bytes()
+}
+
+/// Parse a phrase typed by a user: case and extra whitespace are ignored, the
+/// checksum is enforced and the phrase must have 24 words
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, AppError> {
+    let normalized = Zeroizing::new(
+        phrase
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    let mnemonic = Mnemonic::parse_normalized(&normalized)
+        .map_err(|e| AppError::Validation(format!("Invalid recovery phrase: {}", e)))?;
+    if mnemonic.word_count() != MNEMONIC_WORDS {
+        return Err(AppError::Validation(format!(
+            "Recovery phrase must have {} words",
+            MNEMONIC_WORDS
+        )));
+    }
+    Ok(mnemonic)
+}
+
+/// Path of the published backup for a phrase
+pub fn backup_path(mnemonic: &Mnemonic) -> String {
+    format!("{}/{}.json", RECOVERY_ROOT, SeedKeys::derive(mnemonic).lookup_id())
+}
+
+/// Create a keypair from a mnemonic and wrap its post-quantum keys
+pub fn keypair_from_new_mnemonic(mnemonic: &Mnemonic) -> Result<(HybridKeypair, MnemonicBackup), AppError> {
+    let keys = SeedKeys::derive(mnemonic);
+    let keypair = HybridKeypair::generate_with_classical(*keys.x25519, *keys.ed25519)?;
+    let key_id = keypair.public_bundle().key_id;
+
+    let sealed = encrypt_with_key(
+        &keypair.pq_key_bytes(),
+        &keys.wrap,
+        &backup_aad(&key_id, keypair.created_at),
+    )
+    ?;
+    let backup = MnemonicBackup {
+        version: BACKUP_VERSION,
+        key_id,
+        created_at: keypair.created_at,
+        pq_keys: STANDARD.encode(sealed),
+    };
+    Ok((keypair, backup))
+}
+
+/// Rebuild the keypair of a mnemonic from its backup
+pub fn keypair_from_mnemonic(mnemonic: &Mnemonic, backup: &MnemonicBackup) -> Result<HybridKeypair, AppError> {
+    if backup.version != BACKUP_VERSION {
+        return Err(AppError::Validation(format!(
+            "Unsupported recovery backup version {}",
+            backup.version
+        )));
+    }
+    let keys = SeedKeys::derive(mnemonic);
+    let sealed = STANDARD
+        .decode(&backup.pq_keys)
+        .map_err(|_| AppError::Validation("Corrupt recovery backup".into()))?;
+    let pq_keys = Zeroizing::new(
+        decrypt_with_key(&sealed, &keys.wrap, &backup_aad(&backup.key_id, backup.created_at))
+            .map_err(|_| AppError::Validation("Recovery phrase does not match this backup".into()))?,
+    );
+
+    let keypair = HybridKeypair::from_classical_and_pq(*keys.x25519, *keys.ed25519, &pq_keys, backup.created_at)
+        ?;
+    if keypair.public_bundle().key_id != backup.key_id {
+        return Err(AppError::Validation("Recovered keypair does not match the backup key id".into()));
+    }
+    Ok(keypair)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Create a new identity backed by a 24-word recovery phrase. With `repo` and
+/// `token`, the wrapped post-quantum keys are published there so the phrase
+/// alone is enough to recover; otherwise keep `backup` alongside the phrase.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn export_keypair_mnemonic(
+    client: State<'_, HttpClient>,
+    repo: Option<String>,
+    token: Option<Zeroizing<String>>,
+) -> Result<MnemonicExport, AppError> {
+    let entropy = Zeroizing::new(rand::random::<[u8; 32]>());
+    let mnemonic = Mnemonic::from_entropy(entropy.as_slice())
+        .map_err(|e| AppError::Validation(format!("Mnemonic generation failed: {}", e)))?;
+    let (keypair, backup) = keypair_from_new_mnemonic(&mnemonic)?;
+
+    let backup_path = match (repo, token) {
+        (Some(repo), Some(token)) => {
+            validate_repo(&repo)?;
+            let path = backup_path(&mnemonic);
+            let body = serde_json::to_vec_pretty(&backup)
+                .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+            let message = format!("Publish recovery backup for {}", backup.key_id);
+            put_file_contents(&client.0, &repo, &token, &path, &body, &message, None).await?;
+            Some(path)
+        }
+        (None, None) => None,
+        _ => return Err(AppError::Validation("Pass both repo and token, or neither".into())),
+    };
+
+    Ok(MnemonicExport {
+        mnemonic: mnemonic.to_string(),
+        keypair: register_keypair(keypair)?,
+        backup,
+        backup_path,
+    })
+}
+
+/// Restore an identity from its recovery phrase. The backup is taken from
+/// `backup` when given, otherwise fetched from `repo`.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn import_keypair_mnemonic(
+    client: State<'_, HttpClient>,
+    mnemonic: String,
+    backup: Option<MnemonicBackup>,
+    repo: Option<String>,
+    token: Option<Zeroizing<String>>,
+) -> Result<KeypairInfo, AppError> {
+    let mnemonic = {
+        let phrase = Zeroizing::new(mnemonic);
+        parse_mnemonic(&phrase)?
+    };
+
+    let backup = match (backup, repo, token) {
+        (Some(backup), _, _) => backup,
+        (None, Some(repo), Some(token)) => {
+            validate_repo(&repo)?;
+            let raw = fetch_file_bytes(&client.0, &repo, &token, &backup_path(&mnemonic)).await?;
+            serde_json::from_slice(&raw).map_err(|e| AppError::Validation(format!("Corrupt recovery backup: {}", e)))?
+        }
+        _ => {
+            return Err(AppError::Validation(
+                "Pass the recovery backup, or the repository it was published to".into(),
+            ))
+        }
+    };
+
+    let keypair = keypair_from_mnemonic(&mnemonic, &backup)?;
+    register_keypair(keypair).map_err(AppError::from)
+}
@@ -0,0 +1,334 @@
+//! Remote Change Notifications
+//!
+//! A desktop app has no public endpoint a GitHub webhook could reach, so
+//! remote changes are discovered by polling the repository events API.
+//! Requests are conditional (`If-None-Match`), which GitHub does not count
+//! against the rate limit, and the poll interval follows `X-Poll-Interval`.
+//!
+//! For every new push to the default branch the changed files are looked up
+//! with the compare API and mapped to album folders. Albums of interest are
+//! announced with a `remote-album-changed` event so the UI can refresh them.
+//! Pushes made by this device are announced too; refreshing is idempotent.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::album::{parent_album_path, ALBUM_ROOT};
+use crate::git_data::{default_branch, get_json};
+use crate::github::{response_error, validate_repo, AppError, Bearer, HttpClient};
+use crate::retry::SendWithRetry;
+use crate::util::now_secs;
+
+const DEFAULT_POLL_SECS: u64 = 60;
+const MIN_POLL_SECS: u64 = 15;
+const EVENTS_PER_PAGE: u32 = 30;
+
+lazy_static::lazy_static! {
+    static ref REMOTE_WATCHES: Mutex<HashMap<String, RemoteWatch>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoteWatchInfo {
+    pub repo: String,
+    /// Albums to report; empty means every album
+    pub album_paths: Vec<String>,
+    pub started_at: u64,
+    pub last_checked: Option<u64>,
+    pub last_event_id: Option<u64>,
+    pub changes_seen: u64,
+    pub last_error: Option<String>,
+}
+
+/// Payload of the `remote-album-changed` event
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoteAlbumChange {
+    pub repo: String,
+    pub albums: Vec<String>,
+    pub commit_sha: String,
+    pub actor: String,
+    pub pushed_at: String,
+}
+
+/// A push to the watched branch, as read from the events API
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PushInfo {
+    pub event_id: u64,
+    pub before: String,
+    pub head: String,
+    pub actor: String,
+    pub created_at: String,
+}
+
+struct RemoteWatch {
+    info: Arc<Mutex<RemoteWatchInfo>>,
+    stop: Arc<AtomicBool>,
+}
+
+// ============================================================================
+// Event Parsing
+// ============================================================================
+
+/// Push events to `branch_ref` newer than `after_id`, oldest first
+pub fn new_push_events(events: &[serde_json::Value], branch_ref: &str, after_id: u64) -> Vec<PushInfo> {
+    let mut pushes: Vec<PushInfo> = events
+        .iter()
+        .filter(|e| e["type"].as_str() == Some("PushEvent"))
+        .filter(|e| e["payload"]["ref"].as_str() == Some(branch_ref))
+        .filter_map(|e| {
+            Some(PushInfo {
+                event_id: e["id"].as_str()?.parse().ok()?,
+                before: e["payload"]["before"].as_str()?.to_string(),
+                head: e["payload"]["head"].as_str()?.to_string(),
+                actor: e["actor"]["login"].as_str().unwrap_or("").to_string(),
+                created_at: e["created_at"].as_str().unwrap_or("").to_string(),
+            })
+        })
+        .filter(|p| p.event_id > after_id)
+        .collect();
+    pushes.sort_by_key(|p| p.event_id);
+    pushes
+}
+
+/// Highest event id in a page, used as the baseline on the first poll
+pub fn latest_event_id(events: &[serde_json::Value]) -> Option<u64> {
+    events.iter().filter_map(|e| e["id"].as_str()?.parse().ok()).max()
+}
+
+/// Albums containing the changed paths, restricted to `filter` (and its
+/// sub-albums) when it is not empty
+pub fn changed_albums(paths: &[String], filter: &[String]) -> Vec<String> {
+    let root = format!("{}/", ALBUM_ROOT);
+    let albums: BTreeSet<String> = paths
+        .iter()
+        .filter(|p| p.starts_with(&root))
+        .map(|p| parent_album_path(p).to_string())
+        .filter(|album| album.starts_with(&root))
+        .filter(|album| {
+            filter.is_empty()
+                || filter
+                    .iter()
+                    .any(|f| album == f || album.starts_with(&format!("{}/", f)))
+        })
+        .collect();
+    albums.into_iter().collect()
+}
+
+// ============================================================================
+// Polling
+// ============================================================================
+
+enum Poll {
+    NotModified,
+    Events(Vec<serde_json::Value>),
+}
+
+async fn fetch_events(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    etag: &mut Option<String>,
+    interval: &mut Duration,
+) -> Result<Poll, AppError> {
+    let url = format!("https://api.github.com/repos/{}/events?per_page={}", repo, EVENTS_PER_PAGE);
+    let mut request = client
+        .get(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json");
+    if let Some(tag) = etag.as_deref() {
+        request = request.header("If-None-Match", tag);
+    }
+
+    let res = request.send_with_retry().await?;
+
+    if let Some(secs) = res
+        .headers()
+        .get("x-poll-interval")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok())
+    {
+        *interval = Duration::from_secs(secs.max(MIN_POLL_SECS));
+    }
+
+    if res.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Poll::NotModified);
+    }
+    if !res.status().is_success() {
+        return Err(response_error(res, "Failed to poll repository events").await);
+    }
+
+    *etag = res
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    let events: Vec<serde_json::Value> = res.json().await?;
+    Ok(Poll::Events(events))
+}
+
+async fn changed_paths(client: &Client, repo: &str, token: &str, push: &PushInfo) -> Result<Vec<String>, AppError> {
+    let url = format!("https://api.github.com/repos/{}/compare/{}...{}", repo, push.before, push.head);
+    let json = get_json(client, token, &url, "compare commits").await?;
+
+    Ok(json["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|f| [f["filename"].as_str(), f["previous_filename"].as_str()])
+        .flatten()
+        .map(|s| s.to_string())
+        .collect())
+}
+
+fn spawn_poll_loop(app: AppHandle, token: Zeroizing<String>, info: Arc<Mutex<RemoteWatchInfo>>, stop: Arc<AtomicBool>) {
+    tauri::async_runtime::spawn(async move {
+        let client = app.state::<HttpClient>().0.clone();
+        let (repo, filter) = {
+            let info = info.lock().unwrap();
+            (info.repo.clone(), info.album_paths.clone())
+        };
+        let mut etag = None;
+        let mut interval = Duration::from_secs(DEFAULT_POLL_SECS);
+        let mut branch_ref = None;
+
+        while !stop.load(Ordering::Relaxed) {
+            let result = async {
+                if branch_ref.is_none() {
+                    branch_ref = Some(format!("refs/heads/{}", default_branch(&client, &repo, &token).await?));
+                }
+                let branch_ref = branch_ref.as_deref().unwrap_or_default();
+
+                let Poll::Events(events) = fetch_events(&client, &repo, &token, &mut etag, &mut interval).await?
+                else {
+                    return Ok(());
+                };
+
+                let last_seen = info.lock().unwrap().last_event_id;
+                let Some(after) = last_seen else {
+                    // First page only sets the baseline; history is not replayed
+                    info.lock().unwrap().last_event_id = latest_event_id(&events);
+                    return Ok(());
+                };
+
+                for push in new_push_events(&events, branch_ref, after) {
+                    let paths = changed_paths(&client, &repo, &token, &push).await?;
+                    let albums = changed_albums(&paths, &filter);
+                    {
+                        let mut info = info.lock().unwrap();
+                        info.last_event_id = Some(push.event_id);
+                        if !albums.is_empty() {
+                            info.changes_seen += 1;
+                        }
+                    }
+                    if !albums.is_empty() {
+                        let _ = app.emit(
+                            "remote-album-changed",
+                            RemoteAlbumChange {
+                                repo: repo.clone(),
+                                albums,
+                                commit_sha: push.head.clone(),
+                                actor: push.actor.clone(),
+                                pushed_at: push.created_at.clone(),
+                            },
+                        );
+                    }
+                }
+                Ok::<(), AppError>(())
+            }
+            .await;
+
+            {
+                let mut info = info.lock().unwrap();
+                info.last_checked = Some(now_secs());
+                info.last_error = result.err().map(|e| e.to_string());
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Start polling a repository for remote changes. Restarting an existing
+/// watch replaces its album filter.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn start_remote_watch(
+    app: AppHandle,
+    repo: String,
+    token: Zeroizing<String>,
+    album_paths: Option<Vec<String>>,
+) -> Result<RemoteWatchInfo, AppError> {
+    validate_repo(&repo)?;
+
+    let album_paths: Vec<String> = album_paths
+        .unwrap_or_default()
+        .iter()
+        .map(|p| p.trim_matches('/').to_string())
+        .collect();
+    if album_paths.iter().any(|p| p.is_empty() || p.contains("..")) {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    let info = RemoteWatchInfo {
+        repo: repo.clone(),
+        album_paths,
+        started_at: now_secs(),
+        last_checked: None,
+        last_event_id: None,
+        changes_seen: 0,
+        last_error: None,
+    };
+    let watch = RemoteWatch {
+        info: Arc::new(Mutex::new(info.clone())),
+        stop: Arc::new(AtomicBool::new(false)),
+    };
+    spawn_poll_loop(app, token, watch.info.clone(), watch.stop.clone());
+
+    if let Some(previous) = REMOTE_WATCHES.lock().unwrap().insert(repo, watch) {
+        previous.stop.store(true, Ordering::Relaxed);
+    }
+    Ok(info)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn stop_remote_watch(repo: String) -> Result<(), AppError> {
+    let watch = REMOTE_WATCHES
+        .lock()
+        .unwrap()
+        .remove(&repo)
+        .ok_or_else(|| AppError::Validation("Repository is not being watched".into()))?;
+    watch.stop.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop every remote watch, after a profile switch
+pub(crate) fn stop_all_remote_watches() {
+    for (_, watch) in REMOTE_WATCHES.lock().unwrap().drain() {
+        watch.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn list_remote_watches() -> Vec<RemoteWatchInfo> {
+    let mut watches: Vec<RemoteWatchInfo> = REMOTE_WATCHES
+        .lock()
+        .unwrap()
+        .values()
+        .map(|w| w.info.lock().unwrap().clone())
+        .collect();
+    watches.sort_by(|a, b| a.repo.cmp(&b.repo));
+    watches
+}
@@ -0,0 +1,267 @@
+//! Importing Existing Repositories
+//!
+//! `import_repo_as_album` turns photos that were pushed to a repository
+//! without Vortex into albums: every folder holding images, RAW files or
+//! videos gets a plain album manifest, and its photos are recorded in the
+//! catalog (see `catalog`) so they show up in search, the timeline and
+//! smart albums straight away.
+//!
+//! With `normalize`, folders are also moved below `photos/`, where
+//! `list_albums` finds them: `2019/Summer` becomes `photos/2019/Summer`
+//! and loose photos at the root go to an album named after the repository.
+//! Moves reuse the blobs, so nothing is uploaded again, and everything lands
+//! in a single commit. Without it, albums stay where they are and are
+//! opened by path.
+//!
+//! Folders that already have a manifest are left alone, as are hidden
+//! folders and encrypted blobs, which cannot be opened without theirs.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::album::{album_path_for, manifest_body, AlbumManifest, ALBUM_MANIFEST_FILE, ALBUM_ROOT, ENCRYPTED_BLOB_EXT};
+use crate::catalog::reconcile_listing;
+use crate::crypto::KeypairHandle;
+use crate::git_data::{
+    branch_head, commit_changes, create_blob, get_tree_recursive, index_blobs, TreeChange, TreeEntry, TreeIndex,
+};
+use crate::github::{is_photo_file, validate_repo, AppError, HttpClient, PhotoItem};
+use crate::mirror::replicate_tree_changes;
+use crate::security_verify::move_signature;
+use crate::smart_albums::notify_smart_albums;
+use crate::video::{is_video_file, MediaType};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportedAlbum {
+    /// Album path after the import
+    pub path: String,
+    /// Folder the photos were found in, empty for the repository root
+    pub source: String,
+    pub photos: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedPhoto {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RepoImport {
+    pub albums: Vec<ImportedAlbum>,
+    pub skipped: Vec<SkippedPhoto>,
+    /// `None` when there was nothing to import
+    pub commit_sha: Option<String>,
+}
+
+/// An album to create, with its photos at their final paths
+#[derive(Clone, Debug)]
+pub struct PlannedAlbum {
+    pub path: String,
+    pub source: String,
+    pub photos: Vec<TreeEntry>,
+}
+
+/// What importing a tree takes: albums to create and, when normalizing,
+/// the moves into `photos/`
+#[derive(Clone, Debug, Default)]
+pub struct ImportPlan {
+    pub albums: Vec<PlannedAlbum>,
+    pub moves: Vec<TreeChange>,
+    pub skipped: Vec<SkippedPhoto>,
+}
+
+impl ImportPlan {
+    fn skip(&mut self, path: &str, reason: &str) {
+        self.skipped.push(SkippedPhoto { path: path.to_string(), reason: reason.to_string() });
+    }
+}
+
+fn is_media(path: &str) -> bool {
+    let path = Path::new(path);
+    is_photo_file(path) || is_video_file(path)
+}
+
+fn folder_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(folder, _)| folder)
+}
+
+fn manifest_path(album: &str) -> String {
+    format!("{}/{}", album, ALBUM_MANIFEST_FILE)
+}
+
+fn is_under_album_root(folder: &str) -> bool {
+    folder == ALBUM_ROOT || folder.starts_with(&format!("{}/", ALBUM_ROOT))
+}
+
+/// Where the photos of `folder` go
+fn target_album(folder: &str, repo_name: &str, normalize: bool) -> Result<String, AppError> {
+    match (normalize, folder) {
+        (false, "") => Err(AppError::Validation(
+            "Photos at the repository root can only be imported when normalizing".into(),
+        )),
+        (false, _) => Ok(folder.to_string()),
+        (true, "") => album_path_for(ALBUM_ROOT, repo_name),
+        (true, _) if is_under_album_root(folder) => Ok(folder.to_string()),
+        (true, _) => album_path_for(ALBUM_ROOT, folder),
+    }
+}
+
+/// Plan the import of the tree `index` of the repository `repo_name`
+pub fn plan_import(index: &TreeIndex, repo_name: &str, normalize: bool) -> ImportPlan {
+    let mut plan = ImportPlan::default();
+    let mut folders: BTreeMap<&str, Vec<&TreeEntry>> = BTreeMap::new();
+    for entry in index.values() {
+        let hidden = entry.path.split('/').any(|part| part.starts_with('.'));
+        if hidden || entry.path.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) || !is_media(&entry.path) {
+            continue;
+        }
+        let folder = folder_of(&entry.path);
+        // Loose photos in `photos/` are listed already
+        if folder != ALBUM_ROOT && !index.contains_key(&manifest_path(folder)) {
+            folders.entry(folder).or_default().push(entry);
+        }
+    }
+
+    let mut claimed: HashSet<String> = HashSet::new();
+    for (folder, mut entries) in folders {
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let target = match target_album(folder, repo_name, normalize) {
+            Ok(target) if target != folder && index.contains_key(&manifest_path(&target)) => {
+                Err(AppError::Validation(format!("{} is already an album", target)))
+            }
+            other => other,
+        };
+        let target = match target {
+            Ok(target) => target,
+            Err(e) => {
+                for entry in entries {
+                    plan.skip(&entry.path, &e.to_string());
+                }
+                continue;
+            }
+        };
+
+        let mut photos = Vec::new();
+        for entry in entries {
+            if target == folder {
+                photos.push(entry.clone());
+                continue;
+            }
+            let name = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+            let new_path = format!("{}/{}", target, name);
+            if claimed.contains(&new_path) || index.get(&new_path).is_some_and(|e| e.sha != entry.sha) {
+                plan.skip(&entry.path, "Destination already exists");
+                continue;
+            }
+            // The same photo is already in place: only the copy goes
+            if !index.contains_key(&new_path) {
+                plan.moves.push(TreeChange::put_blob(&new_path, entry));
+                plan.moves.extend(move_signature(index, &entry.path, &new_path));
+            }
+            plan.moves.push(TreeChange::delete(&entry.path));
+            claimed.insert(new_path.clone());
+            photos.push(TreeEntry { path: new_path, ..entry.clone() });
+        }
+
+        if photos.is_empty() {
+            continue;
+        }
+        // Folders that normalize to the same album are merged
+        match plan.albums.iter_mut().find(|a| a.path == target) {
+            Some(album) => album.photos.extend(photos),
+            None => plan.albums.push(PlannedAlbum { path: target, source: folder.to_string(), photos }),
+        }
+    }
+    plan.albums.sort_by(|a, b| a.path.cmp(&b.path));
+    plan
+}
+
+/// How the catalog lists the photos of `album` as of `commit_sha`
+pub fn listed_photos(repo: &str, commit_sha: &str, album: &PlannedAlbum) -> Vec<PhotoItem> {
+    album
+        .photos
+        .iter()
+        .map(|entry| {
+            let name = entry.path.rsplit('/').next().unwrap_or(&entry.path).to_string();
+            PhotoItem {
+                url: format!("https://raw.githubusercontent.com/{}/{}/{}", repo, commit_sha, entry.path),
+                sha: entry.sha.clone(),
+                size: entry.size,
+                media_type: MediaType::of(&name),
+                name,
+                encrypted: false,
+                display_name: None,
+                repo: None,
+                caption: None,
+                exif: None,
+                raw_companion: None,
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Turn the photo folders of `repo` into albums in one commit, moving them
+/// below `photos/` when `normalize` is set
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn import_repo_as_album(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    normalize: Option<bool>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<RepoImport, AppError> {
+    validate_repo(&repo)?;
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let repo_name = repo.rsplit('/').next().unwrap_or(&repo);
+    let plan = plan_import(&index, repo_name, normalize.unwrap_or(false));
+
+    let mut result = RepoImport {
+        albums: plan
+            .albums
+            .iter()
+            .map(|a| ImportedAlbum { path: a.path.clone(), source: a.source.clone(), photos: a.photos.len() })
+            .collect(),
+        skipped: plan.skipped.clone(),
+        commit_sha: None,
+    };
+    if plan.albums.is_empty() {
+        return Ok(result);
+    }
+
+    let mut changes = plan.moves.clone();
+    for album in &plan.albums {
+        let mut manifest = AlbumManifest::new(false, None);
+        let sha = create_blob(&client.0, &repo, &token, &manifest_body(&mut manifest, keypair_handle)?).await?;
+        changes.push(TreeChange::blob(&manifest_path(&album.path), &sha));
+    }
+
+    let photos: usize = result.albums.iter().map(|a| a.photos).sum();
+    let message = format!(
+        "Import {} photo{} into {} album{}",
+        photos,
+        if photos == 1 { "" } else { "s" },
+        plan.albums.len(),
+        if plan.albums.len() == 1 { "" } else { "s" }
+    );
+    let commit = commit_changes(&client.0, &repo, &token, &head, &changes, &message).await?;
+    replicate_tree_changes(&client.0, &repo, &token, &changes);
+
+    for album in &plan.albums {
+        reconcile_listing(&repo, &album.path, &listed_photos(&repo, &commit, album));
+    }
+    notify_smart_albums(&app, &repo);
+    result.commit_sha = Some(commit);
+    Ok(result)
+}
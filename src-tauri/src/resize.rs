@@ -0,0 +1,256 @@
+//! Image Resizing
+//!
+//! Scales photos to a maximum long edge, exact dimensions or a percentage
+//! with a Lanczos filter, for web-sized copies. `max_edge` never upscales:
+//! a photo that already fits is kept as it is. EXIF orientation is applied
+//! to the pixels first, since the output carries no EXIF. PNG and WebP stay
+//! in their format; everything else becomes JPEG.
+//!
+//! As a pipeline step the resized image replaces the input. `keep_original`
+//! has folder jobs write the original next to the output, and
+//! `originals_album` has `upload_folder_recursive` upload it to a separate
+//! album, so the full-size photo is not lost.
+
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use crate::compress_stream::write_via_partial;
+use crate::github::{sanitize_filename, AppError};
+use crate::transcode::{decode_upright, encode_jpeg, encode_webp};
+
+const DEFAULT_QUALITY: u8 = 85;
+/// Largest side accepted for `max_edge` and `exact`
+pub const MAX_DIMENSION: u32 = 16384;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeMode {
+    /// Fit the longer side to `max_edge`
+    MaxEdge,
+    /// Scale to `width` × `height`, ignoring the aspect ratio
+    Exact,
+    /// Scale both sides by `percent`
+    Percent,
+}
+
+fn default_quality() -> u8 {
+    DEFAULT_QUALITY
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResizeOptions {
+    pub mode: ResizeMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_edge: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// 1 to 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u32>,
+    /// 1 to 100, for JPEG and WebP output
+    #[serde(default = "default_quality")]
+    pub quality: u8,
+    /// Write the original next to the output of folder jobs
+    #[serde(default)]
+    pub keep_original: bool,
+    /// Album under `photos/` that routed uploads send the original to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub originals_album: Option<String>,
+}
+
+impl ResizeOptions {
+    pub fn max_edge(max_edge: u32) -> Self {
+        Self {
+            mode: ResizeMode::MaxEdge,
+            max_edge: Some(max_edge),
+            width: None,
+            height: None,
+            percent: None,
+            quality: DEFAULT_QUALITY,
+            keep_original: false,
+            originals_album: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResizeResult {
+    pub width: u32,
+    pub height: u32,
+    pub original_width: u32,
+    pub original_height: u32,
+    pub original_size: u64,
+    pub resized_size: u64,
+    /// False when the photo already fit and was kept
+    pub resized: bool,
+    /// jpeg, png or webp; the input's format when it was kept
+    pub format: String,
+    /// Where the resized copy was written, for `resize_image`
+    pub output_path: Option<String>,
+}
+
+pub(crate) fn validate_options(options: &ResizeOptions) -> Result<(), AppError> {
+    if !(1..=100).contains(&options.quality) {
+        return Err(AppError::Validation("Quality must be 1-100".into()));
+    }
+    let side = |value: Option<u32>, name: &str| match value {
+        Some(v) if (1..=MAX_DIMENSION).contains(&v) => Ok(()),
+        _ => Err(AppError::Validation(format!("{} must be 1-{}", name, MAX_DIMENSION))),
+    };
+    match options.mode {
+        ResizeMode::MaxEdge => side(options.max_edge, "Maximum edge")?,
+        ResizeMode::Exact => {
+            side(options.width, "Width")?;
+            side(options.height, "Height")?;
+        }
+        ResizeMode::Percent => {
+            if !options.percent.is_some_and(|p| (1..=100).contains(&p)) {
+                return Err(AppError::Validation("Percent must be 1-100".into()));
+            }
+        }
+    }
+    if let Some(album) = &options.originals_album {
+        if album.is_empty() || sanitize_filename(album) != *album {
+            return Err(AppError::Validation("Invalid originals album name".into()));
+        }
+    }
+    Ok(())
+}
+
+/// Output size for an upright `width` × `height` image
+pub fn target_size(width: u32, height: u32, options: &ResizeOptions) -> (u32, u32) {
+    let scale = |side: u32, factor: f64| ((side as f64 * factor).round() as u32).max(1);
+    match options.mode {
+        ResizeMode::MaxEdge => {
+            let max_edge = options.max_edge.unwrap_or(u32::MAX);
+            let longer = width.max(height);
+            if longer <= max_edge {
+                return (width, height);
+            }
+            let factor = max_edge as f64 / longer as f64;
+            (scale(width, factor), scale(height, factor))
+        }
+        ResizeMode::Exact => (options.width.unwrap_or(width), options.height.unwrap_or(height)),
+        ResizeMode::Percent => {
+            let factor = options.percent.unwrap_or(100) as f64 / 100.0;
+            (scale(width, factor), scale(height, factor))
+        }
+    }
+}
+
+/// Format that edited copies of an image in `input` are written in: PNG
+/// and WebP stay as they are, everything else becomes JPEG
+pub(crate) fn output_format(input: Option<ImageFormat>) -> ImageFormat {
+    match input {
+        Some(f @ (ImageFormat::Png | ImageFormat::WebP)) => f,
+        _ => ImageFormat::Jpeg,
+    }
+}
+
+pub(crate) fn encode(image: &DynamicImage, format: ImageFormat, quality: u8) -> Result<Vec<u8>, AppError> {
+    match format {
+        ImageFormat::Png => {
+            let mut out = Cursor::new(Vec::new());
+            image
+                .write_to(&mut out, ImageFormat::Png)
+                .map_err(|e| AppError::Validation(format!("PNG encoding failed: {}", e)))?;
+            Ok(out.into_inner())
+        }
+        ImageFormat::WebP => encode_webp(image, quality),
+        _ => encode_jpeg(image, quality),
+    }
+}
+
+pub(crate) fn format_name(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "png",
+        ImageFormat::WebP => "webp",
+        _ => "jpeg",
+    }
+}
+
+/// Resize `data`, returning the bytes to keep and what happened. Fails only
+/// if the input cannot be decoded or the encoder fails.
+pub fn resize_image_data(data: &[u8], options: &ResizeOptions) -> Result<(Vec<u8>, ResizeResult), AppError> {
+    validate_options(options)?;
+    let image = decode_upright(data)?;
+    let (original_width, original_height) = (image.width(), image.height());
+    let (width, height) = target_size(original_width, original_height, options);
+    let input_format = image::guess_format(data).ok();
+
+    let mut result = ResizeResult {
+        width,
+        height,
+        original_width,
+        original_height,
+        original_size: data.len() as u64,
+        resized_size: data.len() as u64,
+        resized: false,
+        format: input_format.map_or("jpeg", format_name).to_string(),
+        output_path: None,
+    };
+    if (width, height) == (original_width, original_height) {
+        return Ok((data.to_vec(), result));
+    }
+
+    let format = output_format(input_format);
+    let output = encode(&image.resize_exact(width, height, FilterType::Lanczos3), format, options.quality)?;
+    result.resized = true;
+    result.resized_size = output.len() as u64;
+    result.format = format_name(format).to_string();
+    Ok((output, result))
+}
+
+/// `<stem>-<width>x<height>.<ext>` next to `input`
+pub fn resized_path(input: &Path, width: u32, height: u32, format: &str) -> PathBuf {
+    let stem = input.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let ext = if format == "jpeg" { "jpg" } else { format };
+    input.with_file_name(format!("{}-{}x{}.{}", stem, width, height, ext))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Write a resized copy of the image at `input_path` to `output_path`, or
+/// next to it (see `resized_path`). The original is kept, and nothing is
+/// written when it already fits.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn resize_image(
+    input_path: String,
+    output_path: Option<String>,
+    options: ResizeOptions,
+) -> Result<ResizeResult, AppError> {
+    validate_options(&options)?;
+
+    tokio::task::spawn_blocking(move || {
+        let input = PathBuf::from(&input_path);
+        let data = std::fs::read(&input)?;
+        let (output, mut result) = resize_image_data(&data, &options)?;
+        if !result.resized {
+            return Ok(result);
+        }
+
+        let target = output_path
+            .map(PathBuf::from)
+            .unwrap_or_else(|| resized_path(&input, result.width, result.height, &result.format));
+        if target == input {
+            return Err(AppError::Validation("Output would overwrite the original".into()));
+        }
+        write_via_partial(&target, |mut writer| {
+            writer.write_all(&output)?;
+            writer.flush()?;
+            Ok(())
+        })?;
+        result.output_path = Some(target.to_string_lossy().to_string());
+        Ok(result)
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Resize task failed: {}", e)))?
+}
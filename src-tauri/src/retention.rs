@@ -0,0 +1,482 @@
+//! Retention Policies
+//!
+//! An album can have a retention policy such as "photos older than two
+//! years move to a cold archive repository". Photos captured more than
+//! `archive_after_days` ago are copied to `archive_repo` under the same
+//! path, compressed with XZ at its highest level when that makes them
+//! smaller (the archived file then gets an `.xz` suffix), and removed from
+//! the album: one commit in the archive, then one per source repository,
+//! as `rebalance_shards` moves albums. The source commit also removes the
+//! photos' `.vxsig` files and their manifest entries; runs have no keypair,
+//! so an edited manifest is saved unsigned.
+//!
+//! Ages come from capture times in the catalog (see `catalog`), so an album
+//! is only evaluated once it has been listed. Photos without a capture
+//! time, encrypted photos (their payloads are bound to the repository) and,
+//! with `keep_favorites`, favorites stay where they are.
+//!
+//! A background task runs every enabled policy each `RUN_INTERVAL_SECS`,
+//! unless an album schedule runs it instead (see `sync_schedule`);
+//! `preview_retention` shows what the next run would move and
+//! `run_retention` runs one at once. Policies live in
+//! `<profile data>/retention.json`, and the token of each is kept in the OS
+//! keychain so the task keeps working after a restart.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::album::{manifest_body, parent_album_path, AlbumManifest, ALBUM_MANIFEST_FILE};
+use crate::catalog::{album_photos_in, forget_photo, CatalogPhoto};
+use crate::compress::xz_compress;
+use crate::config_store::{ConfigList, TokenSlot};
+use crate::git_data::{
+    branch_head, commit_changes, create_blob, get_blob, get_json, get_tree_recursive, index_blobs, TreeChange,
+};
+use crate::github::{validate_repo, AppError, HttpClient};
+use crate::local_store::with_store;
+use crate::mirror::replicate_tree_changes;
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::security_verify::signature_path;
+use crate::sharing::album_id;
+use crate::sync_schedule::{is_scheduled, ScheduledTask};
+use crate::util::{default_true, now_secs};
+
+const RETENTION_FILE: &str = "retention.json";
+/// Emitted with a `RetentionRun` after each background run that moved photos
+pub const RETENTION_RUN_EVENT: &str = "retention-run";
+pub const RUN_INTERVAL_SECS: u64 = 6 * 60 * 60;
+/// Delay before the first background run, so startup is not slowed down
+const FIRST_RUN_DELAY_SECS: u64 = 10 * 60;
+const XZ_MAX_LEVEL: i32 = 9;
+pub const ARCHIVE_SUFFIX: &str = "xz";
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+static POLICIES: ConfigList<RetentionPolicy> = ConfigList::new(RETENTION_FILE, "retention policies");
+static POLICY_TOKENS: TokenSlot = TokenSlot::new("retention-token", "retention");
+
+lazy_static::lazy_static! {
+    static ref LAST_RUNS: Mutex<HashMap<String, RetentionRun>> = Mutex::new(HashMap::new());
+}
+
+/// When the background task next runs, Unix seconds
+static NEXT_RUN_AT: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub repo: String,
+    pub album_path: String,
+    /// Repository photos are archived to
+    pub archive_repo: String,
+    /// Photos captured longer ago than this are archived
+    pub archive_after_days: u32,
+    /// Compress archived photos with XZ where it helps
+    #[serde(default = "default_true")]
+    pub compress: bool,
+    #[serde(default)]
+    pub keep_favorites: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl RetentionPolicy {
+    pub fn id(&self) -> String {
+        album_id(&self.repo, &self.album_path)
+    }
+}
+
+/// A photo the policy archives
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionCandidate {
+    /// Repository holding the photo, the album's repo or one of its shards
+    pub repo: String,
+    pub path: String,
+    pub sha: String,
+    pub taken_at: i64,
+    pub size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPlan {
+    /// Photos captured before this are archived, Unix seconds
+    pub cutoff: i64,
+    pub candidates: Vec<RetentionCandidate>,
+    /// Old favorites kept by `keep_favorites`
+    pub kept_favorites: usize,
+    pub kept_encrypted: usize,
+    /// Photos without a capture time
+    pub undated: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetentionPreview {
+    pub policy: RetentionPolicy,
+    pub plan: RetentionPlan,
+    /// Known size of the photos to archive
+    pub total_bytes: u64,
+    /// When the background task runs next, if it is running
+    pub next_run_at: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionRun {
+    pub album_path: String,
+    pub at: u64,
+    pub archived: usize,
+    /// Size of the archived photos before and after compression
+    pub original_bytes: u64,
+    pub stored_bytes: u64,
+    /// Candidates whose file changed or went away since the catalog saw it
+    pub skipped: usize,
+    /// Why the run failed; nothing was archived then
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetentionInfo {
+    pub policy: RetentionPolicy,
+    pub last_run: Option<RetentionRun>,
+}
+
+/// Photos of `photos` that `policy` archives at `now` (Unix seconds)
+pub fn plan_retention(policy: &RetentionPolicy, photos: &[CatalogPhoto], now: i64) -> RetentionPlan {
+    let cutoff = now - i64::from(policy.archive_after_days) * SECS_PER_DAY;
+    let mut plan = RetentionPlan {
+        cutoff,
+        ..Default::default()
+    };
+    for photo in photos {
+        let Some(taken_at) = photo.taken_at else {
+            plan.undated += 1;
+            continue;
+        };
+        if taken_at >= cutoff {
+            continue;
+        }
+        if photo.item.encrypted {
+            plan.kept_encrypted += 1;
+        } else if policy.keep_favorites && photo.favorite {
+            plan.kept_favorites += 1;
+        } else {
+            plan.candidates.push(RetentionCandidate {
+                repo: photo.item.repo.clone().unwrap_or_else(|| photo.repo.clone()),
+                path: photo.path.clone(),
+                sha: photo.item.sha.clone(),
+                taken_at,
+                size: photo.item.size,
+            });
+        }
+    }
+    plan.candidates.sort_by(|a, b| a.taken_at.cmp(&b.taken_at).then_with(|| a.path.cmp(&b.path)));
+    plan
+}
+
+/// `content` as stored in the archive, with its path there. Compressed
+/// only when `compress` is set and XZ makes it smaller.
+pub fn archive_content(path: &str, content: Vec<u8>, compress: bool) -> Result<(String, Vec<u8>), AppError> {
+    if compress {
+        let packed = xz_compress(&content, XZ_MAX_LEVEL).map_err(|e| AppError::Validation(e.to_string()))?;
+        if packed.len() < content.len() {
+            return Ok((format!("{}.{}", path, ARCHIVE_SUFFIX), packed));
+        }
+    }
+    Ok((path.to_string(), content))
+}
+
+pub fn validate_policy(policy: &RetentionPolicy) -> Result<(), AppError> {
+    validate_repo(&policy.repo)?;
+    validate_repo(&policy.archive_repo)?;
+    if policy.album_path.is_empty() || policy.album_path.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    if policy.archive_repo == policy.repo {
+        return Err(AppError::Validation("The archive must be another repository".into()));
+    }
+    if policy.archive_after_days == 0 {
+        return Err(AppError::Validation("Photos must be at least a day old to archive".into()));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+/// Reload the policies from disk on next use, after a profile switch
+pub(crate) fn forget_cached_policies() {
+    POLICIES.forget();
+    LAST_RUNS.lock().unwrap().clear();
+}
+
+fn find_policy(repo: &str, album_path: &str) -> Result<RetentionPolicy, AppError> {
+    let id = album_id(repo, album_path.trim_matches('/'));
+    POLICIES.with(|policies| policies.iter().find(|p| p.id() == id).cloned())?
+        .ok_or_else(|| AppError::Validation(format!("No retention policy for {}", album_path)))
+}
+
+// ============================================================================
+// Runs
+// ============================================================================
+
+fn plan_for(policy: &RetentionPolicy) -> Result<RetentionPlan, AppError> {
+    let photos = with_store(|store| album_photos_in(store, &policy.repo, &policy.album_path))?.ok_or_else(|| {
+        AppError::Validation(format!("List {} first; retention works from the catalog", policy.album_path))
+    })?;
+    Ok(plan_retention(policy, &photos, now_secs() as i64))
+}
+
+/// Archive what `plan` lists: copy to the archive in one commit, then
+/// remove from each source repository in one commit
+async fn apply_retention(
+    client: &Client,
+    token: &str,
+    policy: &RetentionPolicy,
+    plan: &RetentionPlan,
+) -> Result<RetentionRun, AppError> {
+    let mut run = RetentionRun {
+        album_path: policy.album_path.clone(),
+        at: now_secs(),
+        ..Default::default()
+    };
+    let mut by_repo: BTreeMap<&str, Vec<&RetentionCandidate>> = BTreeMap::new();
+    for candidate in &plan.candidates {
+        by_repo.entry(&candidate.repo).or_default().push(candidate);
+    }
+
+    let mut additions = Vec::new();
+    let mut archived: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (repo, candidates) in by_repo {
+        let head = branch_head(client, repo, token).await?;
+        let tree = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+        for candidate in candidates {
+            // The catalog may be behind: only archive the blob it listed
+            if tree.get(&candidate.path).map(|e| e.sha.as_str()) != Some(candidate.sha.as_str()) {
+                run.skipped += 1;
+                continue;
+            }
+            let content = get_blob(client, repo, token, &candidate.sha).await?;
+            run.original_bytes += content.len() as u64;
+            let compress = policy.compress;
+            let path = candidate.path.clone();
+            let (archived_path, stored) = tokio::task::spawn_blocking(move || archive_content(&path, content, compress))
+                .await
+                .map_err(|e| AppError::Validation(format!("Compression task failed: {}", e)))??;
+            run.stored_bytes += stored.len() as u64;
+            let sha = create_blob(client, &policy.archive_repo, token, &stored).await?;
+            additions.push(TreeChange::blob(&archived_path, &sha));
+            archived.entry(repo).or_default().push(&candidate.path);
+            run.archived += 1;
+        }
+    }
+    if additions.is_empty() {
+        return Ok(run);
+    }
+
+    let message = format!("Archive {} photos of {} from {}", additions.len(), policy.album_path, policy.repo);
+    let archive_head = branch_head(client, &policy.archive_repo, token).await?;
+    commit_changes(client, &policy.archive_repo, token, &archive_head, &additions, &message).await?;
+
+    for (repo, paths) in archived {
+        let message = format!(
+            "Move {} photos of {} to the archive {}",
+            paths.len(),
+            policy.album_path,
+            policy.archive_repo
+        );
+        let head = branch_head(client, repo, token).await?;
+        let tree = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+        let mut changes = Vec::new();
+        let mut albums: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for path in &paths {
+            changes.push(TreeChange::delete(path));
+            if tree.contains_key(&signature_path(path)) {
+                changes.push(TreeChange::delete(&signature_path(path)));
+            }
+            let name = path.rsplit('/').next().unwrap_or(path);
+            albums.entry(parent_album_path(path)).or_default().push(name);
+        }
+        for (album, names) in albums {
+            let manifest_path = format!("{}/{}", album, ALBUM_MANIFEST_FILE);
+            let Some(entry) = tree.get(&manifest_path) else {
+                continue;
+            };
+            let raw = get_blob(client, repo, token, &entry.sha).await?;
+            let mut manifest: AlbumManifest = serde_json::from_slice(&raw)
+                .map_err(|e| AppError::Validation(format!("Invalid album manifest: {}", e)))?;
+            let mut changed = false;
+            for name in names {
+                changed |= manifest.forget_file(name);
+            }
+            if changed {
+                let sha = create_blob(client, repo, token, &manifest_body(&mut manifest, None)?).await?;
+                changes.push(TreeChange::blob(&manifest_path, &sha));
+            }
+        }
+
+        commit_changes(client, repo, token, &head, &changes, &message).await?;
+        replicate_tree_changes(client, repo, token, &changes);
+        for path in paths {
+            forget_photo(repo, path);
+        }
+    }
+
+    let detail = format!(
+        "{} photos to {}, {} of {} bytes stored",
+        run.archived, policy.archive_repo, run.stored_bytes, run.original_bytes
+    );
+    record_activity(ActivityKind::Move, "run_retention", Some(&policy.repo), &policy.album_path, Some(detail));
+    Ok(run)
+}
+
+/// Plan and apply `policy`, remembering the outcome as its last run
+async fn run_policy(client: &Client, policy: &RetentionPolicy) -> Result<RetentionRun, AppError> {
+    let result = async {
+        let token = POLICY_TOKENS.load(&policy.id())?;
+        let plan = plan_for(policy)?;
+        apply_retention(client, &token, policy, &plan).await
+    }
+    .await;
+    let run = match &result {
+        Ok(run) => run.clone(),
+        Err(e) => RetentionRun {
+            album_path: policy.album_path.clone(),
+            at: now_secs(),
+            error: Some(e.to_string()),
+            ..Default::default()
+        },
+    };
+    LAST_RUNS.lock().unwrap().insert(policy.id(), run);
+    result
+}
+
+/// Run the policy of an album, for `run_retention` and scheduled runs (see
+/// `sync_schedule`)
+pub(crate) async fn run_album_retention(client: &Client, repo: &str, album_path: &str) -> Result<RetentionRun, AppError> {
+    let policy = find_policy(repo, album_path)?;
+    run_policy(client, &policy).await
+}
+
+/// Run every enabled policy each `RUN_INTERVAL_SECS`, except those an album
+/// schedule runs
+pub fn start_retention_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut delay = FIRST_RUN_DELAY_SECS;
+        loop {
+            NEXT_RUN_AT.store(now_secs() + delay, Ordering::Relaxed);
+            tokio::time::sleep(Duration::from_secs(delay)).await;
+            delay = RUN_INTERVAL_SECS;
+
+            let policies = match POLICIES.with(|policies| policies.to_vec()) {
+                Ok(policies) => policies,
+                Err(e) => {
+                    tracing::warn!("Could not load retention policies: {}", e);
+                    continue;
+                }
+            };
+            let client = app.state::<HttpClient>().0.clone();
+            let unscheduled = policies
+                .iter()
+                .filter(|p| p.enabled && !is_scheduled(&p.repo, &p.album_path, ScheduledTask::Retention));
+            for policy in unscheduled {
+                let _permit = SCHEDULER.acquire(JobClass::Background).await;
+                match run_policy(&client, policy).await {
+                    Ok(run) if run.archived > 0 => {
+                        let _ = app.emit(RETENTION_RUN_EVENT, &run);
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Retention of {} failed: {}", policy.album_path, e),
+                }
+            }
+        }
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Set (or replace) the retention policy of an album. `token` must reach
+/// both repositories and is stored in the OS keychain.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_retention_policy(
+    client: State<'_, HttpClient>,
+    token: Zeroizing<String>,
+    mut policy: RetentionPolicy,
+) -> Result<RetentionPolicy, AppError> {
+    policy.album_path = policy.album_path.trim_matches('/').to_string();
+    validate_policy(&policy)?;
+
+    // Fail early on a wrong token or archive name
+    let url = format!("https://api.github.com/repos/{}", policy.archive_repo);
+    get_json(&client.0, &token, &url, "get archive repository").await?;
+
+    POLICY_TOKENS.store(&policy.id(), &token)?;
+    POLICIES.update(|policies| {
+        policies.retain(|p| p.id() != policy.id());
+        policies.push(policy.clone());
+    })?;
+    Ok(policy)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn remove_retention_policy(repo: String, album_path: String) -> Result<bool, AppError> {
+    let id = album_id(&repo, album_path.trim_matches('/'));
+    let removed = POLICIES.update(|policies| {
+        let before = policies.len();
+        policies.retain(|p| p.id() != id);
+        policies.len() != before
+    })?;
+    POLICY_TOKENS.delete(&id);
+    LAST_RUNS.lock().unwrap().remove(&id);
+    Ok(removed)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn list_retention_policies() -> Result<Vec<RetentionInfo>, AppError> {
+    let policies = POLICIES.with(|policies| policies.to_vec())?;
+    let runs = LAST_RUNS.lock().unwrap();
+    Ok(policies
+        .into_iter()
+        .map(|policy| RetentionInfo {
+            last_run: runs.get(&policy.id()).cloned(),
+            policy,
+        })
+        .collect())
+}
+
+/// What the next run of an album's policy would archive, from the catalog
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn preview_retention(repo: String, album_path: String) -> Result<RetentionPreview, AppError> {
+    let policy = find_policy(&repo, &album_path)?;
+    let plan = plan_for(&policy)?;
+    let next_run_at = Some(NEXT_RUN_AT.load(Ordering::Relaxed)).filter(|at| *at > 0 && policy.enabled);
+    Ok(RetentionPreview {
+        total_bytes: plan.candidates.iter().filter_map(|c| c.size).sum(),
+        policy,
+        plan,
+        next_run_at,
+    })
+}
+
+/// Run an album's policy now instead of waiting for the background task
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn run_retention(
+    client: State<'_, HttpClient>,
+    repo: String,
+    album_path: String,
+) -> Result<RetentionRun, AppError> {
+    let _permit = SCHEDULER.acquire(JobClass::Sync).await;
+    run_album_retention(&client.0, &repo, &album_path).await
+}
@@ -0,0 +1,413 @@
+//! Request Retry Policy and Circuit Breaker
+//!
+//! `send_with_retry` is the single way GitHub requests leave the app. It
+//! retries transient failures (connection errors, timeouts, 5xx, rate limits)
+//! with full-jitter exponential backoff. Only idempotent methods are retried
+//! after the request may have reached the server. `POST`, `PATCH` and `PUT`
+//! (a contents-API `PUT` creates a commit) are retried solely when the
+//! connection could not be established at all, or when the server answered
+//! that it did not process them (429, 503).
+//!
+//! A per-host circuit breaker counts requests that still failed after all
+//! retries. Once the threshold is reached the host is considered unavailable
+//! and requests fail fast with `AppError::Unavailable` until the cooldown has
+//! passed; then a single trial request decides whether the breaker closes.
+//! If the trial is dropped before it finishes, the next request becomes the
+//! trial. Streaming uploads, which cannot be retried, still go through the
+//! breaker.
+
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::github::AppError;
+use crate::util::now_secs;
+
+lazy_static::lazy_static! {
+    static ref POLICY: Mutex<RetryPolicy> = Mutex::new(RetryPolicy::default());
+    static ref BREAKERS: Mutex<HashMap<String, CircuitBreaker>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts per request, including the first
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Consecutive failed requests before the breaker opens
+    pub failure_threshold: u32,
+    pub cooldown_secs: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 1000,
+            max_delay_ms: 30_000,
+            failure_threshold: 5,
+            cooldown_secs: 30,
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.max_attempts == 0 || self.max_attempts > 10 {
+            return Err(AppError::Validation("max_attempts must be between 1 and 10".into()));
+        }
+        if self.base_delay_ms == 0 || self.base_delay_ms > self.max_delay_ms {
+            return Err(AppError::Validation("base_delay_ms must be positive and not exceed max_delay_ms".into()));
+        }
+        if self.failure_threshold == 0 {
+            return Err(AppError::Validation("failure_threshold must be positive".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Full-jitter backoff: a random delay up to `base * 2^attempt`, capped.
+/// `random` is any uniformly distributed value.
+pub fn backoff_delay(policy: &RetryPolicy, attempt: u32, random: u64) -> Duration {
+    let ceiling = policy
+        .base_delay_ms
+        .saturating_mul(1u64 << attempt.min(20))
+        .min(policy.max_delay_ms);
+    Duration::from_millis(random % (ceiling + 1))
+}
+
+/// Methods that can safely be sent twice. `PUT` is left out: GitHub's
+/// contents API commits on every `PUT`.
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::DELETE | Method::OPTIONS)
+}
+
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+            | StatusCode::REQUEST_TIMEOUT
+    )
+}
+
+/// Statuses that mean the request was turned away before being processed,
+/// so even a non-idempotent request can be sent again
+pub fn is_unprocessed_status(status: StatusCode) -> bool {
+    matches!(status, StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE)
+}
+
+/// Seconds to wait before retrying a rate-limited response, if the server said so
+pub(crate) fn retry_after(res: &Response) -> Option<u64> {
+    let headers = res.headers();
+    if let Some(secs) = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok())
+    {
+        return Some(secs);
+    }
+
+    // GitHub's primary rate limit: 403 with no remaining requests
+    let remaining = headers.get("x-ratelimit-remaining").and_then(|v| v.to_str().ok());
+    let reset = headers
+        .get("x-ratelimit-reset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+    match (remaining, reset) {
+        (Some("0"), Some(reset)) => {
+            let now = now_secs();
+            Some(reset.saturating_sub(now))
+        }
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Circuit Breaker
+// ============================================================================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    /// Requests fail fast until the cooldown has passed
+    Open,
+    /// Cooldown passed; one trial request is in flight
+    HalfOpen,
+}
+
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub opened_at: Option<Instant>,
+    pub last_error: Option<String>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            last_error: None,
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Whether a request may proceed, and if so whether it is the half-open
+    /// trial; `Err` carries the seconds until retry
+    pub fn allow(&mut self, policy: &RetryPolicy, now: Instant) -> Result<bool, u64> {
+        match self.state {
+            BreakerState::Closed => Ok(false),
+            BreakerState::HalfOpen => Err(1),
+            BreakerState::Open => {
+                let cooldown = Duration::from_secs(policy.cooldown_secs);
+                let elapsed = self.opened_at.map(|t| now.saturating_duration_since(t)).unwrap_or(cooldown);
+                if elapsed >= cooldown {
+                    self.state = BreakerState::HalfOpen;
+                    Ok(true)
+                } else {
+                    Err((cooldown - elapsed).as_secs().max(1))
+                }
+            }
+        }
+    }
+
+    /// The trial request ended without an outcome; let the next one try
+    pub fn abandon_trial(&mut self) {
+        if self.state == BreakerState::HalfOpen {
+            self.state = BreakerState::Open;
+        }
+    }
+
+    pub fn on_success(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn on_failure(&mut self, policy: &RetryPolicy, now: Instant, error: String) {
+        self.consecutive_failures += 1;
+        self.last_error = Some(error);
+        if self.state == BreakerState::HalfOpen || self.consecutive_failures >= policy.failure_threshold {
+            self.state = BreakerState::Open;
+            self.opened_at = Some(now);
+        }
+    }
+
+    pub fn retry_in_secs(&self, policy: &RetryPolicy, now: Instant) -> Option<u64> {
+        let opened = self.opened_at.filter(|_| self.state == BreakerState::Open)?;
+        let cooldown = Duration::from_secs(policy.cooldown_secs);
+        Some(cooldown.saturating_sub(now.saturating_duration_since(opened)).as_secs())
+    }
+}
+
+/// Backend availability as shown to the UI
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackendStatus {
+    pub host: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub retry_in_secs: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+fn policy() -> RetryPolicy {
+    POLICY.lock().unwrap().clone()
+}
+
+/// A request admitted by its host's breaker. Dropped without an outcome,
+/// e.g. when the request's future is dropped, it gives up the half-open trial.
+struct BreakerPermit {
+    host: String,
+    trial: bool,
+    recorded: bool,
+}
+
+impl BreakerPermit {
+    fn acquire(host: &str, policy: &RetryPolicy) -> Result<Self, AppError> {
+        let trial = BREAKERS
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_default()
+            .allow(policy, Instant::now())
+            .map_err(AppError::Unavailable)?;
+        Ok(Self {
+            host: host.to_string(),
+            trial,
+            recorded: false,
+        })
+    }
+
+    fn record(&mut self, policy: &RetryPolicy, failure: Option<String>) {
+        self.recorded = true;
+        let mut breakers = BREAKERS.lock().unwrap();
+        let breaker = breakers.entry(self.host.clone()).or_default();
+        match failure {
+            None => breaker.on_success(),
+            Some(error) => {
+                breaker.on_failure(policy, Instant::now(), error);
+                if breaker.state == BreakerState::Open {
+                    tracing::warn!("Circuit breaker opened for {}", self.host);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for BreakerPermit {
+    fn drop(&mut self) {
+        if self.trial && !self.recorded {
+            if let Some(breaker) = BREAKERS.lock().unwrap().get_mut(&self.host) {
+                breaker.abandon_trial();
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Sending
+// ============================================================================
+
+/// Send a request under the current retry policy and circuit breaker.
+/// Non-success responses that are not transient are returned to the caller.
+pub async fn send_with_retry(request: RequestBuilder) -> Result<Response, AppError> {
+    let policy = policy();
+
+    // Streaming bodies cannot be cloned, so they get a single attempt
+    let Some(probe) = request.try_clone() else {
+        let (client, built) = request.build_split();
+        let built = built?;
+        let mut permit = BreakerPermit::acquire(built.url().host_str().unwrap_or_default(), &policy)?;
+        let outcome = client.execute(built).await;
+        let failure = match &outcome {
+            Ok(res) if res.status().is_server_error() => Some(format!("HTTP {}", res.status())),
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        permit.record(&policy, failure);
+        return outcome.map_err(AppError::from);
+    };
+    let built = probe.build()?;
+    let host = built.url().host_str().unwrap_or_default().to_string();
+    let idempotent = is_idempotent(built.method());
+
+    let mut permit = BreakerPermit::acquire(&host, &policy)?;
+
+    let mut attempt = 0;
+    loop {
+        let outcome = request.try_clone().expect("cloneable request").send().await;
+        attempt += 1;
+        let last = attempt >= policy.max_attempts;
+
+        let wait = match &outcome {
+            Ok(res) if is_retryable_status(res.status()) && (idempotent || is_unprocessed_status(res.status())) => {
+                retry_after(res).map(Duration::from_secs)
+            }
+            // The server may have acted on it; sending it again could repeat that
+            Ok(res) if is_retryable_status(res.status()) => {
+                permit.record(&policy, Some(format!("HTTP {}", res.status())));
+                return outcome.map_err(AppError::from);
+            }
+            Ok(res) if res.status() == StatusCode::FORBIDDEN => match retry_after(res) {
+                Some(secs) => Some(Duration::from_secs(secs)),
+                None => {
+                    permit.record(&policy, None);
+                    return outcome.map_err(AppError::from);
+                }
+            },
+            Ok(_) => {
+                permit.record(&policy, None);
+                return outcome.map_err(AppError::from);
+            }
+            // Retry POST/PATCH/PUT only if the request never left the machine
+            Err(e) if idempotent || e.is_connect() => None,
+            Err(_) => {
+                permit.record(&policy, Some("Request failed".into()));
+                return outcome.map_err(AppError::from);
+            }
+        };
+
+        let delay = wait.unwrap_or_else(|| backoff_delay(&policy, attempt - 1, rand::random()));
+        if last || delay > Duration::from_millis(policy.max_delay_ms) {
+            let error = match &outcome {
+                Ok(res) => format!("HTTP {}", res.status()),
+                Err(e) => e.to_string(),
+            };
+            // Rate limits mean the backend is up; they do not trip the breaker
+            let failure = match &outcome {
+                Ok(res) if matches!(res.status(), StatusCode::TOO_MANY_REQUESTS | StatusCode::FORBIDDEN) => None,
+                _ => Some(error),
+            };
+            permit.record(&policy, failure);
+            return outcome.map_err(AppError::from);
+        }
+
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// `RequestBuilder::send` counterpart that goes through `send_with_retry`
+pub trait SendWithRetry {
+    fn send_with_retry(self) -> impl std::future::Future<Output = Result<Response, AppError>> + Send;
+}
+
+impl SendWithRetry for RequestBuilder {
+    fn send_with_retry(self) -> impl std::future::Future<Output = Result<Response, AppError>> + Send {
+        send_with_retry(self)
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn get_retry_policy() -> RetryPolicy {
+    policy()
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn set_retry_policy(policy: RetryPolicy) -> Result<(), AppError> {
+    policy.validate()?;
+    *POLICY.lock().unwrap() = policy;
+    Ok(())
+}
+
+/// Circuit breaker state for every host contacted so far
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn get_backend_status() -> Vec<BackendStatus> {
+    let policy = policy();
+    let now = Instant::now();
+    let mut statuses: Vec<BackendStatus> = BREAKERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(host, b)| BackendStatus {
+            host: host.clone(),
+            state: b.state,
+            consecutive_failures: b.consecutive_failures,
+            retry_in_secs: b.retry_in_secs(&policy, now),
+            last_error: b.last_error.clone(),
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.host.cmp(&b.host));
+    statuses
+}
+
+/// Close all breakers, e.g. after the user explicitly asks to retry
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn reset_circuit_breakers() {
+    BREAKERS.lock().unwrap().clear();
+}
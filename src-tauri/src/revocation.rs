@@ -0,0 +1,261 @@
+//! Key Revocation
+//!
+//! When a device is lost, its key is put on a revocation list kept in the
+//! repository at `.vortex/revocations.json`, so every other device sees it.
+//! `download_secure_photo` and `download_secure_message` refuse to decrypt
+//! with a key on the list.
+//!
+//! Each entry is signed by the revoking keypair with its public bundle
+//! embedded. Anyone with push access can append to the file, so an entry
+//! only counts when its signature is valid and the signer is trusted (the
+//! local keypair, one it replaced, or a verified contact; see
+//! `security_verify::TrustedSigners`) or is the revoked key itself.
+//!
+//! Lists are cached for five minutes per repository.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::crypto::{with_keypair, CryptoError, HybridKeypair, KeypairHandle, PublicBundle};
+use crate::github::{put_file_contents, response_error, validate_repo, AppError, Bearer, GithubError, HttpClient};
+use crate::retry::SendWithRetry;
+use crate::security_verify::TrustedSigners;
+use crate::util::now_secs;
+
+pub const REVOCATION_LIST_PATH: &str = ".vortex/revocations.json";
+const REVOCATION_DOMAIN: &[u8] = b"vortex-image key revocation v1\0";
+const CACHE_DURATION: Duration = Duration::from_secs(5 * 60);
+const MAX_REASON_LEN: usize = 200;
+
+lazy_static::lazy_static! {
+    static ref LIST_CACHE: Mutex<HashMap<String, (Instant, RevocationList)>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevocationEntry {
+    /// Key id of the revoked public bundle
+    pub key_id: String,
+    pub revoked_at: u64,
+    pub reason: String,
+    pub signer: PublicBundle,
+    /// Base64 hybrid signature over the fields above
+    pub signature: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RevocationList {
+    pub entries: Vec<RevocationEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevocationStatus {
+    pub key_id: String,
+    pub revoked: bool,
+    /// The entry that revokes the key
+    pub entry: Option<RevocationEntry>,
+    /// Entries naming the key that were ignored: bad signature or untrusted signer
+    pub ignored: usize,
+}
+
+fn revocation_message(key_id: &str, revoked_at: u64, reason: &str) -> Vec<u8> {
+    let mut message = REVOCATION_DOMAIN.to_vec();
+    message.extend_from_slice(key_id.as_bytes());
+    message.push(0);
+    message.extend_from_slice(&revoked_at.to_le_bytes());
+    message.extend_from_slice(reason.as_bytes());
+    message
+}
+
+impl RevocationEntry {
+    pub fn sign(key_id: &str, reason: &str, keypair: &HybridKeypair) -> Result<Self, CryptoError> {
+        let revoked_at = now_secs();
+        let signature = keypair.sign(&revocation_message(key_id, revoked_at, reason))?;
+        Ok(Self {
+            key_id: key_id.to_string(),
+            revoked_at,
+            reason: reason.to_string(),
+            signer: keypair.public_bundle(),
+            signature: STANDARD.encode(signature),
+        })
+    }
+
+    /// Whether the signature is valid and made by a key allowed to revoke
+    pub fn is_authorized(&self, trusted: &TrustedSigners) -> bool {
+        if self.signer.derived_key_id() != self.signer.key_id {
+            return false;
+        }
+        let valid = STANDARD
+            .decode(&self.signature)
+            .map(|sig| {
+                self.signer
+                    .verify(&revocation_message(&self.key_id, self.revoked_at, &self.reason), &sig)
+                    .is_ok()
+            })
+            .unwrap_or(false);
+        valid && (self.signer.key_id == self.key_id || trusted.contains(&self.signer.key_id))
+    }
+}
+
+impl RevocationList {
+    /// Whether `key_id` is revoked by an authorized entry
+    pub fn status(&self, key_id: &str, trusted: &TrustedSigners) -> RevocationStatus {
+        let (authorized, ignored): (Vec<_>, Vec<_>) = self
+            .entries
+            .iter()
+            .filter(|e| e.key_id == key_id)
+            .partition(|e| e.is_authorized(trusted));
+        RevocationStatus {
+            key_id: key_id.to_string(),
+            revoked: !authorized.is_empty(),
+            entry: authorized.into_iter().min_by_key(|e| e.revoked_at).cloned(),
+            ignored: ignored.len(),
+        }
+    }
+
+    /// Add `entry` unless its key is already revoked by the same signer
+    pub fn add(&mut self, entry: RevocationEntry) -> bool {
+        if self
+            .entries
+            .iter()
+            .any(|e| e.key_id == entry.key_id && e.signer.key_id == entry.signer.key_id)
+        {
+            return false;
+        }
+        self.entries.push(entry);
+        true
+    }
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+async fn fetch_list(client: &Client, repo: &str, token: &str) -> Result<(RevocationList, Option<String>), AppError> {
+    let url = format!("https://api.github.com/repos/{}/contents/{}", repo, REVOCATION_LIST_PATH);
+
+    let res = client
+        .get(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+
+    if res.status() == 404 {
+        return Ok((RevocationList::default(), None));
+    }
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "Failed to load revocation list").await);
+    }
+
+    let json: serde_json::Value = res.json().await?;
+    let sha = json["sha"].as_str().map(|s| s.to_string());
+    let content: String = json["content"].as_str().unwrap_or("").chars().filter(|c| !c.is_whitespace()).collect();
+    let raw = STANDARD
+        .decode(content)
+        .map_err(|_| AppError::Validation("Invalid revocation list encoding".into()))?;
+    let list = serde_json::from_slice(&raw)
+        .map_err(|e| AppError::Validation(format!("Invalid revocation list: {}", e)))?;
+    Ok((list, sha))
+}
+
+fn remember(repo: &str, list: &RevocationList) {
+    LIST_CACHE
+        .lock()
+        .unwrap()
+        .insert(repo.to_string(), (Instant::now(), list.clone()));
+}
+
+/// The revocation list of `repo`, from the cache while it is fresh
+pub(crate) async fn load_list(client: &Client, repo: &str, token: &str) -> Result<RevocationList, AppError> {
+    let cached = LIST_CACHE
+        .lock()
+        .unwrap()
+        .get(repo)
+        .filter(|(fetched, _)| fetched.elapsed() < CACHE_DURATION)
+        .map(|(_, list)| list.clone());
+    if let Some(list) = cached {
+        return Ok(list);
+    }
+    let (list, _) = fetch_list(client, repo, token).await?;
+    remember(repo, &list);
+    Ok(list)
+}
+
+/// Fail if the key `key_id` is on the revocation list of `repo`
+pub(crate) async fn ensure_not_revoked(client: &Client, repo: &str, token: &str, key_id: &str) -> Result<(), AppError> {
+    let list = load_list(client, repo, token).await?;
+    let status = list.status(key_id, &TrustedSigners::load(None));
+    if status.revoked {
+        return Err(GithubError::Forbidden {
+            message: format!("Key {} has been revoked and can no longer decrypt", key_id),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Revoke the key `key_id` (for example of a lost device), signing the entry
+/// with the keypair behind `keypair_handle`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn revoke_device_key(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    keypair_handle: KeypairHandle,
+    key_id: String,
+    reason: String,
+) -> Result<RevocationStatus, AppError> {
+    validate_repo(&repo)?;
+    let key_id = key_id.trim().to_lowercase();
+    if key_id.is_empty() || !key_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::Validation("Invalid key id".into()));
+    }
+    let reason = reason.trim();
+    if reason.len() > MAX_REASON_LEN || reason.chars().any(char::is_control) {
+        return Err(AppError::Validation("Invalid revocation reason".into()));
+    }
+
+    let entry = with_keypair(keypair_handle, |kp| RevocationEntry::sign(&key_id, reason, kp))
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let (mut list, sha) = fetch_list(&client.0, &repo, &token).await?;
+    if list.add(entry) {
+        let body = serde_json::to_vec_pretty(&list)
+            .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        let message = format!("Revoke key {}", key_id);
+        put_file_contents(&client.0, &repo, &token, REVOCATION_LIST_PATH, &body, &message, sha.as_deref()).await?;
+        record_activity(ActivityKind::Key, "revoke_device_key", Some(&repo), &key_id, Some(reason.to_string()));
+    }
+    remember(&repo, &list);
+
+    Ok(list.status(&key_id, &TrustedSigners::load(Some(keypair_handle))))
+}
+
+/// Whether `key_id` is revoked in `repo`. Always fetches the current list.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn check_revocation(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    key_id: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<RevocationStatus, AppError> {
+    validate_repo(&repo)?;
+    let (list, _) = fetch_list(&client.0, &repo, &token).await?;
+    remember(&repo, &list);
+    Ok(list.status(&key_id.trim().to_lowercase(), &TrustedSigners::load(keypair_handle)))
+}
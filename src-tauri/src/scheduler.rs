@@ -0,0 +1,254 @@
+//! Job Scheduler
+//!
+//! Work that competes for CPU and network takes a permit from one place
+//! first, in one of three classes, highest priority first:
+//!
+//! - **interactive** - what the user is waiting on: opening a photo, its
+//!   thumbnail,
+//! - **sync** - uploads and album syncs,
+//! - **background** - prefetching, thumbnail pregeneration, folder
+//!   compression and pipeline jobs, offline queue replay.
+//!
+//! Each class has its own concurrency cap, so a full sync class never holds
+//! up a click. A permit is only granted while no higher class is waiting,
+//! and background work also stays off while interactive work runs. Long
+//! jobs take a permit per file, so they yield between files.
+//!
+//! `get_job_queue_status` shows what runs and waits in each class.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Condvar, Mutex};
+use tokio::sync::Notify;
+
+lazy_static::lazy_static! {
+    pub static ref SCHEDULER: Scheduler = Scheduler::new(ClassLimits::default());
+}
+
+/// Ordered from highest to lowest priority
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobClass {
+    Interactive,
+    Sync,
+    Background,
+}
+
+impl JobClass {
+    pub const ALL: [JobClass; 3] = [JobClass::Interactive, JobClass::Sync, JobClass::Background];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Permits each class may hold at once
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassLimits {
+    pub interactive: usize,
+    pub sync: usize,
+    pub background: usize,
+}
+
+impl Default for ClassLimits {
+    fn default() -> Self {
+        Self {
+            interactive: 4,
+            sync: 2,
+            background: 2,
+        }
+    }
+}
+
+impl ClassLimits {
+    pub fn of(&self, class: JobClass) -> usize {
+        match class {
+            JobClass::Interactive => self.interactive,
+            JobClass::Sync => self.sync,
+            JobClass::Background => self.background,
+        }
+    }
+}
+
+/// Permits held and requests waiting, indexed by class
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Load {
+    pub running: [usize; 3],
+    pub waiting: [usize; 3],
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClassStatus {
+    pub class: JobClass,
+    pub running: usize,
+    pub waiting: usize,
+    pub limit: usize,
+    /// Permits returned since start
+    pub completed: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobQueueStatus {
+    /// Highest priority first
+    pub classes: Vec<ClassStatus>,
+}
+
+/// Whether `class` may take a permit now
+pub fn may_start(class: JobClass, load: &Load, limits: &ClassLimits) -> bool {
+    let i = class.index();
+    if load.running[i] >= limits.of(class).max(1) {
+        return false;
+    }
+    if load.waiting[..i].iter().any(|&n| n > 0) {
+        return false;
+    }
+    class != JobClass::Background || load.running[JobClass::Interactive.index()] == 0
+}
+
+#[derive(Default)]
+struct State {
+    load: Load,
+    completed: [u64; 3],
+}
+
+pub struct Scheduler {
+    limits: ClassLimits,
+    state: Mutex<State>,
+    /// Wakes blocking waiters when a permit is returned
+    freed: Condvar,
+    /// Wakes async waiters when a permit is returned
+    notify: Notify,
+}
+
+/// A running slot in the scheduler, returned on drop
+pub struct Permit<'a> {
+    scheduler: &'a Scheduler,
+    class: JobClass,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        {
+            let mut state = self.scheduler.state.lock().unwrap();
+            state.load.running[self.class.index()] -= 1;
+            state.completed[self.class.index()] += 1;
+        }
+        self.scheduler.wake();
+    }
+}
+
+/// Counts a request as waiting until it is granted or given up
+struct Waiting<'a> {
+    scheduler: &'a Scheduler,
+    class: JobClass,
+    active: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if self.active {
+            self.scheduler.state.lock().unwrap().load.waiting[self.class.index()] -= 1;
+            // Lower classes held back by this request may go now
+            self.scheduler.wake();
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new(limits: ClassLimits) -> Self {
+        Self {
+            limits,
+            state: Mutex::new(State::default()),
+            freed: Condvar::new(),
+            notify: Notify::new(),
+        }
+    }
+
+    fn wake(&self) {
+        self.freed.notify_all();
+        self.notify.notify_waiters();
+    }
+
+    /// Take a permit if `class` may start, counting it as running
+    fn try_start(&self, class: JobClass, state: &mut State) -> bool {
+        // A waiting request does not hold back its own class
+        let mut load = state.load;
+        load.waiting[class.index()] = 0;
+        if may_start(class, &load, &self.limits) {
+            state.load.running[class.index()] += 1;
+            return true;
+        }
+        false
+    }
+
+    /// Wait for a permit of `class`
+    pub async fn acquire(&self, class: JobClass) -> Permit<'_> {
+        let mut waiting: Option<Waiting> = None;
+        loop {
+            // Created before checking, so a release in between still wakes it
+            let notified = self.notify.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if self.try_start(class, &mut state) {
+                    if let Some(waiting) = waiting.as_mut() {
+                        waiting.active = false;
+                        state.load.waiting[class.index()] -= 1;
+                        drop(state);
+                        self.wake();
+                    }
+                    return Permit { scheduler: self, class };
+                }
+                if waiting.is_none() {
+                    state.load.waiting[class.index()] += 1;
+                    waiting = Some(Waiting {
+                        scheduler: self,
+                        class,
+                        active: true,
+                    });
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// `acquire` for blocking threads
+    pub fn acquire_blocking(&self, class: JobClass) -> Permit<'_> {
+        let mut state = self.state.lock().unwrap();
+        if !self.try_start(class, &mut state) {
+            state.load.waiting[class.index()] += 1;
+            while !self.try_start(class, &mut state) {
+                state = self.freed.wait(state).unwrap();
+            }
+            state.load.waiting[class.index()] -= 1;
+            drop(state);
+            self.wake();
+        }
+        Permit { scheduler: self, class }
+    }
+
+    pub fn status(&self) -> JobQueueStatus {
+        let state = self.state.lock().unwrap();
+        JobQueueStatus {
+            classes: JobClass::ALL
+                .iter()
+                .map(|&class| ClassStatus {
+                    class,
+                    running: state.load.running[class.index()],
+                    waiting: state.load.waiting[class.index()],
+                    limit: self.limits.of(class),
+                    completed: state.completed[class.index()],
+                })
+                .collect(),
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Running and waiting work per priority class
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn get_job_queue_status() -> JobQueueStatus {
+    SCHEDULER.status()
+}
@@ -0,0 +1,310 @@
+//! Library Search
+//!
+//! `search_photos` searches the local catalog (see `catalog`) by file name,
+//! album, camera, caption and tags, with free text or per field, and by
+//! capture date, returning pages of results.
+//!
+//! Text goes through an SQLite FTS5 index with the trigram tokenizer, so any
+//! part of a word matches, ignoring case. The catalog on disk is sealed, so
+//! the index is a temporary table that lives in memory with the store's
+//! connection: rows are added as the catalog is written, and the whole index
+//! is rebuilt from the catalog when it is found out of step, as on the first
+//! search after opening the store.
+//!
+//! Photos of encrypted albums are only searched while a keypair is unlocked.
+
+use rusqlite::{params, params_from_iter};
+use serde::{Deserialize, Serialize};
+
+use crate::catalog::{all_photos, photo_by_id, photo_id, CatalogPhoto, SyncState};
+use crate::crypto::KeypairHandle;
+use crate::github::AppError;
+use crate::local_store::{db_error, with_store, LocalStore};
+
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+pub const MAX_SEARCH_LIMIT: usize = 500;
+/// Shortest term the trigram index can look up; shorter ones are scanned for
+const MIN_INDEXED_TERM: usize = 3;
+
+pub const SEARCH_SCHEMA: &str = r#"
+PRAGMA temp_store = MEMORY;
+CREATE VIRTUAL TABLE IF NOT EXISTS temp.catalog_search USING fts5(
+    name, album, camera, caption, tags,
+    id UNINDEXED, repo UNINDEXED,
+    tokenize = 'trigram'
+);
+"#;
+
+/// Unset fields match everything; text fields match any part of a word
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchQuery {
+    /// Words to find in any field
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Stored or original file name
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// Camera make, model or lens
+    #[serde(default)]
+    pub camera: Option<String>,
+    /// Exact tag, ignoring case
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Album path; includes its sub-albums
+    #[serde(default)]
+    pub album: Option<String>,
+    /// Unix seconds, inclusive
+    #[serde(default)]
+    pub taken_from: Option<i64>,
+    /// Unix seconds, inclusive
+    #[serde(default)]
+    pub taken_until: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SearchPage {
+    /// Newest first
+    pub items: Vec<CatalogPhoto>,
+    /// Pass back as `offset` for the next page; `None` on the last page
+    pub next_offset: Option<usize>,
+    pub total: usize,
+}
+
+// ============================================================================
+// Index
+// ============================================================================
+
+fn camera_text(photo: &CatalogPhoto) -> String {
+    let Some(exif) = &photo.item.exif else {
+        return String::new();
+    };
+    [&exif.camera_make, &exif.camera_model, &exif.lens_model]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub(crate) fn index_photo(store: &LocalStore, id: &[u8; 32], photo: &CatalogPhoto) -> Result<(), AppError> {
+    unindex_photo(store, id)?;
+    let name = match &photo.item.display_name {
+        Some(display_name) => format!("{} {}", photo.item.name, display_name),
+        None => photo.item.name.clone(),
+    };
+    store
+        .connection()
+        .execute(
+            "INSERT INTO temp.catalog_search (name, album, camera, caption, tags, id, repo)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                name,
+                photo.album,
+                camera_text(photo),
+                photo.item.caption.as_deref().unwrap_or(""),
+                photo.tags.join(" "),
+                &id[..],
+                photo.repo,
+            ],
+        )
+        .map_err(db_error)?;
+    Ok(())
+}
+
+pub(crate) fn unindex_photo(store: &LocalStore, id: &[u8; 32]) -> Result<(), AppError> {
+    store
+        .connection()
+        .execute("DELETE FROM temp.catalog_search WHERE id = ?1", params![&id[..]])
+        .map_err(db_error)?;
+    Ok(())
+}
+
+pub(crate) fn clear_index(store: &LocalStore) -> Result<(), AppError> {
+    store
+        .connection()
+        .execute("DELETE FROM temp.catalog_search", [])
+        .map_err(db_error)?;
+    Ok(())
+}
+
+/// Rebuild the index unless it holds as many rows as the catalog
+fn ensure_index(store: &LocalStore) -> Result<(), AppError> {
+    let count = |sql: &str| -> Result<i64, AppError> {
+        store.connection().query_row(sql, [], |row| row.get(0)).map_err(db_error)
+    };
+    if count("SELECT COUNT(*) FROM temp.catalog_search")? == count("SELECT COUNT(*) FROM catalog")? {
+        return Ok(());
+    }
+
+    let transaction = store.connection().unchecked_transaction().map_err(db_error)?;
+    clear_index(store)?;
+    for photo in all_photos(store)? {
+        index_photo(store, &photo_id(store, &photo.repo, &photo.path), &photo)?;
+    }
+    transaction.commit().map_err(db_error)
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// `term` as an FTS5 string, which matches it anywhere
+fn quoted(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// `term` as a LIKE pattern matching it anywhere, escaped with `\`
+fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// SQL conditions and their parameters selecting the rows whose text
+/// matches `query`. Terms the trigram index can look up are combined into
+/// one MATCH; shorter ones become LIKE conditions.
+fn text_conditions(query: &SearchQuery) -> (Vec<String>, Vec<String>) {
+    let mut phrases = Vec::new();
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    let mut add = |columns: &[&str], term: &str| {
+        if term.chars().count() >= MIN_INDEXED_TERM {
+            let column_filter = match columns {
+                [column] => format!("{} : ", column),
+                _ => String::new(),
+            };
+            phrases.push(format!("{}{}", column_filter, quoted(term)));
+        } else {
+            let alternatives: Vec<String> = columns.iter().map(|c| format!("{} LIKE ? ESCAPE '\\'", c)).collect();
+            conditions.push(format!("({})", alternatives.join(" OR ")));
+            values.extend(std::iter::repeat(like_pattern(term)).take(columns.len()));
+        }
+    };
+
+    for word in query.text.as_deref().unwrap_or("").split_whitespace() {
+        add(&["name", "album", "camera", "caption", "tags"], word);
+    }
+    if let Some(filename) = query.filename.as_deref().map(str::trim).filter(|f| !f.is_empty()) {
+        add(&["name"], filename);
+    }
+    if let Some(camera) = query.camera.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        add(&["camera"], camera);
+    }
+
+    if !phrases.is_empty() {
+        conditions.insert(0, "catalog_search MATCH ?".into());
+        values.insert(0, phrases.join(" AND "));
+    }
+    (conditions, values)
+}
+
+fn matches_attributes(photo: &CatalogPhoto, query: &SearchQuery, unlocked: bool) -> bool {
+    let album = query.album.as_deref().map(|a| a.trim_matches('/')).filter(|a| !a.is_empty());
+    let tag = query.tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    let dated = query.taken_from.is_some() || query.taken_until.is_some();
+
+    photo.sync_state != SyncState::PendingDelete
+        && (unlocked || !photo.item.encrypted)
+        && album.is_none_or(|a| photo.album == a || photo.album.starts_with(&format!("{}/", a)))
+        && tag.is_none_or(|t| photo.tags.iter().any(|own| own.to_lowercase() == t.to_lowercase()))
+        && (!dated || photo.taken_at.is_some_and(|at| {
+            query.taken_from.is_none_or(|from| at >= from) && query.taken_until.is_none_or(|until| at <= until)
+        }))
+}
+
+/// Every photo of `repo` matching `query`, newest first
+pub fn find_photos_in(
+    store: &LocalStore,
+    repo: &str,
+    query: &SearchQuery,
+    unlocked: bool,
+) -> Result<Vec<CatalogPhoto>, AppError> {
+    if let (Some(from), Some(until)) = (query.taken_from, query.taken_until) {
+        if from > until {
+            return Err(AppError::Validation("Date range ends before it starts".into()));
+        }
+    }
+    ensure_index(store)?;
+
+    let (conditions, values) = text_conditions(query);
+    let mut sql = "SELECT id FROM temp.catalog_search WHERE repo = ?".to_string();
+    for condition in &conditions {
+        sql.push_str(" AND ");
+        sql.push_str(condition);
+    }
+
+    let mut statement = store.connection().prepare(&sql).map_err(db_error)?;
+    let ids: Vec<Vec<u8>> = statement
+        .query_map(params_from_iter(std::iter::once(repo.to_string()).chain(values)), |row| row.get(0))
+        .map_err(db_error)?
+        .collect::<Result<_, _>>()
+        .map_err(db_error)?;
+
+    let mut photos = Vec::new();
+    for id in ids {
+        let Ok(id) = <[u8; 32]>::try_from(id) else {
+            continue;
+        };
+        if let Some(photo) = photo_by_id(store, &id)?.filter(|p| matches_attributes(p, query, unlocked)) {
+            photos.push(photo);
+        }
+    }
+    // Undated photos last
+    photos.sort_by(|a, b| {
+        b.taken_at
+            .is_some()
+            .cmp(&a.taken_at.is_some())
+            .then(b.taken_at.cmp(&a.taken_at))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(photos)
+}
+
+/// The page of `photos` from `offset`
+pub fn page_of(photos: Vec<CatalogPhoto>, offset: usize, limit: usize) -> SearchPage {
+    let total = photos.len();
+    let limit = limit.clamp(1, MAX_SEARCH_LIMIT);
+    let items: Vec<_> = photos.into_iter().skip(offset).take(limit).collect();
+    let next_offset = (offset + items.len() < total).then_some(offset + items.len());
+    SearchPage { items, next_offset, total }
+}
+
+/// Photos of `repo` matching `query`, newest first; `offset` and `limit`
+/// select the page
+pub fn search_photos_in(
+    store: &LocalStore,
+    repo: &str,
+    query: &SearchQuery,
+    unlocked: bool,
+    offset: usize,
+    limit: usize,
+) -> Result<SearchPage, AppError> {
+    Ok(page_of(find_photos_in(store, repo, query, unlocked)?, offset, limit))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Search the library. Omit `offset` for the first page.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn search_photos(
+    repo: String,
+    query: SearchQuery,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<SearchPage, AppError> {
+    let unlocked = keypair_handle.is_some();
+    with_store(|store| {
+        search_photos_in(
+            store,
+            &repo,
+            &query,
+            unlocked,
+            offset.unwrap_or(0),
+            limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        )
+    })
+}
@@ -0,0 +1,264 @@
+//! Plaintext Temp Files
+//!
+//! Viewing a photo of an encrypted album needs its plaintext in a file the
+//! webview can open. `open_photo_for_viewing` decrypts into managed temp
+//! storage instead of the downloads folder:
+//!
+//! - **RAM-backed** where the OS offers a tmpfs (`$XDG_RUNTIME_DIR`, then
+//!   `/dev/shm`), so the plaintext never reaches a disk,
+//! - **wiped on close** everywhere else: a folder below the profile whose
+//!   files are overwritten with zeros before they are removed.
+//!
+//! The folder is private to the user (0700 on Unix) and named per profile.
+//! Every file written is tracked until `release_plaintext` or
+//! `purge_decrypted_cache` wipes it; the purge also takes whatever a crash
+//! left behind, and runs at startup and on exit.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::crypto::{hash_data, KeypairHandle};
+use crate::download_cache::load_photo;
+use crate::github::{open_photo, sanitize_filename, validate_repo, AppError, HttpClient};
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::util::now_secs;
+
+const DISK_DIR: &str = "plaintext";
+const FILE_PREFIX: &str = "pt-";
+/// tmpfs mounts tried after `$XDG_RUNTIME_DIR`, before the profile folder
+const RAM_ROOTS: &[&str] = &["/dev/shm"];
+const WIPE_BLOCK: usize = 64 * 1024;
+
+lazy_static::lazy_static! {
+    static ref ARTIFACTS: Mutex<BTreeMap<String, PlaintextFile>> = Mutex::new(BTreeMap::new());
+}
+
+/// Where plaintext goes, and whether that is memory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlaintextDir {
+    pub path: PathBuf,
+    pub ram_backed: bool,
+}
+
+/// A tracked plaintext file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaintextFile {
+    pub id: String,
+    pub path: String,
+    pub size: u64,
+    /// What was decrypted, e.g. the photo's repo path
+    pub source: String,
+    pub ram_backed: bool,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeReport {
+    pub removed: usize,
+    /// Bytes overwritten before removal; zero for RAM-backed files
+    pub wiped_bytes: u64,
+    /// Files left over from an earlier session
+    pub leftovers: usize,
+    pub failed: Vec<String>,
+}
+
+/// Create `dir` readable by this user only
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// The first usable of `ram_roots`, else `disk_dir`. `name` keeps profiles
+/// apart in shared mounts.
+pub fn choose_dir_in(ram_roots: &[PathBuf], name: &str, disk_dir: &Path) -> Result<PlaintextDir, AppError> {
+    for root in ram_roots.iter().filter(|root| root.is_dir()) {
+        let path = root.join(name);
+        if create_private_dir(&path).is_ok() {
+            return Ok(PlaintextDir { path, ram_backed: true });
+        }
+    }
+    create_private_dir(disk_dir)?;
+    Ok(PlaintextDir {
+        path: disk_dir.to_path_buf(),
+        ram_backed: false,
+    })
+}
+
+/// The active profile's plaintext folder
+pub fn plaintext_dir() -> Result<PlaintextDir, AppError> {
+    let data_dir = crate::profiles::data_dir()?;
+    let mut ram_roots: Vec<PathBuf> = Vec::new();
+    if cfg!(target_os = "linux") {
+        ram_roots.extend(std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from));
+        ram_roots.extend(RAM_ROOTS.iter().map(PathBuf::from));
+    }
+    let digest = hash_data(data_dir.to_string_lossy().as_bytes());
+    let name = format!("vortex-{}", hex::encode(&digest[..8]));
+    choose_dir_in(&ram_roots, &name, &data_dir.join(DISK_DIR))
+}
+
+/// Write `content` to a new file in `dir`, named after `name`
+pub fn write_plaintext_in(dir: &PlaintextDir, source: &str, name: &str, content: &[u8]) -> Result<PlaintextFile, AppError> {
+    let id = format!("{}{}", FILE_PREFIX, hex::encode(rand::random::<[u8; 8]>()));
+    let name = sanitize_filename(name);
+    let path = dir.path.join(if name.is_empty() { id.clone() } else { format!("{}-{}", id, name) });
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    if let Err(e) = file.write_all(content).and_then(|_| file.sync_all()) {
+        drop(file);
+        let _ = wipe_file(&path, !dir.ram_backed);
+        return Err(e.into());
+    }
+
+    Ok(PlaintextFile {
+        id,
+        path: path.to_string_lossy().to_string(),
+        size: content.len() as u64,
+        source: source.to_string(),
+        ram_backed: dir.ram_backed,
+        created_at: now_secs(),
+    })
+}
+
+/// Remove `path`, overwriting it with zeros first if `overwrite`. Returns
+/// the bytes overwritten.
+pub fn wipe_file(path: &Path, overwrite: bool) -> Result<u64, AppError> {
+    let mut wiped = 0;
+    if overwrite {
+        let len = std::fs::metadata(path)?.len();
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        let zeros = [0u8; WIPE_BLOCK];
+        while wiped < len {
+            let n = (len - wiped).min(WIPE_BLOCK as u64) as usize;
+            file.write_all(&zeros[..n])?;
+            wiped += n as u64;
+        }
+        file.sync_all()?;
+    }
+    std::fs::remove_file(path)?;
+    Ok(wiped)
+}
+
+/// Wipe the tracked `files`, then anything else of ours in `dir`
+pub fn purge_in(dir: &PlaintextDir, files: Vec<PlaintextFile>) -> PurgeReport {
+    let mut report = PurgeReport::default();
+    let wipe = |path: &Path, report: &mut PurgeReport| match wipe_file(path, !dir.ram_backed) {
+        Ok(bytes) => {
+            report.removed += 1;
+            report.wiped_bytes += bytes;
+            true
+        }
+        Err(e) => {
+            report.failed.push(format!("{}: {}", path.display(), e));
+            false
+        }
+    };
+
+    for file in files {
+        let path = PathBuf::from(&file.path);
+        if path.exists() {
+            wipe(&path, &mut report);
+        }
+    }
+    if let Ok(entries) = std::fs::read_dir(&dir.path) {
+        for entry in entries.flatten() {
+            let ours = entry.file_name().to_string_lossy().starts_with(FILE_PREFIX);
+            if ours && entry.path().is_file() && wipe(&entry.path(), &mut report) {
+                report.leftovers += 1;
+            }
+        }
+    }
+    report
+}
+
+/// Write and track a plaintext file in the active profile's folder
+pub fn write_plaintext(source: &str, name: &str, content: &[u8]) -> Result<PlaintextFile, AppError> {
+    let file = write_plaintext_in(&plaintext_dir()?, source, name, content)?;
+    ARTIFACTS.lock().unwrap().insert(file.id.clone(), file.clone());
+    Ok(file)
+}
+
+/// Path of the tracked plaintext file `id`
+pub fn tracked_path(id: &str) -> Option<PathBuf> {
+    ARTIFACTS.lock().unwrap().get(id).map(|file| PathBuf::from(&file.path))
+}
+
+/// Wipe every plaintext file of the active profile
+pub fn purge_plaintext() -> Result<PurgeReport, AppError> {
+    let files = std::mem::take(&mut *ARTIFACTS.lock().unwrap()).into_values().collect();
+    let report = purge_in(&plaintext_dir()?, files);
+    if report.removed > 0 {
+        tracing::info!("Wiped {} plaintext files", report.removed);
+    }
+    Ok(report)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Decrypt a photo into managed temp storage for viewing. The file stays
+/// until `release_plaintext` or `purge_decrypted_cache`.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn open_photo_for_viewing(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    remote_path: String,
+    keypair_handle: Option<KeypairHandle>,
+    verify: Option<bool>,
+) -> Result<PlaintextFile, AppError> {
+    validate_repo(&repo)?;
+    let _permit = SCHEDULER.acquire(JobClass::Interactive).await;
+    let (photo, _) = load_photo(&client.0, &repo, &token, &remote_path, keypair_handle, verify.unwrap_or(true)).await?;
+    let (content, filename) = open_photo(&repo, &remote_path, photo, keypair_handle)?;
+    let content = Zeroizing::new(content);
+    write_plaintext(&format!("{}:{}", repo, remote_path), &filename, &content)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn list_plaintext_files() -> Vec<PlaintextFile> {
+    ARTIFACTS.lock().unwrap().values().cloned().collect()
+}
+
+/// Wipe one plaintext file, e.g. when its viewer closes. Returns false if
+/// it was not tracked.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn release_plaintext(id: String) -> Result<bool, AppError> {
+    let Some(file) = ARTIFACTS.lock().unwrap().remove(&id) else {
+        return Ok(false);
+    };
+    let path = Path::new(&file.path);
+    if path.exists() {
+        wipe_file(path, !file.ram_backed)?;
+    }
+    Ok(true)
+}
+
+/// Wipe every decrypted temp file, tracked or left over from a crash
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn purge_decrypted_cache() -> Result<PurgeReport, AppError> {
+    purge_plaintext()
+}
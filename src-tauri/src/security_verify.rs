@@ -0,0 +1,727 @@
+//! Security Verification
+//!
+//! `security_audit_albums` checks every repository of the library (the
+//! primary repo and its shards) for the ways photos end up exposed:
+//!
+//! - the repository is public,
+//! - an album is not encrypted,
+//! - a folder of photos has no album manifest, or its manifest is unsigned
+//!   or no longer matches its signature,
+//! - photos were uploaded without a signature,
+//! - photos the album manifest lists as signed have lost their signature.
+//!
+//! With `auto_fix` set, public repositories are switched to private through
+//! the same call as `update_repo_visibility`. Encryption cannot be applied
+//! after the fact and is only reported.
+//!
+//! Signatures are hybrid (Ed25519 + Dilithium3) signatures of the uploader's
+//! keypair, made with the signer's public bundle embedded so they can be
+//! checked without a key server:
+//!
+//! - manifests carry theirs in the `signature` field, over the manifest
+//!   without that field,
+//! - each photo has a detached `<photo>.vxsig` next to it, over the BLAKE3
+//!   hash and size of the bytes stored in the repository (so it survives
+//!   moves, and encrypted photos are checked before decryption).
+//!
+//! The album manifest also lists the photos uploaded with a signature, with
+//! their hashes, under its own signature. Deleting a listed photo's `.vxsig`
+//! therefore does not make it merely unsigned: it is reported as tampered.
+//!
+//! A valid signature only counts as verified when the signer is the local
+//! keypair, one it replaced, or a verified contact; anyone with push access
+//! can produce a valid signature with a key of their own.
+//!
+//! `verify_activity_log` walks the MAC chain of the local activity log (see
+//! `activity_log`) and reports the first entry that was altered or removed.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{activity_head_in, activity_rows_in, chain_mac, log_key, open_activity};
+use crate::album::{parent_album_path, AlbumManifest, ALBUM_MANIFEST_FILE, ALBUM_ROOT, ENCRYPTED_BLOB_EXT};
+use crate::contacts::load_contacts;
+use crate::crypto::{with_keypair, CryptoError, HybridKeypair, KeypairHandle, PublicBundle};
+use crate::git_data::{branch_head, get_blob, get_json, get_tree_recursive, index_blobs, TreeChange, TreeIndex};
+use crate::github::{
+    fetch_file_bytes, is_image_file, put_file_contents, set_repo_visibility, validate_repo, AppError, GithubError,
+    HttpClient,
+};
+use crate::key_rotation::list_archived_keys;
+use crate::lfs::resolve_lfs_pointer;
+use crate::local_store::{with_store, LocalStore};
+use crate::pipeline::PIPELINE_FILE_EXT;
+use crate::sharding::shard_repos;
+use crate::util::now_secs;
+
+/// Extension of the detached signature stored next to each photo
+pub const SIGNATURE_EXT: &str = "vxsig";
+const PHOTO_SIGNATURE_VERSION: u8 = 1;
+const MANIFEST_DOMAIN: &[u8] = b"vortex-image album manifest v1\0";
+const PHOTO_DOMAIN: &[u8] = b"vortex-image photo v1\0";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditIssue {
+    PublicRepository,
+    UnencryptedAlbum,
+    MissingManifest,
+    UnsignedManifest,
+    /// The manifest was changed after it was signed
+    InvalidManifestSignature,
+    /// At least one photo has no detached signature
+    UnsignedPhotos,
+    /// A photo listed as signed in the manifest has no detached signature
+    MissingPhotoSignatures,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlbumFinding {
+    pub album_path: String,
+    pub issues: Vec<AuditIssue>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RepoAudit {
+    pub repo: String,
+    pub private: bool,
+    /// Issues on the repository itself
+    pub issues: Vec<AuditIssue>,
+    /// Albums with at least one issue
+    pub albums: Vec<AlbumFinding>,
+    /// Set when `auto_fix` made the repository private
+    pub fixed: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SecurityAuditReport {
+    pub repos: Vec<RepoAudit>,
+    /// Issues still open after any fixes
+    pub open_issues: usize,
+    pub fixed: usize,
+}
+
+/// Signature over an album manifest, stored in its `signature` field
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManifestSignature {
+    pub signer: PublicBundle,
+    /// Base64 hybrid signature
+    pub signature: String,
+    pub signed_at: u64,
+}
+
+/// Contents of `<photo>.vxsig`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PhotoSignature {
+    pub version: u8,
+    /// Hex BLAKE3 of the bytes stored in the repository
+    pub blake3: String,
+    pub size: u64,
+    pub signer: PublicBundle,
+    /// Base64 hybrid signature
+    pub signature: String,
+    pub signed_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityStatus {
+    /// Intact and signed by a trusted key
+    Verified,
+    /// Intact, but signed by a key that is neither ours nor a verified contact
+    UntrustedSigner,
+    Unsigned,
+    /// Content does not match its signature
+    Tampered,
+}
+
+/// Outcome of checking one photo or manifest
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IntegrityCheck {
+    pub path: String,
+    pub status: IntegrityStatus,
+    pub signer_key_id: Option<String>,
+    pub signed_at: Option<u64>,
+    /// Why the check failed
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlbumIntegrity {
+    pub album_path: String,
+    /// `None` for folders without a manifest
+    pub manifest: Option<IntegrityCheck>,
+    pub photos: Vec<IntegrityCheck>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub repo: String,
+    pub albums: Vec<AlbumIntegrity>,
+    pub verified: usize,
+    pub untrusted: usize,
+    pub unsigned: usize,
+    pub tampered: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActivityLogCheck {
+    pub entries: u64,
+    pub intact: bool,
+    /// Sequence number where the chain first breaks
+    pub broken_at: Option<u64>,
+    pub detail: Option<String>,
+}
+
+/// Key ids whose signatures count as verified
+#[derive(Clone, Debug, Default)]
+pub struct TrustedSigners(HashSet<String>);
+
+impl TrustedSigners {
+    /// The keypair behind `handle`, keypairs it replaced and verified contacts
+    pub fn load(handle: Option<KeypairHandle>) -> Self {
+        let mut trusted = Self::default();
+        if let Some(key_id) = handle.and_then(|h| with_keypair(h, |kp| Ok(kp.public_bundle().key_id)).ok()) {
+            trusted.insert(key_id);
+        }
+        for archived in list_archived_keys().unwrap_or_default() {
+            trusted.insert(archived.key_id);
+        }
+        if let Ok(book) = load_contacts() {
+            for contact in book.contacts.into_iter().filter(|c| c.verified) {
+                trusted.insert(contact.bundle.derived_key_id());
+            }
+        }
+        trusted
+    }
+
+    pub fn insert(&mut self, key_id: String) {
+        self.0.insert(key_id);
+    }
+
+    pub fn contains(&self, key_id: &str) -> bool {
+        self.0.contains(key_id)
+    }
+}
+
+// ============================================================================
+// Signing
+// ============================================================================
+
+pub fn signature_path(photo_path: &str) -> String {
+    format!("{}.{}", photo_path, SIGNATURE_EXT)
+}
+
+fn manifest_message(manifest: &AlbumManifest) -> Result<Vec<u8>, CryptoError> {
+    let mut unsigned = manifest.clone();
+    unsigned.signature = None;
+    let mut message = MANIFEST_DOMAIN.to_vec();
+    message.extend(serde_json::to_vec(&unsigned).map_err(|e| CryptoError::InvalidInput(e.to_string()))?);
+    Ok(message)
+}
+
+fn photo_message(hash: &blake3::Hash, size: u64) -> Vec<u8> {
+    let mut message = PHOTO_DOMAIN.to_vec();
+    message.extend_from_slice(hash.as_bytes());
+    message.extend_from_slice(&size.to_le_bytes());
+    message
+}
+
+pub fn sign_manifest(manifest: &AlbumManifest, keypair: &HybridKeypair) -> Result<ManifestSignature, CryptoError> {
+    let signature = keypair.sign(&manifest_message(manifest)?)?;
+    Ok(ManifestSignature {
+        signer: keypair.public_bundle(),
+        signature: STANDARD.encode(signature),
+        signed_at: now_secs(),
+    })
+}
+
+/// The `.vxsig` file for `content`, as stored in the repository
+pub fn sign_photo(handle: KeypairHandle, content: &[u8]) -> Result<Vec<u8>, AppError> {
+    let hash = blake3::hash(content);
+    let size = content.len() as u64;
+    let record = with_keypair(handle, |kp| {
+        Ok(PhotoSignature {
+            version: PHOTO_SIGNATURE_VERSION,
+            blake3: hash.to_hex().to_string(),
+            size,
+            signer: kp.public_bundle(),
+            signature: STANDARD.encode(kp.sign(&photo_message(&hash, size))?),
+            signed_at: now_secs(),
+        })
+    })
+    .map_err(|e| AppError::Validation(e.to_string()))?;
+    serde_json::to_vec_pretty(&record).map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))
+}
+
+// ============================================================================
+// Verification
+// ============================================================================
+
+fn tampered(path: &str, signer: Option<&PublicBundle>, detail: &str) -> IntegrityCheck {
+    IntegrityCheck {
+        path: path.to_string(),
+        status: IntegrityStatus::Tampered,
+        signer_key_id: signer.map(|s| s.key_id.clone()),
+        signed_at: None,
+        detail: Some(detail.to_string()),
+    }
+}
+
+fn unsigned(path: &str) -> IntegrityCheck {
+    IntegrityCheck {
+        path: path.to_string(),
+        status: IntegrityStatus::Unsigned,
+        signer_key_id: None,
+        signed_at: None,
+        detail: None,
+    }
+}
+
+/// Check `signature` over `message` by `signer`, then whether we trust them
+fn check_signature(
+    path: &str,
+    message: &[u8],
+    signer: &PublicBundle,
+    signature: &str,
+    signed_at: u64,
+    trusted: &TrustedSigners,
+) -> IntegrityCheck {
+    if signer.derived_key_id() != signer.key_id {
+        return tampered(path, Some(signer), "Signer key id does not match its key");
+    }
+    let valid = STANDARD
+        .decode(signature)
+        .map(|sig| signer.verify(message, &sig).is_ok())
+        .unwrap_or(false);
+    if !valid {
+        return tampered(path, Some(signer), "Signature does not match the content");
+    }
+    IntegrityCheck {
+        path: path.to_string(),
+        status: if trusted.contains(&signer.key_id) {
+            IntegrityStatus::Verified
+        } else {
+            IntegrityStatus::UntrustedSigner
+        },
+        signer_key_id: Some(signer.key_id.clone()),
+        signed_at: Some(signed_at),
+        detail: None,
+    }
+}
+
+pub fn check_manifest(path: &str, manifest: &AlbumManifest, trusted: &TrustedSigners) -> IntegrityCheck {
+    let Some(sig) = &manifest.signature else {
+        return unsigned(path);
+    };
+    match manifest_message(manifest) {
+        Ok(message) => check_signature(path, &message, &sig.signer, &sig.signature, sig.signed_at, trusted),
+        Err(e) => tampered(path, Some(&sig.signer), &e.to_string()),
+    }
+}
+
+/// Check photo bytes as stored in the repository against their `.vxsig`
+pub fn check_photo(path: &str, content: &[u8], signature: Option<&[u8]>, trusted: &TrustedSigners) -> IntegrityCheck {
+    let Some(raw) = signature else {
+        return unsigned(path);
+    };
+    let Ok(sig) = serde_json::from_slice::<PhotoSignature>(raw) else {
+        return tampered(path, None, "Signature file is unreadable");
+    };
+    let hash = blake3::hash(content);
+    if sig.size != content.len() as u64 || !sig.blake3.eq_ignore_ascii_case(hash.to_hex().as_str()) {
+        return tampered(path, Some(&sig.signer), "Content hash does not match the signed hash");
+    }
+    check_signature(
+        path,
+        &photo_message(&hash, sig.size),
+        &sig.signer,
+        &sig.signature,
+        sig.signed_at,
+        trusted,
+    )
+}
+
+/// `check_photo` for a photo whose album manifest may list it as signed. A
+/// listed photo without a `.vxsig`, or with other content, is tampered.
+pub fn check_album_photo(
+    path: &str,
+    content: &[u8],
+    signature: Option<&[u8]>,
+    manifest: Option<&AlbumManifest>,
+    trusted: &TrustedSigners,
+) -> IntegrityCheck {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    if let Some(listed) = manifest.and_then(|m| m.signed.get(name)) {
+        if signature.is_none() {
+            return tampered(path, None, "Signature listed in the album manifest is missing");
+        }
+        if !listed.eq_ignore_ascii_case(blake3::hash(content).to_hex().as_str()) {
+            return tampered(path, None, "Content does not match the hash in the album manifest");
+        }
+    }
+    check_photo(path, content, signature, trusted)
+}
+
+/// Fail on tampered content, pass everything else through for the caller to report
+pub fn ensure_intact(check: IntegrityCheck) -> Result<IntegrityCheck, AppError> {
+    if check.status == IntegrityStatus::Tampered {
+        return Err(AppError::Validation(format!(
+            "Integrity check failed for {}: {}",
+            check.path,
+            check.detail.as_deref().unwrap_or("tampered")
+        )));
+    }
+    Ok(check)
+}
+
+/// Tree changes that carry a photo's `.vxsig` along when it moves from
+/// `from` to `to`; empty if the photo is unsigned
+pub fn move_signature(index: &TreeIndex, from: &str, to: &str) -> Vec<TreeChange> {
+    match index.get(&signature_path(from)) {
+        Some(entry) => vec![
+            TreeChange::put_blob(&signature_path(to), entry),
+            TreeChange::delete(&signature_path(from)),
+        ],
+        None => Vec::new(),
+    }
+}
+
+/// Upload the `.vxsig` made by `sign_photo` for the photo at `photo_path`
+pub(crate) async fn put_photo_signature(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    photo_path: &str,
+    signature: &[u8],
+) -> Result<(), AppError> {
+    let message = format!("Sign {}", photo_path);
+    put_file_contents(client, repo, token, &signature_path(photo_path), signature, &message, None).await?;
+    Ok(())
+}
+
+/// The `.vxsig` of a photo through the contents API, `None` if it has none
+pub(crate) async fn fetch_photo_signature(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    photo_path: &str,
+) -> Result<Option<Vec<u8>>, AppError> {
+    match fetch_file_bytes(client, repo, token, &signature_path(photo_path)).await {
+        Ok(raw) => Ok(Some(raw)),
+        Err(AppError::Github(GithubError::NotFound { .. })) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// ============================================================================
+// Analysis
+// ============================================================================
+
+fn is_photo_blob(path: &str) -> bool {
+    let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("");
+    ext == ENCRYPTED_BLOB_EXT || ext == PIPELINE_FILE_EXT || is_image_file(Path::new(path))
+}
+
+/// Photos directly inside `album`
+fn album_photos<'a>(index: &'a TreeIndex, album: &str) -> Vec<&'a String> {
+    let mut photos: Vec<&String> = index
+        .keys()
+        .filter(|p| is_photo_blob(p) && parent_album_path(p) == album)
+        .collect();
+    photos.sort();
+    photos
+}
+
+/// Whether any photo inside `album` lacks a `.vxsig`
+pub fn has_unsigned_photos(index: &TreeIndex, album: &str) -> bool {
+    album_photos(index, album)
+        .iter()
+        .any(|p| !index.contains_key(&signature_path(p)))
+}
+
+/// Whether a photo inside `album` that `manifest` lists as signed lacks its `.vxsig`
+pub fn has_missing_signatures(index: &TreeIndex, album: &str, manifest: &AlbumManifest) -> bool {
+    manifest.signed.keys().any(|name| {
+        let path = format!("{}/{}", album, name);
+        index.contains_key(&path) && !index.contains_key(&signature_path(&path))
+    })
+}
+
+/// Folders below `photos/` that directly contain photos or an album manifest
+pub fn album_dirs(index: &TreeIndex) -> BTreeSet<String> {
+    let root = format!("{}/", ALBUM_ROOT);
+    index
+        .keys()
+        .filter(|p| p.starts_with(&root))
+        .filter(|p| is_photo_blob(p) || p.ends_with(&format!("/{}", ALBUM_MANIFEST_FILE)))
+        .map(|p| parent_album_path(p).to_string())
+        .collect()
+}
+
+/// Issues of one album given its manifest, if any
+pub fn album_issues(manifest: Option<&serde_json::Value>) -> Vec<AuditIssue> {
+    let Some(manifest) = manifest else {
+        return vec![AuditIssue::MissingManifest];
+    };
+    let mut issues = Vec::new();
+    if !manifest["encrypted"].as_bool().unwrap_or(false) {
+        issues.push(AuditIssue::UnencryptedAlbum);
+    }
+    if manifest.get("signature").map(|s| s.is_null()).unwrap_or(true) {
+        issues.push(AuditIssue::UnsignedManifest);
+    }
+    issues
+}
+
+async fn audit_repo(client: &Client, token: &str, repo: &str, auto_fix: bool) -> Result<RepoAudit, AppError> {
+    let url = format!("https://api.github.com/repos/{}", repo);
+    let info = get_json(client, token, &url, "get repository info").await?;
+    let mut audit = RepoAudit {
+        repo: repo.to_string(),
+        private: info["private"].as_bool().unwrap_or(false),
+        issues: Vec::new(),
+        albums: Vec::new(),
+        fixed: false,
+        error: None,
+    };
+
+    if !audit.private {
+        if auto_fix {
+            audit.private = set_repo_visibility(client, token, repo, true).await?.private;
+            audit.fixed = audit.private;
+        }
+        if !audit.private {
+            audit.issues.push(AuditIssue::PublicRepository);
+        }
+    }
+
+    // An empty repository has no branch to inspect
+    if info["size"].as_u64() == Some(0) {
+        return Ok(audit);
+    }
+
+    let head = branch_head(client, repo, token).await?;
+    let index = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+
+    for album in album_dirs(&index) {
+        let manifest: Option<serde_json::Value> = match index.get(&format!("{}/{}", album, ALBUM_MANIFEST_FILE)) {
+            Some(entry) => serde_json::from_slice(&get_blob(client, repo, token, &entry.sha).await?).ok(),
+            None => None,
+        };
+        let mut issues = album_issues(manifest.as_ref());
+        let parsed = manifest.and_then(|m| serde_json::from_value::<AlbumManifest>(m).ok());
+        if let Some(parsed) = parsed.filter(|m| m.signature.is_some()) {
+            if check_manifest(&album, &parsed, &TrustedSigners::default()).status == IntegrityStatus::Tampered {
+                issues.push(AuditIssue::InvalidManifestSignature);
+            }
+            if has_missing_signatures(&index, &album, &parsed) {
+                issues.push(AuditIssue::MissingPhotoSignatures);
+            }
+        }
+        if has_unsigned_photos(&index, &album) {
+            issues.push(AuditIssue::UnsignedPhotos);
+        }
+        if !issues.is_empty() {
+            audit.albums.push(AlbumFinding { album_path: album, issues });
+        }
+    }
+
+    Ok(audit)
+}
+
+/// Walk the activity log's MAC chain from the first entry to the head
+pub fn check_activity_log_in(store: &LocalStore) -> Result<ActivityLogCheck, AppError> {
+    let rows = activity_rows_in(store)?;
+    let entries = rows.len() as u64;
+    let broken = |seq: u64, detail: &str| ActivityLogCheck {
+        entries,
+        intact: false,
+        broken_at: Some(seq),
+        detail: Some(detail.to_string()),
+    };
+
+    let key = log_key(store);
+    let mut prev = [0u8; 32];
+    for (expected, row) in (1..).zip(&rows) {
+        if row.seq != expected {
+            return Ok(broken(expected, "Entry was removed"));
+        }
+        let mac = chain_mac(&key, &prev, row.seq, &row.record);
+        if row.mac[..] != mac[..] {
+            return Ok(broken(row.seq, "Entry was altered"));
+        }
+        if !open_activity(store, row.seq, &row.record).is_ok_and(|e| e.seq == row.seq) {
+            return Ok(broken(row.seq, "Entry cannot be read"));
+        }
+        prev = mac;
+    }
+
+    match activity_head_in(store) {
+        Ok(None) if rows.is_empty() => {}
+        Ok(Some(head)) if head.seq == entries && head.mac == prev => {}
+        Ok(Some(head)) if head.seq > entries => return Ok(broken(entries + 1, "Newest entries were removed")),
+        _ => return Ok(broken(entries, "Log head does not match the entries")),
+    }
+    Ok(ActivityLogCheck {
+        entries,
+        intact: true,
+        broken_at: None,
+        detail: None,
+    })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Audit the library behind `repo` (including shard repositories).
+/// `auto_fix` makes public repositories private.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn security_audit_albums(
+    client: State<'_, HttpClient>,
+    token: Zeroizing<String>,
+    repo: String,
+    auto_fix: bool,
+) -> Result<SecurityAuditReport, AppError> {
+    validate_repo(&repo)?;
+
+    let mut repos = shard_repos(&client.0, &repo, &token).await?;
+    if !repos.contains(&repo) {
+        repos.insert(0, repo.clone());
+    }
+
+    let mut audits = Vec::with_capacity(repos.len());
+    for r in repos {
+        let audit = match audit_repo(&client.0, &token, &r, auto_fix).await {
+            Ok(audit) => audit,
+            Err(e) => RepoAudit {
+                repo: r,
+                private: false,
+                issues: Vec::new(),
+                albums: Vec::new(),
+                fixed: false,
+                error: Some(e.to_string()),
+            },
+        };
+        audits.push(audit);
+    }
+
+    let open_issues = audits
+        .iter()
+        .map(|a| a.issues.len() + a.albums.iter().map(|f| f.issues.len()).sum::<usize>())
+        .sum();
+    let fixed = audits.iter().filter(|a| a.fixed).count();
+
+    Ok(SecurityAuditReport {
+        repos: audits,
+        open_issues,
+        fixed,
+    })
+}
+
+/// Check every photo and manifest of the albums at or below `album_path`
+/// (the whole library by default) against their signatures
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn verify_album_integrity(
+    client: State<'_, HttpClient>,
+    token: Zeroizing<String>,
+    repo: String,
+    album_path: Option<String>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<IntegrityReport, AppError> {
+    verify_integrity(&client.0, &token, &repo, album_path.as_deref(), keypair_handle).await
+}
+
+/// Verification without the Tauri state, for the command above and the CLI
+pub(crate) async fn verify_integrity(
+    client: &Client,
+    token: &str,
+    repo: &str,
+    album_path: Option<&str>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<IntegrityReport, AppError> {
+    validate_repo(repo)?;
+    let root = album_path
+        .map(|p| p.trim_matches('/'))
+        .filter(|p| !p.is_empty())
+        .unwrap_or(ALBUM_ROOT)
+        .to_string();
+    if root.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    let trusted = TrustedSigners::load(keypair_handle);
+    let head = branch_head(client, repo, token).await?;
+    let index = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+    let prefix = format!("{}/", root);
+
+    let mut albums = Vec::new();
+    for album in album_dirs(&index).into_iter().filter(|a| *a == root || a.starts_with(&prefix)) {
+        let manifest_path = format!("{}/{}", album, ALBUM_MANIFEST_FILE);
+        let (manifest, parsed) = match index.get(&manifest_path) {
+            Some(entry) => {
+                let raw = get_blob(client, repo, token, &entry.sha).await?;
+                match serde_json::from_slice::<AlbumManifest>(&raw) {
+                    Ok(manifest) => (Some(check_manifest(&manifest_path, &manifest, &trusted)), Some(manifest)),
+                    Err(_) => (Some(tampered(&manifest_path, None, "Manifest is not valid JSON")), None),
+                }
+            }
+            None => (None, None),
+        };
+
+        let mut photos = Vec::new();
+        for path in album_photos(&index, &album) {
+            let content = resolve_lfs_pointer(
+                client,
+                repo,
+                token,
+                get_blob(client, repo, token, &index[path].sha).await?,
+            )
+            .await?;
+            let signature = match index.get(&signature_path(path)) {
+                Some(entry) => Some(get_blob(client, repo, token, &entry.sha).await?),
+                None => None,
+            };
+            photos.push(check_album_photo(path, &content, signature.as_deref(), parsed.as_ref(), &trusted));
+        }
+
+        albums.push(AlbumIntegrity {
+            album_path: album,
+            manifest,
+            photos,
+        });
+    }
+
+    let count = |status| {
+        albums
+            .iter()
+            .flat_map(|a| a.manifest.iter().chain(&a.photos))
+            .filter(|c| c.status == status)
+            .count()
+    };
+
+    Ok(IntegrityReport {
+        verified: count(IntegrityStatus::Verified),
+        untrusted: count(IntegrityStatus::UntrustedSigner),
+        unsigned: count(IntegrityStatus::Unsigned),
+        tampered: count(IntegrityStatus::Tampered),
+        repo: repo.to_string(),
+        albums,
+    })
+}
+
+/// Check that no entry of the local activity log was altered or removed
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn verify_activity_log() -> Result<ActivityLogCheck, AppError> {
+    with_store(check_activity_log_in)
+}
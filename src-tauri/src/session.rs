@@ -0,0 +1,458 @@
+//! Forward-Secret Sessions
+//!
+//! A ratcheting channel between two Vortex users, for messages that should
+//! not all fall with one key. `start_session` builds a signed handshake in
+//! the style of X3DH, with the hybrid KEM standing in for the prekey bundle:
+//!
+//! - a random 32-byte seed, sealed to the responder's bundle with the hybrid
+//!   ML-KEM + X25519 KEM (`encrypt_with_aad`);
+//! - DH(initiator identity, responder identity);
+//! - DH(initiator ephemeral, responder identity).
+//!
+//! All three go through HKDF-SHA512, bound to the handshake transcript, into
+//! a root key; the initiator signs the transcript with its hybrid signature,
+//! so `accept_session` knows who it is talking to.
+//!
+//! The root key yields one chain per direction. Every message advances its
+//! chain with a keyed BLAKE3 step (`CK' = H(CK, 0x02)`, `MK = H(CK, 0x01)`),
+//! so each message has its own key and the keys behind it are gone: a leaked
+//! message key exposes one message, a leaked session state none of the
+//! messages before it. Keys of messages that arrive out of order are kept
+//! (at most `MAX_SKIPPED_KEYS`) until they are used once.
+//!
+//! Sessions live in memory only and end with `close_session` or a profile
+//! switch. Transport is up to the caller, e.g. secure message threads.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use hkdf::Hkdf;
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha512;
+use x25519_dalek::{PublicKey as X25519Public, StaticSecret};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::contacts::resolve_recipient;
+use crate::crypto::{
+    decrypt_with_aad, decrypt_with_key, encrypt_with_aad, encrypt_with_key, with_keypair, CryptoError,
+    EncryptedPayload, HybridKeypair, KeypairHandle, PublicBundle,
+};
+use crate::github::{AppError, GithubError};
+use crate::util::now_secs;
+
+const HANDSHAKE_VERSION: u8 = 1;
+/// Domain separator for the root key
+const RATCHET_KDF_DOMAIN: &[u8] = b"vortex-ratchet-v1";
+const HANDSHAKE_DOMAIN: &[u8] = b"vortex-session-handshake-v1";
+const MESSAGE_KEY_STEP: u8 = 0x01;
+const CHAIN_KEY_STEP: u8 = 0x02;
+/// Most messages a peer may skip ahead in one go
+const MAX_SKIP: u32 = 1000;
+/// Most out-of-order message keys kept per session
+const MAX_SKIPPED_KEYS: usize = 1000;
+/// Largest message a session encrypts
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+lazy_static::lazy_static! {
+    static ref SESSIONS: Mutex<HashMap<String, RatchetSession>> = Mutex::new(HashMap::new());
+}
+
+/// First message of a session, from initiator to responder
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionHandshake {
+    pub version: u8,
+    pub session_id: String,
+    pub initiator: PublicBundle,
+    /// Key id of the bundle the seed is sealed to
+    pub responder_key_id: String,
+    /// Initiator's ephemeral X25519 key
+    pub ephemeral: [u8; 32],
+    pub sealed_seed: EncryptedPayload,
+    pub created_at: u64,
+    /// Initiator's hybrid signature over the transcript
+    pub signature: Vec<u8>,
+}
+
+/// One message within a session
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionMessage {
+    pub session_id: String,
+    /// Position in the sender's chain
+    pub counter: u32,
+    /// `[nonce: 12][ciphertext]` under this message's key
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub peer_key_id: String,
+    pub initiator: bool,
+    pub sent: u32,
+    pub received: u32,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionStart {
+    pub session: SessionInfo,
+    /// Deliver to the responder, who passes it to `accept_session`
+    pub handshake: SessionHandshake,
+}
+
+// ============================================================================
+// Symmetric Ratchet
+// ============================================================================
+
+/// One direction of a session
+#[derive(Clone, Zeroize)]
+struct Chain {
+    key: [u8; 32],
+    /// Counter of the next message key
+    counter: u32,
+}
+
+impl Chain {
+    /// Message key for `counter`, moving the chain one step forward
+    fn step(&mut self) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+        let message_key = Zeroizing::new(*blake3::keyed_hash(&self.key, &[MESSAGE_KEY_STEP]).as_bytes());
+        self.key = *blake3::keyed_hash(&self.key, &[CHAIN_KEY_STEP]).as_bytes();
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| CryptoError::InvalidInput("session chain exhausted".into()))?;
+        Ok(message_key)
+    }
+}
+
+pub struct RatchetSession {
+    id: String,
+    peer: PublicBundle,
+    initiator: bool,
+    send: Chain,
+    recv: Chain,
+    /// Keys of messages skipped over, by counter
+    skipped: BTreeMap<u32, [u8; 32]>,
+    created_at: u64,
+}
+
+impl Drop for RatchetSession {
+    fn drop(&mut self) {
+        self.send.zeroize();
+        self.recv.zeroize();
+        for key in self.skipped.values_mut() {
+            key.zeroize();
+        }
+    }
+}
+
+fn message_aad(session_id: &str, from_initiator: bool, counter: u32) -> Vec<u8> {
+    let role = if from_initiator { "i" } else { "r" };
+    format!("{}#{}#{}", session_id, role, counter).into_bytes()
+}
+
+impl RatchetSession {
+    fn from_root(id: String, peer: PublicBundle, initiator: bool, root: &[u8; 32]) -> Result<Self, CryptoError> {
+        let hk = Hkdf::<Sha512>::new(Some(RATCHET_KDF_DOMAIN), root);
+        let mut from_initiator = [0u8; 32];
+        let mut from_responder = [0u8; 32];
+        hk.expand(b"initiator chain", &mut from_initiator)
+            .and_then(|_| hk.expand(b"responder chain", &mut from_responder))
+            .map_err(|_| CryptoError::KeyDerivation("hkdf expand failed".into()))?;
+
+        let (send, recv) = if initiator {
+            (from_initiator, from_responder)
+        } else {
+            (from_responder, from_initiator)
+        };
+        let session = Self {
+            id,
+            peer,
+            initiator,
+            send: Chain { key: send, counter: 0 },
+            recv: Chain { key: recv, counter: 0 },
+            skipped: BTreeMap::new(),
+            created_at: now_secs(),
+        };
+        from_initiator.zeroize();
+        from_responder.zeroize();
+        Ok(session)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn info(&self) -> SessionInfo {
+        SessionInfo {
+            session_id: self.id.clone(),
+            peer_key_id: self.peer.derived_key_id(),
+            initiator: self.initiator,
+            sent: self.send.counter,
+            received: self.recv.counter,
+            created_at: self.created_at,
+        }
+    }
+
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<SessionMessage, CryptoError> {
+        if plaintext.len() > MAX_MESSAGE_SIZE {
+            return Err(CryptoError::InvalidInput(format!(
+                "session messages are limited to {} bytes",
+                MAX_MESSAGE_SIZE
+            )));
+        }
+        let counter = self.send.counter;
+        let key = self.send.step()?;
+        let ciphertext = encrypt_with_key(plaintext, &key, &message_aad(&self.id, self.initiator, counter))?;
+        Ok(SessionMessage {
+            session_id: self.id.clone(),
+            counter,
+            ciphertext,
+        })
+    }
+
+    /// Decrypt a message from the peer. Each message decrypts once; its key
+    /// is deleted afterwards.
+    pub fn decrypt(&mut self, message: &SessionMessage) -> Result<Vec<u8>, CryptoError> {
+        if message.session_id != self.id {
+            return Err(CryptoError::InvalidInput("message belongs to another session".into()));
+        }
+        let aad = message_aad(&self.id, !self.initiator, message.counter);
+
+        if message.counter < self.recv.counter {
+            let key = self
+                .skipped
+                .get(&message.counter)
+                .ok_or_else(|| CryptoError::InvalidInput("message was already decrypted or has expired".into()))?;
+            let plaintext = decrypt_with_key(&message.ciphertext, key, &aad)?;
+            if let Some(mut key) = self.skipped.remove(&message.counter) {
+                key.zeroize();
+            }
+            return Ok(plaintext);
+        }
+        if message.counter - self.recv.counter > MAX_SKIP {
+            return Err(CryptoError::InvalidInput("message is too far ahead of the session".into()));
+        }
+
+        // Work on a copy so a forged message cannot advance the chain
+        let mut chain = self.recv.clone();
+        let mut skipped = Vec::new();
+        while chain.counter < message.counter {
+            let counter = chain.counter;
+            skipped.push((counter, chain.step()?));
+        }
+        let key = chain.step()?;
+        let plaintext = decrypt_with_key(&message.ciphertext, &key, &aad)?;
+
+        self.recv.zeroize();
+        self.recv = chain;
+        for (counter, key) in skipped {
+            self.skipped.insert(counter, *key);
+        }
+        while self.skipped.len() > MAX_SKIPPED_KEYS {
+            if let Some((_, mut key)) = self.skipped.pop_first() {
+                key.zeroize();
+            }
+        }
+        Ok(plaintext)
+    }
+}
+
+// ============================================================================
+// Handshake
+// ============================================================================
+
+fn seed_aad(session_id: &str) -> Vec<u8> {
+    format!("{}#seed", session_id).into_bytes()
+}
+
+impl SessionHandshake {
+    /// Bytes covered by the initiator's signature and bound into the root key
+    fn transcript(&self) -> Result<Vec<u8>, CryptoError> {
+        let sealed = serde_json::to_vec(&self.sealed_seed)
+            .map_err(|e| CryptoError::InvalidInput(e.to_string()))?;
+        let mut out = Vec::with_capacity(256 + sealed.len());
+        out.extend_from_slice(HANDSHAKE_DOMAIN);
+        out.push(self.version);
+        for part in [self.session_id.as_bytes(), self.responder_key_id.as_bytes(), &sealed] {
+            out.extend_from_slice(&(part.len() as u32).to_le_bytes());
+            out.extend_from_slice(part);
+        }
+        out.extend_from_slice(&self.initiator.fingerprint_bytes());
+        out.extend_from_slice(&self.ephemeral);
+        out.extend_from_slice(&self.created_at.to_le_bytes());
+        Ok(out)
+    }
+}
+
+fn root_key(seed: &[u8], identity_dh: &[u8; 32], ephemeral_dh: &[u8; 32], transcript: &[u8]) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    let mut ikm = Zeroizing::new(Vec::with_capacity(96));
+    ikm.extend_from_slice(seed);
+    ikm.extend_from_slice(identity_dh);
+    ikm.extend_from_slice(ephemeral_dh);
+    let hk = Hkdf::<Sha512>::new(Some(RATCHET_KDF_DOMAIN), &ikm);
+    let mut root = Zeroizing::new([0u8; 32]);
+    hk.expand(blake3::hash(transcript).as_bytes(), root.as_mut())
+        .map_err(|_| CryptoError::KeyDerivation("hkdf expand failed".into()))?;
+    Ok(root)
+}
+
+/// Open a session with `responder`; send the handshake to them
+pub fn initiate(keypair: &HybridKeypair, responder: &PublicBundle) -> Result<(RatchetSession, SessionHandshake), CryptoError> {
+    let mut id = [0u8; 16];
+    OsRng.fill_bytes(&mut id);
+    let session_id = hex::encode(id);
+
+    let mut seed = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(seed.as_mut());
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+
+    let mut handshake = SessionHandshake {
+        version: HANDSHAKE_VERSION,
+        sealed_seed: encrypt_with_aad(seed.as_ref(), responder, Some(&seed_aad(&session_id)))?,
+        session_id,
+        initiator: keypair.public_bundle(),
+        responder_key_id: responder.derived_key_id(),
+        ephemeral: X25519Public::from(&ephemeral).to_bytes(),
+        created_at: now_secs(),
+        signature: Vec::new(),
+    };
+    let transcript = handshake.transcript()?;
+    handshake.signature = keypair.sign(&transcript)?;
+
+    let identity_dh = keypair.x25519_agree(&responder.x25519);
+    let ephemeral_dh = Zeroizing::new(ephemeral.diffie_hellman(&X25519Public::from(responder.x25519)).to_bytes());
+    let root = root_key(seed.as_ref(), &identity_dh, &ephemeral_dh, &transcript)?;
+
+    let session = RatchetSession::from_root(handshake.session_id.clone(), responder.clone(), true, &root)?;
+    Ok((session, handshake))
+}
+
+/// Verify a handshake addressed to `keypair` and open its side of the session
+pub fn accept(keypair: &HybridKeypair, handshake: &SessionHandshake) -> Result<RatchetSession, CryptoError> {
+    if handshake.version != HANDSHAKE_VERSION {
+        return Err(CryptoError::InvalidInput(format!(
+            "unsupported handshake version {}",
+            handshake.version
+        )));
+    }
+    if handshake.responder_key_id != keypair.public_bundle().derived_key_id() {
+        return Err(CryptoError::InvalidInput("handshake is addressed to another key".into()));
+    }
+    let transcript = handshake.transcript()?;
+    handshake.initiator.verify(&transcript, &handshake.signature)?;
+
+    let seed = Zeroizing::new(decrypt_with_aad(
+        &handshake.sealed_seed,
+        keypair,
+        Some(&seed_aad(&handshake.session_id)),
+    )?);
+    let identity_dh = keypair.x25519_agree(&handshake.initiator.x25519);
+    let ephemeral_dh = keypair.x25519_agree(&handshake.ephemeral);
+    let root = root_key(&seed, &identity_dh, &ephemeral_dh, &transcript)?;
+
+    RatchetSession::from_root(handshake.session_id.clone(), handshake.initiator.clone(), false, &root)
+}
+
+// ============================================================================
+// Session Store
+// ============================================================================
+
+fn store_session(session: RatchetSession) -> Result<SessionInfo, AppError> {
+    let info = session.info();
+    let mut sessions = SESSIONS.lock().unwrap();
+    if sessions.contains_key(session.id()) {
+        return Err(AppError::Validation("Session is already open".into()));
+    }
+    sessions.insert(session.id().to_string(), session);
+    Ok(info)
+}
+
+fn with_session<T>(session_id: &str, f: impl FnOnce(&mut RatchetSession) -> Result<T, CryptoError>) -> Result<T, AppError> {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let session = sessions.get_mut(session_id).ok_or_else(|| {
+        AppError::from(GithubError::NotFound {
+            message: format!("Session {} not found", session_id),
+        })
+    })?;
+    f(session).map_err(|e| AppError::Validation(e.to_string()))
+}
+
+/// Drop every open session, e.g. when switching profiles
+pub(crate) fn close_all_sessions() {
+    SESSIONS.lock().unwrap().clear();
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Start a session with a public bundle or a contact
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn start_session(
+    keypair_handle: KeypairHandle,
+    bundle: Option<PublicBundle>,
+    contact_id: Option<String>,
+) -> Result<SessionStart, AppError> {
+    let responder = resolve_recipient(bundle, contact_id.as_deref())?;
+    let (session, handshake) = with_keypair(keypair_handle, |kp| initiate(kp, &responder))
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    Ok(SessionStart {
+        session: store_session(session)?,
+        handshake,
+    })
+}
+
+/// Accept a handshake. With `contact_id`, the initiator must be that contact.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn accept_session(
+    keypair_handle: KeypairHandle,
+    handshake: SessionHandshake,
+    contact_id: Option<String>,
+) -> Result<SessionInfo, AppError> {
+    if let Some(id) = contact_id {
+        let contact = crate::contacts::contact_bundle(&id)?;
+        if contact.fingerprint_bytes() != handshake.initiator.fingerprint_bytes() {
+            return Err(AppError::Validation("Handshake was not sent by this contact".into()));
+        }
+    }
+    let session = with_keypair(keypair_handle, |kp| accept(kp, &handshake))
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    store_session(session)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn session_encrypt(session_id: String, plaintext: Vec<u8>) -> Result<SessionMessage, AppError> {
+    let plaintext = Zeroizing::new(plaintext);
+    with_session(&session_id, |session| session.encrypt(&plaintext))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn session_decrypt(message: SessionMessage) -> Result<Vec<u8>, AppError> {
+    with_session(&message.session_id.clone(), |session| session.decrypt(&message))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn list_sessions() -> Vec<SessionInfo> {
+    let mut sessions: Vec<SessionInfo> = SESSIONS.lock().unwrap().values().map(RatchetSession::info).collect();
+    sessions.sort_by_key(|s| s.created_at);
+    sessions
+}
+
+/// End a session and wipe its keys
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn close_session(session_id: String) -> Result<(), AppError> {
+    SESSIONS.lock().unwrap().remove(&session_id).map(|_| ()).ok_or_else(|| {
+        GithubError::NotFound {
+            message: format!("Session {} not found", session_id),
+        }
+        .into()
+    })
+}
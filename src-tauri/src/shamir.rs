@@ -0,0 +1,263 @@
+//! Shamir Key Shares
+//!
+//! `split_key_shares` splits the serialized keypair behind a handle into
+//! `shares` pieces so that any `threshold` of them rebuild it and fewer reveal
+//! nothing about it. Sharing is done byte-wise over GF(2^8) with random
+//! polynomials of degree `threshold - 1`.
+//!
+//! The secret that gets split is `[checksum: 16][keypair bytes]`, so a wrong
+//! mix of shares is detected on recovery instead of producing a broken key.
+//! Each share is a self-describing string,
+//! `vortex-share-1:<base64 of [set id: 8][threshold][total][index][data]>`,
+//! meant to be printed or handed to a trusted person or device.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::crypto::{register_keypair, with_keypair, HybridKeypair, KeypairHandle, KeypairInfo};
+use crate::github::AppError;
+
+const SHARE_PREFIX: &str = "vortex-share-1:";
+const HEADER_LEN: usize = 11;
+const CHECKSUM_LEN: usize = 16;
+
+/// One share as handed to the user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyShare {
+    pub index: u8,
+    pub threshold: u8,
+    pub total: u8,
+    /// Shared by all shares of one split, to tell sets apart
+    pub set_id: String,
+    /// Key the shares rebuild
+    pub key_id: String,
+    /// Encoded share, the only part needed for recovery
+    pub share: String,
+}
+
+/// A decoded share
+#[derive(Clone, Debug, PartialEq)]
+pub struct Share {
+    pub set_id: [u8; 8],
+    pub threshold: u8,
+    pub total: u8,
+    pub index: u8,
+    pub data: Vec<u8>,
+}
+
+// ============================================================================
+// GF(2^8)
+// ============================================================================
+
+/// Multiplication modulo x^8 + x^4 + x^3 + x + 1, without data-dependent branches
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse as a^254
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+// ============================================================================
+// Splitting
+// ============================================================================
+
+fn checksum(secret: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = blake3::derive_key("vortex-image shamir checksum v1", secret);
+    hash[..CHECKSUM_LEN].try_into().expect("hash is 32 bytes")
+}
+
+/// Split `secret` into `total` shares, any `threshold` of which recover it
+pub fn split_secret(secret: &[u8], threshold: u8, total: u8) -> Result<Vec<Share>, AppError> {
+    if threshold < 2 {
+        return Err(AppError::Validation("Threshold must be at least 2".into()));
+    }
+    if total < threshold {
+        return Err(AppError::Validation("Cannot create fewer shares than the threshold".into()));
+    }
+    if secret.is_empty() {
+        return Err(AppError::Validation("Nothing to split".into()));
+    }
+
+    let mut payload = Zeroizing::new(Vec::with_capacity(CHECKSUM_LEN + secret.len()));
+    payload.extend_from_slice(&checksum(secret));
+    payload.extend_from_slice(secret);
+
+    // Coefficients 1..threshold of the polynomial for every byte
+    let degree = threshold as usize - 1;
+    let mut coefficients = Zeroizing::new(vec![0u8; degree * payload.len()]);
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut coefficients);
+
+    let set_id: [u8; 8] = rand::random();
+    Ok((1..=total)
+        .map(|x| {
+            let data = payload
+                .iter()
+                .enumerate()
+                .map(|(i, &s)| {
+                    // Horner, highest coefficient first
+                    let coeffs = &coefficients[i * degree..(i + 1) * degree];
+                    let acc = coeffs.iter().rev().fold(0u8, |acc, &c| gf_mul(acc, x) ^ c);
+                    gf_mul(acc, x) ^ s
+                })
+                .collect();
+            Share {
+                set_id,
+                threshold,
+                total,
+                index: x,
+                data,
+            }
+        })
+        .collect())
+}
+
+/// Recover a secret from at least `threshold` shares of the same set
+pub fn combine_shares(shares: &[Share]) -> Result<Zeroizing<Vec<u8>>, AppError> {
+    let first = shares
+        .first()
+        .ok_or_else(|| AppError::Validation("No shares given".into()))?;
+    if shares
+        .iter()
+        .any(|s| s.set_id != first.set_id || s.threshold != first.threshold || s.data.len() != first.data.len())
+    {
+        return Err(AppError::Validation("Shares belong to different backups".into()));
+    }
+
+    let mut used: Vec<&Share> = Vec::new();
+    for share in shares {
+        if share.index == 0 {
+            return Err(AppError::Validation("Invalid share index".into()));
+        }
+        if !used.iter().any(|s| s.index == share.index) {
+            used.push(share);
+        }
+    }
+    if used.len() < first.threshold as usize {
+        return Err(AppError::Validation(format!(
+            "{} of {} required shares given",
+            used.len(),
+            first.threshold
+        )));
+    }
+    used.truncate(first.threshold as usize);
+
+    // Lagrange basis at x = 0: prod x_j / (x_i ^ x_j)
+    let weights: Vec<u8> = used
+        .iter()
+        .map(|si| {
+            used.iter()
+                .filter(|sj| sj.index != si.index)
+                .fold(1u8, |acc, sj| gf_mul(acc, gf_mul(sj.index, gf_inv(si.index ^ sj.index))))
+        })
+        .collect();
+
+    let mut payload = Zeroizing::new(vec![0u8; first.data.len()]);
+    for (share, &weight) in used.iter().zip(&weights) {
+        for (out, &y) in payload.iter_mut().zip(&share.data) {
+            *out ^= gf_mul(y, weight);
+        }
+    }
+
+    if payload.len() < CHECKSUM_LEN || payload[..CHECKSUM_LEN] != checksum(&payload[CHECKSUM_LEN..]) {
+        return Err(AppError::Validation(
+            "Shares do not fit together; one of them may be damaged".into(),
+        ));
+    }
+    Ok(Zeroizing::new(payload[CHECKSUM_LEN..].to_vec()))
+}
+
+// ============================================================================
+// Encoding
+// ============================================================================
+
+pub fn encode_share(share: &Share) -> String {
+    let mut raw = Zeroizing::new(Vec::with_capacity(HEADER_LEN + share.data.len()));
+    raw.extend_from_slice(&share.set_id);
+    raw.extend_from_slice(&[share.threshold, share.total, share.index]);
+    raw.extend_from_slice(&share.data);
+    format!("{}{}", SHARE_PREFIX, STANDARD.encode(raw.as_slice()))
+}
+
+pub fn decode_share(encoded: &str) -> Result<Share, AppError> {
+    let body = encoded
+        .trim()
+        .strip_prefix(SHARE_PREFIX)
+        .ok_or_else(|| AppError::Validation("Not a Vortex key share".into()))?;
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    let raw = Zeroizing::new(
+        STANDARD
+            .decode(compact)
+            .map_err(|_| AppError::Validation("Key share is not valid base64".into()))?,
+    );
+    if raw.len() <= HEADER_LEN {
+        return Err(AppError::Validation("Key share is truncated".into()));
+    }
+    Ok(Share {
+        set_id: raw[..8].try_into().expect("length checked"),
+        threshold: raw[8],
+        total: raw[9],
+        index: raw[10],
+        data: raw[HEADER_LEN..].to_vec(),
+    })
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Split the keypair behind `keypair_handle` into `shares` recovery shares,
+/// any `threshold` of which restore it
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn split_key_shares(keypair_handle: KeypairHandle, threshold: u8, shares: u8) -> Result<Vec<KeyShare>, AppError> {
+    let (key_id, secret) = with_keypair(keypair_handle, |kp| {
+        Ok((kp.public_bundle().key_id, kp.to_bytes()))
+    })
+    ?;
+
+    let split = split_secret(&secret, threshold, shares)?;
+    Ok(split
+        .iter()
+        .map(|share| KeyShare {
+            index: share.index,
+            threshold: share.threshold,
+            total: share.total,
+            set_id: hex::encode(share.set_id),
+            key_id: key_id.clone(),
+            share: encode_share(share),
+        })
+        .collect())
+}
+
+/// Rebuild a keypair from recovery shares and load it into the store
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn recover_key_from_shares(shares: Vec<String>) -> Result<KeypairInfo, AppError> {
+    let decoded = shares
+        .iter()
+        .map(|s| decode_share(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let secret = combine_shares(&decoded)?;
+    let keypair = HybridKeypair::from_bytes(&secret)?;
+    register_keypair(keypair).map_err(AppError::from)
+}
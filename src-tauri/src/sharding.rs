@@ -0,0 +1,437 @@
+//! Repository Sharding
+//!
+//! GitHub recommends keeping repositories under 1 GB. Once the active shard
+//! of a library grows past the configured threshold, new uploads are routed
+//! to a fresh `<repo>-2`, `<repo>-3`, ... repository. The shard map lives in
+//! the primary repository (`.vortex/shards.json`) so every device sees the
+//! same layout, and `list_photos` merges the same folder across all shards.
+//!
+//! Shard sizes are the blobs in each shard's current tree. GitHub's
+//! repository size includes history, so it never drops when albums move out.
+//! The resolved upload target is cached for a few minutes and resolved by
+//! one upload at a time, so a batch checks sizes once.
+//!
+//! `rebalance_shards` moves whole albums out of oversized shards. Encrypted
+//! albums are never moved: their payloads are bound to the repository name.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::album::{ALBUM_MANIFEST_FILE, ENCRYPTED_BLOB_EXT};
+use crate::git_data::{
+    branch_head, commit_changes, create_blob, get_blob, get_json, get_tree_recursive, index_blobs, TreeChange,
+    TreeIndex,
+};
+use crate::github::{put_file_contents, response_error, validate_repo, validate_repo_name, AppError, Bearer, HttpClient};
+use crate::retry::SendWithRetry;
+use crate::stats::{usage_by_album, AlbumUsage, SOFT_LIMIT_BYTES};
+
+const SHARD_MAP_PATH: &str = ".vortex/shards.json";
+const SHARD_MAP_VERSION: u8 = 1;
+/// How long a resolved upload target is reused before sizes are checked again
+const UPLOAD_TARGET_TTL: Duration = Duration::from_secs(300);
+
+lazy_static::lazy_static! {
+    static ref UPLOAD_TARGETS: Mutex<HashMap<String, (String, Instant)>> = Mutex::new(HashMap::new());
+    /// Held while a target is resolved, so concurrent uploads wait for its result
+    static ref RESOLVING: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShardMap {
+    pub version: u8,
+    /// Shard repositories in creation order; the first entry is the primary repo
+    pub shards: Vec<String>,
+    pub threshold_bytes: u64,
+}
+
+impl ShardMap {
+    pub fn new(primary: &str) -> Self {
+        Self {
+            version: SHARD_MAP_VERSION,
+            shards: vec![primary.to_string()],
+            threshold_bytes: SOFT_LIMIT_BYTES,
+        }
+    }
+
+    pub fn primary(&self) -> &str {
+        &self.shards[0]
+    }
+
+    /// Shard new uploads currently go to
+    pub fn active(&self) -> &str {
+        self.shards.last().map(|s| s.as_str()).unwrap_or_else(|| self.primary())
+    }
+
+    /// Name for the next shard: `owner/photos` -> `owner/photos-2`, `-3`, ...
+    pub fn next_shard_name(&self) -> String {
+        format!("{}-{}", self.primary(), self.shards.len() + 1)
+    }
+}
+
+/// An album relocation between shards
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlbumMove {
+    pub album_path: String,
+    pub from: String,
+    pub to: String,
+    pub bytes: u64,
+    /// Whether `to` does not exist yet and must be created first
+    pub creates_shard: bool,
+}
+
+// ============================================================================
+// Rebalance Planning
+// ============================================================================
+
+/// Plan album moves so every shard ends up under the threshold.
+///
+/// `sizes` lists each shard's current size (see `tree_size_bytes`); `albums`
+/// lists the movable albums per shard. Largest albums move first, to the shard with the
+/// most free space that can hold them, or to a new shard. Albums larger than
+/// the threshold on their own are left in place.
+pub fn plan_rebalance(
+    map: &ShardMap,
+    sizes: &HashMap<String, u64>,
+    albums: &HashMap<String, Vec<AlbumUsage>>,
+) -> Vec<AlbumMove> {
+    let threshold = map.threshold_bytes;
+    let mut sizes: Vec<(String, u64, bool)> = map
+        .shards
+        .iter()
+        .map(|s| (s.clone(), sizes.get(s).copied().unwrap_or(0), false))
+        .collect();
+    let mut next_index = map.shards.len() + 1;
+    let mut moves = Vec::new();
+
+    for i in 0..sizes.len() {
+        if sizes[i].1 <= threshold {
+            continue;
+        }
+
+        let from = sizes[i].0.clone();
+        let mut candidates = albums.get(&from).cloned().unwrap_or_default();
+        candidates.sort_by_key(|a| std::cmp::Reverse(a.total_bytes));
+
+        for album in candidates {
+            if sizes[i].1 <= threshold {
+                break;
+            }
+            if album.total_bytes == 0 || album.total_bytes > threshold {
+                continue;
+            }
+
+            let target = sizes
+                .iter()
+                .enumerate()
+                .filter(|(j, (_, size, _))| *j != i && size + album.total_bytes <= threshold)
+                .min_by_key(|(_, (_, size, _))| *size)
+                .map(|(j, _)| j);
+
+            let j = match target {
+                Some(j) => j,
+                None => {
+                    sizes.push((format!("{}-{}", map.primary(), next_index), 0, true));
+                    next_index += 1;
+                    sizes.len() - 1
+                }
+            };
+
+            sizes[i].1 -= album.total_bytes;
+            sizes[j].1 += album.total_bytes;
+            let creates_shard = sizes[j].2 && !moves.iter().any(|m: &AlbumMove| m.to == sizes[j].0);
+
+            moves.push(AlbumMove {
+                album_path: album.album_path,
+                from: from.clone(),
+                to: sizes[j].0.clone(),
+                bytes: album.total_bytes,
+                creates_shard,
+            });
+        }
+    }
+
+    moves
+}
+
+/// Bytes of the blobs in a shard's current tree
+pub fn tree_size_bytes(index: &TreeIndex) -> u64 {
+    index.values().map(|e| e.size.unwrap_or(0)).sum()
+}
+
+/// Albums that hold encrypted payloads or a manifest cannot change repository
+pub fn is_movable_album(index: &TreeIndex, album_path: &str) -> bool {
+    let prefix = format!("{}/", album_path);
+    album_path != "photos"
+        && !index.keys().any(|p| {
+            p.starts_with(&prefix) && (p.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) || p.ends_with(ALBUM_MANIFEST_FILE))
+        })
+}
+
+// ============================================================================
+// Shard Map Storage
+// ============================================================================
+
+/// Load the shard map from the primary repo, or a single-shard default
+pub(crate) async fn load_shard_map(
+    client: &Client,
+    primary: &str,
+    token: &str,
+) -> Result<(ShardMap, Option<String>), AppError> {
+    let url = format!("https://api.github.com/repos/{}/contents/{}", primary, SHARD_MAP_PATH);
+
+    let res = client
+        .get(&url)
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .send_with_retry()
+        .await?;
+
+    if res.status() == 404 {
+        return Ok((ShardMap::new(primary), None));
+    }
+
+    if !res.status().is_success() {
+        return Err(response_error(res, "Failed to load shard map").await);
+    }
+
+    let json: serde_json::Value = res.json().await?;
+    let sha = json["sha"].as_str().map(|s| s.to_string());
+    let content: String = json["content"].as_str().unwrap_or("").chars().filter(|c| !c.is_whitespace()).collect();
+    let raw = STANDARD
+        .decode(content)
+        .map_err(|_| AppError::Validation("Invalid shard map encoding".into()))?;
+    let map: ShardMap = serde_json::from_slice(&raw)
+        .map_err(|e| AppError::Validation(format!("Invalid shard map: {}", e)))?;
+
+    if map.shards.first().map(|s| s.as_str()) != Some(primary) {
+        return Err(AppError::Validation("Shard map does not belong to this repository".into()));
+    }
+
+    Ok((map, sha))
+}
+
+async fn save_shard_map(
+    client: &Client,
+    token: &str,
+    map: &ShardMap,
+    sha: Option<&str>,
+) -> Result<(), AppError> {
+    let body = serde_json::to_vec_pretty(map)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+    put_file_contents(client, map.primary(), token, SHARD_MAP_PATH, &body, "Update shard map", sha).await?;
+    Ok(())
+}
+
+async fn repo_is_private(client: &Client, repo: &str, token: &str) -> Result<bool, AppError> {
+    let url = format!("https://api.github.com/repos/{}", repo);
+    let json = get_json(client, token, &url, "get repository info").await?;
+    Ok(json["private"].as_bool().unwrap_or(true))
+}
+
+async fn shard_index(client: &Client, repo: &str, token: &str) -> Result<TreeIndex, AppError> {
+    let head = branch_head(client, repo, token).await?;
+    Ok(index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?))
+}
+
+async fn create_shard_repo(client: &Client, token: &str, full_name: &str, private: bool) -> Result<(), AppError> {
+    let name = full_name.rsplit('/').next().unwrap_or(full_name);
+    validate_repo_name(name)?;
+
+    let body = serde_json::json!({
+        "name": name,
+        "description": "Vortex image library shard",
+        "private": private,
+        "auto_init": true
+    });
+
+    let res = client
+        .post("https://api.github.com/user/repos")
+        .header("Authorization", Bearer(token))
+        .header("User-Agent", "vortex-image")
+        .header("Accept", "application/vnd.github+json")
+        .json(&body)
+        .send_with_retry()
+        .await?;
+
+    // 422 means the repository already exists, e.g. created by another device
+    if !res.status().is_success() && res.status() != reqwest::StatusCode::UNPROCESSABLE_ENTITY {
+        return Err(response_error(res, "Failed to create shard").await);
+    }
+
+    Ok(())
+}
+
+/// Append a new shard repository to the map and persist it
+async fn add_shard(
+    client: &Client,
+    token: &str,
+    map: &mut ShardMap,
+    sha: Option<&str>,
+    name: &str,
+) -> Result<(), AppError> {
+    let private = repo_is_private(client, map.primary(), token).await?;
+    create_shard_repo(client, token, name, private).await?;
+    map.shards.push(name.to_string());
+    save_shard_map(client, token, map, sha).await
+}
+
+/// Repository new uploads for `primary` should go to, creating a shard if the
+/// active one has outgrown the threshold.
+pub(crate) async fn resolve_upload_repo(client: &Client, primary: &str, token: &str) -> Result<String, AppError> {
+    if let Some(target) = cached_upload_repo(primary) {
+        return Ok(target);
+    }
+    let _resolving = RESOLVING.lock().await;
+    // Another upload may have resolved it while this one waited
+    if let Some(target) = cached_upload_repo(primary) {
+        return Ok(target);
+    }
+
+    let (mut map, sha) = load_shard_map(client, primary, token).await?;
+    let active_size = tree_size_bytes(&shard_index(client, map.active(), token).await?);
+
+    if active_size >= map.threshold_bytes {
+        let name = map.next_shard_name();
+        add_shard(client, token, &mut map, sha.as_deref(), &name).await?;
+    }
+
+    let target = map.active().to_string();
+    UPLOAD_TARGETS
+        .lock()
+        .unwrap()
+        .insert(primary.to_string(), (target.clone(), Instant::now()));
+    Ok(target)
+}
+
+fn cached_upload_repo(primary: &str) -> Option<String> {
+    let targets = UPLOAD_TARGETS.lock().unwrap();
+    let (target, at) = targets.get(primary)?;
+    (at.elapsed() < UPLOAD_TARGET_TTL).then(|| target.clone())
+}
+
+/// All shard repositories for `primary`, primary first
+pub(crate) async fn shard_repos(client: &Client, primary: &str, token: &str) -> Result<Vec<String>, AppError> {
+    Ok(load_shard_map(client, primary, token).await?.0.shards)
+}
+
+// ============================================================================
+// Album Migration
+// ============================================================================
+
+/// Copy every file of an album into another repository in one commit,
+/// then remove it from the source in one commit.
+async fn move_album(client: &Client, token: &str, index: &TreeIndex, mv: &AlbumMove) -> Result<(), AppError> {
+    let prefix = format!("{}/", mv.album_path);
+    let files: Vec<_> = index.values().filter(|e| e.path.starts_with(&prefix)).collect();
+
+    let mut additions = Vec::with_capacity(files.len());
+    for entry in &files {
+        let content = get_blob(client, &mv.from, token, &entry.sha).await?;
+        let sha = create_blob(client, &mv.to, token, &content).await?;
+        additions.push(TreeChange::blob(&entry.path, &sha));
+    }
+
+    let message = format!("Move album {} from {}", mv.album_path, mv.from);
+    let target_head = branch_head(client, &mv.to, token).await?;
+    commit_changes(client, &mv.to, token, &target_head, &additions, &message).await?;
+
+    let deletions: Vec<TreeChange> = files.iter().map(|e| TreeChange::delete(&e.path)).collect();
+    let message = format!("Move album {} to {}", mv.album_path, mv.to);
+    let source_head = branch_head(client, &mv.from, token).await?;
+    commit_changes(client, &mv.from, token, &source_head, &deletions, &message).await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn get_shard_map(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+) -> Result<ShardMap, AppError> {
+    validate_repo(&repo)?;
+    Ok(load_shard_map(&client.0, &repo, &token).await?.0)
+}
+
+/// Set the size at which a new shard is started
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_shard_threshold(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    threshold_bytes: u64,
+) -> Result<ShardMap, AppError> {
+    validate_repo(&repo)?;
+
+    if threshold_bytes < 1024 * 1024 {
+        return Err(AppError::Validation("Shard threshold must be at least 1 MB".into()));
+    }
+
+    let (mut map, sha) = load_shard_map(&client.0, &repo, &token).await?;
+    map.threshold_bytes = threshold_bytes;
+    save_shard_map(&client.0, &token, &map, sha.as_deref()).await?;
+    UPLOAD_TARGETS.lock().unwrap().remove(&repo);
+
+    Ok(map)
+}
+
+/// Move albums out of shards that exceed the threshold.
+/// With `dry_run` the planned moves are returned without touching any repository.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn rebalance_shards(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    dry_run: bool,
+) -> Result<Vec<AlbumMove>, AppError> {
+    validate_repo(&repo)?;
+
+    let (mut map, mut sha) = load_shard_map(&client.0, &repo, &token).await?;
+
+    let mut sizes = HashMap::new();
+    let mut albums = HashMap::new();
+    let mut indexes = HashMap::new();
+    for shard in &map.shards {
+        let index = shard_index(&client.0, shard, &token).await?;
+
+        let movable: Vec<AlbumUsage> = usage_by_album(&index)
+            .into_iter()
+            .filter(|a| is_movable_album(&index, &a.album_path))
+            .collect();
+
+        sizes.insert(shard.clone(), tree_size_bytes(&index));
+        albums.insert(shard.clone(), movable);
+        indexes.insert(shard.clone(), index);
+    }
+
+    let moves = plan_rebalance(&map, &sizes, &albums);
+    if dry_run {
+        return Ok(moves);
+    }
+
+    for mv in &moves {
+        if mv.creates_shard {
+            add_shard(&client.0, &token, &mut map, sha.as_deref(), &mv.to).await?;
+            sha = load_shard_map(&client.0, &repo, &token).await?.1;
+        }
+        move_album(&client.0, &token, &indexes[&mv.from], mv).await?;
+    }
+
+    UPLOAD_TARGETS.lock().unwrap().remove(&repo);
+    Ok(moves)
+}
@@ -0,0 +1,431 @@
+//! Album Share Links
+//!
+//! A share link packages everything another Vortex user needs to view an
+//! encrypted album: the repository location, the album's read-only content
+//! key (derived from the owner's keypair) and an expiry timestamp.
+//!
+//! Link format: `vortex://share/<base64url([version: 1][json: var][checksum: 8])>`
+//! where the checksum is a truncated BLAKE3 hash used to reject mistyped or
+//! truncated links before any network request is made.
+//!
+//! A photo link points at one photo of a shared album for a shorter time,
+//! without handing out the album key:
+//! `vortex://photo/<base64url(json)>.<base64url(key)>`, where the JSON holds
+//! the repository, the photo path, an expiry and a random id, and the key is
+//! an HMAC-SHA256 over that JSON keyed from the album key. Creating a link
+//! uploads a copy of the photo to `.vortex/links/<id>`, sealed with the link
+//! key and the JSON as associated data. The link only opens that copy:
+//! editing its path or expiry breaks the seal, and the album key cannot be
+//! derived from it. Holders of the album key can check a link's key with
+//! `verify_photo_link`. Copies stay in the repository after they expire.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tauri::State;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::album::{album_key_info, open_album_photo};
+use crate::crypto::{decrypt_with_key, encrypt_with_key, with_keypair, KeypairHandle};
+use crate::github::{fetch_file_bytes, put_file_contents, validate_repo, AppError, HttpClient};
+use crate::util::now_secs;
+
+pub const SHARE_LINK_PREFIX: &str = "vortex://share/";
+const SHARE_LINK_VERSION: u8 = 1;
+const SHARE_LINK_CHECKSUM_LEN: usize = 8;
+/// Links cannot outlive this window (90 days)
+pub const MAX_SHARE_LINK_TTL_SECS: u64 = 90 * 24 * 60 * 60;
+pub const PHOTO_LINK_PREFIX: &str = "vortex://photo/";
+/// Photo links cannot outlive this window (7 days), nor their share link
+pub const MAX_PHOTO_LINK_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+const PHOTO_LINK_KEY_CONTEXT: &str = "vortex-image photo link key v1";
+/// Folder holding the sealed copies photo links open
+pub const PHOTO_LINK_DIR: &str = ".vortex/links";
+const PHOTO_LINK_ID_LEN: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Decoded share link contents. Holds key material, so it is zeroized on drop
+/// and never returned to the frontend directly (see `ShareLinkInfo`).
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct ShareLink {
+    pub repo: String,
+    pub album_path: String,
+    pub album_key: [u8; 32],
+    pub owner_key_id: String,
+    pub created_at: u64,
+    pub expires_at: u64,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Claims of a photo link, bound to its key. They name the photo but carry
+/// no key material.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PhotoLinkClaims {
+    pub repo: String,
+    pub album_path: String,
+    pub remote_path: String,
+    pub expires_at: u64,
+    /// Names the sealed copy below `PHOTO_LINK_DIR`
+    pub id: String,
+}
+
+/// A parsed photo link: its claims, their exact bytes and the key sealing
+/// the photo's copy
+pub struct PhotoLink {
+    pub claims: PhotoLinkClaims,
+    json: Vec<u8>,
+    key: Zeroizing<[u8; 32]>,
+}
+
+/// Public view of a photo link returned by `open_photo_link`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PhotoLinkInfo {
+    pub repo: String,
+    pub album_path: String,
+    pub remote_path: String,
+    pub expires_at: u64,
+}
+
+/// Public view of a share link returned by `open_share_link`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShareLinkInfo {
+    pub repo: String,
+    pub album_path: String,
+    pub owner_key_id: String,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub label: Option<String>,
+}
+
+impl ShareLink {
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    pub fn info(&self) -> ShareLinkInfo {
+        ShareLinkInfo {
+            repo: self.repo.clone(),
+            album_path: self.album_path.clone(),
+            owner_key_id: self.owner_key_id.clone(),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            label: self.label.clone(),
+        }
+    }
+
+    /// Whether a repo path belongs to the shared album
+    pub fn covers_path(&self, remote_path: &str) -> bool {
+        let prefix = format!("{}/", self.album_path.trim_end_matches('/'));
+        remote_path.starts_with(&prefix) && !remote_path.contains("..")
+    }
+}
+
+/// Stable identifier for an album, used as KDF info and AEAD associated data
+pub(crate) fn album_id(repo: &str, album_path: &str) -> String {
+    format!("{}:{}", repo, album_path.trim_matches('/'))
+}
+
+pub fn encode_share_link(link: &ShareLink) -> Result<String, AppError> {
+    let json = serde_json::to_vec(link)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+
+    let mut raw = Vec::with_capacity(1 + json.len() + SHARE_LINK_CHECKSUM_LEN);
+    raw.push(SHARE_LINK_VERSION);
+    raw.extend_from_slice(&json);
+    let checksum = blake3::hash(&raw);
+    raw.extend_from_slice(&checksum.as_bytes()[..SHARE_LINK_CHECKSUM_LEN]);
+
+    let encoded = format!("{}{}", SHARE_LINK_PREFIX, URL_SAFE_NO_PAD.encode(&raw));
+    raw.zeroize();
+    Ok(encoded)
+}
+
+pub fn decode_share_link(link: &str) -> Result<ShareLink, AppError> {
+    let body = link
+        .trim()
+        .strip_prefix(SHARE_LINK_PREFIX)
+        .ok_or_else(|| AppError::Validation("Not a Vortex share link".into()))?;
+
+    let mut raw = URL_SAFE_NO_PAD
+        .decode(body)
+        .map_err(|_| AppError::Validation("Malformed share link".into()))?;
+
+    if raw.len() < 1 + SHARE_LINK_CHECKSUM_LEN {
+        return Err(AppError::Validation("Malformed share link".into()));
+    }
+
+    let (content, checksum) = raw.split_at(raw.len() - SHARE_LINK_CHECKSUM_LEN);
+    if &blake3::hash(content).as_bytes()[..SHARE_LINK_CHECKSUM_LEN] != checksum {
+        raw.zeroize();
+        return Err(AppError::Validation("Share link checksum mismatch".into()));
+    }
+
+    if content[0] != SHARE_LINK_VERSION {
+        let version = content[0];
+        raw.zeroize();
+        return Err(AppError::Validation(format!("Unsupported share link version: {}", version)));
+    }
+
+    let parsed: Result<ShareLink, _> = serde_json::from_slice(&content[1..]);
+    raw.zeroize();
+    let link = parsed.map_err(|e| AppError::Validation(format!("Invalid share link payload: {}", e)))?;
+    validate_repo(&link.repo)?;
+    Ok(link)
+}
+
+/// Decode a link and reject it if it has expired
+fn open_link(link: &str) -> Result<ShareLink, AppError> {
+    let link = decode_share_link(link)?;
+    if link.is_expired(now_secs()) {
+        return Err(AppError::Validation("Share link has expired".into()));
+    }
+    Ok(link)
+}
+
+// ============================================================================
+// Photo Links
+// ============================================================================
+
+/// Link key for `json`: an HMAC keyed from the album key, which the link
+/// does not carry
+fn photo_link_key(album_key: &[u8; 32], json: &[u8]) -> HmacSha256 {
+    let mut key = blake3::derive_key(PHOTO_LINK_KEY_CONTEXT, album_key);
+    let mut mac = HmacSha256::new_from_slice(&key).expect("HMAC accepts any key length");
+    key.zeroize();
+    mac.update(json);
+    mac
+}
+
+pub fn encode_photo_link(claims: &PhotoLinkClaims, album_key: &[u8; 32]) -> Result<String, AppError> {
+    let json = serde_json::to_vec(claims)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+    let key: Zeroizing<[u8; 32]> = Zeroizing::new(photo_link_key(album_key, &json).finalize().into_bytes().into());
+    Ok(format!(
+        "{}{}.{}",
+        PHOTO_LINK_PREFIX,
+        URL_SAFE_NO_PAD.encode(&json),
+        URL_SAFE_NO_PAD.encode(key.as_slice())
+    ))
+}
+
+/// Parse a photo link and check its expiry and scope. Its claims are only
+/// proven by opening the photo's copy, or by `verify_photo_link`.
+pub fn decode_photo_link(link: &str, now: u64) -> Result<PhotoLink, AppError> {
+    let body = link
+        .trim()
+        .strip_prefix(PHOTO_LINK_PREFIX)
+        .ok_or_else(|| AppError::Validation("Not a Vortex photo link".into()))?;
+    let (payload, key) = body
+        .split_once('.')
+        .ok_or_else(|| AppError::Validation("Malformed photo link".into()))?;
+    let json = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| AppError::Validation("Malformed photo link".into()))?;
+    let key: [u8; 32] = URL_SAFE_NO_PAD
+        .decode(key)
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| AppError::Validation("Malformed photo link".into()))?;
+    let claims: PhotoLinkClaims = serde_json::from_slice(&json)
+        .map_err(|e| AppError::Validation(format!("Invalid photo link payload: {}", e)))?;
+
+    validate_repo(&claims.repo)?;
+    if claims.id.len() != PHOTO_LINK_ID_LEN || !claims.id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(AppError::Validation("Malformed photo link".into()));
+    }
+    if now >= claims.expires_at {
+        return Err(AppError::Validation("Photo link has expired".into()));
+    }
+    let prefix = format!("{}/", claims.album_path.trim_end_matches('/'));
+    if !claims.remote_path.starts_with(&prefix) || claims.remote_path.contains("..") {
+        return Err(AppError::Validation("Path is outside the shared album".into()));
+    }
+    Ok(PhotoLink {
+        claims,
+        json,
+        key: Zeroizing::new(key),
+    })
+}
+
+/// Check that a photo link was made with `album_key` and not edited since
+pub fn verify_photo_link(link: &PhotoLink, album_key: &[u8; 32]) -> Result<(), AppError> {
+    photo_link_key(album_key, &link.json)
+        .verify_slice(link.key.as_slice())
+        .map_err(|_| AppError::Validation("Photo link has been tampered with".into()))
+}
+
+impl PhotoLink {
+    /// Repo path of the photo's sealed copy
+    pub fn copy_path(&self) -> String {
+        format!("{}/{}", PHOTO_LINK_DIR, self.claims.id)
+    }
+
+    pub fn seal(&self, data: &[u8]) -> Result<Vec<u8>, AppError> {
+        Ok(encrypt_with_key(data, &self.key, &self.json)?)
+    }
+
+    /// Decrypt the photo's copy; fails if the claims were edited
+    pub fn open(&self, payload: &[u8]) -> Result<Vec<u8>, AppError> {
+        decrypt_with_key(payload, &self.key, &self.json)
+            .map_err(|_| AppError::Validation("Photo link has been tampered with".into()))
+    }
+}
+
+/// Create a share link for an album owned by the keypair behind `handle`.
+/// `key_epoch` must match the album manifest once its key has been rotated.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn create_share_link(
+    handle: KeypairHandle,
+    repo: String,
+    album_path: String,
+    expires_in_secs: u64,
+    label: Option<String>,
+    key_epoch: Option<u32>,
+) -> Result<String, AppError> {
+    validate_repo(&repo)?;
+
+    let album_path = album_path.trim_matches('/').to_string();
+    if album_path.is_empty() || album_path.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    if expires_in_secs == 0 || expires_in_secs > MAX_SHARE_LINK_TTL_SECS {
+        return Err(AppError::Validation(format!(
+            "Expiry must be between 1 and {} seconds",
+            MAX_SHARE_LINK_TTL_SECS
+        )));
+    }
+
+    let id = album_id(&repo, &album_path);
+    let (album_key, owner_key_id) = with_keypair(handle, |kp| {
+        let info = album_key_info(&id, key_epoch.unwrap_or(0));
+        Ok((*kp.derive_album_key(&info)?.as_bytes(), kp.public_bundle().key_id))
+    })
+    .map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let created_at = now_secs();
+    let link = ShareLink {
+        repo,
+        album_path,
+        album_key,
+        owner_key_id,
+        created_at,
+        expires_at: created_at + expires_in_secs,
+        label,
+    };
+
+    encode_share_link(&link)
+}
+
+/// Validate a share link and return what it grants access to
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn open_share_link(link: String) -> Result<ShareLinkInfo, AppError> {
+    Ok(open_link(&link)?.info())
+}
+
+/// Download and decrypt a photo from a shared album
+///
+/// `token` is the viewer's own GitHub token; the link only carries the
+/// decryption key, never repository credentials.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn download_shared_photo(
+    client: State<'_, HttpClient>,
+    link: String,
+    remote_path: String,
+    token: Zeroizing<String>,
+) -> Result<Vec<u8>, AppError> {
+    let link = open_link(&link)?;
+
+    if !link.covers_path(&remote_path) {
+        return Err(AppError::Validation("Path is outside the shared album".into()));
+    }
+
+    let bytes = fetch_file_bytes(&client.0, &link.repo, &token, &remote_path).await?;
+
+    let aad = album_id(&link.repo, &link.album_path);
+    let (data, _) = open_album_photo(&link.album_key, &aad, &bytes)?;
+    Ok(data)
+}
+
+/// Create a temporary link to one photo of a shared album, and upload the
+/// copy it opens. It expires after `expires_in_secs` or with the share link,
+/// whichever is first. `token` needs write access to the album's repository.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn create_photo_link(
+    client: State<'_, HttpClient>,
+    link: String,
+    remote_path: String,
+    expires_in_secs: u64,
+    token: Zeroizing<String>,
+) -> Result<String, AppError> {
+    let share = open_link(&link)?;
+    if !share.covers_path(&remote_path) {
+        return Err(AppError::Validation("Path is outside the shared album".into()));
+    }
+    if expires_in_secs == 0 || expires_in_secs > MAX_PHOTO_LINK_TTL_SECS {
+        return Err(AppError::Validation(format!(
+            "Expiry must be between 1 and {} seconds",
+            MAX_PHOTO_LINK_TTL_SECS
+        )));
+    }
+
+    let bytes = fetch_file_bytes(&client.0, &share.repo, &token, &remote_path).await?;
+    let aad = album_id(&share.repo, &share.album_path);
+    let (data, _) = open_album_photo(&share.album_key, &aad, &bytes)?;
+    let data = Zeroizing::new(data);
+
+    let claims = PhotoLinkClaims {
+        repo: share.repo.clone(),
+        album_path: share.album_path.clone(),
+        remote_path,
+        expires_at: (now_secs() + expires_in_secs).min(share.expires_at),
+        id: hex::encode(rand::random::<[u8; PHOTO_LINK_ID_LEN / 2]>()),
+    };
+    let encoded = encode_photo_link(&claims, &share.album_key)?;
+    let photo_link = decode_photo_link(&encoded, now_secs())?;
+    let sealed = photo_link.seal(&data)?;
+    put_file_contents(
+        &client.0,
+        &share.repo,
+        &token,
+        &photo_link.copy_path(),
+        &sealed,
+        "Add photo link",
+        None,
+    )
+    .await?;
+    Ok(encoded)
+}
+
+/// Parse a photo link and return what it points at
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn open_photo_link(link: String) -> Result<PhotoLinkInfo, AppError> {
+    let claims = decode_photo_link(&link, now_secs())?.claims;
+    Ok(PhotoLinkInfo {
+        repo: claims.repo,
+        album_path: claims.album_path,
+        remote_path: claims.remote_path,
+        expires_at: claims.expires_at,
+    })
+}
+
+/// Download and decrypt the photo behind a photo link
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn download_photo_link(
+    client: State<'_, HttpClient>,
+    link: String,
+    token: Zeroizing<String>,
+) -> Result<Vec<u8>, AppError> {
+    let link = decode_photo_link(&link, now_secs())?;
+    let bytes = fetch_file_bytes(&client.0, &link.claims.repo, &token, &link.copy_path()).await?;
+    link.open(&bytes)
+}
@@ -0,0 +1,348 @@
+//! Duplicate and Near-Duplicate Photos
+//!
+//! Every photo gets two 64-bit perceptual hashes of its upright image:
+//!
+//! - dHash: whether each pixel of a 9×8 grayscale thumbnail is brighter
+//!   than its right neighbour, which follows the layout of the picture;
+//! - pHash: whether each of the 8×8 lowest-frequency DCT coefficients of a
+//!   32×32 grayscale thumbnail is above their median, which survives
+//!   re-encoding, resizing and small exposure changes.
+//!
+//! Two photos are similar when both hashes differ in at most `threshold`
+//! bits. `find_similar_photos` groups a library or album into clusters of
+//! photos linked by similarity, so bursts and re-uploads show up together.
+//! Hashes are cached in the local store by blob SHA, so only photos added
+//! since the last scan are downloaded. Photos from encrypted albums are
+//! decrypted locally and need the keypair.
+
+use image::{imageops::FilterType, DynamicImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{Emitter, State};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::album::{album_key_for, fetch_manifest, open_album_photo, parent_album_path, ENCRYPTED_BLOB_EXT};
+use crate::crypto::KeypairHandle;
+use crate::git_data::{branch_head, get_blob, get_tree_recursive, index_blobs};
+use crate::github::{validate_repo, AppError, HttpClient};
+use crate::lfs::resolve_lfs_pointer;
+use crate::local_store::{with_store, PHOTO_HASHES_NS};
+use crate::sharing::album_id;
+use crate::stats::is_photo_path;
+use crate::transcode::decode_upright;
+
+/// Default bit distance at which photos count as near-duplicates
+pub const DEFAULT_THRESHOLD: u32 = 10;
+pub const MAX_THRESHOLD: u32 = 32;
+const DHASH_WIDTH: u32 = 9;
+const DHASH_HEIGHT: u32 = 8;
+const PHASH_SIZE: usize = 32;
+const PHASH_FREQUENCIES: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhotoHash {
+    pub dhash: u64,
+    pub phash: u64,
+}
+
+impl PhotoHash {
+    /// Bits by which the less similar of the two hashes differs
+    pub fn distance(&self, other: &PhotoHash) -> u32 {
+        (self.dhash ^ other.dhash)
+            .count_ones()
+            .max((self.phash ^ other.phash).count_ones())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimilarPhoto {
+    pub path: String,
+    pub size: u64,
+    /// Distance to the first photo of the cluster
+    pub distance: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PhotoCluster {
+    /// Largest file first, as the copy most worth keeping
+    pub photos: Vec<SimilarPhoto>,
+    /// All photos hash the same
+    pub identical: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SkippedPhoto {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimilarityReport {
+    pub scanned: usize,
+    pub clusters: Vec<PhotoCluster>,
+    pub skipped: Vec<SkippedPhoto>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SimilarityProgress {
+    pub total: usize,
+    pub completed: usize,
+    pub current_file: String,
+}
+
+/// A hashed photo to cluster
+#[derive(Clone, Debug)]
+pub struct HashedPhoto {
+    pub path: String,
+    pub size: u64,
+    pub hash: PhotoHash,
+}
+
+// ============================================================================
+// Hashing
+// ============================================================================
+
+fn grayscale(image: &DynamicImage, width: u32, height: u32) -> image::GrayImage {
+    image.resize_exact(width, height, FilterType::Triangle).to_luma8()
+}
+
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let small = grayscale(image, DHASH_WIDTH, DHASH_HEIGHT);
+    let mut hash = 0u64;
+    for y in 0..DHASH_HEIGHT {
+        for x in 0..DHASH_WIDTH - 1 {
+            let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    hash
+}
+
+pub fn phash(image: &DynamicImage) -> u64 {
+    let small = grayscale(image, PHASH_SIZE as u32, PHASH_SIZE as u32);
+    let pixel = |x: usize, y: usize| small.get_pixel(x as u32, y as u32)[0] as f64;
+    let cosines: Vec<Vec<f64>> = (0..PHASH_FREQUENCIES)
+        .map(|u| {
+            (0..PHASH_SIZE)
+                .map(|x| ((2 * x + 1) as f64 * u as f64 * std::f64::consts::PI / (2 * PHASH_SIZE) as f64).cos())
+                .collect()
+        })
+        .collect();
+
+    // Separable DCT-II, keeping only the lowest frequencies
+    let rows: Vec<Vec<f64>> = (0..PHASH_SIZE)
+        .map(|y| {
+            (0..PHASH_FREQUENCIES)
+                .map(|u| (0..PHASH_SIZE).map(|x| pixel(x, y) * cosines[u][x]).sum())
+                .collect()
+        })
+        .collect();
+    let mut coefficients = Vec::with_capacity(PHASH_FREQUENCIES * PHASH_FREQUENCIES);
+    for v in 0..PHASH_FREQUENCIES {
+        for u in 0..PHASH_FREQUENCIES {
+            coefficients.push((0..PHASH_SIZE).map(|y| rows[y][u] * cosines[v][y]).sum::<f64>());
+        }
+    }
+
+    // The DC term only carries overall brightness
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+    coefficients.iter().fold(0u64, |hash, c| (hash << 1) | (*c > median) as u64)
+}
+
+/// Perceptual hashes of an encoded image
+pub fn hash_photo(data: &[u8]) -> Result<PhotoHash, AppError> {
+    let image = decode_upright(data)?;
+    Ok(PhotoHash { dhash: dhash(&image), phash: phash(&image) })
+}
+
+// ============================================================================
+// Clustering
+// ============================================================================
+
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Group photos within `threshold` of each other. Similarity is chained, so
+/// a cluster may hold photos further apart that share a neighbour.
+pub fn cluster_photos(photos: &[HashedPhoto], threshold: u32) -> Vec<PhotoCluster> {
+    let mut parents: Vec<usize> = (0..photos.len()).collect();
+    for i in 0..photos.len() {
+        for j in i + 1..photos.len() {
+            if photos[i].hash.distance(&photos[j].hash) <= threshold {
+                let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<&HashedPhoto>> = HashMap::new();
+    for (i, photo) in photos.iter().enumerate() {
+        groups.entry(root(&mut parents, i)).or_default().push(photo);
+    }
+
+    let mut clusters: Vec<PhotoCluster> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|mut members| {
+            members.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+            let first = members[0].hash;
+            let identical = members.iter().all(|m| m.hash == first);
+            let photos = members
+                .into_iter()
+                .map(|m| SimilarPhoto {
+                    path: m.path.clone(),
+                    size: m.size,
+                    distance: first.distance(&m.hash),
+                })
+                .collect();
+            PhotoCluster { photos, identical }
+        })
+        .collect();
+    clusters.sort_by(|a, b| {
+        b.photos
+            .len()
+            .cmp(&a.photos.len())
+            .then_with(|| a.photos[0].path.cmp(&b.photos[0].path))
+    });
+    clusters
+}
+
+// ============================================================================
+// Library Scan
+// ============================================================================
+
+fn cached_hash(sha: &str) -> Option<PhotoHash> {
+    with_store(|store| store.get_json(PHOTO_HASHES_NS, sha)).ok().flatten()
+}
+
+fn cache_hash(sha: &str, hash: &PhotoHash) {
+    if let Err(e) = with_store(|store| store.put_json(PHOTO_HASHES_NS, sha, hash, None)) {
+        tracing::warn!("Could not cache photo hash: {}", e);
+    }
+}
+
+/// Album keys by album path, looked up once per album
+struct AlbumKeys {
+    keypair: Option<KeypairHandle>,
+    keys: HashMap<String, [u8; 32]>,
+}
+
+impl AlbumKeys {
+    async fn get(&mut self, client: &reqwest::Client, repo: &str, token: &str, album: &str) -> Result<[u8; 32], AppError> {
+        if let Some(key) = self.keys.get(album) {
+            return Ok(*key);
+        }
+        let handle = self
+            .keypair
+            .ok_or_else(|| AppError::Validation("Photo is encrypted; a keypair is required".into()))?;
+        let (manifest, _) = fetch_manifest(client, repo, token, album)
+            .await?
+            .ok_or_else(|| AppError::Validation("Album has no manifest".into()))?;
+        let key = album_key_for(handle, repo, album, &manifest)?;
+        self.keys.insert(album.to_string(), key);
+        Ok(key)
+    }
+}
+
+/// Download, decrypt if needed and hash one photo
+async fn hash_remote_photo(
+    client: &reqwest::Client,
+    repo: &str,
+    token: &str,
+    path: &str,
+    sha: &str,
+    keys: &mut AlbumKeys,
+) -> Result<PhotoHash, AppError> {
+    let mut content = resolve_lfs_pointer(client, repo, token, get_blob(client, repo, token, sha).await?).await?;
+    if path.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) {
+        let album = parent_album_path(path);
+        let key = keys.get(client, repo, token, album).await?;
+        content = open_album_photo(&key, &album_id(repo, album), &content)?.0;
+    }
+    tauri::async_runtime::spawn_blocking(move || hash_photo(&content))
+        .await
+        .map_err(|e| AppError::Validation(format!("Hashing failed: {}", e)))?
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Find clusters of identical or near-identical photos in `album_path`
+/// (the whole library by default). `threshold` is the number of differing
+/// hash bits still counted as similar, 0 for visually identical only.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
+pub async fn find_similar_photos(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: Option<String>,
+    threshold: Option<u32>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<SimilarityReport, AppError> {
+    validate_repo(&repo)?;
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if threshold > MAX_THRESHOLD {
+        return Err(AppError::Validation(format!("Threshold must be 0-{}", MAX_THRESHOLD)));
+    }
+    let album_path = album_path.as_deref().unwrap_or("photos").trim_matches('/').to_string();
+    if album_path.is_empty() || album_path.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let prefix = format!("{}/", album_path);
+    let mut entries: Vec<_> = index
+        .values()
+        .filter(|e| e.path.starts_with(&prefix) && is_photo_path(&e.path))
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let total = entries.len();
+    let mut keys = AlbumKeys { keypair: keypair_handle, keys: HashMap::new() };
+    let mut hashed = Vec::with_capacity(total);
+    let mut skipped = Vec::new();
+    for (completed, entry) in entries.into_iter().enumerate() {
+        let _ = app.emit(
+            "similarity-progress",
+            SimilarityProgress {
+                total,
+                completed,
+                current_file: entry.path.clone(),
+            },
+        );
+
+        let hash = match cached_hash(&entry.sha) {
+            Some(hash) => Ok(hash),
+            None => hash_remote_photo(&client.0, &repo, &token, &entry.path, &entry.sha, &mut keys)
+                .await
+                .inspect(|hash| cache_hash(&entry.sha, hash)),
+        };
+        match hash {
+            Ok(hash) => hashed.push(HashedPhoto {
+                path: entry.path.clone(),
+                size: entry.size.unwrap_or(0),
+                hash,
+            }),
+            Err(e) => skipped.push(SkippedPhoto { path: entry.path.clone(), reason: e.to_string() }),
+        }
+    }
+
+    Ok(SimilarityReport {
+        scanned: hashed.len(),
+        clusters: cluster_photos(&hashed, threshold),
+        skipped,
+    })
+}
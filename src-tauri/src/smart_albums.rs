@@ -0,0 +1,311 @@
+//! Smart Albums
+//!
+//! A smart album is a saved search (see `search`) plus rules the search has
+//! no field for: favorites, a minimum rating and a minimum size in
+//! megapixels. "All photos from 2023 tagged 'family' larger than 2MP" is
+//!
+//! ```json
+//! { "tag": "family", "taken_from": 1672531200, "taken_until": 1704067199, "min_megapixels": 2.0 }
+//! ```
+//!
+//! Albums are stored in the catalog's database, sealed like its photos, and
+//! their photos are found on demand. Each album also remembers which photos
+//! it held when last evaluated, so listings and organization changes that
+//! move photos in or out of it emit `smart-album-updated`.
+
+use rand::RngCore;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tauri::Emitter;
+
+use crate::AppHandle;
+use crate::catalog::CatalogPhoto;
+use crate::crypto::KeypairHandle;
+use crate::github::AppError;
+use crate::local_store::{db_error, with_store, LocalStore};
+use crate::search::{find_photos_in, page_of, SearchPage, SearchQuery, DEFAULT_SEARCH_LIMIT};
+use crate::tagging::validate_rating;
+use crate::util::now_secs;
+
+/// Emitted with a `SmartAlbumChange` when photos joined or left an album
+pub const SMART_ALBUM_UPDATED_EVENT: &str = "smart-album-updated";
+const MAX_NAME_LEN: usize = 100;
+
+/// Migration 3 (see `migrations`)
+pub const SMART_ALBUM_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS catalog_smart_albums (
+    id BLOB PRIMARY KEY,
+    record BLOB NOT NULL
+);
+"#;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SmartAlbumRules {
+    #[serde(flatten)]
+    pub search: SearchQuery,
+    #[serde(default)]
+    pub favorite: Option<bool>,
+    #[serde(default)]
+    pub min_rating: Option<u8>,
+    /// Photos whose dimensions are unknown never match
+    #[serde(default)]
+    pub min_megapixels: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SmartAlbum {
+    pub id: String,
+    pub repo: String,
+    pub name: String,
+    pub rules: SmartAlbumRules,
+    pub created_at: u64,
+    /// Photos matching when last evaluated
+    #[serde(default)]
+    pub photo_count: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct StoredSmartAlbum {
+    album: SmartAlbum,
+    /// Paths of the photos matching when last evaluated
+    #[serde(default)]
+    members: BTreeSet<String>,
+}
+
+/// Payload of `SMART_ALBUM_UPDATED_EVENT`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SmartAlbumChange {
+    pub repo: String,
+    pub id: String,
+    pub added: usize,
+    pub removed: usize,
+    pub photo_count: usize,
+}
+
+fn new_album_id() -> String {
+    let mut bytes = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+impl SmartAlbumRules {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let search = &self.search;
+        let has_rule = [&search.text, &search.filename, &search.camera, &search.tag, &search.album]
+            .iter()
+            .any(|field| field.as_deref().is_some_and(|f| !f.trim().is_empty()))
+            || search.taken_from.is_some()
+            || search.taken_until.is_some()
+            || self.favorite.is_some()
+            || self.min_rating.is_some()
+            || self.min_megapixels.is_some();
+        if !has_rule {
+            return Err(AppError::Validation("Smart albums need at least one rule".into()));
+        }
+        if let (Some(from), Some(until)) = (search.taken_from, search.taken_until) {
+            if from > until {
+                return Err(AppError::Validation("Date range ends before it starts".into()));
+            }
+        }
+        validate_rating(self.min_rating)?;
+        if self.min_megapixels.is_some_and(|mp| !mp.is_finite() || mp <= 0.0) {
+            return Err(AppError::Validation("Minimum size must be a positive number of megapixels".into()));
+        }
+        Ok(())
+    }
+
+    /// Whether a photo the search found also passes the other rules
+    fn admits(&self, photo: &CatalogPhoto) -> bool {
+        let megapixels = photo
+            .item
+            .exif
+            .as_ref()
+            .and_then(|e| Some(e.width? as f64 * e.height? as f64 / 1_000_000.0));
+        self.favorite.is_none_or(|f| photo.favorite == f)
+            && self.min_rating.is_none_or(|min| photo.rating.unwrap_or(0) >= min)
+            && self.min_megapixels.is_none_or(|min| megapixels.is_some_and(|mp| mp >= min))
+    }
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+fn row_id(store: &LocalStore, repo: &str, id: &str) -> [u8; 32] {
+    store.index_id(&["smart-album", repo, id])
+}
+
+fn put_album(store: &LocalStore, stored: &StoredSmartAlbum) -> Result<(), AppError> {
+    let id = row_id(store, &stored.album.repo, &stored.album.id);
+    let record = zeroize::Zeroizing::new(
+        serde_json::to_vec(stored).map_err(|e| AppError::Validation(e.to_string()))?,
+    );
+    store
+        .connection()
+        .execute(
+            "INSERT OR REPLACE INTO catalog_smart_albums (id, record) VALUES (?1, ?2)",
+            params![&id[..], store.seal(&id, &record)?],
+        )
+        .map_err(db_error)?;
+    Ok(())
+}
+
+fn stored_albums(store: &LocalStore, repo: &str) -> Result<Vec<StoredSmartAlbum>, AppError> {
+    let mut statement = store
+        .connection()
+        .prepare("SELECT id, record FROM catalog_smart_albums")
+        .map_err(db_error)?;
+    let rows = statement
+        .query_map([], |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?)))
+        .map_err(db_error)?;
+
+    let mut albums = Vec::new();
+    for row in rows {
+        let (id, sealed) = row.map_err(db_error)?;
+        let id: [u8; 32] = id
+            .try_into()
+            .map_err(|_| AppError::Validation("Corrupt smart album row".into()))?;
+        let record = store.unseal(&id, &sealed)?;
+        let stored: StoredSmartAlbum = serde_json::from_slice(&record)
+            .map_err(|e| AppError::Validation(format!("Corrupt smart album row: {}", e)))?;
+        if stored.album.repo == repo {
+            albums.push(stored);
+        }
+    }
+    albums.sort_by(|a, b| a.album.name.to_lowercase().cmp(&b.album.name.to_lowercase()));
+    Ok(albums)
+}
+
+fn stored_album(store: &LocalStore, repo: &str, id: &str) -> Result<StoredSmartAlbum, AppError> {
+    stored_albums(store, repo)?
+        .into_iter()
+        .find(|a| a.album.id == id)
+        .ok_or_else(|| AppError::Validation(format!("No smart album {}", id)))
+}
+
+/// Photos of `album`, newest first
+fn evaluate(store: &LocalStore, album: &SmartAlbum, unlocked: bool) -> Result<Vec<CatalogPhoto>, AppError> {
+    let mut photos = find_photos_in(store, &album.repo, &album.rules.search, unlocked)?;
+    photos.retain(|p| album.rules.admits(p));
+    Ok(photos)
+}
+
+pub fn create_smart_album_in(
+    store: &LocalStore,
+    repo: &str,
+    name: &str,
+    rules: SmartAlbumRules,
+) -> Result<SmartAlbum, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(AppError::Validation(format!("Smart album names are 1 to {} characters", MAX_NAME_LEN)));
+    }
+    rules.validate()?;
+
+    let mut album = SmartAlbum {
+        id: new_album_id(),
+        repo: repo.to_string(),
+        name: name.to_string(),
+        rules,
+        created_at: now_secs(),
+        photo_count: 0,
+    };
+    let members: BTreeSet<String> = evaluate(store, &album, true)?.into_iter().map(|p| p.path).collect();
+    album.photo_count = members.len();
+    put_album(store, &StoredSmartAlbum { album: album.clone(), members })?;
+    Ok(album)
+}
+
+pub fn list_smart_albums_in(store: &LocalStore, repo: &str) -> Result<Vec<SmartAlbum>, AppError> {
+    Ok(stored_albums(store, repo)?.into_iter().map(|s| s.album).collect())
+}
+
+pub fn smart_album_photos_in(
+    store: &LocalStore,
+    repo: &str,
+    id: &str,
+    unlocked: bool,
+) -> Result<Vec<CatalogPhoto>, AppError> {
+    evaluate(store, &stored_album(store, repo, id)?.album, unlocked)
+}
+
+pub fn delete_smart_album_in(store: &LocalStore, repo: &str, id: &str) -> Result<bool, AppError> {
+    let removed = store
+        .connection()
+        .execute("DELETE FROM catalog_smart_albums WHERE id = ?1", params![&row_id(store, repo, id)[..]])
+        .map_err(db_error)?;
+    Ok(removed > 0)
+}
+
+/// Re-evaluate the smart albums of `repo`, recording and returning those
+/// whose photos changed
+pub fn refresh_smart_albums_in(store: &LocalStore, repo: &str) -> Result<Vec<SmartAlbumChange>, AppError> {
+    let mut changes = Vec::new();
+    for mut stored in stored_albums(store, repo)? {
+        let members: BTreeSet<String> = evaluate(store, &stored.album, true)?.into_iter().map(|p| p.path).collect();
+        if members == stored.members {
+            continue;
+        }
+        changes.push(SmartAlbumChange {
+            repo: repo.to_string(),
+            id: stored.album.id.clone(),
+            added: members.difference(&stored.members).count(),
+            removed: stored.members.difference(&members).count(),
+            photo_count: members.len(),
+        });
+        stored.album.photo_count = members.len();
+        stored.members = members;
+        put_album(store, &stored)?;
+    }
+    Ok(changes)
+}
+
+/// Emit `SMART_ALBUM_UPDATED_EVENT` for the smart albums of `repo` whose
+/// photos changed since they were last evaluated
+pub(crate) fn notify_smart_albums(app: &AppHandle, repo: &str) {
+    match with_store(|store| refresh_smart_albums_in(store, repo)) {
+        Ok(changes) => {
+            for change in changes {
+                let _ = app.emit(SMART_ALBUM_UPDATED_EVENT, change);
+            }
+        }
+        Err(e) => tracing::warn!("Could not refresh the smart albums of {}: {}", repo, e),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn create_smart_album(repo: String, name: String, rules: SmartAlbumRules) -> Result<SmartAlbum, AppError> {
+    with_store(|store| create_smart_album_in(store, &repo, &name, rules))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn list_smart_albums(repo: String) -> Result<Vec<SmartAlbum>, AppError> {
+    with_store(|store| list_smart_albums_in(store, &repo))
+}
+
+/// One page of a smart album's photos, newest first
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn smart_album_photos(
+    repo: String,
+    id: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<SearchPage, AppError> {
+    let photos = with_store(|store| smart_album_photos_in(store, &repo, &id, keypair_handle.is_some()))?;
+    Ok(page_of(photos, offset.unwrap_or(0), limit.unwrap_or(DEFAULT_SEARCH_LIMIT)))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn delete_smart_album(repo: String, id: String) -> Result<bool, AppError> {
+    with_store(|store| delete_smart_album_in(store, &repo, &id))
+}
@@ -0,0 +1,210 @@
+//! Album Statistics and Storage Usage
+//!
+//! Sizes come from a single recursive tree listing, so no photo has to be
+//! downloaded. Repository size is compared against GitHub's recommendations:
+//! repositories should stay under 1 GB and are strongly advised to stay
+//! under 5 GB.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::album::{fetch_manifest, AlbumManifest, ALBUM_MANIFEST_FILE, ENCRYPTED_BLOB_EXT};
+use crate::git_data::{branch_head, get_json, get_tree_recursive, index_blobs, TreeIndex};
+use crate::github::{is_image_file, validate_repo, AppError, HttpClient};
+
+pub const SOFT_LIMIT_BYTES: u64 = 1024 * 1024 * 1024;
+pub const HARD_LIMIT_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+/// Fraction of the soft limit at which usage is reported as a warning
+const WARNING_RATIO: f64 = 0.8;
+const LARGEST_FILES_COUNT: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaStatus {
+    Ok,
+    Warning,
+    OverSoftLimit,
+    OverHardLimit,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileSize {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlbumStats {
+    pub album_path: String,
+    pub photo_count: usize,
+    pub total_bytes: u64,
+    pub largest_files: Vec<FileSize>,
+    /// Bytes saved by compression, when the album manifest records it
+    pub compression_savings_bytes: Option<u64>,
+    pub percent_of_soft_limit: f64,
+    pub status: QuotaStatus,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlbumUsage {
+    pub album_path: String,
+    pub photo_count: usize,
+    pub total_bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StorageUsage {
+    /// Repository size as reported by GitHub (includes history)
+    pub repo_bytes: u64,
+    pub photo_count: usize,
+    pub photo_bytes: u64,
+    pub soft_limit_bytes: u64,
+    pub hard_limit_bytes: u64,
+    pub percent_of_soft_limit: f64,
+    pub status: QuotaStatus,
+    pub albums: Vec<AlbumUsage>,
+}
+
+pub fn quota_status(bytes: u64) -> QuotaStatus {
+    if bytes >= HARD_LIMIT_BYTES {
+        QuotaStatus::OverHardLimit
+    } else if bytes >= SOFT_LIMIT_BYTES {
+        QuotaStatus::OverSoftLimit
+    } else if bytes as f64 >= SOFT_LIMIT_BYTES as f64 * WARNING_RATIO {
+        QuotaStatus::Warning
+    } else {
+        QuotaStatus::Ok
+    }
+}
+
+fn percent_of_soft_limit(bytes: u64) -> f64 {
+    bytes as f64 / SOFT_LIMIT_BYTES as f64 * 100.0
+}
+
+pub(crate) fn is_photo_path(path: &str) -> bool {
+    path.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) || is_image_file(std::path::Path::new(path))
+}
+
+fn photos_under<'a>(index: &'a TreeIndex, album_path: &str) -> impl Iterator<Item = FileSize> + 'a {
+    let prefix = format!("{}/", album_path.trim_matches('/'));
+    index
+        .values()
+        .filter(move |e| e.path.starts_with(&prefix) && is_photo_path(&e.path))
+        .map(|e| FileSize {
+            path: e.path.clone(),
+            size: e.size.unwrap_or(0),
+        })
+}
+
+/// Compute album statistics from a tree index (recursive, includes sub-albums)
+pub fn album_stats(index: &TreeIndex, album_path: &str, manifest: Option<&AlbumManifest>) -> AlbumStats {
+    let mut files: Vec<FileSize> = photos_under(index, album_path).collect();
+    files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+
+    let total_bytes: u64 = files.iter().map(|f| f.size).sum();
+    let photo_count = files.len();
+    files.truncate(LARGEST_FILES_COUNT);
+
+    let compression_savings_bytes = manifest
+        .filter(|m| m.original_bytes > 0)
+        .map(|m| m.original_bytes.saturating_sub(m.stored_bytes));
+
+    AlbumStats {
+        album_path: album_path.trim_matches('/').to_string(),
+        photo_count,
+        total_bytes,
+        largest_files: files,
+        compression_savings_bytes,
+        percent_of_soft_limit: percent_of_soft_limit(total_bytes),
+        status: quota_status(total_bytes),
+    }
+}
+
+/// Group photos by top-level album under `photos/`, largest first
+pub fn usage_by_album(index: &TreeIndex) -> Vec<AlbumUsage> {
+    let mut albums: BTreeMap<String, AlbumUsage> = BTreeMap::new();
+
+    for entry in index.values().filter(|e| e.path.starts_with("photos/") && is_photo_path(&e.path)) {
+        let mut parts = entry.path.splitn(3, '/');
+        let album_path = match (parts.next(), parts.next(), parts.next()) {
+            (Some(root), Some(album), Some(_)) => format!("{}/{}", root, album),
+            _ => "photos".to_string(),
+        };
+
+        let usage = albums.entry(album_path.clone()).or_insert(AlbumUsage {
+            album_path,
+            photo_count: 0,
+            total_bytes: 0,
+        });
+        usage.photo_count += 1;
+        usage.total_bytes += entry.size.unwrap_or(0);
+    }
+
+    let mut albums: Vec<AlbumUsage> = albums.into_values().collect();
+    albums.sort_by_key(|a| std::cmp::Reverse(a.total_bytes));
+    albums
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn get_album_stats(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+) -> Result<AlbumStats, AppError> {
+    validate_repo(&repo)?;
+
+    let album_path = album_path.trim_matches('/').to_string();
+    if album_path.is_empty() || album_path.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+
+    let manifest_path = format!("{}/{}", album_path, ALBUM_MANIFEST_FILE);
+    let manifest = if index.contains_key(&manifest_path) {
+        fetch_manifest(&client.0, &repo, &token, &album_path).await?.map(|(m, _)| m)
+    } else {
+        None
+    };
+
+    Ok(album_stats(&index, &album_path, manifest.as_ref()))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn get_storage_usage(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+) -> Result<StorageUsage, AppError> {
+    validate_repo(&repo)?;
+
+    let url = format!("https://api.github.com/repos/{}", repo);
+    let info = get_json(&client.0, &token, &url, "get repository info").await?;
+    // GitHub reports repository size in kilobytes
+    let repo_bytes = info["size"].as_u64().unwrap_or(0) * 1024;
+
+    let head = branch_head(&client.0, &repo, &token).await?;
+    let index = index_blobs(get_tree_recursive(&client.0, &repo, &token, &head.tree_sha).await?);
+    let albums = usage_by_album(&index);
+
+    Ok(StorageUsage {
+        repo_bytes,
+        photo_count: albums.iter().map(|a| a.photo_count).sum(),
+        photo_bytes: albums.iter().map(|a| a.total_bytes).sum(),
+        soft_limit_bytes: SOFT_LIMIT_BYTES,
+        hard_limit_bytes: HARD_LIMIT_BYTES,
+        percent_of_soft_limit: percent_of_soft_limit(repo_bytes),
+        status: quota_status(repo_bytes),
+        albums,
+    })
+}
@@ -0,0 +1,331 @@
+//! Storage Backends
+//!
+//! Besides GitHub, album files can be kept on storage the user runs
+//! themselves, such as a NAS. A `StorageBackend` stores files by path below
+//! its root; `backup_album` copies every file of an album there as
+//! `<repo>/<album>/<name>`, and `fetch_backed_up_photo` reads a photo back
+//! when GitHub cannot serve it (see `fetch_photo`).
+//!
+//! Files are copied as stored in the repo, so photos of encrypted albums
+//! stay encrypted to their album key, together with their signatures, the
+//! metadata vault and the manifest. Git LFS and chunked files are copied
+//! with their full content.
+//!
+//! Each copy is recorded in the local store's `storage_backups` table with
+//! the blob SHA it was made from, so unchanged files are skipped next time,
+//! and with its size and BLAKE3 hash, which content read back must match:
+//! the backend is not trusted with anything but keeping the bytes.
+
+use reqwest::Client;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::IpAddr;
+use zeroize::Zeroizing;
+
+use crate::album::{parent_album_path, AlbumManifest, ALBUM_MANIFEST_FILE, ENCRYPTED_BLOB_EXT};
+use crate::crypto::{hash_data, KeypairHandle};
+use crate::download_cache::CachedPhoto;
+use crate::git_data::{branch_head, get_blob, get_tree_recursive, index_blobs, TreeEntry, TreeIndex};
+use crate::github::{AppError, DownloadIntegrity};
+use crate::lfs::resolve_lfs_pointer;
+use crate::local_store::{db_error, with_store, LocalStore};
+use crate::security_verify::{check_album_photo, check_manifest, ensure_intact, signature_path, TrustedSigners};
+
+/// Migration 6 (see `migrations`)
+pub const STORAGE_BACKUP_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS storage_backups (
+    id BLOB PRIMARY KEY,
+    record BLOB NOT NULL
+);
+"#;
+
+/// A file in a backend folder
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredFile {
+    pub name: String,
+    /// `None` when the backend does not report it
+    pub size: Option<u64>,
+}
+
+/// Somewhere to keep files by path, below a root of its own. Paths use `/`
+/// and never start with it.
+pub trait StorageBackend: Send + Sync {
+    /// Name the backend's records are kept under
+    fn name(&self) -> &'static str;
+    /// Files directly in `folder`; empty when it does not exist
+    fn list(&self, folder: &str) -> impl Future<Output = Result<Vec<StoredFile>, AppError>> + Send;
+    /// Create or replace the file at `path`, with any missing folders
+    fn put(&self, path: &str, content: Vec<u8>) -> impl Future<Output = Result<(), AppError>> + Send;
+    /// `Ok(None)` when there is no file at `path`
+    fn get(&self, path: &str) -> impl Future<Output = Result<Option<Vec<u8>>, AppError>> + Send;
+    /// Whether there was a file to delete
+    fn delete(&self, path: &str) -> impl Future<Output = Result<bool, AppError>> + Send;
+}
+
+/// A file of a repo copied to a backend
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackedUpFile {
+    pub repo: String,
+    pub path: String,
+    /// Blob SHA in the repo when it was copied
+    pub sha: String,
+    pub size: u64,
+    /// BLAKE3 of the content, hex
+    pub blake3: String,
+}
+
+impl BackedUpFile {
+    pub fn for_content(repo: &str, path: &str, sha: &str, content: &[u8]) -> Self {
+        Self {
+            repo: repo.to_string(),
+            path: path.to_string(),
+            sha: sha.to_string(),
+            size: content.len() as u64,
+            blake3: hex::encode(hash_data(content)),
+        }
+    }
+
+    /// Fail unless `content` is what was copied
+    pub fn check(&self, content: &[u8]) -> Result<(), AppError> {
+        if content.len() as u64 != self.size || hex::encode(hash_data(content)) != self.blake3 {
+            return Err(AppError::Validation(format!("Backed up copy of {} does not match its record", self.path)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AlbumBackup {
+    pub album: String,
+    pub uploaded: usize,
+    /// Files already copied at their current SHA
+    pub unchanged: usize,
+    /// Copies of files no longer in the album that were deleted
+    pub removed: usize,
+    /// Bytes uploaded
+    pub bytes: u64,
+}
+
+// ============================================================================
+// Paths
+// ============================================================================
+
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Check the URL of a server: HTTPS, or plain HTTP to this machine only.
+/// Returns it without a trailing slash.
+pub fn validate_server_url(url: &str) -> Result<String, AppError> {
+    let parsed = reqwest::Url::parse(url.trim())
+        .map_err(|e| AppError::Validation(format!("Invalid server URL {}: {}", url, e)))?;
+    let host = parsed.host_str().unwrap_or_default();
+    match parsed.scheme() {
+        "https" if !host.is_empty() => {}
+        "http" if is_loopback(host) => {}
+        _ => return Err(AppError::Validation("Servers other than this machine must use HTTPS".into())),
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err(AppError::Validation("Server URLs cannot have a query".into()));
+    }
+    Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+/// Folder of `album` of `repo` on a backend
+pub fn backup_folder(repo: &str, album: &str) -> String {
+    format!("{}/{}", repo, album.trim_matches('/'))
+}
+
+fn backup_path(repo: &str, path: &str) -> String {
+    format!("{}/{}", repo, path)
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Files directly in the folder of `album`, manifest included
+pub fn album_blobs(index: &TreeIndex, album: &str) -> Vec<TreeEntry> {
+    let mut files: Vec<TreeEntry> = index
+        .values()
+        .filter(|entry| entry.path.rsplit_once('/').is_some_and(|(folder, _)| folder == album))
+        .cloned()
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+// ============================================================================
+// Records
+// ============================================================================
+
+fn record_id(store: &LocalStore, backend: &str, repo: &str, path: &str) -> [u8; 32] {
+    store.index_id(&["backup", backend, repo, path])
+}
+
+pub fn record_backup_in(store: &LocalStore, backend: &str, file: &BackedUpFile) -> Result<(), AppError> {
+    let id = record_id(store, backend, &file.repo, &file.path);
+    let json = Zeroizing::new(serde_json::to_vec(file).map_err(|e| AppError::Validation(e.to_string()))?);
+    store
+        .connection()
+        .execute(
+            "INSERT OR REPLACE INTO storage_backups (id, record) VALUES (?1, ?2)",
+            params![&id[..], store.seal(&id, &json)?],
+        )
+        .map_err(db_error)?;
+    Ok(())
+}
+
+pub fn backed_up_in(store: &LocalStore, backend: &str, repo: &str, path: &str) -> Result<Option<BackedUpFile>, AppError> {
+    let id = record_id(store, backend, repo, path);
+    let sealed: Option<Vec<u8>> = store
+        .connection()
+        .query_row("SELECT record FROM storage_backups WHERE id = ?1", params![&id[..]], |row| row.get(0))
+        .optional()
+        .map_err(db_error)?;
+    sealed
+        .map(|sealed| {
+            let json = store.unseal(&id, &sealed)?;
+            serde_json::from_slice(&json).map_err(|e| AppError::Validation(format!("Corrupt backup record: {}", e)))
+        })
+        .transpose()
+}
+
+pub fn forget_backup_in(store: &LocalStore, backend: &str, repo: &str, path: &str) -> Result<bool, AppError> {
+    let id = record_id(store, backend, repo, path);
+    let removed = store
+        .connection()
+        .execute("DELETE FROM storage_backups WHERE id = ?1", params![&id[..]])
+        .map_err(db_error)?;
+    Ok(removed > 0)
+}
+
+// ============================================================================
+// Backup and Restore
+// ============================================================================
+
+/// Copy the files of `album` to `backend`, skipping those already there at
+/// their current SHA and deleting copies of files the album no longer has
+pub async fn backup_album<B: StorageBackend>(
+    backend: &B,
+    client: &Client,
+    repo: &str,
+    token: &str,
+    album: &str,
+) -> Result<AlbumBackup, AppError> {
+    let album = album.trim_matches('/');
+    if album.is_empty() || album.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    let head = branch_head(client, repo, token).await?;
+    let index = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+    let files = album_blobs(&index, album);
+    if files.is_empty() {
+        return Err(AppError::Validation(format!("{} has no files", album)));
+    }
+
+    let folder = backup_folder(repo, album);
+    let stored: HashMap<String, Option<u64>> =
+        backend.list(&folder).await?.into_iter().map(|file| (file.name, file.size)).collect();
+
+    let mut report = AlbumBackup { album: album.to_string(), ..Default::default() };
+    for file in &files {
+        let name = file_name(&file.path);
+        let record = with_store(|store| backed_up_in(store, backend.name(), repo, &file.path))?;
+        let current = record.is_some_and(|record| {
+            record.sha == file.sha
+                && stored.get(name).is_some_and(|size| size.is_none_or(|size| size == record.size))
+        });
+        if current {
+            report.unchanged += 1;
+            continue;
+        }
+
+        let content = get_blob(client, repo, token, &file.sha).await?;
+        let content = resolve_lfs_pointer(client, repo, token, content).await?;
+        let record = BackedUpFile::for_content(repo, &file.path, &file.sha, &content);
+        backend.put(&backup_path(repo, &file.path), content).await?;
+        with_store(|store| record_backup_in(store, backend.name(), &record))?;
+        report.uploaded += 1;
+        report.bytes += record.size;
+    }
+
+    let names: HashSet<&str> = files.iter().map(|file| file_name(&file.path)).collect();
+    for name in stored.keys().filter(|name| !names.contains(name.as_str())) {
+        let path = format!("{}/{}", album, name);
+        backend.delete(&backup_path(repo, &path)).await?;
+        with_store(|store| forget_backup_in(store, backend.name(), repo, &path))?;
+        report.removed += 1;
+    }
+    Ok(report)
+}
+
+/// The copy of `path` on `backend`, checked against its record. `None` when
+/// it was never copied or is gone.
+async fn read_copy<B: StorageBackend>(backend: &B, repo: &str, path: &str) -> Result<Option<(BackedUpFile, Vec<u8>)>, AppError> {
+    let Some(record) = with_store(|store| backed_up_in(store, backend.name(), repo, path))? else {
+        return Ok(None);
+    };
+    let Some(content) = backend.get(&backup_path(repo, path)).await? else {
+        return Ok(None);
+    };
+    record.check(&content)?;
+    Ok(Some((record, content)))
+}
+
+/// The photo at `remote_path` from its copy on `backend`, checked as
+/// `fetch_photo` checks downloads from GitHub
+pub(crate) async fn fetch_backed_up_photo<B: StorageBackend>(
+    backend: &B,
+    repo: &str,
+    remote_path: &str,
+    keypair_handle: Option<KeypairHandle>,
+    verify: bool,
+) -> Result<Option<CachedPhoto>, AppError> {
+    let Some((record, content)) = read_copy(backend, repo, remote_path).await? else {
+        return Ok(None);
+    };
+
+    let manifest_path = format!("{}/{}", parent_album_path(remote_path), ALBUM_MANIFEST_FILE);
+    let manifest = if remote_path.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)) {
+        let (_, raw) = read_copy(backend, repo, &manifest_path)
+            .await?
+            .ok_or_else(|| AppError::Validation("Album manifest is not backed up".into()))?;
+        let manifest: AlbumManifest = serde_json::from_slice(&raw)
+            .map_err(|e| AppError::Validation(format!("Invalid album manifest: {}", e)))?;
+        Some(manifest)
+    } else {
+        None
+    };
+
+    let mut integrity = None;
+    if verify {
+        let trusted = TrustedSigners::load(keypair_handle);
+        let manifest_check = manifest
+            .as_ref()
+            .map(|m| ensure_intact(check_manifest(&manifest_path, m, &trusted)))
+            .transpose()?;
+        let signature = read_copy(backend, repo, &signature_path(remote_path)).await?;
+        let photo = check_album_photo(
+            remote_path,
+            &content,
+            signature.as_ref().map(|(_, raw)| raw.as_slice()),
+            manifest.as_ref(),
+            &trusted,
+        );
+        integrity = Some(DownloadIntegrity {
+            photo: ensure_intact(photo)?,
+            manifest: manifest_check,
+        });
+    }
+
+    tracing::info!("Served {} from the {} backup", remote_path, backend.name());
+    Ok(Some(CachedPhoto { sha: record.sha, content, integrity, manifest }))
+}
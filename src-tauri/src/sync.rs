@@ -0,0 +1,611 @@
+//! Two-Way Album Sync
+//!
+//! `sync_album` reconciles a local folder with an album in both directions.
+//! The state of every file at the last successful sync is kept under
+//! `<profile data>/sync/`, which turns each run into a three-way
+//! comparison: a side changed if its content differs from that baseline.
+//! Local changes are detected by BLAKE3 hash (skipped when the modification
+//! time is unchanged) and remote changes by Git blob SHA.
+//!
+//! When both sides changed the same file differently, the album's conflict
+//! policy decides the outcome: keep both copies, prefer the local version or
+//! prefer the remote version. All remote changes of a run are published as a
+//! single commit; local files are only touched after that commit succeeds.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::album::fetch_manifest;
+use crate::git_data::{branch_head, commit_changes, create_blob, get_blob, get_json, get_tree_recursive, index_blobs, TreeChange};
+use crate::github::{is_image_file, sanitize_filename, validate_repo, AppError, HttpClient};
+use crate::mirror::replicate_tree_changes;
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::sharing::album_id;
+use crate::watcher::should_upload;
+
+const STATE_VERSION: u32 = 1;
+const POLICIES_FILE: &str = "policies.json";
+
+lazy_static::lazy_static! {
+    static ref SYNC_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// How to resolve a file that changed both locally and remotely
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Keep the remote version in place and upload the local one as a copy
+    #[default]
+    KeepBoth,
+    PreferLocal,
+    PreferRemote,
+}
+
+/// Both versions of a conflicting file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflictInfo {
+    /// Path relative to the album
+    pub path: String,
+    pub local_modified: u64,
+    pub remote_modified: u64,
+    /// BLAKE3 hex of the local content, `None` if deleted locally
+    pub local_hash: Option<String>,
+    /// BLAKE3 hex of the remote content, `None` if deleted remotely
+    pub remote_hash: Option<String>,
+}
+
+/// Which version survives a conflict
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConflictResolution {
+    Local,
+    Remote,
+    /// The remote version stays in place and the local one moves to `copy_path`
+    Both { copy_path: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResolvedConflict {
+    pub conflict: ConflictInfo,
+    pub resolution: ConflictResolution,
+}
+
+/// A file's state as of the last successful sync
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncedFile {
+    pub local_hash: String,
+    pub local_modified: u64,
+    pub blob_sha: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    pub version: u32,
+    /// Keyed by path relative to the album
+    pub files: BTreeMap<String, SyncedFile>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalFile {
+    pub hash: String,
+    pub modified: u64,
+}
+
+/// Step computed by `plan_sync` for one path
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyncAction {
+    Upload(String),
+    Download(String),
+    DeleteLocal(String),
+    DeleteRemote(String),
+    /// Both sides changed since the last sync
+    Conflict(String),
+    /// Gone on both sides; drop it from the baseline
+    Forget(String),
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// Commit containing the remote changes, `None` if the remote was untouched
+    pub commit_sha: Option<String>,
+    pub uploaded: Vec<String>,
+    pub downloaded: Vec<String>,
+    pub deleted_local: Vec<String>,
+    pub deleted_remote: Vec<String>,
+    pub conflicts: Vec<ResolvedConflict>,
+    /// Local files whose names cannot be stored remotely as-is
+    pub skipped: Vec<String>,
+}
+
+// ============================================================================
+// Planning
+// ============================================================================
+
+/// Compare both sides against the baseline. `remote` maps paths to blob SHAs.
+pub fn plan_sync(
+    state: &SyncState,
+    local: &BTreeMap<String, LocalFile>,
+    remote: &BTreeMap<String, String>,
+) -> Vec<SyncAction> {
+    let paths: BTreeSet<&String> = state.files.keys().chain(local.keys()).chain(remote.keys()).collect();
+    let mut actions = Vec::new();
+
+    for path in paths {
+        let base = state.files.get(path);
+        let l = local.get(path);
+        let r = remote.get(path);
+
+        let local_changed = match (base, l) {
+            (Some(b), Some(l)) => b.local_hash != l.hash,
+            (None, None) => false,
+            _ => true,
+        };
+        let remote_changed = match (base, r) {
+            (Some(b), Some(r)) => &b.blob_sha != r,
+            (None, None) => false,
+            _ => true,
+        };
+
+        let action = match (local_changed, remote_changed) {
+            (false, false) => continue,
+            (true, false) if l.is_some() => SyncAction::Upload(path.clone()),
+            (true, false) => SyncAction::DeleteRemote(path.clone()),
+            (false, true) if r.is_some() => SyncAction::Download(path.clone()),
+            (false, true) => SyncAction::DeleteLocal(path.clone()),
+            (true, true) if l.is_none() && r.is_none() => SyncAction::Forget(path.clone()),
+            (true, true) => SyncAction::Conflict(path.clone()),
+        };
+        actions.push(action);
+    }
+
+    actions
+}
+
+/// Name for the local copy kept by `KeepBoth`: `IMG_1.jpg` -> `IMG_1-conflict-<ts>.jpg`
+pub fn conflict_copy_path(path: &str, timestamp: u64) -> String {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, path),
+    };
+    let name = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}-conflict-{}.{}", stem, timestamp, ext),
+        _ => format!("{}-conflict-{}", name, timestamp),
+    };
+    match dir {
+        Some(dir) => format!("{}/{}", dir, name),
+        None => name,
+    }
+}
+
+/// Apply a policy to a conflict. A modification always wins over a deletion
+/// under `KeepBoth`, since there is only one version left to keep.
+pub fn resolve_conflict(policy: ConflictPolicy, conflict: &ConflictInfo) -> ConflictResolution {
+    match policy {
+        ConflictPolicy::PreferLocal => ConflictResolution::Local,
+        ConflictPolicy::PreferRemote => ConflictResolution::Remote,
+        ConflictPolicy::KeepBoth => match (&conflict.local_hash, &conflict.remote_hash) {
+            (Some(_), Some(_)) => ConflictResolution::Both {
+                copy_path: conflict_copy_path(&conflict.path, conflict.local_modified),
+            },
+            (Some(_), None) => ConflictResolution::Local,
+            _ => ConflictResolution::Remote,
+        },
+    }
+}
+
+/// Parse GitHub's `YYYY-MM-DDTHH:MM:SSZ` timestamps into Unix seconds
+pub fn parse_github_timestamp(s: &str) -> Option<u64> {
+    let s = s.strip_suffix('Z')?;
+    let (date, time) = s.split_once('T')?;
+    let mut d = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (d.next()??, d.next()??, d.next()??);
+    let mut t = time.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = (t.next()??, t.next()??, t.next()??);
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Days from civil date (proleptic Gregorian), see Howard Hinnant's algorithms
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    u64::try_from(days * 86400 + hour * 3600 + minute * 60 + second).ok()
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn sync_dir() -> Result<PathBuf, AppError> {
+    let dir = crate::profiles::data_dir()?.join("sync");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn state_path(dir: &Path, root: &Path, repo: &str, album: &str) -> PathBuf {
+    let key = format!("{}\n{}", root.to_string_lossy(), album_id(repo, album));
+    let hash = blake3::hash(key.as_bytes()).to_hex();
+    dir.join(format!("{}.json", &hash[..32]))
+}
+
+fn read_json<T: Default + serde::de::DeserializeOwned>(path: &Path, what: &str) -> Result<T, AppError> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let raw = std::fs::read(path)?;
+    serde_json::from_slice(&raw).map_err(|e| AppError::Validation(format!("Corrupt {}: {}", what, e)))
+}
+
+/// Write atomically via a temp file so a crash never leaves a torn file
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), AppError> {
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn load_policies(dir: &Path) -> Result<BTreeMap<String, ConflictPolicy>, AppError> {
+    read_json(&dir.join(POLICIES_FILE), "sync policies")
+}
+
+// ============================================================================
+// Scanning
+// ============================================================================
+
+fn modified_secs(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn hash_file(path: &Path) -> Result<String, AppError> {
+    let data = std::fs::read(path)?;
+    Ok(blake3::hash(&data).to_hex().to_string())
+}
+
+/// Relative path with `/` separators, or `None` if any segment would be
+/// altered by `sanitize_filename` and so could not round-trip
+fn portable_relative(root: &Path, path: &Path) -> Option<String> {
+    let segments: Vec<String> = path
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if segments.is_empty() || segments.iter().any(|s| s.is_empty() || sanitize_filename(s) != *s) {
+        return None;
+    }
+    Some(segments.join("/"))
+}
+
+fn scan_local(
+    root: &Path,
+    dir: &Path,
+    recursive: bool,
+    ignore_patterns: &[String],
+    state: &SyncState,
+    files: &mut BTreeMap<String, LocalFile>,
+    skipped: &mut Vec<String>,
+) -> Result<(), AppError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let metadata = std::fs::metadata(&path)?;
+
+        if metadata.is_dir() {
+            let hidden = path.file_name().and_then(|n| n.to_str()).map(|n| n.starts_with('.')).unwrap_or(true);
+            if recursive && !hidden {
+                scan_local(root, &path, recursive, ignore_patterns, state, files, skipped)?;
+            }
+            continue;
+        }
+        if !metadata.is_file() || !should_upload(root, &path, ignore_patterns) {
+            continue;
+        }
+
+        let Some(relative) = portable_relative(root, &path) else {
+            skipped.push(path.to_string_lossy().to_string());
+            continue;
+        };
+
+        let modified = modified_secs(&metadata);
+        let hash = match state.files.get(&relative) {
+            Some(base) if base.local_modified == modified => base.local_hash.clone(),
+            _ => hash_file(&path)?,
+        };
+        files.insert(relative, LocalFile { hash, modified });
+    }
+    Ok(())
+}
+
+/// Resolve a remote relative path inside `root`, refusing anything that escapes it
+fn local_path(root: &Path, relative: &str) -> Result<PathBuf, AppError> {
+    if relative.split('/').any(|s| s.is_empty() || s == "." || s == "..") {
+        return Err(AppError::Validation(format!("Invalid remote path: {}", relative)));
+    }
+    Ok(relative.split('/').fold(root.to_path_buf(), |p, s| p.join(s)))
+}
+
+/// Time of the last commit touching `path`
+async fn remote_modified(client: &Client, repo: &str, token: &str, path: &str) -> Result<u64, AppError> {
+    let url = format!("https://api.github.com/repos/{}/commits?path={}&per_page=1", repo, path);
+    let json = get_json(client, token, &url, "get file history").await?;
+    Ok(json[0]["commit"]["committer"]["date"]
+        .as_str()
+        .and_then(parse_github_timestamp)
+        .unwrap_or(0))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Reconcile `local_dir` with `album_path` in both directions
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn sync_album(
+    client: State<'_, HttpClient>,
+    local_dir: String,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    recursive: Option<bool>,
+    ignore_patterns: Option<Vec<String>>,
+) -> Result<SyncReport, AppError> {
+    run_sync(
+        &client.0,
+        &local_dir,
+        &repo,
+        &token,
+        &album_path,
+        recursive.unwrap_or(false),
+        &ignore_patterns.unwrap_or_default(),
+    )
+    .await
+}
+
+/// Sync without the Tauri state, for the command above and the CLI
+pub(crate) async fn run_sync(
+    client: &Client,
+    local_dir: &str,
+    repo: &str,
+    token: &str,
+    album_path: &str,
+    recursive: bool,
+    ignore_patterns: &[String],
+) -> Result<SyncReport, AppError> {
+    validate_repo(repo)?;
+
+    let album = album_path.trim_matches('/').to_string();
+    if album.is_empty() || album.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    let root = PathBuf::from(local_dir);
+    if !root.is_dir() {
+        return Err(AppError::Validation("Sync path is not a directory".into()));
+    }
+    let root = root.canonicalize()?;
+    let _permit = SCHEDULER.acquire(JobClass::Sync).await;
+
+    if let Some((manifest, _)) = fetch_manifest(client, repo, token, &album).await? {
+        if manifest.encrypted {
+            return Err(AppError::Validation("Encrypted albums cannot be synced to a local folder".into()));
+        }
+    }
+
+    let dir = sync_dir()?;
+    let state_file = state_path(&dir, &root, repo, &album);
+    let (mut state, policy): (SyncState, ConflictPolicy) = {
+        let _guard = SYNC_LOCK.lock().unwrap();
+        let policy = load_policies(&dir)?.get(&album_id(repo, &album)).copied().unwrap_or_default();
+        (read_json(&state_file, "sync state")?, policy)
+    };
+
+    let mut report = SyncReport::default();
+    let mut local = BTreeMap::new();
+    scan_local(
+        &root,
+        &root,
+        recursive,
+        ignore_patterns,
+        &state,
+        &mut local,
+        &mut report.skipped,
+    )?;
+
+    let head = branch_head(client, repo, token).await?;
+    let index = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+    let prefix = format!("{}/", album);
+    let remote: BTreeMap<String, String> = index
+        .values()
+        .filter_map(|e| {
+            let rest = e.path.strip_prefix(&prefix)?;
+            let nested = rest.contains('/');
+            let wanted = is_image_file(Path::new(rest)) && (recursive || !nested);
+            wanted.then(|| (rest.to_string(), e.sha.clone()))
+        })
+        .collect();
+
+    let remote_path = |relative: &str| format!("{}{}", prefix, relative);
+
+    // Remote side first; local files are only modified once the commit lands
+    let mut changes = Vec::new();
+    let mut uploads: Vec<(String, String)> = Vec::new();
+    let mut downloads: Vec<String> = Vec::new();
+    let mut local_deletes: Vec<String> = Vec::new();
+    let mut copies: Vec<(String, String)> = Vec::new();
+
+    for action in plan_sync(&state, &local, &remote) {
+        match action {
+            SyncAction::Upload(path) => uploads.push((path.clone(), path)),
+            SyncAction::Download(path) => downloads.push(path),
+            SyncAction::DeleteRemote(path) => {
+                changes.push(TreeChange::delete(&remote_path(&path)));
+                state.files.remove(&path);
+                report.deleted_remote.push(path);
+            }
+            SyncAction::DeleteLocal(path) => local_deletes.push(path),
+            SyncAction::Forget(path) => {
+                state.files.remove(&path);
+            }
+            SyncAction::Conflict(path) => {
+                let l = local.get(&path);
+                let r = remote.get(&path);
+
+                let remote_hash = match r {
+                    Some(sha) => Some(blake3::hash(&get_blob(client, repo, token, sha).await?).to_hex().to_string()),
+                    None => None,
+                };
+
+                // Same edit made on both sides; just record it as synced
+                if let (Some(l), Some(r), Some(rh)) = (l, r, &remote_hash) {
+                    if &l.hash == rh {
+                        state.files.insert(
+                            path,
+                            SyncedFile {
+                                local_hash: l.hash.clone(),
+                                local_modified: l.modified,
+                                blob_sha: r.clone(),
+                            },
+                        );
+                        continue;
+                    }
+                }
+
+                let conflict = ConflictInfo {
+                    remote_modified: remote_modified(client, repo, token, &remote_path(&path)).await?,
+                    local_modified: l.map(|f| f.modified).unwrap_or(0),
+                    local_hash: l.map(|f| f.hash.clone()),
+                    remote_hash,
+                    path: path.clone(),
+                };
+                let resolution = resolve_conflict(policy, &conflict);
+
+                match (&resolution, l.is_some(), r.is_some()) {
+                    (ConflictResolution::Local, true, _) => uploads.push((path.clone(), path.clone())),
+                    (ConflictResolution::Local, false, _) => {
+                        changes.push(TreeChange::delete(&remote_path(&path)));
+                        state.files.remove(&path);
+                    }
+                    (ConflictResolution::Remote, _, true) => downloads.push(path.clone()),
+                    (ConflictResolution::Remote, _, false) => local_deletes.push(path.clone()),
+                    (ConflictResolution::Both { copy_path }, _, _) => {
+                        uploads.push((path.clone(), copy_path.clone()));
+                        copies.push((path.clone(), copy_path.clone()));
+                        downloads.push(path.clone());
+                    }
+                }
+
+                report.conflicts.push(ResolvedConflict { conflict, resolution });
+            }
+        }
+    }
+
+    for (source, target) in &uploads {
+        let content = std::fs::read(local_path(&root, source)?)?;
+        let sha = create_blob(client, repo, token, &content).await?;
+        changes.push(TreeChange::blob(&remote_path(target), &sha));
+
+        let file = &local[source];
+        state.files.insert(
+            target.clone(),
+            SyncedFile {
+                local_hash: file.hash.clone(),
+                local_modified: file.modified,
+                blob_sha: sha,
+            },
+        );
+        report.uploaded.push(target.clone());
+    }
+
+    if !changes.is_empty() {
+        let message = format!("Sync {} change{} from local folder", changes.len(), if changes.len() == 1 { "" } else { "s" });
+        report.commit_sha = Some(commit_changes(client, repo, token, &head, &changes, &message).await?);
+        replicate_tree_changes(client, repo, token, &changes);
+        for change in &changes {
+            let kind = if change.sha.is_some() { ActivityKind::Upload } else { ActivityKind::Delete };
+            record_activity(kind, "sync_album", Some(repo), &change.path, None);
+        }
+    }
+
+    for (path, copy) in &copies {
+        let to = local_path(&root, copy)?;
+        std::fs::rename(local_path(&root, path)?, &to)?;
+        let metadata = std::fs::metadata(&to)?;
+        if let Some(file) = state.files.get_mut(copy) {
+            file.local_modified = modified_secs(&metadata);
+        }
+    }
+
+    for path in downloads {
+        let sha = &remote[&path];
+        let content = get_blob(client, repo, token, sha).await?;
+        let target = local_path(&root, &path)?;
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, &content)?;
+
+        state.files.insert(
+            path.clone(),
+            SyncedFile {
+                local_hash: blake3::hash(&content).to_hex().to_string(),
+                local_modified: modified_secs(&std::fs::metadata(&target)?),
+                blob_sha: sha.clone(),
+            },
+        );
+        report.downloaded.push(path);
+    }
+
+    for path in local_deletes {
+        let target = local_path(&root, &path)?;
+        if target.exists() {
+            std::fs::remove_file(&target)?;
+        }
+        state.files.remove(&path);
+        report.deleted_local.push(path);
+    }
+
+    state.version = STATE_VERSION;
+    let _guard = SYNC_LOCK.lock().unwrap();
+    write_json(&state_file, &state)?;
+
+    Ok(report)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_sync_policy(repo: String, album_path: String) -> Result<ConflictPolicy, AppError> {
+    validate_repo(&repo)?;
+    let _guard = SYNC_LOCK.lock().unwrap();
+    let policies = load_policies(&sync_dir()?)?;
+    Ok(policies.get(&album_id(&repo, &album_path)).copied().unwrap_or_default())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn set_sync_policy(repo: String, album_path: String, policy: ConflictPolicy) -> Result<(), AppError> {
+    validate_repo(&repo)?;
+    let _guard = SYNC_LOCK.lock().unwrap();
+    let dir = sync_dir()?;
+    let mut policies = load_policies(&dir)?;
+    policies.insert(album_id(&repo, &album_path), policy);
+    write_json(&dir.join(POLICIES_FILE), &policies)
+}
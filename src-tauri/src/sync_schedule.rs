@@ -0,0 +1,454 @@
+//! Scheduled Album Runs
+//!
+//! `set_sync_schedule` gives an album a cadence, every N minutes or daily
+//! at a set time, and the tasks to run on it:
+//!
+//! - `sync` - `sync_album` against a local folder,
+//! - `retention` - the album's retention policy (see `retention`),
+//! - `verify` - `verify_album_integrity` over the album.
+//!
+//! A background task checks every `TICK_SECS` for albums that are due and
+//! runs their tasks in that order, one album at a time, as background work
+//! (see `scheduler`). A failed task does not stop the ones after it. The
+//! outcome of each run is kept with the schedule and emitted as
+//! `schedule-run`, and `get_sync_schedules` returns it with the next run
+//! time for the UI.
+//!
+//! Daily times are given with the UTC offset they are meant in, since the
+//! backend has no time zone database. Schedules and their last runs live in
+//! `<profile data>/sync_schedules.json`; the token of each is kept in the
+//! OS keychain so runs keep working after a restart.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::config_store::{ConfigList, TokenSlot};
+use crate::git_data::get_json;
+use crate::github::{validate_repo, AppError, HttpClient};
+use crate::retention::run_album_retention;
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::security_verify::verify_integrity;
+use crate::sharing::album_id;
+use crate::sync::run_sync;
+use crate::util::{default_true, now_secs};
+
+const SCHEDULES_FILE: &str = "sync_schedules.json";
+/// Emitted with a `ScheduleRun` after each run
+pub const SCHEDULE_RUN_EVENT: &str = "schedule-run";
+const TICK_SECS: u64 = 60;
+pub const MIN_INTERVAL_MINUTES: u32 = 5;
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+static SCHEDULES: ConfigList<ScheduledAlbum> = ConfigList::new(SCHEDULES_FILE, "sync schedules");
+static SCHEDULE_TOKENS: TokenSlot = TokenSlot::new("schedule-token", "schedule");
+
+lazy_static::lazy_static! {
+    /// Albums with a run in progress
+    static ref RUNNING: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledTask {
+    Sync,
+    Retention,
+    Verify,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Cadence {
+    Interval {
+        minutes: u32,
+    },
+    /// At `hour:minute` in the time zone `utc_offset_minutes` east of UTC
+    Daily {
+        hour: u8,
+        minute: u8,
+        #[serde(default)]
+        utc_offset_minutes: i32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncSchedule {
+    pub repo: String,
+    pub album_path: String,
+    pub cadence: Cadence,
+    pub tasks: Vec<ScheduledTask>,
+    /// Folder the `sync` task reconciles with the album
+    #[serde(default)]
+    pub local_dir: Option<String>,
+    #[serde(default)]
+    pub recursive: bool,
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl SyncSchedule {
+    pub fn id(&self) -> String {
+        album_id(&self.repo, &self.album_path)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskOutcome {
+    pub task: ScheduledTask,
+    /// What the task did, e.g. "3 uploaded, 1 downloaded"
+    pub summary: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub repo: String,
+    pub album_path: String,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub tasks: Vec<TaskOutcome>,
+}
+
+impl ScheduleRun {
+    pub fn succeeded(&self) -> bool {
+        self.tasks.iter().all(|t| t.error.is_none())
+    }
+}
+
+/// A schedule with its run history, as stored
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ScheduledAlbum {
+    schedule: SyncSchedule,
+    /// When the schedule was set, Unix seconds
+    since: u64,
+    #[serde(default)]
+    last_run: Option<ScheduleRun>,
+}
+
+impl ScheduledAlbum {
+    fn next_run_at(&self) -> u64 {
+        next_run_at(&self.schedule.cadence, self.since, self.last_run.as_ref().map(|r| r.started_at))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScheduleStatus {
+    pub schedule: SyncSchedule,
+    pub last_run: Option<ScheduleRun>,
+    /// `None` while disabled
+    pub next_run_at: Option<u64>,
+    pub running: bool,
+}
+
+/// When a schedule that last ran at `last_run` (or was set at `since`, if
+/// it never ran) is due next, Unix seconds. Interval schedules that never ran are
+/// due at once.
+pub fn next_run_at(cadence: &Cadence, since: u64, last_run: Option<u64>) -> u64 {
+    match *cadence {
+        Cadence::Interval { minutes } => last_run.map_or(since, |last| last + u64::from(minutes) * 60),
+        Cadence::Daily {
+            hour,
+            minute,
+            utc_offset_minutes,
+        } => {
+            let after = last_run.unwrap_or(since) as i64;
+            let offset = i64::from(utc_offset_minutes) * 60;
+            let at = i64::from(hour) * 3600 + i64::from(minute) * 60;
+            let local = after + offset;
+            let mut next = local - local.rem_euclid(SECS_PER_DAY) + at;
+            if next <= local {
+                next += SECS_PER_DAY;
+            }
+            (next - offset).max(0) as u64
+        }
+    }
+}
+
+pub fn validate_schedule(schedule: &SyncSchedule) -> Result<(), AppError> {
+    validate_repo(&schedule.repo)?;
+    if schedule.album_path.is_empty() || schedule.album_path.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    match schedule.cadence {
+        Cadence::Interval { minutes } if minutes < MIN_INTERVAL_MINUTES => {
+            return Err(AppError::Validation(format!(
+                "Schedules run at most every {} minutes",
+                MIN_INTERVAL_MINUTES
+            )));
+        }
+        Cadence::Daily {
+            hour,
+            minute,
+            utc_offset_minutes,
+        } if hour > 23 || minute > 59 || utc_offset_minutes.abs() > 14 * 60 => {
+            return Err(AppError::Validation("Invalid time of day".into()));
+        }
+        _ => {}
+    }
+    if schedule.tasks.is_empty() {
+        return Err(AppError::Validation("A schedule needs at least one task".into()));
+    }
+    if schedule.tasks.contains(&ScheduledTask::Sync) {
+        let dir = schedule.local_dir.as_deref().unwrap_or("");
+        if dir.is_empty() || !Path::new(dir).is_dir() {
+            return Err(AppError::Validation("Scheduled sync needs an existing local folder".into()));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+/// Reload the schedules from disk on next use, after a profile switch
+pub(crate) fn forget_cached_schedules() {
+    SCHEDULES.forget();
+}
+
+/// Whether an enabled schedule of the album runs `task`, so other
+/// background runs of it can stand aside
+pub(crate) fn is_scheduled(repo: &str, album_path: &str, task: ScheduledTask) -> bool {
+    let id = album_id(repo, album_path);
+    SCHEDULES.with(|schedules| {
+        schedules
+            .iter()
+            .any(|s| s.schedule.enabled && s.schedule.id() == id && s.schedule.tasks.contains(&task))
+    })
+    .unwrap_or(false)
+}
+
+// ============================================================================
+// Runs
+// ============================================================================
+
+async fn run_task(
+    client: &Client,
+    token: &str,
+    schedule: &SyncSchedule,
+    task: ScheduledTask,
+) -> Result<String, AppError> {
+    match task {
+        ScheduledTask::Sync => {
+            let local_dir = schedule.local_dir.as_deref().unwrap_or("");
+            let report = run_sync(
+                client,
+                local_dir,
+                &schedule.repo,
+                token,
+                &schedule.album_path,
+                schedule.recursive,
+                &schedule.ignore_patterns,
+            )
+            .await?;
+            Ok(format!(
+                "{} uploaded, {} downloaded, {} deleted, {} conflicts",
+                report.uploaded.len(),
+                report.downloaded.len(),
+                report.deleted_local.len() + report.deleted_remote.len(),
+                report.conflicts.len()
+            ))
+        }
+        ScheduledTask::Retention => {
+            let run = run_album_retention(client, &schedule.repo, &schedule.album_path).await?;
+            Ok(format!("{} archived, {} skipped", run.archived, run.skipped))
+        }
+        ScheduledTask::Verify => {
+            let report = verify_integrity(client, token, &schedule.repo, Some(&schedule.album_path), None).await?;
+            Ok(format!(
+                "{} verified, {} unsigned, {} untrusted, {} tampered",
+                report.verified, report.unsigned, report.untrusted, report.tampered
+            ))
+        }
+    }
+}
+
+/// Run the tasks of `schedule` in order and record the run. Fails only if
+/// the album is already running.
+async fn run_schedule(client: &Client, schedule: &SyncSchedule) -> Result<ScheduleRun, AppError> {
+    let id = schedule.id();
+    if !RUNNING.lock().unwrap().insert(id.clone()) {
+        return Err(AppError::Validation(format!("{} is already running", schedule.album_path)));
+    }
+
+    let started_at = now_secs();
+    let token = SCHEDULE_TOKENS.load(&id);
+    let mut tasks = Vec::new();
+    let mut order = schedule.tasks.clone();
+    order.sort();
+    order.dedup();
+    for task in order {
+        let result = match &token {
+            Ok(token) => run_task(client, token, schedule, task).await,
+            Err(e) => Err(AppError::Validation(e.to_string())),
+        };
+        tasks.push(match result {
+            Ok(summary) => TaskOutcome {
+                task,
+                summary: Some(summary),
+                error: None,
+            },
+            Err(e) => TaskOutcome {
+                task,
+                summary: None,
+                error: Some(e.to_string()),
+            },
+        });
+    }
+    let run = ScheduleRun {
+        repo: schedule.repo.clone(),
+        album_path: schedule.album_path.clone(),
+        started_at,
+        finished_at: now_secs(),
+        tasks,
+    };
+    RUNNING.lock().unwrap().remove(&id);
+
+    let saved = SCHEDULES.update(|schedules| {
+        if let Some(entry) = schedules.iter_mut().find(|s| s.schedule.id() == id) {
+            entry.last_run = Some(run.clone());
+        }
+    });
+    if let Err(e) = saved {
+        tracing::warn!("Could not record the run of {}: {}", schedule.album_path, e);
+    }
+    Ok(run)
+}
+
+/// Run due schedules every `TICK_SECS`
+pub fn start_schedule_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(TICK_SECS)).await;
+
+            let at = now_secs();
+            let due: Vec<SyncSchedule> = match SCHEDULES.with(|schedules| {
+                schedules
+                    .iter()
+                    .filter(|s| s.schedule.enabled && s.next_run_at() <= at)
+                    .map(|s| s.schedule.clone())
+                    .collect()
+            }) {
+                Ok(due) => due,
+                Err(e) => {
+                    tracing::warn!("Could not load sync schedules: {}", e);
+                    continue;
+                }
+            };
+
+            let client = app.state::<HttpClient>().0.clone();
+            for schedule in due {
+                let _permit = SCHEDULER.acquire(JobClass::Background).await;
+                match run_schedule(&client, &schedule).await {
+                    Ok(run) => {
+                        if !run.succeeded() {
+                            tracing::warn!("Scheduled run of {} had failures", schedule.album_path);
+                        }
+                        let _ = app.emit(SCHEDULE_RUN_EVENT, &run);
+                    }
+                    Err(e) => tracing::debug!("Skipped scheduled run: {}", e),
+                }
+            }
+        }
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Set (or replace) the schedule of an album. The token is stored in the
+/// OS keychain for the background runs.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_sync_schedule(
+    client: State<'_, HttpClient>,
+    token: Zeroizing<String>,
+    mut schedule: SyncSchedule,
+) -> Result<ScheduleStatus, AppError> {
+    schedule.album_path = schedule.album_path.trim_matches('/').to_string();
+    validate_schedule(&schedule)?;
+
+    // Fail early on a wrong token or repository name
+    let url = format!("https://api.github.com/repos/{}", schedule.repo);
+    get_json(&client.0, &token, &url, "get repository info").await?;
+
+    SCHEDULE_TOKENS.store(&schedule.id(), &token)?;
+    let since = now_secs();
+    SCHEDULES.update(|schedules| {
+        // Replacing a schedule keeps its last run
+        let last_run = schedules
+            .iter()
+            .find(|s| s.schedule.id() == schedule.id())
+            .and_then(|s| s.last_run.clone());
+        schedules.retain(|s| s.schedule.id() != schedule.id());
+        schedules.push(ScheduledAlbum {
+            schedule: schedule.clone(),
+            since,
+            last_run: last_run.clone(),
+        });
+        ScheduleStatus {
+            next_run_at: schedule
+                .enabled
+                .then(|| next_run_at(&schedule.cadence, since, last_run.as_ref().map(|r| r.started_at))),
+            schedule,
+            last_run,
+            running: false,
+        }
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn remove_sync_schedule(repo: String, album_path: String) -> Result<bool, AppError> {
+    let id = album_id(&repo, album_path.trim_matches('/'));
+    let removed = SCHEDULES.update(|schedules| {
+        let before = schedules.len();
+        schedules.retain(|s| s.schedule.id() != id);
+        schedules.len() != before
+    })?;
+    SCHEDULE_TOKENS.delete(&id);
+    Ok(removed)
+}
+
+/// Every schedule with its last run and when it runs next
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_sync_schedules() -> Result<Vec<ScheduleStatus>, AppError> {
+    let running = RUNNING.lock().unwrap().clone();
+    SCHEDULES.with(|schedules| {
+        schedules
+            .iter()
+            .map(|s| ScheduleStatus {
+                next_run_at: s.schedule.enabled.then(|| s.next_run_at()),
+                running: running.contains(&s.schedule.id()),
+                schedule: s.schedule.clone(),
+                last_run: s.last_run.clone(),
+            })
+            .collect()
+    })
+}
+
+/// Run an album's scheduled tasks now; the next run is timed from this one
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn run_sync_schedule_now(
+    client: State<'_, HttpClient>,
+    repo: String,
+    album_path: String,
+) -> Result<ScheduleRun, AppError> {
+    let id = album_id(&repo, album_path.trim_matches('/'));
+    let schedule = SCHEDULES.with(|schedules| {
+        schedules.iter().find(|s| s.schedule.id() == id).map(|s| s.schedule.clone())
+    })?
+    .ok_or_else(|| AppError::Validation(format!("No schedule for {}", album_path)))?;
+    run_schedule(&client.0, &schedule).await
+}
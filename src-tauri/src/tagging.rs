@@ -0,0 +1,252 @@
+//! Tags, Favorites and Ratings
+//!
+//! Photos are organized with free-form tags, a favorite flag and a rating
+//! of 1 to `MAX_RATING` stars. Each change is written to the album manifest,
+//! so the organization travels with the album to other devices, and then to
+//! the local catalog, where `query_photos` searches it (see `catalog`).
+//!
+//! Plain albums keep the organization in the manifest's `organization` map.
+//! Encrypted albums keep the same map as JSON sealed with the album key in
+//! `sealed_organization`, since tags say as much about a photo as its name.
+//!
+//! `sync_photo_organization` copies an album's organization from its manifest
+//! into the catalog, to pick up changes made on other devices.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::album::{album_key_for, fetch_manifest, manifest_for_update, parent_album_path, save_manifest, AlbumManifest};
+use crate::catalog::{import_organization, normalize_tags};
+use crate::crypto::{decrypt_with_key, encrypt_with_key, KeypairHandle};
+use crate::github::{validate_repo, AppError, GithubError, HttpClient};
+use crate::sharing::album_id;
+use crate::smart_albums::notify_smart_albums;
+
+pub const MAX_RATING: u8 = 5;
+/// Tags a single photo can carry
+pub const MAX_TAGS: usize = 64;
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhotoOrganization {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub favorite: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+}
+
+impl PhotoOrganization {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+pub fn validate_rating(rating: Option<u8>) -> Result<(), AppError> {
+    match rating {
+        Some(stars) if stars == 0 || stars > MAX_RATING => Err(AppError::Validation(format!(
+            "Ratings go from 1 to {} stars",
+            MAX_RATING
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn organization_aad(album_id: &str) -> String {
+    format!("{}#organization", album_id)
+}
+
+/// The organization `manifest` records, by file name. Encrypted albums
+/// need their key.
+pub fn read_organization(
+    manifest: &AlbumManifest,
+    album_key: Option<&[u8; 32]>,
+    album_id: &str,
+) -> Result<BTreeMap<String, PhotoOrganization>, AppError> {
+    if !manifest.encrypted {
+        return Ok(manifest.organization.clone());
+    }
+    let (Some(sealed), Some(key)) = (&manifest.sealed_organization, album_key) else {
+        return match manifest.sealed_organization {
+            None => Ok(BTreeMap::new()),
+            Some(_) => Err(AppError::Validation("Unlock your keypair to read this album's tags".into())),
+        };
+    };
+    let raw = STANDARD
+        .decode(sealed)
+        .map_err(|_| AppError::Validation("Invalid sealed organization".into()))?;
+    let json = decrypt_with_key(&raw, key, organization_aad(album_id).as_bytes())
+        .map_err(|e| AppError::Validation(format!("Decryption failed: {}", e)))?;
+    serde_json::from_slice(&json).map_err(|e| AppError::Validation(format!("Corrupt album organization: {}", e)))
+}
+
+/// Store `organization` in `manifest`, dropping photos with nothing set
+pub fn write_organization(
+    manifest: &mut AlbumManifest,
+    album_key: Option<&[u8; 32]>,
+    album_id: &str,
+    mut organization: BTreeMap<String, PhotoOrganization>,
+) -> Result<(), AppError> {
+    organization.retain(|_, o| !o.is_empty());
+    if !manifest.encrypted {
+        manifest.organization = organization;
+        return Ok(());
+    }
+
+    let key = album_key.ok_or_else(|| AppError::Validation("Unlock your keypair to organize this album".into()))?;
+    manifest.organization.clear();
+    manifest.sealed_organization = if organization.is_empty() {
+        None
+    } else {
+        let json = serde_json::to_vec(&organization)
+            .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+        let sealed = encrypt_with_key(&json, key, organization_aad(album_id).as_bytes())
+            .map_err(|e| AppError::Validation(format!("Encryption failed: {}", e)))?;
+        Some(STANDARD.encode(sealed))
+    };
+    Ok(())
+}
+
+fn album_key(
+    manifest: &AlbumManifest,
+    keypair_handle: Option<KeypairHandle>,
+    repo: &str,
+    album_path: &str,
+) -> Result<Option<[u8; 32]>, AppError> {
+    match (manifest.encrypted, keypair_handle) {
+        (false, _) => Ok(None),
+        (true, Some(handle)) => album_key_for(handle, repo, album_path, manifest).map(Some),
+        (true, None) => Err(AppError::Validation("Unlock your keypair to organize this album".into())),
+    }
+}
+
+/// Apply `change` to the organization of the photo at `path`, in its album
+/// manifest and then in the catalog
+async fn organize_photo(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    path: &str,
+    keypair_handle: Option<KeypairHandle>,
+    change: impl FnOnce(&mut PhotoOrganization),
+) -> Result<PhotoOrganization, AppError> {
+    validate_repo(repo)?;
+    let path = path.trim_matches('/');
+    let album_path = parent_album_path(path);
+    let name = path.rsplit('/').next().unwrap_or(path);
+    if album_path.is_empty() || path.contains("..") {
+        return Err(AppError::Validation("Invalid photo path".into()));
+    }
+
+    let (mut manifest, sha) = manifest_for_update(client, repo, token, album_path).await?;
+    if manifest.encrypted && !manifest.entries.contains_key(name) {
+        return Err(GithubError::NotFound {
+            message: format!("Photo not found: {}", path),
+        }
+        .into());
+    }
+    let key = album_key(&manifest, keypair_handle, repo, album_path)?;
+    let id = album_id(repo, album_path);
+
+    let mut organization = read_organization(&manifest, key.as_ref(), &id)?;
+    let entry = organization.entry(name.to_string()).or_default();
+    change(entry);
+    let updated = entry.clone();
+    write_organization(&mut manifest, key.as_ref(), &id, organization.clone())?;
+    save_manifest(client, repo, token, album_path, &mut manifest, sha.as_deref(), keypair_handle).await?;
+
+    import_organization(repo, album_path, &organization);
+    Ok(updated)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Replace the tags of a photo
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn tag_photo(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    tags: Vec<String>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<PhotoOrganization, AppError> {
+    let tags = normalize_tags(&tags)?;
+    if tags.len() > MAX_TAGS {
+        return Err(AppError::Validation(format!("Photos can carry at most {} tags", MAX_TAGS)));
+    }
+    let organization = organize_photo(&client.0, &repo, &token, &path, keypair_handle, |o| o.tags = tags).await?;
+    notify_smart_albums(&app, &repo);
+    Ok(organization)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_favorite(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    favorite: bool,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<PhotoOrganization, AppError> {
+    let organization = organize_photo(&client.0, &repo, &token, &path, keypair_handle, |o| o.favorite = favorite).await?;
+    notify_smart_albums(&app, &repo);
+    Ok(organization)
+}
+
+/// Rate a photo 1 to `MAX_RATING` stars, or clear its rating with `None`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn set_rating(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    path: String,
+    rating: Option<u8>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<PhotoOrganization, AppError> {
+    validate_rating(rating)?;
+    let organization = organize_photo(&client.0, &repo, &token, &path, keypair_handle, |o| o.rating = rating).await?;
+    notify_smart_albums(&app, &repo);
+    Ok(organization)
+}
+
+/// Copy an album's organization from its manifest into the catalog.
+/// Returns how many photos it lists organization for.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn sync_photo_organization(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<usize, AppError> {
+    validate_repo(&repo)?;
+    let album_path = album_path.trim_matches('/');
+    let Some((manifest, _)) = fetch_manifest(&client.0, &repo, &token, album_path).await? else {
+        return Ok(0);
+    };
+    let key = album_key(&manifest, keypair_handle, &repo, album_path)?;
+    let organization = read_organization(&manifest, key.as_ref(), &album_id(&repo, album_path))?;
+    import_organization(&repo, album_path, &organization);
+    notify_smart_albums(&app, &repo);
+    Ok(organization.len())
+}
@@ -0,0 +1,682 @@
+//! Google Takeout Import
+//!
+//! `import_google_takeout` moves a Google Photos library out of a Takeout
+//! export: the `.zip` files Google hands out (large libraries are split
+//! across several) or the folder they were extracted to. Each folder of the
+//! export becomes an album; the yearly `Photos from 2019` folders go to
+//! `Google Photos/2019`, and other folders take the title and description
+//! from their `metadata.json`.
+//!
+//! Google keeps what it knows about a photo in a JSON sidecar next to it,
+//! named after the photo but truncated to Google's filename limit, and for
+//! newer exports suffixed with `.supplemental-metadata`. The description
+//! becomes the caption, the capture time and place fill the gaps of the
+//! photo's own EXIF, and favorites are marked as such (see `tagging`).
+//! Captions and EXIF live in the metadata vault, so Takeout albums are
+//! always encrypted albums.
+//!
+//! Google exports a photo once per album it is in and once more in its year
+//! folder. Photos are deduplicated by content: each is imported into the
+//! first album it was found in, named albums before year folders. Photos
+//! already in an album are skipped as well, so an interrupted import can
+//! simply be run again.
+//!
+//! With a preset, every photo is run through that pipeline first and
+//! stored as `<name>.vxp`, as routed uploads are (see `pipeline_routing`).
+//! Each album is written in a single commit.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{Emitter, State};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::album::{
+    album_key_for, album_path_for, encrypted_blob_name, fetch_manifest, manifest_body, seal_album_photo,
+    seal_filename, AlbumManifest, ALBUM_MANIFEST_FILE, ALBUM_ROOT,
+};
+use crate::crypto::{hash_data, with_keypair, KeypairHandle};
+use crate::git_data::{branch_head, commit_changes, create_blob, TreeChange};
+use crate::github::{is_photo_file, sanitize_filename, validate_repo, AppError, HttpClient, UploadBatchProgress};
+use crate::metadata_vault::{fetch_vault, stage_vault, PhotoMetadata};
+use crate::mirror::replicate_tree_changes;
+use crate::pipeline::{
+    pipeline_context, pipeline_get_presets, process_pipeline_for_file, PipelineConfig, PipelineContext,
+};
+use crate::pipeline_history::{record_run, PipelineRun};
+use crate::pipeline_routing::upload_intent;
+use crate::repo_import::SkippedPhoto;
+use crate::security_verify::{sign_photo, signature_path};
+use crate::sharing::album_id;
+use crate::tagging::{read_organization, write_organization};
+use crate::upload_policy::{check_upload, UploadIntent};
+use crate::video::is_video_file;
+
+/// Parent album of the photos from Google's year folders
+pub const LIBRARY_ALBUM: &str = "Google Photos";
+/// Album details Google exports next to the photos of an album
+pub const ALBUM_METADATA_FILE: &str = "metadata.json";
+/// Google truncates sidecar names to this many characters before `.json`
+const SIDECAR_STEM_LEN: usize = 46;
+const SIDECAR_SUFFIX: &str = ".supplemental-metadata";
+const MAX_CAPTION_LEN: usize = 2000;
+/// Folders of photos Google had deleted or could not process
+const SKIPPED_FOLDERS: &[&str] = &["Trash", "Bin", "Failed Videos"];
+
+// ============================================================================
+// Reading Exports
+// ============================================================================
+
+enum Source {
+    Folder(PathBuf),
+    Zip(zip::ZipArchive<BufReader<File>>),
+}
+
+/// The files of one or more Takeout archives or extracted folders, by
+/// their `/`-separated path within the export
+pub struct TakeoutExport {
+    sources: Vec<Source>,
+    files: BTreeMap<String, usize>,
+}
+
+fn archive_error(path: &str, e: zip::result::ZipError) -> AppError {
+    AppError::Validation(format!("Invalid Takeout archive {}: {}", path, e))
+}
+
+fn walk_folder(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), AppError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk_folder(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+impl TakeoutExport {
+    /// Open `paths`, each a Takeout `.zip` or an extracted folder. A file
+    /// found in several parts is read from the first.
+    pub fn open(paths: &[String]) -> Result<Self, AppError> {
+        let mut export = Self { sources: Vec::new(), files: BTreeMap::new() };
+        for (index, path) in paths.iter().enumerate() {
+            let location = Path::new(path);
+            let names = if location.is_dir() {
+                let mut names = Vec::new();
+                walk_folder(location, location, &mut names)?;
+                export.sources.push(Source::Folder(location.to_path_buf()));
+                names
+            } else {
+                let archive = zip::ZipArchive::new(BufReader::new(File::open(location)?))
+                    .map_err(|e| archive_error(path, e))?;
+                let names = archive.file_names().filter(|n| !n.ends_with('/')).map(str::to_string).collect();
+                export.sources.push(Source::Zip(archive));
+                names
+            };
+            for name in names {
+                export.files.entry(name).or_insert(index);
+            }
+        }
+        Ok(export)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    pub fn read(&mut self, path: &str) -> Result<Vec<u8>, AppError> {
+        let index = *self
+            .files
+            .get(path)
+            .ok_or_else(|| AppError::Validation(format!("{} is not in the export", path)))?;
+        match &mut self.sources[index] {
+            Source::Folder(root) => Ok(std::fs::read(root.join(path))?),
+            Source::Zip(archive) => {
+                let mut file = archive.by_name(path).map_err(|e| archive_error(path, e))?;
+                let mut content = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut content)?;
+                Ok(content)
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Scanning
+// ============================================================================
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TakeoutPhoto {
+    pub path: String,
+    /// Path of the JSON sidecar, when one was found
+    pub sidecar: Option<String>,
+}
+
+/// One folder of the export
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TakeoutFolder {
+    pub folder: String,
+    pub name: String,
+    /// Set for Google's `Photos from <year>` folders
+    pub year: Option<String>,
+    /// Path of the folder's `metadata.json`
+    pub metadata: Option<String>,
+    pub photos: Vec<TakeoutPhoto>,
+}
+
+fn split_path(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+/// The year of a `Photos from 2019` folder
+pub fn year_of_folder(name: &str) -> Option<&str> {
+    let year = name.strip_prefix("Photos from ")?;
+    (year.len() == 4 && year.bytes().all(|b| b.is_ascii_digit())).then_some(year)
+}
+
+/// `IMG(1)` -> (`IMG`, `(1)`), the counter Google adds to clashing names
+fn split_counter(stem: &str) -> (&str, &str) {
+    if let Some(open) = stem.strip_suffix(')').and_then(|s| s.rfind('(')) {
+        let digits = &stem[open + 1..stem.len() - 1];
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            return stem.split_at(open);
+        }
+    }
+    (stem, "")
+}
+
+/// The sidecar of the photo `file_name` among the JSON files `candidates`
+/// of its folder. Handles truncated names, the `.supplemental-metadata`
+/// suffix, counters (`IMG(1).jpg` has `IMG.jpg(1).json`) and edited copies,
+/// which share the original's sidecar.
+pub fn sidecar_for<'a>(file_name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let (stem, ext) = file_name.rsplit_once('.').unwrap_or((file_name, ""));
+    let (stem, counter) = split_counter(stem);
+    let stem = stem.strip_suffix("-edited").unwrap_or(stem);
+    let key = if ext.is_empty() { stem.to_string() } else { format!("{}.{}", stem, ext) };
+    let full = format!("{}{}", key, SIDECAR_SUFFIX);
+
+    candidates
+        .iter()
+        .filter(|c| **c != ALBUM_METADATA_FILE)
+        .filter_map(|candidate| {
+            let (base, found) = split_counter(candidate.strip_suffix(".json")?);
+            let truncated = full.starts_with(base) && base.len() >= key.len().min(SIDECAR_STEM_LEN);
+            (found == counter && (truncated || base == stem)).then_some((base.len(), *candidate))
+        })
+        .max()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Group the media of an export by folder: named albums first, then the
+/// year folders, each sorted by path
+pub fn scan_export<'a>(files: impl IntoIterator<Item = &'a str>) -> Vec<TakeoutFolder> {
+    let mut folders: BTreeMap<&str, (Vec<&str>, Vec<&str>)> = BTreeMap::new();
+    for path in files {
+        let (folder, name) = split_path(path);
+        if name.starts_with('.') {
+            continue;
+        }
+        let (media, json) = folders.entry(folder).or_default();
+        if name.ends_with(".json") {
+            json.push(name);
+        } else if is_photo_file(Path::new(name)) || is_video_file(Path::new(name)) {
+            media.push(name);
+        }
+    }
+
+    let mut scanned: Vec<TakeoutFolder> = folders
+        .into_iter()
+        .filter(|(_, (media, _))| !media.is_empty())
+        .filter_map(|(folder, (media, json))| {
+            let name = split_path(folder).1;
+            if SKIPPED_FOLDERS.contains(&name) {
+                return None;
+            }
+            let join = |file: &str| if folder.is_empty() { file.to_string() } else { format!("{}/{}", folder, file) };
+            Some(TakeoutFolder {
+                folder: folder.to_string(),
+                name: if name.is_empty() { LIBRARY_ALBUM.to_string() } else { name.to_string() },
+                year: year_of_folder(name).map(str::to_string),
+                metadata: json.contains(&ALBUM_METADATA_FILE).then(|| join(ALBUM_METADATA_FILE)),
+                photos: media
+                    .iter()
+                    .map(|file| TakeoutPhoto { path: join(file), sidecar: sidecar_for(file, &json).map(join) })
+                    .collect(),
+            })
+        })
+        .collect();
+    scanned.sort_by(|a, b| (a.year.is_some(), &a.folder).cmp(&(b.year.is_some(), &b.folder)));
+    scanned
+}
+
+// ============================================================================
+// Google Metadata
+// ============================================================================
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct GoogleAlbum {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GoogleTime {
+    /// Unix seconds, as a string
+    pub timestamp: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct GoogleGeo {
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub altitude: f64,
+}
+
+impl GoogleGeo {
+    /// Google writes zeros for photos without a place
+    fn is_set(&self) -> bool {
+        self.latitude != 0.0 || self.longitude != 0.0
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleSidecar {
+    /// Original file name, before Google truncated it for the export
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub photo_taken_time: Option<GoogleTime>,
+    /// The place as edited in Google Photos
+    #[serde(default)]
+    pub geo_data: Option<GoogleGeo>,
+    /// The place from the photo's EXIF at upload
+    #[serde(default)]
+    pub geo_data_exif: Option<GoogleGeo>,
+    #[serde(default)]
+    pub favorited: bool,
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(path: &str, bytes: &[u8]) -> Result<T, AppError> {
+    serde_json::from_slice(bytes).map_err(|e| AppError::Validation(format!("Invalid Takeout metadata {}: {}", path, e)))
+}
+
+/// Unix seconds as an EXIF `YYYY:MM:DD HH:MM:SS` in UTC
+pub fn exif_time(secs: i64) -> String {
+    // Civil date from days (proleptic Gregorian), see Howard Hinnant's algorithms
+    let days = secs.div_euclid(86400);
+    let time = secs.rem_euclid(86400);
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+impl GoogleSidecar {
+    pub fn taken_at(&self) -> Option<String> {
+        let secs: i64 = self.photo_taken_time.as_ref()?.timestamp.trim().parse().ok()?;
+        (secs > 0).then(|| exif_time(secs))
+    }
+
+    pub fn location(&self) -> Option<GoogleGeo> {
+        [self.geo_data, self.geo_data_exif].into_iter().flatten().find(GoogleGeo::is_set)
+    }
+
+    pub fn caption(&self) -> Option<String> {
+        let description = self.description.as_deref().map(str::trim).filter(|d| !d.is_empty())?;
+        Some(description.chars().take(MAX_CAPTION_LEN).collect())
+    }
+
+    /// Add the caption, and the capture time and place where the photo's
+    /// own EXIF has none
+    pub fn apply(&self, metadata: &mut PhotoMetadata) {
+        if let Some(caption) = self.caption() {
+            metadata.caption = Some(caption);
+        }
+        let exif = metadata.exif.get_or_insert_with(Default::default);
+        if exif.taken_at.is_none() {
+            if let Some(taken_at) = self.taken_at() {
+                exif.taken_at = Some(taken_at);
+                exif.time_offset = Some("+00:00".into());
+            }
+        }
+        if exif.latitude.is_none() || exif.longitude.is_none() {
+            if let Some(geo) = self.location() {
+                exif.latitude = Some(geo.latitude);
+                exif.longitude = Some(geo.longitude);
+                exif.altitude = Some(geo.altitude);
+            }
+        }
+        if metadata.exif.as_ref().is_some_and(|e| *e == Default::default()) {
+            metadata.exif = None;
+        }
+    }
+}
+
+/// Album name of `folder` below `photos/`
+pub fn album_name(folder: &TakeoutFolder, album: Option<&GoogleAlbum>) -> String {
+    if let Some(year) = &folder.year {
+        return format!("{}/{}", LIBRARY_ALBUM, year);
+    }
+    album
+        .and_then(|a| a.title.as_deref())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or(&folder.name)
+        .replace('/', "-")
+}
+
+/// Name to store a photo under: Google's original name when it kept the
+/// extension, the exported one otherwise
+fn photo_name(path: &str, sidecar: Option<&GoogleSidecar>) -> String {
+    let exported = split_path(path).1;
+    let extension = |name: &str| Path::new(name).extension().map(|e| e.to_ascii_lowercase());
+    sidecar
+        .and_then(|s| s.title.as_deref())
+        .map(sanitize_filename)
+        .filter(|title| !title.is_empty() && extension(title) == extension(exported))
+        .unwrap_or_else(|| sanitize_filename(exported))
+}
+
+// ============================================================================
+// Importing
+// ============================================================================
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TakeoutAlbum {
+    pub path: String,
+    pub imported: usize,
+    pub commit_sha: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TakeoutImport {
+    pub albums: Vec<TakeoutAlbum>,
+    /// Duplicates and photos already imported
+    pub skipped: Vec<SkippedPhoto>,
+    pub failed: Vec<SkippedPhoto>,
+}
+
+/// Photos of the export bound for one album
+struct AlbumPlan {
+    path: String,
+    description: Option<String>,
+    photos: Vec<TakeoutPhoto>,
+}
+
+fn plan_albums(export: &mut TakeoutExport, result: &mut TakeoutImport) -> Vec<AlbumPlan> {
+    let mut plans: Vec<AlbumPlan> = Vec::new();
+    for folder in scan_export(export.files()) {
+        let album = match &folder.metadata {
+            Some(path) => match export.read(path).and_then(|bytes| parse_json::<GoogleAlbum>(path, &bytes)) {
+                Ok(album) => Some(album),
+                Err(e) => {
+                    tracing::warn!("Ignoring album details {}: {}", path, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let path = match album_path_for(ALBUM_ROOT, &album_name(&folder, album.as_ref())) {
+            Ok(path) => path,
+            Err(e) => {
+                for photo in folder.photos {
+                    result.failed.push(SkippedPhoto { path: photo.path, reason: e.to_string() });
+                }
+                continue;
+            }
+        };
+        let description = album.and_then(|a| a.description).filter(|d| !d.trim().is_empty());
+        // Google albums with the same title are merged
+        match plans.iter_mut().find(|p| p.path == path) {
+            Some(plan) => {
+                plan.photos.extend(folder.photos);
+                plan.description = plan.description.take().or(description);
+            }
+            None => plans.push(AlbumPlan { path, description, photos: folder.photos }),
+        }
+    }
+    plans
+}
+
+/// Run `content` through `config` off the async runtime
+async fn run_preset(
+    content: Vec<u8>,
+    name: &str,
+    config: &PipelineConfig,
+    context: &Arc<PipelineContext>,
+) -> Result<Vec<u8>, AppError> {
+    let started = std::time::Instant::now();
+    let input_size = content.len();
+    let (file_name, job_config, job_context) = (name.to_string(), config.clone(), Arc::clone(context));
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        process_pipeline_for_file(&content, Some(&file_name), &job_config, &job_context)
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Pipeline task failed: {}", e)))?;
+    record_run(&PipelineRun::single(config, input_size, &result, started.elapsed()));
+    Ok(result.map_err(|e| AppError::Validation(e.to_string()))?.data)
+}
+
+fn read_sidecar(export: &mut TakeoutExport, photo: &TakeoutPhoto) -> Option<GoogleSidecar> {
+    let path = photo.sidecar.as_deref()?;
+    match export.read(path).and_then(|bytes| parse_json(path, &bytes)) {
+        Ok(sidecar) => Some(sidecar),
+        Err(e) => {
+            tracing::warn!("Ignoring sidecar {}: {}", path, e);
+            None
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Import the Google Takeout export in `paths` (its `.zip` parts or the
+/// folder it was extracted to) into encrypted albums, optionally running
+/// every photo through the pipeline preset `preset` first with `passwords`
+/// and `pipeline_keypair` for its layers
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_google_takeout(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    paths: Vec<String>,
+    repo: String,
+    token: Zeroizing<String>,
+    keypair_handle: KeypairHandle,
+    preset: Option<String>,
+    passwords: Option<HashMap<String, String>>,
+    pipeline_keypair: Option<Vec<u8>>,
+) -> Result<TakeoutImport, AppError> {
+    validate_repo(&repo)?;
+    if paths.is_empty() {
+        return Err(AppError::Validation("Choose the Takeout archives to import".into()));
+    }
+    let config = match &preset {
+        Some(id) => Some(
+            pipeline_get_presets()
+                .into_iter()
+                .find(|p| &p.id == id)
+                .ok_or_else(|| AppError::Validation(format!("Unknown pipeline preset: {}", id)))?,
+        ),
+        None => None,
+    };
+    let context = Arc::new(pipeline_context(passwords.unwrap_or_default(), pipeline_keypair)?);
+    let intent = UploadIntent {
+        encrypted: true,
+        ..config.as_ref().map(upload_intent).unwrap_or_default()
+    };
+    let owner_key_id = with_keypair(keypair_handle, |kp| Ok(kp.public_bundle().key_id))
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let mut export = TakeoutExport::open(&paths)?;
+    let mut result = TakeoutImport::default();
+    let plans = plan_albums(&mut export, &mut result);
+    let total_files: usize = plans.iter().map(|p| p.photos.len()).sum();
+    let mut completed_files = 0;
+    // Content hash -> where the photo was first found
+    let mut seen: HashMap<[u8; 32], String> = HashMap::new();
+
+    for plan in plans {
+        let existing = fetch_manifest(&client.0, &repo, &token, &plan.path).await?;
+        let mut manifest = match existing {
+            Some((manifest, _)) if !manifest.encrypted => {
+                for photo in plan.photos {
+                    completed_files += 1;
+                    let reason = format!("{} is a plain album", plan.path);
+                    result.failed.push(SkippedPhoto { path: photo.path, reason });
+                }
+                continue;
+            }
+            Some((manifest, _)) => manifest,
+            None => AlbumManifest::new(true, Some(owner_key_id.clone())),
+        };
+        let id = album_id(&repo, &plan.path);
+        let album_key = album_key_for(keypair_handle, &repo, &plan.path, &manifest)?;
+        let (mut vault, _) = fetch_vault(&client.0, &repo, &token, &plan.path, &album_key).await?;
+        let mut organization = read_organization(&manifest, Some(&album_key), &id)?;
+        let mut changes = Vec::new();
+        let mut imported = 0;
+
+        for photo in plan.photos {
+            let _ = app.emit(
+                "batch-upload-progress",
+                UploadBatchProgress {
+                    total_files,
+                    completed_files,
+                    current_file: split_path(&photo.path).1.to_string(),
+                    percent: ((completed_files * 100) / total_files.max(1)) as u8,
+                },
+            );
+            completed_files += 1;
+
+            let content = match export.read(&photo.path) {
+                Ok(content) => content,
+                Err(e) => {
+                    result.failed.push(SkippedPhoto { path: photo.path, reason: e.to_string() });
+                    continue;
+                }
+            };
+            let hash = hash_data(&content);
+            if let Some(first) = seen.get(&hash) {
+                let reason = format!("Duplicate of {}", first);
+                result.skipped.push(SkippedPhoto { path: photo.path, reason });
+                continue;
+            }
+            seen.insert(hash, photo.path.clone());
+            // Named by the original, so the pipeline output is found again on a rerun
+            let blob_name = encrypted_blob_name(&album_key, &content);
+            if manifest.entries.contains_key(&blob_name) {
+                let reason = format!("Already in {}", plan.path);
+                result.skipped.push(SkippedPhoto { path: photo.path, reason });
+                continue;
+            }
+
+            let sidecar = read_sidecar(&mut export, &photo);
+            let name = photo_name(&photo.path, sidecar.as_ref());
+            let mut metadata = PhotoMetadata::for_upload(&name, &content);
+            if let Some(sidecar) = &sidecar {
+                sidecar.apply(&mut metadata);
+            }
+
+            let staged = async {
+                check_upload(&name, &content, intent)?;
+                let (filename, data) = match &config {
+                    Some(config) => {
+                        let processed = run_preset(content.clone(), &name, config, &context).await?;
+                        (format!("{}.vxp", name), processed)
+                    }
+                    None => (name.clone(), content.clone()),
+                };
+                let payload = seal_album_photo(&album_key, &id, &filename, &data)?;
+                let upload_path = format!("{}/{}", plan.path, blob_name);
+                let signature = sign_photo(keypair_handle, &payload)?;
+                let photo_sha = create_blob(&client.0, &repo, &token, &payload).await?;
+                let signature_sha = create_blob(&client.0, &repo, &token, &signature).await?;
+                Ok::<_, AppError>((filename, payload, [
+                    TreeChange::blob(&upload_path, &photo_sha),
+                    TreeChange::blob(&signature_path(&upload_path), &signature_sha),
+                ]))
+            };
+            match staged.await {
+                Ok((filename, payload, staged)) => {
+                    changes.extend(staged);
+                    manifest.entries.insert(blob_name.clone(), seal_filename(&album_key, &id, &filename)?);
+                    manifest.record_signed(&blob_name, &payload);
+                    manifest.original_bytes += content.len() as u64;
+                    manifest.stored_bytes += payload.len() as u64;
+                    metadata.filename = filename;
+                    vault.entries.insert(blob_name.clone(), metadata);
+                    if sidecar.as_ref().is_some_and(|s| s.favorited) {
+                        organization.entry(blob_name).or_default().favorite = true;
+                    }
+                    imported += 1;
+                }
+                Err(e) => result.failed.push(SkippedPhoto { path: photo.path, reason: e.to_string() }),
+            }
+        }
+
+        if imported == 0 {
+            continue;
+        }
+        if manifest.description.is_none() {
+            manifest.set_description(plan.description.as_deref())?;
+        }
+        write_organization(&mut manifest, Some(&album_key), &id, organization)?;
+        changes.push(stage_vault(&client.0, &repo, &token, &plan.path, &mut vault, &manifest, &album_key).await?);
+        let body = manifest_body(&mut manifest, Some(keypair_handle))?;
+        let manifest_sha = create_blob(&client.0, &repo, &token, &body).await?;
+        changes.push(TreeChange::blob(&format!("{}/{}", plan.path, ALBUM_MANIFEST_FILE), &manifest_sha));
+
+        // Fetched per album, since each album is its own commit
+        let head = branch_head(&client.0, &repo, &token).await?;
+        let message = format!(
+            "Import {} photo{} from Google Takeout into {}",
+            imported,
+            if imported == 1 { "" } else { "s" },
+            plan.path
+        );
+        let commit_sha = commit_changes(&client.0, &repo, &token, &head, &changes, &message).await?;
+        replicate_tree_changes(&client.0, &repo, &token, &changes);
+        result.albums.push(TakeoutAlbum { path: plan.path, imported, commit_sha });
+    }
+
+    let _ = app.emit(
+        "batch-upload-progress",
+        UploadBatchProgress {
+            total_files,
+            completed_files: total_files,
+            current_file: String::new(),
+            percent: 100,
+        },
+    );
+    Ok(result)
+}
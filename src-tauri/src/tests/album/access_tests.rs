@@ -0,0 +1,87 @@
+//! Shared Album Tests
+//!
+//! Tests for:
+//! - Wrapping the album key for recipients
+//! - Resolving album keys for owners, recipients and strangers
+//! - Re-sealing photos and re-wrapping grants when the key rotates
+
+use crate::album::{album_key_for, album_key_info, open_album_photo, owner_album_key, seal_album_photo, AlbumManifest};
+use crate::album_access::{grant_access, rekey_photo, rewrap_grants};
+use crate::crypto::{generate_keypair, release_keypair, KeypairInfo};
+use crate::sharing::album_id;
+
+const REPO: &str = "alice/photos";
+const ALBUM: &str = "photos/Trips";
+
+fn keypair() -> KeypairInfo {
+    generate_keypair().expect("keypair generation")
+}
+
+fn owned_album(owner: &KeypairInfo) -> AlbumManifest {
+    AlbumManifest::new(true, Some(owner.key_id.clone()))
+}
+
+#[test]
+fn epoch_zero_keeps_the_original_derivation() {
+    assert_eq!(album_key_info("r:a", 0), "r:a");
+    assert_ne!(album_key_info("r:a", 1), album_key_info("r:a", 2));
+}
+
+#[test]
+fn recipients_unwrap_the_owner_key() {
+    let owner = keypair();
+    let friend = keypair();
+    let stranger = keypair();
+    let id = album_id(REPO, ALBUM);
+
+    let mut manifest = owned_album(&owner);
+    let key = owner_album_key(owner.handle, &id, 0).unwrap();
+    grant_access(&mut manifest, &key, &id, friend.public_bundle.clone()).unwrap();
+
+    assert_eq!(album_key_for(owner.handle, REPO, ALBUM, &manifest).unwrap(), key);
+    assert_eq!(album_key_for(friend.handle, REPO, ALBUM, &manifest).unwrap(), key);
+    assert!(album_key_for(stranger.handle, REPO, ALBUM, &manifest).is_err());
+
+    // A grant is bound to its album
+    assert!(album_key_for(friend.handle, REPO, "photos/Other", &manifest).is_err());
+    // The owner cannot be a recipient
+    assert!(grant_access(&mut manifest, &key, &id, owner.public_bundle.clone()).is_err());
+
+    for kp in [owner, friend, stranger] {
+        release_keypair(kp.handle).unwrap();
+    }
+}
+
+#[test]
+fn rotation_reseals_photos_and_rewraps_grants() {
+    let owner = keypair();
+    let friend = keypair();
+    let revoked = keypair();
+    let id = album_id(REPO, ALBUM);
+
+    let mut manifest = owned_album(&owner);
+    let old_key = owner_album_key(owner.handle, &id, 0).unwrap();
+    grant_access(&mut manifest, &old_key, &id, friend.public_bundle.clone()).unwrap();
+    grant_access(&mut manifest, &old_key, &id, revoked.public_bundle.clone()).unwrap();
+    let payload = seal_album_photo(&old_key, &id, "beach.jpg", b"not really a jpeg").unwrap();
+
+    manifest.access.remove(&revoked.key_id);
+    manifest.key_epoch += 1;
+    let new_key = owner_album_key(owner.handle, &id, manifest.key_epoch).unwrap();
+    assert_ne!(old_key, new_key);
+
+    let rekeyed = rekey_photo(&old_key, &new_key, &id, &payload, None).unwrap();
+    let (data, name) = open_album_photo(&new_key, &id, &rekeyed.payload).unwrap();
+    assert_eq!(data, b"not really a jpeg");
+    assert_eq!(name.as_deref(), Some("beach.jpg"));
+    assert!(open_album_photo(&old_key, &id, &rekeyed.payload).is_err());
+
+    rewrap_grants(&mut manifest, &new_key, &id).unwrap();
+    assert_eq!(album_key_for(friend.handle, REPO, ALBUM, &manifest).unwrap(), new_key);
+    assert_eq!(album_key_for(owner.handle, REPO, ALBUM, &manifest).unwrap(), new_key);
+    assert!(album_key_for(revoked.handle, REPO, ALBUM, &manifest).is_err());
+
+    for kp in [owner, friend, revoked] {
+        release_keypair(kp.handle).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+//! Album Comment Tests
+//!
+//! Tests for:
+//! - Sealing and opening comments and the thread map under the album key
+//! - Author signatures and membership
+//! - Listing a thread's comments from the tree and paging through them
+
+use crate::album::AlbumManifest;
+use crate::comments::{
+    album_members, comment_path, comment_seqs, comment_threads_path, open_comment, page_comments, seal_comment,
+    CommentThreads,
+};
+use crate::crypto::{generate_keypair, release_keypair, KeypairInfo};
+use crate::git_data::{index_blobs, TreeEntry};
+use crate::sharing::album_id;
+
+const ALBUM: &str = "photos/Trips";
+const THREAD: &str = "00112233445566778899aabbccddeeff";
+
+fn keypair() -> KeypairInfo {
+    generate_keypair().expect("keypair generation")
+}
+
+fn blob(path: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.to_string(),
+        mode: "100644".to_string(),
+        kind: "blob".to_string(),
+        sha: format!("sha-{}", path),
+        size: Some(10),
+    }
+}
+
+// ============================================================================
+// Sealing Tests
+// ============================================================================
+
+#[test]
+fn comments_roundtrip_and_verify() {
+    let author = keypair();
+    let key = [5u8; 32];
+    let id = album_id("alice/photos", ALBUM);
+
+    let envelope = seal_comment(author.handle, &key, &id, THREAD, 3, "Great light here").unwrap();
+    assert_eq!(envelope.seq, 3);
+    assert!(!String::from_utf8_lossy(&envelope.sealed).contains("Great light"));
+
+    let members = vec![author.key_id.clone()];
+    let comment = open_comment(&key, &id, THREAD, &envelope, &members).unwrap();
+    assert_eq!(comment.content, "Great light here");
+    assert_eq!(comment.author, author.key_id);
+    assert!(comment.verified);
+    assert!(comment.author_is_member);
+
+    // Bound to the key, album, thread and position
+    assert!(open_comment(&[6u8; 32], &id, THREAD, &envelope, &members).is_err());
+    assert!(open_comment(&key, "alice/photos:photos/Other", THREAD, &envelope, &members).is_err());
+    assert!(open_comment(&key, &id, "ffeeddccbbaa99887766554433221100", &envelope, &members).is_err());
+    let mut moved = envelope.clone();
+    moved.seq = 4;
+    assert!(open_comment(&key, &id, THREAD, &moved, &members).is_err());
+
+    release_keypair(author.handle).unwrap();
+}
+
+#[test]
+fn forged_and_outside_comments_are_flagged() {
+    let author = keypair();
+    let stranger = keypair();
+    let key = [5u8; 32];
+    let id = album_id("alice/photos", ALBUM);
+    let members = vec![author.key_id.clone()];
+
+    let mut tampered = seal_comment(author.handle, &key, &id, THREAD, 1, "hello").unwrap();
+    tampered.posted_at += 60;
+    let comment = open_comment(&key, &id, THREAD, &tampered, &members).unwrap();
+    assert!(!comment.verified);
+
+    // Claiming someone else's key id does not make a stranger a member
+    let mut impostor = seal_comment(stranger.handle, &key, &id, THREAD, 2, "hi").unwrap();
+    impostor.author.key_id = author.key_id.clone();
+    let comment = open_comment(&key, &id, THREAD, &impostor, &members).unwrap();
+    assert_eq!(comment.author, stranger.key_id);
+    assert!(!comment.author_is_member);
+
+    for kp in [author, stranger] {
+        release_keypair(kp.handle).unwrap();
+    }
+}
+
+#[test]
+fn thread_map_roundtrips_under_album_key() {
+    let key = [7u8; 32];
+    let id = album_id("alice/photos", ALBUM);
+    let mut threads = CommentThreads::default();
+    threads.threads.insert("abc.vxe".into(), THREAD.into());
+
+    let sealed = threads.seal(&key, &id).unwrap();
+    assert!(!String::from_utf8_lossy(&sealed).contains("abc.vxe"));
+    assert_eq!(CommentThreads::open(&key, &id, &sealed).unwrap(), threads);
+    assert!(CommentThreads::open(&[8u8; 32], &id, &sealed).is_err());
+}
+
+#[test]
+fn members_are_the_owner_and_recipients() {
+    let manifest = AlbumManifest::new(true, Some("owner".into()));
+    assert_eq!(album_members(&manifest), vec!["owner".to_string()]);
+}
+
+// ============================================================================
+// Listing Tests
+// ============================================================================
+
+#[test]
+fn comment_paths_sort_by_sequence() {
+    assert_eq!(comment_threads_path("/photos/Trips/"), "photos/Trips/.vortex-comments/threads.bin");
+    assert_eq!(comment_path(ALBUM, THREAD, 12), format!("photos/Trips/.vortex-comments/{}/0000000012.cmt", THREAD));
+
+    let index = index_blobs(vec![
+        blob(&comment_path(ALBUM, THREAD, 10)),
+        blob(&comment_path(ALBUM, THREAD, 2)),
+        blob(&comment_path(ALBUM, "other", 1)),
+        blob(&comment_threads_path(ALBUM)),
+        blob("photos/Trips/abc.vxe"),
+    ]);
+    assert_eq!(comment_seqs(&index, ALBUM, THREAD), vec![2, 10]);
+    assert!(comment_seqs(&index, ALBUM, "missing").is_empty());
+}
+
+#[test]
+fn pages_walk_back_from_the_newest() {
+    let seqs: Vec<u64> = (1..=5).collect();
+
+    let (page, next) = page_comments(&seqs, None, 2);
+    assert_eq!(page, &[4, 5]);
+    assert_eq!(next, Some(4));
+
+    let (page, next) = page_comments(&seqs, next, 2);
+    assert_eq!(page, &[2, 3]);
+
+    let (page, next) = page_comments(&seqs, next, 2);
+    assert_eq!(page, &[1]);
+    assert_eq!(next, None);
+
+    let (page, next) = page_comments(&seqs, None, 10);
+    assert_eq!(page.len(), 5);
+    assert_eq!(next, None);
+}
@@ -0,0 +1,246 @@
+//! Device Sync Tests
+//!
+//! Tests for:
+//! - Vector clock ordering and merging
+//! - Persistent device ids
+//! - Three-way merges of manifests edited on two devices
+
+use crate::album::AlbumManifest;
+use crate::device_sync::{device_id_in, merge_manifests, ours_wins, VectorClock};
+use crate::github::{AppError, GithubError};
+use crate::tests::common::temp_dir;
+
+fn base_manifest() -> AlbumManifest {
+    let mut manifest = AlbumManifest::new(false, None);
+    manifest.entries.insert("a.jpg".into(), "A".into());
+    manifest.entries.insert("b.jpg".into(), "B".into());
+    manifest.original_bytes = 100;
+    manifest.stored_bytes = 80;
+    manifest.clock.tick("aaaaaaaaaaaaaaaa");
+    manifest
+}
+
+/// An edit of `base` written by `device`
+fn edit(base: &AlbumManifest, device: &str, f: impl FnOnce(&mut AlbumManifest)) -> AlbumManifest {
+    let mut manifest = base.clone();
+    f(&mut manifest);
+    manifest.clock.tick(device);
+    manifest.device = Some(device.to_string());
+    manifest
+}
+
+const LAPTOP: &str = "1111111111111111";
+const DESKTOP: &str = "2222222222222222";
+
+// ============================================================================
+// Vector Clock Tests
+// ============================================================================
+
+#[test]
+fn later_clock_happens_after() {
+    let a = {
+        let mut clock = VectorClock::new();
+        clock.tick(LAPTOP);
+        clock
+    };
+    let mut b = a.clone();
+    b.tick(DESKTOP);
+
+    assert!(a.happens_before(&b));
+    assert!(!b.happens_before(&a));
+    assert!(!a.is_concurrent(&b));
+    assert!(!a.happens_before(&a));
+}
+
+#[test]
+fn independent_ticks_are_concurrent() {
+    let mut a = VectorClock::new();
+    a.tick(LAPTOP);
+    let mut b = VectorClock::new();
+    b.tick(DESKTOP);
+
+    assert!(a.is_concurrent(&b));
+    assert!(!a.is_concurrent(&a));
+}
+
+#[test]
+fn merge_takes_the_larger_counters() {
+    let mut a = VectorClock::new();
+    a.tick(LAPTOP);
+    a.tick(LAPTOP);
+    let mut b = VectorClock::new();
+    b.tick(LAPTOP);
+    b.tick(DESKTOP);
+
+    a.merge(&b);
+    assert_eq!(a.get(LAPTOP), 2);
+    assert_eq!(a.get(DESKTOP), 1);
+    assert_eq!(a.total(), 3);
+    assert!(b.happens_before(&a));
+}
+
+#[test]
+fn clock_serializes_as_a_plain_map() {
+    let mut clock = VectorClock::new();
+    clock.tick(LAPTOP);
+    let json = serde_json::to_string(&clock).unwrap();
+    assert_eq!(json, format!("{{\"{}\":1}}", LAPTOP));
+}
+
+#[test]
+fn manifests_without_clock_still_load() {
+    let json = r#"{"version":1,"encrypted":false,"created_at":0}"#;
+    let manifest: AlbumManifest = serde_json::from_str(json).unwrap();
+    assert!(manifest.clock.is_empty());
+    assert!(manifest.device.is_none());
+
+    let out = serde_json::to_string(&manifest).unwrap();
+    assert!(!out.contains("clock"));
+}
+
+// ============================================================================
+// Device Id Tests
+// ============================================================================
+
+#[test]
+fn device_id_is_created_once() {
+    let dir = temp_dir("device", "create");
+    let id = device_id_in(&dir).unwrap();
+    assert_eq!(id.len(), 16);
+    assert!(id.bytes().all(|b| b.is_ascii_hexdigit()));
+    assert_eq!(device_id_in(&dir).unwrap(), id);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn invalid_device_id_is_replaced() {
+    let dir = temp_dir("device", "invalid");
+    std::fs::write(dir.join("device-id"), "not an id").unwrap();
+
+    let id = device_id_in(&dir).unwrap();
+    assert_ne!(id, "not an id");
+    assert_eq!(std::fs::read_to_string(dir.join("device-id")).unwrap(), id);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================================================
+// Manifest Merge Tests
+// ============================================================================
+
+#[test]
+fn photos_added_on_both_devices_survive() {
+    let base = base_manifest();
+    let ours = edit(&base, LAPTOP, |m| {
+        m.entries.insert("c.jpg".into(), "C".into());
+        m.original_bytes += 10;
+        m.stored_bytes += 8;
+    });
+    let theirs = edit(&base, DESKTOP, |m| {
+        m.entries.insert("d.jpg".into(), "D".into());
+        m.original_bytes += 20;
+        m.stored_bytes += 15;
+    });
+
+    let merged = merge_manifests(&base, &ours, &theirs).unwrap();
+    let names: Vec<&str> = merged.entries.keys().map(String::as_str).collect();
+    assert_eq!(names, vec!["a.jpg", "b.jpg", "c.jpg", "d.jpg"]);
+    assert_eq!(merged.original_bytes, 130);
+    assert_eq!(merged.stored_bytes, 103);
+}
+
+#[test]
+fn removal_on_one_device_sticks() {
+    let base = base_manifest();
+    let ours = edit(&base, LAPTOP, |m| {
+        m.entries.remove("a.jpg");
+    });
+    let theirs = edit(&base, DESKTOP, |m| {
+        m.entries.insert("c.jpg".into(), "C".into());
+    });
+
+    let merged = merge_manifests(&base, &ours, &theirs).unwrap();
+    assert!(!merged.entries.contains_key("a.jpg"));
+    assert!(merged.entries.contains_key("c.jpg"));
+
+    // The same from the other side
+    let merged = merge_manifests(&base, &theirs, &ours).unwrap();
+    assert!(!merged.entries.contains_key("a.jpg"));
+    assert!(merged.entries.contains_key("c.jpg"));
+}
+
+#[test]
+fn fields_changed_on_one_side_are_kept() {
+    let base = base_manifest();
+    let ours = edit(&base, LAPTOP, |m| {
+        m.description = Some("Summer".into());
+    });
+    let theirs = edit(&base, DESKTOP, |m| {
+        m.cover = Some("b.jpg".into());
+        m.metadata.insert("place".into(), "Lisbon".into());
+    });
+
+    let merged = merge_manifests(&base, &ours, &theirs).unwrap();
+    assert_eq!(merged.description.as_deref(), Some("Summer"));
+    assert_eq!(merged.cover.as_deref(), Some("b.jpg"));
+    assert_eq!(merged.metadata.get("place").map(String::as_str), Some("Lisbon"));
+}
+
+#[test]
+fn concurrent_conflicts_resolve_the_same_on_both_devices() {
+    let base = base_manifest();
+    let ours = edit(&base, LAPTOP, |m| {
+        m.description = Some("From laptop".into());
+    });
+    let theirs = edit(&base, DESKTOP, |m| {
+        m.description = Some("From desktop".into());
+    });
+    assert!(ours.clock.is_concurrent(&theirs.clock));
+
+    let on_laptop = merge_manifests(&base, &ours, &theirs).unwrap();
+    let on_desktop = merge_manifests(&base, &theirs, &ours).unwrap();
+    assert_eq!(on_laptop.description, on_desktop.description);
+    assert_eq!(on_laptop.description.as_deref(), Some("From desktop"));
+    assert_ne!(ours_wins(&ours, &theirs), ours_wins(&theirs, &ours));
+}
+
+#[test]
+fn later_writer_wins_by_clock() {
+    let base = base_manifest();
+    let theirs = edit(&base, DESKTOP, |m| {
+        m.description = Some("Old".into());
+    });
+    // Written after seeing `theirs`
+    let ours = edit(&theirs, LAPTOP, |m| {
+        m.description = Some("New".into());
+    });
+
+    assert!(ours_wins(&ours, &theirs));
+    let merged = merge_manifests(&base, &ours, &theirs).unwrap();
+    assert_eq!(merged.description.as_deref(), Some("New"));
+}
+
+#[test]
+fn merged_clock_covers_both_writers() {
+    let base = base_manifest();
+    let ours = edit(&base, LAPTOP, |_| {});
+    let theirs = edit(&base, DESKTOP, |_| {});
+
+    let merged = merge_manifests(&base, &ours, &theirs).unwrap();
+    assert!(ours.clock.happens_before(&merged.clock));
+    assert!(theirs.clock.happens_before(&merged.clock));
+    assert!(merged.signature.is_none());
+}
+
+#[test]
+fn key_rotation_on_one_side_is_not_merged() {
+    let base = base_manifest();
+    let ours = edit(&base, LAPTOP, |m| {
+        m.entries.insert("c.jpg".into(), "C".into());
+    });
+    let theirs = edit(&base, DESKTOP, |m| {
+        m.key_epoch = 1;
+    });
+
+    let err = merge_manifests(&base, &ours, &theirs).unwrap_err();
+    assert!(matches!(err, AppError::Github(GithubError::Conflict { .. })));
+}
@@ -0,0 +1,106 @@
+//! Encrypted Album Tests
+//!
+//! Tests for:
+//! - Photo payload seal/open roundtrip
+//! - Hashed blob names
+//! - Sealed filenames and manifest serialization
+
+use crate::album::{
+    encrypted_blob_name, open_album_photo, open_filename, parent_album_path, seal_album_photo,
+    seal_filename, AlbumManifest, ENCRYPTED_BLOB_EXT,
+};
+
+const ALBUM_ID: &str = "alice/photos:photos/japan-2024";
+
+// ============================================================================
+// Payload Tests
+// ============================================================================
+
+#[test]
+fn album_photo_roundtrip() {
+    let key = [7u8; 32];
+    let content: Vec<u8> = (0..4096).map(|i| (i % 251) as u8).collect();
+
+    let payload = seal_album_photo(&key, ALBUM_ID, "IMG_0001.jpg", &content).expect("seal");
+    let (data, name) = open_album_photo(&key, ALBUM_ID, &payload).expect("open");
+
+    assert_eq!(data, content);
+    assert_eq!(name.as_deref(), Some("IMG_0001.jpg"));
+}
+
+#[test]
+fn album_photo_hides_filename() {
+    let key = [7u8; 32];
+    let payload = seal_album_photo(&key, ALBUM_ID, "secret-beach.jpg", b"pixels").expect("seal");
+
+    let text = String::from_utf8_lossy(&payload);
+    assert!(!text.contains("secret-beach"), "original filename must not appear in payload");
+}
+
+#[test]
+fn album_photo_rejects_wrong_key() {
+    let payload = seal_album_photo(&[7u8; 32], ALBUM_ID, "a.jpg", b"pixels").expect("seal");
+
+    assert!(open_album_photo(&[8u8; 32], ALBUM_ID, &payload).is_err());
+}
+
+#[test]
+fn album_photo_bound_to_album() {
+    let key = [7u8; 32];
+    let payload = seal_album_photo(&key, ALBUM_ID, "a.jpg", b"pixels").expect("seal");
+
+    assert!(
+        open_album_photo(&key, "alice/photos:photos/other", &payload).is_err(),
+        "payload moved to another album should not decrypt"
+    );
+}
+
+// ============================================================================
+// Naming Tests
+// ============================================================================
+
+#[test]
+fn blob_name_is_deterministic_and_keyed() {
+    let content = b"same photo";
+
+    let a1 = encrypted_blob_name(&[1u8; 32], content);
+    let a2 = encrypted_blob_name(&[1u8; 32], content);
+    let b = encrypted_blob_name(&[2u8; 32], content);
+
+    assert_eq!(a1, a2);
+    assert_ne!(a1, b, "blob names should not link across albums");
+    assert!(a1.ends_with(&format!(".{}", ENCRYPTED_BLOB_EXT)));
+    assert!(a1.trim_end_matches(&format!(".{}", ENCRYPTED_BLOB_EXT))
+        .chars()
+        .all(|c| c.is_ascii_hexdigit()));
+}
+
+#[test]
+fn sealed_filename_roundtrip() {
+    let key = [3u8; 32];
+    let sealed = seal_filename(&key, ALBUM_ID, "IMG_0042.HEIC").expect("seal");
+
+    assert_eq!(open_filename(&key, ALBUM_ID, &sealed).expect("open"), "IMG_0042.HEIC");
+    assert!(open_filename(&[4u8; 32], ALBUM_ID, &sealed).is_err());
+}
+
+#[test]
+fn parent_album_path_strips_file() {
+    assert_eq!(parent_album_path("photos/japan/abc.vxe"), "photos/japan");
+    assert_eq!(parent_album_path("/photos/japan/abc.vxe"), "photos/japan");
+    assert_eq!(parent_album_path("abc.vxe"), "");
+}
+
+// ============================================================================
+// Manifest Tests
+// ============================================================================
+
+#[test]
+fn manifest_defaults_for_older_files() {
+    let json = r#"{"version":1,"encrypted":false,"created_at":1700000000}"#;
+    let manifest: AlbumManifest = serde_json::from_str(json).expect("parse");
+
+    assert!(!manifest.encrypted);
+    assert!(manifest.owner_key_id.is_none());
+    assert!(manifest.entries.is_empty());
+}
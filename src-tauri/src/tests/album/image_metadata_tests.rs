@@ -0,0 +1,129 @@
+//! Image Metadata Tests
+//!
+//! Tests for:
+//! - Reading XMP packets in attribute and element form
+//! - Filling EXIF gaps from XMP
+//! - Capture times across formats and UTC offsets
+//! - Describing a local image with an XMP sidecar
+
+use crate::image_metadata::{capture_time, describe_image, merge_xmp, parse_xmp, xmp_packet, XmpExtract};
+use crate::metadata_vault::ExifExtract;
+use crate::tests::common::jpeg;
+
+const LIGHTROOM_XMP: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmp:Rating="4"
+    xmp:CreateDate="2023-07-14T12:00:00+02:00"
+    tiff:Make="NIKON CORPORATION"
+    tiff:Orientation="6"
+    exif:GPSLatitude="48,51.5N"
+    exif:GPSLongitude="2,21,0W">
+   <tiff:Model>NIKON Z 6</tiff:Model>
+   <aux:Lens>NIKKOR Z 24-70mm f/4 S</aux:Lens>
+   <dc:title>
+    <rdf:Alt><rdf:li xml:lang="x-default">Quai &amp; bridges</rdf:li></rdf:Alt>
+   </dc:title>
+   <dc:subject>
+    <rdf:Bag><rdf:li>paris</rdf:li><rdf:li>river</rdf:li></rdf:Bag>
+   </dc:subject>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+// ============================================================================
+// XMP Tests
+// ============================================================================
+
+#[test]
+fn xmp_fields_are_read() {
+    let packet = xmp_packet(format!("junk before{}junk after", LIGHTROOM_XMP).as_bytes()).unwrap();
+    assert!(packet.starts_with("<x:xmpmeta") && packet.ends_with("</x:xmpmeta>"));
+
+    let xmp = parse_xmp(&packet).unwrap();
+    assert_eq!(xmp.taken_at.as_deref(), Some("2023:07:14 12:00:00"));
+    assert_eq!(xmp.time_offset.as_deref(), Some("+02:00"));
+    assert_eq!(xmp.camera_make.as_deref(), Some("NIKON CORPORATION"));
+    assert_eq!(xmp.camera_model.as_deref(), Some("NIKON Z 6"));
+    assert_eq!(xmp.lens_model.as_deref(), Some("NIKKOR Z 24-70mm f/4 S"));
+    assert_eq!(xmp.orientation, Some(6));
+    assert_eq!(xmp.rating, Some(4));
+    assert_eq!(xmp.title.as_deref(), Some("Quai & bridges"));
+    assert_eq!(xmp.keywords, ["paris", "river"]);
+    assert!((xmp.latitude.unwrap() - (48.0 + 51.5 / 60.0)).abs() < 1e-9);
+    assert!((xmp.longitude.unwrap() + (2.0 + 21.0 / 60.0)).abs() < 1e-9);
+}
+
+#[test]
+fn packets_without_known_fields_are_ignored() {
+    assert!(xmp_packet(b"no packet here").is_none());
+    assert_eq!(parse_xmp("<x:xmpmeta><rdf:RDF/></x:xmpmeta>"), None);
+    assert_eq!(parse_xmp(r#"<x:xmpmeta xmp:CreateDate="0000:00:00 00:00:00"/>"#), None);
+}
+
+// ============================================================================
+// Merge Tests
+// ============================================================================
+
+#[test]
+fn exif_wins_and_xmp_fills_gaps() {
+    let exif = ExifExtract {
+        camera_make: Some("Nikon".into()),
+        taken_at: Some("2023:07:14 11:59:58".into()),
+        ..Default::default()
+    };
+    let xmp = parse_xmp(LIGHTROOM_XMP).unwrap();
+
+    let merged = merge_xmp(Some(exif), Some(&xmp)).unwrap();
+    assert_eq!(merged.camera_make.as_deref(), Some("Nikon"));
+    assert_eq!(merged.taken_at.as_deref(), Some("2023:07:14 11:59:58"));
+    // The offset belongs to the XMP time, so it is not applied to EXIF's
+    assert_eq!(merged.time_offset, None);
+    assert_eq!(merged.camera_model.as_deref(), Some("NIKON Z 6"));
+    assert!(merged.latitude.is_some() && merged.longitude.is_some());
+
+    let only_xmp = merge_xmp(None, Some(&xmp)).unwrap();
+    assert_eq!(only_xmp.time_offset.as_deref(), Some("+02:00"));
+    assert_eq!(merge_xmp(None, Some(&XmpExtract::default())), None);
+}
+
+// ============================================================================
+// Capture Time Tests
+// ============================================================================
+
+#[test]
+fn capture_times_use_the_recorded_offset() {
+    let at = |taken_at: &str, offset: Option<&str>| {
+        capture_time(&ExifExtract {
+            taken_at: Some(taken_at.into()),
+            time_offset: offset.map(str::to_string),
+            ..Default::default()
+        })
+    };
+    assert_eq!(at("2024:05:01 18:30:00", Some("+02:00")), Some(1_714_581_000));
+    assert_eq!(at("2024:05:01 16:30:00", None), Some(1_714_581_000));
+    assert_eq!(at("2024-05-01T16:30:00.250Z", Some("+09:00")), Some(1_714_581_000));
+    assert_eq!(at("1970:01:01 00:00:00", Some("+01:00")), Some(-3600));
+    assert_eq!(at("0000:00:00 00:00:00", None), None);
+    assert_eq!(at("yesterday", None), None);
+}
+
+// ============================================================================
+// Describe Tests
+// ============================================================================
+
+#[test]
+fn sidecar_fills_images_without_a_packet() {
+    let photo = jpeg(64, 48);
+    let plain = describe_image("IMG_1.jpg", &photo, None);
+    assert_eq!(plain.format.as_deref(), Some("jpeg"));
+    assert_eq!((plain.width, plain.height), (Some(64), Some(48)));
+    assert!(plain.exif.is_none() && plain.xmp.is_none() && plain.taken_at_unix.is_none());
+
+    let described = describe_image("IMG_1.jpg", &photo, Some(LIGHTROOM_XMP.as_bytes()));
+    assert_eq!(described.xmp.as_ref().and_then(|x| x.rating), Some(4));
+    assert_eq!(described.taken_at_unix, Some(1_689_328_800));
+    assert_eq!(described.exif.unwrap().orientation, Some(6));
+}
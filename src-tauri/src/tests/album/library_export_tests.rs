@@ -0,0 +1,120 @@
+//! Library Export Tests
+//!
+//! Tests for:
+//! - Finding the albums and files to export from a repository tree
+//! - Destination paths and name clashes
+//! - The `index.csv` written next to the albums
+
+use std::collections::HashSet;
+
+use crate::git_data::{index_blobs, TreeEntry};
+use crate::library_export::{export_albums, index_csv, relative_album, unique_name, IndexRow};
+use crate::metadata_vault::ExifExtract;
+use crate::tagging::PhotoOrganization;
+
+fn entry(path: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.into(),
+        mode: "100644".into(),
+        kind: "blob".into(),
+        sha: format!("sha-{}", path),
+        size: Some(10),
+    }
+}
+
+// ============================================================================
+// Album Tests
+// ============================================================================
+
+#[test]
+fn albums_hold_their_media_but_not_bookkeeping_files() {
+    let index = index_blobs(
+        [
+            "README.md",
+            "photos/loose.jpg",
+            "photos/Trips/Rome/a.jpg",
+            "photos/Trips/Rome/a.jpg.vxsig",
+            "photos/Trips/Rome/b.jpg.vxp",
+            "photos/Vault/.vortex-album.json",
+            "photos/Vault/.vortex-vault.bin",
+            "photos/Vault/3f2a.vxe",
+            "photos/.chunks/abc/0",
+        ]
+        .into_iter()
+        .map(entry)
+        .collect(),
+    );
+    let albums = export_albums(&index);
+    let summary: Vec<(&str, Vec<&str>, bool, bool)> = albums
+        .iter()
+        .map(|a| {
+            let files = a.files.iter().map(|f| f.path.as_str()).collect();
+            (a.path.as_str(), files, a.manifest.is_some(), a.vault.is_some())
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("photos", vec!["photos/loose.jpg"], false, false),
+            ("photos/Trips/Rome", vec!["photos/Trips/Rome/a.jpg", "photos/Trips/Rome/b.jpg.vxp"], false, false),
+            ("photos/Vault", vec!["photos/Vault/3f2a.vxe"], true, true),
+        ]
+    );
+}
+
+#[test]
+fn albums_are_exported_below_the_destination() {
+    assert_eq!(relative_album("photos"), "");
+    assert_eq!(relative_album("photos/Trips/Rome"), "Trips/Rome");
+}
+
+#[test]
+fn clashing_names_are_numbered() {
+    let mut taken = HashSet::new();
+    assert_eq!(unique_name(&mut taken, "IMG_1.jpg"), "IMG_1.jpg");
+    assert_eq!(unique_name(&mut taken, "img_1.JPG"), "img_1 (2).JPG");
+    assert_eq!(unique_name(&mut taken, "IMG_1.jpg"), "IMG_1 (3).jpg");
+    assert_eq!(unique_name(&mut taken, "README"), "README");
+    assert_eq!(unique_name(&mut taken, "README"), "README (2)");
+    // Decrypted names cannot escape the album folder
+    assert!(!unique_name(&mut taken, "../../etc/passwd").contains('/'));
+    assert_eq!(unique_name(&mut taken, ".hidden"), "photo");
+}
+
+// ============================================================================
+// Index Tests
+// ============================================================================
+
+#[test]
+fn index_lists_metadata_with_csv_quoting() {
+    let row = IndexRow {
+        album: "Trips/Rome".into(),
+        file: "Trips/Rome/a.jpg".into(),
+        source_path: "photos/Trips/Rome/3f2a.vxe".into(),
+        bytes: 1234,
+        blake3: "ab".into(),
+        encrypted: false,
+        caption: Some("Dinner, \"al fresco\"".into()),
+        exif: Some(ExifExtract {
+            taken_at: Some("2023:06:15 20:01:02".into()),
+            latitude: Some(41.9),
+            longitude: Some(12.5),
+            ..Default::default()
+        }),
+        organization: PhotoOrganization { tags: vec!["food".into(), "italy".into()], favorite: true, rating: Some(4) },
+    };
+    let csv = index_csv(&[row, IndexRow { file: "b.jpg".into(), ..Default::default() }]);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "album,file,source_path,bytes,blake3,encrypted,taken_at,time_offset,latitude,longitude,altitude,\
+         camera_make,camera_model,caption,tags,favorite,rating"
+    );
+    assert_eq!(
+        lines[1],
+        "Trips/Rome,Trips/Rome/a.jpg,photos/Trips/Rome/3f2a.vxe,1234,ab,false,2023:06:15 20:01:02,,41.9,12.5,,,,\
+         \"Dinner, \"\"al fresco\"\"\",food;italy,true,4"
+    );
+    assert_eq!(lines[2], ",b.jpg,,0,,false,,,,,,,,,,false,");
+    assert_eq!(lines.len(), 3);
+}
@@ -0,0 +1,92 @@
+//! Album Metadata Tests
+//!
+//! Tests for:
+//! - Cover photo validation
+//! - Forgetting a file that left the album
+//! - Description and key-value metadata limits
+//! - Manifest compatibility with older files
+
+use crate::album::{validate_metadata_key, AlbumManifest, ALBUM_MANIFEST_FILE};
+
+// ============================================================================
+// Cover Tests
+// ============================================================================
+
+#[test]
+fn cover_must_be_a_file_in_the_album() {
+    let mut m = AlbumManifest::new(false, None);
+    m.set_cover(Some("sunset.jpg")).unwrap();
+    assert_eq!(m.cover.as_deref(), Some("sunset.jpg"));
+
+    assert!(m.set_cover(Some("../other/a.jpg")).is_err());
+    assert!(m.set_cover(Some(ALBUM_MANIFEST_FILE)).is_err());
+
+    m.set_cover(None).unwrap();
+    assert!(m.cover.is_none());
+}
+
+#[test]
+fn encrypted_cover_must_be_a_known_blob() {
+    let mut m = AlbumManifest::new(true, Some("key".into()));
+    m.entries.insert("abc.vxe".into(), "sealed".into());
+    assert!(m.set_cover(Some("missing.vxe")).is_err());
+    m.set_cover(Some("abc.vxe")).unwrap();
+}
+
+#[test]
+fn forgetting_a_file_drops_its_entries_and_cover() {
+    let mut m = AlbumManifest::new(false, None);
+    m.set_cover(Some("old.jpg")).unwrap();
+    m.hashes.insert("old.jpg".into(), "h".into());
+    m.record_signed("old.jpg", b"old");
+    m.record_signed("new.jpg", b"new");
+    m.metadata.insert("place".into(), "Lisbon".into());
+
+    assert!(m.forget_file("old.jpg"));
+    assert!(m.cover.is_none());
+    assert!(m.hashes.is_empty());
+    assert_eq!(m.signed.keys().collect::<Vec<_>>(), vec!["new.jpg"]);
+    assert_eq!(m.metadata.get("place").map(String::as_str), Some("Lisbon"));
+    assert!(!m.forget_file("old.jpg"));
+}
+
+// ============================================================================
+// Description and Metadata Tests
+// ============================================================================
+
+#[test]
+fn description_is_trimmed_and_bounded() {
+    let mut m = AlbumManifest::new(false, None);
+    m.set_description(Some("  Summer trip  ")).unwrap();
+    assert_eq!(m.description.as_deref(), Some("Summer trip"));
+
+    m.set_description(Some("   ")).unwrap();
+    assert!(m.description.is_none());
+
+    assert!(m.set_description(Some(&"x".repeat(2001))).is_err());
+}
+
+#[test]
+fn metadata_set_and_remove() {
+    let mut m = AlbumManifest::new(false, None);
+    m.set_metadata("location", Some("Kyoto")).unwrap();
+    m.set_metadata("camera.model", Some("X100V")).unwrap();
+    assert_eq!(m.metadata.get("location").map(String::as_str), Some("Kyoto"));
+
+    m.set_metadata("location", None).unwrap();
+    assert!(!m.metadata.contains_key("location"));
+
+    assert!(validate_metadata_key("").is_err());
+    assert!(validate_metadata_key("has space").is_err());
+    assert!(m.set_metadata("k", Some(&"v".repeat(1025))).is_err());
+}
+
+#[test]
+fn old_manifests_load_without_metadata() {
+    let json = r#"{"version":1,"encrypted":false,"created_at":0}"#;
+    let m: AlbumManifest = serde_json::from_str(json).unwrap();
+    assert!(m.cover.is_none() && m.description.is_none() && m.metadata.is_empty());
+
+    let out = serde_json::to_string(&m).unwrap();
+    assert!(!out.contains("metadata") && !out.contains("cover"));
+}
@@ -0,0 +1,26 @@
+//! Album Module Tests
+//!
+//! Organized by functionality:
+//! - `encrypted_album_tests` - Encrypted album payloads, blob naming and manifests
+//! - `image_metadata_tests` - EXIF and XMP extraction and capture times
+//! - `metadata_tests` - Album covers, descriptions and custom metadata
+//! - `subalbum_tests` - Nested album paths
+//! - `access_tests` - Sharing with contacts and key rotation on revocation
+//! - `vault_tests` - Encrypted filenames, captions and EXIF per album
+//! - `repo_import_tests` - Turning photo folders of existing repositories into albums
+//! - `takeout_tests` - Google Takeout exports, sidecars and metadata mapping
+//! - `library_export_tests` - Exporting albums to plain folders with an index
+//! - `comments_tests` - Signed, encrypted comments on album photos
+//! - `device_sync_tests` - Vector clocks and merging manifests edited on two devices
+
+pub mod access_tests;
+pub mod encrypted_album_tests;
+pub mod image_metadata_tests;
+pub mod library_export_tests;
+pub mod metadata_tests;
+pub mod repo_import_tests;
+pub mod subalbum_tests;
+pub mod takeout_tests;
+pub mod vault_tests;
+pub mod comments_tests;
+pub mod device_sync_tests;
@@ -0,0 +1,126 @@
+//! Repository Import Tests
+//!
+//! Tests for:
+//! - Finding photo folders and leaving existing albums alone
+//! - Normalizing folders below `photos/` with blob-preserving moves
+//! - Name clashes and what the catalog lists afterwards
+
+use crate::git_data::{index_blobs, TreeChange, TreeEntry, TreeIndex};
+use crate::repo_import::{listed_photos, plan_import, ImportPlan};
+use crate::video::MediaType;
+
+fn entry(path: &str, sha: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.into(),
+        mode: "100644".into(),
+        kind: "blob".into(),
+        sha: sha.into(),
+        size: Some(100),
+    }
+}
+
+fn tree(paths: &[(&str, &str)]) -> TreeIndex {
+    index_blobs(paths.iter().map(|(path, sha)| entry(path, sha)).collect())
+}
+
+fn albums(plan: &ImportPlan) -> Vec<(&str, &str, usize)> {
+    plan.albums.iter().map(|a| (a.path.as_str(), a.source.as_str(), a.photos.len())).collect()
+}
+
+fn camera_roll() -> TreeIndex {
+    tree(&[
+        ("README.md", "r"),
+        ("cover.jpg", "c"),
+        ("2019/Summer/beach.jpg", "b"),
+        ("2019/Summer/clip.mp4", "v"),
+        ("2019/Summer/notes.txt", "n"),
+        ("2019/Winter/IMG_1.CR2", "w"),
+        (".github/logo.png", "l"),
+        ("photos/Family/.vortex-album.json", "m"),
+        ("photos/Family/kids.jpg", "k"),
+        ("photos/loose.jpg", "x"),
+    ])
+}
+
+// ============================================================================
+// Scanning Tests
+// ============================================================================
+
+#[test]
+fn photo_folders_become_albums_in_place() {
+    let plan = plan_import(&camera_roll(), "camera-roll", false);
+    assert_eq!(albums(&plan), [("2019/Summer", "2019/Summer", 2), ("2019/Winter", "2019/Winter", 1)]);
+    assert!(plan.moves.is_empty());
+    // Loose photos at the root need an album to go to
+    assert_eq!(plan.skipped.len(), 1);
+    assert_eq!(plan.skipped[0].path, "cover.jpg");
+}
+
+#[test]
+fn encrypted_blobs_and_hidden_folders_are_left_out() {
+    let index = tree(&[("secret/3f2a.vxe", "e"), (".cache/thumb.jpg", "t"), ("trip/.hidden.jpg", "h")]);
+    let plan = plan_import(&index, "repo", true);
+    assert!(plan.albums.is_empty());
+    assert!(plan.moves.is_empty());
+    assert!(plan.skipped.is_empty());
+}
+
+// ============================================================================
+// Normalizing Tests
+// ============================================================================
+
+#[test]
+fn normalizing_moves_folders_below_photos() {
+    let plan = plan_import(&camera_roll(), "camera-roll", true);
+    assert_eq!(
+        albums(&plan),
+        [
+            ("photos/2019/Summer", "2019/Summer", 2),
+            ("photos/2019/Winter", "2019/Winter", 1),
+            ("photos/camera-roll", "", 1),
+        ]
+    );
+    assert!(plan.skipped.is_empty());
+    assert!(plan.moves.contains(&TreeChange::blob("photos/2019/Summer/beach.jpg", "b")));
+    assert!(plan.moves.contains(&TreeChange::delete("2019/Summer/beach.jpg")));
+    assert!(plan.moves.contains(&TreeChange::blob("photos/camera-roll/cover.jpg", "c")));
+    assert!(!plan.moves.iter().any(|c| c.path.contains("notes.txt") || c.path.contains("Family")));
+}
+
+#[test]
+fn signatures_follow_and_clashes_are_skipped() {
+    let index = tree(&[
+        ("trip/a.jpg", "a"),
+        ("trip/a.jpg.vxsig", "sig"),
+        ("trip/b.jpg", "b"),
+        ("trip/same.jpg", "s"),
+        ("photos/trip/b.jpg", "other"),
+        ("photos/trip/same.jpg", "s"),
+    ]);
+    let plan = plan_import(&index, "repo", true);
+    assert_eq!(albums(&plan), [("photos/trip", "trip", 2)]);
+    assert_eq!(plan.skipped.len(), 1);
+    assert_eq!(plan.skipped[0].path, "trip/b.jpg");
+    assert!(plan.moves.contains(&TreeChange::blob("photos/trip/a.jpg.vxsig", "sig")));
+    // An identical copy at the destination is kept and the source dropped
+    assert!(plan.moves.contains(&TreeChange::delete("trip/same.jpg")));
+    assert!(!plan.moves.contains(&TreeChange::blob("photos/trip/same.jpg", "s")));
+}
+
+#[test]
+fn existing_albums_are_not_merged_into() {
+    let index = tree(&[("Family/new.jpg", "n"), ("photos/Family/.vortex-album.json", "m")]);
+    let plan = plan_import(&index, "repo", true);
+    assert!(plan.albums.is_empty());
+    assert!(plan.skipped[0].reason.contains("already an album"));
+}
+
+#[test]
+fn catalog_listing_points_at_the_import_commit() {
+    let plan = plan_import(&camera_roll(), "camera-roll", true);
+    let items = listed_photos("alice/camera-roll", "abc123", &plan.albums[0]);
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].name, "beach.jpg");
+    assert_eq!(items[0].url, "https://raw.githubusercontent.com/alice/camera-roll/abc123/photos/2019/Summer/beach.jpg");
+    assert_eq!(items[1].media_type, MediaType::Video);
+}
@@ -0,0 +1,37 @@
+//! Nested Album Tests
+//!
+//! Tests for:
+//! - Album path construction below `photos/`
+//! - Sanitization and depth limits for sub-albums
+
+use crate::album::{album_path_for, parent_album_path, ALBUM_ROOT};
+
+#[test]
+fn top_level_and_nested_paths() {
+    assert_eq!(album_path_for(ALBUM_ROOT, "Trips").unwrap(), "photos/Trips");
+    assert_eq!(album_path_for(ALBUM_ROOT, "Trips/2024/Japan").unwrap(), "photos/Trips/2024/Japan");
+    assert_eq!(album_path_for("photos/Trips/", "2024").unwrap(), "photos/Trips/2024");
+
+    let japan = album_path_for("photos/Trips/2024", "Japan").unwrap();
+    assert_eq!(parent_album_path(&japan), "photos/Trips/2024");
+}
+
+#[test]
+fn names_are_sanitized() {
+    assert_eq!(album_path_for(ALBUM_ROOT, "//Trips//my trip!").unwrap(), "photos/Trips/mytrip");
+    assert_eq!(album_path_for(ALBUM_ROOT, "../secret").unwrap(), "photos/secret");
+    assert!(album_path_for(ALBUM_ROOT, "///").is_err());
+}
+
+#[test]
+fn parents_must_be_inside_the_album_root() {
+    assert!(album_path_for("docs", "x").is_err());
+    assert!(album_path_for("photosx", "x").is_err());
+    assert!(album_path_for("photos/../docs", "x").is_err());
+}
+
+#[test]
+fn nesting_depth_is_limited() {
+    assert!(album_path_for(ALBUM_ROOT, "a/b/c/d/e/f/g/h").is_ok());
+    assert!(album_path_for(ALBUM_ROOT, "a/b/c/d/e/f/g/h/i").is_err());
+}
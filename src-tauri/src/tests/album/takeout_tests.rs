@@ -0,0 +1,172 @@
+//! Google Takeout Tests
+//!
+//! Tests for:
+//! - Reading exports from archives and extracted folders
+//! - Grouping folders into albums and matching photos to their sidecars
+//! - Mapping Google's captions, capture times and places
+
+use std::io::Write;
+
+use crate::metadata_vault::{ExifExtract, PhotoMetadata};
+use crate::takeout::{
+    album_name, exif_time, scan_export, sidecar_for, year_of_folder, GoogleAlbum, GoogleSidecar, TakeoutExport,
+};
+use crate::tests::common::temp_dir;
+
+fn sidecar(json: &str) -> GoogleSidecar {
+    serde_json::from_str(json).unwrap()
+}
+
+fn metadata(exif: Option<ExifExtract>) -> PhotoMetadata {
+    PhotoMetadata { filename: "a.jpg".into(), caption: None, exif, added_at: 1 }
+}
+
+// ============================================================================
+// Export Tests
+// ============================================================================
+
+#[test]
+fn archives_and_folders_are_read_as_one_export() {
+    let dir = temp_dir("takeout", "export");
+    let zip_path = dir.join("takeout-001.zip");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    writer.add_directory("Takeout/Google Photos/Trip/", options).unwrap();
+    writer.start_file("Takeout/Google Photos/Trip/a.jpg", options).unwrap();
+    writer.write_all(b"from the archive").unwrap();
+    writer.finish().unwrap();
+
+    let extracted = dir.join("takeout-002");
+    std::fs::create_dir_all(extracted.join("Takeout/Google Photos/Trip")).unwrap();
+    std::fs::write(extracted.join("Takeout/Google Photos/Trip/a.jpg"), b"from the folder").unwrap();
+    std::fs::write(extracted.join("Takeout/Google Photos/Trip/a.jpg.json"), b"{}").unwrap();
+
+    let paths = [zip_path, extracted].map(|p| p.to_string_lossy().into_owned());
+    let mut export = TakeoutExport::open(&paths).unwrap();
+    let files: Vec<&str> = export.files().collect();
+    assert_eq!(files, ["Takeout/Google Photos/Trip/a.jpg", "Takeout/Google Photos/Trip/a.jpg.json"]);
+    // The first part wins
+    assert_eq!(export.read("Takeout/Google Photos/Trip/a.jpg").unwrap(), b"from the archive");
+    assert_eq!(export.read("Takeout/Google Photos/Trip/a.jpg.json").unwrap(), b"{}");
+    assert!(export.read("Takeout/missing.jpg").is_err());
+}
+
+#[test]
+fn files_that_are_not_archives_are_refused() {
+    let dir = temp_dir("takeout", "invalid");
+    let path = dir.join("notes.txt");
+    std::fs::write(&path, b"not a zip").unwrap();
+    assert!(TakeoutExport::open(&[path.to_string_lossy().into_owned()]).is_err());
+}
+
+// ============================================================================
+// Scanning Tests
+// ============================================================================
+
+#[test]
+fn named_albums_come_before_year_folders() {
+    let files = [
+        "Takeout/Google Photos/Photos from 2019/IMG_1.jpg",
+        "Takeout/Google Photos/Photos from 2019/IMG_1.jpg.json",
+        "Takeout/Google Photos/Summer/IMG_1.jpg",
+        "Takeout/Google Photos/Summer/metadata.json",
+        "Takeout/Google Photos/Summer/clip.mp4",
+        "Takeout/Google Photos/Trash/deleted.jpg",
+        "Takeout/Google Photos/print-subscriptions.json",
+        "Takeout/archive_browser.html",
+    ];
+    let folders = scan_export(files);
+    let names: Vec<(&str, Option<&str>)> = folders.iter().map(|f| (f.name.as_str(), f.year.as_deref())).collect();
+    assert_eq!(names, [("Summer", None), ("Photos from 2019", Some("2019"))]);
+    assert_eq!(folders[0].metadata.as_deref(), Some("Takeout/Google Photos/Summer/metadata.json"));
+    assert_eq!(folders[0].photos.len(), 2);
+    assert_eq!(
+        folders[1].photos[0].sidecar.as_deref(),
+        Some("Takeout/Google Photos/Photos from 2019/IMG_1.jpg.json")
+    );
+}
+
+#[test]
+fn sidecars_are_matched_through_truncation_and_counters() {
+    let long = "PXL_20230615_123456789.PORTRAIT-01.COVER~2.ORIGINAL.jpg";
+    let candidates = [
+        "IMG_1.jpg.json",
+        "IMG_2.jpg.supplemental-metadata.json",
+        "IMG_3.jpg.supplemental-met.json",
+        "IMG_4.jpg(1).json",
+        "PXL_20230615_123456789.PORTRAIT-01.COVER~2.ORI.json",
+        "OLD.json",
+        "metadata.json",
+    ];
+    assert_eq!(sidecar_for("IMG_1.jpg", &candidates), Some("IMG_1.jpg.json"));
+    assert_eq!(sidecar_for("IMG_1-edited.jpg", &candidates), Some("IMG_1.jpg.json"));
+    assert_eq!(sidecar_for("IMG_2.jpg", &candidates), Some("IMG_2.jpg.supplemental-metadata.json"));
+    assert_eq!(sidecar_for("IMG_3.jpg", &candidates), Some("IMG_3.jpg.supplemental-met.json"));
+    assert_eq!(sidecar_for("IMG_4(1).jpg", &candidates), Some("IMG_4.jpg(1).json"));
+    assert_eq!(sidecar_for("IMG_4.jpg", &candidates), None);
+    assert_eq!(sidecar_for(long, &candidates), Some("PXL_20230615_123456789.PORTRAIT-01.COVER~2.ORI.json"));
+    assert_eq!(sidecar_for("OLD.JPG", &candidates), Some("OLD.json"));
+    // A prefix of another photo's name is not enough
+    assert_eq!(sidecar_for("IMG_10.jpg", &candidates), None);
+}
+
+#[test]
+fn albums_are_named_after_their_titles() {
+    let folders = scan_export(["T/Google Photos/Trip(1)/a.jpg", "T/Google Photos/Photos from 2020/b.jpg"]);
+    let titled = GoogleAlbum { title: Some("Trip to Rome / Naples".into()), description: None };
+    assert_eq!(album_name(&folders[0], Some(&titled)), "Trip to Rome - Naples");
+    assert_eq!(album_name(&folders[0], None), "Trip(1)");
+    assert_eq!(album_name(&folders[1], Some(&titled)), "Google Photos/2020");
+    assert_eq!(year_of_folder("Photos from 20xx"), None);
+}
+
+// ============================================================================
+// Metadata Tests
+// ============================================================================
+
+#[test]
+fn timestamps_become_exif_times() {
+    assert_eq!(exif_time(0), "1970:01:01 00:00:00");
+    assert_eq!(exif_time(1_686_832_496), "2023:06:15 12:34:56");
+    assert_eq!(exif_time(951_782_400), "2000:02:29 00:00:00");
+}
+
+#[test]
+fn sidecars_fill_gaps_in_the_exif() {
+    let google = sidecar(
+        r#"{
+            "title": "IMG_1.jpg",
+            "description": "  Dinner in Rome  ",
+            "photoTakenTime": { "timestamp": "1686832496", "formatted": "Jun 15, 2023" },
+            "geoData": { "latitude": 0.0, "longitude": 0.0, "altitude": 0.0 },
+            "geoDataExif": { "latitude": 41.9, "longitude": 12.5, "altitude": 21.0 },
+            "favorited": true
+        }"#,
+    );
+    let mut bare = metadata(None);
+    google.apply(&mut bare);
+    assert_eq!(bare.caption.as_deref(), Some("Dinner in Rome"));
+    let exif = bare.exif.unwrap();
+    assert_eq!(exif.taken_at.as_deref(), Some("2023:06:15 12:34:56"));
+    assert_eq!(exif.time_offset.as_deref(), Some("+00:00"));
+    assert_eq!((exif.latitude, exif.longitude), (Some(41.9), Some(12.5)));
+    assert!(google.favorited);
+
+    // The camera's own local time and place are kept
+    let camera = ExifExtract {
+        taken_at: Some("2023:06:15 14:34:56".into()),
+        latitude: Some(1.0),
+        longitude: Some(2.0),
+        ..Default::default()
+    };
+    let mut shot = metadata(Some(camera.clone()));
+    google.apply(&mut shot);
+    assert_eq!(shot.exif, Some(camera));
+}
+
+#[test]
+fn empty_sidecars_add_nothing() {
+    let mut bare = metadata(None);
+    sidecar(r#"{ "description": "", "geoData": { "latitude": 0.0, "longitude": 0.0 } }"#).apply(&mut bare);
+    assert_eq!(bare, metadata(None));
+}
@@ -0,0 +1,131 @@
+//! Metadata Vault Tests
+//!
+//! Tests for:
+//! - Sealing and opening the vault under the album key
+//! - Caption validation
+//! - Pruning entries of photos that left the album
+//! - EXIF extraction
+
+use exif::{experimental::Writer, Field, In, Rational, Tag, Value};
+
+use crate::album::AlbumManifest;
+use crate::metadata_vault::{extract_exif, vault_path, MetadataVault, PhotoMetadata};
+
+const ALBUM_ID: &str = "alice/photos:photos/Trips";
+
+fn vault_with(blob: &str, filename: &str) -> MetadataVault {
+    let mut vault = MetadataVault::default();
+    vault.set_filename(blob, filename);
+    vault
+}
+
+fn tiff_with(fields: &[Field]) -> Vec<u8> {
+    let mut writer = Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+    let mut out = std::io::Cursor::new(Vec::new());
+    writer.write(&mut out, false).unwrap();
+    out.into_inner()
+}
+
+#[test]
+fn vault_roundtrips_under_album_key() {
+    let key = [3u8; 32];
+    let mut vault = vault_with("abc.vxe", "IMG_0042.jpg");
+    vault.set_caption("abc.vxe", Some("  Sunset at the pier ")).unwrap();
+
+    let sealed = vault.seal(&key, ALBUM_ID).unwrap();
+    // Nothing readable ends up in the repository
+    assert!(!String::from_utf8_lossy(&sealed).contains("IMG_0042"));
+
+    let opened = MetadataVault::open(&key, ALBUM_ID, &sealed).unwrap();
+    assert_eq!(opened, vault);
+    assert_eq!(opened.entries["abc.vxe"].caption.as_deref(), Some("Sunset at the pier"));
+
+    // Bound to both the key and the album
+    assert!(MetadataVault::open(&[4u8; 32], ALBUM_ID, &sealed).is_err());
+    assert!(MetadataVault::open(&key, "alice/photos:photos/Other", &sealed).is_err());
+    assert_eq!(vault_path("/photos/Trips/"), "photos/Trips/.vortex-vault.bin");
+}
+
+#[test]
+fn captions_are_validated() {
+    let mut vault = vault_with("abc.vxe", "a.jpg");
+    assert!(vault.set_caption("missing.vxe", Some("hi")).is_err());
+    assert!(vault.set_caption("abc.vxe", Some(&"x".repeat(2001))).is_err());
+
+    vault.set_caption("abc.vxe", Some("hi")).unwrap();
+    vault.set_caption("abc.vxe", Some("   ")).unwrap();
+    assert_eq!(vault.entries["abc.vxe"].caption, None);
+}
+
+#[test]
+fn set_filename_keeps_caption_and_exif() {
+    let mut vault = MetadataVault::default();
+    vault.entries.insert(
+        "abc.vxe".into(),
+        PhotoMetadata {
+            caption: Some("Beach".into()),
+            ..PhotoMetadata::for_upload("old.jpg", b"not an image")
+        },
+    );
+    vault.set_filename("abc.vxe", "new.jpg");
+    assert_eq!(vault.entries["abc.vxe"].filename, "new.jpg");
+    assert_eq!(vault.entries["abc.vxe"].caption.as_deref(), Some("Beach"));
+}
+
+#[test]
+fn entries_of_removed_photos_are_pruned() {
+    let mut vault = vault_with("keep.vxe", "a.jpg");
+    vault.set_filename("gone.vxe", "b.jpg");
+
+    let mut manifest = AlbumManifest::new(true, None);
+    manifest.entries.insert("keep.vxe".into(), "sealed".into());
+    vault.retain_photos(&manifest);
+    assert_eq!(vault.entries.keys().collect::<Vec<_>>(), ["keep.vxe"]);
+}
+
+#[test]
+fn exif_fields_are_extracted() {
+    assert_eq!(extract_exif(b"not an image"), None);
+
+    let tiff = tiff_with(&[
+        Field {
+            tag: Tag::Make,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![b"Fujifilm".to_vec()]),
+        },
+        Field {
+            tag: Tag::DateTimeOriginal,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![b"2024:05:01 18:30:00".to_vec()]),
+        },
+        Field {
+            tag: Tag::FNumber,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(vec![Rational { num: 28, denom: 10 }]),
+        },
+        Field {
+            tag: Tag::GPSLatitude,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(vec![
+                Rational { num: 33, denom: 1 },
+                Rational { num: 30, denom: 1 },
+                Rational { num: 0, denom: 1 },
+            ]),
+        },
+        Field {
+            tag: Tag::GPSLatitudeRef,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![b"S".to_vec()]),
+        },
+    ]);
+
+    let exif = extract_exif(&tiff).expect("exif");
+    assert_eq!(exif.camera_make.as_deref(), Some("Fujifilm"));
+    assert_eq!(exif.taken_at.as_deref(), Some("2024:05:01 18:30:00"));
+    assert_eq!(exif.f_number, Some(2.8));
+    assert_eq!(exif.latitude, Some(-33.5));
+    assert_eq!(exif.longitude, None);
+}
@@ -0,0 +1,140 @@
+//! Archive Container Tests
+//!
+//! Tests for:
+//! - Plain and encrypted roundtrips
+//! - Per-entry compression choices
+//! - Entry name validation
+//! - Wrong password, tampering and truncation
+
+use std::io::Cursor;
+
+use crate::archive::{validate_entry_name, ArchiveReader, ArchiveWriter};
+use crate::compress::Algorithm;
+
+const PASSWORD: &[u8] = b"correct horse battery staple";
+
+fn jpeg_like() -> Vec<u8> {
+    let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0];
+    data.extend((0..8000).map(|_| rand::random::<u8>()));
+    data
+}
+
+fn build(password: Option<&[u8]>) -> Vec<u8> {
+    let mut archive = ArchiveWriter::new(Vec::new(), password).unwrap();
+    archive.add("notes.txt", &b"album notes ".repeat(500)).unwrap();
+    archive.add("photo.jpg", &jpeg_like()).unwrap();
+    archive.add("empty", &[]).unwrap();
+    archive.finish().unwrap().0
+}
+
+// ============================================================================
+// Roundtrip Tests
+// ============================================================================
+
+#[test]
+fn plain_archive_roundtrip() {
+    let mut archive = ArchiveWriter::new(Vec::new(), None).unwrap();
+    let photo = jpeg_like();
+    archive.add("notes.txt", &b"album notes ".repeat(500)).unwrap();
+    archive.add("photo.jpg", &photo).unwrap();
+    let (bytes, _) = archive.finish().unwrap();
+
+    let mut reader = ArchiveReader::open(Cursor::new(bytes), None).unwrap();
+    let names: Vec<_> = reader.index().entries.iter().map(|e| e.name.clone()).collect();
+    assert_eq!(names, ["notes.txt", "photo.jpg"]);
+    assert_eq!(reader.read_entry(0).unwrap(), b"album notes ".repeat(500));
+    assert_eq!(reader.read_entry(1).unwrap(), photo);
+}
+
+#[test]
+fn encrypted_archive_roundtrip() {
+    let bytes = build(Some(PASSWORD));
+    let mut reader = ArchiveReader::open(Cursor::new(bytes), Some(PASSWORD)).unwrap();
+
+    assert_eq!(reader.index().entries.len(), 3);
+    assert_eq!(reader.read_entry(0).unwrap(), b"album notes ".repeat(500));
+    assert!(reader.read_entry(2).unwrap().is_empty());
+}
+
+#[test]
+fn media_is_stored_and_text_compressed() {
+    let reader = ArchiveReader::open(Cursor::new(build(None)), None).unwrap();
+    let entries = &reader.index().entries;
+
+    assert_ne!(entries[0].algorithm, Algorithm::None);
+    assert!(entries[0].stored_len < entries[0].original_len);
+    assert_eq!(entries[1].algorithm, Algorithm::None);
+}
+
+#[test]
+fn encrypted_archive_hides_names() {
+    let bytes = build(Some(PASSWORD));
+    let haystack = String::from_utf8_lossy(&bytes);
+    assert!(!haystack.contains("notes.txt"));
+    assert!(!haystack.contains("album notes"));
+}
+
+// ============================================================================
+// Name Validation Tests
+// ============================================================================
+
+#[test]
+fn rejects_unsafe_names() {
+    for name in ["", ".", "..", "../escape.jpg", "dir/photo.jpg", "dir\\photo.jpg", "bad\nname"] {
+        assert!(validate_entry_name(name).is_err(), "{:?} should be rejected", name);
+    }
+    assert!(validate_entry_name("IMG_0001.HEIC").is_ok());
+}
+
+#[test]
+fn rejects_duplicate_names() {
+    let mut archive = ArchiveWriter::new(Vec::new(), None).unwrap();
+    archive.add("a.jpg", b"one").unwrap();
+    assert!(archive.add("a.jpg", b"two").is_err());
+}
+
+// ============================================================================
+// Failure Tests
+// ============================================================================
+
+#[test]
+fn wrong_or_missing_password_fails() {
+    let bytes = build(Some(PASSWORD));
+    assert!(ArchiveReader::open(Cursor::new(bytes.clone()), Some(b"wrong password")).is_err());
+    assert!(ArchiveReader::open(Cursor::new(bytes), None).is_err());
+}
+
+#[test]
+fn tampered_entry_is_detected() {
+    let mut bytes = build(None);
+    let offset = {
+        let reader = ArchiveReader::open(Cursor::new(bytes.clone()), None).unwrap();
+        reader.index().entries[1].offset as usize + 100
+    };
+    bytes[offset] ^= 0x01;
+
+    let mut reader = ArchiveReader::open(Cursor::new(bytes), None).unwrap();
+    assert!(reader.read_entry(0).is_ok());
+    assert!(reader.read_entry(1).is_err());
+}
+
+#[test]
+fn tampered_encrypted_entry_is_detected() {
+    let mut bytes = build(Some(PASSWORD));
+    let offset = {
+        let reader = ArchiveReader::open(Cursor::new(bytes.clone()), Some(PASSWORD)).unwrap();
+        reader.index().entries[0].offset as usize + 20
+    };
+    bytes[offset] ^= 0x01;
+
+    let mut reader = ArchiveReader::open(Cursor::new(bytes), Some(PASSWORD)).unwrap();
+    assert!(reader.read_entry(0).is_err());
+}
+
+#[test]
+fn truncated_archive_is_rejected() {
+    let bytes = build(None);
+    assert!(ArchiveReader::open(Cursor::new(bytes[..bytes.len() - 5].to_vec()), None).is_err());
+    assert!(ArchiveReader::open(Cursor::new(b"VXARCHIV".to_vec()), None).is_err());
+    assert!(ArchiveReader::open(Cursor::new(b"PK\x03\x04 zip file".to_vec()), None).is_err());
+}
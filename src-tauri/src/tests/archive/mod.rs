@@ -0,0 +1,6 @@
+//! Archive Module Tests
+//!
+//! Organized by functionality:
+//! - `container_tests` - `.vortex` archive writing, reading and tamper checks
+
+pub mod container_tests;
@@ -0,0 +1,6 @@
+//! Batch Operation Tests
+//!
+//! Organized by functionality:
+//! - `plan_tests` - Delete/move planning against a repository tree
+
+pub mod plan_tests;
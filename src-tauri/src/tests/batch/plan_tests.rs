@@ -0,0 +1,106 @@
+//! Batch Plan Tests
+//!
+//! Tests for:
+//! - Delete planning and per-item failures
+//! - Move planning, conflicts and encrypted blob protection
+
+use crate::batch::{plan_delete, plan_move};
+use crate::git_data::{index_blobs, TreeEntry, TreeIndex};
+
+fn entry(path: &str, kind: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.to_string(),
+        mode: if kind == "tree" { "040000" } else { "100644" }.to_string(),
+        kind: kind.to_string(),
+        sha: format!("sha-{}", path),
+        size: None,
+    }
+}
+
+fn sample_index() -> TreeIndex {
+    index_blobs(vec![
+        entry("photos", "tree"),
+        entry("photos/a.jpg", "blob"),
+        entry("photos/b.jpg", "blob"),
+        entry("photos/trip", "tree"),
+        entry("photos/trip/b.jpg", "blob"),
+        entry("photos/vault/0123abcd.vxe", "blob"),
+    ])
+}
+
+fn paths(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+// ============================================================================
+// Delete Planning Tests
+// ============================================================================
+
+#[test]
+fn delete_plan_stages_existing_files() {
+    let plan = plan_delete(&sample_index(), &paths(&["photos/a.jpg", "/photos/trip/b.jpg"]));
+
+    assert_eq!(plan.changes.len(), 2);
+    assert!(plan.changes.iter().all(|c| c.sha.is_none()));
+    assert!(plan.results.iter().all(|r| r.success));
+}
+
+#[test]
+fn delete_plan_reports_per_item_failures() {
+    let plan = plan_delete(
+        &sample_index(),
+        &paths(&["photos/a.jpg", "photos/missing.jpg", "photos/../a.jpg", "photos/a.jpg", "photos"]),
+    );
+
+    assert_eq!(plan.changes.len(), 1);
+    let errors: Vec<_> = plan.results.iter().map(|r| r.error.as_deref()).collect();
+    assert_eq!(
+        errors,
+        vec![None, Some("File not found"), Some("Invalid path"), Some("Duplicate path"), Some("File not found")]
+    );
+}
+
+// ============================================================================
+// Move Planning Tests
+// ============================================================================
+
+#[test]
+fn move_plan_reuses_blob_and_removes_source() {
+    let plan = plan_move(&sample_index(), &paths(&["photos/a.jpg"]), "photos/trip/");
+
+    assert_eq!(plan.results[0].new_path.as_deref(), Some("photos/trip/a.jpg"));
+    assert_eq!(plan.changes.len(), 2);
+    assert_eq!(plan.changes[0].path, "photos/trip/a.jpg");
+    assert_eq!(plan.changes[0].sha.as_deref(), Some("sha-photos/a.jpg"));
+    assert_eq!(plan.changes[1].path, "photos/a.jpg");
+    assert!(plan.changes[1].sha.is_none());
+}
+
+#[test]
+fn move_plan_rejects_conflicts() {
+    let plan = plan_move(&sample_index(), &paths(&["photos/b.jpg", "photos/a.jpg"]), "photos/trip");
+
+    assert!(!plan.results[0].success, "destination already has b.jpg");
+    assert!(plan.results[1].success);
+    assert_eq!(plan.changes.len(), 2);
+}
+
+#[test]
+fn move_plan_rejects_same_folder_and_encrypted_blobs() {
+    let plan = plan_move(
+        &sample_index(),
+        &paths(&["photos/a.jpg", "photos/vault/0123abcd.vxe"]),
+        "photos",
+    );
+
+    assert!(plan.results.iter().all(|r| !r.success));
+    assert!(plan.changes.is_empty());
+}
+
+#[test]
+fn move_plan_rejects_invalid_destination() {
+    let plan = plan_move(&sample_index(), &paths(&["photos/a.jpg"]), "../elsewhere");
+
+    assert_eq!(plan.results[0].error.as_deref(), Some("Invalid destination"));
+    assert!(plan.changes.is_empty());
+}
@@ -0,0 +1,44 @@
+//! Shared Test Fixtures
+//!
+//! Helpers used by tests of several modules.
+
+use image::{ImageBuffer, ImageFormat, Rgb, RgbImage};
+use std::path::PathBuf;
+
+/// An empty folder below the system temp dir, unique to `group`, `name` and
+/// this test process. Whatever an earlier run left there is removed.
+pub fn temp_dir(group: &str, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("vortex-{}-test-{}-{}", group, name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A file named `name` holding `content`, alone in its own `temp_dir`
+pub fn temp_file(group: &str, name: &str, content: &[u8]) -> PathBuf {
+    let path = temp_dir(group, name).join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+/// A gradient, so encoders and hashes have detail to work with
+pub fn gradient(width: u32, height: u32) -> RgbImage {
+    ImageBuffer::from_fn(width, height, |x, y| Rgb([(x + y) as u8, (x * 2) as u8, (255 - y) as u8]))
+}
+
+/// `image` encoded as `format`
+pub fn encoded(image: &RgbImage, format: ImageFormat) -> Vec<u8> {
+    let mut out = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut out, format).unwrap();
+    out.into_inner()
+}
+
+/// A gradient PNG
+pub fn png(width: u32, height: u32) -> Vec<u8> {
+    encoded(&gradient(width, height), ImageFormat::Png)
+}
+
+/// A gradient JPEG
+pub fn jpeg(width: u32, height: u32) -> Vec<u8> {
+    encoded(&gradient(width, height), ImageFormat::Jpeg)
+}
@@ -1,13 +1,13 @@
 //! Algorithm-Specific Tests
 //!
 //! Tests for individual compression algorithms:
-//! - Zstd, LZ4, Snappy, Brotli, Gzip
+//! - Zstd, LZ4, Snappy, Brotli, Gzip, XZ
 //! - Algorithm parsing and validation
 
 use crate::compress::{
     brotli_compress, brotli_decompress, gzip_compress, gzip_decompress, lz4_compress,
-    lz4_decompress, snap_compress, snap_decompress, zstd_compress, zstd_decompress,
-    Algorithm, CompressError,
+    lz4_decompress, snap_compress, snap_decompress, xz_compress, xz_decompress, zstd_compress,
+    zstd_decompress, Algorithm, CompressError,
 };
 
 // ============================================================================
@@ -225,6 +225,36 @@ fn gzip_empty_data() {
     assert!(decompressed.is_empty());
 }
 
+// ============================================================================
+// XZ Tests
+// ============================================================================
+
+#[test]
+fn xz_roundtrip() {
+    let data = b"xz archival test data ".repeat(200);
+    let compressed = xz_compress(&data, 6).unwrap();
+    let decompressed = xz_decompress(&compressed).unwrap();
+
+    assert!(compressed.len() < data.len());
+    assert_eq!(data.as_slice(), decompressed.as_slice());
+}
+
+#[test]
+fn xz_level_clamping() {
+    let data = vec![42u8; 1000];
+
+    let compressed_low = xz_compress(&data, -5).unwrap();
+    let compressed_high = xz_compress(&data, 100).unwrap();
+
+    assert_eq!(xz_decompress(&compressed_low).unwrap(), data);
+    assert_eq!(xz_decompress(&compressed_high).unwrap(), data);
+}
+
+#[test]
+fn xz_rejects_garbage() {
+    assert!(xz_decompress(b"definitely not an xz stream").is_err());
+}
+
 // ============================================================================
 // Algorithm Parsing Tests
 // ============================================================================
@@ -239,6 +269,8 @@ fn algorithm_from_str_valid() {
     assert_eq!(Algorithm::from("br"), Algorithm::Brotli);
     assert_eq!(Algorithm::from("gzip"), Algorithm::Gzip);
     assert_eq!(Algorithm::from("gz"), Algorithm::Gzip);
+    assert_eq!(Algorithm::from("xz"), Algorithm::Xz);
+    assert_eq!(Algorithm::from("lzma"), Algorithm::Xz);
     assert_eq!(Algorithm::from("none"), Algorithm::None);
 }
 
@@ -0,0 +1,103 @@
+//! Compression Benchmark Tests
+//!
+//! Tests for:
+//! - Measuring a single candidate
+//! - Calibrated recommendation from a report
+//! - Memory estimates
+
+use crate::compress::Algorithm;
+use crate::compress_bench::{
+    benchmark_one, estimated_memory, recommend_from_benchmark, run_benchmark, BenchmarkEntry, BenchmarkReport,
+};
+
+fn entry(algorithm: Algorithm, level: i32, compress_mbps: f64, ratio: f64) -> BenchmarkEntry {
+    BenchmarkEntry {
+        algorithm,
+        level,
+        compress_mbps,
+        decompress_mbps: compress_mbps * 3.0,
+        ratio,
+        estimated_memory_bytes: 0,
+    }
+}
+
+fn report() -> BenchmarkReport {
+    BenchmarkReport {
+        sample_bytes: 1 << 20,
+        cores: 8,
+        measured_at: 0,
+        entries: vec![
+            entry(Algorithm::Lz4, 1, 800.0, 0.55),
+            entry(Algorithm::Zstd, 3, 300.0, 0.40),
+            entry(Algorithm::Zstd, 19, 5.0, 0.30),
+            entry(Algorithm::Xz, 6, 2.0, 0.28),
+        ],
+    }
+}
+
+// ============================================================================
+// Measurement Tests
+// ============================================================================
+
+#[test]
+fn benchmark_one_measures_roundtrip() {
+    let sample = b"benchmark sample text ".repeat(2000);
+    let result = benchmark_one(&sample, Algorithm::Zstd, 3).unwrap();
+
+    assert!(result.compress_mbps > 0.0);
+    assert!(result.decompress_mbps > 0.0);
+    assert!(result.ratio < 0.5, "repetitive text compresses well");
+}
+
+#[test]
+fn benchmark_rejects_tiny_sample() {
+    assert!(run_benchmark(b"too small").is_err());
+}
+
+// ============================================================================
+// Recommendation Tests
+// ============================================================================
+
+#[test]
+fn small_file_gets_best_ratio() {
+    let choice = recommend_from_benchmark(&report(), 1024 * 1024).unwrap();
+    assert_eq!(choice.algorithm, Algorithm::Xz);
+}
+
+#[test]
+fn medium_file_skips_slow_candidates() {
+    // 100 MB: xz (50 s) and zstd 19 (20 s) miss the target, zstd 3 does not
+    let choice = recommend_from_benchmark(&report(), 100 * 1024 * 1024).unwrap();
+    assert_eq!((choice.algorithm, choice.level), (Algorithm::Zstd, 3));
+}
+
+#[test]
+fn huge_file_falls_back_to_fastest() {
+    let choice = recommend_from_benchmark(&report(), 3 * 1024 * 1024 * 1024).unwrap();
+    assert_eq!(choice.algorithm, Algorithm::Lz4);
+}
+
+#[test]
+fn nothing_fast_enough_gives_none() {
+    let choice = recommend_from_benchmark(&report(), 100 * 1024 * 1024 * 1024);
+    assert!(choice.is_none());
+}
+
+#[test]
+fn ties_prefer_faster() {
+    let mut report = report();
+    report.entries.push(entry(Algorithm::Brotli, 9, 50.0, 0.28));
+    let choice = recommend_from_benchmark(&report, 1024 * 1024).unwrap();
+    assert_eq!(choice.algorithm, Algorithm::Brotli);
+}
+
+// ============================================================================
+// Memory Estimate Tests
+// ============================================================================
+
+#[test]
+fn memory_grows_with_level() {
+    assert!(estimated_memory(Algorithm::Xz, 9, 0) > estimated_memory(Algorithm::Xz, 1, 0));
+    assert!(estimated_memory(Algorithm::Zstd, 19, 0) > estimated_memory(Algorithm::Zstd, 1, 0));
+    assert!(estimated_memory(Algorithm::Lz4, 1, 1000) >= 2000);
+}
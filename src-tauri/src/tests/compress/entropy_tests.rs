@@ -0,0 +1,110 @@
+//! Content Classification Tests
+//!
+//! Tests for:
+//! - Magic-byte detection of compressed media and archives
+//! - Entropy of uniform and random data
+//! - Store-only decisions in `compress_auto`
+
+use crate::compress::{compress_auto, compress_file_data, select_algorithm, Algorithm, ItemCompressionSettings};
+use crate::entropy::{analyze, detect_kind, shannon_entropy, ContentKind};
+
+fn with_header(header: &[u8]) -> Vec<u8> {
+    let mut data = header.to_vec();
+    data.extend(std::iter::repeat(0u8).take(4096));
+    data
+}
+
+fn random(len: usize) -> Vec<u8> {
+    (0..len).map(|_| rand::random::<u8>()).collect()
+}
+
+// ============================================================================
+// Detection Tests
+// ============================================================================
+
+#[test]
+fn detects_image_formats() {
+    assert_eq!(detect_kind(&with_header(&[0xFF, 0xD8, 0xFF, 0xE0])), ContentKind::Jpeg);
+    assert_eq!(detect_kind(&with_header(b"\x89PNG\r\n\x1a\n")), ContentKind::Png);
+    assert_eq!(detect_kind(&with_header(b"GIF89a")), ContentKind::Gif);
+    assert_eq!(detect_kind(&with_header(b"RIFF\0\0\0\0WEBPVP8 ")), ContentKind::Webp);
+    assert_eq!(detect_kind(&with_header(b"\0\0\0\x18ftypheic")), ContentKind::Heic);
+    assert_eq!(detect_kind(&with_header(b"\0\0\0\x1cftypavif")), ContentKind::Avif);
+}
+
+#[test]
+fn detects_video_and_archives() {
+    assert_eq!(detect_kind(&with_header(b"\0\0\0\x20ftypisom")), ContentKind::Video);
+    assert_eq!(detect_kind(&with_header(&[0x1A, 0x45, 0xDF, 0xA3])), ContentKind::Video);
+    assert_eq!(detect_kind(&with_header(b"PK\x03\x04")), ContentKind::Archive);
+    assert_eq!(detect_kind(&with_header(&[0x28, 0xB5, 0x2F, 0xFD])), ContentKind::Archive);
+}
+
+#[test]
+fn detects_text_and_binary() {
+    assert_eq!(detect_kind("héllo wörld ".repeat(100).as_bytes()), ContentKind::Text);
+    assert_eq!(detect_kind(&[0u8, 1, 2, 3, 0, 0, 9]), ContentKind::Binary);
+}
+
+// ============================================================================
+// Entropy Tests
+// ============================================================================
+
+#[test]
+fn entropy_bounds() {
+    assert_eq!(shannon_entropy(&[]), 0.0);
+    assert_eq!(shannon_entropy(&[7u8; 1000]), 0.0);
+    assert!((shannon_entropy(&[0u8, 1].repeat(500)) - 1.0).abs() < 1e-9);
+    assert!(shannon_entropy(&random(64 * 1024)) > 7.9);
+}
+
+#[test]
+fn random_binary_is_incompressible() {
+    let analysis = analyze(&random(100_000));
+    assert_eq!(analysis.kind, ContentKind::Binary);
+    assert!(!analysis.compressible);
+}
+
+#[test]
+fn low_entropy_binary_is_compressible() {
+    let analysis = analyze(&[0u8, 0, 0, 1].repeat(5000));
+    assert!(analysis.compressible);
+}
+
+// ============================================================================
+// Auto Selection Tests
+// ============================================================================
+
+#[test]
+fn jpeg_is_stored_as_is() {
+    let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0];
+    data.extend(random(10_000));
+
+    assert_eq!(select_algorithm(&data, false), Algorithm::None);
+    let result = compress_auto(&data, false).unwrap();
+    assert!(!result.was_compressed);
+    assert_eq!(result.data, data);
+
+    let decision = result.decision.expect("auto sets a decision");
+    assert!(decision.store_only);
+    assert_eq!(decision.content, ContentKind::Jpeg);
+}
+
+#[test]
+fn text_decision_names_algorithm() {
+    let data = b"plain text to compress ".repeat(200);
+    let result = compress_auto(&data, false).unwrap();
+    let decision = result.decision.unwrap();
+
+    assert!(!decision.store_only);
+    assert_eq!(decision.content, ContentKind::Text);
+    assert_eq!(result.algorithm, Algorithm::Zstd);
+}
+
+#[test]
+fn file_compression_skips_media_without_extension() {
+    let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+    data.extend(random(5000));
+    let result = compress_file_data(&data, "upload.bin", &ItemCompressionSettings::default()).unwrap();
+    assert!(!result.compressed);
+}
@@ -0,0 +1,82 @@
+//! HEIF Tests
+//!
+//! Tests for:
+//! - Displayed size from the container, including rotated photos
+//! - Telling HEIF apart from AVIF and other formats
+//! - Conversion names and options
+//! - Builds without libheif failing cleanly
+
+use crate::heif::{converted_name, heif_dimensions, is_heif, DisplayFormat, HeifConversion};
+use crate::upload_policy::image_dimensions;
+
+fn boxed(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    [&(8 + payload.len() as u32).to_be_bytes()[..], &kind[..], payload].concat()
+}
+
+fn ispe(width: u32, height: u32) -> Vec<u8> {
+    boxed(b"ispe", &[&[0u8; 4][..], &width.to_be_bytes()[..], &height.to_be_bytes()[..]].concat())
+}
+
+/// iPhone-style grid photo: 512 px tiles, a 4032x3024 grid and an
+/// optional `irot`
+fn heic(brand: &[u8; 4], rotation: Option<u8>) -> Vec<u8> {
+    let mut properties = [ispe(512, 512), ispe(4032, 3024)].concat();
+    if let Some(angle) = rotation {
+        properties.extend(boxed(b"irot", &[angle]));
+    }
+    let ipco = boxed(b"ipco", &properties);
+    let meta = boxed(b"meta", &[&[0u8; 4][..], &boxed(b"iprp", &ipco)[..]].concat());
+    let ftyp = boxed(b"ftyp", &[&brand[..], &[0u8; 4][..], &b"mif1heic"[..]].concat());
+    [ftyp, meta, boxed(b"mdat", &[0xAB; 64])].concat()
+}
+
+// ============================================================================
+// Container Tests
+// ============================================================================
+
+#[test]
+fn dimensions_come_from_the_grid() {
+    assert!(is_heif(&heic(b"heic", None)));
+    assert_eq!(heif_dimensions(&heic(b"heic", None)), Some((4032, 3024)));
+    assert_eq!(heif_dimensions(&heic(b"heix", Some(1))), Some((3024, 4032)));
+    assert_eq!(heif_dimensions(&heic(b"heic", Some(2))), Some((4032, 3024)));
+    assert_eq!(image_dimensions(&heic(b"heic", Some(3))), Some((3024, 4032)));
+}
+
+#[test]
+fn other_formats_are_not_heif() {
+    assert!(!is_heif(&heic(b"avif", None)));
+    assert_eq!(heif_dimensions(&heic(b"avif", None)), None);
+    assert_eq!(heif_dimensions(b"\xFF\xD8\xFF\xE0 not a heic"), None);
+
+    // A HEIF without a meta box has no size to report
+    let bare = boxed(b"ftyp", b"heic\0\0\0\0mif1heic");
+    assert_eq!(heif_dimensions(&bare), None);
+}
+
+// ============================================================================
+// Conversion Tests
+// ============================================================================
+
+#[test]
+fn conversions_rename_and_validate() {
+    assert_eq!(converted_name("IMG_0001.HEIC", DisplayFormat::Jpeg), "IMG_0001.jpg");
+    assert_eq!(converted_name("trip/IMG_0002.heif", DisplayFormat::Webp), "trip/IMG_0002.webp");
+
+    assert_eq!(HeifConversion::default().format, DisplayFormat::Jpeg);
+    assert!(HeifConversion::default().validate().is_ok());
+    let zero = HeifConversion { format: DisplayFormat::Webp, quality: 0 };
+    assert!(zero.validate().is_err());
+}
+
+#[cfg(not(feature = "heif"))]
+#[test]
+fn builds_without_libheif_refuse_to_decode() {
+    use crate::heif::convert_heif;
+    use crate::transcode::decode_upright;
+
+    let photo = heic(b"heic", None);
+    let err = decode_upright(&photo).unwrap_err().to_string();
+    assert!(err.contains("not included"), "{}", err);
+    assert!(convert_heif(&photo, &HeifConversion::default()).is_err());
+}
@@ -0,0 +1,119 @@
+//! Folder Job Tests
+//!
+//! Tests for:
+//! - Folder walking and output paths
+//! - Progress accounting across files
+//! - Failure and cancellation outcomes
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::compress_jobs::{collect_files, run_job, JobFile, JobKind, JobProgress, JobState};
+use crate::github::AppError;
+use crate::tests::common::temp_dir;
+
+fn job_file(relative: &str, size: u64) -> JobFile {
+    JobFile {
+        source: PathBuf::from(relative),
+        relative: relative.to_string(),
+        size,
+    }
+}
+
+fn run(
+    files: &[JobFile],
+    cancel: &AtomicBool,
+    mut work: impl FnMut(&JobFile, &AtomicBool, &mut dyn FnMut(u64)) -> Result<(), AppError>,
+) -> (JobProgress, Vec<JobProgress>) {
+    let mut job = JobProgress::new("job-test", JobKind::Compress, files);
+    let mut reports = Vec::new();
+    run_job(&mut job, files, cancel, &mut work, |p| reports.push(p.clone()));
+    (job, reports)
+}
+
+// ============================================================================
+// Folder Walking Tests
+// ============================================================================
+
+#[test]
+fn collects_nested_files_and_skips_hidden() {
+    let dir = temp_dir("jobs", "walk");
+    std::fs::create_dir_all(dir.join("2024/summer")).unwrap();
+    std::fs::create_dir_all(dir.join(".cache")).unwrap();
+    std::fs::write(dir.join("b.jpg"), b"bb").unwrap();
+    std::fs::write(dir.join("2024/summer/a.png"), b"aaaa").unwrap();
+    std::fs::write(dir.join(".DS_Store"), b"x").unwrap();
+    std::fs::write(dir.join(".cache/thumb"), b"x").unwrap();
+
+    let files = collect_files(&dir).unwrap();
+    let names: Vec<&str> = files.iter().map(|f| f.relative.as_str()).collect();
+    assert_eq!(names, ["2024/summer/a.png", "b.jpg"]);
+    assert_eq!(files[0].size, 4);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn output_path_keeps_folder_structure() {
+    let file = job_file("2024/a.png", 1);
+    assert_eq!(file.output_path(Path::new("/out"), "vxc"), Path::new("/out/2024/a.png.vxc"));
+}
+
+// ============================================================================
+// Runner Tests
+// ============================================================================
+
+#[test]
+fn completed_job_counts_every_byte() {
+    let files = [job_file("a", 100), job_file("b", 50)];
+    let (job, reports) = run(&files, &AtomicBool::new(false), |file, _, progress| {
+        progress(file.size / 2);
+        progress(file.size);
+        Ok(())
+    });
+
+    assert_eq!(job.state, JobState::Completed);
+    assert_eq!((job.files_done, job.files_total), (2, 2));
+    assert_eq!((job.bytes_done, job.bytes_total), (150, 150));
+    assert!(job.current_file.is_none());
+    assert!(reports.iter().any(|p| p.bytes_done == 125 && p.current_file.as_deref() == Some("b")));
+    assert_eq!(reports.last().unwrap().state, JobState::Completed);
+}
+
+#[test]
+fn failure_stops_the_job_and_names_the_file() {
+    let files = [job_file("a", 1), job_file("b", 1), job_file("c", 1)];
+    let mut seen = Vec::new();
+    let (job, _) = run(&files, &AtomicBool::new(false), |file, _, _| {
+        seen.push(file.relative.clone());
+        if file.relative == "b" {
+            return Err(AppError::Validation("disk full".into()));
+        }
+        Ok(())
+    });
+
+    assert_eq!(job.state, JobState::Failed);
+    assert_eq!(job.files_done, 1);
+    assert!(job.error.as_deref().unwrap().starts_with("b: "));
+    assert_eq!(seen, ["a", "b"]);
+}
+
+#[test]
+fn cancellation_is_reported_as_cancelled() {
+    let files = [job_file("a", 1), job_file("b", 1)];
+    let cancel = AtomicBool::new(false);
+    let (job, _) = run(&files, &cancel, |_, cancel, _| {
+        cancel.store(true, Ordering::Relaxed);
+        Err(AppError::Validation("Compression was cancelled".into()))
+    });
+
+    assert_eq!(job.state, JobState::Cancelled);
+    assert_eq!(job.files_done, 0);
+    assert!(job.error.is_none());
+}
+
+#[test]
+fn cancelled_before_start_runs_nothing() {
+    let files = [job_file("a", 1)];
+    let (job, _) = run(&files, &AtomicBool::new(true), |_, _, _| panic!("should not run"));
+    assert_eq!(job.state, JobState::Cancelled);
+}
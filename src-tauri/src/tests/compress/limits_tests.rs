@@ -0,0 +1,143 @@
+//! Decompression Limit Tests
+//!
+//! Tests for:
+//! - Output size, ratio and time limits on every algorithm
+//! - Header-declared sizes rejected before allocation
+//! - Limit validation in `set_decompression_limits`
+
+use std::time::{Duration, Instant};
+
+use crate::compress::{
+    compress, decompress_with_limits, set_decompression_limits, Algorithm, CompressError, CompressionSettings,
+    DecompressionLimit, DecompressionLimits, RATIO_CHECK_FLOOR,
+};
+
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::Zstd,
+    Algorithm::Lz4,
+    Algorithm::Snap,
+    Algorithm::Brotli,
+    Algorithm::Gzip,
+    Algorithm::Xz,
+];
+
+fn compressed(data: &[u8], algorithm: Algorithm) -> Vec<u8> {
+    let settings = CompressionSettings {
+        algorithm,
+        level: 1,
+        prefer_speed: false,
+    };
+    compress(data, &settings).unwrap().data
+}
+
+fn limit_of(result: Result<Vec<u8>, CompressError>) -> DecompressionLimit {
+    match result {
+        Err(CompressError::LimitExceeded { limit, .. }) => limit,
+        other => panic!("expected a limit error, got {:?}", other.map(|d| d.len())),
+    }
+}
+
+// ============================================================================
+// Limit Enforcement Tests
+// ============================================================================
+
+#[test]
+fn defaults_allow_ordinary_data() {
+    let data = b"ordinary photo metadata ".repeat(4096);
+    for &algorithm in ALGORITHMS {
+        let packed = compressed(&data, algorithm);
+        let restored = decompress_with_limits(&packed, algorithm, &DecompressionLimits::default()).unwrap();
+        assert_eq!(restored, data, "{:?}", algorithm);
+    }
+}
+
+#[test]
+fn output_size_limit_applies_to_every_algorithm() {
+    let data = vec![0u8; 256 * 1024];
+    let limits = DecompressionLimits {
+        max_output_bytes: 64 * 1024,
+        ..Default::default()
+    };
+    for &algorithm in ALGORITHMS {
+        let packed = compressed(&data, algorithm);
+        let result = decompress_with_limits(&packed, algorithm, &limits);
+        assert_eq!(limit_of(result), DecompressionLimit::OutputSize, "{:?}", algorithm);
+    }
+}
+
+#[test]
+fn ratio_limit_stops_bombs() {
+    let data = vec![0u8; RATIO_CHECK_FLOOR as usize * 2];
+    let limits = DecompressionLimits {
+        max_ratio: 100,
+        ..Default::default()
+    };
+    let packed = compressed(&data, Algorithm::Zstd);
+    let result = decompress_with_limits(&packed, Algorithm::Zstd, &limits);
+    assert_eq!(limit_of(result), DecompressionLimit::Ratio);
+}
+
+#[test]
+fn ratio_limit_ignores_small_outputs() {
+    let data = vec![0u8; 1024 * 1024];
+    let limits = DecompressionLimits {
+        max_ratio: 2,
+        ..Default::default()
+    };
+    let packed = compressed(&data, Algorithm::Zstd);
+    assert_eq!(decompress_with_limits(&packed, Algorithm::Zstd, &limits).unwrap().len(), data.len());
+}
+
+#[test]
+fn check_reports_timeout() {
+    let limits = DecompressionLimits {
+        timeout_ms: 1,
+        ..Default::default()
+    };
+    let started = Instant::now() - Duration::from_millis(50);
+    match limits.check(10, 10, started) {
+        Err(CompressError::LimitExceeded { limit, max }) => {
+            assert_eq!(limit, DecompressionLimit::Timeout);
+            assert_eq!(max, 1);
+        }
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+}
+
+// ============================================================================
+// Declared Size Tests
+// ============================================================================
+
+#[test]
+fn forged_size_header_is_rejected_before_allocation() {
+    // Four bytes of payload claiming almost 4 GiB of output
+    let mut forged = u32::MAX.to_le_bytes().to_vec();
+    forged.extend_from_slice(&[0, 0, 0, 0]);
+    for algorithm in [Algorithm::Lz4, Algorithm::Snap] {
+        let result = decompress_with_limits(&forged, algorithm, &DecompressionLimits::default());
+        assert_eq!(limit_of(result), DecompressionLimit::OutputSize, "{:?}", algorithm);
+    }
+}
+
+// ============================================================================
+// Command Tests
+// ============================================================================
+
+#[test]
+fn zero_limits_are_rejected() {
+    let limits = DecompressionLimits {
+        max_ratio: 0,
+        ..Default::default()
+    };
+    assert!(set_decompression_limits(Some(limits)).is_err());
+}
+
+#[test]
+fn limit_errors_serialize_as_messages() {
+    let error = CompressError::LimitExceeded {
+        limit: DecompressionLimit::OutputSize,
+        max: 1024,
+    };
+    let json = serde_json::to_string(&error).unwrap();
+    assert!(json.contains("output size limit of 1024"));
+}
@@ -4,7 +4,29 @@
 //! - `algorithm_tests` - Individual algorithm roundtrips and edge cases
 //! - `roundtrip_tests` - Full compression/decompression cycles
 //! - `file_tests` - File-based compression with checksums
+//! - `stream_tests` - Streaming compression, cancellation and progress
+//! - `parallel_tests` - Segment-parallel compression container
+//! - `benchmark_tests` - Device benchmark and calibrated recommendations
+//! - `entropy_tests` - Content classification and store-only bypass
+//! - `limits_tests` - Decompression bomb and resource limits
+//! - `jobs_tests` - Folder compression jobs, progress and cancellation
+//! - `optimize_tests` - Lossless PNG/JPEG optimization and its pipeline step
+//! - `transcode_tests` - AVIF/WebP/JPEG XL transcoding
+//! - `heif_tests` - HEIF/HEIC container parsing and conversion
+//! - `resize_tests` - Max edge, exact and percentage resizing
+//! - `watermark_tests` - PNG and text watermarks
 
 pub mod algorithm_tests;
 pub mod roundtrip_tests;
 pub mod file_tests;
+pub mod stream_tests;
+pub mod parallel_tests;
+pub mod benchmark_tests;
+pub mod entropy_tests;
+pub mod limits_tests;
+pub mod jobs_tests;
+pub mod optimize_tests;
+pub mod transcode_tests;
+pub mod heif_tests;
+pub mod resize_tests;
+pub mod watermark_tests;
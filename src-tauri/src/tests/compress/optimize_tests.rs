@@ -0,0 +1,169 @@
+//! Image Optimization Tests
+//!
+//! Tests for:
+//! - JPEG Huffman re-encoding keeps pixels identical
+//! - Metadata stripping and trailing data
+//! - PNG optimization and pass-through of other formats
+//! - The optimize_image pipeline step
+
+use image::{ImageBuffer, Rgb, RgbImage};
+use std::collections::HashMap;
+
+use crate::image_optimize::{optimize_image_data, OptimizeOptions};
+use crate::jpeg_optimize::optimize_jpeg;
+use crate::pipeline::{
+    process_pipeline, reverse_pipeline, PipelineConfig, PipelineContext, PipelineLayer, PipelineOperation,
+};
+use crate::tests::common::encoded;
+
+fn photo() -> RgbImage {
+    ImageBuffer::from_fn(97, 61, |x, y| Rgb([(x * 5) as u8, (y * 3 + x) as u8, ((x ^ y) * 7) as u8]))
+}
+
+fn jpeg(image: &RgbImage) -> Vec<u8> {
+    encoded(image, image::ImageFormat::Jpeg)
+}
+
+fn png(image: &RgbImage) -> Vec<u8> {
+    encoded(image, image::ImageFormat::Png)
+}
+
+fn pixels(data: &[u8]) -> Vec<u8> {
+    image::load_from_memory(data).unwrap().to_rgb8().into_raw()
+}
+
+/// Insert a segment right after the start-of-image marker
+fn with_segment(data: &[u8], marker: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = data[..2].to_vec();
+    out.extend_from_slice(&[0xFF, marker]);
+    out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+    out.extend_from_slice(payload);
+    out.extend_from_slice(&data[2..]);
+    out
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+// ============================================================================
+// JPEG Tests
+// ============================================================================
+
+#[test]
+fn jpeg_optimization_keeps_pixels() {
+    let original = jpeg(&photo());
+    let optimized = optimize_jpeg(&original, false).unwrap();
+
+    assert!(optimized.len() < original.len());
+    assert_eq!(pixels(&optimized), pixels(&original));
+}
+
+#[test]
+fn jpeg_optimization_is_stable() {
+    let once = optimize_jpeg(&jpeg(&photo()), false).unwrap();
+    let twice = optimize_jpeg(&once, false).unwrap();
+    assert_eq!(once, twice);
+}
+
+#[test]
+fn jpeg_metadata_is_kept_unless_stripped() {
+    let original = with_segment(&jpeg(&photo()), 0xFE, b"shot on a phone");
+
+    let kept = optimize_jpeg(&original, false).unwrap();
+    assert!(contains(&kept, b"shot on a phone"));
+
+    let stripped = optimize_jpeg(&original, true).unwrap();
+    assert!(!contains(&stripped, b"shot on a phone"));
+    assert_eq!(pixels(&stripped), pixels(&original));
+}
+
+#[test]
+fn jpeg_trailing_data_is_kept() {
+    let mut original = jpeg(&photo());
+    original.extend_from_slice(b"motion photo video");
+    let optimized = optimize_jpeg(&original, true).unwrap();
+    assert!(optimized.ends_with(b"motion photo video"));
+}
+
+#[test]
+fn truncated_jpeg_is_rejected() {
+    let original = jpeg(&photo());
+    assert!(optimize_jpeg(&original[..original.len() / 2], false).is_err());
+}
+
+// ============================================================================
+// Dispatch Tests
+// ============================================================================
+
+#[test]
+fn png_optimization_keeps_pixels() {
+    let original = png(&photo());
+    let (optimized, result) = optimize_image_data(&original, &OptimizeOptions::default());
+
+    assert_eq!(pixels(&optimized), pixels(&original));
+    assert!(optimized.len() <= original.len());
+    assert_eq!(result.optimized_size, optimized.len() as u64);
+}
+
+#[test]
+fn other_formats_pass_through() {
+    let data = b"not an image at all".repeat(10);
+    let (output, result) = optimize_image_data(&data, &OptimizeOptions::default());
+
+    assert_eq!(output, data);
+    assert!(!result.optimized);
+    assert_eq!(result.saved_bytes, 0);
+    assert!(result.skipped_reason.is_some());
+}
+
+// ============================================================================
+// Pipeline Tests
+// ============================================================================
+
+fn layer(id: &str, order: u32, operation: PipelineOperation) -> PipelineLayer {
+    PipelineLayer {
+        id: id.to_string(),
+        operation,
+        enabled: true,
+        order,
+        condition: None,
+    }
+}
+
+fn config(layers: Vec<PipelineLayer>) -> PipelineConfig {
+    PipelineConfig {
+        layers,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn pipeline_restores_the_optimized_image() {
+    let original = jpeg(&photo());
+    let config = config(vec![
+        layer("optimize", 0, PipelineOperation::OptimizeImage { options: OptimizeOptions::default() }),
+        layer("compress", 1, PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 }),
+    ]);
+    let context = PipelineContext {
+        passwords: HashMap::new(),
+        keypair: None,
+    };
+
+    let processed = process_pipeline(&original, &config, &context).unwrap();
+    assert_eq!(processed.original_size, original.len());
+    let restored = reverse_pipeline(&processed.data, &context).unwrap();
+
+    assert!(restored.data.len() < original.len());
+    assert_eq!(pixels(&restored.data), pixels(&original));
+}
+
+#[test]
+fn pipeline_rejects_optimization_after_wrapping() {
+    let config = config(vec![
+        layer("compress", 0, PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 }),
+        layer("optimize", 1, PipelineOperation::OptimizeImage { options: OptimizeOptions::default() }),
+    ]);
+    assert!(process_pipeline(&jpeg(&photo()), &config, &PipelineContext::default()).is_err());
+    assert!(crate::pipeline::pipeline_validate(config).is_err());
+}
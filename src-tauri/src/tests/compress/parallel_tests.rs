@@ -0,0 +1,112 @@
+//! Segment-Parallel Compression Tests
+//!
+//! Tests for:
+//! - Segmented container roundtrip across algorithms and thread counts
+//! - Incompressible segments stored as is
+//! - Container validation
+//! - Segmented file compression through `compress_file_data_parallel`
+
+use crate::compress::{
+    compress_file_data_parallel, compress_segmented, decompress_file_data, decompress_segmented,
+    effective_parallelism, Algorithm, ItemCompressionSettings, DEFAULT_SEGMENT_SIZE,
+};
+
+const SEGMENT: usize = 64 * 1024;
+
+/// About five segments of compressible data with a short tail
+fn sample() -> Vec<u8> {
+    b"segment parallel compression sample line\n".repeat(SEGMENT * 5 / 41 + 3)
+}
+
+// ============================================================================
+// Container Tests
+// ============================================================================
+
+#[test]
+fn segmented_roundtrip_all_algorithms() {
+    let data = sample();
+    for algorithm in [Algorithm::Zstd, Algorithm::Lz4, Algorithm::Snap, Algorithm::Brotli, Algorithm::Gzip, Algorithm::Xz] {
+        let container = compress_segmented(&data, algorithm, 3, SEGMENT, 4).unwrap();
+        assert!(container.len() < data.len(), "{:?} should shrink", algorithm);
+        assert_eq!(decompress_segmented(&container, 4).unwrap(), data, "{:?}", algorithm);
+    }
+}
+
+#[test]
+fn output_does_not_depend_on_thread_count() {
+    let data = sample();
+    let single = compress_segmented(&data, Algorithm::Zstd, 3, SEGMENT, 1).unwrap();
+    let many = compress_segmented(&data, Algorithm::Zstd, 3, SEGMENT, 8).unwrap();
+
+    assert_eq!(single, many);
+    assert_eq!(decompress_segmented(&many, 1).unwrap(), data);
+}
+
+#[test]
+fn incompressible_segments_are_stored() {
+    let data: Vec<u8> = (0..SEGMENT * 2).map(|_| rand::random::<u8>()).collect();
+    let container = compress_segmented(&data, Algorithm::Zstd, 3, SEGMENT, 2).unwrap();
+
+    assert_eq!(decompress_segmented(&container, 2).unwrap(), data);
+}
+
+#[test]
+fn empty_input_roundtrip() {
+    let container = compress_segmented(&[], Algorithm::Lz4, 1, SEGMENT, 2).unwrap();
+    assert!(decompress_segmented(&container, 2).unwrap().is_empty());
+}
+
+#[test]
+fn rejects_truncated_container() {
+    let container = compress_segmented(&sample(), Algorithm::Lz4, 1, SEGMENT, 2).unwrap();
+
+    assert!(decompress_segmented(&container[..container.len() - 1], 2).is_err());
+    assert!(decompress_segmented(&container[..10], 2).is_err());
+}
+
+#[test]
+fn rejects_foreign_data() {
+    assert!(decompress_segmented(&[0u8; 64], 2).is_err());
+}
+
+#[test]
+fn zero_threads_means_all_cores() {
+    assert!(effective_parallelism(0) >= 1);
+    assert_eq!(effective_parallelism(3), 3);
+}
+
+// ============================================================================
+// File Compression Tests
+// ============================================================================
+
+#[test]
+fn large_file_is_segmented_and_verified() {
+    let data = b"0123456789abcdef".repeat(2 * DEFAULT_SEGMENT_SIZE / 16 + 100);
+    let settings = ItemCompressionSettings::default();
+
+    let compressed = compress_file_data_parallel(&data, "video.raw", &settings, 4).unwrap();
+    assert!(compressed.compressed);
+    assert!(compressed.segmented);
+
+    assert_eq!(decompress_file_data(&compressed).unwrap(), data);
+}
+
+#[test]
+fn single_thread_is_not_segmented() {
+    let data = b"0123456789abcdef".repeat(2 * DEFAULT_SEGMENT_SIZE / 16 + 100);
+    let settings = ItemCompressionSettings::default();
+
+    let compressed = compress_file_data_parallel(&data, "video.raw", &settings, 1).unwrap();
+    assert!(compressed.compressed);
+    assert!(!compressed.segmented);
+}
+
+#[test]
+fn small_file_is_not_segmented() {
+    let data = sample();
+    let settings = ItemCompressionSettings::default();
+
+    let compressed = compress_file_data_parallel(&data, "notes.txt", &settings, 8).unwrap();
+    assert!(!compressed.segmented);
+    assert_eq!(decompress_file_data(&compressed).unwrap(), data);
+}
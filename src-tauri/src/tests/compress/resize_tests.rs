@@ -0,0 +1,143 @@
+//! Image Resizing Tests
+//!
+//! Tests for:
+//! - Max edge, exact and percentage target sizes
+//! - Keeping photos that already fit, and the output format
+//! - Option validation and the resize pipeline step
+
+use std::path::Path;
+
+use crate::entropy::{detect_kind, ContentKind};
+use crate::pipeline::{pipeline_estimate, PipelineConfig, PipelineLayer, PipelineOperation};
+use crate::pipeline_routing::originals_album;
+use crate::resize::{resize_image_data, resized_path, target_size, validate_options, ResizeMode, ResizeOptions};
+use crate::tests::common::{encoded, gradient};
+
+fn percent(percent: u32) -> ResizeOptions {
+    ResizeOptions {
+        mode: ResizeMode::Percent,
+        percent: Some(percent),
+        ..ResizeOptions::max_edge(1)
+    }
+}
+
+fn layer(options: ResizeOptions) -> PipelineLayer {
+    PipelineLayer {
+        id: "web".into(),
+        operation: PipelineOperation::Resize { options },
+        enabled: true,
+        order: 0,
+        condition: None,
+    }
+}
+
+// ============================================================================
+// Target Size Tests
+// ============================================================================
+
+#[test]
+fn max_edge_keeps_the_aspect_ratio() {
+    let options = ResizeOptions::max_edge(2048);
+    assert_eq!(target_size(6000, 4000, &options), (2048, 1365));
+    assert_eq!(target_size(3000, 4500, &options), (1365, 2048));
+    assert_eq!(target_size(1600, 1200, &options), (1600, 1200), "never upscaled");
+    assert_eq!(target_size(10_000, 2, &options), (2048, 1), "sides stay at least 1px");
+}
+
+#[test]
+fn exact_and_percent_sizes() {
+    let exact = ResizeOptions {
+        mode: ResizeMode::Exact,
+        width: Some(300),
+        height: Some(300),
+        ..ResizeOptions::max_edge(1)
+    };
+    assert_eq!(target_size(640, 480, &exact), (300, 300));
+    assert_eq!(target_size(640, 480, &percent(25)), (160, 120));
+}
+
+// ============================================================================
+// Resize Tests
+// ============================================================================
+
+#[test]
+fn jpeg_is_scaled_down_as_jpeg() {
+    let original = encoded(&gradient(400, 300), image::ImageFormat::Jpeg);
+    let (output, result) = resize_image_data(&original, &ResizeOptions::max_edge(100)).unwrap();
+
+    assert!(result.resized);
+    assert_eq!((result.original_width, result.original_height), (400, 300));
+    assert_eq!(result.format, "jpeg");
+    assert_eq!(detect_kind(&output), ContentKind::Jpeg);
+    let decoded = image::load_from_memory(&output).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (100, 75));
+}
+
+#[test]
+fn png_stays_png_and_fitting_photos_are_kept() {
+    let original = encoded(&gradient(200, 100), image::ImageFormat::Png);
+    let (output, result) = resize_image_data(&original, &percent(50)).unwrap();
+    assert_eq!(result.format, "png");
+    assert_eq!(image::load_from_memory(&output).unwrap().width(), 100);
+
+    let (kept, result) = resize_image_data(&original, &ResizeOptions::max_edge(512)).unwrap();
+    assert!(!result.resized);
+    assert_eq!(kept, original);
+}
+
+#[test]
+fn invalid_options_are_refused() {
+    assert!(validate_options(&ResizeOptions::max_edge(0)).is_err());
+    assert!(validate_options(&percent(150)).is_err());
+    assert!(validate_options(&ResizeOptions { mode: ResizeMode::Exact, ..ResizeOptions::max_edge(10) }).is_err());
+    assert!(validate_options(&ResizeOptions { quality: 0, ..ResizeOptions::max_edge(10) }).is_err());
+    assert!(validate_options(&ResizeOptions {
+        originals_album: Some("../originals".into()),
+        ..ResizeOptions::max_edge(10)
+    })
+    .is_err());
+}
+
+#[test]
+fn resized_path_names_the_size() {
+    assert_eq!(
+        resized_path(Path::new("/photos/IMG_0001.HEIC"), 2048, 1536, "jpeg"),
+        Path::new("/photos/IMG_0001-2048x1536.jpg")
+    );
+}
+
+// ============================================================================
+// Pipeline Tests
+// ============================================================================
+
+#[test]
+fn resize_layer_deserializes_flat() {
+    let json = r#"{"type":"resize","mode":"max_edge","max_edge":1600,"originals_album":"originals"}"#;
+    let operation: PipelineOperation = serde_json::from_str(json).unwrap();
+    match &operation {
+        PipelineOperation::Resize { options } => {
+            assert_eq!(options.max_edge, Some(1600));
+            assert_eq!(options.quality, 85);
+        }
+        other => panic!("unexpected operation {:?}", other),
+    }
+    assert!(operation.is_source_transform());
+}
+
+#[test]
+fn estimate_and_originals_follow_the_layer() {
+    let mut options = percent(50);
+    options.originals_album = Some("originals".into());
+    let config = PipelineConfig {
+        layers: vec![layer(options)],
+        ..Default::default()
+    };
+    assert_eq!(pipeline_estimate(1000, config.clone(), None)["estimated_final_size"], 250);
+    assert_eq!(originals_album(&config), Some("originals"));
+
+    let plain = PipelineConfig {
+        layers: vec![layer(ResizeOptions::max_edge(2048))],
+        ..Default::default()
+    };
+    assert_eq!(originals_album(&plain), None);
+}
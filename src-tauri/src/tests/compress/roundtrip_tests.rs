@@ -6,7 +6,8 @@
 //! - Compression result transparency
 
 use crate::compress::{
-    compress, compress_auto, decompress, select_algorithm, Algorithm, CompressionSettings,
+    compress, compress_auto, compress_auto_for, decompress, select_algorithm, select_algorithm_for, Algorithm,
+    CompressionSettings,
 };
 
 // ============================================================================
@@ -54,6 +55,7 @@ fn compress_decompress_roundtrip_all_algorithms() {
         Algorithm::Snap,
         Algorithm::Brotli,
         Algorithm::Gzip,
+        Algorithm::Xz,
     ];
     
     for algo in algorithms {
@@ -116,6 +118,31 @@ fn select_algorithm_large_data_ratio() {
     assert_eq!(algo, Algorithm::Zstd);
 }
 
+#[test]
+fn select_algorithm_json_uses_brotli() {
+    let data = br#"{"albums": [{"name": "Trip", "photos": 42}]}"#.repeat(10);
+
+    assert_eq!(select_algorithm(&data, false), Algorithm::Brotli);
+    assert_eq!(select_algorithm(&data, true), Algorithm::Lz4, "speed still wins");
+}
+
+#[test]
+fn select_algorithm_archival_uses_xz() {
+    let data = vec![42u8; 1000];
+
+    assert_eq!(select_algorithm_for(&data, false, true), Algorithm::Xz);
+    assert_eq!(select_algorithm_for(&data, true, true), Algorithm::Lz4);
+}
+
+#[test]
+fn compress_auto_archival_roundtrip() {
+    let data = b"archived log line\n".repeat(500);
+
+    let result = compress_auto_for(&data, false, true).unwrap();
+    assert_eq!(result.algorithm, Algorithm::Xz);
+    assert_eq!(decompress(&result.data, result.algorithm).unwrap(), data);
+}
+
 // ============================================================================
 // Compression Result Transparency Tests
 // ============================================================================
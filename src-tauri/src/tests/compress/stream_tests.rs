@@ -0,0 +1,152 @@
+//! Streaming Compression Tests
+//!
+//! Tests for:
+//! - Stream roundtrip for every algorithm
+//! - Header validation and truncation
+//! - Cancellation and progress reporting
+
+use std::io::Cursor;
+use std::sync::atomic::AtomicBool;
+
+use crate::compress::Algorithm;
+use crate::compress_stream::{compress_stream, decompress_stream, read_stream_header, STREAM_BUFFER_SIZE};
+
+const ALL: [Algorithm; 7] = [
+    Algorithm::None,
+    Algorithm::Zstd,
+    Algorithm::Lz4,
+    Algorithm::Snap,
+    Algorithm::Brotli,
+    Algorithm::Gzip,
+    Algorithm::Xz,
+];
+
+/// Spans several buffers and ends mid-buffer
+fn sample() -> Vec<u8> {
+    (0..STREAM_BUFFER_SIZE * 2 + 1234).map(|i| (i % 251) as u8 ^ (i / 4096) as u8).collect()
+}
+
+fn compress_all(data: &[u8], algorithm: Algorithm) -> Vec<u8> {
+    let mut out = Vec::new();
+    let (read, written) = compress_stream(
+        Cursor::new(data.to_vec()),
+        &mut out,
+        algorithm,
+        3,
+        data.len() as u64,
+        &AtomicBool::new(false),
+        |_| {},
+    )
+    .expect("compress");
+    assert_eq!(read, data.len() as u64);
+    assert_eq!(written, out.len() as u64);
+    out
+}
+
+// ============================================================================
+// Roundtrip Tests
+// ============================================================================
+
+#[test]
+fn stream_roundtrip_all_algorithms() {
+    let data = sample();
+    for algorithm in ALL {
+        let compressed = compress_all(&data, algorithm);
+        let mut out = Vec::new();
+        let (found, len) = decompress_stream(Cursor::new(compressed), &mut out, &AtomicBool::new(false), |_, _| {})
+            .unwrap_or_else(|e| panic!("{:?}: {}", algorithm, e));
+
+        assert_eq!(found, algorithm);
+        assert_eq!(len, data.len() as u64);
+        assert!(out == data, "roundtrip failed for {:?}", algorithm);
+    }
+}
+
+#[test]
+fn stream_roundtrip_empty_input() {
+    for algorithm in ALL {
+        let compressed = compress_all(&[], algorithm);
+        let mut out = Vec::new();
+        decompress_stream(Cursor::new(compressed), &mut out, &AtomicBool::new(false), |_, _| {}).unwrap();
+        assert!(out.is_empty());
+    }
+}
+
+#[test]
+fn stream_header_records_algorithm_and_length() {
+    let data = sample();
+    let compressed = compress_all(&data, Algorithm::Xz);
+    let (algorithm, len) = read_stream_header(&mut Cursor::new(compressed)).unwrap();
+
+    assert_eq!(algorithm, Algorithm::Xz);
+    assert_eq!(len, data.len() as u64);
+}
+
+// ============================================================================
+// Validation Tests
+// ============================================================================
+
+#[test]
+fn rejects_foreign_data() {
+    let mut out = Vec::new();
+    let result = decompress_stream(Cursor::new(b"PK\x03\x04 not ours".to_vec()), &mut out, &AtomicBool::new(false), |_, _| {});
+    assert!(result.is_err());
+}
+
+#[test]
+fn rejects_truncated_stream() {
+    let mut compressed = compress_all(&sample(), Algorithm::Zstd);
+    compressed.truncate(compressed.len() / 2);
+
+    let mut out = Vec::new();
+    let result = decompress_stream(Cursor::new(compressed), &mut out, &AtomicBool::new(false), |_, _| {});
+    assert!(result.is_err());
+}
+
+// ============================================================================
+// Cancellation And Progress Tests
+// ============================================================================
+
+#[test]
+fn cancelled_compression_fails() {
+    let data = sample();
+    let mut out = Vec::new();
+    let result = compress_stream(
+        Cursor::new(data.clone()),
+        &mut out,
+        Algorithm::Lz4,
+        1,
+        data.len() as u64,
+        &AtomicBool::new(true),
+        |_| {},
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn cancelled_decompression_fails() {
+    let compressed = compress_all(&sample(), Algorithm::Lz4);
+    let mut out = Vec::new();
+    let result = decompress_stream(Cursor::new(compressed), &mut out, &AtomicBool::new(true), |_, _| {});
+    assert!(result.is_err());
+}
+
+#[test]
+fn progress_is_monotonic_and_complete() {
+    let data = sample();
+    let mut seen = Vec::new();
+    compress_stream(
+        Cursor::new(data.clone()),
+        Vec::new(),
+        Algorithm::Zstd,
+        1,
+        data.len() as u64,
+        &AtomicBool::new(false),
+        |done| seen.push(done),
+    )
+    .unwrap();
+
+    assert!(seen.len() >= 3, "one update per buffer");
+    assert!(seen.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(*seen.last().unwrap(), data.len() as u64);
+}
@@ -0,0 +1,134 @@
+//! Image Transcoding Tests
+//!
+//! Tests for:
+//! - WebP and AVIF output from PNG and JPEG input
+//! - Keeping the input when the output is not smaller
+//! - Option validation and the transcode pipeline step
+
+use image::{ImageBuffer, Rgb, RgbImage};
+use std::path::Path;
+
+use crate::entropy::{detect_kind, ContentKind};
+use crate::pipeline::{pipeline_estimate, PipelineConfig, PipelineLayer, PipelineOperation};
+use crate::tests::common::{encoded, gradient};
+use crate::transcode::{transcode_image_data, transcoded_path, TargetFormat, TranscodeOptions};
+
+fn photo() -> RgbImage {
+    gradient(160, 120)
+}
+
+fn png(image: &RgbImage) -> Vec<u8> {
+    encoded(image, image::ImageFormat::Png)
+}
+
+fn options(format: TargetFormat) -> TranscodeOptions {
+    TranscodeOptions {
+        format,
+        quality: 75,
+        keep_original: false,
+    }
+}
+
+// ============================================================================
+// Encoding Tests
+// ============================================================================
+
+#[test]
+fn png_becomes_smaller_webp() {
+    let original = png(&photo());
+    let (output, result) = transcode_image_data(&original, &options(TargetFormat::Webp)).unwrap();
+
+    assert!(result.transcoded);
+    assert_eq!(detect_kind(&output), ContentKind::Webp);
+    assert!(output.len() < original.len());
+    assert_eq!((result.width, result.height), (160, 120));
+
+    let decoded = image::load_from_memory(&output).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (160, 120));
+}
+
+#[test]
+fn png_becomes_avif() {
+    let original = png(&photo());
+    let (output, result) = transcode_image_data(&original, &options(TargetFormat::Avif)).unwrap();
+
+    assert!(result.transcoded);
+    assert_eq!(detect_kind(&output), ContentKind::Avif);
+    assert_eq!(result.transcoded_size, output.len() as u64);
+}
+
+#[test]
+fn larger_output_keeps_the_input() {
+    // A tiny, flat image compresses to less than any container overhead
+    let tiny = png(&ImageBuffer::from_pixel(1, 1, Rgb([0, 0, 0])));
+    let mut high = options(TargetFormat::Avif);
+    high.quality = 100;
+    let (output, result) = transcode_image_data(&tiny, &high).unwrap();
+
+    assert!(!result.transcoded);
+    assert_eq!(output, tiny);
+}
+
+#[test]
+fn undecodable_input_fails() {
+    assert!(transcode_image_data(b"not an image", &options(TargetFormat::Webp)).is_err());
+}
+
+// ============================================================================
+// Validation Tests
+// ============================================================================
+
+#[test]
+fn quality_must_be_in_range() {
+    let mut bad = options(TargetFormat::Webp);
+    bad.quality = 0;
+    assert!(transcode_image_data(&png(&photo()), &bad).is_err());
+}
+
+#[cfg(not(feature = "jxl"))]
+#[test]
+fn jxl_requires_the_feature() {
+    assert!(transcode_image_data(&png(&photo()), &options(TargetFormat::Jxl)).is_err());
+}
+
+#[test]
+fn transcoded_path_swaps_the_extension() {
+    assert_eq!(
+        transcoded_path(Path::new("/photos/IMG_0001.JPG"), TargetFormat::Avif),
+        Path::new("/photos/IMG_0001.avif")
+    );
+}
+
+// ============================================================================
+// Pipeline Tests
+// ============================================================================
+
+#[test]
+fn estimate_uses_the_format_ratio() {
+    let config = PipelineConfig {
+        layers: vec![PipelineLayer {
+            id: "to-webp".into(),
+            operation: PipelineOperation::Transcode { options: options(TargetFormat::Webp) },
+            enabled: true,
+            order: 0,
+            condition: None,
+        }],
+        ..Default::default()
+    };
+    let estimate = pipeline_estimate(1000, config, None);
+    assert_eq!(estimate["estimated_final_size"], 500);
+}
+
+#[test]
+fn transcode_layer_deserializes_flat() {
+    let json = r#"{"type":"transcode","format":"webp","quality":60}"#;
+    let operation: PipelineOperation = serde_json::from_str(json).unwrap();
+    match operation {
+        PipelineOperation::Transcode { options } => {
+            assert_eq!(options.format, TargetFormat::Webp);
+            assert_eq!(options.quality, 60);
+            assert!(!options.keep_original);
+        }
+        other => panic!("unexpected operation {:?}", other),
+    }
+}
@@ -0,0 +1,144 @@
+//! Watermark Tests
+//!
+//! Tests for:
+//! - Placing, scaling and blending the mark
+//! - Watermarking encoded photos with a PNG
+//! - Option validation and the watermark pipeline step
+
+use image::{ImageBuffer, Rgb, RgbImage, Rgba, RgbaImage};
+
+use crate::pipeline::{pipeline_estimate, PipelineConfig, PipelineLayer, PipelineOperation};
+use crate::tests::common::{encoded, temp_file};
+use crate::watermark::{
+    apply_mark, mark_origin, validate_options, watermark_image_data, WatermarkOptions, WatermarkPosition, MAX_TEXT_LEN,
+};
+
+fn red_mark() -> RgbaImage {
+    ImageBuffer::from_pixel(10, 10, Rgba([255, 0, 0, 255]))
+}
+
+// ============================================================================
+// Compositing Tests
+// ============================================================================
+
+#[test]
+fn marks_sit_inside_the_margin() {
+    // Margin is 2% of the shorter side
+    assert_eq!(mark_origin(400, 200, 100, 50, WatermarkPosition::TopLeft), (4, 4));
+    assert_eq!(mark_origin(400, 200, 100, 50, WatermarkPosition::TopRight), (296, 4));
+    assert_eq!(mark_origin(400, 200, 100, 50, WatermarkPosition::BottomLeft), (4, 146));
+    assert_eq!(mark_origin(400, 200, 100, 50, WatermarkPosition::BottomRight), (296, 146));
+    assert_eq!(mark_origin(400, 200, 100, 50, WatermarkPosition::Center), (150, 75));
+}
+
+#[test]
+fn mark_is_scaled_to_the_photo_width() {
+    let mut photo: RgbaImage = ImageBuffer::from_pixel(400, 200, Rgba([0, 0, 0, 255]));
+    let options = WatermarkOptions { opacity: 1.0, scale: 0.25, ..WatermarkOptions::image("mark.png") };
+    apply_mark(&mut photo, &red_mark(), &options);
+
+    // 100×100 at (296, 96)
+    assert_eq!(photo.get_pixel(346, 146), &Rgba([255, 0, 0, 255]));
+    assert_eq!(photo.get_pixel(290, 146), &Rgba([0, 0, 0, 255]));
+    assert_eq!(photo.get_pixel(10, 10), &Rgba([0, 0, 0, 255]));
+}
+
+#[test]
+fn opacity_blends_the_mark() {
+    let mut photo: RgbaImage = ImageBuffer::from_pixel(100, 100, Rgba([0, 0, 0, 255]));
+    let options = WatermarkOptions {
+        opacity: 0.5,
+        scale: 0.5,
+        position: WatermarkPosition::Center,
+        ..WatermarkOptions::image("mark.png")
+    };
+    apply_mark(&mut photo, &red_mark(), &options);
+
+    let center = photo.get_pixel(50, 50);
+    assert!((120..=136).contains(&center.0[0]), "half red, got {:?}", center);
+    assert_eq!(center.0[3], 255, "the photo stays opaque");
+}
+
+// ============================================================================
+// Encoded Photo Tests
+// ============================================================================
+
+#[test]
+fn photos_keep_their_size_and_format() {
+    let mut mark = Vec::new();
+    red_mark().write_to(&mut std::io::Cursor::new(&mut mark), image::ImageFormat::Png).unwrap();
+    let mark_path = temp_file("watermark", "mark.png", &mark);
+    let options = WatermarkOptions::image(&mark_path.to_string_lossy());
+    let photo: RgbImage = ImageBuffer::from_pixel(320, 240, Rgb([20, 120, 200]));
+
+    let (jpeg, format) = watermark_image_data(&encoded(&photo, image::ImageFormat::Jpeg), &options).unwrap();
+    assert_eq!(format, "jpeg");
+    let decoded = image::load_from_memory(&jpeg).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (320, 240));
+
+    let (png, format) = watermark_image_data(&encoded(&photo, image::ImageFormat::Png), &options).unwrap();
+    assert_eq!(format, "png");
+    let decoded = image::load_from_memory(&png).unwrap().to_rgb8();
+    assert_ne!(decoded.get_pixel(300, 220), &Rgb([20, 120, 200]), "corner is marked");
+    assert_eq!(decoded.get_pixel(10, 10), &Rgb([20, 120, 200]));
+
+    let _ = std::fs::remove_file(mark_path);
+}
+
+#[test]
+fn marks_must_be_png() {
+    let not_png = temp_file("watermark", "mark.jpg", &encoded(&ImageBuffer::from_pixel(8, 8, Rgb([0, 0, 0])), image::ImageFormat::Jpeg));
+    let options = WatermarkOptions::image(&not_png.to_string_lossy());
+    let photo = encoded(&ImageBuffer::from_pixel(64, 64, Rgb([0, 0, 0])), image::ImageFormat::Png);
+    assert!(watermark_image_data(&photo, &options).is_err());
+    let _ = std::fs::remove_file(not_png);
+}
+
+#[test]
+fn invalid_options_are_refused() {
+    assert!(validate_options(&WatermarkOptions::text("© 2026")).is_ok());
+    assert!(validate_options(&WatermarkOptions::text("  ")).is_err());
+    assert!(validate_options(&WatermarkOptions::text(&"x".repeat(MAX_TEXT_LEN + 1))).is_err());
+    assert!(validate_options(&WatermarkOptions { image_path: Some("mark.png".into()), ..WatermarkOptions::text("both") })
+        .is_err());
+    assert!(validate_options(&WatermarkOptions { opacity: 0.0, ..WatermarkOptions::text("a") }).is_err());
+    assert!(validate_options(&WatermarkOptions { scale: 1.5, ..WatermarkOptions::text("a") }).is_err());
+    assert!(validate_options(&WatermarkOptions { quality: 0, ..WatermarkOptions::text("a") }).is_err());
+}
+
+// ============================================================================
+// Pipeline Tests
+// ============================================================================
+
+#[test]
+fn watermark_layer_deserializes_flat() {
+    let json = r#"{"type":"watermark","text":"© Ana","position":"top_left","opacity":0.8}"#;
+    let operation: PipelineOperation = serde_json::from_str(json).unwrap();
+    match &operation {
+        PipelineOperation::Watermark { options } => {
+            assert_eq!(options.position, WatermarkPosition::TopLeft);
+            assert_eq!(options.scale, 0.2);
+            assert_eq!(options.quality, 90);
+        }
+        other => panic!("unexpected operation {:?}", other),
+    }
+    assert_eq!(operation.step_id(), "watermark");
+    assert!(operation.is_source_transform());
+}
+
+#[test]
+fn watermark_keeps_the_estimated_size() {
+    let config = PipelineConfig {
+        layers: vec![PipelineLayer {
+            id: "mark".into(),
+            operation: PipelineOperation::Watermark { options: WatermarkOptions::text("© Ana") },
+            enabled: true,
+            order: 0,
+            condition: None,
+        }],
+        ..Default::default()
+    };
+    let estimate = pipeline_estimate(1000, config, None);
+    assert_eq!(estimate["estimated_final_size"], 1000);
+    assert_eq!(estimate["operations"][0]["operation"], "Watermark \"© Ana\"");
+}
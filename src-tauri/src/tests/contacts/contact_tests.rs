@@ -0,0 +1,91 @@
+//! Contact Book Tests
+//!
+//! Tests for:
+//! - Public bundle fingerprints and derived key ids
+//! - Adding, verifying and removing contacts
+//! - Encrypted save/load roundtrip
+
+use crate::contacts::{fingerprints_match, ContactBook};
+use crate::crypto::{HybridKeypair, PublicBundle};
+use crate::tests::common::temp_dir;
+
+fn bundle() -> PublicBundle {
+    HybridKeypair::generate().expect("keypair generation").public_bundle()
+}
+
+#[test]
+fn fingerprint_is_stable_and_grouped() {
+    let b = bundle();
+    let fp = b.fingerprint();
+    assert_eq!(fp, b.clone().fingerprint());
+    assert_eq!(fp.len(), 39);
+    assert_eq!(fp.split(' ').count(), 8);
+    assert_ne!(fp, bundle().fingerprint());
+    assert_eq!(b.derived_key_id(), b.key_id);
+}
+
+#[test]
+fn fingerprint_comparison_ignores_formatting() {
+    assert!(fingerprints_match("ABCD 1234", "abcd-1234"));
+    assert!(fingerprints_match("ABCD 1234", "abcd1234"));
+    assert!(!fingerprints_match("ABCD 1234", "ABCD 1235"));
+    assert!(!fingerprints_match("ABCD 1234", "  "));
+}
+
+#[test]
+fn add_recomputes_key_id_and_rejects_duplicates() {
+    let mut book = ContactBook::default();
+    let mut b = bundle();
+    let real_id = b.key_id.clone();
+    b.key_id = "spoofed".into();
+
+    let contact = book.add("  Alice ", b.clone()).unwrap();
+    assert_eq!(contact.name, "Alice");
+    assert_eq!(contact.bundle.key_id, real_id);
+    assert!(!contact.verified);
+
+    assert!(book.add("Alice again", b).is_err());
+    assert!(book.add("", bundle()).is_err());
+}
+
+#[test]
+fn verify_requires_matching_fingerprint() {
+    let mut book = ContactBook::default();
+    let b = bundle();
+    let id = book.add("Bob", b.clone()).unwrap().id.clone();
+
+    assert!(book.verify(&id, &bundle().fingerprint()).is_err());
+    assert!(!book.get(&id).unwrap().verified);
+
+    let contact = book.verify(&id, &b.fingerprint().to_lowercase()).unwrap();
+    assert!(contact.verified);
+    assert!(contact.verified_at.is_some());
+
+    assert!(book.verify("missing", &b.fingerprint()).is_err());
+    book.remove(&id).unwrap();
+    assert!(book.remove(&id).is_err());
+}
+
+#[test]
+fn book_roundtrips_encrypted() {
+    let path = temp_dir("contacts", "roundtrip").join("contacts.bin");
+    let mut book = ContactBook::default();
+    book.add("Carol", bundle()).unwrap();
+    book.save_to(&path).unwrap();
+
+    let raw = std::fs::read(&path).unwrap();
+    assert!(!raw.windows(5).any(|w| w == b"Carol"));
+
+    let loaded = ContactBook::load_from(&path).unwrap();
+    assert_eq!(loaded.contacts.len(), 1);
+    assert_eq!(loaded.contacts[0].name, "Carol");
+
+    // Tampering is detected
+    let mut tampered = raw;
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    std::fs::write(&path, tampered).unwrap();
+    assert!(ContactBook::load_from(&path).is_err());
+
+    assert!(ContactBook::load_from(&path.with_file_name("missing.bin")).unwrap().contacts.is_empty());
+}
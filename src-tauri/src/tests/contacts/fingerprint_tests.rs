@@ -0,0 +1,76 @@
+//! Fingerprint Word Tests
+//!
+//! Tests for:
+//! - Encoding fingerprints as alternating word lists and back
+//! - Catching swapped, dropped and unknown words
+//! - Comparing read-back fingerprints and verifying contacts with words
+
+use crate::contacts::ContactBook;
+use crate::crypto::{HybridKeypair, PublicBundle};
+use crate::fingerprint::{compare, from_words, key_fingerprint, parse_fingerprint, to_words};
+
+fn bundle() -> PublicBundle {
+    HybridKeypair::generate().expect("keypair generation").public_bundle()
+}
+
+#[test]
+fn every_byte_round_trips_at_both_parities() {
+    let bytes: Vec<u8> = (0..=255u8).flat_map(|b| [b, b]).collect();
+    let words = to_words(&bytes);
+    assert_eq!(words[..4], ["aardvark", "adroitness", "absurd", "adviser"]);
+    assert_eq!(from_words(&words.join(" ")).unwrap(), bytes);
+    // Even and odd words never coincide
+    assert!((0..256).all(|i| words[2 * i] != words[2 * i + 1]));
+}
+
+#[test]
+fn misread_words_are_caught() {
+    let words = to_words(&[0x00, 0x01, 0x02, 0x03]);
+    assert_eq!(from_words("AARDVARK-adviser, accrue.  Aggregate").unwrap(), vec![0, 1, 2, 3]);
+
+    // Swapped neighbours land on the wrong lists
+    let swapped = format!("{} {} {} {}", words[1], words[0], words[2], words[3]);
+    assert!(from_words(&swapped).is_err());
+    // A dropped word shifts the rest onto the wrong lists
+    let dropped = format!("{} {} {}", words[0], words[2], words[3]);
+    assert!(from_words(&dropped).is_err());
+    assert!(from_words("aardvark giraffe").is_err());
+}
+
+#[test]
+fn fingerprints_compare_as_hex_or_words() {
+    let b = bundle();
+    let fp = key_fingerprint(&b);
+    assert_eq!(fp.words.len(), 16);
+    assert_eq!(fp.hex, b.fingerprint());
+    assert_eq!(parse_fingerprint(&fp.hex).unwrap(), parse_fingerprint(&fp.words.join(" ")).unwrap());
+
+    assert!(compare(&b, &fp.hex.to_lowercase()).unwrap().matches);
+    assert!(compare(&b, &fp.words.join(" ")).unwrap().matches);
+
+    let mut wrong = fp.words.clone();
+    wrong[5] = to_words(&[0; 6])[5].clone();
+    if wrong[5] == fp.words[5] {
+        wrong[5] = to_words(&[1; 6])[5].clone();
+    }
+    let result = compare(&b, &wrong.join(" ")).unwrap();
+    assert!(!result.matches);
+    assert_eq!(result.mismatches, vec![5]);
+
+    // Too short is a mismatch at the missing positions
+    let short = compare(&b, &fp.words[..14].join(" ")).unwrap();
+    assert_eq!(short.mismatches, vec![14, 15]);
+    assert!(compare(&b, "").is_err());
+}
+
+#[test]
+fn contacts_verify_with_words() {
+    let b = bundle();
+    let mut book = ContactBook::default();
+    let id = book.add("Alice", b.clone()).unwrap().id.clone();
+    let words = key_fingerprint(&bundle()).words.join(" ");
+    assert!(book.verify(&id, &words).is_err());
+
+    let words = key_fingerprint(&b).words.join(" ");
+    assert!(book.verify(&id, &words).unwrap().verified);
+}
@@ -0,0 +1,8 @@
+//! Contact Book Tests
+//!
+//! Organized by functionality:
+//! - `contact_tests` - Fingerprints, verification and encrypted persistence
+//! - `fingerprint_tests` - Fingerprint words and read-back comparison
+
+pub mod contact_tests;
+pub mod fingerprint_tests;
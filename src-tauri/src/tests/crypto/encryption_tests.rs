@@ -4,11 +4,12 @@
 //! - Hybrid PQ + classical encryption roundtrip
 //! - Associated Authenticated Data (AAD) binding
 //! - Password-based encryption
+//! - Album key derivation and symmetric encryption
 //! - Edge cases (empty data, large data)
 
 use crate::crypto::{
-    decrypt, decrypt_with_aad, decrypt_with_password, encrypt, encrypt_with_aad,
-    encrypt_with_password, HybridKeypair, KeypairStore,
+    decrypt, decrypt_with_aad, decrypt_with_key, decrypt_with_password, encrypt,
+    encrypt_with_aad, encrypt_with_key, encrypt_with_password, HybridKeypair, KeypairStore,
 };
 
 // ============================================================================
@@ -293,3 +294,52 @@ fn password_encryption_rejects_too_short_data() {
     let result = decrypt_with_password(&short_data, password);
     assert!(result.is_err(), "too short data should fail");
 }
+
+// ============================================================================
+// Album Key Tests
+// ============================================================================
+
+#[test]
+fn album_key_is_deterministic_per_album() {
+    let keypair = HybridKeypair::generate().expect("keypair generation");
+
+    let key_a1 = keypair.derive_album_key("owner/repo:photos/trip").expect("derive");
+    let key_a2 = keypair.derive_album_key("owner/repo:photos/trip").expect("derive");
+    let key_b = keypair.derive_album_key("owner/repo:photos/family").expect("derive");
+
+    assert_eq!(key_a1.as_bytes(), key_a2.as_bytes(), "same album should derive same key");
+    assert_ne!(key_a1.as_bytes(), key_b.as_bytes(), "different albums should derive different keys");
+}
+
+#[test]
+fn album_key_differs_between_keypairs() {
+    let keypair1 = HybridKeypair::generate().expect("keypair 1");
+    let keypair2 = HybridKeypair::generate().expect("keypair 2");
+
+    let key1 = keypair1.derive_album_key("owner/repo:photos/trip").expect("derive");
+    let key2 = keypair2.derive_album_key("owner/repo:photos/trip").expect("derive");
+
+    assert_ne!(key1.as_bytes(), key2.as_bytes());
+}
+
+#[test]
+fn symmetric_key_encryption_roundtrip() {
+    let key = [7u8; 32];
+    let data = b"album photo bytes";
+    let aad = b"owner/repo:photos/trip";
+
+    let encrypted = encrypt_with_key(data, &key, aad).expect("encryption");
+    let decrypted = decrypt_with_key(&encrypted, &key, aad).expect("decryption");
+
+    assert_eq!(decrypted, data);
+}
+
+#[test]
+fn symmetric_key_encryption_binds_aad() {
+    let key = [7u8; 32];
+    let encrypted = encrypt_with_key(b"photo", &key, b"album-a").expect("encryption");
+
+    assert!(decrypt_with_key(&encrypted, &key, b"album-b").is_err(), "wrong AAD should fail");
+    assert!(decrypt_with_key(&encrypted, &[8u8; 32], b"album-a").is_err(), "wrong key should fail");
+    assert!(decrypt_with_key(&encrypted[..20], &key, b"album-a").is_err(), "short data should fail");
+}
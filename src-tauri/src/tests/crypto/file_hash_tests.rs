@@ -0,0 +1,155 @@
+//! File Hash Tests
+//!
+//! Tests for:
+//! - Whole-file BLAKE3 from mapped and streamed reads
+//! - Per-chunk hash trees and the verified prefix
+//! - Progress, cancellation and chunk size limits
+
+use std::io::Cursor;
+use std::sync::atomic::AtomicBool;
+
+use crate::file_hash::{
+    hash_file, hash_reader, tree_chunk_size, verified_prefix, DEFAULT_TREE_CHUNK, MAX_TREE_CHUNK, MIN_TREE_CHUNK,
+};
+use crate::jobs::is_cancelled_error;
+use crate::tests::common::{temp_dir, temp_file};
+
+fn sample(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+fn blake3_hex(data: &[u8]) -> String {
+    blake3::hash(data).to_hex().to_string()
+}
+
+// ============================================================================
+// Whole File Tests
+// ============================================================================
+
+#[test]
+fn mapped_file_matches_in_memory_hash() {
+    let data = sample(300_000);
+    let path = temp_file("hash", "mapped.bin", &data);
+    let hashed = hash_file(&path, None, &AtomicBool::new(false), |_| {}).unwrap();
+    assert_eq!(hashed.hash, blake3_hex(&data));
+    assert_eq!(hashed.size, data.len() as u64);
+    assert!(hashed.tree.is_none());
+}
+
+#[test]
+fn streamed_reader_matches_in_memory_hash() {
+    let data = sample(300_000);
+    let hashed = hash_reader(Cursor::new(&data), Some(MIN_TREE_CHUNK), &AtomicBool::new(false), |_| {}).unwrap();
+    assert_eq!(hashed.hash, blake3_hex(&data));
+    assert!(!hashed.memory_mapped);
+}
+
+#[test]
+fn empty_file_hashes_like_empty_input() {
+    let path = temp_file("hash", "empty.bin", b"");
+    let hashed = hash_file(&path, Some(MIN_TREE_CHUNK), &AtomicBool::new(false), |_| {}).unwrap();
+    assert_eq!(hashed.hash, blake3_hex(b""));
+    assert_eq!(hashed.size, 0);
+    assert!(hashed.tree.unwrap().chunks.is_empty());
+}
+
+#[test]
+fn missing_file_is_an_error() {
+    let path = temp_dir("hash", "missing").join("missing.bin");
+    assert!(hash_file(&path, None, &AtomicBool::new(false), |_| {}).is_err());
+}
+
+// ============================================================================
+// Tree Tests
+// ============================================================================
+
+#[test]
+fn tree_leaves_hash_each_chunk() {
+    let data = sample(MIN_TREE_CHUNK as usize * 3 + 1000);
+    let path = temp_file("hash", "tree.bin", &data);
+    let hashed = hash_file(&path, Some(MIN_TREE_CHUNK), &AtomicBool::new(false), |_| {}).unwrap();
+    let tree = hashed.tree.unwrap();
+
+    assert_eq!(tree.chunk_size, MIN_TREE_CHUNK);
+    assert_eq!(tree.chunks.len(), 4);
+    for (chunk, expected) in tree.chunks.iter().zip(data.chunks(MIN_TREE_CHUNK as usize)) {
+        assert_eq!(chunk.hash, blake3_hex(expected));
+        assert_eq!(chunk.length, expected.len() as u64);
+    }
+    assert_eq!(tree.chunks[3].offset, MIN_TREE_CHUNK * 3);
+    assert_eq!(tree.chunks[3].length, 1000);
+
+    let leaves: Vec<u8> = data.chunks(MIN_TREE_CHUNK as usize).flat_map(|c| *blake3::hash(c).as_bytes()).collect();
+    assert_eq!(tree.root, blake3_hex(&leaves));
+    assert_eq!(hashed.hash, blake3_hex(&data));
+}
+
+#[test]
+fn mapped_and_streamed_trees_agree() {
+    let data = sample(MIN_TREE_CHUNK as usize * 2 + 17);
+    let path = temp_file("hash", "agree.bin", &data);
+    let mapped = hash_file(&path, Some(MIN_TREE_CHUNK), &AtomicBool::new(false), |_| {}).unwrap();
+    let streamed = hash_reader(Cursor::new(&data), Some(MIN_TREE_CHUNK), &AtomicBool::new(false), |_| {}).unwrap();
+    assert_eq!(mapped.tree, streamed.tree);
+    assert_eq!(mapped.hash, streamed.hash);
+}
+
+#[test]
+fn verified_prefix_stops_at_first_changed_chunk() {
+    let data = sample(MIN_TREE_CHUNK as usize * 4);
+    let tree_of = |data: &[u8]| {
+        hash_reader(Cursor::new(data), Some(MIN_TREE_CHUNK), &AtomicBool::new(false), |_| {})
+            .unwrap()
+            .tree
+            .unwrap()
+    };
+    let expected = tree_of(&data);
+    assert_eq!(verified_prefix(&expected, &expected), data.len() as u64);
+
+    let mut changed = data.clone();
+    changed[MIN_TREE_CHUNK as usize * 2 + 5] ^= 0xff;
+    assert_eq!(verified_prefix(&expected, &tree_of(&changed)), MIN_TREE_CHUNK * 2);
+
+    // An upload cut short matches up to where it stopped
+    let partial = &data[..MIN_TREE_CHUNK as usize + 10];
+    assert_eq!(verified_prefix(&expected, &tree_of(partial)), MIN_TREE_CHUNK);
+
+    let other_size = hash_reader(Cursor::new(&data), Some(MIN_TREE_CHUNK * 2), &AtomicBool::new(false), |_| {})
+        .unwrap()
+        .tree
+        .unwrap();
+    assert_eq!(verified_prefix(&expected, &other_size), 0);
+}
+
+#[test]
+fn tree_chunk_size_is_checked() {
+    assert_eq!(tree_chunk_size(false, Some(1)).unwrap(), None);
+    assert_eq!(tree_chunk_size(true, None).unwrap(), Some(DEFAULT_TREE_CHUNK));
+    assert_eq!(tree_chunk_size(true, Some(MIN_TREE_CHUNK)).unwrap(), Some(MIN_TREE_CHUNK));
+    assert!(tree_chunk_size(true, Some(MIN_TREE_CHUNK - 1)).is_err());
+    assert!(tree_chunk_size(true, Some(MAX_TREE_CHUNK + 1)).is_err());
+}
+
+// ============================================================================
+// Progress and Cancellation Tests
+// ============================================================================
+
+#[test]
+fn progress_reaches_the_file_size() {
+    let data = sample(MIN_TREE_CHUNK as usize * 3);
+    let path = temp_file("hash", "progress.bin", &data);
+    let mut reports = Vec::new();
+    hash_file(&path, Some(MIN_TREE_CHUNK), &AtomicBool::new(false), |done| reports.push(done)).unwrap();
+    assert_eq!(reports, vec![MIN_TREE_CHUNK, MIN_TREE_CHUNK * 2, MIN_TREE_CHUNK * 3]);
+}
+
+#[test]
+fn cancelled_hash_fails_as_cancelled() {
+    let data = sample(MIN_TREE_CHUNK as usize * 2);
+    let path = temp_file("hash", "cancelled.bin", &data);
+    let err = hash_file(&path, Some(MIN_TREE_CHUNK), &AtomicBool::new(true), |_| {}).unwrap_err();
+    assert!(is_cancelled_error(&err));
+
+    let err = hash_reader(Cursor::new(&data), None, &AtomicBool::new(true), |_| {}).unwrap_err();
+    assert!(is_cancelled_error(&err));
+}
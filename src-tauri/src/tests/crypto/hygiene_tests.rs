@@ -0,0 +1,77 @@
+//! Secret Hygiene Tests
+//!
+//! Tests for:
+//! - Live and zeroized counts per secret kind
+//! - Keypair serialization returning a secret wrapper
+//! - Deserializing command arguments into secrets
+
+use crate::crypto::{HybridKeypair, SecretBytes, SecretKey32};
+use crate::hygiene::{hygiene_report, SecretKindReport};
+
+fn kind_report(kind: &str) -> Option<SecretKindReport> {
+    hygiene_report().kinds.into_iter().find(|k| k.kind == kind)
+}
+
+#[test]
+fn secrets_are_counted_while_live_and_when_wiped() {
+    let kind = "test secret bytes";
+    let secret = SecretBytes::labeled(kind, vec![7u8; 48]);
+    let live = kind_report(kind).expect("kind reported");
+    assert_eq!(live.live, 1);
+    assert_eq!(live.live_bytes, 48);
+    assert_eq!(live.memory_locked + live.lock_failures, 1);
+    assert_eq!(live.memory_locked == 1, secret.is_locked());
+
+    drop(secret);
+    let wiped = kind_report(kind).expect("kind reported");
+    assert_eq!(wiped.live, 0);
+    assert_eq!(wiped.live_bytes, 0);
+    assert_eq!(wiped.memory_locked, 0);
+    assert_eq!(wiped.zeroized, 1);
+}
+
+#[test]
+fn fixed_size_keys_are_counted() {
+    let kind = "test secret key32";
+    let key = SecretKey32::labeled(kind, [9u8; 32]);
+    assert_eq!(key.as_bytes(), &[9u8; 32]);
+    let copy = key.clone_secret();
+    assert_eq!(kind_report(kind).unwrap().live, 2);
+
+    drop(key);
+    drop(copy);
+    let report = kind_report(kind).unwrap();
+    assert_eq!(report.live, 0);
+    assert_eq!(report.zeroized, 2);
+}
+
+#[test]
+fn empty_secrets_are_not_lock_failures() {
+    let kind = "test empty secret";
+    let empty = SecretBytes::labeled(kind, Vec::new());
+    assert!(!empty.is_locked());
+    assert_eq!(kind_report(kind).unwrap().lock_failures, 0);
+}
+
+#[test]
+fn serialized_keypair_is_a_secret() {
+    let keypair = HybridKeypair::generate().expect("keypair generation");
+    let bytes: SecretBytes = keypair.to_bytes();
+    // The buffer is sized up front and never reallocates
+    let restored = HybridKeypair::from_bytes(&bytes).expect("deserialization");
+    assert_eq!(restored.public_bundle(), keypair.public_bundle());
+}
+
+#[test]
+fn command_arguments_deserialize_into_secrets() {
+    let secret: SecretBytes = serde_json::from_str("[1,2,3]").unwrap();
+    assert_eq!(secret.as_slice(), &[1, 2, 3]);
+    assert!(kind_report("command argument").is_some());
+}
+
+#[test]
+fn report_lists_zeroize_only_holders() {
+    let report = hygiene_report();
+    assert!(!report.zeroized_only.is_empty());
+    assert!(report.zeroized_only.iter().all(|s| s.holder.starts_with("Zeroizing")));
+}
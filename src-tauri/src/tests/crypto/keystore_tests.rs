@@ -0,0 +1,141 @@
+//! Keystore Tests
+//!
+//! Tests for:
+//! - Encrypted file store round-trips
+//! - Falling back along the store chain and deleting from every store
+//! - Sealing a keypair with a key encryption key held in the keystore
+
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::crypto::{generate_keypair, release_keypair, with_keypair, CryptoError};
+use crate::keystore::{open_keypair, seal_keypair, EncryptedFileStore, Keystore, KeystoreBackend, SecretStore};
+use crate::tests::common::temp_dir;
+
+/// An OS keystore that is locked: every write fails
+struct LockedStore;
+
+impl SecretStore for LockedStore {
+    fn backend(&self) -> KeystoreBackend {
+        KeystoreBackend::SecretService
+    }
+
+    fn store(&self, _name: &str, _value: &str) -> Result<(), CryptoError> {
+        Err(CryptoError::Keychain("locked".into()))
+    }
+
+    fn retrieve(&self, _name: &str) -> Result<Option<Zeroizing<String>>, CryptoError> {
+        Err(CryptoError::Keychain("locked".into()))
+    }
+
+    fn delete(&self, _name: &str) -> Result<bool, CryptoError> {
+        Ok(false)
+    }
+}
+
+/// An in-memory stand-in for a working OS keystore
+#[derive(Default)]
+struct MemoryStore(Mutex<Vec<(String, String)>>);
+
+impl SecretStore for MemoryStore {
+    fn backend(&self) -> KeystoreBackend {
+        KeystoreBackend::MacosKeychain
+    }
+
+    fn store(&self, name: &str, value: &str) -> Result<(), CryptoError> {
+        let mut entries = self.0.lock().unwrap();
+        entries.retain(|(n, _)| n != name);
+        entries.push((name.to_string(), value.to_string()));
+        Ok(())
+    }
+
+    fn retrieve(&self, name: &str) -> Result<Option<Zeroizing<String>>, CryptoError> {
+        let entries = self.0.lock().unwrap();
+        Ok(entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| Zeroizing::new(v.clone())))
+    }
+
+    fn delete(&self, name: &str) -> Result<bool, CryptoError> {
+        let mut entries = self.0.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|(n, _)| n != name);
+        Ok(entries.len() != before)
+    }
+}
+
+#[test]
+fn file_store_round_trips() {
+    let dir = temp_dir("keystore", "file");
+    let store = EncryptedFileStore::new(dir.clone());
+
+    assert!(store.retrieve("github_token").unwrap().is_none());
+    store.store("github_token", "ghp_secret").unwrap();
+    assert_eq!(store.retrieve("github_token").unwrap().unwrap().as_str(), "ghp_secret");
+
+    // Only ciphertext reaches the disk
+    let raw = std::fs::read(dir.join("github_token")).unwrap();
+    assert!(!raw.windows(10).any(|w| w == b"ghp_secret"));
+
+    assert!(store.delete("github_token").unwrap());
+    assert!(!store.delete("github_token").unwrap());
+    assert!(store.retrieve("github_token").unwrap().is_none());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn chain_falls_back_and_deletes_everywhere() {
+    let dir = temp_dir("keystore", "chain");
+    let keystore = Keystore::new(vec![Box::new(LockedStore), Box::new(EncryptedFileStore::new(dir.clone()))]);
+    assert_eq!(keystore.backend(), KeystoreBackend::SecretService);
+    assert_eq!(keystore.store("token", "value").unwrap(), KeystoreBackend::EncryptedFile);
+    assert_eq!(keystore.retrieve("token").unwrap().unwrap().as_str(), "value");
+
+    // A secret written before the OS keystore became available is still found
+    let keystore = Keystore::new(vec![
+        Box::new(MemoryStore::default()),
+        Box::new(EncryptedFileStore::new(dir.clone())),
+    ]);
+    assert_eq!(keystore.retrieve("token").unwrap().unwrap().as_str(), "value");
+    assert_eq!(keystore.store("token", "newer").unwrap(), KeystoreBackend::MacosKeychain);
+    assert_eq!(keystore.retrieve("token").unwrap().unwrap().as_str(), "newer");
+
+    assert!(keystore.delete("token").unwrap());
+    assert!(keystore.retrieve("token").unwrap().is_none());
+    assert!(!dir.join("token").exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn secret_names_are_validated() {
+    let keystore = Keystore::new(vec![Box::new(MemoryStore::default())]);
+    for name in ["", "../escape", ".hidden", "a/b", "with space"] {
+        assert!(keystore.store(name, "x").is_err(), "{:?}", name);
+        assert!(keystore.retrieve(name).is_err(), "{:?}", name);
+    }
+    assert!(keystore.store("github_token.v2-backup", "x").is_ok());
+    assert!(keystore.store(&"a".repeat(129), "x").is_err());
+}
+
+#[test]
+fn keypair_seals_with_a_keystore_kek() {
+    let kp = generate_keypair().unwrap();
+    let keystore = Keystore::new(vec![Box::new(MemoryStore::default())]);
+    let sealed = with_keypair(kp.handle, |keypair| seal_keypair(&keystore, keypair)).unwrap();
+
+    let opened = open_keypair(&keystore, &sealed).unwrap().unwrap();
+    assert_eq!(opened.public_bundle(), kp.public_bundle);
+
+    let mut tampered = sealed.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(open_keypair(&keystore, &tampered).is_err());
+
+    // Without the KEK the sealed file is useless
+    let empty = Keystore::new(vec![Box::new(MemoryStore::default())]);
+    assert!(open_keypair(&empty, &sealed).unwrap().is_none());
+
+    release_keypair(kp.handle).unwrap();
+}
@@ -0,0 +1,112 @@
+//! Local Vault Tests
+//!
+//! Tests for:
+//! - Outer and hidden volumes opened by different passwords
+//! - Protecting hidden blocks while writing to the outer volume
+//! - Copy-on-write updates surviving a reopen
+//! - Adding a hidden volume to an existing vault
+
+use std::path::PathBuf;
+
+use crate::crypto::KdfParams;
+use crate::local_vault::{add_hidden, create, LocalVault, BLOCK_PAYLOAD};
+
+/// Cheap costs so the tests do not spend their time in Argon2
+const FAST: KdfParams = KdfParams {
+    memory_kib: 64,
+    iterations: 1,
+    parallelism: 1,
+};
+
+fn vault_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("vortex-local-vault-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn album_names(vault: &LocalVault) -> Vec<String> {
+    vault.albums().into_iter().map(|a| a.name).collect()
+}
+
+#[test]
+fn passwords_open_separate_volumes() {
+    let path = vault_path("separate");
+    create(&path, 8, FAST, b"outer", Some(b"hidden")).unwrap();
+    assert!(create(&path, 8, FAST, b"outer", None).is_err());
+
+    let mut outer = LocalVault::unlock(&path, b"outer", None).unwrap();
+    outer.add_photo("Holiday", "beach.jpg", b"beach").unwrap();
+    let mut hidden = LocalVault::unlock(&path, b"hidden", None).unwrap();
+    hidden.add_photo("Private", "secret.jpg", b"secret").unwrap();
+
+    let outer = LocalVault::unlock(&path, b"outer", None).unwrap();
+    let hidden = LocalVault::unlock(&path, b"hidden", None).unwrap();
+    assert_eq!(album_names(&outer), ["Holiday"]);
+    assert_eq!(album_names(&hidden), ["Private"]);
+    assert_eq!(hidden.read_photo("Private", "secret.jpg").unwrap(), b"secret");
+    assert!(outer.read_photo("Private", "secret.jpg").is_err());
+    assert!(LocalVault::unlock(&path, b"guess", None).is_err());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn protected_outer_writes_leave_hidden_data_alone() {
+    let path = vault_path("protect");
+    create(&path, 6, FAST, b"outer", Some(b"hidden")).unwrap();
+    let mut hidden = LocalVault::unlock(&path, b"hidden", None).unwrap();
+    hidden.add_photo("Private", "a.jpg", &vec![7u8; BLOCK_PAYLOAD + 1]).unwrap();
+
+    // Outer index + hidden index + two hidden data blocks are taken
+    let mut outer = LocalVault::unlock(&path, b"outer", Some(b"hidden")).unwrap();
+    assert_eq!(outer.free_blocks(), 2);
+    assert!(LocalVault::unlock(&path, b"outer", Some(b"outer")).is_err());
+    assert!(outer.add_photo("Holiday", "big.jpg", &vec![1u8; 2 * BLOCK_PAYLOAD]).is_err());
+    outer.add_photo("Holiday", "small.jpg", b"small").unwrap();
+
+    let hidden = LocalVault::unlock(&path, b"hidden", None).unwrap();
+    assert_eq!(hidden.read_photo("Private", "a.jpg").unwrap(), vec![7u8; BLOCK_PAYLOAD + 1]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn updates_survive_reopen() {
+    let path = vault_path("reopen");
+    create(&path, 8, FAST, b"outer", None).unwrap();
+    let len = std::fs::metadata(&path).unwrap().len();
+
+    let mut vault = LocalVault::unlock(&path, b"outer", None).unwrap();
+    vault.add_photo("Trip", "one.jpg", b"one").unwrap();
+    vault.add_photo("Trip", "two.jpg", b"two").unwrap();
+    assert!(vault.add_photo("Trip", "two.jpg", b"again").is_err());
+    vault.remove_photo("Trip", "one.jpg").unwrap();
+    // The container never grows, so its size says nothing about its contents
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+
+    let reopened = LocalVault::unlock(&path, b"outer", None).unwrap();
+    assert_eq!(reopened.albums()[0].photos.len(), 1);
+    assert_eq!(reopened.read_photo("Trip", "two.jpg").unwrap(), b"two");
+    assert!(reopened.read_photo("Trip", "one.jpg").is_err());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn hidden_volume_can_be_added_later() {
+    let path = vault_path("later");
+    create(&path, 8, FAST, b"outer", None).unwrap();
+    let mut outer = LocalVault::unlock(&path, b"outer", None).unwrap();
+    outer.add_photo("Holiday", "beach.jpg", b"beach").unwrap();
+
+    assert!(add_hidden(&path, b"outer", b"outer").is_err());
+    assert!(add_hidden(&path, b"wrong", b"hidden").is_err());
+    add_hidden(&path, b"outer", b"hidden").unwrap();
+
+    let hidden = LocalVault::unlock(&path, b"hidden", None).unwrap();
+    assert!(hidden.albums().is_empty());
+    let outer = LocalVault::unlock(&path, b"outer", None).unwrap();
+    assert_eq!(outer.read_photo("Holiday", "beach.jpg").unwrap(), b"beach");
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,75 @@
+//! Mnemonic Backup Tests
+//!
+//! Tests for:
+//! - Rebuilding a keypair from its recovery phrase and backup
+//! - Rejecting wrong phrases, tampered backups and malformed phrases
+//! - Deterministic backup lookup paths
+
+use bip39::Mnemonic;
+
+use crate::crypto::{decrypt, encrypt};
+use crate::recovery::{backup_path, keypair_from_mnemonic, keypair_from_new_mnemonic, parse_mnemonic, RECOVERY_ROOT};
+
+fn mnemonic(byte: u8) -> Mnemonic {
+    Mnemonic::from_entropy(&[byte; 32]).unwrap()
+}
+
+#[test]
+fn phrase_and_backup_restore_the_same_keypair() {
+    let phrase = mnemonic(7);
+    let (original, backup) = keypair_from_new_mnemonic(&phrase).unwrap();
+    let restored = keypair_from_mnemonic(&phrase, &backup).unwrap();
+
+    assert_eq!(restored.public_bundle(), original.public_bundle());
+    assert_eq!(backup.key_id, original.public_bundle().key_id);
+
+    // Data sealed to the original opens with the restored keypair
+    let payload = encrypt(b"family photos", &original.public_bundle()).unwrap();
+    assert_eq!(decrypt(&payload, &restored).unwrap(), b"family photos");
+}
+
+#[test]
+fn classical_keys_follow_the_phrase() {
+    let (a, _) = keypair_from_new_mnemonic(&mnemonic(1)).unwrap();
+    let (b, _) = keypair_from_new_mnemonic(&mnemonic(1)).unwrap();
+    let (c, _) = keypair_from_new_mnemonic(&mnemonic(2)).unwrap();
+
+    assert_eq!(a.ed_verifying_key, b.ed_verifying_key);
+    assert_eq!(a.x25519_public, b.x25519_public);
+    assert_ne!(a.ed_verifying_key, c.ed_verifying_key);
+    assert_eq!(backup_path(&mnemonic(1)), backup_path(&mnemonic(1)));
+    assert_ne!(backup_path(&mnemonic(1)), backup_path(&mnemonic(2)));
+    assert!(backup_path(&mnemonic(1)).starts_with(RECOVERY_ROOT));
+}
+
+#[test]
+fn wrong_phrase_or_tampered_backup_is_rejected() {
+    let (_, backup) = keypair_from_new_mnemonic(&mnemonic(3)).unwrap();
+    assert!(keypair_from_mnemonic(&mnemonic(4), &backup).is_err());
+
+    let mut tampered = backup.clone();
+    tampered.key_id = "0000000000000000".into();
+    assert!(keypair_from_mnemonic(&mnemonic(3), &tampered).is_err());
+
+    let mut future = backup;
+    future.version = 99;
+    assert!(keypair_from_mnemonic(&mnemonic(3), &future).is_err());
+}
+
+#[test]
+fn phrases_are_normalized_and_checked() {
+    let phrase = mnemonic(5).to_string();
+    let messy = format!("  {}\n", phrase.to_uppercase().replace(' ', "   "));
+    assert_eq!(parse_mnemonic(&messy).unwrap().to_string(), phrase);
+
+    // Bad checksum: swap the last word
+    let mut words: Vec<&str> = phrase.split(' ').collect();
+    let last = if words[23] == "zoo" { "abandon" } else { "zoo" };
+    words[23] = last;
+    assert!(parse_mnemonic(&words.join(" ")).is_err());
+
+    // Valid 12-word phrases are too short
+    let short = Mnemonic::from_entropy(&[5u8; 16]).unwrap().to_string();
+    assert!(parse_mnemonic(&short).is_err());
+    assert!(parse_mnemonic("").is_err());
+}
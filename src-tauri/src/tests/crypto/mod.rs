@@ -6,9 +6,35 @@
 //! - `signature_tests` - Signing and verification
 //! - `token_tests` - Token encryption, versioning, migration
 //! - `property_tests` - Property-based tests with proptest
+//! - `rotation_tests` - Re-wrapping albums and messages on keypair rotation
+//! - `mnemonic_tests` - Recovery phrase backup and restore
+//! - `shamir_tests` - Shamir recovery shares of a keypair
+//! - `stream_tests` - Chunked streaming file encryption
+//! - `keystore_tests` - OS keystore chain and keypair persistence
+//! - `password_tests` - Password strength and recorded KDF parameters
+//! - `session_tests` - Ratcheting sessions between two users
+//! - `timelock_tests` - Time-locked capsules by escrow or sequential work
+//! - `local_vault_tests` - Local vault with outer and hidden volumes
+//! - `hygiene_tests` - Secret wrappers and the hygiene report
+//! - `qr_escrow_tests` - Keypair transfer through QR code parts
+//! - `file_hash_tests` - Streamed and memory-mapped BLAKE3 with hash trees
+//! - `secure_temp_tests` - Plaintext temp files, wiping and purging
 
 pub mod keypair_tests;
 pub mod encryption_tests;
 pub mod signature_tests;
 pub mod token_tests;
 pub mod property_tests;
+pub mod rotation_tests;
+pub mod mnemonic_tests;
+pub mod shamir_tests;
+pub mod stream_tests;
+pub mod keystore_tests;
+pub mod password_tests;
+pub mod session_tests;
+pub mod timelock_tests;
+pub mod local_vault_tests;
+pub mod hygiene_tests;
+pub mod qr_escrow_tests;
+pub mod file_hash_tests;
+pub mod secure_temp_tests;
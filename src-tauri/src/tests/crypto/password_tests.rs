@@ -0,0 +1,117 @@
+//! Password Strength and KDF Parameter Tests
+//!
+//! Tests for:
+//! - Strength scoring and rejecting weak passwords
+//! - KDF costs recorded in and authenticated by the password header
+//! - Decrypting data written before the header existed
+//! - Rejecting out-of-range costs from crafted headers
+//! - Saving and reloading the calibrated costs
+
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit, Nonce};
+
+use crate::crypto::{
+    decrypt_with_password, encrypt_data_password, encrypt_with_password_params, CryptoError,
+    KdfParams,
+};
+use crate::password::{estimate_strength, load_calibration_in, save_calibration_in, MIN_PASSWORD_SCORE};
+use crate::tests::common::temp_dir;
+
+/// Cheap costs so the tests do not spend their time in Argon2
+const FAST: KdfParams = KdfParams {
+    memory_kib: 64,
+    iterations: 1,
+    parallelism: 1,
+};
+
+#[test]
+fn weak_passwords_score_low() {
+    let weak = estimate_strength("password", &[]);
+    assert!(weak.score < MIN_PASSWORD_SCORE);
+    assert!(!weak.acceptable);
+    assert!(weak.warning.is_some() || !weak.suggestions.is_empty());
+
+    let strong = estimate_strength("glacier-Trombone-47-umbrella-quartz", &[]);
+    assert_eq!(strong.score, 4);
+    assert!(strong.acceptable);
+    assert!(strong.guesses_log10 > weak.guesses_log10);
+
+    // Words the attacker knows about the user count against the password
+    let personal = estimate_strength("vortexalbums2024", &["vortexalbums"]);
+    assert!(personal.score < estimate_strength("vortexalbums2024", &[]).score);
+}
+
+#[test]
+fn encrypt_command_rejects_weak_passwords() {
+    let err = encrypt_data_password(b"data".to_vec(), "123456".into()).unwrap_err();
+    assert!(matches!(err, CryptoError::InvalidInput(ref msg) if msg.contains("too weak")));
+}
+
+#[test]
+fn header_records_kdf_params() {
+    let sealed = encrypt_with_password_params(b"album key", b"pw", FAST).unwrap();
+    assert_eq!(&sealed[..4], b"VXPW");
+    assert_eq!(sealed[4], 2);
+    assert_eq!(decrypt_with_password(&sealed, b"pw").unwrap(), b"album key");
+    assert!(decrypt_with_password(&sealed, b"other").is_err());
+
+    // The costs are authenticated: changing them breaks decryption
+    let mut edited = sealed.clone();
+    edited[9] ^= 1;
+    assert!(decrypt_with_password(&edited, b"pw").is_err());
+}
+
+#[test]
+fn crafted_costs_are_refused() {
+    let huge = KdfParams {
+        memory_kib: u32::MAX,
+        ..FAST
+    };
+    assert!(encrypt_with_password_params(b"x", b"pw", huge).is_err());
+
+    let mut sealed = encrypt_with_password_params(b"x", b"pw", FAST).unwrap();
+    sealed[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = decrypt_with_password(&sealed, b"pw").unwrap_err();
+    assert!(matches!(err, CryptoError::InvalidInput(ref msg) if msg.contains("out of range")));
+}
+
+#[test]
+fn headerless_data_still_decrypts() {
+    // [salt: 16][nonce: 12][ciphertext] with the floor costs
+    let salt = [7u8; 16];
+    let nonce = [9u8; 12];
+    let key = KdfParams::FLOOR.derive(b"pw", &salt).unwrap();
+    let ciphertext = ChaCha20Poly1305::new(&key.into())
+        .encrypt(Nonce::from_slice(&nonce), b"legacy".as_slice())
+        .unwrap();
+
+    let mut legacy = salt.to_vec();
+    legacy.extend_from_slice(&nonce);
+    legacy.extend_from_slice(&ciphertext);
+    assert_eq!(decrypt_with_password(&legacy, b"pw").unwrap(), b"legacy");
+}
+
+#[test]
+fn calibration_survives_a_restart() {
+    let dir = temp_dir("password", "calibration");
+    assert_eq!(load_calibration_in(&dir), None);
+
+    let calibrated = KdfParams {
+        memory_kib: 128 * 1024,
+        iterations: 4,
+        ..KdfParams::FLOOR
+    };
+    save_calibration_in(&dir, calibrated).unwrap();
+    assert_eq!(load_calibration_in(&dir), Some(calibrated));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn saved_costs_below_the_floor_are_ignored() {
+    let dir = temp_dir("password", "below-floor");
+    save_calibration_in(&dir, FAST).unwrap();
+    assert_eq!(load_calibration_in(&dir), None);
+
+    std::fs::write(dir.join("kdf-params.json"), b"not json").unwrap();
+    assert_eq!(load_calibration_in(&dir), None);
+    let _ = std::fs::remove_dir_all(&dir);
+}
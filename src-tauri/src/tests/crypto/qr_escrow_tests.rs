@@ -0,0 +1,80 @@
+//! QR Keypair Transfer Tests
+//!
+//! Tests for:
+//! - Splitting a payload into checksummed parts and reassembling it
+//! - Scanning parts out of order and twice
+//! - Rejecting misread parts, missing parts and mixed transfers
+
+use crate::qr_escrow::{decode_part, split_into_parts, QrAssembler, DEFAULT_PART_BYTES};
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+#[test]
+fn parts_reassemble_in_any_order() {
+    let data = payload(5000);
+    let (_, parts) = split_into_parts(&data, DEFAULT_PART_BYTES).unwrap();
+    assert_eq!(parts.len(), 5);
+
+    let mut assembler = QrAssembler::default();
+    for part in parts.iter().rev() {
+        assembler.add(part).unwrap();
+    }
+    // A rescan of a part already seen changes nothing
+    assembler.add(&parts[2]).unwrap();
+    assert!(assembler.missing().is_empty());
+    assert_eq!(assembler.finish().unwrap(), data);
+}
+
+#[test]
+fn parts_carry_index_total_and_transfer_id() {
+    let (transfer_id, parts) = split_into_parts(&payload(250), 100).unwrap();
+    assert_eq!(parts.len(), 3);
+    for (i, part) in parts.iter().enumerate() {
+        let decoded = decode_part(part).unwrap();
+        assert_eq!(decoded.index as usize, i + 1);
+        assert_eq!(decoded.total, 3);
+        assert_eq!(hex::encode(decoded.transfer_id), transfer_id);
+    }
+    assert_eq!(decode_part(&parts[2]).unwrap().data.len(), 50);
+}
+
+#[test]
+fn misread_parts_fail_their_checksum() {
+    let (_, parts) = split_into_parts(&payload(300), 100).unwrap();
+    let mut corrupted = parts[0].clone().into_bytes();
+    let last = corrupted.len() - 3;
+    corrupted[last] = if corrupted[last] == b'A' { b'B' } else { b'A' };
+    let corrupted = String::from_utf8(corrupted).unwrap();
+    assert!(decode_part(&corrupted).is_err());
+    assert!(decode_part("vortex-share-1:AAAA").is_err());
+}
+
+#[test]
+fn missing_parts_are_reported() {
+    let (_, parts) = split_into_parts(&payload(450), 100).unwrap();
+    let mut assembler = QrAssembler::default();
+    assembler.add(&parts[0]).unwrap();
+    assembler.add(&parts[3]).unwrap();
+    assert_eq!(assembler.missing(), vec![2, 3, 5]);
+    assert!(assembler.finish().is_err());
+    assert!(QrAssembler::default().finish().is_err());
+}
+
+#[test]
+fn parts_of_another_transfer_are_rejected() {
+    let (_, first) = split_into_parts(&payload(300), 100).unwrap();
+    let (_, second) = split_into_parts(&payload(300), 100).unwrap();
+    let mut assembler = QrAssembler::default();
+    assembler.add(&first[0]).unwrap();
+    assert!(assembler.add(&second[1]).is_err());
+}
+
+#[test]
+fn part_size_is_bounded() {
+    assert!(split_into_parts(&payload(10), 10).is_err());
+    assert!(split_into_parts(&payload(10), 5000).is_err());
+    // Too many codes for the one-byte part counter
+    assert!(split_into_parts(&payload(300 * 100), 100).is_err());
+}
@@ -0,0 +1,133 @@
+//! Key Rotation Tests
+//!
+//! Tests for:
+//! - Moving owned and shared albums to a rotated keypair
+//! - Re-encrypting secure messages and thread envelopes
+//! - Machine-key sealing used by the local key archive
+
+use crate::album::{album_key_for, owner_album_key, AlbumManifest};
+use crate::album_access::grant_access;
+use crate::crypto::{
+    decrypt, encrypt, generate_keypair, install_rotated_keypair, open_with_machine_key, release_keypair,
+    seal_with_machine_key, with_keypair, EncryptedPayload, HybridKeypair, KeypairInfo,
+};
+use crate::key_rotation::{rewrap_manifest, rewrap_secure_message};
+use crate::sharing::album_id;
+use crate::threads::{open_message, rewrap_envelope, seal_message, ThreadIndex};
+
+const REPO: &str = "alice/photos";
+const ALBUM: &str = "photos/Trips";
+
+fn keypair() -> KeypairInfo {
+    generate_keypair().expect("keypair generation")
+}
+
+#[test]
+fn owned_album_keeps_its_key_after_rotation() {
+    let owner = keypair();
+    let id = album_id(REPO, ALBUM);
+    let mut manifest = AlbumManifest::new(true, Some(owner.key_id.clone()));
+    let key = owner_album_key(owner.handle, &id, 0).unwrap();
+
+    let new_keypair = HybridKeypair::generate().unwrap();
+    let new_bundle = new_keypair.public_bundle();
+    let (owned, granted) = rewrap_manifest(owner.handle, REPO, ALBUM, &mut manifest, &new_bundle).unwrap();
+    assert!(owned && !granted);
+    assert_eq!(manifest.owner_key_id.as_deref(), Some(new_bundle.key_id.as_str()));
+    assert!(manifest.owner_key.is_some());
+
+    install_rotated_keypair(owner.handle, new_keypair).unwrap();
+    // The album key no longer derives from the current keypair, it is unwrapped
+    assert_eq!(album_key_for(owner.handle, REPO, ALBUM, &manifest).unwrap(), key);
+    assert_ne!(owner_album_key(owner.handle, &id, 0).unwrap(), key);
+
+    release_keypair(owner.handle).unwrap();
+}
+
+#[test]
+fn grant_moves_to_the_rotated_key() {
+    let owner = keypair();
+    let friend = keypair();
+    let id = album_id(REPO, ALBUM);
+    let mut manifest = AlbumManifest::new(true, Some(owner.key_id.clone()));
+    let key = owner_album_key(owner.handle, &id, 0).unwrap();
+    grant_access(&mut manifest, &key, &id, friend.public_bundle.clone()).unwrap();
+
+    let new_keypair = HybridKeypair::generate().unwrap();
+    let new_bundle = new_keypair.public_bundle();
+    let (owned, granted) = rewrap_manifest(friend.handle, REPO, ALBUM, &mut manifest, &new_bundle).unwrap();
+    assert!(!owned && granted);
+    assert!(!manifest.access.contains_key(&friend.key_id));
+    assert!(manifest.access.contains_key(&new_bundle.key_id));
+
+    install_rotated_keypair(friend.handle, new_keypair).unwrap();
+    assert_eq!(album_key_for(friend.handle, REPO, ALBUM, &manifest).unwrap(), key);
+
+    // Albums the key has nothing to do with are left alone
+    let stranger = keypair();
+    let before = serde_json::to_string(&manifest).unwrap();
+    let other = HybridKeypair::generate().unwrap().public_bundle();
+    assert_eq!(
+        rewrap_manifest(stranger.handle, REPO, ALBUM, &mut manifest, &other).unwrap(),
+        (false, false)
+    );
+    assert_eq!(serde_json::to_string(&manifest).unwrap(), before);
+
+    for kp in [owner, friend, stranger] {
+        release_keypair(kp.handle).unwrap();
+    }
+}
+
+#[test]
+fn secure_messages_are_reencrypted_for_the_new_key() {
+    let me = keypair();
+    let other = keypair();
+    let raw = serde_json::to_vec(&encrypt(b"hello", &me.public_bundle).unwrap()).unwrap();
+    let foreign = serde_json::to_vec(&encrypt(b"hello", &other.public_bundle).unwrap()).unwrap();
+
+    let new_keypair = HybridKeypair::generate().unwrap();
+    let new_bundle = new_keypair.public_bundle();
+    let rewrapped = rewrap_secure_message(me.handle, &raw, &new_bundle).unwrap().expect("rewrapped");
+    assert!(rewrap_secure_message(me.handle, &foreign, &new_bundle).unwrap().is_none());
+    assert!(rewrap_secure_message(me.handle, b"not json", &new_bundle).unwrap().is_none());
+
+    install_rotated_keypair(me.handle, new_keypair).unwrap();
+    let payload: EncryptedPayload = serde_json::from_slice(&rewrapped).unwrap();
+    assert_eq!(with_keypair(me.handle, |kp| decrypt(&payload, kp)).unwrap(), b"hello");
+
+    release_keypair(me.handle).unwrap();
+    release_keypair(other.handle).unwrap();
+}
+
+#[test]
+fn thread_messages_survive_a_rotation() {
+    let alice = keypair();
+    let bob = keypair();
+    let mut index = ThreadIndex::new("0123abcd", alice.public_bundle.clone(), vec![bob.public_bundle.clone()]).unwrap();
+    let mut envelope = seal_message(bob.handle, &index, 1, 10, b"before rotation").unwrap();
+
+    let new_keypair = HybridKeypair::generate().unwrap();
+    let new_bundle = new_keypair.public_bundle();
+    assert!(rewrap_envelope(bob.handle, &mut envelope, &new_bundle).unwrap());
+    assert!(index.rotate_participant(&bob.key_id, new_bundle.clone()));
+    install_rotated_keypair(bob.handle, new_keypair).unwrap();
+
+    // Bob reads with the new key; the old signature still verifies
+    let message = open_message(bob.handle, &index, &envelope).unwrap();
+    assert_eq!(message.content, "before rotation");
+    assert!(message.verified);
+    assert!(open_message(alice.handle, &index, &envelope).unwrap().verified);
+
+    release_keypair(alice.handle).unwrap();
+    release_keypair(bob.handle).unwrap();
+}
+
+#[test]
+fn machine_key_sealing_round_trips() {
+    let sealed = seal_with_machine_key(b"archived key", b"aad").unwrap();
+    assert_eq!(open_with_machine_key(&sealed, b"aad").unwrap(), b"archived key");
+    assert!(open_with_machine_key(&sealed, b"other").is_err());
+    assert!(open_with_machine_key(&sealed[..20], b"aad").is_err());
+    // Fresh salt every time
+    assert_ne!(seal_with_machine_key(b"archived key", b"aad").unwrap(), sealed);
+}
@@ -0,0 +1,126 @@
+//! Plaintext Temp File Tests
+//!
+//! Tests for:
+//! - Choosing a RAM-backed folder or the wiped disk fallback
+//! - Writing private plaintext files
+//! - Wiping files and purging tracked files and leftovers
+
+use std::path::Path;
+
+use crate::secure_temp::{choose_dir_in, purge_in, wipe_file, write_plaintext_in, PlaintextDir};
+use crate::tests::common::temp_dir;
+
+fn disk_dir(root: &Path) -> PlaintextDir {
+    choose_dir_in(&[], "unused", &root.join("disk")).unwrap()
+}
+
+// ============================================================================
+// Folder Tests
+// ============================================================================
+
+#[test]
+fn ram_root_is_preferred() {
+    let root = temp_dir("plaintext", "ram");
+    let ram = root.join("shm");
+    std::fs::create_dir_all(&ram).unwrap();
+
+    let dir = choose_dir_in(&[root.join("missing"), ram.clone()], "vortex-x", &root.join("disk")).unwrap();
+    assert_eq!(
+        dir,
+        PlaintextDir {
+            path: ram.join("vortex-x"),
+            ram_backed: true
+        }
+    );
+    assert!(dir.path.is_dir());
+    assert!(!root.join("disk").exists());
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn disk_is_the_fallback() {
+    let root = temp_dir("plaintext", "fallback");
+    let dir = choose_dir_in(&[root.join("missing")], "vortex-x", &root.join("disk")).unwrap();
+    assert_eq!(
+        dir,
+        PlaintextDir {
+            path: root.join("disk"),
+            ram_backed: false
+        }
+    );
+    assert!(dir.path.is_dir());
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[cfg(unix)]
+#[test]
+fn folder_and_files_are_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = temp_dir("plaintext", "private");
+    let dir = disk_dir(&root);
+    let file = write_plaintext_in(&dir, "repo:a.jpg", "a.jpg", b"plain").unwrap();
+
+    let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&dir.path), 0o700);
+    assert_eq!(mode(Path::new(&file.path)), 0o600);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+// ============================================================================
+// File Tests
+// ============================================================================
+
+#[test]
+fn plaintext_is_written_under_a_fresh_name() {
+    let root = temp_dir("plaintext", "write");
+    let dir = disk_dir(&root);
+    let a = write_plaintext_in(&dir, "repo:x/a.jpg", "../a b.jpg", b"first").unwrap();
+    let b = write_plaintext_in(&dir, "repo:x/a.jpg", "../a b.jpg", b"second").unwrap();
+
+    assert_ne!(a.id, b.id);
+    assert!(a.id.starts_with("pt-"));
+    assert!(a.path.ends_with("-_ab.jpg"));
+    assert!(Path::new(&a.path).starts_with(&dir.path));
+    assert_eq!(std::fs::read(&a.path).unwrap(), b"first");
+    assert_eq!(a.size, 5);
+    assert_eq!(a.source, "repo:x/a.jpg");
+    assert!(!a.ram_backed);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn wiping_overwrites_before_removal() {
+    let root = temp_dir("plaintext", "wipe");
+    let path = root.join("secret");
+    std::fs::write(&path, vec![7u8; 100_000]).unwrap();
+    assert_eq!(wipe_file(&path, true).unwrap(), 100_000);
+    assert!(!path.exists());
+
+    std::fs::write(&path, b"ram").unwrap();
+    assert_eq!(wipe_file(&path, false).unwrap(), 0);
+    assert!(!path.exists());
+    assert!(wipe_file(&path, true).is_err());
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn purge_takes_tracked_files_and_leftovers() {
+    let root = temp_dir("plaintext", "purge");
+    let dir = disk_dir(&root);
+    let tracked = write_plaintext_in(&dir, "repo:a.jpg", "a.jpg", b"aaaa").unwrap();
+    let leftover = write_plaintext_in(&dir, "repo:b.jpg", "b.jpg", b"bb").unwrap();
+    let gone = write_plaintext_in(&dir, "repo:c.jpg", "c.jpg", b"c").unwrap();
+    std::fs::remove_file(&gone.path).unwrap();
+    std::fs::write(dir.path.join("not-ours"), b"keep").unwrap();
+
+    let report = purge_in(&dir, vec![tracked.clone(), gone]);
+    assert_eq!(report.removed, 2);
+    assert_eq!(report.leftovers, 1);
+    assert_eq!(report.wiped_bytes, 6);
+    assert!(report.failed.is_empty());
+    assert!(!Path::new(&tracked.path).exists());
+    assert!(!Path::new(&leftover.path).exists());
+    assert!(dir.path.join("not-ours").exists());
+    let _ = std::fs::remove_dir_all(&root);
+}
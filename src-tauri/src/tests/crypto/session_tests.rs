@@ -0,0 +1,104 @@
+//! Forward-Secret Session Tests
+//!
+//! Tests for:
+//! - Handshake between initiator and responder
+//! - Fresh keys per message in both directions
+//! - Out-of-order delivery, replays and forged messages
+//! - Rejecting tampered or misaddressed handshakes
+
+use crate::crypto::HybridKeypair;
+use crate::session::{accept, initiate, RatchetSession};
+
+fn pair() -> (RatchetSession, RatchetSession) {
+    let alice = HybridKeypair::generate().unwrap();
+    let bob = HybridKeypair::generate().unwrap();
+    let (initiator, handshake) = initiate(&alice, &bob.public_bundle()).unwrap();
+    let responder = accept(&bob, &handshake).unwrap();
+    (initiator, responder)
+}
+
+#[test]
+fn handshake_opens_both_sides() {
+    let (mut alice, mut bob) = pair();
+    assert_eq!(alice.id(), bob.id());
+    assert!(alice.info().initiator && !bob.info().initiator);
+
+    let hello = alice.encrypt(b"hello bob").unwrap();
+    assert_eq!(bob.decrypt(&hello).unwrap(), b"hello bob");
+    let reply = bob.encrypt(b"hi alice").unwrap();
+    assert_eq!(alice.decrypt(&reply).unwrap(), b"hi alice");
+
+    // A side cannot read its own messages: each direction has its own chain
+    let own = alice.encrypt(b"note").unwrap();
+    assert!(alice.decrypt(&own).is_err());
+}
+
+#[test]
+fn every_message_uses_a_fresh_key() {
+    let (mut alice, mut bob) = pair();
+    let first = alice.encrypt(b"same").unwrap();
+    let second = alice.encrypt(b"same").unwrap();
+    assert_eq!((first.counter, second.counter), (0, 1));
+
+    // The ciphertext of one position does not open at another
+    let mut moved = second.clone();
+    moved.ciphertext = first.ciphertext.clone();
+    assert!(bob.decrypt(&moved).is_err());
+
+    assert_eq!(bob.decrypt(&second).unwrap(), b"same");
+    assert_eq!(bob.decrypt(&first).unwrap(), b"same");
+    assert_eq!(bob.info().received, 2);
+}
+
+#[test]
+fn messages_decrypt_once_in_any_order() {
+    let (mut alice, mut bob) = pair();
+    let messages: Vec<_> = (0..4).map(|i| alice.encrypt(format!("m{}", i).as_bytes()).unwrap()).collect();
+
+    assert_eq!(bob.decrypt(&messages[3]).unwrap(), b"m3");
+    assert_eq!(bob.decrypt(&messages[1]).unwrap(), b"m1");
+    // Keys are deleted after use, so a replay fails
+    assert!(bob.decrypt(&messages[1]).is_err());
+    assert!(bob.decrypt(&messages[3]).is_err());
+    assert_eq!(bob.decrypt(&messages[0]).unwrap(), b"m0");
+    assert_eq!(bob.decrypt(&messages[2]).unwrap(), b"m2");
+}
+
+#[test]
+fn forged_messages_do_not_advance_the_chain() {
+    let (mut alice, mut bob) = pair();
+    let real = alice.encrypt(b"real").unwrap();
+
+    let mut forged = real.clone();
+    forged.counter = 500;
+    assert!(bob.decrypt(&forged).is_err());
+    forged.counter = 5000;
+    assert!(bob.decrypt(&forged).is_err());
+    assert_eq!(bob.info().received, 0);
+
+    let mut tampered = real.clone();
+    *tampered.ciphertext.last_mut().unwrap() ^= 1;
+    assert!(bob.decrypt(&tampered).is_err());
+    assert_eq!(bob.decrypt(&real).unwrap(), b"real");
+}
+
+#[test]
+fn tampered_or_misaddressed_handshakes_are_refused() {
+    let alice = HybridKeypair::generate().unwrap();
+    let bob = HybridKeypair::generate().unwrap();
+    let eve = HybridKeypair::generate().unwrap();
+    let (_, handshake) = initiate(&alice, &bob.public_bundle()).unwrap();
+
+    assert!(accept(&eve, &handshake).is_err());
+
+    let mut swapped = handshake.clone();
+    swapped.ephemeral[0] ^= 1;
+    assert!(accept(&bob, &swapped).is_err());
+
+    // Eve cannot pass off Alice's handshake as her own
+    let mut impersonated = handshake.clone();
+    impersonated.initiator = eve.public_bundle();
+    assert!(accept(&bob, &impersonated).is_err());
+
+    assert!(accept(&bob, &handshake).is_ok());
+}
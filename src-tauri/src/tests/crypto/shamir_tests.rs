@@ -0,0 +1,82 @@
+//! Shamir Key Share Tests
+//!
+//! Tests for:
+//! - Recovering from any threshold-sized subset of shares
+//! - Rejecting too few, mixed or damaged shares
+//! - Share encoding and keypair round-trips through the store
+
+use crate::crypto::{generate_keypair, release_keypair, with_keypair};
+use crate::shamir::{
+    combine_shares, decode_share, encode_share, recover_key_from_shares, split_key_shares, split_secret,
+};
+
+const SECRET: &[u8] = b"correct horse battery staple";
+
+#[test]
+fn any_threshold_subset_recovers_the_secret() {
+    let shares = split_secret(SECRET, 3, 5).unwrap();
+    assert_eq!(shares.len(), 5);
+
+    for a in 0..5 {
+        for b in a + 1..5 {
+            for c in b + 1..5 {
+                let subset = [shares[a].clone(), shares[b].clone(), shares[c].clone()];
+                assert_eq!(combine_shares(&subset).unwrap().as_slice(), SECRET);
+            }
+        }
+    }
+    // More than enough is fine too
+    assert_eq!(combine_shares(&shares).unwrap().as_slice(), SECRET);
+}
+
+#[test]
+fn too_few_or_mixed_shares_fail() {
+    let shares = split_secret(SECRET, 3, 5).unwrap();
+    assert!(combine_shares(&shares[..2]).is_err());
+    // Duplicates do not count twice
+    assert!(combine_shares(&[shares[0].clone(), shares[0].clone(), shares[1].clone()]).is_err());
+
+    let other = split_secret(SECRET, 3, 5).unwrap();
+    assert_ne!(other[0].set_id, shares[0].set_id);
+    assert!(combine_shares(&[shares[0].clone(), shares[1].clone(), other[2].clone()]).is_err());
+
+    let mut damaged = shares[2].clone();
+    damaged.data[20] ^= 0x01;
+    assert!(combine_shares(&[shares[0].clone(), shares[1].clone(), damaged]).is_err());
+}
+
+#[test]
+fn split_parameters_are_validated() {
+    assert!(split_secret(SECRET, 1, 3).is_err());
+    assert!(split_secret(SECRET, 4, 3).is_err());
+    assert!(split_secret(b"", 2, 3).is_err());
+    assert!(split_secret(SECRET, 255, 255).is_ok());
+}
+
+#[test]
+fn shares_survive_encoding() {
+    let shares = split_secret(SECRET, 2, 3).unwrap();
+    let encoded = encode_share(&shares[1]);
+    assert!(encoded.starts_with("vortex-share-1:"));
+    assert_eq!(decode_share(&format!("  {}\n", encoded)).unwrap(), shares[1]);
+
+    assert!(decode_share("vortex-share-1:AAAA").is_err());
+    assert!(decode_share("something else").is_err());
+}
+
+#[test]
+fn keypair_round_trips_through_shares() {
+    let original = generate_keypair().unwrap();
+    let shares = split_key_shares(original.handle, 2, 3).unwrap();
+    assert!(shares.iter().all(|s| s.key_id == original.key_id && s.total == 3));
+
+    let recovered = recover_key_from_shares(vec![shares[2].share.clone(), shares[0].share.clone()]).unwrap();
+    assert_eq!(recovered.public_bundle, original.public_bundle);
+    let signature = with_keypair(recovered.handle, |kp| kp.sign(b"check")).unwrap();
+    assert!(!signature.is_empty());
+
+    assert!(recover_key_from_shares(vec![shares[1].share.clone()]).is_err());
+
+    release_keypair(original.handle).unwrap();
+    release_keypair(recovered.handle).unwrap();
+}
@@ -5,8 +5,9 @@
 //! - Signature verification
 //! - Tamper detection
 //! - Cross-keypair verification failure
+//! - Batch signing and verification
 
-use crate::crypto::HybridKeypair;
+use crate::crypto::{sign_all, verify_all, BatchVerifyItem, HybridKeypair};
 
 // ============================================================================
 // Basic Signature Tests
@@ -203,3 +204,60 @@ fn signature_deterministic_for_same_input() {
     bundle.verify(data, &sig1).expect("sig1 should verify");
     bundle.verify(data, &sig2).expect("sig2 should verify");
 }
+
+// ============================================================================
+// Batch Tests
+// ============================================================================
+
+#[test]
+fn batch_sign_and_verify_keep_input_order() {
+    let keypair = HybridKeypair::generate().expect("keypair generation");
+    let bundle = keypair.public_bundle();
+    let items: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 64]).collect();
+
+    let signed = sign_all(&keypair, &items);
+    assert_eq!(signed.len(), items.len());
+    let mut checks: Vec<BatchVerifyItem> = signed
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            assert_eq!(r.index, i);
+            BatchVerifyItem {
+                data: items[i].clone(),
+                signature: r.signature.clone().expect("signature"),
+                public_bundle: None,
+            }
+        })
+        .collect();
+    checks[3].data[0] ^= 1;
+
+    let results = verify_all(&checks, Some(&bundle));
+    let valid: Vec<bool> = results.iter().map(|r| r.valid).collect();
+    assert_eq!(valid, [true, true, true, false, true, true, true, true]);
+    assert!(results.iter().all(|r| r.error.is_none()));
+}
+
+#[test]
+fn batch_verify_uses_per_item_bundles() {
+    let alice = HybridKeypair::generate().expect("keypair generation");
+    let bob = HybridKeypair::generate().expect("keypair generation");
+    let item = |kp: &HybridKeypair, bundle| BatchVerifyItem {
+        data: b"photo".to_vec(),
+        signature: kp.sign(b"photo").expect("signing"),
+        public_bundle: bundle,
+    };
+
+    let checks = vec![
+        item(&alice, None),
+        item(&bob, Some(bob.public_bundle())),
+        item(&bob, None),
+    ];
+    let results = verify_all(&checks, Some(&alice.public_bundle()));
+    assert!(results[0].valid && results[1].valid);
+    assert!(!results[2].valid);
+
+    // Without a default, items lacking their own bundle are reported, not failed
+    let results = verify_all(&checks, None);
+    assert!(results[0].error.is_some());
+    assert!(results[1].valid);
+}
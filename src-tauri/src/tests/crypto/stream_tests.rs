@@ -0,0 +1,235 @@
+//! Streaming File Encryption Tests
+//!
+//! Tests for:
+//! - Chunked round-trips with passwords and keypairs
+//! - Detecting tampered, reordered, truncated and extended files
+//! - Resuming an interrupted decryption
+//! - KDF costs in password headers, and version 1 files
+//! - Partial output names
+
+use std::io::Cursor;
+use std::path::Path;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+use crate::crypto::{current_kdf_params, generate_keypair, release_keypair, KdfParams, KeypairInfo};
+use crate::file_crypto::{
+    decrypt_stream, encrypt_partial_path, encrypt_stream, resumable_chunks, stream_key, StreamHeader, StreamKey,
+};
+
+const CHUNK: u32 = 1024;
+const TAG: usize = 16;
+
+fn data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+fn password() -> StreamKey {
+    StreamKey::Password(zeroize::Zeroizing::new(b"hunter2".to_vec()))
+}
+
+fn seal(plain: &[u8], key: &StreamKey) -> Vec<u8> {
+    let mut out = Vec::new();
+    encrypt_stream(&mut Cursor::new(plain), &mut out, key, CHUNK, plain.len() as u64, |_| {}).unwrap();
+    out
+}
+
+fn recipient(kp: &KeypairInfo) -> StreamKey {
+    StreamKey::Recipient(kp.public_bundle.clone())
+}
+
+fn open(sealed: &[u8], kp: &KeypairInfo) -> Result<Vec<u8>, crate::github::AppError> {
+    let mut reader = Cursor::new(sealed);
+    let header = StreamHeader::read_from(&mut reader)?;
+    let key = stream_key(&header, None, Some(kp.handle))?;
+    let mut out = Vec::new();
+    decrypt_stream(&mut reader, &mut out, &header, &key, 0, |_| {})?;
+    Ok(out)
+}
+
+#[test]
+fn password_streams_round_trip() {
+    let plain = data(CHUNK as usize * 2 + 7);
+    let sealed = seal(&plain, &password());
+    let mut reader = Cursor::new(&sealed);
+    let header = StreamHeader::read_from(&mut reader).unwrap();
+    assert!(stream_key(&header, None, None).is_err());
+    assert!(stream_key(&header, Some(b"wrong"), None)
+        .and_then(|key| decrypt_stream(&mut reader.clone(), &mut Vec::new(), &header, &key, 0, |_| {}))
+        .is_err());
+
+    let key = stream_key(&header, Some(b"hunter2"), None).unwrap();
+    let mut out = Vec::new();
+    decrypt_stream(&mut reader, &mut out, &header, &key, 0, |_| {}).unwrap();
+    assert_eq!(out, plain);
+}
+
+#[test]
+fn chunk_boundaries_round_trip() {
+    let kp = generate_keypair().unwrap();
+    for len in [0, 1, CHUNK as usize, CHUNK as usize * 3, CHUNK as usize * 3 + 7] {
+        let plain = data(len);
+        assert_eq!(open(&seal(&plain, &recipient(&kp)), &kp).unwrap(), plain, "length {}", len);
+    }
+    release_keypair(kp.handle).unwrap();
+}
+
+#[test]
+fn keypair_streams_round_trip() {
+    let owner = generate_keypair().unwrap();
+    let stranger = generate_keypair().unwrap();
+    let plain = data(CHUNK as usize * 2 + 100);
+    let sealed = seal(&plain, &StreamKey::Recipient(owner.public_bundle.clone()));
+
+    let mut reader = Cursor::new(&sealed);
+    let header = StreamHeader::read_from(&mut reader).unwrap();
+    assert_eq!(header.chunk_count(), 3);
+    assert!(stream_key(&header, None, Some(stranger.handle)).is_err());
+    let key = stream_key(&header, None, Some(owner.handle)).unwrap();
+
+    let mut progress = Vec::new();
+    let mut out = Vec::new();
+    decrypt_stream(&mut reader, &mut out, &header, &key, 0, |done| progress.push(done)).unwrap();
+    assert_eq!(out, plain);
+    assert_eq!(progress, vec![1024, 2048, plain.len() as u64]);
+
+    release_keypair(owner.handle).unwrap();
+    release_keypair(stranger.handle).unwrap();
+}
+
+#[test]
+fn tampering_is_detected() {
+    let kp = generate_keypair().unwrap();
+    let plain = data(CHUNK as usize * 3);
+    let sealed = seal(&plain, &recipient(&kp));
+    let header_len = StreamHeader::read_from(&mut Cursor::new(&sealed)).unwrap().len as usize;
+    let chunk = CHUNK as usize + TAG;
+
+    let mut flipped = sealed.clone();
+    flipped[header_len + chunk + 5] ^= 1;
+    assert!(open(&flipped, &kp).is_err());
+
+    // Swap the first two chunks
+    let mut swapped = sealed[..header_len].to_vec();
+    swapped.extend_from_slice(&sealed[header_len + chunk..header_len + 2 * chunk]);
+    swapped.extend_from_slice(&sealed[header_len..header_len + chunk]);
+    swapped.extend_from_slice(&sealed[header_len + 2 * chunk..]);
+    assert!(open(&swapped, &kp).is_err());
+
+    // Drop the last chunk
+    assert!(open(&sealed[..sealed.len() - chunk], &kp).is_err());
+
+    let mut extended = sealed.clone();
+    extended.push(0);
+    assert!(open(&extended, &kp).is_err());
+
+    // The header is authenticated through every chunk
+    let mut header_edit = sealed.clone();
+    header_edit[21] ^= 1;
+    assert!(open(&header_edit, &kp).is_err());
+
+    assert!(open(b"plain old jpeg bytes that are long enough", &kp).is_err());
+    assert_eq!(open(&sealed, &kp).unwrap(), plain);
+
+    release_keypair(kp.handle).unwrap();
+}
+
+#[test]
+fn decryption_resumes_from_whole_chunks() {
+    let kp = generate_keypair().unwrap();
+    let plain = data(CHUNK as usize * 4 + 10);
+    let sealed = seal(&plain, &recipient(&kp));
+    let mut reader = Cursor::new(&sealed);
+    let header = StreamHeader::read_from(&mut reader).unwrap();
+    let key = stream_key(&header, None, Some(kp.handle)).unwrap();
+
+    // An interrupted run left two and a half chunks behind
+    let partial_len = CHUNK as u64 * 2 + 500;
+    let start = resumable_chunks(&header, partial_len);
+    assert_eq!(start, 2);
+
+    let mut out = plain[..start as usize * CHUNK as usize].to_vec();
+    decrypt_stream(&mut reader, &mut out, &header, &key, start, |_| {}).unwrap();
+    assert_eq!(out, plain);
+
+    // A complete partial still re-checks the last chunk
+    assert_eq!(resumable_chunks(&header, plain.len() as u64), header.chunk_count() - 1);
+    assert!(decrypt_stream(&mut reader, &mut Vec::new(), &header, &key, header.chunk_count(), |_| {}).is_err());
+
+    release_keypair(kp.handle).unwrap();
+}
+
+// ============================================================================
+// Key Derivation Tests
+// ============================================================================
+
+#[test]
+fn password_headers_record_the_kdf_costs() {
+    let sealed = seal(b"costs", &password());
+    let header = StreamHeader::read_from(&mut Cursor::new(&sealed)).unwrap();
+    assert_eq!(header.version, 2);
+    assert_eq!(header.key_block.len(), KdfParams::ENCODED_LEN + 16);
+    assert_eq!(header.key_block[..KdfParams::ENCODED_LEN], current_kdf_params().to_bytes());
+}
+
+#[test]
+fn crafted_kdf_costs_are_rejected() {
+    let mut sealed = seal(b"costs", &password());
+    // memory_kib is the first field of the key block, after the 33-byte fixed header
+    sealed[33..37].copy_from_slice(&u32::MAX.to_le_bytes());
+    let header = StreamHeader::read_from(&mut Cursor::new(&sealed)).unwrap();
+    assert!(stream_key(&header, Some(b"hunter2"), None).is_err());
+}
+
+#[test]
+fn version_1_password_streams_use_the_floor_costs() {
+    // [magic][version 1][password][chunk size][prefix][len][key block len][salt]
+    let plain = b"legacy stream";
+    let salt = [3u8; 16];
+    let prefix = [5u8; 7];
+    let mut header = b"VXSTREAM".to_vec();
+    header.extend_from_slice(&[1, 1]);
+    header.extend_from_slice(&CHUNK.to_le_bytes());
+    header.extend_from_slice(&prefix);
+    header.extend_from_slice(&(plain.len() as u64).to_le_bytes());
+    header.extend_from_slice(&(salt.len() as u32).to_le_bytes());
+    header.extend_from_slice(&salt);
+
+    let key = KdfParams::FLOOR.derive(b"hunter2", &salt).unwrap();
+    let mut nonce = [0u8; 12];
+    nonce[..7].copy_from_slice(&prefix);
+    nonce[11] = 1;
+    let mut aad = blake3::hash(&header).as_bytes().to_vec();
+    aad.extend_from_slice(&[0u8; TAG]);
+    let chunk = ChaCha20Poly1305::new(&key.into())
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plain, aad: &aad })
+        .unwrap();
+    let mut sealed = header;
+    sealed.extend_from_slice(&chunk);
+
+    let mut reader = Cursor::new(&sealed);
+    let header = StreamHeader::read_from(&mut reader).unwrap();
+    assert_eq!(header.version, 1);
+    let key = stream_key(&header, Some(b"hunter2"), None).unwrap();
+    let mut out = Vec::new();
+    decrypt_stream(&mut reader, &mut out, &header, &key, 0, |_| {}).unwrap();
+    assert_eq!(out, plain);
+}
+
+// ============================================================================
+// Partial Output Tests
+// ============================================================================
+
+#[test]
+fn encryption_partials_do_not_collide() {
+    let jpg = encrypt_partial_path(Path::new("/out/photo.jpg"));
+    let png = encrypt_partial_path(Path::new("/out/photo.png"));
+    let again = encrypt_partial_path(Path::new("/out/photo.jpg"));
+
+    assert_eq!(jpg.parent(), Some(Path::new("/out")));
+    assert!(jpg.file_name().unwrap().to_str().unwrap().starts_with("photo.jpg."));
+    assert_eq!(jpg.extension().unwrap(), "part");
+    assert_ne!(jpg, png);
+    assert_ne!(jpg, again);
+}
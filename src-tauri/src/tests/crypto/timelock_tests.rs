@@ -0,0 +1,108 @@
+//! Time-Lock Tests
+//!
+//! Tests for:
+//! - Opening a capsule by sequential work
+//! - Escrow passphrases and the unlock date
+//! - Capsule encoding and tamper detection
+//! - Refusing oversized puzzles and cancelling the work
+
+use std::sync::atomic::AtomicBool;
+
+use crate::timelock::{puzzle_lock, seal, solve_puzzle, TimelockCapsule, TimelockLock};
+
+/// Small enough for tests to generate and solve quickly
+const TEST_BITS: usize = 512;
+
+fn running() -> AtomicBool {
+    AtomicBool::new(false)
+}
+
+#[test]
+fn puzzle_key_is_recovered_by_squaring() {
+    let key = [42u8; 32];
+    let lock = puzzle_lock(&key, 2000, TEST_BITS).unwrap();
+
+    let mut reports = Vec::new();
+    let solved = solve_puzzle(&lock, &running(), |done, total| reports.push((done, total))).unwrap();
+    assert_eq!(*solved, key);
+    assert_eq!(reports.last(), Some(&(2000, 2000)));
+
+    // One squaring short gives the wrong key
+    let TimelockLock::Puzzle { modulus, base, wrapped_key, .. } = lock else {
+        unreachable!()
+    };
+    let short = TimelockLock::Puzzle {
+        modulus,
+        base,
+        squarings: 1999,
+        wrapped_key,
+    };
+    assert!(solve_puzzle(&short, &running(), |_, _| {}).is_err());
+}
+
+#[test]
+fn capsule_opens_by_work_and_survives_encoding() {
+    let capsule = seal(b"surprise", 4_000_000_000, None, Some(500), TEST_BITS).unwrap();
+    let bytes = capsule.to_bytes().unwrap();
+    assert_eq!(&bytes[..4], b"VXTL");
+
+    let decoded = TimelockCapsule::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.open_by_work(&running(), |_, _| {}).unwrap(), b"surprise");
+    assert!(decoded.open_with_passphrase(b"anything", u64::MAX).is_err());
+
+    let info = decoded.info(Some(100));
+    assert!(!info.escrow);
+    assert_eq!((info.squarings, info.estimated_solve_secs), (Some(500), Some(5)));
+
+    // The unlock time is authenticated with the data
+    let mut moved = decoded.clone();
+    moved.unlock_at -= 1;
+    assert!(moved.open_by_work(&running(), |_, _| {}).is_err());
+    assert!(TimelockCapsule::from_bytes(b"XXXX{}").is_err());
+}
+
+#[test]
+fn escrow_opens_only_after_unlock_time() {
+    assert!(seal(b"x", 1000, None, None, TEST_BITS).is_err());
+
+    let capsule = seal(b"reveal", 1000, Some(b"escrow phrase"), None, TEST_BITS).unwrap();
+    let err = capsule.open_with_passphrase(b"escrow phrase", 999).unwrap_err();
+    assert!(matches!(err, crate::crypto::CryptoError::InvalidInput(ref msg) if msg.contains("locked")));
+    assert!(capsule.open_by_work(&running(), |_, _| {}).is_err());
+
+    assert_eq!(capsule.open_with_passphrase(b"escrow phrase", 1000).unwrap(), b"reveal");
+}
+
+#[test]
+fn oversized_puzzle_is_refused() {
+    // Calibrated for 1000 squarings per second over a 100 second lock
+    let mut capsule = seal(b"x", 1_100, None, Some(100_000), TEST_BITS).unwrap();
+    capsule.created_at = 1_000;
+    assert!(capsule.check_work(1000, 1_000).is_ok());
+    // Still fine after the unlock date
+    assert!(capsule.check_work(1000, 5_000).is_ok());
+    // Far more work than the lock runs
+    assert!(capsule.check_work(100, 1_000).is_err());
+
+    // A ten second lock still allows a minute of work
+    capsule.unlock_at = 1_010;
+    assert!(capsule.check_work(2000, 1_000).is_ok());
+    capsule.unlock_at = 1_100;
+
+    // An unlock date more than five years after creation
+    capsule.created_at = 0;
+    capsule.unlock_at = 10 * 365 * 24 * 3600;
+    assert!(capsule.check_work(u64::MAX, 0).is_err());
+
+    let escrow_only = seal(b"x", 1_100, Some(b"escrow phrase"), None, TEST_BITS).unwrap();
+    assert!(escrow_only.check_work(1, 0).is_ok());
+}
+
+#[test]
+fn cancelled_work_stops() {
+    let lock = puzzle_lock(&[1u8; 32], 1_000_000_000, TEST_BITS).unwrap();
+    let cancelled = AtomicBool::new(true);
+    let mut reports = 0;
+    assert!(solve_puzzle(&lock, &cancelled, |_, _| reports += 1).is_err());
+    assert_eq!(reports, 0);
+}
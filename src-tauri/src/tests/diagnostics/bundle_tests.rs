@@ -0,0 +1,80 @@
+//! Diagnostics Bundle Tests
+//!
+//! Tests for:
+//! - Masking tokens, credentials and the home directory in log text
+//! - Cutting log tails at a line start
+//! - Choosing the newest log files
+
+use crate::diagnostics::{log_tail, recent_log_files, redact_secrets, REDACTED};
+use crate::tests::common::temp_dir;
+
+// ============================================================================
+// Redaction Tests
+// ============================================================================
+
+#[test]
+fn github_tokens_are_masked() {
+    let line = "INFO sync: using ghp_abcDEF1234567890 for alice/photos\n";
+    let redacted = redact_secrets(line, None);
+    assert_eq!(redacted, format!("INFO sync: using {} for alice/photos\n", REDACTED));
+
+    let redacted = redact_secrets("pat=github_pat_11AAAA_bbbb", None);
+    assert!(!redacted.contains("11AAAA"));
+}
+
+#[test]
+fn credentials_are_masked() {
+    let redacted = redact_secrets("Authorization: Bearer abc.def-123", None);
+    assert!(!redacted.contains("abc.def-123"));
+
+    let redacted = redact_secrets("unlock failed password=hunter2&retry=1", None);
+    assert_eq!(redacted, format!("unlock failed password={}&retry=1", REDACTED));
+
+    let redacted = redact_secrets(r#"{"access_token": "s3cr3t", "user": "alice"}"#, None);
+    assert_eq!(redacted, format!(r#"{{"access_token": "{}", "user": "alice"}}"#, REDACTED));
+
+    let redacted = redact_secrets("Mnemonic: word1 word2", None);
+    assert!(!redacted.contains("word1"));
+}
+
+#[test]
+fn home_directory_is_replaced() {
+    let redacted = redact_secrets("opened /home/alice/.local/share/vortex-image/store.db", Some("/home/alice"));
+    assert_eq!(redacted, "opened ~/.local/share/vortex-image/store.db");
+}
+
+#[test]
+fn ordinary_lines_are_untouched() {
+    let lines = "INFO upload_photo: uploaded 3 files\nDEBUG rate limit: tokens left 4200\nsecret sharing set up\n";
+    assert_eq!(redact_secrets(lines, None), lines);
+    assert_eq!(redact_secrets("näive ünïcode ghp_x", None), format!("näive ünïcode {}", REDACTED));
+}
+
+// ============================================================================
+// Bundle Tests
+// ============================================================================
+
+#[test]
+fn tails_start_at_a_line() {
+    let content = b"first line\nsecond line\nthird line\n";
+    assert_eq!(log_tail(content, 100), "first line\nsecond line\nthird line\n");
+    assert_eq!(log_tail(content, 15), "third line\n");
+}
+
+#[test]
+fn newest_log_files_are_chosen() {
+    let dir = temp_dir("diagnostics", "recent");
+    for day in ["2026-10-01", "2026-10-02", "2026-10-03", "2026-10-04"] {
+        std::fs::write(dir.join(format!("vortex.{}.log", day)), day).unwrap();
+    }
+    std::fs::write(dir.join("other.log"), "not ours").unwrap();
+
+    let names: Vec<String> = recent_log_files(&dir)
+        .unwrap()
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, vec!["vortex.2026-10-02.log", "vortex.2026-10-03.log", "vortex.2026-10-04.log"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
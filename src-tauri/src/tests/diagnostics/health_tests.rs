@@ -0,0 +1,106 @@
+//! Health Check Tests
+//!
+//! Tests for:
+//! - Judging token scopes and repository access
+//! - Crypto backend, keystore and disk space verdicts
+//! - Combining checks into an overall status
+
+use crate::crypto::check_pqcrypto_backend;
+use crate::health::{
+    crypto_check, disk_check, keystore_check, overall_status, parse_scopes, repo_check, token_check, DiskSpace,
+    HealthCheck, HealthStatus, MIN_FREE_BYTES,
+};
+use crate::keystore::{KeystoreBackend, KeystoreInfo};
+
+const MB: u64 = 1024 * 1024;
+
+fn space(available_bytes: u64, cache_bytes: u64, cache_limit_bytes: u64) -> DiskSpace {
+    DiskSpace {
+        path: "/tmp/offline".into(),
+        available_bytes,
+        cache_bytes,
+        cache_limit_bytes,
+    }
+}
+
+// ============================================================================
+// GitHub Check Tests
+// ============================================================================
+
+#[test]
+fn token_scopes_are_judged() {
+    let scopes = parse_scopes("read:user, repo,  gist");
+    assert_eq!(scopes, vec!["read:user", "repo", "gist"]);
+    assert_eq!(token_check("alice", Some(&scopes)).status, HealthStatus::Ok);
+
+    let scopes = parse_scopes("read:user");
+    assert_eq!(token_check("alice", Some(&scopes)).status, HealthStatus::Warning);
+    assert!(parse_scopes("").is_empty());
+
+    // Fine-grained tokens have no scopes header
+    assert_eq!(token_check("alice", None).status, HealthStatus::Ok);
+}
+
+#[test]
+fn repo_access_is_judged() {
+    let writable = serde_json::json!({"private": true, "permissions": {"admin": true, "push": true}});
+    let check = repo_check("alice/photos", &writable);
+    assert_eq!(check.status, HealthStatus::Ok);
+    assert!(check.message.contains("private"));
+
+    let read_only = serde_json::json!({"private": false, "permissions": {"pull": true, "push": false}});
+    assert_eq!(repo_check("alice/photos", &read_only).status, HealthStatus::Warning);
+}
+
+// ============================================================================
+// Local Check Tests
+// ============================================================================
+
+#[test]
+fn crypto_backend_passes_self_test() {
+    let status = check_pqcrypto_backend();
+    assert!(status.self_test_passed, "{:?}", status.self_test_error);
+    assert_eq!(crypto_check(&status).status, HealthStatus::Ok);
+
+    let mut broken = status.clone();
+    broken.self_test_passed = false;
+    broken.self_test_error = Some("Decrypt".into());
+    assert_eq!(crypto_check(&broken).status, HealthStatus::Error);
+}
+
+#[test]
+fn keystore_fallback_warns() {
+    let mut info = KeystoreInfo {
+        backend: KeystoreBackend::SecretService,
+        chain: vec![KeystoreBackend::SecretService, KeystoreBackend::EncryptedFile],
+        hardware: None,
+        fallback_reason: None,
+    };
+    assert_eq!(keystore_check(&info).status, HealthStatus::Ok);
+
+    info.backend = KeystoreBackend::EncryptedFile;
+    info.fallback_reason = Some("OS keystore is unavailable or locked".into());
+    assert_eq!(keystore_check(&info).status, HealthStatus::Warning);
+}
+
+#[test]
+fn disk_space_against_cache_limit() {
+    assert_eq!(disk_check(&space(50 * 1024 * MB, 0, 1024 * MB)).status, HealthStatus::Ok);
+    // Room for what the cache still may take, counting what it already holds
+    assert_eq!(disk_check(&space(600 * MB, 700 * MB, 1024 * MB)).status, HealthStatus::Ok);
+    assert_eq!(disk_check(&space(600 * MB, 0, 1024 * MB)).status, HealthStatus::Warning);
+    assert_eq!(disk_check(&space(MIN_FREE_BYTES - 1, 0, 0)).status, HealthStatus::Error);
+}
+
+#[test]
+fn overall_status_is_the_worst() {
+    let ok = token_check("alice", None);
+    let skipped = HealthCheck {
+        status: HealthStatus::Skipped,
+        message: "No repository given".into(),
+    };
+    let warning = token_check("alice", Some(&[]));
+    assert_eq!(overall_status(&[&skipped, &ok]), HealthStatus::Ok);
+    assert_eq!(overall_status(&[&ok, &warning, &skipped]), HealthStatus::Warning);
+    assert_eq!(overall_status(&[]), HealthStatus::Skipped);
+}
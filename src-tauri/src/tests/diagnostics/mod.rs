@@ -0,0 +1,8 @@
+//! Diagnostics Module Tests
+//!
+//! Organized by functionality:
+//! - `bundle_tests` - Secret redaction and choosing the logs to bundle
+//! - `health_tests` - Startup health check verdicts
+
+pub mod bundle_tests;
+pub mod health_tests;
@@ -0,0 +1,65 @@
+//! Typed GitHub Error Tests
+//!
+//! Tests for:
+//! - Mapping HTTP statuses to error kinds
+//! - Rate limit and stale SHA detection
+//! - JSON shape of errors returned to the frontend
+
+use crate::github::{classify_status, AppError, GithubError};
+
+// ============================================================================
+// Classification Tests
+// ============================================================================
+
+#[test]
+fn common_statuses_map_to_kinds() {
+    let msg = || "boom".to_string();
+    assert!(matches!(classify_status(401, None, msg()), GithubError::Unauthorized { .. }));
+    assert!(matches!(classify_status(403, None, msg()), GithubError::Forbidden { .. }));
+    assert!(matches!(classify_status(404, None, msg()), GithubError::NotFound { .. }));
+    assert!(matches!(classify_status(409, None, msg()), GithubError::Conflict { .. }));
+    assert!(matches!(classify_status(413, None, msg()), GithubError::TooLarge { .. }));
+    assert!(matches!(classify_status(504, None, msg()), GithubError::NetworkTimeout { .. }));
+    assert_eq!(classify_status(502, None, msg()), GithubError::Server { status: 502, message: msg() });
+    assert_eq!(classify_status(418, None, msg()), GithubError::Api { status: Some(418), message: msg() });
+}
+
+#[test]
+fn rate_limits_carry_retry_after() {
+    assert_eq!(
+        classify_status(403, Some(12), "limit".into()),
+        GithubError::RateLimited { retry_after: 12, message: "limit".into() }
+    );
+    assert_eq!(
+        classify_status(429, None, "limit".into()),
+        GithubError::RateLimited { retry_after: 60, message: "limit".into() }
+    );
+}
+
+#[test]
+fn stale_sha_is_a_conflict() {
+    let stale = classify_status(422, None, "Update failed (422): \"sha\" wasn't supplied".into());
+    assert!(matches!(stale, GithubError::Conflict { .. }));
+
+    let invalid = classify_status(422, None, "Validation Failed".into());
+    assert!(matches!(invalid, GithubError::Validation { .. }));
+}
+
+// ============================================================================
+// Serialization Tests
+// ============================================================================
+
+#[test]
+fn app_errors_serialize_with_kind_tag() {
+    let err: AppError = GithubError::RateLimited { retry_after: 30, message: "slow down".into() }.into();
+    let json = serde_json::to_value(&err).unwrap();
+    assert_eq!(json, serde_json::json!({ "kind": "rate_limited", "retry_after": 30, "message": "slow down" }));
+
+    let json = serde_json::to_value(AppError::Validation("bad repo".into())).unwrap();
+    assert_eq!(json["kind"], "validation");
+    assert_eq!(json["message"], "Validation error: bad repo");
+
+    let json = serde_json::to_value(AppError::Unavailable(5)).unwrap();
+    assert_eq!(json["kind"], "unavailable");
+    assert_eq!(json["retry_after"], 5);
+}
@@ -0,0 +1,6 @@
+//! Error Module Tests
+//!
+//! Organized by functionality:
+//! - `github_error_tests` - HTTP status classification and serialized error shape
+
+pub mod github_error_tests;
@@ -0,0 +1,6 @@
+//! History Module Tests
+//!
+//! Organized by functionality:
+//! - `restore_tests` - Album restore planning and per-album commit files
+
+pub mod restore_tests;
@@ -0,0 +1,75 @@
+//! Album Restore Tests
+//!
+//! Tests for:
+//! - Restore planning between two trees
+//! - Scoping of changes and commit files to one album
+
+use crate::git_data::{index_blobs, TreeEntry, TreeIndex};
+use crate::history::{album_files, plan_restore};
+
+fn blob(path: &str, sha: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.to_string(),
+        mode: "100644".to_string(),
+        kind: "blob".to_string(),
+        sha: sha.to_string(),
+        size: None,
+    }
+}
+
+fn tree(entries: &[(&str, &str)]) -> TreeIndex {
+    index_blobs(entries.iter().map(|(p, s)| blob(p, s)).collect())
+}
+
+// ============================================================================
+// Planning Tests
+// ============================================================================
+
+#[test]
+fn restore_brings_back_deleted_and_removes_added() {
+    let target = tree(&[("photos/trip/a.jpg", "1"), ("photos/trip/b.jpg", "2")]);
+    let current = tree(&[("photos/trip/a.jpg", "1"), ("photos/trip/c.jpg", "3")]);
+
+    let changes = plan_restore(&current, &target, "photos/trip");
+    let summary: Vec<_> = changes.iter().map(|c| (c.path.as_str(), c.sha.as_deref())).collect();
+    assert_eq!(
+        summary,
+        vec![("photos/trip/b.jpg", Some("2")), ("photos/trip/c.jpg", None)]
+    );
+}
+
+#[test]
+fn restore_reverts_modified_files() {
+    let target = tree(&[("photos/trip/a.jpg", "old")]);
+    let current = tree(&[("photos/trip/a.jpg", "new")]);
+
+    let changes = plan_restore(&current, &target, "photos/trip");
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].sha.as_deref(), Some("old"));
+}
+
+#[test]
+fn restore_only_touches_the_album() {
+    let target = tree(&[("photos/trip/a.jpg", "1"), ("photos/other/x.jpg", "9")]);
+    let current = tree(&[("photos/trip/a.jpg", "1"), ("photos/trip2/y.jpg", "8"), ("README.md", "7")]);
+
+    assert!(plan_restore(&current, &target, "photos/trip").is_empty());
+}
+
+// ============================================================================
+// History Tests
+// ============================================================================
+
+#[test]
+fn commit_files_are_scoped_to_album() {
+    let files = vec![
+        serde_json::json!({ "filename": "photos/trip/a.jpg", "status": "added" }),
+        serde_json::json!({ "filename": "photos/other/b.jpg", "status": "removed" }),
+        serde_json::json!({ "filename": "photos/other/c.jpg", "status": "renamed", "previous_filename": "photos/trip/c.jpg" }),
+    ];
+
+    let scoped = album_files(&files, "photos/trip");
+    assert_eq!(scoped.len(), 2);
+    assert_eq!(scoped[0].status, "added");
+    assert_eq!(scoped[1].previous_path.as_deref(), Some("photos/trip/c.jpg"));
+}
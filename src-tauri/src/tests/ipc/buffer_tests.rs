@@ -0,0 +1,274 @@
+//! Large Payload Tests
+//!
+//! Tests for:
+//! - Running data commands from one file to another
+//! - Streamed compression and hashing
+//! - Buffer IDs, appending and releasing
+//! - Streaming a buffer back in chunks
+
+use std::fs::File;
+use std::sync::atomic::AtomicBool;
+use zeroize::Zeroizing;
+
+use crate::compress::{compress, Algorithm, CompressionSettings};
+use crate::compress_stream::decompress_stream;
+use crate::crypto::{generate_keypair, release_keypair};
+use crate::ipc_buffers::{
+    append_buffer_in, buffer_path_in, create_buffer_in, is_buffer_id, read_chunks, release_buffer_in, run_data_op,
+    DataOp,
+};
+use crate::tests::common::temp_dir;
+
+const PASSWORD: &str = "Vortex-large-payload-7#quartz-lantern";
+
+fn sample() -> Vec<u8> {
+    b"large payloads stay out of the JSON channel. ".repeat(4096)
+}
+
+// ============================================================================
+// File Variant Tests
+// ============================================================================
+
+#[test]
+fn compress_round_trips_through_files() {
+    let dir = temp_dir("ipc", "compress");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+
+    let op = DataOp::Compress {
+        algorithm: "zstd".into(),
+        level: None,
+        strict: true,
+    };
+    let compressed = run_data_op(op, &input, Some(&dir.join("input.zst"))).unwrap();
+    let details = compressed.compression.unwrap();
+    assert!(details.data.is_empty());
+    assert_eq!(details.original_size, sample().len());
+    assert_eq!(compressed.size, std::fs::metadata(dir.join("input.zst")).unwrap().len());
+
+    let op = DataOp::Decompress { algorithm: "zstd".into() };
+    run_data_op(op, &dir.join("input.zst"), Some(&dir.join("output.bin"))).unwrap();
+    assert_eq!(std::fs::read(dir.join("output.bin")).unwrap(), sample());
+}
+
+#[test]
+fn password_encryption_round_trips_through_files() {
+    let dir = temp_dir("ipc", "password");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+
+    let op = DataOp::EncryptPassword { password: Zeroizing::new(PASSWORD.into()) };
+    run_data_op(op, &input, Some(&dir.join("sealed.bin"))).unwrap();
+    assert_ne!(std::fs::read(dir.join("sealed.bin")).unwrap(), sample());
+
+    let op = DataOp::DecryptPassword { password: Zeroizing::new(PASSWORD.into()) };
+    run_data_op(op, &dir.join("sealed.bin"), Some(&dir.join("opened.bin"))).unwrap();
+    assert_eq!(std::fs::read(dir.join("opened.bin")).unwrap(), sample());
+}
+
+#[test]
+fn hybrid_encryption_round_trips_through_files() {
+    let dir = temp_dir("ipc", "hybrid");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+    let keypair = generate_keypair().unwrap();
+
+    let op = DataOp::EncryptHybrid {
+        recipient_bundle: Some(keypair.public_bundle.clone()),
+        contact_id: None,
+        aad: None,
+    };
+    run_data_op(op, &input, Some(&dir.join("sealed.json"))).unwrap();
+
+    let op = DataOp::DecryptHybrid {
+        handle: keypair.handle,
+        aad: None,
+    };
+    run_data_op(op, &dir.join("sealed.json"), Some(&dir.join("opened.bin"))).unwrap();
+    assert_eq!(std::fs::read(dir.join("opened.bin")).unwrap(), sample());
+
+    let op = DataOp::DecryptHybrid {
+        handle: keypair.handle,
+        aad: None,
+    };
+    assert!(run_data_op(op, &input, None).is_err());
+    release_keypair(keypair.handle).unwrap();
+}
+
+#[test]
+fn signatures_are_checked_without_output() {
+    let dir = temp_dir("ipc", "sign");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+    let keypair = generate_keypair().unwrap();
+
+    run_data_op(DataOp::Sign { handle: keypair.handle }, &input, Some(&dir.join("input.sig"))).unwrap();
+    let signature = std::fs::read(dir.join("input.sig")).unwrap();
+
+    let op = DataOp::Verify {
+        signature: signature.clone(),
+        public_bundle: keypair.public_bundle.clone(),
+    };
+    let verified = run_data_op(op, &input, Some(&dir.join("ignored"))).unwrap();
+    assert_eq!(verified.valid, Some(true));
+    assert_eq!(verified.output, None);
+    assert!(!dir.join("ignored").exists());
+
+    std::fs::write(&input, b"something else").unwrap();
+    let op = DataOp::Verify {
+        signature,
+        public_bundle: keypair.public_bundle.clone(),
+    };
+    assert_eq!(run_data_op(op, &input, None).unwrap().valid, Some(false));
+    release_keypair(keypair.handle).unwrap();
+}
+
+#[test]
+fn hash_returns_the_digest() {
+    let dir = temp_dir("ipc", "hash");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+
+    let hashed = run_data_op(DataOp::Hash, &input, None).unwrap();
+    assert_eq!(hashed.digest.unwrap(), blake3::hash(&sample()).to_hex().to_string());
+    assert_eq!(hashed.size, 32);
+    assert_eq!(hashed.output, None);
+}
+
+#[test]
+fn compressed_files_use_the_stream_format() {
+    let dir = temp_dir("ipc", "stream");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+
+    let op = DataOp::CompressAuto {
+        prefer_speed: false,
+        archival: None,
+    };
+    let compressed = run_data_op(op, &input, Some(&dir.join("input.vx"))).unwrap();
+    let details = compressed.compression.unwrap();
+    assert!(details.decision.is_some());
+    assert!(details.was_compressed);
+
+    let mut opened = Vec::new();
+    let cancel = AtomicBool::new(false);
+    let (algorithm, size) =
+        decompress_stream(File::open(dir.join("input.vx")).unwrap(), &mut opened, &cancel, |_, _| {}).unwrap();
+    assert_eq!(algorithm, details.algorithm);
+    assert_eq!(size, sample().len() as u64);
+    assert_eq!(opened, sample());
+}
+
+#[test]
+fn compression_without_output_only_measures() {
+    let dir = temp_dir("ipc", "estimate");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+
+    let op = DataOp::Compress {
+        algorithm: "lz4".into(),
+        level: None,
+        strict: false,
+    };
+    let estimated = run_data_op(op, &input, None).unwrap();
+    assert_eq!(estimated.output, None);
+    assert!(estimated.size > 0 && estimated.size < sample().len() as u64);
+}
+
+#[test]
+fn block_compressed_files_still_decompress() {
+    let dir = temp_dir("ipc", "block");
+    let settings = CompressionSettings {
+        algorithm: Algorithm::Zstd,
+        level: 3,
+        prefer_speed: false,
+    };
+    std::fs::write(dir.join("input.zst"), compress(&sample(), &settings).unwrap().data).unwrap();
+
+    let op = DataOp::Decompress { algorithm: "zstd".into() };
+    run_data_op(op, &dir.join("input.zst"), Some(&dir.join("output.bin"))).unwrap();
+    assert_eq!(std::fs::read(dir.join("output.bin")).unwrap(), sample());
+}
+
+#[test]
+fn hash_writes_the_digest_to_output() {
+    let dir = temp_dir("ipc", "hash-out");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+
+    run_data_op(DataOp::Hash, &input, Some(&dir.join("input.b3"))).unwrap();
+    assert_eq!(std::fs::read(dir.join("input.b3")).unwrap(), blake3::hash(&sample()).as_bytes());
+}
+
+#[test]
+fn failed_ops_leave_no_output() {
+    let dir = temp_dir("ipc", "failed");
+    let input = dir.join("input.bin");
+    std::fs::write(&input, sample()).unwrap();
+
+    let op = DataOp::DecryptPassword { password: Zeroizing::new(PASSWORD.into()) };
+    assert!(run_data_op(op, &input, Some(&dir.join("opened.bin"))).is_err());
+    assert!(!dir.join("opened.bin").exists());
+    assert!(!dir.join("opened.bin.part").exists());
+}
+
+// ============================================================================
+// Buffer Tests
+// ============================================================================
+
+#[test]
+fn buffer_ids_are_checked() {
+    let dir = temp_dir("ipc", "ids");
+    let id = create_buffer_in(&dir).unwrap();
+    assert!(is_buffer_id(&id));
+    assert!(buffer_path_in(&dir, &id).is_ok());
+
+    for bad in ["", "buf-", "buf-../../etc/passwd", "buf-ABCDEF0123456789", "other-0123456789abcdef"] {
+        assert!(!is_buffer_id(bad), "{}", bad);
+        assert!(buffer_path_in(&dir, bad).is_err());
+    }
+    assert!(buffer_path_in(&dir, "buf-0123456789abcdef").is_err());
+}
+
+#[test]
+fn buffers_are_appended_and_released() {
+    let dir = temp_dir("ipc", "append");
+    let id = create_buffer_in(&dir).unwrap();
+    assert_eq!(append_buffer_in(&dir, &id, b"hello ").unwrap(), 6);
+    assert_eq!(append_buffer_in(&dir, &id, b"world").unwrap(), 11);
+    assert_eq!(std::fs::read(buffer_path_in(&dir, &id).unwrap()).unwrap(), b"hello world");
+
+    assert!(release_buffer_in(&dir, &id));
+    assert!(!release_buffer_in(&dir, &id));
+    assert!(append_buffer_in(&dir, &id, b"again").is_err());
+}
+
+#[test]
+fn buffers_are_read_in_chunks() {
+    let dir = temp_dir("ipc", "chunks");
+    let id = create_buffer_in(&dir).unwrap();
+    append_buffer_in(&dir, &id, &sample()).unwrap();
+
+    let mut chunks = Vec::new();
+    let sent = read_chunks(&buffer_path_in(&dir, &id).unwrap(), 64 * 1024, |chunk| {
+        chunks.push(chunk);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(sent, sample().len() as u64);
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 64 * 1024));
+    assert_eq!(chunks.concat(), sample());
+}
+
+#[test]
+fn empty_buffer_sends_nothing() {
+    let dir = temp_dir("ipc", "empty");
+    let id = create_buffer_in(&dir).unwrap();
+    let mut calls = 0;
+    let sent = read_chunks(&buffer_path_in(&dir, &id).unwrap(), 4096, |_| {
+        calls += 1;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!((sent, calls), (0, 0));
+}
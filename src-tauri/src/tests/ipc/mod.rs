@@ -0,0 +1,6 @@
+//! IPC Payload Tests
+//!
+//! Organized by functionality:
+//! - `buffer_tests` - Data commands on files and buffers, chunked buffer reads
+
+pub mod buffer_tests;
@@ -0,0 +1,111 @@
+//! Job Cancellation Tests
+//!
+//! Tests for:
+//! - Registering, listing and dropping jobs
+//! - Cancel flags, checkpoints and the `cancelled` error kind
+//! - Stopping a streamed encryption at its next chunk
+
+use std::io::Cursor;
+use std::sync::atomic::Ordering;
+
+use crate::file_crypto::{encrypt_stream, StreamKey};
+use crate::github::{AppError, GithubError};
+use crate::jobs::{cancel_job_in, cancelled, is_cancelled_error, running_jobs, CancelOnWrite, Job, JobCommand};
+
+fn listed(job_id: &str) -> bool {
+    running_jobs().iter().any(|job| job.job_id == job_id)
+}
+
+// ============================================================================
+// Registry Tests
+// ============================================================================
+
+#[test]
+fn jobs_are_listed_while_running() {
+    let job = Job::start(Some("test-listed".into()), JobCommand::EncryptFile).unwrap();
+    assert_eq!(job.id(), "test-listed");
+    assert!(listed("test-listed"));
+
+    assert!(Job::start(Some("test-listed".into()), JobCommand::DecryptFile).is_err());
+    assert!(Job::start(Some(String::new()), JobCommand::DecryptFile).is_err());
+
+    drop(job);
+    assert!(!listed("test-listed"));
+    assert!(!cancel_job_in("test-listed"));
+}
+
+#[test]
+fn generated_ids_are_unique() {
+    let a = Job::start(None, JobCommand::CompressFile).unwrap();
+    let b = Job::start(None, JobCommand::CompressFile).unwrap();
+    assert_ne!(a.id(), b.id());
+}
+
+#[test]
+fn cancelling_trips_checkpoints() {
+    let job = Job::start(Some("test-cancel".into()), JobCommand::UploadFolder).unwrap();
+    assert!(job.check().is_ok());
+
+    assert!(cancel_job_in("test-cancel"));
+    assert!(job.is_cancelled());
+    assert!(is_cancelled_error(&job.check().unwrap_err()));
+    let info = running_jobs().into_iter().find(|j| j.job_id == "test-cancel").unwrap();
+    assert!(info.cancelling);
+}
+
+#[test]
+fn failures_after_cancel_report_cancelled() {
+    let job = Job::start(Some("test-outcome".into()), JobCommand::DecryptFile).unwrap();
+    let failure = || -> Result<(), AppError> { Err(AppError::Validation("Chunk 3 failed".into())) };
+    assert!(!is_cancelled_error(&job.outcome(failure()).unwrap_err()));
+    assert_eq!(job.outcome(Ok(5)).unwrap(), 5);
+
+    cancel_job_in("test-outcome");
+    assert!(is_cancelled_error(&job.outcome(failure()).unwrap_err()));
+    // A job that finished before noticing keeps its result
+    assert_eq!(job.outcome(Ok(5)).unwrap(), 5);
+}
+
+#[test]
+fn cancelled_error_has_its_own_kind() {
+    let json = serde_json::to_value(cancelled("Upload cancelled")).unwrap();
+    assert_eq!(json["kind"], "cancelled");
+    assert_eq!(json["message"], "Upload cancelled");
+    assert!(!is_cancelled_error(&GithubError::NotFound { message: "x".into() }.into()));
+}
+
+// ============================================================================
+// Checkpoint Tests
+// ============================================================================
+
+#[test]
+fn writes_fail_once_cancelled() {
+    let job = Job::start(Some("test-write".into()), JobCommand::EncryptFile).unwrap();
+    let mut out = Vec::new();
+    let mut writer = CancelOnWrite::new(&mut out, job.flag());
+    std::io::Write::write_all(&mut writer, b"first").unwrap();
+
+    cancel_job_in("test-write");
+    assert!(std::io::Write::write_all(&mut writer, b"second").is_err());
+    assert_eq!(out, b"first");
+}
+
+#[test]
+fn encryption_stops_at_next_chunk() {
+    let job = Job::start(Some("test-encrypt".into()), JobCommand::EncryptFile).unwrap();
+    let flag = job.flag();
+    let plain = vec![7u8; 16 * 1024];
+    let key = StreamKey::Password(zeroize::Zeroizing::new(b"hunter2".to_vec()));
+
+    let mut out = Vec::new();
+    let mut writer = CancelOnWrite::new(&mut out, job.flag());
+    let mut chunks = 0;
+    let result = encrypt_stream(&mut Cursor::new(&plain), &mut writer, &key, 4096, plain.len() as u64, |_| {
+        chunks += 1;
+        flag.store(true, Ordering::Relaxed);
+    });
+
+    assert!(result.is_err());
+    assert_eq!(chunks, 1);
+    assert!(is_cancelled_error(&job.outcome(result).unwrap_err()));
+}
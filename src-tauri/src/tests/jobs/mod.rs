@@ -0,0 +1,8 @@
+//! Job Module Tests
+//!
+//! Organized by functionality:
+//! - `cancellation_tests` - Job registry, cancel checkpoints and the cancelled error
+//! - `scheduler_tests` - Priority classes, concurrency caps and permits
+
+pub mod cancellation_tests;
+pub mod scheduler_tests;
@@ -0,0 +1,169 @@
+//! Job Scheduler Tests
+//!
+//! Tests for:
+//! - Per-class caps and priority between classes
+//! - Background work yielding to interactive work
+//! - Permits, waiters and the queue status
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::scheduler::{may_start, ClassLimits, JobClass, Load, Scheduler};
+
+fn limits() -> ClassLimits {
+    ClassLimits {
+        interactive: 2,
+        sync: 1,
+        background: 1,
+    }
+}
+
+fn status_of(scheduler: &Scheduler, class: JobClass) -> (usize, usize, u64) {
+    let status = scheduler.status();
+    let entry = status.classes.iter().find(|c| c.class == class).unwrap();
+    (entry.running, entry.waiting, entry.completed)
+}
+
+// ============================================================================
+// Admission Tests
+// ============================================================================
+
+#[test]
+fn idle_scheduler_admits_every_class() {
+    let load = Load::default();
+    for class in JobClass::ALL {
+        assert!(may_start(class, &load, &limits()));
+    }
+}
+
+#[test]
+fn class_cap_is_enforced() {
+    let load = Load {
+        running: [2, 1, 0],
+        waiting: [0; 3],
+    };
+    assert!(!may_start(JobClass::Interactive, &load, &limits()));
+    assert!(!may_start(JobClass::Sync, &load, &limits()));
+}
+
+#[test]
+fn waiting_higher_class_holds_back_lower_ones() {
+    let load = Load {
+        running: [0, 1, 0],
+        waiting: [0, 1, 0],
+    };
+    assert!(may_start(JobClass::Interactive, &load, &limits()));
+    assert!(!may_start(JobClass::Background, &load, &limits()));
+
+    let load = Load {
+        running: [2, 0, 0],
+        waiting: [1, 0, 0],
+    };
+    assert!(!may_start(JobClass::Sync, &load, &limits()));
+}
+
+#[test]
+fn background_waits_while_interactive_runs() {
+    let load = Load {
+        running: [1, 0, 0],
+        waiting: [0; 3],
+    };
+    assert!(!may_start(JobClass::Background, &load, &limits()));
+    assert!(may_start(JobClass::Sync, &load, &limits()));
+}
+
+#[test]
+fn zero_limit_still_admits_one() {
+    let limits = ClassLimits {
+        interactive: 0,
+        sync: 0,
+        background: 0,
+    };
+    assert!(may_start(JobClass::Sync, &Load::default(), &limits));
+}
+
+// ============================================================================
+// Permit Tests
+// ============================================================================
+
+#[test]
+fn permits_are_counted_and_returned() {
+    let scheduler = Scheduler::new(limits());
+    let a = scheduler.acquire_blocking(JobClass::Interactive);
+    let b = scheduler.acquire_blocking(JobClass::Interactive);
+    assert_eq!(status_of(&scheduler, JobClass::Interactive), (2, 0, 0));
+
+    drop(a);
+    drop(b);
+    assert_eq!(status_of(&scheduler, JobClass::Interactive), (0, 0, 2));
+}
+
+#[test]
+fn status_lists_classes_by_priority() {
+    let scheduler = Scheduler::new(limits());
+    let classes: Vec<JobClass> = scheduler.status().classes.iter().map(|c| c.class).collect();
+    assert_eq!(classes, JobClass::ALL.to_vec());
+    assert_eq!(scheduler.status().classes[1].limit, 1);
+}
+
+#[test]
+fn background_starts_once_interactive_finishes() {
+    let scheduler = Scheduler::new(limits());
+    let interactive = scheduler.acquire_blocking(JobClass::Interactive);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let _permit = scheduler.acquire_blocking(JobClass::Background);
+            tx.send(()).unwrap();
+        });
+
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        assert_eq!(status_of(&scheduler, JobClass::Background), (0, 1, 0));
+
+        drop(interactive);
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    });
+    assert_eq!(status_of(&scheduler, JobClass::Background), (0, 0, 1));
+}
+
+#[test]
+fn full_class_waits_for_a_permit() {
+    let scheduler = Scheduler::new(limits());
+    let sync = scheduler.acquire_blocking(JobClass::Sync);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let _permit = scheduler.acquire_blocking(JobClass::Sync);
+            tx.send(()).unwrap();
+        });
+
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(sync);
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    });
+    assert_eq!(status_of(&scheduler, JobClass::Sync), (0, 0, 2));
+}
+
+#[test]
+fn async_acquire_waits_for_a_permit() {
+    let scheduler = Scheduler::new(limits());
+    let interactive = scheduler.acquire_blocking(JobClass::Interactive);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let _permit = futures::executor::block_on(scheduler.acquire(JobClass::Background));
+            tx.send(()).unwrap();
+        });
+
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+        assert_eq!(status_of(&scheduler, JobClass::Background), (0, 1, 0));
+
+        drop(interactive);
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    });
+    assert_eq!(status_of(&scheduler, JobClass::Background), (0, 0, 1));
+}
@@ -0,0 +1,6 @@
+//! LFS Module Tests
+//!
+//! Organized by functionality:
+//! - `pointer_tests` - Pointer files and `.gitattributes` rules
+
+pub mod pointer_tests;
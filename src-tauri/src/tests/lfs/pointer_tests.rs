@@ -0,0 +1,66 @@
+//! Git LFS Pointer Tests
+//!
+//! Tests for:
+//! - Pointer file generation and parsing
+//! - `.gitattributes` rule detection and merging
+
+use crate::lfs::{is_tracked, tracked_extensions, with_lfs_rules, LfsPointer, LFS_EXTENSIONS};
+
+// ============================================================================
+// Pointer Tests
+// ============================================================================
+
+#[test]
+fn pointer_roundtrip() {
+    let pointer = LfsPointer::for_content(b"raw sensor data");
+    assert_eq!(pointer.size, 15);
+    assert_eq!(pointer.oid.len(), 64);
+
+    let text = pointer.to_bytes();
+    assert!(text.starts_with(b"version https://git-lfs.github.com/spec/v1\noid sha256:"));
+    assert_eq!(LfsPointer::parse(&text), Some(pointer));
+}
+
+#[test]
+fn regular_content_is_not_a_pointer() {
+    assert_eq!(LfsPointer::parse(b"\xff\xd8\xff\xe0 jpeg bytes"), None);
+    assert_eq!(LfsPointer::parse(b"version https://git-lfs.github.com/spec/v1\nsize 10\n"), None);
+    assert_eq!(
+        LfsPointer::parse(b"version https://git-lfs.github.com/spec/v1\noid sha256:abc\nsize 10\n"),
+        None,
+        "oid must be a full SHA-256"
+    );
+
+    let mut large = LfsPointer::for_content(b"x").to_bytes();
+    large.extend([b' '; 2048]);
+    assert_eq!(LfsPointer::parse(&large), None, "oversized content is never a pointer");
+}
+
+// ============================================================================
+// .gitattributes Tests
+// ============================================================================
+
+#[test]
+fn detects_tracked_extensions() {
+    let attrs = "*.psd filter=lfs diff=lfs merge=lfs -text\n*.txt text eol=lf\n# comment\n*.MOV filter=lfs -text\n";
+
+    assert_eq!(tracked_extensions(attrs), vec!["psd", "mov"]);
+    assert!(is_tracked(attrs, "edit.PSD"));
+    assert!(is_tracked(attrs, "clip.mov"));
+    assert!(!is_tracked(attrs, "notes.txt"));
+    assert!(!is_tracked(attrs, "noext"));
+}
+
+#[test]
+fn lfs_rules_merge_idempotently() {
+    let existing = "*.txt text eol=lf\n*.mp4 filter=lfs diff=lfs merge=lfs -text\n";
+
+    let merged = with_lfs_rules(existing);
+    assert!(merged.starts_with(existing), "existing rules are kept");
+    assert_eq!(merged.matches("*.mp4 ").count(), 1);
+    assert_eq!(tracked_extensions(&merged).len(), LFS_EXTENSIONS.len());
+    assert!(is_tracked(&merged, "IMG_0001.CR2"));
+
+    assert_eq!(with_lfs_rules(&merged), merged);
+    assert_eq!(tracked_extensions(&with_lfs_rules("")).len(), LFS_EXTENSIONS.len());
+}
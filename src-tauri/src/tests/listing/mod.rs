@@ -0,0 +1,6 @@
+//! Listing Module Tests
+//!
+//! Organized by functionality:
+//! - `pagination_tests` - Cursor encoding, album filtering and page bounds
+
+pub mod pagination_tests;
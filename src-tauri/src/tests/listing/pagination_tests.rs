@@ -0,0 +1,70 @@
+//! Pagination Tests
+//!
+//! Tests for:
+//! - Cursor encode/decode roundtrip and rejection of garbage
+//! - Album entry filtering from a recursive tree
+//! - Page bounds and next offsets
+
+use crate::git_data::TreeEntry;
+use crate::listing::{album_entries, decode_cursor, encode_cursor, page_bounds, PageCursor};
+
+fn blob(path: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.to_string(),
+        mode: "100644".to_string(),
+        kind: "blob".to_string(),
+        sha: format!("sha-{}", path),
+        size: Some(10),
+    }
+}
+
+// ============================================================================
+// Cursor Tests
+// ============================================================================
+
+#[test]
+fn cursor_roundtrip() {
+    let cursor = PageCursor {
+        shard: 1,
+        commit_sha: "abc123".to_string(),
+        tree_sha: "def456".to_string(),
+        offset: 200,
+    };
+
+    let encoded = encode_cursor(&cursor);
+    assert!(encoded.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    assert_eq!(decode_cursor(&encoded).expect("decode"), cursor);
+}
+
+#[test]
+fn cursor_rejects_garbage() {
+    assert!(decode_cursor("not a cursor!").is_err());
+    assert!(decode_cursor("e30").is_err(), "an empty JSON object lacks the cursor fields");
+}
+
+// ============================================================================
+// Paging Tests
+// ============================================================================
+
+#[test]
+fn album_entries_lists_direct_photos_only() {
+    let tree = vec![
+        blob("photos/trip/a.jpg"),
+        blob("photos/trip/b.vxe"),
+        blob("photos/trip/.vortex-album.json"),
+        blob("photos/trip/notes.txt"),
+        blob("photos/trip/day2/c.jpg"),
+        blob("photos/trip-2/d.jpg"),
+    ];
+
+    let names: Vec<_> = album_entries(&tree, "photos/trip/").iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(names, vec!["photos/trip/a.jpg", "photos/trip/b.vxe"]);
+}
+
+#[test]
+fn page_bounds_walks_listing() {
+    assert_eq!(page_bounds(250, 0, 100), (0, 100, Some(100)));
+    assert_eq!(page_bounds(250, 200, 100), (200, 250, None));
+    assert_eq!(page_bounds(100, 0, 100), (0, 100, None));
+    assert_eq!(page_bounds(10, 50, 100), (10, 10, None), "stale offsets clamp to the end");
+}
@@ -0,0 +1,6 @@
+//! Secure Message Thread Tests
+//!
+//! Organized by functionality:
+//! - `thread_tests` - Thread index, pagination and message sealing
+
+pub mod thread_tests;
@@ -0,0 +1,106 @@
+//! Message Thread Tests
+//!
+//! Tests for:
+//! - Thread index creation and sequencing
+//! - Newest-first pagination with `before_seq` cursors
+//! - Sealing a message for every participant and verifying its sender
+
+use crate::crypto::{generate_keypair, release_keypair, KeypairInfo};
+use crate::threads::{message_path, open_message, page, seal_message, validate_thread_id, MessageRef, ThreadIndex};
+
+fn refs(seqs: std::ops::RangeInclusive<u64>) -> Vec<MessageRef> {
+    seqs.map(|seq| MessageRef {
+        seq,
+        sender: "a".into(),
+        sent_at: seq,
+        size: 1,
+    })
+    .collect()
+}
+
+fn keypair() -> KeypairInfo {
+    generate_keypair().expect("keypair generation")
+}
+
+#[test]
+fn new_thread_requires_a_recipient_and_dedups() {
+    let alice = keypair();
+    let bob = keypair();
+
+    assert!(ThreadIndex::new("0123abcd", alice.public_bundle.clone(), vec![]).is_err());
+    assert!(ThreadIndex::new("0123abcd", alice.public_bundle.clone(), vec![alice.public_bundle.clone()]).is_err());
+
+    let index = ThreadIndex::new(
+        "0123abcd",
+        alice.public_bundle.clone(),
+        vec![bob.public_bundle.clone(), bob.public_bundle.clone()],
+    )
+    .unwrap();
+    assert_eq!(index.participants.len(), 2);
+    assert_eq!(index.next_seq(), 1);
+    assert!(index.participant(&bob.key_id).is_some());
+
+    release_keypair(alice.handle).unwrap();
+    release_keypair(bob.handle).unwrap();
+}
+
+#[test]
+fn thread_ids_and_paths_are_strict() {
+    assert!(validate_thread_id("00ff00ff00ff00ff").is_ok());
+    assert!(validate_thread_id("00FF00FF").is_err());
+    assert!(validate_thread_id("../../etc").is_err());
+    assert!(validate_thread_id("abc").is_err());
+    assert_eq!(message_path("00ff00ff", 7), "messages/threads/00ff00ff/0000000007.msg");
+}
+
+#[test]
+fn pages_walk_back_from_the_newest_message() {
+    let messages = refs(1..=5);
+
+    let (first, next) = page(&messages, None, 2);
+    assert_eq!(first.iter().map(|m| m.seq).collect::<Vec<_>>(), vec![4, 5]);
+    assert_eq!(next, Some(4));
+
+    let (second, next) = page(&messages, next, 2);
+    assert_eq!(second.iter().map(|m| m.seq).collect::<Vec<_>>(), vec![2, 3]);
+
+    let (last, next) = page(&messages, next, 2);
+    assert_eq!(last.iter().map(|m| m.seq).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(next, None);
+
+    let (empty, next) = page(&[], None, 10);
+    assert!(empty.is_empty() && next.is_none());
+}
+
+#[test]
+fn sealed_message_opens_for_every_participant_only() {
+    let alice = keypair();
+    let bob = keypair();
+    let eve = keypair();
+    let index = ThreadIndex::new("0123abcd", alice.public_bundle.clone(), vec![bob.public_bundle.clone()]).unwrap();
+
+    let envelope = seal_message(alice.handle, &index, 1, 42, b"hello bob").unwrap();
+    assert_eq!(envelope.sender, alice.key_id);
+    assert_eq!(envelope.payloads.len(), 2);
+
+    for reader in [&alice, &bob] {
+        let message = open_message(reader.handle, &index, &envelope).unwrap();
+        assert_eq!(message.content, "hello bob");
+        assert!(message.verified);
+    }
+    assert!(open_message(eve.handle, &index, &envelope).is_err());
+
+    // Moving the message to another position breaks the AAD binding
+    let mut moved = envelope.clone();
+    moved.seq = 2;
+    assert!(open_message(bob.handle, &index, &moved).is_err());
+
+    // A forged sender no longer verifies
+    let mut forged = envelope;
+    forged.sender = bob.key_id.clone();
+    assert!(!open_message(bob.handle, &index, &forged).unwrap().verified);
+
+    for kp in [alice, bob, eve] {
+        release_keypair(kp.handle).unwrap();
+    }
+}
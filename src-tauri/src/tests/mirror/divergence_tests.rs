@@ -0,0 +1,75 @@
+//! Album Mirror Tests
+//!
+//! Tests for:
+//! - Mapping primary paths to mirror paths
+//! - Album listings and divergence reports
+
+use std::collections::BTreeMap;
+
+use crate::mirror::{album_listing, diff_listings, MirrorConfig, DEFAULT_API_BASE};
+
+fn config() -> MirrorConfig {
+    MirrorConfig {
+        repo: "alice/photos".into(),
+        album_path: "albums/trip".into(),
+        mirror_repo: "alice-backup/photos".into(),
+        mirror_album_path: "backup/trip".into(),
+        api_base: DEFAULT_API_BASE.into(),
+    }
+}
+
+fn listing(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries.iter().map(|(p, s)| (p.to_string(), s.to_string())).collect()
+}
+
+// ============================================================================
+// Path Mapping Tests
+// ============================================================================
+
+#[test]
+fn maps_album_paths_to_mirror() {
+    let m = config();
+
+    assert_eq!(m.mirror_path("alice/photos", "albums/trip/a.jpg"), Some("backup/trip/a.jpg".into()));
+    assert_eq!(m.mirror_path("alice/photos", "albums/trip/day1/b.jpg"), Some("backup/trip/day1/b.jpg".into()));
+
+    assert_eq!(m.mirror_path("bob/photos", "albums/trip/a.jpg"), None, "other repositories are ignored");
+    assert_eq!(m.mirror_path("alice/photos", "albums/trip2/a.jpg"), None, "sibling albums are not covered");
+    assert_eq!(m.mirror_path("alice/photos", "albums/trip"), None);
+}
+
+#[test]
+fn config_defaults_api_base() {
+    let json = r#"{"repo":"a/b","album_path":"x","mirror_repo":"c/d","mirror_album_path":"x"}"#;
+    let m: MirrorConfig = serde_json::from_str(json).unwrap();
+    assert_eq!(m.api_base, DEFAULT_API_BASE);
+    assert_eq!(m.id(), "a/b:x");
+}
+
+// ============================================================================
+// Divergence Tests
+// ============================================================================
+
+#[test]
+fn album_listing_strips_prefix() {
+    let entries = [("albums/trip/a.jpg", "s1"), ("albums/trip/x/b.jpg", "s2"), ("albums/other/c.jpg", "s3")];
+    let listing = album_listing(entries.iter().copied(), "/albums/trip/");
+
+    assert_eq!(listing.len(), 2);
+    assert_eq!(listing["a.jpg"], "s1");
+    assert_eq!(listing["x/b.jpg"], "s2");
+}
+
+#[test]
+fn reports_missing_extra_and_differing() {
+    let primary = listing(&[("a.jpg", "s1"), ("b.jpg", "s2"), ("c.jpg", "s3")]);
+    let mirror = listing(&[("a.jpg", "s1"), ("b.jpg", "old"), ("z.jpg", "s9")]);
+
+    let report = diff_listings(&primary, &mirror);
+    assert_eq!(report.missing_on_mirror, vec!["c.jpg"]);
+    assert_eq!(report.extra_on_mirror, vec!["z.jpg"]);
+    assert_eq!(report.differing, vec!["b.jpg"]);
+    assert!(!report.in_sync);
+
+    assert!(diff_listings(&primary, &primary).in_sync);
+}
@@ -0,0 +1,8 @@
+//! Mirror Module Tests
+//!
+//! Organized by functionality:
+//! - `divergence_tests` - Path mapping and mirror divergence reports
+//! - `webdav_tests` - Album backups to WebDAV servers
+
+pub mod divergence_tests;
+pub mod webdav_tests;
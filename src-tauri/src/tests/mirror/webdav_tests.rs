@@ -0,0 +1,138 @@
+//! WebDAV Backend Tests
+//!
+//! Tests for:
+//! - Reading PROPFIND listings and Content-Range headers
+//! - Server URLs and the paths below them
+//! - Backup records and the files an album backup holds
+
+use crate::git_data::{index_blobs, TreeEntry};
+use crate::local_store::LocalStore;
+use crate::storage_backend::{
+    album_blobs, backed_up_in, backup_folder, forget_backup_in, record_backup_in, validate_server_url, BackedUpFile,
+    StoredFile,
+};
+use crate::webdav::{parse_content_range, parse_multistatus, webdav_url};
+
+const REPO: &str = "alice/photos";
+
+const LISTING: &str = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns">
+  <d:response>
+    <d:href>/remote.php/dav/files/alice/Vortex/alice/photos/photos/Rome/</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+    <d:propstat>
+      <d:prop><d:getcontentlength/></d:prop>
+      <d:status>HTTP/1.1 404 Not Found</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/remote.php/dav/files/alice/Vortex/alice/photos/photos/Rome/Day%202.jpg</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype/><d:getcontentlength>2048</d:getcontentlength></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>https://nas.local/dav/alice/photos/photos/Rome/.vortex-album.json</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype/></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/remote.php/dav/files/alice/Vortex/alice/photos/photos/Rome/Day%202/</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+
+fn entry(path: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.into(),
+        mode: "100644".into(),
+        kind: "blob".into(),
+        sha: format!("sha-{}", path),
+        size: Some(10),
+    }
+}
+
+// ============================================================================
+// Protocol Tests
+// ============================================================================
+
+#[test]
+fn listings_hold_files_but_not_folders() {
+    let files = parse_multistatus(LISTING).unwrap();
+    assert_eq!(
+        files,
+        [
+            StoredFile { name: ".vortex-album.json".into(), size: None },
+            StoredFile { name: "Day 2.jpg".into(), size: Some(2048) },
+        ]
+    );
+    assert!(parse_multistatus("<html>").is_err());
+    assert!(parse_multistatus(r#"<d:multistatus xmlns:d="DAV:"/>"#).unwrap().is_empty());
+}
+
+#[test]
+fn content_ranges_are_parsed() {
+    assert_eq!(parse_content_range("bytes 0-8388607/20000000"), Some((0, 8388607, Some(20000000))));
+    assert_eq!(parse_content_range("bytes 100-199/*"), Some((100, 199, None)));
+    assert_eq!(parse_content_range("items 0-1/2"), None);
+    assert_eq!(parse_content_range("bytes */2000"), None);
+}
+
+#[test]
+fn paths_are_encoded_below_the_server_folder() {
+    let base = "https://cloud.example.com/remote.php/dav/files/alice/Vortex";
+    assert_eq!(
+        webdav_url(base, "alice/photos/photos/Rome/Day 2#1.jpg").unwrap().as_str(),
+        "https://cloud.example.com/remote.php/dav/files/alice/Vortex/alice/photos/photos/Rome/Day%202%231.jpg"
+    );
+    assert_eq!(webdav_url(&format!("{}/", base), "").unwrap().as_str(), base);
+    assert_eq!(backup_folder(REPO, "/photos/Rome/"), "alice/photos/photos/Rome");
+}
+
+#[test]
+fn servers_other_than_this_machine_need_https() {
+    assert_eq!(validate_server_url("https://nas.example.com/dav/").unwrap(), "https://nas.example.com/dav");
+    assert!(validate_server_url("http://localhost:8080/dav").is_ok());
+    assert!(validate_server_url("http://nas.local/dav").is_err());
+    assert!(validate_server_url("https://nas.example.com/dav?user=alice").is_err());
+}
+
+// ============================================================================
+// Backup Tests
+// ============================================================================
+
+#[test]
+fn album_backups_include_the_manifest() {
+    let index = index_blobs(
+        ["photos/Rome/.vortex-album.json", "photos/Rome/a.jpg", "photos/Rome/a.jpg.vxsig", "photos/Rome/Day 2/b.jpg"]
+            .into_iter()
+            .map(entry)
+            .collect(),
+    );
+    let files: Vec<String> = album_blobs(&index, "photos/Rome").into_iter().map(|e| e.path).collect();
+    assert_eq!(files, ["photos/Rome/.vortex-album.json", "photos/Rome/a.jpg", "photos/Rome/a.jpg.vxsig"]);
+}
+
+#[test]
+fn backups_are_recorded_per_backend() {
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    let file = BackedUpFile::for_content(REPO, "photos/Rome/a.jpg", "sha-1", b"photo");
+    record_backup_in(&store, "webdav", &file).unwrap();
+
+    assert_eq!(backed_up_in(&store, "webdav", REPO, "photos/Rome/a.jpg").unwrap(), Some(file.clone()));
+    assert_eq!(backed_up_in(&store, "s3", REPO, "photos/Rome/a.jpg").unwrap(), None);
+    assert!(file.check(b"photo").is_ok());
+    assert!(file.check(b"tampered").is_err());
+
+    assert!(forget_backup_in(&store, "webdav", REPO, "photos/Rome/a.jpg").unwrap());
+    assert_eq!(backed_up_in(&store, "webdav", REPO, "photos/Rome/a.jpg").unwrap(), None);
+}
@@ -4,10 +4,44 @@
 //! - `crypto/` - Cryptographic operation tests
 //! - `compress/` - Compression algorithm tests  
 //! - `integration/` - End-to-end security pipeline tests
+//! - `sharing/` - Album share link, LAN gallery and peer transfer tests
+//! - `album/` - Encrypted album, comment and multi-device merge tests
+//! - `batch/` - Batch delete/move planning tests
+//! - `stats/` - Album statistics and storage quota tests
+//! - `sharding/` - Repository shard planning tests
+//! - `listing/` - Paginated listing tests
+//! - `offline/` - Offline operation queue tests
+//! - `watcher/` - Folder watcher filtering tests
+//! - `sync/` - Two-way sync planning, schedules, upload deduplication and headless CLI tests
+//! - `lfs/` - Git LFS pointer tests
+//! - `mirror/` - Album mirror divergence and WebDAV backup tests
+//! - `retry/` - Retry policy and circuit breaker tests
+//! - `errors/` - Typed GitHub error tests
+//! - `organize/` - Photo rename, move and classification tests
+//! - `history/` - Album history and restore tests
+//! - `remote/` - Remote change notification tests
+//! - `policy/` - Upload and retention policy tests
+//! - `security/` - Security audit and activity log tests
+//! - `messages/` - Secure message thread tests
+//! - `contacts/` - Contact book tests
+//! - `profiles/` - Identity profile tests
+//! - `store/` - Encrypted local store tests
+//! - `archive/` - Album archive container tests
+//! - `pipeline/` - Pipeline preset tests
+//! - `thumbnails/` - Thumbnail rendering and cache tests
+//! - `raw/` - Camera RAW preview and pairing tests
+//! - `video/` - Video probing and chunked storage tests
+//! - `diagnostics/` - Log redaction, diagnostics bundle and health check tests
+//! - `jobs/` - Cancellable job and scheduler tests
+//! - `ipc/` - Large payload file variants and buffer tests
+//! - `common` - Fixtures shared by the categories above
 //!
 //! Run all tests: `cargo test`
 //! Run specific module: `cargo test crypto::` or `cargo test compress::`
 
+#[cfg(test)]
+pub mod common;
+
 #[cfg(test)]
 pub mod crypto;
 
@@ -16,3 +50,93 @@ pub mod compress;
 
 #[cfg(test)]
 pub mod integration;
+
+#[cfg(test)]
+pub mod sharing;
+
+#[cfg(test)]
+pub mod album;
+
+#[cfg(test)]
+pub mod batch;
+
+#[cfg(test)]
+pub mod stats;
+
+#[cfg(test)]
+pub mod sharding;
+
+#[cfg(test)]
+pub mod listing;
+
+#[cfg(test)]
+pub mod offline;
+
+#[cfg(test)]
+pub mod watcher;
+
+#[cfg(test)]
+pub mod sync;
+
+#[cfg(test)]
+pub mod lfs;
+
+#[cfg(test)]
+pub mod mirror;
+
+#[cfg(test)]
+pub mod retry;
+
+#[cfg(test)]
+pub mod errors;
+
+#[cfg(test)]
+pub mod organize;
+
+#[cfg(test)]
+pub mod history;
+
+#[cfg(test)]
+pub mod remote;
+
+#[cfg(test)]
+pub mod policy;
+
+#[cfg(test)]
+pub mod security;
+
+#[cfg(test)]
+pub mod messages;
+
+#[cfg(test)]
+pub mod contacts;
+
+#[cfg(test)]
+pub mod profiles;
+
+#[cfg(test)]
+pub mod store;
+
+#[cfg(test)]
+pub mod archive;
+
+#[cfg(test)]
+pub mod pipeline;
+
+#[cfg(test)]
+pub mod thumbnails;
+
+#[cfg(test)]
+pub mod raw;
+
+#[cfg(test)]
+pub mod video;
+
+#[cfg(test)]
+pub mod diagnostics;
+
+#[cfg(test)]
+pub mod jobs;
+
+#[cfg(test)]
+pub mod ipc;
@@ -0,0 +1,6 @@
+//! Offline Queue Tests
+//!
+//! Organized by functionality:
+//! - `queue_tests` - Queue persistence and replay conflict decisions
+
+pub mod queue_tests;
@@ -0,0 +1,88 @@
+//! Offline Queue Tests
+//!
+//! Tests for:
+//! - Replay decisions for uploads and deletes
+//! - Queue save/load roundtrip and snapshot cleanup
+
+use crate::offline_queue::{
+    replay_decision, OfflineQueue, OperationStatus, QueuedAction, QueuedOperation, ReplayDecision,
+};
+use crate::tests::common::temp_dir;
+
+fn upload() -> QueuedAction {
+    QueuedAction::Upload {
+        remote_path: "photos/a.jpg".to_string(),
+        source_name: "a.jpg".to_string(),
+    }
+}
+
+fn delete() -> QueuedAction {
+    QueuedAction::Delete {
+        remote_path: "photos/a.jpg".to_string(),
+    }
+}
+
+// ============================================================================
+// Replay Decision Tests
+// ============================================================================
+
+#[test]
+fn upload_applies_when_remote_unchanged() {
+    assert_eq!(replay_decision(&upload(), None, None), ReplayDecision::Apply);
+    assert_eq!(replay_decision(&upload(), Some("abc"), Some("abc")), ReplayDecision::Apply);
+}
+
+#[test]
+fn upload_conflicts_when_remote_changed() {
+    assert_eq!(replay_decision(&upload(), None, Some("abc")), ReplayDecision::Conflict);
+    assert_eq!(replay_decision(&upload(), Some("abc"), Some("def")), ReplayDecision::Conflict);
+    assert_eq!(replay_decision(&upload(), Some("abc"), None), ReplayDecision::Conflict);
+}
+
+#[test]
+fn delete_decisions() {
+    assert_eq!(replay_decision(&delete(), Some("abc"), Some("abc")), ReplayDecision::Apply);
+    assert_eq!(replay_decision(&delete(), Some("abc"), Some("def")), ReplayDecision::Conflict);
+    assert_eq!(replay_decision(&delete(), Some("abc"), None), ReplayDecision::AlreadyApplied);
+}
+
+// ============================================================================
+// Persistence Tests
+// ============================================================================
+
+#[test]
+fn queue_roundtrip_and_remove() {
+    let dir = temp_dir("queue", "roundtrip");
+    std::fs::write(dir.join("op1"), b"snapshot").unwrap();
+
+    let mut queue = OfflineQueue::default();
+    queue.operations.push(QueuedOperation {
+        id: "op1".to_string(),
+        repo: "alice/photos".to_string(),
+        action: upload(),
+        expected_sha: None,
+        queued_at: 1,
+        attempts: 0,
+        status: OperationStatus::Pending,
+        last_error: None,
+    });
+    queue.save_to(&dir).expect("save");
+
+    let mut loaded = OfflineQueue::load_from(&dir).expect("load");
+    assert_eq!(loaded.operations.len(), 1);
+    assert_eq!(loaded.operations[0].action, upload());
+    assert_eq!(loaded.operations[0].remote_path(), "photos/a.jpg");
+
+    loaded.remove(&dir, "op1");
+    assert!(loaded.operations.is_empty());
+    assert!(!dir.join("op1").exists(), "upload snapshot should be removed with the operation");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing_queue_loads_empty() {
+    let dir = temp_dir("queue", "missing");
+    assert!(OfflineQueue::load_from(&dir).expect("load").operations.is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
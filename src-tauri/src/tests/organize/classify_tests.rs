@@ -0,0 +1,151 @@
+//! Photo Classification Tests
+//!
+//! Tests for:
+//! - Screenshot, burst, selfie and panorama labels
+//! - Burst IDs from names and capture times
+//! - Category counts and excluded paths
+
+use crate::classify::{category_counts, classify, excluded_paths, PhotoCategory, PhotoFacts};
+use crate::tests::common::png;
+
+fn facts(name: &str) -> PhotoFacts {
+    PhotoFacts { name: name.into(), ..PhotoFacts::default() }
+}
+
+fn shot(name: &str, camera: &str, taken_at: i64) -> PhotoFacts {
+    PhotoFacts {
+        camera: Some(camera.into()),
+        taken_at: Some(taken_at),
+        ..facts(name)
+    }
+}
+
+fn categories(photos: &[PhotoFacts]) -> Vec<Vec<PhotoCategory>> {
+    classify(photos).into_iter().map(|f| f.categories).collect()
+}
+
+// ============================================================================
+// Label Tests
+// ============================================================================
+
+#[test]
+fn screenshots_are_known_by_name() {
+    let labels = categories(&[
+        facts("Screenshot_20240101-120000.png"),
+        facts("Captura de Tela 2024-01-01 às 12.00.00.png"),
+        facts("Bildschirmfoto 2024-01-01.png"),
+        facts("IMG_0001.JPG"),
+    ]);
+    assert_eq!(labels[0], [PhotoCategory::Screenshot]);
+    assert_eq!(labels[1], [PhotoCategory::Screenshot]);
+    assert_eq!(labels[2], [PhotoCategory::Screenshot]);
+    assert!(labels[3].is_empty());
+}
+
+#[test]
+fn selfies_come_from_the_front_camera() {
+    let selfie = PhotoFacts {
+        lens_model: Some("iPhone 13 front camera 2.71mm f/2.2".into()),
+        ..facts("IMG_0002.HEIC")
+    };
+    let back = PhotoFacts {
+        lens_model: Some("iPhone 13 back dual wide camera 5.1mm f/1.6".into()),
+        ..facts("IMG_0003.HEIC")
+    };
+    assert_eq!(categories(&[selfie, back]), [vec![PhotoCategory::Selfie], vec![]]);
+}
+
+#[test]
+fn panoramas_by_name_or_aspect() {
+    let wide = PhotoFacts::from_content("IMG_0004.png", &png(300, 100));
+    assert_eq!((wide.width, wide.height), (Some(300), Some(100)));
+    let tall_screenshot = PhotoFacts { width: Some(1170), height: Some(4000), ..facts("Screenshot_1.png") };
+
+    let labels = categories(&[
+        wide,
+        PhotoFacts::from_content("IMG_0005.png", &png(200, 100)),
+        facts("PANO_20240101_120000.jpg"),
+        tall_screenshot,
+    ]);
+    assert_eq!(labels[0], [PhotoCategory::Panorama]);
+    assert!(labels[1].is_empty());
+    assert_eq!(labels[2], [PhotoCategory::Panorama]);
+    assert_eq!(labels[3], [PhotoCategory::Screenshot], "long screenshots are not panoramas");
+}
+
+// ============================================================================
+// Burst Tests
+// ============================================================================
+
+#[test]
+fn burst_names_share_an_id() {
+    let files = classify(&[
+        facts("00000IMG_00000_BURST20201231120000123_COVER.jpg"),
+        facts("00001IMG_00001_BURST20201231120000123.jpg"),
+        facts("20201231_120000_BURST001.jpg"),
+        facts("20201231_120000_BURST002.jpg"),
+    ]);
+    assert!(files.iter().all(|f| f.categories == [PhotoCategory::Burst]));
+    assert_eq!(files[0].burst_id, files[1].burst_id);
+    assert_eq!(files[2].burst_id.as_deref(), Some("20201231_120000"));
+    assert_eq!(files[2].burst_id, files[3].burst_id);
+    assert_ne!(files[0].burst_id, files[2].burst_id);
+}
+
+#[test]
+fn quick_runs_from_one_camera_are_bursts() {
+    let files = classify(&[
+        shot("IMG_0012.JPG", "Canon EOS R6", 1_000),
+        shot("IMG_0010.JPG", "Canon EOS R6", 999),
+        shot("IMG_0011.JPG", "Canon EOS R6", 999),
+        // Too far apart, and another camera at the same moment
+        shot("IMG_0013.JPG", "Canon EOS R6", 1_005),
+        shot("DSC_0001.JPG", "Nikon Z6", 1_000),
+        shot("DSC_0002.JPG", "Nikon Z6", 1_001),
+    ]);
+    for file in &files[..3] {
+        assert_eq!(file.categories, [PhotoCategory::Burst]);
+        assert_eq!(file.burst_id.as_deref(), Some("IMG_0010.JPG"), "named after the first frame");
+    }
+    assert!(files[3..].iter().all(|f| f.categories.is_empty()), "two frames are not a burst");
+}
+
+// ============================================================================
+// Grouping Tests
+// ============================================================================
+
+#[test]
+fn counts_cover_every_label() {
+    let files = classify(&[
+        facts("Screenshot_1.png"),
+        facts("Screenshot_2.png"),
+        facts("PANO_1.jpg"),
+        facts("IMG_0001.JPG"),
+    ]);
+    let counts = category_counts(&files);
+    assert_eq!(counts.get(&PhotoCategory::Screenshot), Some(&2));
+    assert_eq!(counts.get(&PhotoCategory::Panorama), Some(&1));
+    assert_eq!(counts.get(&PhotoCategory::Burst), None);
+    assert_eq!(serde_json::to_value(&counts).unwrap()["screenshot"], 2);
+}
+
+#[test]
+fn excluded_paths_follow_the_categories() {
+    let dir = std::env::temp_dir().join(format!("vortex-classify-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let paths: Vec<_> = ["Screenshot_1.png", "IMG_0001.png", "wide.png"]
+        .iter()
+        .map(|name| dir.join(name))
+        .collect();
+    std::fs::write(&paths[0], png(100, 200)).unwrap();
+    std::fs::write(&paths[1], png(150, 100)).unwrap();
+    std::fs::write(&paths[2], png(400, 100)).unwrap();
+
+    let dropped = excluded_paths(&paths, &[PhotoCategory::Screenshot, PhotoCategory::Panorama]);
+    assert!(dropped.contains(&paths[0]));
+    assert!(!dropped.contains(&paths[1]));
+    assert!(dropped.contains(&paths[2]));
+    assert!(excluded_paths(&paths, &[PhotoCategory::Selfie]).is_empty());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,10 @@
+//! Organize Module Tests
+//!
+//! Organized by functionality:
+//! - `rename_move_tests` - Rename and move target paths and cover tracking
+//! - `classify_tests` - Screenshot, burst, selfie and panorama labels
+//! - `tagging_tests` - Tags, favorites and ratings in manifests and the catalog
+
+pub mod rename_move_tests;
+pub mod classify_tests;
+pub mod tagging_tests;
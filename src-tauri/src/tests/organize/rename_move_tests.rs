@@ -0,0 +1,53 @@
+//! Photo Rename and Move Tests
+//!
+//! Tests for:
+//! - Rename target paths and name validation
+//! - Move target paths between albums
+//! - Cover photo tracking across renames and moves
+
+use crate::album::AlbumManifest;
+use crate::organize::{move_target, rename_target, retarget_cover, validate_photo_name};
+
+// ============================================================================
+// Rename Tests
+// ============================================================================
+
+#[test]
+fn rename_stays_in_folder() {
+    assert_eq!(rename_target("photos/Trips/a.jpg", "beach.jpg").unwrap(), "photos/Trips/beach.jpg");
+    assert_eq!(rename_target("/photos/a.jpg/", "b c.jpg").unwrap(), "photos/bc.jpg");
+    assert!(rename_target("photos/a.jpg", "a.jpg").is_err());
+}
+
+#[test]
+fn rename_rejects_reserved_names() {
+    assert!(validate_photo_name("").is_err());
+    assert!(validate_photo_name(".vortex-album.json").is_err());
+    assert!(validate_photo_name("deadbeef.vxe").is_err());
+    assert_eq!(validate_photo_name("../evil.jpg").unwrap(), "_evil.jpg");
+}
+
+// ============================================================================
+// Move Tests
+// ============================================================================
+
+#[test]
+fn move_keeps_file_name() {
+    assert_eq!(move_target("photos/Trips/a.jpg", "photos/Trips/2024").unwrap(), "photos/Trips/2024/a.jpg");
+    assert!(move_target("photos/Trips/a.jpg", "photos/Trips").is_err());
+    assert!(move_target("photos/a.jpg", "docs").is_err());
+    assert!(move_target("photos/a.jpg", "photos/../docs").is_err());
+}
+
+#[test]
+fn cover_follows_the_photo() {
+    let mut m = AlbumManifest::new(false, None);
+    m.cover = Some("a.jpg".into());
+
+    assert!(!retarget_cover(&mut m, "other.jpg", Some("x.jpg")));
+    assert!(retarget_cover(&mut m, "a.jpg", Some("b.jpg")));
+    assert_eq!(m.cover.as_deref(), Some("b.jpg"));
+
+    assert!(retarget_cover(&mut m, "b.jpg", None));
+    assert!(m.cover.is_none());
+}
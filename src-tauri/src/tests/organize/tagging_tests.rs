@@ -0,0 +1,162 @@
+//! Tagging Tests
+//!
+//! Tests for:
+//! - Organization in plain and encrypted album manifests
+//! - Rating validation
+//! - Importing organization into the catalog and querying it
+
+use std::collections::BTreeMap;
+
+use crate::album::AlbumManifest;
+use crate::catalog::{
+    album_photos_in, import_organization_in, query_photos_in, reconcile_album_in, PhotoQuery,
+};
+use crate::github::PhotoItem;
+use crate::local_store::LocalStore;
+use crate::tagging::{read_organization, validate_rating, write_organization, PhotoOrganization, MAX_RATING};
+use crate::video::MediaType;
+
+const ALBUM_ID: &str = "alice/photos:photos/Trips";
+const REPO: &str = "alice/photos";
+
+fn organized(tags: &[&str], favorite: bool, rating: Option<u8>) -> PhotoOrganization {
+    PhotoOrganization {
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        favorite,
+        rating,
+    }
+}
+
+fn photo(name: &str) -> PhotoItem {
+    PhotoItem {
+        name: name.into(),
+        url: format!("https://example.com/{}", name),
+        sha: format!("sha-{}", name),
+        size: None,
+        encrypted: false,
+        display_name: None,
+        repo: None,
+        caption: None,
+        exif: None,
+        raw_companion: None,
+        media_type: MediaType::Photo,
+    }
+}
+
+fn sample() -> BTreeMap<String, PhotoOrganization> {
+    BTreeMap::from([
+        ("a.jpg".to_string(), organized(&["Praia"], true, Some(5))),
+        ("b.jpg".to_string(), organized(&[], false, Some(2))),
+        ("c.jpg".to_string(), PhotoOrganization::default()),
+    ])
+}
+
+// ============================================================================
+// Manifest Tests
+// ============================================================================
+
+#[test]
+fn plain_albums_keep_organization_in_the_clear() {
+    let mut manifest = AlbumManifest::new(false, None);
+    write_organization(&mut manifest, None, ALBUM_ID, sample()).unwrap();
+    assert_eq!(manifest.organization.len(), 2, "photos with nothing set are dropped");
+    assert!(manifest.sealed_organization.is_none());
+
+    let json = serde_json::to_value(&manifest).unwrap();
+    assert_eq!(json["organization"]["a.jpg"]["tags"][0], "Praia");
+    assert!(json["organization"]["b.jpg"].get("favorite").is_none());
+
+    let read = read_organization(&manifest, None, ALBUM_ID).unwrap();
+    assert_eq!(read["b.jpg"].rating, Some(2));
+}
+
+#[test]
+fn encrypted_albums_seal_organization() {
+    let key = [3u8; 32];
+    let mut manifest = AlbumManifest::new(true, None);
+    assert!(write_organization(&mut manifest, None, ALBUM_ID, sample()).is_err());
+    write_organization(&mut manifest, Some(&key), ALBUM_ID, sample()).unwrap();
+
+    assert!(manifest.organization.is_empty());
+    let json = serde_json::to_string(&manifest).unwrap();
+    assert!(!json.contains("Praia"));
+
+    let read = read_organization(&manifest, Some(&key), ALBUM_ID).unwrap();
+    assert_eq!(read["a.jpg"], organized(&["Praia"], true, Some(5)));
+    assert!(read_organization(&manifest, None, ALBUM_ID).is_err());
+    assert!(read_organization(&manifest, Some(&[4u8; 32]), ALBUM_ID).is_err());
+    assert!(read_organization(&manifest, Some(&key), "alice/photos:photos/Other").is_err());
+
+    // Clearing everything leaves nothing sealed
+    write_organization(&mut manifest, Some(&key), ALBUM_ID, BTreeMap::new()).unwrap();
+    assert!(manifest.sealed_organization.is_none());
+    assert!(read_organization(&manifest, None, ALBUM_ID).unwrap().is_empty());
+}
+
+#[test]
+fn ratings_are_one_to_five_stars() {
+    assert!(validate_rating(None).is_ok());
+    assert!(validate_rating(Some(1)).is_ok());
+    assert!(validate_rating(Some(MAX_RATING)).is_ok());
+    assert!(validate_rating(Some(0)).is_err());
+    assert!(validate_rating(Some(MAX_RATING + 1)).is_err());
+}
+
+// ============================================================================
+// Catalog Tests
+// ============================================================================
+
+#[test]
+fn imported_organization_survives_reconciles() {
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    let items = [photo("a.jpg"), photo("b.jpg"), photo("c.jpg")];
+    reconcile_album_in(&store, REPO, "photos/Trips", &items).unwrap();
+
+    assert_eq!(import_organization_in(&store, REPO, "photos/Trips", &sample()).unwrap(), 2);
+    assert_eq!(import_organization_in(&store, REPO, "photos/Trips", &sample()).unwrap(), 0);
+
+    let relisted = [PhotoItem { sha: "changed".into(), ..photo("a.jpg") }, photo("b.jpg"), photo("c.jpg")];
+    reconcile_album_in(&store, REPO, "photos/Trips", &relisted).unwrap();
+    let photos = album_photos_in(&store, REPO, "photos/Trips").unwrap().unwrap();
+    assert_eq!(photos[0].organization(), organized(&["Praia"], true, Some(5)));
+
+    // Organization removed on another device is removed here too
+    let cleared = BTreeMap::from([("a.jpg".to_string(), organized(&[], true, None))]);
+    assert_eq!(import_organization_in(&store, REPO, "photos/Trips", &cleared).unwrap(), 2);
+    let photos = album_photos_in(&store, REPO, "photos/Trips").unwrap().unwrap();
+    assert_eq!(photos[0].organization(), organized(&[], true, None));
+    assert_eq!(photos[1].rating, None);
+}
+
+#[test]
+fn queries_combine_filters() {
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    reconcile_album_in(&store, REPO, "photos/Trips", &[photo("a.jpg"), photo("b.jpg"), photo("c.jpg")]).unwrap();
+    reconcile_album_in(&store, REPO, "photos/Home", &[photo("d.jpg")]).unwrap();
+    reconcile_album_in(&store, "bob/photos", "photos/Trips", &[photo("a.jpg")]).unwrap();
+    import_organization_in(&store, REPO, "photos/Trips", &sample()).unwrap();
+    let home = BTreeMap::from([("d.jpg".to_string(), organized(&["praia"], false, Some(4)))]);
+    import_organization_in(&store, REPO, "photos/Home", &home).unwrap();
+    import_organization_in(&store, "bob/photos", "photos/Trips", &sample()).unwrap();
+
+    let names = |query: PhotoQuery| -> Vec<String> {
+        let mut names: Vec<_> = query_photos_in(&store, REPO, &query).unwrap().into_iter().map(|p| p.path).collect();
+        names.sort();
+        names
+    };
+    assert_eq!(names(PhotoQuery::default()).len(), 4);
+    assert_eq!(names(PhotoQuery { favorite: Some(true), ..Default::default() }), ["photos/Trips/a.jpg"]);
+    assert_eq!(
+        names(PhotoQuery { min_rating: Some(4), ..Default::default() }),
+        ["photos/Home/d.jpg", "photos/Trips/a.jpg"]
+    );
+    assert_eq!(
+        names(PhotoQuery { tag: Some("PRAIA".into()), album: Some("photos/Home/".into()), ..Default::default() }),
+        ["photos/Home/d.jpg"]
+    );
+    assert_eq!(
+        names(PhotoQuery { album: Some("photos/Trips".into()), favorite: Some(false), ..Default::default() }),
+        ["photos/Trips/b.jpg", "photos/Trips/c.jpg"]
+    );
+    assert!(query_photos_in(&store, REPO, &PhotoQuery { min_rating: Some(6), ..Default::default() }).is_err());
+}
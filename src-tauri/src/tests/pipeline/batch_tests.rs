@@ -0,0 +1,177 @@
+//! Batch Pipeline Tests
+//!
+//! Tests for:
+//! - File matching and concurrency settings
+//! - Worker pool progress, failures and cancellation
+//! - Writing pipeline output and the summary report
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::compress_jobs::{collect_files, JobFile, JobKind, JobProgress, JobState};
+use crate::github::AppError;
+use crate::pipeline::{
+    process_folder_file, reverse_pipeline, PipelineConfig, PipelineContext, PipelineLayer, PipelineOperation,
+};
+use crate::pipeline_batch::{
+    build_report, matching_files, resolve_concurrency, run_batch, FileProgress, FileState, MAX_CONCURRENCY,
+};
+use crate::tests::common::temp_dir;
+
+fn job_files(count: usize) -> Vec<JobFile> {
+    (0..count)
+        .map(|i| JobFile {
+            source: PathBuf::from(format!("{:02}.jpg", i)),
+            relative: format!("{:02}.jpg", i),
+            size: 100,
+        })
+        .collect()
+}
+
+fn run(
+    files: &[JobFile],
+    concurrency: usize,
+    stop_on_error: bool,
+    cancel: &AtomicBool,
+    process: impl Fn(&JobFile) -> Result<u64, AppError> + Sync,
+) -> (JobProgress, Vec<FileProgress>, Vec<FileProgress>) {
+    let mut job = JobProgress::new("job-test", JobKind::Pipeline, files);
+    let events = Mutex::new(Vec::new());
+    let finished = run_batch(&mut job, files, concurrency, stop_on_error, cancel, process, |file, _| {
+        events.lock().unwrap().push(file.clone())
+    });
+    (job, finished, events.into_inner().unwrap())
+}
+
+// ============================================================================
+// Selection Tests
+// ============================================================================
+
+#[test]
+fn extensions_filter_files_ignoring_case_and_dots() {
+    let files = vec![
+        JobFile { source: "a.JPG".into(), relative: "a.JPG".into(), size: 1 },
+        JobFile { source: "b.png".into(), relative: "sub/b.png".into(), size: 1 },
+        JobFile { source: "c".into(), relative: "c".into(), size: 1 },
+    ];
+    assert_eq!(matching_files(files.clone(), &[]).len(), 3);
+
+    let matched = matching_files(files, &[".jpg".into(), "png".into()]);
+    let names: Vec<_> = matched.iter().map(|f| f.relative.as_str()).collect();
+    assert_eq!(names, ["a.JPG", "sub/b.png"]);
+}
+
+#[test]
+fn concurrency_is_bounded() {
+    assert_eq!(resolve_concurrency(Some(3)).unwrap(), 3);
+    assert!(resolve_concurrency(Some(0)).is_err());
+    assert!(resolve_concurrency(Some(MAX_CONCURRENCY + 1)).is_err());
+    assert!((1..=MAX_CONCURRENCY).contains(&resolve_concurrency(None).unwrap()));
+}
+
+// ============================================================================
+// Worker Pool Tests
+// ============================================================================
+
+#[test]
+fn every_file_runs_once_within_the_limit() {
+    let files = job_files(12);
+    let running = AtomicUsize::new(0);
+    let peak = AtomicUsize::new(0);
+    let (job, finished, events) = run(&files, 3, false, &AtomicBool::new(false), |_| {
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        running.fetch_sub(1, Ordering::SeqCst);
+        Ok(40)
+    });
+
+    assert_eq!(job.state, JobState::Completed);
+    assert_eq!((job.files_done, job.bytes_done), (12, 1200));
+    assert!(peak.load(Ordering::SeqCst) <= 3);
+    assert_eq!(finished.len(), 12);
+    assert!(finished.windows(2).all(|w| w[0].relative < w[1].relative));
+    assert_eq!(events.iter().filter(|e| e.state == FileState::Processing).count(), 12);
+}
+
+#[test]
+fn failures_are_reported_without_stopping_others() {
+    let files = job_files(6);
+    let (job, finished, _) = run(&files, 2, false, &AtomicBool::new(false), |file| {
+        if file.relative == "03.jpg" {
+            Err(AppError::Validation("corrupt".into()))
+        } else {
+            Ok(1)
+        }
+    });
+
+    assert_eq!(job.state, JobState::Failed);
+    assert_eq!(finished.len(), 6);
+    let failed: Vec<_> = finished.iter().filter(|f| f.state == FileState::Failed).collect();
+    assert_eq!(failed.len(), 1);
+    assert!(failed[0].error.as_deref().unwrap().contains("corrupt"));
+}
+
+#[test]
+fn stop_on_error_and_cancel_leave_files_unprocessed() {
+    let files = job_files(20);
+    let (job, finished, _) = run(&files, 1, true, &AtomicBool::new(false), |_| {
+        Err(AppError::Validation("fail".into()))
+    });
+    assert_eq!(job.state, JobState::Failed);
+    assert_eq!(finished.len(), 1);
+
+    let cancel = AtomicBool::new(false);
+    let (job, finished, _) = run(&files, 1, false, &cancel, |file| {
+        if file.relative == "04.jpg" {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        Ok(1)
+    });
+    assert_eq!(job.state, JobState::Cancelled);
+    assert_eq!(finished.len(), 5);
+}
+
+// ============================================================================
+// Output Tests
+// ============================================================================
+
+#[test]
+fn folder_files_are_processed_and_reported() {
+    let source = temp_dir("batch", "source");
+    let output = temp_dir("batch", "output");
+    std::fs::create_dir_all(source.join("sub")).unwrap();
+    std::fs::write(source.join("a.txt"), b"first file ".repeat(100)).unwrap();
+    std::fs::write(source.join("sub/b.txt"), b"second file ".repeat(100)).unwrap();
+
+    let config = PipelineConfig {
+        name: "Batch".into(),
+        layers: vec![PipelineLayer {
+            id: "compress".into(),
+            operation: PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 },
+            enabled: true,
+            order: 0,
+            condition: None,
+        }],
+        ..Default::default()
+    };
+    let context = PipelineContext::default();
+    let files = collect_files(&source).unwrap();
+    let (job, finished, _) = run(&files, 2, false, &AtomicBool::new(false), |file| {
+        process_folder_file(file, &output, &config, &context).map(|p| p.output_size)
+    });
+    assert_eq!(job.state, JobState::Completed);
+
+    let packed = std::fs::read(output.join("sub/b.txt.vxp")).unwrap();
+    assert_eq!(reverse_pipeline(&packed, &context).unwrap().data, b"second file ".repeat(100));
+
+    let report = build_report(&job, &config, &source.to_string_lossy(), &output.to_string_lossy(), 0, finished);
+    assert_eq!((report.files_succeeded, report.files_failed, report.files_not_processed), (2, 0, 0));
+    assert_eq!(report.bytes_in, 2300);
+    assert!(report.bytes_out < report.bytes_in);
+    assert!(Path::new(&report.output_dir).is_dir());
+
+    let _ = std::fs::remove_dir_all(&source);
+    let _ = std::fs::remove_dir_all(&output);
+}
@@ -0,0 +1,138 @@
+//! Pipeline Checkpoint Tests
+//!
+//! Tests for:
+//! - Writing and reading job checkpoints
+//! - Journal recovery after an interrupted write
+//! - Skipping only files that are done and unchanged
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::compress_jobs::{collect_files, JobFile};
+use crate::pipeline::{process_folder_file, PipelineConfig, PipelineContext, PipelineLayer, PipelineOperation};
+use crate::pipeline_batch::BatchOptions;
+use crate::pipeline_checkpoint::{
+    is_unchanged, read_checkpoint, read_journal, remove_checkpoint, write_checkpoint, FileCheckpoint,
+    JobCheckpoint, Journal, CHECKPOINT_FILE, CHECKPOINT_VERSION, JOURNAL_FILE,
+};
+use crate::tests::common::temp_dir;
+
+fn config() -> PipelineConfig {
+    PipelineConfig {
+        name: "Resumable".into(),
+        layers: vec![PipelineLayer {
+            id: "compress".into(),
+            operation: PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 },
+            enabled: true,
+            order: 0,
+            condition: None,
+        }],
+        ..Default::default()
+    }
+}
+
+fn checkpoint(output_dir: &Path) -> JobCheckpoint {
+    JobCheckpoint {
+        version: CHECKPOINT_VERSION,
+        job_id: "job-resume".into(),
+        folder: "/photos".into(),
+        output_dir: output_dir.to_string_lossy().to_string(),
+        config: config(),
+        options: BatchOptions {
+            extensions: vec!["jpg".into()],
+            ..Default::default()
+        },
+        created_at: 1,
+    }
+}
+
+fn entry(relative: &str, size: u64) -> FileCheckpoint {
+    FileCheckpoint {
+        relative: relative.to_string(),
+        source_hash: "aa".into(),
+        output_hash: "bb".into(),
+        output_size: size,
+    }
+}
+
+// ============================================================================
+// Checkpoint Tests
+// ============================================================================
+
+#[test]
+fn checkpoint_roundtrips_and_resets_journal() {
+    let dir = temp_dir("checkpoint", "roundtrip");
+    std::fs::write(dir.join(JOURNAL_FILE), b"stale\n").unwrap();
+
+    write_checkpoint(&checkpoint(&dir)).unwrap();
+    let loaded = read_checkpoint(&dir).unwrap();
+    assert_eq!(loaded.job_id, "job-resume");
+    assert_eq!(loaded.options.extensions, vec!["jpg".to_string()]);
+    assert_eq!(loaded.config.layers.len(), 1);
+    assert!(read_journal(&dir).is_empty());
+
+    remove_checkpoint(&dir);
+    assert!(!dir.join(CHECKPOINT_FILE).exists());
+    assert!(read_checkpoint(&dir).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn newer_checkpoints_are_rejected() {
+    let dir = temp_dir("checkpoint", "newer");
+    let mut future = checkpoint(&dir);
+    future.version = CHECKPOINT_VERSION + 1;
+    write_checkpoint(&future).unwrap();
+    assert!(read_checkpoint(&dir).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================================================
+// Journal Tests
+// ============================================================================
+
+#[test]
+fn journal_survives_a_torn_last_line() {
+    let dir = temp_dir("checkpoint", "journal");
+    let journal = Journal::open(&dir).unwrap();
+    journal.record(&entry("a.jpg", 10)).unwrap();
+    journal.record(&entry("b.jpg", 20)).unwrap();
+    journal.record(&entry("a.jpg", 30)).unwrap();
+    drop(journal);
+
+    let mut file = std::fs::OpenOptions::new().append(true).open(dir.join(JOURNAL_FILE)).unwrap();
+    file.write_all(b"{\"relative\":\"c.jpg\",\"sour").unwrap();
+
+    let done = read_journal(&dir);
+    assert_eq!(done.len(), 2);
+    assert_eq!(done["a.jpg"].output_size, 30);
+    assert!(!done.contains_key("c.jpg"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn only_unchanged_files_are_skipped() {
+    let source = temp_dir("checkpoint", "source");
+    let output = temp_dir("checkpoint", "output");
+    std::fs::write(source.join("a.jpg"), b"first ".repeat(200)).unwrap();
+    std::fs::write(source.join("b.jpg"), b"second ".repeat(200)).unwrap();
+
+    let files: Vec<JobFile> = collect_files(&source).unwrap();
+    let context = PipelineContext::default();
+    let entries: Vec<FileCheckpoint> = files
+        .iter()
+        .map(|f| FileCheckpoint::new(f, &process_folder_file(f, &output, &config(), &context).unwrap()))
+        .collect();
+    assert!(files.iter().zip(&entries).all(|(f, e)| is_unchanged(f, e, &output)));
+
+    // Edited source
+    std::fs::write(source.join("a.jpg"), b"edited ".repeat(200)).unwrap();
+    assert!(!is_unchanged(&files[0], &entries[0], &output));
+
+    // Damaged output
+    std::fs::write(output.join("b.jpg.vxp"), b"truncated").unwrap();
+    assert!(!is_unchanged(&files[1], &entries[1], &output));
+
+    let _ = std::fs::remove_dir_all(&source);
+    let _ = std::fs::remove_dir_all(&output);
+}
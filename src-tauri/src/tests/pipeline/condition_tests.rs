@@ -0,0 +1,146 @@
+//! Pipeline Condition Tests
+//!
+//! Tests for:
+//! - Condition parsing and type checks
+//! - Three-valued evaluation against file facts
+//! - Conditional layers in processing, validation and estimates
+
+use crate::pipeline::{
+    pipeline_estimate, pipeline_validate, process_pipeline, process_pipeline_for_file, reverse_pipeline,
+    PipelineConfig, PipelineContext, PipelineLayer, PipelineOperation,
+};
+use crate::pipeline_condition::{Condition, ConditionFacts};
+
+const MB: u64 = 1024 * 1024;
+
+fn facts(size: u64, name: Option<&str>) -> ConditionFacts {
+    ConditionFacts {
+        size,
+        name: name.map(str::to_string),
+        format: Some("jpeg".into()),
+    }
+}
+
+fn eval(source: &str, facts: &ConditionFacts) -> Option<bool> {
+    Condition::parse(source).unwrap().evaluate(facts)
+}
+
+fn compress_layer(id: &str, order: u32, condition: Option<&str>) -> PipelineLayer {
+    PipelineLayer {
+        id: id.to_string(),
+        operation: PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 },
+        enabled: true,
+        order,
+        condition: condition.map(str::to_string),
+    }
+}
+
+fn config(layers: Vec<PipelineLayer>) -> PipelineConfig {
+    PipelineConfig {
+        layers,
+        ..Default::default()
+    }
+}
+
+// ============================================================================
+// Parser Tests
+// ============================================================================
+
+#[test]
+fn sizes_units_and_comparisons() {
+    let big = facts(6 * MB, Some("a.jpg"));
+    assert_eq!(eval("size > 5MB", &big), Some(true));
+    assert_eq!(eval("size <= 5 mb", &big), Some(false));
+    assert_eq!(eval("size >= 6144KB and size == 6MB", &big), Some(true));
+    assert_eq!(eval("size < 1GB", &big), Some(true));
+}
+
+#[test]
+fn text_comparisons_ignore_case_and_dots() {
+    let raw = facts(MB, Some("IMG_0001.RAW"));
+    assert_eq!(eval("extension == 'raw'", &raw), Some(true));
+    assert_eq!(eval("extension in [\".dng\", \".raw\"]", &raw), Some(true));
+    assert_eq!(eval("format != \"JPEG\"", &raw), Some(false));
+    assert_eq!(eval("name == 'img_0001.raw'", &raw), Some(true));
+}
+
+#[test]
+fn boolean_operators_and_grouping() {
+    let small = facts(10, Some("a.png"));
+    assert_eq!(eval("not (size > 1KB) && ext == 'png'", &small), Some(true));
+    assert_eq!(eval("size > 1KB or ext == 'png'", &small), Some(true));
+    assert_eq!(eval("!(size < 1KB || false)", &small), Some(false));
+}
+
+#[test]
+fn malformed_conditions_are_rejected() {
+    for source in [
+        "",
+        "size",
+        "size > 'big'",
+        "name > 'a'",
+        "size > 5XB",
+        "colour == 'red'",
+        "(size > 1",
+        "size > 1 size",
+        "name == 'open",
+    ] {
+        assert!(Condition::parse(source).is_err(), "accepted {:?}", source);
+    }
+    assert!(Condition::parse(&"size > 1 and ".repeat(100)).is_err());
+}
+
+#[test]
+fn missing_facts_leave_conditions_undecided() {
+    let unnamed = facts(MB, None);
+    assert_eq!(eval("extension == 'raw'", &unnamed), None);
+    assert_eq!(eval("extension == 'raw' and size > 5MB", &unnamed), Some(false));
+    assert_eq!(eval("extension == 'raw' or size < 5MB", &unnamed), Some(true));
+}
+
+// ============================================================================
+// Pipeline Tests
+// ============================================================================
+
+#[test]
+fn layers_run_only_when_their_condition_holds() {
+    let config = config(vec![
+        compress_layer("big-only", 0, Some("size > 5MB")),
+        compress_layer("text-only", 1, Some("extension == 'txt'")),
+    ]);
+    let data = b"conditional layers ".repeat(100);
+    let context = PipelineContext::default();
+
+    let result = process_pipeline_for_file(&data, Some("notes.txt"), &config, &context).unwrap();
+    assert!(result.layers_applied[0].skipped);
+    assert!(!result.layers_applied[1].skipped);
+    assert!(result.final_size < data.len());
+    assert_eq!(reverse_pipeline(&result.data, &context).unwrap().data, data);
+
+    // Without a file name the extension is unknown and the layer is skipped
+    let unnamed = process_pipeline(&data, &config, &context).unwrap();
+    assert!(unnamed.layers_applied.iter().all(|l| l.skipped));
+    assert_eq!(reverse_pipeline(&unnamed.data, &context).unwrap().data, data);
+}
+
+#[test]
+fn validation_checks_conditions() {
+    assert!(pipeline_validate(config(vec![compress_layer("a", 0, Some("size > 1MB"))])).is_ok());
+    assert!(pipeline_validate(config(vec![compress_layer("a", 0, Some("size >"))])).is_err());
+}
+
+#[test]
+fn estimates_follow_conditions() {
+    let config = config(vec![
+        compress_layer("big-only", 0, Some("size > 5MB")),
+        compress_layer("raw-only", 1, Some("extension == 'raw'")),
+    ]);
+
+    let small = pipeline_estimate(1000, config.clone(), Some("a.jpg".into()));
+    assert_eq!(small["estimated_final_size"], 1000);
+    assert_eq!(small["operations"][0]["applies"], false);
+
+    let unnamed = pipeline_estimate(1000, config, None);
+    assert_eq!(unnamed["operations"][1]["applies"], serde_json::Value::Null);
+    assert_eq!(unnamed["estimated_final_size"], 400);
+}
@@ -0,0 +1,122 @@
+//! Pipeline Dry Run Tests
+//!
+//! Tests for:
+//! - Sampling files spread over the input
+//! - Measured per-step ratios and their projection
+//! - Conditional layers and steps that cannot run
+
+use crate::compress_jobs::{collect_files, JobFile};
+use crate::pipeline::{PipelineConfig, PipelineContext, PipelineLayer, PipelineOperation};
+use crate::pipeline_dry_run::{dry_run, sample_files, DryRunOptions, MAX_SAMPLES};
+use crate::tests::common::temp_dir;
+
+fn layer(id: &str, operation: PipelineOperation, order: u32, condition: Option<&str>) -> PipelineLayer {
+    PipelineLayer {
+        id: id.into(),
+        operation,
+        enabled: true,
+        order,
+        condition: condition.map(str::to_string),
+    }
+}
+
+fn config(layers: Vec<PipelineLayer>) -> PipelineConfig {
+    PipelineConfig {
+        name: "Dry run".into(),
+        layers,
+        ..Default::default()
+    }
+}
+
+fn compress() -> PipelineOperation {
+    PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 }
+}
+
+// ============================================================================
+// Sampling Tests
+// ============================================================================
+
+#[test]
+fn samples_are_spread_over_the_input() {
+    let files: Vec<JobFile> = (0..100)
+        .map(|i| JobFile { source: format!("{}.bin", i).into(), relative: format!("{}.bin", i), size: 1 })
+        .collect();
+
+    let picked: Vec<_> = sample_files(&files, 4).iter().map(|f| f.relative.as_str()).collect();
+    assert_eq!(picked, ["0.bin", "25.bin", "50.bin", "75.bin"]);
+    assert_eq!(sample_files(&files[..3], 4).len(), 3);
+}
+
+#[test]
+fn options_are_bounded() {
+    let dir = temp_dir("dry-run", "options");
+    std::fs::write(dir.join("a.txt"), b"data").unwrap();
+    let files = collect_files(&dir).unwrap();
+    let pipeline = config(vec![layer("compress", compress(), 0, None)]);
+    let context = PipelineContext::default();
+
+    for options in [
+        DryRunOptions { max_samples: 0, ..Default::default() },
+        DryRunOptions { max_samples: MAX_SAMPLES + 1, ..Default::default() },
+        DryRunOptions { window_bytes: 1, ..Default::default() },
+    ] {
+        assert!(dry_run(&files, &pipeline, &context, &options).is_err());
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================================================
+// Projection Tests
+// ============================================================================
+
+#[test]
+fn steps_are_measured_and_projected() {
+    let dir = temp_dir("dry-run", "projection");
+    for i in 0..6 {
+        std::fs::write(dir.join(format!("{}.txt", i)), b"repetitive text ".repeat(4096)).unwrap();
+    }
+    let files = collect_files(&dir).unwrap();
+    let pipeline = config(vec![
+        layer("compress", compress(), 0, None),
+        layer("base64", PipelineOperation::Base64Encode, 1, None),
+    ]);
+    let options = DryRunOptions { max_samples: 3, ..Default::default() };
+
+    let report = dry_run(&files, &pipeline, &PipelineContext::default(), &options).unwrap();
+    assert_eq!((report.files_total, report.files_sampled), (6, 3));
+    assert_eq!(report.bytes_total, 6 * 65536);
+    assert_eq!(report.bytes_sampled, 3 * 65536);
+
+    let (compress, base64) = (&report.steps[0], &report.steps[1]);
+    assert!(compress.measured && compress.error.is_none());
+    assert!(compress.ratio < 0.1);
+    assert_eq!(compress.estimated_input_size, report.bytes_total);
+    assert_eq!(base64.estimated_input_size, compress.estimated_output_size);
+    assert!((base64.ratio - 4.0 / 3.0).abs() < 0.05);
+    assert_eq!(compress.estimated_peak_memory_bytes, (65536.0 * (1.0 + compress.ratio)) as u64);
+    assert!((report.overall_ratio - compress.ratio * base64.ratio).abs() < 1e-6);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn conditions_and_failing_steps_are_reported() {
+    let dir = temp_dir("dry-run", "conditions");
+    std::fs::write(dir.join("small.txt"), b"small ".repeat(100)).unwrap();
+    std::fs::write(dir.join("large.txt"), b"large ".repeat(50_000)).unwrap();
+    let files = collect_files(&dir).unwrap();
+    let pipeline = config(vec![
+        layer("compress", compress(), 0, Some("size > 100KB")),
+        layer("seal", PipelineOperation::EncryptHybridPQ { recipient_bundle: None }, 1, None),
+    ]);
+
+    let report = dry_run(&files, &pipeline, &PipelineContext::default(), &DryRunOptions::default()).unwrap();
+    let (compress, seal) = (&report.steps[0], &report.steps[1]);
+    assert_eq!(compress.applied_samples, 1);
+    assert!(compress.sample_output_bytes < compress.sample_input_bytes);
+
+    assert_eq!(seal.applied_samples, 2);
+    assert!(!seal.measured);
+    assert!(seal.error.is_some());
+    assert_eq!(seal.estimated_duration_ms, 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
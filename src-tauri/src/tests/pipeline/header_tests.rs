@@ -0,0 +1,167 @@
+//! Pipeline Header Tests
+//!
+//! Tests for:
+//! - Self-describing output headers and inspection
+//! - Reversing without the preset, including pre-header output
+//! - Refusing layers from newer step versions
+
+use std::collections::HashMap;
+
+use crate::crypto::hash_data;
+use crate::pipeline::{
+    inspect_metadata, pipeline_inspect, process_pipeline, read_metadata, reverse_pipeline, LayerMetadata,
+    PipelineConfig, PipelineContext, PipelineLayer, PipelineMetadata, PipelineOperation, PIPELINE_FORMAT_VERSION,
+    PIPELINE_MAGIC,
+};
+use crate::pipeline_steps::{find_step, StepContext};
+
+fn layer(id: &str, operation: PipelineOperation, order: u32) -> PipelineLayer {
+    PipelineLayer {
+        id: id.into(),
+        operation,
+        enabled: true,
+        order,
+        condition: None,
+    }
+}
+
+fn sealed_config() -> PipelineConfig {
+    PipelineConfig {
+        id: "archive-1".into(),
+        name: "Archive".into(),
+        layers: vec![
+            layer("compress", PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 }, 0),
+            layer("inner", PipelineOperation::EncryptPassword { password: None }, 1),
+            layer("outer", PipelineOperation::EncryptPassword { password: None }, 2),
+        ],
+        ..Default::default()
+    }
+}
+
+fn passwords() -> PipelineContext {
+    PipelineContext {
+        passwords: HashMap::from([
+            ("inner".to_string(), "first secret".to_string()),
+            ("outer".to_string(), "second secret".to_string()),
+        ]),
+        keypair: None,
+    }
+}
+
+fn encode(metadata: &PipelineMetadata, payload: &[u8], with_magic: bool) -> Vec<u8> {
+    let json = serde_json::to_vec(metadata).unwrap();
+    let mut out = Vec::new();
+    if with_magic {
+        out.extend_from_slice(PIPELINE_MAGIC);
+        out.push(PIPELINE_FORMAT_VERSION);
+    }
+    out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    out.extend_from_slice(&json);
+    out.extend_from_slice(payload);
+    out
+}
+
+// ============================================================================
+// Header Tests
+// ============================================================================
+
+#[test]
+fn output_describes_its_own_layers() {
+    let data = b"self describing ".repeat(64);
+    let processed = process_pipeline(&data, &sealed_config(), &passwords()).unwrap();
+    assert!(processed.data.starts_with(PIPELINE_MAGIC));
+
+    let (metadata, payload_offset) = read_metadata(&processed.data).unwrap();
+    let info = inspect_metadata(&metadata, (processed.data.len() - payload_offset) as u64);
+    assert_eq!(info.format_version, PIPELINE_FORMAT_VERSION);
+    assert_eq!(info.pipeline_name.as_deref(), Some("Archive"));
+    assert_eq!(info.original_size, data.len());
+    assert!(info.reversible);
+
+    let steps: Vec<_> = info.layers.iter().map(|l| (l.step.as_str(), l.secret.as_deref())).collect();
+    assert_eq!(
+        steps,
+        [("compress", None), ("encrypt_password", Some("password")), ("encrypt_password", Some("password"))]
+    );
+    assert_eq!(info.layers[0].params["algorithm"], "zstd");
+    assert_eq!(info.layers[2].layer_id.as_deref(), Some("outer"));
+}
+
+#[test]
+fn inspect_reads_the_header_of_a_file() {
+    let path = std::env::temp_dir().join(format!("vortex-header-test-{}.vxp", std::process::id()));
+    let processed = process_pipeline(b"on disk", &sealed_config(), &passwords()).unwrap();
+    std::fs::write(&path, &processed.data).unwrap();
+
+    let info = pipeline_inspect(path.to_string_lossy().to_string()).unwrap();
+    assert_eq!(info.layers.len(), 3);
+    assert!(info.payload_size > 0 && info.payload_size < processed.data.len() as u64);
+
+    std::fs::write(&path, b"not pipeline output").unwrap();
+    assert!(pipeline_inspect(path.to_string_lossy().to_string()).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+// ============================================================================
+// Reverse Tests
+// ============================================================================
+
+#[test]
+fn layers_reverse_with_their_own_passwords() {
+    let data = b"two passwords ".repeat(32);
+    let processed = process_pipeline(&data, &sealed_config(), &passwords()).unwrap();
+    assert_eq!(reverse_pipeline(&processed.data, &passwords()).unwrap().data, data);
+
+    let missing = PipelineContext {
+        passwords: HashMap::from([("outer".to_string(), "second secret".to_string())]),
+        keypair: None,
+    };
+    assert!(reverse_pipeline(&processed.data, &missing).is_err());
+}
+
+#[test]
+fn output_without_a_header_still_reverses() {
+    let data = b"written by an older version".to_vec();
+    let step = find_step("base64_encode").unwrap();
+    let context = PipelineContext::default();
+    let ctx = StepContext { layer_id: "", file_name: None, pipeline: &context };
+    let (payload, params) = step.process(&data, &serde_json::json!({}), &ctx).unwrap();
+
+    let legacy: PipelineMetadata = serde_json::from_value(serde_json::json!({
+        "version": 1,
+        "layers": [{ "operation_type": "base64_encode", "params": params }],
+        "original_checksum": hash_data(&data).to_vec(),
+        "original_size": data.len(),
+    }))
+    .unwrap();
+    let packed = encode(&legacy, &payload, false);
+
+    assert_eq!(reverse_pipeline(&packed, &context).unwrap().data, data);
+    let (metadata, _) = read_metadata(&packed).unwrap();
+    let info = inspect_metadata(&metadata, payload.len() as u64);
+    assert_eq!(info.format_version, 1);
+    assert_eq!((info.pipeline_name, info.created_at), (None, None));
+    assert_eq!(info.layers[0].step_version, 1);
+}
+
+#[test]
+fn newer_step_versions_are_refused() {
+    let data = b"future".to_vec();
+    let config = PipelineConfig {
+        layers: vec![layer("base64", PipelineOperation::Base64Encode, 0)],
+        ..Default::default()
+    };
+    let context = PipelineContext::default();
+    let processed = process_pipeline(&data, &config, &context).unwrap();
+    let (mut metadata, payload_offset) = read_metadata(&processed.data).unwrap();
+
+    metadata.layers = vec![LayerMetadata { step_version: 99, ..metadata.layers[0].clone() }];
+    let future = encode(&metadata, &processed.data[payload_offset..], true);
+    assert!(reverse_pipeline(&future, &context).is_err());
+    let info = inspect_metadata(&metadata, 0);
+    assert!(!info.layers[0].supported && !info.reversible);
+
+    let mut newer_format = processed.data.clone();
+    newer_format[PIPELINE_MAGIC.len()] = PIPELINE_FORMAT_VERSION + 1;
+    assert!(reverse_pipeline(&newer_format, &context).is_err());
+}
@@ -0,0 +1,125 @@
+//! Pipeline History Tests
+//!
+//! Tests for:
+//! - Recording runs and keeping all-time totals past the log limit
+//! - Runs built from results and failures
+//! - Per-pipeline statistics and throughput regressions
+
+use std::time::Duration;
+
+use crate::local_store::LocalStore;
+use crate::pipeline::{process_pipeline, PipelineConfig, PipelineContext, PipelineError, PipelineLayer, PipelineOperation};
+use crate::pipeline_history::{
+    compute_stats, history_in, record_run_in, totals_in, HistoryTotals, PipelineRun, RunKind, MAX_HISTORY,
+    RECENT_RUNS,
+};
+
+const KEY: [u8; 32] = [9u8; 32];
+
+fn config(id: &str) -> PipelineConfig {
+    PipelineConfig {
+        id: id.into(),
+        name: format!("Pipeline {}", id),
+        layers: vec![PipelineLayer {
+            id: "compress".into(),
+            operation: PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 },
+            enabled: true,
+            order: 0,
+            condition: None,
+        }],
+        ..Default::default()
+    }
+}
+
+fn run(pipeline: &str, input: u64, output: u64, duration_ms: u64) -> PipelineRun {
+    PipelineRun {
+        id: format!("run-{}-{}", pipeline, duration_ms),
+        pipeline_id: pipeline.into(),
+        pipeline_name: format!("Pipeline {}", pipeline),
+        kind: RunKind::Single,
+        started_at: 1,
+        duration_ms,
+        files: 1,
+        input_size: input,
+        output_size: output,
+        steps: vec!["compress".into()],
+        success: true,
+        error: None,
+    }
+}
+
+// ============================================================================
+// Recording Tests
+// ============================================================================
+
+#[test]
+fn log_is_capped_but_totals_keep_counting() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    for i in 0..MAX_HISTORY + 5 {
+        record_run_in(&store, &run("a", 100, 40, i as u64)).unwrap();
+    }
+
+    let history = history_in(&store).unwrap();
+    assert_eq!(history.len(), MAX_HISTORY);
+    assert_eq!(history[0].duration_ms, 5);
+
+    let totals = totals_in(&store).unwrap();
+    assert_eq!(totals.runs, (MAX_HISTORY + 5) as u64);
+    assert_eq!(totals.input_bytes, 100 * (MAX_HISTORY + 5) as u64);
+}
+
+#[test]
+fn runs_follow_results_and_failures() {
+    let data = b"history ".repeat(200);
+    let config = config("a");
+    let result = process_pipeline(&data, &config, &PipelineContext::default());
+    let ok = PipelineRun::single(&config, data.len(), &result, Duration::from_millis(12));
+    assert!(ok.success);
+    assert_eq!(ok.steps, ["compress"]);
+    assert_eq!(ok.input_size, data.len() as u64);
+    assert!(ok.output_size > 0 && ok.output_size < ok.input_size);
+    assert_eq!(ok.duration_ms, 12);
+
+    let failed = PipelineRun::single(&config, 10, &Err(PipelineError::MissingKeypair), Duration::ZERO);
+    assert!(!failed.success);
+    assert_eq!(failed.output_size, 0);
+    assert!(failed.error.is_some());
+}
+
+// ============================================================================
+// Statistics Tests
+// ============================================================================
+
+#[test]
+fn stats_sum_savings_per_pipeline() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    record_run_in(&store, &run("a", 1000, 400, 10)).unwrap();
+    record_run_in(&store, &run("a", 1000, 400, 10)).unwrap();
+    record_run_in(&store, &run("b", 500, 600, 5)).unwrap();
+
+    let stats = compute_stats(totals_in(&store).unwrap(), &history_in(&store).unwrap());
+    assert_eq!(stats.bytes_saved, 1100);
+    assert!((stats.overall_ratio - 1400.0 / 2500.0).abs() < 1e-9);
+    assert_eq!(stats.pipelines[0].pipeline_id, "a");
+    assert_eq!(stats.pipelines[0].bytes_saved, 1200);
+    assert_eq!(stats.pipelines[1].bytes_saved, -100);
+    assert!(stats.pipelines.iter().all(|p| p.throughput_change.is_none()));
+}
+
+#[test]
+fn slower_recent_runs_are_a_regression() {
+    let mut history: Vec<_> = (0..RECENT_RUNS).map(|_| run("a", 1000, 500, 10)).collect();
+    history.extend((0..RECENT_RUNS).map(|_| run("a", 1000, 500, 20)));
+    let mut failed = run("a", 1000, 0, 1);
+    failed.success = false;
+    history.push(failed);
+
+    let stats = compute_stats(HistoryTotals::default(), &history);
+    let usage = &stats.pipelines[0];
+    assert_eq!((usage.runs, usage.failed_runs), (2 * RECENT_RUNS + 1, 1));
+    assert!((usage.throughput_change.unwrap() + 0.5).abs() < 1e-9);
+    assert!(usage.regressed);
+
+    let steady: Vec<_> = (0..2 * RECENT_RUNS).map(|_| run("a", 1000, 500, 10)).collect();
+    assert!(!compute_stats(HistoryTotals::default(), &steady).pipelines[0].regressed);
+}
@@ -0,0 +1,24 @@
+//! Pipeline Module Tests
+//!
+//! Organized by functionality:
+//! - `batch_tests` - Folder processing with a worker pool
+//! - `checkpoint_tests` - Resumable job checkpoints and journal
+//! - `condition_tests` - Layer conditions and their evaluation
+//! - `dry_run_tests` - Sampled dry runs and their projections
+//! - `header_tests` - Self-describing output and inspection
+//! - `history_tests` - Run history and statistics
+//! - `preset_tests` - User presets, import and export
+//! - `routing_tests` - Per-category default pipelines
+//! - `script_tests` - Scripted steps and their sandbox
+//! - `step_tests` - Step registry and custom steps
+
+pub mod batch_tests;
+pub mod checkpoint_tests;
+pub mod condition_tests;
+pub mod dry_run_tests;
+pub mod header_tests;
+pub mod history_tests;
+pub mod preset_tests;
+pub mod routing_tests;
+pub mod script_tests;
+pub mod step_tests;
@@ -0,0 +1,124 @@
+//! Pipeline Preset Tests
+//!
+//! Tests for:
+//! - Saving and replacing user presets
+//! - Protection of built-in presets
+//! - Preset file export and import
+
+use crate::pipeline::{get_preset_pipelines, PipelineConfig, PipelineLayer, PipelineOperation};
+use crate::pipeline_presets::{decode_preset_file, encode_preset_file, import_preset, is_builtin, upsert_preset};
+
+fn preset(id: &str, name: &str) -> PipelineConfig {
+    PipelineConfig {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: "test".to_string(),
+        layers: vec![
+            PipelineLayer {
+                id: "compress".into(),
+                operation: PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 },
+                enabled: true,
+                order: 0,
+                condition: None,
+            },
+            PipelineLayer {
+                id: "encrypt".into(),
+                operation: PipelineOperation::EncryptPassword { password: Some("secret".into()) },
+                enabled: true,
+                order: 1,
+                condition: None,
+            },
+        ],
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
+// ============================================================================
+// Save Tests
+// ============================================================================
+
+#[test]
+fn new_preset_gets_an_id_and_timestamps() {
+    let mut presets = Vec::new();
+    let saved = upsert_preset(&mut presets, preset("", "  Mine  "), 100).unwrap();
+
+    assert!(saved.id.starts_with("user-"));
+    assert_eq!(saved.name, "Mine");
+    assert_eq!((saved.created_at, saved.updated_at), (100, 100));
+    assert_eq!(presets.len(), 1);
+}
+
+#[test]
+fn saving_again_replaces_and_keeps_created_at() {
+    let mut presets = Vec::new();
+    let saved = upsert_preset(&mut presets, preset("", "First"), 100).unwrap();
+    let mut edited = saved.clone();
+    edited.name = "Renamed".into();
+    let replaced = upsert_preset(&mut presets, edited, 200).unwrap();
+
+    assert_eq!(presets.len(), 1);
+    assert_eq!(presets[0].name, "Renamed");
+    assert_eq!((replaced.created_at, replaced.updated_at), (100, 200));
+}
+
+#[test]
+fn builtins_cannot_be_overwritten() {
+    let builtin = get_preset_pipelines().remove(0);
+    assert!(is_builtin(&builtin.id));
+    assert!(upsert_preset(&mut Vec::new(), builtin, 1).is_err());
+}
+
+#[test]
+fn invalid_presets_are_rejected() {
+    let mut presets = Vec::new();
+    assert!(upsert_preset(&mut presets, preset("", " "), 1).is_err());
+
+    let mut empty = preset("", "Empty");
+    empty.layers.clear();
+    assert!(upsert_preset(&mut presets, empty, 1).is_err());
+
+    let mut bad_level = preset("", "Bad");
+    bad_level.layers[0].operation = PipelineOperation::Compress { algorithm: "zstd".into(), level: 99 };
+    assert!(upsert_preset(&mut presets, bad_level, 1).is_err());
+    assert!(presets.is_empty());
+}
+
+// ============================================================================
+// File Tests
+// ============================================================================
+
+#[test]
+fn preset_files_roundtrip_without_passwords() {
+    let original = preset("user-abc", "Shared");
+    let bytes = encode_preset_file(&original).unwrap();
+    assert!(!String::from_utf8_lossy(&bytes).contains("secret"));
+
+    let decoded = decode_preset_file(&bytes).unwrap();
+    assert_eq!(decoded.name, "Shared");
+    assert_eq!(decoded.layers.len(), 2);
+    assert_eq!(decoded.layers[1].operation, PipelineOperation::EncryptPassword { password: None });
+}
+
+#[test]
+fn foreign_and_future_files_are_rejected() {
+    assert!(decode_preset_file(b"{\"format\":\"other\",\"version\":1,\"preset\":{}}").is_err());
+    assert!(decode_preset_file(b"not json").is_err());
+
+    let mut file: serde_json::Value = serde_json::from_slice(&encode_preset_file(&preset("x", "X")).unwrap()).unwrap();
+    file["version"] = 99.into();
+    assert!(decode_preset_file(&serde_json::to_vec(&file).unwrap()).is_err());
+}
+
+#[test]
+fn import_renames_clashing_ids() {
+    let mut presets = Vec::new();
+    let first = import_preset(&mut presets, preset("user-shared", "One"), 1).unwrap();
+    let second = import_preset(&mut presets, preset("user-shared", "Two"), 2).unwrap();
+    let builtin = import_preset(&mut presets, preset("preset-fast-compress", "Three"), 3).unwrap();
+
+    assert_eq!(first.id, "user-shared");
+    assert_ne!(second.id, "user-shared");
+    assert!(!is_builtin(&builtin.id));
+    assert_eq!(presets.len(), 3);
+}
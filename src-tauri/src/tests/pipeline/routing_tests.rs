@@ -0,0 +1,116 @@
+//! Pipeline Routing Tests
+//!
+//! Tests for:
+//! - Classifying files by extension
+//! - Resolving routes to presets and which files a folder upload picks up
+//! - Upload policy intent of a routed pipeline
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::image_optimize::OptimizeOptions;
+use crate::pipeline::{PipelineConfig, PipelineLayer, PipelineOperation};
+use crate::pipeline_routing::{upload_intent, FileCategory, Router};
+
+fn layer(id: &str, operation: PipelineOperation, enabled: bool) -> PipelineLayer {
+    PipelineLayer {
+        id: id.into(),
+        operation,
+        enabled,
+        order: 0,
+        condition: None,
+    }
+}
+
+fn preset(id: &str, layers: Vec<PipelineLayer>) -> PipelineConfig {
+    PipelineConfig {
+        id: id.into(),
+        name: format!("Preset {}", id),
+        layers,
+        ..Default::default()
+    }
+}
+
+fn compress() -> PipelineLayer {
+    layer("compress", PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 }, true)
+}
+
+// ============================================================================
+// Category Tests
+// ============================================================================
+
+#[test]
+fn files_are_classified_by_extension() {
+    assert_eq!(FileCategory::of(Path::new("a/IMG_001.JPG")), Some(FileCategory::Photo));
+    assert_eq!(FileCategory::of(Path::new("shot.cr3")), Some(FileCategory::Raw));
+    assert_eq!(FileCategory::of(Path::new("shot.DNG")), Some(FileCategory::Raw));
+    assert_eq!(FileCategory::of(Path::new("clip.mov")), Some(FileCategory::Video));
+    assert_eq!(FileCategory::of(Path::new("scan.pdf")), Some(FileCategory::Document));
+    assert_eq!(FileCategory::of(Path::new("archive.tar")), None);
+    assert_eq!(FileCategory::of(Path::new("README")), None);
+}
+
+// ============================================================================
+// Router Tests
+// ============================================================================
+
+#[test]
+fn routes_resolve_to_presets() {
+    let presets = [preset("archive", vec![compress()]), preset("photos", vec![compress()])];
+    let table = BTreeMap::from([
+        (FileCategory::Raw, "archive".to_string()),
+        (FileCategory::Photo, "photos".to_string()),
+        (FileCategory::Video, "deleted".to_string()),
+    ]);
+    let router = Router::new(&table, &presets);
+
+    assert_eq!(router.pipeline_for(Path::new("shot.nef")).map(|p| p.id.as_str()), Some("archive"));
+    assert_eq!(router.pipeline_for(Path::new("cat.png")).map(|p| p.id.as_str()), Some("photos"));
+    // A route to a preset that no longer exists is ignored
+    assert!(router.pipeline_for(Path::new("clip.mp4")).is_none());
+    assert!(router.pipeline_for(Path::new("notes.pdf")).is_none());
+}
+
+#[test]
+fn only_photos_and_routed_categories_are_picked_up() {
+    let presets = [preset("archive", vec![compress()])];
+    let unrouted = Router::new(&BTreeMap::new(), &presets);
+    assert!(unrouted.includes(Path::new("cat.jpg")));
+    assert!(!unrouted.includes(Path::new("shot.arw")));
+    assert!(!unrouted.includes(Path::new("notes.txt")));
+
+    let table = BTreeMap::from([(FileCategory::Document, "archive".to_string())]);
+    let router = Router::new(&table, &presets);
+    assert!(router.includes(Path::new("notes.txt")));
+    assert!(!router.includes(Path::new("shot.arw")));
+    assert!(!router.includes(Path::new("archive.zip")));
+}
+
+// ============================================================================
+// Upload Intent Tests
+// ============================================================================
+
+#[test]
+fn intent_follows_enabled_layers() {
+    let plain = preset("plain", vec![compress()]);
+    let intent = upload_intent(&plain);
+    assert!(!intent.encrypted && !intent.strips_metadata);
+
+    let sealed = preset(
+        "sealed",
+        vec![
+            layer(
+                "optimize",
+                PipelineOperation::OptimizeImage { options: OptimizeOptions { strip_metadata: true, ..Default::default() } },
+                true,
+            ),
+            compress(),
+            layer("encrypt", PipelineOperation::EncryptPassword { password: None }, true),
+        ],
+    );
+    let intent = upload_intent(&sealed);
+    assert!(intent.encrypted && intent.strips_metadata);
+
+    let disabled = preset("disabled", vec![layer("encrypt", PipelineOperation::EncryptHybridPQ { recipient_bundle: None }, false)]);
+    assert!(!upload_intent(&disabled).encrypted);
+}
@@ -0,0 +1,139 @@
+//! Script Step Tests
+//!
+//! Tests for:
+//! - Validating scripts and their limits
+//! - Choosing a step and its parameters per file
+//! - Stopping runaway scripts
+
+use serde_json::json;
+
+use crate::entropy::{detect_kind, ContentKind};
+use crate::pipeline::{
+    pipeline_validate, process_pipeline_for_file, read_metadata, reverse_pipeline, PipelineConfig, PipelineContext,
+    PipelineLayer, PipelineOperation,
+};
+use crate::pipeline_script::{MAX_SCRIPT_LEN, MAX_TIMEOUT_MS};
+use crate::tests::common::png;
+
+fn script_layer(params: serde_json::Value) -> PipelineLayer {
+    PipelineLayer {
+        id: "script".into(),
+        operation: PipelineOperation::Step { step: "script".into(), params },
+        enabled: true,
+        order: 0,
+        condition: None,
+    }
+}
+
+fn pipeline(script: &str) -> PipelineConfig {
+    PipelineConfig {
+        name: "Scripted".into(),
+        layers: vec![
+            script_layer(json!({ "script": script })),
+            PipelineLayer {
+                id: "compress".into(),
+                operation: PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 },
+                enabled: true,
+                order: 1,
+                condition: None,
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+fn script_metadata(output: &[u8]) -> serde_json::Value {
+    let (metadata, _) = read_metadata(output).unwrap();
+    metadata.layers[0].params.clone()
+}
+
+const ROUTER: &str = r#"
+    if width >= 150 {
+        #{ step: "transcode", params: #{ format: "webp", quality: 60 }, tags: #{ route: "large" } }
+    } else if extension == "png" {
+        #{ step: "optimize_image", params: #{ level: 2 }, tags: #{ route: "small " + name } }
+    }
+"#;
+
+// ============================================================================
+// Validation Tests
+// ============================================================================
+
+#[test]
+fn scripts_are_checked_by_pipeline_validate() {
+    assert!(pipeline_validate(pipeline(ROUTER)).is_ok());
+    assert!(pipeline_validate(pipeline("if width > { 1")).is_err());
+    assert!(pipeline_validate(pipeline("   ")).is_err());
+    assert!(pipeline_validate(pipeline(&"1;".repeat(MAX_SCRIPT_LEN))).is_err());
+
+    let mut slow = pipeline("()");
+    slow.layers[0] = script_layer(json!({ "script": "()", "timeout_ms": MAX_TIMEOUT_MS + 1 }));
+    assert!(pipeline_validate(slow).is_err());
+
+    // A script prepares the input, so it cannot follow compression
+    let mut late = pipeline(ROUTER);
+    late.layers[0].order = 2;
+    assert!(pipeline_validate(late).is_err());
+}
+
+// ============================================================================
+// Routing Tests
+// ============================================================================
+
+#[test]
+fn script_picks_the_step_per_file() {
+    let context = PipelineContext::default();
+
+    let large = png(160, 120);
+    let result = process_pipeline_for_file(&large, Some("large.png"), &pipeline(ROUTER), &context).unwrap();
+    let metadata = script_metadata(&result.data);
+    assert_eq!(metadata["step"], "transcode");
+    assert_eq!(metadata["tags"]["route"], "large");
+    let restored = reverse_pipeline(&result.data, &context).unwrap().data;
+    assert_eq!(detect_kind(&restored), ContentKind::Webp);
+
+    let small = png(40, 30);
+    let result = process_pipeline_for_file(&small, Some("small.png"), &pipeline(ROUTER), &context).unwrap();
+    let metadata = script_metadata(&result.data);
+    assert_eq!(metadata["step"], "optimize_image");
+    assert_eq!(metadata["tags"]["route"], "small small.png");
+}
+
+#[test]
+fn unit_result_leaves_the_file_alone() {
+    let context = PipelineContext::default();
+    let data = b"not an image at all".repeat(10);
+    let result = process_pipeline_for_file(&data, Some("notes.txt"), &pipeline(ROUTER), &context).unwrap();
+    assert!(script_metadata(&result.data)["step"].is_null());
+    assert_eq!(reverse_pipeline(&result.data, &context).unwrap().data, data);
+}
+
+#[test]
+fn scripts_cannot_pick_wrapping_steps() {
+    let script = r#"#{ step: "compress", params: #{ algorithm: "zstd", level: 3 } }"#;
+    let result = process_pipeline_for_file(b"data", None, &pipeline(script), &PipelineContext::default());
+    assert!(result.is_err());
+
+    let nested = r#"#{ step: "script", params: #{ script: "()" } }"#;
+    assert!(process_pipeline_for_file(b"data", None, &pipeline(nested), &PipelineContext::default()).is_err());
+}
+
+// ============================================================================
+// Sandbox Tests
+// ============================================================================
+
+#[test]
+fn runaway_scripts_are_stopped() {
+    let context = PipelineContext::default();
+    let mut config = pipeline("loop { }");
+    config.layers[0] = script_layer(json!({ "script": "loop { }", "max_operations": 10_000 }));
+    assert!(process_pipeline_for_file(b"data", None, &config, &context).is_err());
+
+    config.layers[0] = script_layer(json!({ "script": "loop { }", "max_operations": 10_000_000, "timeout_ms": 20 }));
+    let started = std::time::Instant::now();
+    assert!(process_pipeline_for_file(b"data", None, &config, &context).is_err());
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+
+    let hungry = r#"let s = "x"; loop { s += s; }"#;
+    assert!(process_pipeline_for_file(b"data", None, &pipeline(hungry), &context).is_err());
+}
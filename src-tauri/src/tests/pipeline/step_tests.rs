@@ -0,0 +1,157 @@
+//! Pipeline Step Registry Tests
+//!
+//! Tests for:
+//! - Built-in step descriptors and schemas
+//! - Registering custom steps
+//! - Running and reversing pipelines with custom steps
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use crate::github::AppError;
+use crate::pipeline::{
+    pipeline_estimate, pipeline_validate, process_pipeline, reverse_pipeline, PipelineConfig,
+    PipelineContext, PipelineError, PipelineLayer, PipelineOperation,
+};
+use crate::pipeline_steps::{describe_steps, find_step, register_step, PipelineStep, StepContext, StepDescriptor, StepEstimate};
+
+/// XORs every byte with `params.key`; its own inverse
+struct XorStep(&'static str);
+
+impl PipelineStep for XorStep {
+    fn id(&self) -> &str {
+        self.0
+    }
+
+    fn describe(&self) -> StepDescriptor {
+        StepDescriptor {
+            id: self.0.to_string(),
+            label: "XOR".to_string(),
+            description: "test step".to_string(),
+            source_transform: false,
+            params_schema: json!({ "type": "object", "properties": { "key": { "type": "integer" } } }),
+        }
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), AppError> {
+        match params["key"].as_u64() {
+            Some(key) if key <= 255 => Ok(()),
+            _ => Err(AppError::Validation("key must be 0-255".into())),
+        }
+    }
+
+    fn process(&self, data: &[u8], params: &Value, _ctx: &StepContext) -> Result<(Vec<u8>, Value), PipelineError> {
+        let key = params["key"].as_u64().unwrap_or(0) as u8;
+        Ok((data.iter().map(|b| b ^ key).collect(), json!({ "key": key })))
+    }
+
+    fn reverse(&self, data: &[u8], metadata: &Value, _ctx: &StepContext) -> Result<Vec<u8>, PipelineError> {
+        let key = metadata["key"].as_u64().unwrap_or(0) as u8;
+        Ok(data.iter().map(|b| b ^ key).collect())
+    }
+
+    fn estimate(&self, _params: &Value) -> StepEstimate {
+        StepEstimate { ratio: 1.0, label: "XOR".to_string() }
+    }
+}
+
+fn config(layers: Vec<PipelineOperation>) -> PipelineConfig {
+    PipelineConfig {
+        layers: layers
+            .into_iter()
+            .enumerate()
+            .map(|(i, operation)| PipelineLayer {
+                id: format!("layer-{}", i),
+                operation,
+                enabled: true,
+                order: i as u32,
+                condition: None,
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn xor_layer(step: &str, key: u64) -> PipelineOperation {
+    PipelineOperation::Step { step: step.to_string(), params: json!({ "key": key }) }
+}
+
+// ============================================================================
+// Registry Tests
+// ============================================================================
+
+#[test]
+fn builtins_are_described_with_schemas() {
+    let steps = describe_steps();
+    for id in ["compress", "encrypt_password", "encrypt_hybrid_pq", "hash", "base64_encode", "optimize_image", "transcode"] {
+        let step = steps.iter().find(|s| s.id == id).unwrap_or_else(|| panic!("missing {}", id));
+        assert_eq!(step.params_schema["type"], "object");
+    }
+    assert!(find_step("transcode").unwrap().describe().source_transform);
+    assert!(!find_step("compress").unwrap().describe().source_transform);
+}
+
+#[test]
+fn duplicate_and_empty_ids_are_rejected() {
+    register_step(Arc::new(XorStep("test_xor_duplicate"))).unwrap();
+    assert!(register_step(Arc::new(XorStep("test_xor_duplicate"))).is_err());
+    assert!(register_step(Arc::new(XorStep("compress"))).is_err());
+    assert!(register_step(Arc::new(XorStep(""))).is_err());
+}
+
+#[test]
+fn operations_map_to_their_step() {
+    let op: PipelineOperation = serde_json::from_value(json!({
+        "type": "step", "step": "watermark", "params": { "text": "hi" }
+    }))
+    .unwrap();
+    assert_eq!(op.step_id(), "watermark");
+    assert_eq!(op.step_params()["text"], "hi");
+
+    let compress = PipelineOperation::Compress { algorithm: "lz4".into(), level: 1 };
+    assert_eq!(compress.step_id(), "compress");
+    assert_eq!(compress.step_params(), json!({ "algorithm": "lz4", "level": 1 }));
+}
+
+// ============================================================================
+// Pipeline Tests
+// ============================================================================
+
+#[test]
+fn custom_step_roundtrips_through_pipeline() {
+    register_step(Arc::new(XorStep("test_xor_roundtrip"))).unwrap();
+    let config = config(vec![
+        PipelineOperation::Compress { algorithm: "zstd".into(), level: 3 },
+        xor_layer("test_xor_roundtrip", 0x5a),
+    ]);
+    let data = b"custom pipeline steps ".repeat(50);
+    let context = PipelineContext::default();
+
+    let processed = process_pipeline(&data, &config, &context).unwrap();
+    assert_eq!(processed.layers_applied[1].operation_type, "test_xor_roundtrip");
+
+    let restored = reverse_pipeline(&processed.data, &context).unwrap();
+    assert_eq!(restored.data, data);
+}
+
+#[test]
+fn unknown_steps_fail_validation_and_processing() {
+    let config = config(vec![xor_layer("test_not_registered", 1)]);
+    assert!(pipeline_validate(config.clone()).is_err());
+    assert!(matches!(
+        process_pipeline(b"data", &config, &PipelineContext::default()),
+        Err(PipelineError::UnknownOperation(_))
+    ));
+}
+
+#[test]
+fn custom_step_params_are_validated_and_estimated() {
+    register_step(Arc::new(XorStep("test_xor_validate"))).unwrap();
+    assert!(pipeline_validate(config(vec![xor_layer("test_xor_validate", 7)])).is_ok());
+    assert!(pipeline_validate(config(vec![xor_layer("test_xor_validate", 999)])).is_err());
+
+    let estimate = pipeline_estimate(1000, config(vec![xor_layer("test_xor_validate", 7)]), None);
+    assert_eq!(estimate["operations"][0]["operation"], "XOR");
+    assert_eq!(estimate["estimated_final_size"], 1000);
+}
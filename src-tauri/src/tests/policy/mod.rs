@@ -0,0 +1,8 @@
+//! Policy Module Tests
+//!
+//! Organized by functionality:
+//! - `upload_policy_tests` - Policy rules and embedded metadata detection
+//! - `retention_tests` - Retention planning and archive compression
+
+pub mod upload_policy_tests;
+pub mod retention_tests;
@@ -0,0 +1,159 @@
+//! Retention Policy Tests
+//!
+//! Tests for:
+//! - Picking photos older than the policy's age
+//! - Photos kept: undated, encrypted and favorites
+//! - Archive compression and policy validation
+
+use crate::catalog::{CatalogPhoto, SyncState};
+use crate::github::PhotoItem;
+use crate::retention::{archive_content, plan_retention, validate_policy, RetentionPolicy, ARCHIVE_SUFFIX};
+use crate::video::MediaType;
+
+const REPO: &str = "owner/photos";
+const DAY: i64 = 24 * 60 * 60;
+const NOW: i64 = 1_800_000_000;
+
+fn policy(days: u32) -> RetentionPolicy {
+    RetentionPolicy {
+        repo: REPO.into(),
+        album_path: "Trips".into(),
+        archive_repo: "owner/cold".into(),
+        archive_after_days: days,
+        compress: true,
+        keep_favorites: false,
+        enabled: true,
+    }
+}
+
+fn photo(name: &str, age_days: Option<i64>) -> CatalogPhoto {
+    CatalogPhoto {
+        repo: REPO.into(),
+        album: "Trips".into(),
+        path: format!("Trips/{}", name),
+        item: PhotoItem {
+            name: name.into(),
+            url: format!("https://example.com/{}", name),
+            sha: format!("sha-{}", name),
+            size: Some(100),
+            encrypted: false,
+            display_name: None,
+            repo: None,
+            caption: None,
+            exif: None,
+            raw_companion: None,
+            media_type: MediaType::Photo,
+        },
+        taken_at: age_days.map(|days| NOW - days * DAY),
+        tags: Vec::new(),
+        favorite: false,
+        rating: None,
+        sync_state: SyncState::Synced,
+        updated_at: 0,
+    }
+}
+
+fn candidate_paths(policy: &RetentionPolicy, photos: &[CatalogPhoto]) -> Vec<String> {
+    plan_retention(policy, photos, NOW).candidates.into_iter().map(|c| c.path).collect()
+}
+
+// ============================================================================
+// Planning Tests
+// ============================================================================
+
+#[test]
+fn photos_older_than_the_policy_are_archived_oldest_first() {
+    let photos = [photo("new.jpg", Some(10)), photo("old.jpg", Some(800)), photo("older.jpg", Some(900))];
+    let plan = plan_retention(&policy(730), &photos, NOW);
+    assert_eq!(plan.cutoff, NOW - 730 * DAY);
+    assert_eq!(
+        plan.candidates.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(),
+        vec!["Trips/older.jpg", "Trips/old.jpg"]
+    );
+    assert_eq!(plan.candidates[0].sha, "sha-older.jpg");
+    assert_eq!(plan.candidates[0].repo, REPO);
+}
+
+#[test]
+fn photos_at_the_cutoff_stay() {
+    let photos = [photo("edge.jpg", Some(730))];
+    assert!(candidate_paths(&policy(730), &photos).is_empty());
+}
+
+#[test]
+fn undated_and_encrypted_photos_stay() {
+    let mut encrypted = photo("sealed.vortex", Some(1000));
+    encrypted.item.encrypted = true;
+    let photos = [photo("unknown.jpg", None), encrypted];
+    let plan = plan_retention(&policy(30), &photos, NOW);
+    assert!(plan.candidates.is_empty());
+    assert_eq!(plan.undated, 1);
+    assert_eq!(plan.kept_encrypted, 1);
+}
+
+#[test]
+fn favorites_stay_only_when_asked() {
+    let mut favorite = photo("best.jpg", Some(1000));
+    favorite.favorite = true;
+    let photos = [favorite];
+    assert_eq!(candidate_paths(&policy(30), &photos), vec!["Trips/best.jpg".to_string()]);
+
+    let keep = RetentionPolicy {
+        keep_favorites: true,
+        ..policy(30)
+    };
+    let plan = plan_retention(&keep, &photos, NOW);
+    assert!(plan.candidates.is_empty());
+    assert_eq!(plan.kept_favorites, 1);
+}
+
+#[test]
+fn sharded_photos_keep_their_repository() {
+    let mut sharded = photo("a.jpg", Some(1000));
+    sharded.item.repo = Some("owner/photos-2".into());
+    let plan = plan_retention(&policy(30), &[sharded], NOW);
+    assert_eq!(plan.candidates[0].repo, "owner/photos-2");
+}
+
+// ============================================================================
+// Archive Tests
+// ============================================================================
+
+#[test]
+fn compressible_photos_are_stored_as_xz() {
+    let content = vec![b'a'; 10_000];
+    let (path, stored) = archive_content("Trips/scan.tiff", content.clone(), true).unwrap();
+    assert_eq!(path, format!("Trips/scan.tiff.{}", ARCHIVE_SUFFIX));
+    assert!(stored.len() < content.len());
+    assert_eq!(crate::compress::xz_decompress(&stored).unwrap(), content);
+}
+
+#[test]
+fn incompressible_or_uncompressed_photos_are_stored_as_is() {
+    let noise: Vec<u8> = (0..4096).map(|_| rand::random::<u8>()).collect();
+    assert_eq!(
+        archive_content("Trips/a.jpg", noise.clone(), true).unwrap(),
+        ("Trips/a.jpg".to_string(), noise)
+    );
+    let text = vec![b'a'; 10_000];
+    assert_eq!(
+        archive_content("Trips/b.tiff", text.clone(), false).unwrap(),
+        ("Trips/b.tiff".to_string(), text)
+    );
+}
+
+#[test]
+fn policies_are_validated() {
+    assert!(validate_policy(&policy(730)).is_ok());
+    assert!(validate_policy(&policy(0)).is_err());
+    let same = RetentionPolicy {
+        archive_repo: REPO.into(),
+        ..policy(30)
+    };
+    assert!(validate_policy(&same).is_err());
+    let escape = RetentionPolicy {
+        album_path: "../x".into(),
+        ..policy(30)
+    };
+    assert!(validate_policy(&escape).is_err());
+}
@@ -0,0 +1,109 @@
+//! Upload Policy Tests
+//!
+//! Tests for:
+//! - Size, extension and encryption rules
+//! - Minimum resolution from image headers
+//! - Embedded metadata detection for JPEG, PNG and WebP
+
+use crate::tests::common::png;
+use crate::upload_policy::{evaluate, has_embedded_metadata, image_dimensions, PolicyRule, UploadIntent, UploadPolicy};
+
+fn rules(policy: &UploadPolicy, name: &str, data: &[u8], intent: UploadIntent) -> Vec<PolicyRule> {
+    evaluate(policy, name, data.len() as u64, Some(data), intent)
+        .into_iter()
+        .map(|v| v.rule)
+        .collect()
+}
+
+// ============================================================================
+// Rule Tests
+// ============================================================================
+
+#[test]
+fn default_policy_allows_everything() {
+    let data = png(1, 1);
+    assert!(rules(&UploadPolicy::default(), "a.bin", &data, UploadIntent::default()).is_empty());
+}
+
+#[test]
+fn size_extension_and_encryption_rules() {
+    let policy = UploadPolicy {
+        max_file_size: Some(10),
+        allowed_extensions: vec!["jpg".into(), "png".into()],
+        require_encryption: true,
+        ..Default::default()
+    };
+    let data = png(1, 1);
+
+    assert_eq!(
+        rules(&policy, "a.gif", &data, UploadIntent::default()),
+        vec![PolicyRule::MaxFileSize, PolicyRule::AllowedExtensions, PolicyRule::Encryption]
+    );
+
+    let relaxed = UploadPolicy { max_file_size: None, ..policy };
+    let encrypted = UploadIntent { encrypted: true, strips_metadata: false };
+    assert!(rules(&relaxed, "A.PNG", &data, encrypted).is_empty());
+}
+
+#[test]
+fn minimum_resolution() {
+    let policy = UploadPolicy {
+        min_width: Some(4),
+        min_height: Some(3),
+        ..Default::default()
+    };
+
+    assert_eq!(image_dimensions(&png(4, 2)), Some((4, 2)));
+    assert_eq!(rules(&policy, "a.png", &png(4, 2), UploadIntent::default()), vec![PolicyRule::MinResolution]);
+    assert!(rules(&policy, "a.png", &png(4, 3), UploadIntent::default()).is_empty());
+    assert_eq!(rules(&policy, "a.png", b"not an image", UploadIntent::default()), vec![PolicyRule::MinResolution]);
+}
+
+#[test]
+fn unreadable_files_fail_data_rules() {
+    let policy = UploadPolicy {
+        require_metadata_stripped: true,
+        ..Default::default()
+    };
+    let violations = evaluate(&policy, "a.jpg", 10, None, UploadIntent::default());
+    assert_eq!(violations[0].rule, PolicyRule::Readable);
+}
+
+// ============================================================================
+// Metadata Tests
+// ============================================================================
+
+#[test]
+fn detects_jpeg_exif() {
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1, 0x00, 0x08];
+    jpeg.extend_from_slice(b"Exif\0\0");
+    jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02]);
+    assert!(has_embedded_metadata(&jpeg));
+
+    let clean = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46, 0xFF, 0xDA, 0x00, 0x02];
+    assert!(!has_embedded_metadata(&clean));
+}
+
+#[test]
+fn detects_png_text_and_webp_exif() {
+    assert!(!has_embedded_metadata(&png(1, 1)));
+
+    let mut tagged = png(1, 1);
+    let iend = tagged.len() - 12;
+    let text = [&[0, 0, 0, 3][..], b"tEXt", b"a\0b", &[0, 0, 0, 0]].concat();
+    tagged.splice(iend..iend, text);
+    assert!(has_embedded_metadata(&tagged));
+
+    let mut webp = b"RIFF\0\0\0\0WEBP".to_vec();
+    webp.extend_from_slice(b"VP8 \x02\0\0\0\0\0");
+    webp.extend_from_slice(b"EXIF\x01\0\0\0\0\0");
+    assert!(has_embedded_metadata(&webp));
+
+    let policy = UploadPolicy {
+        require_metadata_stripped: true,
+        ..Default::default()
+    };
+    assert_eq!(rules(&policy, "a.png", &tagged, UploadIntent::default()), vec![PolicyRule::MetadataStripped]);
+    let stripping = UploadIntent { encrypted: false, strips_metadata: true };
+    assert!(rules(&policy, "a.png", &tagged, stripping).is_empty());
+}
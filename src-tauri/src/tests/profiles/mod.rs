@@ -0,0 +1,6 @@
+//! Identity Profile Tests
+//!
+//! Organized by functionality:
+//! - `profile_tests` - Registry, per-profile directories and secret scoping
+
+pub mod profile_tests;
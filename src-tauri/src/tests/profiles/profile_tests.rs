@@ -0,0 +1,100 @@
+//! Identity Profile Tests
+//!
+//! Tests for:
+//! - Creating and switching profiles in the registry
+//! - Registry persistence
+//! - Data directories and keystore names per profile
+//! - Dropping in-memory keypairs on a switch
+
+use std::path::Path;
+
+use crate::crypto::{HybridKeypair, KeypairStore};
+use crate::keystore::{EncryptedFileStore, Keystore};
+use crate::profiles::{profile_dir, secret_name, ProfileRegistry, DEFAULT_PROFILE};
+use crate::tests::common::temp_dir;
+
+#[test]
+fn profiles_are_created_and_switched() {
+    let mut registry = ProfileRegistry::default();
+    assert_eq!(registry.active().id, DEFAULT_PROFILE);
+    assert_eq!(registry.active().store_file, "settings.json");
+
+    let work = registry.create("  Work Photos ", Some("acme/photos".into())).unwrap();
+    assert_eq!(work.id, "work-photos");
+    assert_eq!(work.name, "Work Photos");
+    assert_eq!(work.repo.as_deref(), Some("acme/photos"));
+    assert_eq!(work.store_file, "settings-work-photos.json");
+    // Creating does not activate
+    assert_eq!(registry.active, DEFAULT_PROFILE);
+
+    // Names are unique, ids are made unique
+    assert!(registry.create("work photos", None).is_err());
+    assert_eq!(registry.create("Work-Photos!", None).unwrap().id, "work-photos-2");
+    assert_eq!(registry.create("日本", None).unwrap().id, "profile");
+    assert!(registry.create("   ", None).is_err());
+    assert!(registry.create(&"x".repeat(65), None).is_err());
+
+    assert_eq!(registry.switch("work-photos").unwrap(), work);
+    assert_eq!(registry.active().id, "work-photos");
+    assert!(registry.switch("missing").is_err());
+    assert_eq!(registry.active, "work-photos");
+
+    let list = registry.list();
+    assert_eq!(list.active, "work-photos");
+    assert_eq!(list.profiles.len(), 4);
+}
+
+#[test]
+fn registry_survives_restart() {
+    let dir = temp_dir("profile", "registry");
+    let path = dir.join("profiles.json");
+    assert_eq!(ProfileRegistry::load_from(&path).unwrap(), ProfileRegistry::default());
+
+    let mut registry = ProfileRegistry::default();
+    registry.create("Personal", None).unwrap();
+    registry.switch("personal").unwrap();
+    registry.save_to(&path).unwrap();
+    assert_eq!(ProfileRegistry::load_from(&path).unwrap(), registry);
+
+    std::fs::write(&path, b"{ not json").unwrap();
+    assert!(ProfileRegistry::load_from(&path).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn profiles_keep_separate_state() {
+    let root = Path::new("/data/vortex-image");
+    // The default profile keeps the pre-profile layout
+    assert_eq!(profile_dir(root, DEFAULT_PROFILE), root);
+    assert_eq!(profile_dir(root, "work"), root.join("profiles").join("work"));
+    assert_eq!(secret_name(DEFAULT_PROFILE, "keypair_kek"), "keypair_kek");
+    assert_eq!(secret_name("work", "keypair_kek"), "work.keypair_kek");
+
+    let dir = temp_dir("profile", "secrets");
+    let keystore = |profile: &str| {
+        Keystore::new(vec![Box::new(EncryptedFileStore::new(dir.clone()))]).for_profile(profile)
+    };
+    keystore(DEFAULT_PROFILE).store("github_token", "personal-token").unwrap();
+    keystore("work").store("github_token", "work-token").unwrap();
+
+    assert_eq!(keystore(DEFAULT_PROFILE).retrieve("github_token").unwrap().unwrap().as_str(), "personal-token");
+    assert_eq!(keystore("work").retrieve("github_token").unwrap().unwrap().as_str(), "work-token");
+    assert!(keystore("other").retrieve("github_token").unwrap().is_none());
+    assert!(dir.join("work.github_token").exists());
+
+    assert!(keystore("work").delete("github_token").unwrap());
+    assert!(keystore(DEFAULT_PROFILE).retrieve("github_token").unwrap().is_some());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cleared_handles_are_never_reused() {
+    let mut store = KeypairStore::new();
+    let old = store.insert(HybridKeypair::generate().unwrap());
+    store.clear();
+    assert!(store.get(old).is_none());
+
+    let new = store.insert(HybridKeypair::generate().unwrap());
+    assert_ne!(new, old);
+    assert!(store.get(old).is_none());
+}
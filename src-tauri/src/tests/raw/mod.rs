@@ -0,0 +1,6 @@
+//! RAW Module Tests
+//!
+//! Organized by functionality:
+//! - `raw_tests` - Embedded previews, RAW metadata and RAW+JPEG pairs
+
+pub mod raw_tests;
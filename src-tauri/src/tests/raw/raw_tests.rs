@@ -0,0 +1,201 @@
+//! RAW Tests
+//!
+//! Tests for:
+//! - Telling RAW files from plain images
+//! - Finding the largest embedded preview in TIFF-based, RAF and scanned files
+//! - Sensor size, orientation and thumbnails from the preview
+//! - RAW+JPEG pairing in folders and album listings
+
+use image::GenericImageView;
+
+use crate::github::PhotoItem;
+use crate::raw::{decode_preview, extract_preview, is_raw, pair_photos, raw_exif, raw_pairs};
+use crate::tests::common::jpeg;
+use crate::thumbnails::render_thumbnail;
+use crate::video::MediaType;
+
+/// Where the payload after `ifds` starts
+fn payload_start(ifds: &[Vec<(u16, u32)>]) -> u32 {
+    8 + ifds.iter().map(|ifd| 2 + 12 * ifd.len() as u32 + 4).sum::<u32>()
+}
+
+/// Little-endian TIFF with chained `ifds` followed by `payload`. Entries
+/// are LONGs, except Orientation which is a SHORT.
+fn tiff(ifds: &[Vec<(u16, u32)>], payload: &[u8]) -> Vec<u8> {
+    let mut out = b"II*\0".to_vec();
+    out.extend(8u32.to_le_bytes());
+    for (i, ifd) in ifds.iter().enumerate() {
+        out.extend((ifd.len() as u16).to_le_bytes());
+        for (tag, value) in ifd {
+            out.extend(tag.to_le_bytes());
+            if *tag == 0x0112 {
+                out.extend(3u16.to_le_bytes());
+                out.extend(1u32.to_le_bytes());
+                out.extend((*value as u16).to_le_bytes());
+                out.extend([0, 0]);
+            } else {
+                out.extend(4u16.to_le_bytes());
+                out.extend(1u32.to_le_bytes());
+                out.extend(value.to_le_bytes());
+            }
+        }
+        let next = if i + 1 < ifds.len() { out.len() as u32 + 4 } else { 0 };
+        out.extend(next.to_le_bytes());
+    }
+    out.extend(payload);
+    out
+}
+
+/// NEF-style file: a reduced IFD0 pointing at the preview, and a second IFD
+/// pointing at a smaller thumbnail
+fn tiff_raw(orientation: u32) -> Vec<u8> {
+    let (thumb, preview) = (jpeg(16, 12), jpeg(64, 48));
+    let layout = |thumb_at: u32, preview_at: u32| {
+        vec![
+            vec![
+                (0x00FE, 1),
+                (0x0100, 6000),
+                (0x0101, 4000),
+                (0x0112, orientation),
+                (0x0201, preview_at),
+                (0x0202, preview.len() as u32),
+            ],
+            vec![(0x0201, thumb_at), (0x0202, thumb.len() as u32)],
+        ]
+    };
+    let start = payload_start(&layout(0, 0));
+    let ifds = layout(start, start + thumb.len() as u32);
+    tiff(&ifds, &[thumb, preview].concat())
+}
+
+fn photo(name: &str) -> PhotoItem {
+    PhotoItem {
+        name: name.into(),
+        url: format!("https://example.com/{}", name),
+        sha: format!("sha-{}", name),
+        size: None,
+        encrypted: false,
+        display_name: None,
+        repo: None,
+        caption: None,
+        exif: None,
+        raw_companion: None,
+        media_type: MediaType::Photo,
+    }
+}
+
+// ============================================================================
+// Detection Tests
+// ============================================================================
+
+#[test]
+fn plain_images_are_not_raw() {
+    let photo = jpeg(32, 24);
+    assert!(!is_raw(&photo));
+    assert!(extract_preview(&photo).is_none());
+
+    // A TIFF whose first image is the full one
+    let plain = tiff(&[vec![(0x0100, 32), (0x0101, 24)]], &[]);
+    assert!(!is_raw(&plain));
+    assert!(is_raw(&tiff_raw(1)));
+}
+
+// ============================================================================
+// Preview Tests
+// ============================================================================
+
+#[test]
+fn largest_tiff_preview_wins() {
+    let preview = extract_preview(&tiff_raw(1)).unwrap();
+    assert_eq!((preview.width, preview.height), (64, 48));
+    assert_eq!(image::load_from_memory(&preview.jpeg).unwrap().dimensions(), (64, 48));
+}
+
+#[test]
+fn raf_header_points_at_the_preview() {
+    let preview = jpeg(40, 30);
+    let mut raf = b"FUJIFILMCCD-RAW 0201FF383501".to_vec();
+    raf.resize(84, 0);
+    raf.extend(100u32.to_be_bytes());
+    raf.extend((preview.len() as u32).to_be_bytes());
+    raf.resize(100, 0);
+    raf.extend(&preview);
+    raf.extend([0u8; 64]);
+
+    assert!(is_raw(&raf));
+    assert_eq!(extract_preview(&raf).unwrap().jpeg, preview);
+}
+
+#[test]
+fn other_containers_are_scanned() {
+    // Lossless JPEG holds sensor data and must not be taken for a preview
+    let lossless: [u8; 15] = [0xFF, 0xD8, 0xFF, 0xC3, 0x00, 0x0B, 0x08, 0x10, 0x00, 0x10, 0x00, 0x01, 0x00, 0x11, 0x00];
+    let preview = jpeg(48, 32);
+    let cr3 = [
+        &b"\0\0\0\x18ftypcrx \0\0\0\x01crx isom"[..],
+        &lossless[..],
+        &[0xAB; 40][..],
+        &preview[..],
+        &[0xCD; 40][..],
+    ]
+    .concat();
+
+    let found = extract_preview(&cr3).unwrap();
+    assert_eq!((found.width, found.height), (48, 32));
+    assert_eq!(found.jpeg, preview);
+    assert!(extract_preview(b"\0\0\0\x18ftypcrx no previews").is_none());
+}
+
+// ============================================================================
+// Metadata Tests
+// ============================================================================
+
+#[test]
+fn metadata_reports_the_sensor() {
+    let exif = raw_exif(&tiff_raw(6)).unwrap();
+    assert_eq!((exif.width, exif.height), (Some(6000), Some(4000)));
+    assert_eq!(exif.orientation, Some(6));
+}
+
+#[test]
+fn previews_take_the_raw_orientation() {
+    let raw = tiff_raw(6);
+    assert_eq!(decode_preview(&raw).unwrap().dimensions(), (48, 64));
+
+    let thumbnail = render_thumbnail(&raw, 32).unwrap();
+    assert_eq!(image::load_from_memory(&thumbnail).unwrap().dimensions(), (24, 32));
+    assert!(decode_preview(&jpeg(8, 8)).is_err());
+}
+
+// ============================================================================
+// Pairing Tests
+// ============================================================================
+
+#[test]
+fn pairs_match_stems_in_the_same_folder() {
+    let pairs = raw_pairs(["IMG_1.CR2", "img_1.JPG", "IMG_2.NEF", "IMG_3.jpg", "sub/IMG_1.jpg", "IMG_4.arw", "IMG_4.heic"]);
+    assert_eq!(pairs.len(), 2);
+    assert_eq!(pairs["img_1.JPG"], "IMG_1.CR2");
+    assert_eq!(pairs["IMG_4.heic"], "IMG_4.arw");
+}
+
+#[test]
+fn listings_fold_raws_into_their_jpegs() {
+    let mut sealed_raw = photo("a1b2.vxe");
+    sealed_raw.display_name = Some("DSC_7.NEF".into());
+    let mut sealed_jpeg = photo("c3d4.vxe");
+    sealed_jpeg.display_name = Some("DSC_7.JPG".into());
+
+    let listed = pair_photos(vec![
+        photo("IMG_1.CR2"),
+        photo("IMG_1.jpg"),
+        photo("IMG_2.NEF"),
+        sealed_raw,
+        sealed_jpeg,
+    ]);
+    let summary: Vec<_> = listed.iter().map(|p| (p.name.as_str(), p.raw_companion.as_deref())).collect();
+    assert_eq!(
+        summary,
+        [("IMG_1.jpg", Some("IMG_1.CR2")), ("IMG_2.NEF", None), ("c3d4.vxe", Some("a1b2.vxe"))]
+    );
+}
@@ -0,0 +1,61 @@
+//! Remote Change Event Tests
+//!
+//! Tests for:
+//! - Filtering push events by branch and last seen id
+//! - Mapping changed paths to albums
+
+use serde_json::json;
+
+use crate::remote_watch::{changed_albums, latest_event_id, new_push_events};
+
+fn push(id: &str, branch: &str, head: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "type": "PushEvent",
+        "actor": { "login": "alice" },
+        "created_at": "2024-05-01T10:00:00Z",
+        "payload": { "ref": format!("refs/heads/{}", branch), "before": "b0", "head": head }
+    })
+}
+
+// ============================================================================
+// Event Tests
+// ============================================================================
+
+#[test]
+fn only_new_pushes_to_branch_oldest_first() {
+    let events = vec![
+        push("105", "main", "h5"),
+        json!({ "id": "104", "type": "WatchEvent", "payload": {} }),
+        push("103", "feature", "h3"),
+        push("102", "main", "h2"),
+        push("100", "main", "h0"),
+    ];
+
+    let pushes = new_push_events(&events, "refs/heads/main", 100);
+    let heads: Vec<_> = pushes.iter().map(|p| p.head.as_str()).collect();
+    assert_eq!(heads, vec!["h2", "h5"]);
+    assert_eq!(pushes[0].actor, "alice");
+
+    assert_eq!(latest_event_id(&events), Some(105));
+    assert_eq!(latest_event_id(&[]), None);
+}
+
+// ============================================================================
+// Album Mapping Tests
+// ============================================================================
+
+#[test]
+fn paths_map_to_albums() {
+    let paths = vec![
+        "photos/Trips/2024/a.jpg".to_string(),
+        "photos/Trips/2024/.vortex-album.json".to_string(),
+        "photos/Family/b.jpg".to_string(),
+        "photos/loose.jpg".to_string(),
+        "README.md".to_string(),
+    ];
+
+    assert_eq!(changed_albums(&paths, &[]), vec!["photos/Family", "photos/Trips/2024"]);
+    assert_eq!(changed_albums(&paths, &["photos/Trips".to_string()]), vec!["photos/Trips/2024"]);
+    assert!(changed_albums(&paths, &["photos/Tri".to_string()]).is_empty());
+}
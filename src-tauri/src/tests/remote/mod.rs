@@ -0,0 +1,6 @@
+//! Remote Watch Module Tests
+//!
+//! Organized by functionality:
+//! - `event_tests` - Push event parsing and album change detection
+
+pub mod event_tests;
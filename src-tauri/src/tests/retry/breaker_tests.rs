@@ -0,0 +1,139 @@
+//! Retry Policy and Circuit Breaker Tests
+//!
+//! Tests for:
+//! - Jittered exponential backoff bounds
+//! - Idempotent method and retryable status detection
+//! - Circuit breaker state transitions, including abandoned trials
+
+use reqwest::{Method, StatusCode};
+use std::time::{Duration, Instant};
+
+use crate::retry::{
+    backoff_delay, is_idempotent, is_retryable_status, is_unprocessed_status, BreakerState, CircuitBreaker,
+    RetryPolicy,
+};
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        base_delay_ms: 100,
+        max_delay_ms: 1000,
+        failure_threshold: 3,
+        cooldown_secs: 30,
+    }
+}
+
+// ============================================================================
+// Backoff Tests
+// ============================================================================
+
+#[test]
+fn backoff_grows_and_is_capped() {
+    let p = policy();
+
+    assert_eq!(backoff_delay(&p, 0, u64::MAX), Duration::from_millis(u64::MAX % 101));
+    assert!(backoff_delay(&p, 0, 12345) <= Duration::from_millis(100));
+    assert!(backoff_delay(&p, 2, 12345) <= Duration::from_millis(400));
+    assert_eq!(backoff_delay(&p, 3, 800), Duration::from_millis(800));
+    assert_eq!(backoff_delay(&p, 0, 0), Duration::ZERO, "full jitter can retry immediately");
+
+    for attempt in [4, 10, 63, u32::MAX] {
+        assert!(backoff_delay(&p, attempt, u64::MAX - 1) <= Duration::from_millis(p.max_delay_ms));
+    }
+}
+
+#[test]
+fn detects_idempotent_methods_and_transient_statuses() {
+    assert!(is_idempotent(&Method::GET));
+    assert!(is_idempotent(&Method::DELETE));
+    assert!(!is_idempotent(&Method::POST));
+    assert!(!is_idempotent(&Method::PATCH));
+    // A contents-API PUT commits each time it is sent
+    assert!(!is_idempotent(&Method::PUT));
+
+    assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+    assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+    assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+    assert!(!is_retryable_status(StatusCode::UNPROCESSABLE_ENTITY));
+
+    assert!(is_unprocessed_status(StatusCode::TOO_MANY_REQUESTS));
+    assert!(is_unprocessed_status(StatusCode::SERVICE_UNAVAILABLE));
+    assert!(!is_unprocessed_status(StatusCode::BAD_GATEWAY));
+    assert!(!is_unprocessed_status(StatusCode::GATEWAY_TIMEOUT));
+}
+
+// ============================================================================
+// Circuit Breaker Tests
+// ============================================================================
+
+#[test]
+fn breaker_opens_after_threshold_and_recovers() {
+    let p = policy();
+    let start = Instant::now();
+    let mut breaker = CircuitBreaker::default();
+
+    for _ in 0..2 {
+        assert!(breaker.allow(&p, start).is_ok());
+        breaker.on_failure(&p, start, "HTTP 502".into());
+    }
+    assert_eq!(breaker.state, BreakerState::Closed);
+
+    breaker.on_failure(&p, start, "HTTP 503".into());
+    assert_eq!(breaker.state, BreakerState::Open);
+    assert_eq!(breaker.allow(&p, start + Duration::from_secs(10)), Err(20));
+    assert_eq!(breaker.retry_in_secs(&p, start + Duration::from_secs(10)), Some(20));
+
+    // Cooldown over: exactly one trial request goes through
+    let later = start + Duration::from_secs(30);
+    assert_eq!(breaker.allow(&p, later), Ok(true));
+    assert_eq!(breaker.state, BreakerState::HalfOpen);
+    assert!(breaker.allow(&p, later).is_err());
+
+    breaker.on_success();
+    assert_eq!(breaker.state, BreakerState::Closed);
+    assert_eq!(breaker.consecutive_failures, 0);
+}
+
+#[test]
+fn failed_trial_reopens_breaker() {
+    let p = policy();
+    let start = Instant::now();
+    let mut breaker = CircuitBreaker::default();
+
+    for _ in 0..3 {
+        breaker.on_failure(&p, start, "timeout".into());
+    }
+    let later = start + Duration::from_secs(31);
+    assert!(breaker.allow(&p, later).is_ok());
+
+    breaker.on_failure(&p, later, "timeout".into());
+    assert_eq!(breaker.state, BreakerState::Open);
+    assert_eq!(breaker.last_error.as_deref(), Some("timeout"));
+    assert!(breaker.allow(&p, later + Duration::from_secs(1)).is_err());
+}
+
+#[test]
+fn abandoned_trial_passes_to_the_next_request() {
+    let p = policy();
+    let start = Instant::now();
+    let mut breaker = CircuitBreaker::default();
+
+    for _ in 0..3 {
+        breaker.on_failure(&p, start, "timeout".into());
+    }
+    let later = start + Duration::from_secs(31);
+    assert_eq!(breaker.allow(&p, later), Ok(true));
+    assert!(breaker.allow(&p, later).is_err());
+
+    // The trial's future was dropped: the breaker does not stay half-open
+    breaker.abandon_trial();
+    assert_eq!(breaker.state, BreakerState::Open);
+    assert_eq!(breaker.allow(&p, later), Ok(true));
+    assert_eq!(breaker.state, BreakerState::HalfOpen);
+
+    // Requests admitted while closed are not trials
+    breaker.on_success();
+    assert_eq!(breaker.allow(&p, later), Ok(false));
+    breaker.abandon_trial();
+    assert_eq!(breaker.state, BreakerState::Closed);
+}
@@ -0,0 +1,6 @@
+//! Retry Module Tests
+//!
+//! Organized by functionality:
+//! - `breaker_tests` - Backoff, idempotency detection and circuit breaker states
+
+pub mod breaker_tests;
@@ -0,0 +1,109 @@
+//! Activity Log Tests
+//!
+//! Tests for:
+//! - Appending entries and paging back through them
+//! - Refusing updates and deletes
+//! - Detecting altered, removed and truncated entries
+
+use crate::activity_log::{activity_page_in, append_activity_in, ActivityKind};
+use crate::local_store::LocalStore;
+use crate::security_verify::check_activity_log_in;
+
+const REPO: &str = "alice/photos";
+
+fn store_with(entries: usize) -> LocalStore {
+    let store = LocalStore::in_memory(&[9u8; 32]).unwrap();
+    for i in 0..entries {
+        let path = format!("photos/{}.jpg", i + 1);
+        append_activity_in(&store, ActivityKind::Upload, "upload_photo", Some(REPO), &path, None).unwrap();
+    }
+    store
+}
+
+fn unlock(store: &LocalStore) {
+    store
+        .connection()
+        .execute_batch("DROP TRIGGER activity_log_no_update; DROP TRIGGER activity_log_no_delete;")
+        .unwrap();
+}
+
+// ============================================================================
+// Log Tests
+// ============================================================================
+
+#[test]
+fn entries_page_newest_first() {
+    let store = store_with(4);
+    let entry = append_activity_in(&store, ActivityKind::Delete, "delete_photo", Some(REPO), "photos/1.jpg", None).unwrap();
+    assert_eq!(entry.seq, 5);
+
+    let page = activity_page_in(&store, None, 2).unwrap();
+    assert_eq!(page.entries.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![5, 4]);
+    assert_eq!(page.entries[0].kind, ActivityKind::Delete);
+    assert_eq!(page.entries[0].target, "photos/1.jpg");
+    assert_eq!(page.next_before, Some(4));
+
+    let page = activity_page_in(&store, Some(2), 10).unwrap();
+    assert_eq!(page.entries.len(), 1);
+    assert_eq!(page.entries[0].target, "photos/1.jpg");
+    assert_eq!(page.next_before, None);
+}
+
+#[test]
+fn rows_cannot_be_changed() {
+    let store = store_with(2);
+    let connection = store.connection();
+    assert!(connection.execute("UPDATE activity_log SET record = x'00' WHERE seq = 1", []).is_err());
+    assert!(connection.execute("DELETE FROM activity_log WHERE seq = 2", []).is_err());
+    assert!(check_activity_log_in(&store).unwrap().intact);
+}
+
+// ============================================================================
+// Tamper Detection Tests
+// ============================================================================
+
+#[test]
+fn untouched_logs_verify() {
+    let empty = check_activity_log_in(&store_with(0)).unwrap();
+    assert!(empty.intact);
+    assert_eq!(empty.entries, 0);
+
+    let check = check_activity_log_in(&store_with(3)).unwrap();
+    assert!(check.intact);
+    assert_eq!(check.entries, 3);
+    assert_eq!(check.broken_at, None);
+}
+
+#[test]
+fn altered_entries_are_found() {
+    let store = store_with(3);
+    unlock(&store);
+    store
+        .connection()
+        .execute("UPDATE activity_log SET record = (SELECT record FROM activity_log WHERE seq = 3) WHERE seq = 2", [])
+        .unwrap();
+
+    let check = check_activity_log_in(&store).unwrap();
+    assert!(!check.intact);
+    assert_eq!(check.broken_at, Some(2));
+}
+
+#[test]
+fn removed_entries_are_found() {
+    let store = store_with(3);
+    unlock(&store);
+    store.connection().execute("DELETE FROM activity_log WHERE seq = 2", []).unwrap();
+    assert_eq!(check_activity_log_in(&store).unwrap().broken_at, Some(2));
+
+    // Dropping the newest entries leaves a valid chain, but not the head's
+    let store = store_with(3);
+    unlock(&store);
+    store.connection().execute("DELETE FROM activity_log WHERE seq = 3", []).unwrap();
+    let check = check_activity_log_in(&store).unwrap();
+    assert!(!check.intact);
+    assert_eq!(check.broken_at, Some(3));
+
+    let store = store_with(2);
+    store.connection().execute("DELETE FROM activity_log_head", []).unwrap();
+    assert!(!check_activity_log_in(&store).unwrap().intact);
+}
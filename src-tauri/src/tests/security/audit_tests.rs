@@ -0,0 +1,53 @@
+//! Security Audit Tests
+//!
+//! Tests for:
+//! - Finding album folders in a repository tree
+//! - Classifying albums by manifest, encryption and signature
+
+use serde_json::json;
+
+use crate::git_data::{index_blobs, TreeEntry};
+use crate::security_verify::{album_dirs, album_issues, AuditIssue};
+
+fn entry(path: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.to_string(),
+        mode: "100644".to_string(),
+        kind: "blob".to_string(),
+        sha: "0".repeat(40),
+        size: None,
+    }
+}
+
+#[test]
+fn album_dirs_cover_photo_folders_and_manifests() {
+    let index = index_blobs(
+        [
+            "photos/Trips/a.jpg",
+            "photos/Trips/2024/b.vxe",
+            "photos/Empty/.vortex-album.json",
+            "photos/Docs/readme.txt",
+            "README.md",
+        ]
+        .into_iter()
+        .map(entry)
+        .collect(),
+    );
+
+    let dirs: Vec<_> = album_dirs(&index).into_iter().collect();
+    assert_eq!(dirs, vec!["photos/Empty", "photos/Trips", "photos/Trips/2024"]);
+}
+
+#[test]
+fn album_issues_by_manifest() {
+    assert_eq!(album_issues(None), vec![AuditIssue::MissingManifest]);
+
+    let plain = json!({ "version": 1, "encrypted": false, "created_at": 0 });
+    assert_eq!(
+        album_issues(Some(&plain)),
+        vec![AuditIssue::UnencryptedAlbum, AuditIssue::UnsignedManifest]
+    );
+
+    let secured = json!({ "version": 1, "encrypted": true, "created_at": 0, "signature": "sig" });
+    assert!(album_issues(Some(&secured)).is_empty());
+}
@@ -0,0 +1,12 @@
+//! Security Verification Module Tests
+//!
+//! Organized by functionality:
+//! - `audit_tests` - Album discovery and per-album audit issues
+//! - `signature_tests` - Manifest and photo signatures
+//! - `revocation_tests` - Signed key revocation entries
+//! - `activity_log_tests` - The append-only local activity log and its MAC chain
+
+pub mod audit_tests;
+pub mod signature_tests;
+pub mod revocation_tests;
+pub mod activity_log_tests;
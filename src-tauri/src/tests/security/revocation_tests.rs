@@ -0,0 +1,82 @@
+//! Key Revocation Tests
+//!
+//! Tests for:
+//! - Revocations by trusted signers and by the revoked key itself
+//! - Ignoring revocations from unknown signers or with edited fields
+//! - Not duplicating a signer's entry for the same key
+
+use crate::crypto::HybridKeypair;
+use crate::revocation::{RevocationEntry, RevocationList};
+use crate::security_verify::TrustedSigners;
+
+fn trusting(key_id: &str) -> TrustedSigners {
+    let mut trusted = TrustedSigners::default();
+    trusted.insert(key_id.to_string());
+    trusted
+}
+
+#[test]
+fn trusted_signer_revokes_another_key() {
+    let owner = HybridKeypair::generate().unwrap();
+    let lost = HybridKeypair::generate().unwrap().public_bundle().key_id;
+    let mut list = RevocationList::default();
+    assert!(list.add(RevocationEntry::sign(&lost, "phone stolen", &owner).unwrap()));
+
+    let status = list.status(&lost, &trusting(&owner.public_bundle().key_id));
+    assert!(status.revoked);
+    assert_eq!(status.entry.unwrap().reason, "phone stolen");
+    assert_eq!(status.ignored, 0);
+
+    // Other keys are unaffected
+    assert!(!list.status(&owner.public_bundle().key_id, &TrustedSigners::default()).revoked);
+}
+
+#[test]
+fn untrusted_signers_are_ignored() {
+    let stranger = HybridKeypair::generate().unwrap();
+    let victim = HybridKeypair::generate().unwrap().public_bundle().key_id;
+    let mut list = RevocationList::default();
+    list.add(RevocationEntry::sign(&victim, "", &stranger).unwrap());
+
+    let status = list.status(&victim, &TrustedSigners::default());
+    assert!(!status.revoked);
+    assert_eq!(status.ignored, 1);
+}
+
+#[test]
+fn a_key_may_revoke_itself() {
+    let device = HybridKeypair::generate().unwrap();
+    let key_id = device.public_bundle().key_id;
+    let mut list = RevocationList::default();
+    list.add(RevocationEntry::sign(&key_id, "retired", &device).unwrap());
+    assert!(list.status(&key_id, &TrustedSigners::default()).revoked);
+}
+
+#[test]
+fn edited_entries_are_ignored() {
+    let owner = HybridKeypair::generate().unwrap();
+    let trusted = trusting(&owner.public_bundle().key_id);
+    let lost = "00112233aabbccdd";
+    let signed = RevocationEntry::sign(lost, "lost", &owner).unwrap();
+
+    let mut retargeted = signed.clone();
+    retargeted.key_id = "ffeeddccbbaa9988".into();
+    let list = RevocationList { entries: vec![retargeted] };
+    let status = list.status("ffeeddccbbaa9988", &trusted);
+    assert!(!status.revoked);
+    assert_eq!(status.ignored, 1);
+
+    let mut backdated = signed;
+    backdated.revoked_at -= 1;
+    let list = RevocationList { entries: vec![backdated] };
+    assert!(!list.status(lost, &trusted).revoked);
+}
+
+#[test]
+fn signer_cannot_add_duplicate_entries() {
+    let owner = HybridKeypair::generate().unwrap();
+    let mut list = RevocationList::default();
+    assert!(list.add(RevocationEntry::sign("00112233aabbccdd", "lost", &owner).unwrap()));
+    assert!(!list.add(RevocationEntry::sign("00112233aabbccdd", "again", &owner).unwrap()));
+    assert_eq!(list.entries.len(), 1);
+}
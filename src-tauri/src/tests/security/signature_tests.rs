@@ -0,0 +1,203 @@
+//! Signature Tests
+//!
+//! Tests for:
+//! - Signing album manifests and detecting edits made after signing
+//! - Detached photo signatures over the stored bytes
+//! - Trusted versus unknown signers
+//! - Photos the signed manifest lists losing their signature
+//! - Signature files following their photo through batch moves and deletes
+
+use crate::album::AlbumManifest;
+use crate::batch::{plan_delete, plan_move};
+use crate::crypto::{generate_keypair, release_keypair};
+use crate::git_data::{index_blobs, TreeEntry};
+use crate::security_verify::{
+    check_album_photo, check_manifest, check_photo, ensure_intact, has_missing_signatures, has_unsigned_photos,
+    sign_photo, IntegrityStatus, PhotoSignature, TrustedSigners,
+};
+
+const PHOTO: &[u8] = b"\xff\xd8\xff\xe0 not really a jpeg";
+
+fn entry(path: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.to_string(),
+        mode: "100644".to_string(),
+        kind: "blob".to_string(),
+        sha: format!("sha-{}", path),
+        size: None,
+    }
+}
+
+fn trusting(key_id: &str) -> TrustedSigners {
+    let mut trusted = TrustedSigners::default();
+    trusted.insert(key_id.to_string());
+    trusted
+}
+
+#[test]
+fn manifest_signatures_cover_every_field() {
+    let kp = generate_keypair().unwrap();
+    let trusted = trusting(&kp.key_id);
+    let mut manifest = AlbumManifest::new(true, Some(kp.key_id.clone()));
+    assert_eq!(check_manifest("m", &manifest, &trusted).status, IntegrityStatus::Unsigned);
+
+    manifest.resign(Some(kp.handle)).unwrap();
+    let check = check_manifest("m", &manifest, &trusted);
+    assert_eq!(check.status, IntegrityStatus::Verified);
+    assert_eq!(check.signer_key_id.as_deref(), Some(kp.key_id.as_str()));
+    assert_eq!(
+        check_manifest("m", &manifest, &TrustedSigners::default()).status,
+        IntegrityStatus::UntrustedSigner
+    );
+
+    // The signature survives the trip through the repository
+    let stored: AlbumManifest = serde_json::from_slice(&serde_json::to_vec_pretty(&manifest).unwrap()).unwrap();
+    assert_eq!(check_manifest("m", &stored, &trusted).status, IntegrityStatus::Verified);
+
+    let mut edited = stored.clone();
+    edited.description = Some("pwned".into());
+    assert_eq!(check_manifest("m", &edited, &trusted).status, IntegrityStatus::Tampered);
+    assert!(ensure_intact(check_manifest("m", &edited, &trusted)).is_err());
+
+    // Saving without a keypair drops the stale signature
+    edited.resign(None).unwrap();
+    assert!(edited.signature.is_none());
+    assert_eq!(check_manifest("m", &edited, &trusted).status, IntegrityStatus::Unsigned);
+
+    release_keypair(kp.handle).unwrap();
+}
+
+#[test]
+fn photo_signatures_check_hash_and_signer() {
+    let kp = generate_keypair().unwrap();
+    let trusted = trusting(&kp.key_id);
+    let signature = sign_photo(kp.handle, PHOTO).unwrap();
+
+    let check = check_photo("photos/a.jpg", PHOTO, Some(&signature), &trusted);
+    assert_eq!(check.status, IntegrityStatus::Verified);
+    assert!(ensure_intact(check).is_ok());
+    assert_eq!(check_photo("photos/a.jpg", PHOTO, None, &trusted).status, IntegrityStatus::Unsigned);
+
+    let mut altered = PHOTO.to_vec();
+    altered[5] ^= 1;
+    let check = check_photo("photos/a.jpg", &altered, Some(&signature), &trusted);
+    assert_eq!(check.status, IntegrityStatus::Tampered);
+    assert!(ensure_intact(check).is_err());
+
+    assert_eq!(
+        check_photo("photos/a.jpg", PHOTO, Some(b"{ not json"), &trusted).status,
+        IntegrityStatus::Tampered
+    );
+
+    release_keypair(kp.handle).unwrap();
+}
+
+#[test]
+fn re_signed_hashes_do_not_verify() {
+    let owner = generate_keypair().unwrap();
+    let other = generate_keypair().unwrap();
+    let trusted = trusting(&owner.key_id);
+    let mut altered = PHOTO.to_vec();
+    altered.push(0);
+
+    // Someone updates the hash to match their edit but cannot re-sign as the owner
+    let mut record: PhotoSignature = serde_json::from_slice(&sign_photo(owner.handle, PHOTO).unwrap()).unwrap();
+    record.blake3 = blake3::hash(&altered).to_hex().to_string();
+    record.size = altered.len() as u64;
+    let forged = serde_json::to_vec(&record).unwrap();
+    assert_eq!(check_photo("p", &altered, Some(&forged), &trusted).status, IntegrityStatus::Tampered);
+
+    // Signing with their own key is valid, but not trusted
+    let theirs = sign_photo(other.handle, &altered).unwrap();
+    let check = check_photo("p", &altered, Some(&theirs), &trusted);
+    assert_eq!(check.status, IntegrityStatus::UntrustedSigner);
+    assert_eq!(check.signer_key_id.as_deref(), Some(other.key_id.as_str()));
+
+    // Claiming the owner's key id with their own key is caught
+    let mut claimed: PhotoSignature = serde_json::from_slice(&theirs).unwrap();
+    claimed.signer.key_id = owner.key_id.clone();
+    let claimed = serde_json::to_vec(&claimed).unwrap();
+    assert_eq!(check_photo("p", &altered, Some(&claimed), &trusted).status, IntegrityStatus::Tampered);
+
+    release_keypair(owner.handle).unwrap();
+    release_keypair(other.handle).unwrap();
+}
+
+#[test]
+fn signature_files_follow_their_photo() {
+    let index = index_blobs(
+        ["photos/a.jpg", "photos/a.jpg.vxsig", "photos/b.jpg", "photos/trip/.gitkeep"]
+            .into_iter()
+            .map(entry)
+            .collect(),
+    );
+    assert!(has_unsigned_photos(&index, "photos"));
+    assert!(!has_unsigned_photos(&index, "photos/trip"));
+
+    let plan = plan_move(&index, &["photos/a.jpg".to_string(), "photos/b.jpg".to_string()], "photos/trip");
+    let paths: Vec<_> = plan.changes.iter().map(|c| c.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "photos/trip/a.jpg",
+            "photos/a.jpg",
+            "photos/trip/a.jpg.vxsig",
+            "photos/a.jpg.vxsig",
+            "photos/trip/b.jpg",
+            "photos/b.jpg",
+        ]
+    );
+    assert_eq!(plan.changes[2].sha.as_deref(), Some("sha-photos/a.jpg.vxsig"));
+
+    let plan = plan_delete(&index, &["photos/a.jpg".to_string()]);
+    let paths: Vec<_> = plan.changes.iter().map(|c| c.path.as_str()).collect();
+    assert_eq!(paths, vec!["photos/a.jpg", "photos/a.jpg.vxsig"]);
+}
+
+#[test]
+fn listed_photos_without_signature_are_tampered() {
+    let kp = generate_keypair().unwrap();
+    let trusted = trusting(&kp.key_id);
+    let signature = sign_photo(kp.handle, PHOTO).unwrap();
+    let mut manifest = AlbumManifest::new(true, Some(kp.key_id.clone()));
+    manifest.record_signed("a.vxe", PHOTO);
+    manifest.resign(Some(kp.handle)).unwrap();
+    assert_eq!(check_manifest("m", &manifest, &trusted).status, IntegrityStatus::Verified);
+
+    let check = check_album_photo("photos/x/a.vxe", PHOTO, Some(&signature), Some(&manifest), &trusted);
+    assert_eq!(check.status, IntegrityStatus::Verified);
+
+    // Deleting the `.vxsig` no longer passes as merely unsigned
+    let check = check_album_photo("photos/x/a.vxe", PHOTO, None, Some(&manifest), &trusted);
+    assert_eq!(check.status, IntegrityStatus::Tampered);
+    assert!(ensure_intact(check).is_err());
+
+    // Nor does replacing photo and signature with someone else's
+    let other = generate_keypair().unwrap();
+    let replaced = b"another photo";
+    let theirs = sign_photo(other.handle, replaced).unwrap();
+    let check = check_album_photo("photos/x/a.vxe", replaced, Some(&theirs), Some(&manifest), &trusted);
+    assert_eq!(check.status, IntegrityStatus::Tampered);
+
+    // Unlisted photos are only unsigned, and dropping the listing breaks the manifest
+    let check = check_album_photo("photos/x/b.vxe", PHOTO, None, Some(&manifest), &trusted);
+    assert_eq!(check.status, IntegrityStatus::Unsigned);
+    manifest.signed.clear();
+    assert_eq!(check_manifest("m", &manifest, &trusted).status, IntegrityStatus::Tampered);
+
+    release_keypair(kp.handle).unwrap();
+    release_keypair(other.handle).unwrap();
+}
+
+#[test]
+fn audit_finds_listed_photos_without_signature() {
+    let mut manifest = AlbumManifest::new(true, None);
+    manifest.record_signed("a.vxe", PHOTO);
+    manifest.record_signed("gone.vxe", PHOTO);
+
+    let signed = index_blobs(["photos/x/a.vxe", "photos/x/a.vxe.vxsig"].into_iter().map(entry).collect());
+    assert!(!has_missing_signatures(&signed, "photos/x", &manifest));
+
+    let stripped = index_blobs(["photos/x/a.vxe"].into_iter().map(entry).collect());
+    assert!(has_missing_signatures(&stripped, "photos/x", &manifest));
+}
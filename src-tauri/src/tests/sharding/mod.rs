@@ -0,0 +1,6 @@
+//! Sharding Module Tests
+//!
+//! Organized by functionality:
+//! - `rebalance_tests` - Shard naming, album movability and rebalance planning
+
+pub mod rebalance_tests;
@@ -0,0 +1,144 @@
+//! Shard Rebalance Tests
+//!
+//! Tests for:
+//! - Shard map defaults and naming
+//! - Album movability (encrypted albums stay put)
+//! - Shard sizes from the current tree
+//! - Rebalance planning across existing and new shards
+
+use std::collections::HashMap;
+
+use crate::git_data::{index_blobs, TreeEntry};
+use crate::sharding::{is_movable_album, plan_rebalance, tree_size_bytes, ShardMap};
+use crate::stats::AlbumUsage;
+
+const MB: u64 = 1024 * 1024;
+
+fn usage(path: &str, bytes: u64) -> AlbumUsage {
+    AlbumUsage {
+        album_path: path.to_string(),
+        photo_count: 1,
+        total_bytes: bytes,
+    }
+}
+
+fn blob(path: &str, size: u64) -> TreeEntry {
+    TreeEntry {
+        path: path.to_string(),
+        mode: "100644".to_string(),
+        kind: "blob".to_string(),
+        sha: "x".to_string(),
+        size: Some(size),
+    }
+}
+
+fn map_with(shards: &[&str], threshold: u64) -> ShardMap {
+    let mut map = ShardMap::new(shards[0]);
+    map.shards = shards.iter().map(|s| s.to_string()).collect();
+    map.threshold_bytes = threshold;
+    map
+}
+
+// ============================================================================
+// Shard Map Tests
+// ============================================================================
+
+#[test]
+fn shard_map_defaults_to_primary() {
+    let map = ShardMap::new("alice/photos");
+
+    assert_eq!(map.primary(), "alice/photos");
+    assert_eq!(map.active(), "alice/photos");
+    assert_eq!(map.next_shard_name(), "alice/photos-2");
+}
+
+#[test]
+fn shard_map_active_is_latest_shard() {
+    let map = map_with(&["alice/photos", "alice/photos-2"], 100 * MB);
+
+    assert_eq!(map.active(), "alice/photos-2");
+    assert_eq!(map.next_shard_name(), "alice/photos-3");
+}
+
+#[test]
+fn encrypted_albums_are_not_movable() {
+    let index = index_blobs(vec![
+        blob("photos/trip/a.jpg", 1),
+        blob("photos/vault/.vortex-album.json", 1),
+        blob("photos/vault/0123.vxe", 1),
+    ]);
+
+    assert!(is_movable_album(&index, "photos/trip"));
+    assert!(!is_movable_album(&index, "photos/vault"));
+    assert!(!is_movable_album(&index, "photos"), "loose photos have no album to move");
+}
+
+#[test]
+fn shard_size_counts_only_the_current_tree() {
+    let mut index = index_blobs(vec![blob("photos/trip/a.jpg", 3 * MB), blob("photos/trip/b.jpg", 2 * MB)]);
+    assert_eq!(tree_size_bytes(&index), 5 * MB);
+
+    // An album moved out no longer counts, whatever the history holds
+    index.retain(|path, _| !path.starts_with("photos/trip/a"));
+    assert_eq!(tree_size_bytes(&index), 2 * MB);
+}
+
+// ============================================================================
+// Rebalance Planning Tests
+// ============================================================================
+
+#[test]
+fn rebalance_noop_under_threshold() {
+    let map = map_with(&["alice/photos"], 100 * MB);
+    let sizes = HashMap::from([("alice/photos".to_string(), 50 * MB)]);
+    let albums = HashMap::from([("alice/photos".to_string(), vec![usage("photos/a", 50 * MB)])]);
+
+    assert!(plan_rebalance(&map, &sizes, &albums).is_empty());
+}
+
+#[test]
+fn rebalance_moves_largest_album_to_existing_shard() {
+    let map = map_with(&["alice/photos", "alice/photos-2"], 100 * MB);
+    let sizes = HashMap::from([
+        ("alice/photos".to_string(), 130 * MB),
+        ("alice/photos-2".to_string(), 10 * MB),
+    ]);
+    let albums = HashMap::from([(
+        "alice/photos".to_string(),
+        vec![usage("photos/small", 20 * MB), usage("photos/big", 60 * MB)],
+    )]);
+
+    let moves = plan_rebalance(&map, &sizes, &albums);
+
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].album_path, "photos/big");
+    assert_eq!(moves[0].to, "alice/photos-2");
+    assert!(!moves[0].creates_shard);
+}
+
+#[test]
+fn rebalance_creates_new_shard_when_full() {
+    let map = map_with(&["alice/photos"], 100 * MB);
+    let sizes = HashMap::from([("alice/photos".to_string(), 250 * MB)]);
+    let albums = HashMap::from([(
+        "alice/photos".to_string(),
+        vec![usage("photos/a", 80 * MB), usage("photos/b", 80 * MB), usage("photos/c", 80 * MB)],
+    )]);
+
+    let moves = plan_rebalance(&map, &sizes, &albums);
+
+    assert_eq!(moves.len(), 2);
+    assert_eq!(moves[0].to, "alice/photos-2");
+    assert!(moves[0].creates_shard);
+    assert_eq!(moves[1].to, "alice/photos-3");
+    assert!(moves[1].creates_shard);
+}
+
+#[test]
+fn rebalance_skips_albums_larger_than_threshold() {
+    let map = map_with(&["alice/photos"], 100 * MB);
+    let sizes = HashMap::from([("alice/photos".to_string(), 150 * MB)]);
+    let albums = HashMap::from([("alice/photos".to_string(), vec![usage("photos/huge", 150 * MB)])]);
+
+    assert!(plan_rebalance(&map, &sizes, &albums).is_empty());
+}
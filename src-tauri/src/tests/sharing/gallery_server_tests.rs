@@ -0,0 +1,178 @@
+//! LAN Gallery Tests
+//!
+//! Tests for:
+//! - Basic auth against the session's credentials
+//! - Lockout of addresses after repeated failed logins, also under parallel
+//!   requests
+//! - The JSON listing served for an album
+//! - Content types and certificate fingerprints
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::gallery_server::{
+    check_basic_auth, content_type, credentials_hash, fingerprint, gallery_listing, AuthOutcome, AuthThrottle,
+    GALLERY_USERNAME, LOCKOUT, MAX_FAILED_ATTEMPTS, MAX_LOCKOUT,
+};
+use crate::github::PhotoItem;
+use crate::metadata_vault::ExifExtract;
+use crate::video::MediaType;
+
+fn basic(user: &str, password: &str) -> String {
+    format!("Basic {}", STANDARD.encode(format!("{}:{}", user, password)))
+}
+
+fn item(name: &str) -> PhotoItem {
+    PhotoItem {
+        name: name.into(),
+        url: format!("https://example.com/{}", name),
+        sha: format!("sha-{}", name),
+        size: Some(100),
+        media_type: MediaType::of(name),
+        encrypted: false,
+        display_name: None,
+        repo: None,
+        caption: None,
+        exif: None,
+        raw_companion: None,
+    }
+}
+
+// ============================================================================
+// Auth Tests
+// ============================================================================
+
+#[test]
+fn only_the_session_credentials_are_accepted() {
+    let expected = credentials_hash(GALLERY_USERNAME, "correct horse");
+    assert!(check_basic_auth(Some(&basic(GALLERY_USERNAME, "correct horse")), &expected));
+
+    assert!(!check_basic_auth(Some(&basic(GALLERY_USERNAME, "wrong horse")), &expected));
+    assert!(!check_basic_auth(Some(&basic("admin", "correct horse")), &expected));
+    assert!(!check_basic_auth(Some("Bearer correct horse"), &expected));
+    assert!(!check_basic_auth(Some("Basic not base64!"), &expected));
+    assert!(!check_basic_auth(None, &expected));
+}
+
+const ATTACKER: &str = "192.168.1.66";
+const FRIEND: &str = "192.168.1.20";
+
+fn ip(address: &str) -> IpAddr {
+    address.parse().unwrap()
+}
+
+/// Try `n` wrong logins from `address`, returning the last outcome
+fn fail(throttle: &mut AuthThrottle, address: &str, n: u32, now: Instant) -> AuthOutcome {
+    (0..n).map(|_| throttle.attempt(ip(address), now, || false)).last().unwrap()
+}
+
+#[test]
+fn repeated_failures_lock_the_address_out() {
+    let mut throttle = AuthThrottle::default();
+    let now = Instant::now();
+    assert_eq!(fail(&mut throttle, ATTACKER, MAX_FAILED_ATTEMPTS - 1, now), AuthOutcome::Rejected(None));
+    assert_eq!(throttle.locked_for(ip(ATTACKER), now), None);
+
+    assert_eq!(fail(&mut throttle, ATTACKER, 1, now), AuthOutcome::Rejected(Some(LOCKOUT)));
+    assert_eq!(throttle.locked_for(ip(ATTACKER), now), Some(LOCKOUT));
+    assert_eq!(throttle.locked_for(ip(ATTACKER), now + LOCKOUT), None);
+
+    // Locked out, even with the right password, while other addresses are not
+    assert_eq!(throttle.attempt(ip(ATTACKER), now, || true), AuthOutcome::LockedOut(LOCKOUT));
+    assert_eq!(throttle.attempt(ip(FRIEND), now, || true), AuthOutcome::Accepted);
+}
+
+#[test]
+fn lockouts_grow_up_to_the_limit() {
+    let mut throttle = AuthThrottle::default();
+    let mut now = Instant::now();
+    let mut expected = LOCKOUT;
+    for _ in 0..20 {
+        assert_eq!(fail(&mut throttle, ATTACKER, MAX_FAILED_ATTEMPTS, now), AuthOutcome::Rejected(Some(expected)));
+        now += expected;
+        expected = (expected * 2).min(MAX_LOCKOUT);
+    }
+    assert_eq!(expected, MAX_LOCKOUT);
+}
+
+#[test]
+fn a_successful_login_resets_the_count() {
+    let mut throttle = AuthThrottle::default();
+    let now = Instant::now();
+    fail(&mut throttle, FRIEND, MAX_FAILED_ATTEMPTS - 1, now);
+    assert_eq!(throttle.attempt(ip(FRIEND), now, || true), AuthOutcome::Accepted);
+    assert_eq!(fail(&mut throttle, FRIEND, MAX_FAILED_ATTEMPTS - 1, now), AuthOutcome::Rejected(None));
+    assert_eq!(throttle.locked_for(ip(FRIEND), now), None);
+}
+
+#[test]
+fn parallel_wrong_logins_stop_at_the_threshold() {
+    const REQUESTS: usize = 64;
+    let throttle = Mutex::new(AuthThrottle::default());
+    let evaluated = AtomicUsize::new(0);
+    let locked_out = AtomicUsize::new(0);
+    let now = Instant::now();
+
+    std::thread::scope(|scope| {
+        for _ in 0..REQUESTS {
+            scope.spawn(|| {
+                let outcome = throttle.lock().unwrap().attempt(ip(ATTACKER), now, || {
+                    evaluated.fetch_add(1, Ordering::SeqCst);
+                    false
+                });
+                if matches!(outcome, AuthOutcome::LockedOut(_)) {
+                    locked_out.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+    });
+
+    assert_eq!(evaluated.load(Ordering::SeqCst), MAX_FAILED_ATTEMPTS as usize);
+    assert_eq!(locked_out.load(Ordering::SeqCst), REQUESTS - MAX_FAILED_ATTEMPTS as usize);
+}
+
+// ============================================================================
+// Listing Tests
+// ============================================================================
+
+#[test]
+fn listings_show_decrypted_names_and_metadata() {
+    let mut sealed = item("3f2a.vxe");
+    sealed.encrypted = true;
+    sealed.display_name = Some("Colosseum.jpg".into());
+    sealed.caption = Some("Day one".into());
+    sealed.exif = Some(ExifExtract { taken_at: Some("2024:05:01 10:00:00".into()), ..Default::default() });
+
+    let listing = gallery_listing("photos/Trips/Rome", &[sealed, item("clip.mp4")]);
+    assert_eq!(listing.name, "Rome");
+    assert_eq!(listing.photos.len(), 2);
+    assert_eq!(listing.photos[0].id, 0);
+    assert_eq!(listing.photos[0].name, "Colosseum.jpg");
+    assert_eq!(listing.photos[0].caption.as_deref(), Some("Day one"));
+    assert_eq!(listing.photos[0].taken_at.as_deref(), Some("2024:05:01 10:00:00"));
+    assert_eq!(listing.photos[1].name, "clip.mp4");
+    assert_eq!(listing.photos[1].media_type, MediaType::Video);
+}
+
+// ============================================================================
+// Response Tests
+// ============================================================================
+
+#[test]
+fn content_types_follow_the_extension() {
+    assert_eq!(content_type("Colosseum.JPG"), "image/jpeg");
+    assert_eq!(content_type("clip.mp4"), "video/mp4");
+    assert_eq!(content_type("scan.heic"), "image/heif");
+    assert_eq!(content_type("notes"), "application/octet-stream");
+}
+
+#[test]
+fn fingerprints_look_like_the_browser_shows_them() {
+    let print = fingerprint(b"certificate");
+    assert_eq!(print.len(), 32 * 3 - 1);
+    assert!(print.split(':').all(|b| b.len() == 2 && b.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase())));
+    assert_ne!(print, fingerprint(b"other certificate"));
+}
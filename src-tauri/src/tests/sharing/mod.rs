@@ -0,0 +1,12 @@
+//! Sharing Module Tests
+//!
+//! Organized by functionality:
+//! - `share_link_tests` - Share link encoding, validation and expiry
+//! - `photo_link_tests` - Expiring photo links that do not carry the album key
+//! - `gallery_server_tests` - Albums served to the local network
+//! - `p2p_transfer_tests` - Tickets and manifests for direct transfers
+
+pub mod share_link_tests;
+pub mod photo_link_tests;
+pub mod gallery_server_tests;
+pub mod p2p_transfer_tests;
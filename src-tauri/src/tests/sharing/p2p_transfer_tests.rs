@@ -0,0 +1,94 @@
+//! Peer-to-Peer Transfer Tests
+//!
+//! Tests for:
+//! - Encoding and decoding transfer tickets
+//! - Manifests built from files and album folders
+//! - Keeping received files inside the destination folder
+
+use std::net::SocketAddr;
+use std::path::Path;
+
+use crate::p2p_transfer::{build_manifest, offer_id, safe_destination, TransferTicket, TICKET_PREFIX};
+use crate::tests::common::temp_dir;
+
+fn ticket() -> TransferTicket {
+    TransferTicket {
+        node_id: "ae58ff8833241ac82d6ff7611046ed67b5072d142c588d0063e942d9a75502b6".into(),
+        relay_url: Some("https://euw1-1.relay.iroh.network./".into()),
+        addrs: vec!["192.168.1.20:41234".parse::<SocketAddr>().unwrap()],
+        secret: hex::encode([7u8; 32]),
+    }
+}
+
+// ============================================================================
+// Ticket Tests
+// ============================================================================
+
+#[test]
+fn tickets_survive_being_copied_around() {
+    let encoded = ticket().encode();
+    assert!(encoded.starts_with(TICKET_PREFIX));
+    assert!(encoded.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    assert_eq!(TransferTicket::decode(&format!("  {}\n", encoded)).unwrap(), ticket());
+    assert_eq!(ticket().secret_bytes().unwrap(), [7u8; 32]);
+}
+
+#[test]
+fn malformed_tickets_are_rejected() {
+    let encoded = ticket().encode();
+    assert!(TransferTicket::decode(&encoded[TICKET_PREFIX.len()..]).is_err());
+    assert!(TransferTicket::decode(&format!("{}!!", TICKET_PREFIX)).is_err());
+    assert!(TransferTicket::decode(&encoded[..encoded.len() - 4]).is_err());
+
+    let short = TransferTicket { secret: hex::encode([7u8; 16]), ..ticket() };
+    assert!(TransferTicket::decode(&short.encode()).is_err());
+}
+
+#[test]
+fn both_sides_derive_the_same_offer_id() {
+    let id = offer_id(&[7u8; 32]);
+    assert_eq!(id.len(), 16);
+    assert_eq!(id, offer_id(&ticket().secret_bytes().unwrap()));
+    assert_ne!(id, offer_id(&[8u8; 32]));
+}
+
+// ============================================================================
+// Manifest Tests
+// ============================================================================
+
+#[test]
+fn folders_are_sent_under_their_own_name() {
+    let dir = temp_dir("p2p", "manifest");
+    let album = dir.join("Rome");
+    std::fs::create_dir_all(album.join("Day 2")).unwrap();
+    std::fs::create_dir_all(album.join(".thumbs")).unwrap();
+    std::fs::write(album.join("a.jpg"), b"first").unwrap();
+    std::fs::write(album.join("notes.txt"), b"not a photo").unwrap();
+    std::fs::write(album.join("Day 2/b.png"), b"second").unwrap();
+    std::fs::write(album.join(".thumbs/a.jpg"), b"thumb").unwrap();
+    std::fs::write(dir.join("loose.jpg"), b"third").unwrap();
+
+    let paths = [album.to_string_lossy().to_string(), dir.join("loose.jpg").to_string_lossy().to_string()];
+    let (manifest, sources) = build_manifest(&paths).unwrap();
+    let names: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(names, ["Rome/Day 2/b.png", "Rome/a.jpg", "loose.jpg"]);
+    assert_eq!(sources[1], album.join("a.jpg"));
+    assert_eq!(manifest.files[1].blake3, blake3::hash(b"first").to_hex().to_string());
+    assert_eq!(manifest.total_bytes(), 16);
+
+    assert!(build_manifest(&[dir.join("missing").to_string_lossy().to_string()]).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn received_paths_stay_inside_the_destination() {
+    let dest = Path::new("/home/alice/Received");
+    assert_eq!(safe_destination(dest, "Rome/Day 2/b.png"), Some(dest.join("Rome/Day2/b.png")));
+    assert_eq!(safe_destination(dest, "loose.jpg"), Some(dest.join("loose.jpg")));
+
+    assert_eq!(safe_destination(dest, "../escape.jpg"), None);
+    assert_eq!(safe_destination(dest, "/etc/passwd"), None);
+    assert_eq!(safe_destination(dest, "Rome//a.jpg"), None);
+    assert_eq!(safe_destination(dest, "Rome/.hidden"), None);
+    assert_eq!(safe_destination(dest, ""), None);
+}
@@ -0,0 +1,163 @@
+//! Photo Link Tests
+//!
+//! Tests for:
+//! - Encode/decode and seal/open roundtrip
+//! - Tampered path and expiry rejection
+//! - Links not carrying the album key
+//! - Expiry and album scoping
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::sharing::{
+    decode_photo_link, encode_photo_link, verify_photo_link, PhotoLinkClaims, PHOTO_LINK_DIR, PHOTO_LINK_PREFIX,
+    SHARE_LINK_PREFIX,
+};
+
+const NOW: u64 = 1_700_000_100;
+const ALBUM_KEY: [u8; 32] = [42u8; 32];
+const PHOTO: &[u8] = b"fuji at dawn";
+
+fn sample_claims() -> PhotoLinkClaims {
+    PhotoLinkClaims {
+        repo: "alice/photos".to_string(),
+        album_path: "photos/japan-2024".to_string(),
+        remote_path: "photos/japan-2024/fuji.jpg".to_string(),
+        expires_at: NOW + 3600,
+        id: "0123456789abcdef0123456789abcdef".to_string(),
+    }
+}
+
+/// Replace the claims of `link` with `claims`, keeping the original key
+fn swap_payload(link: &str, claims: &PhotoLinkClaims) -> String {
+    let (_, key) = link.rsplit_once('.').expect("key");
+    let json = serde_json::to_vec(claims).unwrap();
+    format!("{}{}.{}", PHOTO_LINK_PREFIX, URL_SAFE_NO_PAD.encode(json), key)
+}
+
+/// A link and the copy uploaded for it
+fn sealed_link(claims: &PhotoLinkClaims) -> (String, Vec<u8>) {
+    let link = encode_photo_link(claims, &ALBUM_KEY).expect("encode");
+    let copy = decode_photo_link(&link, NOW).expect("decode").seal(PHOTO).expect("seal");
+    (link, copy)
+}
+
+// ============================================================================
+// Roundtrip Tests
+// ============================================================================
+
+#[test]
+fn photo_link_roundtrip() {
+    let (link, copy) = sealed_link(&sample_claims());
+    assert!(link.starts_with(PHOTO_LINK_PREFIX));
+
+    let decoded = decode_photo_link(&link, NOW).expect("decode");
+    assert_eq!(decoded.claims.remote_path, "photos/japan-2024/fuji.jpg");
+    assert_eq!(decoded.copy_path(), format!("{}/{}", PHOTO_LINK_DIR, sample_claims().id));
+    assert_eq!(decoded.open(&copy).expect("open"), PHOTO);
+    verify_photo_link(&decoded, &ALBUM_KEY).expect("verify");
+}
+
+#[test]
+fn rejects_other_prefix() {
+    assert!(decode_photo_link("vortex://share/abc.def", NOW).is_err());
+}
+
+#[test]
+fn rejects_missing_key() {
+    let (link, _) = sealed_link(&sample_claims());
+    let (body, _) = link.rsplit_once('.').unwrap();
+    assert!(decode_photo_link(body, NOW).is_err());
+}
+
+// ============================================================================
+// Tamper Tests
+// ============================================================================
+
+#[test]
+fn rejects_tampered_path() {
+    let claims = sample_claims();
+    let (link, copy) = sealed_link(&claims);
+
+    let mut edited = claims.clone();
+    edited.remote_path = "photos/japan-2024/other.jpg".to_string();
+    let tampered = decode_photo_link(&swap_payload(&link, &edited), NOW).expect("still parses");
+    assert!(tampered.open(&copy).is_err());
+    assert!(verify_photo_link(&tampered, &ALBUM_KEY).is_err());
+}
+
+#[test]
+fn rejects_extended_expiry() {
+    let claims = sample_claims();
+    let (link, copy) = sealed_link(&claims);
+
+    let mut edited = claims.clone();
+    edited.expires_at += 86_400;
+    let tampered = decode_photo_link(&swap_payload(&link, &edited), NOW).expect("still parses");
+    assert!(tampered.open(&copy).is_err());
+    assert!(verify_photo_link(&tampered, &ALBUM_KEY).is_err());
+}
+
+#[test]
+fn rejects_key_from_other_album() {
+    let link = encode_photo_link(&sample_claims(), &[7u8; 32]).unwrap();
+    let decoded = decode_photo_link(&link, NOW).unwrap();
+    assert!(verify_photo_link(&decoded, &ALBUM_KEY).is_err());
+}
+
+// ============================================================================
+// Key Exposure Tests
+// ============================================================================
+
+#[test]
+fn link_does_not_carry_album_key() {
+    let (link, _) = sealed_link(&sample_claims());
+    assert!(!link.contains(SHARE_LINK_PREFIX));
+    assert!(!link.contains(&URL_SAFE_NO_PAD.encode(ALBUM_KEY)));
+
+    let body = link.strip_prefix(PHOTO_LINK_PREFIX).unwrap();
+    for part in body.split('.') {
+        let raw = URL_SAFE_NO_PAD.decode(part).unwrap();
+        assert!(!raw.windows(ALBUM_KEY.len()).any(|w| w == ALBUM_KEY));
+    }
+    let json = URL_SAFE_NO_PAD.decode(body.split('.').next().unwrap()).unwrap();
+    assert!(!String::from_utf8(json).unwrap().contains("album_key"));
+}
+
+#[test]
+fn links_to_two_photos_have_unrelated_keys() {
+    let mut other = sample_claims();
+    other.remote_path = "photos/japan-2024/kyoto.jpg".to_string();
+    let (first, first_copy) = sealed_link(&sample_claims());
+    let (second, _) = sealed_link(&other);
+    assert_ne!(first.rsplit_once('.').unwrap().1, second.rsplit_once('.').unwrap().1);
+
+    // The second link cannot open the first photo's copy
+    let second = decode_photo_link(&second, NOW).unwrap();
+    assert!(second.open(&first_copy).is_err());
+}
+
+// ============================================================================
+// Scope Tests
+// ============================================================================
+
+#[test]
+fn rejects_expired_link() {
+    let (link, _) = sealed_link(&sample_claims());
+    assert!(decode_photo_link(&link, NOW + 3600).is_err());
+}
+
+#[test]
+fn rejects_path_outside_album() {
+    let mut claims = sample_claims();
+    claims.remote_path = "photos/private/secret.jpg".to_string();
+    let link = encode_photo_link(&claims, &ALBUM_KEY).unwrap();
+    assert!(decode_photo_link(&link, NOW).is_err());
+}
+
+#[test]
+fn rejects_malformed_id() {
+    let mut claims = sample_claims();
+    claims.id = "../keypair.enc".to_string();
+    let link = encode_photo_link(&claims, &ALBUM_KEY).unwrap();
+    assert!(decode_photo_link(&link, NOW).is_err());
+}
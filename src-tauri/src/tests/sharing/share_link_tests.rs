@@ -0,0 +1,110 @@
+//! Share Link Tests
+//!
+//! Tests for:
+//! - Encode/decode roundtrip
+//! - Checksum and prefix validation
+//! - Expiry and album path scoping
+
+use crate::sharing::{decode_share_link, encode_share_link, ShareLink, SHARE_LINK_PREFIX};
+
+fn sample_link() -> ShareLink {
+    ShareLink {
+        repo: "alice/photos".to_string(),
+        album_path: "photos/japan-2024".to_string(),
+        album_key: [42u8; 32],
+        owner_key_id: "0123456789abcdef".to_string(),
+        created_at: 1_700_000_000,
+        expires_at: 1_700_086_400,
+        label: Some("Trip".to_string()),
+    }
+}
+
+// ============================================================================
+// Encoding Tests
+// ============================================================================
+
+#[test]
+fn share_link_roundtrip() {
+    let link = sample_link();
+    let encoded = encode_share_link(&link).expect("encode");
+
+    assert!(encoded.starts_with(SHARE_LINK_PREFIX));
+
+    let decoded = decode_share_link(&encoded).expect("decode");
+    assert_eq!(decoded.repo, link.repo);
+    assert_eq!(decoded.album_path, link.album_path);
+    assert_eq!(decoded.album_key, link.album_key);
+    assert_eq!(decoded.expires_at, link.expires_at);
+    assert_eq!(decoded.label, link.label);
+}
+
+#[test]
+fn share_link_is_url_safe() {
+    let encoded = encode_share_link(&sample_link()).expect("encode");
+    let body = encoded.strip_prefix(SHARE_LINK_PREFIX).unwrap();
+
+    assert!(body
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+}
+
+#[test]
+fn share_link_rejects_wrong_prefix() {
+    let encoded = encode_share_link(&sample_link()).expect("encode");
+    let wrong = encoded.replace(SHARE_LINK_PREFIX, "https://example.com/");
+
+    assert!(decode_share_link(&wrong).is_err());
+}
+
+#[test]
+fn share_link_rejects_tampered_body() {
+    let encoded = encode_share_link(&sample_link()).expect("encode");
+
+    // Flip one character in the middle of the payload
+    let mut chars: Vec<char> = encoded.chars().collect();
+    let idx = SHARE_LINK_PREFIX.len() + 20;
+    chars[idx] = if chars[idx] == 'A' { 'B' } else { 'A' };
+    let tampered: String = chars.into_iter().collect();
+
+    assert!(decode_share_link(&tampered).is_err(), "tampered link should fail checksum");
+}
+
+#[test]
+fn share_link_rejects_truncated_body() {
+    let encoded = encode_share_link(&sample_link()).expect("encode");
+    let truncated = &encoded[..SHARE_LINK_PREFIX.len() + 6];
+
+    assert!(decode_share_link(truncated).is_err());
+}
+
+// ============================================================================
+// Expiry and Scope Tests
+// ============================================================================
+
+#[test]
+fn share_link_expiry() {
+    let link = sample_link();
+
+    assert!(!link.is_expired(link.expires_at - 1));
+    assert!(link.is_expired(link.expires_at));
+    assert!(link.is_expired(link.expires_at + 1));
+}
+
+#[test]
+fn share_link_info_omits_key() {
+    let info = sample_link().info();
+    let json = serde_json::to_value(&info).expect("serialize");
+
+    assert!(json.get("album_key").is_none(), "public info must not expose the key");
+    assert_eq!(json["repo"], "alice/photos");
+}
+
+#[test]
+fn share_link_path_scope() {
+    let link = sample_link();
+
+    assert!(link.covers_path("photos/japan-2024/IMG_0001.jpg"));
+    assert!(!link.covers_path("photos/japan-2024-private/IMG_0001.jpg"));
+    assert!(!link.covers_path("photos/other/IMG_0001.jpg"));
+    assert!(!link.covers_path("photos/japan-2024/../other/IMG_0001.jpg"));
+}
@@ -0,0 +1,8 @@
+//! Statistics Module Tests
+//!
+//! Organized by functionality:
+//! - `similarity_tests` - Perceptual hashes and duplicate clusters
+//! - `usage_tests` - Album statistics, per-album usage and quota thresholds
+
+pub mod similarity_tests;
+pub mod usage_tests;
@@ -0,0 +1,80 @@
+//! Photo Similarity Tests
+//!
+//! Tests for:
+//! - Perceptual hashes surviving re-encoding and resizing
+//! - Telling different pictures apart
+//! - Clustering photos within a threshold
+
+use image::{ImageBuffer, ImageFormat, Rgb, RgbImage};
+
+use crate::similarity::{cluster_photos, hash_photo, HashedPhoto, PhotoHash, DEFAULT_THRESHOLD};
+use crate::tests::common::encoded;
+
+fn scene(width: u32, height: u32) -> RgbImage {
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let (u, v) = (x * 256 / width, y * 256 / height);
+        let sun = if (u as i32 - 180).pow(2) + (v as i32 - 70).pow(2) < 900 { 255 } else { 0 };
+        Rgb([(u / 2 + sun / 2) as u8, (v / 2 + 40) as u8, (255 - v) as u8])
+    })
+}
+
+fn checkerboard(width: u32, height: u32) -> RgbImage {
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let on = (x * 8 / width + y * 8 / height) % 2 == 0;
+        Rgb(if on { [230, 230, 230] } else { [20, 20, 20] })
+    })
+}
+
+fn hashed(path: &str, size: u64, hash: PhotoHash) -> HashedPhoto {
+    HashedPhoto { path: path.into(), size, hash }
+}
+
+// ============================================================================
+// Hash Tests
+// ============================================================================
+
+#[test]
+fn copies_hash_alike() {
+    let original = hash_photo(&encoded(&scene(320, 240), ImageFormat::Png)).unwrap();
+    assert_eq!(original, hash_photo(&encoded(&scene(320, 240), ImageFormat::Png)).unwrap());
+
+    let smaller_jpeg = hash_photo(&encoded(&scene(160, 120), ImageFormat::Jpeg)).unwrap();
+    assert!(original.distance(&smaller_jpeg) <= DEFAULT_THRESHOLD);
+
+    let different = hash_photo(&encoded(&checkerboard(320, 240), ImageFormat::Png)).unwrap();
+    assert!(original.distance(&different) > DEFAULT_THRESHOLD);
+
+    assert!(hash_photo(b"not an image").is_err());
+}
+
+// ============================================================================
+// Cluster Tests
+// ============================================================================
+
+#[test]
+fn clusters_group_linked_photos() {
+    let base = PhotoHash { dhash: 0, phash: 0 };
+    let near = PhotoHash { dhash: 0b111, phash: 0b1 };
+    let chained = PhotoHash { dhash: 0b111_111, phash: 0b1 };
+    let far = PhotoHash { dhash: u64::MAX, phash: u64::MAX };
+
+    let photos = [
+        hashed("photos/a.jpg", 100, base),
+        hashed("photos/b.jpg", 300, near),
+        hashed("photos/c.jpg", 200, chained),
+        hashed("photos/d.jpg", 100, far),
+        hashed("photos/e.jpg", 50, far),
+    ];
+    let clusters = cluster_photos(&photos, 3);
+    assert_eq!(clusters.len(), 2);
+
+    // c is 6 bits from a but joins through b; the largest file leads
+    let paths: Vec<_> = clusters[0].photos.iter().map(|p| p.path.as_str()).collect();
+    assert_eq!(paths, ["photos/b.jpg", "photos/c.jpg", "photos/a.jpg"]);
+    assert_eq!(clusters[0].photos.iter().map(|p| p.distance).collect::<Vec<_>>(), [0, 3, 3]);
+    assert!(!clusters[0].identical);
+    assert!(clusters[1].identical);
+
+    assert!(cluster_photos(&photos, 0).iter().all(|c| c.identical));
+    assert!(cluster_photos(&photos[..1], DEFAULT_THRESHOLD).is_empty());
+}
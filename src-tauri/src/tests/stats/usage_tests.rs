@@ -0,0 +1,90 @@
+//! Storage Usage Tests
+//!
+//! Tests for:
+//! - Album photo counts, sizes and largest files
+//! - Compression savings from album manifests
+//! - Per-album grouping and quota thresholds
+
+use crate::album::AlbumManifest;
+use crate::git_data::{index_blobs, TreeEntry, TreeIndex};
+use crate::stats::{album_stats, quota_status, usage_by_album, QuotaStatus, HARD_LIMIT_BYTES, SOFT_LIMIT_BYTES};
+
+fn blob(path: &str, size: u64) -> TreeEntry {
+    TreeEntry {
+        path: path.to_string(),
+        mode: "100644".to_string(),
+        kind: "blob".to_string(),
+        sha: format!("sha-{}", path),
+        size: Some(size),
+    }
+}
+
+fn sample_index() -> TreeIndex {
+    index_blobs(vec![
+        blob("README.md", 100),
+        blob("photos/loose.jpg", 50),
+        blob("photos/trip/a.jpg", 300),
+        blob("photos/trip/b.png", 200),
+        blob("photos/trip/.vortex-album.json", 80),
+        blob("photos/trip/day2/c.jpg", 400),
+        blob("photos/vault/0123abcd.vxe", 1000),
+        blob("photos/vault/.gitkeep", 0),
+    ])
+}
+
+// ============================================================================
+// Album Stats Tests
+// ============================================================================
+
+#[test]
+fn album_stats_counts_photos_recursively() {
+    let stats = album_stats(&sample_index(), "photos/trip/", None);
+
+    assert_eq!(stats.album_path, "photos/trip");
+    assert_eq!(stats.photo_count, 3, "manifest must not count as a photo");
+    assert_eq!(stats.total_bytes, 900);
+    assert_eq!(stats.largest_files[0].path, "photos/trip/day2/c.jpg");
+    assert!(stats.compression_savings_bytes.is_none());
+    assert_eq!(stats.status, QuotaStatus::Ok);
+}
+
+#[test]
+fn album_stats_includes_encrypted_blobs() {
+    let stats = album_stats(&sample_index(), "photos/vault", None);
+
+    assert_eq!(stats.photo_count, 1);
+    assert_eq!(stats.total_bytes, 1000);
+}
+
+#[test]
+fn album_stats_reports_compression_savings() {
+    let mut manifest = AlbumManifest::new(true, None);
+    manifest.original_bytes = 5000;
+    manifest.stored_bytes = 1000;
+
+    let stats = album_stats(&sample_index(), "photos/vault", Some(&manifest));
+    assert_eq!(stats.compression_savings_bytes, Some(4000));
+}
+
+// ============================================================================
+// Usage and Quota Tests
+// ============================================================================
+
+#[test]
+fn usage_groups_by_top_level_album() {
+    let albums = usage_by_album(&sample_index());
+
+    let names: Vec<_> = albums.iter().map(|a| a.album_path.as_str()).collect();
+    assert_eq!(names, vec!["photos/vault", "photos/trip", "photos"]);
+    assert_eq!(albums[1].photo_count, 3);
+    assert_eq!(albums[1].total_bytes, 900);
+}
+
+#[test]
+fn quota_thresholds() {
+    assert_eq!(quota_status(0), QuotaStatus::Ok);
+    assert_eq!(quota_status(SOFT_LIMIT_BYTES / 2), QuotaStatus::Ok);
+    assert_eq!(quota_status(SOFT_LIMIT_BYTES * 9 / 10), QuotaStatus::Warning);
+    assert_eq!(quota_status(SOFT_LIMIT_BYTES), QuotaStatus::OverSoftLimit);
+    assert_eq!(quota_status(HARD_LIMIT_BYTES), QuotaStatus::OverHardLimit);
+}
@@ -0,0 +1,179 @@
+//! Photo Catalog Tests
+//!
+//! Tests for:
+//! - Reconciling albums with complete and partial listings
+//! - Tags surviving reconciles and case-insensitive lookup
+//! - Pending deletes and locked listings
+//! - Clearing the catalog with the other caches
+
+use crate::catalog::{
+    album_photos_in, listed_items, photos_with_tag_in, reconcile_album_in, record_items_in, remove_photo_in,
+    set_sync_state_in, set_tags_in, CatalogChanges, SyncState, MAX_TAG_LEN,
+};
+use crate::github::PhotoItem;
+use crate::local_store::{LocalStore, CATALOG_NS};
+use crate::video::MediaType;
+
+const KEY: [u8; 32] = [7u8; 32];
+const REPO: &str = "octo/photos";
+
+fn photo(name: &str, sha: &str) -> PhotoItem {
+    PhotoItem {
+        name: name.into(),
+        url: format!("https://example.com/{}", name),
+        sha: sha.into(),
+        size: Some(1024),
+        encrypted: false,
+        display_name: None,
+        repo: None,
+        caption: None,
+        exif: None,
+        raw_companion: None,
+        media_type: MediaType::Photo,
+    }
+}
+
+fn sealed_photo(name: &str, sha: &str, display_name: Option<&str>) -> PhotoItem {
+    PhotoItem {
+        encrypted: true,
+        display_name: display_name.map(Into::into),
+        ..photo(name, sha)
+    }
+}
+
+fn names(store: &LocalStore, album: &str) -> Vec<String> {
+    album_photos_in(store, REPO, album)
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .map(|p| p.item.name)
+        .collect()
+}
+
+// ============================================================================
+// Reconcile Tests
+// ============================================================================
+
+#[test]
+fn albums_never_listed_are_unknown() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    assert!(album_photos_in(&store, REPO, "photos").unwrap().is_none());
+
+    reconcile_album_in(&store, REPO, "photos", &[]).unwrap();
+    assert_eq!(album_photos_in(&store, REPO, "photos").unwrap(), Some(vec![]));
+    assert!(album_photos_in(&store, "octo/other", "photos").unwrap().is_none());
+}
+
+#[test]
+fn reconcile_counts_changes() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    let first = [photo("a.jpg", "1"), photo("b.jpg", "2"), photo("c.jpg", "3")];
+    let changes = reconcile_album_in(&store, REPO, "photos", &first).unwrap();
+    assert_eq!(changes, CatalogChanges { added: 3, updated: 0, removed: 0 });
+    assert!(reconcile_album_in(&store, REPO, "photos", &first).unwrap().is_empty());
+
+    let second = [photo("a.jpg", "1"), photo("b.jpg", "2b"), photo("d.jpg", "4")];
+    let changes = reconcile_album_in(&store, REPO, "photos", &second).unwrap();
+    assert_eq!(changes, CatalogChanges { added: 1, updated: 1, removed: 1 });
+    assert_eq!(names(&store, "photos"), ["a.jpg", "b.jpg", "d.jpg"]);
+
+    let stored = album_photos_in(&store, REPO, "/photos/").unwrap().unwrap();
+    assert_eq!(stored[1].path, "photos/b.jpg");
+    assert_eq!(stored[1].item, second[1]);
+}
+
+#[test]
+fn pages_only_add() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    reconcile_album_in(&store, REPO, "photos", &[photo("a.jpg", "1")]).unwrap();
+    let changes = record_items_in(&store, REPO, "photos", &[photo("b.jpg", "2")]).unwrap();
+    assert_eq!(changes.added, 1);
+    assert_eq!(names(&store, "photos"), ["a.jpg", "b.jpg"]);
+}
+
+#[test]
+fn decrypted_names_survive_locked_listings() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    reconcile_album_in(&store, REPO, "vault", &[sealed_photo("x1.enc", "1", Some("beach.jpg"))]).unwrap();
+
+    // Listed again without the keypair: same blob, no name
+    let changes = reconcile_album_in(&store, REPO, "vault", &[sealed_photo("x1.enc", "1", None)]).unwrap();
+    assert!(changes.is_empty());
+    let stored = album_photos_in(&store, REPO, "vault").unwrap().unwrap();
+    assert_eq!(stored[0].item.display_name.as_deref(), Some("beach.jpg"));
+
+    // A new blob under the same name is another photo
+    reconcile_album_in(&store, REPO, "vault", &[sealed_photo("x1.enc", "2", None)]).unwrap();
+    let stored = album_photos_in(&store, REPO, "vault").unwrap().unwrap();
+    assert_eq!(stored[0].item.display_name, None);
+}
+
+// ============================================================================
+// Tag Tests
+// ============================================================================
+
+#[test]
+fn tags_survive_reconciles() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    reconcile_album_in(&store, REPO, "photos", &[photo("a.jpg", "1"), photo("b.jpg", "2")]).unwrap();
+    let tags = vec!["Praia".to_string(), " praia ".to_string(), "2024".to_string()];
+    assert!(set_tags_in(&store, REPO, "photos/a.jpg", &tags).unwrap());
+    assert!(!set_tags_in(&store, REPO, "photos/missing.jpg", &tags).unwrap());
+
+    reconcile_album_in(&store, REPO, "photos", &[photo("a.jpg", "1b"), photo("b.jpg", "2")]).unwrap();
+    let tagged = photos_with_tag_in(&store, REPO, "PRAIA").unwrap();
+    assert_eq!(tagged.len(), 1);
+    assert_eq!(tagged[0].path, "photos/a.jpg");
+    assert_eq!(tagged[0].tags, ["2024", "Praia"]);
+    assert!(photos_with_tag_in(&store, "octo/other", "praia").unwrap().is_empty());
+
+    assert!(set_tags_in(&store, REPO, "photos/a.jpg", &["x".repeat(MAX_TAG_LEN + 1)]).is_err());
+}
+
+// ============================================================================
+// Listing Tests
+// ============================================================================
+
+#[test]
+fn pending_deletes_are_hidden() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    reconcile_album_in(&store, REPO, "photos", &[photo("a.jpg", "1"), photo("b.jpg", "2")]).unwrap();
+    set_tags_in(&store, REPO, "photos/a.jpg", &["keep".to_string()]).unwrap();
+    assert!(set_sync_state_in(&store, REPO, "photos/a.jpg", SyncState::PendingDelete).unwrap());
+
+    let photos = album_photos_in(&store, REPO, "photos").unwrap().unwrap();
+    assert_eq!(photos.len(), 2, "the catalog keeps the photo until the delete lands");
+    let listed = listed_items(photos, true);
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].name, "b.jpg");
+    assert!(photos_with_tag_in(&store, REPO, "keep").unwrap().is_empty());
+
+    assert!(remove_photo_in(&store, REPO, "photos/a.jpg").unwrap());
+    assert!(!remove_photo_in(&store, REPO, "photos/a.jpg").unwrap());
+    assert_eq!(names(&store, "photos"), ["b.jpg"]);
+}
+
+#[test]
+fn locked_listings_hide_decrypted_names() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    let items = [sealed_photo("x1.enc", "1", Some("beach.jpg")), photo("plain.jpg", "2")];
+    reconcile_album_in(&store, REPO, "vault", &items).unwrap();
+
+    let photos = album_photos_in(&store, REPO, "vault").unwrap().unwrap();
+    let unlocked = listed_items(photos.clone(), true);
+    assert_eq!(unlocked[1].display_name.as_deref(), Some("beach.jpg"));
+    let locked = listed_items(photos, false);
+    assert_eq!(locked[1].display_name, None);
+    assert_eq!(locked[0], items[1]);
+}
+
+#[test]
+fn clearing_caches_empties_the_catalog() {
+    let store = LocalStore::in_memory(&KEY).unwrap();
+    reconcile_album_in(&store, REPO, "photos", &[photo("a.jpg", "1"), photo("b.jpg", "2")]).unwrap();
+    assert_eq!(store.clear_caches().unwrap(), 2);
+    assert!(album_photos_in(&store, REPO, "photos").unwrap().is_none());
+
+    reconcile_album_in(&store, REPO, "photos", &[photo("a.jpg", "1")]).unwrap();
+    assert_eq!(store.clear(CATALOG_NS).unwrap(), 1);
+}
@@ -0,0 +1,177 @@
+//! Download Cache Tests
+//!
+//! Tests for:
+//! - Serving cached blobs, sealed on disk and shared by content
+//! - Dropping entries the catalog lists with another blob
+//! - Least recently used eviction, pinning and clearing
+
+use crate::catalog::reconcile_album_in;
+use crate::download_cache::{
+    cache_stats_in, cached_photo_in, clear_download_cache_in, set_cache_limit_in, set_pinned_in, store_photo_in,
+    CachedPhoto, DEFAULT_LIMIT_BYTES,
+};
+use crate::github::{DownloadIntegrity, PhotoItem};
+use crate::local_store::LocalStore;
+use crate::security_verify::{IntegrityCheck, IntegrityStatus};
+use crate::video::MediaType;
+use crate::tests::common::temp_dir;
+
+const REPO: &str = "alice/photos";
+const LIMIT: u64 = 1024 * 1024;
+
+fn verified(path: &str) -> DownloadIntegrity {
+    DownloadIntegrity {
+        photo: IntegrityCheck {
+            path: path.into(),
+            status: IntegrityStatus::Verified,
+            signer_key_id: Some("key".into()),
+            signed_at: Some(1),
+            detail: None,
+        },
+        manifest: None,
+    }
+}
+
+fn blob(path: &str, sha: &str, content: &[u8]) -> CachedPhoto {
+    CachedPhoto {
+        sha: sha.into(),
+        content: content.to_vec(),
+        integrity: Some(verified(path)),
+        manifest: None,
+    }
+}
+
+fn listed(name: &str, sha: &str) -> PhotoItem {
+    PhotoItem {
+        name: name.into(),
+        url: format!("https://example.com/{}", name),
+        sha: sha.into(),
+        size: None,
+        encrypted: false,
+        display_name: None,
+        repo: None,
+        caption: None,
+        exif: None,
+        raw_companion: None,
+        media_type: MediaType::Photo,
+    }
+}
+
+fn is_cached(store: &LocalStore, dir: &std::path::Path, path: &str) -> bool {
+    cached_photo_in(store, dir, REPO, path, false).unwrap().is_some()
+}
+
+// ============================================================================
+// Lookup Tests
+// ============================================================================
+
+#[test]
+fn cached_photos_are_served_sealed_and_shared() {
+    let dir = temp_dir("download-cache", "serve");
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    assert!(cached_photo_in(&store, &dir, REPO, "photos/a.jpg", true).unwrap().is_none());
+
+    let content = b"jpeg bytes of a".to_vec();
+    store_photo_in(&store, &dir, REPO, "photos/a.jpg", &blob("photos/a.jpg", "sha-a", &content), LIMIT).unwrap();
+    store_photo_in(&store, &dir, REPO, "photos/copy.jpg", &blob("photos/copy.jpg", "sha-a", &content), LIMIT).unwrap();
+
+    let hit = cached_photo_in(&store, &dir, REPO, "photos/a.jpg", true).unwrap().unwrap();
+    assert_eq!(hit.content, content);
+    assert_eq!(hit.sha, "sha-a");
+    assert!(hit.integrity.is_some());
+
+    // One sealed file for both paths
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+    assert_eq!(files.len(), 1);
+    let on_disk = std::fs::read(&files[0]).unwrap();
+    assert!(!on_disk.windows(content.len()).any(|w| w == content.as_slice()));
+
+    let stats = cache_stats_in(&store).unwrap();
+    assert_eq!((stats.photos, stats.bytes), (2, content.len() as u64));
+}
+
+#[test]
+fn unverified_downloads_are_not_served_to_verifying_callers() {
+    let dir = temp_dir("download-cache", "unverified");
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    let photo = CachedPhoto { integrity: None, ..blob("photos/a.jpg", "sha-a", b"a") };
+    store_photo_in(&store, &dir, REPO, "photos/a.jpg", &photo, LIMIT).unwrap();
+    assert!(cached_photo_in(&store, &dir, REPO, "photos/a.jpg", true).unwrap().is_none());
+    assert!(cached_photo_in(&store, &dir, REPO, "photos/a.jpg", false).unwrap().is_some());
+}
+
+#[test]
+fn entries_of_changed_blobs_are_dropped() {
+    let dir = temp_dir("download-cache", "stale");
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    store_photo_in(&store, &dir, REPO, "photos/a.jpg", &blob("photos/a.jpg", "sha-a", b"old"), LIMIT).unwrap();
+
+    reconcile_album_in(&store, REPO, "photos", &[listed("a.jpg", "sha-a")]).unwrap();
+    assert!(is_cached(&store, &dir, "photos/a.jpg"));
+
+    reconcile_album_in(&store, REPO, "photos", &[listed("a.jpg", "sha-edited")]).unwrap();
+    assert!(!is_cached(&store, &dir, "photos/a.jpg"));
+    assert_eq!(cache_stats_in(&store).unwrap().photos, 0);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+}
+
+#[test]
+fn deleted_files_are_misses() {
+    let dir = temp_dir("download-cache", "deleted");
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    store_photo_in(&store, &dir, REPO, "photos/a.jpg", &blob("photos/a.jpg", "sha-a", b"a"), LIMIT).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(!is_cached(&store, &dir, "photos/a.jpg"));
+    assert_eq!(cache_stats_in(&store).unwrap().photos, 0);
+}
+
+// ============================================================================
+// Eviction Tests
+// ============================================================================
+
+#[test]
+fn least_recently_used_are_evicted_and_pinned_kept() {
+    let dir = temp_dir("download-cache", "evict");
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    let limit = 250;
+    for name in ["a", "b"] {
+        let path = format!("photos/{}.jpg", name);
+        store_photo_in(&store, &dir, REPO, &path, &blob(&path, name, &[name.as_bytes()[0]; 100]), limit).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert!(set_pinned_in(&store, &dir, REPO, "photos/a.jpg", true, limit).unwrap());
+    assert!(!set_pinned_in(&store, &dir, REPO, "photos/missing.jpg", true, limit).unwrap());
+
+    // Opening b makes it the most recent, but a is pinned and c is newer
+    assert!(is_cached(&store, &dir, "photos/b.jpg"));
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    store_photo_in(&store, &dir, REPO, "photos/c.jpg", &blob("photos/c.jpg", "c", &[b'c'; 200]), limit).unwrap();
+    assert!(is_cached(&store, &dir, "photos/a.jpg"), "pinned photos are never evicted");
+    assert!(!is_cached(&store, &dir, "photos/b.jpg"));
+    assert!(is_cached(&store, &dir, "photos/c.jpg"));
+
+    let stats = cache_stats_in(&store).unwrap();
+    assert_eq!((stats.pinned_photos, stats.pinned_bytes), (1, 100));
+
+    // Lowering the limit evicts right away
+    assert_eq!(set_cache_limit_in(&store, &dir, 0).unwrap(), 1);
+    assert_eq!(cache_stats_in(&store).unwrap().limit_bytes, 0);
+    assert!(!is_cached(&store, &dir, "photos/c.jpg"));
+}
+
+#[test]
+fn clearing_keeps_pinned_photos_unless_asked() {
+    let dir = temp_dir("download-cache", "clear");
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    for name in ["a", "b"] {
+        let path = format!("photos/{}.jpg", name);
+        store_photo_in(&store, &dir, REPO, &path, &blob(&path, name, name.as_bytes()), LIMIT).unwrap();
+    }
+    set_pinned_in(&store, &dir, REPO, "photos/a.jpg", true, LIMIT).unwrap();
+
+    assert_eq!(clear_download_cache_in(&store, &dir, false).unwrap(), 1);
+    assert!(is_cached(&store, &dir, "photos/a.jpg"));
+    assert_eq!(clear_download_cache_in(&store, &dir, true).unwrap(), 1);
+    let stats = cache_stats_in(&store).unwrap();
+    assert_eq!((stats.photos, stats.bytes, stats.limit_bytes), (0, 0, DEFAULT_LIMIT_BYTES));
+}
@@ -0,0 +1,146 @@
+//! IPFS Backend Tests
+//!
+//! Tests for:
+//! - Node URLs and `add` responses
+//! - Which errors fall back to pinned copies
+//! - Pin records in the local store and CIDs in album manifests
+
+use crate::album::AlbumManifest;
+use crate::git_data::{index_blobs, TreeEntry};
+use crate::github::{AppError, GithubError};
+use crate::ipfs::{
+    album_files, forget_pin_in, github_unreachable, parse_add_response, pinned_in, pins_in, record_pin_in,
+    validate_node_url, IpfsEntry,
+};
+use crate::local_store::LocalStore;
+
+const REPO: &str = "alice/photos";
+
+fn entry(path: &str) -> TreeEntry {
+    TreeEntry {
+        path: path.into(),
+        mode: "100644".into(),
+        kind: "blob".into(),
+        sha: format!("sha-{}", path),
+        size: Some(10),
+    }
+}
+
+fn pinned(cid: &str, content: &[u8]) -> IpfsEntry {
+    IpfsEntry::for_content(cid, "sha-1", content)
+}
+
+// ============================================================================
+// Node Tests
+// ============================================================================
+
+#[test]
+fn remote_nodes_need_https() {
+    assert_eq!(validate_node_url("http://127.0.0.1:5001/").unwrap(), "http://127.0.0.1:5001");
+    assert!(validate_node_url("http://localhost:5001").is_ok());
+    assert!(validate_node_url("http://[::1]:5001").is_ok());
+    assert_eq!(validate_node_url("https://ipfs.example.com/rpc/").unwrap(), "https://ipfs.example.com/rpc");
+
+    assert!(validate_node_url("http://192.168.1.20:5001").is_err());
+    assert!(validate_node_url("http://ipfs.example.com").is_err());
+    assert!(validate_node_url("ftp://127.0.0.1").is_err());
+    assert!(validate_node_url("https://ipfs.example.com/?token=x").is_err());
+    assert!(validate_node_url("not a url").is_err());
+}
+
+#[test]
+fn add_responses_give_the_cid_of_the_last_line() {
+    let body = concat!(
+        "{\"Name\":\"a.jpg\",\"Bytes\":262144}\n",
+        "{\"Name\":\"a.jpg\",\"Hash\":\"bafkreiexample\",\"Size\":\"300000\"}\n",
+    );
+    assert_eq!(parse_add_response(body).unwrap(), "bafkreiexample");
+    assert!(parse_add_response("").is_err());
+    assert!(parse_add_response("{\"Name\":\"a.jpg\"}").is_err());
+}
+
+#[test]
+fn only_outages_and_missing_files_fall_back() {
+    let github = |e: GithubError| AppError::Github(e);
+    assert!(github_unreachable(&AppError::Unavailable(30)));
+    assert!(github_unreachable(&github(GithubError::NotFound { message: "gone".into() })));
+    assert!(github_unreachable(&github(GithubError::Server { status: 502, message: "bad gateway".into() })));
+    assert!(github_unreachable(&github(GithubError::Unauthorized { message: "revoked".into() })));
+
+    assert!(!github_unreachable(&AppError::Validation("tampered".into())));
+    assert!(!github_unreachable(&github(GithubError::RateLimited { retry_after: 60, message: "slow down".into() })));
+    assert!(!github_unreachable(&github(GithubError::Forbidden { message: "no access".into() })));
+}
+
+#[test]
+fn content_must_match_its_record() {
+    let entry = pinned("bafy1", b"photo");
+    assert!(entry.check(b"photo").is_ok());
+    assert!(entry.check(b"other").is_err());
+    assert!(entry.check(b"photo!").is_err());
+}
+
+// ============================================================================
+// Record Tests
+// ============================================================================
+
+#[test]
+fn pins_are_recorded_per_path() {
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    let a = pinned("bafy-a", b"a");
+    record_pin_in(&store, REPO, "photos/Rome/a.jpg", &a).unwrap();
+    record_pin_in(&store, REPO, "photos/Rome/a.jpg.vxsig", &pinned("bafy-sig", b"sig")).unwrap();
+    record_pin_in(&store, REPO, "photos/Rome/Day 2/b.jpg", &pinned("bafy-b", b"b")).unwrap();
+    record_pin_in(&store, "bob/photos", "photos/Rome/a.jpg", &pinned("bafy-other", b"c")).unwrap();
+
+    assert_eq!(pinned_in(&store, REPO, "photos/Rome/a.jpg").unwrap(), Some(a));
+    assert_eq!(pinned_in(&store, REPO, "photos/Rome/c.jpg").unwrap(), None);
+
+    let album: Vec<String> = pins_in(&store, REPO, Some("photos/Rome")).unwrap().into_iter().map(|p| p.path).collect();
+    assert_eq!(album, ["photos/Rome/a.jpg", "photos/Rome/a.jpg.vxsig"]);
+    assert_eq!(pins_in(&store, REPO, None).unwrap().len(), 3);
+
+    // Pinning again replaces the record
+    record_pin_in(&store, REPO, "photos/Rome/a.jpg", &pinned("bafy-a2", b"a2")).unwrap();
+    assert_eq!(pinned_in(&store, REPO, "photos/Rome/a.jpg").unwrap().unwrap().cid, "bafy-a2");
+
+    assert!(forget_pin_in(&store, REPO, "photos/Rome/a.jpg").unwrap());
+    assert!(!forget_pin_in(&store, REPO, "photos/Rome/a.jpg").unwrap());
+    assert_eq!(pinned_in(&store, "bob/photos", "photos/Rome/a.jpg").unwrap().unwrap().cid, "bafy-other");
+}
+
+#[test]
+fn albums_pin_their_files_but_not_their_manifest() {
+    let index = index_blobs(
+        [
+            "photos/Rome/.vortex-album.json",
+            "photos/Rome/.vortex-vault.bin",
+            "photos/Rome/3f2a.vxe",
+            "photos/Rome/3f2a.vxe.vxsig",
+            "photos/Rome/Day 2/b.jpg",
+            "photos/Paris/c.jpg",
+        ]
+        .into_iter()
+        .map(entry)
+        .collect(),
+    );
+    let files: Vec<String> = album_files(&index, "photos/Rome").into_iter().map(|e| e.path).collect();
+    assert_eq!(files, ["photos/Rome/.vortex-vault.bin", "photos/Rome/3f2a.vxe", "photos/Rome/3f2a.vxe.vxsig"]);
+}
+
+// ============================================================================
+// Manifest Tests
+// ============================================================================
+
+#[test]
+fn manifests_without_pins_are_unchanged() {
+    let manifest = AlbumManifest::new(true, Some("owner".into()));
+    let json = serde_json::to_value(&manifest).unwrap();
+    assert!(json.get("ipfs").is_none());
+
+    let mut pinned_manifest = manifest.clone();
+    pinned_manifest.ipfs.insert("3f2a.vxe".into(), pinned("bafy-a", b"a"));
+    let json = serde_json::to_string(&pinned_manifest).unwrap();
+    let back: AlbumManifest = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.ipfs["3f2a.vxe"].cid, "bafy-a");
+}
@@ -0,0 +1,108 @@
+//! Schema Migration Tests
+//!
+//! Tests for:
+//! - Fresh stores and stores from before migrations
+//! - Running pending migrations once, in their own transactions
+//! - Refusing newer, edited and misnumbered migration histories
+
+use rusqlite::Connection;
+
+use crate::local_store::LocalStore;
+use crate::migrations::{migrate, schema_version, Migration, MIGRATIONS};
+
+const PHOTOS: Migration = Migration {
+    version: 1,
+    name: "photos",
+    sql: "CREATE TABLE IF NOT EXISTS photos (id INTEGER PRIMARY KEY, name TEXT);",
+};
+const CAPTIONS: Migration = Migration {
+    version: 2,
+    name: "captions",
+    sql: "ALTER TABLE photos ADD COLUMN caption TEXT;",
+};
+
+fn user_version(conn: &Connection) -> u32 {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap()
+}
+
+fn count(conn: &Connection, sql: &str) -> i64 {
+    conn.query_row(sql, [], |row| row.get(0)).unwrap()
+}
+
+// ============================================================================
+// Upgrade Tests
+// ============================================================================
+
+#[test]
+fn fresh_stores_are_at_the_latest_version() {
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    let version = schema_version(store.connection()).unwrap();
+    assert_eq!(version.version, MIGRATIONS.len() as u32);
+    assert_eq!(version.latest, version.version);
+    let names: Vec<&str> = version.migrations.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, MIGRATIONS.iter().map(|m| m.name).collect::<Vec<_>>());
+    assert_eq!(user_version(store.connection()), version.version);
+
+    // Reopening runs nothing
+    assert_eq!(migrate(store.connection(), MIGRATIONS).unwrap(), 0);
+}
+
+#[test]
+fn stores_from_before_migrations_keep_their_rows() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(PHOTOS.sql).unwrap();
+    conn.execute("INSERT INTO photos (name) VALUES ('kept.jpg')", []).unwrap();
+
+    assert_eq!(migrate(&conn, &[PHOTOS]).unwrap(), 1);
+    assert_eq!(count(&conn, "SELECT COUNT(*) FROM photos"), 1);
+
+    assert_eq!(migrate(&conn, &[PHOTOS, CAPTIONS]).unwrap(), 1);
+    assert_eq!(migrate(&conn, &[PHOTOS, CAPTIONS]).unwrap(), 0, "ALTER TABLE ran twice");
+    assert_eq!(count(&conn, "SELECT COUNT(*) FROM photos WHERE caption IS NULL"), 1);
+    assert_eq!(user_version(&conn), 2);
+}
+
+#[test]
+fn failed_migrations_leave_the_previous_version() {
+    let conn = Connection::open_in_memory().unwrap();
+    migrate(&conn, &[PHOTOS]).unwrap();
+    let broken = Migration {
+        version: 2,
+        name: "broken",
+        sql: "CREATE TABLE albums (id INTEGER); ALTER TABLE missing ADD COLUMN x TEXT;",
+    };
+    assert!(migrate(&conn, &[PHOTOS, broken]).is_err());
+    assert_eq!(count(&conn, "SELECT COUNT(*) FROM sqlite_master WHERE name = 'albums'"), 0);
+    assert_eq!(user_version(&conn), 1);
+    assert_eq!(migrate(&conn, &[PHOTOS, CAPTIONS]).unwrap(), 1);
+}
+
+// ============================================================================
+// History Tests
+// ============================================================================
+
+#[test]
+fn newer_stores_are_refused() {
+    let conn = Connection::open_in_memory().unwrap();
+    migrate(&conn, &[PHOTOS, CAPTIONS]).unwrap();
+    let error = migrate(&conn, &[PHOTOS]).unwrap_err().to_string();
+    assert!(error.contains("newer"), "{}", error);
+}
+
+#[test]
+fn edited_migrations_are_refused() {
+    let conn = Connection::open_in_memory().unwrap();
+    migrate(&conn, &[PHOTOS]).unwrap();
+    let edited = Migration { sql: "CREATE TABLE IF NOT EXISTS photos (id INTEGER PRIMARY KEY);", ..PHOTOS };
+    assert!(migrate(&conn, &[edited]).is_err());
+    let renamed = Migration { name: "pictures", ..PHOTOS };
+    assert!(migrate(&conn, &[renamed]).is_err());
+}
+
+#[test]
+fn migrations_must_be_numbered_in_order() {
+    let conn = Connection::open_in_memory().unwrap();
+    assert!(migrate(&conn, &[CAPTIONS]).is_err());
+    assert!(migrate(&conn, &[PHOTOS, Migration { version: 3, ..CAPTIONS }]).is_err());
+    assert!(MIGRATIONS.iter().enumerate().all(|(i, m)| m.version as usize == i + 1));
+}
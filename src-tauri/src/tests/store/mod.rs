@@ -0,0 +1,22 @@
+//! Local Store Module Tests
+//!
+//! Organized by functionality:
+//! - `local_store_tests` - Encrypted settings and cache entries
+//! - `catalog_tests` - Local photo catalog
+//! - `search_tests` - Full-text and attribute search over the catalog
+//! - `smart_album_tests` - Saved search rules and their change tracking
+//! - `timeline_tests` - Photos grouped by capture date
+//! - `download_cache_tests` - Offline copies of downloaded photos
+//! - `prefetch_tests` - Choosing the neighbors of the viewed photo
+//! - `migration_tests` - Versioned schema upgrades
+//! - `ipfs_tests` - Pinned copies of album files on IPFS
+
+pub mod local_store_tests;
+pub mod catalog_tests;
+pub mod search_tests;
+pub mod smart_album_tests;
+pub mod timeline_tests;
+pub mod download_cache_tests;
+pub mod prefetch_tests;
+pub mod migration_tests;
+pub mod ipfs_tests;
@@ -0,0 +1,51 @@
+//! Prefetch Tests
+//!
+//! Tests for:
+//! - Picking the nearest neighbors, next before previous
+//! - Album edges and out of range indexes
+//! - Skipping photos the download cache already holds
+
+use crate::download_cache::{has_photo_in, store_photo_in, CachedPhoto};
+use crate::local_store::LocalStore;
+use crate::prefetch::neighbor_indexes;
+
+// ============================================================================
+// Neighbor Tests
+// ============================================================================
+
+#[test]
+fn nearest_neighbors_come_first() {
+    assert_eq!(neighbor_indexes(10, 5, 4), [6, 4, 7, 3]);
+    assert_eq!(neighbor_indexes(10, 5, 3), [6, 4, 7]);
+    assert!(neighbor_indexes(10, 5, 0).is_empty());
+}
+
+#[test]
+fn album_edges_shift_the_window() {
+    assert_eq!(neighbor_indexes(10, 0, 3), [1, 2, 3]);
+    assert_eq!(neighbor_indexes(10, 9, 3), [8, 7, 6]);
+    assert_eq!(neighbor_indexes(3, 1, 10), [2, 0]);
+    assert!(neighbor_indexes(1, 0, 4).is_empty());
+    assert!(neighbor_indexes(5, 7, 4).is_empty());
+    assert!(neighbor_indexes(0, 0, 4).is_empty());
+}
+
+// ============================================================================
+// Cache Tests
+// ============================================================================
+
+#[test]
+fn cached_neighbors_are_skipped_only_when_usable() {
+    let dir = std::env::temp_dir().join(format!("vortex-prefetch-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    let unverified = CachedPhoto { sha: "sha-a".into(), content: b"a".to_vec(), integrity: None, manifest: None };
+    store_photo_in(&store, &dir, "alice/photos", "photos/a.jpg", &unverified, 1024).unwrap();
+
+    assert!(has_photo_in(&store, &dir, "alice/photos", "photos/a.jpg", false).unwrap());
+    assert!(!has_photo_in(&store, &dir, "alice/photos", "photos/a.jpg", true).unwrap());
+    assert!(!has_photo_in(&store, &dir, "alice/photos", "photos/b.jpg", false).unwrap());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(!has_photo_in(&store, &dir, "alice/photos", "photos/a.jpg", false).unwrap());
+}
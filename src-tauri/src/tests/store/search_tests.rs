@@ -0,0 +1,207 @@
+//! Library Search Tests
+//!
+//! Tests for:
+//! - Free-text and per-field matches, including short terms
+//! - Tag, album and date filters
+//! - Pagination, newest first
+//! - Encrypted albums while locked, and rebuilding the index
+
+use crate::catalog::{reconcile_album_in, set_sync_state_in, set_tags_in, SyncState};
+use crate::github::PhotoItem;
+use crate::local_store::LocalStore;
+use crate::metadata_vault::ExifExtract;
+use crate::search::{clear_index, search_photos_in, SearchQuery};
+use crate::video::MediaType;
+
+const REPO: &str = "alice/photos";
+/// 2023-06-15 10:00:00 UTC
+const JUNE_2023: i64 = 1_686_823_200;
+/// 2024-01-01 12:00:00 UTC
+const JANUARY_2024: i64 = 1_704_110_400;
+
+fn photo(name: &str) -> PhotoItem {
+    PhotoItem {
+        name: name.into(),
+        url: format!("https://example.com/{}", name),
+        sha: format!("sha-{}", name),
+        size: None,
+        encrypted: false,
+        display_name: None,
+        repo: None,
+        caption: None,
+        exif: None,
+        raw_companion: None,
+        media_type: MediaType::Photo,
+    }
+}
+
+fn shot(name: &str, camera: &str, taken_at: &str) -> PhotoItem {
+    PhotoItem {
+        exif: Some(ExifExtract {
+            camera_make: Some(camera.split(' ').next().unwrap().into()),
+            camera_model: Some(camera.into()),
+            taken_at: Some(taken_at.into()),
+            ..ExifExtract::default()
+        }),
+        ..photo(name)
+    }
+}
+
+/// Trips/Japan: two dated shots; Trips: one undated; Home: one encrypted
+fn library() -> LocalStore {
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    reconcile_album_in(
+        &store,
+        REPO,
+        "photos/Trips/Japan",
+        &[
+            shot("IMG_0042.jpg", "Canon EOS R6", "2024:01:01 12:00:00"),
+            shot("DSC_0001.jpg", "Nikon Z6", "2023:06:15 10:00:00"),
+        ],
+    )
+    .unwrap();
+    reconcile_album_in(&store, REPO, "photos/Trips", &[photo("beach_day.png")]).unwrap();
+    let sealed = PhotoItem {
+        encrypted: true,
+        display_name: Some("secret_beach.jpg".into()),
+        caption: Some("Sunset".into()),
+        ..photo("3f2a.vxe")
+    };
+    reconcile_album_in(&store, REPO, "photos/Home", &[sealed]).unwrap();
+    reconcile_album_in(&store, "bob/photos", "photos/Trips", &[photo("beach_bob.png")]).unwrap();
+    store
+}
+
+fn paths(store: &LocalStore, query: SearchQuery) -> Vec<String> {
+    search_photos_in(store, REPO, &query, true, 0, 50)
+        .unwrap()
+        .items
+        .into_iter()
+        .map(|p| p.path)
+        .collect()
+}
+
+// ============================================================================
+// Text Tests
+// ============================================================================
+
+#[test]
+fn free_text_matches_any_field() {
+    let store = library();
+    assert_eq!(paths(&store, SearchQuery { text: Some("canon".into()), ..Default::default() }), ["photos/Trips/Japan/IMG_0042.jpg"]);
+    assert_eq!(paths(&store, SearchQuery { text: Some("japan".into()), ..Default::default() }).len(), 2);
+    assert_eq!(paths(&store, SearchQuery { text: Some("sunset".into()), ..Default::default() }), ["photos/Home/3f2a.vxe"]);
+    // Every word has to match
+    assert_eq!(
+        paths(&store, SearchQuery { text: Some("beach trips".into()), ..Default::default() }),
+        ["photos/Trips/beach_day.png"]
+    );
+    assert!(paths(&store, SearchQuery { text: Some("\"quoted\" OR".into()), ..Default::default() }).is_empty());
+}
+
+#[test]
+fn fields_match_parts_of_words() {
+    let store = library();
+    assert_eq!(
+        paths(&store, SearchQuery { filename: Some("0042".into()), ..Default::default() }),
+        ["photos/Trips/Japan/IMG_0042.jpg"]
+    );
+    // Decrypted names count as file names
+    assert_eq!(
+        paths(&store, SearchQuery { filename: Some("secret".into()), ..Default::default() }),
+        ["photos/Home/3f2a.vxe"]
+    );
+    assert_eq!(
+        paths(&store, SearchQuery { camera: Some("z6".into()), ..Default::default() }),
+        ["photos/Trips/Japan/DSC_0001.jpg"]
+    );
+    // A camera word is not a file name
+    assert!(paths(&store, SearchQuery { filename: Some("canon".into()), ..Default::default() }).is_empty());
+    // Terms too short for the index are still found, with `_` taken literally
+    assert_eq!(
+        paths(&store, SearchQuery { text: Some("R6".into()), ..Default::default() }),
+        ["photos/Trips/Japan/IMG_0042.jpg"]
+    );
+    assert_eq!(paths(&store, SearchQuery { filename: Some("_d".into()), ..Default::default() }), ["photos/Trips/beach_day.png"]);
+}
+
+// ============================================================================
+// Attribute Tests
+// ============================================================================
+
+#[test]
+fn tags_albums_and_dates_filter() {
+    let store = library();
+    set_tags_in(&store, REPO, "photos/Trips/beach_day.png", &["Praia".to_string()]).unwrap();
+    assert_eq!(
+        paths(&store, SearchQuery { tag: Some("praia".into()), ..Default::default() }),
+        ["photos/Trips/beach_day.png"]
+    );
+    assert_eq!(
+        paths(&store, SearchQuery { text: Some("praia".into()), ..Default::default() }),
+        ["photos/Trips/beach_day.png"]
+    );
+
+    // Albums include their sub-albums
+    assert_eq!(paths(&store, SearchQuery { album: Some("photos/Trips/".into()), ..Default::default() }).len(), 3);
+    assert_eq!(paths(&store, SearchQuery { album: Some("photos/Trip".into()), ..Default::default() }).len(), 0);
+
+    let in_2023 = SearchQuery {
+        taken_from: Some(1_672_531_200),
+        taken_until: Some(1_704_067_199),
+        ..Default::default()
+    };
+    assert_eq!(paths(&store, in_2023), ["photos/Trips/Japan/DSC_0001.jpg"]);
+    let since_june = SearchQuery { taken_from: Some(JUNE_2023), ..Default::default() };
+    assert_eq!(paths(&store, since_june).len(), 2, "undated photos are left out of date ranges");
+
+    let backwards = SearchQuery { taken_from: Some(JANUARY_2024), taken_until: Some(JUNE_2023), ..Default::default() };
+    assert!(search_photos_in(&store, REPO, &backwards, true, 0, 50).is_err());
+}
+
+#[test]
+fn pending_deletes_are_not_found() {
+    let store = library();
+    set_sync_state_in(&store, REPO, "photos/Trips/beach_day.png", SyncState::PendingDelete).unwrap();
+    assert!(paths(&store, SearchQuery { text: Some("beach_day".into()), ..Default::default() }).is_empty());
+}
+
+// ============================================================================
+// Paging and Index Tests
+// ============================================================================
+
+#[test]
+fn pages_run_newest_first() {
+    let store = library();
+    let first = search_photos_in(&store, REPO, &SearchQuery::default(), true, 0, 2).unwrap();
+    assert_eq!(first.total, 4);
+    assert_eq!(first.items[0].taken_at, Some(JANUARY_2024));
+    assert_eq!(first.items[1].taken_at, Some(JUNE_2023));
+    assert_eq!(first.next_offset, Some(2));
+
+    let second = search_photos_in(&store, REPO, &SearchQuery::default(), true, 2, 2).unwrap();
+    assert_eq!(second.items.len(), 2);
+    assert!(second.items.iter().all(|p| p.taken_at.is_none()), "undated photos come last");
+    assert_eq!(second.next_offset, None);
+}
+
+#[test]
+fn locked_searches_skip_encrypted_albums() {
+    let store = library();
+    let query = SearchQuery { text: Some("beach".into()), ..Default::default() };
+    assert_eq!(search_photos_in(&store, REPO, &query, true, 0, 50).unwrap().total, 2);
+    let locked = search_photos_in(&store, REPO, &query, false, 0, 50).unwrap();
+    assert_eq!(locked.total, 1);
+    assert_eq!(locked.items[0].path, "photos/Trips/beach_day.png");
+}
+
+#[test]
+fn index_is_rebuilt_from_the_catalog() {
+    let store = library();
+    clear_index(&store).unwrap();
+    assert_eq!(
+        paths(&store, SearchQuery { camera: Some("canon".into()), ..Default::default() }),
+        ["photos/Trips/Japan/IMG_0042.jpg"]
+    );
+    assert_eq!(paths(&store, SearchQuery::default()).len(), 4);
+}
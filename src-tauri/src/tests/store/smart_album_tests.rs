@@ -0,0 +1,167 @@
+//! Smart Album Tests
+//!
+//! Tests for:
+//! - Rule validation and the flattened rule format
+//! - Evaluating tag, date, rating and megapixel rules
+//! - Change tracking as photos join and leave an album
+
+use std::collections::BTreeMap;
+
+use crate::catalog::{import_organization_in, reconcile_album_in, set_tags_in};
+use crate::github::PhotoItem;
+use crate::local_store::LocalStore;
+use crate::metadata_vault::ExifExtract;
+use crate::search::SearchQuery;
+use crate::smart_albums::{
+    create_smart_album_in, delete_smart_album_in, list_smart_albums_in, refresh_smart_albums_in,
+    smart_album_photos_in, SmartAlbumChange, SmartAlbumRules,
+};
+use crate::tagging::PhotoOrganization;
+use crate::video::MediaType;
+
+const REPO: &str = "alice/photos";
+const ALBUM: &str = "photos/Family";
+
+fn shot(name: &str, taken_at: &str, width: u32, height: u32) -> PhotoItem {
+    PhotoItem {
+        name: name.into(),
+        url: format!("https://example.com/{}", name),
+        sha: format!("sha-{}", name),
+        size: None,
+        encrypted: false,
+        display_name: None,
+        repo: None,
+        caption: None,
+        exif: Some(ExifExtract {
+            taken_at: Some(taken_at.into()),
+            width: Some(width),
+            height: Some(height),
+            ..ExifExtract::default()
+        }),
+        raw_companion: None,
+        media_type: MediaType::Photo,
+    }
+}
+
+fn tag(store: &LocalStore, name: &str, tags: &[&str]) {
+    let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+    set_tags_in(store, REPO, &format!("{}/{}", ALBUM, name), &tags).unwrap();
+}
+
+/// "All photos from 2023 tagged 'family' larger than 2MP"
+fn family_2023() -> SmartAlbumRules {
+    serde_json::from_value(serde_json::json!({
+        "tag": "family",
+        "taken_from": 1_672_531_200,
+        "taken_until": 1_704_067_199,
+        "min_megapixels": 2.0,
+    }))
+    .unwrap()
+}
+
+fn library() -> LocalStore {
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    reconcile_album_in(
+        &store,
+        REPO,
+        ALBUM,
+        &[
+            shot("big.jpg", "2023:07:01 10:00:00", 4000, 3000),
+            shot("small.jpg", "2023:07:02 10:00:00", 1280, 960),
+            shot("later.jpg", "2024:02:01 10:00:00", 4000, 3000),
+            shot("untagged.jpg", "2023:08:01 10:00:00", 4000, 3000),
+        ],
+    )
+    .unwrap();
+    for name in ["big.jpg", "small.jpg", "later.jpg"] {
+        tag(&store, name, &["Family"]);
+    }
+    store
+}
+
+fn names(photos: Vec<crate::catalog::CatalogPhoto>) -> Vec<String> {
+    photos.into_iter().map(|p| p.item.name).collect()
+}
+
+// ============================================================================
+// Rule Tests
+// ============================================================================
+
+#[test]
+fn rules_are_validated() {
+    let store = library();
+    assert!(create_smart_album_in(&store, REPO, "Everything", SmartAlbumRules::default()).is_err());
+    assert!(create_smart_album_in(&store, REPO, "  ", family_2023()).is_err());
+    let bad_rating = SmartAlbumRules { min_rating: Some(6), ..SmartAlbumRules::default() };
+    assert!(create_smart_album_in(&store, REPO, "Stars", bad_rating).is_err());
+    let bad_size = SmartAlbumRules { min_megapixels: Some(f64::NAN), ..SmartAlbumRules::default() };
+    assert!(create_smart_album_in(&store, REPO, "Big", bad_size).is_err());
+    let backwards = SmartAlbumRules {
+        search: SearchQuery { taken_from: Some(2), taken_until: Some(1), ..SearchQuery::default() },
+        ..SmartAlbumRules::default()
+    };
+    assert!(create_smart_album_in(&store, REPO, "Backwards", backwards).is_err());
+    assert!(list_smart_albums_in(&store, REPO).unwrap().is_empty());
+}
+
+#[test]
+fn family_photos_of_2023_larger_than_2mp() {
+    let store = library();
+    let album = create_smart_album_in(&store, REPO, "Family 2023", family_2023()).unwrap();
+    assert_eq!(album.photo_count, 1);
+    assert_eq!(album.rules.search.tag.as_deref(), Some("family"));
+    assert_eq!(names(smart_album_photos_in(&store, REPO, &album.id, true).unwrap()), ["big.jpg"]);
+
+    let listed = list_smart_albums_in(&store, REPO).unwrap();
+    assert_eq!(listed, [album.clone()]);
+    assert!(list_smart_albums_in(&store, "bob/photos").unwrap().is_empty());
+    assert!(smart_album_photos_in(&store, "bob/photos", &album.id, true).is_err());
+}
+
+#[test]
+fn favorites_and_ratings_are_rules() {
+    let store = library();
+    let organization = BTreeMap::from([
+        ("big.jpg".to_string(), PhotoOrganization { favorite: true, rating: Some(5), ..Default::default() }),
+        ("later.jpg".to_string(), PhotoOrganization { rating: Some(3), ..Default::default() }),
+    ]);
+    import_organization_in(&store, REPO, ALBUM, &organization).unwrap();
+
+    let rated = SmartAlbumRules { min_rating: Some(3), ..SmartAlbumRules::default() };
+    let album = create_smart_album_in(&store, REPO, "Rated", rated).unwrap();
+    assert_eq!(names(smart_album_photos_in(&store, REPO, &album.id, true).unwrap()), ["later.jpg", "big.jpg"]);
+
+    let favorites = SmartAlbumRules { favorite: Some(true), ..SmartAlbumRules::default() };
+    let album = create_smart_album_in(&store, REPO, "Favorites", favorites).unwrap();
+    assert_eq!(album.photo_count, 1);
+}
+
+// ============================================================================
+// Change Tests
+// ============================================================================
+
+#[test]
+fn changes_are_reported_once() {
+    let store = library();
+    let album = create_smart_album_in(&store, REPO, "Family 2023", family_2023()).unwrap();
+    assert!(refresh_smart_albums_in(&store, REPO).unwrap().is_empty());
+
+    tag(&store, "untagged.jpg", &["family"]);
+    tag(&store, "big.jpg", &[]);
+    let changes = refresh_smart_albums_in(&store, REPO).unwrap();
+    assert_eq!(
+        changes,
+        [SmartAlbumChange { repo: REPO.into(), id: album.id.clone(), added: 1, removed: 1, photo_count: 1 }]
+    );
+    assert!(refresh_smart_albums_in(&store, REPO).unwrap().is_empty());
+
+    // Photos leaving the library leave the album
+    reconcile_album_in(&store, REPO, ALBUM, &[shot("big.jpg", "2023:07:01 10:00:00", 4000, 3000)]).unwrap();
+    let changes = refresh_smart_albums_in(&store, REPO).unwrap();
+    assert_eq!((changes[0].removed, changes[0].photo_count), (1, 0));
+    assert_eq!(list_smart_albums_in(&store, REPO).unwrap()[0].photo_count, 0);
+
+    assert!(delete_smart_album_in(&store, REPO, &album.id).unwrap());
+    assert!(!delete_smart_album_in(&store, REPO, &album.id).unwrap());
+    assert!(refresh_smart_albums_in(&store, REPO).unwrap().is_empty());
+}
@@ -0,0 +1,127 @@
+//! Timeline Tests
+//!
+//! Tests for:
+//! - Bucket keys from EXIF and ISO 8601 dates
+//! - Day, month and year buckets, newest first
+//! - Choosing representatives and counting undated photos
+
+use std::collections::BTreeMap;
+
+use crate::catalog::{import_organization_in, reconcile_album_in};
+use crate::github::PhotoItem;
+use crate::local_store::LocalStore;
+use crate::metadata_vault::ExifExtract;
+use crate::search::{find_photos_in, SearchQuery};
+use crate::tagging::PhotoOrganization;
+use crate::timeline::{timeline_of, Timeline, TimelineGranularity};
+use crate::video::MediaType;
+
+const REPO: &str = "alice/photos";
+const ALBUM: &str = "photos/Trips";
+
+fn shot(name: &str, taken_at: Option<&str>) -> PhotoItem {
+    PhotoItem {
+        name: name.into(),
+        url: format!("https://example.com/{}", name),
+        sha: format!("sha-{}", name),
+        size: None,
+        encrypted: false,
+        display_name: None,
+        repo: None,
+        caption: None,
+        exif: taken_at.map(|t| ExifExtract { taken_at: Some(t.into()), ..ExifExtract::default() }),
+        raw_companion: None,
+        media_type: MediaType::Photo,
+    }
+}
+
+fn library() -> LocalStore {
+    let store = LocalStore::in_memory(&[7u8; 32]).unwrap();
+    reconcile_album_in(
+        &store,
+        REPO,
+        ALBUM,
+        &[
+            shot("a.jpg", Some("2023:06:15 09:00:00")),
+            shot("b.jpg", Some("2023:06:15 18:00:00")),
+            shot("c.jpg", Some("2023:06:20 12:00:00")),
+            shot("d.jpg", Some("2023:11:02 12:00:00")),
+            shot("e.jpg", Some("2024:01:01 00:30:00")),
+            shot("f.jpg", None),
+        ],
+    )
+    .unwrap();
+    store
+}
+
+fn timeline(store: &LocalStore, granularity: TimelineGranularity, per_bucket: usize) -> Timeline {
+    let photos = find_photos_in(store, REPO, &SearchQuery::default(), true).unwrap();
+    timeline_of(photos, granularity, per_bucket)
+}
+
+fn counts(timeline: &Timeline) -> Vec<(String, usize)> {
+    timeline.buckets.iter().map(|b| (b.key.clone(), b.count)).collect()
+}
+
+// ============================================================================
+// Bucket Key Tests
+// ============================================================================
+
+#[test]
+fn dates_map_to_bucket_keys() {
+    assert_eq!(TimelineGranularity::Day.bucket_of("2023:06:15 09:00:00").as_deref(), Some("2023-06-15"));
+    assert_eq!(TimelineGranularity::Month.bucket_of("2023-06-15T09:00:00Z").as_deref(), Some("2023-06"));
+    assert_eq!(TimelineGranularity::Year.bucket_of("2023:06:15").as_deref(), Some("2023"));
+    // Cameras without a clock set write zeros
+    assert_eq!(TimelineGranularity::Day.bucket_of("0000:00:00 00:00:00"), None);
+    assert_eq!(TimelineGranularity::Day.bucket_of("2023/06/15"), None);
+    assert_eq!(TimelineGranularity::Day.bucket_of("2023"), None);
+    assert_eq!(TimelineGranularity::default(), TimelineGranularity::Month);
+}
+
+// ============================================================================
+// Grouping Tests
+// ============================================================================
+
+#[test]
+fn photos_are_grouped_newest_first() {
+    let store = library();
+    let by_day = timeline(&store, TimelineGranularity::Day, 4);
+    assert_eq!(
+        counts(&by_day),
+        [
+            ("2024-01-01".to_string(), 1),
+            ("2023-11-02".to_string(), 1),
+            ("2023-06-20".to_string(), 1),
+            ("2023-06-15".to_string(), 2),
+        ]
+    );
+    assert_eq!(by_day.undated, 1);
+
+    let by_month = timeline(&store, TimelineGranularity::Month, 4);
+    assert_eq!(
+        counts(&by_month),
+        [("2024-01".to_string(), 1), ("2023-11".to_string(), 1), ("2023-06".to_string(), 3)]
+    );
+
+    let by_year = timeline(&store, TimelineGranularity::Year, 4);
+    assert_eq!(counts(&by_year), [("2024".to_string(), 1), ("2023".to_string(), 4)]);
+}
+
+#[test]
+fn representatives_prefer_favorites_then_ratings() {
+    let store = library();
+    let by_year = timeline(&store, TimelineGranularity::Year, 2);
+    let names: Vec<&str> = by_year.buckets[1].representatives.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["d.jpg", "c.jpg"], "newest first without organization");
+    assert_eq!(by_year.buckets[1].count, 4, "counts cover the whole bucket");
+
+    let organization = BTreeMap::from([
+        ("a.jpg".to_string(), PhotoOrganization { rating: Some(4), ..Default::default() }),
+        ("b.jpg".to_string(), PhotoOrganization { favorite: true, ..Default::default() }),
+    ]);
+    import_organization_in(&store, REPO, ALBUM, &organization).unwrap();
+    let by_year = timeline(&store, TimelineGranularity::Year, 2);
+    let names: Vec<&str> = by_year.buckets[1].representatives.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["b.jpg", "a.jpg"]);
+}
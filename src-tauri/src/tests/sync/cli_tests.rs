@@ -0,0 +1,127 @@
+//! CLI Tests
+//!
+//! Tests for:
+//! - Parsing the upload, sync and verify subcommands
+//! - Album names given with or without `photos/`
+//! - Which reports fail the command
+
+use clap::Parser;
+
+use crate::cli::{album_path, Cli, Command, Outcome};
+use crate::github::{UploadBatchResult, UploadFailure, UploadResult};
+use crate::security_verify::IntegrityReport;
+use crate::sync::SyncReport;
+
+fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+    Cli::try_parse_from(std::iter::once("vortex-cli").chain(args.iter().copied()))
+}
+
+fn integrity(untrusted: usize, unsigned: usize, tampered: usize) -> IntegrityReport {
+    IntegrityReport {
+        repo: "alice/photos".into(),
+        albums: Vec::new(),
+        verified: 3,
+        untrusted,
+        unsigned,
+        tampered,
+    }
+}
+
+// ============================================================================
+// Argument Tests
+// ============================================================================
+
+#[test]
+fn upload_takes_an_album_and_a_folder() {
+    let cli = parse(&["upload", "--repo", "alice/photos", "--album", "Trips", "--sign", "/srv/photos"]).unwrap();
+    assert!(!cli.json);
+    match cli.command {
+        Command::Upload { target, album, recursive, sign, path } => {
+            assert_eq!(target.repo, "alice/photos");
+            assert_eq!(album, "Trips");
+            assert!(!recursive);
+            assert!(sign);
+            assert_eq!(path, std::path::PathBuf::from("/srv/photos"));
+        }
+        other => panic!("parsed as {:?}", other),
+    }
+
+    assert!(parse(&["upload", "--repo", "alice/photos", "--album", "Trips"]).is_err());
+    assert!(parse(&["upload", "--repo", "alice/photos", "/srv/photos"]).is_err());
+}
+
+#[test]
+fn sync_collects_every_ignore_pattern() {
+    let cli = parse(&[
+        "sync", "--repo", "alice/photos", "--album", "Trips", "--ignore", "*.tmp", "--ignore", ".DS_Store", "--json", "/srv/photos",
+    ])
+    .unwrap();
+    assert!(cli.json);
+    match cli.command {
+        Command::Sync { ignore_patterns, recursive, .. } => {
+            assert_eq!(ignore_patterns, ["*.tmp", ".DS_Store"]);
+            assert!(!recursive);
+        }
+        other => panic!("parsed as {:?}", other),
+    }
+}
+
+#[test]
+fn verify_checks_the_whole_library_by_default() {
+    match parse(&["verify", "--repo", "alice/photos"]).unwrap().command {
+        Command::Verify { album, .. } => assert_eq!(album, None),
+        other => panic!("parsed as {:?}", other),
+    }
+    assert!(parse(&["prune", "--repo", "alice/photos"]).is_err());
+}
+
+#[test]
+fn albums_are_placed_under_photos() {
+    assert_eq!(album_path("Trips"), "photos/Trips");
+    assert_eq!(album_path("/Trips/Rome/"), "photos/Trips/Rome");
+    assert_eq!(album_path("photos/Trips"), "photos/Trips");
+    assert_eq!(album_path("photos"), "photos");
+    assert_eq!(album_path("photoshoot"), "photos/photoshoot");
+}
+
+// ============================================================================
+// Outcome Tests
+// ============================================================================
+
+#[test]
+fn tampered_or_untrusted_photos_fail_verification() {
+    assert!(!integrity(0, 2, 0).failed());
+    assert!(integrity(1, 0, 0).failed());
+    assert!(integrity(0, 0, 1).failed());
+    assert!(integrity(0, 2, 1).summary().ends_with("3 verified, 0 untrusted, 2 unsigned, 1 tampered"));
+}
+
+#[test]
+fn skipped_files_fail_a_sync() {
+    let mut report = SyncReport {
+        uploaded: vec!["a.jpg".into()],
+        ..Default::default()
+    };
+    assert!(!report.failed());
+
+    report.skipped.push("/srv/photos/con.jpg".into());
+    assert!(report.failed());
+    assert!(report.summary().starts_with("skipped /srv/photos/con.jpg\n"));
+}
+
+#[test]
+fn any_failed_upload_fails_the_command() {
+    let mut result = UploadBatchResult {
+        succeeded: vec![UploadResult { url: String::new(), sha: "abc".into() }],
+        failed: Vec::new(),
+    };
+    assert!(!result.failed());
+
+    result.failed.push(UploadFailure {
+        path: "/srv/photos/a.jpg".into(),
+        name: "a.jpg".into(),
+        error: "Upload failed".into(),
+    });
+    assert!(result.failed());
+    assert_eq!(result.summary(), "failed  /srv/photos/a.jpg: Upload failed\n1 uploaded, 1 failed");
+}
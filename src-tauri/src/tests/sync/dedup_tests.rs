@@ -0,0 +1,171 @@
+//! Upload Deduplication Tests
+//!
+//! Tests for:
+//! - Skipping content the album's hash index already knows
+//! - Renaming remote files instead of uploading them again
+//! - Stale index entries, replaced paths and the byte report
+//! - Album paths for local files and hashing them
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::AtomicBool;
+
+use crate::github::ImageFile;
+use crate::upload_dedup::{
+    album_relative_path, dedup_report, hash_photos, plan_dedup, DedupAction, LocalPhoto, RenamedPhoto, SkippedPhoto,
+};
+
+fn photo(path: &str, hash: &str, size: u64) -> LocalPhoto {
+    LocalPhoto {
+        source: format!("/local/{}", path),
+        path: path.to_string(),
+        hash: hash.to_string(),
+        size,
+    }
+}
+
+fn index(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries.iter().map(|(hash, path)| (hash.to_string(), path.to_string())).collect()
+}
+
+fn remote(paths: &[&str]) -> BTreeSet<String> {
+    paths.iter().map(|p| p.to_string()).collect()
+}
+
+// ============================================================================
+// Planning Tests
+// ============================================================================
+
+#[test]
+fn known_content_is_skipped() {
+    let photos = [photo("a.jpg", "h1", 100), photo("b.jpg", "h2", 200)];
+    let plan = plan_dedup(&photos, &index(&[("h1", "a.jpg")]), &remote(&["a.jpg"]), false);
+    assert_eq!(
+        plan.actions,
+        vec![DedupAction::Skip { existing: "a.jpg".into() }, DedupAction::Upload]
+    );
+    assert_eq!(plan.index, index(&[("h1", "a.jpg"), ("h2", "b.jpg")]));
+}
+
+#[test]
+fn moved_content_is_skipped_without_rename() {
+    let photos = [photo("new/a.jpg", "h1", 100)];
+    let plan = plan_dedup(&photos, &index(&[("h1", "old/a.jpg")]), &remote(&["old/a.jpg"]), false);
+    assert_eq!(plan.actions, vec![DedupAction::Skip { existing: "old/a.jpg".into() }]);
+    assert_eq!(plan.index, index(&[("h1", "old/a.jpg")]));
+}
+
+#[test]
+fn moved_content_is_renamed_when_asked() {
+    let photos = [photo("new/a.jpg", "h1", 100)];
+    let plan = plan_dedup(&photos, &index(&[("h1", "old/a.jpg")]), &remote(&["old/a.jpg"]), true);
+    assert_eq!(plan.actions, vec![DedupAction::Rename { from: "old/a.jpg".into() }]);
+    assert_eq!(plan.index, index(&[("h1", "new/a.jpg")]));
+}
+
+#[test]
+fn rename_never_overwrites_other_content() {
+    let photos = [photo("b.jpg", "h1", 100)];
+    let plan = plan_dedup(&photos, &index(&[("h1", "a.jpg")]), &remote(&["a.jpg", "b.jpg"]), true);
+    assert_eq!(plan.actions, vec![DedupAction::Skip { existing: "a.jpg".into() }]);
+}
+
+#[test]
+fn local_duplicates_upload_once() {
+    let photos = [photo("a.jpg", "h1", 100), photo("copy/a.jpg", "h1", 100)];
+    for rename in [false, true] {
+        let plan = plan_dedup(&photos, &BTreeMap::new(), &BTreeSet::new(), rename);
+        assert_eq!(
+            plan.actions,
+            vec![DedupAction::Upload, DedupAction::Skip { existing: "a.jpg".into() }]
+        );
+    }
+}
+
+#[test]
+fn stale_entries_are_dropped() {
+    let photos = [photo("a.jpg", "h1", 100)];
+    let plan = plan_dedup(&photos, &index(&[("h1", "a.jpg"), ("h9", "gone.jpg")]), &remote(&[]), false);
+    assert_eq!(plan.actions, vec![DedupAction::Upload]);
+    assert_eq!(plan.index, index(&[("h1", "a.jpg")]));
+}
+
+#[test]
+fn replacing_a_path_drops_its_old_hash() {
+    let photos = [photo("a.jpg", "h2", 100)];
+    let plan = plan_dedup(&photos, &index(&[("h1", "a.jpg")]), &remote(&["a.jpg"]), false);
+    assert_eq!(plan.actions, vec![DedupAction::Upload]);
+    assert_eq!(plan.index, index(&[("h2", "a.jpg")]));
+}
+
+// ============================================================================
+// Report Tests
+// ============================================================================
+
+#[test]
+fn report_counts_skipped_and_transferred_bytes() {
+    let photos = [
+        photo("a.jpg", "h1", 100),
+        photo("b.jpg", "h2", 200),
+        photo("c.jpg", "h3", 400),
+    ];
+    let plan = plan_dedup(
+        &photos,
+        &index(&[("h1", "a.jpg"), ("h3", "old.jpg")]),
+        &remote(&["a.jpg", "old.jpg"]),
+        true,
+    );
+    let report = dedup_report(&photos, &plan);
+
+    assert_eq!(report.uploaded, vec!["b.jpg".to_string()]);
+    assert_eq!(
+        report.skipped,
+        vec![SkippedPhoto {
+            path: "a.jpg".into(),
+            existing: "a.jpg".into()
+        }]
+    );
+    assert_eq!(
+        report.renamed,
+        vec![RenamedPhoto {
+            from: "old.jpg".into(),
+            to: "c.jpg".into()
+        }]
+    );
+    assert_eq!(report.transferred_bytes, 200);
+    assert_eq!(report.skipped_bytes, 500);
+    assert_eq!(report.commit_sha, None);
+}
+
+// ============================================================================
+// Local Photo Tests
+// ============================================================================
+
+#[test]
+fn relative_paths_are_sanitized() {
+    assert_eq!(album_relative_path("Trips/beach.jpg"), "Trips/beach.jpg");
+    assert_eq!(album_relative_path("Trips\\beach.jpg"), "Trips/beach.jpg");
+    assert_eq!(album_relative_path("../x/./my photo.jpg"), "x/myphoto.jpg");
+    assert_eq!(album_relative_path(".."), "");
+}
+
+#[test]
+fn photos_are_hashed_with_blake3() {
+    let dir = std::env::temp_dir().join(format!("vortex-dedup-test-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub/a.jpg"), b"photo bytes").unwrap();
+
+    let images = vec![ImageFile {
+        path: dir.join("sub/a.jpg").to_string_lossy().to_string(),
+        name: "a.jpg".into(),
+        size: 11,
+        relative_path: "sub/a.jpg".into(),
+    }];
+    let photos = hash_photos(&images, &AtomicBool::new(false)).unwrap();
+    assert_eq!(photos.len(), 1);
+    assert_eq!(photos[0].path, "sub/a.jpg");
+    assert_eq!(photos[0].hash, blake3::hash(b"photo bytes").to_hex().to_string());
+    assert_eq!(photos[0].size, 11);
+
+    assert!(hash_photos(&images, &AtomicBool::new(true)).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
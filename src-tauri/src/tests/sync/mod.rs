@@ -0,0 +1,12 @@
+//! Sync Module Tests
+//!
+//! Organized by functionality:
+//! - `plan_tests` - Three-way change detection and conflict policies
+//! - `cli_tests` - Headless CLI arguments and exit status
+//! - `dedup_tests` - Upload deduplication against the album hash index
+//! - `schedule_tests` - Scheduled runs, cadences and validation
+
+pub mod plan_tests;
+pub mod cli_tests;
+pub mod dedup_tests;
+pub mod schedule_tests;
@@ -0,0 +1,143 @@
+//! Two-Way Sync Planning Tests
+//!
+//! Tests for:
+//! - Change detection against the last synced baseline
+//! - Conflict resolution policies
+//! - Conflict copy naming and GitHub timestamp parsing
+
+use std::collections::BTreeMap;
+
+use crate::sync::{
+    conflict_copy_path, parse_github_timestamp, plan_sync, resolve_conflict, ConflictInfo, ConflictPolicy,
+    ConflictResolution, LocalFile, SyncAction, SyncState, SyncedFile,
+};
+
+fn baseline(entries: &[(&str, &str, &str)]) -> SyncState {
+    SyncState {
+        version: 1,
+        files: entries
+            .iter()
+            .map(|(path, hash, sha)| {
+                (
+                    path.to_string(),
+                    SyncedFile {
+                        local_hash: hash.to_string(),
+                        local_modified: 100,
+                        blob_sha: sha.to_string(),
+                    },
+                )
+            })
+            .collect(),
+    }
+}
+
+fn local(entries: &[(&str, &str)]) -> BTreeMap<String, LocalFile> {
+    entries
+        .iter()
+        .map(|(path, hash)| (path.to_string(), LocalFile { hash: hash.to_string(), modified: 200 }))
+        .collect()
+}
+
+fn remote(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries.iter().map(|(p, s)| (p.to_string(), s.to_string())).collect()
+}
+
+fn conflict(local_hash: Option<&str>, remote_hash: Option<&str>) -> ConflictInfo {
+    ConflictInfo {
+        path: "trip/a.jpg".into(),
+        local_modified: 1700000000,
+        remote_modified: 1700000100,
+        local_hash: local_hash.map(String::from),
+        remote_hash: remote_hash.map(String::from),
+    }
+}
+
+// ============================================================================
+// Planning Tests
+// ============================================================================
+
+#[test]
+fn one_sided_changes_propagate() {
+    let state = baseline(&[("a.jpg", "h1", "s1"), ("b.jpg", "h2", "s2"), ("c.jpg", "h3", "s3"), ("d.jpg", "h4", "s4")]);
+    let local = local(&[("a.jpg", "h1x"), ("b.jpg", "h2"), ("d.jpg", "h4"), ("new.jpg", "h5")]);
+    let remote = remote(&[("a.jpg", "s1"), ("b.jpg", "s2x"), ("c.jpg", "s3"), ("fresh.png", "s6")]);
+
+    let actions = plan_sync(&state, &local, &remote);
+
+    assert_eq!(
+        actions,
+        vec![
+            SyncAction::Upload("a.jpg".into()),
+            SyncAction::Download("b.jpg".into()),
+            SyncAction::DeleteRemote("c.jpg".into()),
+            SyncAction::DeleteLocal("d.jpg".into()),
+            SyncAction::Download("fresh.png".into()),
+            SyncAction::Upload("new.jpg".into()),
+        ]
+    );
+}
+
+#[test]
+fn unchanged_files_produce_no_actions() {
+    let state = baseline(&[("a.jpg", "h1", "s1")]);
+    let actions = plan_sync(&state, &local(&[("a.jpg", "h1")]), &remote(&[("a.jpg", "s1")]));
+    assert!(actions.is_empty());
+}
+
+#[test]
+fn changes_on_both_sides_are_conflicts() {
+    let state = baseline(&[("edit.jpg", "h1", "s1"), ("gone.jpg", "h2", "s2"), ("both_gone.jpg", "h3", "s3")]);
+    let local = local(&[("edit.jpg", "h1x"), ("added.jpg", "hx")]);
+    let remote = remote(&[("edit.jpg", "s1x"), ("gone.jpg", "s2x"), ("added.jpg", "sx")]);
+
+    let actions = plan_sync(&state, &local, &remote);
+
+    assert_eq!(
+        actions,
+        vec![
+            SyncAction::Conflict("added.jpg".into()),
+            SyncAction::Forget("both_gone.jpg".into()),
+            SyncAction::Conflict("edit.jpg".into()),
+            SyncAction::Conflict("gone.jpg".into()),
+        ]
+    );
+}
+
+// ============================================================================
+// Policy Tests
+// ============================================================================
+
+#[test]
+fn policies_pick_the_expected_version() {
+    let both = conflict(Some("l"), Some("r"));
+
+    assert_eq!(resolve_conflict(ConflictPolicy::PreferLocal, &both), ConflictResolution::Local);
+    assert_eq!(resolve_conflict(ConflictPolicy::PreferRemote, &both), ConflictResolution::Remote);
+    assert_eq!(
+        resolve_conflict(ConflictPolicy::KeepBoth, &both),
+        ConflictResolution::Both {
+            copy_path: "trip/a-conflict-1700000000.jpg".into()
+        }
+    );
+
+    // Keep-both cannot keep a deleted side, so the surviving edit wins
+    assert_eq!(resolve_conflict(ConflictPolicy::KeepBoth, &conflict(Some("l"), None)), ConflictResolution::Local);
+    assert_eq!(resolve_conflict(ConflictPolicy::KeepBoth, &conflict(None, Some("r"))), ConflictResolution::Remote);
+    assert_eq!(ConflictPolicy::default(), ConflictPolicy::KeepBoth);
+}
+
+#[test]
+fn conflict_copy_names_keep_extension() {
+    assert_eq!(conflict_copy_path("a.jpg", 5), "a-conflict-5.jpg");
+    assert_eq!(conflict_copy_path("x/y/photo.tar.png", 5), "x/y/photo.tar-conflict-5.png");
+    assert_eq!(conflict_copy_path(".hidden", 5), ".hidden-conflict-5");
+    assert_eq!(conflict_copy_path("noext", 5), "noext-conflict-5");
+}
+
+#[test]
+fn parses_github_timestamps() {
+    assert_eq!(parse_github_timestamp("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_github_timestamp("2024-02-29T12:30:15Z"), Some(1709209815));
+    assert_eq!(parse_github_timestamp("2024-13-01T00:00:00Z"), None);
+    assert_eq!(parse_github_timestamp("2024-01-01 00:00:00"), None);
+}
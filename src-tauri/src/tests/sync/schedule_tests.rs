@@ -0,0 +1,96 @@
+//! Sync Schedule Tests
+//!
+//! Tests for:
+//! - Next run times of interval and daily cadences
+//! - Daily times in other time zones
+//! - Schedule validation
+
+use crate::sync_schedule::{next_run_at, validate_schedule, Cadence, ScheduledTask, SyncSchedule};
+
+const DAY: u64 = 24 * 60 * 60;
+/// 2027-01-15 00:00 UTC
+const MIDNIGHT: u64 = 1_800_000_000 - 1_800_000_000 % DAY;
+
+fn schedule(cadence: Cadence, tasks: Vec<ScheduledTask>) -> SyncSchedule {
+    SyncSchedule {
+        repo: "owner/photos".into(),
+        album_path: "Trips".into(),
+        cadence,
+        tasks,
+        local_dir: None,
+        recursive: false,
+        ignore_patterns: Vec::new(),
+        enabled: true,
+    }
+}
+
+fn daily(hour: u8, minute: u8, utc_offset_minutes: i32) -> Cadence {
+    Cadence::Daily {
+        hour,
+        minute,
+        utc_offset_minutes,
+    }
+}
+
+// ============================================================================
+// Cadence Tests
+// ============================================================================
+
+#[test]
+fn interval_runs_at_once_then_every_interval() {
+    let cadence = Cadence::Interval { minutes: 30 };
+    assert_eq!(next_run_at(&cadence, MIDNIGHT, None), MIDNIGHT);
+    assert_eq!(next_run_at(&cadence, MIDNIGHT, Some(MIDNIGHT + 100)), MIDNIGHT + 100 + 30 * 60);
+}
+
+#[test]
+fn daily_runs_at_the_next_occurrence() {
+    let cadence = daily(3, 30, 0);
+    let at = MIDNIGHT + 3 * 3600 + 30 * 60;
+    assert_eq!(next_run_at(&cadence, MIDNIGHT, None), at);
+    // Set after today's time: tomorrow
+    assert_eq!(next_run_at(&cadence, MIDNIGHT + 12 * 3600, None), at + DAY);
+    // Ran today: tomorrow
+    assert_eq!(next_run_at(&cadence, MIDNIGHT, Some(at)), at + DAY);
+    assert_eq!(next_run_at(&cadence, MIDNIGHT, Some(at + 60)), at + DAY);
+}
+
+#[test]
+fn daily_time_follows_the_utc_offset() {
+    // 08:00 at UTC+2 is 06:00 UTC
+    assert_eq!(next_run_at(&daily(8, 0, 120), MIDNIGHT, None), MIDNIGHT + 6 * 3600);
+    // 20:00 at UTC-5 is 01:00 UTC the next day
+    assert_eq!(next_run_at(&daily(20, 0, -300), MIDNIGHT + 2 * 3600, None), MIDNIGHT + DAY + 3600);
+}
+
+// ============================================================================
+// Validation Tests
+// ============================================================================
+
+#[test]
+fn valid_schedules_pass() {
+    assert!(validate_schedule(&schedule(Cadence::Interval { minutes: 60 }, vec![ScheduledTask::Verify])).is_ok());
+    assert!(validate_schedule(&schedule(daily(23, 59, -720), vec![ScheduledTask::Retention])).is_ok());
+}
+
+#[test]
+fn invalid_cadences_are_rejected() {
+    let tasks = vec![ScheduledTask::Verify];
+    assert!(validate_schedule(&schedule(Cadence::Interval { minutes: 1 }, tasks.clone())).is_err());
+    assert!(validate_schedule(&schedule(daily(24, 0, 0), tasks.clone())).is_err());
+    assert!(validate_schedule(&schedule(daily(1, 60, 0), tasks.clone())).is_err());
+    assert!(validate_schedule(&schedule(daily(1, 0, 15 * 60), tasks)).is_err());
+}
+
+#[test]
+fn schedules_need_tasks_and_a_folder_to_sync() {
+    let cadence = Cadence::Interval { minutes: 60 };
+    assert!(validate_schedule(&schedule(cadence, Vec::new())).is_err());
+
+    let mut sync = schedule(cadence, vec![ScheduledTask::Sync]);
+    assert!(validate_schedule(&sync).is_err());
+    sync.local_dir = Some(std::env::temp_dir().to_string_lossy().to_string());
+    assert!(validate_schedule(&sync).is_ok());
+    sync.album_path = "../x".into();
+    assert!(validate_schedule(&sync).is_err());
+}
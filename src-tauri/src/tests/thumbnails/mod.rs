@@ -0,0 +1,6 @@
+//! Thumbnail Module Tests
+//!
+//! Organized by functionality:
+//! - `thumbnail_tests` - Rendering and the LRU disk cache
+
+pub mod thumbnail_tests;
@@ -0,0 +1,93 @@
+//! Thumbnail Tests
+//!
+//! Tests for:
+//! - Rendering WebP thumbnails that fit the requested size
+//! - Content-hash keys and cache hits
+//! - Least recently used eviction and reopening the cache
+
+use image::GenericImageView;
+use std::sync::Mutex;
+
+use crate::entropy::{detect_kind, ContentKind};
+use crate::thumbnails::{render_thumbnail, thumbnail_in, ThumbnailCache, MAX_CACHE_BYTES};
+use crate::tests::common::{png, temp_dir};
+
+// ============================================================================
+// Rendering Tests
+// ============================================================================
+
+#[test]
+fn thumbnails_fit_the_size_without_upscaling() {
+    let thumbnail = render_thumbnail(&png(400, 200), 100).unwrap();
+    assert_eq!(detect_kind(&thumbnail), ContentKind::Webp);
+    assert_eq!(image::load_from_memory(&thumbnail).unwrap().dimensions(), (100, 50));
+
+    let small = render_thumbnail(&png(40, 30), 100).unwrap();
+    assert_eq!(image::load_from_memory(&small).unwrap().dimensions(), (40, 30));
+
+    assert!(render_thumbnail(b"not an image", 100).is_err());
+}
+
+// ============================================================================
+// Cache Tests
+// ============================================================================
+
+#[test]
+fn second_request_is_served_from_the_cache() {
+    let dir = temp_dir("thumbnail", "hit");
+    let cache = Mutex::new(ThumbnailCache::open(&dir, MAX_CACHE_BYTES).unwrap());
+    let photo = png(300, 300);
+
+    let first = thumbnail_in(&cache, &photo, 128).unwrap();
+    assert!(!first.cached);
+    assert_eq!((first.width, first.height), (128, 128));
+    let second = thumbnail_in(&cache, &photo, 128).unwrap();
+    assert!(second.cached);
+    assert_eq!(second.path, first.path);
+
+    // Other sizes and other content get their own entries
+    assert!(!thumbnail_in(&cache, &photo, 64).unwrap().cached);
+    assert!(!thumbnail_in(&cache, &png(300, 299), 128).unwrap().cached);
+    assert_eq!(cache.lock().unwrap().len(), 3);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let dir = temp_dir("thumbnail", "lru");
+    let mut cache = ThumbnailCache::open(&dir, 250).unwrap();
+    cache.insert("a", &[1u8; 100]).unwrap();
+    cache.insert("b", &[2u8; 100]).unwrap();
+    assert!(cache.get("a").is_some());
+    cache.insert("c", &[3u8; 100]).unwrap();
+
+    assert!(cache.get("b").is_none());
+    assert!(cache.get("a").is_some() && cache.get("c").is_some());
+    assert_eq!(cache.total_bytes(), 200);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn reopening_keeps_entries_within_the_budget() {
+    let dir = temp_dir("thumbnail", "reopen");
+    let mut cache = ThumbnailCache::open(&dir, MAX_CACHE_BYTES).unwrap();
+    cache.insert("a", &[1u8; 100]).unwrap();
+    cache.insert("b", &[2u8; 100]).unwrap();
+    drop(cache);
+
+    let mut reopened = ThumbnailCache::open(&dir, 150).unwrap();
+    assert_eq!(reopened.len(), 1);
+    assert_eq!(reopened.total_bytes(), 100);
+
+    // A thumbnail deleted behind the cache's back is a miss
+    let key = if reopened.get("a").is_some() { "a" } else { "b" };
+    std::fs::remove_file(dir.join(format!("{}.webp", key))).unwrap();
+    assert!(reopened.get(key).is_none());
+    assert!(reopened.is_empty());
+
+    assert_eq!(reopened.clear().unwrap(), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,6 @@
+//! Video Module Tests
+//!
+//! Organized by functionality:
+//! - `video_tests` - Container probing, fingerprints, chunk indexes and manifest entries
+
+pub mod video_tests;
@@ -0,0 +1,207 @@
+//! Video Tests
+//!
+//! Tests for:
+//! - Probing MP4, Matroska/WebM, AVI and MPEG-TS headers
+//! - Fingerprints used as thumbnail keys
+//! - Chunk indexes stored in place of large files
+//! - Video entries in album manifests and listings
+
+use std::io::Cursor;
+
+use crate::album::AlbumManifest;
+use crate::chunks::{chunk_path, ChunkIndex, ChunkPart};
+use crate::git_data::TreeEntry;
+use crate::listing::album_entries;
+use crate::tests::common::temp_file;
+use crate::video::{fingerprint, probe, MediaEntry, MediaType, VideoInfo};
+
+/// ISO BMFF box
+fn bx(kind: &[u8], body: &[u8]) -> Vec<u8> {
+    [&(8 + body.len() as u32).to_be_bytes()[..], kind, body].concat()
+}
+
+fn trak(handler: &[u8], fourcc: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let tkhd = [&[0u8; 76][..], &(width << 16).to_be_bytes()[..], &(height << 16).to_be_bytes()[..]].concat();
+    let hdlr = [&[0u8; 8][..], handler, &[0u8; 12][..]].concat();
+    let stsd = [&[0u8, 0, 0, 0, 0, 0, 0, 1][..], &16u32.to_be_bytes()[..], fourcc, &[0u8; 8][..]].concat();
+    let stbl = bx(b"stbl", &bx(b"stsd", &stsd));
+    let mdia = [bx(b"hdlr", &hdlr), bx(b"minf", &stbl)].concat();
+    bx(b"trak", &[bx(b"tkhd", &tkhd), bx(b"mdia", &mdia)].concat())
+}
+
+fn mp4(brand: &[u8]) -> Vec<u8> {
+    let mvhd = [&[0u8; 12][..], &1000u32.to_be_bytes()[..], &12_500u32.to_be_bytes()[..], &[0u8; 80][..]].concat();
+    let moov = [bx(b"mvhd", &mvhd), trak(b"soun", b"mp4a", 0, 0), trak(b"vide", b"hvc1", 1920, 1080)].concat();
+    [bx(b"ftyp", &[brand, &[0u8; 4][..]].concat()), bx(b"mdat", &[0xAB; 64]), bx(b"moov", &moov)].concat()
+}
+
+/// EBML element with an 8-byte size
+fn el(id: u32, body: &[u8]) -> Vec<u8> {
+    let id = id.to_be_bytes();
+    let id = &id[id.iter().position(|b| *b != 0).unwrap_or(3)..];
+    let size = [&[0x01u8][..], &(body.len() as u64).to_be_bytes()[1..]].concat();
+    [id, &size[..], body].concat()
+}
+
+fn webm() -> Vec<u8> {
+    let info = [el(0x2AD7B1, &[0x0F, 0x42, 0x40]), el(0x4489, &12_345.0f64.to_be_bytes())].concat();
+    let audio = [el(0x83, &[2]), el(0x86, b"A_OPUS")].concat();
+    let video = [el(0xB0, &1280u16.to_be_bytes()), el(0xBA, &720u16.to_be_bytes())].concat();
+    let video_track = [el(0x83, &[1]), el(0x86, b"V_VP9"), el(0xE0, &video)].concat();
+    let tracks = el(0x1654AE6B, &[el(0xAE, &audio), el(0xAE, &video_track)].concat());
+    let cluster = el(0x1F43B675, &[0u8; 32]);
+    // Live recordings leave the segment size unknown
+    let segment_header: [u8; 12] = [0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+    let segment = [&segment_header[..], &el(0x1549A966, &info)[..], &tracks[..], &cluster[..]].concat();
+    [el(0x1A45DFA3, &el(0x4282, b"webm")), segment].concat()
+}
+
+fn riff(kind: &[u8], body: &[u8]) -> Vec<u8> {
+    [kind, &(body.len() as u32).to_le_bytes()[..], body].concat()
+}
+
+fn avi() -> Vec<u8> {
+    let mut avih = [0u8; 56];
+    avih[0..4].copy_from_slice(&40_000u32.to_le_bytes());
+    avih[16..20].copy_from_slice(&250u32.to_le_bytes());
+    avih[32..36].copy_from_slice(&640u32.to_le_bytes());
+    avih[36..40].copy_from_slice(&480u32.to_le_bytes());
+    let mut strh = [0u8; 56];
+    strh[0..8].copy_from_slice(b"vidsXVID");
+    let strl = riff(b"LIST", &[&b"strl"[..], &riff(b"strh", &strh)[..]].concat());
+    let hdrl = riff(b"LIST", &[&b"hdrl"[..], &riff(b"avih", &avih)[..], &strl[..]].concat());
+    riff(b"RIFF", &[&b"AVI "[..], &hdrl[..]].concat())
+}
+
+// ============================================================================
+// Probing Tests
+// ============================================================================
+
+#[test]
+fn mp4_reports_the_video_track() {
+    let info = probe(&mut Cursor::new(mp4(b"isom"))).unwrap();
+    assert_eq!(
+        info,
+        VideoInfo {
+            container: "mp4".into(),
+            codec: Some("hevc".into()),
+            duration_secs: Some(12.5),
+            width: Some(1920),
+            height: Some(1080),
+        }
+    );
+    assert_eq!(probe(&mut Cursor::new(mp4(b"qt  "))).unwrap().container, "mov");
+}
+
+#[test]
+fn webm_reports_the_video_track() {
+    let info = probe(&mut Cursor::new(webm())).unwrap();
+    assert_eq!(info.container, "webm");
+    assert_eq!(info.codec.as_deref(), Some("vp9"));
+    assert!((info.duration_secs.unwrap() - 12.345).abs() < 1e-9);
+    assert_eq!((info.width, info.height), (Some(1280), Some(720)));
+}
+
+#[test]
+fn avi_and_transport_streams_are_recognized() {
+    let info = probe(&mut Cursor::new(avi())).unwrap();
+    assert_eq!(info.container, "avi");
+    assert_eq!(info.codec.as_deref(), Some("mpeg4"));
+    assert_eq!(info.duration_secs, Some(10.0));
+    assert_eq!((info.width, info.height), (Some(640), Some(480)));
+
+    let mut ts = vec![0u8; 188 * 3];
+    for packet in ts.chunks_mut(188) {
+        packet[0] = 0x47;
+    }
+    assert_eq!(probe(&mut Cursor::new(ts)).unwrap().container, "mpegts");
+}
+
+#[test]
+fn other_files_are_refused() {
+    assert!(probe(&mut Cursor::new(b"\xff\xd8\xff\xe0 not a video".to_vec())).is_err());
+    assert!(probe(&mut Cursor::new(Vec::new())).is_err());
+    // A movie without its header cannot be described
+    let truncated = [bx(b"ftyp", b"isom\0\0\0\0"), bx(b"mdat", &[0u8; 16])].concat();
+    assert!(probe(&mut Cursor::new(truncated)).is_err());
+}
+
+// ============================================================================
+// Fingerprint Tests
+// ============================================================================
+
+#[test]
+fn fingerprints_follow_content_not_names() {
+    let content = mp4(b"isom");
+    let (a, b) = (temp_file("video", "a.mp4", &content), temp_file("video", "b.mp4", &content));
+    let changed = temp_file("video", "c.mp4", &[content.as_slice(), &[0u8][..]].concat());
+
+    assert_eq!(fingerprint(&a).unwrap(), fingerprint(&b).unwrap());
+    assert_ne!(fingerprint(&a).unwrap(), fingerprint(&changed).unwrap());
+    for path in [a, b, changed] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// ============================================================================
+// Chunk Index Tests
+// ============================================================================
+
+#[test]
+fn chunk_index_roundtrip() {
+    let parts = vec![
+        ChunkPart { sha: "a".repeat(40), size: 32 },
+        ChunkPart { sha: "b".repeat(40), size: 8 },
+    ];
+    let index = ChunkIndex::new("f".repeat(64), parts);
+    assert_eq!(index.size, 40);
+    assert_eq!(ChunkIndex::parse(&index.to_bytes()), Some(index));
+    assert_eq!(chunk_path(&"f".repeat(64), 3), format!(".chunks/{}/00003", "f".repeat(64)));
+}
+
+#[test]
+fn regular_content_is_not_a_chunk_index() {
+    assert_eq!(ChunkIndex::parse(&mp4(b"isom")), None);
+    assert_eq!(ChunkIndex::parse(br#"{"format":"other","version":1,"size":0,"sha256":"","parts":[]}"#), None);
+
+    let mut index = ChunkIndex::new("f".repeat(64), vec![ChunkPart { sha: "a".repeat(40), size: 32 }]);
+    index.size = 64;
+    assert_eq!(ChunkIndex::parse(&index.to_bytes()), None, "sizes must add up");
+}
+
+// ============================================================================
+// Manifest Tests
+// ============================================================================
+
+#[test]
+fn manifests_record_videos_only_when_present() {
+    let mut manifest = AlbumManifest::new(false, None);
+    let json = serde_json::to_value(&manifest).unwrap();
+    assert!(json.get("media").is_none(), "older readers see an unchanged manifest");
+
+    let info = probe(&mut Cursor::new(mp4(b"isom"))).unwrap();
+    manifest.media.insert("clip.mp4".into(), MediaEntry::video(info, 1234));
+    let json = serde_json::to_value(&manifest).unwrap();
+    assert_eq!(json["media"]["clip.mp4"]["media_type"], "video");
+    assert_eq!(json["media"]["clip.mp4"]["codec"], "hevc");
+
+    let back: AlbumManifest = serde_json::from_value(json).unwrap();
+    assert_eq!(back.media["clip.mp4"].size, 1234);
+}
+
+#[test]
+fn listings_include_videos() {
+    let blob = |path: &str| TreeEntry {
+        path: path.into(),
+        mode: "100644".into(),
+        kind: "blob".into(),
+        sha: "0".repeat(40),
+        size: Some(1),
+    };
+    let tree = vec![blob("photos/trip/a.jpg"), blob("photos/trip/clip.MOV"), blob("photos/trip/notes.txt")];
+    let names: Vec<_> = album_entries(&tree, "photos/trip").iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(names, ["photos/trip/a.jpg", "photos/trip/clip.MOV"]);
+
+    assert_eq!(MediaType::of("clip.MOV"), MediaType::Video);
+    assert_eq!(MediaType::of("a.jpg"), MediaType::Photo);
+}
@@ -0,0 +1,70 @@
+//! Watcher Filter Tests
+//!
+//! Tests for:
+//! - Glob pattern matching
+//! - Default and custom ignore rules
+//! - Local to remote path mapping
+
+use std::path::Path;
+
+use crate::watcher::{glob_match, remote_path_for, should_upload};
+
+// ============================================================================
+// Glob Tests
+// ============================================================================
+
+#[test]
+fn glob_matches_wildcards() {
+    assert!(glob_match("*.jpg", "IMG_0001.jpg"));
+    assert!(glob_match("IMG_????.jpg", "IMG_0001.jpg"));
+    assert!(glob_match("*", ""));
+    assert!(glob_match("raw/*", "raw/a.jpg"));
+    assert!(glob_match("*edit*", "photo-edited.png"));
+
+    assert!(!glob_match("*.jpg", "IMG_0001.png"));
+    assert!(!glob_match("IMG_???.jpg", "IMG_0001.jpg"));
+    assert!(!glob_match("a*b", "acd"));
+}
+
+// ============================================================================
+// Ignore Rule Tests
+// ============================================================================
+
+#[test]
+fn should_upload_filters_non_images_and_defaults() {
+    let root = Path::new("/pics");
+
+    assert!(should_upload(root, Path::new("/pics/a.jpg"), &[]));
+    assert!(!should_upload(root, Path::new("/pics/notes.txt"), &[]));
+    assert!(!should_upload(root, Path::new("/pics/.hidden.jpg"), &[]));
+    assert!(!should_upload(root, Path::new("/pics/.cache/a.jpg"), &[]), "hidden folders are ignored");
+}
+
+#[test]
+fn should_upload_respects_custom_patterns() {
+    let root = Path::new("/pics");
+    let patterns = vec!["drafts".to_string(), "*_small.jpg".to_string()];
+
+    assert!(!should_upload(root, Path::new("/pics/drafts/a.jpg"), &patterns));
+    assert!(!should_upload(root, Path::new("/pics/b_small.jpg"), &patterns));
+    assert!(should_upload(root, Path::new("/pics/keep/b.jpg"), &patterns));
+}
+
+// ============================================================================
+// Remote Path Tests
+// ============================================================================
+
+#[test]
+fn remote_path_preserves_subfolders() {
+    let root = Path::new("/pics");
+
+    assert_eq!(
+        remote_path_for(root, Path::new("/pics/2024/a.jpg"), "photos/auto/", false).as_deref(),
+        Some("photos/auto/2024/a.jpg")
+    );
+    assert_eq!(
+        remote_path_for(root, Path::new("/pics/a.jpg"), "photos/auto", true).as_deref(),
+        Some("photos/auto/a.jpg.vxp")
+    );
+    assert!(remote_path_for(root, Path::new("/elsewhere/a.jpg"), "photos/auto", false).is_none());
+}
@@ -0,0 +1,6 @@
+//! Watcher Module Tests
+//!
+//! Organized by functionality:
+//! - `filter_tests` - Glob matching, ignore rules and remote path mapping
+
+pub mod filter_tests;
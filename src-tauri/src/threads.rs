@@ -0,0 +1,505 @@
+//! Secure Message Threads
+//!
+//! `upload_secure_message` stores one sealed blob per call. Threads group
+//! messages into conversations under `messages/threads/<thread_id>/`:
+//!
+//! - `index.json` lists the participants' public bundles and every message
+//!   in order (sequence number, sender key id, timestamp, size). It holds no
+//!   message content.
+//! - `<seq>.msg` is one message, encrypted separately to every participant
+//!   and signed by the sender. The sequence number is bound in as AAD, so a
+//!   message cannot be replayed at another position or into another thread.
+//!
+//! An append writes the message and the updated index in a single commit. If
+//! another device appended first, the branch update is rejected and the call
+//! fails without writing anything; the caller can simply retry.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::contacts::contact_bundle;
+use crate::crypto::{decrypt_with_aad, encrypt_with_aad, with_keypair, EncryptedPayload, KeypairHandle, PublicBundle};
+use crate::git_data::{
+    branch_head, commit_changes, create_blob, get_blob, get_tree_recursive, index_blobs, TreeChange, TreeIndex,
+};
+use crate::github::{validate_repo, AppError, GithubError, HttpClient};
+use crate::mirror::replicate_tree_changes;
+use crate::util::now_secs;
+
+pub const THREADS_ROOT: &str = "messages/threads";
+pub const THREAD_INDEX_FILE: &str = "index.json";
+const THREAD_INDEX_VERSION: u32 = 1;
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+const MAX_PARTICIPANTS: usize = 32;
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 200;
+
+/// Position of one message in a thread
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageRef {
+    pub seq: u64,
+    /// Key id of the sender's public bundle
+    pub sender: String,
+    pub sent_at: u64,
+    /// Size of the plaintext in bytes
+    pub size: u64,
+}
+
+/// Contents of `index.json`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadIndex {
+    pub version: u32,
+    pub thread_id: String,
+    pub created_at: u64,
+    pub participants: Vec<PublicBundle>,
+    pub messages: Vec<MessageRef>,
+    /// Participants' keys that have since rotated, kept to verify the
+    /// signatures of messages sent with them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retired: Vec<PublicBundle>,
+}
+
+/// A stored message: one payload per participant key id
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MessageEnvelope {
+    pub thread_id: String,
+    pub seq: u64,
+    pub sender: String,
+    pub sent_at: u64,
+    pub payloads: BTreeMap<String, EncryptedPayload>,
+    pub signature: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadSummary {
+    pub thread_id: String,
+    pub participants: Vec<String>,
+    pub message_count: usize,
+    pub created_at: u64,
+    pub last_message_at: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadMessage {
+    pub seq: u64,
+    pub sender: String,
+    pub sent_at: u64,
+    pub content: String,
+    /// Whether the sender's signature checked out against the thread index
+    pub verified: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadPage {
+    pub thread_id: String,
+    /// Oldest first
+    pub messages: Vec<ThreadMessage>,
+    /// Pass as `before_seq` to load older messages; `None` at the start
+    pub next_before: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppendResult {
+    pub thread_id: String,
+    pub seq: u64,
+    pub commit_sha: String,
+}
+
+fn new_thread_id() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+pub fn validate_thread_id(id: &str) -> Result<(), AppError> {
+    if (8..=64).contains(&id.len()) && id.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()) {
+        Ok(())
+    } else {
+        Err(AppError::Validation("Invalid thread id".into()))
+    }
+}
+
+pub fn thread_index_path(thread_id: &str) -> String {
+    format!("{}/{}/{}", THREADS_ROOT, thread_id, THREAD_INDEX_FILE)
+}
+
+/// Zero-padded so the files of a thread list in order
+pub fn message_path(thread_id: &str, seq: u64) -> String {
+    format!("{}/{}/{:010}.msg", THREADS_ROOT, thread_id, seq)
+}
+
+fn message_aad(thread_id: &str, seq: u64) -> Vec<u8> {
+    format!("vortex-thread:{}:{}", thread_id, seq).into_bytes()
+}
+
+/// Bytes covered by the sender's signature
+fn signed_bytes(thread_id: &str, seq: u64, sent_at: u64, content: &[u8]) -> Vec<u8> {
+    let mut bytes = message_aad(thread_id, seq);
+    bytes.extend_from_slice(&sent_at.to_be_bytes());
+    bytes.extend_from_slice(blake3::hash(content).as_bytes());
+    bytes
+}
+
+// ============================================================================
+// Index
+// ============================================================================
+
+impl ThreadIndex {
+    /// A new thread between `sender` and `recipients`. Duplicate bundles are
+    /// dropped; at least one other participant is required.
+    pub fn new(thread_id: &str, sender: PublicBundle, recipients: Vec<PublicBundle>) -> Result<Self, AppError> {
+        let mut participants = vec![sender];
+        for bundle in recipients {
+            if bundle.key_id.is_empty() {
+                return Err(AppError::Validation("Recipient bundle has no key id".into()));
+            }
+            if !participants.iter().any(|p| p.key_id == bundle.key_id) {
+                participants.push(bundle);
+            }
+        }
+        if participants.len() < 2 {
+            return Err(AppError::Validation("A thread needs at least one recipient".into()));
+        }
+        if participants.len() > MAX_PARTICIPANTS {
+            return Err(AppError::Validation(format!(
+                "A thread has at most {} participants",
+                MAX_PARTICIPANTS
+            )));
+        }
+        Ok(Self {
+            version: THREAD_INDEX_VERSION,
+            thread_id: thread_id.to_string(),
+            created_at: now_secs(),
+            participants,
+            messages: Vec::new(),
+            retired: Vec::new(),
+        })
+    }
+
+    pub fn participant(&self, key_id: &str) -> Option<&PublicBundle> {
+        self.participants.iter().find(|p| p.key_id == key_id)
+    }
+
+    /// Current or retired bundle of a sender, for signature checks
+    pub fn signer(&self, key_id: &str) -> Option<&PublicBundle> {
+        self.participant(key_id)
+            .or_else(|| self.retired.iter().find(|p| p.key_id == key_id))
+    }
+
+    /// Replace a participant's bundle after their keypair rotated
+    pub fn rotate_participant(&mut self, old_key_id: &str, new_bundle: PublicBundle) -> bool {
+        let Some(pos) = self.participants.iter().position(|p| p.key_id == old_key_id) else {
+            return false;
+        };
+        let old = std::mem::replace(&mut self.participants[pos], new_bundle);
+        self.retired.push(old);
+        true
+    }
+
+    pub fn next_seq(&self) -> u64 {
+        self.messages.last().map(|m| m.seq + 1).unwrap_or(1)
+    }
+
+    pub fn summary(&self) -> ThreadSummary {
+        ThreadSummary {
+            thread_id: self.thread_id.clone(),
+            participants: self.participants.iter().map(|p| p.key_id.clone()).collect(),
+            message_count: self.messages.len(),
+            created_at: self.created_at,
+            last_message_at: self.messages.last().map(|m| m.sent_at),
+        }
+    }
+}
+
+/// Up to `limit` messages before `before_seq` (or the newest ones), oldest
+/// first, plus the cursor for the next older page
+pub fn page(messages: &[MessageRef], before_seq: Option<u64>, limit: usize) -> (&[MessageRef], Option<u64>) {
+    let end = match before_seq {
+        Some(before) => messages.partition_point(|m| m.seq < before),
+        None => messages.len(),
+    };
+    let start = end.saturating_sub(limit);
+    let next = (start > 0).then(|| messages[start].seq);
+    (&messages[start..end], next)
+}
+
+// ============================================================================
+// Sealing
+// ============================================================================
+
+/// Encrypt `content` to every participant and sign it with `sender_handle`
+pub fn seal_message(
+    sender_handle: KeypairHandle,
+    index: &ThreadIndex,
+    seq: u64,
+    sent_at: u64,
+    content: &[u8],
+) -> Result<MessageEnvelope, AppError> {
+    let aad = message_aad(&index.thread_id, seq);
+    let payloads = index
+        .participants
+        .iter()
+        .map(|p| Ok((p.key_id.clone(), encrypt_with_aad(content, p, Some(&aad))?)))
+        .collect::<Result<BTreeMap<_, _>, AppError>>()?;
+
+    let (sender, signature) = with_keypair(sender_handle, |kp| {
+        let sender = kp.public_bundle().key_id;
+        let signature = kp.sign(&signed_bytes(&index.thread_id, seq, sent_at, content))?;
+        Ok((sender, signature))
+    })
+    ?;
+
+    Ok(MessageEnvelope {
+        thread_id: index.thread_id.clone(),
+        seq,
+        sender,
+        sent_at,
+        payloads,
+        signature,
+    })
+}
+
+/// Decrypt the payload addressed to `handle` and check the sender's signature
+pub fn open_message(
+    handle: KeypairHandle,
+    index: &ThreadIndex,
+    envelope: &MessageEnvelope,
+) -> Result<ThreadMessage, AppError> {
+    let aad = message_aad(&index.thread_id, envelope.seq);
+    let content = with_keypair(handle, |kp| {
+        let key_id = kp.public_bundle().key_id;
+        let payload = envelope.payloads.get(&key_id).ok_or(crate::crypto::CryptoError::KeypairNotFound)?;
+        decrypt_with_aad(payload, kp, Some(&aad))
+    })
+    ?;
+
+    let verified = envelope.thread_id == index.thread_id
+        && index.signer(&envelope.sender).is_some_and(|bundle| {
+            let signed = signed_bytes(&index.thread_id, envelope.seq, envelope.sent_at, &content);
+            bundle.verify(&signed, &envelope.signature).is_ok()
+        });
+
+    Ok(ThreadMessage {
+        seq: envelope.seq,
+        sender: envelope.sender.clone(),
+        sent_at: envelope.sent_at,
+        content: String::from_utf8(content).map_err(|_| AppError::Validation("Message is not valid UTF-8".into()))?,
+        verified,
+    })
+}
+
+/// Re-encrypt the payload addressed to the keypair behind `handle` for
+/// `new_bundle`, after that keypair rotated. Returns `false` when the
+/// envelope holds no payload for it.
+pub fn rewrap_envelope(
+    handle: KeypairHandle,
+    envelope: &mut MessageEnvelope,
+    new_bundle: &PublicBundle,
+) -> Result<bool, AppError> {
+    let aad = message_aad(&envelope.thread_id, envelope.seq);
+    let rewrapped = with_keypair(handle, |kp| {
+        let Some(payload) = envelope.payloads.get(&kp.public_bundle().key_id) else {
+            return Ok(None);
+        };
+        let content = Zeroizing::new(decrypt_with_aad(payload, kp, Some(&aad))?);
+        Ok(Some((kp.public_bundle().key_id, encrypt_with_aad(&content, new_bundle, Some(&aad))?)))
+    })
+    ?;
+
+    let Some((old_key_id, payload)) = rewrapped else {
+        return Ok(false);
+    };
+    envelope.payloads.remove(&old_key_id);
+    envelope.payloads.insert(new_bundle.key_id.clone(), payload);
+    Ok(true)
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+async fn head_index(client: &Client, repo: &str, token: &str) -> Result<(crate::git_data::BranchHead, TreeIndex), AppError> {
+    let head = branch_head(client, repo, token).await?;
+    let index = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+    Ok((head, index))
+}
+
+async fn load_thread(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    tree: &TreeIndex,
+    thread_id: &str,
+) -> Result<ThreadIndex, AppError> {
+    let entry = tree.get(&thread_index_path(thread_id)).ok_or_else(|| {
+        AppError::from(GithubError::NotFound {
+            message: format!("Thread {} not found", thread_id),
+        })
+    })?;
+    serde_json::from_slice(&get_blob(client, repo, token, &entry.sha).await?)
+        .map_err(|e| AppError::Validation(format!("Corrupt thread index: {}", e)))
+}
+
+fn own_key_id(handle: KeypairHandle) -> Result<String, AppError> {
+    with_keypair(handle, |kp| Ok(kp.public_bundle())).map(|b| b.key_id).map_err(AppError::from)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Threads in the repository, most recently active first
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn list_secure_threads(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+) -> Result<Vec<ThreadSummary>, AppError> {
+    validate_repo(&repo)?;
+    let (_, tree) = head_index(&client.0, &repo, &token).await?;
+
+    let prefix = format!("{}/", THREADS_ROOT);
+    let suffix = format!("/{}", THREAD_INDEX_FILE);
+    let mut ids: Vec<&str> = tree
+        .keys()
+        .filter_map(|p| p.strip_prefix(&prefix)?.strip_suffix(&suffix))
+        .filter(|id| validate_thread_id(id).is_ok())
+        .collect();
+    ids.sort();
+
+    let mut threads = Vec::with_capacity(ids.len());
+    for id in ids {
+        threads.push(load_thread(&client.0, &repo, &token, &tree, id).await?.summary());
+    }
+    threads.sort_by_key(|t| std::cmp::Reverse(t.last_message_at.unwrap_or(t.created_at)));
+    Ok(threads)
+}
+
+/// Append a message to a thread. Without `thread_id` a new thread is started
+/// with `recipients` and the bundles of `recipient_contacts`; the sender is
+/// always a participant.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
+pub async fn append_secure_message(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    thread_id: Option<String>,
+    recipients: Option<Vec<PublicBundle>>,
+    recipient_contacts: Option<Vec<String>>,
+    content: String,
+    keypair_handle: KeypairHandle,
+) -> Result<AppendResult, AppError> {
+    validate_repo(&repo)?;
+    if content.is_empty() {
+        return Err(AppError::Validation("Message is empty".into()));
+    }
+    if content.len() > MAX_MESSAGE_SIZE {
+        return Err(AppError::Validation(format!(
+            "Message exceeds {} bytes",
+            MAX_MESSAGE_SIZE
+        )));
+    }
+
+    let (head, tree) = head_index(&client.0, &repo, &token).await?;
+    let mut index = match thread_id {
+        Some(id) => {
+            validate_thread_id(&id)?;
+            let index = load_thread(&client.0, &repo, &token, &tree, &id).await?;
+            if index.participant(&own_key_id(keypair_handle)?).is_none() {
+                return Err(AppError::Validation("Keypair is not a participant of this thread".into()));
+            }
+            index
+        }
+        None => {
+            let sender = with_keypair(keypair_handle, |kp| Ok(kp.public_bundle()))?;
+            let mut recipients = recipients.unwrap_or_default();
+            for id in recipient_contacts.unwrap_or_default() {
+                recipients.push(contact_bundle(&id)?);
+            }
+            ThreadIndex::new(&new_thread_id(), sender, recipients)?
+        }
+    };
+
+    let seq = index.next_seq();
+    let sent_at = now_secs();
+    let envelope = seal_message(keypair_handle, &index, seq, sent_at, content.as_bytes())?;
+    index.messages.push(MessageRef {
+        seq,
+        sender: envelope.sender.clone(),
+        sent_at,
+        size: content.len() as u64,
+    });
+
+    let envelope_json = serde_json::to_vec(&envelope)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+    let index_json = serde_json::to_vec_pretty(&index)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+
+    let message_sha = create_blob(&client.0, &repo, &token, &envelope_json).await?;
+    let index_sha = create_blob(&client.0, &repo, &token, &index_json).await?;
+    let changes = vec![
+        TreeChange::blob(&message_path(&index.thread_id, seq), &message_sha),
+        TreeChange::blob(&thread_index_path(&index.thread_id), &index_sha),
+    ];
+
+    let message = format!("Append message {} to thread {}", seq, index.thread_id);
+    let commit_sha = commit_changes(&client.0, &repo, &token, &head, &changes, &message).await?;
+    replicate_tree_changes(&client.0, &repo, &token, &changes);
+
+    Ok(AppendResult {
+        thread_id: index.thread_id,
+        seq,
+        commit_sha,
+    })
+}
+
+/// One page of decrypted messages, newest page first. Pass the returned
+/// `next_before` as `before_seq` to page back through older messages.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn fetch_thread_messages(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    thread_id: String,
+    keypair_handle: KeypairHandle,
+    before_seq: Option<u64>,
+    limit: Option<usize>,
+) -> Result<ThreadPage, AppError> {
+    validate_repo(&repo)?;
+    validate_thread_id(&thread_id)?;
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let (_, tree) = head_index(&client.0, &repo, &token).await?;
+    let index = load_thread(&client.0, &repo, &token, &tree, &thread_id).await?;
+    if index.participant(&own_key_id(keypair_handle)?).is_none() {
+        return Err(AppError::Validation("Keypair is not a participant of this thread".into()));
+    }
+
+    let (refs, next_before) = page(&index.messages, before_seq, limit);
+    let mut messages = Vec::with_capacity(refs.len());
+    for r in refs {
+        let path = message_path(&thread_id, r.seq);
+        let entry = tree.get(&path).ok_or_else(|| {
+            AppError::from(GithubError::NotFound {
+                message: format!("Message {} is missing", path),
+            })
+        })?;
+        let envelope: MessageEnvelope = serde_json::from_slice(&get_blob(&client.0, &repo, &token, &entry.sha).await?)
+            .map_err(|e| AppError::Validation(format!("Corrupt message {}: {}", r.seq, e)))?;
+        if envelope.seq != r.seq {
+            return Err(AppError::Validation(format!("Message {} is out of place", r.seq)));
+        }
+        messages.push(open_message(keypair_handle, &index, &envelope)?);
+    }
+
+    Ok(ThreadPage {
+        thread_id,
+        messages,
+        next_before,
+    })
+}
@@ -0,0 +1,381 @@
+//! Thumbnail Generation and Cache
+//!
+//! `generate_thumbnail` renders a WebP thumbnail fitting a `size` × `size`
+//! square, with EXIF orientation applied, and keeps it under
+//! `<profile data>/thumbnails/`. Entries are keyed by the BLAKE3 hash of the
+//! source content and the size, so a moved or renamed photo reuses its
+//! thumbnail and an edited one gets a new one. Videos are rendered from a
+//! poster frame and keyed by their fingerprint (see `video`).
+//!
+//! The cache holds at most `MAX_CACHE_BYTES`; the least recently used
+//! thumbnails are evicted first. Use is recorded in the file modification
+//! times, so the order survives restarts.
+//!
+//! `pregenerate_thumbnails` fills the cache for an album ahead of the
+//! gallery, rendering in parallel and emitting `thumbnail-progress` events.
+//! It runs as background work (see `scheduler`), so thumbnails the gallery
+//! asks for with `generate_thumbnail` go first.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tauri::Emitter;
+
+use crate::AppHandle;
+use crate::crypto::hash_data;
+use crate::github::AppError;
+use crate::raw::{decode_preview, is_raw};
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::transcode::{decode_upright, encode_webp};
+use crate::video::{extract_poster, fingerprint, is_video_file, probe_file};
+
+const CACHE_DIR: &str = "thumbnails";
+const THUMBNAIL_EXT: &str = "webp";
+pub const DEFAULT_SIZE: u32 = 256;
+pub const MIN_SIZE: u32 = 32;
+pub const MAX_SIZE: u32 = 1024;
+const WEBP_QUALITY: u8 = 80;
+/// Disk budget of the thumbnail cache
+pub const MAX_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+lazy_static::lazy_static! {
+    /// Opened lazily for the active profile; `None` until first use
+    static ref CACHE: Mutex<Option<Arc<Mutex<ThumbnailCache>>>> = Mutex::new(None);
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Thumbnail {
+    /// Cached WebP file, for `convertFileSrc`
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: u64,
+    /// False when it was rendered by this call
+    pub cached: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ThumbnailProgress {
+    pub total: usize,
+    pub completed: usize,
+    pub current_file: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThumbnailFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThumbnailBatch {
+    pub generated: usize,
+    pub cached: usize,
+    pub failed: Vec<ThumbnailFailure>,
+}
+
+struct CacheEntry {
+    bytes: u64,
+    /// Higher is more recent
+    used: u64,
+}
+
+/// LRU cache of thumbnail files in one directory
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    entries: HashMap<String, CacheEntry>,
+    total_bytes: u64,
+    clock: u64,
+}
+
+impl ThumbnailCache {
+    /// Open the cache in `dir`, ordering what is already there by
+    /// modification time
+    pub fn open(dir: &Path, max_bytes: u64) -> Result<Self, AppError> {
+        std::fs::create_dir_all(dir)?;
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(THUMBNAIL_EXT) {
+                continue;
+            }
+            let (Some(key), Ok(metadata)) = (path.file_stem().and_then(|s| s.to_str()), path.metadata()) else {
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            found.push((modified, key.to_string(), metadata.len()));
+        }
+        found.sort();
+
+        let mut cache = Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            entries: HashMap::new(),
+            total_bytes: 0,
+            clock: 0,
+        };
+        for (_, key, bytes) in found {
+            cache.clock += 1;
+            cache.total_bytes += bytes;
+            cache.entries.insert(key, CacheEntry { bytes, used: cache.clock });
+        }
+        cache.evict(None);
+        Ok(cache)
+    }
+
+    /// Key of the thumbnail of `content` at `size`
+    pub fn key(content: &[u8], size: u32) -> String {
+        format!("{}-{}", hex::encode(hash_data(content)), size)
+    }
+
+    fn path_of(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, THUMBNAIL_EXT))
+    }
+
+    /// Path and size of a cached thumbnail, marking it as used
+    pub fn get(&mut self, key: &str) -> Option<(PathBuf, u64)> {
+        let path = self.path_of(key);
+        if !path.exists() {
+            if let Some(entry) = self.entries.remove(key) {
+                self.total_bytes -= entry.bytes;
+            }
+            return None;
+        }
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.used = self.clock;
+        // Best effort: only the order after a restart depends on it
+        let _ = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(SystemTime::now()));
+        Some((path, entry.bytes))
+    }
+
+    /// Store a thumbnail, evicting the least recently used ones past the budget
+    pub fn insert(&mut self, key: &str, data: &[u8]) -> Result<PathBuf, AppError> {
+        let path = self.path_of(key);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, &path)?;
+
+        self.clock += 1;
+        let entry = CacheEntry { bytes: data.len() as u64, used: self.clock };
+        if let Some(old) = self.entries.insert(key.to_string(), entry) {
+            self.total_bytes -= old.bytes;
+        }
+        self.total_bytes += data.len() as u64;
+        self.evict(Some(key));
+        Ok(path)
+    }
+
+    fn evict(&mut self, keep: Option<&str>) {
+        if self.total_bytes <= self.max_bytes {
+            return;
+        }
+        let mut by_age: Vec<(u64, String)> = self
+            .entries
+            .iter()
+            .filter(|(key, _)| Some(key.as_str()) != keep)
+            .map(|(key, entry)| (entry.used, key.clone()))
+            .collect();
+        by_age.sort();
+        for (_, key) in by_age {
+            if self.total_bytes <= self.max_bytes {
+                break;
+            }
+            let _ = std::fs::remove_file(self.path_of(&key));
+            if let Some(entry) = self.entries.remove(&key) {
+                self.total_bytes -= entry.bytes;
+            }
+        }
+    }
+
+    /// Remove every thumbnail, returning how many there were
+    pub fn clear(&mut self) -> Result<usize, AppError> {
+        let count = self.entries.len();
+        for key in self.entries.keys() {
+            let path = self.path_of(key);
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        self.entries.clear();
+        self.total_bytes = 0;
+        Ok(count)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+}
+
+fn check_size(size: Option<u32>) -> Result<u32, AppError> {
+    let size = size.unwrap_or(DEFAULT_SIZE);
+    if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+        return Err(AppError::Validation(format!("Thumbnail size must be {}-{}", MIN_SIZE, MAX_SIZE)));
+    }
+    Ok(size)
+}
+
+/// WebP thumbnail of `data` fitting a `size` square, never upscaled. RAW
+/// files are rendered from their embedded preview.
+pub fn render_thumbnail(data: &[u8], size: u32) -> Result<Vec<u8>, AppError> {
+    let image = if is_raw(data) { decode_preview(data)? } else { decode_upright(data)? };
+    let image = if image.width() > size || image.height() > size {
+        image.thumbnail(size, size)
+    } else {
+        image
+    };
+    encode_webp(&image, WEBP_QUALITY)
+}
+
+/// Thumbnail of `data` from `cache`, rendered on a miss. The cache is only
+/// locked for the lookup and the insert, so renders run in parallel.
+pub fn thumbnail_in(cache: &Mutex<ThumbnailCache>, data: &[u8], size: u32) -> Result<Thumbnail, AppError> {
+    cached_thumbnail(cache, &ThumbnailCache::key(data, size), || render_thumbnail(data, size))
+}
+
+/// Thumbnail of the local file at `path`. Videos are keyed by their
+/// fingerprint and rendered from a poster frame.
+pub fn thumbnail_of_file(cache: &Mutex<ThumbnailCache>, path: &Path, size: u32) -> Result<Thumbnail, AppError> {
+    if !is_video_file(path) {
+        return thumbnail_in(cache, &std::fs::read(path)?, size);
+    }
+    let key = format!("{}-{}", fingerprint(path)?, size);
+    cached_thumbnail(cache, &key, || {
+        let duration = probe_file(path).ok().and_then(|info| info.duration_secs);
+        render_thumbnail(&extract_poster(path, duration)?, size)
+    })
+}
+
+fn cached_thumbnail(
+    cache: &Mutex<ThumbnailCache>,
+    key: &str,
+    render: impl FnOnce() -> Result<Vec<u8>, AppError>,
+) -> Result<Thumbnail, AppError> {
+    let hit = cache.lock().unwrap().get(key);
+    let (path, bytes, cached) = match hit {
+        Some((path, bytes)) => (path, bytes, true),
+        None => {
+            let rendered = render()?;
+            let path = cache.lock().unwrap().insert(key, &rendered)?;
+            (path, rendered.len() as u64, false)
+        }
+    };
+    let (width, height) = image::image_dimensions(&path)
+        .map_err(|e| AppError::Validation(format!("Cannot read thumbnail: {}", e)))?;
+    Ok(Thumbnail {
+        path: path.to_string_lossy().to_string(),
+        width,
+        height,
+        bytes,
+        cached,
+    })
+}
+
+fn shared_cache() -> Result<Arc<Mutex<ThumbnailCache>>, AppError> {
+    let mut guard = CACHE.lock().unwrap();
+    if let Some(cache) = guard.as_ref() {
+        return Ok(Arc::clone(cache));
+    }
+    let dir = crate::profiles::data_dir()?.join(CACHE_DIR);
+    let cache = Arc::new(Mutex::new(ThumbnailCache::open(&dir, MAX_CACHE_BYTES)?));
+    *guard = Some(Arc::clone(&cache));
+    Ok(cache)
+}
+
+/// Reopen the cache on next use, after a profile switch
+pub(crate) fn forget_thumbnail_cache() {
+    *CACHE.lock().unwrap() = None;
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Thumbnail of the local photo or video at `path`, `size` pixels on its
+/// longer side at most (256 by default)
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn generate_thumbnail(path: String, size: Option<u32>) -> Result<Thumbnail, AppError> {
+    let size = check_size(size)?;
+    let cache = shared_cache()?;
+    let _permit = SCHEDULER.acquire(JobClass::Interactive).await;
+    tauri::async_runtime::spawn_blocking(move || thumbnail_of_file(&cache, Path::new(&path), size))
+        .await
+        .map_err(|e| AppError::Validation(format!("Thumbnail task failed: {}", e)))?
+}
+
+/// Render the thumbnails of an album's photos ahead of the gallery. Files
+/// that cannot be read or decoded are reported and skipped.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn pregenerate_thumbnails(
+    app: AppHandle,
+    paths: Vec<String>,
+    size: Option<u32>,
+) -> Result<ThumbnailBatch, AppError> {
+    let size = check_size(size)?;
+    let cache = shared_cache()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let total = paths.len();
+        let completed = AtomicUsize::new(0);
+        let results: Vec<_> = paths
+            .par_iter()
+            .map(|path| {
+                let permit = SCHEDULER.acquire_blocking(JobClass::Background);
+                let result = thumbnail_of_file(&cache, Path::new(path), size);
+                drop(permit);
+                let _ = app.emit(
+                    "thumbnail-progress",
+                    ThumbnailProgress {
+                        total,
+                        completed: completed.fetch_add(1, Ordering::Relaxed) + 1,
+                        current_file: file_name(path),
+                    },
+                );
+                (path, result)
+            })
+            .collect();
+
+        let mut batch = ThumbnailBatch::default();
+        for (path, result) in results {
+            match result {
+                Ok(thumbnail) if thumbnail.cached => batch.cached += 1,
+                Ok(_) => batch.generated += 1,
+                Err(e) => batch.failed.push(ThumbnailFailure { path: path.clone(), error: e.to_string() }),
+            }
+        }
+        batch
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Thumbnail task failed: {}", e)))
+}
+
+/// Delete every cached thumbnail, returning how many were removed
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn clear_thumbnail_cache() -> Result<usize, AppError> {
+    shared_cache()?.lock().unwrap().clear()
+}
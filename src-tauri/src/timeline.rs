@@ -0,0 +1,135 @@
+//! Photo Timeline
+//!
+//! `get_timeline` groups the catalogued photos (see `catalog`) by the day,
+//! month or year they were taken, newest first, for a scrolling timeline.
+//! Dates are the camera's local date from EXIF, so a photo taken late in
+//! the evening abroad stays on the day it was taken.
+//!
+//! Each bucket carries its photo count and a few representative photos:
+//! favorites and the best rated first, then the newest. Their thumbnails
+//! are rendered like those of any listed photo.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::catalog::CatalogPhoto;
+use crate::crypto::KeypairHandle;
+use crate::github::{AppError, PhotoItem};
+use crate::local_store::with_store;
+use crate::search::{find_photos_in, SearchQuery};
+
+pub const DEFAULT_REPRESENTATIVES: usize = 4;
+pub const MAX_REPRESENTATIVES: usize = 12;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineGranularity {
+    Day,
+    #[default]
+    Month,
+    Year,
+}
+
+impl TimelineGranularity {
+    /// Bucket of an EXIF (`YYYY:MM:DD …`) or ISO 8601 date: `2023`,
+    /// `2023-06` or `2023-06-15`
+    pub fn bucket_of(self, date: &str) -> Option<String> {
+        let date = date.trim().get(..10)?;
+        let bytes = date.as_bytes();
+        let separators = matches!((bytes[4], bytes[7]), (b':', b':') | (b'-', b'-'));
+        let digits = [&date[..4], &date[5..7], &date[8..10]]
+            .iter()
+            .all(|part| part.bytes().all(|b| b.is_ascii_digit()));
+        if !separators || !digits || &date[5..7] == "00" || &date[8..10] == "00" {
+            return None;
+        }
+        Some(match self {
+            Self::Day => format!("{}-{}-{}", &date[..4], &date[5..7], &date[8..10]),
+            Self::Month => format!("{}-{}", &date[..4], &date[5..7]),
+            Self::Year => date[..4].to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimelineBucket {
+    /// `2023`, `2023-06` or `2023-06-15`
+    pub key: String,
+    pub count: usize,
+    pub representatives: Vec<PhotoItem>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Timeline {
+    pub granularity: TimelineGranularity,
+    /// Newest first
+    pub buckets: Vec<TimelineBucket>,
+    /// Photos without a capture date
+    pub undated: usize,
+}
+
+/// Favorites first, then by rating, then newest
+fn representative_order(a: &CatalogPhoto, b: &CatalogPhoto) -> std::cmp::Ordering {
+    b.favorite
+        .cmp(&a.favorite)
+        .then(b.rating.cmp(&a.rating))
+        .then(b.taken_at.cmp(&a.taken_at))
+        .then_with(|| a.path.cmp(&b.path))
+}
+
+/// Group `photos` into buckets of `granularity`, keeping up to
+/// `per_bucket` representatives each
+pub fn timeline_of(photos: Vec<CatalogPhoto>, granularity: TimelineGranularity, per_bucket: usize) -> Timeline {
+    let mut buckets: BTreeMap<String, Vec<CatalogPhoto>> = BTreeMap::new();
+    let mut undated = 0;
+    for photo in photos {
+        let bucket = photo
+            .item
+            .exif
+            .as_ref()
+            .and_then(|e| e.taken_at.as_deref())
+            .and_then(|date| granularity.bucket_of(date));
+        match bucket {
+            Some(key) => buckets.entry(key).or_default().push(photo),
+            None => undated += 1,
+        }
+    }
+
+    let buckets = buckets
+        .into_iter()
+        .rev()
+        .map(|(key, mut photos)| {
+            photos.sort_by(representative_order);
+            TimelineBucket {
+                key,
+                count: photos.len(),
+                representatives: photos.into_iter().take(per_bucket).map(|p| p.item).collect(),
+            }
+        })
+        .collect();
+    Timeline { granularity, buckets, undated }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// The library's photos by capture date, optionally of one album and its
+/// sub-albums
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_timeline(
+    repo: String,
+    granularity: Option<TimelineGranularity>,
+    album: Option<String>,
+    per_bucket: Option<usize>,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<Timeline, AppError> {
+    let query = SearchQuery { album, ..SearchQuery::default() };
+    let photos = with_store(|store| find_photos_in(store, &repo, &query, keypair_handle.is_some()))?;
+    Ok(timeline_of(
+        photos,
+        granularity.unwrap_or_default(),
+        per_bucket.unwrap_or(DEFAULT_REPRESENTATIVES).min(MAX_REPRESENTATIVES),
+    ))
+}
@@ -0,0 +1,408 @@
+//! Time-Locked Encryption
+//!
+//! `encrypt_timelock` seals data under a random content key and wraps that
+//! key in one or both of two locks; either one opens the capsule:
+//!
+//! - Passphrase escrow: the key is encrypted with a passphrase that a
+//!   trusted party holds and hands out at `unlock_at`. `decrypt_timelock`
+//!   also refuses the escrow before that date, but only the escrow keeps the
+//!   passphrase from the reader.
+//! - Sequential work: a Rivest-Shamir-Wagner puzzle. The key is hidden under
+//!   `base^(2^t) mod N` for a fresh 2048-bit RSA modulus `N`. Knowing the
+//!   factors, the sender computes this instantly; everyone else has to do
+//!   `t` modular squarings one after another, which no amount of parallel
+//!   hardware speeds up. `t` is calibrated on the sender's machine so the
+//!   squarings take until `unlock_at`; a faster machine finishes earlier, so
+//!   the reveal time is approximate. Before working, `decrypt_timelock`
+//!   measures this machine and refuses a puzzle that would take far longer
+//!   than its lock runs, and the work runs as a job that `cancel_job` stops.
+//!
+//! Capsule layout: `[magic "VXTL"][JSON]`. The unlock time is bound to the
+//! ciphertext as AAD, so editing it breaks decryption.
+
+use num_bigint_dig::{BigUint, RandBigInt, RandPrime};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::crypto::{decrypt_with_key, decrypt_with_password, encrypt_with_key, encrypt_with_password, CryptoError};
+use crate::github::AppError;
+use crate::jobs::{Job, JobCommand};
+use crate::util::now_secs;
+
+pub const TIMELOCK_MAGIC: &[u8; 4] = b"VXTL";
+const TIMELOCK_VERSION: u8 = 1;
+const MODULUS_BITS: usize = 2048;
+/// Furthest unlock date accepted, about five years out
+const MAX_LOCK_SECS: u64 = 5 * 365 * 24 * 3600;
+const CALIBRATION_TIME: Duration = Duration::from_millis(250);
+/// Squarings between `timelock-progress` events
+const PROGRESS_INTERVAL: u64 = 1 << 16;
+/// A puzzle may take this many times as long here as its lock runs
+const WORK_SLACK: u64 = 4;
+/// Puzzles solved within this time are never refused
+const MIN_WORK_SECS: u64 = 60;
+const KEY_AAD: &[u8] = b"vortex-timelock-key";
+const PUZZLE_KDF_CONTEXT: &str = "vortex-image timelock puzzle v1";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimelockLock {
+    /// Content key encrypted with the escrow passphrase
+    Escrow { wrapped_key: Vec<u8> },
+    /// Content key encrypted under a key derived from `base^(2^squarings) mod modulus`
+    Puzzle {
+        modulus: Vec<u8>,
+        base: Vec<u8>,
+        squarings: u64,
+        wrapped_key: Vec<u8>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimelockCapsule {
+    pub version: u8,
+    /// Unix seconds
+    pub unlock_at: u64,
+    pub created_at: u64,
+    pub locks: Vec<TimelockLock>,
+    /// `[nonce: 12][ciphertext]` under the content key
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TimelockInfo {
+    pub unlock_at: u64,
+    pub created_at: u64,
+    pub escrow: bool,
+    pub squarings: Option<u64>,
+    /// Time the puzzle takes on this machine
+    pub estimated_solve_secs: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TimelockProgress {
+    pub done: u64,
+    pub total: u64,
+}
+
+fn data_aad(unlock_at: u64) -> Vec<u8> {
+    format!("vortex-timelock#{}", unlock_at).into_bytes()
+}
+
+fn puzzle_key(solution: &BigUint) -> Zeroizing<[u8; 32]> {
+    Zeroizing::new(blake3::derive_key(PUZZLE_KDF_CONTEXT, &solution.to_bytes_be()))
+}
+
+// ============================================================================
+// Puzzle
+// ============================================================================
+
+/// Modular squarings per second on this machine
+pub fn squarings_per_second(modulus_bits: usize) -> u64 {
+    let modulus = OsRng.gen_biguint(modulus_bits) | BigUint::from(1u32);
+    let mut value = OsRng.gen_biguint_below(&modulus);
+    let start = Instant::now();
+    let mut done = 0u64;
+    while start.elapsed() < CALIBRATION_TIME {
+        for _ in 0..256 {
+            value = &value * &value % &modulus;
+        }
+        done += 256;
+    }
+    (done as f64 / start.elapsed().as_secs_f64()).max(1.0) as u64
+}
+
+/// Hide `key` behind `squarings` sequential squarings modulo a fresh RSA
+/// modulus of `modulus_bits`
+pub fn puzzle_lock(key: &[u8; 32], squarings: u64, modulus_bits: usize) -> Result<TimelockLock, CryptoError> {
+    let p = OsRng.gen_prime(modulus_bits / 2);
+    let q = OsRng.gen_prime(modulus_bits / 2);
+    let modulus = &p * &q;
+    let phi = (&p - 1u32) * (&q - 1u32);
+
+    let base = OsRng.gen_biguint_range(&BigUint::from(2u32), &modulus);
+    // With the factors known, 2^t shrinks modulo phi(N)
+    let exponent = BigUint::from(2u32).modpow(&BigUint::from(squarings), &phi);
+    let solution = base.modpow(&exponent, &modulus);
+
+    Ok(TimelockLock::Puzzle {
+        modulus: modulus.to_bytes_be(),
+        base: base.to_bytes_be(),
+        squarings,
+        wrapped_key: encrypt_with_key(key, &puzzle_key(&solution), KEY_AAD)?,
+    })
+}
+
+/// Do the sequential work of a puzzle lock and recover the content key.
+/// Stops early once `cancel` is set.
+pub fn solve_puzzle(
+    lock: &TimelockLock,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    let TimelockLock::Puzzle {
+        modulus,
+        base,
+        squarings,
+        wrapped_key,
+    } = lock
+    else {
+        return Err(CryptoError::InvalidInput("not a puzzle lock".into()));
+    };
+    let modulus = BigUint::from_bytes_be(modulus);
+    if modulus.bits() < 64 {
+        return Err(CryptoError::InvalidInput("puzzle modulus is too small".into()));
+    }
+
+    let mut value = BigUint::from_bytes_be(base) % &modulus;
+    for done in 0..*squarings {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(CryptoError::InvalidInput("time lock work was cancelled".into()));
+        }
+        if done % PROGRESS_INTERVAL == 0 {
+            progress(done, *squarings);
+        }
+        value = &value * &value % &modulus;
+    }
+    progress(*squarings, *squarings);
+
+    unwrap_key(&decrypt_with_key(wrapped_key, &puzzle_key(&value), KEY_AAD)?)
+}
+
+fn unwrap_key(raw: &[u8]) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    let raw = Zeroizing::new(raw.to_vec());
+    let key: [u8; 32] = raw
+        .as_slice()
+        .try_into()
+        .map_err(|_| CryptoError::InvalidInput("wrapped key has the wrong length".into()))?;
+    Ok(Zeroizing::new(key))
+}
+
+// ============================================================================
+// Capsules
+// ============================================================================
+
+/// Seal `data` until `unlock_at`. At least one of `passphrase` (escrow) and
+/// `squarings` (sequential work) is required.
+pub fn seal(
+    data: &[u8],
+    unlock_at: u64,
+    passphrase: Option<&[u8]>,
+    squarings: Option<u64>,
+    modulus_bits: usize,
+) -> Result<TimelockCapsule, CryptoError> {
+    if passphrase.is_none() && squarings.is_none() {
+        return Err(CryptoError::InvalidInput(
+            "a time lock needs an escrow passphrase, sequential work or both".into(),
+        ));
+    }
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(key.as_mut());
+
+    let mut locks = Vec::new();
+    if let Some(passphrase) = passphrase {
+        locks.push(TimelockLock::Escrow {
+            wrapped_key: encrypt_with_password(key.as_ref(), passphrase)?,
+        });
+    }
+    if let Some(squarings) = squarings {
+        locks.push(puzzle_lock(&key, squarings, modulus_bits)?);
+    }
+
+    Ok(TimelockCapsule {
+        version: TIMELOCK_VERSION,
+        unlock_at,
+        created_at: now_secs(),
+        locks,
+        ciphertext: encrypt_with_key(data, &key, &data_aad(unlock_at))?,
+    })
+}
+
+impl TimelockCapsule {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CryptoError> {
+        let json = serde_json::to_vec(self).map_err(|e| CryptoError::InvalidInput(e.to_string()))?;
+        let mut out = Vec::with_capacity(TIMELOCK_MAGIC.len() + json.len());
+        out.extend_from_slice(TIMELOCK_MAGIC);
+        out.extend_from_slice(&json);
+        Ok(out)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CryptoError> {
+        let json = data
+            .strip_prefix(TIMELOCK_MAGIC.as_slice())
+            .ok_or_else(|| CryptoError::InvalidInput("not a time-locked capsule".into()))?;
+        let capsule: Self = serde_json::from_slice(json)
+            .map_err(|e| CryptoError::InvalidInput(format!("corrupt time-locked capsule: {}", e)))?;
+        if capsule.version != TIMELOCK_VERSION {
+            return Err(CryptoError::InvalidInput(format!(
+                "unsupported time lock version {}",
+                capsule.version
+            )));
+        }
+        Ok(capsule)
+    }
+
+    fn puzzle(&self) -> Option<&TimelockLock> {
+        self.locks.iter().find(|l| matches!(l, TimelockLock::Puzzle { .. }))
+    }
+
+    fn open_with_key(&self, key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+        decrypt_with_key(&self.ciphertext, key, &data_aad(self.unlock_at))
+    }
+
+    /// Open through the escrow lock; refused before `unlock_at`
+    pub fn open_with_passphrase(&self, passphrase: &[u8], now: u64) -> Result<Vec<u8>, CryptoError> {
+        if now < self.unlock_at {
+            return Err(CryptoError::InvalidInput(format!(
+                "capsule is locked for another {} seconds",
+                self.unlock_at - now
+            )));
+        }
+        let wrapped = self
+            .locks
+            .iter()
+            .find_map(|l| match l {
+                TimelockLock::Escrow { wrapped_key } => Some(wrapped_key),
+                _ => None,
+            })
+            .ok_or_else(|| CryptoError::InvalidInput("capsule has no passphrase escrow".into()))?;
+        let key = unwrap_key(&decrypt_with_password(wrapped, passphrase)?)?;
+        self.open_with_key(&key)
+    }
+
+    /// Open by solving the puzzle lock; see `check_work` for untrusted capsules
+    pub fn open_by_work(&self, cancel: &AtomicBool, progress: impl FnMut(u64, u64)) -> Result<Vec<u8>, CryptoError> {
+        let lock = self
+            .puzzle()
+            .ok_or_else(|| CryptoError::InvalidInput("capsule has no sequential-work lock".into()))?;
+        let key = solve_puzzle(lock, cancel, progress)?;
+        self.open_with_key(&key)
+    }
+
+    /// Refuse a puzzle that would take far longer at `squarings_per_second`
+    /// than its lock runs. The lock runs from its creation (or `now`, if
+    /// earlier) until `unlock_at`, and a sender calibrates the squarings to
+    /// that time, so a capsule opened after its unlock date still passes.
+    pub fn check_work(&self, squarings_per_second: u64, now: u64) -> Result<(), CryptoError> {
+        let Some(TimelockLock::Puzzle { squarings, .. }) = self.puzzle() else {
+            return Ok(());
+        };
+        let lock_secs = self.unlock_at.saturating_sub(self.created_at.min(now));
+        if lock_secs > MAX_LOCK_SECS {
+            return Err(CryptoError::InvalidInput("unlock time is more than five years after creation".into()));
+        }
+        let solve_secs = squarings / squarings_per_second.max(1);
+        let allowed_secs = lock_secs.saturating_mul(WORK_SLACK).max(MIN_WORK_SECS);
+        if solve_secs > allowed_secs {
+            return Err(CryptoError::InvalidInput(format!(
+                "the puzzle would take about {} seconds here, far longer than its {} second lock",
+                solve_secs, lock_secs
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn info(&self, squarings_per_second: Option<u64>) -> TimelockInfo {
+        let squarings = self.puzzle().map(|l| match l {
+            TimelockLock::Puzzle { squarings, .. } => *squarings,
+            TimelockLock::Escrow { .. } => 0,
+        });
+        TimelockInfo {
+            unlock_at: self.unlock_at,
+            created_at: self.created_at,
+            escrow: self.locks.iter().any(|l| matches!(l, TimelockLock::Escrow { .. })),
+            squarings,
+            estimated_solve_secs: squarings.zip(squarings_per_second).map(|(t, rate)| t / rate.max(1)),
+        }
+    }
+}
+
+fn blocking_error(e: tokio::task::JoinError) -> CryptoError {
+    CryptoError::InvalidInput(format!("time lock task failed: {}", e))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Seal data so it opens at `unlock_at` (Unix seconds). Sequential work is
+/// used when asked for or when no escrow passphrase is given.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn encrypt_timelock(
+    data: Vec<u8>,
+    unlock_at: u64,
+    passphrase: Option<String>,
+    sequential_work: Option<bool>,
+) -> Result<Vec<u8>, CryptoError> {
+    let now = now_secs();
+    if unlock_at <= now {
+        return Err(CryptoError::InvalidInput("unlock time must be in the future".into()));
+    }
+    if unlock_at - now > MAX_LOCK_SECS {
+        return Err(CryptoError::InvalidInput("unlock time is more than five years away".into()));
+    }
+    let passphrase = passphrase.map(Zeroizing::new);
+    if let Some(passphrase) = &passphrase {
+        crate::password::ensure_strong(passphrase)?;
+    }
+    let work = sequential_work.unwrap_or(passphrase.is_none());
+
+    tokio::task::spawn_blocking(move || {
+        let squarings = work.then(|| squarings_per_second(MODULUS_BITS).saturating_mul(unlock_at - now));
+        let passphrase = passphrase.as_ref().map(|p| p.as_bytes());
+        seal(&data, unlock_at, passphrase, squarings, MODULUS_BITS)?.to_bytes()
+    })
+    .await
+    .map_err(blocking_error)?
+}
+
+/// Open a capsule with the escrow passphrase, or without one by doing the
+/// sequential work. Runs as a cancellable job (see `jobs`) and emits
+/// `timelock-progress` while working.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn decrypt_timelock(
+    app: AppHandle,
+    data: Vec<u8>,
+    passphrase: Option<String>,
+    job_id: Option<String>,
+) -> Result<Vec<u8>, AppError> {
+    let capsule = TimelockCapsule::from_bytes(&data)?;
+    let passphrase = passphrase.map(Zeroizing::new);
+    let job = Job::announce(&app, job_id, JobCommand::DecryptTimelock)?;
+    let cancel = job.flag();
+
+    job.run_blocking(move || match passphrase {
+        Some(passphrase) => Ok(capsule.open_with_passphrase(passphrase.as_bytes(), now_secs())?),
+        None => {
+            capsule.check_work(squarings_per_second(MODULUS_BITS), now_secs())?;
+            Ok(capsule.open_by_work(&cancel, |done, total| {
+                let _ = app.emit("timelock-progress", TimelockProgress { done, total });
+            })?)
+        }
+    })
+    .await
+}
+
+/// Unlock date and locks of a capsule, with the puzzle's solve time here
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn inspect_timelock(data: Vec<u8>) -> Result<TimelockInfo, CryptoError> {
+    let capsule = TimelockCapsule::from_bytes(&data)?;
+    let rate = match capsule.puzzle() {
+        Some(_) => Some(
+            tokio::task::spawn_blocking(|| squarings_per_second(MODULUS_BITS))
+                .await
+                .map_err(blocking_error)?,
+        ),
+        None => None,
+    };
+    Ok(capsule.info(rate))
+}
@@ -0,0 +1,241 @@
+//! Image Transcoding
+//!
+//! Converts photos to AVIF, WebP or JPEG XL at a chosen quality, usually a
+//! third to a half of the JPEG size at the same visual quality. AVIF is
+//! encoded in pure Rust (rav1e through `image`), WebP with libwebp, and
+//! JPEG XL with libjxl behind the optional `jxl` feature.
+//!
+//! EXIF orientation is applied to the pixels before encoding, since the
+//! output carries no EXIF. A result that is not smaller than the input is
+//! discarded and the input kept, as with `optimize_image`.
+//!
+//! As a pipeline step the transcoded image replaces the input. Outside the
+//! pipeline, `keep_original` decides whether the source file stays next to
+//! the new one.
+
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use crate::compress_stream::write_via_partial;
+use crate::github::AppError;
+use crate::heif::{decode_heif, is_heif};
+
+const DEFAULT_QUALITY: u8 = 75;
+/// Encoder effort for AVIF, 1 (slowest) to 10; 6 is close to the best
+/// size per second
+const AVIF_SPEED: u8 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetFormat {
+    Avif,
+    Webp,
+    Jxl,
+}
+
+impl TargetFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Avif => "avif",
+            Self::Webp => "webp",
+            Self::Jxl => "jxl",
+        }
+    }
+
+    /// Typical output size relative to a camera JPEG, for estimates
+    pub fn typical_ratio(self) -> f64 {
+        match self {
+            Self::Avif => 0.35,
+            Self::Webp => 0.5,
+            Self::Jxl => 0.45,
+        }
+    }
+}
+
+fn default_quality() -> u8 {
+    DEFAULT_QUALITY
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TranscodeOptions {
+    pub format: TargetFormat,
+    /// 1 to 100, as in the JPEG quality scale
+    #[serde(default = "default_quality")]
+    pub quality: u8,
+    /// Leave the source file in place next to the transcoded one
+    #[serde(default)]
+    pub keep_original: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TranscodeResult {
+    pub format: TargetFormat,
+    pub width: u32,
+    pub height: u32,
+    pub original_size: u64,
+    pub transcoded_size: u64,
+    /// False when the input was kept because the output was not smaller
+    pub transcoded: bool,
+    /// Where the transcoded file was written, for `transcode_image`
+    pub output_path: Option<String>,
+}
+
+pub(crate) fn validate_options(options: &TranscodeOptions) -> Result<(), AppError> {
+    if !(1..=100).contains(&options.quality) {
+        return Err(AppError::Validation("Quality must be 1-100".into()));
+    }
+    if options.format == TargetFormat::Jxl && !cfg!(feature = "jxl") {
+        return Err(AppError::Validation("JPEG XL support is not included in this build".into()));
+    }
+    Ok(())
+}
+
+/// Decode `data` with its EXIF orientation applied
+pub(crate) fn decode_upright(data: &[u8]) -> Result<DynamicImage, AppError> {
+    if is_heif(data) {
+        return decode_heif(data);
+    }
+    let decode_error = |e: image::ImageError| AppError::Validation(format!("Cannot decode image: {}", e));
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(decode_error)?;
+    let orientation = decoder.orientation().map_err(decode_error)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+fn encode_avif(image: &DynamicImage, quality: u8) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    let encoder = image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut out, AVIF_SPEED, quality);
+    image
+        .write_with_encoder(encoder)
+        .map_err(|e| AppError::Validation(format!("AVIF encoding failed: {}", e)))?;
+    Ok(out)
+}
+
+pub(crate) fn encode_jpeg(image: &DynamicImage, quality: u8) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality);
+    image
+        .to_rgb8()
+        .write_with_encoder(encoder)
+        .map_err(|e| AppError::Validation(format!("JPEG encoding failed: {}", e)))?;
+    Ok(out)
+}
+
+pub(crate) fn encode_webp(image: &DynamicImage, quality: u8) -> Result<Vec<u8>, AppError> {
+    let rgba = image.to_rgba8();
+    let encoded = webp::Encoder::from_rgba(rgba.as_raw(), rgba.width(), rgba.height()).encode(quality as f32);
+    Ok(encoded.to_vec())
+}
+
+/// libjxl's mapping from a JPEG-style quality to a Butteraugli distance
+#[cfg(feature = "jxl")]
+fn jxl_distance(quality: u8) -> f32 {
+    let q = quality as f32;
+    if q >= 100.0 {
+        0.0
+    } else if q >= 30.0 {
+        0.1 + (100.0 - q) * 0.09
+    } else {
+        53.0 / 3000.0 * q * q - 23.0 / 20.0 * q + 25.0
+    }
+}
+
+#[cfg(feature = "jxl")]
+fn encode_jxl(image: &DynamicImage, quality: u8) -> Result<Vec<u8>, AppError> {
+    let rgba = image.to_rgba8();
+    let mut encoder = jpegxl_rs::encoder_builder()
+        .has_alpha(true)
+        .quality(jxl_distance(quality))
+        .build()
+        .map_err(|e| AppError::Validation(format!("JPEG XL encoder unavailable: {}", e)))?;
+    let encoded: jpegxl_rs::encode::EncoderResult<u8> = encoder
+        .encode::<u8, u8>(rgba.as_raw(), rgba.width(), rgba.height())
+        .map_err(|e| AppError::Validation(format!("JPEG XL encoding failed: {}", e)))?;
+    Ok(encoded.data)
+}
+
+#[cfg(not(feature = "jxl"))]
+fn encode_jxl(_image: &DynamicImage, _quality: u8) -> Result<Vec<u8>, AppError> {
+    Err(AppError::Validation("JPEG XL support is not included in this build".into()))
+}
+
+/// Transcode `data`, returning the bytes to keep and what happened. Fails
+/// only if the input cannot be decoded or the encoder fails.
+pub fn transcode_image_data(data: &[u8], options: &TranscodeOptions) -> Result<(Vec<u8>, TranscodeResult), AppError> {
+    validate_options(options)?;
+    let image = decode_upright(data)?;
+    let encoded = match options.format {
+        TargetFormat::Avif => encode_avif(&image, options.quality)?,
+        TargetFormat::Webp => encode_webp(&image, options.quality)?,
+        TargetFormat::Jxl => encode_jxl(&image, options.quality)?,
+    };
+
+    let transcoded = encoded.len() < data.len();
+    let output = if transcoded { encoded } else { data.to_vec() };
+    let result = TranscodeResult {
+        format: options.format,
+        width: image.width(),
+        height: image.height(),
+        original_size: data.len() as u64,
+        transcoded_size: output.len() as u64,
+        transcoded,
+        output_path: None,
+    };
+    Ok((output, result))
+}
+
+/// `input` with its extension replaced by the target format's
+pub fn transcoded_path(input: &Path, format: TargetFormat) -> PathBuf {
+    input.with_extension(format.extension())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Transcode the image at `input_path` into `output_path`, or next to the
+/// input with the new extension. The input is removed afterwards unless
+/// `keep_original` is set or the output was not smaller.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn transcode_image(
+    input_path: String,
+    output_path: Option<String>,
+    options: TranscodeOptions,
+) -> Result<TranscodeResult, AppError> {
+    validate_options(&options)?;
+
+    tokio::task::spawn_blocking(move || {
+        let input = PathBuf::from(&input_path);
+        let data = std::fs::read(&input)?;
+        let (output, mut result) = transcode_image_data(&data, &options)?;
+        if !result.transcoded {
+            return Ok(result);
+        }
+
+        let target = output_path
+            .map(PathBuf::from)
+            .unwrap_or_else(|| transcoded_path(&input, options.format));
+        if target == input {
+            return Err(AppError::Validation("Output would overwrite the original".into()));
+        }
+        write_via_partial(&target, |mut writer| {
+            writer.write_all(&output)?;
+            writer.flush()?;
+            Ok(())
+        })?;
+        if !options.keep_original {
+            std::fs::remove_file(&input)?;
+        }
+        result.output_path = Some(target.to_string_lossy().to_string());
+        Ok(result)
+    })
+    .await
+    .map_err(|e| AppError::Validation(format!("Transcoding task failed: {}", e)))?
+}
@@ -0,0 +1,357 @@
+//! Upload Deduplication
+//!
+//! Uploading the same folder again should not send the same photos again.
+//! Plain album manifests keep a hash index (`AlbumManifest::hashes`, hex
+//! BLAKE3 of the content -> path below the album), and
+//! `upload_folder_to_album` hashes every local photo first (see `file_hash`)
+//! so only content the index does not know is transferred:
+//!
+//! - content already in the album under the same path is skipped,
+//! - under another path it is skipped too, or with `rename` the remote file
+//!   is moved to the local path, which changes a tree entry and sends no data,
+//! - everything else is uploaded.
+//!
+//! Uploads, renames and the updated index land in one commit. With a
+//! keypair, each upload is signed and its `.vxsig` is added to the same
+//! commit (see `security_verify`); renamed photos take their `.vxsig` along.
+//! Index entries whose file is gone (deleted or moved since) are dropped
+//! before planning, so a stale index never hides a missing photo. The report
+//! counts skipped and transferred bytes.
+//!
+//! Encrypted albums are refused: their blobs are already named by a keyed
+//! hash of the content, so `upload_encrypted_photo` turns duplicates away.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::activity_log::{record_activity, ActivityKind};
+use crate::album::{manifest_body, manifest_for_update, ALBUM_MANIFEST_FILE};
+use crate::crypto::KeypairHandle;
+use crate::file_hash::hash_file;
+use crate::git_data::{branch_head, commit_changes, create_blob, get_tree_recursive, index_blobs, TreeChange};
+use crate::github::{
+    collect_images_recursive, is_image_file, sanitize_filename, validate_repo, AppError, HttpClient, ImageFile,
+};
+use crate::jobs::{Job, JobCommand};
+use crate::mirror::replicate_tree_changes;
+use crate::scheduler::{JobClass, SCHEDULER};
+use crate::security_verify::{move_signature, sign_photo, signature_path};
+
+/// A local photo with its content hash
+#[derive(Clone, Debug)]
+pub struct LocalPhoto {
+    pub source: String,
+    /// Path below the album, `/`-separated
+    pub path: String,
+    pub hash: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DedupAction {
+    Upload,
+    /// The content is already in the album at `existing`
+    Skip { existing: String },
+    /// Move the remote file holding the content from `from` to the local path
+    Rename { from: String },
+}
+
+#[derive(Clone, Debug)]
+pub struct DedupPlan {
+    /// One per local photo, in order
+    pub actions: Vec<DedupAction>,
+    /// The hash index once the plan is applied
+    pub index: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedPhoto {
+    pub path: String,
+    pub existing: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenamedPhoto {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DedupReport {
+    pub uploaded: Vec<String>,
+    pub skipped: Vec<SkippedPhoto>,
+    pub renamed: Vec<RenamedPhoto>,
+    pub transferred_bytes: u64,
+    /// Bytes of skipped and renamed photos, which were not sent
+    pub skipped_bytes: u64,
+    pub commit_sha: Option<String>,
+}
+
+/// `relative` as a path below the album: `/`-separated, each part sanitized
+pub fn album_relative_path(relative: &str) -> String {
+    relative
+        .split(['/', '\\'])
+        .map(sanitize_filename)
+        .filter(|part| !part.is_empty() && part != "." && part != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Decide per photo whether to upload, skip or rename it. `remote` holds the
+/// photo paths now in the album; index entries pointing elsewhere are stale
+/// and dropped.
+pub fn plan_dedup(
+    photos: &[LocalPhoto],
+    index: &BTreeMap<String, String>,
+    remote: &BTreeSet<String>,
+    rename: bool,
+) -> DedupPlan {
+    let mut index: BTreeMap<String, String> =
+        index.iter().filter(|(_, path)| remote.contains(*path)).map(|(h, p)| (h.clone(), p.clone())).collect();
+    let mut present = remote.clone();
+    let mut actions = Vec::with_capacity(photos.len());
+
+    for photo in photos {
+        let action = match index.get(&photo.hash).cloned() {
+            Some(existing) if existing == photo.path => DedupAction::Skip { existing },
+            // Only files already in the album move, and never onto a path
+            // that holds something else
+            Some(existing) if rename && remote.contains(&existing) && !present.contains(&photo.path) => {
+                present.remove(&existing);
+                present.insert(photo.path.clone());
+                index.insert(photo.hash.clone(), photo.path.clone());
+                DedupAction::Rename { from: existing }
+            }
+            Some(existing) => DedupAction::Skip { existing },
+            None => {
+                // An upload replacing other content takes its path over
+                index.retain(|_, path| path != &photo.path);
+                index.insert(photo.hash.clone(), photo.path.clone());
+                present.insert(photo.path.clone());
+                DedupAction::Upload
+            }
+        };
+        actions.push(action);
+    }
+    DedupPlan { actions, index }
+}
+
+/// What `plan` will do with `photos`, before anything is sent
+pub fn dedup_report(photos: &[LocalPhoto], plan: &DedupPlan) -> DedupReport {
+    let mut report = DedupReport::default();
+    for (photo, action) in photos.iter().zip(&plan.actions) {
+        match action {
+            DedupAction::Upload => {
+                report.uploaded.push(photo.path.clone());
+                report.transferred_bytes += photo.size;
+            }
+            DedupAction::Skip { existing } => {
+                report.skipped.push(SkippedPhoto {
+                    path: photo.path.clone(),
+                    existing: existing.clone(),
+                });
+                report.skipped_bytes += photo.size;
+            }
+            DedupAction::Rename { from } => {
+                report.renamed.push(RenamedPhoto {
+                    from: from.clone(),
+                    to: photo.path.clone(),
+                });
+                report.skipped_bytes += photo.size;
+            }
+        }
+    }
+    report
+}
+
+/// Name under which the album manifest lists a photo as signed; photos in
+/// sub-folders belong to the sub-album's manifest and are not listed
+fn listed_name(path: &str) -> Option<&str> {
+    (!path.contains('/')).then_some(path)
+}
+
+/// Hash `images`, checking `cancel` between files
+pub fn hash_photos(images: &[ImageFile], cancel: &AtomicBool) -> Result<Vec<LocalPhoto>, AppError> {
+    let mut photos = Vec::with_capacity(images.len());
+    for image in images {
+        let path = album_relative_path(&image.relative_path);
+        if path.is_empty() {
+            continue;
+        }
+        let hashed = hash_file(Path::new(&image.path), None, cancel, |_| {})?;
+        photos.push(LocalPhoto {
+            source: image.path.clone(),
+            path,
+            hash: hashed.hash,
+            size: hashed.size,
+        });
+    }
+    Ok(photos)
+}
+
+/// Upload what `photos` adds to the album at `album`, in one commit with
+/// the updated hash index
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn upload_deduplicated(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    album: &str,
+    photos: &[LocalPhoto],
+    rename: bool,
+    signer: Option<KeypairHandle>,
+    job: &Job,
+) -> Result<DedupReport, AppError> {
+    let (mut manifest, _) = manifest_for_update(client, repo, token, album).await?;
+    if manifest.encrypted {
+        return Err(AppError::Validation(
+            "Encrypted albums already refuse duplicate photos; upload them with upload_encrypted_photo".into(),
+        ));
+    }
+
+    let head = branch_head(client, repo, token).await?;
+    let tree = index_blobs(get_tree_recursive(client, repo, token, &head.tree_sha).await?);
+    let prefix = format!("{}/", album);
+    let remote: BTreeSet<String> = tree
+        .keys()
+        .filter_map(|path| path.strip_prefix(&prefix))
+        .filter(|rest| is_image_file(Path::new(rest)))
+        .map(str::to_string)
+        .collect();
+
+    let plan = plan_dedup(photos, &manifest.hashes, &remote, rename);
+    let mut report = dedup_report(photos, &plan);
+
+    let mut changes = Vec::new();
+    for (photo, action) in photos.iter().zip(&plan.actions) {
+        job.check()?;
+        let target = format!("{}{}", prefix, photo.path);
+        match action {
+            DedupAction::Upload => {
+                let _permit = SCHEDULER.acquire(JobClass::Sync).await;
+                let content = tokio::fs::read(&photo.source).await?;
+                let sha = create_blob(client, repo, token, &content).await?;
+                changes.push(TreeChange::blob(&target, &sha));
+                if let Some(name) = listed_name(&photo.path) {
+                    manifest.signed.remove(name);
+                }
+                match signer {
+                    Some(handle) => {
+                        let signature = create_blob(client, repo, token, &sign_photo(handle, &content)?).await?;
+                        changes.push(TreeChange::blob(&signature_path(&target), &signature));
+                        if let Some(name) = listed_name(&photo.path) {
+                            manifest.record_signed(name, &content);
+                        }
+                    }
+                    // The signature of replaced content would no longer match
+                    None if tree.contains_key(&signature_path(&target)) => {
+                        changes.push(TreeChange::delete(&signature_path(&target)));
+                    }
+                    None => {}
+                }
+            }
+            DedupAction::Rename { from } => {
+                let source = format!("{}{}", prefix, from);
+                if let Some(entry) = tree.get(&source) {
+                    changes.push(TreeChange::delete(&source));
+                    changes.push(TreeChange::put_blob(&target, entry));
+                    changes.extend(move_signature(&tree, &source, &target));
+                    if let Some(hash) = listed_name(from).and_then(|name| manifest.signed.remove(name)) {
+                        if let Some(name) = listed_name(&photo.path) {
+                            manifest.signed.insert(name.to_string(), hash);
+                        }
+                    }
+                }
+            }
+            DedupAction::Skip { .. } => {}
+        }
+    }
+
+    if changes.is_empty() && plan.index == manifest.hashes {
+        return Ok(report);
+    }
+    job.check()?;
+
+    manifest.hashes = plan.index;
+    let manifest_sha = create_blob(client, repo, token, &manifest_body(&mut manifest, signer)?).await?;
+    changes.push(TreeChange::blob(&format!("{}{}", prefix, ALBUM_MANIFEST_FILE), &manifest_sha));
+
+    let message = format!(
+        "Upload {} photo{} to {}, {} already there",
+        report.uploaded.len(),
+        if report.uploaded.len() == 1 { "" } else { "s" },
+        album,
+        report.skipped.len() + report.renamed.len()
+    );
+    report.commit_sha = Some(commit_changes(client, repo, token, &head, &changes, &message).await?);
+    replicate_tree_changes(client, repo, token, &changes);
+
+    let detail = format!(
+        "{} uploaded, {} skipped, {} renamed, {} bytes not sent",
+        report.uploaded.len(),
+        report.skipped.len(),
+        report.renamed.len(),
+        report.skipped_bytes
+    );
+    record_activity(ActivityKind::Upload, "upload_folder_to_album", Some(repo), album, Some(detail));
+    Ok(report)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Upload the photos of a local folder into an album, skipping content the
+/// album already holds. With `rename`, photos already uploaded under another
+/// path are moved to their local path instead. Runs as a job (see `jobs`).
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_folder_to_album(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    path: String,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    rename: Option<bool>,
+    keypair_handle: Option<KeypairHandle>,
+    job_id: Option<String>,
+) -> Result<DedupReport, AppError> {
+    validate_repo(&repo)?;
+    let album = album_path.trim_matches('/').to_string();
+    if album.is_empty() || album.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+    let folder = Path::new(&path);
+    if !folder.is_dir() {
+        return Err(AppError::Validation("Invalid folder path".into()));
+    }
+
+    let job = Job::announce(&app, job_id, JobCommand::UploadFolder)?;
+    let images = collect_images_recursive(folder, folder, &|p| is_image_file(p)).await?;
+    let cancel = job.flag();
+    let photos = tokio::task::spawn_blocking(move || hash_photos(&images, &cancel))
+        .await
+        .map_err(|e| AppError::Validation(format!("Hash task failed: {}", e)))?;
+    let photos = job.outcome(photos)?;
+
+    let result = upload_deduplicated(
+        &client.0,
+        &repo,
+        &token,
+        &album,
+        &photos,
+        rename.unwrap_or(false),
+        keypair_handle,
+        &job,
+    )
+    .await;
+    job.outcome(result)
+}
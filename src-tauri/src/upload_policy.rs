@@ -0,0 +1,372 @@
+//! Pre-Upload Validation Policy
+//!
+//! A single, user-configurable policy checked before any photo upload:
+//! maximum file size, allowed extensions, minimum resolution, and whether
+//! embedded metadata must be stripped or the upload must be encrypted.
+//! `upload_photo` and the folder uploads call `check_upload` on the bytes
+//! they are about to send; `validate_upload` runs the same checks ahead of
+//! time and returns a verdict per file.
+//!
+//! The policy lives in `<profile data>/upload_policy.json`. The
+//! default policy allows everything, so existing uploads are unaffected until
+//! the user opts in.
+
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::github::AppError;
+use crate::heif::{heif_dimensions, is_heif};
+
+const POLICY_FILE: &str = "upload_policy.json";
+
+lazy_static::lazy_static! {
+    /// Loaded lazily from disk; `None` until first use
+    static ref POLICY: Mutex<Option<UploadPolicy>> = Mutex::new(None);
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadPolicy {
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    /// Lowercase extensions without the dot; empty allows any
+    #[serde(default)]
+    pub allowed_extensions: Vec<String>,
+    #[serde(default)]
+    pub min_width: Option<u32>,
+    #[serde(default)]
+    pub min_height: Option<u32>,
+    /// Reject files carrying EXIF/XMP/IPTC or text metadata
+    #[serde(default)]
+    pub require_metadata_stripped: bool,
+    #[serde(default)]
+    pub require_encryption: bool,
+}
+
+/// How the caller is going to upload the file
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct UploadIntent {
+    #[serde(default)]
+    pub encrypted: bool,
+    #[serde(default)]
+    pub strips_metadata: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    MaxFileSize,
+    AllowedExtensions,
+    MinResolution,
+    MetadataStripped,
+    Encryption,
+    Readable,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    pub rule: PolicyRule,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UploadVerdict {
+    pub path: String,
+    pub allowed: bool,
+    pub violations: Vec<PolicyViolation>,
+}
+
+impl UploadPolicy {
+    fn validate(&self) -> Result<(), AppError> {
+        if self.max_file_size == Some(0) {
+            return Err(AppError::Validation("max_file_size must be positive".into()));
+        }
+        if self
+            .allowed_extensions
+            .iter()
+            .any(|e| e.is_empty() || !e.chars().all(|c| c.is_ascii_alphanumeric()))
+        {
+            return Err(AppError::Validation("Extensions must be alphanumeric, without the dot".into()));
+        }
+        Ok(())
+    }
+
+    fn normalized(mut self) -> Self {
+        for ext in &mut self.allowed_extensions {
+            *ext = ext.trim_start_matches('.').to_lowercase();
+        }
+        self.allowed_extensions.sort();
+        self.allowed_extensions.dedup();
+        self
+    }
+
+    fn needs_dimensions(&self) -> bool {
+        self.min_width.is_some() || self.min_height.is_some()
+    }
+}
+
+// ============================================================================
+// Inspection
+// ============================================================================
+
+/// Whether an image carries embedded metadata (EXIF, XMP, IPTC or text chunks).
+/// Only JPEG, PNG and WebP are inspected; other formats report `false`.
+pub fn has_embedded_metadata(data: &[u8]) -> bool {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        jpeg_has_metadata(data)
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_has_metadata(data)
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        webp_has_metadata(data)
+    } else {
+        false
+    }
+}
+
+fn jpeg_has_metadata(data: &[u8]) -> bool {
+    let mut i = 2;
+    while i + 4 <= data.len() && data[i] == 0xFF {
+        let marker = data[i + 1];
+        // Start of scan: no more header segments
+        if marker == 0xDA {
+            break;
+        }
+        let len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        let body = &data[(i + 4).min(data.len())..(i + 2 + len).min(data.len())];
+        match marker {
+            0xE1 if body.starts_with(b"Exif\0") || body.starts_with(b"http://ns.adobe.com/xap/") => return true,
+            // APP13 carries Photoshop/IPTC records, COM is a free-text comment
+            0xED | 0xFE => return true,
+            _ => {}
+        }
+        i += 2 + len;
+    }
+    false
+}
+
+fn png_has_metadata(data: &[u8]) -> bool {
+    let mut i = 8;
+    while i + 8 <= data.len() {
+        let len = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        let kind = &data[i + 4..i + 8];
+        if matches!(kind, b"eXIf" | b"tEXt" | b"iTXt" | b"zTXt") {
+            return true;
+        }
+        if kind == b"IEND" {
+            break;
+        }
+        i += 12 + len;
+    }
+    false
+}
+
+fn webp_has_metadata(data: &[u8]) -> bool {
+    let mut i = 12;
+    while i + 8 <= data.len() {
+        let kind = &data[i..i + 4];
+        if kind == b"EXIF" || kind == b"XMP " {
+            return true;
+        }
+        let len = u32::from_le_bytes([data[i + 4], data[i + 5], data[i + 6], data[i + 7]]) as usize;
+        // Chunks are padded to an even size
+        i += 8 + len + (len & 1);
+    }
+    false
+}
+
+/// Width and height read from the image header, if the format is supported
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if is_heif(data) {
+        return heif_dimensions(data);
+    }
+    image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+fn extension(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+/// Check one file against `policy`. `data` is needed for resolution and
+/// metadata rules; without it those rules report the file as unreadable.
+pub fn evaluate(
+    policy: &UploadPolicy,
+    name: &str,
+    size: u64,
+    data: Option<&[u8]>,
+    intent: UploadIntent,
+) -> Vec<PolicyViolation> {
+    let mut violations = Vec::new();
+    let mut violate = |rule, message: String| violations.push(PolicyViolation { rule, message });
+
+    if let Some(max) = policy.max_file_size {
+        if size > max {
+            violate(PolicyRule::MaxFileSize, format!("File is {} bytes; the limit is {}", size, max));
+        }
+    }
+
+    let ext = extension(name);
+    if !policy.allowed_extensions.is_empty() && !policy.allowed_extensions.contains(&ext) {
+        violate(
+            PolicyRule::AllowedExtensions,
+            format!("Extension .{} is not allowed", if ext.is_empty() { "(none)" } else { &ext }),
+        );
+    }
+
+    if policy.require_encryption && !intent.encrypted {
+        violate(PolicyRule::Encryption, "Uploads must be encrypted".into());
+    }
+
+    let needs_data = policy.needs_dimensions() || (policy.require_metadata_stripped && !intent.strips_metadata);
+    match data {
+        None if needs_data => violate(PolicyRule::Readable, "File could not be read".into()),
+        None => {}
+        Some(data) => {
+            if policy.needs_dimensions() {
+                match image_dimensions(data) {
+                    Some((w, h)) => {
+                        let min_w = policy.min_width.unwrap_or(0);
+                        let min_h = policy.min_height.unwrap_or(0);
+                        if w < min_w || h < min_h {
+                            violate(
+                                PolicyRule::MinResolution,
+                                format!("Image is {}x{}; the minimum is {}x{}", w, h, min_w, min_h),
+                            );
+                        }
+                    }
+                    None => violate(PolicyRule::MinResolution, "Image resolution could not be determined".into()),
+                }
+            }
+            if policy.require_metadata_stripped && !intent.strips_metadata && has_embedded_metadata(data) {
+                violate(PolicyRule::MetadataStripped, "Embedded metadata must be stripped before upload".into());
+            }
+        }
+    }
+
+    violations
+}
+
+// ============================================================================
+// Persistence
+// ============================================================================
+
+fn policy_file() -> Result<PathBuf, AppError> {
+    Ok(crate::profiles::data_dir()?.join(POLICY_FILE))
+}
+
+pub(crate) fn current_policy() -> Result<UploadPolicy, AppError> {
+    let mut guard = POLICY.lock().unwrap();
+    if let Some(policy) = guard.as_ref() {
+        return Ok(policy.clone());
+    }
+    let path = policy_file()?;
+    let policy = if path.exists() {
+        serde_json::from_slice(&std::fs::read(&path)?)
+            .map_err(|e| AppError::Validation(format!("Corrupt upload policy: {}", e)))?
+    } else {
+        UploadPolicy::default()
+    };
+    *guard = Some(policy.clone());
+    Ok(policy)
+}
+
+/// Reload the policy from disk on next use, after a profile switch
+pub(crate) fn forget_cached_policy() {
+    *POLICY.lock().unwrap() = None;
+}
+
+/// Enforce the current policy on bytes about to be uploaded
+pub(crate) fn check_upload(name: &str, data: &[u8], intent: UploadIntent) -> Result<(), AppError> {
+    let policy = current_policy()?;
+    reject(name, evaluate(&policy, name, data.len() as u64, Some(data), intent))
+}
+
+/// Enforce the rules that need no content (size, extension, encryption),
+/// for videos and other files streamed from disk rather than held in memory
+pub(crate) fn check_upload_size(name: &str, size: u64, intent: UploadIntent) -> Result<(), AppError> {
+    let policy = UploadPolicy {
+        min_width: None,
+        min_height: None,
+        require_metadata_stripped: false,
+        ..current_policy()?
+    };
+    reject(name, evaluate(&policy, name, size, None, intent))
+}
+
+fn reject(name: &str, violations: Vec<PolicyViolation>) -> Result<(), AppError> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let reasons: Vec<String> = violations.into_iter().map(|v| v.message).collect();
+    Err(AppError::Validation(format!(
+        "{} rejected by upload policy: {}",
+        name,
+        reasons.join("; ")
+    )))
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_upload_policy() -> Result<UploadPolicy, AppError> {
+    current_policy()
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn set_upload_policy(policy: UploadPolicy) -> Result<UploadPolicy, AppError> {
+    policy.validate()?;
+    let policy = policy.normalized();
+    let json = serde_json::to_vec_pretty(&policy)
+        .map_err(|e| AppError::Validation(format!("Serialization failed: {}", e)))?;
+    let path = policy_file()?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, &path)?;
+    *POLICY.lock().unwrap() = Some(policy.clone());
+    Ok(policy)
+}
+
+/// Check local files against the policy without uploading them
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn validate_upload(paths: Vec<String>, intent: Option<UploadIntent>) -> Result<Vec<UploadVerdict>, AppError> {
+    let policy = current_policy()?;
+    let intent = intent.unwrap_or_default();
+    let needs_data = policy.needs_dimensions() || policy.require_metadata_stripped;
+
+    let mut verdicts = Vec::with_capacity(paths.len());
+    for path in paths {
+        let violations = match tokio::fs::metadata(&path).await {
+            Ok(meta) if meta.is_file() => {
+                let data = if needs_data { tokio::fs::read(&path).await.ok() } else { None };
+                evaluate(&policy, &path, meta.len(), data.as_deref(), intent)
+            }
+            _ => vec![PolicyViolation {
+                rule: PolicyRule::Readable,
+                message: "File not found".into(),
+            }],
+        };
+        verdicts.push(UploadVerdict {
+            allowed: violations.is_empty(),
+            path,
+            violations,
+        });
+    }
+    Ok(verdicts)
+}
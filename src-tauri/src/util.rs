@@ -0,0 +1,16 @@
+//! Shared Helpers
+//!
+//! Small functions used across modules.
+
+/// Current Unix time in seconds; 0 if the clock is set before 1970
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// For `#[serde(default = "default_true")]` on flags that default to on
+pub(crate) fn default_true() -> bool {
+    true
+}
@@ -0,0 +1,663 @@
+//! Video Support
+//!
+//! Albums can hold videos next to photos. `probe` reads the container, video
+//! codec, duration and frame size from MP4/MOV, Matroska/WebM, AVI and
+//! MPEG-TS headers without decoding anything; MPEG-TS is only recognized.
+//! Poster frames need a real decoder: `extract_poster` runs ffmpeg, which
+//! desktop bundles ship as a Tauri sidecar (`scripts/fetch-ffmpeg.sh` puts
+//! it in `binaries/`); the CLI and mobile builds use the one on PATH. Thumbnails of
+//! videos are keyed by `fingerprint` rather than a hash of the whole file.
+//!
+//! `upload_video` sends files over the contents API limit as LFS objects
+//! when the repository tracks them, or in parts (see `chunks`) otherwise.
+//! Each video is recorded in its album manifest's `media` map, so listings
+//! know its duration and codec without downloading it. Chunked videos get
+//! no detached signature, as it would only cover the index.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use tauri::{Emitter, State};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::album::{fetch_manifest, save_manifest};
+use crate::chunks::put_chunked_file;
+use crate::crypto::KeypairHandle;
+use crate::github::{
+    sanitize_filename, upload_content, validate_repo, AppError, HttpClient, UploadProgress, UploadResult,
+    LFS_THRESHOLD_BYTES,
+};
+use crate::lfs::{lfs_tracks, put_lfs_file};
+use crate::security_verify::{put_photo_signature, sign_photo};
+use crate::upload_policy::{check_upload_size, UploadIntent};
+
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v", "avi", "mkv", "webm", "3gp", "mts", "m2ts"];
+/// Matroska, AVI and MPEG-TS headers are looked for in this much of the file
+const HEADER_SCAN_BYTES: u64 = 4 * 1024 * 1024;
+/// Larger `moov` boxes are not read
+const MAX_MOOV_BYTES: u64 = 64 * 1024 * 1024;
+/// Bytes hashed from each end of a file by `fingerprint`
+const FINGERPRINT_SPAN: u64 = 1024 * 1024;
+/// Posters are taken at a tenth of the duration, but no later than this
+const MAX_POSTER_OFFSET_SECS: f64 = 3.0;
+const TS_PACKET: usize = 188;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    #[default]
+    Photo,
+    Video,
+}
+
+impl MediaType {
+    /// Media type of a file by its extension
+    pub fn of(name: &str) -> Self {
+        if is_video_file(Path::new(name)) {
+            Self::Video
+        } else {
+            Self::Photo
+        }
+    }
+}
+
+pub fn is_video_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct VideoInfo {
+    /// mp4, mov, matroska, webm, avi or mpegts
+    pub container: String,
+    /// h264, hevc, av1, vp9, vp8, mpeg4, mjpeg, prores, or the identifier
+    /// found in the file
+    pub codec: Option<String>,
+    pub duration_secs: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// What an album manifest records about a video
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MediaEntry {
+    pub media_type: MediaType,
+    pub container: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    pub size: u64,
+}
+
+impl MediaEntry {
+    pub fn video(info: VideoInfo, size: u64) -> Self {
+        Self {
+            media_type: MediaType::Video,
+            container: info.container,
+            codec: info.codec,
+            duration_secs: info.duration_secs,
+            width: info.width,
+            height: info.height,
+            size,
+        }
+    }
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
+
+fn be_u64(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(at..at.checked_add(8)?)?.try_into().ok()?))
+}
+
+fn le_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
+
+/// Common name of a codec identifier from any of the containers
+fn codec_name(id: &str) -> Option<String> {
+    let id = id.trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_lowercase();
+    let name = match id.as_str() {
+        "" => return None,
+        "avc1" | "avc3" | "h264" | "x264" | "v_mpeg4/iso/avc" => "h264",
+        "hvc1" | "hev1" | "hevc" | "h265" | "v_mpegh/iso/hevc" => "hevc",
+        "av01" | "v_av1" => "av1",
+        "vp09" | "v_vp9" => "vp9",
+        "vp08" | "v_vp8" => "vp8",
+        "mp4v" | "xvid" | "divx" | "dx50" | "fmp4" | "v_mpeg4/iso/asp" => "mpeg4",
+        "mjpg" | "jpeg" | "mjpa" | "mjpb" | "v_mjpeg" => "mjpeg",
+        s if s.starts_with("apc") || s.starts_with("ap4") => "prores",
+        _ => return Some(id),
+    };
+    Some(name.to_string())
+}
+
+// ============================================================================
+// MP4 / MOV
+// ============================================================================
+
+/// Child boxes of an ISO BMFF body, as (type, body)
+struct Boxes<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Iterator for Boxes<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.data;
+        let at = self.at;
+        let (header, size) = match be_u32(data, at)? {
+            0 => (8, (data.len() - at) as u64),
+            1 => (16, be_u64(data, at + 8)?),
+            size => (8, size as u64),
+        };
+        if size < header as u64 {
+            return None;
+        }
+        let end = at.checked_add(usize::try_from(size).ok()?)?.min(data.len());
+        self.at = end;
+        Some((data.get(at + 4..at + 8)?, data.get(at + header..end)?))
+    }
+}
+
+fn boxes(data: &[u8]) -> Boxes<'_> {
+    Boxes { data, at: 0 }
+}
+
+fn child<'a>(data: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    boxes(data).find(|(k, _)| *k == kind).map(|(_, body)| body)
+}
+
+/// Duration from `mvhd`
+fn mvhd_duration(mvhd: &[u8]) -> Option<f64> {
+    let (timescale, duration) = match *mvhd.first()? {
+        1 => (be_u32(mvhd, 20)?, be_u64(mvhd, 24)?),
+        _ => (be_u32(mvhd, 12)?, be_u32(mvhd, 16)? as u64),
+    };
+    (timescale > 0).then(|| duration as f64 / timescale as f64)
+}
+
+/// Codec and display size of the first video track in `moov`
+fn video_track(moov: &[u8]) -> Option<(Option<String>, Option<u32>, Option<u32>)> {
+    boxes(moov).filter(|(k, _)| *k == b"trak").find_map(|(_, trak)| {
+        let mdia = child(trak, b"mdia")?;
+        if child(mdia, b"hdlr")?.get(8..12)? != b"vide" {
+            return None;
+        }
+        let stsd = [&b"minf"[..], &b"stbl"[..], &b"stsd"[..]]
+            .iter()
+            .try_fold(mdia, |body, kind| child(body, kind));
+        let codec = stsd
+            .and_then(|stsd| stsd.get(12..16))
+            .and_then(|fourcc| codec_name(&String::from_utf8_lossy(fourcc)));
+
+        // 16.16 fixed point, at the end of `tkhd`
+        let tkhd = child(trak, b"tkhd");
+        let size_at = if tkhd.and_then(|t| t.first()) == Some(&1) { 88 } else { 76 };
+        let side = |at: usize| tkhd.and_then(|t| be_u32(t, at)).map(|v| v >> 16).filter(|v| *v > 0);
+        Some((codec, side(size_at), side(size_at + 4)))
+    })
+}
+
+fn probe_isobmff<R: Read + Seek>(reader: &mut R, len: u64) -> Result<VideoInfo, AppError> {
+    let mut container = "mov";
+    let mut moov = None;
+    let mut at = 0u64;
+    while at + 8 <= len {
+        reader.seek(SeekFrom::Start(at))?;
+        let mut header = [0u8; 16];
+        reader.read_exact(&mut header[..8])?;
+        let (header_len, size) = match be_u32(&header, 0).unwrap_or(0) {
+            0 => (8, len - at),
+            1 => {
+                reader.read_exact(&mut header[8..])?;
+                (16, be_u64(&header, 8).unwrap_or(0))
+            }
+            size => (8, size as u64),
+        };
+        if size < header_len {
+            break;
+        }
+        let body_len = size.min(len - at) - header_len;
+        match &header[4..8] {
+            b"ftyp" => {
+                let mut brand = [0u8; 4];
+                reader.read_exact(&mut brand)?;
+                container = if &brand == b"qt  " { "mov" } else { "mp4" };
+            }
+            b"moov" if body_len <= MAX_MOOV_BYTES => {
+                let mut body = vec![0u8; body_len as usize];
+                reader.read_exact(&mut body)?;
+                moov = Some(body);
+                break;
+            }
+            _ => {}
+        }
+        at += size;
+    }
+
+    let moov = moov.ok_or_else(|| AppError::Validation("Video has no readable movie header".into()))?;
+    let (codec, width, height) = video_track(&moov).unwrap_or_default();
+    Ok(VideoInfo {
+        container: container.to_string(),
+        codec,
+        duration_secs: child(&moov, b"mvhd").and_then(mvhd_duration),
+        width,
+        height,
+    })
+}
+
+// ============================================================================
+// Matroska / WebM
+// ============================================================================
+
+const EBML_HEADER: u32 = 0x1A45DFA3;
+const EBML_DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x18538067;
+const INFO: u32 = 0x1549A966;
+const TIMECODE_SCALE: u32 = 0x2AD7B1;
+const DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const CLUSTER: u32 = 0x1F43B675;
+
+/// Variable-length integer at `at`, as (value with the marker kept, value
+/// without it, length)
+fn vint(data: &[u8], at: usize) -> Option<(u64, u64, usize)> {
+    let first = *data.get(at)?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let raw = data
+        .get(at..at + len)?
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | *b as u64);
+    let value = raw & (u64::MAX >> (64 - 7 * len));
+    Some((raw, value, len))
+}
+
+/// Child elements of an EBML body, as (ID, body). Bodies running past the
+/// data, or of unknown size, end with it.
+struct Elements<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, _, id_len) = vint(self.data, self.at)?;
+        let (_, size, size_len) = vint(self.data, self.at + id_len)?;
+        let start = self.at + id_len + size_len;
+        let unknown = size == u64::MAX >> (64 - 7 * size_len);
+        let end = match usize::try_from(size) {
+            Ok(size) if !unknown => start.checked_add(size)?.min(self.data.len()),
+            _ => self.data.len(),
+        };
+        self.at = end;
+        Some((u32::try_from(id).ok()?, self.data.get(start..end)?))
+    }
+}
+
+fn elements(data: &[u8]) -> Elements<'_> {
+    Elements { data, at: 0 }
+}
+
+fn ebml_uint(body: &[u8]) -> Option<u64> {
+    (body.len() <= 8).then(|| body.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+fn ebml_float(body: &[u8]) -> Option<f64> {
+    match body.len() {
+        4 => Some(f32::from_be_bytes(body.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(body.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+fn probe_matroska(head: &[u8]) -> Result<VideoInfo, AppError> {
+    let mut info = VideoInfo { container: "matroska".into(), ..Default::default() };
+    for (id, body) in elements(head) {
+        match id {
+            EBML_HEADER => {
+                let doc_type = elements(body).find(|(id, _)| *id == EBML_DOC_TYPE);
+                if doc_type.is_some_and(|(_, value)| value == b"webm") {
+                    info.container = "webm".into();
+                }
+            }
+            SEGMENT => {
+                for (id, body) in elements(body) {
+                    match id {
+                        INFO => {
+                            let mut scale = 1_000_000u64;
+                            let mut duration = None;
+                            for (id, value) in elements(body) {
+                                match id {
+                                    TIMECODE_SCALE => scale = ebml_uint(value).unwrap_or(scale),
+                                    DURATION => duration = ebml_float(value),
+                                    _ => {}
+                                }
+                            }
+                            info.duration_secs = duration.map(|d| d * scale as f64 / 1e9);
+                        }
+                        TRACKS => {
+                            if let Some((codec, width, height)) = matroska_video_track(body) {
+                                info.codec = codec;
+                                info.width = width;
+                                info.height = height;
+                            }
+                        }
+                        CLUSTER => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+fn matroska_video_track(tracks: &[u8]) -> Option<(Option<String>, Option<u32>, Option<u32>)> {
+    elements(tracks).filter(|(id, _)| *id == TRACK_ENTRY).find_map(|(_, entry)| {
+        let field = |wanted: u32| elements(entry).find(|(id, _)| *id == wanted).map(|(_, body)| body);
+        if field(TRACK_TYPE).and_then(ebml_uint) != Some(1) {
+            return None;
+        }
+        let codec = field(CODEC_ID).and_then(|id| codec_name(&String::from_utf8_lossy(id)));
+        let video = field(VIDEO).unwrap_or_default();
+        let side = |wanted: u32| {
+            elements(video)
+                .find(|(id, _)| *id == wanted)
+                .and_then(|(_, body)| ebml_uint(body))
+                .and_then(|v| u32::try_from(v).ok())
+        };
+        Some((codec, side(PIXEL_WIDTH), side(PIXEL_HEIGHT)))
+    })
+}
+
+// ============================================================================
+// AVI and MPEG-TS
+// ============================================================================
+
+/// Body of the first RIFF chunk `kind` in `data` accepted by `wanted`
+fn riff_chunk<'a>(data: &'a [u8], kind: &[u8], wanted: impl Fn(&[u8]) -> bool) -> Option<&'a [u8]> {
+    data.windows(4)
+        .enumerate()
+        .filter(|(_, w)| *w == kind)
+        .filter_map(|(at, _)| {
+            let size = le_u32(data, at + 4)? as usize;
+            data.get(at + 8..(at + 8).checked_add(size)?.min(data.len()))
+        })
+        .find(|body| wanted(body))
+}
+
+fn probe_avi(head: &[u8]) -> Result<VideoInfo, AppError> {
+    let mut info = VideoInfo { container: "avi".into(), ..Default::default() };
+    if let Some(avih) = riff_chunk(head, b"avih", |body| body.len() >= 40) {
+        let (frame_us, frames) = (le_u32(avih, 0).unwrap_or(0), le_u32(avih, 16).unwrap_or(0));
+        if frame_us > 0 && frames > 0 {
+            info.duration_secs = Some(frames as f64 * frame_us as f64 / 1e6);
+        }
+        info.width = le_u32(avih, 32).filter(|v| *v > 0);
+        info.height = le_u32(avih, 36).filter(|v| *v > 0);
+    }
+    if let Some(strh) = riff_chunk(head, b"strh", |body| body.get(0..4) == Some(&b"vids"[..])) {
+        info.codec = strh.get(4..8).and_then(|fourcc| codec_name(&String::from_utf8_lossy(fourcc)));
+    }
+    Ok(info)
+}
+
+/// MPEG-TS, with plain 188-byte packets or the 192-byte packets of M2TS
+fn is_mpegts(head: &[u8]) -> bool {
+    [0, 4].iter().any(|offset| {
+        (0..3).all(|packet| head.get(offset + packet * (TS_PACKET + offset)) == Some(&0x47))
+    })
+}
+
+// ============================================================================
+// Probing
+// ============================================================================
+
+/// Read container, codec, duration and size from the headers of a video
+pub fn probe<R: Read + Seek>(reader: &mut R) -> Result<VideoInfo, AppError> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+    let mut head = Vec::new();
+    reader.by_ref().take(HEADER_SCAN_BYTES).read_to_end(&mut head)?;
+
+    let iso_box = head.get(4..8).is_some_and(|kind| {
+        [&b"ftyp"[..], &b"moov"[..], &b"mdat"[..], &b"free"[..], &b"wide"[..], &b"skip"[..]].contains(&kind)
+    });
+    if iso_box {
+        probe_isobmff(reader, len)
+    } else if be_u32(&head, 0) == Some(EBML_HEADER) {
+        probe_matroska(&head)
+    } else if head.starts_with(b"RIFF") && head.get(8..12) == Some(&b"AVI "[..]) {
+        probe_avi(&head)
+    } else if is_mpegts(&head) {
+        Ok(VideoInfo { container: "mpegts".into(), ..Default::default() })
+    } else {
+        Err(AppError::Validation("Not a supported video container".into()))
+    }
+}
+
+pub fn probe_file(path: &Path) -> Result<VideoInfo, AppError> {
+    probe(&mut std::fs::File::open(path)?)
+}
+
+/// BLAKE3 over the length and both ends of the file at `path`. Videos are
+/// too large to hash whole each time a thumbnail is looked up.
+pub fn fingerprint(path: &Path) -> Result<String, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"video");
+    hasher.update(&len.to_le_bytes());
+
+    let mut span = Vec::new();
+    file.by_ref().take(FINGERPRINT_SPAN).read_to_end(&mut span)?;
+    hasher.update(&span);
+    span.clear();
+    file.seek(SeekFrom::Start(len.saturating_sub(FINGERPRINT_SPAN)))?;
+    file.read_to_end(&mut span)?;
+    hasher.update(&span);
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+// ============================================================================
+// Poster Frames
+// ============================================================================
+
+static FFMPEG_SIDECAR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use the ffmpeg sidecar the shell plugin resolved at startup
+pub fn set_ffmpeg_sidecar(path: PathBuf) {
+    let _ = FFMPEG_SIDECAR.set(path);
+}
+
+/// The ffmpeg sidecar when the bundle has one, else ffmpeg on PATH
+fn ffmpeg_path() -> PathBuf {
+    FFMPEG_SIDECAR
+        .get()
+        .filter(|path| path.is_file())
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(if cfg!(windows) { "ffmpeg.exe" } else { "ffmpeg" }))
+}
+
+/// PNG of a frame near the start of the video at `path`, past any fade-in
+pub fn extract_poster(path: &Path, duration_secs: Option<f64>) -> Result<Vec<u8>, AppError> {
+    let at = duration_secs.map_or(0.0, |d| (d * 0.1).min(MAX_POSTER_OFFSET_SECS));
+    let output = Command::new(ffmpeg_path())
+        .args(["-v", "error", "-nostdin", "-ss", &format!("{:.3}", at), "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "-"])
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::Validation("Video decoder (ffmpeg) is not available".into()),
+            _ => AppError::Io(e),
+        })?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(AppError::Validation(format!(
+            "Cannot extract a poster frame: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+// ============================================================================
+// Uploads
+// ============================================================================
+
+/// Upload the video at `local_path` to `upload_path` and describe it for
+/// the album manifest. Small files go through the contents API; larger
+/// ones through LFS when the repository tracks them, else in parts, with
+/// `on_progress` getting the bytes sent after each part.
+pub(crate) async fn put_video(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    upload_path: &str,
+    local_path: &Path,
+    signer: Option<KeypairHandle>,
+    on_progress: impl Fn(u64),
+) -> Result<(UploadResult, MediaEntry), AppError> {
+    let size = tokio::fs::metadata(local_path).await?.len();
+    let name = upload_path.rsplit('/').next().unwrap_or(upload_path);
+    check_upload_size(name, size, UploadIntent::default())?;
+
+    let path = local_path.to_path_buf();
+    let info = tauri::async_runtime::spawn_blocking(move || probe_file(&path))
+        .await
+        .map_err(|e| AppError::Validation(format!("Probe task failed: {}", e)))??;
+
+    let message = format!("Upload {}", upload_path);
+    let result = if size <= LFS_THRESHOLD_BYTES {
+        let content = tokio::fs::read(local_path).await?;
+        upload_content(client, &content, repo, token, upload_path, signer).await?
+    } else if lfs_tracks(client, repo, token, name).await? {
+        let content = tokio::fs::read(local_path).await?;
+        let signature = signer.map(|h| sign_photo(h, &content)).transpose()?;
+        let result = put_lfs_file(client, repo, token, upload_path, content, &message).await?;
+        if let Some(signature) = signature {
+            put_photo_signature(client, repo, token, upload_path, &signature).await?;
+        }
+        result
+    } else {
+        put_chunked_file(client, repo, token, upload_path, local_path, &message, &on_progress).await?
+    };
+    on_progress(size);
+    Ok((result, MediaEntry::video(info, size)))
+}
+
+/// Add `entries` (upload path, entry) to the manifests of the albums
+/// holding them. Folders without a manifest are left alone.
+pub(crate) async fn record_media(
+    client: &Client,
+    repo: &str,
+    token: &str,
+    entries: Vec<(String, MediaEntry)>,
+    signer: Option<KeypairHandle>,
+) -> Result<(), AppError> {
+    let mut by_album: BTreeMap<String, Vec<(String, MediaEntry)>> = BTreeMap::new();
+    for (upload_path, entry) in entries {
+        if let Some((album, name)) = upload_path.rsplit_once('/') {
+            by_album.entry(album.to_string()).or_default().push((name.to_string(), entry));
+        }
+    }
+    for (album, entries) in by_album {
+        if let Some((mut manifest, sha)) = fetch_manifest(client, repo, token, &album).await? {
+            manifest.media.extend(entries);
+            save_manifest(client, repo, token, &album, &mut manifest, Some(&sha), signer).await?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Container, codec, duration and size of the local video at `path`
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn get_video_info(path: String) -> Result<VideoInfo, AppError> {
+    tauri::async_runtime::spawn_blocking(move || probe_file(Path::new(&path)))
+        .await
+        .map_err(|e| AppError::Validation(format!("Probe task failed: {}", e)))?
+}
+
+/// Upload the video at `path` into the album at `album_path`, emitting
+/// `upload-progress` events under `upload_id`. Encrypted albums are
+/// refused, since videos are stored as they are.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_video(
+    app: AppHandle,
+    client: State<'_, HttpClient>,
+    path: String,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+    upload_id: String,
+    keypair_handle: Option<KeypairHandle>,
+) -> Result<UploadResult, AppError> {
+    validate_repo(&repo)?;
+    let local_path = PathBuf::from(&path);
+    if !local_path.is_file() || !is_video_file(&local_path) {
+        return Err(AppError::Validation("Not a video file".into()));
+    }
+    let name = local_path
+        .file_name()
+        .map(|n| sanitize_filename(&n.to_string_lossy()))
+        .unwrap_or_default();
+    if name.is_empty() {
+        return Err(AppError::Validation("Invalid filename".into()));
+    }
+
+    let album_path = album_path.trim_matches('/');
+    if let Some((manifest, _)) = fetch_manifest(&client.0, &repo, &token, album_path).await? {
+        if manifest.encrypted {
+            return Err(AppError::Validation("Videos cannot be added to encrypted albums".into()));
+        }
+    }
+
+    let upload_path = format!("{}/{}", album_path, name);
+    let total_bytes = tokio::fs::metadata(&local_path).await?.len();
+    let progress = |sent: u64| {
+        let _ = app.emit("upload-progress", UploadProgress {
+            id: upload_id.clone(),
+            bytes_sent: sent,
+            total_bytes,
+            percent: (sent * 100 / total_bytes.max(1)) as u8,
+        });
+    };
+    let (result, entry) =
+        put_video(&client.0, &repo, &token, &upload_path, &local_path, keypair_handle, progress).await?;
+    record_media(&client.0, &repo, &token, vec![(upload_path, entry)], keypair_handle).await?;
+    Ok(result)
+}
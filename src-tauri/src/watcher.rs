@@ -0,0 +1,435 @@
+//! Folder Watcher for Continuous Auto-Upload
+//!
+//! `watch_folder` binds a local directory to an album. File system events are
+//! collected per path and only acted on once a path has been quiet for the
+//! debounce window, so partially written files and editor save bursts result
+//! in a single upload. Files matching an ignore pattern or without an image
+//! extension are skipped.
+//!
+//! When a pipeline is configured (inline or by preset id) each file is run
+//! through it and stored as `<name>.vxp`; otherwise the raw file is uploaded.
+//! Uploads that fail for network reasons go to the offline queue, expecting
+//! the remote SHA the watch last saw for the path, so replay reports a
+//! conflict instead of overwriting a file changed elsewhere in the meantime.
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use zeroize::Zeroizing;
+
+use crate::AppHandle;
+use crate::github::{is_image_file, put_file_contents, sanitize_filename, validate_repo, AppError, HttpClient};
+use crate::offline_queue::{enqueue_upload_bytes, remote_sha};
+use crate::pipeline::{get_preset_pipelines, process_pipeline_for_file, PipelineConfig, PipelineContext, PIPELINE_FILE_EXT};
+use crate::pipeline_history::{record_run, PipelineRun};
+use crate::util::now_secs;
+
+const DEFAULT_DEBOUNCE_MS: u64 = 2000;
+const MIN_DEBOUNCE_MS: u64 = 250;
+const POLL_INTERVAL_MS: u64 = 250;
+/// Temporary and hidden files written by cameras, editors and sync tools
+const DEFAULT_IGNORE_PATTERNS: &[&str] = &[".*", "*.tmp", "*.part", "*.crdownload", "*~", "Thumbs.db"];
+
+lazy_static::lazy_static! {
+    static ref WATCHES: Mutex<HashMap<String, ActiveWatch>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchConfig {
+    pub local_dir: String,
+    pub repo: String,
+    pub album_path: String,
+    #[serde(default)]
+    pub recursive: bool,
+    /// Preset from `pipeline_get_presets`; ignored when `pipeline` is set
+    #[serde(default)]
+    pub preset_id: Option<String>,
+    #[serde(default)]
+    pub pipeline: Option<PipelineConfig>,
+    /// Glob patterns (`*`, `?`) matched against file names and relative paths
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    #[serde(default)]
+    pub debounce_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchInfo {
+    pub id: String,
+    pub config: WatchConfig,
+    pub started_at: u64,
+    pub uploaded: u64,
+    pub failed: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct WatchUploadEvent {
+    watch_id: String,
+    local_path: String,
+    remote_path: Option<String>,
+    queued: bool,
+    error: Option<String>,
+}
+
+struct ActiveWatch {
+    info: Arc<Mutex<WatchInfo>>,
+    stop: Arc<AtomicBool>,
+    // Dropping the watcher unsubscribes from file system events
+    _watcher: RecommendedWatcher,
+}
+
+// ============================================================================
+// Filtering
+// ============================================================================
+
+/// Minimal glob matcher supporting `*` (any run) and `?` (any one character)
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// Whether a changed file should be uploaded
+pub fn should_upload(root: &Path, path: &Path, ignore_patterns: &[String]) -> bool {
+    if !is_image_file(path) {
+        return false;
+    }
+
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let relative_str = relative.to_string_lossy().replace('\\', "/");
+
+    let ignored = |pattern: &str| {
+        glob_match(pattern, &relative_str)
+            || relative
+                .components()
+                .any(|c| glob_match(pattern, &c.as_os_str().to_string_lossy()))
+    };
+
+    !DEFAULT_IGNORE_PATTERNS.iter().any(|p| ignored(p)) && !ignore_patterns.iter().any(|p| ignored(p))
+}
+
+/// Remote path for a watched file, preserving sub-folders when recursive
+pub fn remote_path_for(root: &Path, path: &Path, album_path: &str, processed: bool) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut segments: Vec<String> = relative
+        .components()
+        .map(|c| sanitize_filename(&c.as_os_str().to_string_lossy()))
+        .collect();
+
+    if segments.is_empty() || segments.iter().any(|s| s.is_empty()) {
+        return None;
+    }
+
+    if processed {
+        let last = segments.pop()?;
+        segments.push(format!("{}.{}", last, PIPELINE_FILE_EXT));
+    }
+
+    Some(format!("{}/{}", album_path.trim_matches('/'), segments.join("/")))
+}
+
+fn resolve_pipeline(config: &WatchConfig) -> Result<Option<PipelineConfig>, AppError> {
+    if let Some(pipeline) = &config.pipeline {
+        return Ok(Some(pipeline.clone()));
+    }
+    match &config.preset_id {
+        Some(id) => get_preset_pipelines()
+            .into_iter()
+            .find(|p| &p.id == id)
+            .map(Some)
+            .ok_or_else(|| AppError::Validation(format!("Unknown pipeline preset: {}", id))),
+        None => Ok(None),
+    }
+}
+
+// ============================================================================
+// Upload Loop
+// ============================================================================
+
+struct UploadJob {
+    app: AppHandle,
+    watch_id: String,
+    root: PathBuf,
+    config: WatchConfig,
+    pipeline: Option<PipelineConfig>,
+    passwords: HashMap<String, String>,
+    token: Zeroizing<String>,
+    /// Remote SHA of each path as last seen, `None` when it was absent
+    known_shas: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl UploadJob {
+    async fn upload(&self, path: &Path) -> WatchUploadEvent {
+        let mut event = WatchUploadEvent {
+            watch_id: self.watch_id.clone(),
+            local_path: path.to_string_lossy().to_string(),
+            remote_path: None,
+            queued: false,
+            error: None,
+        };
+
+        let Some(remote_path) = remote_path_for(&self.root, path, &self.config.album_path, self.pipeline.is_some())
+        else {
+            event.error = Some("Unsupported file name".into());
+            return event;
+        };
+        event.remote_path = Some(remote_path.clone());
+
+        let content = match self.prepare(path).await {
+            Ok(content) => content,
+            Err(e) => {
+                event.error = Some(e.to_string());
+                return event;
+            }
+        };
+
+        let client = self.app.state::<HttpClient>().0.clone();
+        let repo = &self.config.repo;
+        let mut known = self.known_shas.lock().unwrap().get(&remote_path).cloned();
+        let result = async {
+            let sha = remote_sha(&client, repo, &self.token, &remote_path).await?;
+            known = Some(sha.clone());
+            let message = format!("Auto-upload {}", remote_path);
+            put_file_contents(&client, repo, &self.token, &remote_path, &content, &message, sha.as_deref()).await
+        }
+        .await;
+
+        match result {
+            Ok(uploaded) => {
+                self.known_shas.lock().unwrap().insert(remote_path, Some(uploaded.sha));
+            }
+            Err(AppError::Network(_) | AppError::Unavailable(_)) => {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("photo");
+                // A path never seen is expected to be absent
+                let expected = known.flatten();
+                match enqueue_upload_bytes(repo, &self.token, &content, name, &remote_path, expected) {
+                    Ok(_) => event.queued = true,
+                    Err(e) => event.error = Some(e.to_string()),
+                }
+            }
+            Err(e) => event.error = Some(e.to_string()),
+        }
+
+        event
+    }
+
+    async fn prepare(&self, path: &Path) -> Result<Vec<u8>, AppError> {
+        let data = tokio::fs::read(path).await?;
+        match &self.pipeline {
+            Some(pipeline) => {
+                let context = PipelineContext {
+                    passwords: self.passwords.clone(),
+                    keypair: None,
+                };
+                let name = path.file_name().and_then(|n| n.to_str());
+                let started = Instant::now();
+                let result = process_pipeline_for_file(&data, name, pipeline, &context);
+                record_run(&PipelineRun::single(pipeline, data.len(), &result, started.elapsed()));
+                result
+                    .map(|r| r.data)
+                    .map_err(|e| AppError::Validation(e.to_string()))
+            }
+            None => Ok(data),
+        }
+    }
+}
+
+fn spawn_upload_loop(
+    job: UploadJob,
+    pending: Arc<Mutex<HashMap<PathBuf, Instant>>>,
+    info: Arc<Mutex<WatchInfo>>,
+    stop: Arc<AtomicBool>,
+    debounce: Duration,
+) {
+    tauri::async_runtime::spawn(async move {
+        while !stop.load(Ordering::Relaxed) {
+            tokio::time::sleep(Duration::from_millis(POLL_INTERVAL_MS)).await;
+
+            let ready: Vec<PathBuf> = {
+                let mut pending = pending.lock().unwrap();
+                let ready: Vec<PathBuf> = pending
+                    .iter()
+                    .filter(|(_, last)| last.elapsed() >= debounce)
+                    .map(|(p, _)| p.clone())
+                    .collect();
+                for path in &ready {
+                    pending.remove(path);
+                }
+                ready
+            };
+
+            for path in ready {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                if !path.is_file() {
+                    continue;
+                }
+
+                let event = job.upload(&path).await;
+                {
+                    let mut info = info.lock().unwrap();
+                    if event.error.is_some() {
+                        info.failed += 1;
+                    } else {
+                        info.uploaded += 1;
+                    }
+                }
+                let _ = job.app.emit("watch-upload", event);
+            }
+        }
+    });
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Start watching a folder. Returns the watch id used by `stop_watch`.
+/// `passwords` supplies keys for password layers of the pipeline and is kept in memory only.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn watch_folder(
+    app: AppHandle,
+    config: WatchConfig,
+    token: Zeroizing<String>,
+    passwords: Option<HashMap<String, String>>,
+) -> Result<String, AppError> {
+    validate_repo(&config.repo)?;
+
+    let root = PathBuf::from(&config.local_dir);
+    if !root.is_dir() {
+        return Err(AppError::Validation("Watch path is not a directory".into()));
+    }
+    let root = root.canonicalize()?;
+
+    if config.album_path.trim_matches('/').is_empty() || config.album_path.contains("..") {
+        return Err(AppError::Validation("Invalid album path".into()));
+    }
+
+    if WATCHES.lock().unwrap().values().any(|w| {
+        let info = w.info.lock().unwrap();
+        Path::new(&info.config.local_dir) == root
+    }) {
+        return Err(AppError::Validation("Folder is already being watched".into()));
+    }
+
+    let pipeline = resolve_pipeline(&config)?;
+    let debounce = Duration::from_millis(config.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS).max(MIN_DEBOUNCE_MS));
+    let mut id_bytes = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut id_bytes);
+    let id = format!("watch-{}", hex::encode(id_bytes));
+
+    let mut config = config;
+    config.local_dir = root.to_string_lossy().to_string();
+
+    let pending: Arc<Mutex<HashMap<PathBuf, Instant>>> = Arc::new(Mutex::new(HashMap::new()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let info = Arc::new(Mutex::new(WatchInfo {
+        id: id.clone(),
+        config: config.clone(),
+        started_at: now_secs(),
+        uploaded: 0,
+        failed: 0,
+    }));
+
+    let event_root = root.clone();
+    let event_pending = pending.clone();
+    let ignore_patterns = config.ignore_patterns.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else { return };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        let mut pending = event_pending.lock().unwrap();
+        for path in event.paths {
+            if should_upload(&event_root, &path, &ignore_patterns) {
+                pending.insert(path, Instant::now());
+            }
+        }
+    })
+    .map_err(|e| AppError::Validation(format!("Failed to start watcher: {}", e)))?;
+
+    let mode = if config.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+    watcher
+        .watch(&root, mode)
+        .map_err(|e| AppError::Validation(format!("Failed to watch folder: {}", e)))?;
+
+    let job = UploadJob {
+        app,
+        watch_id: id.clone(),
+        root,
+        config,
+        pipeline,
+        passwords: passwords.unwrap_or_default(),
+        token,
+        known_shas: Mutex::new(HashMap::new()),
+    };
+    spawn_upload_loop(job, pending, info.clone(), stop.clone(), debounce);
+
+    WATCHES.lock().unwrap().insert(
+        id.clone(),
+        ActiveWatch {
+            info,
+            stop,
+            _watcher: watcher,
+        },
+    );
+
+    Ok(id)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn stop_watch(watch_id: String) -> Result<(), AppError> {
+    let watch = WATCHES
+        .lock()
+        .unwrap()
+        .remove(&watch_id)
+        .ok_or_else(|| AppError::Validation("Unknown watch id".into()))?;
+    watch.stop.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop every folder watch, after a profile switch
+pub(crate) fn stop_all_watches() {
+    for (_, watch) in WATCHES.lock().unwrap().drain() {
+        watch.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all)]
+pub fn list_watches() -> Vec<WatchInfo> {
+    WATCHES
+        .lock()
+        .unwrap()
+        .values()
+        .map(|w| w.info.lock().unwrap().clone())
+        .collect()
+}
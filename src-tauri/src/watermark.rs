@@ -0,0 +1,268 @@
+//! Watermarks
+//!
+//! Composites a PNG or a line of text onto photos. The mark is scaled to a
+//! fraction of the photo's width, placed in a corner or the center with a
+//! small margin, and blended at the given opacity. Text is drawn in white
+//! with a soft shadow so it stays readable on light skies as well as dark
+//! backgrounds; it uses `font_path` or the first common system font found.
+//!
+//! Like resizing, the watermarked image replaces the input. PNG and WebP
+//! stay in their format; everything else becomes JPEG.
+
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::github::AppError;
+use crate::resize::{encode, format_name, output_format};
+use crate::transcode::decode_upright;
+
+const DEFAULT_QUALITY: u8 = 90;
+/// Longest text accepted, in characters
+pub const MAX_TEXT_LEN: usize = 200;
+/// Height text is rendered at before it is scaled to the photo
+const TEXT_RENDER_PX: f32 = 256.0;
+/// Margin from the edges, relative to the photo's shorter side
+const MARGIN: f32 = 0.02;
+
+/// Tried in order when no `font_path` is given
+const SYSTEM_FONTS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+fn default_opacity() -> f32 {
+    0.5
+}
+
+fn default_scale() -> f32 {
+    0.2
+}
+
+fn default_quality() -> u8 {
+    DEFAULT_QUALITY
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WatermarkOptions {
+    /// PNG to composite; exactly one of `image_path` and `text` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// TrueType/OpenType font for `text`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_path: Option<String>,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// 0 (invisible, not accepted) to 1
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Width of the mark relative to the photo's width, up to 1
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    /// 1 to 100, for JPEG and WebP output
+    #[serde(default = "default_quality")]
+    pub quality: u8,
+}
+
+impl WatermarkOptions {
+    pub fn text(text: &str) -> Self {
+        Self {
+            image_path: None,
+            text: Some(text.to_string()),
+            font_path: None,
+            position: WatermarkPosition::default(),
+            opacity: default_opacity(),
+            scale: default_scale(),
+            quality: DEFAULT_QUALITY,
+        }
+    }
+
+    pub fn image(path: &str) -> Self {
+        Self {
+            image_path: Some(path.to_string()),
+            text: None,
+            ..Self::text("")
+        }
+    }
+}
+
+pub(crate) fn validate_options(options: &WatermarkOptions) -> Result<(), AppError> {
+    match (&options.image_path, &options.text) {
+        (Some(path), None) if !path.is_empty() => {}
+        (None, Some(text)) if !text.trim().is_empty() => {
+            if text.chars().count() > MAX_TEXT_LEN {
+                return Err(AppError::Validation(format!(
+                    "Watermark text is limited to {} characters", MAX_TEXT_LEN
+                )));
+            }
+        }
+        _ => return Err(AppError::Validation("Watermark needs either an image or text".into())),
+    }
+    if !(options.opacity > 0.0 && options.opacity <= 1.0) {
+        return Err(AppError::Validation("Opacity must be above 0 and at most 1".into()));
+    }
+    if !(options.scale > 0.0 && options.scale <= 1.0) {
+        return Err(AppError::Validation("Scale must be above 0 and at most 1".into()));
+    }
+    if !(1..=100).contains(&options.quality) {
+        return Err(AppError::Validation("Quality must be 1-100".into()));
+    }
+    Ok(())
+}
+
+fn load_font(font_path: Option<&str>) -> Result<FontVec, AppError> {
+    let path = match font_path {
+        Some(path) => path,
+        None => SYSTEM_FONTS
+            .iter()
+            .copied()
+            .find(|p| std::path::Path::new(p).is_file())
+            .ok_or_else(|| AppError::Validation("No font found for the watermark text; set a font".into()))?,
+    };
+    FontVec::try_from_vec(std::fs::read(path)?)
+        .map_err(|_| AppError::Validation(format!("{} is not a usable font", path)))
+}
+
+/// White `text` with a soft shadow below and to the right, on transparency
+fn render_text(text: &str, font: &FontVec) -> RgbaImage {
+    let scale = PxScale::from(TEXT_RENDER_PX);
+    let scaled = font.as_scaled(scale);
+    let mut glyphs = Vec::new();
+    let mut x = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            x += scaled.kern(previous, id);
+        }
+        glyphs.push(id.with_scale_and_position(scale, point(x, scaled.ascent())));
+        x += scaled.h_advance(id);
+        previous = Some(id);
+    }
+
+    let offset = (TEXT_RENDER_PX / 32.0).round() as u32;
+    let width = x.ceil() as u32 + offset;
+    let height = (scaled.ascent() - scaled.descent()).ceil() as u32 + offset;
+    let mut coverage = GrayImage::new(width.max(1), height.max(1));
+    for glyph in glyphs {
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, c| {
+            let (px, py) = (bounds.min.x as i64 + gx as i64, bounds.min.y as i64 + gy as i64);
+            if px >= 0 && py >= 0 && (px as u32) < coverage.width() && (py as u32) < coverage.height() {
+                let pixel = coverage.get_pixel_mut(px as u32, py as u32);
+                pixel.0[0] = pixel.0[0].max((c.clamp(0.0, 1.0) * 255.0) as u8);
+            }
+        });
+    }
+
+    RgbaImage::from_fn(coverage.width(), coverage.height(), |x, y| {
+        let text = coverage.get_pixel(x, y).0[0] as f32 / 255.0;
+        let shadow = match (x.checked_sub(offset), y.checked_sub(offset)) {
+            (Some(sx), Some(sy)) => coverage.get_pixel(sx, sy).0[0] as f32 / 255.0 * 0.5,
+            _ => 0.0,
+        };
+        let alpha = text + shadow * (1.0 - text);
+        if alpha <= 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let level = (255.0 * text / alpha).round() as u8;
+        Rgba([level, level, level, (alpha * 255.0).round() as u8])
+    })
+}
+
+fn load_mark(options: &WatermarkOptions) -> Result<RgbaImage, AppError> {
+    if let Some(path) = &options.image_path {
+        let data = std::fs::read(path)?;
+        if image::guess_format(&data).ok() != Some(ImageFormat::Png) {
+            return Err(AppError::Validation("Watermark image must be a PNG".into()));
+        }
+        let mark = image::load_from_memory_with_format(&data, ImageFormat::Png)
+            .map_err(|e| AppError::Validation(format!("Cannot decode watermark: {}", e)))?;
+        return Ok(mark.to_rgba8());
+    }
+    let font = load_font(options.font_path.as_deref())?;
+    Ok(render_text(options.text.as_deref().unwrap_or_default(), &font))
+}
+
+/// Top-left corner of a `mark_width` × `mark_height` mark on the photo
+pub fn mark_origin(
+    width: u32,
+    height: u32,
+    mark_width: u32,
+    mark_height: u32,
+    position: WatermarkPosition,
+) -> (i64, i64) {
+    let margin = (width.min(height) as f32 * MARGIN).round() as i64;
+    let right = width as i64 - mark_width as i64 - margin;
+    let bottom = height as i64 - mark_height as i64 - margin;
+    match position {
+        WatermarkPosition::TopLeft => (margin, margin),
+        WatermarkPosition::TopRight => (right, margin),
+        WatermarkPosition::BottomLeft => (margin, bottom),
+        WatermarkPosition::BottomRight => (right, bottom),
+        WatermarkPosition::Center => (
+            (width as i64 - mark_width as i64) / 2,
+            (height as i64 - mark_height as i64) / 2,
+        ),
+    }
+}
+
+/// Composite `mark` onto `photo` as `options` describe
+pub fn apply_mark(photo: &mut RgbaImage, mark: &RgbaImage, options: &WatermarkOptions) {
+    if mark.width() == 0 || mark.height() == 0 {
+        return;
+    }
+    let mut width = ((photo.width() as f32 * options.scale).round() as u32).max(1);
+    let mut height = ((mark.height() as f64 * width as f64 / mark.width() as f64).round() as u32).max(1);
+    // Tall marks are fitted to the photo's height instead
+    if height > photo.height() {
+        width = ((width as f64 * photo.height() as f64 / height as f64).round() as u32).max(1);
+        height = photo.height();
+    }
+
+    let mut mark = imageops::resize(mark, width, height, FilterType::Lanczos3);
+    for pixel in mark.pixels_mut() {
+        pixel.0[3] = (pixel.0[3] as f32 * options.opacity).round() as u8;
+    }
+    let (x, y) = mark_origin(photo.width(), photo.height(), width, height, options.position);
+    imageops::overlay(photo, &mark, x, y);
+}
+
+/// Watermark `data`, returning the new image and its format (jpeg, png or
+/// webp). Fails if the photo or the mark cannot be read.
+pub fn watermark_image_data(data: &[u8], options: &WatermarkOptions) -> Result<(Vec<u8>, &'static str), AppError> {
+    validate_options(options)?;
+    let image = decode_upright(data)?;
+    let has_alpha = image.color().has_alpha();
+    let mut photo = image.to_rgba8();
+    apply_mark(&mut photo, &load_mark(options)?, options);
+
+    let format = output_format(image::guess_format(data).ok());
+    let photo = if has_alpha {
+        DynamicImage::ImageRgba8(photo)
+    } else {
+        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(photo).to_rgb8())
+    };
+    Ok((encode(&photo, format, options.quality)?, format_name(format)))
+}
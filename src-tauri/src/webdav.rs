@@ -0,0 +1,372 @@
+//! WebDAV Storage Backend
+//!
+//! A `StorageBackend` (see `storage_backend`) for WebDAV servers, so albums
+//! can be backed up to a Nextcloud instance, a Synology or other NAS, or
+//! anything else that speaks WebDAV. The server URL is the folder to keep
+//! backups in, such as `https://cloud.example.com/remote.php/dav/files/alice/Vortex`
+//! for Nextcloud; credentials go through `secure_store_token`, so they live
+//! in the OS keychain where there is one. Nextcloud users should use an app
+//! password.
+//!
+//! - Listing uses `PROPFIND` with `Depth: 1`
+//! - Uploads are streamed with a chunked `PUT`, after `MKCOL` for any
+//!   missing folders
+//! - Downloads are ranged `GET`s of `RANGE_BYTES`, so a dropped connection
+//!   during a large video repeats one range rather than the whole file;
+//!   servers that ignore ranges send the file in one go
+
+use percent_encoding::percent_decode_str;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Body, Client, Method, RequestBuilder, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::State;
+use zeroize::Zeroizing;
+
+use crate::crypto::{secure_delete_token, secure_retrieve_token, secure_store_token, KeypairHandle};
+use crate::download_cache::CachedPhoto;
+use crate::github::{validate_repo, AppError, HttpClient};
+use crate::local_store::{with_store, SETTINGS_NS};
+use crate::retry::SendWithRetry;
+use crate::storage_backend::{
+    backup_album, fetch_backed_up_photo, validate_server_url, AlbumBackup, StorageBackend, StoredFile,
+};
+
+/// Setting holding the `WebDavConfig`
+pub const CONFIG_SETTING: &str = "webdav_server";
+/// Name of the password for `secure_store_token`
+const PASSWORD_KEY: &str = "webdav-password";
+pub const BACKEND_NAME: &str = "webdav";
+/// Size of each ranged download
+pub const RANGE_BYTES: u64 = 8 * 1024 * 1024;
+/// Size of the pieces uploads are streamed in
+const CHUNK_BYTES: usize = 1024 * 1024;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop><d:resourcetype/><d:getcontentlength/></d:prop>
+</d:propfind>"#;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebDavConfig {
+    /// Folder on the server backups go to
+    pub url: String,
+    pub username: String,
+}
+
+// ============================================================================
+// Protocol
+// ============================================================================
+
+fn method(name: &str) -> Method {
+    Method::from_bytes(name.as_bytes()).expect("valid WebDAV method")
+}
+
+fn dav(node: &roxmltree::Node, name: &str) -> bool {
+    node.tag_name().name() == name && node.tag_name().namespace() == Some("DAV:")
+}
+
+/// Files in a `PROPFIND` multistatus response. Folders, the listed one
+/// included, are left out.
+pub fn parse_multistatus(xml: &str) -> Result<Vec<StoredFile>, AppError> {
+    let doc = roxmltree::Document::parse(xml)
+        .map_err(|e| AppError::Api(format!("Unreadable WebDAV listing: {}", e)))?;
+    let mut files = Vec::new();
+    for response in doc.descendants().filter(|n| dav(n, "response")) {
+        let Some(href) = response.descendants().find(|n| dav(n, "href")).and_then(|n| n.text()) else {
+            continue;
+        };
+        // Only properties the server found count
+        let found: Vec<roxmltree::Node> = response
+            .children()
+            .filter(|n| dav(n, "propstat"))
+            .filter(|n| {
+                n.children()
+                    .find(|c| dav(c, "status"))
+                    .and_then(|c| c.text())
+                    .is_none_or(|status| status.contains(" 200 "))
+            })
+            .collect();
+        let is_folder = found
+            .iter()
+            .flat_map(|n| n.descendants())
+            .any(|n| dav(&n, "collection"));
+        if is_folder || href.trim_end().ends_with('/') {
+            continue;
+        }
+        let size = found
+            .iter()
+            .flat_map(|n| n.descendants())
+            .find(|n| dav(n, "getcontentlength"))
+            .and_then(|n| n.text())
+            .and_then(|text| text.trim().parse().ok());
+        let name = href.trim().rsplit('/').next().unwrap_or_default();
+        let name = percent_decode_str(name).decode_utf8_lossy().to_string();
+        if !name.is_empty() {
+            files.push(StoredFile { name, size });
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// `(first, last, total)` of a `Content-Range` header
+pub fn parse_content_range(header: &str) -> Option<(u64, u64, Option<u64>)> {
+    let (range, total) = header.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (first, last) = range.split_once('-')?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((first.parse().ok()?, last.parse().ok()?, total))
+}
+
+/// URL of `path` below the folder at `base`, with each segment encoded
+pub fn webdav_url(base: &str, path: &str) -> Result<Url, AppError> {
+    let mut url = Url::parse(base).map_err(|e| AppError::Validation(format!("Invalid WebDAV URL: {}", e)))?;
+    url.path_segments_mut()
+        .map_err(|_| AppError::Validation("Invalid WebDAV URL".into()))?
+        .pop_if_empty()
+        .extend(path.split('/').filter(|segment| !segment.is_empty()));
+    Ok(url)
+}
+
+fn webdav_error(res: &Response, context: &str) -> AppError {
+    match res.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            AppError::Validation("WebDAV server refused the credentials".into())
+        }
+        StatusCode::INSUFFICIENT_STORAGE => AppError::Api(format!("{}: the WebDAV server is full", context)),
+        status => AppError::Api(format!("{} ({})", context, status.as_u16())),
+    }
+}
+
+// ============================================================================
+// Backend
+// ============================================================================
+
+pub struct WebDav {
+    client: Client,
+    config: WebDavConfig,
+    password: Zeroizing<String>,
+    /// Folders known to exist, so each upload does not recreate them
+    folders: Mutex<HashSet<String>>,
+}
+
+impl WebDav {
+    pub fn new(client: &Client, config: WebDavConfig, password: Zeroizing<String>) -> Self {
+        Self { client: client.clone(), config, password, folders: Mutex::new(HashSet::new()) }
+    }
+
+    /// The configured server; `None` when there is none
+    pub fn load(client: &Client) -> Result<Option<Self>, AppError> {
+        let Some(config) = with_store(|store| store.get_json::<WebDavConfig>(SETTINGS_NS, CONFIG_SETTING))? else {
+            return Ok(None);
+        };
+        let password = secure_retrieve_token(PASSWORD_KEY.to_string())
+            .map_err(|e| AppError::Validation(format!("WebDAV password unavailable: {}", e)))?;
+        Ok(Some(Self::new(client, config, Zeroizing::new(password))))
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .basic_auth(&self.config.username, Some(self.password.as_str()))
+            .header("User-Agent", "vortex-image")
+    }
+
+    async fn propfind(&self, path: &str, depth: &str) -> Result<Option<String>, AppError> {
+        let res = self
+            .request(method("PROPFIND"), webdav_url(&self.config.url, path)?)
+            .header("Depth", depth)
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send_with_retry()
+            .await?;
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(res.text().await?)),
+            _ => Err(webdav_error(&res, "WebDAV listing failed")),
+        }
+    }
+
+    /// Create the folders above `path` that do not exist yet
+    async fn make_folders(&self, path: &str) -> Result<(), AppError> {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        for depth in 1..segments.len() {
+            let folder = segments[..depth].join("/");
+            if self.folders.lock().unwrap().contains(&folder) {
+                continue;
+            }
+            let res = self
+                .request(method("MKCOL"), webdav_url(&self.config.url, &folder)?)
+                .send_with_retry()
+                .await?;
+            // 405: the folder already exists
+            if !res.status().is_success() && res.status() != StatusCode::METHOD_NOT_ALLOWED {
+                return Err(webdav_error(&res, &format!("Failed to create {}", folder)));
+            }
+            self.folders.lock().unwrap().insert(folder);
+        }
+        Ok(())
+    }
+}
+
+impl StorageBackend for WebDav {
+    fn name(&self) -> &'static str {
+        BACKEND_NAME
+    }
+
+    async fn list(&self, folder: &str) -> Result<Vec<StoredFile>, AppError> {
+        match self.propfind(folder, "1").await? {
+            Some(xml) => parse_multistatus(&xml),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn put(&self, path: &str, content: Vec<u8>) -> Result<(), AppError> {
+        self.make_folders(path).await?;
+        let length = content.len();
+        let chunks = futures::stream::iter((0..length).step_by(CHUNK_BYTES).map(move |start| {
+            Ok::<_, std::io::Error>(content[start..(start + CHUNK_BYTES).min(length)].to_vec())
+        }));
+        let res = self
+            .request(Method::PUT, webdav_url(&self.config.url, path)?)
+            .header("Content-Type", "application/octet-stream")
+            .body(Body::wrap_stream(chunks))
+            .send_with_retry()
+            .await?;
+        if !res.status().is_success() {
+            return Err(webdav_error(&res, &format!("Failed to upload {}", path)));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<Vec<u8>>, AppError> {
+        let url = webdav_url(&self.config.url, path)?;
+        let mut content = Vec::new();
+        loop {
+            let start = content.len() as u64;
+            let res = self
+                .request(Method::GET, url.clone())
+                .header(RANGE, format!("bytes={}-{}", start, start + RANGE_BYTES - 1))
+                .send_with_retry()
+                .await?;
+            match res.status() {
+                StatusCode::NOT_FOUND if start == 0 => return Ok(None),
+                // The previous range ended exactly at the end of the file
+                StatusCode::RANGE_NOT_SATISFIABLE if start > 0 => break,
+                StatusCode::OK if start == 0 => return Ok(Some(res.bytes().await?.to_vec())),
+                StatusCode::PARTIAL_CONTENT => {
+                    let range = res
+                        .headers()
+                        .get(CONTENT_RANGE)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(parse_content_range);
+                    if range.is_some_and(|(first, _, _)| first != start) {
+                        return Err(AppError::Api(format!("WebDAV server sent the wrong range of {}", path)));
+                    }
+                    let chunk = res.bytes().await?;
+                    content.extend_from_slice(&chunk);
+                    let complete = match range.and_then(|(_, _, total)| total) {
+                        Some(total) => content.len() as u64 >= total,
+                        None => (chunk.len() as u64) < RANGE_BYTES,
+                    };
+                    if complete || chunk.is_empty() {
+                        break;
+                    }
+                }
+                _ => return Err(webdav_error(&res, &format!("Failed to download {}", path))),
+            }
+        }
+        Ok(Some(content))
+    }
+
+    async fn delete(&self, path: &str) -> Result<bool, AppError> {
+        let res = self
+            .request(Method::DELETE, webdav_url(&self.config.url, path)?)
+            .send_with_retry()
+            .await?;
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            _ => Err(webdav_error(&res, &format!("Failed to delete {}", path))),
+        }
+    }
+}
+
+/// The photo at `remote_path` from its WebDAV backup; `None` when there is
+/// no server or no copy
+pub(crate) async fn fetch_webdav_photo(
+    client: &Client,
+    repo: &str,
+    remote_path: &str,
+    keypair_handle: Option<KeypairHandle>,
+    verify: bool,
+) -> Result<Option<CachedPhoto>, AppError> {
+    match WebDav::load(client)? {
+        Some(webdav) => fetch_backed_up_photo(&webdav, repo, remote_path, keypair_handle, verify).await,
+        None => Ok(None),
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Back up to the WebDAV folder at `url`, checking that it can be listed
+/// with the credentials first
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn configure_webdav(
+    client: State<'_, HttpClient>,
+    url: String,
+    username: String,
+    password: String,
+) -> Result<WebDavConfig, AppError> {
+    let password = Zeroizing::new(password);
+    let config = WebDavConfig { url: validate_server_url(&url)?, username: username.trim().to_string() };
+    if config.username.is_empty() {
+        return Err(AppError::Validation("WebDAV username is required".into()));
+    }
+
+    let webdav = WebDav::new(&client.0, config.clone(), password.clone());
+    webdav
+        .propfind("", "0")
+        .await?
+        .ok_or_else(|| AppError::Validation(format!("WebDAV folder {} not found", config.url)))?;
+
+    secure_store_token(PASSWORD_KEY.to_string(), password.to_string())
+        .map_err(|e| AppError::Validation(format!("Failed to store WebDAV password: {}", e)))?;
+    with_store(|store| store.put_json(SETTINGS_NS, CONFIG_SETTING, &config, None))?;
+    Ok(config)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn get_webdav_config() -> Result<Option<WebDavConfig>, AppError> {
+    with_store(|store| store.get_json(SETTINGS_NS, CONFIG_SETTING))
+}
+
+/// Stop using the server. Backups already made stay on it.
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub fn remove_webdav_config() -> Result<bool, AppError> {
+    let _ = secure_delete_token(PASSWORD_KEY.to_string());
+    with_store(|store| store.remove(SETTINGS_NS, CONFIG_SETTING))
+}
+
+/// Copy the files of `album_path` to the WebDAV server
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn backup_album_to_webdav(
+    client: State<'_, HttpClient>,
+    repo: String,
+    token: Zeroizing<String>,
+    album_path: String,
+) -> Result<AlbumBackup, AppError> {
+    validate_repo(&repo)?;
+    let webdav = WebDav::load(&client.0)?.ok_or_else(|| AppError::Validation("Set up a WebDAV server first".into()))?;
+    backup_album(&webdav, &client.0, &repo, &token, &album_path).await
+}
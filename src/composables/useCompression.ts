@@ -7,7 +7,14 @@
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
 
-export type CompressionAlgorithm = 'zstd' | 'lz4' | 'snap' | 'brotli' | 'gzip' | 'none'
+export type CompressionAlgorithm = 'zstd' | 'lz4' | 'snap' | 'brotli' | 'gzip' | 'xz' | 'none'
+
+export interface AutoDecision {
+  content: string
+  entropy: number
+  store_only: boolean
+  reason: string
+}
 
 export interface CompressionResult {
   data: number[]
@@ -15,6 +22,8 @@ export interface CompressionResult {
   original_size: number
   compressed_size: number
   ratio: number
+  was_compressed?: boolean
+  decision?: AutoDecision
 }
 
 export interface ItemCompressionSettings {
@@ -34,6 +43,49 @@ export interface CompressedFileData {
   compressed_size: number
   ratio: number
   checksum: number[]
+  segmented?: boolean
+}
+
+export interface CompressionParallelism {
+  threads: number
+  effective: number
+  available_cores: number
+}
+
+export interface DecompressionLimits {
+  max_output_bytes: number
+  max_ratio: number
+  timeout_ms: number
+}
+
+export type JobState = 'running' | 'completed' | 'failed' | 'cancelled'
+
+/** Payload of the `compress-job-progress` event */
+export interface JobProgress {
+  job_id: string
+  kind: 'compress' | 'pipeline'
+  state: JobState
+  files_done: number
+  files_total: number
+  bytes_done: number
+  bytes_total: number
+  current_file: string | null
+  error: string | null
+}
+
+export interface StreamCompressionResult {
+  output_path: string
+  algorithm: CompressionAlgorithm
+  original_size: number
+  compressed_size: number
+  ratio: number
+}
+
+export interface CompressionProgress {
+  id: string
+  bytes_done: number
+  total_bytes: number
+  percent: number
 }
 
 export interface CompressionRecommendation {
@@ -41,6 +93,23 @@ export interface CompressionRecommendation {
   level: number
   reason: string
   estimated_ratio: number
+  calibrated: boolean
+}
+
+export interface BenchmarkEntry {
+  algorithm: CompressionAlgorithm
+  level: number
+  compress_mbps: number
+  decompress_mbps: number
+  ratio: number
+  estimated_memory_bytes: number
+}
+
+export interface BenchmarkReport {
+  sample_bytes: number
+  cores: number
+  measured_at: number
+  entries: BenchmarkEntry[]
 }
 
 const availableAlgorithms = ref<CompressionAlgorithm[]>([])
@@ -54,7 +123,7 @@ export function useCompression() {
       initialized = true
     } catch (e) {
       console.error('Failed to load compression algorithms:', e)
-      availableAlgorithms.value = ['zstd', 'lz4', 'snap', 'brotli', 'gzip', 'none']
+      availableAlgorithms.value = ['zstd', 'lz4', 'snap', 'brotli', 'gzip', 'xz', 'none']
     }
   }
 
@@ -70,10 +139,11 @@ export function useCompression() {
     })
   }
 
-  async function compressAuto(data: Uint8Array, preferSpeed = false): Promise<CompressionResult> {
+  async function compressAuto(data: Uint8Array, preferSpeed = false, archival = false): Promise<CompressionResult> {
     return await invoke<CompressionResult>('compress_data_auto', {
       data: Array.from(data),
-      preferSpeed
+      preferSpeed,
+      archival
     })
   }
 
@@ -95,12 +165,14 @@ export function useCompression() {
   async function compressFile(
     data: Uint8Array,
     filename: string,
-    settings: ItemCompressionSettings
+    settings: ItemCompressionSettings,
+    jobId?: string
   ): Promise<CompressedFileData> {
     return await invoke<CompressedFileData>('compress_file', {
       data: Array.from(data),
       filename,
-      settings
+      settings,
+      jobId
     })
   }
 
@@ -109,6 +181,82 @@ export function useCompression() {
     return new Uint8Array(result)
   }
 
+  async function compressFileStream(
+    inputPath: string,
+    outputPath: string,
+    operationId: string,
+    algorithm: CompressionAlgorithm = 'zstd',
+    level?: number
+  ): Promise<StreamCompressionResult> {
+    return await invoke<StreamCompressionResult>('compress_file_stream', {
+      inputPath,
+      outputPath,
+      algorithm,
+      level,
+      operationId
+    })
+  }
+
+  async function decompressFileStream(
+    inputPath: string,
+    outputPath: string,
+    operationId: string
+  ): Promise<StreamCompressionResult> {
+    return await invoke<StreamCompressionResult>('decompress_file_stream', {
+      inputPath,
+      outputPath,
+      operationId
+    })
+  }
+
+  async function cancelCompression(operationId: string): Promise<boolean> {
+    return await invoke<boolean>('cancel_compression', { operationId })
+  }
+
+  /** Start compressing a folder in the background; returns the job ID */
+  async function compressFolderStart(
+    folder: string,
+    outputDir: string,
+    algorithm: CompressionAlgorithm,
+    level?: number
+  ): Promise<string> {
+    return await invoke<string>('compress_folder_start', { folder, outputDir, algorithm, level })
+  }
+
+  /** Stop any job, folder job or stream operation by ID */
+  async function cancelJob(jobId: string): Promise<boolean> {
+    return await invoke<boolean>('cancel_job', { jobId })
+  }
+
+  async function listJobs(): Promise<JobProgress[]> {
+    return await invoke<JobProgress[]>('compress_job_list')
+  }
+
+  async function getParallelism(): Promise<CompressionParallelism> {
+    return await invoke<CompressionParallelism>('get_compression_parallelism')
+  }
+
+  async function setParallelism(threads: number): Promise<CompressionParallelism> {
+    return await invoke<CompressionParallelism>('set_compression_parallelism', { threads })
+  }
+
+  async function getDecompressionLimits(): Promise<DecompressionLimits> {
+    return await invoke<DecompressionLimits>('get_decompression_limits')
+  }
+
+  /** Relax or tighten limits for this session; `null` restores the defaults */
+  async function setDecompressionLimits(limits: DecompressionLimits | null): Promise<DecompressionLimits> {
+    return await invoke<DecompressionLimits>('set_decompression_limits', { limits })
+  }
+
+  async function runBenchmark(samplePath: string): Promise<BenchmarkReport> {
+    return await invoke<BenchmarkReport>('benchmark_compression', { samplePath })
+  }
+
+  async function getBenchmark(): Promise<BenchmarkReport | null> {
+    return await invoke<BenchmarkReport | null>('get_compression_benchmark')
+  }
+
   async function getRecommendation(filename: string, fileSize: number): Promise<CompressionRecommendation> {
     return await invoke<CompressionRecommendation>('get_compression_recommendation', {
       filename,
@@ -154,6 +302,7 @@ export function useCompression() {
       snap: { name: 'Snappy', description: 'Very fast, moderate compression', speed: 'Very Fast', ratio: 'Moderate' },
       brotli: { name: 'Brotli', description: 'High compression, slower', speed: 'Slow', ratio: 'Excellent' },
       gzip: { name: 'Gzip', description: 'Universal compatibility', speed: 'Moderate', ratio: 'Good' },
+      xz: { name: 'XZ', description: 'Maximum compression for archival', speed: 'Very Slow', ratio: 'Best' },
       none: { name: 'None', description: 'No compression', speed: 'Instant', ratio: 'None' }
     }
     return info[algorithm]
@@ -168,6 +317,18 @@ export function useCompression() {
     estimate,
     compressFile,
     decompressFile,
+    compressFileStream,
+    decompressFileStream,
+    cancelCompression,
+    compressFolderStart,
+    cancelJob,
+    listJobs,
+    getParallelism,
+    setParallelism,
+    getDecompressionLimits,
+    setDecompressionLimits,
+    runBenchmark,
+    getBenchmark,
     getRecommendation,
     createCompressionSettings,
     formatRatio,
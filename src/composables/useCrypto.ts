@@ -11,6 +11,7 @@
  */
 
 import { ref, computed, onUnmounted } from 'vue'
+import { profileStoreFile } from './useProfiles'
 
 const isTauri = typeof window !== 'undefined' && !!(window as any).__TAURI__
 
@@ -61,6 +62,15 @@ export interface CryptoInfo {
   }
 }
 
+export interface PasswordStrength {
+  score: number
+  guesses_log10: number
+  crack_time: string
+  acceptable: boolean
+  warning: string | null
+  suggestions: string[]
+}
+
 export type EncryptionMethod = 'None' | 'Password' | 'HybridPQ'
 
 export interface EncryptionSettings {
@@ -70,11 +80,42 @@ export interface EncryptionSettings {
   recipient_bundle: PublicBundle | null
 }
 
-export interface EncryptedFileData {
-  data: number[]
-  encrypted: boolean
+export interface FileCryptoResult {
+  output_path: string
   method: EncryptionMethod
-  metadata: Record<string, unknown> | null
+  bytes: number
+  chunks: number
+  resumed_chunks: number
+}
+
+/** Payload of the `file-crypto-progress` event */
+export interface FileCryptoProgress {
+  id: string
+  bytes_done: number
+  total_bytes: number
+  percent: number
+}
+
+export interface HashTree {
+  chunk_size: number
+  chunks: { offset: number; length: number; hash: string }[]
+  root: string
+}
+
+export interface FileHash {
+  hash: string
+  size: number
+  memory_mapped: boolean
+  tree: HashTree | null
+}
+
+/** Payload of the `hash-progress` event */
+export interface HashProgress {
+  job_id: string
+  path: string
+  bytes_done: number
+  total_bytes: number
+  percent: number
 }
 
 export interface EncryptedPayload {
@@ -162,7 +203,7 @@ export function useCrypto() {
     try {
       const { load } = await import('@tauri-apps/plugin-store')
       const { invoke } = await import('@tauri-apps/api/core')
-      const store = await load('settings.json')
+      const store = await load(await profileStoreFile())
       const storedHandle = await store.get<number>('keypairHandle')
       hasStoredKeypair.value = storedHandle !== null && storedHandle !== undefined
       
@@ -205,13 +246,16 @@ export function useCrypto() {
     const { invoke } = await import('@tauri-apps/api/core')
     const { load } = await import('@tauri-apps/plugin-store')
     
+    // Persist the keypair itself, sealed under a key held in the OS keystore
+    await invoke('store_keypair_in_keystore', { handle: keypairHandle.value })
+
     // Store the encrypted keypair using secure token storage
     await invoke('secure_store_token', {
       key: 'keypair_handle',
       value: keypairHandle.value.toString()
     })
     
-    const store = await load('settings.json')
+    const store = await load(await profileStoreFile())
     await store.set('keypairHandle', keypairHandle.value)
     await store.set('publicBundle', publicBundle.value)
     await store.save()
@@ -242,10 +286,27 @@ export function useCrypto() {
       // Validate that the handle is still valid in the backend
       const isValid = await invoke<boolean>('validate_keypair_handle', { handle })
       if (!isValid) {
-        // Handle is stale - clear stored data and return false
+        // Handle is stale after a restart - reload the persisted keypair
+        const restored = await invoke<KeypairInfo | null>('load_keypair_from_keystore')
+        if (restored) {
+          keypairHandle.value = restored.handle
+          publicBundle.value = restored.public_bundle
+          await invoke('secure_store_token', {
+            key: 'keypair_handle',
+            value: restored.handle.toString()
+          })
+          const store = await load(await profileStoreFile())
+          await store.set('keypairHandle', restored.handle)
+          await store.set('publicBundle', restored.public_bundle)
+          await store.save()
+          isUnlocked.value = true
+          return true
+        }
+
+        // Nothing persisted - clear stored data and return false
         console.warn('Stored keypair handle is no longer valid in backend')
         hasStoredKeypair.value = false
-        const store = await load('settings.json')
+        const store = await load(await profileStoreFile())
         await store.delete('keypairHandle')
         await store.delete('publicBundle')
         await store.save()
@@ -255,7 +316,7 @@ export function useCrypto() {
       keypairHandle.value = handle
       
       // Get the public bundle for this handle
-      const store = await load('settings.json')
+      const store = await load(await profileStoreFile())
       const savedBundle = await store.get<PublicBundle>('publicBundle')
       if (savedBundle) {
         publicBundle.value = savedBundle
@@ -290,14 +351,14 @@ export function useCrypto() {
     if (keypairHandle.value === null) throw new Error('No keypair to rotate')
     resetActivityTimer()
     
-    const newBundle = await invoke<PublicBundle>('rotate_keypair', {
+    const { public_bundle: newBundle } = await invoke<{ public_bundle: PublicBundle }>('rotate_keypair', {
       handle: keypairHandle.value
     })
     
     publicBundle.value = newBundle
     
     // Save the new public bundle
-    const store = await load('settings.json')
+    const store = await load(await profileStoreFile())
     await store.set('publicBundle', newBundle)
     await store.save()
     
@@ -396,6 +457,16 @@ export function useCrypto() {
     return new Uint8Array(result)
   }
 
+  /**
+   * Score a password; encryptWithPassword rejects ones that are not acceptable
+   */
+  async function checkPasswordStrength(password: string, userInputs?: string[]): Promise<PasswordStrength> {
+    return await invoke<PasswordStrength>('check_password_strength', {
+      password,
+      userInputs: userInputs ?? null
+    })
+  }
+
   /**
    * Hash data using BLAKE3
    */
@@ -407,38 +478,63 @@ export function useCrypto() {
   }
 
   /**
-   * Encrypt a file with settings
+   * Hash a file on disk with BLAKE3 without loading it.
+   * Progress is reported through the `hash-progress` event; `tree` also
+   * returns per-chunk hashes for checking a resumed upload.
+   */
+  async function hashFile(
+    path: string,
+    options: { tree?: boolean; chunkSize?: number; jobId?: string } = {}
+  ): Promise<FileHash> {
+    return await invoke<FileHash>('hash_file_blake3', {
+      path,
+      tree: options.tree ?? null,
+      chunkSize: options.chunkSize ?? null,
+      jobId: options.jobId
+    })
+  }
+
+  /**
+   * Encrypt a file on disk in streamed chunks.
+   * Progress is reported through the `file-crypto-progress` event.
    */
   async function encryptFile(
-    data: Uint8Array,
+    inputPath: string,
+    outputPath: string,
     settings: EncryptionSettings,
-    password?: string
-  ): Promise<EncryptedFileData> {
+    password?: string,
+    jobId?: string
+  ): Promise<FileCryptoResult> {
     resetActivityTimer()
     
-    return await invoke<EncryptedFileData>('encrypt_file', {
-      data: Array.from(data),
+    return await invoke<FileCryptoResult>('encrypt_file', {
+      inputPath,
+      outputPath,
       settings,
       password: password || null,
-      handle: keypairHandle.value
+      jobId
     })
   }
 
   /**
-   * Decrypt a file
+   * Decrypt a file written by encryptFile. An interrupted decryption of the
+   * same file resumes where it stopped.
    */
   async function decryptFile(
-    encrypted: EncryptedFileData,
-    password?: string
-  ): Promise<Uint8Array> {
+    inputPath: string,
+    outputPath: string,
+    password?: string,
+    jobId?: string
+  ): Promise<FileCryptoResult> {
     resetActivityTimer()
     
-    const result = await invoke<number[]>('decrypt_file', {
-      encrypted,
+    return await invoke<FileCryptoResult>('decrypt_file', {
+      inputPath,
+      outputPath,
       password: password || null,
-      handle: keypairHandle.value
+      handle: keypairHandle.value,
+      jobId
     })
-    return new Uint8Array(result)
   }
 
   /**
@@ -475,6 +571,7 @@ export function useCrypto() {
       // Ignore if not found
       console.debug('No secure token to delete:', e)
     }
+    await invoke('delete_keypair_from_keystore').catch(console.error)
     
     keypairHandle.value = null
     publicBundle.value = null
@@ -482,7 +579,7 @@ export function useCrypto() {
     hasStoredKeypair.value = false
     
     // Clear from local settings
-    const store = await load('settings.json')
+    const store = await load(await profileStoreFile())
     await store.delete('keypairHandle')
     await store.delete('publicBundle')
     await store.save()
@@ -528,6 +625,7 @@ export function useCrypto() {
     decryptFromSender,
     encryptWithPassword,
     decryptWithPassword,
+    checkPasswordStrength,
     encryptFile,
     decryptFile,
     
@@ -537,6 +635,7 @@ export function useCrypto() {
     
     // Utilities
     hashData,
+    hashFile,
     createEncryptionSettings,
     
     // Session management
@@ -0,0 +1,22 @@
+/**
+ * TypeScript Module - 1 exports
+ * Purpose: This device's id, as recorded in album manifest vector clocks
+ * Imports: 0 modules
+ */
+
+/** Writes per device id, as stored in album manifests */
+export type VectorClock = Record<string, number>
+
+export function useDeviceSync() {
+  async function getDeviceId(): Promise<string> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<string>('get_device_id')
+  }
+
+  /** Whether a manifest was last written by another device */
+  async function editedElsewhere(manifest: { device?: string | null }): Promise<boolean> {
+    return !!manifest.device && manifest.device !== (await getDeviceId())
+  }
+
+  return { getDeviceId, editedElsewhere }
+}
@@ -1,5 +1,6 @@
 import { ref } from 'vue'
 import { useGitHubAuth, isDevMode, addMockAlbum } from './useGitHubAuth'
+import { errorMessage } from '../types/errors'
 
 export interface FolderInfo {
   path: string
@@ -74,7 +75,7 @@ export function useFolder() {
       })
       return result
     } catch (e) {
-      error.value = e instanceof Error ? e.message : String(e)
+      error.value = errorMessage(e)
       throw e
     } finally {
       creating.value = false
@@ -4,6 +4,8 @@
  */
 
 import { ref, computed, onUnmounted } from 'vue'
+import { errorMessage } from '../types/errors'
+import { profileStoreFile } from './useProfiles'
 
 // Platform detection
 const isTauri = typeof window !== 'undefined' && !!(window as any).__TAURI__
@@ -173,7 +175,7 @@ export function useGitHubAuth() {
         // Tauri: use plugin-store
         const { invoke } = await import('@tauri-apps/api/core')
         const { load } = await import('@tauri-apps/plugin-store')
-        const store = await load('settings.json')
+        const store = await load(await profileStoreFile())
         
         token.value = await store.get<string>('token') || null
         repo.value = await store.get<string>('repo') || ''
@@ -219,7 +221,7 @@ export function useGitHubAuth() {
       if (isTauri) {
         try {
           const { load } = await import('@tauri-apps/plugin-store')
-          const store = await load('settings.json')
+          const store = await load(await profileStoreFile())
           await store.delete('token')
           await store.save()
         } catch {}
@@ -240,7 +242,7 @@ export function useGitHubAuth() {
       keypairBytes.value = res.keypair_bytes
       publicBundle.value = res.public_bundle
 
-      const store = await load('settings.json')
+      const store = await load(await profileStoreFile())
       await store.set('keypair_bytes', res.keypair_bytes)
       await store.set('public_bundle', res.public_bundle)
       await store.save()
@@ -278,7 +280,7 @@ export function useGitHubAuth() {
 
       return { valid: true, user: userData, scopes, error: null }
     } catch (e) {
-      return { valid: false, user: null, scopes: [], error: String(e) }
+      return { valid: false, user: null, scopes: [], error: errorMessage(e) }
     }
   }
 
@@ -319,7 +321,7 @@ export function useGitHubAuth() {
 
       if (isTauri) {
         const { load } = await import('@tauri-apps/plugin-store')
-        const store = await load('settings.json')
+        const store = await load(await profileStoreFile())
         await store.set('token', token.value)
         await store.save()
 
@@ -336,7 +338,7 @@ export function useGitHubAuth() {
       validating.value = false
       return true
     } catch (e) {
-      error.value = String(e)
+      error.value = errorMessage(e)
       validating.value = false
       return false
     }
@@ -394,7 +396,7 @@ export function useGitHubAuth() {
               await rotateKeys()
             }
 
-            const store = await load('settings.json')
+            const store = await load(await profileStoreFile())
             await store.set('token', t)
             await store.save()
 
@@ -405,12 +407,12 @@ export function useGitHubAuth() {
           clearPolling()
           loading.value = false
           userCode.value = ''
-          error.value = String(e)
+          error.value = errorMessage(e)
         }
       }, res.interval * 1000)
     } catch (e) {
       loading.value = false
-      error.value = String(e)
+      error.value = errorMessage(e)
     }
   }
 
@@ -424,7 +426,7 @@ export function useGitHubAuth() {
     if (isTauri) {
       try {
         const { load } = await import('@tauri-apps/plugin-store')
-        const store = await load('settings.json')
+        const store = await load(await profileStoreFile())
         await store.delete('token')
         await store.save()
       } catch {}
@@ -442,7 +444,7 @@ export function useGitHubAuth() {
     if (isTauri) {
       try {
         const { load } = await import('@tauri-apps/plugin-store')
-        const store = await load('settings.json')
+        const store = await load(await profileStoreFile())
         await store.set('repo', r)
         await store.save()
       } catch {}
@@ -559,7 +561,7 @@ async function downloadKeypairSync(password: string): Promise<boolean> {
     keypairBytes.value = decrypted
     publicBundle.value = result.public_bundle
     
-    const store = await load('settings.json')
+    const store = await load(await profileStoreFile())
     await store.set('keypair_bytes', decrypted)
     await store.set('public_bundle', result.public_bundle)
     await store.save()
@@ -617,7 +619,7 @@ async function importKeypairBackup(backup: string, password: string): Promise<bo
     keypairBytes.value = decrypted
     publicBundle.value = result.public_bundle
     
-    const store = await load('settings.json')
+    const store = await load(await profileStoreFile())
     await store.set('keypair_bytes', decrypted)
     await store.set('public_bundle', result.public_bundle)
     await store.save()
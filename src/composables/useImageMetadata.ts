@@ -48,6 +48,33 @@ export interface ImageMetadata {
   colorSpace?: string
 }
 
+/** `get_image_metadata` result, EXIF merged with XMP */
+interface BackendImageMetadata {
+  name: string
+  size: number
+  format: string | null
+  width: number | null
+  height: number | null
+  exif: {
+    taken_at?: string
+    digitized_at?: string
+    modified_at?: string
+    camera_make?: string
+    camera_model?: string
+    lens_model?: string
+    focal_length?: number
+    f_number?: number
+    exposure_time?: number
+    iso?: number
+    orientation?: number
+    latitude?: number
+    longitude?: number
+    altitude?: number
+  } | null
+  xmp: { title?: string; description?: string } | null
+  taken_at_unix: number | null
+}
+
 const loading = ref(false)
 const error = ref<string | null>(null)
 
@@ -94,6 +121,51 @@ async function extractMetadata(source: File | string): Promise<ImageMetadata | n
   }
 }
 
+/** Metadata of a local file read by the backend, including XMP and sidecars */
+async function readLocalMetadata(path: string): Promise<ImageMetadata | null> {
+  loading.value = true
+  error.value = null
+
+  try {
+    const { invoke } = await import('@tauri-apps/api/core')
+    const raw = await invoke<BackendImageMetadata>('get_image_metadata', { path })
+    const exif = raw.exif ?? {}
+    const metadata: ImageMetadata = {
+      fileName: raw.name,
+      fileSize: raw.size,
+      fileType: raw.format ? `image/${raw.format}` : 'image/unknown',
+      camera: { make: exif.camera_make, model: exif.camera_model, lens: exif.lens_model },
+      settings: {
+        aperture: exif.f_number ? `f/${exif.f_number}` : undefined,
+        shutterSpeed: exif.exposure_time
+          ? exif.exposure_time >= 1 ? `${exif.exposure_time}s` : `1/${Math.round(1 / exif.exposure_time)}s`
+          : undefined,
+        iso: exif.iso,
+        focalLength: exif.focal_length ? `${exif.focal_length}mm` : undefined,
+      },
+      dateTime: {
+        original: raw.taken_at_unix !== null ? new Date(raw.taken_at_unix * 1000) : undefined,
+        digitized: exif.digitized_at ? parseExifDate(exif.digitized_at) : undefined,
+        modified: exif.modified_at ? parseExifDate(exif.modified_at) : undefined,
+      },
+      orientation: exif.orientation,
+      description: raw.xmp?.description ?? raw.xmp?.title,
+    }
+    if (raw.width !== null && raw.height !== null) {
+      metadata.dimensions = { width: raw.width, height: raw.height }
+    }
+    if (exif.latitude !== undefined && exif.longitude !== undefined) {
+      metadata.location = { latitude: exif.latitude, longitude: exif.longitude, altitude: exif.altitude }
+    }
+    return metadata
+  } catch (e) {
+    error.value = e instanceof Error ? e.message : 'Failed to extract metadata'
+    return null
+  } finally {
+    loading.value = false
+  }
+}
+
 async function getImageDimensions(blob: Blob): Promise<{ width: number; height: number } | null> {
   return new Promise((resolve) => {
     const img = new Image()
@@ -412,6 +484,15 @@ function formatDate(date: Date | undefined): string {
   })
 }
 
+/** Save the camera-rendered JPEG embedded in a local RAW file to `destination` */
+async function extractRawPreview(
+  path: string,
+  destination: string
+): Promise<{ path: string; width: number; height: number; bytes: number }> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke('extract_raw_preview', { path, destination })
+}
+
 function formatCoordinates(lat?: number, lon?: number): string {
   if (lat === undefined || lon === undefined) return '-'
   const latDir = lat >= 0 ? 'N' : 'S'
@@ -424,6 +505,8 @@ export function useImageMetadata() {
     loading,
     error,
     extractMetadata,
+    readLocalMetadata,
+    extractRawPreview,
     formatFileSize,
     formatDate,
     formatCoordinates,
@@ -0,0 +1,113 @@
+/**
+ * TypeScript Module - 1 exports
+ * Purpose: Large payloads kept off the JSON invoke channel
+ * Imports: 0 modules
+ */
+
+export type DataOp =
+  | { op: 'compress'; algorithm: string; level?: number | null; strict?: boolean }
+  | { op: 'compress_auto'; prefer_speed: boolean; archival?: boolean | null }
+  | { op: 'decompress'; algorithm: string }
+  | { op: 'encrypt_hybrid'; recipient_bundle?: unknown; contact_id?: string | null; aad?: number[] | null }
+  | { op: 'decrypt_hybrid'; handle: number; aad?: number[] | null }
+  | { op: 'encrypt_password'; password: string }
+  | { op: 'decrypt_password'; password: string }
+  | { op: 'sign'; handle: number }
+  | { op: 'verify'; signature: number[]; public_bundle: unknown }
+  | { op: 'hash' }
+
+export interface DataOpOutput {
+  size: number
+  /** Output path, or the ID of the buffer holding the result */
+  output: string | null
+  compression: Record<string, unknown> | null
+  valid: boolean | null
+  digest: string | null
+}
+
+/** Bytes sent per `buffer_write` call */
+const WRITE_CHUNK = 4 * 1024 * 1024
+
+/**
+ * Data commands for payloads too big for `invoke` arguments. Files are
+ * processed where they lie; in-memory data goes into a backend buffer as
+ * raw chunks and comes back the same way.
+ */
+export function useLargeData() {
+  async function runOnFile(op: DataOp, inputPath: string, outputPath?: string): Promise<DataOpOutput> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<DataOpOutput>('data_op_file', { op, inputPath, outputPath: outputPath ?? null })
+  }
+
+  async function runOnBuffer(op: DataOp, bufferId: string): Promise<DataOpOutput> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<DataOpOutput>('data_op_buffer', { op, bufferId })
+  }
+
+  /** Copy `data` into a new buffer. Returns its ID. */
+  async function upload(data: Uint8Array | Blob): Promise<string> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    const bufferId = await invoke<string>('buffer_create')
+    try {
+      const total = data instanceof Blob ? data.size : data.byteLength
+      for (let offset = 0; offset < total; offset += WRITE_CHUNK) {
+        const slice = data instanceof Blob
+          ? new Uint8Array(await data.slice(offset, offset + WRITE_CHUNK).arrayBuffer())
+          : data.subarray(offset, offset + WRITE_CHUNK)
+        await invoke('buffer_write', slice, { headers: { 'buffer-id': bufferId } })
+      }
+    } catch (e) {
+      await release(bufferId)
+      throw e
+    }
+    return bufferId
+  }
+
+  /** Read a buffer back, passing each chunk to `onChunk` if given */
+  async function download(bufferId: string, onChunk?: (chunk: Uint8Array) => void): Promise<Uint8Array> {
+    const { invoke, Channel } = await import('@tauri-apps/api/core')
+    const chunks: Uint8Array[] = []
+    const channel = new Channel<ArrayBuffer>()
+    channel.onmessage = (buffer) => {
+      const chunk = new Uint8Array(buffer)
+      if (onChunk) onChunk(chunk)
+      else chunks.push(chunk)
+    }
+    const size = await invoke<number>('buffer_read', { bufferId, onChunk: channel, chunkSize: null })
+    const result = new Uint8Array(onChunk ? 0 : size)
+    let offset = 0
+    for (const chunk of chunks) {
+      result.set(chunk, offset)
+      offset += chunk.byteLength
+    }
+    return result
+  }
+
+  async function bufferPath(bufferId: string): Promise<string> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<string>('buffer_path', { bufferId })
+  }
+
+  async function release(bufferId: string): Promise<boolean> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<boolean>('buffer_release', { bufferId })
+  }
+
+  /** Run `op` on in-memory data through buffers, releasing them afterwards */
+  async function run(op: DataOp, data: Uint8Array | Blob): Promise<{ output: DataOpOutput; data: Uint8Array }> {
+    const input = await upload(data)
+    try {
+      const output = await runOnBuffer(op, input)
+      if (!output.output) return { output, data: new Uint8Array(0) }
+      try {
+        return { output, data: await download(output.output) }
+      } finally {
+        await release(output.output)
+      }
+    } finally {
+      await release(input)
+    }
+  }
+
+  return { runOnFile, runOnBuffer, upload, download, bufferPath, release, run }
+}
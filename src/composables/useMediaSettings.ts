@@ -19,6 +19,8 @@ export interface SimpleSettings {
   compress: boolean
   /** Encrypt files for privacy (uses post-quantum encryption when keypair available) */
   encrypt: boolean
+  /** Convert HEIC photos to JPEG or WebP before uploading */
+  convertHeic?: 'jpeg' | 'webp' | null
 }
 
 export interface FolderSettings {
@@ -225,6 +227,7 @@ export function toBackendSettings(
     use_password: boolean
     use_keypair: boolean
   }
+  convert_heif: { format: 'jpeg' | 'webp' } | null
 } {
   return {
     compression: {
@@ -241,6 +244,7 @@ export function toBackendSettings(
       // Prefer keypair (post-quantum) if available, otherwise password
       use_keypair: settings.encrypt && publicBundle !== null,
       use_password: settings.encrypt && publicBundle === null && !!password
-    }
+    },
+    convert_heif: settings.convertHeic ? { format: settings.convertHeic } : null
   }
 }
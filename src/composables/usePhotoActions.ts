@@ -8,6 +8,7 @@ import { useSelection } from './useSelection'
 import { useFavorites } from './useFavorites'
 import { useColorTagStore } from './useColorTags'
 import { useSyncStatus } from './useSyncStatus'
+import { useSecureViewing } from './useSecureViewing'
 import type { Photo } from '../types/photo'
 
 export function usePhotoActions() {
@@ -15,6 +16,7 @@ export function usePhotoActions() {
   const { isFavorite, toggleFavorite } = useFavorites()
   const { getPhotoTag } = useColorTagStore()
   const { getStatus, uploadPhoto, downloadPhoto, removeLocalCopy, deleteFromRemote } = useSyncStatus()
+  const { copySecret } = useSecureViewing()
 
   function getPhotoColorTag(photo: Photo): string | undefined {
     const tag = getPhotoTag(photo.sha)
@@ -38,8 +40,9 @@ export function usePhotoActions() {
     }
   }
 
+  // Raw URLs of private repos carry an access token
   function copyUrl(url: string) {
-    navigator.clipboard.writeText(url)
+    copySecret(url)
   }
 
   return {
@@ -8,6 +8,8 @@
 import { ref, computed, onMounted, onUnmounted, watch } from 'vue'
 import { useGitHubAuth, isDevMode, isWebMode } from './useGitHubAuth'
 import { useMediaSettings, toBackendSettings, type SimpleSettings } from './useMediaSettings'
+import { useCrypto } from './useCrypto'
+import { errorMessage } from '../types/errors'
 
 interface UploadResult {
   url: string
@@ -63,9 +65,11 @@ let initialized = false
 
 export function usePhotoUpload() {
   const { token, repo, publicBundle } = useGitHubAuth()
+  const { keypairHandle } = useCrypto()
   const { getFolderSettings, initialize: initMediaSettings } = useMediaSettings()
 
   let unlisten: (() => void) | null = null
+  let unlistenCatalog: (() => void) | null = null
   let isProcessing = false
 
   const pendingCount = computed(() => queue.value.filter(i => i.status === 'pending').length)
@@ -88,6 +92,8 @@ export function usePhotoUpload() {
         const item = queue.value.find(i => i.id === event.payload.id)
         if (item) item.progress = event.payload.percent
       })
+      // Listings come from the local catalog first; reload once GitHub differs
+      unlistenCatalog = await listen('catalog-updated', () => loadPhotos())
     } catch { }
 
     if (!initialized && token.value && repo.value) {
@@ -101,6 +107,10 @@ export function usePhotoUpload() {
       unlisten()
       unlisten = null
     }
+    if (unlistenCatalog) {
+      unlistenCatalog()
+      unlistenCatalog = null
+    }
   })
 
   /**
@@ -247,7 +257,7 @@ export function usePhotoUpload() {
           } catch (e) {
             next.status = 'failed'
             next.progress = 0
-            next.error = String(e).replace('Error: ', '')
+            next.error = errorMessage(e).replace('Error: ', '')
           }
           continue
         }
@@ -282,7 +292,9 @@ export function usePhotoUpload() {
             uploadId: next.id,
             publicBundle: backendSettings.encryption.use_keypair ? publicBundle.value : null,
             password: backendSettings.encryption.use_password ? next.password : null,
-            settings: backendSettings
+            settings: backendSettings,
+            // Signs the upload so downloads can verify it
+            keypairHandle: keypairHandle.value
           })
 
           next.status = 'success'
@@ -298,7 +310,7 @@ export function usePhotoUpload() {
         } catch (e) {
           next.status = 'failed'
           next.progress = 0
-          next.error = String(e).replace('Error: ', '')
+          next.error = errorMessage(e).replace('Error: ', '')
         }
       }
     } finally {
@@ -306,7 +318,7 @@ export function usePhotoUpload() {
       isProcessing = false
     }
 
-    if (!isDevMode) await loadPhotos()
+    if (!isDevMode) await loadPhotos(undefined, true)
   }
 
   /**
@@ -354,7 +366,7 @@ export function usePhotoUpload() {
     }
   }
 
-  async function loadPhotos(folder?: string) {
+  async function loadPhotos(folder?: string, refresh = false) {
     if (isDevMode) {
       loadingPhotos.value = true
       await new Promise(r => setTimeout(r, 300))
@@ -403,7 +415,8 @@ export function usePhotoUpload() {
       const photoUrls = await invoke<string[]>('list_photos', {
         repo: repo.value,
         token: token.value,
-        folder: folder || undefined
+        folder: folder || undefined,
+        refresh
       })
 
       photos.value = photoUrls.map(url => {
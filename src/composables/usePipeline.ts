@@ -1,17 +1,41 @@
 import { ref, computed } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
 
 // Types
 export interface PipelineOperation {
-  type: 'compress' | 'encrypt_password' | 'encrypt_hybrid_pq' | 'hash' | 'base64_encode' | 'base64_decode'
+  type: 'compress' | 'encrypt_password' | 'encrypt_hybrid_pq' | 'hash' | 'base64_encode' | 'base64_decode' | 'optimize_image' | 'transcode' | 'resize' | 'watermark' | 'step'
   algorithm?: string
   level?: number
+  strip_metadata?: boolean
+  format?: 'avif' | 'webp' | 'jxl'
+  quality?: number
+  keep_original?: boolean
+  mode?: 'max_edge' | 'exact' | 'percent'
+  max_edge?: number
+  width?: number
+  height?: number
+  percent?: number
+  /** Album under `photos/` that routed uploads send the original to */
+  originals_album?: string
+  /** PNG for `type: 'watermark'`; otherwise `text` is drawn */
+  image_path?: string
+  text?: string
+  font_path?: string
+  position?: 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right' | 'center'
+  opacity?: number
+  scale?: number
   public_bundle?: any
+  /** Registered step ID, for `type: 'step'` */
+  step?: string
+  params?: Record<string, unknown>
 }
 
 export interface PipelineLayer {
   id: string
   operation: PipelineOperation
   enabled: boolean
+  /** Run only when this holds, e.g. `size > 5MB and extension == 'raw'` */
+  condition?: string
 }
 
 export interface PipelineConfig {
@@ -30,6 +54,52 @@ export interface PipelinePreset {
   layers: PipelineLayer[]
 }
 
+/** Preset as stored by the backend and in exported preset files */
+export interface StoredPreset {
+  id: string
+  name: string
+  description: string
+  layers: Array<PipelineLayer & { order: number }>
+  created_at: number
+  updated_at: number
+}
+
+/** Step registered in the backend, with a JSON Schema of its parameters */
+export interface StepDescriptor {
+  id: string
+  label: string
+  description: string
+  source_transform: boolean
+  params_schema: Record<string, any>
+}
+
+export interface BatchOptions {
+  /** Extensions to include, e.g. `['jpg', 'png']`; empty includes all */
+  extensions?: string[]
+  concurrency?: number
+  stop_on_error?: boolean
+}
+
+/** Payload of the `pipeline-file-progress` event */
+export interface FileProgress {
+  job_id: string
+  relative: string
+  state: 'processing' | 'done' | 'failed'
+  input_size: number
+  output_size: number | null
+  duration_ms: number
+  error: string | null
+}
+
+/** Unfinished folder job that `resumeJob` can continue */
+export interface ResumableJob {
+  job_id: string
+  folder: string
+  output_dir: string
+  pipeline_name: string
+  created_at: number
+}
+
 export interface PipelineEstimate {
   estimated_final_size: number
   overall_ratio: number
@@ -39,6 +109,110 @@ export interface PipelineEstimate {
   }>
 }
 
+export interface DryRunOptions {
+  max_samples?: number
+  window_bytes?: number
+}
+
+/** Per-step result of `dryRun`, measured on samples and projected onto all input */
+export interface StepDryRun {
+  layer_id: string
+  operation: string
+  applied_samples: number
+  sample_input_bytes: number
+  sample_output_bytes: number
+  ratio: number
+  measured: boolean
+  estimated_input_size: number
+  estimated_output_size: number
+  estimated_duration_ms: number
+  estimated_peak_memory_bytes: number
+  error: string | null
+}
+
+export interface DryRunReport {
+  files_total: number
+  bytes_total: number
+  files_sampled: number
+  bytes_sampled: number
+  steps: StepDryRun[]
+  estimated_final_size: number
+  estimated_duration_ms: number
+  overall_ratio: number
+}
+
+/** A layer of pipeline output, read from the file's own header */
+export interface InspectedLayer {
+  layer_id: string | null
+  step: string
+  label: string | null
+  step_version: number
+  supported: boolean
+  secret: 'password' | 'keypair' | null
+  params: Record<string, unknown>
+}
+
+export interface PipelineInspection {
+  format_version: number
+  pipeline_id: string | null
+  pipeline_name: string | null
+  created_at: number | null
+  original_size: number
+  payload_size: number
+  layers: InspectedLayer[]
+  reversible: boolean
+}
+
+/** A logged pipeline run, from `getHistory` */
+export interface PipelineRun {
+  id: string
+  pipeline_id: string
+  pipeline_name: string
+  kind: 'single' | 'folder'
+  started_at: number
+  duration_ms: number
+  files: number
+  input_size: number
+  output_size: number
+  steps: string[]
+  success: boolean
+  error: string | null
+}
+
+export interface PipelineUsage {
+  pipeline_id: string
+  pipeline_name: string
+  runs: number
+  failed_runs: number
+  input_bytes: number
+  output_bytes: number
+  bytes_saved: number
+  bytes_per_sec: number
+  throughput_change: number | null
+  regressed: boolean
+}
+
+export interface PipelineStats {
+  totals: {
+    runs: number
+    failed_runs: number
+    files: number
+    input_bytes: number
+    output_bytes: number
+    duration_ms: number
+    since: number
+  }
+  bytes_saved: number
+  overall_ratio: number
+  bytes_per_sec: number
+  pipelines: PipelineUsage[]
+}
+
+export type FileCategory = 'photo' | 'raw' | 'video' | 'document'
+
+/** Preset ID per file category, applied by recursive folder uploads */
+export type PipelineRoutes = Partial<Record<FileCategory, string>>
+
 // State
 const pipelines = ref<PipelineConfig[]>([])
 const activePipelineId = ref<string | null>(null)
@@ -100,6 +274,28 @@ const activePipeline = computed(() => {
 })
 
 // Helper functions
+function toStoredPreset(pipeline: PipelineConfig): StoredPreset {
+  return {
+    id: pipeline.id.startsWith('pipeline-') ? '' : pipeline.id,
+    name: pipeline.name,
+    description: pipeline.description || '',
+    layers: pipeline.layers.map((layer, order) => ({ ...layer, order })),
+    created_at: Math.floor(pipeline.createdAt / 1000),
+    updated_at: Math.floor(pipeline.updatedAt / 1000)
+  }
+}
+
+function fromStoredPreset(preset: StoredPreset): PipelineConfig {
+  return {
+    id: preset.id,
+    name: preset.name,
+    description: preset.description,
+    layers: [...preset.layers].sort((a, b) => a.order - b.order).map(({ order: _order, ...layer }) => layer),
+    createdAt: preset.created_at * 1000,
+    updatedAt: preset.updated_at * 1000
+  }
+}
+
 function generateId(): string {
   return `pipeline-${Date.now()}-${Math.random().toString(36).substr(2, 9)}`
 }
@@ -251,7 +447,116 @@ export function usePipeline() {
     saveToStorage()
   }
 
+  // Saved presets
+  async function loadSavedPresets(): Promise<PipelineConfig[]> {
+    const all = await invoke<StoredPreset[]>('pipeline_get_presets')
+    return all.filter(p => !p.id.startsWith('preset-')).map(fromStoredPreset)
+  }
+
+  async function savePreset(pipeline: PipelineConfig): Promise<PipelineConfig> {
+    const saved = await invoke<StoredPreset>('pipeline_save_preset', { config: toStoredPreset(pipeline) })
+    return fromStoredPreset(saved)
+  }
+
+  async function deletePreset(id: string): Promise<boolean> {
+    return await invoke<boolean>('pipeline_delete_preset', { id })
+  }
+
+  async function exportPreset(id: string, path: string): Promise<void> {
+    await invoke('pipeline_export_preset', { id, path })
+  }
+
+  async function importPreset(path: string): Promise<PipelineConfig> {
+    return fromStoredPreset(await invoke<StoredPreset>('pipeline_import_preset', { path }))
+  }
+
+  // Folder processing
+  async function processFolder(
+    folder: string,
+    outputDir: string,
+    pipeline: PipelineConfig,
+    passwords: Record<string, string> = {},
+    keypairBytes: number[] | null = null,
+    options: BatchOptions = {}
+  ): Promise<string> {
+    return await invoke<string>('pipeline_process_folder', {
+      folder,
+      outputDir,
+      config: toStoredPreset(pipeline),
+      passwords,
+      keypairBytes,
+      options
+    })
+  }
+
+  async function listResumableJobs(): Promise<ResumableJob[]> {
+    return await invoke<ResumableJob[]>('pipeline_resumable_jobs')
+  }
+
+  async function resumeJob(
+    jobId: string,
+    passwords: Record<string, string> = {},
+    keypairBytes: number[] | null = null
+  ): Promise<string> {
+    return await invoke<string>('pipeline_resume_job', { jobId, passwords, keypairBytes })
+  }
+
+  async function discardJob(jobId: string): Promise<boolean> {
+    return await invoke<boolean>('pipeline_discard_job', { jobId })
+  }
+
+  // Output inspection
+  async function inspectFile(path: string): Promise<PipelineInspection> {
+    return await invoke<PipelineInspection>('pipeline_inspect', { path })
+  }
+
+  // Run history
+  async function getHistory(limit?: number, pipelineId?: string): Promise<PipelineRun[]> {
+    return await invoke<PipelineRun[]>('pipeline_get_history', {
+      limit: limit ?? null,
+      pipelineId: pipelineId ?? null
+    })
+  }
+
+  async function getStats(): Promise<PipelineStats> {
+    return await invoke<PipelineStats>('pipeline_get_stats')
+  }
+
+  async function clearHistory(): Promise<number> {
+    return await invoke<number>('pipeline_clear_history')
+  }
+
+  // Per-category routing
+  async function getRoutes(): Promise<PipelineRoutes> {
+    return await invoke<PipelineRoutes>('pipeline_get_routes')
+  }
+
+  async function setRoute(category: FileCategory, presetId: string | null): Promise<PipelineRoutes> {
+    return await invoke<PipelineRoutes>('pipeline_set_route', { category, presetId })
+  }
+
+  // Registered steps
+  async function describeSteps(): Promise<StepDescriptor[]> {
+    return await invoke<StepDescriptor[]>('pipeline_describe_steps')
+  }
+
   // Estimation
+  async function dryRun(
+    paths: string[],
+    pipeline: PipelineConfig,
+    passwords: Record<string, string> = {},
+    keypairBytes: number[] | null = null,
+    options: DryRunOptions = {}
+  ): Promise<DryRunReport> {
+    return await invoke<DryRunReport>('pipeline_dry_run', {
+      paths,
+      config: toStoredPreset(pipeline),
+      passwords,
+      keypairBytes,
+      options
+    })
+  }
+
   async function estimatePipeline(inputSize: number, pipeline: PipelineConfig): Promise<PipelineEstimate> {
     let currentSize = inputSize
     const operations: PipelineEstimate['operations'] = []
@@ -269,6 +574,7 @@ export function usePipeline() {
           else if (alg === 'lz4') ratio = 0.5
           else if (alg === 'brotli') ratio = 0.25 + (0.35 * (1 - level / 11))
           else if (alg === 'gzip') ratio = 0.35 + (0.3 * (1 - level / 9))
+          else if (alg === 'xz') ratio = 0.2 + (0.3 * (1 - level / 9))
           else ratio = 0.5
           break
         case 'encrypt_password':
@@ -284,6 +590,15 @@ export function usePipeline() {
         case 'base64_decode':
           ratio = 0.75
           break
+        case 'optimize_image':
+          ratio = 0.85 // Lossless PNG/JPEG optimization
+          break
+        case 'transcode':
+          ratio = { avif: 0.35, webp: 0.5, jxl: 0.45 }[layer.operation.format || 'avif']
+          break
+        case 'resize':
+          ratio = layer.operation.mode === 'percent' ? ((layer.operation.percent || 100) / 100) ** 2 : 0.5
+          break
       }
 
       currentSize = Math.round(currentSize * ratio)
@@ -321,6 +636,38 @@ export function usePipeline() {
     return { type: 'base64_encode' }
   }
 
+  function createTranscodeOperation(
+    format: 'avif' | 'webp' | 'jxl' = 'avif',
+    quality = 75,
+    keepOriginal = false
+  ): PipelineOperation {
+    return { type: 'transcode', format, quality, keep_original: keepOriginal }
+  }
+
+  function createResizeOperation(maxEdge = 2048, quality = 85, originalsAlbum?: string): PipelineOperation {
+    return { type: 'resize', mode: 'max_edge', max_edge: maxEdge, quality, originals_album: originalsAlbum }
+  }
+
+  function createWatermarkOperation(
+    text = `© ${new Date().getFullYear()}`,
+    position: PipelineOperation['position'] = 'bottom_right',
+    opacity = 0.5,
+    scale = 0.2
+  ): PipelineOperation {
+    return { type: 'watermark', text, position, opacity, scale }
+  }
+
+  function createOptimizeImageOperation(level = 2, stripMetadata = false): PipelineOperation {
+    return { type: 'optimize_image', level, strip_metadata: stripMetadata }
+  }
+
+  function createScriptOperation(script: string, maxOperations?: number, timeoutMs?: number): PipelineOperation {
+    const params: Record<string, unknown> = { script }
+    if (maxOperations !== undefined) params.max_operations = maxOperations
+    if (timeoutMs !== undefined) params.timeout_ms = timeoutMs
+    return { type: 'step', step: 'script', params }
+  }
+
   // UI helpers
   function getOperationLabel(operation: PipelineOperation): string {
     switch (operation.type) {
@@ -336,6 +683,18 @@ export function usePipeline() {
         return 'Base64 Encode'
       case 'base64_decode':
         return 'Base64 Decode'
+      case 'optimize_image':
+        return operation.strip_metadata ? 'Optimize Image (strip metadata)' : 'Optimize Image'
+      case 'transcode':
+        return `${(operation.format || 'avif').toUpperCase()} Q${operation.quality || 75}`
+      case 'resize':
+        if (operation.mode === 'percent') return `Resize ${operation.percent}%`
+        if (operation.mode === 'exact') return `Resize ${operation.width}×${operation.height}`
+        return `Resize max ${operation.max_edge}px`
+      case 'watermark':
+        return operation.text ? `Watermark "${operation.text}"` : 'Watermark'
+      case 'step':
+        return operation.step || 'Step'
       default:
         return operation.type
     }
@@ -354,6 +713,12 @@ export function usePipeline() {
       case 'base64_encode':
       case 'base64_decode':
         return '📝'
+      case 'optimize_image':
+      case 'transcode':
+      case 'resize':
+        return '🖼️'
+      case 'watermark':
+        return '©️'
       default:
         return '⚙️'
     }
@@ -380,8 +745,27 @@ export function usePipeline() {
     updateLayer,
     reorderLayers,
     
+    // Saved presets
+    loadSavedPresets,
+    savePreset,
+    deletePreset,
+    exportPreset,
+    importPreset,
+    describeSteps,
+    processFolder,
+    listResumableJobs,
+    resumeJob,
+    discardJob,
+    inspectFile,
+    getHistory,
+    getStats,
+    clearHistory,
+    getRoutes,
+    setRoute,
+    
     // Estimation
     estimatePipeline,
+    dryRun,
     
     // Operation factories
     createCompressOperation,
@@ -389,6 +773,11 @@ export function usePipeline() {
     createHybridPQEncryptOperation,
     createHashOperation,
     createBase64Operation,
+    createOptimizeImageOperation,
+    createScriptOperation,
+    createTranscodeOperation,
+    createResizeOperation,
+    createWatermarkOperation,
     
     // UI helpers
     getOperationLabel,
@@ -0,0 +1,80 @@
+/**
+ * Profiles Composable - Local identities ("personal", "work")
+ *
+ * Each profile has its own keypair, token, contacts and bound repo. Settings
+ * that belong to an identity live in the profile's store file instead of the
+ * shared settings.json.
+ */
+
+import { ref } from 'vue'
+
+const isTauri = typeof window !== 'undefined' && !!(window as any).__TAURI__
+
+export interface Profile {
+  id: string
+  name: string
+  repo: string | null
+  store_file: string
+  created_at: number
+}
+
+export interface ProfileList {
+  active: string
+  profiles: Profile[]
+}
+
+const DEFAULT_STORE_FILE = 'settings.json'
+
+const profiles = ref<Profile[]>([])
+const activeProfile = ref<Profile | null>(null)
+
+async function refreshProfiles(): Promise<ProfileList> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  const list = await invoke<ProfileList>('list_profiles')
+  profiles.value = list.profiles
+  activeProfile.value = list.profiles.find(p => p.id === list.active) ?? null
+  return list
+}
+
+/**
+ * Store file holding the active profile's settings (token, repo, keypair)
+ */
+export async function profileStoreFile(): Promise<string> {
+  if (!isTauri) return DEFAULT_STORE_FILE
+  if (!activeProfile.value) {
+    try {
+      await refreshProfiles()
+    } catch {
+      return DEFAULT_STORE_FILE
+    }
+  }
+  return activeProfile.value?.store_file ?? DEFAULT_STORE_FILE
+}
+
+export function useProfiles() {
+  async function createProfile(name: string, repo?: string): Promise<Profile> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    const profile = await invoke<Profile>('create_profile', { name, repo: repo ?? null })
+    await refreshProfiles()
+    return profile
+  }
+
+  /**
+   * Activate another profile. Keypair handles of the old one stop working,
+   * so the app reloads to pick up the new identity everywhere.
+   */
+  async function switchProfile(id: string): Promise<void> {
+    if (activeProfile.value?.id === id) return
+    const { invoke } = await import('@tauri-apps/api/core')
+    activeProfile.value = await invoke<Profile>('switch_profile', { id })
+    window.location.reload()
+  }
+
+  return {
+    profiles,
+    activeProfile,
+    refreshProfiles,
+    createProfile,
+    switchProfile
+  }
+}
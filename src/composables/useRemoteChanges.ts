@@ -0,0 +1,66 @@
+/**
+ * TypeScript Module - 2 exports
+ * Purpose: Refresh albums when they change on another device
+ * Imports: 2 modules
+ */
+
+import { onMounted, onUnmounted, watch } from 'vue'
+import { useGitHubAuth, isWebMode } from './useGitHubAuth'
+
+export interface RemoteAlbumChange {
+  repo: string
+  albums: string[]
+  commit_sha: string
+  actor: string
+  pushed_at: string
+}
+
+/**
+ * Poll the active repository for remote pushes and call `onChange` for
+ * every `remote-album-changed` event. Restarts when the repository changes.
+ */
+export function useRemoteChanges(onChange: (change: RemoteAlbumChange) => void) {
+  const { token, repo } = useGitHubAuth()
+  let unlisten: (() => void) | null = null
+  let watchedRepo: string | null = null
+
+  async function stopWatching() {
+    if (!watchedRepo) return
+    const { invoke } = await import('@tauri-apps/api/core')
+    await invoke('stop_remote_watch', { repo: watchedRepo }).catch(() => {})
+    watchedRepo = null
+  }
+
+  async function startWatching() {
+    await stopWatching()
+    if (!token.value || !repo.value) return
+    const { invoke } = await import('@tauri-apps/api/core')
+    try {
+      await invoke('start_remote_watch', { repo: repo.value, token: token.value })
+      watchedRepo = repo.value
+    } catch { }
+  }
+
+  onMounted(async () => {
+    if (isWebMode) return
+    try {
+      const { listen } = await import('@tauri-apps/api/event')
+      unlisten = await listen<RemoteAlbumChange>('remote-album-changed', (event) => {
+        if (event.payload.repo === repo.value) onChange(event.payload)
+      })
+    } catch { }
+    await startWatching()
+  })
+
+  watch([token, repo], () => {
+    if (!isWebMode) startWatching()
+  })
+
+  onUnmounted(() => {
+    if (unlisten) {
+      unlisten()
+      unlisten = null
+    }
+    stopWatching()
+  })
+}
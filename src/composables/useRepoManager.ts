@@ -6,6 +6,8 @@
 
 import { ref } from 'vue'
 import { isDevMode } from './useGitHubAuth'
+import { errorMessage } from '../types/errors'
+import { profileStoreFile } from './useProfiles'
 
 export interface RepoConfig {
   name: string
@@ -79,7 +81,7 @@ export function useRepoManager() {
     try {
       const { invoke } = await import('@tauri-apps/api/core')
       const { load } = await import('@tauri-apps/plugin-store')
-      const store = await load('settings.json')
+      const store = await load(await profileStoreFile())
       const savedRepo = await store.get<string>('repo')
       const savedToken = await store.get<string>('token')
 
@@ -99,7 +101,7 @@ export function useRepoManager() {
     
     try {
       const { load } = await import('@tauri-apps/plugin-store')
-      const store = await load('settings.json')
+      const store = await load(await profileStoreFile())
       await store.set('repo', repoFullName)
       await store.save()
     } catch {
@@ -144,7 +146,7 @@ export function useRepoManager() {
 
       return result
     } catch (e) {
-      error.value = e instanceof Error ? e.message : String(e)
+      error.value = errorMessage(e)
       throw e
     } finally {
       creating.value = false
@@ -165,7 +167,7 @@ export function useRepoManager() {
       })
       return result
     } catch (e) {
-      error.value = e instanceof Error ? e.message : String(e)
+      error.value = errorMessage(e)
       throw e
     }
   }
@@ -201,7 +203,7 @@ export function useRepoManager() {
 
       return result
     } catch (e) {
-      error.value = e instanceof Error ? e.message : String(e)
+      error.value = errorMessage(e)
       throw e
     } finally {
       syncing.value = false
@@ -222,7 +224,7 @@ export function useRepoManager() {
       currentRepo.value = remoteInfo
       return remoteInfo.private
     } catch (e) {
-      error.value = e instanceof Error ? e.message : String(e)
+      error.value = errorMessage(e)
       throw e
     } finally {
       syncing.value = false
@@ -0,0 +1,81 @@
+/**
+ * TypeScript Module - 1 exports
+ * Purpose: Per-album retention policies that move old photos to an archive repo
+ * Imports: 0 modules
+ */
+
+export interface RetentionPolicy {
+  repo: string
+  album_path: string
+  archive_repo: string
+  archive_after_days: number
+  compress: boolean
+  keep_favorites: boolean
+  enabled: boolean
+}
+
+export interface RetentionCandidate {
+  repo: string
+  path: string
+  sha: string
+  taken_at: number
+  size: number | null
+}
+
+export interface RetentionPreview {
+  policy: RetentionPolicy
+  plan: {
+    cutoff: number
+    candidates: RetentionCandidate[]
+    kept_favorites: number
+    kept_encrypted: number
+    undated: number
+  }
+  total_bytes: number
+  next_run_at: number | null
+}
+
+export interface RetentionRun {
+  album_path: string
+  at: number
+  archived: number
+  original_bytes: number
+  stored_bytes: number
+  skipped: number
+  error: string | null
+}
+
+export interface RetentionInfo {
+  policy: RetentionPolicy
+  last_run: RetentionRun | null
+}
+
+export function useRetention() {
+  async function setPolicy(token: string, policy: RetentionPolicy): Promise<RetentionPolicy> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<RetentionPolicy>('set_retention_policy', { token, policy })
+  }
+
+  async function removePolicy(repo: string, albumPath: string): Promise<boolean> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<boolean>('remove_retention_policy', { repo, albumPath })
+  }
+
+  async function listPolicies(): Promise<RetentionInfo[]> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<RetentionInfo[]>('list_retention_policies')
+  }
+
+  /** What the next run would archive, from the local catalog */
+  async function preview(repo: string, albumPath: string): Promise<RetentionPreview> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<RetentionPreview>('preview_retention', { repo, albumPath })
+  }
+
+  async function runNow(repo: string, albumPath: string): Promise<RetentionRun> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<RetentionRun>('run_retention', { repo, albumPath })
+  }
+
+  return { setPolicy, removePolicy, listPolicies, preview, runNow }
+}
@@ -0,0 +1,82 @@
+/**
+ * TypeScript Module - 1 exports
+ * Purpose: Decrypted photo files and clipboard contents that clean up after themselves
+ * Imports: 0 modules
+ */
+
+export interface PlaintextFile {
+  id: string
+  path: string
+  size: number
+  source: string
+  /** True when the file lives in RAM (tmpfs) rather than on disk */
+  ram_backed: boolean
+  created_at: number
+}
+
+export interface PurgeReport {
+  removed: number
+  wiped_bytes: number
+  leftovers: number
+  failed: string[]
+}
+
+/** How long copied secrets stay on the clipboard */
+const CLIPBOARD_CLEAR_MS = 30_000
+
+/**
+ * Photos decrypted for viewing go to managed temp storage and are wiped
+ * when released. Copied secrets are cleared from the clipboard again
+ * unless something else was copied meanwhile.
+ */
+export function useSecureViewing() {
+  async function openForViewing(
+    repo: string,
+    token: string,
+    remotePath: string,
+    keypairHandle?: number,
+    verify = true
+  ): Promise<PlaintextFile> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<PlaintextFile>('open_photo_for_viewing', {
+      repo,
+      token,
+      remotePath,
+      keypairHandle: keypairHandle ?? null,
+      verify
+    })
+  }
+
+  /** Wipe a file from `openForViewing`, e.g. when its viewer closes */
+  async function release(id: string): Promise<boolean> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<boolean>('release_plaintext', { id })
+  }
+
+  async function listOpen(): Promise<PlaintextFile[]> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<PlaintextFile[]>('list_plaintext_files')
+  }
+
+  async function purge(): Promise<PurgeReport> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<PurgeReport>('purge_decrypted_cache')
+  }
+
+  /** Copy `text`, clearing it again after `clearAfterMs` if still there */
+  async function copySecret(text: string, clearAfterMs = CLIPBOARD_CLEAR_MS): Promise<void> {
+    await navigator.clipboard.writeText(text)
+    setTimeout(async () => {
+      try {
+        if ((await navigator.clipboard.readText()) === text) {
+          await navigator.clipboard.writeText('')
+        }
+      } catch {
+        // Reading needs focus or permission; clearing blindly could drop
+        // something the user copied since, so leave it
+      }
+    }, clearAfterMs)
+  }
+
+  return { openForViewing, release, listOpen, purge, copySecret }
+}
@@ -0,0 +1,69 @@
+/**
+ * TypeScript Module - 1 exports
+ * Purpose: Find duplicate and near-duplicate photos in the library
+ * Imports: 2 modules
+ */
+
+import { ref } from 'vue'
+import { useGitHubAuth } from './useGitHubAuth'
+
+export interface SimilarPhoto {
+  path: string
+  size: number
+  distance: number
+}
+
+export interface PhotoCluster {
+  photos: SimilarPhoto[]
+  identical: boolean
+}
+
+export interface SimilarityReport {
+  scanned: number
+  clusters: PhotoCluster[]
+  skipped: { path: string; reason: string }[]
+}
+
+/**
+ * Scan an album, or the whole library, for clusters of visually identical
+ * or near-identical photos. Lower thresholds only match closer copies.
+ */
+export function useSimilarPhotos() {
+  const { token, repo } = useGitHubAuth()
+  const scanning = ref(false)
+  const progress = ref({ total: 0, completed: 0 })
+  const report = ref<SimilarityReport | null>(null)
+
+  async function findSimilar(
+    albumPath: string | null = null,
+    threshold: number | null = null,
+    keypairHandle: number | null = null
+  ): Promise<SimilarityReport> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    const { listen } = await import('@tauri-apps/api/event')
+    scanning.value = true
+    const unlisten = await listen<{ total: number; completed: number }>('similarity-progress', (event) => {
+      progress.value = { total: event.payload.total, completed: event.payload.completed }
+    })
+    try {
+      report.value = await invoke<SimilarityReport>('find_similar_photos', {
+        repo: repo.value,
+        token: token.value,
+        albumPath,
+        threshold,
+        keypairHandle
+      })
+      return report.value
+    } finally {
+      unlisten()
+      scanning.value = false
+    }
+  }
+
+  return {
+    scanning,
+    progress,
+    report,
+    findSimilar
+  }
+}
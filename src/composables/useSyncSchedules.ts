@@ -0,0 +1,78 @@
+/**
+ * TypeScript Module - 1 exports
+ * Purpose: Background album syncs, retention and integrity checks on a schedule
+ * Imports: 0 modules
+ */
+
+export type ScheduledTask = 'sync' | 'retention' | 'verify'
+
+export type Cadence =
+  | { kind: 'interval'; minutes: number }
+  | { kind: 'daily'; hour: number; minute: number; utc_offset_minutes: number }
+
+export interface SyncSchedule {
+  repo: string
+  album_path: string
+  cadence: Cadence
+  tasks: ScheduledTask[]
+  local_dir?: string | null
+  recursive?: boolean
+  ignore_patterns?: string[]
+  enabled: boolean
+}
+
+export interface TaskOutcome {
+  task: ScheduledTask
+  summary: string | null
+  error: string | null
+}
+
+export interface ScheduleRun {
+  repo: string
+  album_path: string
+  started_at: number
+  finished_at: number
+  tasks: TaskOutcome[]
+}
+
+export interface ScheduleStatus {
+  schedule: SyncSchedule
+  last_run: ScheduleRun | null
+  next_run_at: number | null
+  running: boolean
+}
+
+/** A daily cadence at `hour:minute` in this machine's time zone */
+export function dailyAt(hour: number, minute: number): Cadence {
+  return { kind: 'daily', hour, minute, utc_offset_minutes: -new Date().getTimezoneOffset() }
+}
+
+export function useSyncSchedules() {
+  async function setSchedule(token: string, schedule: SyncSchedule): Promise<ScheduleStatus> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<ScheduleStatus>('set_sync_schedule', { token, schedule })
+  }
+
+  async function removeSchedule(repo: string, albumPath: string): Promise<boolean> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<boolean>('remove_sync_schedule', { repo, albumPath })
+  }
+
+  async function listSchedules(): Promise<ScheduleStatus[]> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<ScheduleStatus[]>('get_sync_schedules')
+  }
+
+  async function runNow(repo: string, albumPath: string): Promise<ScheduleRun> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<ScheduleRun>('run_sync_schedule_now', { repo, albumPath })
+  }
+
+  /** Call `handler` after every scheduled run. Returns the unlisten function. */
+  async function onRun(handler: (run: ScheduleRun) => void): Promise<() => void> {
+    const { listen } = await import('@tauri-apps/api/event')
+    return await listen<ScheduleRun>('schedule-run', (event) => handler(event.payload))
+  }
+
+  return { setSchedule, removeSchedule, listSchedules, runNow, onRun }
+}
@@ -7,6 +7,7 @@
 import { ref } from 'vue'
 import { useGitHubAuth, isDevMode } from './useGitHubAuth'
 import { useUploadToast } from './useUploadToast'
+import { errorMessage } from '../types/errors'
 
 export type SyncStatus = 'local-only' | 'remote-only' | 'synced'
 
@@ -87,7 +88,7 @@ export function useSyncStatus() {
       setStatus(transferId, 'completed')
       setStatusState(photoId, 'synced', state.localPath, state.remotePath)
     } catch (error) {
-      setStatus(transferId, 'failed', String(error))
+      setStatus(transferId, 'failed', errorMessage(error))
     }
   }
 
@@ -103,7 +104,7 @@ export function useSyncStatus() {
     
     try {
       const { invoke } = await import('@tauri-apps/api/core')
-      const localPath = await invoke<string>('download_photo', {
+      const { path: localPath } = await invoke<{ path: string }>('download_photo', {
         remotePath: state.remotePath,
         repo: repo.value,
         token: token.value,
@@ -114,7 +115,7 @@ export function useSyncStatus() {
       setStatus(transferId, 'completed')
       setStatusState(photoId, 'synced', localPath, state.remotePath)
     } catch (error) {
-      setStatus(transferId, 'failed', String(error))
+      setStatus(transferId, 'failed', errorMessage(error))
     }
   }
 
@@ -0,0 +1,91 @@
+/**
+ * TypeScript Module - 1 exports
+ * Purpose: Cached WebP thumbnails of local images
+ * Imports: 1 modules
+ */
+
+import { isWebMode } from './useGitHubAuth'
+
+export interface Thumbnail {
+  path: string
+  width: number
+  height: number
+  bytes: number
+  cached: boolean
+}
+
+export interface ThumbnailProgress {
+  total: number
+  completed: number
+  current_file: string
+}
+
+export interface ThumbnailBatch {
+  generated: number
+  cached: number
+  failed: { path: string; error: string }[]
+}
+
+/**
+ * Thumbnails rendered and cached by the backend, keyed by content so moved
+ * or renamed photos keep theirs. `thumbnailUrl` falls back to the original
+ * image when a thumbnail cannot be made.
+ */
+export function useThumbnails() {
+  async function generateThumbnail(path: string, size?: number): Promise<Thumbnail> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<Thumbnail>('generate_thumbnail', { path, size: size ?? null })
+  }
+
+  async function thumbnailUrl(path: string, size?: number): Promise<string> {
+    const { convertFileSrc } = await import('@tauri-apps/api/core')
+    if (isWebMode) return convertFileSrc(path)
+    try {
+      return convertFileSrc((await generateThumbnail(path, size)).path)
+    } catch {
+      return convertFileSrc(path)
+    }
+  }
+
+  /**
+   * URL for showing a local photo full-size. HEIC photos, which the webview
+   * cannot show, are converted to the largest WebP thumbnail instead.
+   */
+  async function displayUrl(path: string): Promise<string> {
+    const { convertFileSrc } = await import('@tauri-apps/api/core')
+    if (isWebMode || !/\.(heic|heif)$/i.test(path)) return convertFileSrc(path)
+    return thumbnailUrl(path, 1024)
+  }
+
+  /** Render an album's thumbnails ahead of showing its grid */
+  async function pregenerate(
+    paths: string[],
+    size?: number,
+    onProgress?: (progress: ThumbnailProgress) => void
+  ): Promise<ThumbnailBatch> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    let unlisten: (() => void) | null = null
+    if (onProgress) {
+      const { listen } = await import('@tauri-apps/api/event')
+      unlisten = await listen<ThumbnailProgress>('thumbnail-progress', (event) => onProgress(event.payload))
+    }
+    try {
+      return await invoke<ThumbnailBatch>('pregenerate_thumbnails', { paths, size: size ?? null })
+    } finally {
+      unlisten?.()
+    }
+  }
+
+  async function clearCache(): Promise<number> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return await invoke<number>('clear_thumbnail_cache')
+  }
+
+  return {
+    generateThumbnail,
+    thumbnailUrl,
+    displayUrl,
+    pregenerate,
+    clearCache
+  }
+}
@@ -0,0 +1,77 @@
+/**
+ * TypeScript Module - 1 exports
+ * Purpose: Probe local videos and upload them into albums
+ * Imports: 2 modules
+ */
+
+import { ref } from 'vue'
+import { useGitHubAuth } from './useGitHubAuth'
+
+export type MediaType = 'photo' | 'video'
+
+export interface VideoInfo {
+  container: string
+  codec: string | null
+  duration_secs: number | null
+  width: number | null
+  height: number | null
+}
+
+/**
+ * Videos large enough to pass the contents API limit go up through LFS or
+ * in chunks; `progress` follows the bytes sent.
+ */
+export function useVideos() {
+  const { token, repo } = useGitHubAuth()
+  const uploading = ref(false)
+  const progress = ref({ sent: 0, total: 0 })
+
+  async function getVideoInfo(path: string): Promise<VideoInfo> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    return invoke<VideoInfo>('get_video_info', { path })
+  }
+
+  async function uploadVideo(
+    path: string,
+    albumPath: string,
+    keypairHandle: number | null = null
+  ): Promise<{ url: string; sha: string }> {
+    const { invoke } = await import('@tauri-apps/api/core')
+    const { listen } = await import('@tauri-apps/api/event')
+    const uploadId = `video-${Date.now()}`
+    uploading.value = true
+    const unlisten = await listen<{ id: string; bytes_sent: number; total_bytes: number }>('upload-progress', (event) => {
+      if (event.payload.id === uploadId) {
+        progress.value = { sent: event.payload.bytes_sent, total: event.payload.total_bytes }
+      }
+    })
+    try {
+      return await invoke('upload_video', {
+        path,
+        repo: repo.value,
+        token: token.value,
+        albumPath,
+        uploadId,
+        keypairHandle
+      })
+    } finally {
+      unlisten()
+      uploading.value = false
+    }
+  }
+
+  function formatDuration(seconds: number | null): string {
+    if (seconds === null) return '-'
+    const total = Math.round(seconds)
+    const mins = Math.floor(total / 60)
+    return `${mins}:${String(total % 60).padStart(2, '0')}`
+  }
+
+  return {
+    uploading,
+    progress,
+    getVideoInfo,
+    uploadVideo,
+    formatDuration
+  }
+}
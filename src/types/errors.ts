@@ -0,0 +1,40 @@
+/**
+ * TypeScript Module - 4 exports
+ * Purpose: Typed errors returned by backend commands
+ * Imports: 0 modules
+ */
+
+export type GithubErrorKind =
+  | 'not_found'
+  | 'unauthorized'
+  | 'forbidden'
+  | 'rate_limited'
+  | 'conflict'
+  | 'network_timeout'
+  | 'network'
+  | 'too_large'
+  | 'validation'
+  | 'unavailable'
+  | 'server'
+  | 'api'
+  | 'io'
+  | 'cancelled'
+
+export interface GithubError {
+  kind: GithubErrorKind
+  message: string
+  /** Seconds to wait, for `rate_limited` and `unavailable` */
+  retry_after?: number
+  /** HTTP status, for `server` and `api` */
+  status?: number | null
+}
+
+export function isGithubError(e: unknown): e is GithubError {
+  return typeof e === 'object' && e !== null && 'kind' in e && 'message' in e
+}
+
+export function errorMessage(e: unknown): string {
+  if (isGithubError(e)) return e.message
+  if (e instanceof Error) return e.message
+  return String(e)
+}